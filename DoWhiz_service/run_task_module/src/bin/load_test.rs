@@ -97,6 +97,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             google_access_token: None,
             has_unified_account: false,
             user_identities: Default::default(),
+            reply_preferences: Default::default(),
+            trace_id: None,
+            sandbox: None,
         });
     }
 
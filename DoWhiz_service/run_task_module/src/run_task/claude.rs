@@ -1,67 +1,68 @@
 use std::env;
 use std::fs;
 use std::io;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::process::Command;
 
-use super::constants::{CLAUDE_FOUNDRY_RESOURCE_DEFAULT, DEFAULT_CLAUDE_MODEL};
-
-/// Check if cross-channel routing was requested and return the correct expected reply path.
-/// If reply_routing.json specifies a different target channel, compute the expected file for that target.
-fn resolve_expected_reply_path(workspace_dir: &Path, default_path: PathBuf) -> PathBuf {
-    let routing_file = workspace_dir.join("reply_routing.json");
-    if !routing_file.exists() {
-        return default_path;
-    }
+use super::constants::{
+    CLAUDE_FOUNDRY_RESOURCE_DEFAULT, DEFAULT_CLAUDE_MODEL, DOCKER_CLAUDE_HOME_DIR,
+    DOCKER_WORKSPACE_DIR, TRACE_ID_ENV_KEY,
+};
+use super::docker::{resolve_sandbox, sandbox_docker_image, SandboxProfile};
+use super::env::path_with_dowhiz_bin;
+use super::errors::RunTaskError;
+use super::github_auth::{ensure_github_cli_auth, resolve_github_auth};
+use super::prompt::prompt_for_request;
+use super::runner::{RunContext, Runner};
+use super::scheduled::{extract_scheduled_tasks, extract_scheduler_actions};
+use super::types::{RunTaskOutput, RunTaskRequest, TokenUsage};
+use super::utils::{run_command_with_timeout, run_task_timeout, tail_string};
+use super::workspace::{
+    canonicalize_dir, resolve_expected_reply_path, workspace_path_in_container,
+};
 
-    // Try to read and parse reply_routing.json
-    let routing_content = match fs::read_to_string(&routing_file) {
-        Ok(content) => content,
-        Err(_) => return default_path,
-    };
+pub(super) struct ClaudeRunner;
 
-    // Parse the JSON to get target channel
-    let routing: serde_json::Value = match serde_json::from_str(&routing_content) {
-        Ok(v) => v,
-        Err(_) => return default_path,
-    };
+impl Runner for ClaudeRunner {
+    type Execution = RunTaskOutput;
 
-    let target_channel = match routing.get("channel").and_then(|c| c.as_str()) {
-        Some(ch) => ch.to_lowercase(),
-        None => return default_path,
-    };
+    fn execute(&self, ctx: &RunContext<'_>) -> Result<RunTaskOutput, RunTaskError> {
+        run_claude_task(
+            ctx.request.clone(),
+            ctx.runner,
+            ctx.reply_html_path.clone(),
+            ctx.reply_attachments_dir.clone(),
+        )
+    }
 
-    // Determine expected reply path based on TARGET channel
-    match target_channel.as_str() {
-        "email" | "googledocs" | "googlesheets" | "googleslides" => {
-            workspace_dir.join("reply_email_draft.html")
-        }
-        "slack" | "discord" | "telegram" | "sms" | "whatsapp" | "bluebubbles" => {
-            workspace_dir.join("reply_message.txt")
-        }
-        "notion" => {
-            // Notion agent posts directly via API and creates .notion_api_replied marker
-            workspace_dir.join(".notion_api_replied")
-        }
-        _ => default_path,
+    fn collect(
+        &self,
+        _ctx: &RunContext<'_>,
+        output: RunTaskOutput,
+    ) -> Result<RunTaskOutput, RunTaskError> {
+        Ok(output)
     }
 }
-use super::env::load_env_sources;
-use super::errors::RunTaskError;
-use super::github_auth::{ensure_github_cli_auth, resolve_github_auth};
-use super::prompt::{build_prompt, load_memory_context};
-use super::scheduled::{extract_scheduled_tasks, extract_scheduler_actions};
-use super::types::{RunTaskOutput, RunTaskRequest};
-use super::utils::{run_command_with_timeout, run_task_timeout, tail_string};
 
-pub(super) fn run_claude_task(
+fn run_claude_task(
     request: RunTaskRequest<'_>,
     runner: &str,
     reply_html_path: std::path::PathBuf,
     reply_attachments_dir: std::path::PathBuf,
 ) -> Result<RunTaskOutput, RunTaskError> {
-    load_env_sources(request.workspace_dir)?;
-    let github_auth = resolve_github_auth(None)?;
+    let sandbox = resolve_sandbox(request.sandbox);
+    // Sandboxed runs keep Claude's home (and the askpass script) inside the
+    // mounted workspace.
+    let host_workspace_dir = match sandbox {
+        Some(_) => Some(canonicalize_dir(request.workspace_dir)?),
+        None => None,
+    };
+    let claude_home = match &host_workspace_dir {
+        Some(dir) => dir.join(DOCKER_CLAUDE_HOME_DIR),
+        None => dowhiz_claude_home()?,
+    };
+    let github_auth =
+        resolve_github_auth(host_workspace_dir.as_ref().map(|_| claude_home.as_path()))?;
 
     let api_key =
         env::var("AZURE_OPENAI_API_KEY_BACKUP").map_err(|_| RunTaskError::MissingEnv {
@@ -79,24 +80,14 @@ pub(super) fn run_claude_task(
         request.model_name.to_string()
     };
 
-    let memory_context = load_memory_context(request.workspace_dir, request.memory_dir)?;
-    let prompt = build_prompt(
-        request.input_email_dir,
-        request.input_attachments_dir,
-        request.memory_dir,
-        request.reference_dir,
-        request.workspace_dir,
-        runner,
-        &memory_context,
-        !request.reply_to.is_empty(),
-        request.channel,
-        request.has_unified_account,
-        request.user_identities,
-    );
+    let prompt = prompt_for_request(&request, runner)?;
 
     ensure_github_cli_auth(&github_auth)?;
-    let mut env_overrides = prepare_claude_env(&api_key, &model_name)?;
+    let mut env_overrides = prepare_claude_env(&api_key, &model_name, &claude_home)?;
     env_overrides.extend(github_auth.env_overrides.clone());
+    if let Some(trace_id) = request.trace_id {
+        env_overrides.push((TRACE_ID_ENV_KEY.to_string(), trace_id.to_string()));
+    }
     if let Some(askpass_path) = github_auth.askpass_path.as_ref() {
         env_overrides.push((
             "GIT_ASKPASS".to_string(),
@@ -104,7 +95,16 @@ pub(super) fn run_claude_task(
         ));
         env_overrides.push(("GIT_TERMINAL_PROMPT".to_string(), "0".to_string()));
     }
-    let output = run_claude_command(request.workspace_dir, &prompt, &model_name, &env_overrides)?;
+    let output = match (&sandbox, &host_workspace_dir) {
+        (Some(sandbox), Some(host_workspace_dir)) => run_sandboxed_claude_command(
+            host_workspace_dir,
+            sandbox,
+            &prompt,
+            &model_name,
+            &env_overrides,
+        )?,
+        _ => run_claude_command(request.workspace_dir, &prompt, &model_name, &env_overrides)?,
+    };
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     let stderr = String::from_utf8_lossy(&output.stderr);
@@ -150,13 +150,15 @@ pub(super) fn run_claude_task(
         scheduled_tasks_error,
         scheduler_actions,
         scheduler_actions_error,
-        token_usage: None, // TODO: Extract from Claude API response
+        token_usage: extract_claude_usage(&stdout),
+        output_issues: Vec::new(),
     })
 }
 
 fn prepare_claude_env(
     api_key: &str,
     model_name: &str,
+    claude_home: &Path,
 ) -> Result<Vec<(String, String)>, RunTaskError> {
     let foundry_resource = env::var("ANTHROPIC_FOUNDRY_RESOURCE")
         .ok()
@@ -176,6 +178,7 @@ fn prepare_claude_env(
         .unwrap_or_else(|| "claude-haiku-4-5".to_string());
 
     ensure_claude_settings(
+        claude_home,
         model_name,
         api_key,
         &foundry_resource,
@@ -184,23 +187,6 @@ fn prepare_claude_env(
         &default_haiku,
     )?;
 
-    // Get current PATH and prepend our custom bin directory for tools like google-docs
-    let current_path = env::var("PATH").unwrap_or_default();
-    // Look for DOWHIZ_BIN_DIR env var, or use default location relative to crate
-    let dowhiz_bin_dir = env::var("DOWHIZ_BIN_DIR")
-        .ok()
-        .filter(|v| !v.trim().is_empty())
-        .unwrap_or_else(|| {
-            // Default: assume bin/ is sibling to scheduler_module
-            let manifest_dir = env!("CARGO_MANIFEST_DIR");
-            let parent = Path::new(manifest_dir).parent().unwrap_or(Path::new("."));
-            parent.join("bin").to_string_lossy().into_owned()
-        });
-    let extended_path = format!("{}:{}", dowhiz_bin_dir, current_path);
-
-    // Set CLAUDE_HOME to use DoWhiz-specific config directory
-    let claude_home = dowhiz_claude_home()?;
-
     Ok(vec![
         (
             "AZURE_OPENAI_API_KEY_BACKUP".to_string(),
@@ -217,7 +203,7 @@ fn prepare_claude_env(
         ("ANTHROPIC_DEFAULT_OPUS_MODEL".to_string(), default_opus),
         ("ANTHROPIC_DEFAULT_SONNET_MODEL".to_string(), default_sonnet),
         ("ANTHROPIC_DEFAULT_HAIKU_MODEL".to_string(), default_haiku),
-        ("PATH".to_string(), extended_path),
+        ("PATH".to_string(), path_with_dowhiz_bin()),
     ])
 }
 
@@ -233,6 +219,7 @@ fn dowhiz_claude_home() -> Result<std::path::PathBuf, RunTaskError> {
 }
 
 fn ensure_claude_settings(
+    settings_dir: &Path,
     model_name: &str,
     api_key: &str,
     foundry_resource: &str,
@@ -240,9 +227,7 @@ fn ensure_claude_settings(
     default_sonnet: &str,
     default_haiku: &str,
 ) -> Result<(), RunTaskError> {
-    // Use DoWhiz-specific Claude home to avoid overwriting user's ~/.claude/settings.json
-    let settings_dir = dowhiz_claude_home()?;
-    fs::create_dir_all(&settings_dir)?;
+    fs::create_dir_all(settings_dir)?;
     let settings_path = settings_dir.join("settings.json");
     let payload = serde_json::json!({
         "env": {
@@ -292,14 +277,68 @@ fn run_claude_command(
     }
 }
 
+/// Run Claude inside the runner image under `sandbox`'s limits. Only the
+/// prepared overrides reach the container, never the worker's environment.
+fn run_sandboxed_claude_command(
+    host_workspace_dir: &Path,
+    sandbox: &SandboxProfile,
+    prompt: &str,
+    model_name: &str,
+    env_overrides: &[(String, String)],
+) -> Result<std::process::Output, RunTaskError> {
+    let image = sandbox_docker_image()?;
+    let mut cmd = Command::new("docker");
+    cmd.arg("run")
+        .arg("--rm")
+        .arg("--workdir")
+        .arg(DOCKER_WORKSPACE_DIR)
+        .arg("-v")
+        .arg(format!(
+            "{}:{}",
+            host_workspace_dir.display(),
+            DOCKER_WORKSPACE_DIR
+        ))
+        .arg("-e")
+        .arg(format!("HOME={}", DOCKER_WORKSPACE_DIR))
+        .args(sandbox.docker_args());
+    for (key, value) in env_overrides {
+        // The host PATH means nothing inside the image.
+        if key == "PATH" {
+            continue;
+        }
+        // Host paths under the workspace (CLAUDE_HOME, GIT_ASKPASS) are remapped.
+        let value = workspace_path_in_container(Path::new(value), host_workspace_dir)
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|| value.clone());
+        cmd.arg("-e").arg(format!("{}={}", key, value));
+    }
+    cmd.arg("--entrypoint").arg("claude").arg(&image);
+    apply_claude_args(&mut cmd, prompt, model_name);
+
+    match run_command_with_timeout(cmd, run_task_timeout(), "docker run") {
+        Ok(output) => Ok(output),
+        Err(RunTaskError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+            Err(RunTaskError::DockerNotFound)
+        }
+        Err(err) => Err(err),
+    }
+}
+
 fn build_claude_command(
     workspace_dir: &Path,
     prompt: &str,
     model_name: &str,
     env_overrides: &[(String, String)],
 ) -> Command {
-    let max_turns = claude_max_turns();
     let mut cmd = Command::new("claude");
+    apply_claude_args(&mut cmd, prompt, model_name);
+    cmd.current_dir(workspace_dir);
+    apply_env_pairs(&mut cmd, env_overrides);
+    cmd
+}
+
+fn apply_claude_args(cmd: &mut Command, prompt: &str, model_name: &str) {
+    let max_turns = claude_max_turns();
     cmd.arg("-p")
         .arg("--output-format")
         .arg("stream-json")
@@ -311,10 +350,7 @@ fn build_claude_command(
         .arg("--max-turns")
         .arg(max_turns.to_string())
         .arg("--dangerously-skip-permissions")
-        .arg(prompt)
-        .current_dir(workspace_dir);
-    apply_env_pairs(&mut cmd, env_overrides);
-    cmd
+        .arg(prompt);
 }
 
 fn claude_max_turns() -> u32 {
@@ -394,6 +430,29 @@ fn extract_claude_text(raw: &str) -> (String, Vec<String>) {
     (text, logs)
 }
 
+/// Usage and cost from the final `result` event:
+/// `{"type":"result","total_cost_usd":0.12,"usage":{"input_tokens":N,...}}`.
+/// Claude counts cache reads and writes apart from `input_tokens`; they are
+/// folded in so `input_tokens` covers the whole prompt like other runners.
+fn extract_claude_usage(raw: &str) -> Option<TokenUsage> {
+    let event = raw
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line.trim()).ok())
+        .find(|event| event.get("type").and_then(|value| value.as_str()) == Some("result"))?;
+    let usage = event.get("usage")?;
+    let count = |key: &str| usage.get(key).and_then(|value| value.as_u64()).unwrap_or(0);
+    let cached_input_tokens = count("cache_read_input_tokens");
+    Some(TokenUsage {
+        input_tokens: count("input_tokens")
+            + count("cache_creation_input_tokens")
+            + cached_input_tokens,
+        cached_input_tokens,
+        output_tokens: count("output_tokens"),
+        cost_usd: event.get("total_cost_usd").and_then(|value| value.as_f64()),
+    })
+}
+
 fn extract_claude_fragment(event: &serde_json::Value) -> Option<String> {
     // Direct text field
     if let Some(text) = event.get("text").and_then(|value| value.as_str()) {
@@ -441,3 +500,21 @@ fn extract_claude_fragment(event: &serde_json::Value) -> Option<String> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_claude_usage_reads_result_event() {
+        let raw = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Done."}]}}
+{"type":"result","subtype":"success","result":"Done.","total_cost_usd":0.0421,"usage":{"input_tokens":12,"cache_creation_input_tokens":300,"cache_read_input_tokens":4000,"output_tokens":250}}"#;
+        let usage = extract_claude_usage(raw).expect("usage");
+        assert_eq!(usage.input_tokens, 4312);
+        assert_eq!(usage.cached_input_tokens, 4000);
+        assert_eq!(usage.output_tokens, 250);
+        assert_eq!(usage.cost_usd, Some(0.0421));
+
+        assert!(extract_claude_usage(r#"{"type":"assistant"}"#).is_none());
+    }
+}
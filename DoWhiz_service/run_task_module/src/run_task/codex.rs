@@ -14,16 +14,21 @@ use serde::Deserialize;
 use super::constants::{
     CODEX_CONFIG_BASE_URL_PLACEHOLDER, CODEX_CONFIG_BLOCK_TEMPLATE, CODEX_CONFIG_MARKER,
     CODEX_MODEL_NAME, CODEX_SANDBOX_MODE, DOCKER_CODEX_HOME_DIR, DOCKER_WORKSPACE_DIR,
+    TRACE_ID_ENV_KEY,
 };
-use super::docker::{docker_cli_available, ensure_docker_image_available};
+use super::docker::{docker_cli_available, ensure_docker_image_available, resolve_sandbox};
 use super::env::{env_enabled, normalize_env_prefix, read_env_list, read_env_trimmed};
 use super::errors::RunTaskError;
+use super::external_command::ExternalCommand;
 use super::github_auth::{ensure_github_cli_auth, resolve_github_auth};
-use super::prompt::{build_prompt, load_memory_context};
+use super::prompt::prompt_for_request;
+use super::runner::{RunContext, Runner};
 use super::scheduled::{extract_scheduled_tasks, extract_scheduler_actions};
 use super::types::{RunTaskOutput, RunTaskRequest, TokenUsage};
 use super::utils::{run_command_with_timeout, run_task_timeout, tail_string};
-use super::workspace::{canonicalize_dir, workspace_path_in_container};
+use super::workspace::{
+    canonicalize_dir, resolve_expected_reply_path, workspace_path_in_container,
+};
 
 const PAYMENT_ENV_KEYS: &[&str] = &[
     "GOATX402_API_URL",
@@ -190,54 +195,35 @@ struct AzureAciConfig {
     container_share_root: PathBuf,
 }
 
-/// Check if cross-channel routing was requested and return the correct expected reply path.
-/// If reply_routing.json specifies a different target channel, compute the expected file for that target.
-fn resolve_expected_reply_path(workspace_dir: &Path, default_path: PathBuf) -> PathBuf {
-    let routing_file = workspace_dir.join("reply_routing.json");
-    if !routing_file.exists() {
-        return default_path;
-    }
-
-    // Try to read and parse reply_routing.json
-    let routing_content = match fs::read_to_string(&routing_file) {
-        Ok(content) => content,
-        Err(_) => return default_path,
-    };
+pub(super) struct CodexRunner;
 
-    // Parse the JSON to get target channel
-    let routing: serde_json::Value = match serde_json::from_str(&routing_content) {
-        Ok(v) => v,
-        Err(_) => return default_path,
-    };
+impl Runner for CodexRunner {
+    type Execution = RunTaskOutput;
 
-    let target_channel = match routing.get("channel").and_then(|c| c.as_str()) {
-        Some(ch) => ch.to_lowercase(),
-        None => return default_path,
-    };
+    fn execute(&self, ctx: &RunContext<'_>) -> Result<RunTaskOutput, RunTaskError> {
+        run_codex_task(
+            ctx.request.clone(),
+            ctx.runner,
+            ctx.reply_html_path.clone(),
+            ctx.reply_attachments_dir.clone(),
+        )
+    }
 
-    // Determine expected reply path based on TARGET channel
-    match target_channel.as_str() {
-        "email" | "googledocs" | "googlesheets" | "googleslides" => {
-            workspace_dir.join("reply_email_draft.html")
-        }
-        "slack" | "discord" | "telegram" | "sms" | "whatsapp" | "bluebubbles" => {
-            workspace_dir.join("reply_message.txt")
-        }
-        "notion" => {
-            // Notion agent posts directly via API and creates .notion_api_replied marker
-            workspace_dir.join(".notion_api_replied")
-        }
-        _ => default_path,
+    fn collect(
+        &self,
+        _ctx: &RunContext<'_>,
+        output: RunTaskOutput,
+    ) -> Result<RunTaskOutput, RunTaskError> {
+        Ok(output)
     }
 }
 
-pub(super) fn run_codex_task(
+fn run_codex_task(
     request: RunTaskRequest<'_>,
     runner: &str,
     reply_html_path: PathBuf,
     reply_attachments_dir: PathBuf,
 ) -> Result<RunTaskOutput, RunTaskError> {
-    super::env::load_env_sources(request.workspace_dir)?;
     let backend = resolve_execution_backend();
     match backend {
         ExecutionBackend::AzureAci => {
@@ -254,10 +240,12 @@ pub(super) fn run_codex_task(
         return run_codex_task_azure_aci(request, runner, reply_html_path, reply_attachments_dir);
     }
     ensure_local_execution_allowed()?;
+    let sandbox = resolve_sandbox(request.sandbox);
     let docker_image = read_env_trimmed("RUN_TASK_DOCKER_IMAGE");
-    let docker_requested = env_enabled("RUN_TASK_USE_DOCKER");
+    let docker_requested = env_enabled("RUN_TASK_USE_DOCKER") || sandbox.is_some();
     let docker_available = docker_requested && docker_cli_available();
-    let docker_required = env_enabled("RUN_TASK_DOCKER_REQUIRED");
+    // A sandboxed run never falls back to the host.
+    let docker_required = env_enabled("RUN_TASK_DOCKER_REQUIRED") || sandbox.is_some();
     let use_docker = docker_requested && docker_available;
     if docker_requested && !docker_available {
         if docker_required {
@@ -336,20 +324,7 @@ pub(super) fn run_codex_task(
     )?;
     let human_approval_gate_env_overrides = collect_human_approval_gate_env_overrides();
 
-    let memory_context = load_memory_context(request.workspace_dir, request.memory_dir)?;
-    let prompt = build_prompt(
-        request.input_email_dir,
-        request.input_attachments_dir,
-        request.memory_dir,
-        request.reference_dir,
-        request.workspace_dir,
-        runner,
-        &memory_context,
-        !request.reply_to.is_empty(),
-        request.channel,
-        request.has_unified_account,
-        request.user_identities,
-    );
+    let prompt = prompt_for_request(&request, runner)?;
 
     let timeout = run_task_timeout();
     let output = if use_docker {
@@ -443,6 +418,10 @@ pub(super) fn run_codex_task(
         }
         cmd.arg("-e")
             .arg(format!("{}=1", HUMAN_APPROVAL_GATE_REQUIRE_MCP_ENV_KEY));
+        if let Some(trace_id) = request.trace_id {
+            cmd.arg("-e")
+                .arg(format!("{}={}", TRACE_ID_ENV_KEY, trace_id));
+        }
         for (key, value) in &github_auth.env_overrides {
             cmd.arg("-e").arg(format!("{}={}", key, value));
         }
@@ -452,7 +431,9 @@ pub(super) fn run_codex_task(
                 .arg("-e")
                 .arg("GIT_TERMINAL_PROMPT=0");
         }
-        if let Some(network) = read_env_trimmed("RUN_TASK_DOCKER_NETWORK") {
+        if let Some(sandbox) = &sandbox {
+            cmd.args(sandbox.docker_args());
+        } else if let Some(network) = read_env_trimmed("RUN_TASK_DOCKER_NETWORK") {
             cmd.arg("--network").arg(network);
         }
         for dns in read_env_list("RUN_TASK_DOCKER_DNS") {
@@ -566,6 +547,9 @@ pub(super) fn run_codex_task(
             cmd.env(key, value);
         }
         cmd.env(HUMAN_APPROVAL_GATE_REQUIRE_MCP_ENV_KEY, "1");
+        if let Some(trace_id) = request.trace_id {
+            cmd.env(TRACE_ID_ENV_KEY, trace_id);
+        }
         for (key, value) in github_auth.env_overrides {
             cmd.env(key, value);
         }
@@ -657,6 +641,7 @@ pub(super) fn run_codex_task(
         scheduler_actions,
         scheduler_actions_error,
         token_usage,
+        output_issues: Vec::new(),
     })
 }
 
@@ -751,20 +736,7 @@ fn run_codex_task_azure_aci(
         collect_google_workspace_cli_env_overrides(&host_workspace_dir)?;
     let human_approval_gate_env_overrides = collect_human_approval_gate_env_overrides();
 
-    let memory_context = load_memory_context(request.workspace_dir, request.memory_dir)?;
-    let prompt = build_prompt(
-        request.input_email_dir,
-        request.input_attachments_dir,
-        request.memory_dir,
-        request.reference_dir,
-        request.workspace_dir,
-        runner,
-        &memory_context,
-        !request.reply_to.is_empty(),
-        request.channel,
-        request.has_unified_account,
-        request.user_identities,
-    );
+    let prompt = prompt_for_request(&request, runner)?;
 
     // Remote executor reads prompt from workspace file to avoid oversized command lines.
     let prompt_path = host_workspace_dir.join(".codex_remote_prompt.txt");
@@ -850,6 +822,9 @@ fn run_codex_task_azure_aci(
     for (key, value) in github_auth.env_overrides {
         env_overrides.push((key, value));
     }
+    if let Some(trace_id) = request.trace_id {
+        env_overrides.push((TRACE_ID_ENV_KEY.to_string(), trace_id.to_string()));
+    }
     if let Some(ref token) = request.google_access_token {
         env_overrides.push(("GOOGLE_ACCESS_TOKEN".to_string(), token.to_string()));
         // Also set GOOGLE_WORKSPACE_CLI_TOKEN for gws CLI (third-party @googleworkspace/cli)
@@ -967,6 +942,7 @@ fn run_codex_task_azure_aci(
         scheduler_actions,
         scheduler_actions_error,
         token_usage,
+        output_issues: Vec::new(),
     })
 }
 
@@ -1295,20 +1271,24 @@ fn poll_aci_state(
         let remaining = timeout.saturating_sub(elapsed);
         let show_timeout = remaining.min(Duration::from_secs(60));
 
-        let mut show_cmd = Command::new("az");
-        show_cmd
-            .arg("container")
-            .arg("show")
-            .arg("--name")
-            .arg(container_name)
-            .arg("--resource-group")
-            .arg(&config.resource_group)
-            .arg("--query")
-            .arg("instanceView.state")
-            .arg("--output")
-            .arg("tsv")
-            .arg("--only-show-errors");
-        let output = run_command_with_timeout(show_cmd, show_timeout, "az container show")?;
+        let show = ExternalCommand::new("az container show", || {
+            let mut show_cmd = Command::new("az");
+            show_cmd
+                .arg("container")
+                .arg("show")
+                .arg("--name")
+                .arg(container_name)
+                .arg("--resource-group")
+                .arg(&config.resource_group)
+                .arg("--query")
+                .arg("instanceView.state")
+                .arg("--output")
+                .arg("tsv")
+                .arg("--only-show-errors");
+            show_cmd
+        })
+        .timeout(show_timeout);
+        let output = show.run()?;
         if !output.status.success() {
             let mut combined = String::new();
             combined.push_str(&String::from_utf8_lossy(&output.stdout));
@@ -1341,18 +1321,22 @@ fn poll_aci_state(
 }
 
 fn fetch_aci_logs(config: &AzureAciConfig, container_name: &str) -> Result<String, RunTaskError> {
-    let mut logs_cmd = Command::new("az");
-    logs_cmd
-        .arg("container")
-        .arg("logs")
-        .arg("--name")
-        .arg(container_name)
-        .arg("--resource-group")
-        .arg(&config.resource_group)
-        .arg("--only-show-errors")
-        .arg("--output")
-        .arg("tsv");
-    let output = run_command_with_timeout(logs_cmd, Duration::from_secs(120), "az container logs")?;
+    let logs = ExternalCommand::new("az container logs", || {
+        let mut logs_cmd = Command::new("az");
+        logs_cmd
+            .arg("container")
+            .arg("logs")
+            .arg("--name")
+            .arg(container_name)
+            .arg("--resource-group")
+            .arg(&config.resource_group)
+            .arg("--only-show-errors")
+            .arg("--output")
+            .arg("tsv");
+        logs_cmd
+    })
+    .timeout(Duration::from_secs(120));
+    let output = logs.run()?;
     if !output.status.success() {
         return Ok(String::new());
     }
@@ -1365,23 +1349,27 @@ fn create_aci_container(
     create_command: &str,
     env_overrides: &[(String, String)],
 ) -> Result<(), RunTaskError> {
-    let mut create_cmd = build_aci_create_command(config, container_name, create_command);
-    if !env_overrides.is_empty() {
-        create_cmd.arg("--environment-variables");
-        for (key, value) in env_overrides {
-            create_cmd.arg(format!("{key}={value}"));
+    // Re-running create with the same name and spec is idempotent, so a
+    // throttled or timed-out attempt can be retried.
+    let create = ExternalCommand::new("az container create", || {
+        let mut create_cmd = build_aci_create_command(config, container_name, create_command);
+        if !env_overrides.is_empty() {
+            create_cmd.arg("--environment-variables");
+            for (key, value) in env_overrides {
+                create_cmd.arg(format!("{key}={value}"));
+            }
         }
-    }
+        create_cmd
+    })
+    .timeout(Duration::from_secs(300));
 
-    let create_output =
-        match run_command_with_timeout(create_cmd, Duration::from_secs(300), "az container create")
-        {
-            Ok(output) => output,
-            Err(RunTaskError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
-                return Err(RunTaskError::AzureCliNotFound)
-            }
-            Err(err) => return Err(err),
-        };
+    let create_output = match create.run() {
+        Ok(output) => output,
+        Err(RunTaskError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(RunTaskError::AzureCliNotFound)
+        }
+        Err(err) => return Err(err),
+    };
     if !create_output.status.success() {
         let mut combined = String::new();
         combined.push_str(&String::from_utf8_lossy(&create_output.stdout));
@@ -1451,18 +1439,22 @@ fn build_aci_create_command(
 }
 
 fn cleanup_stale_aci_containers(config: &AzureAciConfig) -> Result<usize, RunTaskError> {
-    let mut list_cmd = Command::new("az");
-    list_cmd
-        .arg("container")
-        .arg("list")
-        .arg("--resource-group")
-        .arg(&config.resource_group)
-        .arg("--query")
-        .arg("[?starts_with(name, 'dwz-codex-') && (instanceView.state == null || instanceView.state == 'Succeeded' || instanceView.state == 'Failed' || instanceView.state == 'Terminated' || instanceView.state == 'Stopped')].name")
-        .arg("--output")
-        .arg("tsv")
-        .arg("--only-show-errors");
-    let output = run_command_with_timeout(list_cmd, Duration::from_secs(120), "az container list")?;
+    let list = ExternalCommand::new("az container list", || {
+        let mut list_cmd = Command::new("az");
+        list_cmd
+            .arg("container")
+            .arg("list")
+            .arg("--resource-group")
+            .arg(&config.resource_group)
+            .arg("--query")
+            .arg("[?starts_with(name, 'dwz-codex-') && (instanceView.state == null || instanceView.state == 'Succeeded' || instanceView.state == 'Failed' || instanceView.state == 'Terminated' || instanceView.state == 'Stopped')].name")
+            .arg("--output")
+            .arg("tsv")
+            .arg("--only-show-errors");
+        list_cmd
+    })
+    .timeout(Duration::from_secs(120));
+    let output = list.run()?;
     if !output.status.success() {
         let mut combined = String::new();
         combined.push_str(&String::from_utf8_lossy(&output.stdout));
@@ -1499,17 +1491,23 @@ fn delete_aci_container_with_timeout(
     container_name: &str,
     command_timeout: Duration,
 ) -> Result<(), RunTaskError> {
-    let mut delete_cmd = Command::new("az");
-    delete_cmd
-        .arg("container")
-        .arg("delete")
-        .arg("--name")
-        .arg(container_name)
-        .arg("--resource-group")
-        .arg(&config.resource_group)
-        .arg("--yes")
-        .arg("--only-show-errors");
-    let output = run_command_with_timeout(delete_cmd, command_timeout, "az container delete")?;
+    let delete = ExternalCommand::new("az container delete", || {
+        let mut delete_cmd = Command::new("az");
+        delete_cmd
+            .arg("container")
+            .arg("delete")
+            .arg("--name")
+            .arg(container_name)
+            .arg("--resource-group")
+            .arg(&config.resource_group)
+            .arg("--yes")
+            .arg("--only-show-errors");
+        delete_cmd
+    })
+    // Callers own the retry policy: a later stale sweep or the retry loop below.
+    .attempts(1)
+    .timeout(command_timeout);
+    let output = delete.run()?;
     if !output.status.success() {
         let mut combined = String::new();
         combined.push_str(&String::from_utf8_lossy(&output.stdout));
@@ -1565,19 +1563,22 @@ fn wait_for_aci_container_deleted(
 ) -> Result<(), RunTaskError> {
     let started = Instant::now();
     loop {
-        let mut show_cmd = Command::new("az");
-        show_cmd
-            .arg("container")
-            .arg("show")
-            .arg("--name")
-            .arg(container_name)
-            .arg("--resource-group")
-            .arg(&config.resource_group)
-            .arg("--only-show-errors")
-            .arg("--output")
-            .arg("json");
-        let output =
-            run_command_with_timeout(show_cmd, Duration::from_secs(60), "az container show")?;
+        let show = ExternalCommand::new("az container show", || {
+            let mut show_cmd = Command::new("az");
+            show_cmd
+                .arg("container")
+                .arg("show")
+                .arg("--name")
+                .arg(container_name)
+                .arg("--resource-group")
+                .arg(&config.resource_group)
+                .arg("--only-show-errors")
+                .arg("--output")
+                .arg("json");
+            show_cmd
+        })
+        .timeout(Duration::from_secs(60));
+        let output = show.run()?;
         if output.status.success() {
             if started.elapsed() >= timeout {
                 return Err(RunTaskError::CommandTimeout {
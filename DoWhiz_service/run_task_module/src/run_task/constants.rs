@@ -20,13 +20,20 @@ env_key = "AZURE_OPENAI_API_KEY_BACKUP"
 wire_api = "responses"
 "#;
 pub(super) const DEFAULT_CLAUDE_MODEL: &str = "claude-opus-4-5";
+pub(super) const DEFAULT_GEMINI_MODEL: &str = "gemini-2.5-pro";
 pub(super) const CLAUDE_FOUNDRY_RESOURCE_DEFAULT: &str = "knowhiz-service-openai-backup-2";
+pub(super) const TRACE_ID_ENV_KEY: &str = "DOWHIZ_TRACE_ID";
 pub(super) const DOCKER_WORKSPACE_DIR: &str = "/workspace";
 pub(super) const DOCKER_CODEX_HOME_DIR: &str = ".codex";
+pub(super) const DOCKER_CLAUDE_HOME_DIR: &str = ".claude_home";
+pub(super) const DOCKER_GEMINI_HOME_DIR: &str = ".gemini";
 pub(super) const SCHEDULED_TASKS_BEGIN: &str = "SCHEDULED_TASKS_JSON_BEGIN";
 pub(super) const SCHEDULED_TASKS_END: &str = "SCHEDULED_TASKS_JSON_END";
 pub(super) const SCHEDULER_ACTIONS_BEGIN: &str = "SCHEDULER_ACTIONS_JSON_BEGIN";
 pub(super) const SCHEDULER_ACTIONS_END: &str = "SCHEDULER_ACTIONS_JSON_END";
+pub(super) const OUTPUT_MANIFEST_FILE: &str = "run_output.json";
+pub(super) const OUTPUT_ISSUES_FILE: &str = "run_output_issues.md";
+pub(super) const EMAIL_HTML_STRICT_ENV: &str = "RUN_TASK_EMAIL_HTML_STRICT";
 pub(super) const GIT_ASKPASS_SCRIPT: &str = r#"#!/bin/sh
 case "$1" in
   *Username*)
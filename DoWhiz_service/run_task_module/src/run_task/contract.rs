@@ -0,0 +1,605 @@
+//! Output contract between a runner and the scheduler.
+//!
+//! Besides the reply file, a runner may leave `run_output.json` in the
+//! workspace root:
+//!
+//! ```json
+//! {
+//!   "reply": "reply_email_draft.html",
+//!   "attachments": ["work/report.pdf"],
+//!   "scheduled_tasks": [{"type": "send_email", "...": "..."}],
+//!   "scheduler_actions": [{"action": "cancel", "task_ids": ["..."]}],
+//!   "memory_updates": [{"section": "Preferences", "facts": ["Prefers short replies"]}]
+//! }
+//! ```
+//!
+//! Every field is optional. Manifest entries take precedence over the
+//! `*_JSON_BEGIN`/`*_JSON_END` blocks in the transcript, which stay the
+//! fallback. The manifest is parsed leniently (code fences, trailing commas)
+//! and each entry is checked on its own, so one bad entry is dropped and
+//! reported instead of discarding the rest. The reply itself is checked and
+//! repaired where that is safe; a reply that is still empty fails the run.
+//! HTML replies are normalized for email clients (see [`super::email_html`])
+//! and get a plaintext alternative next to them; with
+//! `RUN_TASK_EMAIL_HTML_STRICT=1` a draft that needed content removed or its
+//! structure repaired is rejected instead, and the runner gets one attempt to
+//! fix it.
+//! Problems are returned in [`RunTaskOutput::output_issues`] and written to
+//! [`OUTPUT_ISSUES_FILE`] so the next run in the thread sees them.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::constants::{
+    EMAIL_HTML_STRICT_ENV, OUTPUT_ISSUES_FILE, OUTPUT_MANIFEST_FILE, SCHEDULED_TASKS_BEGIN,
+    SCHEDULED_TASKS_END, SCHEDULER_ACTIONS_BEGIN, SCHEDULER_ACTIONS_END,
+};
+use super::email_html::normalize_email_html;
+use super::env::env_enabled;
+use super::errors::RunTaskError;
+use super::runner::RunContext;
+use super::types::{RunTaskOutput, ScheduledTaskRequest, SchedulerActionRequest};
+use super::workspace::resolve_expected_reply_path;
+
+#[derive(Debug, Default, Deserialize)]
+struct OutputManifest {
+    /// Reply file, relative to the workspace; copied to the channel's
+    /// expected reply path when it differs.
+    #[serde(default)]
+    reply: Option<String>,
+    /// Files to attach, relative to the workspace.
+    #[serde(default)]
+    attachments: Vec<String>,
+    /// Present (even empty) means the manifest replaces the transcript block.
+    #[serde(default)]
+    scheduled_tasks: Option<Vec<Value>>,
+    #[serde(default)]
+    scheduler_actions: Option<Vec<Value>>,
+    #[serde(default)]
+    memory_updates: Vec<MemoryUpdate>,
+}
+
+/// Facts to add under a `## section` heading of `memory/memo.md`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct MemoryUpdate {
+    section: String,
+    #[serde(default)]
+    facts: Vec<String>,
+}
+
+/// What `run_output.json` contributes once its files are in place.
+#[derive(Debug, Default)]
+pub(super) struct StagedManifest {
+    scheduled_tasks: Option<Vec<ScheduledTaskRequest>>,
+    scheduler_actions: Option<Vec<SchedulerActionRequest>>,
+    issues: Vec<String>,
+}
+
+/// Read and consume `run_output.json`, copying its reply and attachments
+/// into place and applying its memory updates. Runs before the runner
+/// checks for its reply file.
+pub(super) fn stage_output_manifest(ctx: &RunContext<'_>) -> Result<StagedManifest, RunTaskError> {
+    let workspace_dir = ctx.request.workspace_dir;
+    let mut staged = StagedManifest::default();
+    let manifest_path = workspace_dir.join(OUTPUT_MANIFEST_FILE);
+    if !manifest_path.exists() {
+        return Ok(staged);
+    }
+    let raw = fs::read_to_string(&manifest_path)?;
+    // Consumed once; a manifest left behind must not replay next run.
+    fs::remove_file(&manifest_path)?;
+    let manifest = match parse_manifest(&raw) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            staged.issues.push(format!(
+                "{} is not valid JSON ({}); used the reply file and transcript blocks instead",
+                OUTPUT_MANIFEST_FILE, err
+            ));
+            return Ok(staged);
+        }
+    };
+    let issues = &mut staged.issues;
+
+    if let Some(reply) = manifest.reply.as_deref() {
+        let expected = resolve_expected_reply_path(workspace_dir, ctx.reply_html_path.clone());
+        match resolve_in_workspace(workspace_dir, reply) {
+            Some(path) if path.is_file() => {
+                if path != expected {
+                    fs::copy(&path, &expected)?;
+                }
+            }
+            Some(_) => issues.push(format!("reply {} does not exist", reply)),
+            None => issues.push(format!("reply {} is outside the workspace", reply)),
+        }
+    }
+
+    for attachment in &manifest.attachments {
+        let Some(path) = resolve_in_workspace(workspace_dir, attachment) else {
+            issues.push(format!(
+                "attachment {} is outside the workspace",
+                attachment
+            ));
+            continue;
+        };
+        if !path.is_file() {
+            issues.push(format!("attachment {} does not exist", attachment));
+            continue;
+        }
+        if path.parent() == Some(ctx.reply_attachments_dir.as_path()) {
+            continue;
+        }
+        if let Some(name) = path.file_name() {
+            fs::copy(&path, ctx.reply_attachments_dir.join(name))?;
+        }
+    }
+
+    staged.scheduled_tasks = manifest
+        .scheduled_tasks
+        .map(|entries| parse_entries(&entries, "scheduled_tasks", issues));
+    staged.scheduler_actions = manifest
+        .scheduler_actions
+        .map(|entries| parse_entries(&entries, "scheduler_actions", issues));
+
+    if !manifest.memory_updates.is_empty() {
+        let memo_path = workspace_dir.join(ctx.request.memory_dir).join("memo.md");
+        let memo = fs::read_to_string(&memo_path).unwrap_or_default();
+        let mut updated = memo.clone();
+        for update in &manifest.memory_updates {
+            if update.section.trim().is_empty() {
+                issues.push("memory update without a section was dropped".to_string());
+                continue;
+            }
+            updated = add_memory_facts(&updated, update);
+        }
+        if updated != memo {
+            fs::write(&memo_path, updated)?;
+        }
+    }
+    Ok(staged)
+}
+
+/// Merge the manifest into the collected output and validate the reply.
+pub(super) fn apply_output_contract(
+    ctx: &RunContext<'_>,
+    staged: StagedManifest,
+    mut output: RunTaskOutput,
+) -> Result<RunTaskOutput, RunTaskError> {
+    let mut issues = staged.issues;
+    if let Some(tasks) = staged.scheduled_tasks {
+        output.scheduled_tasks = tasks;
+        output.scheduled_tasks_error = None;
+    }
+    if let Some(actions) = staged.scheduler_actions {
+        output.scheduler_actions = actions;
+        output.scheduler_actions_error = None;
+    }
+
+    if !ctx.request.reply_to.is_empty() && output.reply_html_path.exists() {
+        let strict = env_enabled(EMAIL_HTML_STRICT_ENV);
+        if let Err(err) = validate_reply(&output.reply_html_path, strict, &mut issues) {
+            if let RunTaskError::DraftRejected {
+                issues: rejected, ..
+            } = &err
+            {
+                issues.extend(rejected.iter().cloned());
+                write_output_issues(ctx.request.workspace_dir, &issues)?;
+            }
+            return Err(err);
+        }
+    }
+
+    write_output_issues(ctx.request.workspace_dir, &issues)?;
+    output.output_issues = issues;
+    Ok(output)
+}
+
+/// Log `issues` and leave them in [`OUTPUT_ISSUES_FILE`] for the next run,
+/// clearing the file when there are none.
+fn write_output_issues(workspace_dir: &Path, issues: &[String]) -> Result<(), RunTaskError> {
+    let issues_path = workspace_dir.join(OUTPUT_ISSUES_FILE);
+    if issues.is_empty() {
+        if issues_path.exists() {
+            fs::remove_file(&issues_path)?;
+        }
+        return Ok(());
+    }
+    for issue in issues {
+        eprintln!("[run_task] output issue: {}", issue);
+    }
+    let listed: Vec<String> = issues.iter().map(|issue| format!("- {}", issue)).collect();
+    fs::write(&issues_path, listed.join("\n") + "\n")?;
+    Ok(())
+}
+
+fn parse_entries<T: serde::de::DeserializeOwned>(
+    entries: &[Value],
+    field: &str,
+    issues: &mut Vec<String>,
+) -> Vec<T> {
+    entries
+        .iter()
+        .enumerate()
+        .filter_map(
+            |(index, entry)| match serde_json::from_value(entry.clone()) {
+                Ok(parsed) => Some(parsed),
+                Err(err) => {
+                    issues.push(format!("{}[{}] was dropped: {}", field, index, err));
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// `raw` relative to the workspace, or `None` when it would leave it.
+fn resolve_in_workspace(workspace_dir: &Path, raw: &str) -> Option<PathBuf> {
+    let relative = Path::new(raw.trim());
+    let escapes = relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+    if raw.trim().is_empty() || escapes {
+        return None;
+    }
+    Some(workspace_dir.join(relative))
+}
+
+/// Parse the manifest, tolerating a code fence around it, text before or
+/// after the object and trailing commas.
+fn parse_manifest(raw: &str) -> Result<OutputManifest, String> {
+    let trimmed = strip_code_fence(raw.trim_start_matches('\u{feff}'));
+    let strict_err = match serde_json::from_str(trimmed) {
+        Ok(manifest) => return Ok(manifest),
+        Err(err) => err.to_string(),
+    };
+    let (Some(start), Some(end)) = (trimmed.find('{'), trimmed.rfind('}')) else {
+        return Err(strict_err);
+    };
+    if end < start {
+        return Err(strict_err);
+    }
+    serde_json::from_str(&drop_trailing_commas(&trimmed[start..=end])).map_err(|_| strict_err)
+}
+
+fn drop_trailing_commas(json: &str) -> String {
+    let chars: Vec<char> = json.chars().collect();
+    let mut out = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, &ch) in chars.iter().enumerate() {
+        if in_string {
+            out.push(ch);
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        if ch == '"' {
+            in_string = true;
+        } else if ch == ',' {
+            let next = chars[index + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']')) {
+                continue;
+            }
+        }
+        out.push(ch);
+    }
+    out
+}
+
+fn strip_code_fence(text: &str) -> &str {
+    let trimmed = text.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    let Some(body) = rest.strip_suffix("```") else {
+        return trimmed;
+    };
+    // Drop the language tag on the opening line.
+    match body.find('\n') {
+        Some(newline) => body[newline + 1..].trim(),
+        None => body.trim(),
+    }
+}
+
+/// Check the reply file and repair what can be repaired: a markdown code
+/// fence around the reply, leaked scheduler blocks, and plain text in an
+/// HTML reply. HTML is then normalized for email clients and its plaintext
+/// alternative written next to it. Fails when nothing usable is left, and
+/// in `strict` mode when normalization had to remove or repair anything.
+fn validate_reply(path: &Path, strict: bool, issues: &mut Vec<String>) -> Result<(), RunTaskError> {
+    let is_html = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html"));
+    let is_text = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"));
+    if !is_html && !is_text {
+        return Ok(());
+    }
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let Ok(raw) = fs::read_to_string(path) else {
+        return Err(RunTaskError::OutputInvalid {
+            path: path.to_path_buf(),
+            issues: vec![format!("{} is not valid UTF-8", name)],
+        });
+    };
+
+    let mut repaired = strip_code_fence(&raw).to_string();
+    if repaired.len() != raw.trim().len() {
+        issues.push(format!("{} was wrapped in a code fence; removed it", name));
+    }
+    let stripped = strip_marked_blocks(&repaired);
+    if stripped.len() != repaired.trim().len() {
+        issues.push(format!(
+            "{} contained scheduler JSON blocks; removed them",
+            name
+        ));
+    }
+    repaired = stripped;
+    if repaired.is_empty() {
+        return Err(RunTaskError::OutputInvalid {
+            path: path.to_path_buf(),
+            issues: vec![format!("{} is empty", name)],
+        });
+    }
+    if is_html && !looks_like_html(&repaired) {
+        issues.push(format!("{} had no HTML markup; wrapped it as HTML", name));
+        repaired = html_reply(&repaired);
+    }
+    if is_html {
+        let normalized = normalize_email_html(&repaired);
+        for note in &normalized.notes {
+            eprintln!("[run_task] {}: {}", name, note);
+        }
+        let defects = normalized
+            .defects
+            .iter()
+            .map(|defect| format!("{}: {}", name, defect));
+        if strict && !normalized.defects.is_empty() {
+            return Err(RunTaskError::DraftRejected {
+                path: path.to_path_buf(),
+                issues: defects.collect(),
+            });
+        }
+        issues.extend(defects);
+        fs::write(path.with_extension("txt"), normalized.text + "\n")?;
+        repaired = normalized.html;
+    }
+    if repaired.trim() != raw.trim() {
+        fs::write(path, repaired)?;
+    }
+    Ok(())
+}
+
+fn looks_like_html(text: &str) -> bool {
+    let lowered = text.to_ascii_lowercase();
+    [
+        "<html", "<body", "<p", "<div", "<br", "<table", "<ul", "<ol", "<h1", "<h2", "<h3",
+        "<span", "<a ",
+    ]
+    .iter()
+    .any(|tag| lowered.contains(tag))
+}
+
+/// `text` without the scheduled-task and scheduler-action JSON blocks.
+pub(super) fn strip_marked_blocks(text: &str) -> String {
+    let mut remaining = text.to_string();
+    for (begin, end) in [
+        (SCHEDULED_TASKS_BEGIN, SCHEDULED_TASKS_END),
+        (SCHEDULER_ACTIONS_BEGIN, SCHEDULER_ACTIONS_END),
+    ] {
+        while let Some(start) = remaining.find(begin) {
+            let Some(end_rel) = remaining[start..].find(end) else {
+                remaining.truncate(start);
+                break;
+            };
+            remaining.replace_range(start..start + end_rel + end.len(), "");
+        }
+    }
+    remaining.trim().to_string()
+}
+
+/// Wrap a reply as an HTML document, escaping it into paragraphs unless it
+/// is already markup.
+pub(super) fn html_reply(body: &str) -> String {
+    let body = body.trim();
+    let inner = if body.starts_with('<') {
+        body.to_string()
+    } else {
+        body.split("\n\n")
+            .map(|paragraph| {
+                format!(
+                    "<p>{}</p>",
+                    escape_html(paragraph.trim()).replace('\n', "<br>")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    format!("<html><body>\n{}\n</body></html>\n", inner)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Append `update.facts` under `## section`, creating the section when
+/// missing and skipping facts already listed.
+fn add_memory_facts(memo: &str, update: &MemoryUpdate) -> String {
+    let heading = format!("## {}", update.section.trim());
+    let mut lines: Vec<String> = memo.lines().map(str::to_string).collect();
+    let section_start = lines
+        .iter()
+        .position(|line| line.trim().eq_ignore_ascii_case(&heading));
+    let mut insert_at = match section_start {
+        Some(start) => lines[start + 1..]
+            .iter()
+            .position(|line| line.starts_with("## "))
+            .map(|offset| start + 1 + offset)
+            .unwrap_or(lines.len()),
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(heading);
+            lines.len()
+        }
+    };
+    // Keep new facts above the blank line that separates sections.
+    while insert_at > 0 && lines[insert_at - 1].trim().is_empty() {
+        insert_at -= 1;
+    }
+    for fact in &update.facts {
+        let fact = fact.trim().trim_start_matches("- ").trim();
+        let line = format!("- {}", fact);
+        if fact.is_empty() || lines.iter().any(|existing| existing.trim() == line) {
+            continue;
+        }
+        lines.insert(insert_at, line);
+        insert_at += 1;
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_parser_tolerates_fences_and_trailing_commas() {
+        let raw = "Here is the manifest:\n```json\n{\"reply\": \"reply_message.txt\", \"attachments\": [\"a.pdf\",],}\n```";
+        let manifest = parse_manifest(raw).expect("lenient parse");
+        assert_eq!(manifest.reply.as_deref(), Some("reply_message.txt"));
+        assert_eq!(manifest.attachments, vec!["a.pdf".to_string()]);
+        assert_eq!(
+            drop_trailing_commas(r#"{"a": "x,]", "b": [1, 2, ], }"#),
+            r#"{"a": "x,]", "b": [1, 2 ] }"#
+        );
+        assert!(parse_manifest("not json").is_err());
+    }
+
+    #[test]
+    fn invalid_entries_are_dropped_and_reported() {
+        let entries: Vec<Value> = serde_json::from_str(
+            r#"[{"action": "cancel", "task_ids": ["a"]}, {"action": "explode"}]"#,
+        )
+        .unwrap();
+        let mut issues = Vec::new();
+        let actions =
+            parse_entries::<SchedulerActionRequest>(&entries, "scheduler_actions", &mut issues);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].starts_with("scheduler_actions[1] was dropped"));
+    }
+
+    #[test]
+    fn paths_outside_the_workspace_are_rejected() {
+        let workspace = Path::new("/ws");
+        assert_eq!(
+            resolve_in_workspace(workspace, "work/report.pdf"),
+            Some(PathBuf::from("/ws/work/report.pdf"))
+        );
+        assert_eq!(resolve_in_workspace(workspace, "../other/secret"), None);
+        assert_eq!(resolve_in_workspace(workspace, "/etc/passwd"), None);
+        assert_eq!(resolve_in_workspace(workspace, " "), None);
+    }
+
+    #[test]
+    fn reply_repairs_fences_blocks_and_plain_text() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("reply_email_draft.html");
+        fs::write(
+            &path,
+            format!(
+                "```html\nAll done & sent.\n{}\n[]\n{}\n```",
+                SCHEDULED_TASKS_BEGIN, SCHEDULED_TASKS_END
+            ),
+        )
+        .unwrap();
+        let mut issues = Vec::new();
+        validate_reply(&path, false, &mut issues).expect("repairable");
+        assert_eq!(issues.len(), 3);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "<html><body>\n<p>All done &amp; sent.</p>\n</body></html>\n"
+        );
+
+        let mut issues = Vec::new();
+        validate_reply(&path, false, &mut issues).expect("already valid");
+        assert!(issues.is_empty());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("reply_email_draft.txt")).unwrap(),
+            "All done & sent.\n"
+        );
+
+        fs::write(&path, "```\n```").unwrap();
+        match validate_reply(&path, false, &mut Vec::new()) {
+            Err(RunTaskError::OutputInvalid { issues, .. }) => {
+                assert_eq!(issues, vec!["reply_email_draft.html is empty".to_string()])
+            }
+            other => panic!("expected OutputInvalid, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn strict_mode_rejects_drafts_that_needed_repair() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("reply_email_draft.html");
+        let draft = "<html><body><p>Hi</p><script>track()</script></body></html>";
+        fs::write(&path, draft).unwrap();
+        match validate_reply(&path, true, &mut Vec::new()) {
+            Err(RunTaskError::DraftRejected { issues, .. }) => assert_eq!(
+                issues,
+                vec![
+                    "reply_email_draft.html: removed <script>, which email clients do not support"
+                        .to_string()
+                ]
+            ),
+            other => panic!("expected DraftRejected, got {:?}", other),
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), draft);
+
+        let mut issues = Vec::new();
+        validate_reply(&path, false, &mut issues).expect("repaired");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "<html><body><p>Hi</p></body></html>\n"
+        );
+    }
+
+    #[test]
+    fn memory_facts_land_in_their_section_once() {
+        let memo = "# Memo\n\n## Profile\n- Name: Ann\n\n## Projects\n- Apollo\n";
+        let update = MemoryUpdate {
+            section: "Profile".to_string(),
+            facts: vec!["Goes to Stanford".to_string(), "- Name: Ann".to_string()],
+        };
+        let updated = add_memory_facts(memo, &update);
+        assert_eq!(
+            updated,
+            "# Memo\n\n## Profile\n- Name: Ann\n- Goes to Stanford\n\n## Projects\n- Apollo\n"
+        );
+
+        let update = MemoryUpdate {
+            section: "Preferences".to_string(),
+            facts: vec!["Short replies".to_string()],
+        };
+        assert_eq!(
+            add_memory_facts("", &update),
+            "## Preferences\n- Short replies\n"
+        );
+    }
+}
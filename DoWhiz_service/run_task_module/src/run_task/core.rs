@@ -1,6 +1,9 @@
-use super::claude::run_claude_task;
-use super::codex::run_codex_task;
+use super::claude::ClaudeRunner;
+use super::codex::CodexRunner;
 use super::errors::RunTaskError;
+use super::gemini::GeminiRunner;
+use super::local_model::LocalModelRunner;
+use super::runner::{run_with, RunContext};
 use super::types::{RunTaskOutput, RunTaskParams, RunTaskRequest};
 use super::workspace::{prepare_workspace, remap_workspace_dir, write_placeholder_reply};
 
@@ -19,6 +22,9 @@ pub fn run_task(params: &RunTaskParams) -> Result<RunTaskOutput, RunTaskError> {
         google_access_token: params.google_access_token.as_deref(),
         has_unified_account: params.has_unified_account,
         user_identities: &params.user_identities,
+        reply_preferences: &params.reply_preferences,
+        trace_id: params.trace_id.as_deref(),
+        sandbox: params.sandbox.as_ref(),
     };
 
     let (reply_html_path, reply_attachments_dir) = prepare_workspace(&request)?;
@@ -36,12 +42,21 @@ pub fn run_task(params: &RunTaskParams) -> Result<RunTaskOutput, RunTaskError> {
             scheduler_actions: Vec::new(),
             scheduler_actions_error: None,
             token_usage: None,
+            output_issues: Vec::new(),
         });
     }
 
+    let ctx = RunContext {
+        request,
+        runner: &runner,
+        reply_html_path,
+        reply_attachments_dir,
+    };
     match runner.as_str() {
-        "claude" => run_claude_task(request, &runner, reply_html_path, reply_attachments_dir),
-        _ => run_codex_task(request, &runner, reply_html_path, reply_attachments_dir),
+        "claude" => run_with(&ClaudeRunner, &ctx),
+        "gemini" => run_with(&GeminiRunner, &ctx),
+        "local" | "ollama" => run_with(&LocalModelRunner, &ctx),
+        _ => run_with(&CodexRunner, &ctx),
     }
 }
 
@@ -2,11 +2,128 @@ use std::env;
 use std::io;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::time::Duration;
 
-use super::env::{env_enabled_default, resolve_env_path};
+use serde::Deserialize;
+
+use super::env::{env_enabled, env_enabled_default, read_env_trimmed, resolve_env_path};
 use super::errors::RunTaskError;
+use super::external_command::ExternalCommand;
 use super::utils::tail_string;
 
+const DOCKER_BUILD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+const DEFAULT_SANDBOX_CPUS: &str = "2";
+const DEFAULT_SANDBOX_MEMORY: &str = "4g";
+const DEFAULT_SANDBOX_PIDS_LIMIT: u32 = 512;
+
+/// Limits for the container a sandboxed runner executes in. Unset fields
+/// fall back to the worker-wide `RUN_TASK_SANDBOX_*` values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SandboxProfile {
+    /// `docker run --cpus`, e.g. `"1.5"`.
+    pub cpus: Option<String>,
+    /// `docker run --memory`, e.g. `"4g"`; swap is disabled.
+    pub memory: Option<String>,
+    /// Maximum processes in the container.
+    pub pids_limit: Option<u32>,
+    /// Writable layer size (`--storage-opt size=`); needs a storage driver
+    /// with quota support, such as overlay2 on xfs with `pquota`.
+    pub disk: Option<String>,
+    /// `none`, `bridge` or the name of a docker network.
+    pub network: Option<String>,
+}
+
+impl SandboxProfile {
+    fn from_env() -> Self {
+        Self {
+            cpus: Some(
+                read_env_trimmed("RUN_TASK_SANDBOX_CPUS")
+                    .unwrap_or_else(|| DEFAULT_SANDBOX_CPUS.to_string()),
+            ),
+            memory: Some(
+                read_env_trimmed("RUN_TASK_SANDBOX_MEMORY")
+                    .unwrap_or_else(|| DEFAULT_SANDBOX_MEMORY.to_string()),
+            ),
+            pids_limit: Some(
+                read_env_trimmed("RUN_TASK_SANDBOX_PIDS_LIMIT")
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(DEFAULT_SANDBOX_PIDS_LIMIT),
+            ),
+            disk: read_env_trimmed("RUN_TASK_SANDBOX_DISK"),
+            network: read_env_trimmed("RUN_TASK_SANDBOX_NETWORK")
+                .or_else(|| read_env_trimmed("RUN_TASK_DOCKER_NETWORK")),
+        }
+    }
+
+    fn overlay(self, profile: &SandboxProfile) -> Self {
+        Self {
+            cpus: profile.cpus.clone().or(self.cpus),
+            memory: profile.memory.clone().or(self.memory),
+            pids_limit: profile.pids_limit.or(self.pids_limit),
+            disk: profile.disk.clone().or(self.disk),
+            network: profile.network.clone().or(self.network),
+        }
+    }
+
+    /// `docker run` flags enforcing the profile.
+    pub(super) fn docker_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "--security-opt".to_string(),
+            "no-new-privileges".to_string(),
+        ];
+        if let Some(cpus) = &self.cpus {
+            args.extend(["--cpus".to_string(), cpus.clone()]);
+        }
+        if let Some(memory) = &self.memory {
+            args.extend([
+                "--memory".to_string(),
+                memory.clone(),
+                "--memory-swap".to_string(),
+                memory.clone(),
+            ]);
+        }
+        if let Some(pids_limit) = self.pids_limit {
+            args.extend(["--pids-limit".to_string(), pids_limit.to_string()]);
+        }
+        if let Some(disk) = &self.disk {
+            args.extend(["--storage-opt".to_string(), format!("size={}", disk)]);
+        }
+        if let Some(network) = &self.network {
+            args.extend(["--network".to_string(), network.clone()]);
+        }
+        args
+    }
+}
+
+/// The sandbox for one run: the worker defaults overlaid with the employee's
+/// profile. `None` (run as before) unless the employee has a profile or
+/// `RUN_TASK_SANDBOX` is on.
+pub(super) fn resolve_sandbox(employee: Option<&SandboxProfile>) -> Option<SandboxProfile> {
+    if employee.is_none() && !env_enabled("RUN_TASK_SANDBOX") {
+        return None;
+    }
+    let defaults = SandboxProfile::from_env();
+    Some(match employee {
+        Some(profile) => defaults.overlay(profile),
+        None => defaults,
+    })
+}
+
+/// Image and CLI check for a sandboxed run, which must not fall back to the
+/// host.
+pub(super) fn sandbox_docker_image() -> Result<String, RunTaskError> {
+    if !docker_cli_available() {
+        return Err(RunTaskError::DockerNotFound);
+    }
+    let image = read_env_trimmed("RUN_TASK_DOCKER_IMAGE").ok_or(RunTaskError::MissingEnv {
+        key: "RUN_TASK_DOCKER_IMAGE",
+    })?;
+    ensure_docker_image_available(&image)?;
+    Ok(image)
+}
+
 pub(super) fn ensure_docker_image_available(image: &str) -> Result<(), RunTaskError> {
     if docker_image_exists(image)? {
         return Ok(());
@@ -23,22 +140,27 @@ pub(super) fn ensure_docker_image_available(image: &str) -> Result<(), RunTaskEr
     }
 
     let (dockerfile, context) = resolve_docker_build_paths()?;
-    let mut cmd = Command::new("docker");
-    cmd.args([
-        "build",
-        "-t",
-        image,
-        "-f",
-        dockerfile.to_string_lossy().as_ref(),
-        context.to_string_lossy().as_ref(),
-    ]);
-
-    let output = match cmd.output() {
+    let build = ExternalCommand::new("docker build", || {
+        let mut cmd = Command::new("docker");
+        cmd.args([
+            "build",
+            "-t",
+            image,
+            "-f",
+            dockerfile.to_string_lossy().as_ref(),
+            context.to_string_lossy().as_ref(),
+        ]);
+        cmd
+    })
+    .backoff(Duration::from_secs(15))
+    .timeout(DOCKER_BUILD_TIMEOUT);
+
+    let output = match build.run() {
         Ok(output) => output,
-        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+        Err(RunTaskError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
             return Err(RunTaskError::DockerNotFound)
         }
-        Err(err) => return Err(RunTaskError::Io(err)),
+        Err(err) => return Err(err),
     };
     let mut combined_output = String::new();
     combined_output.push_str(&String::from_utf8_lossy(&output.stdout));
@@ -56,15 +178,18 @@ pub(super) fn ensure_docker_image_available(image: &str) -> Result<(), RunTaskEr
 }
 
 pub(super) fn docker_image_exists(image: &str) -> Result<bool, RunTaskError> {
-    let output = match Command::new("docker")
-        .args(["image", "inspect", image])
-        .output()
-    {
+    let inspect = ExternalCommand::new("docker image inspect", || {
+        let mut cmd = Command::new("docker");
+        cmd.args(["image", "inspect", image]);
+        cmd
+    })
+    .timeout(Duration::from_secs(60));
+    let output = match inspect.run() {
         Ok(output) => output,
-        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+        Err(RunTaskError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
             return Err(RunTaskError::DockerNotFound)
         }
-        Err(err) => return Err(RunTaskError::Io(err)),
+        Err(err) => return Err(err),
     };
 
     Ok(output.status.success())
@@ -119,3 +244,50 @@ pub(super) fn docker_cli_available() -> bool {
         Err(_) => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn employee_profile_overrides_defaults_field_by_field() {
+        let defaults = SandboxProfile {
+            cpus: Some("2".to_string()),
+            memory: Some("4g".to_string()),
+            pids_limit: Some(512),
+            disk: None,
+            network: Some("dowhiz".to_string()),
+        };
+        let employee = SandboxProfile {
+            memory: Some("1g".to_string()),
+            network: Some("none".to_string()),
+            ..SandboxProfile::default()
+        };
+        assert_eq!(
+            defaults.overlay(&employee).docker_args(),
+            [
+                "--security-opt",
+                "no-new-privileges",
+                "--cpus",
+                "2",
+                "--memory",
+                "1g",
+                "--memory-swap",
+                "1g",
+                "--pids-limit",
+                "512",
+                "--network",
+                "none",
+            ]
+        );
+    }
+
+    #[test]
+    fn profile_parses_from_toml_and_rejects_unknown_keys() {
+        let profile: SandboxProfile =
+            toml::from_str("cpus = \"0.5\"\ndisk = \"10g\"").expect("profile");
+        assert_eq!(profile.cpus.as_deref(), Some("0.5"));
+        assert_eq!(profile.disk.as_deref(), Some("10g"));
+        assert!(toml::from_str::<SandboxProfile>("gpus = 1").is_err());
+    }
+}
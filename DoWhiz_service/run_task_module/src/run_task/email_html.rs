@@ -0,0 +1,900 @@
+//! Pre-send normalization of HTML email drafts.
+//!
+//! Gmail and Outlook ignore `<style>` blocks in many contexts, drop scripts
+//! and embeds, and render unbalanced markup unpredictably. Before an HTML
+//! reply is handed to the scheduler it is rewritten so that:
+//!
+//! - rules from `<style>` blocks with simple selectors (`p`, `.note`, `#top`,
+//!   `td.total`) are inlined into `style` attributes; other rules are dropped;
+//! - scripts, embeds, media and form controls are removed, as are `on*`
+//!   handlers and `javascript:` links;
+//! - stray closing tags are dropped and unclosed elements are closed;
+//! - the draft is wrapped in `<html><body>` when it has no `<body>`.
+//!
+//! A plaintext alternative is rendered alongside. Removals and structural
+//! fixes are reported as defects, which strict mode rejects.
+
+use std::collections::BTreeSet;
+
+/// Removed together with everything inside them.
+const DROPPED_ELEMENTS: &[&str] = &[
+    "script", "iframe", "object", "embed", "applet", "frame", "frameset", "video", "audio",
+    "canvas", "svg", "noscript", "select", "textarea",
+];
+/// Removed, keeping what is inside them.
+const UNWRAPPED_ELEMENTS: &[&str] = &["form", "button"];
+/// Removed standalone tags.
+const DROPPED_TAGS: &[&str] = &["input", "link", "base"];
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+/// Elements whose end tag HTML lets authors leave out.
+const OPTIONAL_END: &[&str] = &[
+    "p", "li", "dt", "dd", "tr", "td", "th", "thead", "tbody", "tfoot", "option", "colgroup",
+    "head", "body", "html",
+];
+/// Elements that start a new line in the plaintext alternative.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p",
+    "div",
+    "br",
+    "tr",
+    "table",
+    "ul",
+    "ol",
+    "li",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "hr",
+    "pre",
+    "section",
+    "article",
+    "header",
+    "footer",
+];
+
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct NormalizedEmail {
+    pub(super) html: String,
+    pub(super) text: String,
+    /// Rewrites that keep the draft as written, e.g. inlined CSS.
+    pub(super) notes: Vec<String>,
+    /// Content that was removed or markup that had to be repaired.
+    pub(super) defects: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    Text(String),
+    Tag(Tag),
+    /// Comments and doctypes, passed through unchanged.
+    Other(String),
+}
+
+#[derive(Debug, Clone)]
+struct Tag {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    attrs: Vec<(String, Option<String>)>,
+    raw: String,
+    modified: bool,
+}
+
+impl Tag {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    fn closing(name: &str) -> Self {
+        Tag {
+            name: name.to_string(),
+            closing: true,
+            self_closing: false,
+            attrs: Vec::new(),
+            raw: format!("</{}>", name),
+            modified: false,
+        }
+    }
+
+    fn render(&self) -> String {
+        if !self.modified {
+            return self.raw.clone();
+        }
+        let mut out = format!("<{}", self.name);
+        for (key, value) in &self.attrs {
+            match value {
+                Some(value) => {
+                    out.push_str(&format!(" {}=\"{}\"", key, value.replace('"', "&quot;")))
+                }
+                None => out.push_str(&format!(" {}", key)),
+            }
+        }
+        out.push_str(if self.self_closing { " />" } else { ">" });
+        out
+    }
+}
+
+/// A `<style>` rule with a selector simple enough to match without a DOM.
+#[derive(Debug, Clone, PartialEq)]
+struct CssRule {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    declarations: String,
+}
+
+impl CssRule {
+    fn specificity(&self) -> (usize, usize, usize) {
+        (
+            usize::from(self.id.is_some()),
+            self.classes.len(),
+            usize::from(self.tag.is_some()),
+        )
+    }
+
+    fn matches(&self, tag: &Tag) -> bool {
+        if self.tag.as_deref().is_some_and(|name| name != tag.name) {
+            return false;
+        }
+        if let Some(id) = &self.id {
+            if tag.attr("id") != Some(id.as_str()) {
+                return false;
+            }
+        }
+        let classes: Vec<&str> = tag.attr("class").unwrap_or("").split_whitespace().collect();
+        self.classes
+            .iter()
+            .all(|class| classes.contains(&class.as_str()))
+    }
+}
+
+pub(super) fn normalize_email_html(html: &str) -> NormalizedEmail {
+    let mut result = NormalizedEmail::default();
+    let mut tokens = tokenize(html);
+
+    let mut css = String::new();
+    let mut removed = BTreeSet::new();
+    tokens = drop_elements(tokens, &mut css, &mut removed);
+    for name in &removed {
+        result.defects.push(format!(
+            "removed <{}>, which email clients do not support",
+            name
+        ));
+    }
+
+    let (rules, skipped) = parse_css(&css);
+    if !rules.is_empty() {
+        inline_css(&mut tokens, &rules);
+        result
+            .notes
+            .push(format!("inlined {} CSS rule(s) from <style>", rules.len()));
+    }
+    if skipped > 0 {
+        result.notes.push(format!(
+            "dropped {} CSS rule(s) with selectors that cannot be inlined",
+            skipped
+        ));
+    }
+
+    let (handlers, scripted_links) = strip_active_attributes(&mut tokens);
+    if handlers > 0 {
+        result.defects.push(format!(
+            "removed {} on* event handler attribute(s)",
+            handlers
+        ));
+    }
+    if scripted_links > 0 {
+        result
+            .defects
+            .push(format!("removed {} javascript: link(s)", scripted_links));
+    }
+
+    let (stray, unclosed) = balance(&mut tokens);
+    if !stray.is_empty() {
+        result.defects.push(format!(
+            "dropped stray closing tag(s): {}",
+            stray.join(", ")
+        ));
+    }
+    if !unclosed.is_empty() {
+        result.defects.push(format!(
+            "closed unclosed element(s): {}",
+            unclosed.join(", ")
+        ));
+    }
+
+    let mut html: String = tokens
+        .iter()
+        .map(|token| match token {
+            Token::Text(text) | Token::Other(text) => text.clone(),
+            Token::Tag(tag) => tag.render(),
+        })
+        .collect();
+    let has_body = tokens
+        .iter()
+        .any(|token| matches!(token, Token::Tag(tag) if tag.name == "body"));
+    if !has_body {
+        html = format!("<html><body>\n{}\n</body></html>", html.trim());
+        result
+            .notes
+            .push("wrapped the draft in <html><body>".to_string());
+    }
+    result.html = html.trim().to_string() + "\n";
+
+    result.text = plain_text(&tokens);
+    if result.text.is_empty() {
+        result
+            .defects
+            .push("the draft has no visible text".to_string());
+    }
+    result
+}
+
+fn tokenize(html: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").map(|end| end + 7).unwrap_or(rest.len());
+            flush_text(&mut tokens, &mut text);
+            tokens.push(Token::Other(rest[..end].to_string()));
+            rest = &rest[end..];
+            continue;
+        }
+        let next = rest[1..].chars().next();
+        let starts_tag = next.is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '/' || ch == '!');
+        let Some(end) = starts_tag.then(|| tag_end(rest)).flatten() else {
+            text.push('<');
+            rest = &rest[1..];
+            continue;
+        };
+        flush_text(&mut tokens, &mut text);
+        let raw = &rest[..=end];
+        rest = &rest[end + 1..];
+        if next == Some('!') {
+            tokens.push(Token::Other(raw.to_string()));
+            continue;
+        }
+        let Some(tag) = parse_tag(raw) else {
+            tokens.push(Token::Text(raw.to_string()));
+            continue;
+        };
+        let raw_text = !tag.closing && matches!(tag.name.as_str(), "script" | "style");
+        let name = tag.name.clone();
+        tokens.push(Token::Tag(tag));
+        if raw_text {
+            let close = format!("</{}", name);
+            let end = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+            if end > 0 {
+                tokens.push(Token::Text(rest[..end].to_string()));
+            }
+            rest = &rest[end..];
+        }
+    }
+    text.push_str(rest);
+    flush_text(&mut tokens, &mut text);
+    tokens
+}
+
+fn flush_text(tokens: &mut Vec<Token>, text: &mut String) {
+    if !text.is_empty() {
+        tokens.push(Token::Text(std::mem::take(text)));
+    }
+}
+
+/// Index of the `>` closing the tag at the start of `rest`, skipping quoted
+/// attribute values.
+fn tag_end(rest: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, ch) in rest.char_indices().skip(1) {
+        match (quote, ch) {
+            (Some(open), _) if ch == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '>') => return Some(index),
+            (None, '<') => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_tag(raw: &str) -> Option<Tag> {
+    let inner = raw.strip_prefix('<')?.strip_suffix('>')?;
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, inner),
+    };
+    let self_closing = inner.trim_end().ends_with('/');
+    let inner = inner.trim_end().trim_end_matches('/');
+    let name_end = inner
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '-' || ch == ':'))
+        .unwrap_or(inner.len());
+    let name = inner[..name_end].to_ascii_lowercase();
+    if name.is_empty() {
+        return None;
+    }
+    Some(Tag {
+        name,
+        closing,
+        self_closing,
+        attrs: parse_attrs(&inner[name_end..]),
+        raw: raw.to_string(),
+        modified: false,
+    })
+}
+
+fn parse_attrs(mut rest: &str) -> Vec<(String, Option<String>)> {
+    let mut attrs = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        let key_end = rest
+            .find(|ch: char| ch.is_whitespace() || ch == '=')
+            .unwrap_or(rest.len());
+        if key_end == 0 {
+            break;
+        }
+        let key = rest[..key_end].to_ascii_lowercase();
+        rest = rest[key_end..].trim_start();
+        let Some(value) = rest.strip_prefix('=') else {
+            attrs.push((key, None));
+            continue;
+        };
+        let value = value.trim_start();
+        let (parsed, remaining) = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => match value[1..].find(quote) {
+                Some(end) => (&value[1..=end], &value[end + 2..]),
+                None => (&value[1..], ""),
+            },
+            _ => {
+                let end = value.find(char::is_whitespace).unwrap_or(value.len());
+                (&value[..end], &value[end..])
+            }
+        };
+        attrs.push((key, Some(parsed.to_string())));
+        rest = remaining;
+    }
+    attrs
+}
+
+/// Remove unsupported elements, collecting `<style>` contents into `css`
+/// and the names of removed elements into `removed`.
+fn drop_elements(
+    tokens: Vec<Token>,
+    css: &mut String,
+    removed: &mut BTreeSet<String>,
+) -> Vec<Token> {
+    let mut kept = Vec::with_capacity(tokens.len());
+    // Name and nesting depth of the element being skipped.
+    let mut skipping: Option<(String, usize)> = None;
+    let mut in_style = false;
+    for token in tokens {
+        if let Some((name, depth)) = skipping.as_mut() {
+            if let Token::Tag(tag) = &token {
+                if tag.name == *name && !tag.self_closing {
+                    if tag.closing {
+                        *depth -= 1;
+                    } else {
+                        *depth += 1;
+                    }
+                }
+            }
+            if *depth == 0 {
+                skipping = None;
+            }
+            continue;
+        }
+        let Token::Tag(tag) = &token else {
+            if in_style {
+                if let Token::Text(text) = &token {
+                    css.push_str(text);
+                    css.push('\n');
+                }
+            } else {
+                kept.push(token);
+            }
+            continue;
+        };
+        let name = tag.name.as_str();
+        if name == "style" {
+            in_style = !tag.closing && !tag.self_closing;
+            continue;
+        }
+        if DROPPED_ELEMENTS.contains(&name) {
+            removed.insert(name.to_string());
+            if !tag.closing && !tag.self_closing && !VOID_ELEMENTS.contains(&name) {
+                skipping = Some((name.to_string(), 1));
+            }
+            continue;
+        }
+        if UNWRAPPED_ELEMENTS.contains(&name) || DROPPED_TAGS.contains(&name) {
+            removed.insert(name.to_string());
+            continue;
+        }
+        kept.push(token);
+    }
+    kept
+}
+
+/// Inlinable rules in source order, and how many rules were skipped.
+fn parse_css(css: &str) -> (Vec<CssRule>, usize) {
+    let css = strip_css_comments(css);
+    let mut rules = Vec::new();
+    let mut skipped = 0;
+    let mut rest = css.as_str();
+    while let Some(open) = rest.find('{') {
+        let prelude = rest[..open].trim();
+        let Some(close) = matching_brace(&rest[open..]) else {
+            break;
+        };
+        let body = rest[open + 1..open + close].trim();
+        rest = &rest[open + close + 1..];
+        if prelude.starts_with('@') {
+            skipped += 1;
+            continue;
+        }
+        let declarations = body.trim_end_matches(';').trim();
+        if declarations.is_empty() {
+            continue;
+        }
+        for selector in prelude.split(',') {
+            match parse_selector(selector.trim()) {
+                Some((tag, id, classes)) => rules.push(CssRule {
+                    tag,
+                    id,
+                    classes,
+                    declarations: declarations.to_string(),
+                }),
+                None => skipped += 1,
+            }
+        }
+    }
+    (rules, skipped)
+}
+
+fn strip_css_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        rest = rest[start + 2..]
+            .find("*/")
+            .map(|end| &rest[start + 2 + end + 2..])
+            .unwrap_or("");
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Offset of the `}` matching the `{` at the start of `text`.
+fn matching_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, ch) in text.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+type Selector = (Option<String>, Option<String>, Vec<String>);
+
+/// `tag`, `#id` and `.class` parts of a compound selector; `None` for
+/// combinators, pseudo-classes, attribute selectors and `*`.
+fn parse_selector(selector: &str) -> Option<Selector> {
+    let is_ident = |ch: char| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_';
+    if selector.is_empty()
+        || !selector
+            .chars()
+            .all(|ch| is_ident(ch) || ch == '.' || ch == '#')
+    {
+        return None;
+    }
+    let mut tag = None;
+    let mut id = None;
+    let mut classes = Vec::new();
+    let mut rest = selector;
+    while !rest.is_empty() {
+        let (marker, body) = match rest.chars().next() {
+            Some(marker @ ('.' | '#')) => (Some(marker), &rest[1..]),
+            _ => (None, rest),
+        };
+        let end = body.find(['.', '#']).unwrap_or(body.len());
+        let name = &body[..end];
+        if name.is_empty() {
+            return None;
+        }
+        match marker {
+            Some('.') => classes.push(name.to_string()),
+            Some(_) if id.is_none() => id = Some(name.to_string()),
+            None if tag.is_none() => tag = Some(name.to_ascii_lowercase()),
+            _ => return None,
+        }
+        rest = &body[end..];
+    }
+    Some((tag, id, classes))
+}
+
+/// Prepend matching declarations to each element's `style`, so its own
+/// inline style still wins.
+fn inline_css(tokens: &mut [Token], rules: &[CssRule]) {
+    for token in tokens.iter_mut() {
+        let Token::Tag(tag) = token else {
+            continue;
+        };
+        if tag.closing || matches!(tag.name.as_str(), "html" | "head" | "title" | "meta") {
+            continue;
+        }
+        let mut matched: Vec<(usize, &CssRule)> = rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| rule.matches(tag))
+            .collect();
+        if matched.is_empty() {
+            continue;
+        }
+        matched.sort_by_key(|(index, rule)| (rule.specificity(), *index));
+        let mut style: Vec<&str> = matched
+            .iter()
+            .map(|(_, rule)| rule.declarations.as_str())
+            .collect();
+        let existing = tag
+            .attr("style")
+            .map(|value| value.trim().trim_end_matches(';').to_string());
+        if let Some(existing) = existing.as_deref().filter(|value| !value.is_empty()) {
+            style.push(existing);
+        }
+        let style = style.join("; ") + ";";
+        tag.attrs.retain(|(key, _)| key != "style");
+        tag.attrs.push(("style".to_string(), Some(style)));
+        tag.modified = true;
+    }
+}
+
+/// Remove `on*` handlers and `javascript:` URLs; returns how many of each.
+fn strip_active_attributes(tokens: &mut [Token]) -> (usize, usize) {
+    let mut handlers = 0;
+    let mut links = 0;
+    for token in tokens.iter_mut() {
+        let Token::Tag(tag) = token else {
+            continue;
+        };
+        let before = tag.attrs.len();
+        tag.attrs.retain(|(key, value)| {
+            if key.starts_with("on") {
+                handlers += 1;
+                return false;
+            }
+            let scripted = matches!(key.as_str(), "href" | "src" | "action")
+                && value.as_deref().is_some_and(|value| {
+                    value
+                        .trim()
+                        .to_ascii_lowercase()
+                        .replace(char::is_whitespace, "")
+                        .starts_with("javascript:")
+                });
+            if scripted {
+                links += 1;
+            }
+            !scripted
+        });
+        if tag.attrs.len() != before {
+            tag.modified = true;
+        }
+    }
+    (handlers, links)
+}
+
+/// Drop closing tags with no open element and close elements left open.
+/// Elements whose end tag is optional are closed without a report.
+fn balance(tokens: &mut Vec<Token>) -> (Vec<String>, Vec<String>) {
+    let mut stray = Vec::new();
+    let mut unclosed = Vec::new();
+    let mut open: Vec<String> = Vec::new();
+    let mut balanced = Vec::with_capacity(tokens.len());
+    for token in tokens.drain(..) {
+        let Token::Tag(tag) = &token else {
+            balanced.push(token);
+            continue;
+        };
+        if VOID_ELEMENTS.contains(&tag.name.as_str()) || tag.self_closing {
+            if !tag.closing {
+                balanced.push(token);
+            }
+            continue;
+        }
+        if !tag.closing {
+            let implied = implied_end(&tag.name);
+            while open
+                .last()
+                .is_some_and(|name| implied.contains(&name.as_str()))
+            {
+                if let Some(name) = open.pop() {
+                    balanced.push(Token::Tag(Tag::closing(&name)));
+                }
+            }
+            open.push(tag.name.clone());
+            balanced.push(token);
+            continue;
+        }
+        let Some(position) = open.iter().rposition(|name| *name == tag.name) else {
+            stray.push(format!("</{}>", tag.name));
+            continue;
+        };
+        for name in open.drain(position + 1..).rev() {
+            if !OPTIONAL_END.contains(&name.as_str()) {
+                unclosed.push(format!("<{}>", name));
+            }
+            balanced.push(Token::Tag(Tag::closing(&name)));
+        }
+        open.pop();
+        balanced.push(token);
+    }
+    for name in open.into_iter().rev() {
+        if !OPTIONAL_END.contains(&name.as_str()) {
+            unclosed.push(format!("<{}>", name));
+        }
+        balanced.push(Token::Tag(Tag::closing(&name)));
+    }
+    *tokens = balanced;
+    (stray, unclosed)
+}
+
+/// Open elements that an opening `name` tag closes, as in `<li>a<li>b`.
+fn implied_end(name: &str) -> &'static [&'static str] {
+    match name {
+        "li" => &["li"],
+        "p" => &["p"],
+        "dt" | "dd" => &["dt", "dd"],
+        "td" | "th" => &["td", "th"],
+        "tr" => &["td", "th", "tr"],
+        "option" => &["option"],
+        _ => &[],
+    }
+}
+
+/// Plaintext rendering: block elements on their own lines, list items as
+/// `- ` bullets and links followed by their URL.
+fn plain_text(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut hidden = 0usize;
+    let mut link: Option<(String, usize)> = None;
+    for token in tokens {
+        match token {
+            Token::Text(text) if hidden == 0 => {
+                let decoded = decode_entities(text);
+                let mut words = decoded.split_whitespace().peekable();
+                if words.peek().is_none() {
+                    if !decoded.is_empty() && !out.ends_with([' ', '\n']) && !out.is_empty() {
+                        out.push(' ');
+                    }
+                    continue;
+                }
+                if decoded.starts_with(char::is_whitespace)
+                    && !out.ends_with([' ', '\n'])
+                    && !out.is_empty()
+                {
+                    out.push(' ');
+                }
+                out.push_str(&words.collect::<Vec<_>>().join(" "));
+                if decoded.ends_with(char::is_whitespace) {
+                    out.push(' ');
+                }
+            }
+            Token::Tag(tag) => {
+                let name = tag.name.as_str();
+                if matches!(name, "head" | "title") {
+                    if tag.closing {
+                        hidden = hidden.saturating_sub(1);
+                    } else {
+                        hidden += 1;
+                    }
+                    continue;
+                }
+                if hidden > 0 {
+                    continue;
+                }
+                if name == "a" {
+                    if tag.closing {
+                        if let Some((href, start)) = link.take() {
+                            if out[start..].trim() != href {
+                                out.truncate(out.trim_end_matches(' ').len());
+                                out.push_str(&format!(" ({})", href));
+                            }
+                        }
+                    } else if let Some(href) =
+                        tag.attr("href").filter(|href| !href.starts_with('#'))
+                    {
+                        link = Some((decode_entities(href), out.len()));
+                    }
+                    continue;
+                }
+                if name == "img" && !tag.closing {
+                    if let Some(alt) = tag
+                        .attr("alt")
+                        .map(decode_entities)
+                        .filter(|alt| !alt.trim().is_empty())
+                    {
+                        out.push_str(&format!("[{}]", alt.trim()));
+                    }
+                    continue;
+                }
+                if BLOCK_ELEMENTS.contains(&name) {
+                    out.truncate(out.trim_end_matches(' ').len());
+                    let breaks = if matches!(
+                        name,
+                        "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "table" | "blockquote"
+                    ) {
+                        2
+                    } else {
+                        1
+                    };
+                    let existing = out.len() - out.trim_end_matches('\n').len();
+                    if !out.is_empty() {
+                        for _ in existing..breaks {
+                            out.push('\n');
+                        }
+                    }
+                    if name == "li" && !tag.closing {
+                        out.push_str("- ");
+                    }
+                } else if matches!(name, "td" | "th") && tag.closing {
+                    out.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut text = out.lines().map(str::trim).collect::<Vec<_>>().join("\n");
+    while text.contains("\n\n\n") {
+        text = text.replace("\n\n\n", "\n\n");
+    }
+    text.trim().to_string()
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            ch.map(|ch| (ch, end))
+        });
+        match decoded {
+            Some((ch, end)) => {
+                out.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_are_inlined_and_unsupported_markup_is_removed() {
+        let draft = r#"<html><head><style>
+/* brand */
+p { color: #333; margin: 0 }
+.note, #top { font-weight: bold; }
+td.total { text-align: right }
+div > p { color: red }
+@media (max-width: 600px) { p { font-size: 18px } }
+</style></head><body>
+<p id="top" style="font-size: 14px">Hi Ann &amp; team,</p>
+<p class="note" onclick="track()">Totals below.</p>
+<script>alert("x")</script>
+<table><tr><td class="total">$5</td></tr></table>
+<a href="javascript:void(0)">bad</a> <a href="https://example.com/r">report</a>
+</body></html>"#;
+        let normalized = normalize_email_html(draft);
+        assert!(normalized.html.contains(
+            r#"<p id="top" style="color: #333; margin: 0; font-weight: bold; font-size: 14px;">"#
+        ));
+        assert!(normalized
+            .html
+            .contains(r#"<p class="note" style="color: #333; margin: 0; font-weight: bold;">"#));
+        assert!(normalized
+            .html
+            .contains(r#"<td class="total" style="text-align: right;">"#));
+        assert!(!normalized.html.contains("<style"));
+        assert!(!normalized.html.contains("alert"));
+        assert!(!normalized.html.contains("javascript:"));
+        assert_eq!(
+            normalized.notes,
+            vec![
+                "inlined 4 CSS rule(s) from <style>".to_string(),
+                "dropped 2 CSS rule(s) with selectors that cannot be inlined".to_string(),
+            ]
+        );
+        assert_eq!(
+            normalized.defects,
+            vec![
+                "removed <script>, which email clients do not support".to_string(),
+                "removed 1 on* event handler attribute(s)".to_string(),
+                "removed 1 javascript: link(s)".to_string(),
+            ]
+        );
+        assert_eq!(
+            normalized.text,
+            "Hi Ann & team,\n\nTotals below.\n\n$5\n\nbad report (https://example.com/r)"
+        );
+
+        let again = normalize_email_html(&normalized.html);
+        assert_eq!(again.html, normalized.html);
+        assert!(again.notes.is_empty() && again.defects.is_empty());
+    }
+
+    #[test]
+    fn structure_is_repaired_and_reported() {
+        let normalized = normalize_email_html("<div><b>Done</div></span><ul><li>one<li>two</ul>");
+        assert_eq!(
+            normalized.html,
+            "<html><body>\n<div><b>Done</b></div><ul><li>one</li><li>two</li></ul>\n</body></html>\n"
+        );
+        assert_eq!(
+            normalized.defects,
+            vec![
+                "dropped stray closing tag(s): </span>".to_string(),
+                "closed unclosed element(s): <b>".to_string(),
+            ]
+        );
+        assert_eq!(
+            normalized.notes,
+            vec!["wrapped the draft in <html><body>".to_string()]
+        );
+        assert_eq!(normalized.text, "Done\n- one\n- two");
+
+        let empty = normalize_email_html("<html><body><iframe src=\"x\"></iframe></body></html>");
+        assert!(empty
+            .defects
+            .contains(&"the draft has no visible text".to_string()));
+    }
+}
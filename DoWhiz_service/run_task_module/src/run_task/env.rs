@@ -91,6 +91,22 @@ pub(super) fn normalize_env_prefix(raw: &str) -> String {
         .collect()
 }
 
+/// `PATH` with the DoWhiz tool directory (google-docs and friends) in front.
+pub(super) fn path_with_dowhiz_bin() -> String {
+    let current_path = env::var("PATH").unwrap_or_default();
+    // Look for DOWHIZ_BIN_DIR env var, or use default location relative to crate
+    let dowhiz_bin_dir = env::var("DOWHIZ_BIN_DIR")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| {
+            // Default: assume bin/ is sibling to scheduler_module
+            let manifest_dir = env!("CARGO_MANIFEST_DIR");
+            let parent = Path::new(manifest_dir).parent().unwrap_or(Path::new("."));
+            parent.join("bin").to_string_lossy().into_owned()
+        });
+    format!("{}:{}", dowhiz_bin_dir, current_path)
+}
+
 pub(super) fn read_env_trimmed(key: &str) -> Option<String> {
     let value = env::var(key).ok()?;
     let trimmed = value.trim();
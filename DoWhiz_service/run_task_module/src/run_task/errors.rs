@@ -26,6 +26,14 @@ pub enum RunTaskError {
         status: Option<i32>,
         output: String,
     },
+    GeminiNotFound,
+    GeminiInstallFailed {
+        output: String,
+    },
+    GeminiFailed {
+        status: Option<i32>,
+        output: String,
+    },
     DockerNotFound,
     DockerFailed {
         status: Option<i32>,
@@ -52,6 +60,23 @@ pub enum RunTaskError {
         path: PathBuf,
         output: String,
     },
+    OutputInvalid {
+        path: PathBuf,
+        issues: Vec<String>,
+    },
+    /// Strict mode turned down an HTML email draft that needed repair.
+    DraftRejected {
+        path: PathBuf,
+        issues: Vec<String>,
+    },
+    LocalModelFailed {
+        status: Option<u16>,
+        output: String,
+    },
+    /// The run was stopped through `terminate_run` while `command` ran.
+    Terminated {
+        command: &'static str,
+    },
 }
 
 impl fmt::Display for RunTaskError {
@@ -85,6 +110,15 @@ impl fmt::Display for RunTaskError {
                 "Claude failed (status: {:?}). Output tail:\n{}",
                 status, output
             ),
+            RunTaskError::GeminiNotFound => write!(f, "Gemini CLI not found on PATH."),
+            RunTaskError::GeminiInstallFailed { output } => {
+                write!(f, "Failed to install Gemini CLI. Output tail:\n{}", output)
+            }
+            RunTaskError::GeminiFailed { status, output } => write!(
+                f,
+                "Gemini failed (status: {:?}). Output tail:\n{}",
+                status, output
+            ),
             RunTaskError::DockerNotFound => write!(f, "Docker CLI not found on PATH."),
             RunTaskError::DockerFailed { status, output } => write!(
                 f,
@@ -126,6 +160,26 @@ impl fmt::Display for RunTaskError {
                     output
                 )
             }
+            RunTaskError::OutputInvalid { path, issues } => write!(
+                f,
+                "Output failed validation: {}\n- {}",
+                path.display(),
+                issues.join("\n- ")
+            ),
+            RunTaskError::DraftRejected { path, issues } => write!(
+                f,
+                "Email draft rejected by strict HTML checks: {}\n- {}",
+                path.display(),
+                issues.join("\n- ")
+            ),
+            RunTaskError::LocalModelFailed { status, output } => write!(
+                f,
+                "Local model request failed (status: {:?}). Output tail:\n{}",
+                status, output
+            ),
+            RunTaskError::Terminated { command } => {
+                write!(f, "Run terminated by the supervisor ({} was stopped)", command)
+            }
         }
     }
 }
@@ -0,0 +1,324 @@
+//! Per-execution logs of the commands a run starts.
+//!
+//! The caller enters an [`ExecLogScope`] on the thread that executes a run,
+//! like a [`super::processes::RunScope`]. Every command run_task starts on
+//! that thread then has its stdout and stderr appended to the scope's file,
+//! one `stdout| `/`stderr| ` prefixed line at a time, between a header and an
+//! exit line. The output is still returned to the caller as before.
+//!
+//! A file past `EXEC_LOG_MAX_BYTES` (default 10 MiB) is rotated to `.1`,
+//! `.2`, ... keeping `EXEC_LOG_ROTATIONS` (default 3) old files, and entering
+//! a scope keeps only the newest `EXEC_LOG_KEEP` (default 20) execution logs
+//! in its directory.
+
+use std::cell::RefCell;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Directory of a workspace that holds its execution logs.
+pub const EXEC_LOG_DIR: &str = ".logs";
+
+const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+const DEFAULT_ROTATIONS: usize = 3;
+const DEFAULT_KEEP: usize = 20;
+
+thread_local! {
+    static CURRENT_LOG: RefCell<Option<Arc<ExecLog>>> = const { RefCell::new(None) };
+}
+
+/// Appends the output of this thread's commands to one execution's log
+/// until dropped.
+pub struct ExecLogScope {
+    previous: Option<Arc<ExecLog>>,
+}
+
+impl ExecLogScope {
+    /// Opens (or creates) `path` and its directory. Fails without entering
+    /// the scope, so a run whose log cannot be written still goes ahead.
+    pub fn enter(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let log = Arc::new(ExecLog::open(path.into(), LogLimits::from_env())?);
+        if let Some(dir) = log.path.parent() {
+            prune_logs(dir, &log.path, log.limits.keep)?;
+        }
+        let previous = CURRENT_LOG.with(|current| current.replace(Some(log)));
+        Ok(Self { previous })
+    }
+}
+
+impl Drop for ExecLogScope {
+    fn drop(&mut self) {
+        CURRENT_LOG.with(|current| *current.borrow_mut() = self.previous.take());
+    }
+}
+
+/// The log of the execution on this thread, if any.
+pub(super) fn current_log() -> Option<Arc<ExecLog>> {
+    CURRENT_LOG.with(|current| current.borrow().clone())
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LogLimits {
+    max_bytes: u64,
+    rotations: usize,
+    keep: usize,
+}
+
+impl LogLimits {
+    fn from_env() -> Self {
+        let parse = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        Self {
+            max_bytes: parse("EXEC_LOG_MAX_BYTES")
+                .filter(|value| *value > 0)
+                .unwrap_or(DEFAULT_MAX_BYTES),
+            rotations: parse("EXEC_LOG_ROTATIONS")
+                .map(|value| value as usize)
+                .unwrap_or(DEFAULT_ROTATIONS),
+            keep: parse("EXEC_LOG_KEEP")
+                .filter(|value| *value > 0)
+                .map(|value| value as usize)
+                .unwrap_or(DEFAULT_KEEP),
+        }
+    }
+}
+
+pub(super) struct ExecLog {
+    path: PathBuf,
+    limits: LogLimits,
+    file: Mutex<LogFile>,
+}
+
+struct LogFile {
+    file: File,
+    len: u64,
+}
+
+impl ExecLog {
+    fn open(path: PathBuf, limits: LogLimits) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            path,
+            limits,
+            file: Mutex::new(LogFile { file, len }),
+        })
+    }
+
+    /// Appends `line`, rotating first when it would take the file past its
+    /// limit. Write errors are dropped: the log must not fail the run.
+    pub(super) fn write_line(&self, line: &[u8]) {
+        let mut log = self
+            .file
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner());
+        let len = line.len() as u64 + 1;
+        if log.len > 0 && log.len + len > self.limits.max_bytes {
+            if let Ok(file) = self.rotate() {
+                *log = LogFile { file, len: 0 };
+            }
+        }
+        if log.file.write_all(line).is_ok() && log.file.write_all(b"\n").is_ok() {
+            log.len += len;
+        }
+    }
+
+    fn rotate(&self) -> io::Result<File> {
+        let rotated = |index: usize| rotated_path(&self.path, index);
+        if self.limits.rotations == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(rotated(self.limits.rotations));
+            for index in (1..self.limits.rotations).rev() {
+                let _ = fs::rename(rotated(index), rotated(index + 1));
+            }
+            fs::rename(&self.path, rotated(1))?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Splits one stream of a command into prefixed lines of its log.
+pub(super) struct LogStream {
+    log: Arc<ExecLog>,
+    prefix: &'static str,
+    pending: Vec<u8>,
+}
+
+impl LogStream {
+    pub(super) fn new(log: Arc<ExecLog>, prefix: &'static str) -> Self {
+        Self {
+            log,
+            prefix,
+            pending: Vec::new(),
+        }
+    }
+
+    pub(super) fn write(&mut self, chunk: &[u8]) {
+        self.pending.extend_from_slice(chunk);
+        while let Some(end) = self.pending.iter().position(|byte| *byte == b'\n') {
+            let rest = self.pending.split_off(end + 1);
+            let line = std::mem::replace(&mut self.pending, rest);
+            self.emit(&line[..end]);
+        }
+    }
+
+    fn emit(&self, line: &[u8]) {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let mut prefixed = Vec::with_capacity(self.prefix.len() + line.len());
+        prefixed.extend_from_slice(self.prefix.as_bytes());
+        prefixed.extend_from_slice(line);
+        self.log.write_line(&prefixed);
+    }
+}
+
+impl Drop for LogStream {
+    fn drop(&mut self) {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.emit(&line);
+        }
+    }
+}
+
+/// Keeps the newest `keep` execution logs in `dir`, counting `current`.
+/// Logs are named `exec_<id>.log`, with ids that sort by start time.
+fn prune_logs(dir: &Path, current: &Path, keep: usize) -> io::Result<()> {
+    let mut logs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if name.starts_with("exec_") && name.ends_with(".log") && path != current {
+            logs.push(path);
+        }
+    }
+    logs.sort();
+    let excess = (logs.len() + 1).saturating_sub(keep);
+    for log in logs.into_iter().take(excess) {
+        for index in 1.. {
+            if fs::remove_file(rotated_path(&log, index)).is_err() {
+                break;
+            }
+        }
+        fs::remove_file(&log)?;
+    }
+    Ok(())
+}
+
+/// The last `max_bytes` of the log at `path`, starting at a line boundary,
+/// and whether earlier output was left out.
+pub fn read_log_tail(path: &Path, max_bytes: u64) -> io::Result<(String, bool)> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(max_bytes);
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let truncated = start > 0 || rotated_path(path, 1).exists();
+    if start > 0 {
+        match bytes.iter().position(|byte| *byte == b'\n') {
+            Some(end) => {
+                bytes.drain(..=end);
+            }
+            None => bytes.clear(),
+        }
+    }
+    Ok((String::from_utf8_lossy(&bytes).into_owned(), truncated))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::run_task::utils::run_command_with_timeout;
+    use std::process::Command;
+    use std::time::Duration;
+
+    #[test]
+    fn commands_in_scope_are_logged_by_stream() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let path = temp.path().join(".logs").join("exec_1.log");
+        let scope = ExecLogScope::enter(&path).expect("scope");
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg("echo out; echo err >&2; printf partial");
+        let output = run_command_with_timeout(cmd, Duration::from_secs(10), "sh").expect("run");
+        drop(scope);
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "out\npartial");
+
+        let log = fs::read_to_string(&path).expect("log");
+        assert!(log.starts_with("--- sh started\n"), "{}", log);
+        assert!(log.contains("stdout| out\n"));
+        assert!(log.contains("stderr| err\n"));
+        assert!(log.contains("stdout| partial\n"));
+        assert!(log.ends_with("--- sh exited with status 0\n"), "{}", log);
+        assert!(current_log().is_none());
+    }
+
+    #[test]
+    fn logs_rotate_and_old_executions_are_pruned() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let limits = LogLimits {
+            max_bytes: 16,
+            rotations: 2,
+            keep: 2,
+        };
+        let path = temp.path().join("exec_2.log");
+        let log = ExecLog::open(path.clone(), limits).expect("open");
+        for line in ["aaaaaaaaaa", "bbbbbbbbbb", "cccccccccc", "dddddddddd"] {
+            log.write_line(line.as_bytes());
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "dddddddddd\n");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "cccccccccc\n"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 2)).unwrap(),
+            "bbbbbbbbbb\n"
+        );
+        assert!(!rotated_path(&path, 3).exists());
+
+        let (tail, truncated) = read_log_tail(&path, 100).expect("tail");
+        assert_eq!(tail, "dddddddddd\n");
+        assert!(truncated);
+
+        fs::write(temp.path().join("exec_1.log"), "old\n").unwrap();
+        let current = temp.path().join("exec_3.log");
+        prune_logs(temp.path(), &current, 2).expect("prune");
+        assert!(!temp.path().join("exec_1.log").exists());
+        assert!(path.exists());
+        assert!(rotated_path(&path, 2).exists());
+    }
+
+    #[test]
+    fn tail_starts_at_a_line_boundary() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let path = temp.path().join("exec_1.log");
+        fs::write(&path, "first line\nsecond\nthird\n").unwrap();
+        assert_eq!(
+            read_log_tail(&path, 10).unwrap(),
+            ("third\n".to_string(), true)
+        );
+        assert_eq!(
+            read_log_tail(&path, 1000).unwrap(),
+            ("first line\nsecond\nthird\n".to_string(), false)
+        );
+    }
+}
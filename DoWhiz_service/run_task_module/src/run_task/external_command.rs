@@ -0,0 +1,301 @@
+//! Retries for the CLIs run_task shells out to (az, gh, docker).
+//!
+//! [`ExternalCommand`] runs a command under a timeout and, when the failure
+//! looks transient (throttling, network errors, timeouts), runs it again after
+//! an exponential backoff. Auth and other failures are returned after the
+//! first attempt. Each finished invocation is reported to the observer set
+//! with [`set_external_command_observer`].
+
+use std::process::{Command, Output};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::errors::RunTaskError;
+use super::utils::{run_command_with_input, tail_string};
+
+const DEFAULT_ATTEMPTS: u32 = 3;
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(2);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+const AUTH_MARKERS: &[&str] = &[
+    "az login",
+    "authorizationfailed",
+    "authenticationfailed",
+    "authentication failed",
+    "unauthorized",
+    "bad credentials",
+    "invalid_grant",
+    "expired token",
+    "token has expired",
+    "not logged in",
+    "denied: requested access",
+    "permission denied while trying to connect to the docker daemon",
+];
+
+const TRANSIENT_MARKERS: &[&str] = &[
+    "timed out",
+    "timeout",
+    "connection reset",
+    "connection refused",
+    "connection aborted",
+    "temporarily unavailable",
+    "service unavailable",
+    "serviceunavailable",
+    "too many requests",
+    "toomanyrequests",
+    "throttl",
+    "rate limit",
+    "internal server error",
+    "bad gateway",
+    "gateway timeout",
+    "network is unreachable",
+    "could not resolve host",
+    "temporary failure in name resolution",
+    "tls handshake",
+    "unexpected eof",
+    "cannot connect to the docker daemon",
+];
+
+/// Why an external command failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureClass {
+    /// Credentials are missing, expired or lack permission; retrying won't help.
+    Auth,
+    /// Throttling, network trouble or a timeout; worth another attempt.
+    Transient,
+    /// Anything else, such as bad arguments or a missing resource.
+    Fatal,
+}
+
+impl FailureClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FailureClass::Auth => "auth",
+            FailureClass::Transient => "transient",
+            FailureClass::Fatal => "fatal",
+        }
+    }
+}
+
+/// One finished external command, across all of its attempts.
+#[derive(Debug, Clone)]
+pub struct ExternalCommandReport {
+    pub label: &'static str,
+    pub attempts: u32,
+    pub elapsed: Duration,
+    /// `None` when the last attempt succeeded.
+    pub failure: Option<FailureClass>,
+}
+
+static OBSERVER: OnceLock<fn(&ExternalCommandReport)> = OnceLock::new();
+
+/// Install the process-wide callback that receives an
+/// [`ExternalCommandReport`] for every external command. Only the first call
+/// takes effect.
+pub fn set_external_command_observer(observer: fn(&ExternalCommandReport)) {
+    let _ = OBSERVER.set(observer);
+}
+
+/// Classify a failed command from its combined stdout and stderr.
+pub(super) fn classify_failure(output: &str) -> FailureClass {
+    let lowered = output.to_ascii_lowercase();
+    if AUTH_MARKERS.iter().any(|marker| lowered.contains(marker)) {
+        FailureClass::Auth
+    } else if TRANSIENT_MARKERS
+        .iter()
+        .any(|marker| lowered.contains(marker))
+    {
+        FailureClass::Transient
+    } else {
+        FailureClass::Fatal
+    }
+}
+
+/// A command that can be run more than once. `build` is called per attempt
+/// because a `Command` cannot be reused after it has been spawned.
+pub(super) struct ExternalCommand<'a, F> {
+    label: &'static str,
+    build: F,
+    input: Option<&'a [u8]>,
+    attempts: u32,
+    backoff: Duration,
+    timeout: Duration,
+}
+
+impl<'a, F: Fn() -> Command> ExternalCommand<'a, F> {
+    pub(super) fn new(label: &'static str, build: F) -> Self {
+        Self {
+            label,
+            build,
+            input: None,
+            attempts: DEFAULT_ATTEMPTS,
+            backoff: DEFAULT_BACKOFF,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Total attempts, including the first.
+    pub(super) fn attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry; doubled for each one after it.
+    pub(super) fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Limit for each attempt.
+    pub(super) fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Written to stdin on every attempt.
+    pub(super) fn input(mut self, input: &'a [u8]) -> Self {
+        self.input = Some(input);
+        self
+    }
+
+    /// Run until success, a non-transient failure or the last attempt. A
+    /// non-zero exit is returned as `Ok` so callers keep their own error
+    /// mapping; spawn errors and a final timeout are `Err`.
+    pub(super) fn run(&self) -> Result<Output, RunTaskError> {
+        let started = Instant::now();
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let result =
+                run_command_with_input((self.build)(), self.input, self.timeout, self.label);
+            let failure = match &result {
+                Ok(output) if output.status.success() => None,
+                Ok(output) => Some(classify_failure(&combined_output(output))),
+                Err(RunTaskError::CommandTimeout { .. }) => Some(FailureClass::Transient),
+                Err(_) => Some(FailureClass::Fatal),
+            };
+
+            if failure == Some(FailureClass::Transient) && attempt < self.attempts {
+                let delay = self.backoff_for(attempt);
+                eprintln!(
+                    "[run_task] {} failed transiently (attempt {}/{}), retrying in {}s: {}",
+                    self.label,
+                    attempt,
+                    self.attempts,
+                    delay.as_secs(),
+                    describe(&result)
+                );
+                thread::sleep(delay);
+                continue;
+            }
+
+            if let Some(observer) = OBSERVER.get() {
+                observer(&ExternalCommandReport {
+                    label: self.label,
+                    attempts: attempt,
+                    elapsed: started.elapsed(),
+                    failure,
+                });
+            }
+            return result;
+        }
+    }
+
+    fn backoff_for(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << (attempt - 1).min(16);
+        self.backoff.saturating_mul(factor).min(MAX_BACKOFF)
+    }
+}
+
+fn combined_output(output: &Output) -> String {
+    let mut combined = String::new();
+    combined.push_str(&String::from_utf8_lossy(&output.stdout));
+    combined.push_str(&String::from_utf8_lossy(&output.stderr));
+    combined
+}
+
+fn describe(result: &Result<Output, RunTaskError>) -> String {
+    match result {
+        Ok(output) => tail_string(&combined_output(output), 300),
+        Err(err) => tail_string(&err.to_string(), 300),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn sh(script: String) -> impl Fn() -> Command {
+        move || {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(&script);
+            cmd
+        }
+    }
+
+    #[test]
+    fn classifies_auth_transient_and_fatal_output() {
+        assert_eq!(
+            classify_failure("ERROR: Please run 'az login' to setup account."),
+            FailureClass::Auth
+        );
+        assert_eq!(
+            classify_failure("HTTP 401: Bad credentials (https://api.github.com/)"),
+            FailureClass::Auth
+        );
+        assert_eq!(
+            classify_failure("(TooManyRequests) Rate limit exceeded, retry later"),
+            FailureClass::Transient
+        );
+        assert_eq!(
+            classify_failure("(ResourceNotFound) The Resource 'dwz-codex-1' was not found."),
+            FailureClass::Fatal
+        );
+    }
+
+    #[test]
+    fn retries_transient_failures_until_success() {
+        let temp = TempDir::new().expect("tempdir");
+        let counter = temp.path().join("count");
+        let script = format!(
+            "echo x >> {path}; [ $(wc -l < {path}) -ge 3 ] && echo ok && exit 0; \
+             echo 'connection reset by peer' >&2; exit 1",
+            path = counter.display()
+        );
+        let output = ExternalCommand::new("test retry", sh(script))
+            .backoff(Duration::from_millis(10))
+            .run()
+            .expect("run");
+        assert!(output.status.success());
+        assert_eq!(fs::read_to_string(&counter).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn does_not_retry_auth_or_fatal_failures() {
+        let temp = TempDir::new().expect("tempdir");
+        let counter = temp.path().join("count");
+        let script = format!(
+            "echo x >> {}; echo 'error: not logged in' >&2; exit 1",
+            counter.display()
+        );
+        let output = ExternalCommand::new("test auth", sh(script))
+            .backoff(Duration::from_millis(10))
+            .run()
+            .expect("run");
+        assert!(!output.status.success());
+        assert_eq!(fs::read_to_string(&counter).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn passes_input_to_each_attempt() {
+        let output = ExternalCommand::new("test input", sh("cat".to_string()))
+            .input(b"token\n")
+            .run()
+            .expect("run");
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "token\n");
+    }
+}
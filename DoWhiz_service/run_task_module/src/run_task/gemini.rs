@@ -0,0 +1,345 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::constants::{
+    DEFAULT_GEMINI_MODEL, DOCKER_GEMINI_HOME_DIR, DOCKER_WORKSPACE_DIR, TRACE_ID_ENV_KEY,
+};
+use super::docker::{resolve_sandbox, sandbox_docker_image, SandboxProfile};
+use super::env::{path_with_dowhiz_bin, read_env_trimmed};
+use super::errors::RunTaskError;
+use super::github_auth::{ensure_github_cli_auth, resolve_github_auth};
+use super::prompt::prompt_for_request;
+use super::runner::{RunContext, Runner};
+use super::scheduled::{extract_scheduled_tasks, extract_scheduler_actions};
+use super::types::{RunTaskOutput, RunTaskRequest, TokenUsage};
+use super::utils::{run_command_with_timeout, run_task_timeout, tail_string};
+use super::workspace::{
+    canonicalize_dir, resolve_expected_reply_path, workspace_path_in_container,
+};
+
+pub(super) struct GeminiRunner;
+
+impl Runner for GeminiRunner {
+    type Execution = RunTaskOutput;
+
+    fn execute(&self, ctx: &RunContext<'_>) -> Result<RunTaskOutput, RunTaskError> {
+        run_gemini_task(
+            ctx.request.clone(),
+            ctx.runner,
+            ctx.reply_html_path.clone(),
+            ctx.reply_attachments_dir.clone(),
+        )
+    }
+
+    fn collect(
+        &self,
+        _ctx: &RunContext<'_>,
+        output: RunTaskOutput,
+    ) -> Result<RunTaskOutput, RunTaskError> {
+        Ok(output)
+    }
+}
+
+fn run_gemini_task(
+    request: RunTaskRequest<'_>,
+    runner: &str,
+    reply_html_path: PathBuf,
+    reply_attachments_dir: PathBuf,
+) -> Result<RunTaskOutput, RunTaskError> {
+    let sandbox = resolve_sandbox(request.sandbox);
+    // Inside the sandbox HOME is the workspace, so Gemini's settings and the
+    // askpass script live under workspace/.gemini.
+    let host_workspace_dir = match sandbox {
+        Some(_) => Some(canonicalize_dir(request.workspace_dir)?),
+        None => None,
+    };
+    let gemini_home = host_workspace_dir
+        .as_ref()
+        .map(|dir| dir.join(DOCKER_GEMINI_HOME_DIR));
+    let github_auth = resolve_github_auth(gemini_home.as_deref())?;
+
+    let api_key = read_env_trimmed("GEMINI_API_KEY").ok_or(RunTaskError::MissingEnv {
+        key: "GEMINI_API_KEY",
+    })?;
+    let model_name = if request.model_name.trim().is_empty() {
+        read_env_trimmed("GEMINI_MODEL").unwrap_or_else(|| DEFAULT_GEMINI_MODEL.to_string())
+    } else {
+        request.model_name.to_string()
+    };
+
+    let prompt = prompt_for_request(&request, runner)?;
+
+    ensure_github_cli_auth(&github_auth)?;
+    let mut env_overrides = vec![
+        ("GEMINI_API_KEY".to_string(), api_key),
+        ("PATH".to_string(), path_with_dowhiz_bin()),
+    ];
+    env_overrides.extend(github_auth.env_overrides.clone());
+    if let Some(trace_id) = request.trace_id {
+        env_overrides.push((TRACE_ID_ENV_KEY.to_string(), trace_id.to_string()));
+    }
+    if let Some(askpass_path) = github_auth.askpass_path.as_ref() {
+        env_overrides.push((
+            "GIT_ASKPASS".to_string(),
+            askpass_path.to_string_lossy().into_owned(),
+        ));
+        env_overrides.push(("GIT_TERMINAL_PROMPT".to_string(), "0".to_string()));
+    }
+    let output = match (&sandbox, &host_workspace_dir) {
+        (Some(sandbox), Some(host_workspace_dir)) => run_sandboxed_gemini_command(
+            host_workspace_dir,
+            sandbox,
+            &prompt,
+            &model_name,
+            &env_overrides,
+        )?,
+        _ => run_gemini_command(request.workspace_dir, &prompt, &model_name, &env_overrides)?,
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut combined_output = String::new();
+    combined_output.push_str(&stdout);
+    combined_output.push_str(&stderr);
+    let output_tail = tail_string(&combined_output, 2000);
+
+    if !output.status.success() {
+        return Err(RunTaskError::GeminiFailed {
+            status: output.status.code(),
+            output: output_tail,
+        });
+    }
+
+    let Some(result) = parse_gemini_output(&stdout) else {
+        return Err(RunTaskError::GeminiFailed {
+            status: output.status.code(),
+            output: output_tail,
+        });
+    };
+    if result.response.trim().is_empty() {
+        return Err(RunTaskError::GeminiFailed {
+            status: output.status.code(),
+            output: output_tail,
+        });
+    }
+    let (scheduled_tasks, scheduled_tasks_error) = extract_scheduled_tasks(&result.response);
+    let (scheduler_actions, scheduler_actions_error) = extract_scheduler_actions(&result.response);
+    let response_tail = tail_string(&result.response, 2000);
+
+    let expected_reply_path = resolve_expected_reply_path(request.workspace_dir, reply_html_path);
+    if !request.reply_to.is_empty() && !expected_reply_path.exists() {
+        return Err(RunTaskError::OutputMissing {
+            path: expected_reply_path,
+            output: response_tail,
+        });
+    }
+
+    Ok(RunTaskOutput {
+        reply_html_path: expected_reply_path,
+        reply_attachments_dir,
+        codex_output: response_tail,
+        scheduled_tasks,
+        scheduled_tasks_error,
+        scheduler_actions,
+        scheduler_actions_error,
+        token_usage: result.usage,
+        output_issues: Vec::new(),
+    })
+}
+
+fn run_gemini_command(
+    workspace_dir: &Path,
+    prompt: &str,
+    model_name: &str,
+    env_overrides: &[(String, String)],
+) -> Result<std::process::Output, RunTaskError> {
+    let timeout = run_task_timeout();
+    match run_command_with_timeout(
+        build_gemini_command(workspace_dir, prompt, model_name, env_overrides),
+        timeout,
+        "gemini",
+    ) {
+        Ok(output) => return Ok(output),
+        Err(RunTaskError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+
+    ensure_gemini_cli_installed(env_overrides)?;
+    match run_command_with_timeout(
+        build_gemini_command(workspace_dir, prompt, model_name, env_overrides),
+        timeout,
+        "gemini",
+    ) {
+        Ok(output) => Ok(output),
+        Err(RunTaskError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+            Err(RunTaskError::GeminiNotFound)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Run Gemini inside the runner image under `sandbox`'s limits. Only the
+/// prepared overrides reach the container, never the worker's environment.
+fn run_sandboxed_gemini_command(
+    host_workspace_dir: &Path,
+    sandbox: &SandboxProfile,
+    prompt: &str,
+    model_name: &str,
+    env_overrides: &[(String, String)],
+) -> Result<std::process::Output, RunTaskError> {
+    let image = sandbox_docker_image()?;
+    let mut cmd = Command::new("docker");
+    cmd.arg("run")
+        .arg("--rm")
+        .arg("--workdir")
+        .arg(DOCKER_WORKSPACE_DIR)
+        .arg("-v")
+        .arg(format!(
+            "{}:{}",
+            host_workspace_dir.display(),
+            DOCKER_WORKSPACE_DIR
+        ))
+        .arg("-e")
+        .arg(format!("HOME={}", DOCKER_WORKSPACE_DIR))
+        .args(sandbox.docker_args());
+    for (key, value) in env_overrides {
+        // The host PATH means nothing inside the image.
+        if key == "PATH" {
+            continue;
+        }
+        let value = workspace_path_in_container(Path::new(value), host_workspace_dir)
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|| value.clone());
+        cmd.arg("-e").arg(format!("{}={}", key, value));
+    }
+    cmd.arg("--entrypoint").arg("gemini").arg(&image);
+    apply_gemini_args(&mut cmd, prompt, model_name);
+
+    match run_command_with_timeout(cmd, run_task_timeout(), "docker run") {
+        Ok(output) => Ok(output),
+        Err(RunTaskError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+            Err(RunTaskError::DockerNotFound)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+fn build_gemini_command(
+    workspace_dir: &Path,
+    prompt: &str,
+    model_name: &str,
+    env_overrides: &[(String, String)],
+) -> Command {
+    let mut cmd = Command::new("gemini");
+    apply_gemini_args(&mut cmd, prompt, model_name);
+    cmd.current_dir(workspace_dir);
+    for (key, value) in env_overrides {
+        cmd.env(key, value);
+    }
+    cmd
+}
+
+/// Headless mode: `--yolo` approves tool calls and `--output-format json`
+/// prints one JSON object with the final response and token stats.
+fn apply_gemini_args(cmd: &mut Command, prompt: &str, model_name: &str) {
+    cmd.arg("--model")
+        .arg(model_name)
+        .arg("--yolo")
+        .arg("--output-format")
+        .arg("json")
+        .arg("--prompt")
+        .arg(prompt);
+}
+
+fn ensure_gemini_cli_installed(env_overrides: &[(String, String)]) -> Result<(), RunTaskError> {
+    let mut cmd = Command::new("npm");
+    cmd.args(["i", "-g", "@google/gemini-cli"]);
+    for (key, value) in env_overrides {
+        cmd.env(key, value);
+    }
+    let output = match cmd.output() {
+        Ok(output) => output,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(RunTaskError::GeminiInstallFailed {
+                output: "npm not found on PATH".to_string(),
+            })
+        }
+        Err(err) => return Err(RunTaskError::Io(err)),
+    };
+    let mut combined = String::new();
+    combined.push_str(&String::from_utf8_lossy(&output.stdout));
+    combined.push_str(&String::from_utf8_lossy(&output.stderr));
+    if !output.status.success() {
+        return Err(RunTaskError::GeminiInstallFailed {
+            output: tail_string(&combined, 2000),
+        });
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+struct GeminiResult {
+    response: String,
+    usage: Option<TokenUsage>,
+}
+
+/// Parse the `--output-format json` object. The CLI may print log lines
+/// before it, so the object is taken from the first `{` at a line start.
+fn parse_gemini_output(stdout: &str) -> Option<GeminiResult> {
+    let start = stdout
+        .match_indices('{')
+        .map(|(index, _)| index)
+        .find(|index| *index == 0 || stdout[..*index].ends_with('\n'))?;
+    let value: serde_json::Value = serde_json::from_str(stdout[start..].trim()).ok()?;
+    let response = value.get("response")?.as_str()?.to_string();
+
+    // stats.models.<model>.tokens.{prompt,cached,candidates}, summed over
+    // every model the CLI routed to.
+    let usage = value
+        .get("stats")
+        .and_then(|stats| stats.get("models"))
+        .and_then(|models| models.as_object())
+        .map(|models| {
+            let mut usage = TokenUsage::default();
+            for tokens in models.values().filter_map(|model| model.get("tokens")) {
+                let count = |key: &str| tokens.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+                usage.input_tokens += count("prompt");
+                usage.cached_input_tokens += count("cached");
+                usage.output_tokens += count("candidates");
+            }
+            usage
+        });
+    Some(GeminiResult { response, usage })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gemini_output_reads_response_and_sums_model_tokens() {
+        let stdout = r#"Loaded cached credentials.
+{
+  "response": "Reply written to reply_email_draft.html.",
+  "stats": {
+    "models": {
+      "gemini-2.5-pro": { "tokens": { "prompt": 1200, "cached": 200, "candidates": 80 } },
+      "gemini-2.5-flash": { "tokens": { "prompt": 300, "candidates": 20 } }
+    }
+  }
+}"#;
+        let result = parse_gemini_output(stdout).expect("parsed");
+        assert_eq!(result.response, "Reply written to reply_email_draft.html.");
+        let usage = result.usage.expect("usage");
+        assert_eq!(usage.input_tokens, 1500);
+        assert_eq!(usage.cached_input_tokens, 200);
+        assert_eq!(usage.output_tokens, 100);
+    }
+
+    #[test]
+    fn parse_gemini_output_rejects_missing_response() {
+        assert!(parse_gemini_output("not json").is_none());
+        assert!(parse_gemini_output(r#"{"error": {"message": "quota"}}"#).is_none());
+    }
+}
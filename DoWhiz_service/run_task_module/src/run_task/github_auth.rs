@@ -1,16 +1,19 @@
 use std::env;
 use std::fs;
-use std::io::{self, Write};
+use std::io;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Command;
 use std::sync::{Mutex, OnceLock};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use super::constants::GIT_ASKPASS_SCRIPT;
 use super::env::{env_enabled, env_missing_or_empty, normalize_env_prefix, read_env_trimmed};
 use super::errors::RunTaskError;
+use super::external_command::ExternalCommand;
 use super::utils::tail_string;
 
+const GH_COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
 static GH_AUTH_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
 
 #[derive(Debug)]
@@ -146,9 +149,7 @@ fn is_keyring_error(output: &str) -> bool {
 }
 
 fn gh_auth_status_ok(github_auth: &GitHubAuthConfig) -> Result<bool, RunTaskError> {
-    let mut status_cmd = Command::new("gh");
-    status_cmd.args(["auth", "status", "--hostname", "github.com"]);
-    apply_env_overrides(&mut status_cmd, &github_auth.env_overrides, &[]);
+    let status_cmd = || gh_command(github_auth, &["auth", "status", "--hostname", "github.com"]);
     match run_auth_command(status_cmd, None, "gh auth status") {
         Ok(()) => Ok(true),
         Err(RunTaskError::GitHubAuthFailed { .. }) => Ok(false),
@@ -161,25 +162,28 @@ fn gh_auth_login(
     token: &str,
     insecure_storage: bool,
 ) -> Result<(), RunTaskError> {
-    let mut login_cmd = Command::new("gh");
-    login_cmd.args([
-        "auth",
-        "login",
-        "--with-token",
-        "--hostname",
-        "github.com",
-        "--git-protocol",
-        "https",
-    ]);
-    if insecure_storage {
-        login_cmd.arg("--insecure-storage");
-    }
-    login_cmd.env_remove("GH_TOKEN").env_remove("GITHUB_TOKEN");
-    apply_env_overrides(
-        &mut login_cmd,
-        &github_auth.env_overrides,
-        &["GH_TOKEN", "GITHUB_TOKEN"],
-    );
+    let login_cmd = || {
+        let mut login_cmd = Command::new("gh");
+        login_cmd.args([
+            "auth",
+            "login",
+            "--with-token",
+            "--hostname",
+            "github.com",
+            "--git-protocol",
+            "https",
+        ]);
+        if insecure_storage {
+            login_cmd.arg("--insecure-storage");
+        }
+        login_cmd.env_remove("GH_TOKEN").env_remove("GITHUB_TOKEN");
+        apply_env_overrides(
+            &mut login_cmd,
+            &github_auth.env_overrides,
+            &["GH_TOKEN", "GITHUB_TOKEN"],
+        );
+        login_cmd
+    };
     run_auth_command(login_cmd, Some(token), "gh auth login")
 }
 
@@ -208,19 +212,27 @@ pub(super) fn ensure_github_cli_auth(github_auth: &GitHubAuthConfig) -> Result<(
         Err(err) => return Err(err),
     }
 
-    let mut setup_cmd = Command::new("gh");
-    setup_cmd.args(["auth", "setup-git", "--hostname", "github.com"]);
-    apply_env_overrides(&mut setup_cmd, &github_auth.env_overrides, &[]);
+    let setup_cmd = || {
+        gh_command(
+            github_auth,
+            &["auth", "setup-git", "--hostname", "github.com"],
+        )
+    };
     run_auth_command(setup_cmd, None, "gh auth setup-git")?;
 
-    let mut status_cmd = Command::new("gh");
-    status_cmd.args(["auth", "status", "--hostname", "github.com"]);
-    apply_env_overrides(&mut status_cmd, &github_auth.env_overrides, &[]);
+    let status_cmd = || gh_command(github_auth, &["auth", "status", "--hostname", "github.com"]);
     run_auth_command(status_cmd, None, "gh auth status")?;
 
     Ok(())
 }
 
+fn gh_command(github_auth: &GitHubAuthConfig, args: &[&str]) -> Command {
+    let mut cmd = Command::new("gh");
+    cmd.args(args);
+    apply_env_overrides(&mut cmd, &github_auth.env_overrides, &[]);
+    cmd
+}
+
 fn apply_env_overrides(cmd: &mut Command, overrides: &[(String, String)], skip: &[&str]) {
     for (key, value) in overrides {
         if skip.iter().any(|blocked| *blocked == key.as_str()) {
@@ -231,30 +243,23 @@ fn apply_env_overrides(cmd: &mut Command, overrides: &[(String, String)], skip:
 }
 
 fn run_auth_command(
-    mut cmd: Command,
+    build: impl Fn() -> Command,
     input: Option<&str>,
     label: &'static str,
 ) -> Result<(), RunTaskError> {
-    if input.is_some() {
-        cmd.stdin(Stdio::piped());
+    let payload = input.map(|value| format!("{}\n", value));
+    let mut command = ExternalCommand::new(label, build).timeout(GH_COMMAND_TIMEOUT);
+    if let Some(payload) = payload.as_deref() {
+        command = command.input(payload.as_bytes());
     }
-    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
-    let mut child = match cmd.spawn() {
-        Ok(child) => child,
-        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+    let output = match command.run() {
+        Ok(output) => output,
+        Err(RunTaskError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
             return Err(RunTaskError::GitHubAuthCommandNotFound { command: "gh" })
         }
-        Err(err) => return Err(RunTaskError::Io(err)),
+        Err(err) => return Err(err),
     };
 
-    if let Some(payload) = input {
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin.write_all(payload.as_bytes())?;
-            stdin.write_all(b"\n")?;
-        }
-    }
-
-    let output = child.wait_with_output()?;
     let mut combined_output = String::new();
     combined_output.push_str(&String::from_utf8_lossy(&output.stdout));
     combined_output.push_str(&String::from_utf8_lossy(&output.stderr));
@@ -0,0 +1,329 @@
+//! Runner for self-hosted models behind an OpenAI-compatible chat API, such
+//! as Ollama (`runner = "local"` or `"ollama"`).
+//!
+//! Unlike the codex and claude CLIs the model gets no tools: run_task reads
+//! the incoming message, guidance and memory into one chat request and writes
+//! the model's answer to the reply file itself.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use super::contract::{html_reply, strip_marked_blocks};
+use super::env::read_env_trimmed;
+use super::errors::RunTaskError;
+use super::prompt::{build_guidance_section, load_memory_context};
+use super::runner::{RunContext, Runner};
+use super::scheduled::{extract_scheduled_tasks, extract_scheduler_actions};
+use super::types::{RunTaskOutput, TokenUsage};
+use super::utils::{run_task_timeout, tail_string};
+use super::workspace::resolve_rel_dir;
+
+const DEFAULT_BASE_URL: &str = "http://localhost:11434/v1";
+const DEFAULT_MODEL: &str = "llama3.1";
+const MAX_INCOMING_CHARS: usize = 24_000;
+
+pub(super) struct LocalModelRunner;
+
+#[derive(Debug)]
+pub(super) struct LocalModelReply {
+    text: String,
+    usage: Option<TokenUsage>,
+}
+
+impl Runner for LocalModelRunner {
+    type Execution = LocalModelReply;
+
+    fn prepare(&self, ctx: &RunContext<'_>) -> Result<(), RunTaskError> {
+        super::env::load_env_sources(ctx.request.workspace_dir)?;
+        if ctx.request.channel.eq_ignore_ascii_case("notion") && !ctx.request.reply_to.is_empty() {
+            return Err(RunTaskError::LocalModelFailed {
+                status: None,
+                output: "notion replies are posted through the Notion API; use the codex or claude runner".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn execute(&self, ctx: &RunContext<'_>) -> Result<LocalModelReply, RunTaskError> {
+        let base_url = read_env_trimmed("RUN_TASK_LOCAL_MODEL_BASE_URL")
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        let model = read_env_trimmed("RUN_TASK_LOCAL_MODEL")
+            .or_else(|| Some(ctx.request.model_name.trim().to_string()))
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+        let messages = json!([
+            { "role": "system", "content": system_prompt(ctx) },
+            { "role": "user", "content": user_prompt(ctx)? },
+        ]);
+        eprintln!(
+            "[run_task] local model request base_url={} model={}",
+            base_url, model
+        );
+        chat_completion(&base_url, &model, messages)
+    }
+
+    fn collect(
+        &self,
+        ctx: &RunContext<'_>,
+        reply: LocalModelReply,
+    ) -> Result<RunTaskOutput, RunTaskError> {
+        let (scheduled_tasks, scheduled_tasks_error) = extract_scheduled_tasks(&reply.text);
+        let (scheduler_actions, scheduler_actions_error) = extract_scheduler_actions(&reply.text);
+        let body = strip_marked_blocks(&reply.text);
+        if !ctx.request.reply_to.is_empty() {
+            if body.trim().is_empty() {
+                return Err(RunTaskError::LocalModelFailed {
+                    status: None,
+                    output: format!(
+                        "model returned no reply: {}",
+                        tail_string(&reply.text, 2000)
+                    ),
+                });
+            }
+            let is_html = ctx
+                .reply_html_path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("html"));
+            let rendered = if is_html {
+                html_reply(&body)
+            } else {
+                body.trim().to_string()
+            };
+            fs::write(&ctx.reply_html_path, rendered)?;
+        }
+
+        Ok(RunTaskOutput {
+            reply_html_path: ctx.reply_html_path.clone(),
+            reply_attachments_dir: ctx.reply_attachments_dir.clone(),
+            codex_output: tail_string(&reply.text, 2000),
+            scheduled_tasks,
+            scheduled_tasks_error,
+            scheduler_actions,
+            scheduler_actions_error,
+            token_usage: reply.usage,
+            output_issues: Vec::new(),
+        })
+    }
+}
+
+fn system_prompt(ctx: &RunContext<'_>) -> String {
+    let format = if ctx.request.reply_to.is_empty() {
+        "No reply will be sent; answer with a short note of what you concluded."
+    } else if ctx
+        .reply_html_path
+        .extension()
+        .is_some_and(|ext| ext == "html")
+    {
+        "Answer with the body of an HTML email reply only, no <html> wrapper and no commentary."
+    } else {
+        "Answer with the plain text chat reply only, no HTML and no commentary."
+    };
+    format!(
+        "You are a DoWhiz digital employee. Be patient, friendly and helpful. \
+         You cannot run tools, browse the web, read or write files, or send anything yourself; \
+         everything you know about the request is in the next message. \
+         If the request needs actions you cannot take, say so plainly and do not pretend they were done. \
+         {format}\n\nEmployee guidance:\n{guidance}",
+        guidance = build_guidance_section(ctx.request.workspace_dir, ctx.runner)
+    )
+}
+
+fn user_prompt(ctx: &RunContext<'_>) -> Result<String, RunTaskError> {
+    let request = &ctx.request;
+    let memory = load_memory_context(request.workspace_dir, request.memory_dir)?;
+    let incoming_dir = resolve_rel_dir(
+        request.workspace_dir,
+        request.input_email_dir,
+        "input_email_dir",
+    )?;
+    let incoming = read_incoming(&incoming_dir)?;
+    Ok(format!(
+        "Channel: {channel}\n\nMemory about this user:\n{memory}\n\nIncoming message:\n{incoming}",
+        channel = request.channel,
+        memory = if memory.trim().is_empty() {
+            "(none)"
+        } else {
+            memory.trim()
+        },
+        incoming = tail_string(&incoming, MAX_INCOMING_CHARS),
+    ))
+}
+
+/// The thread history when there is one, else the email body, followed by
+/// any chat message text files.
+fn read_incoming(incoming_dir: &Path) -> Result<String, RunTaskError> {
+    let mut parts = Vec::new();
+    let history = incoming_dir.join("thread_history.md");
+    let email = incoming_dir.join("email.html");
+    if history.is_file() {
+        parts.push(fs::read_to_string(history)?);
+    } else if email.is_file() {
+        parts.push(fs::read_to_string(email)?);
+    }
+    let mut messages = fs::read_dir(incoming_dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.ends_with("_message.txt"))
+        })
+        .collect::<Vec<_>>();
+    messages.sort();
+    for path in messages {
+        parts.push(fs::read_to_string(path)?);
+    }
+    Ok(parts.join("\n\n"))
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletion {
+    choices: Vec<ChatChoice>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+fn chat_completion(
+    base_url: &str,
+    model: &str,
+    messages: serde_json::Value,
+) -> Result<LocalModelReply, RunTaskError> {
+    let failed = |status: Option<u16>, output: String| RunTaskError::LocalModelFailed {
+        status,
+        output: tail_string(&output, 2000),
+    };
+    let client = reqwest::blocking::Client::builder()
+        .timeout(run_task_timeout())
+        .build()
+        .map_err(|err| failed(None, err.to_string()))?;
+    let mut request = client
+        .post(format!(
+            "{}/chat/completions",
+            base_url.trim_end_matches('/')
+        ))
+        .json(&json!({ "model": model, "messages": messages, "stream": false }));
+    if let Ok(api_key) = env::var("RUN_TASK_LOCAL_MODEL_API_KEY") {
+        if !api_key.trim().is_empty() {
+            request = request.bearer_auth(api_key.trim());
+        }
+    }
+    let response = request
+        .send()
+        .map_err(|err| failed(None, err.to_string()))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .map_err(|err| failed(Some(status.as_u16()), err.to_string()))?;
+    if !status.is_success() {
+        return Err(failed(Some(status.as_u16()), body));
+    }
+    let completion: ChatCompletion = serde_json::from_str(&body)
+        .map_err(|err| failed(Some(status.as_u16()), format!("{}: {}", err, body)))?;
+    let text = completion
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.message.content)
+        .unwrap_or_default();
+    Ok(LocalModelReply {
+        text,
+        usage: completion.usage.map(|usage| TokenUsage {
+            input_tokens: usage.prompt_tokens,
+            cached_input_tokens: 0,
+            output_tokens: usage.completion_tokens,
+            cost_usd: None,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::constants::{SCHEDULED_TASKS_BEGIN, SCHEDULED_TASKS_END};
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn strips_json_blocks_and_wraps_plain_text_as_html() {
+        let text = format!(
+            "Hi Ann,\n\nYour report is attached & ready.\n{}\n[]\n{}",
+            SCHEDULED_TASKS_BEGIN, SCHEDULED_TASKS_END
+        );
+        let body = strip_marked_blocks(&text);
+        assert_eq!(body, "Hi Ann,\n\nYour report is attached & ready.");
+        assert_eq!(
+            html_reply(&body),
+            "<html><body>\n<p>Hi Ann,</p>\n<p>Your report is attached &amp; ready.</p>\n</body></html>\n"
+        );
+        assert_eq!(
+            html_reply("<p>Done.</p>"),
+            "<html><body>\n<p>Done.</p>\n</body></html>\n"
+        );
+    }
+
+    #[test]
+    fn chat_completion_reads_reply_and_usage() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 4096];
+            while !String::from_utf8_lossy(&request).contains("\"stream\"") {
+                let read = stream.read(&mut chunk).expect("read");
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&chunk[..read]);
+            }
+            let body = r#"{"choices":[{"message":{"role":"assistant","content":"Hello!"}}],"usage":{"prompt_tokens":12,"completion_tokens":3}}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .expect("write");
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        let reply = chat_completion(
+            &base_url,
+            "llama3.1",
+            json!([{ "role": "user", "content": "hi" }]),
+        )
+        .expect("reply");
+        let request = server.join().expect("server");
+        assert!(request.starts_with("POST /v1/chat/completions"));
+        assert!(request.contains("\"model\":\"llama3.1\""));
+        assert_eq!(reply.text, "Hello!");
+        let usage = reply.usage.expect("usage");
+        assert_eq!((usage.input_tokens, usage.output_tokens), (12, 3));
+    }
+}
@@ -1,21 +1,38 @@
 mod claude;
 mod codex;
 mod constants;
+mod contract;
 mod core;
 mod docker;
+mod email_html;
 mod env;
 mod errors;
+mod exec_log;
+mod external_command;
+mod gemini;
 mod github_auth;
+mod local_model;
+mod processes;
 mod prompt;
+mod prompt_sections;
+mod runner;
 mod scheduled;
+mod skills_index;
 mod types;
 mod utils;
 mod workspace;
 
 pub use codex::cleanup_all_aci_containers;
 pub use core::run_task;
+pub use docker::SandboxProfile;
 pub use errors::RunTaskError;
+pub use exec_log::{read_log_tail, ExecLogScope, EXEC_LOG_DIR};
+pub use external_command::{set_external_command_observer, ExternalCommandReport, FailureClass};
+pub use processes::{terminate_run, RunScope};
+pub use prompt::{WORKSPACE_RECOVERY_NOTE, WORKSPACE_RECOVERY_NOTIFIED};
+pub use skills_index::{write_skills_index, SKILLS_INDEX_FILE};
 pub use types::{
+    ApprovalRequest, MeetingProvider, RecurrenceFrequency, ReplyFormatting, ReplyPreferences,
     RunTaskOutput, RunTaskParams, ScheduleRequest, ScheduledSendEmailTask, ScheduledTaskRequest,
-    SchedulerActionRequest, UserIdentities,
+    SchedulerActionRequest, SheetEditRequest, TokenUsage, UserIdentities, FORMATTED_REPLY_CHANNELS,
 };
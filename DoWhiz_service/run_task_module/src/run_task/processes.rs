@@ -0,0 +1,200 @@
+//! Child processes of in-flight runs, so a supervisor can stop a stalled run.
+//!
+//! The caller enters a [`RunScope`] on the thread that executes a run. Every
+//! command run_task starts on that thread gets its own process group and is
+//! registered under the scope's id until it exits. [`terminate_run`] sends
+//! those groups SIGTERM and, after a grace period, SIGKILL. The run then fails
+//! with [`RunTaskError::Terminated`] instead of going on to write a reply.
+//!
+//! Azure ACI runs are containers, not local processes, and are not covered.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::process::{Command, Stdio};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::errors::RunTaskError;
+
+#[derive(Debug, Default)]
+struct RunProcesses {
+    /// Process group ids; each command leads its own group.
+    groups: HashSet<u32>,
+    terminated: bool,
+}
+
+static RUNS: LazyLock<Mutex<HashMap<String, RunProcesses>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+thread_local! {
+    static CURRENT_RUN: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn runs() -> MutexGuard<'static, HashMap<String, RunProcesses>> {
+    RUNS.lock().unwrap_or_else(|poison| poison.into_inner())
+}
+
+/// Marks the current thread as executing run `run_id` until dropped.
+#[derive(Debug)]
+pub struct RunScope {
+    run_id: String,
+    previous: Option<String>,
+}
+
+impl RunScope {
+    pub fn enter(run_id: impl Into<String>) -> Self {
+        let run_id = run_id.into();
+        runs().entry(run_id.clone()).or_default();
+        let previous = CURRENT_RUN.with(|current| current.replace(Some(run_id.clone())));
+        Self { run_id, previous }
+    }
+}
+
+impl Drop for RunScope {
+    fn drop(&mut self) {
+        CURRENT_RUN.with(|current| *current.borrow_mut() = self.previous.take());
+        runs().remove(&self.run_id);
+    }
+}
+
+/// Registration of one child process; removed when dropped.
+pub(super) struct ChildRegistration {
+    run_id: String,
+    group: u32,
+}
+
+impl Drop for ChildRegistration {
+    fn drop(&mut self) {
+        if let Some(run) = runs().get_mut(&self.run_id) {
+            run.groups.remove(&self.group);
+        }
+    }
+}
+
+/// The run executing on this thread, if any.
+pub(super) fn current_run() -> Option<String> {
+    CURRENT_RUN.with(|current| current.borrow().clone())
+}
+
+/// Start `cmd` in its own process group, so its descendants can be
+/// signalled with it.
+pub(super) fn isolate(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+pub(super) fn register(run_id: &str, pid: u32) -> ChildRegistration {
+    runs()
+        .entry(run_id.to_string())
+        .or_default()
+        .groups
+        .insert(pid);
+    ChildRegistration {
+        run_id: run_id.to_string(),
+        group: pid,
+    }
+}
+
+/// Fails once `run_id` has been terminated; checked around each command.
+pub(super) fn ensure_not_terminated(
+    run_id: &str,
+    command: &'static str,
+) -> Result<(), RunTaskError> {
+    if runs().get(run_id).is_some_and(|run| run.terminated) {
+        return Err(RunTaskError::Terminated { command });
+    }
+    Ok(())
+}
+
+/// Stop every process of `run_id`: SIGTERM to each process group, then
+/// SIGKILL to the groups still alive after `grace`. Later commands of the
+/// run fail without starting. Returns how many groups were signalled; 0 when
+/// the run has nothing running or is not known here.
+pub fn terminate_run(run_id: &str, grace: Duration) -> usize {
+    let groups: Vec<u32> = match runs().get_mut(run_id) {
+        Some(run) => {
+            run.terminated = true;
+            run.groups.iter().copied().collect()
+        }
+        None => return 0,
+    };
+    for group in &groups {
+        signal_group(*group, "TERM");
+    }
+
+    let deadline = Instant::now() + grace;
+    let mut alive: Vec<u32> = groups.clone();
+    while !alive.is_empty() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(200));
+        alive.retain(|group| signal_group(*group, "0"));
+    }
+    for group in alive {
+        eprintln!(
+            "[run_task] run {} group {} ignored SIGTERM, killing",
+            run_id, group
+        );
+        signal_group(group, "KILL");
+    }
+    groups.len()
+}
+
+/// `kill -<signal> -- -<group>`; true when at least one process was
+/// signalled. Signal `0` only checks that the group still exists.
+pub(super) fn signal_group(group: u32, signal: &str) -> bool {
+    if cfg!(not(unix)) {
+        return false;
+    }
+    Command::new("kill")
+        .arg(format!("-{}", signal))
+        .arg("--")
+        .arg(format!("-{}", group))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::run_task::utils::run_command_with_timeout;
+
+    #[test]
+    fn terminate_run_stops_the_command_and_fails_the_run() {
+        let run_id = format!("test-run-{}", std::process::id());
+        let worker_run_id = run_id.clone();
+        let worker = thread::spawn(move || {
+            let _scope = RunScope::enter(worker_run_id);
+            let mut cmd = Command::new("sh");
+            // The child ignores SIGTERM, so only SIGKILL stops it.
+            cmd.arg("-c").arg("trap '' TERM; sleep 30 & wait");
+            run_command_with_timeout(cmd, Duration::from_secs(60), "sh")
+        });
+
+        let started = Instant::now();
+        while runs().get(&run_id).is_none_or(|run| run.groups.is_empty()) {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "command never registered"
+            );
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(terminate_run(&run_id, Duration::from_millis(500)), 1);
+
+        let result = worker.join().expect("worker");
+        assert!(matches!(
+            result,
+            Err(RunTaskError::Terminated { command: "sh" })
+        ));
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(runs().get(&run_id).is_none());
+        assert_eq!(terminate_run(&run_id, Duration::from_millis(10)), 0);
+    }
+}
@@ -3,8 +3,13 @@ use std::path::{Path, PathBuf};
 
 use serde_json::Value;
 
+use super::constants::OUTPUT_ISSUES_FILE;
 use super::errors::RunTaskError;
-use super::types::UserIdentities;
+use super::prompt_sections::{PromptBuilder, PromptSection, RenderedPrompt, Truncation};
+use super::skills_index::{render_entries, scan_skills, write_skills_index, SKILLS_INDEX_FILE};
+use super::types::{
+    ReplyFormatting, ReplyPreferences, RunTaskRequest, UserIdentities, FORMATTED_REPLY_CHANNELS,
+};
 use super::workspace::resolve_rel_dir;
 
 const GITHUB_NOTIFICATIONS_ADDRESS: &str = "notifications@github.com";
@@ -20,6 +25,78 @@ fn mark_registration_prompted(workspace_dir: &Path) {
     let _ = fs::write(workspace_dir.join(".registration_prompted"), "1");
 }
 
+/// Written by the scheduler when it rebuilds a corrupt workspace.
+pub const WORKSPACE_RECOVERY_NOTE: &str = "workspace_recovery.md";
+/// Written by the scheduler once a reply carrying the recovery notice was
+/// delivered.
+pub const WORKSPACE_RECOVERY_NOTIFIED: &str = ".workspace_recovery_notified";
+
+/// Tell the agent that earlier thread context may be summarized, until a
+/// reply saying so has gone out.
+fn build_workspace_recovery_section(workspace_dir: &Path) -> &'static str {
+    if !workspace_dir.join(WORKSPACE_RECOVERY_NOTE).exists()
+        || workspace_dir.join(WORKSPACE_RECOVERY_NOTIFIED).exists()
+    {
+        return "";
+    }
+    r#"
+Workspace Recovery Notice:
+- This thread's workspace was rebuilt after its files were found corrupt (see workspace_recovery.md).
+- Earlier messages were restored from the mail archive where possible, so some context may be missing or summarized.
+- Briefly tell the user in your reply that earlier context in this conversation may be summarized, and ask them to resend anything important that seems missing.
+"#
+}
+
+/// Problems the output contract found in the previous run of this thread.
+fn build_output_issues_section(workspace_dir: &Path) -> String {
+    let Ok(issues) = fs::read_to_string(workspace_dir.join(OUTPUT_ISSUES_FILE)) else {
+        return String::new();
+    };
+    if issues.trim().is_empty() {
+        return String::new();
+    }
+    format!(
+        r#"
+Previous Output Issues:
+- Your previous run in this thread produced output that had to be repaired, partly dropped or rejected:
+{}
+- Avoid repeating these. If something the user asked for was dropped (an attachment, a scheduled email), handle it in this run.
+"#,
+        issues.trim_end()
+    )
+}
+
+/// Builds the prompt for `request` and writes its debug dump to the
+/// workspace. A failed dump is logged; the run goes ahead without it.
+pub(super) fn prompt_for_request(
+    request: &RunTaskRequest<'_>,
+    runner: &str,
+) -> Result<String, RunTaskError> {
+    let memory_context = load_memory_context(request.workspace_dir, request.memory_dir)?;
+    let prompt = build_prompt(
+        request.input_email_dir,
+        request.input_attachments_dir,
+        request.memory_dir,
+        request.reference_dir,
+        request.workspace_dir,
+        runner,
+        &memory_context,
+        !request.reply_to.is_empty(),
+        request.channel,
+        request.has_unified_account,
+        request.user_identities,
+        request.reply_preferences,
+    );
+    if let Err(err) = prompt.write_debug_dump(request.workspace_dir) {
+        eprintln!(
+            "[run_task] failed to write prompt debug dump in {}: {}",
+            request.workspace_dir.display(),
+            err
+        );
+    }
+    Ok(prompt.text)
+}
+
 pub(super) fn build_prompt(
     input_email_dir: &Path,
     input_attachments_dir: &Path,
@@ -32,23 +109,15 @@ pub(super) fn build_prompt(
     channel: &str,
     has_unified_account: bool,
     user_identities: &UserIdentities,
-) -> String {
-    let memory_section = if memory_context.trim().is_empty() {
-        "Memory context (from memory/*.md):\n- (no memory files found)\n\n".to_string()
-    } else {
-        format!(
-            "Memory context (from memory/*.md):\n{memory_context}\n\n",
-            memory_context = memory_context.trim_end()
-        )
-    };
-
+    reply_preferences: &ReplyPreferences,
+) -> RenderedPrompt {
     // Channel-specific reply instructions
     let reply_instruction = if !reply_required {
         "2. After finishing the task (step one), do not write any reply. This inbound message is from a non-replyable address, so skip creating any reply files."
     } else {
         match channel.to_lowercase().as_str() {
             "slack" => {
-                "2. After finishing the task (step one), write a plain text reply in reply_message.txt in the workspace root. Use Slack mrkdwn formatting: *bold*, _italic_, `code`, ```code blocks```. Keep the reply concise and conversational. Do not use HTML. If there are files to attach, put them in reply_attachments/ and mention them in the reply. Do not pretend the job has been done without actually doing it."
+                "2. After finishing the task (step one), write a plain text reply in reply_message.txt in the workspace root. Use Slack mrkdwn formatting: *bold*, _italic_, `code`, ```code blocks```. Keep the reply concise and conversational. Do not use HTML. If there are files to attach, put them in reply_attachments/ and mention them in the reply. If the user must confirm before you act (apply an edit, send something on their behalf), also write reply_confirmation.json: {\"prompt\": \"Apply this edit?\", \"on_approve\": {\"type\": \"follow_up_run_task\", \"instruction\": \"...\"}, \"on_reject\": {\"type\": \"noop\"}}. It is posted as Approve/Reject buttons and the chosen instruction comes back to you as a new message in this thread. Do not pretend the job has been done without actually doing it."
             }
             "discord" => {
                 "2. After finishing the task (step one), write a plain text reply in reply_message.txt in the workspace root. Use Discord markdown formatting: **bold**, *italic*, `code`, ```code blocks```. Keep the reply concise and conversational. Do not use HTML. If there are files to attach, put them in reply_attachments/ and mention them in the reply. Do not pretend the job has been done without actually doing it."
@@ -68,6 +137,12 @@ pub(super) fn build_prompt(
             "wechat" => {
                 "2. After finishing the task (step one), write a plain text reply in reply_message.txt in the workspace root. Keep the reply concise and conversational. Do not use HTML or markdown. If there are files to attach, put them in reply_attachments/ and mention them in the reply. Do not pretend the job has been done without actually doing it."
             }
+            "jira" => {
+                "2. After finishing the task (step one), write a plain text reply in reply_message.txt in the workspace root. It is posted as a comment on the Jira issue in incoming_email/jira_metadata.json, so keep it to what the issue's watchers need: what you did, what is left, and links. Do not use HTML or markdown. To change the issue itself (status, fields) or open new issues, use the create_jira_issue and update_jira_issue scheduler actions. Do not pretend the job has been done without actually doing it."
+            }
+            "internal" => {
+                "2. After finishing the task (step one), write a plain text reply in reply_message.txt in the workspace root. This request came from another DoWhiz employee, not a person: your reply goes back to them as the result and they pass it on to the user, so lead with the outcome and include everything they need without a follow-up question. If there are files to hand over, put them in reply_attachments/. Do not pretend the job has been done without actually doing it."
+            }
             "notion" => {
                 r#"2. After finishing the task (step one), you MUST reply directly to the Notion comment using the Notion API.
 
@@ -100,10 +175,15 @@ Keep your reply concise and helpful. Do not pretend the job has been done withou
     };
     let github_coauthor_section = build_github_coauthor_section(workspace_dir, input_email_dir);
     let user_identities_section = build_user_identities_section(user_identities);
+    let reply_preferences_section = build_reply_preferences_section(reply_preferences);
+    let reply_formatting_section =
+        build_reply_formatting_section(&reply_preferences.formatting, channel);
     let filesystem_security_section =
         build_allowed_paths_section(&user_identities.allowed_user_ids);
     let web_auth_capabilities_section = build_web_auth_capabilities_section();
     let human_approval_gate_section = build_human_approval_gate_section();
+    let workspace_recovery_section = build_workspace_recovery_section(workspace_dir);
+    let output_issues_section = build_output_issues_section(workspace_dir);
 
     // Build registration prompt section if user doesn't have a unified account
     // and we haven't prompted them yet in this thread.
@@ -127,30 +207,78 @@ Account Registration Notice:
         ""
     };
 
-    format!(
-        r#"You are a DoWhiz digital employee. Follow the employee guidance provided below. Your task is to read incoming emails, understand the user's intent, finish the task, and draft appropriate email replies. You can also use memory and reference materials for context (already saved under current workspace). Always be cute, patient, friendly and helpful in your replies.
-
-Employee guidance (from workspace files):
-{guidance_section}
-
-You main goal is
-1. Most importantly, understand the task described in the incoming email and get the task done.
-{reply_instruction}
-
-Inputs (relative to workspace root):
+    let mut builder = PromptBuilder::new();
+    builder
+        .push(
+            PromptSection::budgeted(
+                "persona",
+                guidance_section,
+                8_000,
+                Truncation::KeepHead,
+                "read SOUL.md and AGENTS.md in the workspace root for the full guidance",
+            )
+            .framed(
+                "You are a DoWhiz digital employee. Follow the employee guidance provided below. Your task is to read incoming emails, understand the user's intent, finish the task, and draft appropriate email replies. You can also use memory and reference materials for context (already saved under current workspace). Always be cute, patient, friendly and helpful in your replies.\n\nEmployee guidance (from workspace files):\n",
+                "\n\n",
+            ),
+        )
+        .push(PromptSection::fixed(
+            "goal",
+            format!(
+                "You main goal is\n1. Most importantly, understand the task described in the incoming email and get the task done.\n{reply_instruction}\n\n"
+            ),
+        ))
+        .push(PromptSection::fixed(
+            "inputs",
+            format!(
+                r#"Inputs (relative to workspace root):
 - Incoming email dir: {input_email} (email.html, postmark_payload.json, thread_history.md, entries/)
 - For incoming email, all previous emails in current thread: /incoming_email/entries/
 - Incoming attachments dir: {input_attachments}
+- Image attachments may have <image name>_attachment_description.md files next to them in the attachments dir with OCR text and a description; use them when you cannot view the image directly.
 - Memory dir (memory about the current user): {memory}
-- Reference dir (contain all past emails with the current user): {reference}
-
-{discord_context_section}
-{github_coauthor_section}
+- Reference dir: {reference}. Past emails with the current user are not copied there; search them with `past-emails search "<words>"` and read one with `past-emails show <id>` (index: {reference}/past_emails/search_index.json).
+- Links in the incoming message may already be fetched as readable text into {reference}/links/; read those files before fetching the pages yourself.
 
-Memory about the current user:
-```{memory_section}```
+"#,
+                input_email = input_email_dir.display(),
+                input_attachments = input_attachments_dir.display(),
+                memory = memory_dir.display(),
+                reference = reference_dir.display(),
+            ),
+        ))
+        .push(attachments_manifest_section(
+            workspace_dir,
+            input_attachments_dir,
+        ))
+        .push(thread_history_section(workspace_dir, input_email_dir))
+        .push(PromptSection::fixed(
+            "channel_context",
+            format!("{discord_context_section}\n{github_coauthor_section}\n\n"),
+        ))
+        .push(memory_section(memory_context))
+        .push(PromptSection::fixed("memory_policy", MEMORY_POLICY_SECTION))
+        .push(skills_index_section(workspace_dir))
+        .push(PromptSection::fixed(
+            "capabilities",
+            format!(
+                "{}\n{web_auth_capabilities_section}\n{human_approval_gate_section}\n",
+                build_cross_channel_capabilities_section()
+            ),
+        ))
+        .push(PromptSection::fixed(
+            "user_context",
+            format!("{user_identities_section}{reply_preferences_section}{reply_formatting_section}"),
+        ))
+        .push(PromptSection::fixed("rules", RULES_SECTION))
+        .push(PromptSection::fixed(
+            "notices",
+            format!("{filesystem_security_section}{workspace_recovery_section}{output_issues_section}{registration_section}"),
+        ));
+    builder.render()
+}
 
-Memory management and maintain policy:
+const MEMORY_POLICY_SECTION: &str = r#"Memory management and maintain policy:
 - Read all Markdown files under memory/ before starting; they are long-term, per-user memory.
 - Persist durable facts only (identity, preferences, recurring tasks, projects, contacts,
   decisions, and working processes). Do not store transient email-specific details.
@@ -164,10 +292,9 @@ Memory management and maintain policy:
 Scheduling:
 - For any scheduling (email or task), you MUST use the skill "scheduler_maintain".
 
-{cross_channel_capabilities}
-{web_auth_capabilities_section}
-{human_approval_gate_section}
-{user_identities_section}
+"#;
+
+const RULES_SECTION: &str = r#"
 Rules:
 - Each workspace includes a `.env` file at the workspace root. You may edit it to manage per-user secrets; updates are synced back after the task completes.
 - Do not modify input directories. Any file editing requests should be done on the copied version of attachments and save into reply_email_attachments/ to be sent back to the user. Mark version updates as "_v2", "_v3", etc. in the filename.
@@ -175,25 +302,131 @@ Rules:
   Prefer creating a work/ directory for clones, patches, and build artifacts.
 - If attachments include version suffixes like _v1, _v2, the highest version should be the latest version.
 - Avoid interactive commands; use non-interactive flags for git/gh (for example, `gh pr create --title ... --body ...`).
-{filesystem_security_section}{registration_section}"#,
-        input_email = input_email_dir.display(),
-        input_attachments = input_attachments_dir.display(),
-        memory = memory_dir.display(),
-        reference = reference_dir.display(),
-        memory_section = memory_section,
-        guidance_section = guidance_section,
-        reply_instruction = reply_instruction,
-        discord_context_section = discord_context_section,
-        github_coauthor_section = github_coauthor_section,
-        cross_channel_capabilities = build_cross_channel_capabilities_section(),
-        web_auth_capabilities_section = web_auth_capabilities_section,
-        human_approval_gate_section = human_approval_gate_section,
-        user_identities_section = user_identities_section,
-        filesystem_security_section = filesystem_security_section,
-        registration_section = registration_section,
+- You may list your outputs in run_output.json at the workspace root: {"reply": "<reply file>", "attachments": ["work/report.pdf"], "scheduled_tasks": [...], "scheduler_actions": [...], "memory_updates": [{"section": "Preferences", "facts": ["..."]}]}. Every field is optional; scheduled_tasks and scheduler_actions there replace the JSON blocks.
+"#;
+
+/// Memory files inlined into the prompt, cut from the end when over budget.
+fn memory_section(memory_context: &str) -> PromptSection {
+    let content = if memory_context.trim().is_empty() {
+        "Memory context (from memory/*.md):\n- (no memory files found)\n\n".to_string()
+    } else {
+        format!(
+            "Memory context (from memory/*.md):\n{memory_context}\n\n",
+            memory_context = memory_context.trim_end()
+        )
+    };
+    PromptSection::budgeted(
+        "memory",
+        content,
+        8_000,
+        Truncation::KeepHead,
+        "read the files under memory/ for the rest",
+    )
+    .framed("Memory about the current user:\n```", "```\n\n")
+}
+
+/// The thread so far, newest messages kept when over budget.
+fn thread_history_section(workspace_dir: &Path, input_email_dir: &Path) -> PromptSection {
+    let path = workspace_dir
+        .join(input_email_dir)
+        .join("thread_history.md");
+    let Some(history) = load_optional_text(&path) else {
+        return PromptSection::fixed("thread_history", "");
+    };
+    PromptSection::budgeted(
+        "thread_history",
+        history,
+        6_000,
+        Truncation::KeepTail,
+        "older messages are in thread_history.md",
+    )
+    .framed(
+        "Thread history (from thread_history.md, oldest first):\n```markdown\n",
+        "\n```\n\n",
+    )
+}
+
+/// The skills manifest, so the runner opens only the skills it needs. A
+/// partial index would hide skills, so an oversized one is left out.
+fn skills_index_section(workspace_dir: &Path) -> PromptSection {
+    let skills = scan_skills(workspace_dir);
+    if skills.is_empty() {
+        return PromptSection::fixed("skills_index", "");
+    }
+    if !workspace_dir.join(SKILLS_INDEX_FILE).exists() {
+        let _ = write_skills_index(workspace_dir);
+    }
+    PromptSection::budgeted(
+        "skills_index",
+        render_entries(&skills),
+        4_000,
+        Truncation::Omit,
+        "read skills_index.md to see the available skills",
+    )
+    .framed(
+        format!(
+            "Skills (not preloaded; the full list with entry files is in {SKILLS_INDEX_FILE}). \
+             Open a skill's SKILL.md only when the task needs that skill, then follow it:\n"
+        ),
+        "\n",
     )
 }
 
+/// The incoming attachments with their sizes.
+fn attachments_manifest_section(
+    workspace_dir: &Path,
+    input_attachments_dir: &Path,
+) -> PromptSection {
+    let Ok(entries) = fs::read_dir(workspace_dir.join(input_attachments_dir)) else {
+        return PromptSection::fixed("attachments_manifest", "");
+    };
+    let mut files: Vec<(String, u64)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| {
+                (
+                    entry.file_name().to_string_lossy().into_owned(),
+                    metadata.len(),
+                )
+            })
+        })
+        .collect();
+    if files.is_empty() {
+        return PromptSection::fixed("attachments_manifest", "");
+    }
+    files.sort();
+    let manifest = files
+        .iter()
+        .map(|(name, len)| format!("- {name} ({})\n", format_size(*len)))
+        .collect::<String>();
+    PromptSection::budgeted(
+        "attachments_manifest",
+        manifest,
+        1_000,
+        Truncation::KeepHead,
+        "list the attachments dir for the rest",
+    )
+    .framed(
+        format!(
+            "Incoming attachments ({count} files in {dir}):\n",
+            count = files.len(),
+            dir = input_attachments_dir.display()
+        ),
+        "\n",
+    )
+}
+
+fn format_size(len: u64) -> String {
+    if len < 1_024 {
+        format!("{len} B")
+    } else if len < 1_048_576 {
+        format!("{:.1} KiB", len as f64 / 1_024.0)
+    } else {
+        format!("{:.1} MiB", len as f64 / 1_048_576.0)
+    }
+}
+
 /// Build the cross-channel capabilities section that informs the agent
 /// about available tools for operating across different channels.
 fn build_cross_channel_capabilities_section() -> &'static str {
@@ -356,6 +589,88 @@ If the user requests routing to an unlisted identifier, politely decline and exp
     )
 }
 
+fn build_reply_preferences_section(preferences: &ReplyPreferences) -> String {
+    let mut lines = Vec::new();
+    if let Some(language) = preferences
+        .language
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        lines.push(format!(
+            "- Reply language: {}. Write replies in this language unless the user writes to you in another one.",
+            language
+        ));
+    }
+    if let Some(channel) = preferences
+        .preferred_channel
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        lines.push(format!(
+            "- Preferred contact channel: {}. When this run was not started by a message from the user (a scheduled or recurring task), deliver the reply there with reply_routing.json if one of the user's linked identifiers is on that channel.",
+            channel
+        ));
+    }
+    if lines.is_empty() {
+        return String::new();
+    }
+    format!("\nUser preferences:\n{}\n", lines.join("\n"))
+}
+
+/// The employee's formatting for `channel`. On chat channels the signature
+/// and length limit are also enforced when the reply is sent.
+fn build_reply_formatting_section(formatting: &ReplyFormatting, channel: &str) -> String {
+    let enforced = FORMATTED_REPLY_CHANNELS
+        .iter()
+        .any(|formatted| formatted.eq_ignore_ascii_case(channel));
+    let mut lines = Vec::new();
+    match formatting.emoji {
+        Some(false) => lines.push("- Do not use emoji.".to_string()),
+        Some(true) => lines.push("- Emoji are welcome where they fit, sparingly.".to_string()),
+        None => {}
+    }
+    if let Some(max_chars) = formatting.max_chars {
+        lines.push(if enforced {
+            format!(
+                "- Keep the reply under {} characters; longer replies are cut off when sent.",
+                max_chars
+            )
+        } else {
+            format!("- Keep the reply under {} characters.", max_chars)
+        });
+    }
+    if let Some(tone) = formatting
+        .tone
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        lines.push(format!("- Tone: {}", tone));
+    }
+    if let Some(signature) = formatting
+        .signature
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        lines.push(if enforced {
+            "- A signature is added below the reply when it is sent; do not sign the reply yourself."
+                .to_string()
+        } else {
+            format!("- End the reply with this signature:\n{}", signature)
+        });
+    }
+    if lines.is_empty() {
+        return String::new();
+    }
+    format!(
+        "\nReply formatting for this channel:\n{}\n",
+        lines.join("\n")
+    )
+}
+
 fn build_allowed_paths_section(allowed_user_ids: &[String]) -> String {
     if allowed_user_ids.is_empty() {
         return r#"
@@ -566,7 +881,7 @@ fn normalize_github_login(raw: &str) -> Option<String> {
     }
 }
 
-fn build_guidance_section(workspace_dir: &Path, runner: &str) -> String {
+pub(super) fn build_guidance_section(workspace_dir: &Path, runner: &str) -> String {
     let mut blocks = Vec::new();
 
     if let Some(content) = load_optional_text(&workspace_dir.join("SOUL.md")) {
@@ -580,6 +895,11 @@ fn build_guidance_section(workspace_dir: &Path, runner: &str) -> String {
             blocks.push(format_guidance_block("CLAUDE.md", &content));
         }
     }
+    if runner.eq_ignore_ascii_case("gemini") {
+        if let Some(content) = load_optional_text(&workspace_dir.join("GEMINI.md")) {
+            blocks.push(format_guidance_block("GEMINI.md", &content));
+        }
+    }
 
     if blocks.is_empty() {
         "- (no employee guidance files found)\n".to_string()
@@ -689,6 +1009,23 @@ mod tests {
         assert!(!context.contains("note.txt"));
     }
 
+    #[test]
+    fn build_guidance_section_includes_runner_specific_file() {
+        let temp = TempDir::new().expect("tempdir");
+        fs::write(temp.path().join("AGENTS.md"), "shared rules").expect("AGENTS.md");
+        fs::write(temp.path().join("CLAUDE.md"), "claude rules").expect("CLAUDE.md");
+        fs::write(temp.path().join("GEMINI.md"), "gemini rules").expect("GEMINI.md");
+
+        let gemini = build_guidance_section(temp.path(), "gemini");
+        assert!(gemini.contains("shared rules"));
+        assert!(gemini.contains("GEMINI.md:\n```\ngemini rules"));
+        assert!(!gemini.contains("claude rules"));
+
+        let codex = build_guidance_section(temp.path(), "codex");
+        assert!(!codex.contains("gemini rules"));
+        assert!(!codex.contains("claude rules"));
+    }
+
     #[test]
     fn build_prompt_includes_memory_policy_and_section() {
         let prompt = build_prompt(
@@ -703,7 +1040,9 @@ mod tests {
             "email",
             true, // has_unified_account
             &UserIdentities::default(),
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         assert!(prompt.contains("Memory context"));
         assert!(prompt.contains("memory/memo.md"));
@@ -726,7 +1065,9 @@ mod tests {
             "email",
             true, // has_unified_account
             &UserIdentities::default(),
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         assert!(prompt.contains("non-replyable"));
         assert!(!prompt.contains("write a proper HTML email draft"));
@@ -749,7 +1090,9 @@ mod tests {
             "email",
             false, // has_unified_account = false
             &UserIdentities::default(),
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         assert!(prompt.contains("Account Registration Notice"));
         assert!(prompt.contains("www.dowhiz.com/auth/index.html"));
@@ -767,11 +1110,84 @@ mod tests {
             "email",
             false,
             &UserIdentities::default(),
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         assert!(!prompt2.contains("Account Registration Notice"));
     }
 
+    #[test]
+    fn build_prompt_includes_workspace_recovery_notice_until_delivered() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspace = temp.path();
+        fs::write(
+            workspace.join(WORKSPACE_RECOVERY_NOTE),
+            "# Workspace recovered\n",
+        )
+        .expect("write recovery note");
+
+        let build = || {
+            build_prompt(
+                Path::new("incoming_email"),
+                Path::new("incoming_attachments"),
+                Path::new("memory"),
+                Path::new("references"),
+                workspace,
+                "codex",
+                "",
+                true,
+                "email",
+                true,
+                &UserIdentities::default(),
+                &ReplyPreferences::default(),
+            )
+            .text
+        };
+
+        let prompt = build();
+        assert!(prompt.contains("Workspace Recovery Notice"));
+        assert!(prompt.contains("may be summarized"));
+        // A run whose reply never went out asks again.
+        assert!(build().contains("Workspace Recovery Notice"));
+
+        fs::write(workspace.join(WORKSPACE_RECOVERY_NOTIFIED), "1").expect("write marker");
+        assert!(!build().contains("Workspace Recovery Notice"));
+    }
+
+    #[test]
+    fn build_prompt_reports_previous_output_issues() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspace = temp.path();
+        let build = || {
+            build_prompt(
+                Path::new("incoming_email"),
+                Path::new("incoming_attachments"),
+                Path::new("memory"),
+                Path::new("references"),
+                workspace,
+                "codex",
+                "",
+                true,
+                "email",
+                true,
+                &UserIdentities::default(),
+                &ReplyPreferences::default(),
+            )
+            .text
+        };
+
+        assert!(!build().contains("Previous Output Issues"));
+        fs::write(
+            workspace.join(OUTPUT_ISSUES_FILE),
+            "- attachment work/report.pdf does not exist\n",
+        )
+        .expect("write issues");
+        let prompt = build();
+        assert!(prompt.contains("Previous Output Issues"));
+        assert!(prompt.contains("- attachment work/report.pdf does not exist"));
+    }
+
     #[test]
     fn build_prompt_includes_discord_context_snapshot_when_available() {
         let temp = TempDir::new().expect("tempdir");
@@ -796,7 +1212,9 @@ mod tests {
             "discord",
             true,
             &UserIdentities::default(),
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         assert!(prompt.contains("Discord context snapshot (auto-generated"));
         assert!(prompt.contains("Quoted + thread context"));
@@ -829,7 +1247,9 @@ mod tests {
             "email",
             true,
             &UserIdentities::default(),
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         assert!(prompt.contains("GitHub Attribution Requirement"));
         assert!(
@@ -865,7 +1285,9 @@ mod tests {
             "email",
             true,
             &UserIdentities::default(),
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         assert!(!prompt.contains("GitHub Attribution Requirement"));
         assert!(!prompt.contains("Co-authored-by:"));
@@ -924,6 +1346,64 @@ mod tests {
         assert!(section.contains("discord target: reply_message.txt"));
     }
 
+    #[test]
+    fn build_prompt_includes_reply_preferences_when_set() {
+        let temp = TempDir::new().expect("tempdir");
+        let build = |preferences: &ReplyPreferences| {
+            build_prompt(
+                Path::new("incoming_email"),
+                Path::new("incoming_attachments"),
+                Path::new("memory"),
+                Path::new("references"),
+                temp.path(),
+                "codex",
+                "",
+                true,
+                "email",
+                true,
+                &UserIdentities::default(),
+                preferences,
+            )
+            .text
+        };
+
+        assert!(!build(&ReplyPreferences::default()).contains("User preferences:"));
+        let prompt = build(&ReplyPreferences {
+            preferred_channel: Some("slack".to_string()),
+            language: Some("French".to_string()),
+            ..Default::default()
+        });
+        assert!(prompt.contains("User preferences:"));
+        assert!(prompt.contains("- Reply language: French."));
+        assert!(prompt.contains("- Preferred contact channel: slack."));
+    }
+
+    #[test]
+    fn reply_formatting_section_defers_signature_to_the_sender_on_chat() {
+        let formatting = ReplyFormatting {
+            emoji: Some(false),
+            signature: Some("Best,\nOliver".to_string()),
+            max_chars: Some(600),
+            tone: Some("formal, no slang".to_string()),
+        };
+        let slack = build_reply_formatting_section(&formatting, "slack");
+        assert!(slack.contains("Reply formatting for this channel:"));
+        assert!(slack.contains("- Do not use emoji."));
+        assert!(slack.contains("under 600 characters; longer replies are cut off"));
+        assert!(slack.contains("- Tone: formal, no slang"));
+        assert!(slack.contains("do not sign the reply yourself"));
+        assert!(!slack.contains("Oliver"));
+
+        let email = build_reply_formatting_section(&formatting, "email");
+        assert!(email.contains("- Keep the reply under 600 characters.\n"));
+        assert!(email.contains("End the reply with this signature:\nBest,\nOliver"));
+
+        assert_eq!(
+            build_reply_formatting_section(&ReplyFormatting::default(), "slack"),
+            ""
+        );
+    }
+
     #[test]
     fn build_prompt_includes_user_identities_when_present() {
         let temp = TempDir::new().expect("tempdir");
@@ -946,7 +1426,9 @@ mod tests {
             "email",
             true,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         assert!(prompt.contains("Cross-channel routing"));
         assert!(prompt.contains("test@example.com"));
@@ -970,7 +1452,9 @@ mod tests {
             "email",
             true,
             &UserIdentities::default(),
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Verify cross-channel tools section is included
         assert!(prompt.contains("Cross-channel Tools"));
@@ -1002,7 +1486,9 @@ mod tests {
             "email",
             true,
             &UserIdentities::default(),
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         assert!(prompt.contains("Human Approval Gate"));
         assert!(prompt.contains("dowhiz_human_approval_gate_request_and_wait"));
@@ -1073,7 +1559,9 @@ mod tests {
             "email",
             false,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         assert!(prompt.contains("Filesystem Security"));
         assert!(prompt.contains("/users/test-user-uuid/"));
@@ -1096,7 +1584,9 @@ mod tests {
             "email",
             false,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         assert!(prompt.contains("Filesystem Security"));
         assert!(prompt.contains("ONLY access files within your current workspace directory"));
@@ -1130,7 +1620,9 @@ mod tests {
             "email",
             false, // has_unified_account = false
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Should have workspace-only restriction
         assert!(prompt.contains("ONLY access files within your current workspace directory"));
@@ -1166,7 +1658,9 @@ mod tests {
             "email",
             true, // has_unified_account = true
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Should have the specific user path
         assert!(prompt.contains(&format!("/users/{}/", user_uuid)));
@@ -1211,7 +1705,9 @@ mod tests {
             "email",
             true,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Should have all user paths
         assert!(prompt.contains(&format!("/users/{}/", email_uuid)));
@@ -1248,7 +1744,9 @@ mod tests {
             "email",
             true, // has_unified_account = true
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Should fall back to workspace-only restriction
         assert!(prompt.contains("ONLY access files within your current workspace directory"));
@@ -1273,7 +1771,9 @@ mod tests {
             "email",
             false,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Security section should appear after the rules
         let rules_pos = prompt
@@ -1305,7 +1805,9 @@ mod tests {
             "email",
             false,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Should mention that cross-channel is not available
         assert!(prompt.contains("no linked DoWhiz account"));
@@ -1335,7 +1837,9 @@ mod tests {
             "email",
             true,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Should have cross-channel routing info
         assert!(prompt.contains("Cross-channel routing"));
@@ -1380,7 +1884,9 @@ mod tests {
             "email",
             true,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Codex should see exactly one allowed path
         assert!(prompt.contains("/users/uuid-email-alice/"));
@@ -1425,7 +1931,9 @@ mod tests {
             "email", // inbound channel is email
             true,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Codex should see ALL four allowed paths
         assert!(prompt.contains("/users/uuid-email-bob/"));
@@ -1474,7 +1982,9 @@ mod tests {
             "email",
             true,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Should only list the path once (deduplicated)
         let user_path_count = prompt.matches("/users/uuid-charlie-shared/").count();
@@ -1511,7 +2021,9 @@ mod tests {
             "slack", // <-- Inbound channel is Slack
             true,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Should still have both paths - channel doesn't affect security
         assert!(prompt.contains("/users/uuid-email-dave/"));
@@ -1545,7 +2057,9 @@ mod tests {
             "email",
             true,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Check exact phrases Codex will see
         assert!(prompt.contains("Filesystem Security:"));
@@ -1574,7 +2088,9 @@ mod tests {
             "email",
             false,
             &identities,
-        );
+            &ReplyPreferences::default(),
+        )
+        .text;
 
         // Check exact phrases
         assert!(prompt.contains("Filesystem Security:"));
@@ -1585,4 +2101,58 @@ mod tests {
             "Do NOT traverse to parent directories or access paths outside this workspace."
         ));
     }
+
+    #[test]
+    fn build_prompt_indexes_thread_history_skills_and_attachments() {
+        let temp = TempDir::new().expect("tempdir");
+        let incoming = temp.path().join("incoming_email");
+        fs::create_dir_all(&incoming).expect("incoming dir");
+        let history = (1..=3000)
+            .map(|n| format!("message {n}\n"))
+            .collect::<String>();
+        fs::write(incoming.join("thread_history.md"), history).expect("history");
+        let attachments = temp.path().join("incoming_attachments");
+        fs::create_dir_all(&attachments).expect("attachments dir");
+        fs::write(attachments.join("report.pdf"), vec![0u8; 2048]).expect("report");
+        let skill = temp.path().join(".agents").join("skills").join("pdf");
+        fs::create_dir_all(&skill).expect("skill dir");
+        fs::write(
+            skill.join("SKILL.md"),
+            "---\nname: pdf\ndescription: Read and write PDF files.\n---\n\n# PDF\n",
+        )
+        .expect("skill");
+
+        let prompt = build_prompt(
+            Path::new("incoming_email"),
+            Path::new("incoming_attachments"),
+            Path::new("memory"),
+            Path::new("references"),
+            temp.path(),
+            "codex",
+            "",
+            true,
+            "email",
+            true,
+            &UserIdentities::default(),
+            &ReplyPreferences::default(),
+        );
+
+        assert!(prompt.text.contains("- report.pdf (2.0 KiB)"));
+        assert!(prompt
+            .text
+            .contains("- pdf: Read and write PDF files. (`.agents/skills/pdf/SKILL.md`)"));
+        assert!(temp.path().join(SKILLS_INDEX_FILE).exists());
+        assert!(prompt.text.contains("message 3000\n"));
+        assert!(!prompt.text.contains("message 1\n"));
+        assert!(prompt
+            .text
+            .contains("older messages are in thread_history.md"));
+        let history_report = prompt
+            .sections
+            .iter()
+            .find(|section| section.name == "thread_history")
+            .expect("thread_history section");
+        assert!(history_report.truncated);
+        assert!(history_report.tokens <= 6_000 + 50);
+    }
 }
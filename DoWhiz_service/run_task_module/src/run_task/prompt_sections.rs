@@ -0,0 +1,324 @@
+//! Composable prompt sections with per-section token budgets.
+//!
+//! `build_prompt` assembles the runner prompt from named [`PromptSection`]s,
+//! in order. A section with a budget has its content cut to fit with its
+//! [`Truncation`] strategy; its framing text and sections without a budget are
+//! kept whole. Tokens are estimated at 4 characters each. The default budget
+//! of a section can be overridden with `RUN_TASK_PROMPT_BUDGET_<NAME>` (e.g.
+//! `RUN_TASK_PROMPT_BUDGET_MEMORY=4000`); `0` removes the budget.
+//!
+//! The rendered prompt and a per-section report are written to
+//! `.prompt_debug/` in the workspace, so the exact input of the last run can
+//! be inspected.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+/// Directory of a workspace that holds the last rendered prompt.
+pub(super) const PROMPT_DEBUG_DIR: &str = ".prompt_debug";
+
+const CHARS_PER_TOKEN: usize = 4;
+
+/// How a section's content is cut when it is over its budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Truncation {
+    /// Keep the start, cut the end.
+    KeepHead,
+    /// Keep the end, cut the start (newest thread messages come last).
+    KeepTail,
+    /// Drop the content and leave only the note.
+    Omit,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct PromptSection {
+    name: &'static str,
+    prefix: String,
+    content: String,
+    suffix: String,
+    budget_tokens: Option<usize>,
+    truncation: Truncation,
+    note: &'static str,
+}
+
+impl PromptSection {
+    /// A section kept whole.
+    pub(super) fn fixed(name: &'static str, text: impl Into<String>) -> Self {
+        Self {
+            name,
+            prefix: String::new(),
+            content: text.into(),
+            suffix: String::new(),
+            budget_tokens: None,
+            truncation: Truncation::KeepHead,
+            note: "",
+        }
+    }
+
+    /// A section whose content is cut to `default_budget_tokens`, unless
+    /// `RUN_TASK_PROMPT_BUDGET_<NAME>` says otherwise. `note` tells the agent
+    /// where to find the rest.
+    pub(super) fn budgeted(
+        name: &'static str,
+        content: impl Into<String>,
+        default_budget_tokens: usize,
+        truncation: Truncation,
+        note: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            prefix: String::new(),
+            content: content.into(),
+            suffix: String::new(),
+            budget_tokens: budget_from_env(name, default_budget_tokens),
+            truncation,
+            note,
+        }
+    }
+
+    /// Text around the content that does not count against the budget.
+    pub(super) fn framed(mut self, prefix: impl Into<String>, suffix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self.suffix = suffix.into();
+        self
+    }
+
+    #[cfg(test)]
+    fn with_budget(mut self, budget_tokens: Option<usize>) -> Self {
+        self.budget_tokens = budget_tokens;
+        self
+    }
+
+    fn render(&self) -> (String, SectionReport) {
+        let content_tokens = estimate_tokens(&self.content);
+        let (content, truncated) = match self.budget_tokens {
+            Some(budget) if content_tokens > budget => (
+                truncate(&self.content, budget, self.truncation, self.note),
+                true,
+            ),
+            _ => (self.content.clone(), false),
+        };
+        let text = format!("{}{}{}", self.prefix, content, self.suffix);
+        let report = SectionReport {
+            name: self.name,
+            tokens: estimate_tokens(&text),
+            content_tokens,
+            budget_tokens: self.budget_tokens,
+            truncation: self.truncation,
+            truncated,
+        };
+        (text, report)
+    }
+}
+
+/// What one section contributed to a rendered prompt.
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct SectionReport {
+    pub(super) name: &'static str,
+    /// Estimated tokens of the section as rendered.
+    pub(super) tokens: usize,
+    /// Estimated tokens of the content before truncation.
+    pub(super) content_tokens: usize,
+    pub(super) budget_tokens: Option<usize>,
+    pub(super) truncation: Truncation,
+    pub(super) truncated: bool,
+}
+
+#[derive(Debug, Default)]
+pub(super) struct PromptBuilder {
+    sections: Vec<PromptSection>,
+}
+
+impl PromptBuilder {
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `section`; sections that render empty are skipped.
+    pub(super) fn push(&mut self, section: PromptSection) -> &mut Self {
+        if !(section.prefix.is_empty() && section.content.is_empty() && section.suffix.is_empty()) {
+            self.sections.push(section);
+        }
+        self
+    }
+
+    pub(super) fn render(&self) -> RenderedPrompt {
+        let mut text = String::new();
+        let mut sections = Vec::with_capacity(self.sections.len());
+        for section in &self.sections {
+            let (rendered, report) = section.render();
+            text.push_str(&rendered);
+            sections.push(report);
+        }
+        RenderedPrompt { text, sections }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct RenderedPrompt {
+    pub(super) text: String,
+    pub(super) sections: Vec<SectionReport>,
+}
+
+impl RenderedPrompt {
+    /// Writes `prompt.md` and `sections.json` under [`PROMPT_DEBUG_DIR`],
+    /// replacing those of the previous run.
+    pub(super) fn write_debug_dump(&self, workspace_dir: &Path) -> io::Result<()> {
+        let dir = workspace_dir.join(PROMPT_DEBUG_DIR);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("prompt.md"), &self.text)?;
+        let report = serde_json::json!({
+            "total_tokens": estimate_tokens(&self.text),
+            "sections": self.sections,
+        });
+        fs::write(
+            dir.join("sections.json"),
+            serde_json::to_vec_pretty(&report).map_err(io::Error::other)?,
+        )
+    }
+}
+
+pub(super) fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+fn budget_from_env(name: &str, default_budget_tokens: usize) -> Option<usize> {
+    let key = format!("RUN_TASK_PROMPT_BUDGET_{}", name.to_ascii_uppercase());
+    let budget = std::env::var(key)
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(default_budget_tokens);
+    (budget > 0).then_some(budget)
+}
+
+/// Cuts `content` to about `budget_tokens`, on a line boundary where one is
+/// close, and marks the cut with `note`.
+fn truncate(content: &str, budget_tokens: usize, truncation: Truncation, note: &str) -> String {
+    let marker = if note.is_empty() {
+        "(truncated to fit the prompt budget)".to_string()
+    } else {
+        format!("(truncated to fit the prompt budget; {note})")
+    };
+    let keep_chars = budget_tokens
+        .saturating_mul(CHARS_PER_TOKEN)
+        .saturating_sub(marker.chars().count() + 2);
+    match truncation {
+        Truncation::Omit => marker,
+        Truncation::KeepHead => {
+            let head: String = content.chars().take(keep_chars).collect();
+            let head = match head.rfind('\n') {
+                Some(idx) if idx >= head.len() / 2 => &head[..idx],
+                _ => head.as_str(),
+            };
+            format!("{}\n{marker}\n", head.trim_end())
+        }
+        Truncation::KeepTail => {
+            let skip = content.chars().count().saturating_sub(keep_chars);
+            let tail: String = content.chars().skip(skip).collect();
+            let tail = match tail.find('\n') {
+                Some(idx) if idx < tail.len() / 2 => &tail[idx + 1..],
+                _ => tail.as_str(),
+            };
+            format!("{marker}\n{}", tail.trim_start())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn numbered_lines(count: usize) -> String {
+        (1..=count)
+            .map(|n| format!("line {n:03}\n"))
+            .collect::<String>()
+    }
+
+    #[test]
+    fn budgeted_section_keeps_head_or_tail_on_line_boundaries() {
+        let content = numbered_lines(100);
+        let head = PromptSection::budgeted("memory", &content, 0, Truncation::KeepHead, "")
+            .with_budget(Some(50))
+            .render()
+            .0;
+        assert!(head.starts_with("line 001\n"));
+        assert!(!head.contains("line 100"));
+        assert!(head
+            .trim_end()
+            .ends_with("(truncated to fit the prompt budget)"));
+        assert!(estimate_tokens(&head) <= 50);
+
+        let tail = PromptSection::budgeted(
+            "thread_history",
+            &content,
+            0,
+            Truncation::KeepTail,
+            "read thread_history.md",
+        )
+        .with_budget(Some(50))
+        .render()
+        .0;
+        assert!(
+            tail.starts_with("(truncated to fit the prompt budget; read thread_history.md)\nline ")
+        );
+        assert!(tail.ends_with("line 100\n"));
+        assert!(!tail.contains("line 001"));
+        assert!(estimate_tokens(&tail) <= 50);
+    }
+
+    #[test]
+    fn omitted_section_keeps_framing_and_note_only() {
+        let (text, report) = PromptSection::budgeted(
+            "skills_index",
+            numbered_lines(100),
+            0,
+            Truncation::Omit,
+            "list .agents/skills/",
+        )
+        .framed("Skills:\n", "\n\n")
+        .with_budget(Some(10))
+        .render();
+        assert_eq!(
+            text,
+            "Skills:\n(truncated to fit the prompt budget; list .agents/skills/)\n\n"
+        );
+        assert!(report.truncated);
+        assert_eq!(report.content_tokens, 225);
+    }
+
+    #[test]
+    fn builder_renders_in_order_and_dumps_report() {
+        let temp = TempDir::new().expect("tempdir");
+        let mut builder = PromptBuilder::new();
+        builder
+            .push(PromptSection::fixed("persona", "You are a test.\n"))
+            .push(PromptSection::fixed("empty", ""))
+            .push(
+                PromptSection::budgeted("memory", "facts", 0, Truncation::KeepHead, "")
+                    .framed("Memory:\n", "\n")
+                    .with_budget(Some(100)),
+            );
+        let prompt = builder.render();
+        assert_eq!(prompt.text, "You are a test.\nMemory:\nfacts\n");
+        let names: Vec<_> = prompt.sections.iter().map(|section| section.name).collect();
+        assert_eq!(names, ["persona", "memory"]);
+
+        prompt.write_debug_dump(temp.path()).expect("dump");
+        let dir = temp.path().join(PROMPT_DEBUG_DIR);
+        assert_eq!(
+            fs::read_to_string(dir.join("prompt.md")).expect("prompt.md"),
+            prompt.text
+        );
+        let report: serde_json::Value =
+            serde_json::from_slice(&fs::read(dir.join("sections.json")).expect("sections.json"))
+                .expect("json");
+        assert_eq!(report["sections"][1]["name"], "memory");
+        assert_eq!(report["sections"][1]["budget_tokens"], 100);
+        assert_eq!(report["sections"][1]["truncated"], false);
+    }
+}
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use super::contract::{apply_output_contract, stage_output_manifest};
+use super::env::load_env_sources;
+use super::errors::RunTaskError;
+use super::types::{RunTaskOutput, RunTaskRequest};
+
+/// Everything a runner needs for one task.
+pub(super) struct RunContext<'a> {
+    pub(super) request: RunTaskRequest<'a>,
+    /// Normalized runner name, e.g. `codex`, `claude` or `local`.
+    pub(super) runner: &'a str,
+    pub(super) reply_html_path: PathBuf,
+    pub(super) reply_attachments_dir: PathBuf,
+}
+
+/// A backend that works a task in its prepared workspace.
+///
+/// The codex and claude CLIs read their own transcripts while they run, so
+/// their `collect` passes the output through; backends without a CLI, such as
+/// [`super::local_model::LocalModelRunner`], write the reply in `collect`.
+pub(super) trait Runner {
+    /// What `execute` hands to `collect`.
+    type Execution;
+
+    /// Load configuration and check the runner can serve this request.
+    fn prepare(&self, ctx: &RunContext<'_>) -> Result<(), RunTaskError> {
+        load_env_sources(ctx.request.workspace_dir)
+    }
+
+    /// Run the model on the task.
+    fn execute(&self, ctx: &RunContext<'_>) -> Result<Self::Execution, RunTaskError>;
+
+    /// Turn the execution into the reply, scheduled tasks and usage.
+    fn collect(
+        &self,
+        ctx: &RunContext<'_>,
+        execution: Self::Execution,
+    ) -> Result<RunTaskOutput, RunTaskError>;
+}
+
+pub(super) fn run_with<R: Runner>(
+    runner: &R,
+    ctx: &RunContext<'_>,
+) -> Result<RunTaskOutput, RunTaskError> {
+    runner.prepare(ctx)?;
+    let mut repair_attempted = false;
+    loop {
+        let execution = runner.execute(ctx)?;
+        let staged = stage_output_manifest(ctx)?;
+        let output = runner.collect(ctx, execution)?;
+        match apply_output_contract(ctx, staged, output) {
+            // The rejection is in the issues file, which the next prompt
+            // includes; a second rejection fails the run.
+            Err(RunTaskError::DraftRejected { path, .. }) if !repair_attempted => {
+                eprintln!(
+                    "[run_task] {} was rejected; asking the runner to repair it",
+                    path.display()
+                );
+                repair_attempted = true;
+            }
+            result => return result,
+        }
+    }
+}
@@ -100,6 +100,7 @@ fn extract_json_segments(output: &str, begin: &str, end: &str) -> Vec<String> {
 
 #[cfg(test)]
 mod tests {
+    use super::super::types::MeetingProvider;
     use super::*;
 
     #[test]
@@ -127,6 +128,109 @@ mod tests {
         }
     }
 
+    #[test]
+    fn extract_scheduler_actions_parses_list_tasks() {
+        let output = format!(
+            "{}\n[{{\"action\":\"list_tasks\"}},{{\"action\":\"cancel\",\"task_ids\":[\"a\"]}}]\n{}",
+            SCHEDULER_ACTIONS_BEGIN, SCHEDULER_ACTIONS_END
+        );
+        let (actions, error) = extract_scheduler_actions(&output);
+        assert!(error.is_none());
+        assert!(matches!(actions[0], SchedulerActionRequest::ListTasks));
+        assert!(matches!(actions[1], SchedulerActionRequest::Cancel { .. }));
+    }
+
+    #[test]
+    fn extract_scheduler_actions_parses_handoff() {
+        let output = format!(
+            "{}\n[{{\"action\":\"handoff\",\"employee_id\":\"boiled_egg\",\"summary\":\"Fix the login bug\"}}]\n{}",
+            SCHEDULER_ACTIONS_BEGIN, SCHEDULER_ACTIONS_END
+        );
+        let (actions, error) = extract_scheduler_actions(&output);
+        assert!(error.is_none());
+        match &actions[0] {
+            SchedulerActionRequest::Handoff {
+                employee_id,
+                summary,
+            } => {
+                assert_eq!(employee_id, "boiled_egg");
+                assert_eq!(summary, "Fix the login bug");
+            }
+            other => panic!("unexpected action: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn extract_scheduler_actions_parses_jira_update() {
+        let output = format!(
+            "{}\n[{{\"action\":\"update_jira_issue\",\"issue_key\":\"OPS-42\",\"transition\":\"Done\",\"comment\":\"Renewed.\"}}]\n{}",
+            SCHEDULER_ACTIONS_BEGIN, SCHEDULER_ACTIONS_END
+        );
+        let (actions, error) = extract_scheduler_actions(&output);
+        assert!(error.is_none());
+        match &actions[0] {
+            SchedulerActionRequest::UpdateJiraIssue {
+                issue_key,
+                summary,
+                transition,
+                comment,
+                ..
+            } => {
+                assert_eq!(issue_key, "OPS-42");
+                assert!(summary.is_none());
+                assert_eq!(transition.as_deref(), Some("Done"));
+                assert_eq!(comment.as_deref(), Some("Renewed."));
+            }
+            other => panic!("unexpected action: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn extract_scheduler_actions_defaults_meeting_to_half_hour_google_meet() {
+        let output = format!(
+            "{}\n[{{\"action\":\"create_meeting\",\"title\":\"Intro call\",\"start\":\"2026-10-20T15:00:00Z\"}}]\n{}",
+            SCHEDULER_ACTIONS_BEGIN, SCHEDULER_ACTIONS_END
+        );
+        let (actions, error) = extract_scheduler_actions(&output);
+        assert!(error.is_none());
+        match &actions[0] {
+            SchedulerActionRequest::CreateMeeting {
+                duration_minutes,
+                attendees,
+                provider,
+                ..
+            } => {
+                assert_eq!(*duration_minutes, 30);
+                assert!(attendees.is_empty());
+                assert_eq!(*provider, MeetingProvider::GoogleMeet);
+            }
+            other => panic!("unexpected action: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn extract_scheduler_actions_defaults_preview_to_three_runs_in_utc() {
+        let output = format!(
+            "{}\n[{{\"action\":\"preview_schedule\",\"expression\":\"0 0 17 * * Tue\"}}]\n{}",
+            SCHEDULER_ACTIONS_BEGIN, SCHEDULER_ACTIONS_END
+        );
+        let (actions, error) = extract_scheduler_actions(&output);
+        assert!(error.is_none());
+        match &actions[0] {
+            SchedulerActionRequest::PreviewSchedule {
+                timezone,
+                count,
+                add_to_reply,
+                ..
+            } => {
+                assert_eq!(*timezone, None);
+                assert_eq!(*count, 3);
+                assert!(!add_to_reply);
+            }
+            other => panic!("unexpected action: {:?}", other),
+        }
+    }
+
     #[test]
     fn extract_scheduler_actions_reports_invalid_json() {
         let output = format!(
@@ -152,6 +256,24 @@ mod tests {
         assert_eq!(tasks.len(), 1);
     }
 
+    #[test]
+    fn extract_scheduled_tasks_reads_approval_request() {
+        let output = format!(
+            "{}\n[{{\"type\":\"send_email\",\"delay_seconds\":0,\"subject\":\"NDA\",\"html_path\":\"x.html\",\"approval\":{{\"summary\":\"Send the NDA to legal?\"}}}}]\n{}",
+            SCHEDULED_TASKS_BEGIN, SCHEDULED_TASKS_END
+        );
+        let (tasks, error) = extract_scheduled_tasks(&output);
+        assert!(error.is_none());
+        match &tasks[0] {
+            ScheduledTaskRequest::SendEmail(task) => assert_eq!(
+                task.approval
+                    .as_ref()
+                    .map(|approval| approval.summary.as_str()),
+                Some("Send the NDA to legal?")
+            ),
+        }
+    }
+
     #[test]
     fn extract_scheduler_actions_prefers_latest_valid_block() {
         let output = format!(
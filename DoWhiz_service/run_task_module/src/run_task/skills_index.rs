@@ -0,0 +1,149 @@
+//! `skills_index.md`: the manifest of the skills in a workspace.
+//!
+//! Skills live under `.agents/skills/<dir>/SKILL.md`. Rather than reading them
+//! all up front, the runner gets this index (name, description and entry path
+//! of each skill) and opens a skill's entry file only when the task needs it.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Workspace-root file holding the skills manifest.
+pub const SKILLS_INDEX_FILE: &str = "skills_index.md";
+
+/// Descriptions are clipped so the index stays small; the full text is in
+/// the skill's entry file.
+const MAX_DESCRIPTION_CHARS: usize = 240;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) struct SkillEntry {
+    pub(super) name: String,
+    pub(super) description: String,
+    /// Path of the skill's `SKILL.md`, relative to the workspace root.
+    pub(super) entry_path: String,
+}
+
+/// The skills under `.agents/skills/`, sorted by name. A skill directory
+/// without a `SKILL.md` is skipped.
+pub(super) fn scan_skills(workspace_dir: &Path) -> Vec<SkillEntry> {
+    let Ok(entries) = fs::read_dir(workspace_dir.join(".agents").join("skills")) else {
+        return Vec::new();
+    };
+    let mut skills: Vec<SkillEntry> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let dir_name = entry.file_name().to_string_lossy().into_owned();
+            let content = fs::read_to_string(entry.path().join("SKILL.md")).ok()?;
+            let (name, description) = parse_skill_frontmatter(&content);
+            Some(SkillEntry {
+                name: name.unwrap_or_else(|| dir_name.clone()),
+                description: description
+                    .map(|text| text.chars().take(MAX_DESCRIPTION_CHARS).collect())
+                    .unwrap_or_default(),
+                entry_path: format!(".agents/skills/{dir_name}/SKILL.md"),
+            })
+        })
+        .collect();
+    skills.sort();
+    skills
+}
+
+/// One `- name: description (entry)` line per skill.
+pub(super) fn render_entries(skills: &[SkillEntry]) -> String {
+    skills
+        .iter()
+        .map(|skill| {
+            if skill.description.is_empty() {
+                format!("- {} (`{}`)\n", skill.name, skill.entry_path)
+            } else {
+                format!(
+                    "- {}: {} (`{}`)\n",
+                    skill.name, skill.description, skill.entry_path
+                )
+            }
+        })
+        .collect()
+}
+
+/// Rewrites [`SKILLS_INDEX_FILE`] from the skills now in the workspace and
+/// returns how many it lists. A workspace without skills gets no index.
+pub fn write_skills_index(workspace_dir: &Path) -> io::Result<usize> {
+    let skills = scan_skills(workspace_dir);
+    let path = workspace_dir.join(SKILLS_INDEX_FILE);
+    if skills.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(0);
+    }
+    fs::write(
+        path,
+        format!(
+            "# Skills index\n\nRead a skill's entry file only when the task needs that skill.\n\n{}",
+            render_entries(&skills)
+        ),
+    )?;
+    Ok(skills.len())
+}
+
+/// `name` and `description` from a SKILL.md YAML front matter block.
+fn parse_skill_frontmatter(content: &str) -> (Option<String>, Option<String>) {
+    let mut lines = content.lines();
+    if lines.next().map(str::trim) != Some("---") {
+        return (None, None);
+    }
+    let mut name = None;
+    let mut description = None;
+    for line in lines.take_while(|line| line.trim() != "---") {
+        let unquote = |value: &str| value.trim().trim_matches('"').to_string();
+        if let Some(value) = line.strip_prefix("name:") {
+            name = Some(unquote(value)).filter(|value| !value.is_empty());
+        } else if let Some(value) = line.strip_prefix("description:") {
+            description = Some(unquote(value)).filter(|value| !value.is_empty());
+        }
+    }
+    (name, description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_skill(workspace: &Path, dir: &str, content: &str) {
+        let skill = workspace.join(".agents").join("skills").join(dir);
+        fs::create_dir_all(&skill).expect("skill dir");
+        fs::write(skill.join("SKILL.md"), content).expect("SKILL.md");
+    }
+
+    #[test]
+    fn write_skills_index_lists_name_description_and_entry() {
+        let temp = TempDir::new().expect("tempdir");
+        write_skill(
+            temp.path(),
+            "pdf",
+            "---\nname: pdf\ndescription: \"Read and write PDF files.\"\n---\n\n# PDF\n",
+        );
+        write_skill(temp.path(), "notes", "# Notes without front matter\n");
+        fs::create_dir_all(temp.path().join(".agents/skills/empty")).expect("empty dir");
+
+        let count = write_skills_index(temp.path()).expect("index");
+
+        assert_eq!(count, 2);
+        let index = fs::read_to_string(temp.path().join(SKILLS_INDEX_FILE)).expect("read");
+        assert!(index.starts_with("# Skills index\n"));
+        assert!(index.contains("- notes (`.agents/skills/notes/SKILL.md`)\n"));
+        assert!(
+            index.contains("- pdf: Read and write PDF files. (`.agents/skills/pdf/SKILL.md`)\n")
+        );
+    }
+
+    #[test]
+    fn write_skills_index_removes_stale_index_without_skills() {
+        let temp = TempDir::new().expect("tempdir");
+        fs::write(temp.path().join(SKILLS_INDEX_FILE), "stale").expect("stale");
+
+        assert_eq!(write_skills_index(temp.path()).expect("index"), 0);
+        assert!(!temp.path().join(SKILLS_INDEX_FILE).exists());
+    }
+}
@@ -1,13 +1,19 @@
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
-/// Token usage from Codex JSON output
-#[derive(Debug, Clone, Default, Deserialize)]
+use super::docker::SandboxProfile;
+
+/// Token usage reported by the runner. `input_tokens` includes the cached
+/// ones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TokenUsage {
     pub input_tokens: u64,
     #[serde(default)]
     pub cached_input_tokens: u64,
     pub output_tokens: u64,
+    /// API cost in USD, when the runner reports one (Claude does).
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
 }
 
 /// User's linked channel identifiers for cross-channel routing
@@ -36,6 +42,138 @@ pub struct UserIdentities {
     pub allowed_user_ids: Vec<String>,
 }
 
+/// User preferences that shape the reply
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplyPreferences {
+    /// Channel the user prefers to be contacted on: "email", "slack", etc.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_channel: Option<String>,
+    /// Language replies are written in, as the user named it (e.g. "French")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// The employee's formatting for the run's channel
+    #[serde(default, skip_serializing_if = "ReplyFormatting::is_empty")]
+    pub formatting: ReplyFormatting,
+}
+
+/// How an employee's replies look on one channel, from
+/// `[employees.formatting.<channel>]` in employee.toml. Unset fields leave
+/// the reply as the runner wrote it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReplyFormatting {
+    /// `false` keeps emoji out of replies; `true` allows them sparingly.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<bool>,
+    /// Added below each reply on chat channels; asked for in the prompt on
+    /// email.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Longest reply in characters, signature included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_chars: Option<usize>,
+    /// Style guidance for the prompt, e.g. "formal, no slang".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tone: Option<String>,
+}
+
+/// Channels whose replies are shaped by [`ReplyFormatting::apply`] when
+/// sent; elsewhere the formatting only reaches the prompt.
+pub const FORMATTED_REPLY_CHANNELS: [&str; 7] = [
+    "slack",
+    "discord",
+    "bluebubbles",
+    "telegram",
+    "whatsapp",
+    "wechat",
+    "sms",
+];
+
+impl ReplyFormatting {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// `text` without emoji when they are off, cut to `max_chars` and
+    /// followed by the signature.
+    pub fn apply(&self, text: &str) -> String {
+        let mut body = if self.emoji == Some(false) {
+            strip_emoji(text)
+        } else {
+            text.to_string()
+        };
+        let signature = self
+            .signature
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        if let Some(max_chars) = self.max_chars {
+            let reserved = signature.map_or(0, |value| value.chars().count() + 2);
+            body = truncate_reply(&body, max_chars.saturating_sub(reserved));
+        }
+        match signature {
+            Some(signature) => format!("{}\n\n{}", body.trim_end(), signature),
+            None => body,
+        }
+    }
+}
+
+fn is_emoji(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B50..=0x2B55 | 0xFE0F | 0x200D | 0xE0020..=0xE007F
+    )
+}
+
+/// Drop emoji along with the space they leave doubled.
+fn strip_emoji(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut dropped = false;
+    for ch in text.chars() {
+        if is_emoji(ch) {
+            dropped = true;
+            continue;
+        }
+        if dropped {
+            if ch == ' ' && (stripped.is_empty() || stripped.ends_with([' ', '\n'])) {
+                continue;
+            }
+            if ch == '\n' {
+                stripped.truncate(stripped.trim_end_matches(' ').len());
+            }
+            dropped = false;
+        }
+        stripped.push(ch);
+    }
+    if dropped {
+        stripped.truncate(stripped.trim_end_matches(' ').len());
+    }
+    stripped
+}
+
+/// `text` cut to `max_chars` characters, at a word break when one is near,
+/// with an ellipsis marking the cut.
+fn truncate_reply(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    if max_chars == 0 {
+        return String::new();
+    }
+    let keep = max_chars - 1;
+    let end = text
+        .char_indices()
+        .nth(keep)
+        .map_or(text.len(), |(index, _)| index);
+    let mut cut = &text[..end];
+    if let Some(space) = cut.rfind(char::is_whitespace) {
+        if cut[..space].chars().count() >= keep * 4 / 5 {
+            cut = &cut[..space];
+        }
+    }
+    format!("{}…", cut.trim_end())
+}
+
 #[derive(Debug, Clone)]
 pub struct RunTaskParams {
     pub workspace_dir: PathBuf,
@@ -55,6 +193,12 @@ pub struct RunTaskParams {
     pub has_unified_account: bool,
     /// User's linked channel identifiers for cross-channel routing
     pub user_identities: UserIdentities,
+    /// User's reply language and preferred contact channel
+    pub reply_preferences: ReplyPreferences,
+    /// Correlation ID of the inbound message, exported to the runner as `DOWHIZ_TRACE_ID`
+    pub trace_id: Option<String>,
+    /// Employee's runner container limits; runs in a sandbox when set
+    pub sandbox: Option<SandboxProfile>,
 }
 
 #[derive(Debug, Clone)]
@@ -70,17 +214,22 @@ pub(super) struct RunTaskRequest<'a> {
     pub(super) google_access_token: Option<&'a str>,
     pub(super) has_unified_account: bool,
     pub(super) user_identities: &'a UserIdentities,
+    pub(super) reply_preferences: &'a ReplyPreferences,
+    pub(super) trace_id: Option<&'a str>,
+    pub(super) sandbox: Option<&'a SandboxProfile>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ScheduledTaskRequest {
     SendEmail(ScheduledSendEmailTask),
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "action", rename_all = "snake_case")]
 pub enum SchedulerActionRequest {
+    /// List the tasks this thread has scheduled, as of after the other actions.
+    ListTasks,
     Cancel {
         task_ids: Vec<String>,
     },
@@ -96,17 +245,208 @@ pub enum SchedulerActionRequest {
         codex_disabled: Option<bool>,
         #[serde(default)]
         reply_to: Vec<String>,
+        #[serde(default)]
+        approval: Option<ApprovalRequest>,
+        /// Shown in task listings, e.g. "Weekly sales digest every Monday".
+        #[serde(default)]
+        description: Option<String>,
+        /// Last time a recurring task may run (RFC 3339).
+        #[serde(default)]
+        until: Option<String>,
+        /// Number of runs after which a recurring task stops.
+        #[serde(default)]
+        count: Option<u32>,
+    },
+    /// Hand the conversation to another employee, who takes it up by email.
+    Handoff {
+        employee_id: String,
+        /// What the other employee should do, with the context they need.
+        summary: String,
+    },
+    /// Ask another employee for something; their answer comes back to this
+    /// thread as a new message.
+    Delegate {
+        employee_id: String,
+        /// The request, with the context the other employee needs.
+        request: String,
+    },
+    /// Replace the text of a Slack or Discord message this thread sent,
+    /// e.g. a "working on it" note; IDs are in `thread_state.json`.
+    EditMessage {
+        message_id: String,
+        text: String,
+    },
+    /// Delete a Slack or Discord message this thread sent.
+    DeleteMessage {
+        message_id: String,
+    },
+    /// Change cells of a Google Sheet shared with the employee, e.g. add an
+    /// expense to a tracker. Edits are applied in order.
+    UpdateSheet {
+        /// Spreadsheet ID or its `docs.google.com/spreadsheets/d/...` URL.
+        spreadsheet_id: String,
+        edits: Vec<SheetEditRequest>,
+    },
+    /// Open a Jira issue, e.g. for a bug reported by email or Slack.
+    CreateJiraIssue {
+        /// Project key, e.g. `"OPS"`.
+        project: String,
+        summary: String,
+        #[serde(default)]
+        description: Option<String>,
+        /// Issue type name; defaults to `Task`.
+        #[serde(default)]
+        issue_type: Option<String>,
+        #[serde(default)]
+        labels: Vec<String>,
+    },
+    /// Change a Jira issue: edit its fields, move it to another status and/or
+    /// comment on it, in that order.
+    UpdateJiraIssue {
+        /// Issue key, e.g. `"OPS-42"`.
+        issue_key: String,
+        #[serde(default)]
+        summary: Option<String>,
+        #[serde(default)]
+        description: Option<String>,
+        /// Replaces the issue's labels.
+        #[serde(default)]
+        labels: Option<Vec<String>>,
+        /// Transition or target status name, e.g. `"In Progress"`.
+        #[serde(default)]
+        transition: Option<String>,
+        #[serde(default)]
+        comment: Option<String>,
     },
+    /// File a Linear issue, e.g. for "file a bug about X"; its link is added
+    /// to the reply.
+    CreateLinearIssue {
+        /// Team key or name, e.g. `"ENG"`.
+        team: String,
+        title: String,
+        /// Markdown body.
+        #[serde(default)]
+        description: Option<String>,
+        /// Names of existing labels, e.g. `["Bug"]`.
+        #[serde(default)]
+        labels: Vec<String>,
+        /// 0 = none, 1 = urgent, 2 = high, 3 = medium, 4 = low.
+        #[serde(default)]
+        priority: Option<u8>,
+    },
+    /// Create a video meeting and send calendar invites; its join link is
+    /// added to the reply.
+    CreateMeeting {
+        title: String,
+        /// Start time (RFC 3339).
+        start: String,
+        #[serde(default = "default_meeting_minutes")]
+        duration_minutes: u32,
+        /// Emails invited to the calendar event.
+        #[serde(default)]
+        attendees: Vec<String>,
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        provider: MeetingProvider,
+    },
+    /// Check a cron expression and list its next runs in a time zone, e.g.
+    /// to confirm "next run: Tue 9:00 AM PST" to the user.
+    PreviewSchedule {
+        /// Six-field cron expression (seconds first), evaluated in UTC.
+        expression: String,
+        /// IANA time zone the runs are shown in, e.g. `"America/Los_Angeles"`;
+        /// UTC when unset.
+        #[serde(default)]
+        timezone: Option<String>,
+        #[serde(default = "default_preview_count")]
+        count: usize,
+        /// Add the next run to the end of the reply.
+        #[serde(default)]
+        add_to_reply: bool,
+    },
+}
+
+/// Where a `create_meeting` action hosts the call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeetingProvider {
+    #[default]
+    GoogleMeet,
+    Zoom,
+}
+
+fn default_meeting_minutes() -> u32 {
+    30
 }
 
-#[derive(Debug, Clone, Deserialize)]
+fn default_preview_count() -> usize {
+    3
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SheetEditRequest {
+    /// Add rows after the table in `range`, e.g. `"Expenses!A:D"`.
+    Append {
+        range: String,
+        values: Vec<Vec<serde_json::Value>>,
+    },
+    /// Overwrite the cells of `range`, e.g. `"Summary!B2"`.
+    Update {
+        range: String,
+        values: Vec<Vec<serde_json::Value>>,
+    },
+}
+
+/// Asks the scheduler to hold the task it is attached to until a human
+/// approves it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    /// Question shown to the approver, e.g. "Send this contract to legal?"
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ScheduleRequest {
-    Cron { expression: String },
-    OneShot { run_at: String },
+    Cron {
+        expression: String,
+    },
+    OneShot {
+        run_at: String,
+    },
+    /// RRULE-style recurrence; the scheduler turns it into a cron expression.
+    Recurring {
+        frequency: RecurrenceFrequency,
+        #[serde(default = "default_recurrence_interval")]
+        interval: u32,
+        /// Weekday names or RRULE codes, e.g. `["mon", "we"]`.
+        #[serde(default)]
+        by_weekday: Vec<String>,
+        /// Days of the month for monthly recurrences.
+        #[serde(default)]
+        by_month_day: Vec<u32>,
+        /// UTC time of day as `HH:MM`; defaults to the time it is created.
+        #[serde(default)]
+        at: Option<String>,
+    },
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecurrenceFrequency {
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+fn default_recurrence_interval() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScheduledSendEmailTask {
     pub subject: String,
     pub html_path: String,
@@ -122,9 +462,11 @@ pub struct ScheduledSendEmailTask {
     pub delay_minutes: Option<i64>,
     pub delay_seconds: Option<i64>,
     pub run_at: Option<String>,
+    #[serde(default)]
+    pub approval: Option<ApprovalRequest>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunTaskOutput {
     pub reply_html_path: PathBuf,
     pub reply_attachments_dir: PathBuf,
@@ -134,4 +476,31 @@ pub struct RunTaskOutput {
     pub scheduler_actions: Vec<SchedulerActionRequest>,
     pub scheduler_actions_error: Option<String>,
     pub token_usage: Option<TokenUsage>,
+    /// Problems in the run's output that were repaired or dropped.
+    pub output_issues: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reply_formatting_strips_emoji_cuts_and_signs() {
+        let formatting = ReplyFormatting {
+            emoji: Some(false),
+            signature: Some("— Oliver".to_string()),
+            max_chars: Some(40),
+            tone: None,
+        };
+        assert_eq!(
+            formatting.apply("All done 🎉 see you 👋\n✅ Shipped"),
+            "All done see you\nShipped\n\n— Oliver"
+        );
+        let reply = formatting.apply("The report is ready and waiting in the shared drive folder");
+        assert_eq!(reply, "The report is ready and…\n\n— Oliver");
+        assert!(reply.chars().count() <= 40);
+
+        assert_eq!(ReplyFormatting::default().apply("Hi 👋"), "Hi 👋");
+        assert!(ReplyFormatting::default().is_empty());
+    }
 }
@@ -1,10 +1,12 @@
-use std::io::Read;
+use std::io::{Read, Write};
 use std::process::{Command, ExitStatus, Output, Stdio};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use super::errors::RunTaskError;
+use super::exec_log::{self, LogStream};
+use super::processes;
 
 const DEFAULT_SCHEDULER_TASK_TIMEOUT_SECS: u64 = 600;
 const DEFAULT_RUN_TASK_TIMEOUT_SECS: u64 = 36000;
@@ -48,11 +50,13 @@ pub(super) fn run_task_timeout() -> Duration {
     Duration::from_secs(timeout_secs)
 }
 
-/// Spawns a thread to continuously drain a pipe into a buffer.
+/// Spawns a thread to continuously drain a pipe into a buffer, and into the
+/// execution log when given.
 /// This prevents the pipe buffer from filling up and blocking the child process.
 fn spawn_pipe_drainer<R: Read + Send + 'static>(
     pipe: R,
     buffer: Arc<Mutex<Vec<u8>>>,
+    mut log: Option<LogStream>,
 ) -> thread::JoinHandle<()> {
     thread::spawn(move || {
         let mut pipe = pipe;
@@ -64,6 +68,9 @@ fn spawn_pipe_drainer<R: Read + Send + 'static>(
                     if let Ok(mut buf) = buffer.lock() {
                         buf.extend_from_slice(&chunk[..n]);
                     }
+                    if let Some(log) = log.as_mut() {
+                        log.write(&chunk[..n]);
+                    }
                 }
                 Err(_) => break,
             }
@@ -72,27 +79,63 @@ fn spawn_pipe_drainer<R: Read + Send + 'static>(
 }
 
 pub(super) fn run_command_with_timeout(
+    cmd: Command,
+    timeout: Duration,
+    label: &'static str,
+) -> Result<Output, RunTaskError> {
+    run_command_with_input(cmd, None, timeout, label)
+}
+
+/// Like [`run_command_with_timeout`], writing `input` to the child's stdin
+/// first when given.
+///
+/// Inside a [`processes::RunScope`] the child leads its own process group,
+/// registered under the run so [`processes::terminate_run`] can stop it.
+/// Inside an [`exec_log::ExecLogScope`] its output is also appended to the
+/// execution's log.
+pub(super) fn run_command_with_input(
     mut cmd: Command,
+    input: Option<&[u8]>,
     timeout: Duration,
     label: &'static str,
 ) -> Result<Output, RunTaskError> {
+    if input.is_some() {
+        cmd.stdin(Stdio::piped());
+    }
     cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let run_id = processes::current_run();
+    if let Some(run_id) = &run_id {
+        processes::ensure_not_terminated(run_id, label)?;
+        processes::isolate(&mut cmd);
+    }
+    let log = exec_log::current_log();
     let mut child = cmd.spawn().map_err(RunTaskError::Io)?;
+    if let Some(log) = &log {
+        log.write_line(format!("--- {} started", label).as_bytes());
+    }
+    let registration = run_id
+        .as_deref()
+        .map(|run_id| processes::register(run_id, child.id()));
     let start = Instant::now();
 
+    if let (Some(payload), Some(mut stdin)) = (input, child.stdin.take()) {
+        // Dropping stdin afterwards closes it so the child sees EOF.
+        let _ = stdin.write_all(payload);
+    }
+
     // Take ownership of stdout/stderr and spawn drainer threads
     // This prevents pipe buffer from filling up and blocking the child
     let stdout_buf = Arc::new(Mutex::new(Vec::new()));
     let stderr_buf = Arc::new(Mutex::new(Vec::new()));
 
-    let stdout_handle = child
-        .stdout
-        .take()
-        .map(|pipe| spawn_pipe_drainer(pipe, Arc::clone(&stdout_buf)));
-    let stderr_handle = child
-        .stderr
-        .take()
-        .map(|pipe| spawn_pipe_drainer(pipe, Arc::clone(&stderr_buf)));
+    let stdout_handle = child.stdout.take().map(|pipe| {
+        let stream = log.clone().map(|log| LogStream::new(log, "stdout| "));
+        spawn_pipe_drainer(pipe, Arc::clone(&stdout_buf), stream)
+    });
+    let stderr_handle = child.stderr.take().map(|pipe| {
+        let stream = log.clone().map(|log| LogStream::new(log, "stderr| "));
+        spawn_pipe_drainer(pipe, Arc::clone(&stderr_buf), stream)
+    });
 
     // Poll for exit or timeout
     let status: ExitStatus;
@@ -105,6 +148,9 @@ pub(super) fn run_command_with_timeout(
         }
 
         if start.elapsed() >= timeout {
+            if registration.is_some() {
+                processes::signal_group(child.id(), "KILL");
+            }
             let _ = child.kill();
             status = child.wait().map_err(RunTaskError::Io)?;
             timed_out = true;
@@ -113,6 +159,7 @@ pub(super) fn run_command_with_timeout(
 
         thread::sleep(Duration::from_millis(200));
     }
+    drop(registration);
 
     // Wait for drainer threads to finish
     if let Some(h) = stdout_handle {
@@ -132,6 +179,19 @@ pub(super) fn run_command_with_timeout(
         Err(arc) => arc.lock().map(|g| g.clone()).unwrap_or_default(),
     };
 
+    if let Some(log) = &log {
+        let ending = match status.code() {
+            _ if timed_out => format!("--- {} timed out after {}s", label, timeout.as_secs()),
+            Some(code) => format!("--- {} exited with status {}", label, code),
+            None => format!("--- {} was stopped by a signal", label),
+        };
+        log.write_line(ending.as_bytes());
+    }
+
+    if let Some(run_id) = &run_id {
+        processes::ensure_not_terminated(run_id, label)?;
+    }
+
     if timed_out {
         let mut combined = String::new();
         combined.push_str(&String::from_utf8_lossy(&stdout));
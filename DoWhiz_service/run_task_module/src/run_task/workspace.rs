@@ -2,7 +2,7 @@ use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use super::constants::DOCKER_WORKSPACE_DIR;
+use super::constants::{DOCKER_WORKSPACE_DIR, OUTPUT_MANIFEST_FILE};
 use super::env::env_enabled;
 use super::errors::RunTaskError;
 use super::types::RunTaskRequest;
@@ -115,7 +115,7 @@ pub(super) fn prepare_workspace(
     // Notion uses .notion_api_replied marker file (agent posts via API directly)
     // Email and GoogleDocs use HTML reply_email_draft.html
     let (reply_path, reply_attachments_dir) = match request.channel.to_lowercase().as_str() {
-        "slack" | "discord" | "telegram" | "sms" | "bluebubbles" => (
+        "slack" | "discord" | "telegram" | "sms" | "bluebubbles" | "internal" | "jira" => (
             request.workspace_dir.join("reply_message.txt"),
             request.workspace_dir.join("reply_attachments"),
         ),
@@ -130,9 +130,56 @@ pub(super) fn prepare_workspace(
     };
     ensure_dir_exists(&reply_attachments_dir, "reply_attachments_dir")?;
 
+    // A manifest left by an earlier failed run must not apply to this one.
+    let stale_manifest = request.workspace_dir.join(OUTPUT_MANIFEST_FILE);
+    if stale_manifest.exists() {
+        fs::remove_file(stale_manifest)?;
+    }
+
     Ok((reply_path, reply_attachments_dir))
 }
 
+/// Check if cross-channel routing was requested and return the correct expected reply path.
+/// If reply_routing.json specifies a different target channel, compute the expected file for that target.
+pub(super) fn resolve_expected_reply_path(workspace_dir: &Path, default_path: PathBuf) -> PathBuf {
+    let routing_file = workspace_dir.join("reply_routing.json");
+    if !routing_file.exists() {
+        return default_path;
+    }
+
+    // Try to read and parse reply_routing.json
+    let routing_content = match fs::read_to_string(&routing_file) {
+        Ok(content) => content,
+        Err(_) => return default_path,
+    };
+
+    // Parse the JSON to get target channel
+    let routing: serde_json::Value = match serde_json::from_str(&routing_content) {
+        Ok(v) => v,
+        Err(_) => return default_path,
+    };
+
+    let target_channel = match routing.get("channel").and_then(|c| c.as_str()) {
+        Some(ch) => ch.to_lowercase(),
+        None => return default_path,
+    };
+
+    // Determine expected reply path based on TARGET channel
+    match target_channel.as_str() {
+        "email" | "googledocs" | "googlesheets" | "googleslides" => {
+            workspace_dir.join("reply_email_draft.html")
+        }
+        "slack" | "discord" | "telegram" | "sms" | "whatsapp" | "bluebubbles" => {
+            workspace_dir.join("reply_message.txt")
+        }
+        "notion" => {
+            // Notion agent posts directly via API and creates .notion_api_replied marker
+            workspace_dir.join(".notion_api_replied")
+        }
+        _ => default_path,
+    }
+}
+
 pub(super) fn write_placeholder_reply(path: &Path) -> Result<(), RunTaskError> {
     let placeholder = "<html><body><p>Codex disabled. Received your email.</p></body></html>";
     fs::write(path, placeholder)?;
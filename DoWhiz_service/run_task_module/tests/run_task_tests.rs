@@ -660,6 +660,9 @@ fn run_task_rejects_absolute_input_dir() {
         google_access_token: std::env::var("GOOGLE_ACCESS_TOKEN").ok(),
         has_unified_account: true,
         user_identities: Default::default(),
+        reply_preferences: Default::default(),
+        trace_id: None,
+        sandbox: None,
     };
 
     let err = run_task(&request).unwrap_err();
@@ -438,5 +438,8 @@ pub fn build_params(workspace: &Path) -> RunTaskParams {
         google_access_token: std::env::var("GOOGLE_ACCESS_TOKEN").ok(),
         has_unified_account: true, // Default to true for tests
         user_identities: Default::default(),
+        reply_preferences: Default::default(),
+        trace_id: None,
+        sandbox: None,
     }
 }
@@ -1,4 +1,5 @@
 use chrono::{DateTime, Utc};
+use postgres::types::ToSql;
 use postgres_native_tls::MakeTlsConnector;
 use r2d2::{Pool, PooledConnection};
 use r2d2_postgres::PostgresConnectionManager;
@@ -10,6 +11,7 @@ use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::env_alias::var_with_scale_oliver;
+use crate::task_costs::{CostDimension, TaskCostQuery, TaskCostRecord, TaskCostTotal};
 
 type PgPool = Pool<PostgresConnectionManager<MakeTlsConnector>>;
 type PgConn = PooledConnection<PostgresConnectionManager<MakeTlsConnector>>;
@@ -143,6 +145,19 @@ pub struct RecommendationPreferenceRecord {
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone)]
+pub struct DigestPreferenceRecord {
+    pub account_id: Uuid,
+    pub enabled: bool,
+    /// `email` or `slack`.
+    pub channel: String,
+    /// Linked identifier the digest is sent to.
+    pub identifier: String,
+    /// Hour of the day (UTC) the digest goes out.
+    pub hour_utc: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone)]
 pub struct RecommendationFeedbackRecord {
     pub id: Uuid,
@@ -400,6 +415,15 @@ impl AccountStore {
                 updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
             );
 
+            CREATE TABLE IF NOT EXISTS account_digest_preferences (
+                account_id UUID PRIMARY KEY REFERENCES accounts(id) ON DELETE CASCADE,
+                enabled BOOLEAN NOT NULL DEFAULT FALSE,
+                channel TEXT NOT NULL,
+                identifier TEXT NOT NULL,
+                hour_utc INTEGER NOT NULL DEFAULT 13,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            );
+
             CREATE TABLE IF NOT EXISTS account_recommendation_feedback (
                 id UUID PRIMARY KEY,
                 account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
@@ -414,6 +438,32 @@ impl AccountStore {
                 ON account_recommendation_feedback (account_id, created_at DESC);
             CREATE INDEX IF NOT EXISTS account_recommendation_feedback_key_state_time_idx
                 ON account_recommendation_feedback (account_id, recommendation_key, state_signature, created_at DESC);
+
+            CREATE TABLE IF NOT EXISTS task_costs (
+                id UUID PRIMARY KEY,
+                account_id UUID NULL,
+                user_id TEXT NULL,
+                employee_id TEXT NULL,
+                runner TEXT NOT NULL,
+                model TEXT NOT NULL,
+                channel TEXT NOT NULL,
+                trace_id TEXT NULL,
+                input_tokens BIGINT NOT NULL,
+                cached_input_tokens BIGINT NOT NULL,
+                output_tokens BIGINT NOT NULL,
+                cost_usd DOUBLE PRECISION NULL,
+                cost_source TEXT NULL,
+                recorded_at TIMESTAMPTZ NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS task_costs_time_idx
+                ON task_costs (recorded_at);
+            CREATE INDEX IF NOT EXISTS task_costs_account_time_idx
+                ON task_costs (account_id, recorded_at);
+            CREATE INDEX IF NOT EXISTS task_costs_user_time_idx
+                ON task_costs (user_id, recorded_at);
+            CREATE INDEX IF NOT EXISTS task_costs_employee_time_idx
+                ON task_costs (employee_id, recorded_at);
             ",
         )?;
         Ok(())
@@ -637,6 +687,69 @@ impl AccountStore {
         Ok(())
     }
 
+    /// Store one run's usage; see [`crate::task_costs`].
+    pub fn record_task_cost(&self, record: &TaskCostRecord) -> Result<(), AccountStoreError> {
+        let mut conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO task_costs (
+                id, account_id, user_id, employee_id, runner, model, channel, trace_id,
+                input_tokens, cached_input_tokens, output_tokens, cost_usd, cost_source,
+                recorded_at
+             ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)",
+            &[
+                &Uuid::new_v4(),
+                &record.account_id,
+                &record.user_id,
+                &record.employee_id,
+                &record.runner,
+                &record.model,
+                &record.channel,
+                &record.trace_id,
+                &record.input_tokens,
+                &record.cached_input_tokens,
+                &record.output_tokens,
+                &record.cost_usd,
+                &record.cost_source,
+                &record.recorded_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Usage totals matching `query`, one row per group.
+    pub fn task_cost_totals(
+        &self,
+        query: &TaskCostQuery,
+        dimensions: &[CostDimension],
+    ) -> Result<Vec<TaskCostTotal>, AccountStoreError> {
+        let (sql, params) = query.to_sql(dimensions);
+        let params: Vec<&(dyn ToSql + Sync)> = params
+            .iter()
+            .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+            .collect();
+        let mut conn = self.conn()?;
+        let rows = conn.query(sql.as_str(), &params)?;
+        let offset = dimensions.len();
+        Ok(rows
+            .iter()
+            .map(|row| TaskCostTotal {
+                group: dimensions
+                    .iter()
+                    .enumerate()
+                    .map(|(index, dimension)| {
+                        (dimension.as_str().to_string(), row.get::<_, String>(index))
+                    })
+                    .collect(),
+                tasks: row.get(offset),
+                input_tokens: row.get(offset + 1),
+                cached_input_tokens: row.get(offset + 2),
+                output_tokens: row.get(offset + 3),
+                cost_usd: row.get(offset + 4),
+                unpriced_tasks: row.get(offset + 5),
+            })
+            .collect())
+    }
+
     // =========================================================================
     // Billing methods
     // =========================================================================
@@ -1001,6 +1114,55 @@ impl AccountStore {
         })
     }
 
+    pub fn get_digest_preference(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<DigestPreferenceRecord>, AccountStoreError> {
+        let mut conn = self.conn()?;
+        let row = conn.query_opt(
+            "SELECT account_id, enabled, channel, identifier, hour_utc, updated_at
+             FROM account_digest_preferences
+             WHERE account_id = $1",
+            &[&account_id],
+        )?;
+        Ok(row.as_ref().map(digest_preference_from_row))
+    }
+
+    /// All stored digest preferences, enabled or not, so disabled ones can be
+    /// taken off the schedule too.
+    pub fn list_digest_preferences(
+        &self,
+    ) -> Result<Vec<DigestPreferenceRecord>, AccountStoreError> {
+        let mut conn = self.conn()?;
+        let rows = conn.query(
+            "SELECT account_id, enabled, channel, identifier, hour_utc, updated_at
+             FROM account_digest_preferences",
+            &[],
+        )?;
+        Ok(rows.iter().map(digest_preference_from_row).collect())
+    }
+
+    pub fn upsert_digest_preference(
+        &self,
+        account_id: Uuid,
+        enabled: bool,
+        channel: &str,
+        identifier: &str,
+        hour_utc: i32,
+    ) -> Result<DigestPreferenceRecord, AccountStoreError> {
+        let mut conn = self.conn()?;
+        let row = conn.query_one(
+            "INSERT INTO account_digest_preferences (account_id, enabled, channel, identifier, hour_utc, updated_at)
+             VALUES ($1, $2, $3, $4, $5, NOW())
+             ON CONFLICT (account_id)
+             DO UPDATE SET enabled = EXCLUDED.enabled, channel = EXCLUDED.channel,
+                 identifier = EXCLUDED.identifier, hour_utc = EXCLUDED.hour_utc, updated_at = NOW()
+             RETURNING account_id, enabled, channel, identifier, hour_utc, updated_at",
+            &[&account_id, &enabled, &channel, &identifier, &hour_utc],
+        )?;
+        Ok(digest_preference_from_row(&row))
+    }
+
     pub fn record_recommendation_feedback(
         &self,
         account_id: Uuid,
@@ -1177,6 +1339,17 @@ impl AccountStore {
     }
 }
 
+fn digest_preference_from_row(row: &postgres::Row) -> DigestPreferenceRecord {
+    DigestPreferenceRecord {
+        account_id: row.get(0),
+        enabled: row.get(1),
+        channel: row.get(2),
+        identifier: row.get(3),
+        hour_utc: row.get(4),
+        updated_at: row.get(5),
+    }
+}
+
 impl Drop for AccountStore {
     fn drop(&mut self) {
         let primary_pool = self.primary_pool.take();
@@ -1228,8 +1401,10 @@ pub fn channel_to_identifier_type(channel: &crate::channel::Channel) -> &'static
         Channel::Discord => "discord",
         Channel::BlueBubbles => "phone",
         Channel::WeChat => "wechat",
+        Channel::Jira => "jira",
         Channel::GoogleDocs | Channel::GoogleSheets | Channel::GoogleSlides => "email",
         Channel::Notion => "email", // Notion accounts are linked by email
+        Channel::Internal => "employee",
     }
 }
 
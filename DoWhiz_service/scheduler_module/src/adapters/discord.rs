@@ -8,9 +8,10 @@ use std::collections::HashSet;
 use std::env;
 
 use crate::channel::{
-    AdapterError, Attachment, Channel, ChannelMetadata, InboundMessage, OutboundAdapter,
-    OutboundMessage, SendResult,
+    AdapterError, Attachment, Channel, ChannelMetadata, InboundMessage, MessageRef,
+    OutboundAdapter, OutboundMessage, SendResult,
 };
+use crate::message_link_store::ReactionControl;
 
 /// Adapter for converting Discord Gateway events to normalized messages.
 ///
@@ -130,6 +131,68 @@ impl DiscordInboundAdapter {
     }
 }
 
+impl DiscordInboundAdapter {
+    /// Convert a control reaction (⏰/❌/✅) on one of our messages into an inbound message.
+    ///
+    /// `message_id` is the reacted-to message and `metadata.reaction_control` the
+    /// requested control. Reactions by our bots, on other users' messages, or with
+    /// other emoji are rejected.
+    pub fn from_serenity_reaction(
+        &self,
+        reaction: &serenity::model::channel::Reaction,
+    ) -> Result<InboundMessage, AdapterError> {
+        use serenity::model::channel::ReactionType;
+
+        let ReactionType::Unicode(emoji) = &reaction.emoji else {
+            return Err(AdapterError::ParseError(
+                "ignoring custom emoji reaction".to_string(),
+            ));
+        };
+        let control = ReactionControl::from_emoji(emoji)
+            .ok_or_else(|| AdapterError::ParseError("ignoring non-control reaction".to_string()))?;
+        let user_id = reaction
+            .user_id
+            .map(|id| id.get())
+            .ok_or(AdapterError::MissingField("user_id"))?;
+        if self.bot_user_ids.contains(&user_id) {
+            return Err(AdapterError::ParseError(
+                "ignoring bot reaction".to_string(),
+            ));
+        }
+        if let Some(author_id) = reaction.message_author_id {
+            if !self.bot_user_ids.is_empty() && !self.bot_user_ids.contains(&author_id.get()) {
+                return Err(AdapterError::ParseError(
+                    "ignoring reaction on a message not posted by the bot".to_string(),
+                ));
+            }
+        }
+
+        let channel_id = reaction.channel_id.get();
+        let message_id = reaction.message_id.get().to_string();
+        Ok(InboundMessage {
+            channel: Channel::Discord,
+            sender: user_id.to_string(),
+            sender_name: None,
+            recipient: channel_id.to_string(),
+            subject: None,
+            text_body: Some(control.as_str().to_string()),
+            html_body: None,
+            thread_id: message_id.clone(),
+            message_id: Some(message_id.clone()),
+            attachments: Vec::new(),
+            reply_to: vec![user_id.to_string(), channel_id.to_string()],
+            raw_payload: serde_json::to_vec(reaction).unwrap_or_default(),
+            metadata: ChannelMetadata {
+                discord_guild_id: reaction.guild_id.map(|id| id.get()),
+                discord_channel_id: Some(channel_id),
+                discord_message_id: Some(message_id),
+                reaction_control: Some(control.as_str().to_string()),
+                ..Default::default()
+            },
+        })
+    }
+}
+
 /// Adapter for sending messages via Discord REST API.
 #[derive(Debug, Clone)]
 pub struct DiscordOutboundAdapter {
@@ -180,6 +243,150 @@ impl DiscordOutboundAdapter {
             )))
         }
     }
+
+    fn message_url(&self, message: &MessageRef) -> String {
+        let api_base = env::var("DISCORD_API_BASE_URL")
+            .unwrap_or_else(|_| "https://discord.com/api/v10".to_string());
+        format!(
+            "{}/channels/{}/messages/{}",
+            api_base.trim_end_matches('/'),
+            message.channel_id,
+            message.message_id
+        )
+    }
+
+    fn send_message_request(
+        &self,
+        request: reqwest::blocking::RequestBuilder,
+        action: &str,
+    ) -> Result<(), AdapterError> {
+        let response = request
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .send()
+            .map_err(|e| AdapterError::SendError(format!("message {} failed: {}", action, e)))?;
+        if response.status().is_success() {
+            return Ok(());
+        }
+        let error_text = response
+            .text()
+            .unwrap_or_else(|_| "unknown error".to_string());
+        Err(AdapterError::SendError(format!(
+            "message {} failed: {}",
+            action, error_text
+        )))
+    }
+
+    /// Start a public thread from an existing channel message, returning the thread ID.
+    /// Discord API: POST /channels/{channel_id}/messages/{message_id}/threads
+    ///
+    /// A thread started from a message shares that message's ID, so a thread that
+    /// already exists for the message resolves to the same ID instead of failing.
+    pub fn start_thread_from_message(
+        &self,
+        channel_id: u64,
+        message_id: u64,
+        name: &str,
+    ) -> Result<u64, AdapterError> {
+        let api_base = env::var("DISCORD_API_BASE_URL")
+            .unwrap_or_else(|_| "https://discord.com/api/v10".to_string());
+        let url = format!(
+            "{}/channels/{}/messages/{}/threads",
+            api_base.trim_end_matches('/'),
+            channel_id,
+            message_id
+        );
+
+        let request = DiscordStartThreadRequest {
+            name: discord_thread_name(name),
+            auto_archive_duration: DISCORD_THREAD_AUTO_ARCHIVE_MINUTES,
+        };
+
+        let client = reqwest::blocking::Client::new();
+        let response = client
+            .post(&url)
+            .header("Authorization", format!("Bot {}", self.bot_token))
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .map_err(|e| AdapterError::SendError(format!("thread creation failed: {}", e)))?;
+
+        if response.status().is_success() {
+            let thread: DiscordThreadResponse = response.json().map_err(|e| {
+                AdapterError::SendError(format!("Failed to parse thread response: {}", e))
+            })?;
+            return thread
+                .id
+                .parse()
+                .map_err(|_| AdapterError::SendError("Invalid thread channel ID".to_string()));
+        }
+
+        let error_text = response
+            .text()
+            .unwrap_or_else(|_| "unknown error".to_string());
+        let already_exists = serde_json::from_str::<DiscordApiError>(&error_text)
+            .map(|err| err.code == DISCORD_THREAD_ALREADY_CREATED_CODE)
+            .unwrap_or(false);
+        if already_exists {
+            return Ok(message_id);
+        }
+        Err(AdapterError::SendError(format!(
+            "thread creation failed: {}",
+            error_text
+        )))
+    }
+}
+
+/// Discord API error code for "A thread has already been created for this message".
+const DISCORD_THREAD_ALREADY_CREATED_CODE: u64 = 160004;
+
+/// Archive bot-created threads after a day without activity.
+const DISCORD_THREAD_AUTO_ARCHIVE_MINUTES: u32 = 1440;
+
+/// Discord caps thread names at 100 characters.
+const DISCORD_THREAD_NAME_MAX_CHARS: usize = 100;
+
+/// Return the thread ID when `channel` is a thread, or `None` for regular channels and DMs.
+pub fn discord_thread_id_for_channel(channel: &serenity::model::channel::Channel) -> Option<u64> {
+    use serenity::model::channel::{Channel as SerenityChannel, ChannelType};
+
+    match channel {
+        SerenityChannel::Guild(guild_channel)
+            if matches!(
+                guild_channel.kind,
+                ChannelType::PublicThread | ChannelType::PrivateThread | ChannelType::NewsThread
+            ) =>
+        {
+            Some(guild_channel.id.get())
+        }
+        _ => None,
+    }
+}
+
+/// Build a thread name from the first line of the message that starts it.
+///
+/// User/role/channel mentions are dropped so the name reads as plain text.
+pub fn discord_thread_name(text: &str) -> String {
+    let first_line = text
+        .lines()
+        .map(|line| {
+            line.split_whitespace()
+                .filter(|word| !(word.starts_with("<@") || word.starts_with("<#")))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    if first_line.is_empty() {
+        return "New conversation".to_string();
+    }
+    if first_line.chars().count() <= DISCORD_THREAD_NAME_MAX_CHARS {
+        return first_line;
+    }
+    let truncated: String = first_line
+        .chars()
+        .take(DISCORD_THREAD_NAME_MAX_CHARS - 3)
+        .collect();
+    format!("{}...", truncated.trim_end())
 }
 
 impl OutboundAdapter for DiscordOutboundAdapter {
@@ -212,6 +419,8 @@ impl OutboundAdapter for DiscordOutboundAdapter {
                     .ok()
                     .map(|id| DiscordMessageReference { message_id: id })
             }),
+            nonce: message.metadata.discord_nonce.clone(),
+            enforce_nonce: message.metadata.discord_nonce.as_ref().map(|_| true),
         };
 
         // Send via Discord REST API
@@ -256,6 +465,22 @@ impl OutboundAdapter for DiscordOutboundAdapter {
         }
     }
 
+    /// Discord API: PATCH /channels/{channel_id}/messages/{message_id}
+    fn update(&self, message: &MessageRef, new_content: &str) -> Result<(), AdapterError> {
+        let client = reqwest::blocking::Client::new();
+        let request = client
+            .patch(self.message_url(message))
+            .json(&serde_json::json!({ "content": new_content }));
+        self.send_message_request(request, "edit")
+    }
+
+    /// Discord API: DELETE /channels/{channel_id}/messages/{message_id}
+    fn delete(&self, message: &MessageRef) -> Result<(), AdapterError> {
+        let client = reqwest::blocking::Client::new();
+        let request = client.delete(self.message_url(message));
+        self.send_message_request(request, "delete")
+    }
+
     fn channel(&self) -> Channel {
         Channel::Discord
     }
@@ -304,6 +529,12 @@ pub struct DiscordCreateMessageRequest {
     pub content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message_reference: Option<DiscordMessageReference>,
+    /// With `enforce_nonce`, Discord returns the earlier message instead of
+    /// posting a duplicate when the same nonce is sent again shortly after.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enforce_nonce: Option<bool>,
 }
 
 /// Message reference for replies.
@@ -332,6 +563,26 @@ pub struct DiscordDmChannelResponse {
     pub id: String,
 }
 
+/// Request body for starting a thread from a message.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DiscordStartThreadRequest {
+    pub name: String,
+    pub auto_archive_duration: u32,
+}
+
+/// Response from Discord when starting a thread (a channel object).
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct DiscordThreadResponse {
+    pub id: String,
+}
+
+/// Error body returned by the Discord REST API.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct DiscordApiError {
+    #[serde(default)]
+    code: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -360,6 +611,8 @@ mod tests {
         let request = DiscordCreateMessageRequest {
             content: "Hello, Discord!".to_string(),
             message_reference: None,
+            nonce: None,
+            enforce_nonce: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -374,6 +627,8 @@ mod tests {
             message_reference: Some(DiscordMessageReference {
                 message_id: 123456789012345678,
             }),
+            nonce: None,
+            enforce_nonce: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -508,4 +763,131 @@ mod tests {
 
         env::remove_var("DISCORD_API_BASE_URL");
     }
+
+    #[test]
+    #[serial]
+    fn start_thread_from_message_returns_thread_id() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/channels/555/messages/777/threads")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "name": "Summarize the launch notes",
+                "auto_archive_duration": 1440
+            })))
+            .with_status(201)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": "777", "type": 11, "parent_id": "555"}"#)
+            .create();
+
+        env::set_var("DISCORD_API_BASE_URL", server.url());
+        let adapter = DiscordOutboundAdapter::new("test_token".to_string());
+        let result =
+            adapter.start_thread_from_message(555, 777, "<@123> Summarize the launch notes");
+
+        mock.assert();
+        assert_eq!(result.unwrap(), 777);
+
+        env::remove_var("DISCORD_API_BASE_URL");
+    }
+
+    #[test]
+    #[serial]
+    fn start_thread_from_message_reuses_existing_thread() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/channels/555/messages/777/threads")
+            .with_status(400)
+            .with_body(
+                r#"{"message": "A thread has already been created for this message", "code": 160004}"#,
+            )
+            .create();
+
+        env::set_var("DISCORD_API_BASE_URL", server.url());
+        let adapter = DiscordOutboundAdapter::new("test_token".to_string());
+        let result = adapter.start_thread_from_message(555, 777, "hello");
+
+        mock.assert();
+        assert_eq!(result.unwrap(), 777);
+
+        env::remove_var("DISCORD_API_BASE_URL");
+    }
+
+    #[test]
+    #[serial]
+    fn update_and_delete_address_the_sent_message() {
+        use crate::channel::{MessageRef, OutboundAdapter};
+
+        let mut server = mockito::Server::new();
+        let update = server
+            .mock("PATCH", "/channels/555/messages/777")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "content": "Done: 3 files updated"
+            })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": "777", "timestamp": "2024-01-01T00:00:00Z", "channel_id": "555"}"#)
+            .create();
+        let delete = server
+            .mock("DELETE", "/channels/555/messages/777")
+            .with_status(204)
+            .create();
+
+        env::set_var("DISCORD_API_BASE_URL", server.url());
+        let adapter = DiscordOutboundAdapter::new("test_token".to_string());
+        let message = MessageRef {
+            channel_id: "555".to_string(),
+            message_id: "777".to_string(),
+        };
+        assert!(adapter.update(&message, "Done: 3 files updated").is_ok());
+        assert!(adapter.delete(&message).is_ok());
+
+        update.assert();
+        delete.assert();
+        env::remove_var("DISCORD_API_BASE_URL");
+    }
+
+    #[test]
+    fn reaction_on_bot_message_becomes_control_message() {
+        let reaction = |emoji: &str, author: u64| -> serenity::model::channel::Reaction {
+            serde_json::from_value(serde_json::json!({
+                "user_id": "42",
+                "channel_id": "555",
+                "message_id": "777",
+                "guild_id": "111",
+                "message_author_id": author.to_string(),
+                "emoji": {"id": null, "name": emoji},
+                "burst": false,
+                "type": 0
+            }))
+            .unwrap()
+        };
+        let adapter = DiscordInboundAdapter::new(HashSet::from([999u64]));
+
+        let message = adapter
+            .from_serenity_reaction(&reaction("❌", 999))
+            .unwrap();
+        assert_eq!(message.sender, "42");
+        assert_eq!(message.message_id.as_deref(), Some("777"));
+        assert_eq!(message.metadata.discord_channel_id, Some(555));
+        assert_eq!(message.metadata.reaction_control.as_deref(), Some("cancel"));
+
+        assert!(adapter
+            .from_serenity_reaction(&reaction("❌", 1234))
+            .is_err());
+        assert!(adapter
+            .from_serenity_reaction(&reaction("🎉", 999))
+            .is_err());
+    }
+
+    #[test]
+    fn thread_name_strips_mentions_and_truncates() {
+        assert_eq!(
+            discord_thread_name("<@42> can you check\nthe second line"),
+            "can you check"
+        );
+        assert_eq!(discord_thread_name("<@42>\n  "), "New conversation");
+        let long = discord_thread_name(&"word ".repeat(40));
+        assert!(long.chars().count() <= 100);
+        assert!(long.ends_with("..."));
+    }
 }
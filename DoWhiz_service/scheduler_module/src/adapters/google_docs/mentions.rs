@@ -1,57 +1,121 @@
 use regex::Regex;
-use std::sync::{LazyLock, OnceLock};
-use tracing::warn;
+use std::sync::{LazyLock, OnceLock, RwLock};
+use tracing::{info, warn};
 
-/// Oliver (little_bear) patterns - require @ prefix or email format
-static OLIVER_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
-    vec![
-        Regex::new(r"(?i)@oliver\b").unwrap(),
-        Regex::new(r"(?i)@little[_\s-]?bear\b").unwrap(),
-        Regex::new(r"(?i)oliver@dowhiz\.com").unwrap(),
-    ]
-});
+use crate::employee_config::EmployeeDirectory;
 
-/// Maggie (mini_mouse) patterns - require @ prefix or email format
-static MAGGIE_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
-    vec![
-        Regex::new(r"(?i)@maggie\b").unwrap(),
-        Regex::new(r"(?i)@mini[_\s-]?mouse\b").unwrap(),
-        Regex::new(r"(?i)maggie@dowhiz\.com").unwrap(),
-    ]
-});
+/// How one employee is mentioned in comments. Mentions need an `@` prefix
+/// or the full address, so names in signatures do not trigger.
+#[derive(Debug, Clone)]
+struct EmployeeMentions {
+    id: String,
+    display_name: String,
+    /// Lowercased `@name` forms and the id, for `EMPLOYEE_MENTION_FILTER`.
+    names: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl EmployeeMentions {
+    /// Patterns for `@` + the display name, the id and each of `mention_names`
+    /// (with `_`, `-` or a space between words, or nothing), and for each
+    /// address. Names with other punctuation, e.g. "Proto (Staging)", are
+    /// not mentionable.
+    fn new(
+        id: &str,
+        display_name: Option<&str>,
+        mention_names: &[String],
+        addresses: &[String],
+    ) -> Self {
+        let mut names: Vec<String> = Vec::new();
+        for name in display_name
+            .into_iter()
+            .chain(std::iter::once(id))
+            .chain(mention_names.iter().map(String::as_str))
+        {
+            let name = name.trim().trim_start_matches('@').to_lowercase();
+            let mentionable = name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ' '));
+            if mentionable && !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        let mut patterns: Vec<Regex> = names
+            .iter()
+            .filter_map(|name| {
+                let words: Vec<&str> = name
+                    .split(['_', '-', ' '])
+                    .filter(|word| !word.is_empty())
+                    .collect();
+                if words.is_empty() {
+                    return None;
+                }
+                Regex::new(&format!(r"(?i)@{}\b", words.join(r"[_\s-]?"))).ok()
+            })
+            .collect();
+        patterns.extend(
+            addresses
+                .iter()
+                .map(|address| address.trim())
+                .filter(|address| !address.is_empty())
+                .filter_map(|address| Regex::new(&format!("(?i){}", regex::escape(address))).ok()),
+        );
+
+        Self {
+            id: id.to_string(),
+            display_name: display_name.unwrap_or(id).to_string(),
+            names,
+            patterns,
+        }
+    }
 
-/// Proto / Boiled-Egg (boiled_egg) patterns - require @ prefix or email format
-static PROTO_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
-    vec![
-        Regex::new(r"(?i)@proto\b").unwrap(),
-        Regex::new(r"(?i)@boiled[_\s-]?egg\b").unwrap(),
-        Regex::new(r"(?i)proto@dowhiz\.com").unwrap(),
-    ]
-});
+    fn is_mentioned(&self, text: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(text))
+    }
 
-/// Devin / Sticky-Octopus (sticky_octopus) patterns - require @ prefix or email format
-static DEVIN_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
-    vec![
-        Regex::new(r"(?i)@devin\b").unwrap(),
-        Regex::new(r"(?i)@sticky[_\s-]?octopus\b").unwrap(),
-        Regex::new(r"(?i)devin@dowhiz\.com").unwrap(),
-        Regex::new(r"(?i)coder@dowhiz\.com").unwrap(),
-    ]
-});
+    fn answers_to(&self, filter: &str) -> bool {
+        let filter = filter.trim().to_lowercase();
+        self.id.eq_ignore_ascii_case(&filter) || self.names.contains(&filter)
+    }
+}
 
-/// All employee patterns (used when no filter is set)
-static ALL_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
-    let mut patterns = Vec::new();
-    patterns.extend(OLIVER_PATTERNS.iter().cloned());
-    patterns.extend(MAGGIE_PATTERNS.iter().cloned());
-    patterns.extend(PROTO_PATTERNS.iter().cloned());
-    patterns.extend(DEVIN_PATTERNS.iter().cloned());
-    patterns
-});
+/// Every employee's mentions, set from the employee directory at startup.
+static EMPLOYEE_MENTIONS: LazyLock<RwLock<Vec<EmployeeMentions>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
+/// Build mention patterns and display names from `directory`. Called at
+/// startup by processes that poll Google Workspace comments; until then no
+/// comment counts as a mention.
+pub fn configure_employee_mentions(directory: &EmployeeDirectory) {
+    let mentions: Vec<EmployeeMentions> = directory
+        .employees
+        .iter()
+        .map(|employee| {
+            EmployeeMentions::new(
+                &employee.id,
+                employee.display_name.as_deref(),
+                &employee.mention_names,
+                &employee.addresses,
+            )
+        })
+        .collect();
+    info!(
+        "employee mentions configured: {}",
+        mentions
+            .iter()
+            .map(|employee| format!("{} (@{})", employee.id, employee.names.join(", @")))
+            .collect::<Vec<_>>()
+            .join("; ")
+    );
+    *EMPLOYEE_MENTIONS
+        .write()
+        .unwrap_or_else(|poison| poison.into_inner()) = mentions;
+}
 
 /// Cache the employee mention filter from environment.
-/// Set EMPLOYEE_MENTION_FILTER to the employee_id (e.g., "proto" for local, "little_bear" for production)
-/// to only respond to mentions of that specific employee.
+/// Set EMPLOYEE_MENTION_FILTER to the employee_id or a mention name (e.g., "proto" for local,
+/// "little_bear" for production) to only respond to mentions of that specific employee.
 static EMPLOYEE_FILTER: OnceLock<Option<String>> = OnceLock::new();
 
 fn get_employee_filter() -> Option<&'static String> {
@@ -64,198 +128,135 @@ fn get_employee_filter() -> Option<&'static String> {
 /// When EMPLOYEE_MENTION_FILTER is set, only checks patterns for that employee.
 /// This allows local testing (proto) and production (oliver) to not interfere with each other.
 pub fn contains_employee_mention(text: &str) -> bool {
-    let filter = get_employee_filter();
-
-    match filter.map(|s| s.as_str()) {
-        Some("oliver") | Some("little_bear") => OLIVER_PATTERNS.iter().any(|p| p.is_match(text)),
-        Some("proto") | Some("boiled_egg") => PROTO_PATTERNS.iter().any(|p| p.is_match(text)),
-        Some("maggie") | Some("mini_mouse") => MAGGIE_PATTERNS.iter().any(|p| p.is_match(text)),
-        Some("devin") | Some("sticky_octopus") => DEVIN_PATTERNS.iter().any(|p| p.is_match(text)),
-        None => {
-            // No filter - check all patterns (backwards compatible)
-            ALL_PATTERNS.iter().any(|p| p.is_match(text))
-        }
-        Some(other) => {
-            // Unknown filter - warn and check all
-            warn!(
-                "Unknown EMPLOYEE_MENTION_FILTER: {}, checking all patterns",
-                other
-            );
-            ALL_PATTERNS.iter().any(|p| p.is_match(text))
-        }
-    }
+    let mentions = EMPLOYEE_MENTIONS
+        .read()
+        .unwrap_or_else(|poison| poison.into_inner());
+    mentions_employee(&mentions, get_employee_filter().map(String::as_str), text)
 }
 
-/// Extract the employee name from a mention.
-/// Returns the canonical display name for the employee.
-/// This function is called after `contains_employee_mention` returns true,
-/// so we can use looser matching here to extract the name.
-pub fn extract_employee_name(text: &str) -> Option<&'static str> {
-    let text_lower = text.to_lowercase();
-    // Oliver (little_bear)
-    if text_lower.contains("@oliver")
-        || text_lower.contains("oliver@dowhiz")
-        || text_lower.contains("@little_bear")
-        || text_lower.contains("@little-bear")
-    {
-        Some("Oliver")
-    // Maggie (mini_mouse)
-    } else if text_lower.contains("@maggie")
-        || text_lower.contains("maggie@dowhiz")
-        || text_lower.contains("@mini_mouse")
-        || text_lower.contains("@mini-mouse")
-    {
-        Some("Maggie")
-    // Proto / Boiled-Egg (boiled_egg) - for local testing
-    } else if text_lower.contains("@proto")
-        || text_lower.contains("proto@dowhiz")
-        || text_lower.contains("@boiled_egg")
-        || text_lower.contains("@boiled-egg")
-    {
-        Some("Proto")
-    // Devin / Sticky-Octopus (sticky_octopus)
-    } else if text_lower.contains("@devin")
-        || text_lower.contains("devin@dowhiz")
-        || text_lower.contains("coder@dowhiz")
-        || text_lower.contains("@sticky_octopus")
-        || text_lower.contains("@sticky-octopus")
-    {
-        Some("Devin")
-    } else {
-        None
+fn mentions_employee(mentions: &[EmployeeMentions], filter: Option<&str>, text: &str) -> bool {
+    if let Some(filter) = filter {
+        if let Some(employee) = mentions.iter().find(|employee| employee.answers_to(filter)) {
+            return employee.is_mentioned(text);
+        }
+        // Unknown filter - warn and check all
+        warn!(
+            "Unknown EMPLOYEE_MENTION_FILTER: {}, checking all patterns",
+            filter
+        );
     }
+    mentions.iter().any(|employee| employee.is_mentioned(text))
 }
 
-/// Check if text matches Oliver patterns (for testing/direct use).
-#[allow(dead_code)]
-pub fn matches_oliver_patterns(text: &str) -> bool {
-    OLIVER_PATTERNS.iter().any(|p| p.is_match(text))
-}
-
-/// Check if text matches Proto patterns (for testing/direct use).
-#[allow(dead_code)]
-pub fn matches_proto_patterns(text: &str) -> bool {
-    PROTO_PATTERNS.iter().any(|p| p.is_match(text))
+/// Extract the display name of the employee mentioned in `text`, from the
+/// employee directory (the id when it has none).
+pub fn extract_employee_name(text: &str) -> Option<String> {
+    let mentions = EMPLOYEE_MENTIONS
+        .read()
+        .unwrap_or_else(|poison| poison.into_inner());
+    mentioned_display_name(&mentions, text)
 }
 
-/// Check if text matches Maggie patterns (for testing/direct use).
-#[allow(dead_code)]
-pub fn matches_maggie_patterns(text: &str) -> bool {
-    MAGGIE_PATTERNS.iter().any(|p| p.is_match(text))
-}
-
-/// Check if text matches Devin patterns (for testing/direct use).
-#[allow(dead_code)]
-pub fn matches_devin_patterns(text: &str) -> bool {
-    DEVIN_PATTERNS.iter().any(|p| p.is_match(text))
+fn mentioned_display_name(mentions: &[EmployeeMentions], text: &str) -> Option<String> {
+    mentions
+        .iter()
+        .find(|employee| employee.is_mentioned(text))
+        .map(|employee| employee.display_name.clone())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // Note: contains_employee_mention behavior depends on EMPLOYEE_MENTION_FILTER env var.
-    // When not set (default in tests), it checks ALL patterns.
-    // When set to "proto", it only checks Proto patterns, etc.
+    fn employees() -> Vec<EmployeeMentions> {
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        vec![
+            EmployeeMentions::new(
+                "little_bear",
+                Some("Oliver"),
+                &[],
+                &strings(&["oliver@dowhiz.com", "little-bear@dowhiz.com"]),
+            ),
+            EmployeeMentions::new(
+                "mini_mouse",
+                Some("Maggie"),
+                &[],
+                &strings(&["maggie@dowhiz.com"]),
+            ),
+            EmployeeMentions::new(
+                "boiled_egg",
+                Some("Boiled-Egg"),
+                &strings(&["proto"]),
+                &strings(&["proto@dowhiz.com"]),
+            ),
+            EmployeeMentions::new(
+                "sticky_octopus",
+                Some("Sticky-Octopus"),
+                &strings(&["devin"]),
+                &strings(&["devin@dowhiz.com", "coder@dowhiz.com"]),
+            ),
+        ]
+    }
 
     #[test]
     fn test_employee_mention_detection() {
-        // Explicit @ mentions should trigger (when no filter or filter matches)
-        assert!(contains_employee_mention("@Oliver please review"));
-        assert!(contains_employee_mention("@oliver can you help?"));
-        assert!(contains_employee_mention("@proto check this"));
-        assert!(contains_employee_mention("@PROTO look at this"));
-        assert!(contains_employee_mention("@maggie please check"));
-        assert!(contains_employee_mention("@devin help me"));
-        assert!(contains_employee_mention("@boiled-egg fix this"));
-        assert!(contains_employee_mention("@little_bear help"));
-        assert!(contains_employee_mention("@mini-mouse check"));
-        assert!(contains_employee_mention("@sticky_octopus review"));
+        let employees = employees();
+        let mentioned = |text: &str| mentions_employee(&employees, None, text);
 
-        // Email format should trigger
-        assert!(contains_employee_mention("Contact oliver@dowhiz.com"));
-        assert!(contains_employee_mention("proto@dowhiz.com please help"));
-        assert!(contains_employee_mention("maggie@dowhiz.com"));
-        assert!(contains_employee_mention("devin@dowhiz.com"));
-        assert!(contains_employee_mention("coder@dowhiz.com"));
+        // Explicit @ mentions of display names, ids and mention names
+        assert!(mentioned("@Oliver please review"));
+        assert!(mentioned("@oliver can you help?"));
+        assert!(mentioned("@proto check this"));
+        assert!(mentioned("@PROTO look at this"));
+        assert!(mentioned("@maggie please check"));
+        assert!(mentioned("@devin help me"));
+        assert!(mentioned("@boiled-egg fix this"));
+        assert!(mentioned("@little_bear help"));
+        assert!(mentioned("@little bear help"));
+        assert!(mentioned("@mini-mouse check"));
+        assert!(mentioned("@sticky_octopus review"));
+
+        // Addresses should trigger
+        assert!(mentioned("Contact oliver@dowhiz.com"));
+        assert!(mentioned("proto@dowhiz.com please help"));
+        assert!(mentioned("coder@dowhiz.com"));
 
         // Names without @ should NOT trigger (prevents signature false positives)
-        assert!(!contains_employee_mention("Hey oliver can you help?"));
-        assert!(!contains_employee_mention("Oliver, check this"));
-        assert!(!contains_employee_mention("little_bear please fix"));
-        assert!(!contains_employee_mention("Boiled-Egg"));
-        assert!(!contains_employee_mention("Go eggs! Boiled-Egg"));
-        assert!(!contains_employee_mention("proto"));
-        assert!(!contains_employee_mention("maggie said hello"));
+        assert!(!mentioned("Hey oliver can you help?"));
+        assert!(!mentioned("little_bear please fix"));
+        assert!(!mentioned("Go eggs! Boiled-Egg"));
+        assert!(!mentioned("@protocol review"));
 
         // Unrelated text should NOT trigger
-        assert!(!contains_employee_mention("Hey John can you help?"));
-        assert!(!contains_employee_mention("This is a regular comment"));
-    }
-
-    #[test]
-    fn test_oliver_patterns() {
-        assert!(matches_oliver_patterns("@Oliver please"));
-        assert!(matches_oliver_patterns("@oliver can you"));
-        assert!(matches_oliver_patterns("@little_bear help"));
-        assert!(matches_oliver_patterns("@little-bear help"));
-        assert!(matches_oliver_patterns("oliver@dowhiz.com"));
-
-        // Should NOT match other employees
-        assert!(!matches_oliver_patterns("@proto check"));
-        assert!(!matches_oliver_patterns("@maggie please"));
-        assert!(!matches_oliver_patterns("proto@dowhiz.com"));
-    }
-
-    #[test]
-    fn test_proto_patterns() {
-        assert!(matches_proto_patterns("@proto check"));
-        assert!(matches_proto_patterns("@PROTO look"));
-        assert!(matches_proto_patterns("@boiled-egg fix"));
-        assert!(matches_proto_patterns("@boiled_egg fix"));
-        assert!(matches_proto_patterns("proto@dowhiz.com"));
-
-        // Should NOT match other employees
-        assert!(!matches_proto_patterns("@oliver please"));
-        assert!(!matches_proto_patterns("@maggie check"));
-        assert!(!matches_proto_patterns("oliver@dowhiz.com"));
+        assert!(!mentioned("Hey John can you help?"));
     }
 
     #[test]
-    fn test_maggie_patterns() {
-        assert!(matches_maggie_patterns("@maggie please"));
-        assert!(matches_maggie_patterns("@MAGGIE help"));
-        assert!(matches_maggie_patterns("@mini-mouse check"));
-        assert!(matches_maggie_patterns("@mini_mouse check"));
-        assert!(matches_maggie_patterns("maggie@dowhiz.com"));
-
-        // Should NOT match other employees
-        assert!(!matches_maggie_patterns("@oliver please"));
-        assert!(!matches_maggie_patterns("@proto check"));
-    }
-
-    #[test]
-    fn test_devin_patterns() {
-        assert!(matches_devin_patterns("@devin help"));
-        assert!(matches_devin_patterns("@DEVIN review"));
-        assert!(matches_devin_patterns("@sticky-octopus fix"));
-        assert!(matches_devin_patterns("@sticky_octopus fix"));
-        assert!(matches_devin_patterns("devin@dowhiz.com"));
-        assert!(matches_devin_patterns("coder@dowhiz.com"));
-
-        // Should NOT match other employees
-        assert!(!matches_devin_patterns("@oliver please"));
-        assert!(!matches_devin_patterns("@proto check"));
+    fn filter_limits_mentions_to_one_employee() {
+        let employees = employees();
+        for filter in ["proto", "boiled_egg", "Boiled-Egg"] {
+            assert!(mentions_employee(&employees, Some(filter), "@proto check"));
+            assert!(!mentions_employee(
+                &employees,
+                Some(filter),
+                "@oliver check"
+            ));
+        }
+        assert!(mentions_employee(
+            &employees,
+            Some("nobody"),
+            "@maggie please"
+        ));
     }
 
     #[test]
     fn test_extract_employee_name() {
-        // extract_employee_name is used after detection, so it can be looser
-        assert_eq!(extract_employee_name("@Oliver please"), Some("Oliver"));
-        assert_eq!(extract_employee_name("oliver@dowhiz.com"), Some("Oliver"));
-        assert_eq!(extract_employee_name("@proto help"), Some("Proto"));
-        assert_eq!(extract_employee_name("@maggie check"), Some("Maggie"));
-        assert_eq!(extract_employee_name("@devin review"), Some("Devin"));
-        assert_eq!(extract_employee_name("John help"), None);
+        let employees = employees();
+        let name = |text: &str| mentioned_display_name(&employees, text);
+        assert_eq!(name("@Oliver please").as_deref(), Some("Oliver"));
+        assert_eq!(name("oliver@dowhiz.com").as_deref(), Some("Oliver"));
+        assert_eq!(name("@proto help").as_deref(), Some("Boiled-Egg"));
+        assert_eq!(name("@devin review").as_deref(), Some("Sticky-Octopus"));
+        assert_eq!(name("John help"), None);
     }
 }
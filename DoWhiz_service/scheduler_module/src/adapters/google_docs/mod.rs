@@ -9,9 +9,13 @@ mod inbound;
 mod mentions;
 mod models;
 mod outbound;
+mod suggestions;
 
 pub use formatting::format_edit_proposal;
 pub use inbound::GoogleDocsInboundAdapter;
-pub use mentions::{contains_employee_mention, extract_employee_name};
+pub use mentions::{configure_employee_mentions, contains_employee_mention, extract_employee_name};
 pub use models::{ActionableComment, DocumentStyles, GoogleDocsComment, TextStyleInfo};
 pub use outbound::GoogleDocsOutboundAdapter;
+pub use suggestions::{
+    ResolvedSuggestions, StyleRun, SuggestionKind, SuggestionState, TrackedSuggestion,
+};
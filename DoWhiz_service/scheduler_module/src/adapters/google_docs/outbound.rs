@@ -1,9 +1,17 @@
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use crate::channel::{AdapterError, Channel, OutboundAdapter, OutboundMessage, SendResult};
 use crate::google_auth::GoogleAuth;
 
 use super::models::{CommentReply, DocumentStyles, TextStyleInfo};
+use super::suggestions::{
+    deletion_mark_request, insertion_mark_request, plan_resolution, DocText, Resolution,
+    ResolvedSuggestions, SuggestionState, TrackedSuggestion,
+};
+
+/// Times an edit is resolved and sent again when the document changed
+/// between reading it and writing to it.
+const MAX_EDIT_ATTEMPTS: usize = 3;
 
 /// Adapter for posting replies to Google Docs comments.
 #[derive(Debug, Clone)]
@@ -119,6 +127,17 @@ impl GoogleDocsOutboundAdapter {
         &self,
         document_id: &str,
         requests: Vec<serde_json::Value>,
+    ) -> Result<(), AdapterError> {
+        self.apply_document_edit_at_revision(document_id, requests, None)
+    }
+
+    /// Apply an edit only if the document is still at `revision_id`.
+    /// Fails with `AdapterError::EditConflict` if it was edited since.
+    pub fn apply_document_edit_at_revision(
+        &self,
+        document_id: &str,
+        requests: Vec<serde_json::Value>,
+        revision_id: Option<&str>,
     ) -> Result<(), AdapterError> {
         let access_token = self
             .auth
@@ -132,9 +151,12 @@ impl GoogleDocsOutboundAdapter {
             document_id
         );
 
-        let payload = serde_json::json!({
+        let mut payload = serde_json::json!({
             "requests": requests
         });
+        if let Some(revision_id) = revision_id {
+            payload["writeControl"] = serde_json::json!({ "requiredRevisionId": revision_id });
+        }
 
         let response = client
             .post(&url)
@@ -147,6 +169,13 @@ impl GoogleDocsOutboundAdapter {
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().unwrap_or_default();
+            if revision_id.is_some() && is_revision_mismatch(status.as_u16(), &body) {
+                return Err(AdapterError::EditConflict(format!(
+                    "{} is no longer at revision {}",
+                    document_id,
+                    revision_id.unwrap_or_default()
+                )));
+            }
             error!(
                 "Failed to apply edit to {}: {} - {}",
                 document_id, status, body
@@ -207,511 +236,210 @@ impl GoogleDocsOutboundAdapter {
         search_text: &str,
     ) -> Result<Option<(i64, i64)>, AdapterError> {
         let doc = self.get_document_structure(document_id)?;
+        Ok(DocText::from_document(&doc).find(search_text))
+    }
 
-        // Extract body content
-        let body = doc.get("body").and_then(|b| b.get("content"));
-        if body.is_none() {
-            return Ok(None);
-        }
-
-        let content = body
-            .unwrap()
-            .as_array()
-            .ok_or_else(|| AdapterError::ParseError("Invalid document structure".to_string()))?;
-
-        // Build full text and track positions
-        let mut full_text = String::new();
-        let mut text_positions: Vec<(usize, i64)> = Vec::new(); // (string_pos, doc_index)
-
-        for element in content {
-            if let Some(paragraph) = element.get("paragraph") {
-                if let Some(elements) = paragraph.get("elements").and_then(|e| e.as_array()) {
-                    for elem in elements {
-                        if let Some(text_run) = elem.get("textRun") {
-                            if let Some(content_text) =
-                                text_run.get("content").and_then(|c| c.as_str())
-                            {
-                                let start_idx =
-                                    elem.get("startIndex").and_then(|i| i.as_i64()).unwrap_or(0);
-                                text_positions.push((full_text.len(), start_idx));
-                                full_text.push_str(content_text);
-                            }
-                        }
-                    }
-                }
+    /// Read the document, build batch requests from it and send them at the
+    /// revision that was read. If someone edited the document in between,
+    /// text positions are resolved again from the new content.
+    fn edit_at_current_revision<T>(
+        &self,
+        document_id: &str,
+        mut build: impl FnMut(&DocText) -> Result<(Vec<serde_json::Value>, T), AdapterError>,
+    ) -> Result<T, AdapterError> {
+        let mut attempt = 1;
+        loop {
+            let doc = DocText::from_document(&self.get_document_structure(document_id)?);
+            let (requests, value) = build(&doc)?;
+            if requests.is_empty() {
+                return Ok(value);
             }
-        }
-
-        // Find the search text in full_text
-        if let Some(string_pos) = full_text.find(search_text) {
-            // Convert string position to document index
-            let mut doc_start_idx = 0i64;
-            for (str_pos, doc_idx) in &text_positions {
-                if *str_pos <= string_pos {
-                    doc_start_idx = *doc_idx + (string_pos - str_pos) as i64;
+            match self.apply_document_edit_at_revision(
+                document_id,
+                requests,
+                doc.revision_id.as_deref(),
+            ) {
+                Err(AdapterError::EditConflict(reason)) if attempt < MAX_EDIT_ATTEMPTS => {
+                    attempt += 1;
+                    warn!(
+                        "{}; resolving the edit again (attempt {}/{})",
+                        reason, attempt, MAX_EDIT_ATTEMPTS
+                    );
                 }
+                result => return result.map(|()| value),
             }
-            let doc_end_idx = doc_start_idx + search_text.len() as i64;
-            return Ok(Some((doc_start_idx, doc_end_idx)));
         }
-
-        Ok(None)
     }
 
     /// Mark text for deletion with red color and strikethrough.
     /// Used in suggesting mode to show text that will be removed.
-    pub fn mark_deletion(&self, document_id: &str, text_to_mark: &str) -> Result<(), AdapterError> {
-        let position = self.find_text_position(document_id, text_to_mark)?;
-
-        let (start_idx, end_idx) = position.ok_or_else(|| {
-            AdapterError::SendError(format!("Text not found in document: '{}'", text_to_mark))
+    /// Returns the id of the tracked suggestion.
+    pub fn mark_deletion(
+        &self,
+        document_id: &str,
+        text_to_mark: &str,
+    ) -> Result<String, AdapterError> {
+        let mut state = SuggestionState::load(document_id)?;
+        let deletion = self.edit_at_current_revision(document_id, |doc| {
+            let (start_idx, end_idx) = find_anchor(doc, text_to_mark)?;
+            Ok((
+                vec![deletion_mark_request(start_idx, end_idx)],
+                TrackedSuggestion::deletion(doc, start_idx, end_idx),
+            ))
         })?;
-
-        // Apply red color and strikethrough
-        let requests = vec![serde_json::json!({
-            "updateTextStyle": {
-                "range": {
-                    "startIndex": start_idx,
-                    "endIndex": end_idx
-                },
-                "textStyle": {
-                    "foregroundColor": {
-                        "color": {
-                            "rgbColor": {
-                                "red": 1.0,
-                                "green": 0.0,
-                                "blue": 0.0
-                            }
-                        }
-                    },
-                    "strikethrough": true
-                },
-                "fields": "foregroundColor,strikethrough"
-            }
-        })];
-
-        self.apply_document_edit(document_id, requests)?;
         info!(
             "Marked deletion '{}' at indices {}-{}",
-            text_to_mark, start_idx, end_idx
+            text_to_mark, deletion.start_index, deletion.end_index
         );
-        Ok(())
+        let id = deletion.id.clone();
+        state.suggestions.push(deletion);
+        state.save()?;
+        Ok(id)
     }
 
     /// Insert new text with blue color (suggesting mode).
     /// The text is inserted after the specified anchor text.
+    /// Returns the id of the tracked suggestion.
     pub fn insert_suggestion(
         &self,
         document_id: &str,
         after_text: &str,
         new_text: &str,
-    ) -> Result<(), AdapterError> {
-        let position = self.find_text_position(document_id, after_text)?;
-
-        let (_, end_idx) = position.ok_or_else(|| {
-            AdapterError::SendError(format!("Anchor text not found: '{}'", after_text))
-        })?;
-
-        // Insert text and make it blue (explicitly remove strikethrough in case anchor has it)
-        let requests = vec![
-            serde_json::json!({
-                "insertText": {
-                    "location": {
-                        "index": end_idx
-                    },
-                    "text": new_text
-                }
-            }),
-            serde_json::json!({
-                "updateTextStyle": {
-                    "range": {
-                        "startIndex": end_idx,
-                        "endIndex": end_idx + new_text.chars().count() as i64
-                    },
-                    "textStyle": {
-                        "foregroundColor": {
-                            "color": {
-                                "rgbColor": {
-                                    "red": 0.0,
-                                    "green": 0.0,
-                                    "blue": 1.0
-                                }
-                            }
+    ) -> Result<String, AdapterError> {
+        let mut state = SuggestionState::load(document_id)?;
+        let insertion = self.edit_at_current_revision(document_id, |doc| {
+            let (_, end_idx) = find_anchor(doc, after_text)?;
+            let base = state.base_style_at(doc, end_idx);
+            let insertion = TrackedSuggestion::insertion(doc, end_idx, new_text, base);
+            let requests = vec![
+                serde_json::json!({
+                    "insertText": {
+                        "location": {
+                            "index": end_idx
                         },
-                        "strikethrough": false
-                    },
-                    "fields": "foregroundColor,strikethrough"
-                }
-            }),
-        ];
+                        "text": new_text
+                    }
+                }),
+                // Explicitly not strikethrough, in case the anchor is marked for deletion
+                insertion_mark_request(insertion.start_index, insertion.end_index),
+            ];
+            Ok((requests, insertion))
+        })?;
 
-        self.apply_document_edit(document_id, requests)?;
+        let id = insertion.id.clone();
+        state.shift_for_insert(
+            insertion.start_index,
+            insertion.end_index - insertion.start_index,
+        );
+        state.suggestions.push(insertion);
+        state.save()?;
         info!("Inserted suggestion '{}' after '{}'", new_text, after_text);
-        Ok(())
+        Ok(id)
     }
 
     /// Replace text with revision marks (suggesting mode).
     /// Old text gets red + strikethrough, new text gets blue.
+    /// Returns the ids of the tracked deletion and insertion.
     pub fn suggest_replace(
         &self,
         document_id: &str,
         old_text: &str,
         new_text: &str,
-    ) -> Result<(), AdapterError> {
-        let position = self.find_text_position(document_id, old_text)?;
-
-        let (start_idx, end_idx) = position.ok_or_else(|| {
-            AdapterError::SendError(format!("Text to replace not found: '{}'", old_text))
-        })?;
-
-        // First, mark old text as deleted (red + strikethrough)
-        // Then insert new text (blue) right after the old text
-        let requests = vec![
-            // Mark old text as deleted
-            serde_json::json!({
-                "updateTextStyle": {
-                    "range": {
-                        "startIndex": start_idx,
-                        "endIndex": end_idx
-                    },
-                    "textStyle": {
-                        "foregroundColor": {
-                            "color": {
-                                "rgbColor": {
-                                    "red": 1.0,
-                                    "green": 0.0,
-                                    "blue": 0.0
-                                }
-                            }
-                        },
-                        "strikethrough": true
-                    },
-                    "fields": "foregroundColor,strikethrough"
-                }
-            }),
-            // Insert new text right after old text
-            serde_json::json!({
-                "insertText": {
-                    "location": {
-                        "index": end_idx
-                    },
-                    "text": new_text
-                }
-            }),
-            // Make new text blue (and explicitly remove strikethrough since it may inherit from previous text)
-            serde_json::json!({
-                "updateTextStyle": {
-                    "range": {
-                        "startIndex": end_idx,
-                        "endIndex": end_idx + new_text.chars().count() as i64
-                    },
-                    "textStyle": {
-                        "foregroundColor": {
-                            "color": {
-                                "rgbColor": {
-                                    "red": 0.0,
-                                    "green": 0.0,
-                                    "blue": 1.0
-                                }
-                            }
+    ) -> Result<Vec<String>, AdapterError> {
+        let mut state = SuggestionState::load(document_id)?;
+        let (deletion, insertion) = self.edit_at_current_revision(document_id, |doc| {
+            let (start_idx, end_idx) = find_anchor(doc, old_text)?;
+            let deletion = TrackedSuggestion::deletion(doc, start_idx, end_idx);
+            // The new text takes the old text's style once applied
+            let base = state.base_style_at(doc, end_idx);
+            let insertion = TrackedSuggestion::insertion(doc, end_idx, new_text, base);
+
+            // Mark old text as deleted, then insert the new text right after it
+            let requests = vec![
+                deletion_mark_request(start_idx, end_idx),
+                serde_json::json!({
+                    "insertText": {
+                        "location": {
+                            "index": end_idx
                         },
-                        "strikethrough": false
-                    },
-                    "fields": "foregroundColor,strikethrough"
-                }
-            }),
-        ];
+                        "text": new_text
+                    }
+                }),
+                insertion_mark_request(insertion.start_index, insertion.end_index),
+            ];
+            Ok((requests, (deletion, insertion)))
+        })?;
 
-        self.apply_document_edit(document_id, requests)?;
+        let ids = vec![deletion.id.clone(), insertion.id.clone()];
+        state.shift_for_insert(
+            insertion.start_index,
+            insertion.end_index - insertion.start_index,
+        );
+        state.suggestions.push(deletion);
+        state.suggestions.push(insertion);
+        state.save()?;
         info!("Suggested replacement: '{}' -> '{}'", old_text, new_text);
-        Ok(())
+        Ok(ids)
     }
 
-    /// Apply all suggestions in the document.
-    /// Deletes all red strikethrough text and converts blue text to black.
-    pub fn apply_suggestions(&self, document_id: &str) -> Result<(), AdapterError> {
-        let doc = self.get_document_structure(document_id)?;
-
-        let body = doc.get("body").and_then(|b| b.get("content"));
-        if body.is_none() {
-            return Ok(());
-        }
-
-        let content = body
-            .unwrap()
-            .as_array()
-            .ok_or_else(|| AdapterError::ParseError("Invalid document structure".to_string()))?;
-
-        // Collect ranges to delete (red strikethrough) and ranges to normalize (blue)
-        let mut ranges_to_delete: Vec<(i64, i64)> = Vec::new();
-        let mut ranges_to_normalize: Vec<(i64, i64)> = Vec::new();
-
-        for element in content {
-            if let Some(paragraph) = element.get("paragraph") {
-                if let Some(elements) = paragraph.get("elements").and_then(|e| e.as_array()) {
-                    for elem in elements {
-                        if let Some(text_run) = elem.get("textRun") {
-                            let start_idx =
-                                elem.get("startIndex").and_then(|i| i.as_i64()).unwrap_or(0);
-                            let end_idx =
-                                elem.get("endIndex").and_then(|i| i.as_i64()).unwrap_or(0);
-
-                            if let Some(text_style) = text_run.get("textStyle") {
-                                // Check for red strikethrough (deletion markers)
-                                let is_strikethrough = text_style
-                                    .get("strikethrough")
-                                    .and_then(|s| s.as_bool())
-                                    .unwrap_or(false);
-                                let is_red = text_style
-                                    .get("foregroundColor")
-                                    .and_then(|fc| fc.get("color"))
-                                    .and_then(|c| c.get("rgbColor"))
-                                    .map(|rgb| {
-                                        let r =
-                                            rgb.get("red").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                                        let g = rgb
-                                            .get("green")
-                                            .and_then(|v| v.as_f64())
-                                            .unwrap_or(0.0);
-                                        let b =
-                                            rgb.get("blue").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                                        r > 0.8 && g < 0.2 && b < 0.2 // Check if red
-                                    })
-                                    .unwrap_or(false);
-
-                                if is_strikethrough && is_red {
-                                    ranges_to_delete.push((start_idx, end_idx));
-                                    continue;
-                                }
-
-                                // Check for blue text (addition markers)
-                                let is_blue = text_style
-                                    .get("foregroundColor")
-                                    .and_then(|fc| fc.get("color"))
-                                    .and_then(|c| c.get("rgbColor"))
-                                    .map(|rgb| {
-                                        let r =
-                                            rgb.get("red").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                                        let g = rgb
-                                            .get("green")
-                                            .and_then(|v| v.as_f64())
-                                            .unwrap_or(0.0);
-                                        let b =
-                                            rgb.get("blue").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                                        r < 0.2 && g < 0.2 && b > 0.8 // Check if blue
-                                    })
-                                    .unwrap_or(false);
-
-                                if is_blue {
-                                    ranges_to_normalize.push((start_idx, end_idx));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        // Build requests: delete red text (in reverse order) then normalize blue text
-        let mut requests: Vec<serde_json::Value> = Vec::new();
-
-        // Sort ranges_to_delete in reverse order (to avoid index shifting issues)
-        let mut sorted_delete = ranges_to_delete.clone();
-        sorted_delete.sort_by(|a, b| b.0.cmp(&a.0));
-
-        for (start_idx, end_idx) in sorted_delete {
-            requests.push(serde_json::json!({
-                "deleteContentRange": {
-                    "range": {
-                        "startIndex": start_idx,
-                        "endIndex": end_idx
-                    }
-                }
-            }));
-        }
-
-        // Normalize blue text to black (remove color)
-        let ranges_to_normalize_len = ranges_to_normalize.len();
-        for (start_idx, end_idx) in ranges_to_normalize {
-            // Adjust indices based on deletions that occurred before this range
-            let mut adjusted_start = start_idx;
-            let mut adjusted_end = end_idx;
-            for (del_start, del_end) in &ranges_to_delete {
-                if *del_end <= start_idx {
-                    let deleted_length = del_end - del_start;
-                    adjusted_start -= deleted_length;
-                    adjusted_end -= deleted_length;
-                }
-            }
-
-            requests.push(serde_json::json!({
-                "updateTextStyle": {
-                    "range": {
-                        "startIndex": adjusted_start,
-                        "endIndex": adjusted_end
-                    },
-                    "textStyle": {
-                        "foregroundColor": {}  // Reset to default (black)
-                    },
-                    "fields": "foregroundColor"
-                }
-            }));
-        }
-
-        if !requests.is_empty() {
-            self.apply_document_edit(document_id, requests)?;
-            info!(
-                "Applied suggestions: deleted {} ranges, normalized {} ranges",
-                ranges_to_delete.len(),
-                ranges_to_normalize_len
-            );
-        }
-
-        Ok(())
+    /// Apply all suggestions tracked for the document: delete suggested
+    /// deletions and give suggested insertions their surrounding style.
+    pub fn apply_suggestions(
+        &self,
+        document_id: &str,
+    ) -> Result<ResolvedSuggestions, AdapterError> {
+        self.resolve_suggestions(document_id, Resolution::Apply, None)
     }
 
-    /// Discard all suggestions in the document.
-    /// Removes blue text and restores red strikethrough text to normal.
-    pub fn discard_suggestions(&self, document_id: &str) -> Result<(), AdapterError> {
-        let doc = self.get_document_structure(document_id)?;
-
-        let body = doc.get("body").and_then(|b| b.get("content"));
-        if body.is_none() {
-            return Ok(());
-        }
-
-        let content = body
-            .unwrap()
-            .as_array()
-            .ok_or_else(|| AdapterError::ParseError("Invalid document structure".to_string()))?;
-
-        // Collect ranges to delete (blue text) and ranges to restore (red strikethrough)
-        let mut ranges_to_delete: Vec<(i64, i64)> = Vec::new();
-        let mut ranges_to_restore: Vec<(i64, i64)> = Vec::new();
-
-        for element in content {
-            if let Some(paragraph) = element.get("paragraph") {
-                if let Some(elements) = paragraph.get("elements").and_then(|e| e.as_array()) {
-                    for elem in elements {
-                        if let Some(text_run) = elem.get("textRun") {
-                            let start_idx =
-                                elem.get("startIndex").and_then(|i| i.as_i64()).unwrap_or(0);
-                            let end_idx =
-                                elem.get("endIndex").and_then(|i| i.as_i64()).unwrap_or(0);
-
-                            if let Some(text_style) = text_run.get("textStyle") {
-                                // Check for blue text (to be deleted)
-                                let is_blue = text_style
-                                    .get("foregroundColor")
-                                    .and_then(|fc| fc.get("color"))
-                                    .and_then(|c| c.get("rgbColor"))
-                                    .map(|rgb| {
-                                        let r =
-                                            rgb.get("red").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                                        let g = rgb
-                                            .get("green")
-                                            .and_then(|v| v.as_f64())
-                                            .unwrap_or(0.0);
-                                        let b =
-                                            rgb.get("blue").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                                        r < 0.2 && g < 0.2 && b > 0.8
-                                    })
-                                    .unwrap_or(false);
-
-                                if is_blue {
-                                    ranges_to_delete.push((start_idx, end_idx));
-                                    continue;
-                                }
-
-                                // Check for red strikethrough (to be restored)
-                                let is_strikethrough = text_style
-                                    .get("strikethrough")
-                                    .and_then(|s| s.as_bool())
-                                    .unwrap_or(false);
-                                let is_red = text_style
-                                    .get("foregroundColor")
-                                    .and_then(|fc| fc.get("color"))
-                                    .and_then(|c| c.get("rgbColor"))
-                                    .map(|rgb| {
-                                        let r =
-                                            rgb.get("red").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                                        let g = rgb
-                                            .get("green")
-                                            .and_then(|v| v.as_f64())
-                                            .unwrap_or(0.0);
-                                        let b =
-                                            rgb.get("blue").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                                        r > 0.8 && g < 0.2 && b < 0.2
-                                    })
-                                    .unwrap_or(false);
-
-                                if is_strikethrough && is_red {
-                                    ranges_to_restore.push((start_idx, end_idx));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        // Build requests: delete blue text (in reverse order) then restore red text
-        let mut requests: Vec<serde_json::Value> = Vec::new();
-
-        // Sort ranges_to_delete in reverse order
-        let mut sorted_delete = ranges_to_delete.clone();
-        sorted_delete.sort_by(|a, b| b.0.cmp(&a.0));
-
-        for (start_idx, end_idx) in sorted_delete {
-            requests.push(serde_json::json!({
-                "deleteContentRange": {
-                    "range": {
-                        "startIndex": start_idx,
-                        "endIndex": end_idx
-                    }
-                }
-            }));
-        }
+    /// Apply only the tracked suggestions with the given ids.
+    pub fn apply_suggestions_by_id(
+        &self,
+        document_id: &str,
+        ids: &[String],
+    ) -> Result<ResolvedSuggestions, AdapterError> {
+        self.resolve_suggestions(document_id, Resolution::Apply, Some(ids))
+    }
 
-        // Restore red text to normal (remove color and strikethrough)
-        let ranges_to_restore_len = ranges_to_restore.len();
-        for (start_idx, end_idx) in ranges_to_restore {
-            // Adjust indices based on deletions
-            let mut adjusted_start = start_idx;
-            let mut adjusted_end = end_idx;
-            for (del_start, del_end) in &ranges_to_delete {
-                if *del_end <= start_idx {
-                    let deleted_length = del_end - del_start;
-                    adjusted_start -= deleted_length;
-                    adjusted_end -= deleted_length;
-                }
-            }
+    /// Discard all suggestions tracked for the document: delete suggested
+    /// insertions and restore the original style of suggested deletions.
+    pub fn discard_suggestions(
+        &self,
+        document_id: &str,
+    ) -> Result<ResolvedSuggestions, AdapterError> {
+        self.resolve_suggestions(document_id, Resolution::Discard, None)
+    }
 
-            requests.push(serde_json::json!({
-                "updateTextStyle": {
-                    "range": {
-                        "startIndex": adjusted_start,
-                        "endIndex": adjusted_end
-                    },
-                    "textStyle": {
-                        "foregroundColor": {},  // Reset to default
-                        "strikethrough": false
-                    },
-                    "fields": "foregroundColor,strikethrough"
-                }
-            }));
+    fn resolve_suggestions(
+        &self,
+        document_id: &str,
+        resolution: Resolution,
+        only: Option<&[String]>,
+    ) -> Result<ResolvedSuggestions, AdapterError> {
+        let mut state = SuggestionState::load(document_id)?;
+        let (selected, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut state.suggestions)
+            .into_iter()
+            .partition(|suggestion| only.is_none_or(|ids| ids.contains(&suggestion.id)));
+        state.suggestions = rest;
+        if selected.is_empty() {
+            return Ok(ResolvedSuggestions::default());
         }
 
-        if !requests.is_empty() {
-            self.apply_document_edit(document_id, requests)?;
-            info!(
-                "Discarded suggestions: deleted {} ranges, restored {} ranges",
-                ranges_to_delete.len(),
-                ranges_to_restore_len
-            );
+        let (deleted, outcome) = self.edit_at_current_revision(document_id, |doc| {
+            let plan = plan_resolution(doc, &selected, resolution);
+            Ok((plan.requests, (plan.deleted, plan.outcome)))
+        })?;
+        for (start_idx, end_idx) in &deleted {
+            state.shift_for_delete(*start_idx, *end_idx);
         }
-
-        Ok(())
+        state.save()?;
+        info!(
+            "{:?} suggestions on {}: {} resolved, {} no longer found",
+            resolution,
+            document_id,
+            outcome.resolved,
+            outcome.missing.len()
+        );
+        Ok(outcome)
     }
 
     /// Get existing styles from the document, useful for maintaining consistent formatting.
@@ -903,12 +631,6 @@ impl GoogleDocsOutboundAdapter {
         bold: Option<bool>,
         italic: Option<bool>,
     ) -> Result<(), AdapterError> {
-        let position = self.find_text_position(document_id, text_to_style)?;
-
-        let (start_idx, end_idx) = position.ok_or_else(|| {
-            AdapterError::SendError(format!("Text not found in document: '{}'", text_to_style))
-        })?;
-
         let mut text_style = serde_json::Map::new();
         let mut fields = Vec::new();
 
@@ -976,18 +698,20 @@ impl GoogleDocsOutboundAdapter {
             ));
         }
 
-        let requests = vec![serde_json::json!({
-            "updateTextStyle": {
-                "range": {
-                    "startIndex": start_idx,
-                    "endIndex": end_idx
-                },
-                "textStyle": text_style,
-                "fields": fields.join(",")
-            }
-        })];
-
-        self.apply_document_edit(document_id, requests)?;
+        let (start_idx, end_idx) = self.edit_at_current_revision(document_id, |doc| {
+            let (start_idx, end_idx) = find_anchor(doc, text_to_style)?;
+            let requests = vec![serde_json::json!({
+                "updateTextStyle": {
+                    "range": {
+                        "startIndex": start_idx,
+                        "endIndex": end_idx
+                    },
+                    "textStyle": text_style,
+                    "fields": fields.join(",")
+                }
+            })];
+            Ok((requests, (start_idx, end_idx)))
+        })?;
         info!(
             "Applied style to '{}' at indices {}-{}: fields={:?}",
             text_to_style, start_idx, end_idx, fields
@@ -1017,33 +741,7 @@ impl GoogleDocsOutboundAdapter {
         width_pt: Option<f64>,
         height_pt: Option<f64>,
     ) -> Result<String, AdapterError> {
-        let mut request = serde_json::json!({
-            "insertInlineImage": {
-                "uri": image_url,
-                "location": {
-                    "index": index
-                }
-            }
-        });
-
-        // Add objectSize if dimensions are specified
-        if width_pt.is_some() || height_pt.is_some() {
-            let mut size = serde_json::Map::new();
-            if let Some(w) = width_pt {
-                size.insert(
-                    "width".to_string(),
-                    serde_json::json!({ "magnitude": w, "unit": "PT" }),
-                );
-            }
-            if let Some(h) = height_pt {
-                size.insert(
-                    "height".to_string(),
-                    serde_json::json!({ "magnitude": h, "unit": "PT" }),
-                );
-            }
-            request["insertInlineImage"]["objectSize"] = serde_json::Value::Object(size);
-        }
-
+        let request = inline_image_request(image_url, index, width_pt, height_pt);
         self.apply_document_edit(document_id, vec![request])?;
 
         info!(
@@ -1067,13 +765,17 @@ impl GoogleDocsOutboundAdapter {
         width_pt: Option<f64>,
         height_pt: Option<f64>,
     ) -> Result<String, AdapterError> {
-        let position = self.find_text_position(document_id, after_text)?;
-
-        let (_, end_idx) = position.ok_or_else(|| {
-            AdapterError::SendError(format!("Anchor text not found: '{}'", after_text))
+        let index = self.edit_at_current_revision(document_id, |doc| {
+            let (_, end_idx) = find_anchor(doc, after_text)?;
+            let request = inline_image_request(image_url, end_idx, width_pt, height_pt);
+            Ok((vec![request], end_idx))
         })?;
 
-        self.insert_image(document_id, image_url, end_idx, width_pt, height_pt)
+        info!(
+            "Inserted image from {} after '{}' (index {}) in document {}",
+            image_url, after_text, index, document_id
+        );
+        Ok(format!("image_at_index_{}", index))
     }
 
     /// Create a new document.
@@ -1150,6 +852,53 @@ impl GoogleDocsOutboundAdapter {
     }
 }
 
+/// Range of `text` in the document, or `AdapterError::TextNotFound`.
+fn find_anchor(doc: &DocText, text: &str) -> Result<(i64, i64), AdapterError> {
+    doc.find(text)
+        .ok_or_else(|| AdapterError::TextNotFound(text.to_string()))
+}
+
+/// Whether a failed batchUpdate was rejected because `requiredRevisionId`
+/// no longer matches the document.
+fn is_revision_mismatch(status: u16, body: &str) -> bool {
+    status == 400 && body.to_ascii_lowercase().contains("revision")
+}
+
+fn inline_image_request(
+    image_url: &str,
+    index: i64,
+    width_pt: Option<f64>,
+    height_pt: Option<f64>,
+) -> serde_json::Value {
+    let mut request = serde_json::json!({
+        "insertInlineImage": {
+            "uri": image_url,
+            "location": {
+                "index": index
+            }
+        }
+    });
+
+    // Add objectSize if dimensions are specified
+    if width_pt.is_some() || height_pt.is_some() {
+        let mut size = serde_json::Map::new();
+        if let Some(w) = width_pt {
+            size.insert(
+                "width".to_string(),
+                serde_json::json!({ "magnitude": w, "unit": "PT" }),
+            );
+        }
+        if let Some(h) = height_pt {
+            size.insert(
+                "height".to_string(),
+                serde_json::json!({ "magnitude": h, "unit": "PT" }),
+            );
+        }
+        request["insertInlineImage"]["objectSize"] = serde_json::Value::Object(size);
+    }
+    request
+}
+
 impl OutboundAdapter for GoogleDocsOutboundAdapter {
     fn send(&self, message: &OutboundMessage) -> Result<SendResult, AdapterError> {
         let document_id = message
@@ -1187,3 +936,37 @@ impl OutboundAdapter for GoogleDocsOutboundAdapter {
         Channel::GoogleDocs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_revision_mismatch_errors() {
+        let body = r#"{"error": {"code": 400, "message": "The required revision ID 'ALm37BV' does not match the latest revision.", "status": "INVALID_ARGUMENT"}}"#;
+        assert!(is_revision_mismatch(400, body));
+        assert!(!is_revision_mismatch(
+            400,
+            r#"{"error": {"message": "Invalid requests[0].insertText: Index 90 must be less than the end index"}}"#
+        ));
+        assert!(!is_revision_mismatch(403, body));
+    }
+
+    #[test]
+    fn missing_anchor_is_reported_as_text_not_found() {
+        let doc = DocText::from_document(&serde_json::json!({
+            "revisionId": "rev-1",
+            "body": {"content": [{"paragraph": {"elements": [{
+                "startIndex": 1,
+                "endIndex": 13,
+                "textRun": {"content": "Hello world\n", "textStyle": {}}
+            }]}}]}
+        }));
+        assert_eq!(doc.revision_id.as_deref(), Some("rev-1"));
+        assert_eq!(find_anchor(&doc, "world").unwrap(), (7, 12));
+        assert!(matches!(
+            find_anchor(&doc, "planet"),
+            Err(AdapterError::TextNotFound(text)) if text == "planet"
+        ));
+    }
+}
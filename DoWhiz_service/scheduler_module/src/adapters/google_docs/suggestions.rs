@@ -0,0 +1,684 @@
+//! Sidecar state for suggesting-mode edits.
+//!
+//! The Docs API cannot create real suggestions, so suggested insertions are
+//! shown in blue and suggested deletions in red strikethrough. Which ranges
+//! are suggestions is recorded in a state file per document, together with
+//! their text, the text around them and the style they had before marking.
+//! Apply and discard find each range again from that record, never from its
+//! color, so text the user colored themselves is left alone and edits made
+//! since the suggestion only move it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::channel::AdapterError;
+
+/// Characters of surrounding text kept to find a suggestion again.
+const CONTEXT_CHARS: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    Insertion,
+    Deletion,
+}
+
+/// Style of part of a suggestion before it was marked, restored when the
+/// marking is removed. `offset` and `length` are in document index units
+/// (UTF-16) from the start of the suggestion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StyleRun {
+    pub offset: i64,
+    pub length: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub foreground_color: Option<Value>,
+    #[serde(default)]
+    pub strikethrough: bool,
+}
+
+/// One suggested insertion or deletion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackedSuggestion {
+    pub id: String,
+    pub kind: SuggestionKind,
+    /// Range when last written; only a hint once the document has changed.
+    pub start_index: i64,
+    pub end_index: i64,
+    pub text: String,
+    pub context_before: String,
+    pub context_after: String,
+    pub original_style: Vec<StyleRun>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TrackedSuggestion {
+    /// Track existing text `[start, end)` as a suggested deletion.
+    pub(super) fn deletion(doc: &DocText, start: i64, end: i64) -> Self {
+        let (context_before, context_after) = doc.context(start, end);
+        Self {
+            id: Uuid::new_v4().simple().to_string(),
+            kind: SuggestionKind::Deletion,
+            start_index: start,
+            end_index: end,
+            text: doc.text(start, end),
+            context_before,
+            context_after,
+            original_style: doc.style_runs(start, end),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Track `text`, about to be inserted at `at`, as a suggested insertion
+    /// that takes `base` style once applied.
+    pub(super) fn insertion(doc: &DocText, at: i64, text: &str, base: StyleRun) -> Self {
+        let (context_before, context_after) = doc.context(at, at);
+        let length = utf16_len(text);
+        Self {
+            id: Uuid::new_v4().simple().to_string(),
+            kind: SuggestionKind::Insertion,
+            start_index: at,
+            end_index: at + length,
+            text: text.to_string(),
+            context_before,
+            context_after,
+            original_style: vec![StyleRun {
+                offset: 0,
+                length,
+                ..base
+            }],
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Every pending suggestion on one document.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SuggestionState {
+    pub document_id: String,
+    #[serde(default)]
+    pub suggestions: Vec<TrackedSuggestion>,
+}
+
+impl SuggestionState {
+    /// Load the state of `document_id` from `GOOGLE_DOCS_SUGGESTIONS_DIR`
+    /// (default `~/.dowhiz/DoWhiz/google_docs/suggestions`).
+    pub fn load(document_id: &str) -> Result<Self, AdapterError> {
+        Self::load_from(&suggestions_dir(), document_id)
+    }
+
+    fn load_from(dir: &Path, document_id: &str) -> Result<Self, AdapterError> {
+        let path = state_path(dir, document_id);
+        match fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw)
+                .map_err(|err| AdapterError::ParseError(format!("{}: {}", path.display(), err))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self {
+                document_id: document_id.to_string(),
+                suggestions: Vec::new(),
+            }),
+            Err(err) => Err(AdapterError::ConfigError(format!(
+                "failed to read {}: {}",
+                path.display(),
+                err
+            ))),
+        }
+    }
+
+    /// Write the state back; removes the file once nothing is pending.
+    pub fn save(&self) -> Result<(), AdapterError> {
+        self.save_to(&suggestions_dir())
+    }
+
+    fn save_to(&self, dir: &Path) -> Result<(), AdapterError> {
+        let path = state_path(dir, &self.document_id);
+        let io_error = |err: std::io::Error| {
+            AdapterError::ConfigError(format!("failed to write {}: {}", path.display(), err))
+        };
+        if self.suggestions.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(io_error(err)),
+                _ => Ok(()),
+            };
+        }
+        fs::create_dir_all(dir).map_err(io_error)?;
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|err| AdapterError::ParseError(err.to_string()))?;
+        fs::write(&path, json).map_err(io_error)
+    }
+
+    /// Move recorded ranges after `length` units were inserted at `at`.
+    pub(super) fn shift_for_insert(&mut self, at: i64, length: i64) {
+        for suggestion in &mut self.suggestions {
+            if suggestion.start_index >= at {
+                suggestion.start_index += length;
+                suggestion.end_index += length;
+            } else if suggestion.end_index > at {
+                suggestion.end_index += length;
+            }
+        }
+    }
+
+    /// Move recorded ranges after `[start, end)` was deleted.
+    pub(super) fn shift_for_delete(&mut self, start: i64, end: i64) {
+        for suggestion in &mut self.suggestions {
+            if suggestion.start_index >= end {
+                suggestion.start_index -= end - start;
+                suggestion.end_index -= end - start;
+            }
+        }
+    }
+
+    /// Style that text inserted at `at` should end up with: that of the
+    /// character before it, as it was before any suggestion marked it.
+    pub(super) fn base_style_at(&self, doc: &DocText, at: i64) -> StyleRun {
+        let tracked = self.suggestions.iter().find_map(|suggestion| {
+            let offset = at - 1 - suggestion.start_index;
+            if suggestion.kind != SuggestionKind::Deletion
+                || offset < 0
+                || at > suggestion.end_index
+            {
+                return None;
+            }
+            suggestion
+                .original_style
+                .iter()
+                .find(|run| run.offset <= offset && offset < run.offset + run.length)
+                .cloned()
+        });
+        tracked
+            .or_else(|| doc.style_runs(at - 1, at).into_iter().next())
+            .unwrap_or(StyleRun {
+                offset: 0,
+                length: 0,
+                foreground_color: None,
+                strikethrough: false,
+            })
+    }
+}
+
+fn suggestions_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("GOOGLE_DOCS_SUGGESTIONS_DIR") {
+        if !dir.trim().is_empty() {
+            return PathBuf::from(dir.trim());
+        }
+    }
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home)
+        .join(".dowhiz")
+        .join("DoWhiz")
+        .join("google_docs")
+        .join("suggestions")
+}
+
+fn state_path(dir: &Path, document_id: &str) -> PathBuf {
+    let name: String = document_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("{}.json", name))
+}
+
+fn utf16_len(text: &str) -> i64 {
+    text.encode_utf16().count() as i64
+}
+
+/// The text of a document with the index of every character.
+#[derive(Debug, Default)]
+pub(super) struct DocText {
+    /// Revision the text was read at, for `requiredRevisionId`.
+    pub revision_id: Option<String>,
+    chars: Vec<char>,
+    indices: Vec<i64>,
+    /// Text style of the run each character belongs to.
+    styles: Vec<usize>,
+    run_styles: Vec<Value>,
+}
+
+impl DocText {
+    /// Collect the text runs of a `documents.get` response, tables included.
+    pub(super) fn from_document(doc: &Value) -> Self {
+        let mut text = Self {
+            revision_id: doc
+                .get("revisionId")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            ..Self::default()
+        };
+        if let Some(content) = doc
+            .get("body")
+            .and_then(|body| body.get("content"))
+            .and_then(Value::as_array)
+        {
+            text.collect(content);
+        }
+        text
+    }
+
+    fn collect(&mut self, content: &[Value]) {
+        for element in content {
+            if let Some(elements) = element
+                .get("paragraph")
+                .and_then(|paragraph| paragraph.get("elements"))
+                .and_then(Value::as_array)
+            {
+                for elem in elements {
+                    let Some(run) = elem.get("textRun") else {
+                        continue;
+                    };
+                    let Some(content) = run.get("content").and_then(Value::as_str) else {
+                        continue;
+                    };
+                    let mut index = elem.get("startIndex").and_then(Value::as_i64).unwrap_or(0);
+                    self.run_styles
+                        .push(run.get("textStyle").cloned().unwrap_or(Value::Null));
+                    for c in content.chars() {
+                        self.chars.push(c);
+                        self.indices.push(index);
+                        self.styles.push(self.run_styles.len() - 1);
+                        index += c.len_utf16() as i64;
+                    }
+                }
+            }
+            if let Some(rows) = element
+                .get("table")
+                .and_then(|table| table.get("tableRows"))
+                .and_then(Value::as_array)
+            {
+                for row in rows {
+                    for cell in row
+                        .get("tableCells")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                    {
+                        if let Some(content) = cell.get("content").and_then(Value::as_array) {
+                            self.collect(content);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Range of the first occurrence of `needle`.
+    pub(super) fn find(&self, needle: &str) -> Option<(i64, i64)> {
+        let needle: Vec<char> = needle.chars().collect();
+        self.occurrences(&needle)
+            .first()
+            .map(|pos| self.span(*pos, needle.len()))
+    }
+
+    fn occurrences(&self, needle: &[char]) -> Vec<usize> {
+        if needle.is_empty() || needle.len() > self.chars.len() {
+            return Vec::new();
+        }
+        (0..=self.chars.len() - needle.len())
+            .filter(|pos| self.chars[*pos..*pos + needle.len()] == *needle)
+            .collect()
+    }
+
+    fn span(&self, pos: usize, len: usize) -> (i64, i64) {
+        let last = pos + len - 1;
+        (
+            self.indices[pos],
+            self.indices[last] + self.chars[last].len_utf16() as i64,
+        )
+    }
+
+    /// Position of the first character at or after `index`.
+    fn position(&self, index: i64) -> usize {
+        self.indices.partition_point(|at| *at < index)
+    }
+
+    pub(super) fn text(&self, start: i64, end: i64) -> String {
+        let from = self.position(start);
+        let to = self.position(end);
+        self.chars[from..to.max(from)].iter().collect()
+    }
+
+    fn context(&self, start: i64, end: i64) -> (String, String) {
+        let from = self.position(start);
+        let to = self.position(end);
+        let before = self.chars[from.saturating_sub(CONTEXT_CHARS)..from]
+            .iter()
+            .collect();
+        let after = self.chars[to..(to + CONTEXT_CHARS).min(self.chars.len())]
+            .iter()
+            .collect();
+        (before, after)
+    }
+
+    fn style_runs(&self, start: i64, end: i64) -> Vec<StyleRun> {
+        let mut runs: Vec<StyleRun> = Vec::new();
+        let mut last_style = None;
+        for pos in self.position(start)..self.position(end) {
+            let length = self.chars[pos].len_utf16() as i64;
+            if last_style == Some(self.styles[pos]) {
+                if let Some(run) = runs.last_mut() {
+                    run.length += length;
+                }
+                continue;
+            }
+            last_style = Some(self.styles[pos]);
+            let style = &self.run_styles[self.styles[pos]];
+            runs.push(StyleRun {
+                offset: self.indices[pos] - start,
+                length,
+                foreground_color: style.get("foregroundColor").cloned(),
+                strikethrough: style
+                    .get("strikethrough")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            });
+        }
+        runs
+    }
+
+    /// Current range of `suggestion`: the recorded range if it still holds
+    /// the text, else the occurrence of the text nearest to it whose
+    /// surrounding text still matches on at least one side.
+    pub(super) fn locate(&self, suggestion: &TrackedSuggestion) -> Option<(i64, i64)> {
+        if self.text(suggestion.start_index, suggestion.end_index) == suggestion.text {
+            return Some((suggestion.start_index, suggestion.end_index));
+        }
+        let needle: Vec<char> = suggestion.text.chars().collect();
+        let before: Vec<char> = suggestion.context_before.chars().collect();
+        let after: Vec<char> = suggestion.context_after.chars().collect();
+        self.occurrences(&needle)
+            .into_iter()
+            .filter(|pos| {
+                let before_matches =
+                    *pos >= before.len() && self.chars[pos - before.len()..*pos] == *before;
+                let end = pos + needle.len();
+                let after_matches = end + after.len() <= self.chars.len()
+                    && self.chars[end..end + after.len()] == *after;
+                before_matches || after_matches
+            })
+            .map(|pos| self.span(pos, needle.len()))
+            .min_by_key(|(start, _)| (start - suggestion.start_index).abs())
+    }
+}
+
+/// Whether pending suggestions are accepted or rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Resolution {
+    Apply,
+    Discard,
+}
+
+/// Outcome of applying or discarding suggestions.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedSuggestions {
+    pub resolved: usize,
+    /// Suggestions whose text could no longer be found; they are dropped.
+    pub missing: Vec<TrackedSuggestion>,
+}
+
+/// Batch requests resolving `suggestions`, and the ranges they delete.
+/// Styles are restored first, then ranges are deleted from the end of the
+/// document backwards, so no request shifts another's indices.
+pub(super) struct ResolutionPlan {
+    pub requests: Vec<Value>,
+    pub deleted: Vec<(i64, i64)>,
+    pub outcome: ResolvedSuggestions,
+}
+
+pub(super) fn plan_resolution(
+    doc: &DocText,
+    suggestions: &[TrackedSuggestion],
+    resolution: Resolution,
+) -> ResolutionPlan {
+    let mut claimed: Vec<(i64, i64)> = Vec::new();
+    let mut restores: Vec<Value> = Vec::new();
+    let mut deleted: Vec<(i64, i64)> = Vec::new();
+    let mut outcome = ResolvedSuggestions::default();
+
+    for suggestion in suggestions {
+        let located = doc.locate(suggestion).filter(|(start, end)| {
+            !claimed
+                .iter()
+                .any(|(other_start, other_end)| start < other_end && other_start < end)
+        });
+        let Some((start, end)) = located else {
+            outcome.missing.push(suggestion.clone());
+            continue;
+        };
+        claimed.push((start, end));
+        outcome.resolved += 1;
+
+        let remove = matches!(
+            (resolution, suggestion.kind),
+            (Resolution::Apply, SuggestionKind::Deletion)
+                | (Resolution::Discard, SuggestionKind::Insertion)
+        );
+        if remove {
+            deleted.push((start, end));
+        } else {
+            restores.extend(suggestion.original_style.iter().map(|run| {
+                style_request(
+                    start + run.offset,
+                    (start + run.offset + run.length).min(end),
+                    run.foreground_color.as_ref(),
+                    run.strikethrough,
+                )
+            }));
+        }
+    }
+
+    deleted.sort_by_key(|(start, _)| std::cmp::Reverse(*start));
+    let mut requests = restores;
+    requests.extend(deleted.iter().map(|(start, end)| {
+        json!({
+            "deleteContentRange": {
+                "range": {"startIndex": start, "endIndex": end}
+            }
+        })
+    }));
+    ResolutionPlan {
+        requests,
+        deleted,
+        outcome,
+    }
+}
+
+/// Red strikethrough marking a suggested deletion.
+pub(super) fn deletion_mark_request(start: i64, end: i64) -> Value {
+    style_request(start, end, Some(&rgb_color(1.0, 0.0, 0.0)), true)
+}
+
+/// Blue marking a suggested insertion.
+pub(super) fn insertion_mark_request(start: i64, end: i64) -> Value {
+    style_request(start, end, Some(&rgb_color(0.0, 0.0, 1.0)), false)
+}
+
+fn rgb_color(red: f64, green: f64, blue: f64) -> Value {
+    json!({"color": {"rgbColor": {"red": red, "green": green, "blue": blue}}})
+}
+
+/// Set foreground color and strikethrough on `[start, end)`; no color resets
+/// it to the document default.
+fn style_request(
+    start: i64,
+    end: i64,
+    foreground_color: Option<&Value>,
+    strikethrough: bool,
+) -> Value {
+    let mut text_style = json!({ "strikethrough": strikethrough });
+    if let Some(color) = foreground_color {
+        text_style["foregroundColor"] = color.clone();
+    }
+    json!({
+        "updateTextStyle": {
+            "range": {"startIndex": start, "endIndex": end},
+            "textStyle": text_style,
+            "fields": "foregroundColor,strikethrough"
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(runs: &[(&str, Value)]) -> Value {
+        let mut index = 1;
+        let elements: Vec<Value> = runs
+            .iter()
+            .map(|(text, style)| {
+                let start = index;
+                index += utf16_len(text);
+                json!({
+                    "startIndex": start,
+                    "endIndex": index,
+                    "textRun": {"content": text, "textStyle": style}
+                })
+            })
+            .collect();
+        json!({"body": {"content": [{"paragraph": {"elements": elements}}]}})
+    }
+
+    fn green() -> Value {
+        rgb_color(0.0, 0.6, 0.0)
+    }
+
+    #[test]
+    fn finds_text_in_utf16_indices() {
+        let doc = DocText::from_document(&document(&[
+            ("Emoji 😀 then ", json!({})),
+            ("plain text\n", json!({})),
+        ]));
+        // The emoji takes two index units.
+        assert_eq!(doc.find("then"), Some((10, 14)));
+        assert_eq!(doc.find("plain"), Some((15, 20)));
+        assert_eq!(doc.text(15, 20), "plain");
+        assert_eq!(doc.find("missing"), None);
+    }
+
+    #[test]
+    fn deletion_keeps_the_original_style_of_each_run() {
+        let doc = DocText::from_document(&document(&[
+            ("Keep ", json!({})),
+            ("green", json!({"foregroundColor": green()})),
+            (" words\n", json!({})),
+        ]));
+        let (start, end) = doc.find("green words").unwrap();
+        let suggestion = TrackedSuggestion::deletion(&doc, start, end);
+        assert_eq!(suggestion.text, "green words");
+        assert_eq!(suggestion.context_before, "Keep ");
+        assert_eq!(
+            suggestion.original_style,
+            vec![
+                StyleRun {
+                    offset: 0,
+                    length: 5,
+                    foreground_color: Some(green()),
+                    strikethrough: false,
+                },
+                StyleRun {
+                    offset: 5,
+                    length: 6,
+                    foreground_color: None,
+                    strikethrough: false,
+                },
+            ]
+        );
+
+        let plan = plan_resolution(&doc, &[suggestion], Resolution::Discard);
+        assert_eq!(plan.outcome.resolved, 1);
+        assert!(plan.deleted.is_empty());
+        assert_eq!(
+            plan.requests[0]["updateTextStyle"]["textStyle"]["foregroundColor"],
+            green()
+        );
+        assert!(plan.requests[1]["updateTextStyle"]["textStyle"]
+            .get("foregroundColor")
+            .is_none());
+    }
+
+    #[test]
+    fn suggestions_are_found_again_after_the_document_moves() {
+        let before = DocText::from_document(&document(&[("The cat sat on the mat.\n", json!({}))]));
+        let (start, end) = before.find("sat").unwrap();
+        let deletion = TrackedSuggestion::deletion(&before, start, end);
+
+        // The user typed a sentence in front and colored the text blue.
+        let after = DocText::from_document(&document(&[
+            ("Intro. ", json!({})),
+            (
+                "The cat sat on the mat.\n",
+                json!({"foregroundColor": rgb_color(0.0, 0.0, 1.0)}),
+            ),
+        ]));
+        assert_eq!(after.locate(&deletion), Some((start + 7, end + 7)));
+
+        let plan = plan_resolution(&after, std::slice::from_ref(&deletion), Resolution::Apply);
+        assert_eq!(plan.deleted, vec![(start + 7, end + 7)]);
+        assert_eq!(plan.requests.len(), 1);
+
+        // Gone entirely, or only present without matching context.
+        let gone = DocText::from_document(&document(&[("Nothing was here, sat.\n", json!({}))]));
+        let plan = plan_resolution(&gone, &[deletion], Resolution::Apply);
+        assert_eq!(plan.outcome.resolved, 0);
+        assert_eq!(plan.outcome.missing.len(), 1);
+        assert!(plan.requests.is_empty());
+    }
+
+    #[test]
+    fn replacement_resolves_without_index_shifts() {
+        let doc = DocText::from_document(&document(&[("One two three\n", json!({}))]));
+        let (start, end) = doc.find("two").unwrap();
+        let mut state = SuggestionState {
+            document_id: "doc".to_string(),
+            suggestions: vec![TrackedSuggestion::deletion(&doc, start, end)],
+        };
+        let base = state.base_style_at(&doc, end);
+        let insertion = TrackedSuggestion::insertion(&doc, end, "2", base);
+        state.shift_for_insert(end, 1);
+        state.suggestions.push(insertion);
+
+        let marked = DocText::from_document(&document(&[("One two2 three\n", json!({}))]));
+        let plan = plan_resolution(&marked, &state.suggestions, Resolution::Apply);
+        assert_eq!(plan.outcome.resolved, 2);
+        assert_eq!(plan.deleted, vec![(5, 8)]);
+        // Style restore for the insertion comes before the deletion.
+        assert_eq!(
+            plan.requests[0]["updateTextStyle"]["range"],
+            json!({"startIndex": 8, "endIndex": 9})
+        );
+        assert!(plan.requests[1].get("deleteContentRange").is_some());
+
+        let plan = plan_resolution(&marked, &state.suggestions, Resolution::Discard);
+        assert_eq!(plan.deleted, vec![(8, 9)]);
+    }
+
+    #[test]
+    fn state_round_trips_and_is_removed_when_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let doc = DocText::from_document(&document(&[("Hello world\n", json!({}))]));
+        let mut state = SuggestionState::load_from(dir.path(), "doc/1").unwrap();
+        assert!(state.suggestions.is_empty());
+        state
+            .suggestions
+            .push(TrackedSuggestion::deletion(&doc, 7, 12));
+        state.save_to(dir.path()).unwrap();
+        assert!(dir.path().join("doc_1.json").exists());
+        assert_eq!(
+            SuggestionState::load_from(dir.path(), "doc/1").unwrap(),
+            state
+        );
+
+        state.suggestions.clear();
+        state.save_to(dir.path()).unwrap();
+        assert!(!dir.path().join("doc_1.json").exists());
+    }
+}
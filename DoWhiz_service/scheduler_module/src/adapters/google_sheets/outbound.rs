@@ -2,13 +2,25 @@
 
 use tracing::{error, info};
 
-use crate::channel::{AdapterError, Channel, OutboundAdapter, OutboundMessage, SendResult};
+use crate::channel::{
+    AdapterError, Channel, OutboundAdapter, OutboundMessage, SendResult, SheetEdit,
+};
 use crate::google_auth::GoogleAuth;
 
 use super::super::google_common::{CommentReply, GoogleCommentsClient};
 use super::super::google_docs::contains_employee_mention;
 use std::collections::HashSet;
 
+const DEFAULT_SHEETS_API_BASE_URL: &str = "https://sheets.googleapis.com";
+
+fn sheets_api_base() -> String {
+    std::env::var("GOOGLE_SHEETS_API_BASE_URL")
+        .ok()
+        .map(|value| value.trim_end_matches('/').to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_SHEETS_API_BASE_URL.to_string())
+}
+
 /// Adapter for posting replies to Google Sheets comments and editing spreadsheets.
 #[derive(Debug, Clone)]
 pub struct GoogleSheetsOutboundAdapter {
@@ -58,7 +70,8 @@ impl GoogleSheetsOutboundAdapter {
         let client = reqwest::blocking::Client::new();
 
         let url = format!(
-            "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}?valueInputOption=USER_ENTERED",
+            "{}/v4/spreadsheets/{}/values/{}?valueInputOption=USER_ENTERED",
+            sheets_api_base(),
             spreadsheet_id,
             urlencoding::encode(range)
         );
@@ -109,7 +122,8 @@ impl GoogleSheetsOutboundAdapter {
         let client = reqwest::blocking::Client::new();
 
         let url = format!(
-            "https://sheets.googleapis.com/v4/spreadsheets/{}:batchUpdate",
+            "{}/v4/spreadsheets/{}:batchUpdate",
+            sheets_api_base(),
             spreadsheet_id
         );
 
@@ -158,7 +172,8 @@ impl GoogleSheetsOutboundAdapter {
         let client = reqwest::blocking::Client::new();
 
         let url = format!(
-            "https://sheets.googleapis.com/v4/spreadsheets/{}/values/{}:append?valueInputOption=USER_ENTERED&insertDataOption=INSERT_ROWS",
+            "{}/v4/spreadsheets/{}/values/{}:append?valueInputOption=USER_ENTERED&insertDataOption=INSERT_ROWS",
+            sheets_api_base(),
             spreadsheet_id,
             urlencoding::encode(range)
         );
@@ -195,6 +210,76 @@ impl GoogleSheetsOutboundAdapter {
         Ok(())
     }
 
+    /// Read the cells of `range` as rows of formatted values.
+    pub fn read_range(
+        &self,
+        spreadsheet_id: &str,
+        range: &str,
+    ) -> Result<Vec<Vec<serde_json::Value>>, AdapterError> {
+        let access_token = self
+            .auth
+            .get_access_token()
+            .map_err(|e| AdapterError::ConfigError(e.to_string()))?;
+
+        let client = reqwest::blocking::Client::new();
+
+        let url = format!(
+            "{}/v4/spreadsheets/{}/values/{}",
+            sheets_api_base(),
+            spreadsheet_id,
+            urlencoding::encode(range)
+        );
+
+        let response = client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", access_token))
+            .send()
+            .map_err(|e| AdapterError::SendError(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().unwrap_or_default();
+            error!(
+                "Failed to read range {} of spreadsheet {}: {} - {}",
+                range, spreadsheet_id, status, body
+            );
+            return Err(AdapterError::SendError(format!(
+                "HTTP {}: {}",
+                status, body
+            )));
+        }
+
+        let body: serde_json::Value = response
+            .json()
+            .map_err(|e| AdapterError::ParseError(e.to_string()))?;
+        // Sheets omits `values` for an empty range.
+        Ok(body
+            .get("values")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default())
+    }
+
+    /// Apply `edits` in order, stopping at the first that fails.
+    pub fn apply_edits(
+        &self,
+        spreadsheet_id: &str,
+        edits: &[SheetEdit],
+    ) -> Result<(), AdapterError> {
+        for edit in edits {
+            match edit {
+                SheetEdit::Append { range, values } => {
+                    self.append_rows(spreadsheet_id, range, values.clone())?
+                }
+                SheetEdit::Update { range, values } => {
+                    self.update_values(spreadsheet_id, range, values.clone())?
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Get spreadsheet metadata (sheet names, properties, etc.).
     pub fn get_spreadsheet_metadata(
         &self,
@@ -208,7 +293,8 @@ impl GoogleSheetsOutboundAdapter {
         let client = reqwest::blocking::Client::new();
 
         let url = format!(
-            "https://sheets.googleapis.com/v4/spreadsheets/{}?fields=properties,sheets.properties",
+            "{}/v4/spreadsheets/{}?fields=properties,sheets.properties",
+            sheets_api_base(),
             spreadsheet_id
         );
 
@@ -245,11 +331,22 @@ impl OutboundAdapter for GoogleSheetsOutboundAdapter {
             .as_ref()
             .ok_or_else(|| AdapterError::ConfigError("Missing spreadsheet ID".to_string()))?;
 
-        let comment_id = message
-            .metadata
-            .google_sheets_comment_id
-            .as_ref()
-            .ok_or_else(|| AdapterError::ConfigError("Missing comment ID".to_string()))?;
+        let edits = &message.metadata.google_sheets_edits;
+        let comment_id = match message.metadata.google_sheets_comment_id.as_ref() {
+            Some(comment_id) => Some(comment_id),
+            None if !edits.is_empty() => None,
+            None => return Err(AdapterError::ConfigError("Missing comment ID".to_string())),
+        };
+
+        self.apply_edits(spreadsheet_id, edits)?;
+        let Some(comment_id) = comment_id else {
+            return Ok(SendResult {
+                success: true,
+                message_id: spreadsheet_id.clone(),
+                submitted_at: chrono::Utc::now().to_rfc3339(),
+                error: None,
+            });
+        };
 
         let reply_content = if !message.text_body.is_empty() {
             &message.text_body
@@ -273,3 +370,105 @@ impl OutboundAdapter for GoogleSheetsOutboundAdapter {
         Channel::GoogleSheets
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::ChannelMetadata;
+    use crate::google_auth::GoogleAuthConfig;
+    use serde_json::json;
+    use serial_test::serial;
+
+    fn adapter() -> GoogleSheetsOutboundAdapter {
+        let auth = GoogleAuth::new(GoogleAuthConfig {
+            access_token: Some("test_token".to_string()),
+            ..Default::default()
+        })
+        .expect("auth");
+        GoogleSheetsOutboundAdapter::new(auth)
+    }
+
+    #[test]
+    #[serial]
+    fn send_applies_edits_without_a_comment() {
+        let mut server = mockito::Server::new();
+        let append = server
+            .mock(
+                "POST",
+                "/v4/spreadsheets/sheet1/values/Expenses%21A%3AC:append",
+            )
+            .match_query(mockito::Matcher::Any)
+            .match_body(mockito::Matcher::Json(json!({
+                "values": [["2026-10-16", "Taxi", 23.5]]
+            })))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let update = server
+            .mock("PUT", "/v4/spreadsheets/sheet1/values/Summary%21B2")
+            .match_query(mockito::Matcher::Any)
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        std::env::set_var("GOOGLE_SHEETS_API_BASE_URL", server.url());
+
+        let message = OutboundMessage {
+            channel: Channel::GoogleSheets,
+            from: None,
+            to: vec![],
+            cc: vec![],
+            bcc: vec![],
+            subject: String::new(),
+            text_body: String::new(),
+            html_body: String::new(),
+            html_path: None,
+            attachments_dir: None,
+            thread_id: None,
+            metadata: ChannelMetadata {
+                google_sheets_spreadsheet_id: Some("sheet1".to_string()),
+                google_sheets_edits: vec![
+                    SheetEdit::Append {
+                        range: "Expenses!A:C".to_string(),
+                        values: vec![vec![json!("2026-10-16"), json!("Taxi"), json!(23.5)]],
+                    },
+                    SheetEdit::Update {
+                        range: "Summary!B2".to_string(),
+                        values: vec![vec![json!("=SUM(Expenses!C:C)")]],
+                    },
+                ],
+                ..Default::default()
+            },
+        };
+        let result = adapter().send(&message);
+        std::env::remove_var("GOOGLE_SHEETS_API_BASE_URL");
+
+        append.assert();
+        update.assert();
+        assert_eq!(result.expect("send").message_id, "sheet1");
+    }
+
+    #[test]
+    fn send_needs_a_comment_or_edits() {
+        let message = OutboundMessage {
+            channel: Channel::GoogleSheets,
+            from: None,
+            to: vec![],
+            cc: vec![],
+            bcc: vec![],
+            subject: String::new(),
+            text_body: "hello".to_string(),
+            html_body: String::new(),
+            html_path: None,
+            attachments_dir: None,
+            thread_id: None,
+            metadata: ChannelMetadata {
+                google_sheets_spreadsheet_id: Some("sheet1".to_string()),
+                ..Default::default()
+            },
+        };
+        assert!(matches!(
+            adapter().send(&message),
+            Err(AdapterError::ConfigError(_))
+        ));
+    }
+}
@@ -0,0 +1,879 @@
+//! Jira Cloud adapter for issue webhooks and issue changes via the REST API.
+//!
+//! This module provides adapters for working with Jira issues:
+//! - `JiraInboundAdapter`: Parses issue and comment webhooks that mention the
+//!   employee or assign an issue to them
+//! - `JiraOutboundAdapter`: Comments on, transitions, creates and edits issues
+//!   via the REST API v3
+
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::info;
+
+use super::google_docs::contains_employee_mention;
+use crate::channel::{
+    AdapterError, Channel, ChannelMetadata, InboundAdapter, InboundMessage, OutboundAdapter,
+    OutboundMessage, SendResult,
+};
+
+/// Jira site and API credentials for one employee.
+///
+/// Each setting is read from `{EMPLOYEE}_JIRA_*` first (e.g.
+/// `OLIVER_JIRA_API_TOKEN`), then from the global `JIRA_*`.
+#[derive(Debug, Clone)]
+pub struct JiraConfig {
+    /// Site URL, e.g. `https://acme.atlassian.net`
+    pub base_url: String,
+    /// Atlassian account email the API token belongs to
+    pub email: String,
+    pub api_token: String,
+}
+
+impl JiraConfig {
+    pub fn from_env_for_employee(employee_id: Option<&str>) -> Result<Self, AdapterError> {
+        let setting = |name: &str| {
+            jira_env(employee_id, name)
+                .ok_or_else(|| AdapterError::ConfigError(format!("{} not set", name)))
+        };
+        Ok(Self {
+            base_url: setting("JIRA_BASE_URL")?.trim_end_matches('/').to_string(),
+            email: setting("JIRA_EMAIL")?,
+            api_token: setting("JIRA_API_TOKEN")?,
+        })
+    }
+
+    /// Browser link to an issue.
+    pub fn issue_url(&self, issue_key: &str) -> String {
+        format!("{}/browse/{}", self.base_url, issue_key)
+    }
+}
+
+/// The employee's own Jira account ID (`{EMPLOYEE}_JIRA_ACCOUNT_ID`, then
+/// `JIRA_ACCOUNT_ID`), used to spot mentions and skip their own events.
+pub fn resolve_jira_account_id(employee_id: Option<&str>) -> Option<String> {
+    jira_env(employee_id, "JIRA_ACCOUNT_ID")
+}
+
+fn jira_env(employee_id: Option<&str>, name: &str) -> Option<String> {
+    let employee_key =
+        employee_id.map(|emp_id| format!("{}_{}", emp_id.to_uppercase().replace('-', "_"), name));
+    employee_key
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(name))
+        .find_map(|key| {
+            std::env::var(key)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        })
+}
+
+// ============================================================================
+// Inbound
+// ============================================================================
+
+/// Adapter for parsing Jira issue and comment webhooks.
+///
+/// Accepts new comments that mention the employee, new issues that mention
+/// or are assigned to them, and issue updates that assign the issue to them
+/// or add a mention to the description. Everything else, including events
+/// the employee caused, is rejected with a parse error.
+#[derive(Debug, Clone, Default)]
+pub struct JiraInboundAdapter {
+    /// The employee's Jira account ID; without it only name mentions count.
+    pub account_id: Option<String>,
+}
+
+impl JiraInboundAdapter {
+    pub fn new(account_id: Option<String>) -> Self {
+        Self { account_id }
+    }
+
+    fn mentions_employee(&self, text: &JiraText) -> bool {
+        let by_account = self
+            .account_id
+            .as_ref()
+            .is_some_and(|own| text.mentions.iter().any(|id| id == own));
+        by_account || contains_employee_mention(&text.text)
+    }
+
+    fn is_employee(&self, user: Option<&JiraUser>) -> bool {
+        match (
+            self.account_id.as_deref(),
+            user.and_then(|user| user.account_id.as_deref()),
+        ) {
+            (Some(own), Some(account_id)) => own == account_id,
+            _ => false,
+        }
+    }
+
+    fn assigned_to_employee(&self, webhook: &JiraWebhook, issue: &JiraIssue) -> bool {
+        let Some(own) = self.account_id.as_deref() else {
+            return false;
+        };
+        if webhook.webhook_event == "jira:issue_created" {
+            return self.is_employee(issue.fields.assignee.as_ref());
+        }
+        webhook
+            .changed_fields()
+            .any(|item| item.field == "assignee" && item.to.as_deref() == Some(own))
+    }
+}
+
+impl InboundAdapter for JiraInboundAdapter {
+    fn parse(&self, raw_payload: &[u8]) -> Result<InboundMessage, AdapterError> {
+        let webhook: JiraWebhook = serde_json::from_slice(raw_payload)?;
+        let issue = webhook
+            .issue
+            .as_ref()
+            .ok_or(AdapterError::MissingField("issue"))?;
+
+        let (author, text, message_id, comment_id) = match webhook.webhook_event.as_str() {
+            "comment_created" => {
+                let comment = webhook
+                    .comment
+                    .as_ref()
+                    .ok_or(AdapterError::MissingField("comment"))?;
+                let text = JiraText::from_body(&comment.body);
+                if !self.mentions_employee(&text) {
+                    return Err(AdapterError::ParseError(
+                        "comment does not mention the employee".to_string(),
+                    ));
+                }
+                (
+                    comment.author.as_ref(),
+                    text,
+                    format!("comment:{}", comment.id),
+                    Some(comment.id.clone()),
+                )
+            }
+            "jira:issue_created" | "jira:issue_updated" => {
+                let text = issue
+                    .fields
+                    .description
+                    .as_ref()
+                    .map(JiraText::from_body)
+                    .unwrap_or_default();
+                let is_new = webhook.webhook_event == "jira:issue_created";
+                let description_changed = is_new
+                    || webhook
+                        .changed_fields()
+                        .any(|item| item.field == "description");
+                let relevant = self.assigned_to_employee(&webhook, issue)
+                    || (description_changed && self.mentions_employee(&text));
+                if !relevant {
+                    return Err(AdapterError::ParseError(
+                        "issue is not assigned to and does not mention the employee".to_string(),
+                    ));
+                }
+                (
+                    webhook.user.as_ref(),
+                    text,
+                    format!(
+                        "{}:{}:{}",
+                        webhook.webhook_event,
+                        issue.id,
+                        webhook.timestamp.unwrap_or_default()
+                    ),
+                    None,
+                )
+            }
+            other => {
+                return Err(AdapterError::ParseError(format!(
+                    "unsupported webhook event: {}",
+                    other
+                )))
+            }
+        };
+        if self.is_employee(author) {
+            return Err(AdapterError::ParseError(
+                "event was caused by the employee".to_string(),
+            ));
+        }
+
+        let sender = author
+            .and_then(|user| user.account_id.clone().or(user.email_address.clone()))
+            .unwrap_or_else(|| "unknown".to_string());
+        let summary = issue.fields.summary.as_deref().unwrap_or_default();
+        let text_body = if text.text.trim().is_empty() {
+            summary.to_string()
+        } else {
+            text.text
+        };
+
+        Ok(InboundMessage {
+            channel: Channel::Jira,
+            sender,
+            sender_name: author.and_then(|user| user.display_name.clone()),
+            recipient: webhook.project_key().unwrap_or_default(),
+            subject: Some(format!("[{}] {}", issue.key, summary)),
+            text_body: Some(text_body),
+            html_body: None,
+            thread_id: format!("jira:{}", issue.key),
+            message_id: Some(message_id),
+            attachments: vec![],
+            reply_to: vec![issue.key.clone()],
+            raw_payload: raw_payload.to_vec(),
+            metadata: ChannelMetadata {
+                jira_issue_key: Some(issue.key.clone()),
+                jira_issue_url: issue.browse_url(),
+                jira_comment_id: comment_id,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn channel(&self) -> Channel {
+        Channel::Jira
+    }
+}
+
+/// Plain text of a Jira body plus the account IDs it mentions.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct JiraText {
+    text: String,
+    mentions: Vec<String>,
+}
+
+impl JiraText {
+    /// Read a body in wiki markup (webhooks) or Atlassian Document Format
+    /// (REST API v3).
+    fn from_body(body: &Value) -> Self {
+        let mut out = Self::default();
+        match body {
+            Value::String(markup) => {
+                out.text = markup.clone();
+                out.mentions = markup
+                    .split("[~accountid:")
+                    .skip(1)
+                    .filter_map(|rest| rest.split_once(']'))
+                    .map(|(account_id, _)| account_id.trim().to_string())
+                    .collect();
+            }
+            Value::Object(_) => {
+                out.push_adf(body);
+                out.text = out.text.trim_end().to_string();
+            }
+            _ => {}
+        }
+        out
+    }
+
+    fn push_adf(&mut self, node: &Value) {
+        let node_type = node.get("type").and_then(Value::as_str);
+        let attr = |name: &str| {
+            node.get("attrs")
+                .and_then(|attrs| attrs.get(name))
+                .and_then(Value::as_str)
+        };
+        match node_type {
+            Some("text") => self
+                .text
+                .push_str(node.get("text").and_then(Value::as_str).unwrap_or_default()),
+            Some("mention") => {
+                if let Some(account_id) = attr("id") {
+                    self.mentions.push(account_id.to_string());
+                }
+                self.text.push_str(attr("text").unwrap_or("@someone"));
+            }
+            Some("hardBreak") => self.text.push('\n'),
+            _ => {}
+        }
+        for child in node
+            .get("content")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            self.push_adf(child);
+        }
+        if matches!(node_type, Some("paragraph" | "heading" | "codeBlock")) {
+            self.text.push('\n');
+        }
+    }
+}
+
+// ============================================================================
+// Outbound
+// ============================================================================
+
+/// A new issue for [`JiraOutboundAdapter::create_issue`].
+#[derive(Debug, Clone, Default)]
+pub struct JiraIssueDraft {
+    /// Project key, e.g. `OPS`
+    pub project: String,
+    pub summary: String,
+    pub description: Option<String>,
+    /// Issue type name; `Task` when unset
+    pub issue_type: Option<String>,
+    pub labels: Vec<String>,
+}
+
+/// Changes for [`JiraOutboundAdapter::update_issue`]: field edits first,
+/// then the transition, then the comment.
+#[derive(Debug, Clone, Default)]
+pub struct JiraIssueUpdate {
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    /// Replaces the issue's labels
+    pub labels: Option<Vec<String>>,
+    /// Transition name or target status, e.g. `Done`
+    pub transition: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl JiraIssueUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.summary.is_none()
+            && self.description.is_none()
+            && self.labels.is_none()
+            && self.transition.is_none()
+            && self.comment.is_none()
+    }
+}
+
+/// Adapter for Jira Cloud's REST API v3, authenticated with an API token.
+#[derive(Debug, Clone)]
+pub struct JiraOutboundAdapter {
+    config: JiraConfig,
+    client: reqwest::blocking::Client,
+}
+
+impl JiraOutboundAdapter {
+    pub fn new(config: JiraConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    pub fn config(&self) -> &JiraConfig {
+        &self.config
+    }
+
+    fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value, AdapterError> {
+        let url = format!("{}/rest/api/3/{}", self.config.base_url, path);
+        let mut request = self
+            .client
+            .request(method, &url)
+            .basic_auth(&self.config.email, Some(&self.config.api_token))
+            .header("Accept", "application/json");
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request
+            .send()
+            .map_err(|e| AdapterError::SendError(format!("Jira request failed: {}", e)))?;
+        let status = response.status();
+        let text = response.text().unwrap_or_default();
+        if !status.is_success() {
+            return Err(AdapterError::SendError(format!(
+                "Jira API error {} for {}: {}",
+                status, path, text
+            )));
+        }
+        if text.trim().is_empty() {
+            return Ok(Value::Null);
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Post `text` as a comment on `issue_key`; returns the comment ID.
+    pub fn add_comment(&self, issue_key: &str, text: &str) -> Result<String, AdapterError> {
+        let response = self.request(
+            Method::POST,
+            &format!("issue/{}/comment", issue_key),
+            Some(json!({ "body": adf_document(text) })),
+        )?;
+        Ok(response["id"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Move `issue_key` through the transition named `transition`, or the one
+    /// leading to the status of that name; returns the new status.
+    pub fn transition_issue(
+        &self,
+        issue_key: &str,
+        transition: &str,
+    ) -> Result<String, AdapterError> {
+        let path = format!("issue/{}/transitions", issue_key);
+        let available = self.request(Method::GET, &path, None)?;
+        let transitions = available["transitions"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let wanted = transition.trim();
+        let chosen = transitions
+            .iter()
+            .find(|candidate| {
+                [
+                    &candidate["id"],
+                    &candidate["name"],
+                    &candidate["to"]["name"],
+                ]
+                .iter()
+                .any(|value| {
+                    value
+                        .as_str()
+                        .is_some_and(|v| v.eq_ignore_ascii_case(wanted))
+                })
+            })
+            .ok_or_else(|| {
+                let names: Vec<&str> = transitions
+                    .iter()
+                    .filter_map(|candidate| candidate["name"].as_str())
+                    .collect();
+                AdapterError::SendError(format!(
+                    "{} has no transition '{}' (available: {})",
+                    issue_key,
+                    wanted,
+                    names.join(", ")
+                ))
+            })?;
+        self.request(
+            Method::POST,
+            &path,
+            Some(json!({ "transition": { "id": chosen["id"] } })),
+        )?;
+        let status = chosen["to"]["name"]
+            .as_str()
+            .or(chosen["name"].as_str())
+            .unwrap_or(wanted)
+            .to_string();
+        info!("moved Jira issue {} to {}", issue_key, status);
+        Ok(status)
+    }
+
+    /// Create an issue; returns its key.
+    pub fn create_issue(&self, draft: &JiraIssueDraft) -> Result<String, AdapterError> {
+        let mut fields = json!({
+            "project": { "key": draft.project.trim() },
+            "summary": draft.summary.trim(),
+            "issuetype": { "name": draft.issue_type.as_deref().unwrap_or("Task") },
+        });
+        if let Some(description) = draft.description.as_deref() {
+            fields["description"] = adf_document(description);
+        }
+        if !draft.labels.is_empty() {
+            fields["labels"] = json!(draft.labels);
+        }
+        let response = self.request(Method::POST, "issue", Some(json!({ "fields": fields })))?;
+        let key = response["key"]
+            .as_str()
+            .ok_or(AdapterError::MissingField("key"))?
+            .to_string();
+        info!("created Jira issue {}", key);
+        Ok(key)
+    }
+
+    /// Apply `update` to `issue_key`.
+    pub fn update_issue(
+        &self,
+        issue_key: &str,
+        update: &JiraIssueUpdate,
+    ) -> Result<(), AdapterError> {
+        let mut fields = serde_json::Map::new();
+        if let Some(summary) = update.summary.as_deref() {
+            fields.insert("summary".to_string(), json!(summary.trim()));
+        }
+        if let Some(description) = update.description.as_deref() {
+            fields.insert("description".to_string(), adf_document(description));
+        }
+        if let Some(labels) = update.labels.as_ref() {
+            fields.insert("labels".to_string(), json!(labels));
+        }
+        if !fields.is_empty() {
+            self.request(
+                Method::PUT,
+                &format!("issue/{}", issue_key),
+                Some(json!({ "fields": fields })),
+            )?;
+        }
+        if let Some(transition) = update.transition.as_deref() {
+            self.transition_issue(issue_key, transition)?;
+        }
+        if let Some(comment) = update.comment.as_deref() {
+            self.add_comment(issue_key, comment)?;
+        }
+        Ok(())
+    }
+}
+
+impl OutboundAdapter for JiraOutboundAdapter {
+    fn send(&self, message: &OutboundMessage) -> Result<SendResult, AdapterError> {
+        let issue_key = message
+            .metadata
+            .jira_issue_key
+            .as_deref()
+            .or(message.to.first().map(String::as_str))
+            .ok_or(AdapterError::MissingField("jira_issue_key"))?;
+        let text = if message.text_body.trim().is_empty() {
+            message.html_body.as_str()
+        } else {
+            message.text_body.as_str()
+        };
+
+        let comment_id = self.add_comment(issue_key, text)?;
+        info!(
+            "commented on Jira issue {}, comment_id={}",
+            issue_key, comment_id
+        );
+
+        Ok(SendResult {
+            success: true,
+            message_id: comment_id,
+            submitted_at: chrono::Utc::now().to_rfc3339(),
+            error: None,
+        })
+    }
+
+    fn channel(&self) -> Channel {
+        Channel::Jira
+    }
+}
+
+/// Plain text as an Atlassian Document Format document: one paragraph per
+/// blank-line-separated block, with line breaks kept.
+fn adf_document(text: &str) -> Value {
+    let paragraphs: Vec<Value> = text
+        .trim()
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(|block| {
+            let mut content = Vec::new();
+            for (index, line) in block.trim().lines().enumerate() {
+                if index > 0 {
+                    content.push(json!({ "type": "hardBreak" }));
+                }
+                if !line.is_empty() {
+                    content.push(json!({ "type": "text", "text": line }));
+                }
+            }
+            json!({ "type": "paragraph", "content": content })
+        })
+        .collect();
+    json!({ "type": "doc", "version": 1, "content": paragraphs })
+}
+
+// ============================================================================
+// Jira API Types
+// ============================================================================
+
+/// Jira webhook body for issue and comment events.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraWebhook {
+    pub webhook_event: String,
+    #[serde(default)]
+    pub timestamp: Option<i64>,
+    #[serde(default)]
+    pub issue: Option<JiraIssue>,
+    #[serde(default)]
+    pub comment: Option<JiraComment>,
+    /// Who caused the event
+    #[serde(default)]
+    pub user: Option<JiraUser>,
+    #[serde(default)]
+    pub changelog: Option<JiraChangelog>,
+}
+
+impl JiraWebhook {
+    /// Key of the issue's project, used as the gateway route key.
+    pub fn project_key(&self) -> Option<String> {
+        let issue = self.issue.as_ref()?;
+        issue
+            .fields
+            .project
+            .as_ref()
+            .map(|project| project.key.clone())
+            .or_else(|| {
+                issue
+                    .key
+                    .split_once('-')
+                    .map(|(project, _)| project.to_string())
+            })
+    }
+
+    fn changed_fields(&self) -> impl Iterator<Item = &JiraChangeItem> {
+        self.changelog
+            .iter()
+            .flat_map(|changelog| changelog.items.iter())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JiraIssue {
+    pub id: String,
+    pub key: String,
+    /// REST URL of the issue, e.g. `https://acme.atlassian.net/rest/api/2/issue/10001`
+    #[serde(rename = "self", default)]
+    pub self_url: Option<String>,
+    #[serde(default)]
+    pub fields: JiraIssueFields,
+}
+
+impl JiraIssue {
+    /// Browser link to the issue, derived from its REST URL.
+    pub fn browse_url(&self) -> Option<String> {
+        let (site, _) = self.self_url.as_deref()?.split_once("/rest/")?;
+        Some(format!("{}/browse/{}", site, self.key))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JiraIssueFields {
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// Wiki markup or Atlassian Document Format
+    #[serde(default)]
+    pub description: Option<Value>,
+    #[serde(default)]
+    pub status: Option<JiraStatus>,
+    #[serde(default)]
+    pub project: Option<JiraProject>,
+    #[serde(default)]
+    pub assignee: Option<JiraUser>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JiraStatus {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JiraProject {
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraUser {
+    #[serde(default)]
+    pub account_id: Option<String>,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub email_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JiraComment {
+    pub id: String,
+    /// Wiki markup or Atlassian Document Format
+    #[serde(default)]
+    pub body: Value,
+    #[serde(default)]
+    pub author: Option<JiraUser>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JiraChangelog {
+    #[serde(default)]
+    pub items: Vec<JiraChangeItem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JiraChangeItem {
+    pub field: String,
+    /// New value's ID, e.g. the assignee's account ID
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPLOYEE: &str = "5b10ac8d82e05b22cc7d4ef5";
+
+    fn adapter() -> JiraInboundAdapter {
+        JiraInboundAdapter::new(Some(EMPLOYEE.to_string()))
+    }
+
+    fn comment_webhook(body: Value, author: &str) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "timestamp": 1760000000000i64,
+            "webhookEvent": "comment_created",
+            "issue": {
+                "id": "10001",
+                "key": "OPS-42",
+                "self": "https://acme.atlassian.net/rest/api/2/issue/10001",
+                "fields": { "summary": "Renew TLS cert", "project": { "key": "OPS" } }
+            },
+            "comment": {
+                "id": "10050",
+                "body": body,
+                "author": { "accountId": author, "displayName": "Ann Lee" }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_comment_mentioning_employee() {
+        let raw = comment_webhook(
+            json!(format!("[~accountid:{}] can you take this?", EMPLOYEE)),
+            "ann",
+        );
+        let message = adapter().parse(&raw).unwrap();
+
+        assert_eq!(message.channel, Channel::Jira);
+        assert_eq!(message.sender, "ann");
+        assert_eq!(message.sender_name.as_deref(), Some("Ann Lee"));
+        assert_eq!(message.recipient, "OPS");
+        assert_eq!(message.subject.as_deref(), Some("[OPS-42] Renew TLS cert"));
+        assert_eq!(message.thread_id, "jira:OPS-42");
+        assert_eq!(message.message_id.as_deref(), Some("comment:10050"));
+        assert_eq!(message.reply_to, vec!["OPS-42".to_string()]);
+        assert_eq!(message.metadata.jira_issue_key.as_deref(), Some("OPS-42"));
+        assert_eq!(message.metadata.jira_comment_id.as_deref(), Some("10050"));
+        assert_eq!(
+            message.metadata.jira_issue_url.as_deref(),
+            Some("https://acme.atlassian.net/browse/OPS-42")
+        );
+    }
+
+    #[test]
+    fn parse_adf_comment_renders_mentions() {
+        let body = json!({
+            "type": "doc",
+            "version": 1,
+            "content": [
+                { "type": "paragraph", "content": [
+                    { "type": "mention", "attrs": { "id": EMPLOYEE, "text": "@Oliver" } },
+                    { "type": "text", "text": " please triage" }
+                ]},
+                { "type": "paragraph", "content": [{ "type": "text", "text": "Thanks" }] }
+            ]
+        });
+        let message = adapter().parse(&comment_webhook(body, "ann")).unwrap();
+        assert_eq!(
+            message.text_body.as_deref(),
+            Some("@Oliver please triage\nThanks")
+        );
+    }
+
+    #[test]
+    fn ignores_comments_without_mention_or_by_employee() {
+        let unrelated = comment_webhook(json!("looks good to me"), "ann");
+        assert!(adapter().parse(&unrelated).is_err());
+
+        let own = comment_webhook(json!(format!("[~accountid:{}] done", EMPLOYEE)), EMPLOYEE);
+        assert!(adapter().parse(&own).is_err());
+    }
+
+    #[test]
+    fn parse_issue_assigned_to_employee() {
+        let raw = serde_json::to_vec(&json!({
+            "timestamp": 1760000000000i64,
+            "webhookEvent": "jira:issue_updated",
+            "issue": {
+                "id": "10002",
+                "key": "OPS-43",
+                "fields": { "summary": "Rotate keys", "description": null }
+            },
+            "user": { "accountId": "ann" },
+            "changelog": { "items": [{ "field": "assignee", "to": EMPLOYEE }] }
+        }))
+        .unwrap();
+        let message = adapter().parse(&raw).unwrap();
+        assert_eq!(message.recipient, "OPS");
+        assert_eq!(message.text_body.as_deref(), Some("Rotate keys"));
+        assert_eq!(
+            message.message_id.as_deref(),
+            Some("jira:issue_updated:10002:1760000000000")
+        );
+
+        let status_change = serde_json::to_vec(&json!({
+            "webhookEvent": "jira:issue_updated",
+            "issue": { "id": "10002", "key": "OPS-43", "fields": {
+                "description": format!("[~accountid:{}] FYI", EMPLOYEE)
+            }},
+            "changelog": { "items": [{ "field": "status", "to": "3" }] }
+        }))
+        .unwrap();
+        assert!(adapter().parse(&status_change).is_err());
+    }
+
+    #[test]
+    fn adf_document_splits_paragraphs_and_lines() {
+        assert_eq!(
+            adf_document("Done.\nDeployed to prod.\n\nThanks"),
+            json!({ "type": "doc", "version": 1, "content": [
+                { "type": "paragraph", "content": [
+                    { "type": "text", "text": "Done." },
+                    { "type": "hardBreak" },
+                    { "type": "text", "text": "Deployed to prod." }
+                ]},
+                { "type": "paragraph", "content": [{ "type": "text", "text": "Thanks" }] }
+            ]})
+        );
+    }
+
+    #[test]
+    fn update_issue_edits_fields_transitions_and_comments() {
+        let mut server = mockito::Server::new();
+        let edit = server
+            .mock("PUT", "/rest/api/3/issue/OPS-42")
+            .match_body(mockito::Matcher::Json(json!({
+                "fields": { "labels": ["ops"] }
+            })))
+            .with_status(204)
+            .create();
+        let list = server
+            .mock("GET", "/rest/api/3/issue/OPS-42/transitions")
+            .with_status(200)
+            .with_body(
+                json!({ "transitions": [
+                    { "id": "11", "name": "Start progress", "to": { "name": "In Progress" } },
+                    { "id": "31", "name": "Resolve", "to": { "name": "Done" } }
+                ]})
+                .to_string(),
+            )
+            .create();
+        let transition = server
+            .mock("POST", "/rest/api/3/issue/OPS-42/transitions")
+            .match_body(mockito::Matcher::Json(
+                json!({ "transition": { "id": "31" } }),
+            ))
+            .with_status(204)
+            .create();
+        let comment = server
+            .mock("POST", "/rest/api/3/issue/OPS-42/comment")
+            .with_status(201)
+            .with_body(r#"{"id":"10060"}"#)
+            .create();
+
+        let adapter = JiraOutboundAdapter::new(JiraConfig {
+            base_url: server.url(),
+            email: "oliver@example.com".to_string(),
+            api_token: "token".to_string(),
+        });
+        adapter
+            .update_issue(
+                "OPS-42",
+                &JiraIssueUpdate {
+                    labels: Some(vec!["ops".to_string()]),
+                    transition: Some("done".to_string()),
+                    comment: Some("Renewed.".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        edit.assert();
+        list.assert();
+        transition.assert();
+        comment.assert();
+    }
+}
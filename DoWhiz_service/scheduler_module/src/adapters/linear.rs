@@ -0,0 +1,295 @@
+//! Linear client for filing issues.
+//!
+//! `LinearClient` creates issues through Linear's GraphQL API. The agent
+//! names teams and labels the way people do ("ENG", "Bug"); the client looks
+//! up their IDs before creating the issue.
+
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::channel::AdapterError;
+
+const DEFAULT_LINEAR_API_URL: &str = "https://api.linear.app/graphql";
+
+/// A new issue for [`LinearClient::create_issue`].
+#[derive(Debug, Clone, Default)]
+pub struct LinearIssueDraft {
+    /// Team key or name, e.g. `ENG`
+    pub team: String,
+    pub title: String,
+    /// Markdown body
+    pub description: Option<String>,
+    /// Label names, matched case-insensitively against the team's and the
+    /// workspace's labels
+    pub labels: Vec<String>,
+    /// 0 = none, 1 = urgent, 2 = high, 3 = medium, 4 = low
+    pub priority: Option<u8>,
+}
+
+/// An issue created by [`LinearClient::create_issue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinearIssue {
+    /// Human-readable ID, e.g. `ENG-123`
+    pub identifier: String,
+    pub url: String,
+    /// Requested labels that do not exist; the issue was created without them
+    pub missing_labels: Vec<String>,
+}
+
+/// Linear GraphQL API client, authenticated with a personal API key.
+#[derive(Debug, Clone)]
+pub struct LinearClient {
+    api_key: String,
+    api_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl LinearClient {
+    /// `LINEAR_API_URL` overrides the API endpoint.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        let api_url = std::env::var("LINEAR_API_URL")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| DEFAULT_LINEAR_API_URL.to_string());
+        Self {
+            api_key: api_key.into(),
+            api_url,
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// Use `{EMPLOYEE}_LINEAR_API_KEY` (e.g. `OLIVER_LINEAR_API_KEY`), then
+    /// `LINEAR_API_KEY`.
+    pub fn from_env_for_employee(employee_id: Option<&str>) -> Result<Self, AdapterError> {
+        let employee_key = employee_id
+            .map(|emp_id| format!("{}_LINEAR_API_KEY", emp_id.to_uppercase().replace('-', "_")));
+        employee_key
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once("LINEAR_API_KEY"))
+            .find_map(|key| {
+                std::env::var(key)
+                    .ok()
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+            })
+            .map(Self::new)
+            .ok_or_else(|| AdapterError::ConfigError("LINEAR_API_KEY not set".to_string()))
+    }
+
+    fn graphql(&self, query: &str, variables: Value) -> Result<Value, AdapterError> {
+        let response = self
+            .client
+            .post(&self.api_url)
+            .header("Authorization", &self.api_key)
+            .json(&json!({ "query": query, "variables": variables }))
+            .send()
+            .map_err(|e| AdapterError::SendError(format!("Linear request failed: {}", e)))?;
+        let status = response.status();
+        let text = response.text().unwrap_or_default();
+        if !status.is_success() {
+            return Err(AdapterError::SendError(format!(
+                "Linear API error {}: {}",
+                status, text
+            )));
+        }
+        let mut body: Value = serde_json::from_str(&text)?;
+        if let Some(errors) = body.get("errors").and_then(Value::as_array) {
+            let messages: Vec<&str> = errors
+                .iter()
+                .filter_map(|error| error["message"].as_str())
+                .collect();
+            return Err(AdapterError::SendError(format!(
+                "Linear API error: {}",
+                messages.join("; ")
+            )));
+        }
+        Ok(body["data"].take())
+    }
+
+    /// ID of the team whose key or name is `team`.
+    fn team_id(&self, team: &str) -> Result<String, AdapterError> {
+        let data = self.graphql(
+            "query { teams(first: 250) { nodes { id key name } } }",
+            json!({}),
+        )?;
+        let wanted = team.trim();
+        data["teams"]["nodes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|node| {
+                [&node["id"], &node["key"], &node["name"]]
+                    .iter()
+                    .any(|value| {
+                        value
+                            .as_str()
+                            .is_some_and(|v| v.eq_ignore_ascii_case(wanted))
+                    })
+            })
+            .and_then(|node| node["id"].as_str())
+            .map(str::to_string)
+            .ok_or_else(|| AdapterError::SendError(format!("no Linear team named {}", wanted)))
+    }
+
+    /// IDs of the labels named in `labels` that `team_id` can use (its own and
+    /// workspace-wide ones), and the names that matched none.
+    fn label_ids(
+        &self,
+        team_id: &str,
+        labels: &[String],
+    ) -> Result<(Vec<String>, Vec<String>), AdapterError> {
+        if labels.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let data = self.graphql(
+            "query { issueLabels(first: 250) { nodes { id name team { id } } } }",
+            json!({}),
+        )?;
+        let available: Vec<&Value> = data["issueLabels"]["nodes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|node| node["team"].is_null() || node["team"]["id"].as_str() == Some(team_id))
+            .collect();
+        let mut ids = Vec::new();
+        let mut missing = Vec::new();
+        for label in labels {
+            let found = available.iter().find(|node| {
+                node["name"]
+                    .as_str()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(label.trim()))
+            });
+            match found.and_then(|node| node["id"].as_str()) {
+                Some(id) => ids.push(id.to_string()),
+                None => missing.push(label.clone()),
+            }
+        }
+        Ok((ids, missing))
+    }
+
+    /// Create an issue from `draft`. Labels that do not exist are left off
+    /// and reported in [`LinearIssue::missing_labels`].
+    pub fn create_issue(&self, draft: &LinearIssueDraft) -> Result<LinearIssue, AdapterError> {
+        if let Some(priority) = draft.priority.filter(|priority| *priority > 4) {
+            return Err(AdapterError::SendError(format!(
+                "priority must be 0-4, got {}",
+                priority
+            )));
+        }
+        let team_id = self.team_id(&draft.team)?;
+        let (label_ids, missing_labels) = self.label_ids(&team_id, &draft.labels)?;
+        if !missing_labels.is_empty() {
+            warn!(
+                "Linear labels not found in team {}: {}",
+                draft.team,
+                missing_labels.join(", ")
+            );
+        }
+
+        let mut input = json!({
+            "teamId": team_id,
+            "title": draft.title,
+        });
+        if let Some(description) = &draft.description {
+            input["description"] = json!(description);
+        }
+        if !label_ids.is_empty() {
+            input["labelIds"] = json!(label_ids);
+        }
+        if let Some(priority) = draft.priority {
+            input["priority"] = json!(priority);
+        }
+        let data = self.graphql(
+            "mutation($input: IssueCreateInput!) { issueCreate(input: $input) { success issue { identifier url } } }",
+            json!({ "input": input }),
+        )?;
+        let issue = &data["issueCreate"]["issue"];
+        let (Some(identifier), Some(url)) = (issue["identifier"].as_str(), issue["url"].as_str())
+        else {
+            return Err(AdapterError::SendError(
+                "Linear did not return the created issue".to_string(),
+            ));
+        };
+        info!("created Linear issue {} in team {}", identifier, draft.team);
+        Ok(LinearIssue {
+            identifier: identifier.to_string(),
+            url: url.to_string(),
+            missing_labels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_issue_resolves_team_and_labels() {
+        let mut server = mockito::Server::new();
+        let teams = server
+            .mock("POST", "/")
+            .match_header("authorization", "lin_api_test")
+            .match_body(mockito::Matcher::Regex("teams".to_string()))
+            .with_body(
+                json!({ "data": { "teams": { "nodes": [
+                    { "id": "team-ops", "key": "OPS", "name": "Operations" },
+                    { "id": "team-eng", "key": "ENG", "name": "Engineering" }
+                ] } } })
+                .to_string(),
+            )
+            .create();
+        let labels = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::Regex("issueLabels".to_string()))
+            .with_body(
+                json!({ "data": { "issueLabels": { "nodes": [
+                    { "id": "label-bug", "name": "Bug", "team": null },
+                    { "id": "label-ops-only", "name": "Frontend", "team": { "id": "team-ops" } }
+                ] } } })
+                .to_string(),
+            )
+            .create();
+        let create = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "variables": { "input": {
+                    "teamId": "team-eng",
+                    "title": "Login fails on Safari",
+                    "labelIds": ["label-bug"],
+                    "priority": 2
+                } }
+            })))
+            .with_body(
+                json!({ "data": { "issueCreate": { "success": true, "issue": {
+                    "identifier": "ENG-123",
+                    "url": "https://linear.app/acme/issue/ENG-123/login-fails-on-safari"
+                } } } })
+                .to_string(),
+            )
+            .create();
+
+        let mut client = LinearClient::new("lin_api_test");
+        client.api_url = server.url();
+        let issue = client
+            .create_issue(&LinearIssueDraft {
+                team: "eng".to_string(),
+                title: "Login fails on Safari".to_string(),
+                description: None,
+                labels: vec!["bug".to_string(), "Frontend".to_string()],
+                priority: Some(2),
+            })
+            .expect("create issue");
+
+        teams.assert();
+        labels.assert();
+        create.assert();
+        assert_eq!(issue.identifier, "ENG-123");
+        assert_eq!(
+            issue.url,
+            "https://linear.app/acme/issue/ENG-123/login-fails-on-safari"
+        );
+        assert_eq!(issue.missing_labels, vec!["Frontend".to_string()]);
+    }
+}
@@ -0,0 +1,426 @@
+//! Video meeting creation for the `create_meeting` scheduler action.
+//!
+//! - `GoogleCalendarClient`: Creates calendar events on the employee's primary
+//!   calendar, with a Google Meet conference or an external join link, and
+//!   invites the attendees
+//! - `ZoomClient`: Creates Zoom meetings with a Server-to-Server OAuth app
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::channel::AdapterError;
+use crate::google_auth::GoogleAuth;
+
+const DEFAULT_CALENDAR_API_BASE_URL: &str = "https://www.googleapis.com/calendar/v3";
+const DEFAULT_ZOOM_API_BASE_URL: &str = "https://api.zoom.us/v2";
+const DEFAULT_ZOOM_OAUTH_URL: &str = "https://zoom.us/oauth/token";
+
+fn env_or(name: &str, default: &str) -> String {
+    std::env::var(name)
+        .ok()
+        .map(|value| value.trim().trim_end_matches('/').to_string())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// Where the meeting is hosted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MeetingProvider {
+    #[default]
+    GoogleMeet,
+    Zoom,
+}
+
+impl std::fmt::Display for MeetingProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeetingProvider::GoogleMeet => write!(f, "Google Meet"),
+            MeetingProvider::Zoom => write!(f, "Zoom"),
+        }
+    }
+}
+
+/// A meeting to create.
+#[derive(Debug, Clone)]
+pub struct MeetingRequest {
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub duration_minutes: u32,
+    /// Emails that get a calendar invite
+    pub attendees: Vec<String>,
+    pub description: Option<String>,
+}
+
+impl MeetingRequest {
+    pub fn end(&self) -> DateTime<Utc> {
+        self.start + Duration::minutes(i64::from(self.duration_minutes))
+    }
+}
+
+/// A created calendar event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarEvent {
+    /// Link to the event in Google Calendar
+    pub html_link: String,
+    /// Google Meet join URL, when the event has a Meet conference
+    pub meet_url: Option<String>,
+}
+
+/// Google Calendar API client for the employee's primary calendar.
+#[derive(Debug, Clone)]
+pub struct GoogleCalendarClient {
+    auth: GoogleAuth,
+    api_base: String,
+    client: reqwest::blocking::Client,
+}
+
+impl GoogleCalendarClient {
+    /// `GOOGLE_CALENDAR_API_BASE_URL` overrides the API host.
+    pub fn new(auth: GoogleAuth) -> Self {
+        Self {
+            auth,
+            api_base: env_or(
+                "GOOGLE_CALENDAR_API_BASE_URL",
+                DEFAULT_CALENDAR_API_BASE_URL,
+            ),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// Create an event for `request` and email invites to its attendees. With
+    /// `join_url` the event points at that meeting; without it the event gets
+    /// a new Google Meet conference.
+    pub fn create_event(
+        &self,
+        request: &MeetingRequest,
+        join_url: Option<&str>,
+    ) -> Result<CalendarEvent, AdapterError> {
+        let access_token = self
+            .auth
+            .get_access_token()
+            .map_err(|e| AdapterError::ConfigError(e.to_string()))?;
+
+        let description = match (join_url, request.description.as_deref()) {
+            (Some(url), Some(text)) => Some(format!("{}\n\nJoin: {}", text, url)),
+            (Some(url), None) => Some(format!("Join: {}", url)),
+            (None, text) => text.map(str::to_string),
+        };
+        let mut event = json!({
+            "summary": request.title,
+            "start": { "dateTime": request.start.to_rfc3339() },
+            "end": { "dateTime": request.end().to_rfc3339() },
+            "attendees": request
+                .attendees
+                .iter()
+                .map(|email| json!({ "email": email }))
+                .collect::<Vec<_>>(),
+        });
+        if let Some(description) = description {
+            event["description"] = json!(description);
+        }
+        match join_url {
+            Some(url) => event["location"] = json!(url),
+            None => {
+                event["conferenceData"] = json!({
+                    "createRequest": {
+                        "requestId": Uuid::new_v4().to_string(),
+                        "conferenceSolutionKey": { "type": "hangoutsMeet" }
+                    }
+                })
+            }
+        }
+
+        let url = format!(
+            "{}/calendars/primary/events?conferenceDataVersion=1&sendUpdates=all",
+            self.api_base
+        );
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&access_token)
+            .json(&event)
+            .send()
+            .map_err(|e| AdapterError::SendError(format!("Calendar request failed: {}", e)))?;
+        let status = response.status();
+        let text = response.text().unwrap_or_default();
+        if !status.is_success() {
+            return Err(AdapterError::SendError(format!(
+                "Calendar API error {}: {}",
+                status, text
+            )));
+        }
+        let created: Value = serde_json::from_str(&text)?;
+        let meet_url = created["hangoutLink"]
+            .as_str()
+            .or_else(|| {
+                created["conferenceData"]["entryPoints"]
+                    .as_array()?
+                    .iter()
+                    .find(|entry| entry["entryPointType"] == "video")?["uri"]
+                    .as_str()
+            })
+            .map(str::to_string);
+        if join_url.is_none() && meet_url.is_none() {
+            return Err(AdapterError::SendError(
+                "Calendar did not return a Google Meet link".to_string(),
+            ));
+        }
+        info!(
+            "created calendar event {:?} at {}",
+            request.title, request.start
+        );
+        Ok(CalendarEvent {
+            html_link: created["htmlLink"].as_str().unwrap_or_default().to_string(),
+            meet_url,
+        })
+    }
+}
+
+/// Zoom API client using a Server-to-Server OAuth app.
+///
+/// Credentials are read from `{EMPLOYEE}_ZOOM_*` first (e.g.
+/// `OLIVER_ZOOM_CLIENT_SECRET`), then from the global `ZOOM_*`.
+#[derive(Debug, Clone)]
+pub struct ZoomClient {
+    account_id: String,
+    client_id: String,
+    client_secret: String,
+    api_base: String,
+    oauth_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl ZoomClient {
+    /// `ZOOM_API_BASE_URL` and `ZOOM_OAUTH_URL` override the API and token
+    /// endpoints.
+    pub fn from_env_for_employee(employee_id: Option<&str>) -> Result<Self, AdapterError> {
+        let setting = |name: &str| {
+            let employee_key = employee_id
+                .map(|emp_id| format!("{}_{}", emp_id.to_uppercase().replace('-', "_"), name));
+            employee_key
+                .iter()
+                .map(String::as_str)
+                .chain(std::iter::once(name))
+                .find_map(|key| {
+                    std::env::var(key)
+                        .ok()
+                        .map(|value| value.trim().to_string())
+                        .filter(|value| !value.is_empty())
+                })
+                .ok_or_else(|| AdapterError::ConfigError(format!("{} not set", name)))
+        };
+        Ok(Self {
+            account_id: setting("ZOOM_ACCOUNT_ID")?,
+            client_id: setting("ZOOM_CLIENT_ID")?,
+            client_secret: setting("ZOOM_CLIENT_SECRET")?,
+            api_base: env_or("ZOOM_API_BASE_URL", DEFAULT_ZOOM_API_BASE_URL),
+            oauth_url: env_or("ZOOM_OAUTH_URL", DEFAULT_ZOOM_OAUTH_URL),
+            client: reqwest::blocking::Client::new(),
+        })
+    }
+
+    fn access_token(&self) -> Result<String, AdapterError> {
+        let response = self
+            .client
+            .post(&self.oauth_url)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&[
+                ("grant_type", "account_credentials"),
+                ("account_id", self.account_id.as_str()),
+            ])
+            .send()
+            .map_err(|e| AdapterError::SendError(format!("Zoom token request failed: {}", e)))?;
+        let status = response.status();
+        let text = response.text().unwrap_or_default();
+        if !status.is_success() {
+            return Err(AdapterError::ConfigError(format!(
+                "Zoom token error {}: {}",
+                status, text
+            )));
+        }
+        let body: Value = serde_json::from_str(&text)?;
+        body["access_token"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(AdapterError::MissingField("access_token"))
+    }
+
+    /// Schedule a meeting for `request`; returns its join URL.
+    pub fn create_meeting(&self, request: &MeetingRequest) -> Result<String, AdapterError> {
+        let access_token = self.access_token()?;
+        let mut meeting = json!({
+            "topic": request.title,
+            "type": 2,
+            "start_time": request.start.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            "duration": request.duration_minutes,
+            "timezone": "UTC",
+        });
+        if let Some(description) = &request.description {
+            meeting["agenda"] = json!(description);
+        }
+        let response = self
+            .client
+            .post(format!("{}/users/me/meetings", self.api_base))
+            .bearer_auth(&access_token)
+            .json(&meeting)
+            .send()
+            .map_err(|e| AdapterError::SendError(format!("Zoom request failed: {}", e)))?;
+        let status = response.status();
+        let text = response.text().unwrap_or_default();
+        if !status.is_success() {
+            return Err(AdapterError::SendError(format!(
+                "Zoom API error {}: {}",
+                status, text
+            )));
+        }
+        let created: Value = serde_json::from_str(&text)?;
+        let join_url = created["join_url"]
+            .as_str()
+            .ok_or(AdapterError::MissingField("join_url"))?;
+        info!(
+            "created Zoom meeting {:?} at {}",
+            request.title, request.start
+        );
+        Ok(join_url.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::google_auth::GoogleAuthConfig;
+    use chrono::TimeZone;
+
+    fn request() -> MeetingRequest {
+        MeetingRequest {
+            title: "Quarterly review".to_string(),
+            start: Utc.with_ymd_and_hms(2026, 10, 20, 15, 0, 0).unwrap(),
+            duration_minutes: 45,
+            attendees: vec!["ana@example.com".to_string()],
+            description: None,
+        }
+    }
+
+    fn calendar(server: &mockito::Server) -> GoogleCalendarClient {
+        let auth = GoogleAuth::new(GoogleAuthConfig {
+            access_token: Some("test_token".to_string()),
+            ..Default::default()
+        })
+        .expect("auth");
+        let mut client = GoogleCalendarClient::new(auth);
+        client.api_base = server.url();
+        client
+    }
+
+    #[test]
+    fn create_event_requests_a_meet_conference() {
+        let mut server = mockito::Server::new();
+        let create = server
+            .mock("POST", "/calendars/primary/events")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("conferenceDataVersion".into(), "1".into()),
+                mockito::Matcher::UrlEncoded("sendUpdates".into(), "all".into()),
+            ]))
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "summary": "Quarterly review",
+                "end": { "dateTime": "2026-10-20T15:45:00+00:00" },
+                "attendees": [{ "email": "ana@example.com" }],
+                "conferenceData": { "createRequest": {
+                    "conferenceSolutionKey": { "type": "hangoutsMeet" }
+                } }
+            })))
+            .with_body(
+                json!({
+                    "htmlLink": "https://calendar.google.com/event?eid=abc",
+                    "hangoutLink": "https://meet.google.com/abc-defg-hij"
+                })
+                .to_string(),
+            )
+            .create();
+
+        let event = calendar(&server)
+            .create_event(&request(), None)
+            .expect("create event");
+
+        create.assert();
+        assert_eq!(
+            event.meet_url.as_deref(),
+            Some("https://meet.google.com/abc-defg-hij")
+        );
+        assert_eq!(event.html_link, "https://calendar.google.com/event?eid=abc");
+    }
+
+    #[test]
+    fn create_event_puts_an_external_link_in_the_invite() {
+        let mut server = mockito::Server::new();
+        let create = server
+            .mock("POST", "/calendars/primary/events")
+            .match_query(mockito::Matcher::Any)
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "location": "https://zoom.us/j/123",
+                "description": "Join: https://zoom.us/j/123"
+            })))
+            .with_body(
+                json!({ "htmlLink": "https://calendar.google.com/event?eid=def" }).to_string(),
+            )
+            .create();
+
+        let event = calendar(&server)
+            .create_event(&request(), Some("https://zoom.us/j/123"))
+            .expect("create event");
+
+        create.assert();
+        assert_eq!(event.meet_url, None);
+    }
+
+    #[test]
+    fn zoom_creates_meeting_with_account_credentials() {
+        let mut server = mockito::Server::new();
+        let token = server
+            .mock("POST", "/oauth/token")
+            .match_header(
+                "authorization",
+                format!(
+                    "Basic {}",
+                    base64::Engine::encode(
+                        &base64::engine::general_purpose::STANDARD,
+                        "client:secret"
+                    )
+                )
+                .as_str(),
+            )
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("grant_type".into(), "account_credentials".into()),
+                mockito::Matcher::UrlEncoded("account_id".into(), "acct".into()),
+            ]))
+            .with_body(json!({ "access_token": "zoom_token" }).to_string())
+            .create();
+        let create = server
+            .mock("POST", "/v2/users/me/meetings")
+            .match_header("authorization", "Bearer zoom_token")
+            .match_body(mockito::Matcher::PartialJson(json!({
+                "topic": "Quarterly review",
+                "type": 2,
+                "start_time": "2026-10-20T15:00:00Z",
+                "duration": 45
+            })))
+            .with_body(json!({ "join_url": "https://zoom.us/j/123" }).to_string())
+            .create();
+
+        let client = ZoomClient {
+            account_id: "acct".to_string(),
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+            api_base: format!("{}/v2", server.url()),
+            oauth_url: format!("{}/oauth/token", server.url()),
+            client: reqwest::blocking::Client::new(),
+        };
+        let join_url = client.create_meeting(&request()).expect("create meeting");
+
+        token.assert();
+        create.assert();
+        assert_eq!(join_url, "https://zoom.us/j/123");
+    }
+}
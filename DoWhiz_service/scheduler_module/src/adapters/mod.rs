@@ -10,6 +10,9 @@ pub mod google_docs;
 pub mod google_sheets;
 pub mod google_slides;
 pub mod image_search;
+pub mod jira;
+pub mod linear;
+pub mod meetings;
 pub mod postmark;
 pub mod slack;
 pub mod telegram;
@@ -23,16 +26,26 @@ pub use bluebubbles::{
 pub use discord::{DiscordInboundAdapter, DiscordOutboundAdapter};
 pub use google_common::{ActionableComment, GoogleComment, GoogleCommentsClient, GoogleFileType};
 pub use google_docs::{
-    contains_employee_mention, extract_employee_name, format_edit_proposal, GoogleDocsComment,
-    GoogleDocsInboundAdapter, GoogleDocsOutboundAdapter,
+    configure_employee_mentions, contains_employee_mention, extract_employee_name,
+    format_edit_proposal, GoogleDocsComment, GoogleDocsInboundAdapter, GoogleDocsOutboundAdapter,
 };
 pub use google_sheets::{GoogleSheetsInboundAdapter, GoogleSheetsOutboundAdapter};
 pub use google_slides::{GoogleSlidesInboundAdapter, GoogleSlidesOutboundAdapter};
 pub use image_search::{ImageResult, ImageUrls, SearchResponse, UnsplashClient};
+pub use jira::{
+    resolve_jira_account_id, JiraConfig, JiraInboundAdapter, JiraIssueDraft, JiraIssueUpdate,
+    JiraOutboundAdapter, JiraWebhook,
+};
+pub use linear::{LinearClient, LinearIssue, LinearIssueDraft};
+pub use meetings::{GoogleCalendarClient, MeetingProvider, MeetingRequest, ZoomClient};
 pub use postmark::{PostmarkInboundAdapter, PostmarkOutboundAdapter};
 pub use slack::{
-    is_url_verification, SlackChallengeResponse, SlackEventWrapper, SlackInboundAdapter,
-    SlackMessageEvent, SlackOutboundAdapter, SlackUrlVerification,
+    confirmation_blocks, is_url_verification, parse_interaction_payload, parse_slash_command,
+    parse_slash_command_text, post_to_response_url, resolve_slack_signing_secret,
+    verify_slack_signature, SlackChallengeResponse, SlackCommandAction, SlackEphemeralResponse,
+    SlackEventWrapper, SlackInboundAdapter, SlackInteractionPayload, SlackMessageEvent,
+    SlackOutboundAdapter, SlackReactionItem, SlackSignatureError, SlackSlashCommand,
+    SlackUrlVerification,
 };
 pub use telegram::{
     send_quick_telegram_response, TelegramInboundAdapter, TelegramOutboundAdapter, TelegramUpdate,
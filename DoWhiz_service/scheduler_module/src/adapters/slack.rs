@@ -3,26 +3,65 @@
 //! This module provides adapters for handling messages via Slack:
 //! - `SlackInboundAdapter`: Parses Slack event payloads
 //! - `SlackOutboundAdapter`: Sends messages via Slack Web API
+//! - `SlackSlashCommand`: Parses `/dowhiz` slash-command form payloads
+//! - `SlackInteractionPayload`: Parses button clicks and modal submissions
+//! - `verify_slack_signature`: Checks `X-Slack-Signature` against a signing secret
 
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use std::collections::HashSet;
 use std::env;
 
 use crate::channel::{
-    AdapterError, Attachment, Channel, ChannelMetadata, InboundAdapter, InboundMessage,
+    AdapterError, Attachment, Channel, ChannelMetadata, InboundAdapter, InboundMessage, MessageRef,
     OutboundAdapter, OutboundMessage, SendResult,
 };
+use crate::message_link_store::ReactionControl;
 
 /// Adapter for parsing Slack event webhook payloads.
 #[derive(Debug, Clone, Default)]
 pub struct SlackInboundAdapter {
     /// Bot user IDs that this adapter handles (messages from these are ignored)
     pub bot_user_ids: HashSet<String>,
+    /// Signing secret of the Slack app the requests belong to; unset skips verification
+    pub signing_secret: Option<String>,
 }
 
 impl SlackInboundAdapter {
     pub fn new(bot_user_ids: HashSet<String>) -> Self {
-        Self { bot_user_ids }
+        Self {
+            bot_user_ids,
+            signing_secret: None,
+        }
+    }
+
+    pub fn with_signing_secret(mut self, signing_secret: Option<String>) -> Self {
+        self.signing_secret = signing_secret.filter(|value| !value.trim().is_empty());
+        self
+    }
+
+    /// Verify a request's `X-Slack-Request-Timestamp` / `X-Slack-Signature` pair.
+    ///
+    /// Passes when no signing secret is configured.
+    pub fn verify_request(
+        &self,
+        timestamp: Option<&str>,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<(), SlackSignatureError> {
+        let Some(secret) = self.signing_secret.as_deref() else {
+            return Ok(());
+        };
+        let signature = signature.ok_or(SlackSignatureError::MissingSignature)?;
+        let timestamp = timestamp.ok_or(SlackSignatureError::MissingTimestamp)?;
+        verify_slack_signature(
+            secret,
+            timestamp,
+            signature,
+            body,
+            chrono::Utc::now().timestamp(),
+        )
     }
 
     /// Check if the sender is a bot that should be ignored.
@@ -45,6 +84,70 @@ impl SlackInboundAdapter {
         // Use thread_ts if present, otherwise use ts (message timestamp) as thread ID
         event.thread_ts.clone().unwrap_or_else(|| event.ts.clone())
     }
+
+    /// Convert a `reaction_added` event with a control emoji into an inbound message.
+    ///
+    /// `message_id` is the reacted-to message and `metadata.reaction_control` the
+    /// requested control. Reactions by our bots, on other users' messages, or with
+    /// other emoji are rejected.
+    pub fn parse_reaction(&self, raw_payload: &[u8]) -> Result<InboundMessage, AdapterError> {
+        let wrapper: SlackEventWrapper = serde_json::from_slice(raw_payload)
+            .map_err(|e| AdapterError::ParseError(e.to_string()))?;
+        let event = wrapper.event.ok_or(AdapterError::MissingField("event"))?;
+        if event.event_type != "reaction_added" {
+            return Err(AdapterError::ParseError(format!(
+                "unsupported event.type: {}",
+                event.event_type
+            )));
+        }
+        let control = event
+            .reaction
+            .as_deref()
+            .and_then(ReactionControl::from_slack_reaction)
+            .ok_or_else(|| AdapterError::ParseError("ignoring non-control reaction".to_string()))?;
+        let sender = event.user.ok_or(AdapterError::MissingField("user"))?;
+        if self.bot_user_ids.contains(&sender) {
+            return Err(AdapterError::ParseError(
+                "ignoring bot reaction".to_string(),
+            ));
+        }
+        if let Some(author) = event.item_user.as_ref() {
+            if !self.bot_user_ids.is_empty() && !self.bot_user_ids.contains(author) {
+                return Err(AdapterError::ParseError(
+                    "ignoring reaction on a message not posted by the bot".to_string(),
+                ));
+            }
+        }
+        let item = event
+            .item
+            .filter(|item| item.item_type == "message")
+            .ok_or(AdapterError::MissingField("item"))?;
+        let channel_id = item
+            .channel
+            .ok_or(AdapterError::MissingField("item.channel"))?;
+        let message_ts = item.ts.ok_or(AdapterError::MissingField("item.ts"))?;
+
+        Ok(InboundMessage {
+            channel: Channel::Slack,
+            sender,
+            sender_name: None,
+            recipient: channel_id.clone(),
+            subject: None,
+            text_body: Some(control.as_str().to_string()),
+            html_body: None,
+            thread_id: message_ts.clone(),
+            message_id: Some(message_ts),
+            attachments: Vec::new(),
+            reply_to: vec![channel_id.clone()],
+            raw_payload: raw_payload.to_vec(),
+            metadata: ChannelMetadata {
+                slack_channel_id: Some(channel_id),
+                slack_team_id: wrapper.team_id,
+                reaction_control: Some(control.as_str().to_string()),
+                ..Default::default()
+            },
+        })
+    }
 }
 
 impl InboundAdapter for SlackInboundAdapter {
@@ -171,18 +274,81 @@ impl OutboundAdapter for SlackOutboundAdapter {
             },
             thread_ts: message.thread_id.clone(),
             mrkdwn: Some(true),
+            blocks: None,
+        };
+
+        self.post_message(&request)
+    }
+
+    fn update(&self, message: &MessageRef, new_content: &str) -> Result<(), AdapterError> {
+        let request = serde_json::json!({
+            "channel": message.channel_id,
+            "ts": message.message_id,
+            "text": new_content,
+        });
+        accepted(self.call("chat.update", &request)?)
+    }
+
+    fn delete(&self, message: &MessageRef) -> Result<(), AdapterError> {
+        let request = serde_json::json!({
+            "channel": message.channel_id,
+            "ts": message.message_id,
+        });
+        accepted(self.call("chat.delete", &request)?)
+    }
+
+    fn channel(&self) -> Channel {
+        Channel::Slack
+    }
+}
+
+/// A failed Web API call as an error; `chat.update` and `chat.delete` have
+/// nothing else to report.
+fn accepted(result: SendResult) -> Result<(), AdapterError> {
+    if result.success {
+        Ok(())
+    } else {
+        Err(AdapterError::SendError(result.error.unwrap_or_default()))
+    }
+}
+
+impl SlackOutboundAdapter {
+    /// Post an Approve/Reject prompt whose buttons carry `callback_id`.
+    pub fn send_confirmation(
+        &self,
+        channel: &str,
+        thread_ts: Option<&str>,
+        callback_id: &str,
+        prompt: &str,
+    ) -> Result<SendResult, AdapterError> {
+        let request = SlackPostMessageRequest {
+            channel: channel.to_string(),
+            text: prompt.to_string(),
+            thread_ts: thread_ts.map(str::to_string),
+            mrkdwn: Some(true),
+            blocks: Some(confirmation_blocks(callback_id, prompt)),
         };
+        self.post_message(&request)
+    }
 
-        // Send via Slack API
+    fn post_message(&self, request: &SlackPostMessageRequest) -> Result<SendResult, AdapterError> {
+        self.call("chat.postMessage", request)
+    }
+
+    fn call<T: serde::Serialize>(
+        &self,
+        method: &str,
+        request: &T,
+    ) -> Result<SendResult, AdapterError> {
         let api_base =
             env::var("SLACK_API_BASE_URL").unwrap_or_else(|_| "https://slack.com/api".to_string());
-        let url = format!("{}/chat.postMessage", api_base.trim_end_matches('/'));
+        let url = format!("{}/{}", api_base.trim_end_matches('/'), method);
         let client = reqwest::blocking::Client::new();
         let response = client
             .post(url)
             .header("Authorization", format!("Bearer {}", self.bot_token))
             .header("Content-Type", "application/json")
-            .json(&request)
+            .json(request)
             .send()
             .map_err(|e| AdapterError::SendError(e.to_string()))?;
 
@@ -206,12 +372,95 @@ impl OutboundAdapter for SlackOutboundAdapter {
             })
         }
     }
+}
 
-    fn channel(&self) -> Channel {
-        Channel::Slack
+// ============================================================================
+// Request signing
+// ============================================================================
+
+/// Oldest (or furthest in the future) request timestamp Slack signatures are accepted for.
+pub const SLACK_SIGNATURE_MAX_AGE_SECS: i64 = 60 * 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum SlackSignatureError {
+    #[error("missing X-Slack-Signature header")]
+    MissingSignature,
+    #[error("missing X-Slack-Request-Timestamp header")]
+    MissingTimestamp,
+    #[error("invalid request timestamp")]
+    InvalidTimestamp,
+    #[error("request timestamp outside the replay window")]
+    StaleTimestamp,
+    #[error("signature does not match")]
+    InvalidSignature,
+}
+
+impl SlackSignatureError {
+    /// Short machine-readable reason, used as the webhook response status.
+    pub fn reason(self) -> &'static str {
+        match self {
+            Self::MissingSignature => "missing_signature",
+            Self::MissingTimestamp => "missing_timestamp",
+            Self::InvalidTimestamp => "invalid_timestamp",
+            Self::StaleTimestamp => "stale_timestamp",
+            Self::InvalidSignature => "invalid_signature",
+        }
     }
 }
 
+/// Verify a Slack request signature (`v0=` HMAC-SHA256 over `v0:{timestamp}:{body}`).
+///
+/// Requests whose timestamp is more than `SLACK_SIGNATURE_MAX_AGE_SECS` away
+/// from `now` are rejected so captured requests can't be replayed later.
+pub fn verify_slack_signature(
+    signing_secret: &str,
+    timestamp: &str,
+    signature: &str,
+    body: &[u8],
+    now: i64,
+) -> Result<(), SlackSignatureError> {
+    let timestamp_value: i64 = timestamp
+        .trim()
+        .parse()
+        .map_err(|_| SlackSignatureError::InvalidTimestamp)?;
+    if (now - timestamp_value).abs() > SLACK_SIGNATURE_MAX_AGE_SECS {
+        return Err(SlackSignatureError::StaleTimestamp);
+    }
+
+    let provided = signature
+        .trim()
+        .strip_prefix("v0=")
+        .and_then(|value| hex::decode(value).ok())
+        .ok_or(SlackSignatureError::InvalidSignature)?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(signing_secret.as_bytes())
+        .map_err(|_| SlackSignatureError::InvalidSignature)?;
+    mac.update(b"v0:");
+    mac.update(timestamp.trim().as_bytes());
+    mac.update(b":");
+    mac.update(body);
+    mac.verify_slice(&provided)
+        .map_err(|_| SlackSignatureError::InvalidSignature)
+}
+
+/// Resolve the signing secret for an employee's Slack app.
+///
+/// Looks for `{EMPLOYEE}_SLACK_SIGNING_SECRET` first (e.g., `OLIVER_SLACK_SIGNING_SECRET`),
+/// then falls back to the global `SLACK_SIGNING_SECRET`.
+pub fn resolve_slack_signing_secret(employee_id: Option<&str>) -> Option<String> {
+    let read = |key: &str| {
+        env::var(key)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    employee_id
+        .and_then(|employee_id| {
+            let employee_env = employee_id.to_uppercase().replace('-', "_");
+            read(&format!("{}_SLACK_SIGNING_SECRET", employee_env))
+        })
+        .or_else(|| read("SLACK_SIGNING_SECRET"))
+}
+
 // ============================================================================
 // Slack-specific types
 // ============================================================================
@@ -265,7 +514,8 @@ pub struct SlackMessageEvent {
     pub user: Option<String>,
     /// Message text
     pub text: Option<String>,
-    /// Message timestamp (also serves as message ID)
+    /// Message timestamp (also serves as message ID); absent on reaction events
+    #[serde(default)]
     pub ts: String,
     /// Thread timestamp (if message is in a thread)
     pub thread_ts: Option<String>,
@@ -279,6 +529,24 @@ pub struct SlackMessageEvent {
     pub channel_type: Option<String>,
     /// Event timestamp
     pub event_ts: Option<String>,
+    /// Reaction name for `reaction_added` events (e.g. "alarm_clock")
+    #[serde(default)]
+    pub reaction: Option<String>,
+    /// Message a `reaction_added` event refers to
+    #[serde(default)]
+    pub item: Option<SlackReactionItem>,
+    /// Author of the reacted-to message
+    #[serde(default)]
+    pub item_user: Option<String>,
+}
+
+/// Item referenced by a `reaction_added` event.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackReactionItem {
+    #[serde(rename = "type")]
+    pub item_type: String,
+    pub channel: Option<String>,
+    pub ts: Option<String>,
 }
 
 /// Slack file attachment.
@@ -294,6 +562,268 @@ pub struct SlackFile {
     pub size: Option<i64>,
 }
 
+/// Slash command payload (`application/x-www-form-urlencoded`) sent by Slack.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SlackSlashCommand {
+    /// Command name, including the leading slash (e.g. "/dowhiz")
+    #[serde(default)]
+    pub command: String,
+    /// Everything typed after the command name
+    #[serde(default)]
+    pub text: String,
+    /// User ID who invoked the command
+    #[serde(default)]
+    pub user_id: String,
+    pub user_name: Option<String>,
+    /// Channel ID where the command was invoked
+    #[serde(default)]
+    pub channel_id: String,
+    pub team_id: Option<String>,
+    pub api_app_id: Option<String>,
+    /// URL for delayed (ephemeral) responses, valid for 30 minutes
+    pub response_url: Option<String>,
+    /// Unique per invocation; used for deduplication
+    pub trigger_id: Option<String>,
+}
+
+impl SlackSlashCommand {
+    /// Convert the slash command into a normalized inbound message.
+    ///
+    /// The command name and response_url are carried in metadata so the worker
+    /// can answer ephemerally instead of starting a full RunTask.
+    pub fn to_inbound_message(&self, raw_payload: &[u8]) -> InboundMessage {
+        let invocation_id = self
+            .trigger_id
+            .clone()
+            .unwrap_or_else(|| format!("{}:{}", self.channel_id, self.user_id));
+        InboundMessage {
+            channel: Channel::Slack,
+            sender: self.user_id.clone(),
+            sender_name: self.user_name.clone(),
+            recipient: self.channel_id.clone(),
+            subject: None,
+            text_body: Some(self.text.clone()),
+            html_body: None,
+            thread_id: invocation_id.clone(),
+            message_id: Some(invocation_id),
+            attachments: Vec::new(),
+            reply_to: vec![self.channel_id.clone()],
+            raw_payload: raw_payload.to_vec(),
+            metadata: ChannelMetadata {
+                slack_channel_id: Some(self.channel_id.clone()),
+                slack_team_id: self.team_id.clone(),
+                slack_command: Some(self.command.clone()),
+                slack_response_url: self.response_url.clone(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Action requested through a slash command's text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlackCommandAction {
+    /// `/dowhiz` or `/dowhiz help`
+    Help,
+    /// `/dowhiz status`
+    Status,
+    /// `/dowhiz tasks`
+    Tasks,
+    /// `/dowhiz remind me in 10 minutes to ...`
+    Remind {
+        delay: std::time::Duration,
+        message: String,
+    },
+    /// Anything else is treated as a quick question
+    Ask(String),
+}
+
+/// Ephemeral reply body for slash commands (only visible to the invoking user).
+#[derive(Debug, Clone, Serialize)]
+pub struct SlackEphemeralResponse {
+    pub response_type: &'static str,
+    pub text: String,
+}
+
+impl SlackEphemeralResponse {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            response_type: "ephemeral",
+            text: text.into(),
+        }
+    }
+}
+
+/// Usage text returned for `/dowhiz help` and malformed commands.
+pub const SLASH_COMMAND_HELP: &str = "*DoWhiz commands*\n\
+• `/dowhiz status` – summary of your scheduled work\n\
+• `/dowhiz tasks` – list upcoming tasks\n\
+• `/dowhiz remind me in 30 minutes to check the deploy` – schedule a reminder\n\
+• `/dowhiz <question>` – quick answer (mention me for bigger requests)";
+
+/// Interactivity payload (button clicks, modal submissions).
+///
+/// Slack posts these as a form with a single `payload` field holding JSON.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackInteractionPayload {
+    /// "block_actions" or "view_submission"
+    #[serde(rename = "type")]
+    pub interaction_type: String,
+    pub user: SlackInteractionUser,
+    pub team: Option<SlackInteractionRef>,
+    pub api_app_id: Option<String>,
+    pub channel: Option<SlackInteractionRef>,
+    pub message: Option<SlackInteractionMessage>,
+    #[serde(default)]
+    pub actions: Vec<SlackBlockAction>,
+    pub view: Option<SlackView>,
+    pub response_url: Option<String>,
+    pub trigger_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackInteractionUser {
+    pub id: String,
+    pub username: Option<String>,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackInteractionRef {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackInteractionMessage {
+    pub ts: String,
+    pub thread_ts: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackBlockAction {
+    pub action_id: String,
+    pub block_id: Option<String>,
+    pub value: Option<String>,
+    pub action_ts: Option<String>,
+}
+
+/// Modal view attached to a `view_submission`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackView {
+    pub callback_id: Option<String>,
+    pub private_metadata: Option<String>,
+    /// `state.values[block_id][action_id]` as sent by Slack
+    pub state: Option<serde_json::Value>,
+}
+
+impl SlackInteractionPayload {
+    /// Identifier of the pending action this interaction answers.
+    ///
+    /// Modals use the view's callback_id; confirmation buttons live in a block
+    /// whose block_id is the callback_id.
+    pub fn callback_id(&self) -> Option<&str> {
+        if self.interaction_type == "view_submission" {
+            return self
+                .view
+                .as_ref()
+                .and_then(|view| view.callback_id.as_deref())
+                .filter(|value| !value.is_empty());
+        }
+        self.actions
+            .first()
+            .and_then(|action| action.block_id.as_deref())
+            .filter(|value| !value.is_empty())
+    }
+
+    /// The button's value (or action_id), or "approve" for a modal submission.
+    pub fn decision(&self) -> Option<&str> {
+        if self.interaction_type == "view_submission" {
+            return Some("approve");
+        }
+        let action = self.actions.first()?;
+        Some(action.value.as_deref().unwrap_or(&action.action_id))
+    }
+
+    /// Plain-text values entered in a modal, as `block_id.action_id: value` lines.
+    pub fn submitted_values(&self) -> Vec<String> {
+        let Some(values) = self
+            .view
+            .as_ref()
+            .and_then(|view| view.state.as_ref())
+            .and_then(|state| state.get("values"))
+            .and_then(|values| values.as_object())
+        else {
+            return Vec::new();
+        };
+        let mut lines = Vec::new();
+        for (block_id, actions) in values {
+            let Some(actions) = actions.as_object() else {
+                continue;
+            };
+            for (action_id, element) in actions {
+                let value = element.get("value").and_then(|v| v.as_str()).or_else(|| {
+                    element
+                        .get("selected_option")
+                        .and_then(|option| option.get("value"))
+                        .and_then(|v| v.as_str())
+                });
+                if let Some(value) = value {
+                    lines.push(format!("{}.{}: {}", block_id, action_id, value));
+                }
+            }
+        }
+        lines
+    }
+
+    /// Convert the interaction into a normalized inbound message for the worker.
+    pub fn to_inbound_message(&self, raw_payload: &[u8]) -> Result<InboundMessage, AdapterError> {
+        let callback_id = self
+            .callback_id()
+            .ok_or(AdapterError::MissingField("callback_id"))?
+            .to_string();
+        let decision = self
+            .decision()
+            .ok_or(AdapterError::MissingField("actions"))?;
+        let mut text = decision.to_string();
+        for line in self.submitted_values() {
+            text.push('\n');
+            text.push_str(&line);
+        }
+        let channel_id = self.channel.as_ref().map(|channel| channel.id.clone());
+        let thread_id = self
+            .message
+            .as_ref()
+            .map(|message| message.thread_ts.clone().unwrap_or(message.ts.clone()))
+            .unwrap_or_else(|| callback_id.clone());
+        let message_id = self
+            .actions
+            .first()
+            .and_then(|action| action.action_ts.clone())
+            .or(self.trigger_id.clone());
+        Ok(InboundMessage {
+            channel: Channel::Slack,
+            sender: self.user.id.clone(),
+            sender_name: self.user.username.clone().or(self.user.name.clone()),
+            recipient: channel_id.clone().unwrap_or_default(),
+            subject: None,
+            text_body: Some(text),
+            html_body: None,
+            thread_id,
+            message_id,
+            attachments: Vec::new(),
+            reply_to: channel_id.clone().into_iter().collect(),
+            raw_payload: raw_payload.to_vec(),
+            metadata: ChannelMetadata {
+                slack_channel_id: channel_id,
+                slack_team_id: self.team.as_ref().map(|team| team.id.clone()),
+                slack_response_url: self.response_url.clone(),
+                slack_callback_id: Some(callback_id),
+                ..Default::default()
+            },
+        })
+    }
+}
+
 /// Request body for chat.postMessage API.
 #[derive(Debug, Clone, Serialize)]
 pub struct SlackPostMessageRequest {
@@ -303,6 +833,9 @@ pub struct SlackPostMessageRequest {
     pub thread_ts: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mrkdwn: Option<bool>,
+    /// Block Kit layout; `text` stays as the notification fallback
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks: Option<serde_json::Value>,
 }
 
 /// Response from Slack API.
@@ -333,6 +866,147 @@ pub fn is_url_verification(payload: &[u8]) -> Option<SlackUrlVerification> {
     }
 }
 
+/// Parse an interactivity form payload (`payload=<json>`).
+pub fn parse_interaction_payload(body: &[u8]) -> Result<SlackInteractionPayload, AdapterError> {
+    #[derive(Deserialize)]
+    struct Form {
+        payload: String,
+    }
+    let form: Form =
+        serde_urlencoded::from_bytes(body).map_err(|e| AdapterError::ParseError(e.to_string()))?;
+    serde_json::from_str(&form.payload).map_err(|e| AdapterError::ParseError(e.to_string()))
+}
+
+/// Block Kit layout for an Approve/Reject prompt.
+///
+/// The actions block carries the callback_id so clicks can be matched back to
+/// the stored pending action.
+pub fn confirmation_blocks(callback_id: &str, prompt: &str) -> serde_json::Value {
+    serde_json::json!([
+        {
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": prompt }
+        },
+        {
+            "type": "actions",
+            "block_id": callback_id,
+            "elements": [
+                {
+                    "type": "button",
+                    "action_id": "approve",
+                    "style": "primary",
+                    "text": { "type": "plain_text", "text": "✅ Approve" },
+                    "value": "approve"
+                },
+                {
+                    "type": "button",
+                    "action_id": "reject",
+                    "style": "danger",
+                    "text": { "type": "plain_text", "text": "❌ Reject" },
+                    "value": "reject"
+                }
+            ]
+        }
+    ])
+}
+
+/// POST a message body to an interaction/slash-command `response_url`.
+pub async fn post_to_response_url(
+    response_url: &str,
+    body: &serde_json::Value,
+) -> Result<(), AdapterError> {
+    let response = reqwest::Client::new()
+        .post(response_url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| AdapterError::SendError(e.to_string()))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(AdapterError::SendError(format!(
+            "response_url returned {}: {}",
+            status, text
+        )));
+    }
+    Ok(())
+}
+
+/// Parse a slash-command form payload.
+pub fn parse_slash_command(payload: &[u8]) -> Result<SlackSlashCommand, AdapterError> {
+    let command: SlackSlashCommand = serde_urlencoded::from_bytes(payload)
+        .map_err(|e| AdapterError::ParseError(e.to_string()))?;
+    if command.command.trim().is_empty() {
+        return Err(AdapterError::MissingField("command"));
+    }
+    if command.user_id.trim().is_empty() {
+        return Err(AdapterError::MissingField("user_id"));
+    }
+    Ok(command)
+}
+
+/// Interpret the text typed after `/dowhiz`.
+pub fn parse_slash_command_text(text: &str) -> SlackCommandAction {
+    let trimmed = text.trim();
+    let (head, rest) = match trimmed.split_once(char::is_whitespace) {
+        Some((head, rest)) => (head, rest.trim()),
+        None => (trimmed, ""),
+    };
+    match head.to_ascii_lowercase().as_str() {
+        "" | "help" => SlackCommandAction::Help,
+        "status" if rest.is_empty() => SlackCommandAction::Status,
+        "tasks" | "list" if rest.is_empty() => SlackCommandAction::Tasks,
+        "remind" => parse_remind(rest).unwrap_or(SlackCommandAction::Help),
+        _ => SlackCommandAction::Ask(trimmed.to_string()),
+    }
+}
+
+/// Parse `[me] in <n> <unit> [to|that|about] <message>`.
+fn parse_remind(text: &str) -> Option<SlackCommandAction> {
+    let mut words = text.split_whitespace().peekable();
+    if words.peek().is_some_and(|w| w.eq_ignore_ascii_case("me")) {
+        words.next();
+    }
+    if !words.next()?.eq_ignore_ascii_case("in") {
+        return None;
+    }
+    let amount_word = words.next()?;
+    // Accept both "10 minutes" and "10m".
+    let split_at = amount_word
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(amount_word.len());
+    let amount: u64 = amount_word[..split_at].parse().ok()?;
+    let unit = if split_at < amount_word.len() {
+        amount_word[split_at..].to_string()
+    } else {
+        words.next()?.to_string()
+    };
+    let unit_secs = match unit.to_ascii_lowercase().trim_end_matches(',') {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 60 * 60,
+        "d" | "day" | "days" => 24 * 60 * 60,
+        _ => return None,
+    };
+    if amount == 0 {
+        return None;
+    }
+    if words
+        .peek()
+        .is_some_and(|w| matches!(w.to_ascii_lowercase().as_str(), "to" | "that" | "about"))
+    {
+        words.next();
+    }
+    let message = words.collect::<Vec<_>>().join(" ");
+    if message.is_empty() {
+        return None;
+    }
+    Some(SlackCommandAction::Remind {
+        delay: std::time::Duration::from_secs(amount.checked_mul(unit_secs)?),
+        message,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -511,6 +1185,35 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn parse_reaction_on_bot_message() {
+        let payload = r#"{
+            "type": "event_callback",
+            "team_id": "T123",
+            "event": {
+                "type": "reaction_added",
+                "user": "U_USER",
+                "reaction": "alarm_clock",
+                "item_user": "U_BOT",
+                "item": {"type": "message", "channel": "C123", "ts": "1700000000.000100"},
+                "event_ts": "1700000050.000200"
+            }
+        }"#;
+
+        let adapter = SlackInboundAdapter::new(HashSet::from(["U_BOT".to_string()]));
+        let message = adapter.parse_reaction(payload.as_bytes()).unwrap();
+        assert_eq!(message.sender, "U_USER");
+        assert_eq!(message.message_id.as_deref(), Some("1700000000.000100"));
+        assert_eq!(message.metadata.slack_channel_id.as_deref(), Some("C123"));
+        assert_eq!(message.metadata.reaction_control.as_deref(), Some("snooze"));
+
+        let other_author =
+            payload.replace("\"item_user\": \"U_BOT\"", "\"item_user\": \"U_OTHER\"");
+        assert!(adapter.parse_reaction(other_author.as_bytes()).is_err());
+        let other_emoji = payload.replace("alarm_clock", "tada");
+        assert!(adapter.parse_reaction(other_emoji.as_bytes()).is_err());
+    }
+
     #[test]
     fn own_bot_user_id_filtered() {
         let payload = r#"{
@@ -614,4 +1317,203 @@ mod tests {
 
         assert_eq!(message.text_body, Some("".to_string()));
     }
+
+    #[test]
+    fn parse_slash_command_form_payload() {
+        let payload = "command=%2Fdowhiz&text=remind+me+in+10+minutes+to+stretch&user_id=U123&channel_id=C456&team_id=T789&api_app_id=A111&response_url=https%3A%2F%2Fhooks.slack.com%2Fcommands%2F1&trigger_id=13345224609.738474920.8088930838d88f008e0";
+
+        let command = parse_slash_command(payload.as_bytes()).unwrap();
+        assert_eq!(command.command, "/dowhiz");
+        assert_eq!(command.api_app_id.as_deref(), Some("A111"));
+
+        let message = command.to_inbound_message(payload.as_bytes());
+        assert_eq!(message.sender, "U123");
+        assert_eq!(message.metadata.slack_channel_id.as_deref(), Some("C456"));
+        assert_eq!(message.metadata.slack_command.as_deref(), Some("/dowhiz"));
+        assert_eq!(
+            message.metadata.slack_response_url.as_deref(),
+            Some("https://hooks.slack.com/commands/1")
+        );
+    }
+
+    #[test]
+    fn parse_slash_command_requires_user() {
+        assert!(parse_slash_command(b"command=%2Fdowhiz&text=status").is_err());
+    }
+
+    #[test]
+    fn parse_slash_command_text_actions() {
+        assert_eq!(parse_slash_command_text(""), SlackCommandAction::Help);
+        assert_eq!(
+            parse_slash_command_text(" Status "),
+            SlackCommandAction::Status
+        );
+        assert_eq!(parse_slash_command_text("tasks"), SlackCommandAction::Tasks);
+        assert_eq!(
+            parse_slash_command_text("status of the launch?"),
+            SlackCommandAction::Ask("status of the launch?".to_string())
+        );
+        assert_eq!(
+            parse_slash_command_text("remind me in 2 hours to call Sam"),
+            SlackCommandAction::Remind {
+                delay: std::time::Duration::from_secs(2 * 60 * 60),
+                message: "call Sam".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_slash_command_text("remind in 15m check the deploy"),
+            SlackCommandAction::Remind {
+                delay: std::time::Duration::from_secs(15 * 60),
+                message: "check the deploy".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_slash_command_text("remind me tomorrow"),
+            SlackCommandAction::Help
+        );
+    }
+
+    fn form_encode_payload(json: &str) -> Vec<u8> {
+        serde_urlencoded::to_string([("payload", json)])
+            .unwrap()
+            .into_bytes()
+    }
+
+    #[test]
+    fn parse_block_action_interaction() {
+        let json = r#"{
+            "type": "block_actions",
+            "user": {"id": "U123", "username": "sam"},
+            "team": {"id": "T123"},
+            "api_app_id": "A123",
+            "channel": {"id": "C123"},
+            "message": {"ts": "1700000000.000200", "thread_ts": "1700000000.000100"},
+            "response_url": "https://hooks.slack.com/actions/1",
+            "actions": [{
+                "action_id": "approve",
+                "block_id": "cb-42",
+                "value": "approve",
+                "action_ts": "1700000001.000000"
+            }]
+        }"#;
+        let body = form_encode_payload(json);
+
+        let payload = parse_interaction_payload(&body).unwrap();
+        assert_eq!(payload.callback_id(), Some("cb-42"));
+        assert_eq!(payload.decision(), Some("approve"));
+
+        let message = payload.to_inbound_message(&body).unwrap();
+        assert_eq!(message.sender, "U123");
+        assert_eq!(message.thread_id, "1700000000.000100");
+        assert_eq!(message.text_body.as_deref(), Some("approve"));
+        assert_eq!(message.metadata.slack_callback_id.as_deref(), Some("cb-42"));
+        assert_eq!(message.metadata.slack_team_id.as_deref(), Some("T123"));
+    }
+
+    #[test]
+    fn parse_view_submission_collects_values() {
+        let json = r#"{
+            "type": "view_submission",
+            "user": {"id": "U123"},
+            "view": {
+                "callback_id": "cb-modal",
+                "state": {"values": {
+                    "reason": {"input": {"type": "plain_text_input", "value": "ship it"}},
+                    "priority": {"pick": {"type": "static_select", "selected_option": {"value": "high"}}}
+                }}
+            }
+        }"#;
+        let body = form_encode_payload(json);
+
+        let payload = parse_interaction_payload(&body).unwrap();
+        assert_eq!(payload.callback_id(), Some("cb-modal"));
+        let message = payload.to_inbound_message(&body).unwrap();
+        let text = message.text_body.unwrap();
+        assert!(text.starts_with("approve\n"));
+        assert!(text.contains("reason.input: ship it"));
+        assert!(text.contains("priority.pick: high"));
+    }
+
+    #[test]
+    fn interaction_without_callback_is_rejected() {
+        let json =
+            r#"{"type": "block_actions", "user": {"id": "U1"}, "actions": [{"action_id": "x"}]}"#;
+        let payload = parse_interaction_payload(&form_encode_payload(json)).unwrap();
+        assert!(payload.to_inbound_message(b"").is_err());
+    }
+
+    #[test]
+    fn confirmation_blocks_carry_callback_id() {
+        let blocks = confirmation_blocks("cb-9", "Apply this edit?");
+        assert_eq!(blocks[1]["block_id"], "cb-9");
+        assert_eq!(blocks[1]["elements"][0]["value"], "approve");
+        assert_eq!(blocks[1]["elements"][1]["value"], "reject");
+    }
+
+    fn slack_test_signature(secret: &str, timestamp: &str, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("v0:{}:", timestamp).as_bytes());
+        mac.update(body);
+        format!("v0={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn verify_slack_signature_accepts_valid_and_rejects_tampered() {
+        let body = br#"{"type":"event_callback","api_app_id":"A1"}"#;
+        let signature = slack_test_signature("secret", "1700000000", body);
+
+        assert_eq!(
+            verify_slack_signature("secret", "1700000000", &signature, body, 1_700_000_010),
+            Ok(())
+        );
+        assert_eq!(
+            verify_slack_signature("other", "1700000000", &signature, body, 1_700_000_010),
+            Err(SlackSignatureError::InvalidSignature)
+        );
+        assert_eq!(
+            verify_slack_signature(
+                "secret",
+                "1700000000",
+                &signature,
+                br#"{"type":"event_callback","api_app_id":"A2"}"#,
+                1_700_000_010
+            ),
+            Err(SlackSignatureError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn verify_slack_signature_enforces_replay_window() {
+        let body = b"token=x&command=%2Fdowhiz";
+        let signature = slack_test_signature("secret", "1700000000", body);
+        let too_late = 1_700_000_000 + SLACK_SIGNATURE_MAX_AGE_SECS + 1;
+        assert_eq!(
+            verify_slack_signature("secret", "1700000000", &signature, body, too_late),
+            Err(SlackSignatureError::StaleTimestamp)
+        );
+        assert_eq!(
+            verify_slack_signature("secret", "soon", &signature, body, too_late),
+            Err(SlackSignatureError::InvalidTimestamp)
+        );
+    }
+
+    #[test]
+    fn adapter_verify_request_requires_headers_only_with_secret() {
+        let body = b"{}";
+        let open = SlackInboundAdapter::new(HashSet::new()).with_signing_secret(None);
+        assert_eq!(open.verify_request(None, None, body), Ok(()));
+
+        let signed = SlackInboundAdapter::new(HashSet::new())
+            .with_signing_secret(Some("secret".to_string()));
+        assert_eq!(
+            signed.verify_request(None, None, body),
+            Err(SlackSignatureError::MissingSignature)
+        );
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let signature = slack_test_signature("secret", &timestamp, body);
+        assert_eq!(
+            signed.verify_request(Some(&timestamp), Some(&signature), body),
+            Ok(())
+        );
+    }
 }
@@ -0,0 +1,518 @@
+//! Alerts for failures an operator has to act on.
+//!
+//! [`raise`] logs an `ALERT` line and hands the alert to every configured
+//! sink on a background thread, so a slow or failing sink never holds up the
+//! caller. Sinks are configured from the environment:
+//!
+//! - `ALERT_SLACK_WEBHOOK_URL`: a Slack incoming webhook for the ops channel.
+//! - `ALERT_PAGERDUTY_ROUTING_KEY`: a PagerDuty Events API v2 integration;
+//!   alerts trigger incidents deduplicated on their kind and key.
+//! - `ALERT_WEBHOOK_URL`: any endpoint taking the alert as a JSON POST.
+//! - `ALERT_EMAIL`: comma-separated addresses mailed through the outbound
+//!   email path.
+//!
+//! More can be added with [`add_sink`]. An alert with the same kind and key
+//! as one raised in the last `ALERT_THROTTLE_SECS` (default 900) is only
+//! logged.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+use tracing::{error, warn};
+
+use crate::scheduler::escape_html;
+
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+const ALERT_EMAIL_DIR: &str = "dowhiz_alerts";
+const DEFAULT_THROTTLE_SECS: u64 = 900;
+const HTTP_TIMEOUT_SECS: u64 = 10;
+
+static SINKS: OnceLock<RwLock<Vec<Arc<dyn AlertSink>>>> = OnceLock::new();
+static THROTTLE: OnceLock<Mutex<Throttle>> = OnceLock::new();
+
+#[derive(Debug, thiserror::Error)]
+pub enum AlertError {
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("{sink} returned {status}: {body}")]
+    Rejected {
+        sink: &'static str,
+        status: u16,
+        body: String,
+    },
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("email error: {0}")]
+    Email(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertKind {
+    /// A task failed on every attempt and was disabled.
+    RetriesExhausted,
+    /// The watchdog stopped a run that outlived its timeout.
+    WatchdogKill,
+    /// The ingestion consumer stopped without being asked to.
+    IngestionConsumerDied,
+    /// The store integrity check found damage it did not repair.
+    StoreCorruption,
+}
+
+impl AlertKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::RetriesExhausted => "retries_exhausted",
+            Self::WatchdogKill => "watchdog_kill",
+            Self::IngestionConsumerDied => "ingestion_consumer_died",
+            Self::StoreCorruption => "store_corruption",
+        }
+    }
+
+    /// PagerDuty severity: `critical` when the worker itself is degraded.
+    pub fn severity(self) -> &'static str {
+        match self {
+            Self::IngestionConsumerDied | Self::StoreCorruption => "critical",
+            Self::RetriesExhausted | Self::WatchdogKill => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub kind: AlertKind,
+    /// What failed, such as a task id; alerts are throttled and deduplicated
+    /// per kind and key.
+    pub key: String,
+    pub summary: String,
+    pub details: Vec<(&'static str, String)>,
+    pub raised_at: DateTime<Utc>,
+}
+
+impl Alert {
+    pub fn new(kind: AlertKind, key: impl Into<String>, summary: impl Into<String>) -> Self {
+        Self {
+            kind,
+            key: key.into(),
+            summary: summary.into(),
+            details: Vec::new(),
+            raised_at: Utc::now(),
+        }
+    }
+
+    pub fn detail(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.details.push((name, value.to_string()));
+        self
+    }
+
+    fn dedup_key(&self) -> String {
+        format!("{}:{}", self.kind.as_str(), self.key)
+    }
+
+    fn details_json(&self) -> Map<String, Value> {
+        self.details
+            .iter()
+            .map(|(name, value)| (name.to_string(), Value::from(value.as_str())))
+            .collect()
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "kind": self.kind.as_str(),
+            "severity": self.kind.severity(),
+            "key": self.key,
+            "summary": self.summary,
+            "details": self.details_json(),
+            "raised_at": self.raised_at.to_rfc3339(),
+        })
+    }
+}
+
+/// Somewhere alerts are delivered.
+pub trait AlertSink: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn send(&self, alert: &Alert) -> Result<(), AlertError>;
+}
+
+/// Log `alert` and deliver it to every sink, unless the same alert was
+/// raised within the throttle window.
+pub fn raise(alert: Alert) {
+    error!(
+        "ALERT: kind={} key={} summary={} details={:?}",
+        alert.kind.as_str(),
+        alert.key,
+        alert.summary,
+        alert.details
+    );
+    let admitted = THROTTLE
+        .get_or_init(|| Mutex::new(Throttle::new(throttle_window())))
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .admit(&alert.dedup_key(), Instant::now());
+    if !admitted {
+        return;
+    }
+    // Sinks hold blocking HTTP clients, which must not be built or used on
+    // an async runtime thread.
+    let spawned = std::thread::Builder::new()
+        .name("alert".to_string())
+        .spawn(move || {
+            let sinks = sinks()
+                .read()
+                .unwrap_or_else(|poison| poison.into_inner())
+                .clone();
+            deliver(&sinks, &alert);
+        });
+    if let Err(err) = spawned {
+        warn!("failed to start alert delivery: {}", err);
+    }
+}
+
+/// Deliver alerts to `sink` as well as the configured ones.
+pub fn add_sink(sink: Arc<dyn AlertSink>) {
+    sinks()
+        .write()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .push(sink);
+}
+
+fn sinks() -> &'static RwLock<Vec<Arc<dyn AlertSink>>> {
+    SINKS.get_or_init(|| RwLock::new(sinks_from_env()))
+}
+
+fn deliver(sinks: &[Arc<dyn AlertSink>], alert: &Alert) {
+    for sink in sinks {
+        if let Err(err) = sink.send(alert) {
+            warn!(
+                "failed to send {} alert {} to {}: {}",
+                alert.kind.as_str(),
+                alert.key,
+                sink.name(),
+                err
+            );
+        }
+    }
+}
+
+fn env_value(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn throttle_window() -> Duration {
+    Duration::from_secs(
+        env_value("ALERT_THROTTLE_SECS")
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_THROTTLE_SECS),
+    )
+}
+
+fn sinks_from_env() -> Vec<Arc<dyn AlertSink>> {
+    let client = || {
+        reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(HTTP_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default()
+    };
+    let mut sinks: Vec<Arc<dyn AlertSink>> = Vec::new();
+    if let Some(url) = env_value("ALERT_SLACK_WEBHOOK_URL") {
+        sinks.push(Arc::new(SlackWebhookSink {
+            client: client(),
+            url,
+        }));
+    }
+    if let Some(routing_key) = env_value("ALERT_PAGERDUTY_ROUTING_KEY") {
+        sinks.push(Arc::new(PagerDutySink {
+            client: client(),
+            url: PAGERDUTY_EVENTS_URL.to_string(),
+            routing_key,
+        }));
+    }
+    if let Some(url) = env_value("ALERT_WEBHOOK_URL") {
+        sinks.push(Arc::new(WebhookSink {
+            client: client(),
+            url,
+        }));
+    }
+    if let Some(recipients) = env_value("ALERT_EMAIL") {
+        let recipients: Vec<String> = recipients
+            .split(',')
+            .map(|address| address.trim().to_string())
+            .filter(|address| !address.is_empty())
+            .collect();
+        if !recipients.is_empty() {
+            sinks.push(Arc::new(EmailSink { recipients }));
+        }
+    }
+    sinks
+}
+
+/// Last delivery time of each alert, for the throttle window.
+struct Throttle {
+    window: Duration,
+    sent: HashMap<String, Instant>,
+}
+
+impl Throttle {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            sent: HashMap::new(),
+        }
+    }
+
+    fn admit(&mut self, key: &str, now: Instant) -> bool {
+        let window = self.window;
+        self.sent
+            .retain(|_, sent_at| now.saturating_duration_since(*sent_at) < window);
+        if self.sent.contains_key(key) {
+            return false;
+        }
+        self.sent.insert(key.to_string(), now);
+        true
+    }
+}
+
+fn post_json(
+    client: &reqwest::blocking::Client,
+    sink: &'static str,
+    url: &str,
+    body: &Value,
+) -> Result<(), AlertError> {
+    let response = client.post(url).json(body).send()?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    Err(AlertError::Rejected {
+        sink,
+        status: status.as_u16(),
+        body: response.text().unwrap_or_default(),
+    })
+}
+
+struct SlackWebhookSink {
+    client: reqwest::blocking::Client,
+    url: String,
+}
+
+fn slack_text(alert: &Alert) -> String {
+    let mut text = format!(
+        ":rotating_light: *{}* ({}): {}",
+        alert.kind.as_str(),
+        alert.kind.severity(),
+        alert.summary
+    );
+    for (name, value) in &alert.details {
+        let _ = write!(text, "\n• {}: `{}`", name, value);
+    }
+    text
+}
+
+impl AlertSink for SlackWebhookSink {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn send(&self, alert: &Alert) -> Result<(), AlertError> {
+        post_json(
+            &self.client,
+            self.name(),
+            &self.url,
+            &json!({ "text": slack_text(alert) }),
+        )
+    }
+}
+
+struct PagerDutySink {
+    client: reqwest::blocking::Client,
+    url: String,
+    routing_key: String,
+}
+
+fn pagerduty_event(routing_key: &str, alert: &Alert) -> Value {
+    json!({
+        "routing_key": routing_key,
+        "event_action": "trigger",
+        "dedup_key": alert.dedup_key(),
+        "payload": {
+            "summary": alert.summary,
+            "source": "dowhiz",
+            "severity": alert.kind.severity(),
+            "component": alert.kind.as_str(),
+            "timestamp": alert.raised_at.to_rfc3339(),
+            "custom_details": alert.details_json(),
+        },
+    })
+}
+
+impl AlertSink for PagerDutySink {
+    fn name(&self) -> &'static str {
+        "pagerduty"
+    }
+
+    fn send(&self, alert: &Alert) -> Result<(), AlertError> {
+        post_json(
+            &self.client,
+            self.name(),
+            &self.url,
+            &pagerduty_event(&self.routing_key, alert),
+        )
+    }
+}
+
+struct WebhookSink {
+    client: reqwest::blocking::Client,
+    url: String,
+}
+
+impl AlertSink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn send(&self, alert: &Alert) -> Result<(), AlertError> {
+        post_json(&self.client, self.name(), &self.url, &alert.to_json())
+    }
+}
+
+struct EmailSink {
+    recipients: Vec<String>,
+}
+
+impl AlertSink for EmailSink {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn send(&self, alert: &Alert) -> Result<(), AlertError> {
+        let mut html = format!("<p>{}</p><ul>", escape_html(&alert.summary));
+        for (name, value) in &alert.details {
+            let _ = write!(
+                html,
+                "<li>{}: {}</li>",
+                escape_html(name),
+                escape_html(value)
+            );
+        }
+        let _ = write!(
+            html,
+            "</ul><p>Raised at {}</p>",
+            alert.raised_at.to_rfc3339()
+        );
+
+        let stem = format!(
+            "alert_{}_{}",
+            alert.kind.as_str(),
+            alert.raised_at.format("%Y%m%dT%H%M%S%f")
+        );
+        let dir = std::env::temp_dir().join(ALERT_EMAIL_DIR);
+        std::fs::create_dir_all(&dir)?;
+        let html_path = dir.join(format!("{}.html", stem));
+        std::fs::write(&html_path, html)?;
+        let attachments_dir = dir.join(format!("attachments_{}", stem));
+        std::fs::create_dir_all(&attachments_dir)?;
+
+        let params = send_emails_module::SendEmailParams {
+            subject: format!(
+                "[{}] {}: {}",
+                alert.kind.severity(),
+                alert.kind.as_str(),
+                alert.summary
+            ),
+            html_path,
+            attachments_dir,
+            from: self.recipients.first().cloned(),
+            to: self.recipients.clone(),
+            cc: vec![],
+            bcc: vec![],
+            in_reply_to: None,
+            references: None,
+            reply_to: None,
+        };
+        send_emails_module::send_email(&params)
+            .map(|_| ())
+            .map_err(|err| AlertError::Email(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert() -> Alert {
+        Alert::new(
+            AlertKind::RetriesExhausted,
+            "task-1",
+            "Task task-1 failed 3 times and was disabled",
+        )
+        .detail("user_id", "u1")
+        .detail("retries", 3)
+    }
+
+    #[test]
+    fn same_alert_is_throttled_within_the_window() {
+        let mut throttle = Throttle::new(Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(throttle.admit("retries_exhausted:task-1", start));
+        assert!(!throttle.admit("retries_exhausted:task-1", start + Duration::from_secs(30)));
+        assert!(throttle.admit("watchdog_kill:task-1", start + Duration::from_secs(30)));
+        assert!(throttle.admit("retries_exhausted:task-1", start + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn payloads_carry_kind_severity_and_details() {
+        let alert = alert();
+        let body = alert.to_json();
+        assert_eq!(body["kind"], "retries_exhausted");
+        assert_eq!(body["severity"], "error");
+        assert_eq!(body["details"]["retries"], "3");
+
+        let event = pagerduty_event("rk", &alert);
+        assert_eq!(event["dedup_key"], "retries_exhausted:task-1");
+        assert_eq!(event["payload"]["severity"], "error");
+        assert_eq!(event["payload"]["custom_details"]["user_id"], "u1");
+
+        let text = slack_text(&alert);
+        assert!(text.contains("*retries_exhausted* (error)"), "{}", text);
+        assert!(text.contains("• user_id: `u1`"), "{}", text);
+    }
+
+    #[test]
+    fn http_sinks_report_rejections() {
+        let mut server = mockito::Server::new();
+        let accepted = server
+            .mock("POST", "/hook")
+            .match_body(mockito::Matcher::PartialJson(
+                json!({ "kind": "watchdog_kill", "key": "task-2" }),
+            ))
+            .with_status(200)
+            .create();
+        let sink = WebhookSink {
+            client: reqwest::blocking::Client::new(),
+            url: format!("{}/hook", server.url()),
+        };
+        sink.send(&Alert::new(AlertKind::WatchdogKill, "task-2", "stopped"))
+            .expect("accepted");
+        accepted.assert();
+
+        server.mock("POST", "/down").with_status(503).create();
+        let sink = WebhookSink {
+            client: reqwest::blocking::Client::new(),
+            url: format!("{}/down", server.url()),
+        };
+        let err = sink
+            .send(&Alert::new(AlertKind::WatchdogKill, "task-2", "stopped"))
+            .unwrap_err();
+        assert!(
+            matches!(err, AlertError::Rejected { status: 503, .. }),
+            "{}",
+            err
+        );
+    }
+}
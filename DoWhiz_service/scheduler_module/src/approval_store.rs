@@ -0,0 +1,308 @@
+//! Approval requests for scheduled tasks held until a human signs off.
+//!
+//! A run_task can attach an `approval` to a scheduled send or a new run_task.
+//! The scheduler stores that task disabled and records a request here, keyed
+//! by the task id, then notifies the employee's approver. The approval page
+//! and Slack buttons claim the request exactly once; only an approval enables
+//! the held task.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use mongodb::bson::{doc, DateTime as BsonDateTime, Document};
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument, UpdateOptions};
+use mongodb::sync::Collection;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::channel::Channel;
+use crate::mongo_store::{create_client_from_env, database_from_env};
+
+/// Pending requests can be decided this long after they were raised.
+pub const APPROVAL_TTL_HOURS: i64 = 72;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ApprovalStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// The approver's answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalDecision {
+    Approve,
+    Reject,
+}
+
+impl ApprovalDecision {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "approve" | "confirm" | "yes" => Some(Self::Approve),
+            "reject" | "cancel" | "no" => Some(Self::Reject),
+            _ => None,
+        }
+    }
+
+    pub fn status(self) -> ApprovalStatus {
+        match self {
+            Self::Approve => ApprovalStatus::Approved,
+            Self::Reject => ApprovalStatus::Rejected,
+        }
+    }
+}
+
+/// Raw `[employees.approvals]` table.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ApproverConfig {
+    /// `email` or `slack`.
+    #[serde(default)]
+    pub channel: Option<String>,
+    /// Email address, or Slack channel ID, that receives approval requests.
+    #[serde(default)]
+    pub approver: Option<String>,
+    /// Slack user IDs who may answer confirmation buttons posted in someone
+    /// else's thread.
+    #[serde(default)]
+    pub slack_users: Vec<String>,
+}
+
+/// Where approval requests are sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Approver {
+    pub channel: Channel,
+    pub address: String,
+}
+
+impl Approver {
+    /// `None` when the table is absent; an error when it is incomplete or
+    /// names a channel approvals cannot be sent on.
+    pub fn from_config(config: &ApproverConfig) -> Result<Option<Self>, String> {
+        let address = config
+            .approver
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        let channel = config
+            .channel
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        let (channel, address) = match (channel, address) {
+            (None, None) => return Ok(None),
+            (_, None) => return Err("approver is required".to_string()),
+            (channel, Some(address)) => (channel.unwrap_or("email"), address),
+        };
+        let channel = channel.parse::<Channel>()?;
+        if !matches!(channel, Channel::Email | Channel::Slack) {
+            return Err(format!("approvals cannot be sent on {}", channel));
+        }
+        Ok(Some(Self {
+            channel,
+            address: address.to_string(),
+        }))
+    }
+}
+
+/// A held task waiting for its approver.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRecord {
+    /// Same as `task_id`, so a replayed run_task completion finds the
+    /// existing request instead of raising a second one.
+    pub approval_id: String,
+    /// Secret carried by the approval link.
+    pub token: String,
+    #[serde(default)]
+    pub employee_id: Option<String>,
+    /// User whose task index must be refreshed once the task is enabled.
+    #[serde(default)]
+    pub owner_user_id: Option<String>,
+    pub tasks_db_path: String,
+    pub task_id: String,
+    /// Task kind label, e.g. `send_email` or `run_task`.
+    pub kind: String,
+    pub summary: String,
+    pub approver: Approver,
+    /// Slack user whose thread raised the request; may answer it alongside
+    /// the approver.
+    #[serde(default)]
+    pub requester: Option<String>,
+    pub status: ApprovalStatus,
+    #[serde(default)]
+    pub decided_by: Option<String>,
+    #[serde(with = "mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub expires_at: DateTime<Utc>,
+    /// JSON of the inbound envelope a `first_contact` request holds back.
+    #[serde(default)]
+    pub held_envelope: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApprovalStoreError {
+    #[error("mongodb error: {0}")]
+    Mongo(#[from] mongodb::error::Error),
+    #[error("bson error: {0}")]
+    Serialize(#[from] mongodb::bson::ser::Error),
+    #[error("bson error: {0}")]
+    Deserialize(#[from] mongodb::bson::de::Error),
+    #[error("mongo config error: {0}")]
+    MongoConfig(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ApprovalStore {
+    requests: Collection<Document>,
+}
+
+impl ApprovalStore {
+    pub fn new() -> Result<Self, ApprovalStoreError> {
+        let client = create_client_from_env()
+            .map_err(|err| ApprovalStoreError::MongoConfig(err.to_string()))?;
+        let db = database_from_env(&client);
+        let requests = db.collection::<Document>("approval_requests");
+        Ok(Self { requests })
+    }
+
+    /// Store `record` unless a request with its id exists. Returns whether it
+    /// was new, i.e. whether the approver still has to be notified.
+    pub fn insert_if_absent(&self, record: &ApprovalRecord) -> Result<bool, ApprovalStoreError> {
+        let document = mongodb::bson::to_document(record)?;
+        let result = self.requests.update_one(
+            doc! { "approval_id": &record.approval_id },
+            doc! { "$setOnInsert": document },
+            UpdateOptions::builder().upsert(Some(true)).build(),
+        )?;
+        Ok(result.upserted_id.is_some())
+    }
+
+    pub fn get(&self, approval_id: &str) -> Result<Option<ApprovalRecord>, ApprovalStoreError> {
+        match self
+            .requests
+            .find_one(doc! { "approval_id": approval_id }, None)?
+        {
+            Some(document) => Ok(Some(mongodb::bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Atomically decide a pending, unexpired request.
+    ///
+    /// `token` must match when given (approval links); Slack clicks are
+    /// already verified by their request signature and pass `None`. Returns
+    /// `None` when the request is unknown, expired, already decided, or the
+    /// token is wrong.
+    pub fn claim(
+        &self,
+        approval_id: &str,
+        token: Option<&str>,
+        decision: ApprovalDecision,
+        decided_by: &str,
+    ) -> Result<Option<ApprovalRecord>, ApprovalStoreError> {
+        let mut filter = doc! {
+            "approval_id": approval_id,
+            "status": ApprovalStatus::Pending.as_str(),
+            "expires_at": { "$gt": BsonDateTime::now() },
+        };
+        if let Some(token) = token {
+            filter.insert("token", token);
+        }
+        let updated = self.requests.find_one_and_update(
+            filter,
+            doc! {
+                "$set": {
+                    "status": decision.status().as_str(),
+                    "decided_by": decided_by,
+                }
+            },
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        )?;
+        match updated {
+            Some(document) => Ok(Some(mongodb::bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+static APPROVAL_STORE: std::sync::OnceLock<Option<Arc<ApprovalStore>>> = std::sync::OnceLock::new();
+
+/// Get or initialize the global ApprovalStore (returns None if not configured)
+pub fn get_global_approval_store() -> Option<Arc<ApprovalStore>> {
+    APPROVAL_STORE
+        .get_or_init(|| match ApprovalStore::new() {
+            Ok(store) => Some(Arc::new(store)),
+            Err(err) => {
+                warn!(
+                    "ApprovalStore not available ({}), held tasks cannot be approved",
+                    err
+                );
+                None
+            }
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decision_parse_accepts_button_values() {
+        assert_eq!(
+            ApprovalDecision::parse(" Approve "),
+            Some(ApprovalDecision::Approve)
+        );
+        assert_eq!(
+            ApprovalDecision::parse("no"),
+            Some(ApprovalDecision::Reject)
+        );
+        assert_eq!(ApprovalDecision::parse("maybe"), None);
+    }
+
+    #[test]
+    fn approver_defaults_to_email_and_rejects_other_channels() {
+        let approver = Approver::from_config(&ApproverConfig {
+            channel: None,
+            approver: Some(" legal@acme.com ".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            approver,
+            Some(Approver {
+                channel: Channel::Email,
+                address: "legal@acme.com".to_string(),
+            })
+        );
+        assert_eq!(
+            Approver::from_config(&ApproverConfig::default()).unwrap(),
+            None
+        );
+        assert!(Approver::from_config(&ApproverConfig {
+            channel: Some("slack".to_string()),
+            approver: None,
+            ..Default::default()
+        })
+        .is_err());
+        assert!(Approver::from_config(&ApproverConfig {
+            channel: Some("sms".to_string()),
+            approver: Some("+15550100100".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! At-rest encryption of the per-user mail archive.
+//!
+//! With `MAIL_ARCHIVE_MASTER_KEY` set, each user's `mail_root` gets a random
+//! data key, stored wrapped by the master key in `mail_root/.data_key`.
+//! Archived messages (and quarantined workspaces) are sealed with it file by
+//! file as soon as they are written, keeping their names. Reads go through
+//! [`read_archived`], which passes plaintext files from before encryption was
+//! turned on through unchanged; only hydrate decrypts.
+//!
+//! Sealed files are `DWZ1 || iv || AES-256-CBC(data) || HMAC-SHA256(iv || ciphertext)`.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+type Aes256CbcEnc = cbc::Encryptor<aes::Aes256>;
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+/// Hex-encoded 32-byte key wrapping every user's data key.
+pub const MAIL_ARCHIVE_MASTER_KEY_ENV: &str = "MAIL_ARCHIVE_MASTER_KEY";
+
+/// Wrapped data key, at the top of the user's mail root.
+pub const DATA_KEY_FILE: &str = ".data_key";
+
+const SEALED_MAGIC: &[u8] = b"DWZ1";
+const IV_LEN: usize = 16;
+const TAG_LEN: usize = 32;
+const SEAL_TMP_SUFFIX: &str = ".sealing";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ArchiveCryptoError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("{0} must be 64 hex characters")]
+    InvalidKey(&'static str),
+    #[error("{} is sealed but {MAIL_ARCHIVE_MASTER_KEY_ENV} or the data key is missing", .0)]
+    KeyUnavailable(String),
+    #[error("sealed data is corrupt or was sealed with another key")]
+    Integrity,
+}
+
+/// A user's mail archive data key.
+#[derive(Clone)]
+pub struct ArchiveKey([u8; 32]);
+
+impl std::fmt::Debug for ArchiveKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ArchiveKey(..)")
+    }
+}
+
+impl ArchiveKey {
+    #[cfg(test)]
+    pub(crate) fn from_bytes(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    /// The data key of `mail_root`, created on first use. `None` when archive
+    /// encryption is off.
+    pub fn for_writing(mail_root: &Path) -> Result<Option<Self>, ArchiveCryptoError> {
+        let Some(master) = key_from_env(MAIL_ARCHIVE_MASTER_KEY_ENV)? else {
+            return Ok(None);
+        };
+        let key_path = mail_root.join(DATA_KEY_FILE);
+        if let Some(key) = Self::unwrap_from(&master, &key_path)? {
+            return Ok(Some(key));
+        }
+
+        fs::create_dir_all(mail_root)?;
+        let data_key: [u8; 32] = rand::random();
+        let tmp_path = mail_root.join(format!("{}.{}", DATA_KEY_FILE, uuid::Uuid::new_v4()));
+        fs::write(&tmp_path, seal(&master, &data_key))?;
+        // hard_link fails if another worker created the key meanwhile; use theirs.
+        let linked = fs::hard_link(&tmp_path, &key_path);
+        let _ = fs::remove_file(&tmp_path);
+        match linked {
+            Ok(()) => Ok(Some(Self(data_key))),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                Self::unwrap_from(&master, &key_path)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// The existing data key of `mail_root`, if the master key is set and the
+    /// archive has one.
+    pub fn for_reading(mail_root: &Path) -> Result<Option<Self>, ArchiveCryptoError> {
+        match key_from_env(MAIL_ARCHIVE_MASTER_KEY_ENV)? {
+            Some(master) => Self::unwrap_from(&master, &mail_root.join(DATA_KEY_FILE)),
+            None => Ok(None),
+        }
+    }
+
+    fn unwrap_from(master: &[u8; 32], key_path: &Path) -> Result<Option<Self>, ArchiveCryptoError> {
+        let wrapped = match fs::read(key_path) {
+            Ok(wrapped) => wrapped,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let key = open(master, &wrapped)?
+            .try_into()
+            .map_err(|_| ArchiveCryptoError::Integrity)?;
+        Ok(Some(Self(key)))
+    }
+
+    /// `data` sealed with this key, readable through [`read_archived`].
+    pub fn seal_bytes(&self, data: &[u8]) -> Vec<u8> {
+        seal(&self.0, data)
+    }
+
+    /// Seal every plaintext file under `dir` in place. Returns the number of
+    /// files sealed.
+    pub fn seal_dir(&self, dir: &Path) -> Result<usize, ArchiveCryptoError> {
+        let mut sealed = 0;
+        let mut stack = vec![dir.to_path_buf()];
+        while let Some(current) = stack.pop() {
+            for entry in fs::read_dir(&current)? {
+                let entry = entry?;
+                let file_type = entry.file_type()?;
+                let path = entry.path();
+                if file_type.is_dir() {
+                    stack.push(path);
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !file_type.is_file()
+                    || name.starts_with(DATA_KEY_FILE)
+                    || name.ends_with(SEAL_TMP_SUFFIX)
+                    || is_sealed_file(&path)?
+                {
+                    continue;
+                }
+                let tmp_path = path.with_file_name(format!("{}{}", name, SEAL_TMP_SUFFIX));
+                fs::write(&tmp_path, seal(&self.0, &fs::read(&path)?))?;
+                fs::rename(&tmp_path, &path)?;
+                sealed += 1;
+            }
+        }
+        Ok(sealed)
+    }
+}
+
+/// Contents of an archived file, decrypted when it is sealed.
+pub fn read_archived(key: Option<&ArchiveKey>, path: &Path) -> Result<Vec<u8>, ArchiveCryptoError> {
+    let data = fs::read(path)?;
+    if !is_sealed(&data) {
+        return Ok(data);
+    }
+    let key = key.ok_or_else(|| ArchiveCryptoError::KeyUnavailable(path.display().to_string()))?;
+    open(&key.0, &data)
+}
+
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.len() >= SEALED_MAGIC.len() + IV_LEN + TAG_LEN && data.starts_with(SEALED_MAGIC)
+}
+
+/// Whether `path` starts like a sealed file, without reading all of it.
+pub fn is_sealed_file(path: &Path) -> io::Result<bool> {
+    let mut magic = [0u8; 4];
+    let mut file = fs::File::open(path)?;
+    match file.read_exact(&mut magic) {
+        Ok(()) => Ok(magic == SEALED_MAGIC),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Hex-encoded 32-byte key from `name`; `None` when unset or blank.
+pub fn key_from_env(name: &'static str) -> Result<Option<[u8; 32]>, ArchiveCryptoError> {
+    let Some(raw) = std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    let bytes = hex::decode(raw.trim()).map_err(|_| ArchiveCryptoError::InvalidKey(name))?;
+    bytes
+        .try_into()
+        .map(Some)
+        .map_err(|_| ArchiveCryptoError::InvalidKey(name))
+}
+
+pub fn seal(key: &[u8; 32], data: &[u8]) -> Vec<u8> {
+    let iv: [u8; IV_LEN] = rand::random();
+    let ciphertext =
+        Aes256CbcEnc::new(key.into(), &iv.into()).encrypt_padded_vec_mut::<Pkcs7>(data);
+    let mut out = Vec::with_capacity(SEALED_MAGIC.len() + IV_LEN + ciphertext.len() + TAG_LEN);
+    out.extend_from_slice(SEALED_MAGIC);
+    out.extend_from_slice(&iv);
+    out.extend_from_slice(&ciphertext);
+    let tag = mac(key, &out[SEALED_MAGIC.len()..]).finalize().into_bytes();
+    out.extend_from_slice(&tag);
+    out
+}
+
+pub fn open(key: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>, ArchiveCryptoError> {
+    if !is_sealed(sealed) {
+        return Err(ArchiveCryptoError::Integrity);
+    }
+    let body = &sealed[SEALED_MAGIC.len()..];
+    let (body, tag) = body.split_at(body.len() - TAG_LEN);
+    mac(key, body)
+        .verify_slice(tag)
+        .map_err(|_| ArchiveCryptoError::Integrity)?;
+    let (iv, ciphertext) = body.split_at(IV_LEN);
+    let iv: [u8; IV_LEN] = iv.try_into().map_err(|_| ArchiveCryptoError::Integrity)?;
+    Aes256CbcDec::new(key.into(), &iv.into())
+        .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+        .map_err(|_| ArchiveCryptoError::Integrity)
+}
+
+/// MAC keyed separately from the cipher, derived from the same secret.
+fn mac(key: &[u8; 32], body: &[u8]) -> HmacSha256 {
+    let mac_key = Sha256::new()
+        .chain_update(b"dowhiz-archive-mac")
+        .chain_update(key)
+        .finalize();
+    let mut mac = HmacSha256::new_from_slice(&mac_key).expect("hmac accepts any key length");
+    mac.update(body);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn seal_round_trips_and_detects_tampering() {
+        let key = [7u8; 32];
+        let sealed = seal(&key, b"call 555-010-0100");
+        assert!(is_sealed(&sealed));
+        assert_eq!(open(&key, &sealed).unwrap(), b"call 555-010-0100".to_vec());
+
+        let mut tampered = sealed.clone();
+        tampered[SEALED_MAGIC.len() + IV_LEN] ^= 1;
+        assert!(matches!(
+            open(&key, &tampered),
+            Err(ArchiveCryptoError::Integrity)
+        ));
+        assert!(matches!(
+            open(&[8u8; 32], &sealed),
+            Err(ArchiveCryptoError::Integrity)
+        ));
+    }
+
+    #[test]
+    fn seal_dir_is_idempotent_and_read_archived_passes_plaintext() {
+        let temp = TempDir::new().expect("tempdir");
+        let message = temp.path().join("2026").join("02").join("msg_1");
+        let email = message.join("incoming_email");
+        fs::create_dir_all(&email).expect("dirs");
+        fs::write(email.join("postmark_payload.json"), "{}").expect("payload");
+        fs::write(message.join("empty.txt"), "").expect("empty");
+        let legacy = temp.path().join("legacy.txt");
+
+        let key = ArchiveKey([3u8; 32]);
+        assert_eq!(key.seal_dir(&message).unwrap(), 2);
+        assert_eq!(key.seal_dir(&message).unwrap(), 0);
+
+        let payload = email.join("postmark_payload.json");
+        assert!(is_sealed_file(&payload).unwrap());
+        assert_eq!(read_archived(Some(&key), &payload).unwrap(), b"{}".to_vec());
+        assert!(matches!(
+            read_archived(None, &payload),
+            Err(ArchiveCryptoError::KeyUnavailable(_))
+        ));
+
+        fs::write(&legacy, "plain").expect("legacy");
+        assert_eq!(read_archived(None, &legacy).unwrap(), b"plain".to_vec());
+    }
+
+    #[test]
+    fn data_key_is_wrapped_and_reused() {
+        let temp = TempDir::new().expect("tempdir");
+        let master = [9u8; 32];
+        let key_path = temp.path().join(DATA_KEY_FILE);
+        assert!(ArchiveKey::unwrap_from(&master, &key_path)
+            .unwrap()
+            .is_none());
+
+        fs::write(&key_path, seal(&master, &[5u8; 32])).expect("key");
+        let key = ArchiveKey::unwrap_from(&master, &key_path)
+            .unwrap()
+            .expect("key");
+        assert_eq!(key.0, [5u8; 32]);
+        assert!(ArchiveKey::unwrap_from(&[1u8; 32], &key_path).is_err());
+    }
+}
@@ -0,0 +1,497 @@
+//! Cold tier for the per-user mail archive.
+//!
+//! With `[employees.archive_tiering]` enabled, a background pass packs every
+//! archived message (`mail_root/YYYY/MM/<msg>/`) whose files have not changed
+//! for `after_days` into one gzip bundle, uploads it to the object store picked
+//! by `ARCHIVE_TIER_BACKEND` (Azure Blob, S3 or a local directory) and removes
+//! the local copy. Sealed files stay sealed inside the bundle.
+//!
+//! `mail_root/.tier_index.json` records where each tiered message went and
+//! keeps its search record, so workspaces can still find it through the
+//! past-email search index. The tier index is sealed like the archive when
+//! archive encryption is on. [`rehydrate`] brings messages back on demand.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::archive_crypto::{self, ArchiveCryptoError, ArchiveKey};
+use crate::object_store::{safe_relative_path, ObjectStore, ObjectStoreError};
+use crate::past_email_index::IndexedEmail;
+
+/// Tier index, at the top of the user's mail root.
+pub const TIER_INDEX_FILE: &str = ".tier_index.json";
+
+/// Env prefix of the cold store, see [`crate::object_store`].
+pub const COLD_STORE_ENV_PREFIX: &str = "ARCHIVE_TIER";
+
+const DEFAULT_AFTER_DAYS: u32 = 90;
+const INDEX_VERSION: u32 = 1;
+const BUNDLE_VERSION: u32 = 1;
+const BUNDLE_SUFFIX: &str = ".json.gz";
+
+#[derive(Debug, thiserror::Error)]
+pub enum TieringError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("archive encryption error: {0}")]
+    ArchiveCrypto(#[from] ArchiveCryptoError),
+    #[error("cold storage error: {0}")]
+    ObjectStore(#[from] ObjectStoreError),
+    #[error("archive tiering is misconfigured: {0}")]
+    Config(String),
+    #[error("no tiered message matches {0}")]
+    NotTiered(String),
+    #[error("tiered bundle for {0} is corrupt")]
+    Corrupt(String),
+}
+
+/// Raw `[employees.archive_tiering]` table.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ArchiveTieringConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Messages unchanged for this many days move to cold storage; default 90.
+    #[serde(default)]
+    pub after_days: Option<u32>,
+    /// Object key prefix; the employee id when unset.
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveTiering {
+    pub after_days: u32,
+    pub prefix: Option<String>,
+}
+
+impl ArchiveTiering {
+    pub fn from_config(config: &ArchiveTieringConfig) -> Result<Option<Self>, String> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let after_days = config.after_days.unwrap_or(DEFAULT_AFTER_DAYS);
+        if after_days == 0 {
+            return Err("after_days must be at least 1".to_string());
+        }
+        let prefix = config
+            .prefix
+            .as_deref()
+            .map(|prefix| prefix.trim().trim_matches('/').to_string())
+            .filter(|prefix| !prefix.is_empty());
+        Ok(Some(Self { after_days, prefix }))
+    }
+}
+
+/// Where each tiered message of one mail root went.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TierIndex {
+    pub version: u32,
+    /// Keyed by archive path relative to the mail root, e.g. `2026/02/msg-1`
+    pub entries: BTreeMap<String, TieredEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TieredEntry {
+    pub backend: String,
+    pub object_key: String,
+    /// RFC 3339
+    pub tiered_at: String,
+    pub size_bytes: u64,
+    /// Hex SHA-256 of the bundle
+    pub sha256: String,
+    /// Search record, so the message stays findable while tiered
+    pub email: Option<IndexedEmail>,
+}
+
+impl TierIndex {
+    /// The tier index of `mail_root`; empty when nothing was tiered yet.
+    pub fn load(mail_root: &Path, key: Option<&ArchiveKey>) -> Result<Self, TieringError> {
+        let path = mail_root.join(TIER_INDEX_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let data = archive_crypto::read_archived(key, &path)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    fn write(&mut self, mail_root: &Path, key: Option<&ArchiveKey>) -> Result<(), TieringError> {
+        self.version = INDEX_VERSION;
+        let data = serde_json::to_vec(self)?;
+        let data = match key {
+            Some(key) => key.seal_bytes(&data),
+            None => data,
+        };
+        let tmp_path = mail_root.join(format!("{}.{}", TIER_INDEX_FILE, uuid::Uuid::new_v4()));
+        fs::write(&tmp_path, data)?;
+        fs::rename(&tmp_path, mail_root.join(TIER_INDEX_FILE))?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TierReport {
+    pub tiered: usize,
+    pub bytes_uploaded: u64,
+    pub failed: usize,
+}
+
+/// Move the messages under `mail_root` whose files are all older than
+/// `policy.after_days` to `store`, as `<key_prefix>/<YYYY/MM/msg>.json.gz`.
+/// A failing upload stops the pass; the next pass picks up where it stopped.
+pub fn tier_mail_archive(
+    mail_root: &Path,
+    store: &dyn ObjectStore,
+    policy: &ArchiveTiering,
+    key_prefix: &str,
+    now: DateTime<Utc>,
+) -> Result<TierReport, TieringError> {
+    let mut report = TierReport::default();
+    if !mail_root.is_dir() {
+        return Ok(report);
+    }
+    let key = ArchiveKey::for_writing(mail_root)?;
+    let cutoff = SystemTime::from(now - chrono::Duration::days(i64::from(policy.after_days)));
+    let mut index = TierIndex::load(mail_root, key.as_ref())?;
+
+    for (id, dir) in archive_entries(mail_root)? {
+        match last_modified(&dir) {
+            Ok(Some(modified)) if modified <= cutoff => {}
+            Ok(_) => continue,
+            Err(err) => {
+                warn!("archive tiering skipped {}: {}", dir.display(), err);
+                report.failed += 1;
+                continue;
+            }
+        }
+        let email = crate::past_emails::indexed_email_for_entry(mail_root, key.as_ref(), &dir)
+            .unwrap_or_else(|err| {
+                warn!("archive tiering: no search record for {}: {}", id, err);
+                None
+            });
+        let bundle = match pack_entry(&id, &dir) {
+            Ok(bundle) => bundle,
+            Err(err) => {
+                warn!("archive tiering skipped {}: {}", dir.display(), err);
+                report.failed += 1;
+                continue;
+            }
+        };
+        let object_key = format!("{}/{}{}", key_prefix.trim_matches('/'), id, BUNDLE_SUFFIX);
+        store.put(&object_key, &bundle)?;
+        index.entries.insert(
+            id,
+            TieredEntry {
+                backend: store.name().to_string(),
+                object_key,
+                tiered_at: now.to_rfc3339(),
+                size_bytes: bundle.len() as u64,
+                sha256: hex::encode(Sha256::digest(&bundle)),
+                email,
+            },
+        );
+        index.write(mail_root, key.as_ref())?;
+        fs::remove_dir_all(&dir)?;
+        report.tiered += 1;
+        report.bytes_uploaded += bundle.len() as u64;
+    }
+    Ok(report)
+}
+
+/// Download the tiered messages whose id is `id`, or starts with `id/` (a
+/// whole month, e.g. `2026/02`), back into `mail_root`. Returns the ids
+/// restored.
+pub fn rehydrate(
+    mail_root: &Path,
+    store: &dyn ObjectStore,
+    id: &str,
+) -> Result<Vec<String>, TieringError> {
+    let id = id.trim().trim_matches('/');
+    let key = ArchiveKey::for_reading(mail_root)?;
+    let mut index = TierIndex::load(mail_root, key.as_ref())?;
+    let month_prefix = format!("{}/", id);
+    let matching: Vec<String> = index
+        .entries
+        .keys()
+        .filter(|entry| !id.is_empty() && (*entry == id || entry.starts_with(&month_prefix)))
+        .cloned()
+        .collect();
+    if matching.is_empty() {
+        return Err(TieringError::NotTiered(id.to_string()));
+    }
+
+    let mut restored = Vec::with_capacity(matching.len());
+    for entry_id in matching {
+        let Some(entry) = index.entries.get(&entry_id).cloned() else {
+            continue;
+        };
+        if entry.backend != store.name() {
+            return Err(TieringError::Config(format!(
+                "{} was tiered to {} but the configured backend is {}",
+                entry_id,
+                entry.backend,
+                store.name()
+            )));
+        }
+        let bundle = store.get(&entry.object_key)?;
+        if hex::encode(Sha256::digest(&bundle)) != entry.sha256 {
+            return Err(TieringError::Corrupt(entry_id));
+        }
+        unpack_bundle(&entry_id, &bundle, &mail_root.join(&entry_id))?;
+        index.entries.remove(&entry_id);
+        index.write(mail_root, key.as_ref())?;
+        restored.push(entry_id);
+    }
+    Ok(restored)
+}
+
+/// Search records of the messages tiered out of `mail_root`. An unreadable
+/// tier index is logged and treated as empty.
+pub fn tiered_emails(mail_root: &Path, key: Option<&ArchiveKey>) -> Vec<IndexedEmail> {
+    match TierIndex::load(mail_root, key) {
+        Ok(index) => index
+            .entries
+            .into_values()
+            .filter_map(|entry| entry.email)
+            .collect(),
+        Err(err) => {
+            warn!(
+                "failed to read tier index of {}: {}",
+                mail_root.display(),
+                err
+            );
+            Vec::new()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Bundle {
+    version: u32,
+    id: String,
+    files: Vec<BundleFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BundleFile {
+    /// Relative to the message directory, `/`-separated
+    path: String,
+    /// Base64 of the file as archived (sealed files stay sealed)
+    data: String,
+}
+
+/// Message directories `YYYY/MM/<msg>` under `mail_root`, by id.
+fn archive_entries(mail_root: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut entries = Vec::new();
+    for year in numeric_subdirs(mail_root, 4)? {
+        for month in numeric_subdirs(&year, 2)? {
+            for entry in fs::read_dir(&month)? {
+                let entry = entry?;
+                if !entry.file_type()?.is_dir() {
+                    continue;
+                }
+                let id = [&year, &month, &entry.path()]
+                    .iter()
+                    .filter_map(|path| path.file_name())
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                entries.push((id, entry.path()));
+            }
+        }
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+fn numeric_subdirs(dir: &Path, digits: usize) -> io::Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_dir()
+            && name.len() == digits
+            && name.chars().all(|ch| ch.is_ascii_digit())
+        {
+            dirs.push(entry.path());
+        }
+    }
+    Ok(dirs)
+}
+
+/// Files under `dir`, sorted, skipping symlinks.
+fn entry_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Newest modification time of the files under `dir`.
+fn last_modified(dir: &Path) -> io::Result<Option<SystemTime>> {
+    let mut newest = None;
+    for path in entry_files(dir)? {
+        let modified = fs::metadata(&path)?.modified()?;
+        newest = newest.max(Some(modified));
+    }
+    Ok(newest)
+}
+
+fn pack_entry(id: &str, dir: &Path) -> Result<Vec<u8>, TieringError> {
+    let mut files = Vec::new();
+    for path in entry_files(dir)? {
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        files.push(BundleFile {
+            path: relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/"),
+            data: base64::engine::general_purpose::STANDARD.encode(fs::read(&path)?),
+        });
+    }
+    let bundle = Bundle {
+        version: BUNDLE_VERSION,
+        id: id.to_string(),
+        files,
+    };
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&serde_json::to_vec(&bundle)?)?;
+    Ok(encoder.finish()?)
+}
+
+fn unpack_bundle(id: &str, data: &[u8], dest: &Path) -> Result<usize, TieringError> {
+    let mut json = Vec::new();
+    GzDecoder::new(data)
+        .read_to_end(&mut json)
+        .map_err(|_| TieringError::Corrupt(id.to_string()))?;
+    let bundle: Bundle =
+        serde_json::from_slice(&json).map_err(|_| TieringError::Corrupt(id.to_string()))?;
+    if bundle.id != id {
+        return Err(TieringError::Corrupt(id.to_string()));
+    }
+    for file in &bundle.files {
+        let relative =
+            safe_relative_path(&file.path).ok_or_else(|| TieringError::Corrupt(id.to_string()))?;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(&file.data)
+            .map_err(|_| TieringError::Corrupt(id.to_string()))?;
+        let path = dest.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, bytes)?;
+    }
+    Ok(bundle.files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn write_message(mail_root: &Path, id: &str, subject: &str, age_days: u64) {
+        let dir = mail_root.join(id);
+        fs::create_dir_all(dir.join("incoming_email")).expect("incoming_email");
+        fs::create_dir_all(dir.join("incoming_attachments")).expect("attachments");
+        let files = [
+            (
+                dir.join("incoming_email").join("postmark_payload.json"),
+                serde_json::json!({
+                    "From": "Alice <alice@example.com>",
+                    "To": "oliver@dowhiz.com",
+                    "Subject": subject,
+                    "Date": "Mon, 05 Jan 2026 10:00:00 +0000",
+                    "MessageID": format!("<{}@example.com>", subject),
+                    "TextBody": format!("Body of {}", subject),
+                })
+                .to_string()
+                .into_bytes(),
+            ),
+            (
+                dir.join("incoming_attachments").join("notes.txt"),
+                b"attachment".to_vec(),
+            ),
+        ];
+        let modified = SystemTime::now() - Duration::from_secs(age_days * 24 * 3600);
+        for (path, data) in files {
+            fs::write(&path, data).expect("write");
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(modified))
+                .expect("set mtime");
+        }
+    }
+
+    #[test]
+    fn tiers_old_messages_and_rehydrates_them() {
+        let temp = tempfile::TempDir::new().expect("tempdir");
+        let mail_root = temp.path().join("mail");
+        let store = crate::object_store::LocalObjectStore::new(temp.path().join("cold"));
+        write_message(&mail_root, "2026/01/old-msg", "Old invoice", 200);
+        write_message(&mail_root, "2026/03/new-msg", "New invoice", 1);
+        let original = fs::read(mail_root.join("2026/01/old-msg/incoming_attachments/notes.txt"))
+            .expect("read");
+        let policy = ArchiveTiering {
+            after_days: 90,
+            prefix: None,
+        };
+
+        let report = tier_mail_archive(&mail_root, &store, &policy, "oliver/user-1", Utc::now())
+            .expect("tier");
+        assert_eq!(report.tiered, 1);
+        assert!(!mail_root.join("2026/01/old-msg").exists());
+        assert!(mail_root.join("2026/03/new-msg").is_dir());
+        assert!(temp
+            .path()
+            .join("cold/oliver/user-1/2026/01/old-msg.json.gz")
+            .is_file());
+        let tiered = tiered_emails(&mail_root, None);
+        assert_eq!(tiered.len(), 1);
+        assert_eq!(tiered[0].id, "2026/01/old-msg");
+        assert_eq!(tiered[0].subject, "Old invoice");
+
+        let references = temp.path().join("references");
+        let indexed = crate::past_emails::index_past_emails(&mail_root, &references, "user-1")
+            .expect("index");
+        assert_eq!(indexed, 2, "tiered messages stay searchable");
+
+        assert!(matches!(
+            rehydrate(&mail_root, &store, "2025"),
+            Err(TieringError::NotTiered(_))
+        ));
+        let restored = rehydrate(&mail_root, &store, "2026/01").expect("rehydrate");
+        assert_eq!(restored, vec!["2026/01/old-msg".to_string()]);
+        assert_eq!(
+            fs::read(mail_root.join("2026/01/old-msg/incoming_attachments/notes.txt"))
+                .expect("read"),
+            original
+        );
+        assert!(tiered_emails(&mail_root, None).is_empty());
+    }
+}
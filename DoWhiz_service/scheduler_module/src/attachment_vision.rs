@@ -0,0 +1,391 @@
+//! Inbound image OCR and description pipeline.
+//!
+//! Before a RunTask starts, image attachments in the workspace's incoming
+//! attachments dir can be sent to a vision-capable model. The model returns the
+//! visible text (OCR) plus a short description, which is written next to the
+//! image as `<image name>_attachment_description.md`. This lets text-only
+//! runners handle screenshot-heavy requests ("what's wrong with this error?").
+//!
+//! Configuration:
+//! - `ATTACHMENT_VISION_ENABLED`: Set to "true" to enable (default: disabled)
+//! - `ATTACHMENT_VISION_MODEL`: Model to use (default: `gpt-5.4`)
+//! - `ATTACHMENT_VISION_MAX_IMAGES`: Max images described per task (default: 8)
+//! - `ATTACHMENT_VISION_MAX_BYTES`: Skip images larger than this (default: 10 MiB)
+//! - Credentials follow the message router: `AZURE_OPENAI_API_KEY_BACKUP` +
+//!   `AZURE_OPENAI_ENDPOINT_BACKUP`, otherwise `OPENAI_API_KEY` / `OPENAI_API_URL`.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+/// Default OpenAI API URL
+const DEFAULT_OPENAI_URL: &str = "https://api.openai.com/v1";
+
+/// Default vision model
+const DEFAULT_MODEL: &str = "gpt-5.4";
+
+/// Timeout for a single vision request
+const VISION_TIMEOUT: Duration = Duration::from_secs(60);
+
+const DEFAULT_MAX_IMAGES: usize = 8;
+const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Suffix of the generated description files.
+pub const DESCRIPTION_FILE_SUFFIX: &str = "_attachment_description.md";
+
+const VISION_PROMPT: &str = r#"You are preparing an image attachment for an assistant that cannot see images.
+
+Respond in Markdown with exactly these sections:
+
+## Extracted text
+Transcribe all readable text verbatim (error messages, code, UI labels, tables). Preserve line breaks. Write "(none)" if there is no text.
+
+## Description
+Describe what the image shows in a few sentences: the kind of image (screenshot, photo, chart, document scan), the application or context, and anything that looks wrong or noteworthy (errors, warnings, highlighted areas)."#;
+
+/// Configuration for the attachment vision stage
+#[derive(Debug, Clone)]
+pub struct AttachmentVisionConfig {
+    /// Whether the stage is enabled
+    pub enabled: bool,
+    /// OpenAI / Azure OpenAI API key
+    pub api_key: Option<String>,
+    /// OpenAI-compatible API URL
+    pub api_url: String,
+    /// Model to use
+    pub model: String,
+    /// Whether to use Azure OpenAI auth header
+    pub use_azure_auth: bool,
+    /// Max images described per task
+    pub max_images: usize,
+    /// Images above this size are skipped
+    pub max_bytes: u64,
+}
+
+impl AttachmentVisionConfig {
+    pub fn from_env() -> Self {
+        let azure_api_key = env_var_non_empty("AZURE_OPENAI_API_KEY_BACKUP");
+        let azure_endpoint = env_var_non_empty("AZURE_OPENAI_ENDPOINT_BACKUP");
+        let (api_key, api_url, use_azure_auth) =
+            if let (Some(api_key), Some(endpoint)) = (azure_api_key, azure_endpoint) {
+                (Some(api_key), normalize_azure_endpoint(&endpoint), true)
+            } else {
+                let api_url = env_var_non_empty("OPENAI_API_URL")
+                    .unwrap_or_else(|| DEFAULT_OPENAI_URL.to_string());
+                (env_var_non_empty("OPENAI_API_KEY"), api_url, false)
+            };
+
+        Self {
+            enabled: env_var_non_empty("ATTACHMENT_VISION_ENABLED")
+                .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            api_key,
+            api_url,
+            model: env_var_non_empty("ATTACHMENT_VISION_MODEL")
+                .unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            use_azure_auth,
+            max_images: env_var_non_empty("ATTACHMENT_VISION_MAX_IMAGES")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_MAX_IMAGES),
+            max_bytes: env_var_non_empty("ATTACHMENT_VISION_MAX_BYTES")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_MAX_BYTES),
+        }
+    }
+}
+
+/// Run the vision stage over `attachments_dir` using configuration from env.
+///
+/// Failures are logged and never fail the task; the runner simply proceeds
+/// without descriptions.
+pub fn preprocess_image_attachments(attachments_dir: &Path) {
+    let config = AttachmentVisionConfig::from_env();
+    if !config.enabled {
+        return;
+    }
+    let Some(api_key) = config.api_key.clone() else {
+        warn!("ATTACHMENT_VISION_ENABLED is set but no OpenAI credentials are configured");
+        return;
+    };
+    let client = match reqwest::blocking::Client::builder()
+        .timeout(VISION_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("failed to build attachment vision client: {}", err);
+            return;
+        }
+    };
+
+    let written = describe_image_attachments(
+        attachments_dir,
+        config.max_images,
+        config.max_bytes,
+        |mime, bytes| request_description(&client, &config, &api_key, mime, bytes),
+    );
+    if written > 0 {
+        info!(
+            "attachment vision wrote {} description(s) in {}",
+            written,
+            attachments_dir.display()
+        );
+    }
+}
+
+/// Describe every image in `attachments_dir` with `describe` and write the
+/// result as `<image name>_attachment_description.md`. A description whose
+/// recorded hash matches the image is kept, so retries don't pay for the same
+/// image twice; one left by an earlier image of the same name is replaced.
+///
+/// Returns the number of description files written.
+pub fn describe_image_attachments<F>(
+    attachments_dir: &Path,
+    max_images: usize,
+    max_bytes: u64,
+    mut describe: F,
+) -> usize
+where
+    F: FnMut(&str, &[u8]) -> Result<String, String>,
+{
+    let images = list_image_attachments(attachments_dir);
+    let mut written = 0usize;
+    for path in images.iter().take(max_images) {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let target = description_path(attachments_dir, &name);
+        let Some(mime) = image_mime_type(path) else {
+            continue;
+        };
+        let size = std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+        if size == 0 || size > max_bytes {
+            info!(
+                "skipping attachment vision for {} ({} bytes)",
+                path.display(),
+                size
+            );
+            continue;
+        }
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                warn!(
+                    "failed to read image attachment {}: {}",
+                    path.display(),
+                    err
+                );
+                continue;
+            }
+        };
+        let hash_line = format!("Source sha256: `{}`", hex::encode(Sha256::digest(&bytes)));
+        let described = std::fs::read_to_string(&target)
+            .is_ok_and(|existing| existing.lines().any(|line| line == hash_line));
+        if described {
+            continue;
+        }
+        let description = match describe(mime, &bytes) {
+            Ok(description) if !description.trim().is_empty() => description,
+            Ok(_) => {
+                warn!("attachment vision returned empty output for {}", name);
+                continue;
+            }
+            Err(err) => {
+                warn!("attachment vision failed for {}: {}", name, err);
+                continue;
+            }
+        };
+        let content = format!(
+            "# Attachment description: {}\n\nSource file: `{}`\n{}\n\n{}\n",
+            name,
+            name,
+            hash_line,
+            description.trim()
+        );
+        match std::fs::write(&target, content) {
+            Ok(()) => written += 1,
+            Err(err) => warn!("failed to write {}: {}", target.display(), err),
+        }
+    }
+    written
+}
+
+fn description_path(attachments_dir: &Path, image_name: &str) -> PathBuf {
+    attachments_dir.join(format!("{}{}", image_name, DESCRIPTION_FILE_SUFFIX))
+}
+
+fn list_image_attachments(attachments_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(attachments_dir) else {
+        return Vec::new();
+    };
+    let mut images: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && image_mime_type(path).is_some())
+        .collect();
+    images.sort();
+    images
+}
+
+fn image_mime_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+fn request_description(
+    client: &reqwest::blocking::Client,
+    config: &AttachmentVisionConfig,
+    api_key: &str,
+    mime: &str,
+    bytes: &[u8],
+) -> Result<String, String> {
+    let url = format!("{}/chat/completions", config.api_url.trim_end_matches('/'));
+    let data_url = format!("data:{};base64,{}", mime, BASE64_STANDARD.encode(bytes));
+    let request = json!({
+        "model": config.model,
+        "messages": [{
+            "role": "user",
+            "content": [
+                { "type": "text", "text": VISION_PROMPT },
+                { "type": "image_url", "image_url": { "url": data_url } }
+            ]
+        }],
+        "max_completion_tokens": 2048
+    });
+
+    let mut builder = client.post(&url).header("Content-Type", "application/json");
+    if config.use_azure_auth {
+        builder = builder.header("api-key", api_key);
+    } else {
+        builder = builder.header("Authorization", format!("Bearer {}", api_key));
+    }
+    let response = builder
+        .json(&request)
+        .send()
+        .map_err(|err| format!("HTTP request failed: {}", err))?;
+    let status = response.status();
+    let body = response.text().unwrap_or_default();
+    if !status.is_success() {
+        return Err(format!("vision API returned {}: {}", status, body));
+    }
+    let payload: serde_json::Value =
+        serde_json::from_str(&body).map_err(|err| format!("invalid response: {}", err))?;
+    Ok(payload
+        .pointer("/choices/0/message/content")
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .to_string())
+}
+
+fn normalize_azure_endpoint(raw: &str) -> String {
+    let trimmed = raw.trim().trim_end_matches('/');
+    if trimmed.ends_with("/openai/v1") {
+        trimmed.to_string()
+    } else {
+        format!("{}/openai/v1", trimmed)
+    }
+}
+
+fn env_var_non_empty(key: &str) -> Option<String> {
+    env::var(key)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn writes_a_description_named_after_each_image() {
+        let temp = TempDir::new().expect("tempdir");
+        let dir = temp.path();
+        std::fs::write(dir.join("b_screenshot.png"), b"png-bytes").expect("png");
+        std::fs::write(dir.join("a_photo.JPG"), b"jpg-bytes").expect("jpg");
+        std::fs::write(dir.join("notes.pdf"), b"pdf-bytes").expect("pdf");
+
+        let mut seen = Vec::new();
+        let written = describe_image_attachments(dir, 8, 1024, |mime, bytes| {
+            seen.push((mime.to_string(), bytes.to_vec()));
+            Ok("## Extracted text\nTypeError".to_string())
+        });
+
+        assert_eq!(written, 2);
+        assert_eq!(seen[0].0, "image/jpeg");
+        assert_eq!(seen[1].0, "image/png");
+        let first = std::fs::read_to_string(dir.join("a_photo.JPG_attachment_description.md"))
+            .expect("first description");
+        assert!(first.contains("Source file: `a_photo.JPG`"));
+        assert!(first.contains("TypeError"));
+        assert!(dir
+            .join("b_screenshot.png_attachment_description.md")
+            .exists());
+        assert!(!dir.join("notes.pdf_attachment_description.md").exists());
+    }
+
+    #[test]
+    fn skips_described_and_oversized_images() {
+        let temp = TempDir::new().expect("tempdir");
+        let dir = temp.path();
+        std::fs::write(dir.join("a.png"), b"small").expect("a");
+        std::fs::write(dir.join("b.png"), vec![0u8; 64]).expect("b");
+        assert_eq!(
+            describe_image_attachments(dir, 8, 32, |_, _| Ok("described".to_string())),
+            1
+        );
+
+        let mut calls = 0;
+        let written = describe_image_attachments(dir, 8, 32, |_, _| {
+            calls += 1;
+            Ok("again".to_string())
+        });
+
+        assert_eq!(written, 0);
+        assert_eq!(calls, 0);
+        assert!(!dir.join("b.png_attachment_description.md").exists());
+    }
+
+    #[test]
+    fn a_new_image_with_the_same_name_is_described_again() {
+        let temp = TempDir::new().expect("tempdir");
+        let dir = temp.path();
+        std::fs::write(dir.join("image.png"), b"first").expect("first");
+        describe_image_attachments(dir, 8, 1024, |_, _| Ok("a cat".to_string()));
+
+        std::fs::write(dir.join("image.png"), b"second").expect("second");
+        let written = describe_image_attachments(dir, 8, 1024, |_, _| Ok("a dog".to_string()));
+
+        assert_eq!(written, 1);
+        let description =
+            std::fs::read_to_string(dir.join("image.png_attachment_description.md")).unwrap();
+        assert!(description.contains("a dog"));
+        assert!(!description.contains("a cat"));
+    }
+
+    #[test]
+    fn failed_descriptions_are_not_written() {
+        let temp = TempDir::new().expect("tempdir");
+        let dir = temp.path();
+        std::fs::write(dir.join("a.webp"), b"webp").expect("a");
+
+        let written = describe_image_attachments(dir, 8, 1024, |_, _| Err("boom".to_string()));
+
+        assert_eq!(written, 0);
+        assert!(!dir.join("a.webp_attachment_description.md").exists());
+    }
+}
@@ -0,0 +1,224 @@
+//! Append-only record of external actions the agent took for a user.
+//!
+//! Entries are written when the scheduler delivers a message on a channel and
+//! when the `google-docs` CLI changes a document from inside a run. The store
+//! only ever inserts; nothing in the service updates or deletes entries.
+//! Payloads are not kept, only their SHA-256, so the log can prove what was
+//! sent without duplicating user content.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use mongodb::bson::{doc, DateTime as BsonDateTime, Document};
+use mongodb::options::FindOptions;
+use mongodb::sync::Collection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::mongo_store::{create_client_from_env, database_from_env};
+
+pub const DEFAULT_LIST_LIMIT: i64 = 100;
+pub const MAX_LIST_LIMIT: i64 = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    /// Who acted, e.g. `employee:little_bear`.
+    pub actor: String,
+    /// Whose request the action served, e.g. `user:<id>`, when known.
+    #[serde(default)]
+    pub on_behalf_of: Option<String>,
+    /// What was done, e.g. `send_message` or `google_docs.apply_edit`.
+    pub action: String,
+    /// What it was done to: recipients, a document ID, a file ID.
+    pub target: String,
+    #[serde(default)]
+    pub channel: Option<String>,
+    /// `sha256:<hex>` of the payload that left the service.
+    pub payload_hash: String,
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    #[serde(default)]
+    pub task_id: Option<String>,
+    #[serde(with = "mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Filters for [`AuditStore::list`]; unset fields match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub on_behalf_of: Option<String>,
+    pub action: Option<String>,
+    pub channel: Option<String>,
+    pub trace_id: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+impl AuditQuery {
+    fn filter(&self) -> Document {
+        let mut filter = Document::new();
+        for (key, value) in [
+            ("actor", &self.actor),
+            ("on_behalf_of", &self.on_behalf_of),
+            ("action", &self.action),
+            ("channel", &self.channel),
+            ("trace_id", &self.trace_id),
+        ] {
+            if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                filter.insert(key, value);
+            }
+        }
+        let mut window = Document::new();
+        if let Some(since) = self.since {
+            window.insert("$gte", BsonDateTime::from_chrono(since));
+        }
+        if let Some(until) = self.until {
+            window.insert("$lt", BsonDateTime::from_chrono(until));
+        }
+        if !window.is_empty() {
+            filter.insert("recorded_at", window);
+        }
+        filter
+    }
+
+    fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_LIST_LIMIT)
+            .clamp(1, MAX_LIST_LIMIT)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuditStoreError {
+    #[error("mongodb error: {0}")]
+    Mongo(#[from] mongodb::error::Error),
+    #[error("bson error: {0}")]
+    Serialize(#[from] mongodb::bson::ser::Error),
+    #[error("bson error: {0}")]
+    Deserialize(#[from] mongodb::bson::de::Error),
+    #[error("mongo config error: {0}")]
+    MongoConfig(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditStore {
+    entries: Collection<Document>,
+}
+
+impl AuditStore {
+    pub fn new() -> Result<Self, AuditStoreError> {
+        let client = create_client_from_env()
+            .map_err(|err| AuditStoreError::MongoConfig(err.to_string()))?;
+        let db = database_from_env(&client);
+        let entries = db.collection::<Document>("agent_audit_log");
+        Ok(Self { entries })
+    }
+
+    pub fn append(&self, entry: &AuditEntry) -> Result<(), AuditStoreError> {
+        let document = mongodb::bson::to_document(entry)?;
+        self.entries.insert_one(document, None)?;
+        Ok(())
+    }
+
+    /// Newest entries first.
+    pub fn list(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, AuditStoreError> {
+        let options = FindOptions::builder()
+            .sort(doc! { "recorded_at": -1 })
+            .limit(query.limit())
+            .build();
+        let cursor = self.entries.find(query.filter(), options)?;
+        let mut entries = Vec::new();
+        for document in cursor {
+            entries.push(mongodb::bson::from_document(document?)?);
+        }
+        Ok(entries)
+    }
+}
+
+/// `sha256:<hex>` digest used for [`AuditEntry::payload_hash`].
+pub fn payload_hash(payload: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(payload)))
+}
+
+static AUDIT_STORE: std::sync::OnceLock<Option<Arc<AuditStore>>> = std::sync::OnceLock::new();
+
+/// Get or initialize the global AuditStore (returns None if not configured)
+pub fn get_global_audit_store() -> Option<Arc<AuditStore>> {
+    AUDIT_STORE
+        .get_or_init(|| match AuditStore::new() {
+            Ok(store) => Some(Arc::new(store)),
+            Err(err) => {
+                warn!(
+                    "AuditStore not available ({}), audit entries are logged only",
+                    err
+                );
+                None
+            }
+        })
+        .clone()
+}
+
+/// Append `entry` to the audit log. Best effort: the action already happened,
+/// so a store failure is logged rather than failing the caller.
+pub fn record(entry: AuditEntry) {
+    info!(
+        "audit actor={} on_behalf_of={} action={} target={} payload_hash={}",
+        entry.actor,
+        entry.on_behalf_of.as_deref().unwrap_or("-"),
+        entry.action,
+        entry.target,
+        entry.payload_hash
+    );
+    let Some(store) = get_global_audit_store() else {
+        return;
+    };
+    if let Err(err) = store.append(&entry) {
+        warn!(
+            "failed to append audit entry action={}: {}",
+            entry.action, err
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_hash_is_prefixed_sha256() {
+        assert_eq!(
+            payload_hash(b"hello"),
+            "sha256:2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn query_filter_skips_blank_fields_and_bounds_window() {
+        let since = Utc::now();
+        let query = AuditQuery {
+            actor: Some("employee:little_bear".to_string()),
+            on_behalf_of: Some("  ".to_string()),
+            since: Some(since),
+            ..Default::default()
+        };
+        let filter = query.filter();
+        assert_eq!(filter.get_str("actor").unwrap(), "employee:little_bear");
+        assert!(!filter.contains_key("on_behalf_of"));
+        let window = filter.get_document("recorded_at").unwrap();
+        assert!(window.contains_key("$gte"));
+        assert!(!window.contains_key("$lt"));
+    }
+
+    #[test]
+    fn query_limit_is_clamped() {
+        let mut query = AuditQuery::default();
+        assert_eq!(query.limit(), DEFAULT_LIST_LIMIT);
+        query.limit = Some(0);
+        assert_eq!(query.limit(), 1);
+        query.limit = Some(50_000);
+        assert_eq!(query.limit(), MAX_LIST_LIMIT);
+    }
+}
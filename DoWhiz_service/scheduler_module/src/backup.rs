@@ -0,0 +1,592 @@
+//! Backups of the service state database.
+//!
+//! Every shared state store (tasks, users, task index, Slack installations,
+//! ...) lives in the employee's MongoDB database, so one backup is a
+//! snapshot of all its collections: a gzip file of JSON lines, one per document in
+//! canonical extended JSON, closed by a manifest line with per-collection
+//! document counts and index specs. Documents are read through a snapshot
+//! session when the deployment supports it, so the collections agree with
+//! each other; standalone servers fall back to plain reads and the manifest
+//! records `consistent: false`.
+//!
+//! [`restore_backup`] verifies the file, loads it into staging collections,
+//! checks the counts and only then renames the staging collections over the
+//! live ones, so a broken backup never replaces good data.
+//!
+//! Thread state is not in MongoDB and not in backups: it lives in each
+//! user's SQLite `state/threads.db` (see [`crate::thread_state`]), and a lost
+//! database is rebuilt from the workspaces' `thread_state.json` snapshots
+//! when it is next opened.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use mongodb::bson::{self, doc, Bson, Document};
+use mongodb::options::SessionOptions;
+use mongodb::sync::{Client, Database};
+use mongodb::IndexModel;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::mongo_store::MongoStoreError;
+use crate::object_store::{ObjectStore, ObjectStoreError};
+
+/// Env prefix of the store backups are uploaded to, see
+/// [`crate::object_store`].
+pub const BACKUP_STORE_ENV_PREFIX: &str = "BACKUP";
+
+const FILE_PREFIX: &str = "dowhiz-state-";
+const FILE_SUFFIX: &str = ".jsonl.gz";
+const FORMAT_VERSION: u32 = 1;
+const RESTORE_MARKER: &str = "__restore_";
+const INSERT_BATCH: usize = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("mongodb error: {0}")]
+    Mongo(#[from] mongodb::error::Error),
+    #[error("bson error: {0}")]
+    Bson(#[from] bson::ser::Error),
+    #[error(transparent)]
+    MongoStore(#[from] MongoStoreError),
+    #[error("object storage error: {0}")]
+    ObjectStore(#[from] ObjectStoreError),
+    #[error("invalid backup: {0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub database: String,
+    pub created_at: DateTime<Utc>,
+    /// Whether all collections were read from one snapshot.
+    pub consistent: bool,
+    pub collections: BTreeMap<String, CollectionManifest>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CollectionManifest {
+    pub documents: u64,
+    /// Index specs in canonical extended JSON.
+    #[serde(default)]
+    pub indexes: Vec<serde_json::Value>,
+}
+
+impl BackupManifest {
+    pub fn documents(&self) -> u64 {
+        self.collections.values().map(|c| c.documents).sum()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum BackupLine {
+    Document {
+        collection: String,
+        document: serde_json::Value,
+    },
+    Manifest(BackupManifest),
+}
+
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub manifest: BackupManifest,
+}
+
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub collections: usize,
+    pub documents: u64,
+    /// Live collections the backup does not contain; left untouched.
+    pub untouched: Vec<String>,
+}
+
+/// File name of a backup of `database` taken at `now`. Names sort by time.
+pub fn backup_file_name(database: &str, now: DateTime<Utc>) -> String {
+    format!(
+        "{}{}-{}{}",
+        FILE_PREFIX,
+        database,
+        now.format("%Y%m%dT%H%M%SZ"),
+        FILE_SUFFIX
+    )
+}
+
+/// Writes a backup of every collection of `database` into `dir`.
+pub fn create_backup(
+    client: &Client,
+    database: &str,
+    dir: &Path,
+    now: DateTime<Utc>,
+) -> Result<Backup, BackupError> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(backup_file_name(database, now));
+    let result = match dump_database(client, database, &path, now, true) {
+        Err(BackupError::Mongo(err)) => {
+            warn!(
+                "snapshot read of {} failed ({}); backing up without a snapshot",
+                database, err
+            );
+            dump_database(client, database, &path, now, false)
+        }
+        result => result,
+    };
+    match result {
+        Ok(manifest) => Ok(Backup { path, manifest }),
+        Err(err) => {
+            let _ = fs::remove_file(partial_path(&path));
+            Err(err)
+        }
+    }
+}
+
+fn dump_database(
+    client: &Client,
+    database: &str,
+    path: &Path,
+    now: DateTime<Utc>,
+    snapshot: bool,
+) -> Result<BackupManifest, BackupError> {
+    let db = client.database(database);
+    let mut names = db
+        .list_collection_names(doc! { "type": "collection" })?
+        .into_iter()
+        .filter(|name| is_backed_up(name))
+        .collect::<Vec<_>>();
+    names.sort();
+    let mut session = if snapshot {
+        Some(client.start_session(Some(SessionOptions::builder().snapshot(true).build()))?)
+    } else {
+        None
+    };
+
+    let mut writer = BackupWriter::create(path)?;
+    let mut manifest = BackupManifest {
+        version: FORMAT_VERSION,
+        database: database.to_string(),
+        created_at: now,
+        consistent: snapshot,
+        collections: BTreeMap::new(),
+    };
+    for name in names {
+        let collection = db.collection::<Document>(&name);
+        match session.as_mut() {
+            Some(session) => {
+                let mut cursor = collection.find_with_session(None, None, session)?;
+                while let Some(document) = cursor.next(session) {
+                    writer.document(&name, document?)?;
+                }
+            }
+            None => {
+                for document in collection.find(None, None)? {
+                    writer.document(&name, document?)?;
+                }
+            }
+        }
+        let mut indexes = Vec::new();
+        for index in collection.list_indexes(None)? {
+            indexes.push(bson::to_bson(&index?)?.into_canonical_extjson());
+        }
+        manifest.collections.insert(
+            name,
+            CollectionManifest {
+                documents: 0,
+                indexes,
+            },
+        );
+    }
+    writer.finish(manifest)
+}
+
+/// Reads the whole backup and checks it is complete; returns its manifest.
+pub fn verify_backup(path: &Path) -> Result<BackupManifest, BackupError> {
+    read_backup(path, |_, _| Ok(()))
+}
+
+/// Replaces the collections of `database` with the backup at `path`.
+///
+/// Run with the workers stopped: writes that land between the load and the
+/// swap are lost.
+pub fn restore_backup(
+    client: &Client,
+    database: &str,
+    path: &Path,
+    now: DateTime<Utc>,
+) -> Result<RestoreReport, BackupError> {
+    let manifest = verify_backup(path)?;
+    let db = client.database(database);
+    let suffix = format!("{}{}", RESTORE_MARKER, now.format("%Y%m%d%H%M%S"));
+    let staged = stage_backup(&db, path, &manifest, &suffix);
+    if let Err(err) = staged {
+        for name in manifest.collections.keys() {
+            let staging = db.collection::<Document>(&format!("{}{}", name, suffix));
+            if let Err(drop_err) = staging.drop(None) {
+                warn!(
+                    "failed to drop staging collection for {}: {}",
+                    name, drop_err
+                );
+            }
+        }
+        return Err(err);
+    }
+
+    let admin = client.database("admin");
+    for name in manifest.collections.keys() {
+        admin.run_command(
+            doc! {
+                "renameCollection": format!("{}.{}{}", database, name, suffix),
+                "to": format!("{}.{}", database, name),
+                "dropTarget": true,
+            },
+            None,
+        )?;
+    }
+    let untouched = db
+        .list_collection_names(doc! { "type": "collection" })?
+        .into_iter()
+        .filter(|name| is_backed_up(name) && !manifest.collections.contains_key(name))
+        .collect();
+    Ok(RestoreReport {
+        collections: manifest.collections.len(),
+        documents: manifest.documents(),
+        untouched,
+    })
+}
+
+fn stage_backup(
+    db: &Database,
+    path: &Path,
+    manifest: &BackupManifest,
+    suffix: &str,
+) -> Result<(), BackupError> {
+    let mut batches: BTreeMap<String, Vec<Document>> = BTreeMap::new();
+    read_backup(path, |collection, document| {
+        let batch = batches.entry(collection.to_string()).or_default();
+        batch.push(document);
+        if batch.len() >= INSERT_BATCH {
+            db.collection::<Document>(&format!("{}{}", collection, suffix))
+                .insert_many(batch.drain(..), None)?;
+        }
+        Ok(())
+    })?;
+    for (collection, batch) in batches {
+        if !batch.is_empty() {
+            db.collection::<Document>(&format!("{}{}", collection, suffix))
+                .insert_many(batch, None)?;
+        }
+    }
+
+    for (name, expected) in &manifest.collections {
+        let staging_name = format!("{}{}", name, suffix);
+        if expected.documents == 0 {
+            db.create_collection(&staging_name, None)?;
+        }
+        let staging = db.collection::<Document>(&staging_name);
+        for spec in &expected.indexes {
+            let spec = Bson::try_from(spec.clone())
+                .map_err(|err| BackupError::Invalid(format!("index on {}: {}", name, err)))?;
+            let model: IndexModel = bson::from_bson(spec)
+                .map_err(|err| BackupError::Invalid(format!("index on {}: {}", name, err)))?;
+            let is_id_index = model
+                .options
+                .as_ref()
+                .and_then(|options| options.name.as_deref())
+                == Some("_id_");
+            if !is_id_index {
+                staging.create_index(model, None)?;
+            }
+        }
+        let loaded = staging.count_documents(None, None)?;
+        if loaded != expected.documents {
+            return Err(BackupError::Invalid(format!(
+                "staged {} documents for {} but the backup has {}",
+                loaded, name, expected.documents
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Deletes all but the newest `keep` backups in `dir`; returns how many
+/// were deleted.
+pub fn prune_backups(dir: &Path, keep: usize) -> io::Result<usize> {
+    let mut files = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX)
+                    })
+            })
+            .collect::<Vec<_>>(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    files.sort();
+    let excess = files.len().saturating_sub(keep);
+    for path in &files[..excess] {
+        fs::remove_file(path)?;
+    }
+    Ok(excess)
+}
+
+/// Uploads a backup under `<database>/<file name>`; returns the object key.
+pub fn upload_backup(store: &dyn ObjectStore, backup: &Backup) -> Result<String, BackupError> {
+    let file_name = backup
+        .path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| BackupError::Invalid(backup.path.display().to_string()))?;
+    let key = format!("{}/{}", backup.manifest.database, file_name);
+    store.put(&key, &fs::read(&backup.path)?)?;
+    Ok(key)
+}
+
+/// Downloads the backup at `key` into `dir`; returns the local path.
+pub fn download_backup(
+    store: &dyn ObjectStore,
+    key: &str,
+    dir: &Path,
+) -> Result<PathBuf, BackupError> {
+    let file_name = key
+        .rsplit('/')
+        .next()
+        .filter(|name| name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX))
+        .ok_or_else(|| BackupError::Invalid(format!("{} is not a backup key", key)))?;
+    fs::create_dir_all(dir)?;
+    let path = dir.join(file_name);
+    fs::write(&path, store.get(key)?)?;
+    Ok(path)
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    path.with_extension("partial")
+}
+
+fn is_backed_up(collection: &str) -> bool {
+    !collection.starts_with("system.") && !collection.contains(RESTORE_MARKER)
+}
+
+/// Streams a backup into a temporary file that only takes the final name
+/// once the manifest is written.
+struct BackupWriter {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    encoder: GzEncoder<BufWriter<File>>,
+    counts: BTreeMap<String, u64>,
+}
+
+impl BackupWriter {
+    fn create(path: &Path) -> Result<Self, BackupError> {
+        let tmp_path = partial_path(path);
+        let file = File::create(&tmp_path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            tmp_path,
+            encoder: GzEncoder::new(BufWriter::new(file), Compression::default()),
+            counts: BTreeMap::new(),
+        })
+    }
+
+    fn document(&mut self, collection: &str, document: Document) -> Result<(), BackupError> {
+        self.line(&BackupLine::Document {
+            collection: collection.to_string(),
+            document: Bson::Document(document).into_canonical_extjson(),
+        })?;
+        *self.counts.entry(collection.to_string()).or_default() += 1;
+        Ok(())
+    }
+
+    fn line(&mut self, line: &BackupLine) -> Result<(), BackupError> {
+        serde_json::to_writer(&mut self.encoder, line)?;
+        self.encoder.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(mut self, mut manifest: BackupManifest) -> Result<BackupManifest, BackupError> {
+        for (collection, count) in &self.counts {
+            manifest
+                .collections
+                .entry(collection.clone())
+                .or_default()
+                .documents = *count;
+        }
+        self.line(&BackupLine::Manifest(manifest.clone()))?;
+        let mut file = self.encoder.finish()?;
+        file.flush()?;
+        file.get_ref().sync_all()?;
+        fs::rename(&self.tmp_path, &self.path)?;
+        Ok(manifest)
+    }
+}
+
+/// Calls `on_document` for every document, then checks the counts against
+/// the manifest, which must be the last line.
+fn read_backup(
+    path: &Path,
+    mut on_document: impl FnMut(&str, Document) -> Result<(), BackupError>,
+) -> Result<BackupManifest, BackupError> {
+    let reader = BufReader::new(GzDecoder::new(File::open(path)?));
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+    let mut manifest = None;
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if manifest.is_some() {
+            return Err(BackupError::Invalid(format!(
+                "line {} follows the manifest",
+                number + 1
+            )));
+        }
+        match serde_json::from_str::<BackupLine>(&line)? {
+            BackupLine::Document {
+                collection,
+                document,
+            } => {
+                let Ok(Bson::Document(document)) = Bson::try_from(document) else {
+                    return Err(BackupError::Invalid(format!(
+                        "line {} is not a document",
+                        number + 1
+                    )));
+                };
+                on_document(&collection, document)?;
+                *counts.entry(collection).or_default() += 1;
+            }
+            BackupLine::Manifest(found) => manifest = Some(found),
+        }
+    }
+
+    let manifest =
+        manifest.ok_or_else(|| BackupError::Invalid("missing manifest; truncated?".to_string()))?;
+    if manifest.version != FORMAT_VERSION {
+        return Err(BackupError::Invalid(format!(
+            "unsupported version {}",
+            manifest.version
+        )));
+    }
+    for (collection, expected) in &manifest.collections {
+        let found = counts.remove(collection).unwrap_or(0);
+        if found != expected.documents {
+            return Err(BackupError::Invalid(format!(
+                "{} has {} documents but the manifest lists {}",
+                collection, found, expected.documents
+            )));
+        }
+    }
+    if let Some(collection) = counts.keys().next() {
+        return Err(BackupError::Invalid(format!(
+            "{} is missing from the manifest",
+            collection
+        )));
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::oid::ObjectId;
+
+    fn write_sample(path: &Path, now: DateTime<Utc>) -> Vec<Document> {
+        let documents = vec![
+            doc! { "_id": ObjectId::new(), "user_id": "u1", "created_at": bson::DateTime::from_chrono(now) },
+            doc! { "_id": "t1", "attempts": 3_i64, "payload": { "nested": [1, 2.5] } },
+        ];
+        let mut writer = BackupWriter::create(path).expect("create");
+        writer
+            .document("users", documents[0].clone())
+            .expect("write");
+        writer
+            .document("tasks", documents[1].clone())
+            .expect("write");
+        let mut collections = BTreeMap::new();
+        collections.insert(
+            "slack_installations".to_string(),
+            CollectionManifest::default(),
+        );
+        writer
+            .finish(BackupManifest {
+                version: FORMAT_VERSION,
+                database: "dowhiz_test".to_string(),
+                created_at: now,
+                consistent: true,
+                collections,
+            })
+            .expect("finish");
+        documents
+    }
+
+    #[test]
+    fn backups_round_trip_and_reject_truncated_files() {
+        let temp = tempfile::TempDir::new().expect("tempdir");
+        let now = Utc::now();
+        let path = temp.path().join(backup_file_name("dowhiz_test", now));
+        let written = write_sample(&path, now);
+        assert!(!partial_path(&path).exists());
+
+        let mut read = Vec::new();
+        let manifest = read_backup(&path, |collection, document| {
+            read.push((collection.to_string(), document));
+            Ok(())
+        })
+        .expect("read");
+        assert_eq!(manifest.documents(), 2);
+        assert_eq!(manifest.collections["slack_installations"].documents, 0);
+        assert_eq!(read[0], ("users".to_string(), written[0].clone()));
+        assert_eq!(read[1], ("tasks".to_string(), written[1].clone()));
+
+        // Drop the manifest line to simulate a backup cut short.
+        let mut text = String::new();
+        io::Read::read_to_string(&mut GzDecoder::new(File::open(&path).unwrap()), &mut text)
+            .unwrap();
+        let truncated: Vec<&str> = text.lines().take(2).collect();
+        let mut encoder = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+        encoder.write_all(truncated.join("\n").as_bytes()).unwrap();
+        encoder.finish().unwrap();
+        assert!(matches!(verify_backup(&path), Err(BackupError::Invalid(_))));
+    }
+
+    #[test]
+    fn prune_keeps_newest_backups() {
+        let temp = tempfile::TempDir::new().expect("tempdir");
+        let start = Utc::now();
+        for day in 0..4 {
+            let name = backup_file_name("dowhiz_test", start + chrono::Duration::days(day));
+            fs::write(temp.path().join(name), b"").expect("write");
+        }
+        fs::write(temp.path().join("notes.txt"), b"").expect("write");
+
+        assert_eq!(prune_backups(temp.path(), 2).expect("prune"), 2);
+        let mut left: Vec<_> = fs::read_dir(temp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            vec![
+                backup_file_name("dowhiz_test", start + chrono::Duration::days(2)),
+                backup_file_name("dowhiz_test", start + chrono::Duration::days(3)),
+                "notes.txt".to_string(),
+            ]
+        );
+        assert_eq!(prune_backups(&temp.path().join("missing"), 2).unwrap(), 0);
+    }
+}
@@ -0,0 +1,936 @@
+//! Operator CLI over the service's `/admin` API (see `service/ops.rs`).
+//!
+//! Talks to `DOWHIZ_API_URL` (default `http://localhost:9001`) with the
+//! Supabase access token of an ops admin in `DOWHIZ_ADMIN_TOKEN`.
+
+use base64::Engine;
+use serde_json::Value;
+use std::env;
+use std::path::{Component, Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+const DEFAULT_API_URL: &str = "http://localhost:9001";
+const DEFAULT_FOLLOW_INTERVAL_SECS: u64 = 5;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Users {
+        identifier_type: Option<String>,
+        limit: Option<String>,
+    },
+    Tasks {
+        user_id: String,
+    },
+    Task {
+        user_id: String,
+        task_id: String,
+    },
+    FindTasks {
+        user_id: Option<String>,
+        channel: Option<String>,
+        kind: Option<String>,
+        enabled: Option<String>,
+        next_run_from: Option<String>,
+        next_run_to: Option<String>,
+        cursor: Option<String>,
+        limit: Option<String>,
+    },
+    Cancel {
+        user_id: String,
+        task_id: String,
+    },
+    Run {
+        user_id: String,
+        task_id: String,
+    },
+    Executions {
+        user_id: Option<String>,
+        task_id: Option<String>,
+        status: Option<String>,
+        limit: Option<String>,
+        follow: bool,
+        interval_secs: u64,
+    },
+    ExecLog {
+        execution_id: String,
+        bytes: Option<String>,
+    },
+    DeadLetters,
+    Requeue {
+        envelope_id: String,
+    },
+    Threads {
+        user_id: String,
+        since: Option<String>,
+        limit: Option<String>,
+    },
+    Workspaces {
+        user_id: String,
+    },
+    Dump {
+        user_id: String,
+        workspace: String,
+        out: PathBuf,
+    },
+    Retract {
+        user_id: String,
+        workspace: String,
+        message_id: String,
+    },
+    Rehydrate {
+        user_id: String,
+        id: String,
+    },
+    Skills,
+    InstallSkill {
+        name: String,
+        version: String,
+        source: Value,
+        subdir: Option<String>,
+        content_sha256: Option<String>,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    api_url: String,
+    token: Option<String>,
+    json: bool,
+    command: Command,
+}
+
+fn parse_args(raw: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut api_url = env::var("DOWHIZ_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+    let mut token = env::var("DOWHIZ_ADMIN_TOKEN").ok();
+    let mut json = false;
+    let mut positional = Vec::new();
+    let mut options: Vec<(String, String)> = Vec::new();
+    let mut follow = false;
+
+    let mut raw = raw.into_iter();
+    while let Some(arg) = raw.next() {
+        match arg.as_str() {
+            "--help" | "-h" => return Err(help_text()),
+            "--json" => json = true,
+            "--follow" | "-f" => follow = true,
+            "--url" | "--token" | "--type" | "--limit" | "--user" | "--task" | "--status"
+            | "--interval" | "--out" | "--channel" | "--kind" | "--enabled" | "--from" | "--to"
+            | "--cursor" | "--bytes" | "--git" | "--archive" | "--sha256" | "--subdir"
+            | "--content-sha256" | "--since" => {
+                let value = raw
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                match arg.as_str() {
+                    "--url" => api_url = value,
+                    "--token" => token = Some(value),
+                    _ => options.push((arg, value)),
+                }
+            }
+            other if other.starts_with("--") => return Err(format!("unknown option: {}", other)),
+            _ => positional.push(arg),
+        }
+    }
+
+    let option = |name: &str| {
+        options
+            .iter()
+            .rev()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    };
+    let mut positional = positional.into_iter();
+    let name = positional.next().ok_or_else(help_text)?;
+    let mut next = |what: &str| {
+        positional
+            .next()
+            .ok_or_else(|| format!("{} needs <{}>", name, what))
+    };
+    let command = match name.as_str() {
+        "users" => Command::Users {
+            identifier_type: option("--type"),
+            limit: option("--limit"),
+        },
+        "tasks" => Command::Tasks {
+            user_id: next("user_id")?,
+        },
+        "task" => Command::Task {
+            user_id: next("user_id")?,
+            task_id: next("task_id")?,
+        },
+        "find-tasks" => Command::FindTasks {
+            user_id: option("--user"),
+            channel: option("--channel"),
+            kind: option("--kind"),
+            enabled: option("--enabled"),
+            next_run_from: option("--from"),
+            next_run_to: option("--to"),
+            cursor: option("--cursor"),
+            limit: option("--limit"),
+        },
+        "cancel" => Command::Cancel {
+            user_id: next("user_id")?,
+            task_id: next("task_id")?,
+        },
+        "run" => Command::Run {
+            user_id: next("user_id")?,
+            task_id: next("task_id")?,
+        },
+        "executions" => Command::Executions {
+            user_id: option("--user"),
+            task_id: option("--task"),
+            status: option("--status"),
+            limit: option("--limit"),
+            follow,
+            interval_secs: match option("--interval") {
+                Some(value) => value
+                    .parse()
+                    .map_err(|_| format!("invalid --interval: {}", value))?,
+                None => DEFAULT_FOLLOW_INTERVAL_SECS,
+            },
+        },
+        "exec-log" => Command::ExecLog {
+            execution_id: next("execution_id")?,
+            bytes: option("--bytes"),
+        },
+        "dead-letters" => Command::DeadLetters,
+        "requeue" => Command::Requeue {
+            envelope_id: next("envelope_id")?,
+        },
+        "threads" => Command::Threads {
+            user_id: next("user_id")?,
+            since: option("--since"),
+            limit: option("--limit"),
+        },
+        "workspaces" => Command::Workspaces {
+            user_id: next("user_id")?,
+        },
+        "dump" => {
+            let user_id = next("user_id")?;
+            let workspace = next("workspace")?;
+            let out = option("--out").map(PathBuf::from).unwrap_or_else(|| {
+                PathBuf::from(format!(
+                    "{}-{}",
+                    user_id,
+                    workspace.replace(['/', ':'], "_")
+                ))
+            });
+            Command::Dump {
+                user_id,
+                workspace,
+                out,
+            }
+        }
+        "retract" => Command::Retract {
+            user_id: next("user_id")?,
+            workspace: next("workspace")?,
+            message_id: next("message_id")?,
+        },
+        "rehydrate" => Command::Rehydrate {
+            user_id: next("user_id")?,
+            id: next("id")?,
+        },
+        "skills" => Command::Skills,
+        "install-skill" => {
+            let name = next("name")?;
+            let version = next("version")?;
+            let source = match (option("--git"), option("--archive"), option("--sha256")) {
+                (Some(url), None, None) => serde_json::json!({ "type": "git", "url": url }),
+                (None, Some(url), Some(sha256)) => {
+                    serde_json::json!({ "type": "archive", "url": url, "sha256": sha256 })
+                }
+                (None, Some(_), None) => return Err("--archive needs --sha256".to_string()),
+                _ => return Err("install-skill needs either --git or --archive".to_string()),
+            };
+            Command::InstallSkill {
+                name,
+                version,
+                source,
+                subdir: option("--subdir"),
+                content_sha256: option("--content-sha256"),
+            }
+        }
+        other => return Err(format!("unknown command: {}\n\n{}", other, help_text())),
+    };
+
+    Ok(Args {
+        api_url: api_url.trim_end_matches('/').to_string(),
+        token,
+        json,
+        command,
+    })
+}
+
+fn help_text() -> String {
+    [
+        "Operate a DoWhiz service through its admin API",
+        "",
+        "Usage:",
+        "  DOWHIZ_API_URL=https://api.example.com DOWHIZ_ADMIN_TOKEN=<supabase token> \\",
+        "  cargo run -p scheduler_module --bin dowhizctl -- <command> [options]",
+        "",
+        "Commands:",
+        "  users [--type TYPE] [--limit N]     List users, oldest first.",
+        "  tasks <user_id>                     List a user's tasks.",
+        "  task <user_id> <task_id>            Show a task and its recent executions.",
+        "  find-tasks [--user ID] [--channel C] [--kind K] [--enabled true|false]",
+        "             [--from RFC3339] [--to RFC3339] [--limit N] [--cursor C]",
+        "                                      Indexed tasks of all users by next run.",
+        "  cancel <user_id> <task_id>          Disable a task.",
+        "  run <user_id> <task_id>             Make an enabled task due now.",
+        "  executions [--user ID] [--task ID] [--status S] [--limit N] [--follow [--interval SECS]]",
+        "                                      Recent executions; --follow keeps polling.",
+        "  exec-log <execution_id> [--bytes N] End of a run_task execution's log.",
+        "  dead-letters                        List failed ingestion envelopes.",
+        "  requeue <envelope_id>               Retry a dead letter.",
+        "  threads <user_id> [--since RFC3339] [--limit N]",
+        "                                      A user's threads, most recently active first.",
+        "  workspaces <user_id>                List a user's thread workspaces.",
+        "  dump <user_id> <workspace> [--out DIR]",
+        "                                      Copy a workspace (name or thread key) to DIR.",
+        "  retract <user_id> <workspace> <message_id>",
+        "                                      Delete a Slack or Discord reply sent in the thread.",
+        "  rehydrate <user_id> <id>            Restore archived mail from cold storage: one",
+        "                                      message (2026/02/msg) or a month (2026/02).",
+        "  skills                              List skills installed for the employee.",
+        "  install-skill <name> <version> (--git URL | --archive URL --sha256 HEX)",
+        "             [--subdir DIR] [--content-sha256 HEX]",
+        "                                      Install or update a skill at a pinned version",
+        "                                      (a git tag, branch or commit).",
+        "",
+        "Options:",
+        "  --url URL      API base URL (default DOWHIZ_API_URL, then http://localhost:9001).",
+        "  --token TOKEN  Admin bearer token (default DOWHIZ_ADMIN_TOKEN).",
+        "  --json         Print the raw JSON response.",
+    ]
+    .join("\n")
+}
+
+struct ApiClient {
+    http: reqwest::blocking::Client,
+    base_url: String,
+    token: String,
+}
+
+impl ApiClient {
+    fn get(&self, path: &str, query: &[(&str, Option<String>)]) -> Result<Value, BoxError> {
+        let query: Vec<(&str, String)> = query
+            .iter()
+            .filter_map(|(key, value)| value.clone().map(|value| (*key, value)))
+            .collect();
+        let request = self
+            .http
+            .get(format!("{}{}", self.base_url, path))
+            .query(&query);
+        self.send(request)
+    }
+
+    fn post(&self, path: &str) -> Result<Value, BoxError> {
+        self.send(self.http.post(format!("{}{}", self.base_url, path)))
+    }
+
+    fn post_json(&self, path: &str, body: &Value) -> Result<Value, BoxError> {
+        self.send(
+            self.http
+                .post(format!("{}{}", self.base_url, path))
+                .json(body),
+        )
+    }
+
+    fn send(&self, request: reqwest::blocking::RequestBuilder) -> Result<Value, BoxError> {
+        let response = request.bearer_auth(&self.token).send()?;
+        let status = response.status();
+        let body: Value = response.json().unwrap_or(Value::Null);
+        if !status.is_success() {
+            let message = body
+                .get("error")
+                .and_then(Value::as_str)
+                .unwrap_or_else(|| status.canonical_reason().unwrap_or("request failed"));
+            return Err(format!("{} ({})", message, status.as_u16()).into());
+        }
+        Ok(body)
+    }
+}
+
+fn text<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(Value::as_str).unwrap_or("-")
+}
+
+fn items<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// One line per task: id, kind, enabled, next run and description.
+fn task_line(task: &Value) -> String {
+    let schedule = task.get("schedule").cloned().unwrap_or(Value::Null);
+    let next_run = schedule
+        .get("run_at")
+        .or_else(|| schedule.get("next_run"))
+        .and_then(Value::as_str)
+        .unwrap_or("-");
+    let enabled = if task.get("enabled").and_then(Value::as_bool) == Some(true) {
+        "enabled"
+    } else {
+        "disabled"
+    };
+    let kind = task
+        .get("kind")
+        .map(|kind| text(kind, "type"))
+        .unwrap_or("-");
+    format!(
+        "{}  {:<10} {:<8} {:<26} {}",
+        text(task, "id"),
+        kind,
+        enabled,
+        next_run,
+        task.get("description")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    )
+}
+
+fn execution_line(execution: &Value) -> String {
+    format!(
+        "{}  {:<26} {:<8} {}:{}  task={}  {}",
+        text(execution, "id"),
+        text(execution, "started_at"),
+        text(execution, "status"),
+        text(execution, "owner_kind"),
+        text(execution, "owner_id"),
+        text(execution, "task_id"),
+        execution
+            .get("error_message")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    )
+}
+
+/// Where a dumped file goes under `out`, or `None` for a path that would
+/// leave it.
+fn dump_target(out: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+    relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then(|| out.join(relative))
+}
+
+fn run(args: Args) -> Result<(), BoxError> {
+    let token = args
+        .token
+        .clone()
+        .ok_or("set DOWHIZ_ADMIN_TOKEN or pass --token")?;
+    let client = ApiClient {
+        http: reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(120))
+            .build()?,
+        base_url: args.api_url.clone(),
+        token,
+    };
+    let print_json = |value: &Value| -> Result<(), BoxError> {
+        println!("{}", serde_json::to_string_pretty(value)?);
+        Ok(())
+    };
+
+    match args.command {
+        Command::Users {
+            identifier_type,
+            limit,
+        } => {
+            let body = client.get(
+                "/admin/users",
+                &[("identifier_type", identifier_type), ("limit", limit)],
+            )?;
+            if args.json {
+                return print_json(&body);
+            }
+            for user in items(&body, "users") {
+                println!(
+                    "{}  {:<10} {:<40} last seen {}",
+                    text(user, "user_id"),
+                    text(user, "identifier_type"),
+                    text(user, "identifier"),
+                    text(user, "last_seen_at")
+                );
+            }
+        }
+        Command::Tasks { user_id } => {
+            let body = client.get(&format!("/admin/users/{}/tasks", user_id), &[])?;
+            if args.json {
+                return print_json(&body);
+            }
+            for task in items(&body, "tasks") {
+                println!("{}", task_line(task));
+            }
+        }
+        Command::Task { user_id, task_id } => {
+            let body = client.get(&format!("/admin/users/{}/tasks/{}", user_id, task_id), &[])?;
+            print_json(&body)?;
+        }
+        Command::FindTasks {
+            user_id,
+            channel,
+            kind,
+            enabled,
+            next_run_from,
+            next_run_to,
+            cursor,
+            limit,
+        } => {
+            let body = client.get(
+                "/admin/tasks",
+                &[
+                    ("user_id", user_id),
+                    ("channel", channel),
+                    ("kind", kind),
+                    ("enabled", enabled),
+                    ("next_run_from", next_run_from),
+                    ("next_run_to", next_run_to),
+                    ("cursor", cursor),
+                    ("limit", limit),
+                ],
+            )?;
+            if args.json {
+                return print_json(&body);
+            }
+            for task in items(&body, "tasks") {
+                println!(
+                    "{}  {}  {:<9} {:<10} {:<8} next run {}",
+                    text(task, "user_id"),
+                    text(task, "task_id"),
+                    text(task, "kind"),
+                    text(task, "channel"),
+                    match task.get("enabled").and_then(Value::as_bool) {
+                        Some(true) => "enabled",
+                        _ => "disabled",
+                    },
+                    text(task, "next_run")
+                );
+            }
+            if let Some(cursor) = body.get("next_cursor").and_then(Value::as_str) {
+                println!("More with --cursor {}", cursor);
+            }
+        }
+        Command::Cancel { user_id, task_id } => {
+            let body = client.post(&format!(
+                "/admin/users/{}/tasks/{}/cancel",
+                user_id, task_id
+            ))?;
+            if args.json {
+                return print_json(&body);
+            }
+            match body.get("cancelled").and_then(Value::as_bool) {
+                Some(true) => println!("Cancelled {}", task_id),
+                _ => println!("{} was already disabled", task_id),
+            }
+        }
+        Command::Run { user_id, task_id } => {
+            let body = client.post(&format!("/admin/users/{}/tasks/{}/run", user_id, task_id))?;
+            if args.json {
+                return print_json(&body);
+            }
+            match body.get("triggered").and_then(Value::as_bool) {
+                Some(true) => println!("{} is due now", task_id),
+                _ => println!("{} is disabled; nothing to run", task_id),
+            }
+        }
+        Command::Executions {
+            user_id,
+            task_id,
+            status,
+            limit,
+            follow,
+            interval_secs,
+        } => {
+            let mut since: Option<String> = None;
+            loop {
+                let body = client.get(
+                    "/admin/executions",
+                    &[
+                        ("user_id", user_id.clone()),
+                        ("task_id", task_id.clone()),
+                        ("status", status.clone()),
+                        ("since", since.clone()),
+                        ("limit", limit.clone()),
+                    ],
+                )?;
+                let executions = items(&body, "executions");
+                if args.json {
+                    print_json(&body)?;
+                } else {
+                    // Newest first from the API; printed oldest first like a log.
+                    for execution in executions.iter().rev() {
+                        println!("{}", execution_line(execution));
+                    }
+                }
+                if let Some(latest) = executions
+                    .first()
+                    .and_then(|execution| execution.get("started_at"))
+                    .and_then(Value::as_str)
+                {
+                    since = Some(latest.to_string());
+                }
+                if !follow {
+                    break;
+                }
+                thread::sleep(Duration::from_secs(interval_secs.max(1)));
+            }
+        }
+        Command::ExecLog {
+            execution_id,
+            bytes,
+        } => {
+            let body = client.get(
+                &format!("/admin/executions/{}/log", execution_id),
+                &[("bytes", bytes)],
+            )?;
+            if args.json {
+                return print_json(&body);
+            }
+            if body.get("truncated").and_then(Value::as_bool) == Some(true) {
+                println!(
+                    "[earlier output omitted; see {} on the worker]",
+                    text(&body, "log_path")
+                );
+            }
+            print!("{}", text(&body, "tail"));
+        }
+        Command::DeadLetters => {
+            let body = client.get("/admin/dead-letters", &[])?;
+            if args.json {
+                return print_json(&body);
+            }
+            for dead_letter in items(&body, "dead_letters") {
+                println!(
+                    "{}  {:<8} failed {}  attempts={}  {}",
+                    text(dead_letter, "id"),
+                    text(dead_letter, "channel"),
+                    text(dead_letter, "failed_at"),
+                    dead_letter
+                        .get("attempts")
+                        .and_then(Value::as_i64)
+                        .unwrap_or_default(),
+                    text(dead_letter, "last_error")
+                );
+            }
+        }
+        Command::Requeue { envelope_id } => {
+            let body = client.post(&format!("/admin/dead-letters/{}/requeue", envelope_id))?;
+            if args.json {
+                return print_json(&body);
+            }
+            println!("Requeued {}", envelope_id);
+        }
+        Command::Threads {
+            user_id,
+            since,
+            limit,
+        } => {
+            let body = client.get(
+                &format!("/admin/users/{}/threads", user_id),
+                &[("since", since), ("limit", limit)],
+            )?;
+            if args.json {
+                return print_json(&body);
+            }
+            for thread in items(&body, "threads") {
+                let number =
+                    |key: &str| thread.get(key).and_then(Value::as_u64).unwrap_or_default();
+                println!(
+                    "{}  epoch={:<4} messages={:<4} updated {}  {}",
+                    text(thread, "workspace"),
+                    number("epoch"),
+                    number("messages"),
+                    text(thread, "updated_at"),
+                    text(thread, "thread_id")
+                );
+            }
+        }
+        Command::Workspaces { user_id } => {
+            let body = client.get(&format!("/admin/users/{}/workspaces", user_id), &[])?;
+            if args.json {
+                return print_json(&body);
+            }
+            for workspace in items(&body, "workspaces") {
+                println!("{}", workspace.as_str().unwrap_or_default());
+            }
+        }
+        Command::Dump {
+            user_id,
+            workspace,
+            out,
+        } => {
+            let body = client.get(
+                &format!(
+                    "/admin/users/{}/workspaces/{}",
+                    user_id,
+                    urlencoding::encode(&workspace)
+                ),
+                &[],
+            )?;
+            let mut written = 0;
+            let mut skipped = Vec::new();
+            for file in items(&body, "files") {
+                let path = text(file, "path");
+                let (Some(target), Some(content)) = (
+                    dump_target(&out, path),
+                    file.get("content_base64").and_then(Value::as_str),
+                ) else {
+                    skipped.push(path.to_string());
+                    continue;
+                };
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(
+                    &target,
+                    base64::engine::general_purpose::STANDARD.decode(content)?,
+                )?;
+                written += 1;
+            }
+            println!(
+                "Wrote {} files of {} to {}",
+                written,
+                text(&body, "workspace"),
+                out.display()
+            );
+            for path in skipped {
+                println!("  skipped {} (over the dump size limit)", path);
+            }
+        }
+        Command::Retract {
+            user_id,
+            workspace,
+            message_id,
+        } => {
+            let body = client.post(&format!(
+                "/admin/users/{}/workspaces/{}/messages/{}/delete",
+                user_id,
+                urlencoding::encode(&workspace),
+                urlencoding::encode(&message_id)
+            ))?;
+            if args.json {
+                return print_json(&body);
+            }
+            println!("Deleted {}", message_id);
+        }
+        Command::Rehydrate { user_id, id } => {
+            let body = client.post_json(
+                &format!("/admin/users/{}/mail/rehydrate", user_id),
+                &serde_json::json!({ "id": id }),
+            )?;
+            if args.json {
+                return print_json(&body);
+            }
+            let restored = items(&body, "restored");
+            println!("Restored {} message(s)", restored.len());
+            for id in restored {
+                println!("  {}", id.as_str().unwrap_or_default());
+            }
+        }
+        Command::Skills => {
+            let body = client.get("/admin/skills", &[])?;
+            if args.json {
+                return print_json(&body);
+            }
+            for skill in items(&body, "skills") {
+                println!(
+                    "{:<24} {:<16} {}  installed {} by {}",
+                    text(skill, "name"),
+                    text(skill, "version"),
+                    skill
+                        .pointer("/source/url")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default(),
+                    text(skill, "installed_at"),
+                    text(skill, "installed_by")
+                );
+            }
+        }
+        Command::InstallSkill {
+            name,
+            version,
+            source,
+            subdir,
+            content_sha256,
+        } => {
+            let body = client.post_json(
+                "/admin/skills",
+                &serde_json::json!({
+                    "name": name,
+                    "version": version,
+                    "source": source,
+                    "subdir": subdir,
+                    "content_sha256": content_sha256,
+                }),
+            )?;
+            if args.json {
+                return print_json(&body);
+            }
+            let installed = body.get("installed").unwrap_or(&Value::Null);
+            println!(
+                "Installed {}@{} (sha256 {})",
+                text(installed, "name"),
+                text(installed, "version"),
+                text(installed, "content_sha256")
+            );
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), BoxError> {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            std::process::exit(2);
+        }
+    };
+    run(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(raw: &[&str]) -> Result<Args, String> {
+        parse_args(raw.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_commands_and_options() {
+        let parsed = args(&["--url", "http://ops:9001/", "task", "u1", "t1"]).expect("args");
+        assert_eq!(parsed.api_url, "http://ops:9001");
+        assert_eq!(
+            parsed.command,
+            Command::Task {
+                user_id: "u1".to_string(),
+                task_id: "t1".to_string(),
+            }
+        );
+
+        let parsed = args(&["executions", "--status", "failed", "-f"]).expect("args");
+        assert_eq!(
+            parsed.command,
+            Command::Executions {
+                user_id: None,
+                task_id: None,
+                status: Some("failed".to_string()),
+                limit: None,
+                follow: true,
+                interval_secs: DEFAULT_FOLLOW_INTERVAL_SECS,
+            }
+        );
+
+        let parsed = args(&["exec-log", "65f0c0ffee", "--bytes", "4096"]).expect("args");
+        assert_eq!(
+            parsed.command,
+            Command::ExecLog {
+                execution_id: "65f0c0ffee".to_string(),
+                bytes: Some("4096".to_string()),
+            }
+        );
+
+        let parsed =
+            args(&["find-tasks", "--channel", "slack", "--enabled", "false"]).expect("args");
+        assert_eq!(
+            parsed.command,
+            Command::FindTasks {
+                user_id: None,
+                channel: Some("slack".to_string()),
+                kind: None,
+                enabled: Some("false".to_string()),
+                next_run_from: None,
+                next_run_to: None,
+                cursor: None,
+                limit: None,
+            }
+        );
+
+        let parsed = args(&["threads", "u1", "--since", "2026-02-01T00:00:00Z"]).expect("args");
+        assert_eq!(
+            parsed.command,
+            Command::Threads {
+                user_id: "u1".to_string(),
+                since: Some("2026-02-01T00:00:00Z".to_string()),
+                limit: None,
+            }
+        );
+
+        let parsed = args(&["dump", "u1", "slack:T1:C1:1.0"]).expect("args");
+        assert_eq!(
+            parsed.command,
+            Command::Dump {
+                user_id: "u1".to_string(),
+                workspace: "slack:T1:C1:1.0".to_string(),
+                out: PathBuf::from("u1-slack_T1_C1_1.0"),
+            }
+        );
+
+        let parsed =
+            args(&["retract", "u1", "slack:T1:C1:1.0", "1700000000.000200"]).expect("args");
+        assert_eq!(
+            parsed.command,
+            Command::Retract {
+                user_id: "u1".to_string(),
+                workspace: "slack:T1:C1:1.0".to_string(),
+                message_id: "1700000000.000200".to_string(),
+            }
+        );
+
+        let parsed = args(&[
+            "install-skill",
+            "pdf",
+            "v1.2.0",
+            "--archive",
+            "https://skills.example.com/pdf.tar.gz",
+            "--sha256",
+            "abc123",
+        ])
+        .expect("args");
+        assert_eq!(
+            parsed.command,
+            Command::InstallSkill {
+                name: "pdf".to_string(),
+                version: "v1.2.0".to_string(),
+                source: serde_json::json!({
+                    "type": "archive",
+                    "url": "https://skills.example.com/pdf.tar.gz",
+                    "sha256": "abc123",
+                }),
+                subdir: None,
+                content_sha256: None,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_missing_arguments() {
+        assert!(args(&["cancel", "u1"]).is_err());
+        assert!(args(&["retract", "u1", "ws"]).is_err());
+        assert!(args(&["rehydrate", "u1"]).is_err());
+        assert!(args(&["exec-log"]).is_err());
+        assert!(args(&["install-skill", "pdf", "v1", "--archive", "https://x/p.tgz"]).is_err());
+        assert!(args(&["install-skill", "pdf", "v1"]).is_err());
+        assert!(args(&["users", "--limit"]).is_err());
+        assert!(args(&["frobnicate"]).is_err());
+    }
+
+    #[test]
+    fn dump_target_stays_under_the_output_dir() {
+        let out = Path::new("/tmp/dump");
+        assert_eq!(
+            dump_target(out, "incoming_email/0001.txt"),
+            Some(PathBuf::from("/tmp/dump/incoming_email/0001.txt"))
+        );
+        assert_eq!(dump_target(out, "../escape.txt"), None);
+        assert_eq!(dump_target(out, "/etc/passwd"), None);
+    }
+}
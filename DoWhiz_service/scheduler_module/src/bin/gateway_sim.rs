@@ -0,0 +1,403 @@
+#[path = "gateway_sim/fixtures.rs"]
+mod fixtures;
+#[path = "gateway_sim/scenario.rs"]
+mod scenario;
+#[path = "gateway_sim/signing.rs"]
+mod signing;
+
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use fixtures::{encode_body, load_template, render, Provider};
+use scenario::{load_scenario, Step};
+use signing::{sign_request, SigningSecrets};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+const DEFAULT_GATEWAY: &str = "http://127.0.0.1:9100";
+const DEFAULT_SLACK_APP_ID: &str = "A0SIMULATOR";
+const DEFAULT_SLACK_TEAM_ID: &str = "T0SIMULATOR";
+
+enum Command {
+    Send(Step),
+    Scenario(PathBuf),
+}
+
+struct Options {
+    command: Command,
+    gateway: Option<String>,
+    fixtures_dir: Option<PathBuf>,
+    dry_run: bool,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = env::args().skip(1);
+    let command = args.next().ok_or_else(help_text)?;
+    let mut gateway = None;
+    let mut fixtures_dir = None;
+    let mut dry_run = false;
+    let mut scenario_path = None;
+    let mut provider = None;
+    let mut text = None;
+    let mut thread = None;
+    let mut from = None;
+    let mut to = None;
+    let mut subject = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--gateway" => gateway = args.next(),
+            "--fixtures-dir" => fixtures_dir = args.next().map(PathBuf::from),
+            "--dry-run" => dry_run = true,
+            "--provider" => provider = args.next(),
+            "--text" => text = args.next(),
+            "--thread" => thread = args.next(),
+            "--from" => from = args.next(),
+            "--to" => to = args.next(),
+            "--subject" => subject = args.next(),
+            "--help" | "-h" => return Err(help_text()),
+            value if command == "scenario" && scenario_path.is_none() => {
+                scenario_path = Some(PathBuf::from(value));
+            }
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+
+    let command = match command.as_str() {
+        "send" => {
+            let provider = provider.ok_or("--provider is required for send")?;
+            let provider = Provider::parse(&provider)
+                .ok_or_else(|| format!("unknown provider: {}", provider))?;
+            Command::Send(Step {
+                provider,
+                text: text.unwrap_or_else(|| "Hello from gateway_sim".to_string()),
+                thread,
+                from,
+                to,
+                subject,
+                delay_ms: 0,
+                expect_status: None,
+            })
+        }
+        "scenario" => Command::Scenario(scenario_path.ok_or("scenario path is required")?),
+        "--help" | "-h" => return Err(help_text()),
+        other => return Err(format!("unknown command: {}\n\n{}", other, help_text())),
+    };
+
+    Ok(Options {
+        command,
+        gateway,
+        fixtures_dir,
+        dry_run,
+    })
+}
+
+fn help_text() -> String {
+    [
+        "Post synthesized, signed provider webhooks at a local ingestion gateway",
+        "",
+        "Usage:",
+        "  cargo run -p scheduler_module --bin gateway_sim -- send --provider <postmark|slack|twilio|telegram> [--text TEXT] [--thread NAME] [--from ID] [--to ID] [--subject SUBJECT]",
+        "  cargo run -p scheduler_module --bin gateway_sim -- scenario scripts/gateway_sim/email_thread.toml",
+        "",
+        "Options:",
+        "  --gateway URL        gateway base URL (default http://127.0.0.1:9100)",
+        "  --fixtures-dir DIR   override built-in templates with DIR/<provider>.json",
+        "  --dry-run            print requests instead of posting them",
+        "",
+        "Environment (signing; unset means the request is sent unsigned):",
+        "  SLACK_SIGNING_SECRET, POSTMARK_INBOUND_TOKEN, POSTMARK_INBOUND_BASIC_AUTH,",
+        "  TWILIO_AUTH_TOKEN (+ TWILIO_WEBHOOK_URL), TELEGRAM_WEBHOOK_SECRET",
+    ]
+    .join("\n")
+}
+
+/// Per-thread state carried between steps of a scenario.
+#[derive(Debug, Default, Clone)]
+struct ThreadTrack {
+    message_ids: Vec<String>,
+    slack_thread_ts: Option<String>,
+    subject: Option<String>,
+}
+
+struct Simulator {
+    gateway: String,
+    fixtures_dir: Option<PathBuf>,
+    secrets: SigningSecrets,
+    dry_run: bool,
+    client: reqwest::blocking::Client,
+    threads: HashMap<String, ThreadTrack>,
+    seq: u64,
+}
+
+impl Simulator {
+    fn send(&mut self, step: &Step) -> Result<(), BoxError> {
+        if step.delay_ms > 0 {
+            std::thread::sleep(Duration::from_millis(step.delay_ms));
+        }
+        self.seq += 1;
+        let now = Utc::now();
+        let vars = self.build_vars(step, now);
+        let template = load_template(step.provider, self.fixtures_dir.as_deref())?;
+        let body = encode_body(step.provider, &render(&template, &vars))?;
+        let url = format!(
+            "{}{}",
+            self.gateway.trim_end_matches('/'),
+            step.provider.path()
+        );
+        let headers = sign_request(step.provider, &body, &url, now.timestamp(), &self.secrets)?;
+        let content_type = match step.provider {
+            Provider::Twilio => "application/x-www-form-urlencoded",
+            _ => "application/json",
+        };
+
+        println!(
+            "[{}] {} -> POST {}{}",
+            self.seq,
+            step.provider,
+            url,
+            if headers.is_empty() {
+                " (unsigned)"
+            } else {
+                ""
+            }
+        );
+        if self.dry_run {
+            for (name, value) in &headers {
+                println!("  {}: {}", name, value);
+            }
+            println!("  {}", String::from_utf8_lossy(&body));
+            return Ok(());
+        }
+
+        let mut request = self
+            .client
+            .post(&url)
+            .header("Content-Type", content_type)
+            .body(body);
+        for (name, value) in &headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let response = request.send()?;
+        let status = response.status().as_u16();
+        let text = response.text().unwrap_or_default();
+        println!("  <- {} {}", status, text);
+        if let Some(expected) = step.expect_status {
+            if expected != status {
+                return Err(format!(
+                    "step {} expected status {} but got {}",
+                    self.seq, expected, status
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    fn build_vars(&mut self, step: &Step, now: chrono::DateTime<Utc>) -> HashMap<String, String> {
+        let nonce = Uuid::new_v4().simple().to_string();
+        let message_id = format!("<sim-{}@gateway-sim.local>", nonce);
+        let ts = format!("{}.{:06}", now.timestamp(), self.seq % 1_000_000);
+        let from = step
+            .from
+            .clone()
+            .unwrap_or_else(|| step.provider.default_from().to_string());
+        let to = step
+            .to
+            .clone()
+            .unwrap_or_else(|| step.provider.default_to().to_string());
+
+        let track = step
+            .thread
+            .as_ref()
+            .map(|name| self.threads.entry(name.clone()).or_default());
+        let (in_reply_to, references, thread_ts, subject) = match track {
+            Some(track) => {
+                let in_reply_to = track.message_ids.last().cloned().unwrap_or_default();
+                let references = track.message_ids.join(" ");
+                let thread_ts = track.slack_thread_ts.clone().unwrap_or_default();
+                if track.slack_thread_ts.is_none() {
+                    track.slack_thread_ts = Some(ts.clone());
+                }
+                let subject = match (&step.subject, &track.subject) {
+                    (Some(subject), _) => subject.clone(),
+                    (None, Some(first)) => format!("Re: {}", first),
+                    (None, None) => "Simulated message".to_string(),
+                };
+                if track.subject.is_none() {
+                    track.subject = Some(subject.clone());
+                }
+                track.message_ids.push(message_id.clone());
+                (in_reply_to, references, thread_ts, subject)
+            }
+            None => (
+                String::new(),
+                String::new(),
+                String::new(),
+                step.subject
+                    .clone()
+                    .unwrap_or_else(|| "Simulated message".to_string()),
+            ),
+        };
+
+        let mut vars = HashMap::new();
+        vars.insert("from".to_string(), from.clone());
+        vars.insert("from_id".to_string(), from);
+        vars.insert("to".to_string(), to.clone());
+        vars.insert("chat_id".to_string(), to);
+        vars.insert("text".to_string(), step.text.clone());
+        vars.insert("subject".to_string(), subject);
+        vars.insert("message_id".to_string(), message_id);
+        vars.insert("provider_message_id".to_string(), nonce.clone());
+        vars.insert("in_reply_to".to_string(), in_reply_to);
+        vars.insert("references".to_string(), references);
+        vars.insert("ts".to_string(), ts);
+        vars.insert("thread_ts".to_string(), thread_ts);
+        vars.insert("nonce".to_string(), nonce);
+        vars.insert("message_seq".to_string(), self.seq.to_string());
+        vars.insert(
+            "update_id".to_string(),
+            (now.timestamp() * 1000 + self.seq as i64).to_string(),
+        );
+        vars.insert("date".to_string(), now.timestamp().to_string());
+        vars.insert("date_rfc2822".to_string(), now.to_rfc2822());
+        vars.insert(
+            "app_id".to_string(),
+            env::var("GATEWAY_SIM_SLACK_APP_ID").unwrap_or_else(|_| DEFAULT_SLACK_APP_ID.into()),
+        );
+        vars.insert(
+            "team_id".to_string(),
+            env::var("GATEWAY_SIM_SLACK_TEAM_ID").unwrap_or_else(|_| DEFAULT_SLACK_TEAM_ID.into()),
+        );
+        vars
+    }
+}
+
+fn main() -> Result<(), BoxError> {
+    dotenvy::dotenv().ok();
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    let (steps, scenario_gateway, scenario_fixtures) = match options.command {
+        Command::Send(step) => (vec![step], None, None),
+        Command::Scenario(path) => {
+            let scenario = load_scenario(&path)?;
+            if let Some(name) = scenario.name.as_deref() {
+                println!("scenario: {} ({} steps)", name, scenario.steps.len());
+            }
+            (scenario.steps, scenario.gateway, scenario.fixtures_dir)
+        }
+    };
+
+    let mut simulator = Simulator {
+        gateway: options
+            .gateway
+            .or(scenario_gateway)
+            .or_else(|| env::var("GATEWAY_SIM_URL").ok())
+            .unwrap_or_else(|| DEFAULT_GATEWAY.to_string()),
+        fixtures_dir: options.fixtures_dir.or(scenario_fixtures),
+        secrets: SigningSecrets::from_env(),
+        dry_run: options.dry_run,
+        client: reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?,
+        threads: HashMap::new(),
+        seq: 0,
+    };
+    for step in &steps {
+        simulator.send(step)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulator() -> Simulator {
+        Simulator {
+            gateway: DEFAULT_GATEWAY.to_string(),
+            fixtures_dir: None,
+            secrets: SigningSecrets::default(),
+            dry_run: true,
+            client: reqwest::blocking::Client::new(),
+            threads: HashMap::new(),
+            seq: 0,
+        }
+    }
+
+    fn step(provider: Provider, text: &str) -> Step {
+        Step {
+            provider,
+            text: text.to_string(),
+            thread: Some("t1".to_string()),
+            from: None,
+            to: None,
+            subject: None,
+            delay_ms: 0,
+            expect_status: None,
+        }
+    }
+
+    fn rendered(sim: &mut Simulator, step: &Step) -> serde_json::Value {
+        sim.seq += 1;
+        let vars = sim.build_vars(step, Utc::now());
+        render(&load_template(step.provider, None).unwrap(), &vars)
+    }
+
+    #[test]
+    fn email_thread_steps_reply_to_previous_message() {
+        let mut sim = simulator();
+        let first = rendered(&mut sim, &step(Provider::Postmark, "hello"));
+        let second = rendered(&mut sim, &step(Provider::Postmark, "follow up"));
+
+        let header = |payload: &serde_json::Value, name: &str| {
+            payload["Headers"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|header| header["Name"] == name)
+                .map(|header| header["Value"].as_str().unwrap().to_string())
+        };
+        assert_eq!(header(&first, "In-Reply-To"), None);
+        assert_eq!(header(&second, "In-Reply-To"), header(&first, "Message-ID"));
+        assert_eq!(second["Subject"], "Re: Simulated message");
+        assert_eq!(second["TextBody"], "follow up");
+    }
+
+    #[test]
+    fn slack_thread_steps_share_thread_ts_and_telegram_ids_are_numbers() {
+        let mut sim = simulator();
+        let first = rendered(&mut sim, &step(Provider::Slack, "hi"));
+        let second = rendered(&mut sim, &step(Provider::Slack, "again"));
+        assert!(first["event"].get("thread_ts").is_none());
+        assert_eq!(second["event"]["thread_ts"], first["event"]["ts"]);
+        assert_eq!(second["api_app_id"], DEFAULT_SLACK_APP_ID);
+
+        let telegram = rendered(&mut sim, &step(Provider::Telegram, "ping"));
+        assert!(telegram["message"]["chat"]["id"].is_i64());
+        assert!(telegram["update_id"].is_i64());
+        assert_eq!(telegram["message"]["text"], "ping");
+    }
+
+    #[test]
+    fn twilio_body_is_form_encoded() {
+        let mut sim = simulator();
+        let step = step(Provider::Twilio, "text me");
+        let payload = rendered(&mut sim, &step);
+        let body = encode_body(Provider::Twilio, &payload).unwrap();
+        let params: HashMap<String, String> = serde_urlencoded::from_bytes(&body).unwrap();
+        assert_eq!(params["Body"], "text me");
+        assert_eq!(params["To"], Provider::Twilio.default_to());
+    }
+}
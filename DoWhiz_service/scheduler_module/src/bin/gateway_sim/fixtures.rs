@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Provider {
+    Postmark,
+    Slack,
+    Twilio,
+    Telegram,
+}
+
+impl Provider {
+    pub(super) fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "postmark" | "email" => Some(Self::Postmark),
+            "slack" => Some(Self::Slack),
+            "twilio" | "sms" => Some(Self::Twilio),
+            "telegram" => Some(Self::Telegram),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_str(self) -> &'static str {
+        match self {
+            Self::Postmark => "postmark",
+            Self::Slack => "slack",
+            Self::Twilio => "twilio",
+            Self::Telegram => "telegram",
+        }
+    }
+
+    pub(super) fn path(self) -> &'static str {
+        match self {
+            Self::Postmark => "/postmark/inbound",
+            Self::Slack => "/slack/events",
+            Self::Twilio => "/sms/twilio",
+            Self::Telegram => "/telegram/webhook",
+        }
+    }
+
+    pub(super) fn default_from(self) -> &'static str {
+        match self {
+            Self::Postmark => "Sim User <sim-user@example.com>",
+            Self::Slack => "U0SIMUSER",
+            Self::Twilio => "+15550002000",
+            Self::Telegram => "100200300",
+        }
+    }
+
+    pub(super) fn default_to(self) -> &'static str {
+        match self {
+            Self::Postmark => "oliver@dowhiz.com",
+            Self::Slack => "D0SIMCHAN",
+            Self::Twilio => "+15550001000",
+            Self::Telegram => "100200300",
+        }
+    }
+
+    fn builtin_template(self) -> &'static str {
+        match self {
+            Self::Postmark => POSTMARK_TEMPLATE,
+            Self::Slack => SLACK_TEMPLATE,
+            Self::Twilio => TWILIO_TEMPLATE,
+            Self::Telegram => TELEGRAM_TEMPLATE,
+        }
+    }
+}
+
+impl fmt::Display for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Placeholders whose values are emitted as JSON numbers when they fill a
+/// whole string (Telegram ids must not be quoted).
+const NUMERIC_PLACEHOLDERS: &[&str] = &["chat_id", "from_id", "update_id", "message_seq", "date"];
+
+const POSTMARK_TEMPLATE: &str = r#"{
+  "From": "{{from}}",
+  "To": "{{to}}",
+  "ToFull": [{"Email": "{{to}}", "Name": ""}],
+  "Subject": "{{subject}}",
+  "TextBody": "{{text}}",
+  "HtmlBody": "<p>{{text}}</p>",
+  "MessageID": "{{provider_message_id}}",
+  "Date": "{{date_rfc2822}}",
+  "Headers": [
+    {"Name": "Message-ID", "Value": "{{message_id}}"},
+    {"Name": "In-Reply-To", "Value": "{{in_reply_to}}"},
+    {"Name": "References", "Value": "{{references}}"}
+  ],
+  "Attachments": []
+}"#;
+
+const SLACK_TEMPLATE: &str = r#"{
+  "token": "sim",
+  "team_id": "{{team_id}}",
+  "api_app_id": "{{app_id}}",
+  "type": "event_callback",
+  "event_id": "Ev{{message_seq}}{{nonce}}",
+  "event_time": "{{date}}",
+  "event": {
+    "type": "message",
+    "channel_type": "im",
+    "user": "{{from}}",
+    "text": "{{text}}",
+    "channel": "{{to}}",
+    "ts": "{{ts}}",
+    "thread_ts": "{{thread_ts}}",
+    "team": "{{team_id}}"
+  }
+}"#;
+
+const TWILIO_TEMPLATE: &str = r#"{
+  "MessageSid": "SM{{nonce}}",
+  "AccountSid": "ACsimulator",
+  "From": "{{from}}",
+  "To": "{{to}}",
+  "Body": "{{text}}",
+  "NumMedia": "0"
+}"#;
+
+const TELEGRAM_TEMPLATE: &str = r#"{
+  "update_id": "{{update_id}}",
+  "message": {
+    "message_id": "{{message_seq}}",
+    "from": {"id": "{{from_id}}", "is_bot": false, "first_name": "Sim", "username": "sim_user"},
+    "chat": {"id": "{{chat_id}}", "type": "private"},
+    "date": "{{date}}",
+    "text": "{{text}}"
+  }
+}"#;
+
+/// Load the template for a provider, preferring `<fixtures_dir>/<provider>.json`.
+pub(super) fn load_template(
+    provider: Provider,
+    fixtures_dir: Option<&Path>,
+) -> Result<Value, BoxError> {
+    if let Some(dir) = fixtures_dir {
+        let path = dir.join(format!("{}.json", provider.as_str()));
+        if path.is_file() {
+            let raw = std::fs::read_to_string(&path)?;
+            return Ok(serde_json::from_str(&raw)
+                .map_err(|err| format!("invalid fixture {}: {}", path.display(), err))?);
+        }
+    }
+    Ok(serde_json::from_str(provider.builtin_template())?)
+}
+
+/// Substitute `{{name}}` placeholders in every string of the template.
+///
+/// Object fields whose value renders to an empty string are dropped so
+/// optional headers (`In-Reply-To`, `thread_ts`) vanish on first messages.
+pub(super) fn render(template: &Value, vars: &HashMap<String, String>) -> Value {
+    match template {
+        Value::String(raw) => render_string(raw, vars),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render(item, vars))
+                .filter(|item| !is_empty_header(item))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), render(value, vars)))
+                .filter(|(_, value)| value.as_str() != Some(""))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn render_string(raw: &str, vars: &HashMap<String, String>) -> Value {
+    if let Some(name) = raw
+        .strip_prefix("{{")
+        .and_then(|rest| rest.strip_suffix("}}"))
+    {
+        if NUMERIC_PLACEHOLDERS.contains(&name) {
+            if let Some(number) = vars.get(name).and_then(|value| value.parse::<i64>().ok()) {
+                return Value::from(number);
+            }
+        }
+    }
+    let mut output = raw.to_string();
+    for (name, value) in vars {
+        output = output.replace(&format!("{{{{{}}}}}", name), value);
+    }
+    Value::String(output)
+}
+
+/// Postmark header entries look like `{"Name": .., "Value": ""}` once the
+/// value was dropped; remove them entirely.
+fn is_empty_header(item: &Value) -> bool {
+    item.as_object()
+        .map(|map| map.contains_key("Name") && !map.contains_key("Value"))
+        .unwrap_or(false)
+}
+
+/// Twilio posts form-encoded bodies; every other provider posts JSON.
+pub(super) fn encode_body(provider: Provider, rendered: &Value) -> Result<Vec<u8>, BoxError> {
+    match provider {
+        Provider::Twilio => {
+            let params: Vec<(String, String)> = rendered
+                .as_object()
+                .ok_or("twilio fixture must be a JSON object")?
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(text) => text.clone(),
+                        other => other.to_string(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect();
+            Ok(serde_urlencoded::to_string(params)?.into_bytes())
+        }
+        _ => Ok(serde_json::to_vec(rendered)?),
+    }
+}
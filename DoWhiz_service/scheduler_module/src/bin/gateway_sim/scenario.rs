@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::fixtures::Provider;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A scripted sequence of inbound webhooks, loaded from TOML.
+///
+/// Steps that share a `thread` name continue the same conversation: email
+/// steps reply to the previous Message-ID, Slack steps reuse the first `ts`
+/// as `thread_ts`, and SMS/Telegram steps keep the same sender and chat.
+#[derive(Debug, Clone, Deserialize)]
+pub(super) struct Scenario {
+    #[serde(default)]
+    pub(super) name: Option<String>,
+    #[serde(default)]
+    pub(super) gateway: Option<String>,
+    #[serde(default)]
+    pub(super) fixtures_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub(super) steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(super) struct Step {
+    pub(super) provider: Provider,
+    pub(super) text: String,
+    #[serde(default)]
+    pub(super) thread: Option<String>,
+    #[serde(default)]
+    pub(super) from: Option<String>,
+    #[serde(default)]
+    pub(super) to: Option<String>,
+    #[serde(default)]
+    pub(super) subject: Option<String>,
+    /// Wait this long before sending the step.
+    #[serde(default)]
+    pub(super) delay_ms: u64,
+    /// Fail the run if the gateway answers with a different status.
+    #[serde(default)]
+    pub(super) expect_status: Option<u16>,
+}
+
+pub(super) fn load_scenario(path: &Path) -> Result<Scenario, BoxError> {
+    let raw = std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read scenario {}: {}", path.display(), err))?;
+    let mut scenario: Scenario = toml::from_str(&raw)
+        .map_err(|err| format!("invalid scenario {}: {}", path.display(), err))?;
+    if scenario.steps.is_empty() {
+        return Err(format!("scenario {} has no steps", path.display()).into());
+    }
+    // Fixture paths in a scenario are relative to the scenario file.
+    if let (Some(dir), Some(parent)) = (scenario.fixtures_dir.as_ref(), path.parent()) {
+        if dir.is_relative() {
+            scenario.fixtures_dir = Some(parent.join(dir));
+        }
+    }
+    Ok(scenario)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_multi_step_scenario() {
+        let scenario: Scenario = toml::from_str(
+            r#"
+name = "email follow-up"
+gateway = "http://127.0.0.1:9100"
+
+[[steps]]
+provider = "postmark"
+thread = "intro"
+subject = "Hello"
+text = "Can you summarize this?"
+
+[[steps]]
+provider = "postmark"
+thread = "intro"
+text = "Also add action items."
+delay_ms = 250
+expect_status = 200
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(scenario.steps.len(), 2);
+        assert_eq!(scenario.steps[0].provider, Provider::Postmark);
+        assert_eq!(scenario.steps[1].thread.as_deref(), Some("intro"));
+        assert_eq!(scenario.steps[1].delay_ms, 250);
+        assert_eq!(scenario.steps[1].expect_status, Some(200));
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::env;
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::Sha256;
+
+use super::fixtures::Provider;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Secrets used to sign simulated webhooks. They default to the same env vars
+/// the gateway verifies with, so a local gateway accepts the requests as-is.
+#[derive(Debug, Clone, Default)]
+pub(super) struct SigningSecrets {
+    pub(super) slack_signing_secret: Option<String>,
+    pub(super) postmark_inbound_token: Option<String>,
+    pub(super) postmark_basic_auth: Option<String>,
+    pub(super) twilio_auth_token: Option<String>,
+    pub(super) twilio_webhook_url: Option<String>,
+    pub(super) telegram_secret_token: Option<String>,
+}
+
+impl SigningSecrets {
+    pub(super) fn from_env() -> Self {
+        let read = |name: &str| {
+            env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            slack_signing_secret: read("SLACK_SIGNING_SECRET"),
+            postmark_inbound_token: read("POSTMARK_INBOUND_TOKEN"),
+            postmark_basic_auth: read("POSTMARK_INBOUND_BASIC_AUTH"),
+            twilio_auth_token: read("TWILIO_AUTH_TOKEN"),
+            twilio_webhook_url: read("TWILIO_WEBHOOK_URL"),
+            telegram_secret_token: read("TELEGRAM_WEBHOOK_SECRET"),
+        }
+    }
+}
+
+/// Build the provider-specific auth headers for a request body.
+///
+/// `target_url` is only used for Twilio when `TWILIO_WEBHOOK_URL` is unset,
+/// since Twilio signs the public URL it posted to.
+pub(super) fn sign_request(
+    provider: Provider,
+    body: &[u8],
+    target_url: &str,
+    timestamp: i64,
+    secrets: &SigningSecrets,
+) -> Result<Vec<(String, String)>, BoxError> {
+    let mut headers = Vec::new();
+    match provider {
+        Provider::Postmark => {
+            if let Some(credentials) = secrets.postmark_basic_auth.as_deref() {
+                let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+                headers.push(("Authorization".to_string(), format!("Basic {}", encoded)));
+            }
+            if let Some(token) = secrets.postmark_inbound_token.as_deref() {
+                headers.push(("X-Postmark-Token".to_string(), token.to_string()));
+            }
+        }
+        Provider::Slack => {
+            if let Some(secret) = secrets.slack_signing_secret.as_deref() {
+                headers.push((
+                    "X-Slack-Request-Timestamp".to_string(),
+                    timestamp.to_string(),
+                ));
+                headers.push((
+                    "X-Slack-Signature".to_string(),
+                    slack_signature(secret, timestamp, body)?,
+                ));
+            }
+        }
+        Provider::Twilio => {
+            if let Some(token) = secrets.twilio_auth_token.as_deref() {
+                let url = secrets.twilio_webhook_url.as_deref().unwrap_or(target_url);
+                headers.push((
+                    "X-Twilio-Signature".to_string(),
+                    twilio_signature(token, url, body)?,
+                ));
+            }
+        }
+        Provider::Telegram => {
+            if let Some(token) = secrets.telegram_secret_token.as_deref() {
+                headers.push((
+                    "X-Telegram-Bot-Api-Secret-Token".to_string(),
+                    token.to_string(),
+                ));
+            }
+        }
+    }
+    Ok(headers)
+}
+
+fn slack_signature(secret: &str, timestamp: i64, body: &[u8]) -> Result<String, BoxError> {
+    let base = format!("v0:{}:{}", timestamp, String::from_utf8_lossy(body));
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(base.as_bytes());
+    Ok(format!("v0={}", hex::encode(mac.finalize().into_bytes())))
+}
+
+fn twilio_signature(token: &str, url: &str, body: &[u8]) -> Result<String, BoxError> {
+    let params: BTreeMap<String, String> = serde_urlencoded::from_bytes(body)?;
+    let mut data = url.to_string();
+    for (key, value) in params {
+        data.push_str(&key);
+        data.push_str(&value);
+    }
+    let mut mac = Hmac::<Sha1>::new_from_slice(token.as_bytes())?;
+    mac.update(data.as_bytes());
+    Ok(base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slack_signature_matches_documented_example() {
+        // Example from Slack's "Verifying requests from Slack" guide.
+        let body = b"token=xyzz0WbapA4vBCDEFasx0q6G&team_id=T1DC2JH3J&team_domain=testteamnow&channel_id=G8PSS9T3V&channel_name=foobar&user_id=U2CERLKJA&user_name=roadrunner&command=%2Fwebhook-collect&text=&response_url=https%3A%2F%2Fhooks.slack.com%2Fcommands%2FT1DC2JH3J%2F397700885554%2F96rGlfmibIGlgcZRskXaIFfN&trigger_id=398738663015.47445629121.803a0bc887a14d10d2c447fce8b6703c";
+        let signature =
+            slack_signature("8f742231b10e8888abcd99yyyzzz85a5", 1531420618, body).unwrap();
+        assert_eq!(
+            signature,
+            "v0=a2114d57b48eac39b9ad189dd8316235a7b4a8d21a10bd27519666489c69b503"
+        );
+    }
+
+    #[test]
+    fn unsigned_when_no_secrets_configured() {
+        let headers = sign_request(
+            Provider::Slack,
+            b"{}",
+            "http://127.0.0.1:9100/slack/events",
+            0,
+            &SigningSecrets::default(),
+        )
+        .unwrap();
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn twilio_signature_sorts_params_and_prefers_configured_url() {
+        let secrets = SigningSecrets {
+            twilio_auth_token: Some("token".to_string()),
+            twilio_webhook_url: Some("https://example.com/sms/twilio".to_string()),
+            ..SigningSecrets::default()
+        };
+        let headers = sign_request(
+            Provider::Twilio,
+            b"To=%2B1555&From=%2B1666&Body=hi",
+            "http://127.0.0.1:9100/sms/twilio",
+            0,
+            &secrets,
+        )
+        .unwrap();
+        let expected = twilio_signature(
+            "token",
+            "https://example.com/sms/twilio",
+            b"Body=hi&From=%2B1666&To=%2B1555",
+        )
+        .unwrap();
+        assert_eq!(headers, vec![("X-Twilio-Signature".to_string(), expected)]);
+    }
+}
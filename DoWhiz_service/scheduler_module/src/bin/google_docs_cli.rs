@@ -8,8 +8,13 @@
 //! - Create new documents
 //! - Share documents and manage permissions
 
+use chrono::Utc;
 use scheduler_module::adapters::google_common::{GoogleDriveClient, PermissionRole};
-use scheduler_module::adapters::google_docs::GoogleDocsOutboundAdapter;
+use scheduler_module::adapters::google_docs::{
+    GoogleDocsOutboundAdapter, ResolvedSuggestions, SuggestionKind,
+};
+use scheduler_module::audit_store::{self, AuditEntry};
+use scheduler_module::channel::{AdapterError, Channel};
 use scheduler_module::google_auth::{GoogleAuth, GoogleAuthConfig};
 use std::env;
 use std::process::exit;
@@ -26,9 +31,9 @@ Commands:
   reply-comment <doc_id> <comment_id> <message>  Reply to a comment
 
 Direct Edit Operations:
-  apply-edit <doc_id> --find="text" --replace="new text"
-  insert-text <doc_id> --after="anchor" --text="text to insert"
-  delete-text <doc_id> --find="text to delete"
+  apply-edit <doc_id> --find="text" --replace="new text" [--comment-id=<id>]
+  insert-text <doc_id> --after="anchor" --text="text to insert" [--comment-id=<id>]
+  delete-text <doc_id> --find="text to delete" [--comment-id=<id>]
   insert-image <doc_id> --url="https://..." [--after="anchor text"] [--index=1] [--width=200] [--height=150]
 
 Image Search (Unsplash):
@@ -39,11 +44,18 @@ Style Operations:
   set-style <doc_id> --find="text" [--color="#FF0000"] [--font="Arial"] [--size=12] [--bold] [--italic]
 
 Suggesting Mode Operations:
-  mark-deletion <doc_id> --find="text to mark"
-  insert-suggestion <doc_id> --after="anchor" --text="suggestion text"
-  suggest-replace <doc_id> --find="old text" --replace="new text"
+  mark-deletion <doc_id> --find="text to mark" [--comment-id=<id>]
+  insert-suggestion <doc_id> --after="anchor" --text="suggestion text" [--comment-id=<id>]
+  suggest-replace <doc_id> --find="old text" --replace="new text" [--comment-id=<id>]
   apply-suggestions <doc_id>
   discard-suggestions <doc_id>
+  (Suggestions are tracked per document, so apply/discard only touch text
+   marked by these commands, even after other edits to the document.)
+
+Edits are sent at the document revision they were computed from; if someone
+edits the document in between, the text is located again before retrying.
+With --comment-id, an edit whose text is no longer in the document is
+abandoned with a reply on that comment instead of an edit in the wrong place.
 
 Document Management:
   create-document --title="My Document"        Create a new document
@@ -61,6 +73,9 @@ Environment Variables:
   GOOGLE_REFRESH_TOKEN   - Google OAuth refresh token
   EMPLOYEE_ID            - (optional) Employee ID for per-employee tokens
   UNSPLASH_ACCESS_KEY    - (optional) Unsplash API key for image search
+  MONGODB_URI            - (optional) Record document changes in the audit log
+  GOOGLE_DOCS_SUGGESTIONS_DIR - (optional) Where pending suggestions are tracked
+                           (default ~/.dowhiz/DoWhiz/google_docs/suggestions)
 
 Note: In sandbox environments without network access, set GOOGLE_ACCESS_TOKEN
       to a pre-generated token. This avoids the need for OAuth token refresh.
@@ -166,6 +181,7 @@ fn main() {
     }
 
     let command = &args[1];
+    let comment_id = parse_arg(&args, "--comment-id");
 
     let result = match command.as_str() {
         "list-documents" => cmd_list_documents(),
@@ -205,7 +221,7 @@ fn main() {
                 eprintln!("Error: --find and --replace are required");
                 exit(1);
             }
-            cmd_apply_edit(&args[2], &find, &replace)
+            cmd_apply_edit(&args[2], comment_id.as_deref(), &find, &replace)
         }
         "insert-text" => {
             if args.len() < 3 {
@@ -219,7 +235,7 @@ fn main() {
                 eprintln!("Error: --after and --text are required");
                 exit(1);
             }
-            cmd_insert_text(&args[2], &after, &text)
+            cmd_insert_text(&args[2], comment_id.as_deref(), &after, &text)
         }
         "delete-text" => {
             if args.len() < 3 {
@@ -232,7 +248,7 @@ fn main() {
                 eprintln!("Error: --find is required");
                 exit(1);
             }
-            cmd_delete_text(&args[2], &find)
+            cmd_delete_text(&args[2], comment_id.as_deref(), &find)
         }
         "insert-image" => {
             if args.len() < 3 {
@@ -262,7 +278,7 @@ fn main() {
                 eprintln!("Error: --find is required");
                 exit(1);
             }
-            cmd_mark_deletion(&args[2], &find)
+            cmd_mark_deletion(&args[2], comment_id.as_deref(), &find)
         }
         "insert-suggestion" => {
             if args.len() < 3 {
@@ -276,7 +292,7 @@ fn main() {
                 eprintln!("Error: --after and --text are required");
                 exit(1);
             }
-            cmd_insert_suggestion(&args[2], &after, &text)
+            cmd_insert_suggestion(&args[2], comment_id.as_deref(), &after, &text)
         }
         "suggest-replace" => {
             if args.len() < 3 {
@@ -290,7 +306,7 @@ fn main() {
                 eprintln!("Error: --find and --replace are required");
                 exit(1);
             }
-            cmd_suggest_replace(&args[2], &find, &replace)
+            cmd_suggest_replace(&args[2], comment_id.as_deref(), &find, &replace)
         }
         "apply-suggestions" => {
             if args.len() < 3 {
@@ -426,6 +442,7 @@ fn main() {
 
     match result {
         Ok(output) => {
+            record_audit(command, &args, &output);
             println!("{}", output);
         }
         Err(e) => {
@@ -435,6 +452,59 @@ fn main() {
     }
 }
 
+/// Commands that change a document or its sharing and so get an audit entry.
+const AUDITED_COMMANDS: &[&str] = &[
+    "reply-comment",
+    "apply-edit",
+    "insert-text",
+    "delete-text",
+    "insert-image",
+    "mark-deletion",
+    "insert-suggestion",
+    "suggest-replace",
+    "apply-suggestions",
+    "discard-suggestions",
+    "set-style",
+    "create-document",
+    "share",
+    "remove-permission",
+];
+
+fn record_audit(command: &str, args: &[String], output: &str) {
+    if !AUDITED_COMMANDS.contains(&command) {
+        return;
+    }
+    let file_id = args.get(2).cloned().unwrap_or_default();
+    let target = match command {
+        "create-document" => output
+            .lines()
+            .find_map(|line| line.strip_prefix("Document ID: "))
+            .unwrap_or_default()
+            .to_string(),
+        "share" => format!(
+            "{} -> {}",
+            file_id,
+            parse_arg(args, "--email").unwrap_or_default()
+        ),
+        _ => file_id,
+    };
+    let env_non_empty = |key: &str| env::var(key).ok().filter(|value| !value.trim().is_empty());
+    audit_store::record(AuditEntry {
+        actor: format!(
+            "employee:{}",
+            env_non_empty("EMPLOYEE_ID").unwrap_or_else(|| "unknown".to_string())
+        ),
+        on_behalf_of: None,
+        action: format!("google_docs.{}", command.replace('-', "_")),
+        target,
+        channel: Some(Channel::GoogleDocs.to_string()),
+        payload_hash: audit_store::payload_hash(args[2..].join("\n").as_bytes()),
+        trace_id: env_non_empty("DOWHIZ_TRACE_ID"),
+        task_id: None,
+        recorded_at: Utc::now(),
+    });
+}
+
 fn cmd_list_documents() -> Result<String, String> {
     let auth = get_auth()?;
     let adapter = scheduler_module::adapters::google_docs::GoogleDocsInboundAdapter::new(
@@ -522,18 +592,23 @@ fn cmd_reply_comment(doc_id: &str, comment_id: &str, message: &str) -> Result<St
     Ok(format!("Successfully posted reply (id={})", reply.id))
 }
 
-fn cmd_apply_edit(doc_id: &str, find: &str, replace: &str) -> Result<String, String> {
+fn cmd_apply_edit(
+    doc_id: &str,
+    comment_id: Option<&str>,
+    find: &str,
+    replace: &str,
+) -> Result<String, String> {
     let auth = get_auth()?;
     let adapter = GoogleDocsOutboundAdapter::new(auth);
 
-    // For direct edit, we use suggest_replace then apply_suggestions
-    adapter
+    // For direct edit, we use suggest_replace then apply just that suggestion
+    let ids = adapter
         .suggest_replace(doc_id, find, replace)
-        .map_err(|e| format!("Failed to mark edit: {}", e))?;
+        .map_err(|e| edit_error(&adapter, doc_id, comment_id, "Failed to mark edit", e))?;
 
     adapter
-        .apply_suggestions(doc_id)
-        .map_err(|e| format!("Failed to apply edit: {}", e))?;
+        .apply_suggestions_by_id(doc_id, &ids)
+        .map_err(|e| edit_error(&adapter, doc_id, comment_id, "Failed to apply edit", e))?;
 
     Ok(format!(
         "Successfully replaced \"{}\" with \"{}\"",
@@ -541,18 +616,23 @@ fn cmd_apply_edit(doc_id: &str, find: &str, replace: &str) -> Result<String, Str
     ))
 }
 
-fn cmd_insert_text(doc_id: &str, after: &str, text: &str) -> Result<String, String> {
+fn cmd_insert_text(
+    doc_id: &str,
+    comment_id: Option<&str>,
+    after: &str,
+    text: &str,
+) -> Result<String, String> {
     let auth = get_auth()?;
     let adapter = GoogleDocsOutboundAdapter::new(auth);
 
-    // For direct insert, add as suggestion then apply
-    adapter
+    // For direct insert, add as suggestion then apply it
+    let id = adapter
         .insert_suggestion(doc_id, after, text)
-        .map_err(|e| format!("Failed to mark insertion: {}", e))?;
+        .map_err(|e| edit_error(&adapter, doc_id, comment_id, "Failed to mark insertion", e))?;
 
     adapter
-        .apply_suggestions(doc_id)
-        .map_err(|e| format!("Failed to apply insertion: {}", e))?;
+        .apply_suggestions_by_id(doc_id, &[id])
+        .map_err(|e| edit_error(&adapter, doc_id, comment_id, "Failed to apply insertion", e))?;
 
     Ok(format!(
         "Successfully inserted \"{}\" after \"{}\"",
@@ -560,22 +640,48 @@ fn cmd_insert_text(doc_id: &str, after: &str, text: &str) -> Result<String, Stri
     ))
 }
 
-fn cmd_delete_text(doc_id: &str, find: &str) -> Result<String, String> {
+fn cmd_delete_text(doc_id: &str, comment_id: Option<&str>, find: &str) -> Result<String, String> {
     let auth = get_auth()?;
     let adapter = GoogleDocsOutboundAdapter::new(auth);
 
-    // For direct delete, mark for deletion then apply
-    adapter
+    // For direct delete, mark for deletion then apply it
+    let id = adapter
         .mark_deletion(doc_id, find)
-        .map_err(|e| format!("Failed to mark deletion: {}", e))?;
+        .map_err(|e| edit_error(&adapter, doc_id, comment_id, "Failed to mark deletion", e))?;
 
     adapter
-        .apply_suggestions(doc_id)
-        .map_err(|e| format!("Failed to apply deletion: {}", e))?;
+        .apply_suggestions_by_id(doc_id, &[id])
+        .map_err(|e| edit_error(&adapter, doc_id, comment_id, "Failed to apply deletion", e))?;
 
     Ok(format!("Successfully deleted \"{}\"", find))
 }
 
+/// Describe a failed edit. When its text is no longer in the document and the
+/// edit was asked for in a comment, reply there so the commenter knows why
+/// nothing changed.
+fn edit_error(
+    adapter: &GoogleDocsOutboundAdapter,
+    doc_id: &str,
+    comment_id: Option<&str>,
+    context: &str,
+    err: AdapterError,
+) -> String {
+    let (AdapterError::TextNotFound(text), Some(comment_id)) = (&err, comment_id) else {
+        return format!("{}: {}", context, err);
+    };
+    let reply = format!(
+        "I didn't make this change: \"{}\" is no longer in the document, so it may have been edited since your comment. Let me know the current wording and I'll try again.",
+        text
+    );
+    match adapter.reply_to_comment(doc_id, comment_id, &reply) {
+        Ok(_) => format!("{}: {} (replied on comment {})", context, err, comment_id),
+        Err(reply_err) => format!(
+            "{}: {} (failed to reply on comment {}: {})",
+            context, err, comment_id, reply_err
+        ),
+    }
+}
+
 fn cmd_insert_image(
     doc_id: &str,
     url: &str,
@@ -621,13 +727,13 @@ fn cmd_insert_image(
     ))
 }
 
-fn cmd_mark_deletion(doc_id: &str, find: &str) -> Result<String, String> {
+fn cmd_mark_deletion(doc_id: &str, comment_id: Option<&str>, find: &str) -> Result<String, String> {
     let auth = get_auth()?;
     let adapter = GoogleDocsOutboundAdapter::new(auth);
 
     adapter
         .mark_deletion(doc_id, find)
-        .map_err(|e| format!("Failed to mark deletion: {}", e))?;
+        .map_err(|e| edit_error(&adapter, doc_id, comment_id, "Failed to mark deletion", e))?;
 
     Ok(format!(
         "Successfully marked \"{}\" for deletion (red strikethrough)",
@@ -635,13 +741,26 @@ fn cmd_mark_deletion(doc_id: &str, find: &str) -> Result<String, String> {
     ))
 }
 
-fn cmd_insert_suggestion(doc_id: &str, after: &str, text: &str) -> Result<String, String> {
+fn cmd_insert_suggestion(
+    doc_id: &str,
+    comment_id: Option<&str>,
+    after: &str,
+    text: &str,
+) -> Result<String, String> {
     let auth = get_auth()?;
     let adapter = GoogleDocsOutboundAdapter::new(auth);
 
     adapter
         .insert_suggestion(doc_id, after, text)
-        .map_err(|e| format!("Failed to insert suggestion: {}", e))?;
+        .map_err(|e| {
+            edit_error(
+                &adapter,
+                doc_id,
+                comment_id,
+                "Failed to insert suggestion",
+                e,
+            )
+        })?;
 
     Ok(format!(
         "Successfully inserted suggestion \"{}\" (blue) after \"{}\"",
@@ -649,13 +768,26 @@ fn cmd_insert_suggestion(doc_id: &str, after: &str, text: &str) -> Result<String
     ))
 }
 
-fn cmd_suggest_replace(doc_id: &str, find: &str, replace: &str) -> Result<String, String> {
+fn cmd_suggest_replace(
+    doc_id: &str,
+    comment_id: Option<&str>,
+    find: &str,
+    replace: &str,
+) -> Result<String, String> {
     let auth = get_auth()?;
     let adapter = GoogleDocsOutboundAdapter::new(auth);
 
     adapter
         .suggest_replace(doc_id, find, replace)
-        .map_err(|e| format!("Failed to suggest replacement: {}", e))?;
+        .map_err(|e| {
+            edit_error(
+                &adapter,
+                doc_id,
+                comment_id,
+                "Failed to suggest replacement",
+                e,
+            )
+        })?;
 
     Ok(format!(
         "Successfully suggested replacing \"{}\" (red strikethrough) with \"{}\" (blue)",
@@ -667,25 +799,46 @@ fn cmd_apply_suggestions(doc_id: &str) -> Result<String, String> {
     let auth = get_auth()?;
     let adapter = GoogleDocsOutboundAdapter::new(auth);
 
-    adapter
+    let resolved = adapter
         .apply_suggestions(doc_id)
         .map_err(|e| format!("Failed to apply suggestions: {}", e))?;
 
-    Ok(
-        "Successfully applied all suggestions (deleted red text, normalized blue text to black)"
-            .to_string(),
-    )
+    Ok(format!(
+        "Successfully applied {} suggestion(s) (deleted marked text, restored the style of added text){}",
+        resolved.resolved,
+        format_missing(&resolved)
+    ))
 }
 
 fn cmd_discard_suggestions(doc_id: &str) -> Result<String, String> {
     let auth = get_auth()?;
     let adapter = GoogleDocsOutboundAdapter::new(auth);
 
-    adapter
+    let resolved = adapter
         .discard_suggestions(doc_id)
         .map_err(|e| format!("Failed to discard suggestions: {}", e))?;
 
-    Ok("Successfully discarded all suggestions (deleted blue text, restored red text)".to_string())
+    Ok(format!(
+        "Successfully discarded {} suggestion(s) (deleted added text, restored marked text){}",
+        resolved.resolved,
+        format_missing(&resolved)
+    ))
+}
+
+/// Note the suggestions whose text was edited away since they were made.
+fn format_missing(resolved: &ResolvedSuggestions) -> String {
+    let mut output = String::new();
+    for suggestion in &resolved.missing {
+        let kind = match suggestion.kind {
+            SuggestionKind::Insertion => "insertion",
+            SuggestionKind::Deletion => "deletion",
+        };
+        output.push_str(&format!(
+            "\nSkipped {} of \"{}\": the text is no longer in the document",
+            kind, suggestion.text
+        ));
+    }
+    output
 }
 
 fn cmd_get_styles(doc_id: &str) -> Result<String, String> {
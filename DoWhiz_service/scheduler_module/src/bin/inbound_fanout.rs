@@ -28,7 +28,7 @@ struct ForwardResponse {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt().with_target(false).init();
+    let _telemetry = scheduler_module::telemetry::init("inbound_fanout");
 
     let host = env::var("FANOUT_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
     let port = env::var("FANOUT_PORT")
@@ -25,13 +25,16 @@ use tokio::task;
 use tracing::{info, warn};
 
 use scheduler_module::account_store::AccountStore;
+use scheduler_module::adapters::configure_employee_mentions;
 use scheduler_module::blob_store::get_blob_store;
 use scheduler_module::employee_config::load_employee_directory;
 use scheduler_module::google_auth::GoogleAuth;
 use scheduler_module::google_drive_changes::{GoogleDriveChangesConfig, GoogleDriveChangesManager};
+use scheduler_module::inbound_dedupe::get_global_inbound_dedupe_store;
 use scheduler_module::ingestion_queue::{
     build_servicebus_queue_from_env, resolve_ingestion_queue_backend, IngestionQueue,
 };
+use scheduler_module::migrations::migrate_from_env;
 use scheduler_module::service::agent_market::{agent_market_router, AgentMarketState};
 use scheduler_module::service::auth::{auth_router, AuthState};
 
@@ -43,23 +46,26 @@ use discord::spawn_discord_gateway;
 use google_drive_webhook::handle_google_drive_webhook;
 use google_workspace::spawn_google_workspace_poller;
 use handlers::{
-    create_90_day_plan, create_workspace_brief, health, ingest_bluebubbles, ingest_postmark,
-    ingest_slack, ingest_sms, ingest_telegram, ingest_wechat, ingest_whatsapp,
-    verify_wechat_webhook, verify_whatsapp_webhook,
+    create_90_day_plan, create_workspace_brief, health, ingest_bluebubbles, ingest_jira,
+    ingest_postmark, ingest_slack, ingest_slack_command, ingest_slack_interaction, ingest_sms,
+    ingest_telegram, ingest_wechat, ingest_whatsapp, verify_wechat_webhook,
+    verify_whatsapp_webhook,
 };
 use routes::normalize_routes;
 use state::{build_address_map, GatewayConfig, GatewayState};
+use verify::WebhookVerifiers;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    tracing_subscriber::fmt().with_target(false).init();
     dotenvy::dotenv().ok();
+    let _telemetry = scheduler_module::telemetry::init("inbound_gateway");
 
     let config_path = resolve_gateway_config_path()?;
     let config_file: GatewayConfigFile = load_gateway_config(&config_path)?;
 
     let employee_config_path = resolve_employee_config_path();
     let employee_directory = load_employee_directory(&employee_config_path)?;
+    configure_employee_mentions(&employee_directory);
     let address_to_employee = build_address_map(&employee_directory);
 
     let host = env::var("GATEWAY_HOST")
@@ -117,6 +123,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         (None, None)
     };
 
+    let webhook_verifiers = WebhookVerifiers::from_env();
+    info!(
+        "gateway webhook verification enabled for channels={:?}",
+        webhook_verifiers.configured_channels()
+    );
+
+    // Stores no longer create their indexes; the dedupe store relies on its
+    // unique index, so bring the schema up before opening any of them.
+    task::spawn_blocking(migrate_from_env)
+        .await
+        .map_err(|err| -> Box<dyn std::error::Error + Send + Sync> { err.into() })??;
+
+    let inbound_dedupe = task::spawn_blocking(get_global_inbound_dedupe_store)
+        .await
+        .map_err(|err| -> Box<dyn std::error::Error + Send + Sync> { err.into() })?;
+
     let state = Arc::new(GatewayState {
         config: GatewayConfig {
             defaults: config_file.defaults,
@@ -126,6 +148,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         employee_directory,
         address_to_employee,
         queue,
+        webhook_verifiers,
+        inbound_dedupe,
         drive_changes_manager,
         drive_change_notifier,
     });
@@ -208,6 +232,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         .route("/health", get(health))
         .route("/postmark/inbound", post(ingest_postmark))
         .route("/slack/events", post(ingest_slack))
+        .route("/slack/commands", post(ingest_slack_command))
+        .route("/slack/interactions", post(ingest_slack_interaction))
         .route("/bluebubbles/webhook", post(ingest_bluebubbles))
         .route("/telegram/webhook", post(ingest_telegram))
         .route("/sms/twilio", post(ingest_sms))
@@ -215,6 +241,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         .route("/whatsapp/webhook", post(ingest_whatsapp))
         .route("/wechat/webhook", get(verify_wechat_webhook))
         .route("/wechat/webhook", post(ingest_wechat))
+        .route("/jira/webhook", post(ingest_jira))
         .route(
             "/webhooks/google-drive-changes",
             post(handle_google_drive_webhook),
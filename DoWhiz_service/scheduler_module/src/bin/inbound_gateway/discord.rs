@@ -2,11 +2,11 @@ use std::collections::HashSet;
 use std::env;
 use std::sync::Arc;
 
-use scheduler_module::adapters::discord::DiscordInboundAdapter;
-use scheduler_module::channel::Channel;
+use scheduler_module::adapters::discord::{discord_thread_id_for_channel, DiscordInboundAdapter};
+use scheduler_module::channel::{Channel, InboundMessage};
 use tracing::{error, info, warn};
 
-use super::handlers::build_envelope;
+use super::handlers::{build_envelope, enqueue_deduped};
 use super::state::{GatewayConfig, GatewayState, RouteDecision};
 
 /// Configuration for a single employee's Discord bot.
@@ -242,8 +242,8 @@ impl serenity::all::EventHandler for DiscordIngressHandler {
         info!("Discord bot connected as {}", ready.user.name);
     }
 
-    async fn message(&self, _ctx: serenity::all::Context, msg: serenity::all::Message) {
-        let inbound = match self.inner.adapter.from_serenity_message(&msg) {
+    async fn message(&self, ctx: serenity::all::Context, msg: serenity::all::Message) {
+        let mut inbound = match self.inner.adapter.from_serenity_message(&msg) {
             Ok(message) => message,
             Err(err) => {
                 if !err.to_string().contains("ignoring bot") {
@@ -270,52 +270,94 @@ impl serenity::all::EventHandler for DiscordIngressHandler {
             return;
         }
 
-        // Use the employee_id from this handler's state (each bot client knows its employee)
-        let route = RouteDecision {
-            tenant_id: self.inner.tenant_id.clone(),
-            employee_id: self.inner.employee_id.clone(),
-        };
+        // Messages inside a thread keep their conversation (and replies) in that thread.
+        if !is_direct_message {
+            match msg.channel_id.to_channel(&ctx).await {
+                Ok(channel) => {
+                    inbound.metadata.discord_thread_id = discord_thread_id_for_channel(&channel);
+                }
+                Err(err) => warn!(
+                    "gateway discord channel lookup failed for {}: {}",
+                    msg.channel_id, err
+                ),
+            }
+        }
 
         info!(
             "discord gateway routing message to employee={} (dm={}, mention={}, reply_to_bot={})",
             self.inner.employee_id, is_direct_message, is_mention, is_reply_to_bot
         );
+        enqueue_discord_inbound(&self.inner, &inbound).await;
+    }
 
-        let external_message_id = inbound.message_id.clone();
-        let envelope = match build_envelope(
-            route,
-            Channel::Discord,
-            external_message_id,
-            &inbound,
-            &inbound.raw_payload,
-        )
-        .await
-        {
-            Ok(envelope) => envelope,
-            Err(err) => {
-                error!("gateway failed to store raw payload: {}", err);
-                return;
-            }
+    async fn reaction_add(
+        &self,
+        _ctx: serenity::all::Context,
+        add_reaction: serenity::all::Reaction,
+    ) {
+        // Only ⏰/❌/✅ on the bot's own messages are thread controls.
+        let inbound = match self.inner.adapter.from_serenity_reaction(&add_reaction) {
+            Ok(message) => message,
+            Err(_) => return,
         };
+        info!(
+            "discord gateway routing {:?} reaction to employee={} message_id={:?}",
+            inbound.metadata.reaction_control, self.inner.employee_id, inbound.message_id
+        );
+        enqueue_discord_inbound(&self.inner, &inbound).await;
+    }
+}
 
-        let envelope_id = envelope.envelope_id;
-        let dedupe_key = envelope.dedupe_key.clone();
-        let queue = self.inner.state.queue.clone();
-        match tokio::task::spawn_blocking(move || queue.enqueue(&envelope)).await {
-            Ok(Ok(result)) => {
-                if result.inserted {
-                    info!("gateway enqueued discord message {}", envelope_id);
-                } else {
-                    info!("gateway duplicate discord message {}", dedupe_key);
-                }
-            }
-            Ok(Err(err)) => {
-                error!("gateway discord enqueue error: {}", err);
-            }
-            Err(err) => {
-                error!("gateway discord enqueue join error: {}", err);
+async fn enqueue_discord_inbound(inner: &DiscordIngressState, inbound: &InboundMessage) {
+    // Use the employee_id from this handler's state (each bot client knows its employee)
+    let route = RouteDecision {
+        tenant_id: inner.tenant_id.clone(),
+        employee_id: inner.employee_id.clone(),
+    };
+
+    let external_message_id = match inbound.metadata.reaction_control.as_deref() {
+        // One control per user, message, and emoji kind.
+        Some(control) => Some(format!(
+            "reaction:{}:{}:{}",
+            inbound.message_id.as_deref().unwrap_or_default(),
+            inbound.sender,
+            control
+        )),
+        None => inbound.message_id.clone(),
+    };
+    let envelope = match build_envelope(
+        route,
+        Channel::Discord,
+        external_message_id,
+        inbound,
+        &inbound.raw_payload,
+    )
+    .await
+    {
+        Ok(envelope) => envelope,
+        Err(err) => {
+            error!("gateway failed to store raw payload: {}", err);
+            return;
+        }
+    };
+
+    let envelope_id = envelope.envelope_id;
+    let dedupe_key = envelope.dedupe_key.clone();
+    let state = inner.state.clone();
+    match tokio::task::spawn_blocking(move || enqueue_deduped(&state, &envelope)).await {
+        Ok(Ok(result)) => {
+            if result.inserted {
+                info!("gateway enqueued discord message {}", envelope_id);
+            } else {
+                info!("gateway duplicate discord message {}", dedupe_key);
             }
         }
+        Ok(Err(err)) => {
+            error!("gateway discord enqueue error: {}", err);
+        }
+        Err(err) => {
+            error!("gateway discord enqueue join error: {}", err);
+        }
     }
 }
 
@@ -325,7 +367,9 @@ async fn run_discord_gateway(
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let intents = serenity::all::GatewayIntents::GUILD_MESSAGES
         | serenity::all::GatewayIntents::DIRECT_MESSAGES
-        | serenity::all::GatewayIntents::MESSAGE_CONTENT;
+        | serenity::all::GatewayIntents::MESSAGE_CONTENT
+        | serenity::all::GatewayIntents::GUILD_MESSAGE_REACTIONS
+        | serenity::all::GatewayIntents::DIRECT_MESSAGE_REACTIONS;
 
     let handler = DiscordIngressHandler {
         inner: Arc::new(state),
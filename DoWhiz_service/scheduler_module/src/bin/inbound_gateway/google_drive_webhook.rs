@@ -23,6 +23,7 @@ use super::state::GatewayState;
 /// - `X-Goog-Resource-ID`: The resource ID being watched
 /// - `X-Goog-Resource-State`: sync, add, remove, update, trash, untrash, change
 /// - `X-Goog-Message-Number`: Message sequence number
+/// - `X-Goog-Channel-Token`: The token we registered the channel with
 ///
 /// On receiving a notification, we trigger an immediate poll for comments
 /// on the affected file, reducing latency from 15s polling to near-real-time.
@@ -54,6 +55,11 @@ pub(super) async fn handle_google_drive_webhook(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
+    let channel_token = headers
+        .get("X-Goog-Channel-Token")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+
     if channel_id.is_empty() || resource_id.is_empty() {
         warn!("Google Drive webhook missing required headers");
         return (
@@ -67,6 +73,7 @@ pub(super) async fn handle_google_drive_webhook(
         resource_id,
         resource_state,
         message_number,
+        channel_token,
     };
 
     debug!(
@@ -1,10 +1,10 @@
 use std::collections::HashSet;
 use std::env;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use scheduler_module::google_auth::GoogleAuthConfig;
-use scheduler_module::google_drive_changes::GoogleDriveChangesConfig;
+use scheduler_module::google_drive_changes::{GoogleDriveChangesConfig, GoogleDriveChangesManager};
 use scheduler_module::google_workspace_poller::{
     GoogleWorkspacePoller, GoogleWorkspacePollerConfig, WorkspaceFileType,
 };
@@ -129,14 +129,36 @@ fn run_workspace_poller(
 
     info!("{} poller thread started", file_type.display_name());
 
-    // Track which files we're monitoring (for push notifications)
+    // Files this thread registered channels for, so channels of files that
+    // are no longer shared get stopped
     let mut monitored_files: HashSet<String> = HashSet::new();
-    // Track files where watch channel registration failed (to avoid spamming retries)
+    // Files where watch channel registration failed, retried after the next full poll
     let mut failed_watch_files: HashSet<String> = HashSet::new();
+    // Note: Google Slides does NOT support files.watch API (returns 403)
+    let push_manager = state
+        .drive_changes_manager
+        .as_ref()
+        .filter(|_| file_type.supports_push_notifications());
+    let mut last_full_poll: Option<Instant> = None;
 
     loop {
-        // Regular polling
-        match poll_workspace_comments(&poller, &state, file_type) {
+        // With push notifications, watched files are only polled every fallback
+        // interval in case a notification was lost; the rest every interval
+        let full_poll = match (push_manager, last_full_poll) {
+            (Some(manager), Some(at)) => at.elapsed() >= manager.fallback_poll_interval(),
+            _ => true,
+        };
+        let polled = match push_manager {
+            Some(manager) if !full_poll => {
+                poll_unwatched_files(&poller, &state, file_type, manager)
+            }
+            _ => {
+                last_full_poll = Some(Instant::now());
+                failed_watch_files.clear();
+                poll_workspace_comments(&poller, &state, file_type)
+            }
+        };
+        match polled {
             Ok(count) => {
                 if count > 0 {
                     info!(
@@ -151,46 +173,50 @@ fn run_workspace_poller(
             }
         }
 
-        // Register watch channels for new files (if push notifications enabled and supported)
-        // Note: Google Slides does NOT support files.watch API (returns 403)
-        if file_type.supports_push_notifications() {
-            if let Some(ref manager) = state.drive_changes_manager {
-                if let Ok(files) = poller.list_files(file_type) {
-                    for file in files {
-                        // Skip files that are already monitored or previously failed
-                        if monitored_files.contains(&file.id)
-                            || failed_watch_files.contains(&file.id)
-                        {
-                            continue;
+        // Register watch channels for new files and stop those of removed ones
+        if let Some(manager) = push_manager {
+            if let Ok(files) = poller.list_files(file_type) {
+                let listed: HashSet<&str> = files.iter().map(|file| file.id.as_str()).collect();
+                monitored_files.retain(|file_id| {
+                    if listed.contains(file_id.as_str()) {
+                        return true;
+                    }
+                    if let Err(e) = manager.stop_watching(file_id) {
+                        warn!("Failed to stop watch channel for {}: {}", file_id, e);
+                    }
+                    false
+                });
+
+                for file in &files {
+                    if manager.is_watching(&file.id) || failed_watch_files.contains(&file.id) {
+                        continue;
+                    }
+                    match manager.watch_file(&file.id) {
+                        Ok(_) => {
+                            info!(
+                                "Registered watch channel for {} file: {} ({})",
+                                file_type.display_name(),
+                                file.name.as_deref().unwrap_or("unknown"),
+                                file.id
+                            );
+                            monitored_files.insert(file.id.clone());
                         }
-                        match manager.watch_file(&file.id) {
-                            Ok(_) => {
-                                info!(
-                                    "Registered watch channel for {} file: {} ({})",
-                                    file_type.display_name(),
-                                    file.name.as_deref().unwrap_or("unknown"),
-                                    file.id
-                                );
-                                monitored_files.insert(file.id.clone());
-                            }
-                            Err(e) => {
-                                // Log once and don't retry until restart
-                                warn!(
-                                    "Failed to register watch for {} file {} ({}): {} - will not retry",
-                                    file_type.display_name(),
-                                    file.name.as_deref().unwrap_or("unknown"),
-                                    file.id,
-                                    e
-                                );
-                                failed_watch_files.insert(file.id.clone());
-                            }
+                        Err(e) => {
+                            warn!(
+                                "Failed to register watch for {} file {} ({}): {} - polling it until the next full poll",
+                                file_type.display_name(),
+                                file.name.as_deref().unwrap_or("unknown"),
+                                file.id,
+                                e
+                            );
+                            failed_watch_files.insert(file.id.clone());
                         }
                     }
+                }
 
-                    // Renew expiring channels
-                    if let Err(e) = manager.renew_expiring_channels() {
-                        warn!("Failed to renew watch channels: {}", e);
-                    }
+                // Renew expiring channels
+                if let Err(e) = manager.renew_expiring_channels() {
+                    warn!("Failed to renew watch channels: {}", e);
                 }
             }
         }
@@ -237,6 +263,24 @@ fn run_workspace_poller(
     }
 }
 
+/// Poll the files that have no watch channel, so their comments are not
+/// left to the fallback poll.
+fn poll_unwatched_files(
+    poller: &GoogleWorkspacePoller,
+    state: &GatewayState,
+    file_type: WorkspaceFileType,
+    manager: &GoogleDriveChangesManager,
+) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let mut tasks_created = 0usize;
+    for file in poller.list_files(file_type)? {
+        if manager.is_watching(&file.id) {
+            continue;
+        }
+        tasks_created += poll_single_file(poller, state, file_type, &file.id)?;
+    }
+    Ok(tasks_created)
+}
+
 /// Poll a single file immediately (triggered by push notification).
 fn poll_single_file(
     poller: &GoogleWorkspacePoller,
@@ -15,25 +15,26 @@ use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 use scheduler_module::adapters::bluebubbles::BlueBubblesInboundAdapter;
+use scheduler_module::adapters::jira::{resolve_jira_account_id, JiraInboundAdapter, JiraWebhook};
 use scheduler_module::adapters::postmark::PostmarkInboundPayload;
 use scheduler_module::adapters::slack::{
-    is_url_verification, SlackChallengeResponse, SlackEventWrapper, SlackInboundAdapter,
+    is_url_verification, parse_interaction_payload, parse_slash_command, parse_slash_command_text,
+    resolve_slack_signing_secret, SlackChallengeResponse, SlackCommandAction,
+    SlackEphemeralResponse, SlackEventWrapper, SlackInboundAdapter, SLASH_COMMAND_HELP,
 };
 use scheduler_module::adapters::telegram::TelegramInboundAdapter;
 use scheduler_module::adapters::wechat::WeChatInboundAdapter;
 use scheduler_module::adapters::whatsapp::WhatsAppInboundAdapter;
 use scheduler_module::channel::{Channel, ChannelMetadata, InboundAdapter, InboundMessage};
 use scheduler_module::ingestion::{IngestionEnvelope, IngestionPayload};
-use scheduler_module::ingestion_queue::IngestionQueue;
+use scheduler_module::ingestion_queue::{EnqueueResult, IngestionQueueError};
 use scheduler_module::raw_payload_store::{self, RawPayloadStoreError};
+use scheduler_module::trace_context::{self, new_trace_id};
 use scheduler_module::user_store::extract_emails;
 
 use super::routes::{build_dedupe_key, normalize_email, normalize_phone_number, resolve_route};
 use super::state::{find_service_address, GatewayState, RouteDecision, RouteKey, RouteTarget};
-use super::verify::{
-    verify_bluebubbles, verify_postmark, verify_slack, verify_twilio, verify_wechat,
-    verify_whatsapp_subscription,
-};
+use super::verify::{verify_slack, verify_wechat, verify_whatsapp_subscription, WebhookVerifier};
 
 /// Request payload for creating a workspace brief document
 #[derive(Debug, Deserialize)]
@@ -71,7 +72,10 @@ pub(super) async fn ingest_postmark(
     headers: HeaderMap,
     body: Bytes,
 ) -> impl IntoResponse {
-    if let Err(reason) = verify_postmark(&headers) {
+    if let Err(reason) = state
+        .webhook_verifiers
+        .verify(Channel::Email, &headers, &body)
+    {
         return (StatusCode::UNAUTHORIZED, Json(json!({"status": reason})));
     }
 
@@ -145,7 +149,7 @@ pub(super) async fn ingest_postmark(
                 );
             }
         };
-    enqueue_envelope(state.queue.clone(), envelope).await
+    enqueue_envelope(state.clone(), envelope).await
 }
 
 pub(super) async fn ingest_slack(
@@ -154,16 +158,16 @@ pub(super) async fn ingest_slack(
     body: Bytes,
 ) -> impl IntoResponse {
     if let Some(verification) = is_url_verification(&body) {
+        // The URL check carries no app id, so it is checked against the global secret.
+        if let Err(reason) = verify_slack(&headers, &body, None) {
+            return (StatusCode::UNAUTHORIZED, Json(json!({"status": reason})));
+        }
         let response = SlackChallengeResponse {
             challenge: verification.challenge,
         };
         return (StatusCode::OK, Json(json!(response)));
     }
 
-    if let Err(reason) = verify_slack(&headers, &body) {
-        return (StatusCode::UNAUTHORIZED, Json(json!({"status": reason})));
-    }
-
     let wrapper: SlackEventWrapper = match serde_json::from_slice(&body) {
         Ok(value) => value,
         Err(_) => return (StatusCode::BAD_REQUEST, Json(json!({"status": "bad_json"}))),
@@ -183,13 +187,28 @@ pub(super) async fn ingest_slack(
         return (StatusCode::OK, Json(json!({"status": "no_route"})));
     };
 
+    let bot_user_id = resolve_slack_bot_user_id_for_employee(&route.employee_id);
+    let mut bot_user_ids = HashSet::new();
+    if let Some(id) = bot_user_id.clone() {
+        bot_user_ids.insert(id);
+    }
+    let adapter = SlackInboundAdapter::new(bot_user_ids)
+        .with_signing_secret(resolve_slack_signing_secret(Some(&route.employee_id)));
+    if let Err(reason) = adapter.verify(&headers, &body) {
+        warn!(
+            "gateway rejected slack event for employee={} api_app_id={}: {}",
+            route.employee_id, api_app_id, reason
+        );
+        return (StatusCode::UNAUTHORIZED, Json(json!({"status": reason})));
+    }
+
     info!(
         "gateway slack routing: api_app_id={} -> employee_id={}",
         api_app_id, route.employee_id
     );
 
-    let bot_user_id = resolve_slack_bot_user_id_for_employee(&route.employee_id);
-    if !should_enqueue_slack_message(&wrapper, bot_user_id.as_deref()) {
+    let is_reaction = is_slack_reaction_event(&wrapper);
+    if !is_reaction && !should_enqueue_slack_message(&wrapper, bot_user_id.as_deref()) {
         info!(
             "gateway ignoring slack event for employee={} api_app_id={} (not dm/app_mention/mention)",
             route.employee_id, api_app_id
@@ -197,12 +216,12 @@ pub(super) async fn ingest_slack(
         return (StatusCode::OK, Json(json!({"status": "ignored"})));
     }
 
-    let mut bot_user_ids = HashSet::new();
-    if let Some(id) = bot_user_id {
-        bot_user_ids.insert(id);
-    }
-    let adapter = SlackInboundAdapter::new(bot_user_ids);
-    let message = match adapter.parse(&body) {
+    let parsed = if is_reaction {
+        adapter.parse_reaction(&body)
+    } else {
+        adapter.parse(&body)
+    };
+    let message = match parsed {
         Ok(message) => message,
         Err(err) => {
             warn!("gateway failed to parse slack payload: {}", err);
@@ -220,7 +239,172 @@ pub(super) async fn ingest_slack(
             );
         }
     };
-    enqueue_envelope(state.queue.clone(), envelope).await
+    enqueue_envelope(state.clone(), envelope).await
+}
+
+/// Handle Slack slash commands (`/dowhiz ...`).
+/// POST /slack/commands
+///
+/// Help is answered inline; everything else is queued for the worker, which
+/// replies ephemerally via the command's response_url instead of starting a
+/// full RunTask.
+pub(super) async fn ingest_slack_command(
+    State(state): State<Arc<GatewayState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let command = match parse_slash_command(&body) {
+        Ok(command) => command,
+        Err(err) => {
+            warn!("gateway failed to parse slack slash command: {}", err);
+            return (StatusCode::BAD_REQUEST, Json(json!({"status": "bad_form"})));
+        }
+    };
+
+    let api_app_id = command.api_app_id.as_deref().unwrap_or("");
+    let route = resolve_slack_route(api_app_id, &state);
+    let employee_id = route.as_ref().map(|route| route.employee_id.as_str());
+    if let Err(reason) = verify_slack(&headers, &body, employee_id) {
+        return (StatusCode::UNAUTHORIZED, Json(json!({"status": reason})));
+    }
+
+    let action = parse_slash_command_text(&command.text);
+    if action == SlackCommandAction::Help {
+        return (
+            StatusCode::OK,
+            Json(json!(SlackEphemeralResponse::new(SLASH_COMMAND_HELP))),
+        );
+    }
+
+    let Some(route) = route else {
+        info!(
+            "gateway no route for slack command api_app_id={}",
+            api_app_id
+        );
+        return (
+            StatusCode::OK,
+            Json(json!(SlackEphemeralResponse::new(
+                "This workspace is not connected to a DoWhiz employee yet."
+            ))),
+        );
+    };
+
+    info!(
+        "gateway slack command {} from user={} -> employee_id={}",
+        command.command, command.user_id, route.employee_id
+    );
+
+    let message = command.to_inbound_message(&body);
+    let envelope = match build_envelope(
+        route,
+        Channel::Slack,
+        command.trigger_id.clone(),
+        &message,
+        &body,
+    )
+    .await
+    {
+        Ok(envelope) => envelope,
+        Err(err) => {
+            error!("gateway failed to store raw payload: {}", err);
+            return (
+                StatusCode::OK,
+                Json(json!(SlackEphemeralResponse::new(
+                    "Sorry, I couldn't take that command right now. Please try again."
+                ))),
+            );
+        }
+    };
+
+    let (status, _) = enqueue_envelope(state.clone(), envelope).await;
+    let ack = if status.is_success() {
+        slash_command_ack(&action)
+    } else {
+        "Sorry, I couldn't take that command right now. Please try again."
+    };
+    (
+        StatusCode::OK,
+        Json(json!(SlackEphemeralResponse::new(ack))),
+    )
+}
+
+/// Handle Slack interactivity (button clicks, modal submissions).
+/// POST /slack/interactions
+///
+/// Only interactions that answer a stored pending action (identified by
+/// callback_id) are queued; the worker claims the action and applies it.
+pub(super) async fn ingest_slack_interaction(
+    State(state): State<Arc<GatewayState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let payload = match parse_interaction_payload(&body) {
+        Ok(payload) => payload,
+        Err(err) => {
+            warn!("gateway failed to parse slack interaction: {}", err);
+            return (StatusCode::BAD_REQUEST, Json(json!({"status": "bad_form"})));
+        }
+    };
+
+    let api_app_id = payload.api_app_id.as_deref().unwrap_or("");
+    let route = resolve_slack_route(api_app_id, &state);
+    let employee_id = route.as_ref().map(|route| route.employee_id.as_str());
+    if let Err(reason) = verify_slack(&headers, &body, employee_id) {
+        return (StatusCode::UNAUTHORIZED, Json(json!({"status": reason})));
+    }
+
+    let message = match payload.to_inbound_message(&body) {
+        Ok(message) => message,
+        Err(err) => {
+            info!(
+                "gateway ignoring slack interaction type={} ({})",
+                payload.interaction_type, err
+            );
+            return (StatusCode::OK, Json(json!({"status": "ignored"})));
+        }
+    };
+
+    let Some(route) = route else {
+        info!(
+            "gateway no route for slack interaction api_app_id={}",
+            api_app_id
+        );
+        return (StatusCode::OK, Json(json!({"status": "no_route"})));
+    };
+
+    info!(
+        "gateway slack interaction type={} callback_id={:?} from user={} -> employee_id={}",
+        payload.interaction_type,
+        payload.callback_id(),
+        payload.user.id,
+        route.employee_id
+    );
+
+    let external_id = message.message_id.clone();
+    let envelope = match build_envelope(route, Channel::Slack, external_id, &message, &body).await {
+        Ok(envelope) => envelope,
+        Err(err) => {
+            error!("gateway failed to store raw payload: {}", err);
+            return (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({"status": "payload_store_failed"})),
+            );
+        }
+    };
+    let (status, response) = enqueue_envelope(state.clone(), envelope).await;
+    if payload.interaction_type == "view_submission" && status.is_success() {
+        // An empty response_action closes the modal.
+        return (StatusCode::OK, Json(json!({"response_action": "clear"})));
+    }
+    (status, response)
+}
+
+fn slash_command_ack(action: &SlackCommandAction) -> &'static str {
+    match action {
+        SlackCommandAction::Status | SlackCommandAction::Tasks => "Checking your tasks…",
+        SlackCommandAction::Remind { .. } => "Scheduling your reminder…",
+        SlackCommandAction::Ask(_) | SlackCommandAction::Help => "On it…",
+    }
 }
 
 fn resolve_slack_bot_user_id_for_employee(employee_id: &str) -> Option<String> {
@@ -318,6 +502,15 @@ fn resolve_slack_route(api_app_id: &str, state: &GatewayState) -> Option<RouteDe
     resolve_route(Channel::Slack, api_app_id, state)
 }
 
+/// Reactions are queued as thread controls (⏰/❌/✅); the adapter filters the emoji.
+fn is_slack_reaction_event(wrapper: &SlackEventWrapper) -> bool {
+    wrapper
+        .event
+        .as_ref()
+        .map(|event| event.event_type == "reaction_added")
+        .unwrap_or(false)
+}
+
 fn should_enqueue_slack_message(wrapper: &SlackEventWrapper, bot_user_id: Option<&str>) -> bool {
     let Some(event) = wrapper.event.as_ref() else {
         return false;
@@ -351,7 +544,10 @@ pub(super) async fn ingest_bluebubbles(
     headers: HeaderMap,
     body: Bytes,
 ) -> impl IntoResponse {
-    if let Err(reason) = verify_bluebubbles(&headers) {
+    if let Err(reason) = state
+        .webhook_verifiers
+        .verify(Channel::BlueBubbles, &headers, &body)
+    {
         return (StatusCode::UNAUTHORIZED, Json(json!({"status": reason})));
     }
 
@@ -394,7 +590,7 @@ pub(super) async fn ingest_bluebubbles(
             );
         }
     };
-    enqueue_envelope(state.queue.clone(), envelope).await
+    enqueue_envelope(state.clone(), envelope).await
 }
 
 pub(super) async fn ingest_sms(
@@ -402,7 +598,10 @@ pub(super) async fn ingest_sms(
     headers: HeaderMap,
     body: Bytes,
 ) -> impl IntoResponse {
-    if let Err(reason) = verify_twilio(&headers, &body) {
+    if let Err(reason) = state
+        .webhook_verifiers
+        .verify(Channel::Sms, &headers, &body)
+    {
         return (StatusCode::UNAUTHORIZED, Json(json!({"status": reason})));
     }
 
@@ -459,13 +658,21 @@ pub(super) async fn ingest_sms(
             );
         }
     };
-    enqueue_envelope(state.queue.clone(), envelope).await
+    enqueue_envelope(state.clone(), envelope).await
 }
 
 pub(super) async fn ingest_telegram(
     State(state): State<Arc<GatewayState>>,
+    headers: HeaderMap,
     body: Bytes,
 ) -> impl IntoResponse {
+    if let Err(reason) = state
+        .webhook_verifiers
+        .verify(Channel::Telegram, &headers, &body)
+    {
+        return (StatusCode::UNAUTHORIZED, Json(json!({"status": reason})));
+    }
+
     let adapter = TelegramInboundAdapter::new();
     let message = match adapter.parse(&body) {
         Ok(message) => message,
@@ -486,7 +693,11 @@ pub(super) async fn ingest_telegram(
         return (StatusCode::OK, Json(json!({"status": "no_route"})));
     };
 
-    let external_message_id = message.message_id.clone();
+    // Telegram message IDs are only unique within a chat.
+    let external_message_id = message
+        .message_id
+        .as_ref()
+        .map(|message_id| format!("{}:{}", chat_id, message_id));
     let envelope = match build_envelope(
         route,
         Channel::Telegram,
@@ -505,7 +716,7 @@ pub(super) async fn ingest_telegram(
             );
         }
     };
-    enqueue_envelope(state.queue.clone(), envelope).await
+    enqueue_envelope(state.clone(), envelope).await
 }
 
 /// Query parameters for WhatsApp webhook verification
@@ -583,7 +794,7 @@ pub(super) async fn ingest_whatsapp(
             );
         }
     };
-    enqueue_envelope(state.queue.clone(), envelope).await
+    enqueue_envelope(state.clone(), envelope).await
 }
 
 /// Query parameters for WeChat webhook verification
@@ -663,14 +874,65 @@ pub(super) async fn ingest_wechat(
                 );
             }
         };
-    enqueue_envelope(state.queue.clone(), envelope).await
+    enqueue_envelope(state.clone(), envelope).await
+}
+
+/// Handle Jira issue and comment webhooks, routed by project key.
+pub(super) async fn ingest_jira(
+    State(state): State<Arc<GatewayState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    if let Err(reason) = state
+        .webhook_verifiers
+        .verify(Channel::Jira, &headers, &body)
+    {
+        return (StatusCode::UNAUTHORIZED, Json(json!({"status": reason})));
+    }
+
+    let project_key = match serde_json::from_slice::<JiraWebhook>(&body) {
+        Ok(webhook) => webhook.project_key().unwrap_or_default(),
+        Err(err) => {
+            debug!("gateway ignoring jira event: {}", err);
+            return (StatusCode::OK, Json(json!({"status": "ignored"})));
+        }
+    };
+
+    let Some(route) = resolve_route(Channel::Jira, &project_key, &state) else {
+        info!("gateway no route for jira project={}", project_key);
+        return (StatusCode::OK, Json(json!({"status": "no_route"})));
+    };
+
+    // Mentions are matched against the routed employee's own Jira account.
+    let adapter = JiraInboundAdapter::new(resolve_jira_account_id(Some(&route.employee_id)));
+    let message = match adapter.parse(&body) {
+        Ok(message) => message,
+        Err(err) => {
+            debug!("gateway ignoring jira event: {}", err);
+            return (StatusCode::OK, Json(json!({"status": "ignored"})));
+        }
+    };
+
+    let external_message_id = message.message_id.clone();
+    let envelope =
+        match build_envelope(route, Channel::Jira, external_message_id, &message, &body).await {
+            Ok(envelope) => envelope,
+            Err(err) => {
+                error!("gateway failed to store raw payload: {}", err);
+                return (
+                    StatusCode::BAD_GATEWAY,
+                    Json(json!({"status": "payload_store_failed"})),
+                );
+            }
+        };
+    enqueue_envelope(state.clone(), envelope).await
 }
 
 pub(super) async fn enqueue_envelope(
-    queue: Arc<dyn IngestionQueue>,
+    state: Arc<GatewayState>,
     envelope: IngestionEnvelope,
 ) -> (StatusCode, Json<serde_json::Value>) {
-    let result = tokio::task::spawn_blocking(move || queue.enqueue(&envelope)).await;
+    let result = tokio::task::spawn_blocking(move || enqueue_deduped(&state, &envelope)).await;
     match result {
         Ok(Ok(result)) => {
             if result.inserted {
@@ -696,6 +958,40 @@ pub(super) async fn enqueue_envelope(
     }
 }
 
+/// Enqueue unless the gateway's replay store has already accepted this delivery.
+/// Blocking; call from `spawn_blocking`.
+pub(super) fn enqueue_deduped(
+    state: &GatewayState,
+    envelope: &IngestionEnvelope,
+) -> Result<EnqueueResult, IngestionQueueError> {
+    let _trace = trace_context::enter(&envelope.trace_id());
+    let dedupe = state.inbound_dedupe.as_deref();
+    if let Some(store) = dedupe {
+        match store.claim(envelope, Utc::now()) {
+            Ok(true) => {}
+            Ok(false) => {
+                info!(
+                    "gateway dropping replayed {} delivery external_id={:?} employee={}",
+                    envelope.channel, envelope.external_message_id, envelope.employee_id
+                );
+                return Ok(EnqueueResult { inserted: false });
+            }
+            // Fall through: the queue's own dedupe key still catches exact repeats.
+            Err(err) => warn!("gateway inbound dedupe check failed: {}", err),
+        }
+    }
+    let result = state.queue.enqueue(envelope);
+    if result.is_err() {
+        if let Some(store) = dedupe {
+            // Let the provider's retry through once the queue recovers.
+            if let Err(err) = store.release(envelope) {
+                warn!("gateway failed to release inbound dedupe claim: {}", err);
+            }
+        }
+    }
+    result
+}
+
 const NO_REPLY_MARKERS: [&str; 5] = [
     "noreply",
     "no-reply",
@@ -982,6 +1278,8 @@ pub(super) async fn build_envelope(
         payload: queue_payload,
         raw_payload_ref,
         account_id: None,
+        trace_id: Some(new_trace_id()),
+        handoff: None,
     })
 }
 
@@ -1135,7 +1433,7 @@ Use the google-docs skill to create and share the document."#,
     envelope.account_id = request.account_id;
 
     let task_id = envelope.envelope_id.to_string();
-    let result = enqueue_envelope(state.queue.clone(), envelope).await;
+    let result = enqueue_envelope(state.clone(), envelope).await;
 
     // Augment response with task_id for potential polling
     match result {
@@ -1299,7 +1597,7 @@ Use the google-docs skill to create and share the document."#,
     envelope.account_id = request.account_id;
 
     let task_id = envelope.envelope_id.to_string();
-    let result = enqueue_envelope(state.queue.clone(), envelope).await;
+    let result = enqueue_envelope(state.clone(), envelope).await;
 
     match result {
         (StatusCode::OK, Json(mut body)) => {
@@ -1418,6 +1716,9 @@ mod tests {
                 files: None,
                 channel_type: Some("channel".to_string()),
                 event_ts: None,
+                reaction: None,
+                item: None,
+                item_user: None,
             }),
             event_id: Some("Ev1".to_string()),
             event_time: None,
@@ -1447,6 +1748,9 @@ mod tests {
                 files: None,
                 channel_type: Some("channel".to_string()),
                 event_ts: None,
+                reaction: None,
+                item: None,
+                item_user: None,
             }),
             event_id: Some("Ev2".to_string()),
             event_time: None,
@@ -1476,6 +1780,9 @@ mod tests {
                 files: None,
                 channel_type: Some("im".to_string()),
                 event_ts: None,
+                reaction: None,
+                item: None,
+                item_user: None,
             }),
             event_id: Some("Ev3".to_string()),
             event_time: None,
@@ -1746,5 +2053,7 @@ pub(super) fn build_envelope_blocking(
         payload: queue_payload,
         raw_payload_ref,
         account_id: None,
+        trace_id: Some(new_trace_id()),
+        handoff: None,
     })
 }
@@ -7,10 +7,12 @@ use scheduler_module::adapters::postmark::PostmarkInboundPayload;
 use scheduler_module::channel::Channel;
 use scheduler_module::employee_config::EmployeeDirectory;
 use scheduler_module::google_drive_changes::GoogleDriveChangesManager;
+use scheduler_module::inbound_dedupe::InboundDedupeStore;
 use scheduler_module::ingestion_queue::IngestionQueue;
 use scheduler_module::mailbox;
 
 use super::config::GatewayDefaultsConfig;
+use super::verify::WebhookVerifiers;
 
 #[derive(Clone)]
 pub(super) struct GatewayConfig {
@@ -24,6 +26,10 @@ pub(super) struct GatewayState {
     pub(super) employee_directory: EmployeeDirectory,
     pub(super) address_to_employee: HashMap<String, String>,
     pub(super) queue: Arc<dyn IngestionQueue>,
+    /// Per-channel inbound webhook authentication
+    pub(super) webhook_verifiers: WebhookVerifiers,
+    /// Replay protection for provider redeliveries (None when Mongo is unavailable)
+    pub(super) inbound_dedupe: Option<Arc<InboundDedupeStore>>,
     /// Google Drive push notification manager (optional, only if enabled)
     pub(super) drive_changes_manager: Option<Arc<GoogleDriveChangesManager>>,
     /// Channel to notify workspace poller of file changes
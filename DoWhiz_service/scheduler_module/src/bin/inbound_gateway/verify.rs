@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::env;
+use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use axum::http::HeaderMap;
@@ -7,104 +8,285 @@ use base64::Engine;
 use hmac::{Hmac, Mac};
 use sha1::Sha1;
 use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+use scheduler_module::adapters::slack::{
+    resolve_slack_signing_secret, SlackInboundAdapter, SlackSignatureError,
+    SLACK_SIGNATURE_MAX_AGE_SECS,
+};
+use scheduler_module::channel::Channel;
+
+/// Verify a Slack request with the signing secret of `employee_id`'s Slack app
+/// (falling back to `SLACK_SIGNING_SECRET`).
+pub(super) fn verify_slack(
+    headers: &HeaderMap,
+    body: &[u8],
+    employee_id: Option<&str>,
+) -> Result<(), &'static str> {
+    let adapter = SlackInboundAdapter::default()
+        .with_signing_secret(resolve_slack_signing_secret(employee_id));
+    adapter.verify(headers, body)
+}
 
-pub(super) fn verify_slack(headers: &HeaderMap, body: &[u8]) -> Result<(), &'static str> {
-    let secret = env::var("SLACK_SIGNING_SECRET").ok();
-    let Some(secret) = secret.filter(|value| !value.trim().is_empty()) else {
+/// Verify a Slack request against `adapter`'s signing secret and reject exact
+/// replays of a signature already accepted inside the replay window.
+fn verify_slack_request(
+    adapter: &SlackInboundAdapter,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<(), &'static str> {
+    if adapter.signing_secret.is_none() {
         return Ok(());
-    };
-    let signature = headers
-        .get("x-slack-signature")
-        .and_then(|value| value.to_str().ok())
-        .ok_or("missing_signature")?;
-    let timestamp = headers
-        .get("x-slack-request-timestamp")
-        .and_then(|value| value.to_str().ok())
-        .ok_or("missing_timestamp")?;
-    let timestamp_value: i64 = timestamp.parse().map_err(|_| "invalid_timestamp")?;
+    }
+    let signature = header_value(headers, "x-slack-signature");
+    let timestamp = header_value(headers, "x-slack-request-timestamp");
+    adapter
+        .verify_request(timestamp, signature, body)
+        .map_err(SlackSignatureError::reason)?;
 
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or(Duration::from_secs(0))
         .as_secs() as i64;
-    if (now - timestamp_value).abs() > 60 * 5 {
-        return Err("stale_timestamp");
+    if !remember_slack_signature(signature.unwrap_or_default(), now) {
+        return Err("replayed_request");
     }
+    Ok(())
+}
 
-    let base = format!("v0:{}:{}", timestamp, String::from_utf8_lossy(body));
-    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).map_err(|_| "bad_secret")?;
-    mac.update(base.as_bytes());
-    let expected = format!("v0={}", hex::encode(mac.finalize().into_bytes()));
-    if expected != signature {
-        return Err("invalid_signature");
+/// Record a verified signature; returns false if it was already seen within the window.
+fn remember_slack_signature(signature: &str, now: i64) -> bool {
+    static SEEN: OnceLock<Mutex<HashMap<String, i64>>> = OnceLock::new();
+    let seen = SEEN.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut seen = seen.lock().unwrap_or_else(|poison| poison.into_inner());
+    seen.retain(|_, seen_at| now - *seen_at <= SLACK_SIGNATURE_MAX_AGE_SECS);
+    seen.insert(signature.to_string(), now).is_none()
+}
+
+/// Authenticates an inbound webhook from its headers and raw body before the
+/// gateway parses it. Errors are short snake_case reasons returned to the caller
+/// as `{"status": reason}` with a 401.
+pub(super) trait WebhookVerifier: Send + Sync {
+    fn verify(&self, headers: &HeaderMap, body: &[u8]) -> Result<(), &'static str>;
+}
+
+/// Per-channel webhook verifiers, built once at startup from env.
+///
+/// A channel without a configured secret has no verifier and is accepted as-is,
+/// which keeps local setups working without credentials.
+#[derive(Default)]
+pub(super) struct WebhookVerifiers {
+    by_channel: HashMap<Channel, Box<dyn WebhookVerifier>>,
+}
+
+impl WebhookVerifiers {
+    pub(super) fn from_env() -> Self {
+        let mut verifiers = Self::default();
+        if let Some(verifier) = PostmarkVerifier::from_env() {
+            verifiers.insert(Channel::Email, verifier);
+        }
+        if let Some(token) = env_secret("BLUEBUBBLES_WEBHOOK_TOKEN") {
+            verifiers.insert(
+                Channel::BlueBubbles,
+                SharedTokenVerifier::new("x-bluebubbles-token", token),
+            );
+        }
+        if let Some(token) = env_secret("TELEGRAM_WEBHOOK_SECRET") {
+            verifiers.insert(
+                Channel::Telegram,
+                SharedTokenVerifier::new("x-telegram-bot-api-secret-token", token),
+            );
+        }
+        if let Some(verifier) = TwilioVerifier::from_env() {
+            verifiers.insert(Channel::Sms, verifier);
+        }
+        if let Some(secret) = env_secret("JIRA_WEBHOOK_SECRET") {
+            verifiers.insert(Channel::Jira, HubSignatureVerifier::new(secret));
+        }
+        verifiers
+    }
+
+    pub(super) fn insert(&mut self, channel: Channel, verifier: impl WebhookVerifier + 'static) {
+        self.by_channel.insert(channel, Box::new(verifier));
+    }
+
+    pub(super) fn verify(
+        &self,
+        channel: Channel,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<(), &'static str> {
+        match self.by_channel.get(&channel) {
+            Some(verifier) => verifier.verify(headers, body),
+            None => Ok(()),
+        }
+    }
+
+    pub(super) fn configured_channels(&self) -> Vec<Channel> {
+        let mut channels: Vec<Channel> = self.by_channel.keys().copied().collect();
+        channels.sort_by_key(|channel| channel.to_string());
+        channels
     }
-    Ok(())
 }
 
-pub(super) fn verify_postmark(headers: &HeaderMap) -> Result<(), &'static str> {
-    let token = env::var("POSTMARK_INBOUND_TOKEN").ok();
-    let Some(token) = token.filter(|value| !value.trim().is_empty()) else {
-        return Ok(());
-    };
-    let header = headers
-        .get("x-postmark-token")
-        .and_then(|value| value.to_str().ok())
-        .ok_or("missing_token")?;
-    if header != token {
-        return Err("invalid_token");
+impl WebhookVerifier for SlackInboundAdapter {
+    fn verify(&self, headers: &HeaderMap, body: &[u8]) -> Result<(), &'static str> {
+        verify_slack_request(self, headers, body)
     }
-    Ok(())
 }
 
-pub(super) fn verify_bluebubbles(headers: &HeaderMap) -> Result<(), &'static str> {
-    let token = env::var("BLUEBUBBLES_WEBHOOK_TOKEN").ok();
-    let Some(token) = token.filter(|value| !value.trim().is_empty()) else {
-        return Ok(());
-    };
-    let header = headers
-        .get("x-bluebubbles-token")
-        .and_then(|value| value.to_str().ok())
-        .ok_or("missing_token")?;
-    if header != token {
-        return Err("invalid_token");
+/// Postmark inbound webhooks: HTTP basic auth embedded in the webhook URL
+/// (`POSTMARK_INBOUND_BASIC_AUTH=user:password`) and/or a shared
+/// `X-Postmark-Token` header (`POSTMARK_INBOUND_TOKEN`). Every configured
+/// credential must match.
+pub(super) struct PostmarkVerifier {
+    basic_auth: Option<String>,
+    token: Option<String>,
+}
+
+impl PostmarkVerifier {
+    fn from_env() -> Option<Self> {
+        let basic_auth = env_secret("POSTMARK_INBOUND_BASIC_AUTH");
+        let token = env_secret("POSTMARK_INBOUND_TOKEN");
+        if basic_auth.is_none() && token.is_none() {
+            return None;
+        }
+        Some(Self { basic_auth, token })
     }
-    Ok(())
 }
 
-pub(super) fn verify_twilio(headers: &HeaderMap, body: &[u8]) -> Result<(), &'static str> {
-    let token = env::var("TWILIO_AUTH_TOKEN").ok();
-    let url = env::var("TWILIO_WEBHOOK_URL").ok();
-    let (Some(token), Some(url)) = (token, url) else {
-        return Ok(());
-    };
-    if token.trim().is_empty() || url.trim().is_empty() {
-        return Ok(());
+impl WebhookVerifier for PostmarkVerifier {
+    fn verify(&self, headers: &HeaderMap, _body: &[u8]) -> Result<(), &'static str> {
+        if let Some(expected) = self.basic_auth.as_deref() {
+            let credentials = header_value(headers, "authorization")
+                .and_then(|value| value.strip_prefix("Basic "))
+                .ok_or("missing_credentials")?;
+            let decoded = base64::engine::general_purpose::STANDARD
+                .decode(credentials.trim())
+                .map_err(|_| "invalid_credentials")?;
+            if !bool::from(decoded.ct_eq(expected.as_bytes())) {
+                return Err("invalid_credentials");
+            }
+        }
+        if let Some(expected) = self.token.as_deref() {
+            let token = header_value(headers, "x-postmark-token").ok_or("missing_token")?;
+            if !bool::from(token.as_bytes().ct_eq(expected.as_bytes())) {
+                return Err("invalid_token");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A static secret echoed back in a request header, as used by BlueBubbles
+/// (`X-BlueBubbles-Token`) and Telegram's `secret_token`
+/// (`X-Telegram-Bot-Api-Secret-Token`).
+pub(super) struct SharedTokenVerifier {
+    header: &'static str,
+    token: String,
+}
+
+impl SharedTokenVerifier {
+    pub(super) fn new(header: &'static str, token: String) -> Self {
+        Self { header, token }
+    }
+}
+
+impl WebhookVerifier for SharedTokenVerifier {
+    fn verify(&self, headers: &HeaderMap, _body: &[u8]) -> Result<(), &'static str> {
+        let provided = header_value(headers, self.header).ok_or("missing_token")?;
+        if !bool::from(provided.as_bytes().ct_eq(self.token.as_bytes())) {
+            return Err("invalid_token");
+        }
+        Ok(())
     }
-    let signature = headers
-        .get("x-twilio-signature")
-        .and_then(|value| value.to_str().ok())
-        .ok_or("missing_signature")?;
-
-    let params: HashMap<String, String> =
-        serde_urlencoded::from_bytes(body).map_err(|_| "bad_form")?;
-    let mut keys: Vec<_> = params.keys().cloned().collect();
-    keys.sort();
-    let mut data = url.clone();
-    for key in keys {
-        if let Some(value) = params.get(&key) {
-            data.push_str(&key);
-            data.push_str(value);
+}
+
+/// Twilio SMS webhooks: base64 HMAC-SHA1 over the public webhook URL followed
+/// by the sorted form parameters, sent as `X-Twilio-Signature`.
+pub(super) struct TwilioVerifier {
+    auth_token: String,
+    webhook_url: String,
+}
+
+impl TwilioVerifier {
+    fn from_env() -> Option<Self> {
+        let auth_token = env_secret("TWILIO_AUTH_TOKEN")?;
+        let Some(webhook_url) = env_secret("TWILIO_WEBHOOK_URL") else {
+            warn!("TWILIO_AUTH_TOKEN is set without TWILIO_WEBHOOK_URL; SMS webhooks are not verified");
+            return None;
+        };
+        Some(Self {
+            auth_token,
+            webhook_url,
+        })
+    }
+}
+
+impl WebhookVerifier for TwilioVerifier {
+    fn verify(&self, headers: &HeaderMap, body: &[u8]) -> Result<(), &'static str> {
+        let signature = header_value(headers, "x-twilio-signature").ok_or("missing_signature")?;
+        let signature = base64::engine::general_purpose::STANDARD
+            .decode(signature.trim())
+            .map_err(|_| "invalid_signature")?;
+
+        let params: HashMap<String, String> =
+            serde_urlencoded::from_bytes(body).map_err(|_| "bad_form")?;
+        let mut keys: Vec<_> = params.keys().cloned().collect();
+        keys.sort();
+        let mut data = self.webhook_url.clone();
+        for key in keys {
+            if let Some(value) = params.get(&key) {
+                data.push_str(&key);
+                data.push_str(value);
+            }
         }
+
+        let mut mac =
+            Hmac::<Sha1>::new_from_slice(self.auth_token.as_bytes()).map_err(|_| "bad_secret")?;
+        mac.update(data.as_bytes());
+        mac.verify_slice(&signature)
+            .map_err(|_| "invalid_signature")
     }
+}
 
-    let mut mac = Hmac::<Sha1>::new_from_slice(token.as_bytes()).map_err(|_| "bad_secret")?;
-    mac.update(data.as_bytes());
-    let expected = base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes());
+/// Jira Cloud webhooks registered with a secret: hex HMAC-SHA256 of the raw
+/// body, sent as `X-Hub-Signature: sha256=<hex>`.
+pub(super) struct HubSignatureVerifier {
+    secret: String,
+}
 
-    if expected != signature {
-        return Err("invalid_signature");
+impl HubSignatureVerifier {
+    pub(super) fn new(secret: String) -> Self {
+        Self { secret }
     }
-    Ok(())
+}
+
+impl WebhookVerifier for HubSignatureVerifier {
+    fn verify(&self, headers: &HeaderMap, body: &[u8]) -> Result<(), &'static str> {
+        let signature = header_value(headers, "x-hub-signature")
+            .and_then(|value| value.strip_prefix("sha256="))
+            .ok_or("missing_signature")?;
+        let signature = hex::decode(signature).map_err(|_| "invalid_signature")?;
+
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(self.secret.as_bytes()).map_err(|_| "bad_secret")?;
+        mac.update(body);
+        mac.verify_slice(&signature)
+            .map_err(|_| "invalid_signature")
+    }
+}
+
+fn env_secret(name: &str) -> Option<String> {
+    env::var(name)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
 }
 
 /// Verify WhatsApp webhook subscription request.
@@ -613,4 +795,125 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "hub.challenge.12345");
     }
+
+    // ==================== Webhook Verifier Tests ====================
+
+    fn headers_with(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn webhook_verifiers_accept_unconfigured_channels() {
+        let verifiers = WebhookVerifiers::default();
+        assert!(verifiers
+            .verify(Channel::Telegram, &HeaderMap::new(), b"{}")
+            .is_ok());
+    }
+
+    #[test]
+    fn shared_token_verifier_checks_header() {
+        let mut verifiers = WebhookVerifiers::default();
+        verifiers.insert(
+            Channel::Telegram,
+            SharedTokenVerifier::new("x-telegram-bot-api-secret-token", "s3cret".to_string()),
+        );
+
+        let missing = verifiers.verify(Channel::Telegram, &HeaderMap::new(), b"{}");
+        assert_eq!(missing, Err("missing_token"));
+        let wrong = headers_with(&[("x-telegram-bot-api-secret-token", "nope")]);
+        assert_eq!(
+            verifiers.verify(Channel::Telegram, &wrong, b"{}"),
+            Err("invalid_token")
+        );
+        let good = headers_with(&[("x-telegram-bot-api-secret-token", "s3cret")]);
+        assert!(verifiers.verify(Channel::Telegram, &good, b"{}").is_ok());
+        // Other channels stay unverified.
+        assert!(verifiers.verify(Channel::Sms, &wrong, b"").is_ok());
+    }
+
+    #[test]
+    fn postmark_verifier_requires_every_configured_credential() {
+        let verifier = PostmarkVerifier {
+            basic_auth: Some("postmark:hunter2".to_string()),
+            token: Some("tok".to_string()),
+        };
+        let auth = format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode("postmark:hunter2")
+        );
+
+        assert_eq!(
+            verifier.verify(&headers_with(&[("x-postmark-token", "tok")]), b"{}"),
+            Err("missing_credentials")
+        );
+        assert_eq!(
+            verifier.verify(&headers_with(&[("authorization", auth.as_str())]), b"{}"),
+            Err("missing_token")
+        );
+        let bad_auth = format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode("postmark:wrong")
+        );
+        let bad = headers_with(&[("authorization", &bad_auth), ("x-postmark-token", "tok")]);
+        assert_eq!(verifier.verify(&bad, b"{}"), Err("invalid_credentials"));
+        let good = headers_with(&[("authorization", &auth), ("x-postmark-token", "tok")]);
+        assert!(verifier.verify(&good, b"{}").is_ok());
+    }
+
+    #[test]
+    fn twilio_verifier_signs_url_and_sorted_params() {
+        let verifier = TwilioVerifier {
+            auth_token: "token".to_string(),
+            webhook_url: "https://example.com/sms/twilio".to_string(),
+        };
+        let mut mac = Hmac::<Sha1>::new_from_slice(b"token").unwrap();
+        mac.update(b"https://example.com/sms/twilio" as &[u8]);
+        mac.update(b"BodyhiFrom+1666To+1555");
+        let signature =
+            base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes());
+        let body = b"To=%2B1555&From=%2B1666&Body=hi";
+
+        assert!(verifier
+            .verify(
+                &headers_with(&[("x-twilio-signature", signature.as_str())]),
+                body
+            )
+            .is_ok());
+        assert_eq!(
+            verifier.verify(&headers_with(&[("x-twilio-signature", "bogus")]), body),
+            Err("invalid_signature")
+        );
+        assert_eq!(
+            verifier.verify(&HeaderMap::new(), body),
+            Err("missing_signature")
+        );
+    }
+
+    #[test]
+    fn hub_signature_verifier_checks_body_hmac() {
+        let verifier = HubSignatureVerifier::new("jira-secret".to_string());
+        let body = br#"{"webhookEvent":"comment_created"}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"jira-secret").unwrap();
+        mac.update(body);
+        let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+
+        assert!(verifier
+            .verify(&headers_with(&[("x-hub-signature", signature.as_str())]), body)
+            .is_ok());
+        assert_eq!(
+            verifier.verify(
+                &headers_with(&[("x-hub-signature", signature.as_str())]),
+                b"{}"
+            ),
+            Err("invalid_signature")
+        );
+        assert_eq!(
+            verifier.verify(&HeaderMap::new(), body),
+            Err("missing_signature")
+        );
+    }
 }
@@ -0,0 +1,195 @@
+//! Past-email search CLI for the digital employee.
+//!
+//! Searches the workspace's `references/past_emails/search_index.json`,
+//! which the service builds from the user's mail archive, and prints single
+//! messages from it.
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use scheduler_module::past_email_index::{SearchIndex, SearchQuery, SEARCH_INDEX_FILE};
+use std::env;
+use std::path::PathBuf;
+use std::process::exit;
+
+fn print_usage() {
+    eprintln!(
+        r##"Usage: past-emails <command> [arguments]
+
+Commands:
+  search <query> [--limit=10] [--since=YYYY-MM-DD] [--from=TEXT]
+                        Rank past emails against the query (newest first without a query)
+    --from      Only emails whose From/To/Cc contains TEXT
+  show <id>             Print one email (id or message id from search results)
+
+Options:
+  --index=PATH          Index file (default: references/past_emails/search_index.json)
+  --json                Print JSON instead of text
+
+Examples:
+  past-emails search "invoice march"
+  past-emails search "flight" --since=2026-01-01 --from=alice@example.com
+  past-emails show 2026/02/msg-1
+"##
+    );
+}
+
+fn parse_arg(args: &[String], flag: &str) -> Option<String> {
+    let prefix = format!("{}=", flag);
+    args.iter()
+        .find_map(|arg| arg.strip_prefix(&prefix).map(str::to_string))
+}
+
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|a| a == flag)
+}
+
+fn positional(args: &[String]) -> Vec<String> {
+    args.iter()
+        .skip(1)
+        .filter(|arg| !arg.starts_with("--"))
+        .cloned()
+        .collect()
+}
+
+fn load_index(args: &[String]) -> SearchIndex {
+    let path = parse_arg(args, "--index")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            PathBuf::from("references")
+                .join("past_emails")
+                .join(SEARCH_INDEX_FILE)
+        });
+    match SearchIndex::load(&path) {
+        Ok(index) => index,
+        Err(err) => {
+            eprintln!("Error: failed to read {}: {}", path.display(), err);
+            exit(1);
+        }
+    }
+}
+
+fn cmd_search(args: &[String]) {
+    let limit = match parse_arg(args, "--limit").map(|raw| raw.parse::<usize>()) {
+        None => 10,
+        Some(Ok(limit)) => limit,
+        Some(Err(_)) => {
+            eprintln!("Error: --limit must be a number");
+            exit(1);
+        }
+    };
+    let since = match parse_arg(args, "--since") {
+        None => None,
+        Some(raw) => match NaiveDate::parse_from_str(&raw, "%Y-%m-%d") {
+            Ok(date) => date
+                .and_hms_opt(0, 0, 0)
+                .map(|start| Utc.from_utc_datetime(&start)),
+            Err(_) => {
+                eprintln!("Error: --since must be YYYY-MM-DD");
+                exit(1);
+            }
+        },
+    };
+    let index = load_index(args);
+    let query = SearchQuery {
+        text: positional(args).join(" "),
+        limit,
+        since,
+        participant: parse_arg(args, "--from"),
+    };
+    let hits = index.search(&query);
+
+    if has_flag(args, "--json") {
+        let results: Vec<_> = hits
+            .iter()
+            .map(|hit| {
+                serde_json::json!({
+                    "id": hit.email.id,
+                    "message_id": hit.email.message_id,
+                    "date": hit.email.date,
+                    "direction": hit.email.direction,
+                    "from": hit.email.from,
+                    "to": hit.email.to,
+                    "subject": hit.email.subject,
+                    "score": hit.score,
+                    "snippet": hit.snippet,
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&results).unwrap_or_default()
+        );
+        return;
+    }
+    if hits.is_empty() {
+        println!("No matching emails ({} indexed).", index.emails.len());
+        return;
+    }
+    for hit in hits {
+        let email = hit.email;
+        println!(
+            "[{}] {} {} | {} | {}",
+            email.id,
+            email.date.as_deref().unwrap_or("unknown date"),
+            email.direction,
+            if email.direction == "outbound" {
+                format!("to {}", email.to)
+            } else {
+                format!("from {}", email.from)
+            },
+            email.subject
+        );
+        if !hit.snippet.is_empty() {
+            println!("    {}", hit.snippet);
+        }
+    }
+}
+
+fn cmd_show(args: &[String]) {
+    let Some(id) = positional(args).into_iter().next() else {
+        eprintln!("Error: email id required");
+        print_usage();
+        exit(1);
+    };
+    let index = load_index(args);
+    let Some(email) = index.get(&id) else {
+        eprintln!("Error: no email with id {}", id);
+        exit(1);
+    };
+    if has_flag(args, "--json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(email).unwrap_or_default()
+        );
+        return;
+    }
+    println!("Id: {}", email.id);
+    println!("Message-ID: {}", email.message_id);
+    println!("Date: {}", email.date.as_deref().unwrap_or("unknown"));
+    println!("Direction: {}", email.direction);
+    println!("From: {}", email.from);
+    println!("To: {}", email.to);
+    if !email.cc.is_empty() {
+        println!("Cc: {}", email.cc);
+    }
+    println!("Subject: {}", email.subject);
+    if !email.attachments.is_empty() {
+        println!(
+            "Attachments (not copied to the workspace): {}",
+            email.attachments.join(", ")
+        );
+    }
+    println!("\n{}", email.body);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("search") => cmd_search(&args),
+        Some("show") => cmd_show(&args),
+        Some("--help") | Some("-h") => print_usage(),
+        _ => {
+            print_usage();
+            exit(1);
+        }
+    }
+}
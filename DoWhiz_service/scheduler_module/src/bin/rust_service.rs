@@ -50,7 +50,7 @@ fn help_text() -> String {
 
 #[tokio::main]
 async fn main() -> Result<(), BoxError> {
-    tracing_subscriber::fmt().with_target(false).init();
+    let _telemetry = scheduler_module::telemetry::init("rust_service");
 
     let (host_override, port_override) = match parse_args() {
         Ok(values) => values,
@@ -67,6 +67,7 @@ async fn main() -> Result<(), BoxError> {
     if let Some(port) = port_override {
         config.port = port;
     }
+    scheduler_module::telemetry::set_log_employee_id(&config.employee_id);
 
     let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
     tokio::spawn(async move {
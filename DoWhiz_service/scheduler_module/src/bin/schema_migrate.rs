@@ -0,0 +1,242 @@
+use scheduler_module::ingestion_queue;
+use scheduler_module::migrations::{self, SchemaStatus, Step};
+use scheduler_module::mongo_store;
+use std::env;
+use std::path::PathBuf;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Store {
+    Mongo,
+    Ingestion,
+    Threads,
+}
+
+enum Command {
+    Status,
+    Up {
+        store: Option<Store>,
+        to: Option<u32>,
+    },
+    Down {
+        store: Store,
+        to: u32,
+        yes: bool,
+    },
+}
+
+fn parse_args() -> Result<Command, String> {
+    let mut args = env::args().skip(1);
+    let command = args.next().ok_or_else(help_text)?;
+    let mut store = None;
+    let mut to = None;
+    let mut yes = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--store" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --store".to_string())?;
+                store = Some(match value.as_str() {
+                    "mongo" => Store::Mongo,
+                    "ingestion" => Store::Ingestion,
+                    "threads" => Store::Threads,
+                    other => return Err(format!("unknown store: {}", other)),
+                });
+            }
+            "--to" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --to".to_string())?;
+                to = Some(
+                    value
+                        .parse::<u32>()
+                        .map_err(|_| format!("invalid version: {}", value))?,
+                );
+            }
+            "--yes" => yes = true,
+            "--help" | "-h" => return Err(help_text()),
+            _ => return Err(format!("unknown argument: {}", arg)),
+        }
+    }
+
+    match command.as_str() {
+        "status" => Ok(Command::Status),
+        "up" => Ok(Command::Up { store, to }),
+        "down" => Ok(Command::Down {
+            store: store.ok_or_else(|| "down needs --store mongo|ingestion|threads".to_string())?,
+            to: to.ok_or_else(|| "down needs --to VERSION".to_string())?,
+            yes,
+        }),
+        "--help" | "-h" => Err(help_text()),
+        other => Err(format!("unknown command: {}", other)),
+    }
+}
+
+fn help_text() -> String {
+    [
+        "Show, apply and roll back schema migrations",
+        "",
+        "Usage:",
+        "  cargo run -p scheduler_module --bin schema_migrate -- <command>",
+        "",
+        "Commands:",
+        "  status                              Applied and latest version of each store.",
+        "  up [--store mongo|ingestion|threads] [--to N]",
+        "                                      Apply migrations up to N (default latest).",
+        "  down --store mongo|ingestion|threads --to N [--yes]",
+        "                                      Roll back migrations above N.",
+        "",
+        "Stores: mongo (MONGODB_URI) and ingestion (INGESTION_DB_URL/SUPABASE_DB_URL/",
+        "DATABASE_URL, table INGESTION_QUEUE_TABLE) and threads (every",
+        "USERS_ROOT/<id>/state/threads.db). Run down with the build that",
+        "applied the migrations, before starting the older build.",
+    ]
+    .join("\n")
+}
+
+fn print_status(status: &SchemaStatus) {
+    println!(
+        "{}: version {} (latest {}, {} pending), applied {:?}",
+        status.store,
+        status.current(),
+        status.latest,
+        status.pending(),
+        status.applied
+    );
+}
+
+fn print_steps(store: &str, steps: &[Step]) {
+    if steps.is_empty() {
+        println!("{}: nothing to do", store);
+    }
+    for step in steps {
+        match step {
+            Step::Up(version) => println!("{}: applied {}", store, version),
+            Step::Down(version) => println!("{}: rolled back {}", store, version),
+        }
+    }
+}
+
+/// Runs `migrate` on every thread database under `USERS_ROOT`.
+fn each_thread_db(
+    mut migrate: impl FnMut(&mut rusqlite::Connection, &std::path::Path) -> Result<(), BoxError>,
+) -> Result<(), BoxError> {
+    let users_root = env::var("USERS_ROOT")
+        .map(PathBuf::from)
+        .map_err(|_| "the threads store needs USERS_ROOT")?;
+    for (db_path, workspaces_root) in migrations::thread_databases(&users_root)? {
+        println!("{}", db_path.display());
+        let mut conn = rusqlite::Connection::open(&db_path)?;
+        conn.busy_timeout(std::time::Duration::from_secs(10))?;
+        migrate(&mut conn, &workspaces_root)?;
+    }
+    Ok(())
+}
+
+fn ingestion_configured() -> bool {
+    ["INGESTION_DB_URL", "SUPABASE_DB_URL", "DATABASE_URL"]
+        .iter()
+        .any(|key| env::var(key).is_ok_and(|value| !value.trim().is_empty()))
+}
+
+fn main() -> Result<(), BoxError> {
+    dotenvy::dotenv().ok();
+    let command = match parse_args() {
+        Ok(command) => command,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return Ok(());
+        }
+    };
+
+    match command {
+        Command::Status => {
+            let client = mongo_store::create_client_from_env()?;
+            let db = mongo_store::database_from_env(&client);
+            print_status(&migrations::mongo_schema_status(&db)?);
+            if ingestion_configured() {
+                print_status(&ingestion_queue::ingestion_schema_status_from_env()?);
+            }
+            if env::var("USERS_ROOT").is_ok() {
+                each_thread_db(|conn, _| {
+                    print_status(&migrations::thread_db_schema_status(conn)?);
+                    Ok(())
+                })?;
+            }
+        }
+        Command::Up { store, to } => {
+            if store.is_none() || store == Some(Store::Mongo) {
+                let client = mongo_store::create_client_from_env()?;
+                let db = mongo_store::database_from_env(&client);
+                let target = to.unwrap_or_else(migrations::latest_mongo_version);
+                print_steps("mongo", &migrations::migrate_mongo(&db, target)?);
+            }
+            if store == Some(Store::Ingestion) || (store.is_none() && ingestion_configured()) {
+                let target = match to {
+                    Some(target) => target,
+                    None => ingestion_queue::ingestion_schema_status_from_env()?.latest,
+                };
+                print_steps(
+                    "ingestion",
+                    &ingestion_queue::migrate_ingestion_schema_from_env(target)?,
+                );
+            }
+            if store == Some(Store::Threads) || (store.is_none() && env::var("USERS_ROOT").is_ok())
+            {
+                let target = to.unwrap_or_else(migrations::latest_thread_db_version);
+                each_thread_db(|conn, workspaces_root| {
+                    let steps = migrations::migrate_thread_db(conn, workspaces_root, target)?;
+                    print_steps("threads", &steps);
+                    Ok(())
+                })?;
+            }
+        }
+        Command::Down {
+            store: Store::Threads,
+            to,
+            yes,
+        } => {
+            each_thread_db(|conn, workspaces_root| {
+                print_status(&migrations::thread_db_schema_status(conn)?);
+                if yes {
+                    let steps = migrations::migrate_thread_db(conn, workspaces_root, to)?;
+                    print_steps("threads", &steps);
+                }
+                Ok(())
+            })?;
+            if !yes {
+                println!("rerun with --yes to roll threads back to version {}", to);
+            }
+        }
+        Command::Down { store, to, yes } => {
+            let status = match store {
+                Store::Mongo => {
+                    let client = mongo_store::create_client_from_env()?;
+                    migrations::mongo_schema_status(&mongo_store::database_from_env(&client))?
+                }
+                Store::Ingestion => ingestion_queue::ingestion_schema_status_from_env()?,
+                Store::Threads => unreachable!("thread databases are rolled back above"),
+            };
+            print_status(&status);
+            if !yes {
+                println!(
+                    "rerun with --yes to roll {} back to version {}",
+                    status.store, to
+                );
+                return Ok(());
+            }
+            let steps = match store {
+                Store::Mongo => {
+                    let client = mongo_store::create_client_from_env()?;
+                    migrations::migrate_mongo(&mongo_store::database_from_env(&client), to)?
+                }
+                Store::Ingestion => ingestion_queue::migrate_ingestion_schema_from_env(to)?,
+                Store::Threads => unreachable!("thread databases are rolled back above"),
+            };
+            print_steps(status.store, &steps);
+        }
+    }
+    Ok(())
+}
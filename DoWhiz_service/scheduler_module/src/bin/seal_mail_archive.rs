@@ -0,0 +1,83 @@
+use scheduler_module::archive_crypto::{ArchiveKey, MAIL_ARCHIVE_MASTER_KEY_ENV};
+use std::env;
+use std::path::PathBuf;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+struct Args {
+    archive_roots: Vec<PathBuf>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut archive_roots = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--archive-root" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --archive-root".to_string())?;
+                archive_roots.push(PathBuf::from(value));
+            }
+            "--users-root" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --users-root".to_string())?;
+                let entries = std::fs::read_dir(&value)
+                    .map_err(|err| format!("failed to read {}: {}", value, err))?;
+                for entry in entries.flatten() {
+                    let mail_root = entry.path().join("mail");
+                    if mail_root.is_dir() {
+                        archive_roots.push(mail_root);
+                    }
+                }
+            }
+            "--help" | "-h" => {
+                return Err(help_text());
+            }
+            _ => {
+                return Err(format!("unknown argument: {}", arg));
+            }
+        }
+    }
+
+    if archive_roots.is_empty() {
+        return Err("missing --archive-root or --users-root".to_string());
+    }
+    Ok(Args { archive_roots })
+}
+
+fn help_text() -> String {
+    [
+        "Encrypt plaintext messages already in user mail archives",
+        "",
+        "Usage:",
+        "  MAIL_ARCHIVE_MASTER_KEY=<64 hex chars> \\",
+        "  cargo run -p scheduler_module --bin seal_mail_archive -- \\",
+        "    --users-root /path/to/users",
+        "",
+        "Options:",
+        "  --archive-root  One user mail archive root (repeatable).",
+        "  --users-root    Seal <users-root>/<id>/mail for every user.",
+    ]
+    .join("\n")
+}
+
+fn main() -> Result<(), BoxError> {
+    let args = match parse_args() {
+        Ok(values) => values,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return Ok(());
+        }
+    };
+
+    for archive_root in &args.archive_roots {
+        let key = ArchiveKey::for_writing(archive_root)?
+            .ok_or_else(|| format!("{} is not set", MAIL_ARCHIVE_MASTER_KEY_ENV))?;
+        let sealed = key.seal_dir(archive_root)?;
+        println!("Sealed {} files in {}", sealed, archive_root.display());
+    }
+    Ok(())
+}
@@ -3,6 +3,7 @@ use scheduler_module::channel::{Channel, ChannelMetadata};
 use scheduler_module::ingestion::{IngestionEnvelope, IngestionPayload};
 use scheduler_module::ingestion_queue::build_queue_from_env;
 use scheduler_module::service_bus_queue::resolve_service_bus_config_from_env;
+use scheduler_module::trace_context::new_trace_id;
 use std::env;
 use uuid::Uuid;
 
@@ -83,6 +84,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             },
             raw_payload_ref: None,
             account_id: None,
+            trace_id: Some(new_trace_id()),
+            handoff: None,
         };
 
         queue.enqueue(&envelope)?;
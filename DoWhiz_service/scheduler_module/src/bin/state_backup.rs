@@ -0,0 +1,179 @@
+use chrono::Utc;
+use scheduler_module::backup::{self, BACKUP_STORE_ENV_PREFIX};
+use scheduler_module::mongo_store;
+use scheduler_module::object_store::object_store_from_env;
+use std::env;
+use std::path::PathBuf;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+enum Command {
+    Create {
+        out: PathBuf,
+        upload: bool,
+    },
+    Verify {
+        file: PathBuf,
+    },
+    Restore {
+        source: Source,
+        out: PathBuf,
+        yes: bool,
+    },
+}
+
+enum Source {
+    File(PathBuf),
+    Store(String),
+}
+
+fn parse_args() -> Result<Command, String> {
+    let mut args = env::args().skip(1);
+    let command = args.next().ok_or_else(help_text)?;
+    let mut out = PathBuf::from("backups");
+    let mut upload = false;
+    let mut from_store = None;
+    let mut yes = false;
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --out".to_string())?;
+                out = PathBuf::from(value);
+            }
+            "--upload" => upload = true,
+            "--from-store" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --from-store".to_string())?;
+                from_store = Some(value);
+            }
+            "--yes" => yes = true,
+            "--help" | "-h" => return Err(help_text()),
+            _ if arg.starts_with("--") => return Err(format!("unknown argument: {}", arg)),
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    match command.as_str() {
+        "create" => Ok(Command::Create { out, upload }),
+        "verify" => positional
+            .pop()
+            .map(|file| Command::Verify { file })
+            .ok_or_else(|| "missing backup file".to_string()),
+        "restore" => {
+            let source = match (from_store, positional.pop()) {
+                (Some(key), None) => Source::Store(key),
+                (None, Some(file)) => Source::File(file),
+                _ => return Err("restore takes a backup file or --from-store KEY".to_string()),
+            };
+            Ok(Command::Restore { source, out, yes })
+        }
+        "--help" | "-h" => Err(help_text()),
+        other => Err(format!("unknown command: {}", other)),
+    }
+}
+
+fn help_text() -> String {
+    [
+        "Back up, verify and restore the service state database",
+        "",
+        "Usage:",
+        "  MONGODB_URI=... cargo run -p scheduler_module --bin state_backup -- <command>",
+        "",
+        "Commands:",
+        "  create [--out DIR] [--upload]  Write a backup to DIR (default ./backups);",
+        "                                 --upload also puts it in the BACKUP_* store.",
+        "  verify <file>                  Check a backup is complete.",
+        "  restore <file> --yes           Replace the database with a backup.",
+        "  restore --from-store KEY --yes",
+        "                                 Download a backup from the BACKUP_* store",
+        "                                 into --out, then restore it.",
+        "",
+        "Stop the workers before restoring; writes made during a restore are lost.",
+    ]
+    .join("\n")
+}
+
+fn main() -> Result<(), BoxError> {
+    dotenvy::dotenv().ok();
+    let command = match parse_args() {
+        Ok(command) => command,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return Ok(());
+        }
+    };
+
+    let database = mongo_store::mongo_database_name_from_env();
+    match command {
+        Command::Create { out, upload } => {
+            let client = mongo_store::create_client_from_env()?;
+            let backup = backup::create_backup(&client, &database, &out, Utc::now())?;
+            println!(
+                "Backed up {} documents in {} collections to {}{}",
+                backup.manifest.documents(),
+                backup.manifest.collections.len(),
+                backup.path.display(),
+                if backup.manifest.consistent {
+                    ""
+                } else {
+                    " (not from a snapshot)"
+                }
+            );
+            if upload {
+                let store = object_store_from_env(BACKUP_STORE_ENV_PREFIX)?
+                    .ok_or("BACKUP_BACKEND is not set")?;
+                let key = backup::upload_backup(store.as_ref(), &backup)?;
+                println!("Uploaded to {} as {}", store.name(), key);
+            }
+        }
+        Command::Verify { file } => {
+            let manifest = backup::verify_backup(&file)?;
+            println!(
+                "{} is complete: {} documents in {} collections from {} taken {}",
+                file.display(),
+                manifest.documents(),
+                manifest.collections.len(),
+                manifest.database,
+                manifest.created_at.to_rfc3339()
+            );
+        }
+        Command::Restore { source, out, yes } => {
+            let file = match source {
+                Source::File(file) => file,
+                Source::Store(key) => {
+                    let store = object_store_from_env(BACKUP_STORE_ENV_PREFIX)?
+                        .ok_or("BACKUP_BACKEND is not set")?;
+                    backup::download_backup(store.as_ref(), &key, &out)?
+                }
+            };
+            let manifest = backup::verify_backup(&file)?;
+            if !yes {
+                println!(
+                    "{} holds {} documents from {}; rerun with --yes to replace {}",
+                    file.display(),
+                    manifest.documents(),
+                    manifest.database,
+                    database
+                );
+                return Ok(());
+            }
+            let client = mongo_store::create_client_from_env()?;
+            let report = backup::restore_backup(&client, &database, &file, Utc::now())?;
+            println!(
+                "Restored {} documents in {} collections into {}",
+                report.documents, report.collections, database
+            );
+            if !report.untouched.is_empty() {
+                println!(
+                    "Not in the backup, left as is: {}",
+                    report.untouched.join(", ")
+                );
+            }
+        }
+    }
+    Ok(())
+}
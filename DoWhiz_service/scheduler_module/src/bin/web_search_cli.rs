@@ -0,0 +1,133 @@
+//! Web search CLI for the digital employee.
+//!
+//! Searches through the service's `/tools/search` endpoint when the
+//! workspace has a `.dowhiz_tools.json` (or `DOWHIZ_TOOLS_URL` is set), and
+//! with local provider keys otherwise. Results are saved as markdown under
+//! `references/web_search/` so they can be cited in the reply.
+
+use scheduler_module::tools::search::{self, Freshness, SearchRequest, ToolsConfig};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+fn print_usage() {
+    eprintln!(
+        r##"Usage: web-search <query> [options]
+
+Options:
+  --count=N                     Number of results, 1-20 (default 10)
+  --freshness=day|week|month|year  Only results from this period
+  --out=DIR                     References directory (default: references)
+  --json                        Print the results as JSON instead of a summary
+
+Examples:
+  web-search "what changed in kubernetes this week" --freshness=week
+  web-search "postgres 18 release notes" --count=5
+
+Environment Variables:
+  DOWHIZ_TOOLS_URL       - Service URL to search through (overrides .dowhiz_tools.json)
+  TOOLS_API_TOKEN        - Bearer token for the service's /tools/search endpoint
+  WEB_SEARCH_PROVIDER    - brave or tavily, when searching without the service
+  BRAVE_SEARCH_API_KEY   - Brave Search API key
+  TAVILY_API_KEY         - Tavily API key
+"##
+    );
+}
+
+fn parse_arg(args: &[String], flag: &str) -> Option<String> {
+    let prefix = format!("{}=", flag);
+    args.iter()
+        .find_map(|arg| arg.strip_prefix(&prefix).map(str::to_string))
+}
+
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|a| a == flag)
+}
+
+fn main() {
+    dotenvy::dotenv().ok();
+    let args: Vec<String> = env::args().skip(1).collect();
+    if has_flag(&args, "--help") || has_flag(&args, "-h") {
+        print_usage();
+        exit(0);
+    }
+
+    let query = args
+        .iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if query.trim().is_empty() {
+        eprintln!("Error: search query required");
+        print_usage();
+        exit(1);
+    }
+    let count = match parse_arg(&args, "--count").map(|raw| raw.parse::<u32>()) {
+        None => 10,
+        Some(Ok(count)) => count,
+        Some(Err(_)) => {
+            eprintln!("Error: --count must be a number");
+            exit(1);
+        }
+    };
+    let freshness = match parse_arg(&args, "--freshness") {
+        None => None,
+        Some(raw) => match Freshness::parse(&raw) {
+            Some(freshness) => Some(freshness),
+            None => {
+                eprintln!("Error: --freshness must be day, week, month or year");
+                exit(1);
+            }
+        },
+    };
+    let references_dir =
+        PathBuf::from(parse_arg(&args, "--out").unwrap_or_else(|| "references".to_string()));
+    let request = SearchRequest {
+        query,
+        count,
+        freshness,
+    };
+
+    let remote = ToolsConfig::from_env().or_else(|| ToolsConfig::load(Path::new(".")));
+    let result = match &remote {
+        Some(config) => {
+            eprintln!("[web-search] Searching via {}", config.search_url);
+            config.search(&request)
+        }
+        None => search::search(&request),
+    };
+    let response = match result {
+        Ok(response) => response,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            exit(1);
+        }
+    };
+
+    let path = match search::write_results(&references_dir, &request, &response, chrono::Utc::now())
+    {
+        Ok(path) => path,
+        Err(err) => {
+            eprintln!("Error: failed to save results: {}", err);
+            exit(1);
+        }
+    };
+
+    if has_flag(&args, "--json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&response).unwrap_or_default()
+        );
+    } else {
+        println!(
+            "{} results via {} saved to {}",
+            response.results.len(),
+            response.provider,
+            path.display()
+        );
+        for (index, result) in response.results.iter().enumerate() {
+            println!("{}. {}\n   {}", index + 1, result.title, result.url);
+        }
+    }
+}
@@ -34,6 +34,10 @@ pub enum Channel {
     Notion,
     /// WeChat Work (企业微信) via qyapi
     WeChat,
+    /// Jira Cloud issues and comments
+    Jira,
+    /// Employee-to-employee requests and results over the ingestion queue
+    Internal,
 }
 
 impl Default for Channel {
@@ -57,6 +61,8 @@ impl std::fmt::Display for Channel {
             Channel::BlueBubbles => write!(f, "bluebubbles"),
 Channel::Notion => write!(f, "notion"),
             Channel::WeChat => write!(f, "wechat"),
+            Channel::Jira => write!(f, "jira"),
+            Channel::Internal => write!(f, "internal"),
         }
     }
 }
@@ -78,6 +84,8 @@ impl std::str::FromStr for Channel {
             "bluebubbles" | "imessage" => Ok(Channel::BlueBubbles),
 "notion" => Ok(Channel::Notion),
             "wechat" | "weixin" => Ok(Channel::WeChat),
+            "jira" => Ok(Channel::Jira),
+            "internal" => Ok(Channel::Internal),
             _ => Err(format!("unknown channel: {}", s)),
         }
     }
@@ -141,6 +149,12 @@ pub struct ChannelMetadata {
     pub slack_channel_id: Option<String>,
     /// Slack-specific: Team ID
     pub slack_team_id: Option<String>,
+    /// Slack-specific: Slash command name (e.g. "/dowhiz") when the message came from a slash command
+    pub slack_command: Option<String>,
+    /// Slack-specific: response_url used for ephemeral slash-command replies
+    pub slack_response_url: Option<String>,
+    /// Slack-specific: callback_id of the pending action a button click/modal answers
+    pub slack_callback_id: Option<String>,
     /// Discord-specific: Guild (server) ID
     pub discord_guild_id: Option<u64>,
     /// Discord-specific: Channel ID
@@ -149,6 +163,13 @@ pub struct ChannelMetadata {
     pub discord_message_id: Option<String>,
     /// Discord-specific: Quoted/referenced message ID (if this message is a reply)
     pub discord_referenced_message_id: Option<String>,
+    /// Discord-specific: Thread channel ID when the message was posted inside a thread
+    pub discord_thread_id: Option<u64>,
+    /// Discord-specific: outbound nonce; Discord drops a repeat send carrying the same one
+    pub discord_nonce: Option<String>,
+    /// Slack/Discord: control reaction (`snooze`, `cancel`, `approve`) added to the employee
+    /// message identified by `message_id`
+    pub reaction_control: Option<String>,
     /// Telegram-specific: Chat ID
     pub telegram_chat_id: Option<i64>,
     /// WhatsApp-specific: Phone number (sender's phone)
@@ -171,6 +192,9 @@ pub struct ChannelMetadata {
     pub google_sheets_spreadsheet_name: Option<String>,
     /// Google Sheets-specific: Sheet name (tab) where comment is located
     pub google_sheets_sheet_name: Option<String>,
+    /// Google Sheets-specific: cell changes applied before the comment reply, if any
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub google_sheets_edits: Vec<SheetEdit>,
     /// Google Slides-specific: Presentation ID
     pub google_slides_presentation_id: Option<String>,
     /// Google Slides-specific: Comment ID to reply to
@@ -207,6 +231,14 @@ pub struct ChannelMetadata {
     pub wechat_user_id: Option<String>,
     /// WeChat Work-specific: Agent ID (应用ID)
     pub wechat_agent_id: Option<String>,
+    /// Jira-specific: Issue key (e.g. "OPS-42") to comment on
+    pub jira_issue_key: Option<String>,
+    /// Jira-specific: Browser link to the issue
+    pub jira_issue_url: Option<String>,
+    /// Jira-specific: Comment that mentioned the employee, if any
+    pub jira_comment_id: Option<String>,
+    /// Internal-specific: ties a delegated request to its result
+    pub internal_correlation: Option<crate::internal_bus::InternalCorrelation>,
 
     // =========================================================================
     // Multi-channel collaboration support
@@ -285,11 +317,49 @@ pub trait InboundAdapter {
     fn channel(&self) -> Channel;
 }
 
+/// A message already on the platform, e.g. from [`SendResult::message_id`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageRef {
+    /// Conversation the message is in (Slack or Discord channel ID).
+    pub channel_id: String,
+    /// Platform message ID (Slack `ts`, Discord message ID).
+    pub message_id: String,
+}
+
+/// One change to a Google Sheets range. Values are entered as if typed, so
+/// `"=SUM(B2:B9)"` becomes a formula and `"12.50"` a number.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SheetEdit {
+    /// Add rows after the table found in `range`, e.g. `"Expenses!A:D"`.
+    Append {
+        range: String,
+        values: Vec<Vec<serde_json::Value>>,
+    },
+    /// Overwrite the cells of `range`, e.g. `"Summary!B2"`.
+    Update {
+        range: String,
+        values: Vec<Vec<serde_json::Value>>,
+    },
+}
+
 /// Trait for sending normalized outbound messages to a specific platform.
 pub trait OutboundAdapter {
     /// Send an outbound message to the platform.
     fn send(&self, message: &OutboundMessage) -> Result<SendResult, AdapterError>;
 
+    /// Replace the content of a message the bot sent.
+    fn update(&self, message: &MessageRef, new_content: &str) -> Result<(), AdapterError> {
+        let _ = (message, new_content);
+        Err(AdapterError::Unsupported(self.channel()))
+    }
+
+    /// Delete a message the bot sent.
+    fn delete(&self, message: &MessageRef) -> Result<(), AdapterError> {
+        let _ = message;
+        Err(AdapterError::Unsupported(self.channel()))
+    }
+
     /// Get the channel this adapter handles.
     fn channel(&self) -> Channel;
 }
@@ -305,6 +375,12 @@ pub enum AdapterError {
     SendError(String),
     #[error("configuration error: {0}")]
     ConfigError(String),
+    #[error("{0} messages cannot be edited or deleted")]
+    Unsupported(Channel),
+    #[error("text not found in document: '{0}'")]
+    TextNotFound(String),
+    #[error("document changed during the edit: {0}")]
+    EditConflict(String),
     #[error("io error: {0}")]
     IoError(#[from] std::io::Error),
     #[error("json error: {0}")]
@@ -320,6 +396,7 @@ mod tests {
     #[test]
     fn channel_display_wechat() {
         assert_eq!(Channel::WeChat.to_string(), "wechat");
+        assert_eq!(Channel::Jira.to_string(), "jira");
     }
 
     #[test]
@@ -360,6 +437,7 @@ mod tests {
         assert_eq!("google_slides".parse::<Channel>().unwrap(), Channel::GoogleSlides);
         assert_eq!("bluebubbles".parse::<Channel>().unwrap(), Channel::BlueBubbles);
         assert_eq!("imessage".parse::<Channel>().unwrap(), Channel::BlueBubbles);
+        assert_eq!("jira".parse::<Channel>().unwrap(), Channel::Jira);
     }
 
     #[test]
@@ -392,6 +470,7 @@ mod tests {
             Channel::GoogleSlides,
             Channel::BlueBubbles,
             Channel::WeChat,
+            Channel::Jira,
         ];
         for channel in channels {
             let json = serde_json::to_string(&channel).unwrap();
@@ -7,7 +7,6 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
 
 use serenity::all::{Context, EventHandler, GatewayIntents, Message, Ready};
 use serenity::async_trait;
@@ -15,7 +14,9 @@ use serenity::Client;
 use tracing::{error, info, warn};
 
 use crate::account_store::lookup_account_by_channel;
-use crate::adapters::discord::{DiscordInboundAdapter, DiscordOutboundAdapter};
+use crate::adapters::discord::{
+    discord_thread_id_for_channel, DiscordInboundAdapter, DiscordOutboundAdapter,
+};
 use crate::blob_store::get_blob_store;
 use crate::channel::Channel;
 use crate::index_store::IndexStore;
@@ -23,9 +24,9 @@ use crate::memory_diff::{MemoryDiff, SectionChange};
 use crate::memory_queue::{global_memory_queue, MemoryWriteRequest};
 use crate::message_router::{MessageRouter, RouterDecision};
 use crate::service::{
-    build_discord_message_text_with_quote, build_discord_router_context,
+    build_discord_message_text_with_quote, build_discord_router_context, discord_thread_key,
     hydrate_discord_attachments, hydrate_discord_context_files, persist_discord_ingest_context,
-    ServiceConfig,
+    resolve_discord_reply_channel, ServiceConfig,
 };
 use crate::user_store::UserStore;
 use crate::{ModuleExecutor, RunTaskTask, Scheduler, TaskKind};
@@ -108,9 +109,9 @@ impl EventHandler for DiscordEventHandler {
         info!("Discord bot connected as {}", ready.user.name);
     }
 
-    async fn message(&self, _ctx: Context, msg: Message) {
+    async fn message(&self, ctx: Context, msg: Message) {
         // Convert serenity Message to InboundMessage
-        let inbound = match self.adapter.from_serenity_message(&msg) {
+        let mut inbound = match self.adapter.from_serenity_message(&msg) {
             Ok(m) => m,
             Err(e) => {
                 // Most errors here are "ignoring bot message" which is expected
@@ -140,6 +141,19 @@ impl EventHandler for DiscordEventHandler {
             return;
         }
 
+        // Messages inside a thread keep their conversation (and replies) in that thread.
+        if !is_direct_message {
+            match msg.channel_id.to_channel(&ctx).await {
+                Ok(channel) => {
+                    inbound.metadata.discord_thread_id = discord_thread_id_for_channel(&channel);
+                }
+                Err(err) => warn!(
+                    "Discord channel lookup failed for {}: {}",
+                    msg.channel_id, err
+                ),
+            }
+        }
+
         let msg_len = inbound.text_body.as_ref().map(|t| t.len()).unwrap_or(0);
         info!(
             "Discord message from {} in channel {:?} (dm={}, mention={}, reply_to_bot={}, len={}): {:?}",
@@ -331,7 +345,7 @@ fn process_discord_message(
         .unwrap_or_else(|| "dm".to_string());
 
     // Thread key for conversation grouping
-    let thread_key = format!("discord:{}:{}:{}", guild_id, channel_id, message.thread_id);
+    let thread_key = discord_thread_key(message)?;
 
     let user = state
         .user_store
@@ -383,21 +397,14 @@ fn process_discord_message(
         );
     }
 
+    let reply_channel_id = resolve_discord_reply_channel(&config.employee_id, message, channel_id);
+
     // Determine model and runner
-    let model_name = match config.employee_profile.model.clone() {
-        Some(model) => model,
-        None => {
-            if config
-                .employee_profile
-                .runner
-                .eq_ignore_ascii_case("claude")
-            {
-                String::new()
-            } else {
-                config.codex_model.clone()
-            }
-        }
-    };
+    let model_name = config
+        .employee_profile
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model_for_runner(&config.employee_profile.runner));
 
     info!(
         "workspace ready at {} for guild {} thread={} epoch={}",
@@ -417,8 +424,8 @@ fn process_discord_message(
         model_name,
         runner: config.employee_profile.runner.clone(),
         codex_disabled: config.codex_disabled,
-        // reply_to[0] = user_id (for account lookup), reply_to[1] = channel_id
-        reply_to: vec![message.sender.clone(), channel_id.to_string()],
+        // reply_to[0] = user_id (for account lookup), reply_to[1] = reply channel/thread id
+        reply_to: vec![message.sender.clone(), reply_channel_id.to_string()],
         reply_from: None,
         archive_root: Some(user_paths.mail_root.clone()),
         thread_id: Some(thread_key.clone()),
@@ -430,6 +437,8 @@ fn process_discord_message(
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     // Schedule the task using user-based scheduler
@@ -443,7 +452,13 @@ fn process_discord_message(
             err
         );
     }
-    let task_id = scheduler.add_one_shot_in(Duration::from_secs(0), TaskKind::RunTask(run_task))?;
+    let delay = crate::service::inbound_coalesce_delay(
+        &scheduler,
+        &workspace,
+        &config.employee_profile.inbound_policy,
+        Channel::Discord,
+    );
+    let task_id = scheduler.add_one_shot_in(delay, TaskKind::RunTask(run_task))?;
 
     // Sync to index_store using real user_id
     index_store.sync_user_tasks(&user.user_id, scheduler.tasks())?;
@@ -1,9 +1,18 @@
+use run_task_module::SandboxProfile;
 use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::approval_store::{Approver, ApproverConfig};
+use crate::archive_tiering::{ArchiveTiering, ArchiveTieringConfig};
+use crate::inbound_policy::{InboundPolicy, InboundPolicyConfig};
+use crate::message_router::{RouterProfile, RouterProfileConfig};
+use crate::outbound_policy::{OutboundPolicy, OutboundPolicyConfig};
+use crate::redaction::{RedactionConfig, RedactionPolicy};
+use crate::reply_formatting::{ChannelFormatting, ReplyFormattingConfig};
+
 pub type BoxError = Box<dyn Error + Send + Sync>;
 
 #[derive(Debug, Deserialize)]
@@ -32,6 +41,8 @@ pub struct EmployeeConfigEntry {
     #[serde(default)]
     pub claude_path: Option<PathBuf>,
     #[serde(default)]
+    pub gemini_path: Option<PathBuf>,
+    #[serde(default)]
     pub soul_path: Option<PathBuf>,
     #[serde(default)]
     pub skills_dir: Option<PathBuf>,
@@ -44,6 +55,38 @@ pub struct EmployeeConfigEntry {
     /// Whether this employee handles BlueBubbles/iMessage. Only one employee should have this enabled.
     #[serde(default)]
     pub bluebubbles_enabled: bool,
+    /// Limits on outbound recipients and channels; see [`OutboundPolicy`].
+    #[serde(default)]
+    pub outbound_policy: OutboundPolicyConfig,
+    /// Per-sender caps and who may start a conversation; see [`InboundPolicy`].
+    #[serde(default)]
+    pub inbound_policy: InboundPolicyConfig,
+    /// Who approves this employee's held tasks; see [`Approver`].
+    #[serde(default)]
+    pub approvals: ApproverConfig,
+    /// PII redaction of archived mail; see [`RedactionPolicy`].
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    /// Moving old archived mail to object storage; see [`ArchiveTiering`].
+    #[serde(default)]
+    pub archive_tiering: ArchiveTieringConfig,
+    /// Container limits for this employee's runner; see [`SandboxProfile`].
+    #[serde(default)]
+    pub sandbox: Option<SandboxProfile>,
+    /// Language of system messages for users who have not set one, e.g.
+    /// "es" or "Japanese"; see [`crate::i18n`].
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Names the employee answers to as `@name` in Google Workspace comments,
+    /// besides its display name and id, e.g. `["devin"]`.
+    #[serde(default)]
+    pub mention_names: Vec<String>,
+    /// Quick-response router overrides; see [`RouterProfileConfig`].
+    #[serde(default)]
+    pub router: RouterProfileConfig,
+    /// Reply formatting per channel; see [`ChannelFormatting`].
+    #[serde(default)]
+    pub formatting: ReplyFormattingConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +100,7 @@ pub struct EmployeeProfile {
     pub runtime_root: Option<PathBuf>,
     pub agents_path: Option<PathBuf>,
     pub claude_path: Option<PathBuf>,
+    pub gemini_path: Option<PathBuf>,
     pub soul_path: Option<PathBuf>,
     pub skills_dir: Option<PathBuf>,
     /// Whether this employee handles Discord messages.
@@ -65,6 +109,28 @@ pub struct EmployeeProfile {
     pub slack_enabled: bool,
     /// Whether this employee handles BlueBubbles/iMessage.
     pub bluebubbles_enabled: bool,
+    pub outbound_policy: OutboundPolicy,
+    pub inbound_policy: InboundPolicy,
+    /// Receives approval requests; the requester when unset.
+    pub approver: Option<Approver>,
+    /// Slack users besides the requester who may answer confirmation buttons.
+    pub slack_approvers: Vec<String>,
+    /// Applied when archiving mail; `None` archives payloads verbatim.
+    pub redaction: Option<RedactionPolicy>,
+    /// Moves old archived mail to cold storage; `None` keeps it all local.
+    pub archive_tiering: Option<ArchiveTiering>,
+    /// Runs the runner in a limited container; `None` leaves it to
+    /// `RUN_TASK_SANDBOX`.
+    pub sandbox: Option<SandboxProfile>,
+    /// Default language of system messages; see [`crate::i18n`].
+    pub language: Option<String>,
+    /// `@name` mentions it answers to besides its display name and id.
+    pub mention_names: Vec<String>,
+    /// Quick-response router settings; see [`crate::message_router`].
+    pub router: RouterProfile,
+    /// Emoji, signature, length and tone of replies per channel; see
+    /// [`crate::reply_formatting`].
+    pub formatting: ChannelFormatting,
 }
 
 impl EmployeeProfile {
@@ -124,6 +190,20 @@ pub fn load_employee_directory(config_path: &Path) -> Result<EmployeeDirectory,
             .map(|value| normalize_address(value))
             .collect();
         service_addresses.extend(address_set.iter().cloned());
+        let outbound_policy = OutboundPolicy::from_config(&entry.outbound_policy)
+            .map_err(|err| format!("employee '{}' outbound_policy: {}", entry.id, err))?;
+        let inbound_policy = InboundPolicy::from_config(&entry.inbound_policy)
+            .map_err(|err| format!("employee '{}' inbound_policy: {}", entry.id, err))?;
+        let approver = Approver::from_config(&entry.approvals)
+            .map_err(|err| format!("employee '{}' approvals: {}", entry.id, err))?;
+        let redaction = RedactionPolicy::from_config(&entry.redaction)
+            .map_err(|err| format!("employee '{}' redaction: {}", entry.id, err))?;
+        let archive_tiering = ArchiveTiering::from_config(&entry.archive_tiering)
+            .map_err(|err| format!("employee '{}' archive_tiering: {}", entry.id, err))?;
+        let router = RouterProfile::from_config(&entry.router, base_dir)
+            .map_err(|err| format!("employee '{}' router: {}", entry.id, err))?;
+        let formatting = ChannelFormatting::from_config(&entry.formatting)
+            .map_err(|err| format!("employee '{}' formatting: {}", entry.id, err))?;
 
         let profile = EmployeeProfile {
             id: entry.id.trim().to_string(),
@@ -145,11 +225,34 @@ pub fn load_employee_directory(config_path: &Path) -> Result<EmployeeDirectory,
             runtime_root: resolve_optional_path(base_dir, entry.runtime_root.as_ref()),
             agents_path: resolve_optional_path(base_dir, entry.agents_path.as_ref()),
             claude_path: resolve_optional_path(base_dir, entry.claude_path.as_ref()),
+            gemini_path: resolve_optional_path(base_dir, entry.gemini_path.as_ref()),
             soul_path: resolve_optional_path(base_dir, entry.soul_path.as_ref()),
             skills_dir: resolve_optional_path(base_dir, entry.skills_dir.as_ref()),
             discord_enabled: entry.discord_enabled,
             slack_enabled: entry.slack_enabled,
             bluebubbles_enabled: entry.bluebubbles_enabled,
+            outbound_policy,
+            inbound_policy,
+            approver,
+            slack_approvers: entry
+                .approvals
+                .slack_users
+                .iter()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .collect(),
+            redaction,
+            archive_tiering,
+            sandbox: entry.sandbox.clone(),
+            language: entry.language.clone(),
+            mention_names: entry
+                .mention_names
+                .iter()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .collect(),
+            router,
+            formatting,
         };
 
         employee_by_id.insert(profile.id.clone(), profile.clone());
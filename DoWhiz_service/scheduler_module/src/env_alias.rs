@@ -40,6 +40,9 @@ const SCALE_ALIAS_SYNC_KEYS: &[&str] = &[
     "SERVICE_BUS_NAMESPACE",
     "SERVICE_BUS_POLICY_NAME",
     "SERVICE_BUS_POLICY_KEY",
+    "SERVICE_BUS_TOPIC_NAME",
+    "SERVICE_BUS_SUBSCRIPTION",
+    "SERVICE_BUS_DEAD_LETTER_QUEUE",
     "RAW_PAYLOAD_STORAGE_BACKEND",
     "RAW_PAYLOAD_PATH_PREFIX",
     "AZURE_STORAGE_ACCOUNT",
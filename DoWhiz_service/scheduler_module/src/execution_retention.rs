@@ -0,0 +1,318 @@
+//! Retention of the `task_executions` history.
+//!
+//! An execution is kept while it is younger than `TASK_EXECUTION_RETENTION_DAYS`
+//! or among the newest `TASK_EXECUTION_KEEP_PER_TASK` runs of its task; with
+//! only one of the two set, that one decides alone. Rows past both are
+//! pruned in batches. When `EXECUTION_EXPORT_BACKEND` is set, each batch is
+//! first written to that object store as a gzip file of JSON lines
+//! (canonical extended JSON) for long-term analytics, and a batch that fails
+//! to export is not deleted.
+
+use std::io::Write;
+
+use chrono::{DateTime, Duration, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson, DateTime as BsonDateTime, Document};
+use mongodb::error::ErrorKind;
+use mongodb::options::{AggregateOptions, FindOptions};
+use mongodb::sync::{Collection, Database};
+use tracing::warn;
+
+use crate::mongo_store::MongoStoreError;
+use crate::object_store::{ObjectStore, ObjectStoreError};
+
+/// Env prefix of the store pruned executions are exported to.
+pub const EXECUTION_EXPORT_ENV_PREFIX: &str = "EXECUTION_EXPORT";
+
+const COLLECTION: &str = "task_executions";
+const BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RetentionError {
+    #[error("mongodb error: {0}")]
+    Mongo(#[from] mongodb::error::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    MongoStore(#[from] MongoStoreError),
+    #[error("export failed: {0}")]
+    Export(#[from] ObjectStoreError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_age: Option<Duration>,
+    pub keep_per_task: Option<usize>,
+}
+
+impl RetentionPolicy {
+    /// `None` unless `TASK_EXECUTION_RETENTION_DAYS` or
+    /// `TASK_EXECUTION_KEEP_PER_TASK` is set to a positive number.
+    pub fn from_env() -> Option<Self> {
+        parse_policy(
+            std::env::var("TASK_EXECUTION_RETENTION_DAYS")
+                .ok()
+                .as_deref(),
+            std::env::var("TASK_EXECUTION_KEEP_PER_TASK")
+                .ok()
+                .as_deref(),
+        )
+    }
+
+    fn cutoff(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.max_age.map(|max_age| now - max_age)
+    }
+}
+
+fn parse_policy(days: Option<&str>, keep_per_task: Option<&str>) -> Option<RetentionPolicy> {
+    let positive = |value: Option<&str>| {
+        value
+            .and_then(|value| value.trim().parse::<u32>().ok())
+            .filter(|value| *value > 0)
+    };
+    let policy = RetentionPolicy {
+        max_age: positive(days).map(|days| Duration::days(days as i64)),
+        keep_per_task: positive(keep_per_task).map(|keep| keep as usize),
+    };
+    (policy.max_age.is_some() || policy.keep_per_task.is_some()).then_some(policy)
+}
+
+#[derive(Debug, Default)]
+pub struct PruneReport {
+    pub pruned: u64,
+    pub exported: u64,
+    pub compacted: bool,
+}
+
+/// Prune executions past `policy`, exporting them to `export` first. With
+/// `compact`, the collection is compacted afterwards to return the space.
+pub fn prune_task_executions(
+    db: &Database,
+    policy: &RetentionPolicy,
+    export: Option<&dyn ObjectStore>,
+    compact: bool,
+    now: DateTime<Utc>,
+) -> Result<PruneReport, RetentionError> {
+    let executions = db.collection::<Document>(COLLECTION);
+    let mut batch = Batch {
+        executions: &executions,
+        export,
+        database: db.name(),
+        now,
+        ids: Vec::new(),
+        report: PruneReport::default(),
+    };
+
+    let cutoff = policy.cutoff(now);
+    match (policy.keep_per_task, cutoff) {
+        (None, None) => {}
+        (None, Some(cutoff)) => {
+            let options = FindOptions::builder().projection(doc! { "_id": 1 }).build();
+            let cursor = executions.find(
+                doc! { "started_at": { "$lt": BsonDateTime::from_chrono(cutoff) } },
+                options,
+            )?;
+            for row in cursor {
+                if let Ok(id) = row?.get_object_id("_id") {
+                    batch.push(id)?;
+                }
+            }
+        }
+        (Some(keep), cutoff) => {
+            for group in tasks_over(&executions, keep)? {
+                let options = FindOptions::builder()
+                    .sort(doc! { "started_at": -1 })
+                    .projection(doc! { "_id": 1, "started_at": 1 })
+                    .build();
+                let mut runs = Vec::new();
+                for row in executions.find(group, options)? {
+                    let row = row?;
+                    if let Ok(id) = row.get_object_id("_id") {
+                        let started_at =
+                            row.get_datetime("started_at").ok().map(|at| at.to_chrono());
+                        runs.push((id, started_at));
+                    }
+                }
+                for id in prunable(&runs, keep, cutoff) {
+                    batch.push(id)?;
+                }
+            }
+        }
+    }
+    batch.flush()?;
+    let mut report = batch.report;
+
+    if compact && report.pruned > 0 {
+        match db.run_command(doc! { "compact": COLLECTION }, None) {
+            Ok(_) => report.compacted = true,
+            Err(err) if matches!(err.kind.as_ref(), ErrorKind::Command(_)) => {
+                warn!("task_executions compact is not supported here: {}", err)
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(report)
+}
+
+/// Executions queued for deletion, flushed every `BATCH_SIZE` ids.
+struct Batch<'a> {
+    executions: &'a Collection<Document>,
+    export: Option<&'a dyn ObjectStore>,
+    database: &'a str,
+    now: DateTime<Utc>,
+    ids: Vec<ObjectId>,
+    report: PruneReport,
+}
+
+impl Batch<'_> {
+    fn push(&mut self, id: ObjectId) -> Result<(), RetentionError> {
+        self.ids.push(id);
+        if self.ids.len() >= BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), RetentionError> {
+        if self.ids.is_empty() {
+            return Ok(());
+        }
+        let ids = std::mem::take(&mut self.ids);
+        if let Some(store) = self.export {
+            self.report.exported +=
+                export_batch(self.executions, store, self.database, &ids, self.now)?;
+        }
+        self.report.pruned += self
+            .executions
+            .delete_many(doc! { "_id": { "$in": ids } }, None)?
+            .deleted_count;
+        Ok(())
+    }
+}
+
+/// Owner/task filters of every task with more than `keep` executions.
+fn tasks_over(
+    executions: &Collection<Document>,
+    keep: usize,
+) -> Result<Vec<Document>, RetentionError> {
+    let cursor = executions.aggregate(
+        [
+            doc! { "$group": {
+                "_id": {
+                    "kind": "$owner_scope.kind",
+                    "id": "$owner_scope.id",
+                    "task_id": "$task_id",
+                },
+                "count": { "$sum": 1 },
+            } },
+            doc! { "$match": { "count": { "$gt": keep as i64 } } },
+        ],
+        AggregateOptions::builder().allow_disk_use(true).build(),
+    )?;
+    let mut groups = Vec::new();
+    for row in cursor {
+        let row = row?;
+        let Ok(key) = row.get_document("_id") else {
+            continue;
+        };
+        let field = |name: &str| key.get(name).cloned().unwrap_or(Bson::Null);
+        groups.push(doc! {
+            "owner_scope.kind": field("kind"),
+            "owner_scope.id": field("id"),
+            "task_id": field("task_id"),
+        });
+    }
+    Ok(groups)
+}
+
+/// Of one task's runs, newest first, those past the newest `keep` that are
+/// also older than `cutoff` when one is set.
+fn prunable(
+    runs: &[(ObjectId, Option<DateTime<Utc>>)],
+    keep: usize,
+    cutoff: Option<DateTime<Utc>>,
+) -> Vec<ObjectId> {
+    runs.iter()
+        .skip(keep)
+        .filter(|(_, started_at)| match (cutoff, started_at) {
+            (Some(cutoff), Some(started_at)) => *started_at < cutoff,
+            _ => true,
+        })
+        .map(|(id, _)| *id)
+        .collect()
+}
+
+/// Writes the batch to `<database>/<yyyy-mm-dd>/task_executions-<ts>-<first id>.jsonl.gz`.
+fn export_batch(
+    executions: &Collection<Document>,
+    store: &dyn ObjectStore,
+    database: &str,
+    ids: &[ObjectId],
+    now: DateTime<Utc>,
+) -> Result<u64, RetentionError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let mut exported = 0;
+    for row in executions.find(doc! { "_id": { "$in": ids } }, None)? {
+        let line = Bson::Document(row?).into_canonical_extjson();
+        serde_json::to_writer(&mut encoder, &line)?;
+        encoder.write_all(b"\n")?;
+        exported += 1;
+    }
+    let key = format!(
+        "{}/{}/task_executions-{}-{}.jsonl.gz",
+        database,
+        now.format("%Y-%m-%d"),
+        now.format("%Y%m%dT%H%M%SZ"),
+        ids[0].to_hex()
+    );
+    store.put(&key, &encoder.finish()?)?;
+    Ok(exported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_needs_a_positive_limit() {
+        assert_eq!(parse_policy(None, None), None);
+        assert_eq!(parse_policy(Some("0"), Some("junk")), None);
+        assert_eq!(
+            parse_policy(Some("90"), None),
+            Some(RetentionPolicy {
+                max_age: Some(Duration::days(90)),
+                keep_per_task: None,
+            })
+        );
+        assert_eq!(
+            parse_policy(None, Some(" 50 ")).and_then(|policy| policy.keep_per_task),
+            Some(50)
+        );
+    }
+
+    #[test]
+    fn keeps_recent_runs_or_the_newest_per_task() {
+        let now = Utc::now();
+        let runs: Vec<(ObjectId, Option<DateTime<Utc>>)> = [1, 10, 100, 200]
+            .iter()
+            .map(|days| (ObjectId::new(), Some(now - Duration::days(*days))))
+            .collect();
+
+        // Newest two kept regardless of age; the rest go.
+        assert_eq!(prunable(&runs, 2, None), vec![runs[2].0, runs[3].0]);
+        // Only runs past the newest one and older than 90 days go.
+        assert_eq!(
+            prunable(&runs, 1, Some(now - Duration::days(90))),
+            vec![runs[2].0, runs[3].0]
+        );
+        assert_eq!(
+            prunable(&runs, 1, Some(now - Duration::days(150))),
+            vec![runs[3].0]
+        );
+        assert!(prunable(&runs, 4, None).is_empty());
+    }
+}
@@ -5,9 +5,7 @@
 
 use chrono::Utc;
 use mongodb::bson::{doc, Bson, DateTime as BsonDateTime, Document};
-use mongodb::options::IndexOptions;
 use mongodb::sync::Collection;
-use mongodb::IndexModel;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -18,7 +16,7 @@ use tracing::{debug, error, info, warn};
 use crate::adapters::google_docs::{ActionableComment, GoogleDocsInboundAdapter};
 use crate::channel::Channel;
 use crate::google_auth::{GoogleAuth, GoogleAuthConfig};
-use crate::mongo_store::{create_client_from_env, database_from_env, ensure_index_compatible};
+use crate::mongo_store::{create_client_from_env, database_from_env};
 use crate::{RunTaskTask, Scheduler, SchedulerError, TaskExecutor, TaskKind};
 
 /// Configuration for Google Docs polling.
@@ -200,23 +198,7 @@ impl MongoDocsProcessedStore {
             .map_err(|err| SchedulerError::Storage(format!("mongo config error: {err}")))?;
         let db = database_from_env(&client);
         let processed_comments = db.collection::<Document>("processed_comments");
-        ensure_index_compatible(
-            &processed_comments,
-            IndexModel::builder()
-                .keys(doc! { "file_id": 1, "tracking_id": 1 })
-                .options(IndexOptions::builder().unique(Some(true)).build())
-                .build(),
-        )
-        .map_err(mongo_scheduler_err)?;
         let workspace_files = db.collection::<Document>("workspace_files");
-        ensure_index_compatible(
-            &workspace_files,
-            IndexModel::builder()
-                .keys(doc! { "file_id": 1, "file_type": 1 })
-                .options(IndexOptions::builder().unique(Some(true)).build())
-                .build(),
-        )
-        .map_err(mongo_scheduler_err)?;
         Ok(Self {
             processed_comments,
             workspace_files,
@@ -468,6 +450,8 @@ impl GoogleDocsPoller {
                     requester_identifier_type: None,
                     requester_identifier: None,
                     account_id: None,
+                    trace_id: None,
+                    scheduled: false,
                 };
 
                 // Schedule the task
@@ -9,6 +9,9 @@
 //! 1. Register a watch channel for each monitored file using `files.watch`
 //! 2. Receive push notifications at `/webhooks/google-drive-changes`
 //! 3. When a notification arrives, fetch comments for that specific file
+//! 4. Every `GOOGLE_DRIVE_PUSH_FALLBACK_POLL_SECS` (default 600), poll all files
+//!    anyway, in case a notification was lost; files without a channel are
+//!    still polled on the regular interval
 //!
 //! ## Requirements
 //!
@@ -22,6 +25,8 @@
 //! // Enable push notifications instead of polling
 //! GOOGLE_DRIVE_PUSH_ENABLED=true
 //! GOOGLE_DRIVE_WEBHOOK_URL=https://your-domain.com/webhooks/google-drive-changes
+//! # Echoed by Google in X-Goog-Channel-Token; notifications without it are dropped
+//! GOOGLE_DRIVE_CHANNEL_TOKEN=<random secret>
 //! ```
 
 use std::collections::HashMap;
@@ -42,6 +47,9 @@ const DEFAULT_CHANNEL_EXPIRATION_SECS: u64 = 3600;
 /// Renew channels 5 minutes before expiration.
 const CHANNEL_RENEWAL_BUFFER_SECS: u64 = 300;
 
+/// Full poll interval while push notifications are active (10 minutes).
+const DEFAULT_FALLBACK_POLL_SECS: u64 = 600;
+
 /// HTTP client timeout for API calls.
 const API_TIMEOUT_SECS: u64 = 30;
 
@@ -54,6 +62,10 @@ pub struct GoogleDriveChangesConfig {
     pub webhook_url: Option<String>,
     /// Channel expiration time in seconds.
     pub channel_expiration_secs: u64,
+    /// Secret sent with each channel and checked on every notification.
+    pub channel_token: Option<String>,
+    /// How often watched files are polled anyway, in seconds.
+    pub fallback_poll_secs: u64,
 }
 
 impl Default for GoogleDriveChangesConfig {
@@ -62,6 +74,8 @@ impl Default for GoogleDriveChangesConfig {
             enabled: false,
             webhook_url: None,
             channel_expiration_secs: DEFAULT_CHANNEL_EXPIRATION_SECS,
+            channel_token: None,
+            fallback_poll_secs: DEFAULT_FALLBACK_POLL_SECS,
         }
     }
 }
@@ -82,10 +96,22 @@ impl GoogleDriveChangesConfig {
             .and_then(|v| v.parse().ok())
             .unwrap_or(DEFAULT_CHANNEL_EXPIRATION_SECS);
 
+        let channel_token = std::env::var("GOOGLE_DRIVE_CHANNEL_TOKEN")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+
+        let fallback_poll_secs = std::env::var("GOOGLE_DRIVE_PUSH_FALLBACK_POLL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_FALLBACK_POLL_SECS);
+
         Self {
             enabled,
             webhook_url,
             channel_expiration_secs,
+            channel_token,
+            fallback_poll_secs,
         }
     }
 
@@ -150,6 +176,8 @@ pub struct ChangeNotification {
     pub resource_state: String,
     /// Message number (for ordering).
     pub message_number: Option<String>,
+    /// Token the channel was registered with.
+    pub channel_token: Option<String>,
 }
 
 /// Manager for Google Drive watch channels.
@@ -209,12 +237,15 @@ impl GoogleDriveChangesManager {
             file_id
         );
 
-        let payload = serde_json::json!({
+        let mut payload = serde_json::json!({
             "id": channel_id,
             "type": "web_hook",
             "address": webhook_url,
             "expiration": expiration_ms.to_string(),
         });
+        if let Some(token) = &self.config.channel_token {
+            payload["token"] = serde_json::Value::String(token.clone());
+        }
 
         info!("Registering watch channel for file {}", file_id);
 
@@ -261,13 +292,7 @@ impl GoogleDriveChangesManager {
             created_at: Utc::now(),
         };
 
-        // Store the channel
-        if let Ok(mut channels) = self.channels.lock() {
-            channels.insert(file_id.to_string(), channel.clone());
-        }
-        if let Ok(mut resource_map) = self.resource_to_file.lock() {
-            resource_map.insert(watch_response.resource_id, file_id.to_string());
-        }
+        self.track(channel.clone());
 
         info!(
             "Created watch channel {} for file {}, expires at {}",
@@ -277,6 +302,33 @@ impl GoogleDriveChangesManager {
         Ok(channel)
     }
 
+    fn track(&self, channel: WatchChannel) {
+        if let Ok(mut resource_map) = self.resource_to_file.lock() {
+            resource_map.insert(channel.resource_id.clone(), channel.file_id.clone());
+        }
+        if let Ok(mut channels) = self.channels.lock() {
+            channels.insert(channel.file_id.clone(), channel);
+        }
+    }
+
+    /// Whether `file_id` has a channel that has not expired, so changes to it
+    /// arrive as notifications.
+    pub fn is_watching(&self, file_id: &str) -> bool {
+        self.channels
+            .lock()
+            .map(|channels| {
+                channels
+                    .get(file_id)
+                    .is_some_and(|channel| !channel.is_expired())
+            })
+            .unwrap_or(false)
+    }
+
+    /// Full poll interval while files are watched.
+    pub fn fallback_poll_interval(&self) -> Duration {
+        Duration::from_secs(self.config.fallback_poll_secs)
+    }
+
     /// Stop watching a file.
     pub fn stop_watching(&self, file_id: &str) -> Result<(), AdapterError> {
         let channel = {
@@ -345,34 +397,62 @@ impl GoogleDriveChangesManager {
     /// Handle an incoming change notification.
     /// Returns the file_id that changed, if the notification is valid.
     pub fn handle_notification(&self, notification: &ChangeNotification) -> Option<String> {
-        // Ignore sync notifications (sent when channel is created)
-        if notification.resource_state == "sync" {
+        if let Some(token) = &self.config.channel_token {
+            if notification.channel_token.as_deref() != Some(token.as_str()) {
+                warn!(
+                    "Ignoring notification for channel {} with a wrong channel token",
+                    notification.channel_id
+                );
+                return None;
+            }
+        }
+
+        // Ignore sync notifications (sent when channel is created) and files
+        // that went away; there are no comments to fetch.
+        if matches!(
+            notification.resource_state.as_str(),
+            "sync" | "remove" | "trash"
+        ) {
             debug!(
-                "Ignoring sync notification for channel {}",
-                notification.channel_id
+                "Ignoring {} notification for channel {}",
+                notification.resource_state, notification.channel_id
             );
             return None;
         }
 
         // Look up the file_id from the resource_id
-        let file_id = {
+        let Some(file_id) = ({
             let resource_map = self.resource_to_file.lock().ok()?;
             resource_map.get(&notification.resource_id).cloned()
-        };
-
-        if let Some(ref file_id) = file_id {
-            info!(
-                "Change notification for file {}: state={}",
-                file_id, notification.resource_state
-            );
-        } else {
+        }) else {
             warn!(
                 "Unknown resource_id {} in change notification",
                 notification.resource_id
             );
+            return None;
+        };
+
+        // A renewed file keeps its resource_id; its old channel may still fire
+        // until it expires.
+        let current = {
+            let channels = self.channels.lock().ok()?;
+            channels
+                .get(&file_id)
+                .is_some_and(|channel| channel.id == notification.channel_id)
+        };
+        if !current {
+            debug!(
+                "Ignoring notification from replaced channel {} for file {}",
+                notification.channel_id, file_id
+            );
+            return None;
         }
 
-        file_id
+        info!(
+            "Change notification for file {}: state={}",
+            file_id, notification.resource_state
+        );
+        Some(file_id)
     }
 
     /// Renew channels that are about to expire.
@@ -456,4 +536,56 @@ mod tests {
         // Channel with 600 seconds left should NOT need renewal
         assert!(!channel2.needs_renewal());
     }
+
+    #[test]
+    fn notifications_need_the_token_and_the_current_channel() {
+        let auth = GoogleAuth::new(crate::google_auth::GoogleAuthConfig {
+            access_token: Some("test_token".to_string()),
+            ..Default::default()
+        })
+        .expect("auth");
+        let manager = GoogleDriveChangesManager::new(
+            GoogleDriveChangesConfig {
+                enabled: true,
+                webhook_url: Some("https://example.com/webhooks/google-drive-changes".to_string()),
+                channel_token: Some("secret".to_string()),
+                ..Default::default()
+            },
+            auth,
+        );
+        manager.track(WatchChannel {
+            id: "chan2".to_string(),
+            file_id: "file1".to_string(),
+            resource_id: "res1".to_string(),
+            expires_at: Utc::now() + chrono::Duration::seconds(600),
+            created_at: Utc::now(),
+        });
+        assert!(manager.is_watching("file1"));
+        assert!(!manager.is_watching("file2"));
+
+        let notification =
+            |channel_id: &str, state: &str, token: Option<&str>| ChangeNotification {
+                channel_id: channel_id.to_string(),
+                resource_id: "res1".to_string(),
+                resource_state: state.to_string(),
+                message_number: Some("2".to_string()),
+                channel_token: token.map(str::to_string),
+            };
+        assert_eq!(
+            manager.handle_notification(&notification("chan2", "update", Some("secret"))),
+            Some("file1".to_string())
+        );
+        assert_eq!(
+            manager.handle_notification(&notification("chan2", "update", None)),
+            None
+        );
+        assert_eq!(
+            manager.handle_notification(&notification("chan1", "update", Some("secret"))),
+            None
+        );
+        assert_eq!(
+            manager.handle_notification(&notification("chan2", "sync", Some("secret"))),
+            None
+        );
+    }
 }
@@ -5,9 +5,7 @@
 
 use chrono::Utc;
 use mongodb::bson::{doc, Bson, DateTime as BsonDateTime, Document};
-use mongodb::options::IndexOptions;
 use mongodb::sync::Collection;
-use mongodb::IndexModel;
 use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::Mutex;
@@ -22,7 +20,7 @@ use crate::adapters::google_sheets::GoogleSheetsInboundAdapter;
 use crate::adapters::google_slides::GoogleSlidesInboundAdapter;
 use crate::channel::{Channel, InboundMessage};
 use crate::google_auth::{GoogleAuth, GoogleAuthConfig};
-use crate::mongo_store::{create_client_from_env, database_from_env, ensure_index_compatible};
+use crate::mongo_store::{create_client_from_env, database_from_env};
 use crate::SchedulerError;
 
 /// Default TTL for file list cache (5 minutes).
@@ -343,23 +341,7 @@ impl MongoWorkspaceProcessedStore {
             .map_err(|err| SchedulerError::Storage(format!("mongo config error: {err}")))?;
         let db = database_from_env(&client);
         let processed_comments = db.collection::<Document>("processed_comments");
-        ensure_index_compatible(
-            &processed_comments,
-            IndexModel::builder()
-                .keys(doc! { "file_id": 1, "tracking_id": 1 })
-                .options(IndexOptions::builder().unique(Some(true)).build())
-                .build(),
-        )
-        .map_err(mongo_scheduler_err)?;
         let workspace_files = db.collection::<Document>("workspace_files");
-        ensure_index_compatible(
-            &workspace_files,
-            IndexModel::builder()
-                .keys(doc! { "file_id": 1, "file_type": 1 })
-                .options(IndexOptions::builder().unique(Some(true)).build())
-                .build(),
-        )
-        .map_err(mongo_scheduler_err)?;
         Ok(Self {
             processed_comments,
             workspace_files,
@@ -947,6 +929,16 @@ impl GoogleWorkspacePoller {
             return Ok(vec![]);
         };
 
+        // Files first seen here (e.g. shared since the last full poll) need
+        // their owner recorded for account linking
+        let owner_email = file
+            .owners
+            .as_ref()
+            .and_then(|owners| owners.first())
+            .and_then(|o| o.email_address.as_deref());
+        self.store
+            .register_file(&file.id, file.name.as_deref(), file_type, owner_email)?;
+
         // Get comments for this specific file
         let comments = match file_type {
             WorkspaceFileType::Docs => {
@@ -0,0 +1,321 @@
+//! Synthetic end-to-end health probe.
+//!
+//! A [`TaskKind::Probe`](crate::TaskKind::Probe) cron task in the
+//! employee-level scheduler sends the employee an internal request from
+//! itself, marked as a probe. It takes the real path: the ingestion queue and
+//! consumer, a run_task with the runner disabled, the reply task and the
+//! internal send back. When the reply arrives the round trip is recorded in a
+//! state file next to the employee's tasks database; a probe left unanswered
+//! past its SLA counts as failed. `/health/detail` reports that file.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::internal_bus::{self, InternalCorrelation, InternalMessageKind};
+use crate::telemetry;
+use crate::ProbeTask;
+
+/// File next to the employee's tasks database holding the probe state.
+const PROBE_STATE_FILE: &str = "health_probe.json";
+/// Body of every probe request; the runner is disabled, so it is never read.
+const PROBE_REQUEST: &str = "Health probe. No action needed.";
+
+/// Serializes read-modify-write of the state file between the scheduler,
+/// which sends probes, and the ingestion consumer, which records replies.
+static STATE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, thiserror::Error)]
+pub enum HealthProbeError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeOutcome {
+    Ok,
+    /// The reply came, but after the SLA.
+    Late,
+    /// No reply by the next probe run.
+    TimedOut,
+    /// The request never reached the queue.
+    SendFailed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeRun {
+    pub correlation_id: Uuid,
+    pub sent_at: DateTime<Utc>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub outcome: Option<ProbeOutcome>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl ProbeRun {
+    fn finished(mut self, now: DateTime<Utc>, outcome: ProbeOutcome) -> Self {
+        self.completed_at = Some(now);
+        self.outcome = Some(outcome);
+        self
+    }
+
+    fn elapsed(&self, now: DateTime<Utc>) -> std::time::Duration {
+        (now - self.sent_at).to_std().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeState {
+    pub sla_secs: u64,
+    /// Sent and not answered yet.
+    #[serde(default)]
+    pub pending: Option<ProbeRun>,
+    /// The latest probe that finished, either way.
+    #[serde(default)]
+    pub last: Option<ProbeRun>,
+    #[serde(default)]
+    pub last_success_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub consecutive_failures: u32,
+}
+
+impl ProbeState {
+    fn overdue(&self, run: &ProbeRun, now: DateTime<Utc>) -> bool {
+        now - run.sent_at > Duration::seconds(self.sla_secs as i64)
+    }
+
+    fn finish(&mut self, run: ProbeRun) {
+        if run.outcome == Some(ProbeOutcome::Ok) {
+            self.last_success_at = run.completed_at;
+            self.consecutive_failures = 0;
+        } else {
+            self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        }
+        self.last = Some(run);
+    }
+
+    /// Close the pending probe as timed out once it is past its SLA.
+    fn expire(&mut self, now: DateTime<Utc>) -> Option<ProbeRun> {
+        if !self
+            .pending
+            .as_ref()
+            .is_some_and(|run| self.overdue(run, now))
+        {
+            return None;
+        }
+        let run = self.pending.take()?.finished(now, ProbeOutcome::TimedOut);
+        self.finish(run.clone());
+        Some(run)
+    }
+
+    /// Whether the pipeline looks healthy at `now`: the latest probe
+    /// succeeded and the pending one, if any, is still within its SLA.
+    /// `None` until a probe has finished.
+    pub fn healthy(&self, now: DateTime<Utc>) -> Option<bool> {
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|run| self.overdue(run, now))
+        {
+            return Some(false);
+        }
+        let last = self.last.as_ref()?;
+        Some(last.outcome == Some(ProbeOutcome::Ok))
+    }
+}
+
+/// Where the probe state of the employee whose tasks database is at
+/// `scheduler_state_path` is kept.
+pub fn state_path(scheduler_state_path: &Path) -> PathBuf {
+    scheduler_state_path.with_file_name(PROBE_STATE_FILE)
+}
+
+pub fn load_state(path: &Path) -> Result<Option<ProbeState>, HealthProbeError> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn save_state(path: &Path, state: &ProbeState) -> Result<(), HealthProbeError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec_pretty(state)?)?;
+    Ok(())
+}
+
+fn update_state<T>(
+    path: &Path,
+    update: impl FnOnce(&mut ProbeState) -> T,
+) -> Result<T, HealthProbeError> {
+    let _guard = STATE_LOCK
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    let mut state = load_state(path)?.unwrap_or_default();
+    let result = update(&mut state);
+    save_state(path, &state)?;
+    Ok(result)
+}
+
+/// One probe run: time out the previous probe if it is overdue, then send a
+/// new one unless the previous is still within its SLA. A failed send is
+/// recorded as the probe's outcome rather than failing the task, so the
+/// scheduler does not retry it.
+pub fn run_probe(task: &ProbeTask, now: DateTime<Utc>) -> Result<(), HealthProbeError> {
+    let send = update_state(&task.state_path, |state| {
+        state.sla_secs = task.sla_secs;
+        if let Some(run) = state.expire(now) {
+            warn!(
+                "health probe {} for {} timed out after {}s",
+                run.correlation_id, task.employee_id, task.sla_secs
+            );
+            telemetry::record_probe(run.elapsed(now), false);
+        }
+        state.pending.is_none()
+    })?;
+    if !send {
+        return Ok(());
+    }
+
+    let correlation_id = Uuid::new_v4();
+    let envelope = internal_bus::internal_envelope(
+        &task.employee_id,
+        &task.employee_id,
+        PROBE_REQUEST,
+        Vec::new(),
+        InternalCorrelation {
+            correlation_id,
+            kind: InternalMessageKind::Request,
+            requester_employee_id: task.employee_id.clone(),
+            requester_user_id: String::new(),
+            probe: true,
+        },
+        None,
+    );
+    let run = ProbeRun {
+        correlation_id,
+        sent_at: now,
+        completed_at: None,
+        outcome: None,
+        error: None,
+    };
+    match internal_bus::send(&envelope) {
+        Ok(()) => update_state(&task.state_path, |state| state.pending = Some(run)),
+        Err(err) => {
+            warn!("health probe for {} not sent: {}", task.employee_id, err);
+            telemetry::record_probe(std::time::Duration::ZERO, false);
+            update_state(&task.state_path, |state| {
+                let mut run = run.finished(now, ProbeOutcome::SendFailed);
+                run.error = Some(err.to_string());
+                state.finish(run);
+            })
+        }
+    }
+}
+
+/// Record the reply to a probe. Anything but the pending probe is ignored.
+pub fn record_reply(
+    path: &Path,
+    correlation_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Option<ProbeOutcome>, HealthProbeError> {
+    update_state(path, |state| {
+        let run = state
+            .pending
+            .take_if(|run| run.correlation_id == correlation_id)?;
+        let outcome = if state.overdue(&run, now) {
+            ProbeOutcome::Late
+        } else {
+            ProbeOutcome::Ok
+        };
+        let elapsed = run.elapsed(now);
+        info!(
+            "health probe {} answered in {}ms ({:?})",
+            correlation_id,
+            elapsed.as_millis(),
+            outcome
+        );
+        telemetry::record_probe(elapsed, outcome == ProbeOutcome::Ok);
+        state.finish(run.finished(now, outcome));
+        Some(outcome)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn pending_state(path: &Path, sent_at: DateTime<Utc>) -> Uuid {
+        let correlation_id = Uuid::new_v4();
+        update_state(path, |state| {
+            state.sla_secs = 300;
+            state.pending = Some(ProbeRun {
+                correlation_id,
+                sent_at,
+                completed_at: None,
+                outcome: None,
+                error: None,
+            });
+        })
+        .expect("save");
+        correlation_id
+    }
+
+    #[test]
+    fn reply_within_sla_marks_the_probe_healthy() {
+        let temp = TempDir::new().expect("tempdir");
+        let path = state_path(&temp.path().join("tasks.db"));
+        let sent_at = Utc::now();
+        let correlation_id = pending_state(&path, sent_at);
+
+        assert_eq!(
+            record_reply(&path, Uuid::new_v4(), sent_at).expect("unrelated"),
+            None
+        );
+        let outcome =
+            record_reply(&path, correlation_id, sent_at + Duration::seconds(20)).expect("record");
+        assert_eq!(outcome, Some(ProbeOutcome::Ok));
+
+        let state = load_state(&path).expect("load").expect("state");
+        assert!(state.pending.is_none());
+        assert_eq!(state.consecutive_failures, 0);
+        assert_eq!(state.healthy(sent_at + Duration::hours(1)), Some(true));
+    }
+
+    #[test]
+    fn unanswered_probe_turns_unhealthy_past_its_sla() {
+        let temp = TempDir::new().expect("tempdir");
+        let path = state_path(&temp.path().join("tasks.db"));
+        let sent_at = Utc::now();
+        pending_state(&path, sent_at);
+
+        let state = load_state(&path).expect("load").expect("state");
+        assert_eq!(state.healthy(sent_at + Duration::seconds(60)), None);
+        assert_eq!(state.healthy(sent_at + Duration::seconds(301)), Some(false));
+
+        let later = sent_at + Duration::seconds(600);
+        let expired = update_state(&path, |state| state.expire(later)).expect("expire");
+        assert_eq!(
+            expired.and_then(|run| run.outcome),
+            Some(ProbeOutcome::TimedOut)
+        );
+        let state = load_state(&path).expect("load").expect("state");
+        assert!(state.pending.is_none());
+        assert_eq!(state.consecutive_failures, 1);
+        assert_eq!(state.healthy(later), Some(false));
+    }
+}
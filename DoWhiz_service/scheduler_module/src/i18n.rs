@@ -0,0 +1,641 @@
+//! Localized system messages.
+//!
+//! Text the service writes to users itself, rather than through an agent run,
+//! comes from this catalog: the usage budget notice, watchdog failure
+//! notifications, the Slack install page, daily digests and slash command
+//! replies. The language is the user's `language` preference when it names
+//! a supported language, then the employee's `language` setting, then
+//! English. Templates use `{name}` placeholders filled by
+//! [`Locale::render`].
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+use crate::user_store::lookup_user_preferences;
+
+/// A language the catalog has translations for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+    Fr,
+    Zh,
+    Ja,
+}
+
+/// A system message; see the catalog functions below for the English text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    BudgetReached,
+    TaskFailureNotice,
+    SlackInstallTitle,
+    SlackInstallDescription,
+    SlackInstallHeading,
+    SlackInstallBody,
+    SlackInstallClose,
+    DigestSubject,
+    DigestInbound,
+    DigestCompleted,
+    DigestUpcoming,
+    ListMore,
+    ReminderSubject,
+    ReminderBody,
+    ReminderScheduled,
+    NeedsAgent,
+    NoTasks,
+    StatusSummary,
+    NextUp,
+    NoUpcomingTasks,
+    UpcomingTasks,
+    Now,
+    InMinutes,
+    InHours,
+    InDays,
+    HandoffNotice,
+    StopConfirmed,
+    EmailBounced,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 5] = [Locale::En, Locale::Es, Locale::Fr, Locale::Zh, Locale::Ja];
+
+    /// Parse a language code ("es", "zh-CN", "fr_FR") or name ("Spanish",
+    /// "español", "日本語").
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        let primary = value.split(['-', '_']).next().unwrap_or_default();
+        let locale = match primary {
+            "en" | "english" => Locale::En,
+            "es" | "spanish" | "español" | "espanol" => Locale::Es,
+            "fr" | "french" | "français" | "francais" => Locale::Fr,
+            "zh" | "chinese" | "mandarin" | "中文" | "简体中文" | "繁體中文" | "汉语" => {
+                Locale::Zh
+            }
+            "ja" | "japanese" | "日本語" => Locale::Ja,
+            _ => return None,
+        };
+        Some(locale)
+    }
+
+    /// First supported language of an `Accept-Language` header.
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        header
+            .split(',')
+            .filter_map(|entry| entry.split(';').next())
+            .find_map(Locale::parse)
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Es => "es",
+            Locale::Fr => "fr",
+            Locale::Zh => "zh",
+            Locale::Ja => "ja",
+        }
+    }
+
+    /// The template for `message`.
+    pub fn text(self, message: Message) -> &'static str {
+        match self {
+            Locale::En => en(message),
+            Locale::Es => es(message),
+            Locale::Fr => fr(message),
+            Locale::Zh => zh(message),
+            Locale::Ja => ja(message),
+        }
+    }
+
+    /// The template for `message` with its `{name}` placeholders filled from
+    /// `args`. Unknown placeholders are left as they are.
+    pub fn render(self, message: Message, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let template = self.text(message);
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let value = after.find('}').and_then(|end| {
+                args.iter()
+                    .find(|(name, _)| *name == &after[..end])
+                    .map(|(_, value)| (value, end))
+            });
+            match value {
+                Some((value, end)) => {
+                    out.push_str(&value.to_string());
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    out.push('{');
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// A calendar date, written the way the language usually writes it.
+    pub fn format_date(self, at: DateTime<Utc>) -> String {
+        let format = match self {
+            Locale::En => "%B %-d, %Y",
+            Locale::Es | Locale::Fr => "%d/%m/%Y",
+            Locale::Zh | Locale::Ja => "%Y年%-m月%-d日",
+        };
+        at.format(format).to_string()
+    }
+}
+
+/// The user's language if it is supported, else the employee's, else English.
+pub fn resolve_locale(user_language: Option<&str>, employee_language: Option<&str>) -> Locale {
+    user_language
+        .and_then(Locale::parse)
+        .or_else(|| employee_language.and_then(Locale::parse))
+        .unwrap_or_default()
+}
+
+/// [`resolve_locale`] with the stored preference of `user_id`.
+pub fn user_locale(user_id: &str, employee_language: Option<&str>) -> Locale {
+    let preferences = lookup_user_preferences(user_id);
+    resolve_locale(preferences.language.as_deref(), employee_language)
+}
+
+fn en(message: Message) -> &'static str {
+    match message {
+        Message::BudgetReached => {
+            "Thanks for your message! You've reached your usage budget for now, so I can't take \
+on new work until it resets. Please try again later or contact your administrator to raise the \
+limit."
+        }
+        Message::TaskFailureNotice => {
+            "Task Failure Notification
+==========================
+
+Task ID: {task_id}
+User ID: {user_id}
+Thread ID: {thread_id}
+Started at: {started_at}
+Failed at: {failed_at}
+Retry count: {retry_count} (max: {max_retries})
+
+The task has been automatically disabled after exceeding the maximum retry attempts.
+
+Possible causes:
+- The task timed out (took longer than {timeout_secs} seconds)
+- The processing service crashed or became unresponsive
+- Network or external service issues
+
+Recommended actions:
+- Check the service logs for more details
+- Try the operation again by creating a new request
+- Contact support if the issue persists
+"
+        }
+        Message::SlackInstallTitle => "DoWhiz Slack Integration | Install Complete and Next Steps",
+        Message::SlackInstallDescription => {
+            "DoWhiz Slack integration is installed. Confirm your workspace, learn next steps, and \
+start chatting with digital employees right away."
+        }
+        Message::SlackInstallHeading => "Installation Complete!",
+        Message::SlackInstallBody => {
+            "DoWhiz has been successfully installed to <strong>{team}</strong>."
+        }
+        Message::SlackInstallClose => {
+            "You can now close this window and start chatting with the bot in Slack."
+        }
+        Message::DigestSubject => "Your daily digest for {date}",
+        Message::DigestInbound => "Messages received",
+        Message::DigestCompleted => "Completed",
+        Message::DigestUpcoming => "Coming up in the next 24 hours",
+        Message::ListMore => "…and {count} more",
+        Message::ReminderSubject => "Reminder: {reminder}",
+        Message::ReminderBody => ":alarm_clock: Reminder: {reminder}",
+        Message::ReminderScheduled => "Got it. I'll remind you {when} (at {at}).",
+        Message::NeedsAgent => {
+            "That needs more than a quick answer. Mention me in a channel or send me a DM and \
+I'll take it on."
+        }
+        Message::NoTasks => "You have no scheduled tasks.",
+        Message::StatusSummary => {
+            "*Status*: {active} active task(s), {due} due now, {upcoming} in the next 7 days."
+        }
+        Message::NextUp => "Next up {when}: {label}",
+        Message::NoUpcomingTasks => "No upcoming tasks in the next 7 days.",
+        Message::UpcomingTasks => "*Upcoming tasks*",
+        Message::Now => "now",
+        Message::InMinutes => "in {count} min",
+        Message::InHours => "in {count} h",
+        Message::InDays => "in {count} days",
+        Message::HandoffNotice => {
+            "I've handed this over to {employee}, who will follow up with you by email at {email}."
+        }
+        Message::StopConfirmed => {
+            "Understood, I've stopped. I won't send anything else until you message me again."
+        }
+        Message::EmailBounced => {
+            "My email reply to {email} could not be delivered ({reason}). Please check the address, \
+or keep talking to me here."
+        }
+    }
+}
+
+fn es(message: Message) -> &'static str {
+    match message {
+        Message::BudgetReached => {
+            "¡Gracias por tu mensaje! Has alcanzado tu límite de uso por ahora, así que no puedo \
+aceptar trabajo nuevo hasta que se restablezca. Vuelve a intentarlo más tarde o pide a tu \
+administrador que aumente el límite."
+        }
+        Message::TaskFailureNotice => {
+            "Aviso de fallo de tarea
+=======================
+
+ID de tarea: {task_id}
+ID de usuario: {user_id}
+ID de hilo: {thread_id}
+Iniciada: {started_at}
+Fallida: {failed_at}
+Reintentos: {retry_count} (máximo: {max_retries})
+
+La tarea se desactivó automáticamente tras superar el número máximo de reintentos.
+
+Posibles causas:
+- La tarea superó el tiempo límite (más de {timeout_secs} segundos)
+- El servicio de procesamiento falló o dejó de responder
+- Problemas de red o de servicios externos
+
+Acciones recomendadas:
+- Revisa los registros del servicio para más detalles
+- Vuelve a intentarlo creando una nueva solicitud
+- Contacta con soporte si el problema continúa
+"
+        }
+        Message::SlackInstallTitle => {
+            "Integración de DoWhiz con Slack | Instalación completada y próximos pasos"
+        }
+        Message::SlackInstallDescription => {
+            "La integración de DoWhiz con Slack está instalada. Confirma tu espacio de trabajo, \
+consulta los próximos pasos y empieza a chatear con los empleados digitales."
+        }
+        Message::SlackInstallHeading => "¡Instalación completada!",
+        Message::SlackInstallBody => {
+            "DoWhiz se ha instalado correctamente en <strong>{team}</strong>."
+        }
+        Message::SlackInstallClose => {
+            "Ya puedes cerrar esta ventana y empezar a chatear con el bot en Slack."
+        }
+        Message::DigestSubject => "Tu resumen diario del {date}",
+        Message::DigestInbound => "Mensajes recibidos",
+        Message::DigestCompleted => "Completadas",
+        Message::DigestUpcoming => "Próximas 24 horas",
+        Message::ListMore => "…y {count} más",
+        Message::ReminderSubject => "Recordatorio: {reminder}",
+        Message::ReminderBody => ":alarm_clock: Recordatorio: {reminder}",
+        Message::ReminderScheduled => "Entendido. Te lo recordaré {when} (a las {at}).",
+        Message::NeedsAgent => {
+            "Eso necesita algo más que una respuesta rápida. Mencióname en un canal o envíame un \
+mensaje directo y me encargo."
+        }
+        Message::NoTasks => "No tienes tareas programadas.",
+        Message::StatusSummary => {
+            "*Estado*: {active} tarea(s) activa(s), {due} pendiente(s) ahora, {upcoming} en los \
+próximos 7 días."
+        }
+        Message::NextUp => "Siguiente {when}: {label}",
+        Message::NoUpcomingTasks => "No hay tareas en los próximos 7 días.",
+        Message::UpcomingTasks => "*Próximas tareas*",
+        Message::Now => "ahora",
+        Message::InMinutes => "en {count} min",
+        Message::InHours => "en {count} h",
+        Message::InDays => "en {count} días",
+        Message::HandoffNotice => "He pasado esto a {employee}, que te escribirá a {email}.",
+        Message::StopConfirmed => {
+            "Entendido, me detengo. No enviaré nada más hasta que vuelvas a escribirme."
+        }
+        Message::EmailBounced => {
+            "No se pudo entregar mi respuesta por correo a {email} ({reason}). Revisa la dirección \
+o sigue escribiéndome por aquí."
+        }
+    }
+}
+
+fn fr(message: Message) -> &'static str {
+    match message {
+        Message::BudgetReached => {
+            "Merci pour votre message ! Vous avez atteint votre limite d'utilisation pour le \
+moment, je ne peux donc pas accepter de nouveau travail avant sa réinitialisation. Réessayez plus \
+tard ou demandez à votre administrateur d'augmenter la limite."
+        }
+        Message::TaskFailureNotice => {
+            "Notification d'échec de tâche
+=============================
+
+ID de la tâche : {task_id}
+ID de l'utilisateur : {user_id}
+ID du fil : {thread_id}
+Démarrée le : {started_at}
+Échouée le : {failed_at}
+Nombre de tentatives : {retry_count} (max : {max_retries})
+
+La tâche a été désactivée automatiquement après avoir dépassé le nombre maximal de tentatives.
+
+Causes possibles :
+- La tâche a expiré (plus de {timeout_secs} secondes)
+- Le service de traitement a planté ou ne répondait plus
+- Problèmes de réseau ou de services externes
+
+Actions recommandées :
+- Consultez les journaux du service pour plus de détails
+- Réessayez en créant une nouvelle demande
+- Contactez le support si le problème persiste
+"
+        }
+        Message::SlackInstallTitle => {
+            "Intégration Slack de DoWhiz | Installation terminée et prochaines étapes"
+        }
+        Message::SlackInstallDescription => {
+            "L'intégration Slack de DoWhiz est installée. Vérifiez votre espace de travail, \
+découvrez les prochaines étapes et commencez à discuter avec vos employés numériques."
+        }
+        Message::SlackInstallHeading => "Installation terminée !",
+        Message::SlackInstallBody => "DoWhiz a bien été installé dans <strong>{team}</strong>.",
+        Message::SlackInstallClose => {
+            "Vous pouvez fermer cette fenêtre et commencer à discuter avec le bot dans Slack."
+        }
+        Message::DigestSubject => "Votre résumé quotidien du {date}",
+        Message::DigestInbound => "Messages reçus",
+        Message::DigestCompleted => "Terminées",
+        Message::DigestUpcoming => "Dans les prochaines 24 heures",
+        Message::ListMore => "…et {count} de plus",
+        Message::ReminderSubject => "Rappel : {reminder}",
+        Message::ReminderBody => ":alarm_clock: Rappel : {reminder}",
+        Message::ReminderScheduled => "C'est noté. Je vous le rappellerai {when} (à {at}).",
+        Message::NeedsAgent => {
+            "Cela demande plus qu'une réponse rapide. Mentionnez-moi dans un canal ou envoyez-moi \
+un message direct et je m'en occupe."
+        }
+        Message::NoTasks => "Vous n'avez aucune tâche planifiée.",
+        Message::StatusSummary => {
+            "*Statut* : {active} tâche(s) active(s), {due} à exécuter maintenant, {upcoming} dans \
+les 7 prochains jours."
+        }
+        Message::NextUp => "Prochaine {when} : {label}",
+        Message::NoUpcomingTasks => "Aucune tâche prévue dans les 7 prochains jours.",
+        Message::UpcomingTasks => "*Tâches à venir*",
+        Message::Now => "maintenant",
+        Message::InMinutes => "dans {count} min",
+        Message::InHours => "dans {count} h",
+        Message::InDays => "dans {count} jours",
+        Message::HandoffNotice => {
+            "J'ai confié cette demande à {employee}, qui vous répondra par e-mail à {email}."
+        }
+        Message::StopConfirmed => {
+            "C'est noté, j'arrête. Je n'enverrai plus rien avant votre prochain message."
+        }
+        Message::EmailBounced => {
+            "Ma réponse par e-mail à {email} n'a pas pu être distribuée ({reason}). Vérifiez \
+l'adresse ou continuez à m'écrire ici."
+        }
+    }
+}
+
+fn zh(message: Message) -> &'static str {
+    match message {
+        Message::BudgetReached => {
+            "感谢你的消息！你目前已达到使用额度上限，在额度重置前我无法接受新的工作。请稍后再试，\
+或联系管理员提高额度。"
+        }
+        Message::TaskFailureNotice => {
+            "任务失败通知
+============
+
+任务 ID：{task_id}
+用户 ID：{user_id}
+会话 ID：{thread_id}
+开始时间：{started_at}
+失败时间：{failed_at}
+重试次数：{retry_count}（上限：{max_retries}）
+
+该任务超过最大重试次数，已被自动停用。
+
+可能的原因：
+- 任务超时（耗时超过 {timeout_secs} 秒）
+- 处理服务崩溃或无响应
+- 网络或外部服务问题
+
+建议操作：
+- 查看服务日志了解详情
+- 重新发起请求再试一次
+- 如果问题持续，请联系支持团队
+"
+        }
+        Message::SlackInstallTitle => "DoWhiz Slack 集成 | 安装完成及后续步骤",
+        Message::SlackInstallDescription => {
+            "DoWhiz Slack 集成已安装。确认你的工作区，了解后续步骤，立即开始与数字员工聊天。"
+        }
+        Message::SlackInstallHeading => "安装完成！",
+        Message::SlackInstallBody => "DoWhiz 已成功安装到 <strong>{team}</strong>。",
+        Message::SlackInstallClose => "现在可以关闭此窗口，在 Slack 中开始与机器人聊天。",
+        Message::DigestSubject => "{date} 每日摘要",
+        Message::DigestInbound => "收到的消息",
+        Message::DigestCompleted => "已完成",
+        Message::DigestUpcoming => "未来 24 小时",
+        Message::ListMore => "……还有 {count} 项",
+        Message::ReminderSubject => "提醒：{reminder}",
+        Message::ReminderBody => ":alarm_clock: 提醒：{reminder}",
+        Message::ReminderScheduled => "好的，我会{when}提醒你（{at}）。",
+        Message::NeedsAgent => {
+            "这个问题需要的不只是快速回答。请在频道中提及我或给我发私信，我来处理。"
+        }
+        Message::NoTasks => "你没有已安排的任务。",
+        Message::StatusSummary => {
+            "*状态*：{active} 个进行中的任务，{due} 个现在到期，{upcoming} 个在未来 7 天内。"
+        }
+        Message::NextUp => "下一个（{when}）：{label}",
+        Message::NoUpcomingTasks => "未来 7 天内没有任务。",
+        Message::UpcomingTasks => "*即将执行的任务*",
+        Message::Now => "现在",
+        Message::InMinutes => "{count} 分钟后",
+        Message::InHours => "{count} 小时后",
+        Message::InDays => "{count} 天后",
+        Message::HandoffNotice => "我已将此事转交给 {employee}，对方会通过邮箱 {email} 与你联系。",
+        Message::StopConfirmed => "好的，已停止。在你再次给我发消息之前，我不会再发送任何内容。",
+        Message::EmailBounced => {
+            "我发往 {email} 的邮件回复无法送达（{reason}）。请检查邮箱地址，或继续在这里和我沟通。"
+        }
+    }
+}
+
+fn ja(message: Message) -> &'static str {
+    match message {
+        Message::BudgetReached => {
+            "メッセージありがとうございます。現在、利用上限に達しているため、上限がリセットされる\
+まで新しい作業を受け付けられません。しばらくしてから再度お試しいただくか、管理者に上限の引き上げを\
+ご依頼ください。"
+        }
+        Message::TaskFailureNotice => {
+            "タスク失敗のお知らせ
+====================
+
+タスク ID：{task_id}
+ユーザー ID：{user_id}
+スレッド ID：{thread_id}
+開始日時：{started_at}
+失敗日時：{failed_at}
+再試行回数：{retry_count}（上限：{max_retries}）
+
+最大再試行回数を超えたため、このタスクは自動的に無効化されました。
+
+考えられる原因：
+- タスクがタイムアウトした（{timeout_secs} 秒を超過）
+- 処理サービスがクラッシュした、または応答しなくなった
+- ネットワークまたは外部サービスの問題
+
+推奨される対応：
+- 詳細はサービスのログを確認してください
+- 新しいリクエストを作成して再度お試しください
+- 問題が解決しない場合はサポートにお問い合わせください
+"
+        }
+        Message::SlackInstallTitle => "DoWhiz Slack 連携 | インストール完了と次のステップ",
+        Message::SlackInstallDescription => {
+            "DoWhiz の Slack 連携がインストールされました。ワークスペースと次のステップを確認して、\
+すぐにデジタル社員とのチャットを始めましょう。"
+        }
+        Message::SlackInstallHeading => "インストール完了！",
+        Message::SlackInstallBody => {
+            "DoWhiz を <strong>{team}</strong> に正常にインストールしました。"
+        }
+        Message::SlackInstallClose => {
+            "このウィンドウを閉じて、Slack でボットとのチャットを始めてください。"
+        }
+        Message::DigestSubject => "{date} のデイリーダイジェスト",
+        Message::DigestInbound => "受信したメッセージ",
+        Message::DigestCompleted => "完了",
+        Message::DigestUpcoming => "今後 24 時間の予定",
+        Message::ListMore => "…ほか {count} 件",
+        Message::ReminderSubject => "リマインダー：{reminder}",
+        Message::ReminderBody => ":alarm_clock: リマインダー：{reminder}",
+        Message::ReminderScheduled => "承知しました。{when}にお知らせします（{at}）。",
+        Message::NeedsAgent => {
+            "この内容は簡単な回答では対応できません。チャンネルでメンションするか、DM を送って\
+ください。対応します。"
+        }
+        Message::NoTasks => "予定されているタスクはありません。",
+        Message::StatusSummary => {
+            "*ステータス*：有効なタスク {active} 件、実行待ち {due} 件、今後 7 日間 {upcoming} 件。"
+        }
+        Message::NextUp => "次のタスク（{when}）：{label}",
+        Message::NoUpcomingTasks => "今後 7 日間のタスクはありません。",
+        Message::UpcomingTasks => "*今後のタスク*",
+        Message::Now => "今すぐ",
+        Message::InMinutes => "{count} 分後",
+        Message::InHours => "{count} 時間後",
+        Message::InDays => "{count} 日後",
+        Message::HandoffNotice => {
+            "この件は {employee} に引き継ぎました。{email} 宛てにメールでご連絡します。"
+        }
+        Message::StopConfirmed => {
+            "承知しました。停止しました。次にメッセージをいただくまで、こちらからは何も送信しません。"
+        }
+        Message::EmailBounced => {
+            "{email} へのメールでの返信を配信できませんでした（{reason}）。アドレスをご確認いただくか、\
+引き続きこちらでメッセージをお送りください。"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    const MESSAGES: [Message; 28] = [
+        Message::BudgetReached,
+        Message::TaskFailureNotice,
+        Message::SlackInstallTitle,
+        Message::SlackInstallDescription,
+        Message::SlackInstallHeading,
+        Message::SlackInstallBody,
+        Message::SlackInstallClose,
+        Message::DigestSubject,
+        Message::DigestInbound,
+        Message::DigestCompleted,
+        Message::DigestUpcoming,
+        Message::ListMore,
+        Message::ReminderSubject,
+        Message::ReminderBody,
+        Message::ReminderScheduled,
+        Message::NeedsAgent,
+        Message::NoTasks,
+        Message::StatusSummary,
+        Message::NextUp,
+        Message::NoUpcomingTasks,
+        Message::UpcomingTasks,
+        Message::Now,
+        Message::InMinutes,
+        Message::InHours,
+        Message::InDays,
+        Message::HandoffNotice,
+        Message::StopConfirmed,
+        Message::EmailBounced,
+    ];
+
+    fn placeholders(template: &str) -> BTreeSet<&str> {
+        template
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn every_translation_fills_the_same_placeholders() {
+        for message in MESSAGES {
+            let english = placeholders(Locale::En.text(message));
+            for locale in Locale::ALL {
+                let text = locale.text(message);
+                assert!(!text.trim().is_empty(), "{:?} {:?}", locale, message);
+                assert_eq!(
+                    placeholders(text),
+                    english,
+                    "{:?} {:?} placeholders",
+                    locale,
+                    message
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn languages_resolve_from_codes_and_names() {
+        assert_eq!(Locale::parse("es-MX"), Some(Locale::Es));
+        assert_eq!(Locale::parse(" French "), Some(Locale::Fr));
+        assert_eq!(Locale::parse("zh_CN"), Some(Locale::Zh));
+        assert_eq!(Locale::parse("日本語"), Some(Locale::Ja));
+        assert_eq!(Locale::parse("Klingon"), None);
+        assert_eq!(
+            Locale::from_accept_language("de-DE,de;q=0.9,fr;q=0.8,en;q=0.7"),
+            Some(Locale::Fr)
+        );
+
+        assert_eq!(resolve_locale(Some("Spanish"), Some("ja")), Locale::Es);
+        assert_eq!(resolve_locale(Some("Klingon"), Some("ja")), Locale::Ja);
+        assert_eq!(resolve_locale(None, None), Locale::En);
+    }
+
+    #[test]
+    fn render_fills_placeholders_once() {
+        let text = Locale::Es.render(
+            Message::ReminderScheduled,
+            &[("when", &"en 5 min"), ("at", &"{when}")],
+        );
+        assert_eq!(text, "Entendido. Te lo recordaré en 5 min (a las {when}).");
+        assert_eq!(
+            Locale::En.render(Message::ListMore, &[]),
+            "…and {count} more"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! Replay protection for inbound webhooks, checked by the gateway before enqueue.
+//!
+//! Providers redeliver the same event under different identity rules: Slack
+//! retries reuse the `event_id`, Postmark redeliveries reuse the email
+//! `Message-ID`, and Telegram replays reuse a `message_id` that is only unique
+//! within one chat. Entries are partitioned by channel, scoped to the receiving
+//! employee, and expire after a channel-specific window so the collection stays
+//! bounded.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use mongodb::bson::{doc, DateTime as BsonDateTime, Document};
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::sync::Collection;
+
+use crate::channel::Channel;
+use crate::ingestion::IngestionEnvelope;
+use crate::mongo_store::{create_client_from_env, database_from_env};
+
+const HOUR_SECS: i64 = 60 * 60;
+
+/// How long a delivery is remembered for `channel`.
+///
+/// Slack gives up retrying within minutes, Telegram keeps undelivered updates
+/// for 24 hours, and mail can be redelivered (or re-forwarded) days later.
+pub fn dedupe_window(channel: Channel) -> chrono::Duration {
+    let secs = match channel {
+        Channel::Slack | Channel::Discord => HOUR_SECS,
+        Channel::Email => 7 * 24 * HOUR_SECS,
+        _ => 24 * HOUR_SECS,
+    };
+    chrono::Duration::seconds(secs)
+}
+
+/// The provider-level identity of an inbound delivery, or `None` when the
+/// provider gave no stable ID (those fall back to the queue's payload hash).
+pub fn dedupe_identity(envelope: &IngestionEnvelope) -> Option<String> {
+    let id = envelope
+        .external_message_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())?;
+    let identity = match envelope.channel {
+        // Message-IDs are case-insensitive and often arrive with or without brackets.
+        Channel::Email => id
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .to_ascii_lowercase(),
+        Channel::Telegram => match envelope.payload.metadata.telegram_chat_id {
+            Some(chat_id) if !id.starts_with(&format!("{}:", chat_id)) => {
+                format!("{}:{}", chat_id, id)
+            }
+            _ => id.to_string(),
+        },
+        _ => id.to_string(),
+    };
+    Some(format!("{}:{}", envelope.employee_id, identity))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InboundDedupeError {
+    #[error("mongodb error: {0}")]
+    Mongo(#[from] mongodb::error::Error),
+    #[error("mongo config error: {0}")]
+    MongoConfig(String),
+}
+
+/// Channel-partitioned record of recently accepted inbound deliveries.
+#[derive(Debug, Clone)]
+pub struct InboundDedupeStore {
+    entries: Collection<Document>,
+}
+
+impl InboundDedupeStore {
+    pub fn new() -> Result<Self, InboundDedupeError> {
+        let client = create_client_from_env()
+            .map_err(|err| InboundDedupeError::MongoConfig(err.to_string()))?;
+        let db = database_from_env(&client);
+        let entries = db.collection::<Document>("inbound_dedupe");
+        Ok(Self { entries })
+    }
+
+    /// Record `envelope` as seen. Returns false when the same delivery was
+    /// already accepted inside its channel's window.
+    pub fn claim(
+        &self,
+        envelope: &IngestionEnvelope,
+        now: DateTime<Utc>,
+    ) -> Result<bool, InboundDedupeError> {
+        let Some(key) = dedupe_identity(envelope) else {
+            return Ok(true);
+        };
+        let channel = envelope.channel.to_string();
+        let expires_at = BsonDateTime::from_chrono(now + dedupe_window(envelope.channel));
+        let seen_at = BsonDateTime::from_chrono(now);
+        let insert = self.entries.insert_one(
+            doc! {
+                "channel": &channel,
+                "key": &key,
+                "seen_at": seen_at,
+                "expires_at": expires_at,
+            },
+            None,
+        );
+        match insert {
+            Ok(_) => Ok(true),
+            Err(err) if is_duplicate_key(&err) => {
+                // Mongo's TTL monitor only runs about once a minute, so an expired
+                // row can still be present; take it over instead of rejecting.
+                let result = self.entries.update_one(
+                    doc! {
+                        "channel": &channel,
+                        "key": &key,
+                        "expires_at": { "$lte": seen_at },
+                    },
+                    doc! { "$set": { "seen_at": seen_at, "expires_at": expires_at } },
+                    None,
+                )?;
+                Ok(result.modified_count > 0)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Forget a claim, so a provider retry is accepted after a failed enqueue.
+    pub fn release(&self, envelope: &IngestionEnvelope) -> Result<(), InboundDedupeError> {
+        let Some(key) = dedupe_identity(envelope) else {
+            return Ok(());
+        };
+        self.entries.delete_one(
+            doc! { "channel": envelope.channel.to_string(), "key": key },
+            None,
+        )?;
+        Ok(())
+    }
+}
+
+fn is_duplicate_key(err: &mongodb::error::Error) -> bool {
+    matches!(
+        err.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) if write_error.code == 11000
+    )
+}
+
+static INBOUND_DEDUPE_STORE: std::sync::OnceLock<Option<Arc<InboundDedupeStore>>> =
+    std::sync::OnceLock::new();
+
+/// Get or initialize the global InboundDedupeStore (returns None if not configured)
+pub fn get_global_inbound_dedupe_store() -> Option<Arc<InboundDedupeStore>> {
+    INBOUND_DEDUPE_STORE
+        .get_or_init(|| match InboundDedupeStore::new() {
+            Ok(store) => Some(Arc::new(store)),
+            Err(err) => {
+                tracing::warn!(
+                    "InboundDedupeStore not available ({}), relying on queue dedupe only",
+                    err
+                );
+                None
+            }
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::ChannelMetadata;
+    use crate::ingestion::IngestionPayload;
+    use uuid::Uuid;
+
+    fn envelope(channel: Channel, external_id: Option<&str>) -> IngestionEnvelope {
+        IngestionEnvelope {
+            envelope_id: Uuid::new_v4(),
+            received_at: Utc::now(),
+            tenant_id: None,
+            employee_id: "little_bear".to_string(),
+            channel,
+            external_message_id: external_id.map(str::to_string),
+            dedupe_key: "dedupe".to_string(),
+            payload: IngestionPayload {
+                sender: "sender".to_string(),
+                sender_name: None,
+                recipient: "recipient".to_string(),
+                subject: None,
+                text_body: None,
+                html_body: None,
+                thread_id: "thread".to_string(),
+                message_id: None,
+                attachments: Vec::new(),
+                reply_to: Vec::new(),
+                metadata: ChannelMetadata::default(),
+            },
+            raw_payload_ref: None,
+            account_id: None,
+            trace_id: None,
+            handoff: None,
+        }
+    }
+
+    #[test]
+    fn email_identity_ignores_brackets_and_case() {
+        let bracketed = envelope(Channel::Email, Some("<ABC@Mail.Example.com>"));
+        let bare = envelope(Channel::Email, Some("abc@mail.example.com"));
+        assert_eq!(dedupe_identity(&bracketed), dedupe_identity(&bare));
+        assert_eq!(
+            dedupe_identity(&bare).as_deref(),
+            Some("little_bear:abc@mail.example.com")
+        );
+    }
+
+    #[test]
+    fn telegram_identity_is_scoped_to_chat() {
+        let mut first = envelope(Channel::Telegram, Some("5"));
+        first.payload.metadata.telegram_chat_id = Some(100);
+        let mut second = envelope(Channel::Telegram, Some("5"));
+        second.payload.metadata.telegram_chat_id = Some(200);
+        assert_ne!(dedupe_identity(&first), dedupe_identity(&second));
+
+        let mut prefixed = envelope(Channel::Telegram, Some("100:5"));
+        prefixed.payload.metadata.telegram_chat_id = Some(100);
+        assert_eq!(dedupe_identity(&prefixed), dedupe_identity(&first));
+    }
+
+    #[test]
+    fn missing_or_blank_ids_are_not_deduped() {
+        assert_eq!(dedupe_identity(&envelope(Channel::Slack, None)), None);
+        assert_eq!(dedupe_identity(&envelope(Channel::Slack, Some("  "))), None);
+    }
+
+    #[test]
+    fn windows_follow_provider_retry_behaviour() {
+        assert_eq!(dedupe_window(Channel::Slack), chrono::Duration::hours(1));
+        assert_eq!(
+            dedupe_window(Channel::Telegram),
+            chrono::Duration::hours(24)
+        );
+        assert_eq!(dedupe_window(Channel::Email), chrono::Duration::days(7));
+    }
+}
@@ -0,0 +1,224 @@
+//! Per-employee limits on who may reach an employee, and how often.
+//!
+//! Configured under `[employees.inbound_policy]` in `employee.toml` and
+//! enforced by the ingestion consumer before a message gets a workspace.
+//! An empty policy lets everyone in, subject only to the service-wide
+//! per-sender cap. The same table sets how long a chat thread's run waits
+//! for follow-up messages; see [`InboundPolicy::coalesce_window`].
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::channel::Channel;
+
+/// Raw `[employees.inbound_policy]` table.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct InboundPolicyConfig {
+    /// Most messages one sender may send per hour; `0` lifts the cap.
+    /// Defaults to `INBOUND_SENDER_MAX_PER_HOUR`.
+    #[serde(default)]
+    pub max_messages_per_hour: Option<usize>,
+    /// Who may start a conversation: `open` (default), `allowlist`, or
+    /// `approval` to ask the approver about each new sender.
+    #[serde(default)]
+    pub new_contacts: Option<String>,
+    /// Always let in: email addresses, `@domain`, phone numbers or chat IDs.
+    #[serde(default)]
+    pub allowed_senders: Vec<String>,
+    /// Seconds a chat message's run waits for more messages of its thread;
+    /// `0` starts it at once. Defaults to `INBOUND_COALESCE_SECS`.
+    #[serde(default)]
+    pub coalesce_secs: Option<u64>,
+    /// Per-channel `coalesce_secs`, e.g. `{ slack = 5, sms = 20 }`.
+    #[serde(default)]
+    pub coalesce_channels: HashMap<String, u64>,
+    /// Longest a burst may hold back its run, counted from its first
+    /// message. Defaults to `INBOUND_COALESCE_MAX_SECS`.
+    #[serde(default)]
+    pub coalesce_max_secs: Option<u64>,
+}
+
+/// How senders the employee has not heard from before are treated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NewContacts {
+    #[default]
+    Open,
+    /// Only `allowed_senders` and known contacts get through.
+    Allowlist,
+    /// A new sender's first message waits for the approver.
+    Approval,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InboundPolicy {
+    pub max_messages_per_hour: Option<usize>,
+    pub new_contacts: NewContacts,
+    pub allowed_senders: Vec<String>,
+    pub coalesce: Option<Duration>,
+    pub coalesce_channels: HashMap<Channel, Duration>,
+    pub coalesce_max: Option<Duration>,
+}
+
+impl InboundPolicy {
+    pub fn from_config(config: &InboundPolicyConfig) -> Result<Self, String> {
+        let new_contacts = match config
+            .new_contacts
+            .as_deref()
+            .map(|value| value.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("open") => NewContacts::Open,
+            Some("allowlist") => NewContacts::Allowlist,
+            Some("approval") => NewContacts::Approval,
+            Some(other) => return Err(format!("unknown new_contacts mode: {}", other)),
+        };
+        let mut coalesce_channels = HashMap::new();
+        for (channel, secs) in &config.coalesce_channels {
+            let channel = channel
+                .parse::<Channel>()
+                .map_err(|err| format!("coalesce_channels: {}", err))?;
+            coalesce_channels.insert(channel, Duration::from_secs(*secs));
+        }
+        Ok(Self {
+            max_messages_per_hour: config.max_messages_per_hour,
+            new_contacts,
+            allowed_senders: config
+                .allowed_senders
+                .iter()
+                .map(|value| normalize_sender(value))
+                .filter(|value| !value.is_empty())
+                .collect(),
+            coalesce: config.coalesce_secs.map(Duration::from_secs),
+            coalesce_channels,
+            coalesce_max: config.coalesce_max_secs.map(Duration::from_secs),
+        })
+    }
+
+    /// How long a run for a message on `channel` waits for more messages:
+    /// the channel's setting, else the employee's; `None` leaves it to the
+    /// service default.
+    pub fn coalesce_window(&self, channel: Channel) -> Option<Duration> {
+        self.coalesce_channels
+            .get(&channel)
+            .copied()
+            .or(self.coalesce)
+    }
+
+    /// Whether `sender` is on the allow list.
+    pub fn allows(&self, sender: &str) -> bool {
+        let sender = normalize_sender(sender);
+        if sender.is_empty() {
+            return false;
+        }
+        let domain = sender
+            .contains('@')
+            .then(|| sender.rsplit('@').next().unwrap_or_default());
+        self.allowed_senders.iter().any(|entry| {
+            *entry == sender
+                || entry.strip_prefix('@').is_some_and(|allowed| {
+                    domain.is_some_and(|domain| {
+                        domain == allowed
+                            || domain
+                                .strip_suffix(allowed)
+                                .is_some_and(|prefix| prefix.ends_with('.'))
+                    })
+                })
+        })
+    }
+}
+
+/// Lowercased bare address for emails (`Name <addr>` included), digits for
+/// phone numbers, and the trimmed value for anything else.
+pub fn normalize_sender(value: &str) -> String {
+    let value = value.trim().to_ascii_lowercase();
+    let address = match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => value[start + 1..end].trim(),
+        _ => value.as_str(),
+    };
+    if address.contains('@') {
+        return address.to_string();
+    }
+    let digits = phone_digits(address);
+    let looks_like_phone = !digits.is_empty()
+        && address
+            .chars()
+            .all(|ch| ch.is_ascii_digit() || "+-() .".contains(ch));
+    if looks_like_phone {
+        digits
+    } else {
+        address.to_string()
+    }
+}
+
+fn phone_digits(value: &str) -> String {
+    value.chars().filter(char::is_ascii_digit).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(new_contacts: &str, allowed: &[&str]) -> InboundPolicy {
+        InboundPolicy::from_config(&InboundPolicyConfig {
+            new_contacts: Some(new_contacts.to_string()),
+            allowed_senders: allowed.iter().map(|value| value.to_string()).collect(),
+            ..Default::default()
+        })
+        .expect("valid policy")
+    }
+
+    #[test]
+    fn allow_list_matches_addresses_domains_numbers_and_ids() {
+        let policy = policy(
+            "allowlist",
+            &["Ann@Acme.com", "@partner.io", "+1 (555) 010-0100", "U123"],
+        );
+        assert_eq!(policy.new_contacts, NewContacts::Allowlist);
+        assert!(policy.allows("Ann Lee <ann@acme.com>"));
+        assert!(!policy.allows("bob@acme.com"));
+        assert!(policy.allows("x@eu.partner.io"));
+        assert!(!policy.allows("x@notpartner.io"));
+        assert!(policy.allows("+15550100100"));
+        assert!(policy.allows("u123"));
+        assert!(!policy.allows(""));
+    }
+
+    #[test]
+    fn channel_coalesce_window_overrides_the_employee_one() {
+        let policy = InboundPolicy::from_config(&InboundPolicyConfig {
+            coalesce_secs: Some(10),
+            coalesce_channels: HashMap::from([("slack".to_string(), 3)]),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            policy.coalesce_window(Channel::Slack),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            policy.coalesce_window(Channel::Sms),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(InboundPolicy::default().coalesce_window(Channel::Sms), None);
+        assert!(InboundPolicy::from_config(&InboundPolicyConfig {
+            coalesce_channels: HashMap::from([("pager".to_string(), 3)]),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn unknown_mode_is_rejected() {
+        assert!(InboundPolicy::from_config(&InboundPolicyConfig {
+            new_contacts: Some("closed".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+        assert_eq!(
+            InboundPolicy::from_config(&InboundPolicyConfig::default()).unwrap(),
+            InboundPolicy::default()
+        );
+    }
+}
@@ -1,21 +1,40 @@
-use chrono::{DateTime, Utc};
-use mongodb::bson::{doc, DateTime as BsonDateTime, Document};
+use chrono::{DateTime, TimeZone, Utc};
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, Bson, DateTime as BsonDateTime, Document};
 use mongodb::error::ErrorKind;
 use mongodb::options::FindOptions;
-use mongodb::options::IndexOptions;
 use mongodb::options::UpdateOptions;
 use mongodb::sync::Collection;
-use mongodb::IndexModel;
-use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
 use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 use tracing::warn;
 
-use crate::mongo_store::{create_client_from_env, database_from_env, ensure_index_compatible};
+use crate::mongo_store::{create_client_from_env, database_from_env};
+use crate::scheduler::{task_kind_channel, task_kind_label};
 use crate::{Schedule, ScheduledTask};
 
+/// How long incremental syncs are trusted before a user's rows are rewritten.
+const DEFAULT_FULL_RECONCILE_SECS: u64 = 600;
+const DEFAULT_QUERY_LIMIT: usize = 100;
+const MAX_QUERY_LIMIT: usize = 1000;
+
 #[derive(Debug)]
 pub struct IndexStore {
     mongo: MongoIndexStore,
+    synced: Mutex<HashMap<String, SyncedUser>>,
+    full_reconcile_interval: Duration,
+}
+
+/// Rows this process last wrote for a user, used to turn `sync_user_tasks`
+/// into an incremental update.
+#[derive(Debug)]
+struct SyncedUser {
+    rows: BTreeMap<String, IndexRow>,
+    reconciled_at: Instant,
 }
 
 #[derive(Debug, Clone)]
@@ -29,6 +48,61 @@ pub struct TaskRef {
     pub user_id: String,
 }
 
+/// Filters for [`IndexStore::query_tasks`]. Unset fields match every row.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TaskIndexQuery {
+    pub user_id: Option<String>,
+    /// Channel name, e.g. "email" or "slack".
+    pub channel: Option<String>,
+    /// Task kind, e.g. "run_task" or "send_email".
+    pub kind: Option<String>,
+    pub enabled: Option<bool>,
+    /// Only rows whose next run is at or after this instant.
+    pub next_run_from: Option<DateTime<Utc>>,
+    /// Only rows whose next run is before this instant.
+    pub next_run_to: Option<DateTime<Utc>>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl TaskIndexQuery {
+    fn limit(&self) -> usize {
+        self.limit
+            .unwrap_or(DEFAULT_QUERY_LIMIT)
+            .clamp(1, MAX_QUERY_LIMIT)
+    }
+}
+
+/// One task index row. For a disabled task `next_run` is the run it last
+/// had scheduled.
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexedTask {
+    pub user_id: String,
+    pub task_id: String,
+    pub kind: String,
+    pub channel: String,
+    pub enabled: bool,
+    pub next_run: DateTime<Utc>,
+}
+
+/// Rows ordered by next run; `next_cursor` is set while more rows match.
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexedTaskPage {
+    pub tasks: Vec<IndexedTask>,
+    pub next_cursor: Option<String>,
+}
+
+/// Heartbeat whose indexed deadline has passed without the task running.
+#[derive(Debug, Clone)]
+pub struct MissedHeartbeat {
+    pub task_id: String,
+    pub user_id: String,
+    pub name: String,
+    pub due_at: DateTime<Utc>,
+    pub deadline: DateTime<Utc>,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum IndexStoreError {
     #[error("mongodb error: {0}")]
@@ -37,21 +111,120 @@ pub enum IndexStoreError {
     Io(#[from] std::io::Error),
     #[error("mongo config error: {0}")]
     MongoConfig(String),
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(String),
 }
 
 impl IndexStore {
     pub fn new(_path: impl Into<PathBuf>) -> Result<Self, IndexStoreError> {
+        let full_reconcile_secs = env::var("TASK_INDEX_FULL_RECONCILE_SECS")
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .unwrap_or(DEFAULT_FULL_RECONCILE_SECS);
         Ok(Self {
             mongo: MongoIndexStore::new()?,
+            synced: Mutex::new(HashMap::new()),
+            full_reconcile_interval: Duration::from_secs(full_reconcile_secs),
         })
     }
 
+    /// Bring the user's index rows in line with `tasks`.
+    ///
+    /// Only rows that changed since the last sync from this process are
+    /// written. The first sync for a user, and any sync after
+    /// `TASK_INDEX_FULL_RECONCILE_SECS`, falls back to [`Self::reconcile_user_tasks`]
+    /// so rows touched by other processes converge.
     pub fn sync_user_tasks(
         &self,
         user_id: &str,
         tasks: &[ScheduledTask],
     ) -> Result<(), IndexStoreError> {
-        self.mongo.sync_user_tasks(user_id, tasks)
+        let rows = task_rows(tasks);
+        let previous = {
+            let mut synced = self.synced_users();
+            match synced.get(user_id) {
+                Some(entry) if entry.reconciled_at.elapsed() < self.full_reconcile_interval => {
+                    Some(entry.rows.clone())
+                }
+                _ => {
+                    synced.remove(user_id);
+                    None
+                }
+            }
+        };
+        let Some(previous) = previous else {
+            return self.reconcile_rows(user_id, rows);
+        };
+
+        let result = self.mongo.apply_diff(user_id, &previous, &rows);
+        let mut synced = self.synced_users();
+        match result {
+            Ok(()) => {
+                if let Some(entry) = synced.get_mut(user_id) {
+                    entry.rows = rows;
+                }
+            }
+            // The cached view may no longer match Mongo; force a full rewrite next time.
+            Err(_) => {
+                synced.remove(user_id);
+            }
+        }
+        result
+    }
+
+    /// Rewrite every index row for the user from `tasks`.
+    pub fn reconcile_user_tasks(
+        &self,
+        user_id: &str,
+        tasks: &[ScheduledTask],
+    ) -> Result<(), IndexStoreError> {
+        self.reconcile_rows(user_id, task_rows(tasks))
+    }
+
+    /// Index (or re-index) a single task.
+    pub fn upsert_task_ref(
+        &self,
+        user_id: &str,
+        task: &ScheduledTask,
+    ) -> Result<(), IndexStoreError> {
+        let task_id = task.id.to_string();
+        let row = index_row(task);
+        self.mongo.upsert_row(user_id, &task_id, &row)?;
+        if let Some(entry) = self.synced_users().get_mut(user_id) {
+            entry.rows.insert(task_id, row);
+        }
+        Ok(())
+    }
+
+    /// Drop a single task from the index.
+    pub fn remove_task_ref(&self, user_id: &str, task_id: &str) -> Result<(), IndexStoreError> {
+        self.mongo.remove_rows(user_id, &[task_id.to_string()])?;
+        if let Some(entry) = self.synced_users().get_mut(user_id) {
+            entry.rows.remove(task_id);
+        }
+        Ok(())
+    }
+
+    fn reconcile_rows(
+        &self,
+        user_id: &str,
+        rows: BTreeMap<String, IndexRow>,
+    ) -> Result<(), IndexStoreError> {
+        self.mongo.replace_user_rows(user_id, &rows)?;
+        self.synced_users().insert(
+            user_id.to_string(),
+            SyncedUser {
+                rows,
+                reconciled_at: Instant::now(),
+            },
+        );
+        Ok(())
+    }
+
+    fn synced_users(&self) -> MutexGuard<'_, HashMap<String, SyncedUser>> {
+        self.synced
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner())
     }
 
     pub fn due_user_ids(
@@ -69,6 +242,48 @@ impl IndexStore {
     ) -> Result<Vec<TaskRef>, IndexStoreError> {
         self.mongo.due_task_refs(now, limit)
     }
+
+    /// Heartbeats past their deadline that have not been alerted on yet.
+    pub fn missed_heartbeats(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<MissedHeartbeat>, IndexStoreError> {
+        self.mongo.missed_heartbeats(now, limit)
+    }
+
+    /// Indexed next run of each of the user's enabled tasks, as the due-task
+    /// poller sees it.
+    pub fn user_next_runs(
+        &self,
+        user_id: &str,
+    ) -> Result<HashMap<String, DateTime<Utc>>, IndexStoreError> {
+        self.mongo.user_next_runs(user_id)
+    }
+
+    /// Record that `missed` was alerted so it is not reported again. A later
+    /// run moves the deadline, which re-arms the heartbeat.
+    pub fn mark_heartbeat_alerted(&self, missed: &MissedHeartbeat) -> Result<(), IndexStoreError> {
+        self.mongo.mark_heartbeat_alerted(missed)
+    }
+
+    /// Every user with at least one index row.
+    pub fn indexed_user_ids(&self) -> Result<Vec<String>, IndexStoreError> {
+        self.mongo.indexed_user_ids()
+    }
+
+    /// Whether each of the user's index rows is enabled, by task ID.
+    pub fn indexed_task_states(
+        &self,
+        user_id: &str,
+    ) -> Result<HashMap<String, bool>, IndexStoreError> {
+        self.mongo.indexed_task_states(user_id)
+    }
+
+    /// One page of the rows matching `query`, ordered by next run.
+    pub fn query_tasks(&self, query: &TaskIndexQuery) -> Result<IndexedTaskPage, IndexStoreError> {
+        self.mongo.query_tasks(query)
+    }
 }
 
 impl MongoIndexStore {
@@ -77,66 +292,88 @@ impl MongoIndexStore {
             .map_err(|err| IndexStoreError::MongoConfig(err.to_string()))?;
         let db = database_from_env(&client);
         let task_index = db.collection::<Document>("task_index");
-        // user_id must come first for sharded collections (Cosmos DB shard key compatibility)
-        ensure_index_compatible(
-            &task_index,
-            IndexModel::builder()
-                .keys(doc! { "user_id": 1, "task_id": 1 })
-                .options(IndexOptions::builder().unique(Some(true)).build())
-                .build(),
-        )?;
-        ensure_index_compatible(
-            &task_index,
-            IndexModel::builder()
-                .keys(doc! { "enabled": 1, "next_run": 1 })
-                .build(),
-        )?;
         Ok(Self { task_index })
     }
 
-    fn sync_user_tasks(
+    /// Rewrite the user's rows, writing only those that differ from what is
+    /// stored. Rows of disabled tasks pile up, so rewriting all of them on
+    /// every reconcile would not scale.
+    fn replace_user_rows(
         &self,
         user_id: &str,
-        tasks: &[ScheduledTask],
+        rows: &BTreeMap<String, IndexRow>,
     ) -> Result<(), IndexStoreError> {
-        let task_rows = enabled_task_next_runs(tasks);
-        let task_ids: Vec<String> = task_rows
-            .iter()
-            .map(|(task_id, _)| task_id.clone())
-            .collect();
+        let mut stored = BTreeMap::new();
+        for row in self.task_index.find(doc! { "user_id": user_id }, None)? {
+            let doc = row?;
+            if let Ok(task_id) = doc.get_str("task_id") {
+                // Rows written before kind and channel were indexed are
+                // unreadable here and get rewritten.
+                let row = stored_row(&doc).unwrap_or_else(IndexRow::unreadable);
+                stored.insert(task_id.to_string(), row);
+            }
+        }
+        self.apply_diff(user_id, &stored, rows)
+    }
+
+    fn upsert_row(
+        &self,
+        user_id: &str,
+        task_id: &str,
+        row: &IndexRow,
+    ) -> Result<(), IndexStoreError> {
+        let (heartbeat_name, heartbeat_deadline) = match &row.heartbeat {
+            Some((name, deadline)) => (
+                Bson::String(name.clone()),
+                Bson::DateTime(BsonDateTime::from_chrono(*deadline)),
+            ),
+            None => (Bson::Null, Bson::Null),
+        };
+        self.task_index.update_one(
+            doc! { "task_id": task_id, "user_id": user_id },
+            doc! {
+                "$set": {
+                    "next_run": BsonDateTime::from_chrono(row.next_run),
+                    "enabled": row.enabled,
+                    "kind": &row.kind,
+                    "channel": &row.channel,
+                    "heartbeat_name": heartbeat_name,
+                    "heartbeat_deadline": heartbeat_deadline,
+                },
+                "$setOnInsert": {
+                    "task_id": task_id,
+                    "user_id": user_id,
+                },
+            },
+            UpdateOptions::builder().upsert(Some(true)).build(),
+        )?;
+        Ok(())
+    }
 
-        if task_ids.is_empty() {
-            self.task_index
-                .delete_many(doc! { "user_id": user_id }, None)?;
-            return Ok(());
+    fn apply_diff(
+        &self,
+        user_id: &str,
+        previous: &BTreeMap<String, IndexRow>,
+        current: &BTreeMap<String, IndexRow>,
+    ) -> Result<(), IndexStoreError> {
+        let (upserts, removals) = diff_rows(previous, current);
+        if !removals.is_empty() {
+            self.remove_rows(user_id, &removals)?;
+        }
+        for (task_id, row) in &upserts {
+            self.upsert_row(user_id, task_id, row)?;
         }
+        Ok(())
+    }
 
+    fn remove_rows(&self, user_id: &str, task_ids: &[String]) -> Result<(), IndexStoreError> {
         self.task_index.delete_many(
             doc! {
                 "user_id": user_id,
-                "task_id": { "$nin": task_ids.clone() },
+                "task_id": { "$in": task_ids },
             },
             None,
         )?;
-
-        let options = UpdateOptions::builder().upsert(Some(true)).build();
-        for (task_id, next_run) in task_rows {
-            self.task_index.update_one(
-                doc! { "task_id": &task_id, "user_id": user_id },
-                doc! {
-                    "$set": {
-                        "next_run": BsonDateTime::from_chrono(next_run),
-                        "enabled": true,
-                    },
-                    "$setOnInsert": {
-                        "task_id": &task_id,
-                        "user_id": user_id,
-                    },
-                },
-                options.clone(),
-            )?;
-        }
-
         Ok(())
     }
 
@@ -220,21 +457,285 @@ impl MongoIndexStore {
         }
         Ok(refs)
     }
+
+    fn missed_heartbeats(
+        &self,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<MissedHeartbeat>, IndexStoreError> {
+        let filter = doc! {
+            "enabled": true,
+            "heartbeat_deadline": { "$lte": BsonDateTime::from_chrono(now) },
+        };
+        let options = FindOptions::builder().limit(limit as i64).build();
+        let mut missed = Vec::new();
+        for row in self.task_index.find(filter, options)? {
+            let doc = row?;
+            let (Ok(task_id), Ok(user_id), Ok(deadline), Ok(due_at)) = (
+                doc.get_str("task_id"),
+                doc.get_str("user_id"),
+                doc.get_datetime("heartbeat_deadline"),
+                doc.get_datetime("next_run"),
+            ) else {
+                continue;
+            };
+            if doc.get_datetime("heartbeat_alerted_deadline").ok() == Some(deadline) {
+                continue;
+            }
+            missed.push(MissedHeartbeat {
+                task_id: task_id.to_string(),
+                user_id: user_id.to_string(),
+                name: doc
+                    .get_str("heartbeat_name")
+                    .unwrap_or("heartbeat")
+                    .to_string(),
+                due_at: due_at.to_chrono(),
+                deadline: deadline.to_chrono(),
+            });
+        }
+        Ok(missed)
+    }
+
+    fn user_next_runs(
+        &self,
+        user_id: &str,
+    ) -> Result<HashMap<String, DateTime<Utc>>, IndexStoreError> {
+        let mut next_runs = HashMap::new();
+        for row in self
+            .task_index
+            .find(doc! { "user_id": user_id, "enabled": true }, None)?
+        {
+            let doc = row?;
+            if let (Ok(task_id), Ok(next_run)) =
+                (doc.get_str("task_id"), doc.get_datetime("next_run"))
+            {
+                next_runs.insert(task_id.to_string(), next_run.to_chrono());
+            }
+        }
+        Ok(next_runs)
+    }
+
+    fn indexed_user_ids(&self) -> Result<Vec<String>, IndexStoreError> {
+        let mut user_ids: Vec<String> = self
+            .task_index
+            .distinct("user_id", None, None)?
+            .into_iter()
+            .filter_map(|value| match value {
+                Bson::String(user_id) => Some(user_id),
+                _ => None,
+            })
+            .collect();
+        user_ids.sort();
+        Ok(user_ids)
+    }
+
+    fn indexed_task_states(&self, user_id: &str) -> Result<HashMap<String, bool>, IndexStoreError> {
+        let mut states = HashMap::new();
+        for row in self.task_index.find(doc! { "user_id": user_id }, None)? {
+            let doc = row?;
+            if let Ok(task_id) = doc.get_str("task_id") {
+                states.insert(
+                    task_id.to_string(),
+                    doc.get_bool("enabled").unwrap_or(false),
+                );
+            }
+        }
+        Ok(states)
+    }
+
+    fn query_tasks(&self, query: &TaskIndexQuery) -> Result<IndexedTaskPage, IndexStoreError> {
+        let mut filter = Document::new();
+        for (key, value) in [
+            ("user_id", &query.user_id),
+            ("channel", &query.channel),
+            ("kind", &query.kind),
+        ] {
+            if let Some(value) = value.as_deref().filter(|value| !value.is_empty()) {
+                filter.insert(key, value);
+            }
+        }
+        if let Some(enabled) = query.enabled {
+            filter.insert("enabled", enabled);
+        }
+        let mut next_run = Document::new();
+        if let Some(from) = query.next_run_from {
+            next_run.insert("$gte", BsonDateTime::from_chrono(from));
+        }
+        if let Some(to) = query.next_run_to {
+            next_run.insert("$lt", BsonDateTime::from_chrono(to));
+        }
+        if !next_run.is_empty() {
+            filter.insert("next_run", next_run);
+        }
+        if let Some(cursor) = query.cursor.as_deref().filter(|value| !value.is_empty()) {
+            let (after_run, after_id) = decode_cursor(cursor)?;
+            let after_run = BsonDateTime::from_chrono(after_run);
+            filter.insert(
+                "$or",
+                vec![
+                    doc! { "next_run": { "$gt": after_run } },
+                    doc! { "next_run": after_run, "_id": { "$gt": after_id } },
+                ],
+            );
+        }
+
+        let limit = query.limit();
+        let options = FindOptions::builder()
+            .sort(doc! { "next_run": 1, "_id": 1 })
+            .limit(limit as i64 + 1)
+            .build();
+        let mut tasks = Vec::new();
+        let mut last_key = None;
+        for row in self.task_index.find(filter, options)? {
+            let doc = row?;
+            if tasks.len() == limit {
+                return Ok(IndexedTaskPage {
+                    tasks,
+                    next_cursor: last_key.map(|(run, id)| encode_cursor(run, id)),
+                });
+            }
+            let (Ok(id), Ok(user_id), Ok(task_id), Ok(next_run)) = (
+                doc.get_object_id("_id"),
+                doc.get_str("user_id"),
+                doc.get_str("task_id"),
+                doc.get_datetime("next_run"),
+            ) else {
+                continue;
+            };
+            last_key = Some((next_run.to_chrono(), id));
+            tasks.push(IndexedTask {
+                user_id: user_id.to_string(),
+                task_id: task_id.to_string(),
+                kind: doc.get_str("kind").unwrap_or_default().to_string(),
+                channel: doc.get_str("channel").unwrap_or_default().to_string(),
+                enabled: doc.get_bool("enabled").unwrap_or(false),
+                next_run: next_run.to_chrono(),
+            });
+        }
+        Ok(IndexedTaskPage {
+            tasks,
+            next_cursor: None,
+        })
+    }
+
+    fn mark_heartbeat_alerted(&self, missed: &MissedHeartbeat) -> Result<(), IndexStoreError> {
+        let deadline = BsonDateTime::from_chrono(missed.deadline);
+        self.task_index.update_one(
+            doc! {
+                "user_id": &missed.user_id,
+                "task_id": &missed.task_id,
+                "heartbeat_deadline": deadline,
+            },
+            doc! { "$set": { "heartbeat_alerted_deadline": deadline } },
+            None,
+        )?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct IndexRow {
+    next_run: DateTime<Utc>,
+    enabled: bool,
+    kind: String,
+    channel: String,
+    heartbeat: Option<(String, DateTime<Utc>)>,
+}
+
+impl IndexRow {
+    /// Placeholder for a stored row that cannot be read back; it never
+    /// equals a real row, so the row is rewritten.
+    fn unreadable() -> Self {
+        Self {
+            next_run: DateTime::<Utc>::MIN_UTC,
+            enabled: false,
+            kind: String::new(),
+            channel: String::new(),
+            heartbeat: None,
+        }
+    }
+}
+
+fn index_row(task: &ScheduledTask) -> IndexRow {
+    let next_run = match &task.schedule {
+        Schedule::Cron { next_run, .. } => *next_run,
+        Schedule::OneShot { run_at } => *run_at,
+    };
+    let heartbeat = task
+        .heartbeat_deadline()
+        .filter(|_| task.enabled)
+        .map(|(spec, deadline)| (spec.name.clone(), deadline));
+    IndexRow {
+        next_run,
+        enabled: task.enabled,
+        kind: task_kind_label(&task.kind).to_string(),
+        channel: task_kind_channel(&task.kind).to_string(),
+        heartbeat,
+    }
+}
+
+fn stored_row(doc: &Document) -> Option<IndexRow> {
+    let heartbeat = match (
+        doc.get_str("heartbeat_name"),
+        doc.get_datetime("heartbeat_deadline"),
+    ) {
+        (Ok(name), Ok(deadline)) => Some((name.to_string(), deadline.to_chrono())),
+        _ => None,
+    };
+    Some(IndexRow {
+        next_run: doc.get_datetime("next_run").ok()?.to_chrono(),
+        enabled: doc.get_bool("enabled").ok()?,
+        kind: doc.get_str("kind").ok()?.to_string(),
+        channel: doc.get_str("channel").ok()?.to_string(),
+        heartbeat,
+    })
 }
 
-fn enabled_task_next_runs(tasks: &[ScheduledTask]) -> Vec<(String, DateTime<Utc>)> {
-    let mut deduped: BTreeMap<String, DateTime<Utc>> = BTreeMap::new();
+/// One row per task; an enabled copy of a repeated task ID wins.
+fn task_rows(tasks: &[ScheduledTask]) -> BTreeMap<String, IndexRow> {
+    let mut deduped: BTreeMap<String, IndexRow> = BTreeMap::new();
     for task in tasks {
-        if !task.enabled {
-            continue;
+        let row = index_row(task);
+        let id = task.id.to_string();
+        if row.enabled || !deduped.get(&id).is_some_and(|existing| existing.enabled) {
+            deduped.insert(id, row);
         }
-        let next_run = match &task.schedule {
-            Schedule::Cron { next_run, .. } => *next_run,
-            Schedule::OneShot { run_at } => *run_at,
-        };
-        deduped.insert(task.id.to_string(), next_run);
     }
-    deduped.into_iter().collect()
+    deduped
+}
+
+fn encode_cursor(next_run: DateTime<Utc>, id: ObjectId) -> String {
+    format!("{}.{}", next_run.timestamp_millis(), id.to_hex())
+}
+
+fn decode_cursor(cursor: &str) -> Result<(DateTime<Utc>, ObjectId), IndexStoreError> {
+    let invalid = || IndexStoreError::InvalidCursor(cursor.to_string());
+    let (millis, id) = cursor.split_once('.').ok_or_else(invalid)?;
+    let next_run = millis
+        .parse::<i64>()
+        .ok()
+        .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+        .ok_or_else(invalid)?;
+    let id = ObjectId::parse_str(id).map_err(|_| invalid())?;
+    Ok((next_run, id))
+}
+
+/// Rows to write and task IDs to delete to turn `previous` into `current`.
+fn diff_rows(
+    previous: &BTreeMap<String, IndexRow>,
+    current: &BTreeMap<String, IndexRow>,
+) -> (Vec<(String, IndexRow)>, Vec<String>) {
+    let upserts = current
+        .iter()
+        .filter(|(task_id, row)| previous.get(*task_id) != Some(*row))
+        .map(|(task_id, row)| (task_id.clone(), row.clone()))
+        .collect();
+    let removals = previous
+        .keys()
+        .filter(|task_id| !current.contains_key(*task_id))
+        .cloned()
+        .collect();
+    (upserts, removals)
 }
 
 fn is_order_by_index_excluded(err: &mongodb::error::Error) -> bool {
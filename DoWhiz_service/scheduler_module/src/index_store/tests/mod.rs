@@ -1,6 +1,7 @@
-use super::IndexStore;
-use crate::{Schedule, ScheduledTask, TaskKind};
+use super::{decode_cursor, diff_rows, encode_cursor, task_rows, IndexStore, TaskIndexQuery};
+use crate::{HeartbeatSpec, NoopTask, Schedule, ScheduledTask, TaskKind};
 use chrono::{Duration, Utc};
+use mongodb::bson::oid::ObjectId;
 use tempfile::TempDir;
 use uuid::Uuid;
 
@@ -16,19 +17,25 @@ fn sync_user_tasks_and_query_due_users() {
 
     let due_task = ScheduledTask {
         id: Uuid::new_v4(),
-        kind: TaskKind::Noop,
+        kind: TaskKind::Noop(NoopTask::default()),
         schedule: Schedule::OneShot { run_at: past },
         enabled: true,
         created_at: now,
         last_run: None,
+        approval: None,
+        description: None,
+        ends: None,
     };
     let future_task = ScheduledTask {
         id: Uuid::new_v4(),
-        kind: TaskKind::Noop,
+        kind: TaskKind::Noop(NoopTask::default()),
         schedule: Schedule::OneShot { run_at: future },
         enabled: true,
         created_at: now,
         last_run: None,
+        approval: None,
+        description: None,
+        ends: None,
     };
     let user_a = format!("user_a_{}", Uuid::new_v4());
     let user_b = format!("user_b_{}", Uuid::new_v4());
@@ -56,19 +63,25 @@ fn sync_user_tasks_dedupes_repeated_task_ids() {
 
     let mut first = ScheduledTask {
         id: task_id,
-        kind: TaskKind::Noop,
+        kind: TaskKind::Noop(NoopTask::default()),
         schedule: Schedule::OneShot { run_at: first_due },
         enabled: true,
         created_at: now,
         last_run: None,
+        approval: None,
+        description: None,
+        ends: None,
     };
     let second = ScheduledTask {
         id: task_id,
-        kind: TaskKind::Noop,
+        kind: TaskKind::Noop(NoopTask::default()),
         schedule: Schedule::OneShot { run_at: latest_due },
         enabled: true,
         created_at: now,
         last_run: None,
+        approval: None,
+        description: None,
+        ends: None,
     };
 
     let user_id = format!("user_a_{}", Uuid::new_v4());
@@ -98,3 +111,204 @@ fn sync_user_tasks_dedupes_repeated_task_ids() {
         .collect();
     assert_eq!(matching.len(), 1);
 }
+
+#[test]
+fn missed_heartbeats_alert_once_per_deadline() {
+    let temp = TempDir::new().unwrap();
+    let db_path = temp.path().join("task_index.db");
+    let store = IndexStore::new(db_path).unwrap();
+
+    let now = Utc::now();
+    let mut heartbeat = ScheduledTask {
+        id: Uuid::new_v4(),
+        kind: TaskKind::Noop(NoopTask {
+            heartbeat: Some(HeartbeatSpec {
+                name: "user".to_string(),
+                grace_secs: 60,
+            }),
+        }),
+        schedule: Schedule::OneShot {
+            run_at: now - Duration::minutes(5),
+        },
+        enabled: true,
+        created_at: now,
+        last_run: None,
+        approval: None,
+        description: None,
+        ends: None,
+    };
+    let user_id = format!("user_hb_{}", Uuid::new_v4());
+    store
+        .sync_user_tasks(&user_id, &[heartbeat.clone()])
+        .unwrap();
+
+    let missed_for_user = |store: &IndexStore| {
+        store
+            .missed_heartbeats(now, 10_000)
+            .unwrap()
+            .into_iter()
+            .filter(|missed| missed.user_id == user_id)
+            .collect::<Vec<_>>()
+    };
+    let missed = missed_for_user(&store);
+    assert_eq!(missed.len(), 1);
+    assert_eq!(missed[0].name, "user");
+
+    store.mark_heartbeat_alerted(&missed[0]).unwrap();
+    assert!(missed_for_user(&store).is_empty());
+
+    // A fresh (but again overdue) deadline re-arms the alert.
+    heartbeat.schedule = Schedule::OneShot {
+        run_at: now - Duration::minutes(3),
+    };
+    store.sync_user_tasks(&user_id, &[heartbeat]).unwrap();
+    assert_eq!(missed_for_user(&store).len(), 1);
+}
+
+fn noop_task(run_at: chrono::DateTime<Utc>) -> ScheduledTask {
+    ScheduledTask {
+        id: Uuid::new_v4(),
+        kind: TaskKind::Noop(NoopTask::default()),
+        schedule: Schedule::OneShot { run_at },
+        enabled: true,
+        created_at: run_at,
+        last_run: None,
+        approval: None,
+        description: None,
+        ends: None,
+    }
+}
+
+#[test]
+fn diff_rows_writes_only_changed_and_removed_tasks() {
+    let now = Utc::now();
+    let unchanged = noop_task(now);
+    let mut moved = noop_task(now);
+    let removed = noop_task(now);
+    let previous = task_rows(&[unchanged.clone(), moved.clone(), removed.clone()]);
+
+    moved.schedule = Schedule::OneShot {
+        run_at: now + Duration::hours(1),
+    };
+    let added = noop_task(now);
+    let mut disabled = unchanged.clone();
+    disabled.id = Uuid::new_v4();
+    disabled.enabled = false;
+    let current = task_rows(&[unchanged, moved.clone(), added.clone(), disabled.clone()]);
+
+    let (upserts, removals) = diff_rows(&previous, &current);
+    let mut upserted: Vec<String> = upserts.into_iter().map(|(task_id, _)| task_id).collect();
+    upserted.sort();
+    let mut expected = vec![
+        moved.id.to_string(),
+        added.id.to_string(),
+        disabled.id.to_string(),
+    ];
+    expected.sort();
+    assert_eq!(upserted, expected);
+    assert_eq!(removals, vec![removed.id.to_string()]);
+}
+
+#[test]
+fn incremental_task_refs_update_due_index() {
+    let temp = TempDir::new().unwrap();
+    let store = IndexStore::new(temp.path().join("task_index.db")).unwrap();
+
+    let now = Utc::now();
+    let user_id = format!("user_inc_{}", Uuid::new_v4());
+    let mut task = noop_task(now - Duration::minutes(1));
+    store.sync_user_tasks(&user_id, &[]).unwrap();
+
+    store.upsert_task_ref(&user_id, &task).unwrap();
+    assert!(store.due_user_ids(now, 10_000).unwrap().contains(&user_id));
+
+    task.enabled = false;
+    store.upsert_task_ref(&user_id, &task).unwrap();
+    assert!(!store.due_user_ids(now, 10_000).unwrap().contains(&user_id));
+
+    task.enabled = true;
+    store.sync_user_tasks(&user_id, &[task.clone()]).unwrap();
+    store
+        .remove_task_ref(&user_id, &task.id.to_string())
+        .unwrap();
+    assert!(!store.due_user_ids(now, 10_000).unwrap().contains(&user_id));
+}
+
+#[test]
+fn task_rows_keep_disabled_tasks_and_prefer_enabled_copies() {
+    let now = Utc::now();
+    let enabled = noop_task(now);
+    let mut disabled_copy = enabled.clone();
+    disabled_copy.enabled = false;
+    let mut disabled = noop_task(now);
+    disabled.enabled = false;
+
+    let rows = task_rows(&[enabled.clone(), disabled_copy, disabled.clone()]);
+    assert!(rows[&enabled.id.to_string()].enabled);
+    let disabled_row = &rows[&disabled.id.to_string()];
+    assert!(!disabled_row.enabled);
+    assert_eq!(disabled_row.kind, "noop");
+    assert_eq!(disabled_row.channel, "email");
+}
+
+#[test]
+fn cursors_round_trip_and_reject_garbage() {
+    let next_run = Utc::now();
+    let id = ObjectId::new();
+    let (decoded_run, decoded_id) = decode_cursor(&encode_cursor(next_run, id)).unwrap();
+    assert_eq!(decoded_run.timestamp_millis(), next_run.timestamp_millis());
+    assert_eq!(decoded_id, id);
+    assert!(decode_cursor("nope").is_err());
+    assert!(decode_cursor("12.not-an-id").is_err());
+}
+
+#[test]
+fn query_tasks_filters_and_pages() {
+    let temp = TempDir::new().unwrap();
+    let store = IndexStore::new(temp.path().join("task_index.db")).unwrap();
+
+    let now = Utc::now();
+    let user_id = format!("user_query_{}", Uuid::new_v4());
+    let mut tasks: Vec<ScheduledTask> = (0..5)
+        .map(|minutes| noop_task(now + Duration::minutes(minutes)))
+        .collect();
+    tasks[4].enabled = false;
+    store.sync_user_tasks(&user_id, &tasks).unwrap();
+
+    let mut query = TaskIndexQuery {
+        user_id: Some(user_id.clone()),
+        kind: Some("noop".to_string()),
+        enabled: Some(true),
+        limit: Some(3),
+        ..Default::default()
+    };
+    let first = store.query_tasks(&query).unwrap();
+    assert_eq!(first.tasks.len(), 3);
+    assert_eq!(first.tasks[0].task_id, tasks[0].id.to_string());
+    query.cursor = first.next_cursor.clone();
+    assert!(query.cursor.is_some());
+    let second = store.query_tasks(&query).unwrap();
+    assert_eq!(second.tasks.len(), 1);
+    assert_eq!(second.tasks[0].task_id, tasks[3].id.to_string());
+    assert!(second.next_cursor.is_none());
+
+    let disabled = store
+        .query_tasks(&TaskIndexQuery {
+            user_id: Some(user_id.clone()),
+            enabled: Some(false),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(disabled.tasks.len(), 1);
+    assert_eq!(disabled.tasks[0].task_id, tasks[4].id.to_string());
+
+    let window = store
+        .query_tasks(&TaskIndexQuery {
+            user_id: Some(user_id),
+            next_run_from: Some(now + Duration::seconds(30)),
+            next_run_to: Some(now + Duration::minutes(2) + Duration::seconds(30)),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(window.tasks.len(), 2);
+}
@@ -6,6 +6,10 @@ use uuid::Uuid;
 use crate::channel::{Attachment, Channel, ChannelMetadata, InboundMessage};
 use crate::raw_payload_store;
 
+pub mod azure_bus;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IngestionEnvelope {
     pub envelope_id: Uuid,
@@ -20,9 +24,78 @@ pub struct IngestionEnvelope {
     pub raw_payload_ref: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub account_id: Option<Uuid>,
+    /// Correlation ID minted at ingestion; see [`crate::trace_context`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    /// Set when another employee handed the conversation over.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handoff: Option<HandoffProvenance>,
+}
+
+/// Where a handed-off conversation came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandoffProvenance {
+    pub from_employee_id: String,
+    /// Workspace of the thread the conversation was handed off from.
+    pub from_workspace: String,
+    /// Channel the user was talking to the first employee on.
+    pub from_channel: Channel,
+    pub summary: String,
+    pub handed_off_at: DateTime<Utc>,
+}
+
+/// How urgently an envelope is claimed. Document and tracker events are
+/// background work: nobody is waiting on them in a chat window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestionPriority {
+    Interactive,
+    Background,
+}
+
+/// Channels whose envelopes are claimed at [`IngestionPriority::Background`]
+pub const BACKGROUND_CHANNELS: [Channel; 5] = [
+    Channel::GoogleDocs,
+    Channel::GoogleSheets,
+    Channel::GoogleSlides,
+    Channel::Notion,
+    Channel::Jira,
+];
+
+impl IngestionPriority {
+    pub fn of(channel: Channel) -> Self {
+        if BACKGROUND_CHANNELS.contains(&channel) {
+            Self::Background
+        } else {
+            Self::Interactive
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Interactive => "interactive",
+            Self::Background => "background",
+        }
+    }
 }
 
 impl IngestionEnvelope {
+    pub fn priority(&self) -> IngestionPriority {
+        IngestionPriority::of(self.channel)
+    }
+
+    /// How long the envelope has waited since the gateway received it.
+    pub fn queued_for(&self, now: DateTime<Utc>) -> std::time::Duration {
+        (now - self.received_at).to_std().unwrap_or_default()
+    }
+
+    /// The envelope's trace ID, falling back to its envelope ID for messages
+    /// queued before trace IDs were minted.
+    pub fn trace_id(&self) -> String {
+        self.trace_id
+            .clone()
+            .unwrap_or_else(|| self.envelope_id.simple().to_string())
+    }
+
     pub fn raw_payload_bytes(&self) -> Vec<u8> {
         if let Some(ref payload_ref) = self.raw_payload_ref {
             match raw_payload_store::download_raw_payload(payload_ref) {
@@ -0,0 +1,425 @@
+//! Azure Service Bus topic consumer.
+//!
+//! `ServiceBusIngestionQueue` shares one queue across employees and unlocks
+//! envelopes addressed to someone else. Here each employee reads its own
+//! subscription on a topic (filtered on the `employee_id` message property),
+//! so any producer can publish envelopes and a worker can run without the
+//! inbound gateway. Messages that can never be processed are dead-lettered
+//! instead of cycling through redeliveries.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use azure_core::auth::Secret;
+use azure_messaging_servicebus::prelude::{QueueClient, SubscriptionReceiver, TopicClient};
+use azure_messaging_servicebus::service_bus::{
+    PeekLockResponse, SendMessageOptions, SettableBrokerProperties,
+};
+use tokio::runtime::Runtime;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::env_alias::var_with_scale_oliver;
+use crate::ingestion::IngestionEnvelope;
+use crate::ingestion_queue::{EnqueueResult, IngestionQueue, IngestionQueueError, QueuedEnvelope};
+use crate::service_bus_queue::{
+    map_service_bus_error, resolve_i64_env, resolve_lock_timings_from_env,
+    resolve_service_bus_credentials_from_env, spawn_lock_renewer, PendingLock,
+};
+
+const EMPLOYEE_ID_PLACEHOLDER: &str = "{employee_id}";
+const DEFAULT_SUBSCRIPTION: &str = EMPLOYEE_ID_PLACEHOLDER;
+const DEFAULT_MAX_DELIVERY_COUNT: i64 = 5;
+
+#[derive(Debug, Clone)]
+pub struct AzureBusConfig {
+    pub namespace: String,
+    pub policy_name: String,
+    pub policy_key: String,
+    pub topic_name: String,
+    /// Subscription name; `{employee_id}` is replaced per employee.
+    pub subscription: String,
+    pub peek_lock_timeout: Duration,
+    pub lock_renew_interval: Duration,
+    /// Failed deliveries beyond this are dead-lettered rather than unlocked.
+    pub max_delivery_count: i32,
+    /// Queue that receives dead-lettered message bodies. When unset, failed
+    /// messages are abandoned and Service Bus moves them to the subscription's
+    /// own `$DeadLetterQueue` once its MaxDeliveryCount is reached.
+    pub dead_letter_queue: Option<String>,
+}
+
+pub struct AzureBusTopicConsumer {
+    config: AzureBusConfig,
+    topic: TopicClient,
+    dead_letter: Option<QueueClient>,
+    runtime: Option<Runtime>,
+    receivers: Mutex<HashMap<String, (String, SubscriptionReceiver)>>,
+    pending: Mutex<HashMap<Uuid, PendingLock>>,
+}
+
+impl AzureBusTopicConsumer {
+    pub fn from_env() -> Result<Self, IngestionQueueError> {
+        Self::new(resolve_azure_bus_config_from_env()?)
+    }
+
+    pub fn new(config: AzureBusConfig) -> Result<Self, IngestionQueueError> {
+        let http_client = azure_core::new_http_client();
+        let topic = TopicClient::new(
+            http_client.clone(),
+            config.namespace.clone(),
+            config.topic_name.clone(),
+            config.policy_name.clone(),
+            Secret::new(config.policy_key.clone()),
+        )
+        .map_err(|err| IngestionQueueError::ServiceBus(err.to_string()))?;
+        let dead_letter = config
+            .dead_letter_queue
+            .as_ref()
+            .map(|queue_name| {
+                QueueClient::new(
+                    http_client.clone(),
+                    config.namespace.clone(),
+                    queue_name.clone(),
+                    config.policy_name.clone(),
+                    Secret::new(config.policy_key.clone()),
+                )
+            })
+            .transpose()
+            .map_err(|err| IngestionQueueError::ServiceBus(err.to_string()))?;
+        let runtime =
+            Runtime::new().map_err(|err| IngestionQueueError::ServiceBus(err.to_string()))?;
+        Ok(Self {
+            config,
+            topic,
+            dead_letter,
+            runtime: Some(runtime),
+            receivers: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn runtime(&self) -> Result<&Runtime, IngestionQueueError> {
+        self.runtime.as_ref().ok_or_else(|| {
+            IngestionQueueError::ServiceBus("service bus runtime dropped".to_string())
+        })
+    }
+
+    /// The subscription name and receiver for `employee_id`.
+    fn receiver_for(
+        &self,
+        employee_id: &str,
+    ) -> Result<(String, SubscriptionReceiver), IngestionQueueError> {
+        let mut receivers = self.receivers.lock().map_err(|_| {
+            IngestionQueueError::ServiceBus("subscription receiver lock poisoned".to_string())
+        })?;
+        if let Some(entry) = receivers.get(employee_id) {
+            return Ok(entry.clone());
+        }
+        let subscription = resolve_subscription_name(&self.config.subscription, employee_id);
+        let receiver = self.topic.subscription_receiver(&subscription);
+        receivers.insert(
+            employee_id.to_string(),
+            (subscription.clone(), receiver.clone()),
+        );
+        Ok((subscription, receiver))
+    }
+
+    /// Publish `envelope` to the topic. `employee_id` is set as a message
+    /// property so each subscription's SQL filter can select its employee.
+    pub fn enqueue(
+        &self,
+        envelope: &IngestionEnvelope,
+    ) -> Result<EnqueueResult, IngestionQueueError> {
+        let payload_json = serde_json::to_string(envelope).map_err(IngestionQueueError::Json)?;
+        let broker = SettableBrokerProperties {
+            message_id: Some(envelope.dedupe_key.clone()),
+            ..Default::default()
+        };
+        let options = SendMessageOptions {
+            content_type: Some("application/json".to_string()),
+            broker_properties: Some(broker),
+            custom_properties: Some(HashMap::from([(
+                "employee_id".to_string(),
+                envelope.employee_id.clone(),
+            )])),
+        };
+        self.runtime()?
+            .block_on(
+                self.topic
+                    .topic_sender()
+                    .send_message(&payload_json, Some(options)),
+            )
+            .map_err(map_service_bus_error)?;
+        Ok(EnqueueResult { inserted: true })
+    }
+
+    pub fn claim_next(
+        &self,
+        employee_id: &str,
+    ) -> Result<Option<QueuedEnvelope>, IngestionQueueError> {
+        let (subscription, receiver) = self.receiver_for(employee_id)?;
+        let response = self
+            .runtime()?
+            .block_on(receiver.peek_lock_message2(Some(self.config.peek_lock_timeout)))
+            .map_err(map_service_bus_error)?;
+        if *response.status() == azure_core::StatusCode::NoContent {
+            return Ok(None);
+        }
+        if *response.status() != azure_core::StatusCode::Ok
+            && *response.status() != azure_core::StatusCode::Created
+        {
+            return Err(IngestionQueueError::ServiceBus(format!(
+                "unexpected service bus status {}",
+                response.status()
+            )));
+        }
+        let body = response.body();
+        let envelope: IngestionEnvelope = match serde_json::from_str(&body) {
+            Ok(envelope) => envelope,
+            Err(err) => {
+                let reason = format!("invalid envelope: {err}");
+                self.dead_letter(&response, &subscription, &reason)?;
+                return Ok(None);
+            }
+        };
+        if envelope.employee_id != employee_id {
+            // This subscription's copy is ours alone; other employees get their
+            // own. Reaching here means the subscription filter is too broad.
+            warn!(
+                "azure bus subscription={} delivered envelope for employee={} to employee={}; dropping",
+                subscription, envelope.employee_id, employee_id
+            );
+            self.runtime()?
+                .block_on(response.delete_message())
+                .map_err(map_service_bus_error)?;
+            return Ok(None);
+        }
+        if delivery_count(&response) > self.config.max_delivery_count {
+            self.dead_letter(&response, &subscription, "max delivery count exceeded")?;
+            return Ok(None);
+        }
+
+        let handle_id = Uuid::new_v4();
+        let response = Arc::new(response);
+        let renewer = match spawn_lock_renewer(
+            self.runtime()?.handle().clone(),
+            self.config.lock_renew_interval,
+            format!("{}/{}", self.config.topic_name, subscription),
+            handle_id,
+            response.clone(),
+        ) {
+            Ok(renewer) => renewer,
+            Err(err) => {
+                self.runtime()?
+                    .block_on(response.unlock_message())
+                    .map_err(map_service_bus_error)?;
+                return Err(err);
+            }
+        };
+        let mut pending = self
+            .pending
+            .lock()
+            .map_err(|_| IngestionQueueError::ServiceBus("pending lock poisoned".to_string()))?;
+        pending.insert(handle_id, PendingLock::new(response, renewer));
+        Ok(Some(QueuedEnvelope {
+            id: handle_id,
+            envelope,
+        }))
+    }
+
+    pub fn mark_done(&self, id: &Uuid) -> Result<(), IngestionQueueError> {
+        let mut pending = self.take_pending(id)?;
+        pending.stop_renewer();
+        self.runtime()?
+            .block_on(pending.response.delete_message())
+            .map_err(map_service_bus_error)?;
+        Ok(())
+    }
+
+    /// Unlock for redelivery, or dead-letter once the delivery budget is spent.
+    pub fn mark_failed(&self, id: &Uuid, error: &str) -> Result<(), IngestionQueueError> {
+        let mut pending = self.take_pending(id)?;
+        pending.stop_renewer();
+        if delivery_count(&pending.response) >= self.config.max_delivery_count {
+            return self.dead_letter(&pending.response, &self.config.topic_name, error);
+        }
+        self.runtime()?
+            .block_on(pending.response.unlock_message())
+            .map_err(map_service_bus_error)?;
+        Ok(())
+    }
+
+    /// Move a message out of the subscription. With a dead-letter queue
+    /// configured the body is copied there with the reason and then completed;
+    /// otherwise it is abandoned for Service Bus to dead-letter.
+    fn dead_letter(
+        &self,
+        response: &PeekLockResponse,
+        source: &str,
+        reason: &str,
+    ) -> Result<(), IngestionQueueError> {
+        let runtime = self.runtime()?;
+        let Some(dead_letter) = self.dead_letter.as_ref() else {
+            warn!(
+                "azure bus message from {} cannot be processed ({}); abandoning until Service Bus dead-letters it",
+                source, reason
+            );
+            return runtime
+                .block_on(response.unlock_message())
+                .map_err(map_service_bus_error);
+        };
+        let broker = SettableBrokerProperties {
+            message_id: response
+                .broker_properties()
+                .map(|properties| properties.message_id),
+            ..Default::default()
+        };
+        let options = SendMessageOptions {
+            content_type: Some("application/json".to_string()),
+            broker_properties: Some(broker),
+            custom_properties: Some(HashMap::from([
+                ("dead_letter_reason".to_string(), reason.to_string()),
+                ("dead_letter_source".to_string(), source.to_string()),
+                (
+                    "delivery_count".to_string(),
+                    delivery_count(response).to_string(),
+                ),
+            ])),
+        };
+        runtime
+            .block_on(dead_letter.send_message(&response.body(), Some(options)))
+            .map_err(map_service_bus_error)?;
+        warn!(
+            "azure bus message from {} dead-lettered to {}: {}",
+            source,
+            self.config.dead_letter_queue.as_deref().unwrap_or_default(),
+            reason
+        );
+        runtime
+            .block_on(response.delete_message())
+            .map_err(map_service_bus_error)?;
+        Ok(())
+    }
+
+    fn take_pending(&self, id: &Uuid) -> Result<PendingLock, IngestionQueueError> {
+        let mut pending = self
+            .pending
+            .lock()
+            .map_err(|_| IngestionQueueError::ServiceBus("pending lock poisoned".to_string()))?;
+        pending
+            .remove(id)
+            .ok_or_else(|| IngestionQueueError::ServiceBus("missing pending lock".to_string()))
+    }
+}
+
+impl IngestionQueue for AzureBusTopicConsumer {
+    fn enqueue(&self, envelope: &IngestionEnvelope) -> Result<EnqueueResult, IngestionQueueError> {
+        AzureBusTopicConsumer::enqueue(self, envelope)
+    }
+
+    fn claim_next(&self, employee_id: &str) -> Result<Option<QueuedEnvelope>, IngestionQueueError> {
+        AzureBusTopicConsumer::claim_next(self, employee_id)
+    }
+
+    fn mark_done(&self, id: &Uuid) -> Result<(), IngestionQueueError> {
+        AzureBusTopicConsumer::mark_done(self, id)
+    }
+
+    fn mark_failed(&self, id: &Uuid, error: &str) -> Result<(), IngestionQueueError> {
+        AzureBusTopicConsumer::mark_failed(self, id, error)
+    }
+}
+
+impl Drop for AzureBusTopicConsumer {
+    fn drop(&mut self) {
+        if let Ok(mut pending) = self.pending.lock() {
+            for (_, mut lock) in pending.drain() {
+                lock.stop_renewer();
+            }
+        }
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+fn delivery_count(response: &PeekLockResponse) -> i32 {
+    response
+        .broker_properties()
+        .map(|properties| properties.delivery_count)
+        .unwrap_or(1)
+}
+
+/// `{EMPLOYEE}_SERVICE_BUS_SUBSCRIPTION` wins; otherwise `{employee_id}` in
+/// the template is replaced.
+fn resolve_subscription_name(template: &str, employee_id: &str) -> String {
+    let key = format!(
+        "{}_SERVICE_BUS_SUBSCRIPTION",
+        employee_id.trim().to_ascii_uppercase()
+    );
+    if let Some(value) = env::var(key)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    {
+        return value;
+    }
+    template.replace(EMPLOYEE_ID_PLACEHOLDER, employee_id)
+}
+
+pub fn resolve_azure_bus_config_from_env() -> Result<AzureBusConfig, IngestionQueueError> {
+    let (peek_lock_timeout, lock_renew_interval) = resolve_lock_timings_from_env();
+    let credentials = resolve_service_bus_credentials_from_env()?;
+    let topic_name = var_with_scale_oliver("SERVICE_BUS_TOPIC_NAME")
+        .or(credentials.entity_path)
+        .ok_or_else(|| {
+            IngestionQueueError::Config(
+                "missing SCALE_OLIVER_SERVICE_BUS_TOPIC_NAME/SERVICE_BUS_TOPIC_NAME".to_string(),
+            )
+        })?;
+    let subscription = var_with_scale_oliver("SERVICE_BUS_SUBSCRIPTION")
+        .unwrap_or_else(|| DEFAULT_SUBSCRIPTION.to_string());
+    let max_delivery_count =
+        resolve_i64_env("SERVICE_BUS_MAX_DELIVERY_COUNT", DEFAULT_MAX_DELIVERY_COUNT)
+            .min(i32::MAX as i64) as i32;
+    Ok(AzureBusConfig {
+        namespace: credentials.namespace,
+        policy_name: credentials.policy_name,
+        policy_key: credentials.policy_key,
+        topic_name,
+        subscription,
+        peek_lock_timeout,
+        lock_renew_interval,
+        max_delivery_count,
+        dead_letter_queue: var_with_scale_oliver("SERVICE_BUS_DEAD_LETTER_QUEUE"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscription_template_substitutes_employee_id() {
+        assert_eq!(
+            resolve_subscription_name("worker-{employee_id}", "test_sub_employee"),
+            "worker-test_sub_employee"
+        );
+        assert_eq!(
+            resolve_subscription_name("shared", "test_sub_employee"),
+            "shared"
+        );
+    }
+
+    #[test]
+    fn per_employee_subscription_overrides_template() {
+        let key = "TEST_SUB_OVERRIDE_SERVICE_BUS_SUBSCRIPTION";
+        env::set_var(key, "custom-sub");
+        let resolved = resolve_subscription_name(DEFAULT_SUBSCRIPTION, "test_sub_override");
+        env::remove_var(key);
+        assert_eq!(resolved, "custom-sub");
+    }
+}
@@ -0,0 +1,407 @@
+//! Kafka / Redpanda ingestion queue (built with the `kafka` feature).
+//!
+//! Each employee reads its own topic through a consumer group, so adding
+//! workers spreads the topic's partitions across them. Envelopes are keyed by
+//! thread, which keeps one conversation on one partition and in order.
+//!
+//! Kafka has no per-message unlock: a failed envelope is republished with its
+//! attempt count bumped and, once the attempt budget is spent, copied to the
+//! dead-letter topic. Either way its offset is committed so the partition
+//! keeps moving.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, CommitMode, Consumer};
+use rdkafka::message::{Header, Headers, Message, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::{Offset, TopicPartitionList};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::ingestion::IngestionEnvelope;
+use crate::ingestion_queue::{EnqueueResult, IngestionQueue, IngestionQueueError, QueuedEnvelope};
+
+const EMPLOYEE_ID_PLACEHOLDER: &str = "{employee_id}";
+const DEFAULT_TOPIC: &str = "dowhiz-ingestion-{employee_id}";
+const DEFAULT_GROUP_ID: &str = "dowhiz-worker-{employee_id}";
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_POLL_TIMEOUT_MS: u64 = 1000;
+const ATTEMPT_HEADER: &str = "dowhiz-attempt";
+const DEAD_LETTER_REASON_HEADER: &str = "dowhiz-dead-letter-reason";
+const DEAD_LETTER_SOURCE_HEADER: &str = "dowhiz-dead-letter-source";
+
+#[derive(Debug, Clone)]
+pub struct KafkaConfig {
+    /// `bootstrap.servers`.
+    pub brokers: String,
+    /// Topic name; `{employee_id}` is replaced per employee.
+    pub topic: String,
+    /// Consumer group; `{employee_id}` is replaced per employee.
+    pub group_id: String,
+    /// Dead-letter topic; defaults to `<topic>.dlq` of the source topic.
+    pub dead_letter_topic: Option<String>,
+    /// Deliveries (first attempt included) before an envelope is dead-lettered.
+    pub max_attempts: u32,
+    pub poll_timeout: Duration,
+    /// Extra librdkafka settings (security protocol, SASL credentials).
+    pub client_settings: Vec<(String, String)>,
+}
+
+/// Offset and body of a claimed message, kept until it is marked done/failed.
+struct PendingMessage {
+    employee_id: String,
+    topic: String,
+    partition: i32,
+    offset: i64,
+    key: Option<Vec<u8>>,
+    payload: Vec<u8>,
+    attempt: u32,
+}
+
+pub struct KafkaIngestionQueue {
+    config: KafkaConfig,
+    producer: FutureProducer,
+    consumers: Mutex<HashMap<String, Arc<BaseConsumer>>>,
+    pending: Mutex<HashMap<Uuid, PendingMessage>>,
+}
+
+impl KafkaIngestionQueue {
+    pub fn from_env() -> Result<Self, IngestionQueueError> {
+        Self::new(resolve_kafka_config_from_env()?)
+    }
+
+    pub fn new(config: KafkaConfig) -> Result<Self, IngestionQueueError> {
+        let producer = client_config(&config)
+            .set("message.timeout.ms", "30000")
+            .create()
+            .map_err(map_kafka_error)?;
+        Ok(Self {
+            config,
+            producer,
+            consumers: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn consumer_for(&self, employee_id: &str) -> Result<Arc<BaseConsumer>, IngestionQueueError> {
+        let mut consumers = self
+            .consumers
+            .lock()
+            .map_err(|_| IngestionQueueError::Kafka("consumer lock poisoned".to_string()))?;
+        if let Some(consumer) = consumers.get(employee_id) {
+            return Ok(consumer.clone());
+        }
+        let topic = resolve_template(&self.config.topic, employee_id);
+        let consumer: BaseConsumer = client_config(&self.config)
+            .set(
+                "group.id",
+                resolve_template(&self.config.group_id, employee_id),
+            )
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .map_err(map_kafka_error)?;
+        consumer.subscribe(&[&topic]).map_err(map_kafka_error)?;
+        let consumer = Arc::new(consumer);
+        consumers.insert(employee_id.to_string(), consumer.clone());
+        Ok(consumer)
+    }
+
+    /// Publish `envelope` to its employee's topic, keyed by thread.
+    pub fn enqueue(
+        &self,
+        envelope: &IngestionEnvelope,
+    ) -> Result<EnqueueResult, IngestionQueueError> {
+        let payload = serde_json::to_vec(envelope).map_err(IngestionQueueError::Json)?;
+        let topic = resolve_template(&self.config.topic, &envelope.employee_id);
+        self.publish(
+            &topic,
+            Some(envelope.payload.thread_id.as_bytes()),
+            &payload,
+            OwnedHeaders::new(),
+        )?;
+        Ok(EnqueueResult { inserted: true })
+    }
+
+    pub fn claim_next(
+        &self,
+        employee_id: &str,
+    ) -> Result<Option<QueuedEnvelope>, IngestionQueueError> {
+        let consumer = self.consumer_for(employee_id)?;
+        let pending = match consumer.poll(self.config.poll_timeout) {
+            None => return Ok(None),
+            Some(Err(err)) => return Err(map_kafka_error(err)),
+            Some(Ok(message)) => PendingMessage {
+                employee_id: employee_id.to_string(),
+                topic: message.topic().to_string(),
+                partition: message.partition(),
+                offset: message.offset(),
+                key: message.key().map(<[u8]>::to_vec),
+                payload: message.payload().unwrap_or_default().to_vec(),
+                attempt: message.headers().map(attempt_from_headers).unwrap_or(0),
+            },
+        };
+        let envelope: IngestionEnvelope = match serde_json::from_slice(&pending.payload) {
+            Ok(envelope) => envelope,
+            Err(err) => {
+                self.dead_letter(&pending, &format!("invalid envelope: {err}"))?;
+                self.commit(&consumer, &pending)?;
+                return Ok(None);
+            }
+        };
+        if envelope.employee_id != employee_id {
+            warn!(
+                "kafka topic={} delivered envelope for employee={} to employee={}; skipping",
+                pending.topic, envelope.employee_id, employee_id
+            );
+            self.commit(&consumer, &pending)?;
+            return Ok(None);
+        }
+
+        let handle_id = Uuid::new_v4();
+        let mut pending_map = self
+            .pending
+            .lock()
+            .map_err(|_| IngestionQueueError::Kafka("pending lock poisoned".to_string()))?;
+        pending_map.insert(handle_id, pending);
+        Ok(Some(QueuedEnvelope {
+            id: handle_id,
+            envelope,
+        }))
+    }
+
+    pub fn mark_done(&self, id: &Uuid) -> Result<(), IngestionQueueError> {
+        let pending = self.take_pending(id)?;
+        let consumer = self.consumer_for(&pending.employee_id)?;
+        self.commit(&consumer, &pending)
+    }
+
+    /// Republish for another attempt, or dead-letter once attempts run out.
+    pub fn mark_failed(&self, id: &Uuid, error: &str) -> Result<(), IngestionQueueError> {
+        let pending = self.take_pending(id)?;
+        let next_attempt = pending.attempt + 1;
+        if next_attempt >= self.config.max_attempts {
+            self.dead_letter(&pending, error)?;
+        } else {
+            let headers = OwnedHeaders::new().insert(Header {
+                key: ATTEMPT_HEADER,
+                value: Some(&next_attempt.to_string()),
+            });
+            self.publish(
+                &pending.topic,
+                pending.key.as_deref(),
+                &pending.payload,
+                headers,
+            )?;
+        }
+        let consumer = self.consumer_for(&pending.employee_id)?;
+        self.commit(&consumer, &pending)
+    }
+
+    fn dead_letter(
+        &self,
+        pending: &PendingMessage,
+        reason: &str,
+    ) -> Result<(), IngestionQueueError> {
+        let topic = self
+            .config
+            .dead_letter_topic
+            .clone()
+            .unwrap_or_else(|| format!("{}.dlq", pending.topic));
+        let source = format!("{}/{}@{}", pending.topic, pending.partition, pending.offset);
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: ATTEMPT_HEADER,
+                value: Some(&pending.attempt.to_string()),
+            })
+            .insert(Header {
+                key: DEAD_LETTER_REASON_HEADER,
+                value: Some(reason),
+            })
+            .insert(Header {
+                key: DEAD_LETTER_SOURCE_HEADER,
+                value: Some(&source),
+            });
+        self.publish(&topic, pending.key.as_deref(), &pending.payload, headers)?;
+        warn!(
+            "kafka message {} dead-lettered to {}: {}",
+            source, topic, reason
+        );
+        Ok(())
+    }
+
+    /// Send one record and wait for the broker to acknowledge it.
+    fn publish(
+        &self,
+        topic: &str,
+        key: Option<&[u8]>,
+        payload: &[u8],
+        headers: OwnedHeaders,
+    ) -> Result<(), IngestionQueueError> {
+        let mut record = FutureRecord::to(topic).payload(payload).headers(headers);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+        let delivery = self
+            .producer
+            .send_result(record)
+            .map_err(|(err, _)| map_kafka_error(err))?;
+        match futures::executor::block_on(delivery) {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err((err, _))) => Err(map_kafka_error(err)),
+            Err(_) => Err(IngestionQueueError::Kafka(
+                "kafka delivery canceled".to_string(),
+            )),
+        }
+    }
+
+    fn commit(
+        &self,
+        consumer: &BaseConsumer,
+        pending: &PendingMessage,
+    ) -> Result<(), IngestionQueueError> {
+        let mut offsets = TopicPartitionList::new();
+        offsets
+            .add_partition_offset(
+                &pending.topic,
+                pending.partition,
+                Offset::Offset(pending.offset + 1),
+            )
+            .map_err(map_kafka_error)?;
+        consumer
+            .commit(&offsets, CommitMode::Sync)
+            .map_err(map_kafka_error)
+    }
+
+    fn take_pending(&self, id: &Uuid) -> Result<PendingMessage, IngestionQueueError> {
+        let mut pending = self
+            .pending
+            .lock()
+            .map_err(|_| IngestionQueueError::Kafka("pending lock poisoned".to_string()))?;
+        pending
+            .remove(id)
+            .ok_or_else(|| IngestionQueueError::Kafka("missing pending message".to_string()))
+    }
+}
+
+impl IngestionQueue for KafkaIngestionQueue {
+    fn enqueue(&self, envelope: &IngestionEnvelope) -> Result<EnqueueResult, IngestionQueueError> {
+        KafkaIngestionQueue::enqueue(self, envelope)
+    }
+
+    fn claim_next(&self, employee_id: &str) -> Result<Option<QueuedEnvelope>, IngestionQueueError> {
+        KafkaIngestionQueue::claim_next(self, employee_id)
+    }
+
+    fn mark_done(&self, id: &Uuid) -> Result<(), IngestionQueueError> {
+        KafkaIngestionQueue::mark_done(self, id)
+    }
+
+    fn mark_failed(&self, id: &Uuid, error: &str) -> Result<(), IngestionQueueError> {
+        KafkaIngestionQueue::mark_failed(self, id, error)
+    }
+}
+
+fn map_kafka_error(err: rdkafka::error::KafkaError) -> IngestionQueueError {
+    IngestionQueueError::Kafka(err.to_string())
+}
+
+fn client_config(config: &KafkaConfig) -> ClientConfig {
+    let mut client = ClientConfig::new();
+    client.set("bootstrap.servers", &config.brokers);
+    for (key, value) in &config.client_settings {
+        client.set(key, value);
+    }
+    client
+}
+
+fn attempt_from_headers<H: Headers>(headers: &H) -> u32 {
+    headers
+        .iter()
+        .filter(|header| header.key == ATTEMPT_HEADER)
+        .filter_map(|header| header.value)
+        .filter_map(|value| std::str::from_utf8(value).ok())
+        .filter_map(|value| value.trim().parse::<u32>().ok())
+        .last()
+        .unwrap_or(0)
+}
+
+fn resolve_template(template: &str, employee_id: &str) -> String {
+    template.replace(EMPLOYEE_ID_PLACEHOLDER, employee_id)
+}
+
+fn env_value(key: &str) -> Option<String> {
+    env::var(key)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+pub fn resolve_kafka_config_from_env() -> Result<KafkaConfig, IngestionQueueError> {
+    let brokers = env_value("KAFKA_BROKERS")
+        .ok_or_else(|| IngestionQueueError::Config("missing KAFKA_BROKERS".to_string()))?;
+    let max_attempts = env_value("KAFKA_MAX_ATTEMPTS")
+        .and_then(|value| value.parse::<u32>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_MAX_ATTEMPTS);
+    let poll_timeout_ms = env_value("KAFKA_POLL_TIMEOUT_MS")
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_POLL_TIMEOUT_MS);
+    let client_settings = [
+        ("KAFKA_SECURITY_PROTOCOL", "security.protocol"),
+        ("KAFKA_SASL_MECHANISM", "sasl.mechanism"),
+        ("KAFKA_SASL_USERNAME", "sasl.username"),
+        ("KAFKA_SASL_PASSWORD", "sasl.password"),
+    ]
+    .into_iter()
+    .filter_map(|(env_key, client_key)| {
+        env_value(env_key).map(|value| (client_key.to_string(), value))
+    })
+    .collect();
+    Ok(KafkaConfig {
+        brokers,
+        topic: env_value("KAFKA_TOPIC").unwrap_or_else(|| DEFAULT_TOPIC.to_string()),
+        group_id: env_value("KAFKA_GROUP_ID").unwrap_or_else(|| DEFAULT_GROUP_ID.to_string()),
+        dead_letter_topic: env_value("KAFKA_DEAD_LETTER_TOPIC"),
+        max_attempts,
+        poll_timeout: Duration::from_millis(poll_timeout_ms),
+        client_settings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_resolve_per_employee() {
+        assert_eq!(
+            resolve_template(DEFAULT_TOPIC, "little_bear"),
+            "dowhiz-ingestion-little_bear"
+        );
+        assert_eq!(resolve_template("shared", "little_bear"), "shared");
+    }
+
+    #[test]
+    fn attempt_header_defaults_to_zero_and_takes_latest() {
+        assert_eq!(attempt_from_headers(&OwnedHeaders::new()), 0);
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: ATTEMPT_HEADER,
+                value: Some("1"),
+            })
+            .insert(Header {
+                key: "other",
+                value: Some("9"),
+            })
+            .insert(Header {
+                key: ATTEMPT_HEADER,
+                value: Some("3"),
+            });
+        assert_eq!(attempt_from_headers(&headers), 3);
+    }
+}
@@ -1,13 +1,18 @@
+use chrono::{DateTime, Utc};
 use postgres::types::Type;
+use postgres::GenericClient;
 use postgres_native_tls::MakeTlsConnector;
 use r2d2::{Pool, PooledConnection};
 use r2d2_postgres::PostgresConnectionManager;
+use std::collections::BTreeSet;
 use std::env;
 use tracing::{error, warn};
 use uuid::Uuid;
 
 use crate::env_alias::{bool_with_scale_oliver, var_with_scale_oliver};
-use crate::ingestion::IngestionEnvelope;
+use crate::ingestion::azure_bus::AzureBusTopicConsumer;
+use crate::ingestion::{IngestionEnvelope, BACKGROUND_CHANNELS};
+use crate::migrations::{plan_steps, MigrationError, SchemaStatus, Step};
 use crate::service_bus_queue::ServiceBusIngestionQueue;
 
 /// Custom error handler that logs the actual connection error
@@ -36,8 +41,14 @@ pub enum IngestionQueueError {
     InvalidTableName(String),
     #[error("ingestion queue config error: {0}")]
     Config(String),
+    #[error("ingestion queue migration error: {0}")]
+    Migration(#[from] MigrationError),
     #[error("service bus error: {0}")]
     ServiceBus(String),
+    #[error("kafka error: {0}")]
+    Kafka(String),
+    #[error("{0} is not supported by this queue backend")]
+    Unsupported(&'static str),
 }
 
 #[derive(Debug, Clone)]
@@ -51,11 +62,40 @@ pub struct QueuedEnvelope {
     pub envelope: IngestionEnvelope,
 }
 
+/// An envelope that used up its attempts and is no longer claimed.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    pub employee_id: String,
+    pub channel: String,
+    pub dedupe_key: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub failed_at: Option<DateTime<Utc>>,
+}
+
 pub trait IngestionQueue: Send + Sync {
     fn enqueue(&self, envelope: &IngestionEnvelope) -> Result<EnqueueResult, IngestionQueueError>;
     fn claim_next(&self, employee_id: &str) -> Result<Option<QueuedEnvelope>, IngestionQueueError>;
     fn mark_done(&self, id: &Uuid) -> Result<(), IngestionQueueError>;
     fn mark_failed(&self, id: &Uuid, error: &str) -> Result<(), IngestionQueueError>;
+
+    /// Dead letters of `employee_id`, most recently failed first. Broker
+    /// backends keep theirs in the broker's own dead-letter queue.
+    fn list_dead_letters(
+        &self,
+        _employee_id: &str,
+        _limit: i64,
+    ) -> Result<Vec<DeadLetter>, IngestionQueueError> {
+        Err(IngestionQueueError::Unsupported("listing dead letters"))
+    }
+
+    /// Make a dead letter claimable again with a fresh set of attempts.
+    /// Returns false when `id` is not a dead letter.
+    fn requeue_dead_letter(&self, _id: &Uuid) -> Result<bool, IngestionQueueError> {
+        Err(IngestionQueueError::Unsupported("requeueing dead letters"))
+    }
 }
 
 #[derive(Clone)]
@@ -64,6 +104,8 @@ pub struct PostgresIngestionQueue {
     table: String,
     lease_secs: i64,
     max_attempts: i32,
+    /// Background envelopes are claimed as if received this much later.
+    background_lag_secs: i64,
     use_typed_queries: bool,
 }
 
@@ -74,6 +116,20 @@ pub fn resolve_ingestion_queue_backend() -> String {
         .unwrap_or_else(|| "postgres".to_string())
 }
 
+/// True for the message-broker backends (Service Bus queue or topic, Kafka),
+/// which need no database URL.
+pub fn is_broker_backend(backend: &str) -> bool {
+    matches!(
+        backend,
+        "servicebus"
+            | "service_bus"
+            | "servicebus_topic"
+            | "service_bus_topic"
+            | "kafka"
+            | "redpanda"
+    )
+}
+
 pub fn build_queue_from_env(
     db_url_override: Option<String>,
 ) -> Result<std::sync::Arc<dyn IngestionQueue>, IngestionQueueError> {
@@ -82,6 +138,13 @@ pub fn build_queue_from_env(
         let queue = ServiceBusIngestionQueue::from_env()?;
         return Ok(std::sync::Arc::new(queue));
     }
+    if backend == "servicebus_topic" || backend == "service_bus_topic" {
+        let queue = AzureBusTopicConsumer::from_env()?;
+        return Ok(std::sync::Arc::new(queue));
+    }
+    if backend == "kafka" || backend == "redpanda" {
+        return build_kafka_queue_from_env();
+    }
 
     if let Some(db_url) = db_url_override {
         if !db_url.trim().is_empty() {
@@ -93,6 +156,20 @@ pub fn build_queue_from_env(
     Ok(std::sync::Arc::new(PostgresIngestionQueue::from_env()?))
 }
 
+#[cfg(feature = "kafka")]
+fn build_kafka_queue_from_env() -> Result<std::sync::Arc<dyn IngestionQueue>, IngestionQueueError> {
+    let queue = crate::ingestion::kafka::KafkaIngestionQueue::from_env()?;
+    Ok(std::sync::Arc::new(queue))
+}
+
+#[cfg(not(feature = "kafka"))]
+fn build_kafka_queue_from_env() -> Result<std::sync::Arc<dyn IngestionQueue>, IngestionQueueError> {
+    Err(IngestionQueueError::Config(
+        "INGESTION_QUEUE_BACKEND=kafka needs scheduler_module built with --features kafka"
+            .to_string(),
+    ))
+}
+
 pub fn build_servicebus_queue_from_env(
 ) -> Result<std::sync::Arc<dyn IngestionQueue>, IngestionQueueError> {
     let queue = ServiceBusIngestionQueue::from_env()?;
@@ -129,14 +206,7 @@ impl PostgresIngestionQueue {
             pool_db_url.parse().map_err(IngestionQueueError::Postgres)?;
         let direct_config: postgres::Config =
             db_url.parse().map_err(IngestionQueueError::Postgres)?;
-        let mut tls_builder = native_tls::TlsConnector::builder();
-        if resolve_bool_env("INGESTION_QUEUE_TLS_ALLOW_INVALID_CERTS") {
-            tls_builder.danger_accept_invalid_certs(true);
-            tls_builder.danger_accept_invalid_hostnames(true);
-        }
-        let tls_connector = tls_builder
-            .build()
-            .map_err(|err| IngestionQueueError::Config(err.to_string()))?;
+        let tls_connector = tls_connector_from_env()?;
         let tls_for_pool = MakeTlsConnector::new(tls_connector.clone());
         let tls_for_direct = MakeTlsConnector::new(tls_connector.clone());
         let tls_for_fallback = MakeTlsConnector::new(tls_connector);
@@ -153,6 +223,7 @@ impl PostgresIngestionQueue {
             table,
             lease_secs,
             max_attempts,
+            background_lag_secs: resolve_i64_env("INGESTION_BACKGROUND_LAG_SECS", 600),
             use_typed_queries,
         };
         if let Err(err) = queue.ensure_schema_with_config(&direct_config, tls_for_direct) {
@@ -182,68 +253,22 @@ impl PostgresIngestionQueue {
         Ok(pool.get()?)
     }
 
-    #[allow(dead_code)]
-    fn ensure_schema(&self) -> Result<(), IngestionQueueError> {
-        let mut conn = self.connection()?;
-        let statement = format!(
-            "CREATE TABLE IF NOT EXISTS {table} (
-                id UUID PRIMARY KEY,
-                tenant_id TEXT,
-                employee_id TEXT NOT NULL,
-                channel TEXT NOT NULL,
-                external_message_id TEXT,
-                dedupe_key TEXT NOT NULL UNIQUE,
-                payload_json TEXT NOT NULL,
-                status TEXT NOT NULL,
-                created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
-                locked_at TIMESTAMPTZ,
-                locked_by TEXT,
-                processed_at TIMESTAMPTZ,
-                attempts INTEGER NOT NULL DEFAULT 0,
-                last_error TEXT,
-                available_at TIMESTAMPTZ
-            );
-            CREATE INDEX IF NOT EXISTS {table}_pending_idx
-                ON {table}(employee_id, status, created_at);
-            CREATE INDEX IF NOT EXISTS {table}_available_idx
-                ON {table}(status, available_at);",
-            table = self.table
-        );
-        conn.batch_execute(&statement)?;
-        Ok(())
-    }
-
     fn ensure_schema_with_config(
         &self,
         config: &postgres::Config,
         tls: MakeTlsConnector,
     ) -> Result<(), IngestionQueueError> {
         let mut conn = config.connect(tls)?;
-        let statement = format!(
-            "CREATE TABLE IF NOT EXISTS {table} (
-                id UUID PRIMARY KEY,
-                tenant_id TEXT,
-                employee_id TEXT NOT NULL,
-                channel TEXT NOT NULL,
-                external_message_id TEXT,
-                dedupe_key TEXT NOT NULL UNIQUE,
-                payload_json TEXT NOT NULL,
-                status TEXT NOT NULL,
-                created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
-                locked_at TIMESTAMPTZ,
-                locked_by TEXT,
-                processed_at TIMESTAMPTZ,
-                attempts INTEGER NOT NULL DEFAULT 0,
-                last_error TEXT,
-                available_at TIMESTAMPTZ
+        let applied = applied_ingestion_versions(&mut conn, &self.table)?;
+        let latest = latest_ingestion_version();
+        if let Some(current) = applied.iter().next_back().filter(|v| **v > latest) {
+            warn!(
+                "ingestion queue schema is at version {} but this build knows up to {}; skipping migrations",
+                current, latest
             );
-            CREATE INDEX IF NOT EXISTS {table}_pending_idx
-                ON {table}(employee_id, status, created_at);
-            CREATE INDEX IF NOT EXISTS {table}_available_idx
-                ON {table}(status, available_at);",
-            table = self.table
-        );
-        conn.batch_execute(&statement)?;
+            return Ok(());
+        }
+        migrate_ingestion_table(&mut conn, &self.table, latest)?;
         Ok(())
     }
 
@@ -305,6 +330,7 @@ impl IngestionQueue for PostgresIngestionQueue {
         let mut conn = self.connection()?;
         let instance_id = resolve_worker_instance_id(employee_id);
         let lease_secs = self.lease_secs;
+        let background_lag_secs = self.background_lag_secs;
 
         let mut tx = conn.transaction()?;
         let select_statement = format!(
@@ -318,9 +344,13 @@ impl IngestionQueue for PostgresIngestionQueue {
                AND (available_at IS NULL OR available_at <= now())
                AND attempts < $3
              ORDER BY created_at
+                 + CASE WHEN channel IN ({background}) THEN $4::bigint * interval '1 second'
+                        ELSE interval '0 second' END,
+                 created_at
              LIMIT 1
              FOR UPDATE SKIP LOCKED",
-            table = self.table
+            table = self.table,
+            background = background_channels_sql()
         );
         let row = if self.use_typed_queries {
             let mut rows = tx.query_typed(
@@ -329,13 +359,19 @@ impl IngestionQueue for PostgresIngestionQueue {
                     (&employee_id, Type::TEXT),
                     (&lease_secs, Type::INT8),
                     (&self.max_attempts, Type::INT4),
+                    (&background_lag_secs, Type::INT8),
                 ],
             )?;
             rows.pop()
         } else {
             tx.query_opt(
                 &select_statement,
-                &[&employee_id, &lease_secs, &self.max_attempts],
+                &[
+                    &employee_id,
+                    &lease_secs,
+                    &self.max_attempts,
+                    &background_lag_secs,
+                ],
             )?
         };
 
@@ -469,6 +505,64 @@ impl IngestionQueue for PostgresIngestionQueue {
         }
         Ok(())
     }
+
+    fn list_dead_letters(
+        &self,
+        employee_id: &str,
+        limit: i64,
+    ) -> Result<Vec<DeadLetter>, IngestionQueueError> {
+        let mut conn = self.connection()?;
+        let statement = format!(
+            "SELECT id, employee_id, channel, dedupe_key, attempts, last_error, created_at, processed_at
+             FROM {table}
+             WHERE employee_id = $1 AND status = 'failed'
+             ORDER BY processed_at DESC NULLS LAST
+             LIMIT $2",
+            table = self.table
+        );
+        let rows = if self.use_typed_queries {
+            conn.query_typed(
+                &statement,
+                &[(&employee_id, Type::TEXT), (&limit, Type::INT8)],
+            )?
+        } else {
+            conn.query(&statement, &[&employee_id, &limit])?
+        };
+        Ok(rows
+            .iter()
+            .map(|row| DeadLetter {
+                id: row.get(0),
+                employee_id: row.get(1),
+                channel: row.get(2),
+                dedupe_key: row.get(3),
+                attempts: row.get(4),
+                last_error: row.get(5),
+                created_at: row.get(6),
+                failed_at: row.get(7),
+            })
+            .collect())
+    }
+
+    fn requeue_dead_letter(&self, id: &Uuid) -> Result<bool, IngestionQueueError> {
+        let mut conn = self.connection()?;
+        let statement = format!(
+            "UPDATE {table}
+             SET status = 'pending',
+                 attempts = 0,
+                 available_at = NULL,
+                 locked_at = NULL,
+                 locked_by = NULL
+             WHERE id = $1 AND status = 'failed'
+             RETURNING 1",
+            table = self.table
+        );
+        let updated = if self.use_typed_queries {
+            conn.query_typed(&statement, &[(&id, Type::UUID)])?.len() as u64
+        } else {
+            conn.execute(&statement, &[id])?
+        };
+        Ok(updated > 0)
+    }
 }
 
 impl Drop for PostgresIngestionQueue {
@@ -479,6 +573,147 @@ impl Drop for PostgresIngestionQueue {
     }
 }
 
+struct SqlMigration {
+    version: u32,
+    name: &'static str,
+    up: &'static str,
+    down: &'static str,
+}
+
+/// Ordered schema migrations of the Postgres queue; `{table}` is replaced by
+/// the queue table. Released migrations are never edited.
+const INGESTION_MIGRATIONS: &[SqlMigration] = &[SqlMigration {
+    version: 1,
+    name: "create_queue",
+    up: "CREATE TABLE IF NOT EXISTS {table} (
+            id UUID PRIMARY KEY,
+            tenant_id TEXT,
+            employee_id TEXT NOT NULL,
+            channel TEXT NOT NULL,
+            external_message_id TEXT,
+            dedupe_key TEXT NOT NULL UNIQUE,
+            payload_json TEXT NOT NULL,
+            status TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            locked_at TIMESTAMPTZ,
+            locked_by TEXT,
+            processed_at TIMESTAMPTZ,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            available_at TIMESTAMPTZ
+        );
+        CREATE INDEX IF NOT EXISTS {table}_pending_idx
+            ON {table}(employee_id, status, created_at);
+        CREATE INDEX IF NOT EXISTS {table}_available_idx
+            ON {table}(status, available_at);",
+    down: "DROP TABLE IF EXISTS {table};",
+}];
+
+fn latest_ingestion_version() -> u32 {
+    INGESTION_MIGRATIONS
+        .last()
+        .map(|migration| migration.version)
+        .unwrap_or(0)
+}
+
+fn migrations_table(table: &str) -> String {
+    format!("{}_schema_migrations", table)
+}
+
+fn applied_ingestion_versions(
+    conn: &mut impl GenericClient,
+    table: &str,
+) -> Result<BTreeSet<u32>, IngestionQueueError> {
+    let migrations = migrations_table(table);
+    conn.batch_execute(&format!(
+        "CREATE TABLE IF NOT EXISTS {migrations} (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );"
+    ))?;
+    let rows = conn.query(&format!("SELECT version FROM {migrations}"), &[])?;
+    Ok(rows.iter().map(|row| row.get::<_, i32>(0) as u32).collect())
+}
+
+/// Moves the queue schema to `target` in one transaction, serialized across
+/// workers by an advisory lock on the table name.
+fn migrate_ingestion_table(
+    conn: &mut postgres::Client,
+    table: &str,
+    target: u32,
+) -> Result<Vec<Step>, IngestionQueueError> {
+    let migrations = migrations_table(table);
+    let mut tx = conn.transaction()?;
+    tx.execute("SELECT pg_advisory_xact_lock(hashtext($1))", &[&table])?;
+    let applied = applied_ingestion_versions(&mut tx, table)?;
+    let known: Vec<u32> = INGESTION_MIGRATIONS.iter().map(|m| m.version).collect();
+    let steps = plan_steps(&known, &applied, target)?;
+    for step in &steps {
+        let (version, up) = match *step {
+            Step::Up(version) => (version, true),
+            Step::Down(version) => (version, false),
+        };
+        let migration = INGESTION_MIGRATIONS
+            .iter()
+            .find(|migration| migration.version == version)
+            .ok_or(MigrationError::UnknownVersion(version))?;
+        let statement = if up { migration.up } else { migration.down };
+        tx.batch_execute(&statement.replace("{table}", table))?;
+        if up {
+            tx.execute(
+                &format!("INSERT INTO {migrations} (version, name) VALUES ($1, $2)"),
+                &[&(version as i32), &migration.name],
+            )?;
+        } else {
+            tx.execute(
+                &format!("DELETE FROM {migrations} WHERE version = $1"),
+                &[&(version as i32)],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(steps)
+}
+
+fn connect_direct_from_env() -> Result<(postgres::Client, String), IngestionQueueError> {
+    let config: postgres::Config = resolve_db_url()?
+        .parse()
+        .map_err(IngestionQueueError::Postgres)?;
+    let table = sanitize_table_name(&resolve_table_name()?)?;
+    let conn = config.connect(MakeTlsConnector::new(tls_connector_from_env()?))?;
+    Ok((conn, table))
+}
+
+/// Schema status of the queue table named by `INGESTION_QUEUE_TABLE`.
+pub fn ingestion_schema_status_from_env() -> Result<SchemaStatus, IngestionQueueError> {
+    let (mut conn, table) = connect_direct_from_env()?;
+    Ok(SchemaStatus {
+        store: "ingestion",
+        applied: applied_ingestion_versions(&mut conn, &table)?
+            .into_iter()
+            .collect(),
+        latest: latest_ingestion_version(),
+    })
+}
+
+/// Applies or rolls back queue migrations until the schema is at `target`.
+pub fn migrate_ingestion_schema_from_env(target: u32) -> Result<Vec<Step>, IngestionQueueError> {
+    let (mut conn, table) = connect_direct_from_env()?;
+    migrate_ingestion_table(&mut conn, &table, target)
+}
+
+fn tls_connector_from_env() -> Result<native_tls::TlsConnector, IngestionQueueError> {
+    let mut tls_builder = native_tls::TlsConnector::builder();
+    if resolve_bool_env("INGESTION_QUEUE_TLS_ALLOW_INVALID_CERTS") {
+        tls_builder.danger_accept_invalid_certs(true);
+        tls_builder.danger_accept_invalid_hostnames(true);
+    }
+    tls_builder
+        .build()
+        .map_err(|err| IngestionQueueError::Config(err.to_string()))
+}
+
 fn resolve_db_url() -> Result<String, IngestionQueueError> {
     env::var("INGESTION_DB_URL")
         .ok()
@@ -522,6 +757,15 @@ fn sanitize_table_name(raw: &str) -> Result<String, IngestionQueueError> {
     Ok(raw.to_string())
 }
 
+/// `BACKGROUND_CHANNELS` as a quoted SQL list of channel names.
+fn background_channels_sql() -> String {
+    BACKGROUND_CHANNELS
+        .iter()
+        .map(|channel| format!("'{}'", channel))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 fn resolve_i64_env(key: &str, default_value: i64) -> i64 {
     env::var(key)
         .ok()
@@ -561,7 +805,21 @@ fn resolve_pooler_db_url() -> Option<String> {
         })
 }
 
-fn resolve_worker_instance_id(employee_id: &str) -> String {
+/// The worker's queue, registered at startup so the scheduler can enqueue
+/// handoffs to other employees.
+static INGESTION_QUEUE: std::sync::OnceLock<std::sync::Arc<dyn IngestionQueue>> =
+    std::sync::OnceLock::new();
+
+pub fn set_global_ingestion_queue(queue: std::sync::Arc<dyn IngestionQueue>) {
+    let _ = INGESTION_QUEUE.set(queue);
+}
+
+/// The registered queue; `None` outside the worker.
+pub fn get_global_ingestion_queue() -> Option<std::sync::Arc<dyn IngestionQueue>> {
+    INGESTION_QUEUE.get().cloned()
+}
+
+pub(crate) fn resolve_worker_instance_id(employee_id: &str) -> String {
     if let Ok(value) = env::var("WORKER_INSTANCE_ID") {
         let trimmed = value.trim();
         if !trimmed.is_empty() {
@@ -627,6 +885,8 @@ mod tests {
             payload: IngestionPayload::from_inbound(&message),
             raw_payload_ref: None,
             account_id: None,
+            trace_id: None,
+            handoff: None,
         }
     }
 
@@ -716,4 +976,65 @@ mod tests {
         assert!(!second.inserted);
         queue.drop_table_for_tests();
     }
+
+    #[test]
+    fn background_channels_are_quoted_for_sql() {
+        assert_eq!(
+            background_channels_sql(),
+            "'google_docs', 'google_sheets', 'google_slides', 'notion', 'jira'"
+        );
+    }
+
+    #[test]
+    fn interactive_envelopes_are_claimed_before_recent_background_ones() {
+        if is_service_bus_backend() {
+            eprintln!("Service Bus backend claims in arrival order; skipping.");
+            return;
+        }
+        let Some(queue) = postgres_queue() else {
+            return;
+        };
+        let mut background = sample_envelope("emp", "background-1");
+        background.channel = Channel::Jira;
+        queue.enqueue(&background).expect("enqueue");
+        queue
+            .enqueue(&sample_envelope("emp", "interactive-1"))
+            .expect("enqueue");
+
+        let first = queue.claim_next("emp").expect("claim").expect("envelope");
+        assert_eq!(first.envelope.dedupe_key, "interactive-1");
+        let second = queue.claim_next("emp").expect("claim").expect("envelope");
+        assert_eq!(second.envelope.dedupe_key, "background-1");
+        queue.drop_table_for_tests();
+    }
+
+    #[test]
+    fn failed_envelope_is_listed_and_requeued() {
+        if is_service_bus_backend() {
+            eprintln!("Service Bus backend keeps dead letters in the broker; skipping.");
+            return;
+        }
+        let Some(mut queue) = postgres_queue() else {
+            return;
+        };
+        queue.max_attempts = 1;
+        let envelope = sample_envelope("emp", "dead-letter-1");
+        queue.enqueue(&envelope).expect("enqueue");
+        let claimed = queue.claim_next("emp").expect("claim").expect("envelope");
+        queue.mark_failed(&claimed.id, "boom").expect("fail");
+        assert!(queue.claim_next("emp").expect("claim").is_none());
+
+        let dead = queue.list_dead_letters("emp", 10).expect("list");
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].dedupe_key, "dead-letter-1");
+        assert_eq!(dead[0].last_error.as_deref(), Some("boom"));
+
+        assert!(queue.requeue_dead_letter(&claimed.id).expect("requeue"));
+        assert!(!queue
+            .requeue_dead_letter(&claimed.id)
+            .expect("requeue again"));
+        let reclaimed = queue.claim_next("emp").expect("claim").expect("envelope");
+        assert_eq!(reclaimed.id, claimed.id);
+        queue.drop_table_for_tests();
+    }
 }
@@ -0,0 +1,306 @@
+//! Internal messaging between employees.
+//!
+//! An employee delegates a request to another over [`Channel::Internal`]; the
+//! delegate works it in a thread of its own and its reply travels back the
+//! same way as the result. Both directions are ingestion envelopes on the
+//! shared queue, so nothing goes out over real email. The requester parks the
+//! run_task that asked in its state dir, keyed by correlation id, and repeats
+//! it on the same thread once the result arrives.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::channel::{Attachment, Channel, ChannelMetadata};
+use crate::ingestion::{IngestionEnvelope, IngestionPayload};
+use crate::ingestion_queue::{get_global_ingestion_queue, IngestionQueueError};
+use crate::RunTaskTask;
+
+/// Directory under the requester's state dir holding pending delegations.
+const DELEGATIONS_DIR: &str = "delegations";
+/// File in the delegate's workspace naming the request it answers.
+pub const CORRELATION_FILE: &str = "internal_correlation.json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum InternalBusError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("queue error: {0}")]
+    Queue(#[from] IngestionQueueError),
+    #[error("no ingestion queue configured")]
+    NoQueue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InternalMessageKind {
+    Request,
+    Result,
+}
+
+/// Ties a delegated request to its result; carried in
+/// [`ChannelMetadata::internal_correlation`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InternalCorrelation {
+    pub correlation_id: Uuid,
+    pub kind: InternalMessageKind,
+    /// Employee whose thread waits for the result.
+    pub requester_employee_id: String,
+    /// Owner of the requester's scheduler; the thread resumes there.
+    pub requester_user_id: String,
+    /// A health probe the employee sent itself; see [`crate::health_probe`].
+    #[serde(default)]
+    pub probe: bool,
+}
+
+/// A delegated request still waiting for its result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingDelegation {
+    pub correlation_id: Uuid,
+    pub delegate_employee_id: String,
+    pub request: String,
+    /// The run_task that asked, repeated on its thread with the result.
+    pub task: RunTaskTask,
+    pub created_at: DateTime<Utc>,
+}
+
+fn pending_path(state_dir: &Path, correlation_id: Uuid) -> PathBuf {
+    state_dir
+        .join(DELEGATIONS_DIR)
+        .join(format!("{}.json", correlation_id))
+}
+
+pub fn save_pending(state_dir: &Path, pending: &PendingDelegation) -> Result<(), InternalBusError> {
+    let path = pending_path(state_dir, pending.correlation_id);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec_pretty(pending)?)?;
+    Ok(())
+}
+
+/// Remove and return the pending delegation, so a result resumes the thread
+/// at most once.
+pub fn take_pending(
+    state_dir: &Path,
+    correlation_id: Uuid,
+) -> Result<Option<PendingDelegation>, InternalBusError> {
+    let path = pending_path(state_dir, correlation_id);
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    fs::remove_file(&path)?;
+    Ok(Some(serde_json::from_slice(&bytes)?))
+}
+
+/// The envelope carrying `text` from one employee to another.
+pub fn internal_envelope(
+    from_employee_id: &str,
+    to_employee_id: &str,
+    text: &str,
+    attachments: Vec<Attachment>,
+    correlation: InternalCorrelation,
+    trace_id: Option<String>,
+) -> IngestionEnvelope {
+    let kind = match correlation.kind {
+        InternalMessageKind::Request => "request",
+        InternalMessageKind::Result => "result",
+    };
+    IngestionEnvelope {
+        envelope_id: Uuid::new_v4(),
+        received_at: Utc::now(),
+        tenant_id: None,
+        employee_id: to_employee_id.to_string(),
+        channel: Channel::Internal,
+        external_message_id: None,
+        // One request and one result per delegation, however often a send is retried.
+        dedupe_key: format!("internal:{}:{}", correlation.correlation_id, kind),
+        payload: IngestionPayload {
+            sender: from_employee_id.to_string(),
+            sender_name: None,
+            recipient: to_employee_id.to_string(),
+            subject: None,
+            text_body: Some(text.to_string()),
+            html_body: None,
+            thread_id: format!("internal:{}", correlation.correlation_id),
+            message_id: None,
+            attachments,
+            reply_to: vec![from_employee_id.to_string()],
+            metadata: ChannelMetadata {
+                internal_correlation: Some(correlation),
+                ..Default::default()
+            },
+        },
+        raw_payload_ref: None,
+        account_id: None,
+        trace_id,
+        handoff: None,
+    }
+}
+
+pub fn send(envelope: &IngestionEnvelope) -> Result<(), InternalBusError> {
+    let queue = get_global_ingestion_queue().ok_or(InternalBusError::NoQueue)?;
+    queue.enqueue(envelope)?;
+    Ok(())
+}
+
+/// Files directly under `dir`, encoded for an envelope.
+pub fn read_attachments(dir: &Path) -> Result<Vec<Attachment>, InternalBusError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    paths.sort();
+    let mut attachments = Vec::with_capacity(paths.len());
+    for path in paths {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        attachments.push(Attachment {
+            name: name.to_string(),
+            // Written back to disk by name on the other side.
+            content_type: "application/octet-stream".to_string(),
+            content: base64::engine::general_purpose::STANDARD.encode(fs::read(&path)?),
+        });
+    }
+    Ok(attachments)
+}
+
+/// Decode `attachments` into `dir`, keeping only the file name of each.
+pub fn write_attachments(dir: &Path, attachments: &[Attachment]) -> Result<(), InternalBusError> {
+    if attachments.is_empty() {
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+    for attachment in attachments {
+        let Some(name) = Path::new(&attachment.name).file_name() else {
+            continue;
+        };
+        let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(&attachment.content)
+        else {
+            continue;
+        };
+        fs::write(dir.join(name), bytes)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::tests::run_task_in;
+    use tempfile::TempDir;
+
+    fn correlation(kind: InternalMessageKind) -> InternalCorrelation {
+        InternalCorrelation {
+            correlation_id: Uuid::new_v4(),
+            kind,
+            requester_employee_id: "little_bear".to_string(),
+            requester_user_id: "user-1".to_string(),
+            probe: false,
+        }
+    }
+
+    fn run_task(workspace: &Path) -> RunTaskTask {
+        RunTaskTask {
+            thread_id: Some("slack:T1:C1:1.0".to_string()),
+            ..run_task_in(workspace)
+        }
+    }
+
+    #[test]
+    fn pending_delegation_is_taken_once() {
+        let temp = TempDir::new().expect("tempdir");
+        let correlation_id = Uuid::new_v4();
+        let pending = PendingDelegation {
+            correlation_id,
+            delegate_employee_id: "boiled_egg".to_string(),
+            request: "Fix the failing build".to_string(),
+            task: run_task(temp.path()),
+            created_at: Utc::now(),
+        };
+        save_pending(temp.path(), &pending).expect("save");
+
+        let taken = take_pending(temp.path(), correlation_id)
+            .expect("take")
+            .expect("pending");
+        assert_eq!(taken.delegate_employee_id, "boiled_egg");
+        assert_eq!(taken.task.thread_id.as_deref(), Some("slack:T1:C1:1.0"));
+        assert!(take_pending(temp.path(), correlation_id)
+            .expect("take again")
+            .is_none());
+    }
+
+    #[test]
+    fn internal_envelope_dedupes_per_correlation_and_kind() {
+        let request = correlation(InternalMessageKind::Request);
+        let first = internal_envelope(
+            "little_bear",
+            "boiled_egg",
+            "Fix the failing build",
+            Vec::new(),
+            request.clone(),
+            None,
+        );
+        let retry = internal_envelope(
+            "little_bear",
+            "boiled_egg",
+            "Fix the failing build",
+            Vec::new(),
+            request.clone(),
+            None,
+        );
+        let result = internal_envelope(
+            "boiled_egg",
+            "little_bear",
+            "Done",
+            Vec::new(),
+            InternalCorrelation {
+                kind: InternalMessageKind::Result,
+                ..request.clone()
+            },
+            None,
+        );
+
+        assert_eq!(first.employee_id, "boiled_egg");
+        assert_eq!(first.channel, Channel::Internal);
+        assert_eq!(first.dedupe_key, retry.dedupe_key);
+        assert_ne!(first.dedupe_key, result.dedupe_key);
+        assert_eq!(first.payload.metadata.internal_correlation, Some(request));
+    }
+
+    #[test]
+    fn attachments_round_trip_by_file_name() {
+        let temp = TempDir::new().expect("tempdir");
+        let source = temp.path().join("reply_attachments");
+        fs::create_dir_all(&source).expect("source");
+        fs::write(source.join("report.txt"), "all green").expect("write");
+
+        let mut attachments = read_attachments(&source).expect("read");
+        assert_eq!(attachments.len(), 1);
+        attachments[0].name = "../escape/report.txt".to_string();
+        let target = temp.path().join("incoming_attachments");
+        write_attachments(&target, &attachments).expect("write");
+
+        assert_eq!(
+            fs::read_to_string(target.join("report.txt")).expect("read back"),
+            "all green"
+        );
+        assert!(!temp.path().join("escape").exists());
+    }
+}
@@ -1,44 +1,79 @@
 pub mod adapters;
+pub mod alerting;
+pub mod archive_crypto;
+pub mod archive_tiering;
 pub mod artifact_extractor;
+pub mod attachment_vision;
+pub mod backup;
 pub mod channel;
 pub mod discord_gateway;
 pub mod domain;
 pub mod employee_config;
 pub mod env_alias;
+pub mod execution_retention;
 pub(crate) mod github_inbound;
 pub mod google_auth;
 pub mod google_docs_poller;
 pub mod google_drive_changes;
 pub mod google_workspace_poller;
+pub mod health_probe;
+pub mod i18n;
+pub mod inbound_dedupe;
+pub mod inbound_policy;
 pub mod ingestion;
 pub mod notion_browser;
 pub(crate) mod notion_email_detector;
 pub mod ingestion_queue;
+pub mod internal_bus;
+pub mod link_fetch;
 pub mod mailbox;
+pub mod message_link_store;
 pub mod message_router;
+pub mod migrations;
 pub mod mongo_store;
+pub mod object_store;
+pub mod outbound_policy;
 pub mod raw_payload_store;
+pub mod router_audit;
+pub mod redaction;
+pub mod reply_formatting;
 pub mod service_bus_queue;
+pub mod skill_market;
+pub mod slack_action_store;
 pub mod slack_store;
 pub mod notion_store;
 pub mod storage_backend;
+pub mod telemetry;
 pub(crate) mod thread_state;
+pub mod tools;
+pub mod trace_context;
+pub mod user_skills;
+pub(crate) mod workspace_recovery;
+pub(crate) mod workspace_snapshot;
 
 pub mod account_store;
+pub mod approval_store;
+pub mod audit_store;
 pub mod blob_store;
 pub mod index_store;
 pub mod memory_diff;
 pub mod memory_queue;
 pub mod memory_store;
+pub mod past_email_index;
 pub mod past_emails;
 pub mod secrets_store;
 pub mod service;
+pub mod task_budgets;
+pub mod task_costs;
 pub mod user_store;
 
 mod scheduler;
 
 pub use scheduler::{
-    load_google_access_token_from_service_env, load_tasks_with_status, ModuleExecutor, RunTaskTask,
-    Schedule, ScheduledTask, Scheduler, SchedulerError, SendReplyTask, TaskExecution, TaskExecutor,
-    TaskKind, TaskStatusSummary,
+    acquire_task_lease, list_task_deliveries, list_task_executions,
+    load_google_access_token_from_service_env, load_tasks_with_status, record_delivery_outcome,
+    task_execution_log_path, DeliveryOutcome, DigestTask, ExecutionQuery, HeartbeatSpec,
+    ModuleExecutor, NoopTask, ProbeTask, ProviderEventMatch, RecurrenceEnd, RunTaskTask, Schedule,
+    ScheduledTask, Scheduler, SchedulerError, SendReplyTask, TaskApproval, TaskDeliveryRecord,
+    TaskExecution, TaskExecutionRecord, TaskExecutor, TaskKind, TaskLease, TaskStatusSummary,
 };
@@ -0,0 +1,586 @@
+//! Fetch links from inbound messages into the workspace.
+//!
+//! Before a RunTask starts, `http(s)` URLs in the top-level message files of
+//! the incoming dir (`email.html`, `NNNN_<channel>.txt`, ...) are fetched,
+//! run through the inbound HTML cleaner and saved as readable text in
+//! `references/links/`. Runners then read the page without needing network
+//! access of their own.
+//!
+//! Fetches honour `robots.txt` (user agent `DoWhizBot`), skip hosts that
+//! resolve to private or loopback addresses, and stop at the size limit.
+//!
+//! Configuration:
+//! - `LINK_FETCH_ENABLED`: Set to "true" to enable (default: disabled)
+//! - `LINK_FETCH_MAX_LINKS`: Max pages fetched per task (default: 5)
+//! - `LINK_FETCH_MAX_BYTES`: Pages are cut off after this many bytes (default: 2 MiB)
+//! - `LINK_FETCH_TIMEOUT_SECS`: Timeout per request (default: 10)
+
+use std::collections::HashMap;
+use std::env;
+use std::io::Read;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use kuchiki::traits::*;
+use reqwest::Url;
+use tracing::{info, warn};
+
+/// Directory under the references dir that pages are saved to.
+pub const LINKS_DIR: &str = "links";
+
+const USER_AGENT: &str = "DoWhizBot/1.0 (+https://www.dowhiz.com)";
+const ROBOTS_AGENT: &str = "dowhizbot";
+const DEFAULT_MAX_LINKS: usize = 5;
+const DEFAULT_MAX_BYTES: u64 = 2 * 1024 * 1024;
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const MAX_REDIRECTS: usize = 5;
+
+/// Configuration for the link fetch stage
+#[derive(Debug, Clone)]
+pub struct LinkFetchConfig {
+    /// Whether the stage is enabled
+    pub enabled: bool,
+    /// Max pages fetched per task
+    pub max_links: usize,
+    /// Pages are cut off after this many bytes
+    pub max_bytes: u64,
+    /// Timeout per request
+    pub timeout: Duration,
+    /// Allow hosts on private networks (tests only)
+    pub allow_private_hosts: bool,
+}
+
+impl LinkFetchConfig {
+    pub fn from_env() -> Self {
+        let number = |key: &str| {
+            env::var(key)
+                .ok()
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        Self {
+            enabled: env::var("LINK_FETCH_ENABLED")
+                .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            max_links: number("LINK_FETCH_MAX_LINKS")
+                .map(|value| value as usize)
+                .unwrap_or(DEFAULT_MAX_LINKS),
+            max_bytes: number("LINK_FETCH_MAX_BYTES").unwrap_or(DEFAULT_MAX_BYTES),
+            timeout: Duration::from_secs(
+                number("LINK_FETCH_TIMEOUT_SECS").unwrap_or(DEFAULT_TIMEOUT_SECS),
+            ),
+            allow_private_hosts: false,
+        }
+    }
+}
+
+/// Run the link fetch stage over `incoming_dir` using configuration from env.
+///
+/// Failures are logged and never fail the task; the runner simply proceeds
+/// without the pages.
+pub fn fetch_message_links(incoming_dir: &Path, references_dir: &Path) {
+    let config = LinkFetchConfig::from_env();
+    if !config.enabled {
+        return;
+    }
+    let written = fetch_links_with(&config, incoming_dir, references_dir);
+    if written > 0 {
+        info!(
+            "link fetch saved {} page(s) in {}",
+            written,
+            references_dir.join(LINKS_DIR).display()
+        );
+    }
+}
+
+/// Fetch the URLs found in `incoming_dir` into `references_dir/links/`.
+/// Pages saved by an earlier attempt are kept, so retries don't fetch them
+/// again.
+///
+/// Returns the number of pages written.
+pub fn fetch_links_with(
+    config: &LinkFetchConfig,
+    incoming_dir: &Path,
+    references_dir: &Path,
+) -> usize {
+    let urls = message_urls(incoming_dir);
+    if urls.is_empty() {
+        return 0;
+    }
+    let allow_private = config.allow_private_hosts;
+    let client = match reqwest::blocking::Client::builder()
+        .timeout(config.timeout)
+        .user_agent(USER_AGENT)
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if is_fetchable(attempt.url(), allow_private) {
+                attempt.follow()
+            } else {
+                attempt.stop()
+            }
+        }))
+        .build()
+    {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("failed to build link fetch client: {}", err);
+            return 0;
+        }
+    };
+    let links_dir = references_dir.join(LINKS_DIR);
+    let mut robots: HashMap<String, Option<String>> = HashMap::new();
+    let mut written = 0usize;
+    for url in urls.iter().take(config.max_links) {
+        let target = links_dir.join(link_file_name(url));
+        if target.exists() {
+            continue;
+        }
+        if !is_fetchable(url, allow_private) {
+            info!("skipping link {}: not a public http(s) URL", url);
+            continue;
+        }
+        let origin = url.origin().ascii_serialization();
+        let rules = robots
+            .entry(origin.clone())
+            .or_insert_with(|| fetch_robots(&client, &origin, config.max_bytes));
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        if rules
+            .as_deref()
+            .is_some_and(|rules| !robots_allows(rules, &path))
+        {
+            info!("skipping link {}: disallowed by robots.txt", url);
+            continue;
+        }
+        let page = match fetch_page(&client, url, config.max_bytes) {
+            Ok(page) => page,
+            Err(err) => {
+                warn!("link fetch failed for {}: {}", url, err);
+                continue;
+            }
+        };
+        let mut content = format!(
+            "# {}\n\nSource: {}\nFetched: {}\n",
+            page.title.as_deref().unwrap_or(url.as_str()),
+            url,
+            chrono::Utc::now().to_rfc3339()
+        );
+        if page.truncated {
+            content.push_str(&format!(
+                "Note: the page was cut off after {} bytes.\n",
+                config.max_bytes
+            ));
+        }
+        content.push_str(&format!("\n{}\n", page.text));
+        let saved =
+            std::fs::create_dir_all(&links_dir).and_then(|_| std::fs::write(&target, content));
+        match saved {
+            Ok(()) => written += 1,
+            Err(err) => warn!("failed to write {}: {}", target.display(), err),
+        }
+    }
+    written
+}
+
+/// URLs in the top-level message files of `incoming_dir`, newest file first,
+/// without duplicates.
+fn message_urls(incoming_dir: &Path) -> Vec<Url> {
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(incoming_dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| matches!(ext, "html" | "txt" | "md"))
+            })
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name != "thread_history.md")
+            })
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    files.sort();
+    files.reverse();
+
+    let mut urls: Vec<Url> = Vec::new();
+    for file in files {
+        let Ok(text) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        for url in extract_urls(&text) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+    urls
+}
+
+fn extract_urls(text: &str) -> Vec<Url> {
+    let pattern = regex::Regex::new(r#"https?://[^\s<>"'`]+"#).expect("url regex");
+    pattern
+        .find_iter(text)
+        .filter_map(|found| {
+            let raw = found
+                .as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '}'])
+                .replace("&amp;", "&");
+            let mut url = Url::parse(&raw).ok()?;
+            url.set_fragment(None);
+            Some(url)
+        })
+        .collect()
+}
+
+/// Only http(s) URLs whose host resolves to public addresses, so messages
+/// can't point the service at its own network.
+fn is_fetchable(url: &Url, allow_private: bool) -> bool {
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+    if allow_private {
+        return true;
+    }
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    match (host, port).to_socket_addrs() {
+        Ok(addrs) => {
+            let addrs: Vec<_> = addrs.collect();
+            !addrs.is_empty() && addrs.iter().all(|addr| is_public_ip(addr.ip()))
+        }
+        Err(_) => false,
+    }
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(mapped));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // Link-local, fe80::/10
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// The site's robots.txt, or `None` when it has none or can't be read.
+fn fetch_robots(
+    client: &reqwest::blocking::Client,
+    origin: &str,
+    max_bytes: u64,
+) -> Option<String> {
+    let response = client.get(format!("{}/robots.txt", origin)).send().ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let mut body = Vec::new();
+    response.take(max_bytes).read_to_end(&mut body).ok()?;
+    Some(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Whether `robots` lets [`ROBOTS_AGENT`] fetch `path`. Uses the group naming
+/// the agent, else the `*` group; the longest matching rule wins and `Allow`
+/// wins ties.
+fn robots_allows(robots: &str, path: &str) -> bool {
+    #[derive(Default)]
+    struct Group {
+        agents: Vec<String>,
+        /// `(allow, pattern)`
+        rules: Vec<(bool, String)>,
+    }
+
+    let mut groups: Vec<Group> = Vec::new();
+    let mut in_agents = false;
+    for line in robots.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+        match key.as_str() {
+            "user-agent" => {
+                if !in_agents {
+                    groups.push(Group::default());
+                }
+                in_agents = true;
+                if let Some(group) = groups.last_mut() {
+                    group.agents.push(value.to_ascii_lowercase());
+                }
+            }
+            "allow" | "disallow" => {
+                in_agents = false;
+                if let Some(group) = groups.last_mut() {
+                    if !value.is_empty() {
+                        group.rules.push((key == "allow", value.to_string()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    let group = groups
+        .iter()
+        .find(|group| {
+            group
+                .agents
+                .iter()
+                .any(|agent| ROBOTS_AGENT.starts_with(agent.as_str()) && agent != "*")
+        })
+        .or_else(|| {
+            groups
+                .iter()
+                .find(|group| group.agents.iter().any(|agent| agent == "*"))
+        });
+    let Some(group) = group else {
+        return true;
+    };
+    group
+        .rules
+        .iter()
+        .filter(|(_, pattern)| robots_pattern_matches(pattern, path))
+        .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+        .is_none_or(|(allow, _)| *allow)
+}
+
+/// Match a robots.txt path pattern (`*` wildcards, `$` end anchor) against
+/// the start of `path`.
+fn robots_pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut regex = String::from("^");
+    for (index, part) in pattern.split('*').enumerate() {
+        if index > 0 {
+            regex.push_str(".*");
+        }
+        regex.push_str(&regex::escape(part));
+    }
+    if anchored {
+        regex.push('$');
+    }
+    regex::Regex::new(&regex).is_ok_and(|regex| regex.is_match(path))
+}
+
+struct Page {
+    title: Option<String>,
+    text: String,
+    truncated: bool,
+}
+
+fn fetch_page(
+    client: &reqwest::blocking::Client,
+    url: &Url,
+    max_bytes: u64,
+) -> Result<Page, String> {
+    let response = client
+        .get(url.clone())
+        .send()
+        .map_err(|err| err.to_string())?;
+    let status = response.status();
+    if status.is_redirection() {
+        return Err("redirected to a host that is not fetched".to_string());
+    }
+    if !status.is_success() {
+        return Err(format!("HTTP {}", status));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("text/html")
+        .to_ascii_lowercase();
+    let is_html = content_type.contains("html");
+    if !is_html && !content_type.starts_with("text/") {
+        return Err(format!("unsupported content type {}", content_type));
+    }
+    let mut body = Vec::new();
+    response
+        .take(max_bytes + 1)
+        .read_to_end(&mut body)
+        .map_err(|err| err.to_string())?;
+    let truncated = body.len() as u64 > max_bytes;
+    body.truncate(max_bytes as usize);
+    let raw = String::from_utf8_lossy(&body);
+
+    if !is_html {
+        return Ok(Page {
+            title: None,
+            text: raw.trim().to_string(),
+            truncated,
+        });
+    }
+    let title = kuchiki::parse_html()
+        .one(raw.as_ref())
+        .select_first("title")
+        .ok()
+        .map(|title| {
+            title
+                .as_node()
+                .text_contents()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|title| !title.is_empty());
+    Ok(Page {
+        title,
+        text: crate::service::html::page_to_text(&raw),
+        truncated,
+    })
+}
+
+/// `<host>_<path>.md`, lowercased, with other characters as `-`.
+fn link_file_name(url: &Url) -> String {
+    let raw = format!(
+        "{}{}{}",
+        url.host_str().unwrap_or("link"),
+        url.path(),
+        url.query()
+            .map(|query| format!("?{}", query))
+            .unwrap_or_default()
+    );
+    let mut name = String::new();
+    for ch in raw.chars() {
+        if ch.is_ascii_alphanumeric() || ch == '.' {
+            name.push(ch.to_ascii_lowercase());
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+        if name.len() >= 100 {
+            break;
+        }
+    }
+    format!("{}.md", name.trim_matches(['-', '.']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> LinkFetchConfig {
+        LinkFetchConfig {
+            enabled: true,
+            max_links: 5,
+            max_bytes: 64 * 1024,
+            timeout: Duration::from_secs(5),
+            allow_private_hosts: true,
+        }
+    }
+
+    #[test]
+    fn linked_pages_are_saved_as_readable_text() {
+        let mut server = mockito::Server::new();
+        server
+            .mock("GET", "/robots.txt")
+            .with_body("User-agent: *\nDisallow: /private\n")
+            .create();
+        let page = server
+            .mock("GET", "/blog/release")
+            .with_header("content-type", "text/html; charset=utf-8")
+            .with_body(
+                r#"<html><head><title>Release 2.0</title><script>track()</script></head>
+                <body><nav><a href="/">Home</a></nav>
+                <h1>Release 2.0</h1>
+                <p>The release adds <a href="https://example.com/docs">new docs</a>.</p>
+                <ul><li>Faster builds</li><li>Fewer bugs</li></ul>
+                <footer>Copyright</footer></body></html>"#,
+            )
+            .create();
+        let private = server.mock("GET", "/private/notes").expect(0).create();
+
+        let temp = tempfile::TempDir::new().expect("tempdir");
+        let incoming = temp.path().join("incoming_email");
+        std::fs::create_dir_all(&incoming).unwrap();
+        std::fs::write(
+            incoming.join("0001_slack.txt"),
+            format!(
+                "Can you summarize {url}/blog/release? Also {url}/private/notes and {url}/blog/release again.",
+                url = server.url()
+            ),
+        )
+        .unwrap();
+        let references = temp.path().join("references");
+
+        assert_eq!(fetch_links_with(&test_config(), &incoming, &references), 1);
+        page.assert();
+        private.assert();
+
+        let saved = std::fs::read_dir(references.join(LINKS_DIR))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        assert_eq!(saved.len(), 1);
+        assert!(saved[0]
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .ends_with("-blog-release.md"));
+        let content = std::fs::read_to_string(&saved[0]).unwrap();
+        assert!(content.starts_with(&format!(
+            "# Release 2.0\n\nSource: {}/blog/release\n",
+            server.url()
+        )));
+        assert!(content.ends_with(
+            "# Release 2.0\n\nThe release adds [new docs](https://example.com/docs).\n\n- Faster builds\n- Fewer bugs\n"
+        ));
+        assert!(!content.contains("Home") && !content.contains("Copyright"));
+
+        // A retry keeps the saved page instead of fetching it again.
+        assert_eq!(fetch_links_with(&test_config(), &incoming, &references), 0);
+        page.assert();
+    }
+
+    #[test]
+    fn robots_rules_pick_the_most_specific_group_and_rule() {
+        let robots = "User-agent: *\nDisallow: /\n\nUser-agent: DoWhizBot\nDisallow: /drafts\nAllow: /drafts/public$\nDisallow: /*.pdf\n";
+        assert!(robots_allows(robots, "/blog/post"));
+        assert!(!robots_allows(robots, "/drafts/secret"));
+        assert!(robots_allows(robots, "/drafts/public"));
+        assert!(!robots_allows(robots, "/files/report.pdf"));
+        assert!(!robots_allows("User-agent: *\nDisallow: /\n", "/anything"));
+        assert!(robots_allows(
+            "User-agent: other\nDisallow: /\n",
+            "/anything"
+        ));
+    }
+
+    #[test]
+    fn private_and_non_http_urls_are_not_fetched() {
+        let url = |raw: &str| Url::parse(raw).unwrap();
+        assert!(!is_fetchable(&url("http://127.0.0.1/admin"), false));
+        assert!(!is_fetchable(&url("http://10.0.0.5/"), false));
+        assert!(!is_fetchable(&url("http://[::1]:8080/"), false));
+        assert!(!is_fetchable(
+            &url("http://169.254.169.254/latest/meta-data"),
+            false
+        ));
+        assert!(!is_fetchable(&url("ftp://example.com/file"), false));
+        assert!(is_public_ip("93.184.216.34".parse().unwrap()));
+    }
+}
@@ -0,0 +1,214 @@
+//! Links between messages an employee posted in chat and the thread they belong to.
+//!
+//! Every Slack/Discord reply is recorded here keyed by `(channel, message_id)` so a
+//! reaction on that message can be traced back to the requester, the thread
+//! workspace, and any pending confirmation it carries.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use mongodb::bson::{doc, Document};
+use mongodb::options::UpdateOptions;
+use mongodb::sync::Collection;
+use serde::{Deserialize, Serialize};
+
+use crate::channel::Channel;
+use crate::mongo_store::{create_client_from_env, database_from_env};
+
+/// What a control reaction on an employee message asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReactionControl {
+    /// ⏰ push the thread's pending work back (or remind later if nothing is pending)
+    Snooze,
+    /// ❌ cancel pending work on the thread
+    Cancel,
+    /// ✅ approve the action proposed in the message
+    Approve,
+}
+
+impl ReactionControl {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Snooze => "snooze",
+            Self::Cancel => "cancel",
+            Self::Approve => "approve",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "snooze" => Some(Self::Snooze),
+            "cancel" => Some(Self::Cancel),
+            "approve" => Some(Self::Approve),
+            _ => None,
+        }
+    }
+
+    /// Map a Slack reaction name (`reaction_added.reaction`).
+    pub fn from_slack_reaction(name: &str) -> Option<Self> {
+        // Skin-tone variants arrive as `name::skin-tone-2`.
+        match name.split("::").next().unwrap_or(name) {
+            "alarm_clock" => Some(Self::Snooze),
+            "x" => Some(Self::Cancel),
+            "white_check_mark" | "heavy_check_mark" | "ballot_box_with_check" => {
+                Some(Self::Approve)
+            }
+            _ => None,
+        }
+    }
+
+    /// Map a unicode emoji (Discord reactions).
+    pub fn from_emoji(emoji: &str) -> Option<Self> {
+        match emoji.trim_end_matches('\u{fe0f}') {
+            "⏰" => Some(Self::Snooze),
+            "❌" => Some(Self::Cancel),
+            "✅" | "✔" | "☑" => Some(Self::Approve),
+            _ => None,
+        }
+    }
+}
+
+/// A message posted by an employee in a chat thread.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageLink {
+    pub channel: Channel,
+    /// Slack message `ts` or Discord message ID
+    pub message_id: String,
+    /// Slack channel ID or Discord channel/thread ID the message was posted in
+    pub channel_id: String,
+    #[serde(default)]
+    pub employee_id: Option<String>,
+    /// Chat user whose thread produced the message (owner of the tasks)
+    pub requester_id: String,
+    pub workspace_dir: String,
+    /// Slack confirmation callback_id posted alongside this message, if any
+    #[serde(default)]
+    pub pending_action_id: Option<String>,
+    #[serde(with = "mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MessageLinkStoreError {
+    #[error("mongodb error: {0}")]
+    Mongo(#[from] mongodb::error::Error),
+    #[error("bson error: {0}")]
+    Serialize(#[from] mongodb::bson::ser::Error),
+    #[error("bson error: {0}")]
+    Deserialize(#[from] mongodb::bson::de::Error),
+    #[error("mongo config error: {0}")]
+    MongoConfig(String),
+}
+
+/// Store for message → thread links.
+#[derive(Debug, Clone)]
+pub struct MessageLinkStore {
+    links: Collection<Document>,
+}
+
+impl MessageLinkStore {
+    pub fn new() -> Result<Self, MessageLinkStoreError> {
+        let client = create_client_from_env()
+            .map_err(|err| MessageLinkStoreError::MongoConfig(err.to_string()))?;
+        let db = database_from_env(&client);
+        let links = db.collection::<Document>("chat_message_links");
+        Ok(Self { links })
+    }
+
+    /// Insert or replace the link for a message.
+    pub fn record(&self, link: &MessageLink) -> Result<(), MessageLinkStoreError> {
+        let document = mongodb::bson::to_document(link)?;
+        self.links.update_one(
+            doc! { "channel": link.channel.to_string(), "message_id": &link.message_id },
+            doc! { "$set": document },
+            UpdateOptions::builder().upsert(true).build(),
+        )?;
+        Ok(())
+    }
+
+    pub fn find(
+        &self,
+        channel: Channel,
+        message_id: &str,
+    ) -> Result<Option<MessageLink>, MessageLinkStoreError> {
+        match self.links.find_one(
+            doc! { "channel": channel.to_string(), "message_id": message_id },
+            None,
+        )? {
+            Some(document) => Ok(Some(mongodb::bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+static MESSAGE_LINK_STORE: std::sync::OnceLock<Option<Arc<MessageLinkStore>>> =
+    std::sync::OnceLock::new();
+
+/// Get or initialize the global MessageLinkStore (returns None if not configured)
+pub fn get_global_message_link_store() -> Option<Arc<MessageLinkStore>> {
+    MESSAGE_LINK_STORE
+        .get_or_init(|| match MessageLinkStore::new() {
+            Ok(store) => Some(Arc::new(store)),
+            Err(err) => {
+                tracing::warn!(
+                    "MessageLinkStore not available ({}), reaction controls disabled",
+                    err
+                );
+                None
+            }
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reaction_control_maps_slack_names_and_emoji() {
+        assert_eq!(
+            ReactionControl::from_slack_reaction("alarm_clock"),
+            Some(ReactionControl::Snooze)
+        );
+        assert_eq!(
+            ReactionControl::from_slack_reaction("white_check_mark::skin-tone-3"),
+            Some(ReactionControl::Approve)
+        );
+        assert_eq!(ReactionControl::from_slack_reaction("tada"), None);
+        assert_eq!(
+            ReactionControl::from_emoji("❌"),
+            Some(ReactionControl::Cancel)
+        );
+        assert_eq!(
+            ReactionControl::from_emoji("✔\u{fe0f}"),
+            Some(ReactionControl::Approve)
+        );
+        assert_eq!(
+            ReactionControl::parse("snooze"),
+            Some(ReactionControl::Snooze)
+        );
+    }
+
+    #[test]
+    fn message_link_round_trips_through_bson() {
+        let link = MessageLink {
+            channel: Channel::Discord,
+            message_id: "111222333".to_string(),
+            channel_id: "555".to_string(),
+            employee_id: Some("little_bear".to_string()),
+            requester_id: "42".to_string(),
+            workspace_dir: "/tmp/users/u1/workspaces/thread".to_string(),
+            pending_action_id: None,
+            created_at: Utc::now(),
+        };
+
+        let document = mongodb::bson::to_document(&link).expect("to bson");
+        assert_eq!(document.get_str("channel").unwrap(), "discord");
+        assert!(document.get_datetime("created_at").is_ok());
+
+        let parsed: MessageLink = mongodb::bson::from_document(document).expect("from bson");
+        assert_eq!(parsed.channel, Channel::Discord);
+        assert_eq!(parsed.requester_id, "42");
+    }
+}
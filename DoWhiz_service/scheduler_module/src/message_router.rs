@@ -9,8 +9,17 @@
 //! - `ROUTER_MODEL`: Model to use (default: `gpt-5.4`)
 //! - `AZURE_OPENAI_API_KEY_BACKUP` + `AZURE_OPENAI_ENDPOINT_BACKUP`: use Azure OpenAI
 //! - `ROUTER_ENABLED`: Set to "false" to disable routing (default: enabled)
+//! - `ROUTER_STREAMING`: Set to "false" to stop streaming quick responses to
+//!   Slack and Discord (default: enabled)
+//!
+//! An employee's `[employees.router]` table in employee.toml overrides the
+//! provider, model, temperature and classification prompt, sets its length
+//! and token limits, and picks what happens when the model API cannot be
+//! reached; see [`RouterProfileConfig`].
 
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use reqwest::Client;
@@ -33,6 +42,33 @@ const FORWARD_MARKER: &str = "FORWARD_TO_AGENT";
 /// Messages longer than this are automatically forwarded to the full pipeline.
 const MAX_SIMPLE_MESSAGE_LENGTH: usize = 300;
 
+/// Default cap on the tokens of a quick response
+const DEFAULT_MAX_REPLY_TOKENS: u32 = 1024;
+
+/// Longest message the heuristic router answers; longer ones are forwarded
+const HEURISTIC_MAX_WORDS: usize = 4;
+
+/// Greetings the heuristic router answers without a model
+const HEURISTIC_GREETINGS: &[&str] = &[
+    "hi",
+    "hello",
+    "hey",
+    "hiya",
+    "good morning",
+    "good afternoon",
+    "good evening",
+];
+
+/// Thanks the heuristic router answers without a model
+const HEURISTIC_THANKS: &[&str] = &[
+    "thanks",
+    "thank you",
+    "thx",
+    "ty",
+    "much appreciated",
+    "cheers",
+];
+
 /// Build system prompt for the classifier/responder with employee identity
 fn build_system_prompt(employee_name: Option<&str>) -> String {
     let name = employee_name.unwrap_or("Boiled-Egg");
@@ -43,6 +79,8 @@ Your job is to classify messages:
 1. RESPOND DIRECTLY to questions you can answer quickly (greetings, casual chat, simple questions, thank you messages)
 2. Output ONLY "FORWARD_TO_AGENT" for tasks that require tools, code, file operations, research, or multi-step work
 
+If the conversation context lists your recent quick replies, a short follow-up to them (an answer, a choice, a confirmation like "3pm works") continues that exchange: reply directly unless it asks for one of the tasks above.
+
 When responding directly:
 - IMPORTANT: If user memory is provided, use their name and any relevant details to personalize your response
 - Address the user by name when greeting them or when it feels natural
@@ -85,6 +123,148 @@ pub enum RouterDecision {
     Passthrough,
 }
 
+/// Where the router's model runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouterProvider {
+    OpenAi,
+    Azure,
+    /// No model: greetings and thanks are answered locally, the rest is forwarded.
+    Heuristic,
+}
+
+impl std::str::FromStr for RouterProvider {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "openai" => Ok(Self::OpenAi),
+            "azure" => Ok(Self::Azure),
+            "heuristic" => Ok(Self::Heuristic),
+            other => Err(format!(
+                "unknown provider '{}' (expected openai, azure or heuristic)",
+                other
+            )),
+        }
+    }
+}
+
+/// What the router does when its model API fails or cannot be reached
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RouterFallback {
+    /// Answer greetings and thanks locally and forward the rest.
+    #[default]
+    Heuristic,
+    /// Forward every message to the full pipeline.
+    Forward,
+}
+
+/// Raw `[employees.router]` table.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouterProfileConfig {
+    /// `openai`, `azure` or `heuristic`; unset picks Azure when its
+    /// credentials are set and OpenAI otherwise.
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Replaces `ROUTER_MODEL` for this employee.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Sampling temperature between 0 and 2; the provider default when unset.
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Classification prompt, relative to employee.toml. `{name}` is replaced
+    /// by the employee's display name, and the prompt must tell the model to
+    /// answer `FORWARD_TO_AGENT` for work it cannot do itself.
+    #[serde(default)]
+    pub prompt_path: Option<PathBuf>,
+    /// Longer messages skip the router (default 300 characters).
+    #[serde(default)]
+    pub max_message_chars: Option<usize>,
+    /// Cap on the tokens of a quick response (default 1024).
+    #[serde(default)]
+    pub max_reply_tokens: Option<u32>,
+    /// `heuristic` (default) or `forward`; see [`RouterFallback`].
+    #[serde(default)]
+    pub fallback: Option<String>,
+}
+
+/// An employee's router settings, validated and with the prompt loaded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RouterProfile {
+    pub provider: Option<RouterProvider>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub prompt: Option<String>,
+    pub max_message_chars: Option<usize>,
+    pub max_reply_tokens: Option<u32>,
+    pub fallback: RouterFallback,
+}
+
+impl RouterProfile {
+    /// Validate `config`; `base_dir` is the directory of employee.toml.
+    pub fn from_config(config: &RouterProfileConfig, base_dir: &Path) -> Result<Self, String> {
+        let provider = config
+            .provider
+            .as_deref()
+            .filter(|value| !value.trim().is_empty())
+            .map(str::parse)
+            .transpose()?;
+        if let Some(temperature) = config.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(format!("temperature {} is outside 0 to 2", temperature));
+            }
+        }
+        if config.max_message_chars == Some(0) || config.max_reply_tokens == Some(0) {
+            return Err("max_message_chars and max_reply_tokens must be positive".to_string());
+        }
+        let prompt = match config.prompt_path.as_ref() {
+            Some(path) => {
+                let path = if path.is_absolute() {
+                    path.clone()
+                } else {
+                    base_dir.join(path)
+                };
+                let prompt = fs::read_to_string(&path)
+                    .map_err(|err| format!("prompt_path {}: {}", path.display(), err))?;
+                if !prompt.contains(FORWARD_MARKER) {
+                    return Err(format!(
+                        "prompt_path {} never mentions {}, so nothing would be forwarded",
+                        path.display(),
+                        FORWARD_MARKER
+                    ));
+                }
+                Some(prompt)
+            }
+            None => None,
+        };
+        let fallback = match config.fallback.as_deref().map(str::trim) {
+            None | Some("") => RouterFallback::default(),
+            Some(value) if value.eq_ignore_ascii_case("heuristic") => RouterFallback::Heuristic,
+            Some(value) if value.eq_ignore_ascii_case("forward") => RouterFallback::Forward,
+            Some(other) => {
+                return Err(format!(
+                    "unknown fallback '{}' (expected heuristic or forward)",
+                    other
+                ))
+            }
+        };
+        Ok(Self {
+            provider,
+            model: config
+                .model
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string),
+            temperature: config.temperature,
+            prompt,
+            max_message_chars: config.max_message_chars,
+            max_reply_tokens: config.max_reply_tokens,
+            fallback,
+        })
+    }
+}
+
 /// Configuration for the message router
 #[derive(Debug, Clone)]
 pub struct RouterConfig {
@@ -98,10 +278,31 @@ pub struct RouterConfig {
     pub enabled: bool,
     /// Whether to use Azure OpenAI auth header
     pub use_azure_auth: bool,
+    /// Whether channels that can edit a posted message stream quick responses
+    pub streaming: bool,
+    /// Answer from local heuristics only, without calling a model
+    pub heuristic_only: bool,
+    /// Sampling temperature; the provider default when unset
+    pub temperature: Option<f32>,
+    /// Classification prompt template replacing the built-in one
+    pub prompt: Option<String>,
+    /// Messages longer than this (in chars) skip the router
+    pub max_message_chars: usize,
+    /// Cap on the tokens of a quick response
+    pub max_reply_tokens: u32,
+    /// What to do when the model API fails
+    pub fallback: RouterFallback,
 }
 
 impl Default for RouterConfig {
     fn default() -> Self {
+        Self::for_profile(&RouterProfile::default())
+    }
+}
+
+impl RouterConfig {
+    /// Environment configuration with an employee's overrides applied.
+    pub fn for_profile(profile: &RouterProfile) -> Self {
         let azure_api_key = env::var("AZURE_OPENAI_API_KEY_BACKUP")
             .ok()
             .map(|value| value.trim().to_string())
@@ -110,11 +311,13 @@ impl Default for RouterConfig {
             .ok()
             .map(|value| value.trim().to_string())
             .filter(|value| !value.is_empty());
+        let azure = match (azure_api_key, azure_endpoint) {
+            (Some(api_key), Some(endpoint)) => Some((api_key, normalize_azure_endpoint(&endpoint))),
+            _ => None,
+        };
 
-        let (openai_api_key, openai_url, use_azure_auth) =
-            if let (Some(api_key), Some(endpoint)) = (azure_api_key, azure_endpoint) {
-                (Some(api_key), normalize_azure_endpoint(&endpoint), true)
-            } else {
+        let (openai_api_key, openai_url, use_azure_auth) = match (profile.provider, azure) {
+            (Some(RouterProvider::OpenAi), _) | (None, None) => {
                 let openai_api_key = env::var("OPENAI_API_KEY")
                     .ok()
                     .map(|value| value.trim().to_string())
@@ -122,16 +325,36 @@ impl Default for RouterConfig {
                 let openai_url =
                     env::var("OPENAI_API_URL").unwrap_or_else(|_| DEFAULT_OPENAI_URL.to_string());
                 (openai_api_key, openai_url, false)
-            };
+            }
+            (_, Some((api_key, url))) => (Some(api_key), url, true),
+            (Some(RouterProvider::Azure | RouterProvider::Heuristic), None) => {
+                (None, DEFAULT_OPENAI_URL.to_string(), true)
+            }
+        };
 
         Self {
             openai_api_key,
             openai_url,
-            model: env::var("ROUTER_MODEL").unwrap_or_else(|_| DEFAULT_MODEL.to_string()),
+            model: profile
+                .model
+                .clone()
+                .or_else(|| env::var("ROUTER_MODEL").ok())
+                .unwrap_or_else(|| DEFAULT_MODEL.to_string()),
             enabled: env::var("ROUTER_ENABLED")
                 .map(|v| v.to_lowercase() != "false")
                 .unwrap_or(true),
             use_azure_auth,
+            streaming: env::var("ROUTER_STREAMING")
+                .map(|v| v.to_lowercase() != "false")
+                .unwrap_or(true),
+            heuristic_only: profile.provider == Some(RouterProvider::Heuristic),
+            temperature: profile.temperature,
+            prompt: profile.prompt.clone(),
+            max_message_chars: profile
+                .max_message_chars
+                .unwrap_or(MAX_SIMPLE_MESSAGE_LENGTH),
+            max_reply_tokens: profile.max_reply_tokens.unwrap_or(DEFAULT_MAX_REPLY_TOKENS),
+            fallback: profile.fallback,
         }
     }
 }
@@ -149,6 +372,11 @@ impl MessageRouter {
         Self::with_config(RouterConfig::default())
     }
 
+    /// Create a message router with the employee's `[employees.router]` settings
+    pub fn for_employee(profile: &crate::employee_config::EmployeeProfile) -> Self {
+        Self::with_config(RouterConfig::for_profile(&profile.router))
+    }
+
     /// Create a new message router with custom configuration
     pub fn with_config(config: RouterConfig) -> Self {
         let client = Client::builder()
@@ -188,63 +416,145 @@ impl MessageRouter {
         employee_name: Option<&str>,
         extra_context: Option<&str>,
     ) -> RouterDecision {
+        if let Some(decision) = self.precheck(message, employee_name) {
+            return decision;
+        }
+
+        let result = self
+            .call_openai(message, memory, employee_name, extra_context)
+            .await;
+
+        match result {
+            Ok(response) => Self::decide(&response),
+            Err(e) => self.fall_back(message, employee_name, &e),
+        }
+    }
+
+    /// Whether quick responses should be streamed where the channel allows it
+    pub fn is_streaming(&self) -> bool {
+        self.config.streaming
+    }
+
+    /// Like [`classify`](Self::classify), but streams the model output and
+    /// calls `on_partial` with the reply so far whenever it grows. Nothing is
+    /// reported while the output could still be the forward marker, and a
+    /// trailing memory update is never included.
+    pub async fn classify_streaming(
+        &self,
+        message: &str,
+        memory: Option<&str>,
+        employee_name: Option<&str>,
+        extra_context: Option<&str>,
+        on_partial: impl FnMut(&str),
+    ) -> RouterDecision {
+        if let Some(decision) = self.precheck(message, employee_name) {
+            return decision;
+        }
+
+        let result = self
+            .stream_openai(message, memory, employee_name, extra_context, on_partial)
+            .await;
+
+        match result {
+            Ok(response) => Self::decide(&response),
+            Err(e) => self.fall_back(message, employee_name, &e),
+        }
+    }
+
+    /// The decision for `message` when no model call is needed.
+    fn precheck(&self, message: &str, employee_name: Option<&str>) -> Option<RouterDecision> {
         if !self.config.enabled {
             debug!("Router disabled, passing through");
-            return RouterDecision::Passthrough;
+            return Some(RouterDecision::Passthrough);
+        }
+
+        if self.config.heuristic_only {
+            return Some(if message.trim().is_empty() {
+                RouterDecision::Passthrough
+            } else {
+                heuristic_decision(message, employee_name)
+            });
         }
 
         if self.config.openai_api_key.is_none() {
             warn!("OPENAI_API_KEY not set, router disabled");
-            return RouterDecision::Passthrough;
+            return Some(RouterDecision::Passthrough);
         }
 
         if message.trim().is_empty() {
-            return RouterDecision::Passthrough;
+            return Some(RouterDecision::Passthrough);
         }
 
         // Messages over the length threshold go straight to pipeline
-        if message.len() > MAX_SIMPLE_MESSAGE_LENGTH {
+        if message.len() > self.config.max_message_chars {
             debug!(
                 "Message too long ({} chars > {}), forwarding to pipeline",
                 message.len(),
-                MAX_SIMPLE_MESSAGE_LENGTH
+                self.config.max_message_chars
             );
-            return RouterDecision::Complex;
+            return Some(RouterDecision::Complex);
         }
+        None
+    }
 
-        let result = self
-            .call_openai(message, memory, employee_name, extra_context)
-            .await;
-
-        match result {
-            Ok(response) => {
-                let trimmed = response.trim();
-                debug!("Router raw response: {}", trimmed);
-                if trimmed.contains(FORWARD_MARKER) {
-                    info!("Router decision: Complex (forward to pipeline)");
-                    RouterDecision::Complex
-                } else {
-                    let (reply, memory_update) = Self::parse_response(trimmed);
-                    info!(
-                        "Router decision: Simple (local response, memory_update={})",
-                        memory_update.is_some()
-                    );
-                    if let Some(ref update) = memory_update {
-                        debug!("Memory update content: {}", update);
-                    }
-                    RouterDecision::Simple {
-                        response: reply,
-                        memory_update,
-                    }
-                }
+    /// The decision when the model call failed with `error`.
+    fn fall_back(&self, message: &str, employee_name: Option<&str>, error: &str) -> RouterDecision {
+        match self.config.fallback {
+            RouterFallback::Heuristic => {
+                warn!("Router error, answering heuristically: {}", error);
+                heuristic_decision(message, employee_name)
             }
-            Err(e) => {
-                warn!("Router error, passing through: {}", e);
+            RouterFallback::Forward => {
+                warn!("Router error, passing through: {}", error);
                 RouterDecision::Passthrough
             }
         }
     }
 
+    /// Turn the full model output into a routing decision.
+    fn decide(response: &str) -> RouterDecision {
+        let trimmed = response.trim();
+        debug!("Router raw response: {}", trimmed);
+        if trimmed.contains(FORWARD_MARKER) {
+            info!("Router decision: Complex (forward to pipeline)");
+            RouterDecision::Complex
+        } else {
+            let (reply, memory_update) = Self::parse_response(trimmed);
+            info!(
+                "Router decision: Simple (local response, memory_update={})",
+                memory_update.is_some()
+            );
+            if let Some(ref update) = memory_update {
+                debug!("Memory update content: {}", update);
+            }
+            RouterDecision::Simple {
+                response: reply,
+                memory_update,
+            }
+        }
+    }
+
+    /// The part of partial model output that can be shown to the user.
+    fn visible_partial(output: &str) -> Option<&str> {
+        const MEMORY_START: &str = "<MEMORY_UPDATE>";
+
+        let output = output.trim_start();
+        if FORWARD_MARKER.starts_with(output) || output.contains(FORWARD_MARKER) {
+            return None;
+        }
+        let mut visible = match output.find(MEMORY_START) {
+            Some(idx) => &output[..idx],
+            None => output,
+        };
+        // The memory update tag may be arriving a few characters at a time.
+        if let Some(idx) = visible.rfind('<') {
+            if MEMORY_START.starts_with(&visible[idx..]) {
+                visible = &visible[..idx];
+            }
+        }
+        Some(visible.trim_end()).filter(|visible| !visible.is_empty())
+    }
+
     /// Parse response to extract reply and optional memory update
     fn parse_response(response: &str) -> (String, Option<String>) {
         const MEMORY_START: &str = "<MEMORY_UPDATE>";
@@ -266,14 +576,15 @@ impl MessageRouter {
         }
     }
 
-    /// Make a request to the OpenAI API (async)
-    async fn call_openai(
+    /// Build the chat completions request for `message`.
+    fn chat_request(
         &self,
         message: &str,
         memory: Option<&str>,
         employee_name: Option<&str>,
         extra_context: Option<&str>,
-    ) -> Result<String, String> {
+        stream: bool,
+    ) -> Result<reqwest::RequestBuilder, String> {
         let api_key = self
             .config
             .openai_api_key
@@ -300,7 +611,10 @@ impl MessageRouter {
         sections.push(format!("Message: {}", message));
         let user_content = sections.join("\n\n");
 
-        let system_prompt = build_system_prompt(employee_name);
+        let system_prompt = match self.config.prompt.as_deref() {
+            Some(template) => template.replace("{name}", employee_name.unwrap_or("Boiled-Egg")),
+            None => build_system_prompt(employee_name),
+        };
         let request = OpenAIChatRequest {
             model: self.config.model.clone(),
             messages: vec![
@@ -313,7 +627,9 @@ impl MessageRouter {
                     content: user_content,
                 },
             ],
-            max_completion_tokens: 1024,
+            max_completion_tokens: self.config.max_reply_tokens,
+            temperature: self.config.temperature,
+            stream,
         };
 
         debug!("Calling OpenAI: {} with model {}", url, self.config.model);
@@ -328,9 +644,19 @@ impl MessageRouter {
             request_builder =
                 request_builder.header("Authorization", format!("Bearer {}", api_key));
         }
+        Ok(request_builder.json(&request))
+    }
 
-        let response = request_builder
-            .json(&request)
+    /// Make a request to the OpenAI API (async)
+    async fn call_openai(
+        &self,
+        message: &str,
+        memory: Option<&str>,
+        employee_name: Option<&str>,
+        extra_context: Option<&str>,
+    ) -> Result<String, String> {
+        let response = self
+            .chat_request(message, memory, employee_name, extra_context, false)?
             .send()
             .await
             .map_err(|e| format!("HTTP request failed: {}", e))?;
@@ -356,6 +682,110 @@ impl MessageRouter {
 
         Ok(content)
     }
+
+    /// Make a streaming request to the OpenAI API and collect the output.
+    async fn stream_openai(
+        &self,
+        message: &str,
+        memory: Option<&str>,
+        employee_name: Option<&str>,
+        extra_context: Option<&str>,
+        mut on_partial: impl FnMut(&str),
+    ) -> Result<String, String> {
+        let mut response = self
+            .chat_request(message, memory, employee_name, extra_context, true)?
+            .send()
+            .await
+            .map_err(|e| format!("HTTP request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("OpenAI returned {}: {}", status, body));
+        }
+
+        let mut pending = String::new();
+        let mut content = String::new();
+        let mut reported = 0;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("Failed to read stream: {}", e))?
+        {
+            pending.push_str(&String::from_utf8_lossy(&chunk));
+            // Server-sent events: one `data:` line per delta.
+            while let Some(end) = pending.find('\n') {
+                let line: String = pending.drain(..=end).collect();
+                let Some(data) = line.trim().strip_prefix("data:") else {
+                    continue;
+                };
+                let data = data.trim();
+                if data == "[DONE]" {
+                    break;
+                }
+                let delta: OpenAIStreamChunk = serde_json::from_str(data)
+                    .map_err(|e| format!("Failed to parse stream chunk: {}", e))?;
+                if let Some(text) = delta
+                    .choices
+                    .first()
+                    .and_then(|choice| choice.delta.content.as_deref())
+                {
+                    content.push_str(text);
+                }
+            }
+            if let Some(visible) = Self::visible_partial(&content) {
+                if visible.len() > reported {
+                    reported = visible.len();
+                    on_partial(visible);
+                }
+            }
+        }
+
+        debug!("OpenAI stream finished");
+
+        Ok(content)
+    }
+}
+
+/// Answer greetings and thanks without a model and forward everything else.
+fn heuristic_decision(message: &str, employee_name: Option<&str>) -> RouterDecision {
+    let phrase = heuristic_words(message);
+    if phrase.is_empty() || phrase.split(' ').count() > HEURISTIC_MAX_WORDS {
+        return RouterDecision::Complex;
+    }
+    let name = employee_name.unwrap_or("Boiled-Egg");
+    let addressed = [heuristic_words(name), "there".to_string()];
+    // "hi" and "hi oliver" both count, "hi can you" does not.
+    let matches = |phrases: &[&str]| {
+        phrases.iter().any(|candidate| {
+            phrase == *candidate
+                || phrase
+                    .strip_prefix(candidate)
+                    .and_then(|rest| rest.strip_prefix(' '))
+                    .is_some_and(|rest| addressed.iter().any(|name| name == rest))
+        })
+    };
+    let response = if matches(HEURISTIC_GREETINGS) {
+        format!("Hi! I'm {}. What can I help you with?", name)
+    } else if matches(HEURISTIC_THANKS) {
+        "You're welcome! Let me know if there's anything else.".to_string()
+    } else {
+        return RouterDecision::Complex;
+    };
+    info!("Router decision: Simple (heuristic)");
+    RouterDecision::Simple {
+        response,
+        memory_update: None,
+    }
+}
+
+/// Lowercase words of `text` without punctuation, joined by single spaces.
+fn heuristic_words(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 impl Default for MessageRouter {
@@ -383,6 +813,10 @@ struct OpenAIChatRequest {
     model: String,
     messages: Vec<OpenAIChatMessage>,
     max_completion_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 /// Chat message for OpenAI API
@@ -404,6 +838,27 @@ struct OpenAIChatChoice {
     message: OpenAIChatMessage,
 }
 
+/// One server-sent event of a streamed chat completion
+#[derive(Debug, Clone, Deserialize)]
+struct OpenAIStreamChunk {
+    #[serde(default)]
+    choices: Vec<OpenAIStreamChoice>,
+}
+
+/// Choice in a streamed chat completion event
+#[derive(Debug, Clone, Deserialize)]
+struct OpenAIStreamChoice {
+    #[serde(default)]
+    delta: OpenAIStreamDelta,
+}
+
+/// Text added by one streamed chat completion event
+#[derive(Debug, Clone, Default, Deserialize)]
+struct OpenAIStreamDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -498,6 +953,260 @@ mod tests {
         assert!(!prompt.contains("Boiled-Egg"));
     }
 
+    #[test]
+    fn visible_partial_hides_the_forward_marker_and_memory_update() {
+        assert_eq!(MessageRouter::visible_partial("FORWARD_TO"), None);
+        assert_eq!(MessageRouter::visible_partial(" FORWARD_TO_AGENT"), None);
+        assert_eq!(MessageRouter::visible_partial("   "), None);
+        assert_eq!(MessageRouter::visible_partial("Hi Ada"), Some("Hi Ada"));
+        assert_eq!(
+            MessageRouter::visible_partial("Noted!\n\n<MEMO"),
+            Some("Noted!")
+        );
+        assert_eq!(
+            MessageRouter::visible_partial("Noted!\n<MEMORY_UPDATE>\n## Profile"),
+            Some("Noted!")
+        );
+        assert_eq!(MessageRouter::visible_partial("a <b"), Some("a <b"));
+    }
+
+    fn test_config(url: String) -> RouterConfig {
+        RouterConfig {
+            openai_api_key: Some("test-key".to_string()),
+            openai_url: url,
+            model: "test-model".to_string(),
+            enabled: true,
+            use_azure_auth: false,
+            streaming: true,
+            heuristic_only: false,
+            temperature: None,
+            prompt: None,
+            max_message_chars: MAX_SIMPLE_MESSAGE_LENGTH,
+            max_reply_tokens: DEFAULT_MAX_REPLY_TOKENS,
+            fallback: RouterFallback::Heuristic,
+        }
+    }
+
+    fn streaming_router(url: String) -> MessageRouter {
+        MessageRouter::with_config(test_config(url))
+    }
+
+    #[test]
+    fn heuristic_answers_only_greetings_and_thanks() {
+        for message in [
+            "hi",
+            "Hello!",
+            "hey there",
+            "Good morning, Oliver",
+            "Thanks!!",
+        ] {
+            assert!(
+                matches!(
+                    heuristic_decision(message, Some("Oliver")),
+                    RouterDecision::Simple { .. }
+                ),
+                "{}",
+                message
+            );
+        }
+        for message in ["hi can you book a flight", "what's the weather", "hey bob"] {
+            assert!(
+                matches!(
+                    heuristic_decision(message, Some("Oliver")),
+                    RouterDecision::Complex
+                ),
+                "{}",
+                message
+            );
+        }
+        assert!(matches!(
+            heuristic_decision("hi boiled egg", None),
+            RouterDecision::Simple { .. }
+        ));
+    }
+
+    #[test]
+    fn router_profile_validates_and_loads_the_prompt() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("router.md"),
+            "You are {name}. Say FORWARD_TO_AGENT for real work.",
+        )
+        .unwrap();
+        fs::write(dir.path().join("bad.md"), "You are {name}.").unwrap();
+
+        let config = RouterProfileConfig {
+            provider: Some("Heuristic".to_string()),
+            model: Some(" gpt-4.1-mini ".to_string()),
+            temperature: Some(0.3),
+            prompt_path: Some(PathBuf::from("router.md")),
+            max_message_chars: Some(120),
+            max_reply_tokens: None,
+            fallback: Some("forward".to_string()),
+        };
+        let profile = RouterProfile::from_config(&config, dir.path()).unwrap();
+        assert_eq!(profile.provider, Some(RouterProvider::Heuristic));
+        assert_eq!(profile.model.as_deref(), Some("gpt-4.1-mini"));
+        assert_eq!(profile.fallback, RouterFallback::Forward);
+        assert!(profile.prompt.unwrap().starts_with("You are {name}."));
+
+        let invalid = [
+            RouterProfileConfig {
+                provider: Some("ollama".to_string()),
+                ..Default::default()
+            },
+            RouterProfileConfig {
+                temperature: Some(3.0),
+                ..Default::default()
+            },
+            RouterProfileConfig {
+                prompt_path: Some(PathBuf::from("bad.md")),
+                ..Default::default()
+            },
+            RouterProfileConfig {
+                fallback: Some("retry".to_string()),
+                ..Default::default()
+            },
+            RouterProfileConfig {
+                max_message_chars: Some(0),
+                ..Default::default()
+            },
+        ];
+        for config in invalid {
+            assert!(
+                RouterProfile::from_config(&config, dir.path()).is_err(),
+                "{:?}",
+                config
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_model_falls_back_to_heuristics_or_forwards() {
+        // Nothing listens on port 9 (discard) locally.
+        let heuristic = MessageRouter::with_config(test_config("http://127.0.0.1:9".to_string()));
+        assert!(matches!(
+            heuristic.classify("thanks!", None, None, None).await,
+            RouterDecision::Simple { .. }
+        ));
+        assert!(matches!(
+            heuristic
+                .classify("draft my report", None, None, None)
+                .await,
+            RouterDecision::Complex
+        ));
+
+        let forward = MessageRouter::with_config(RouterConfig {
+            fallback: RouterFallback::Forward,
+            ..test_config("http://127.0.0.1:9".to_string())
+        });
+        assert!(matches!(
+            forward.classify("thanks!", None, None, None).await,
+            RouterDecision::Passthrough
+        ));
+    }
+
+    #[tokio::test]
+    async fn custom_prompt_and_temperature_are_sent() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::PartialJson(serde_json::json!({
+                    "model": "test-model",
+                    "temperature": 0.5,
+                    "max_completion_tokens": 64,
+                })),
+                mockito::Matcher::Regex("You are Oliver. FORWARD_TO_AGENT otherwise.".to_string()),
+            ]))
+            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":"Hi!"}}]}"#)
+            .create_async()
+            .await;
+        let router = MessageRouter::with_config(RouterConfig {
+            temperature: Some(0.5),
+            prompt: Some("You are {name}. FORWARD_TO_AGENT otherwise.".to_string()),
+            max_reply_tokens: 64,
+            ..test_config(server.url())
+        });
+
+        let decision = router.classify("hello", None, Some("Oliver"), None).await;
+
+        mock.assert_async().await;
+        assert!(matches!(decision, RouterDecision::Simple { .. }));
+    }
+
+    fn sse(deltas: &[&str]) -> String {
+        let mut body = String::new();
+        for delta in deltas {
+            let event = serde_json::json!({ "choices": [{ "delta": { "content": delta } }] });
+            body.push_str(&format!("data: {}\n\n", event));
+        }
+        body.push_str("data: [DONE]\n\n");
+        body
+    }
+
+    #[tokio::test]
+    async fn classify_streaming_reports_partials_and_parses_the_reply() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({ "stream": true }),
+            ))
+            .with_header("content-type", "text/event-stream")
+            .with_body(sse(&[
+                "Hi ",
+                "Ada, ",
+                "noted!",
+                "\n<MEMORY_UPDATE>\n## Profile\n- Goes to Stanford\n</MEMORY_UPDATE>",
+            ]))
+            .create_async()
+            .await;
+
+        let mut partials = Vec::new();
+        let decision = streaming_router(server.url())
+            .classify_streaming("I go to Stanford", None, None, None, |partial| {
+                partials.push(partial.to_string())
+            })
+            .await;
+
+        mock.assert_async().await;
+        assert!(!partials.is_empty());
+        assert_eq!(partials.last().map(String::as_str), Some("Hi Ada, noted!"));
+        match decision {
+            RouterDecision::Simple {
+                response,
+                memory_update,
+            } => {
+                assert_eq!(response, "Hi Ada, noted!");
+                assert_eq!(
+                    memory_update.as_deref(),
+                    Some("## Profile\n- Goes to Stanford")
+                );
+            }
+            other => panic!("expected Simple response, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn classify_streaming_forwards_without_partials() {
+        let mut server = mockito::Server::new_async().await;
+        let _mock = server
+            .mock("POST", "/chat/completions")
+            .with_header("content-type", "text/event-stream")
+            .with_body(sse(&["FORWARD", "_TO_", "AGENT"]))
+            .create_async()
+            .await;
+
+        let mut partials = 0;
+        let decision = streaming_router(server.url())
+            .classify_streaming("Build me a website", None, None, None, |_| partials += 1)
+            .await;
+
+        assert_eq!(partials, 0);
+        assert!(matches!(decision, RouterDecision::Complex));
+    }
+
     #[tokio::test]
     #[ignore]
     async fn router_live_smoke_gpt_52() {
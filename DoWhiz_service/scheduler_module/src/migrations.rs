@@ -0,0 +1,890 @@
+//! Versioned schema migrations.
+//!
+//! Each store keeps the versions applied to it in a migration table: the
+//! `schema_migrations` collection of the MongoDB state database (tasks,
+//! executions, users, task index, Slack installations, ...),
+//! `<queue table>_schema_migrations` for the Postgres ingestion queue (see
+//! [`crate::ingestion_queue::migrate_ingestion_schema`]), and the
+//! `schema_migrations` table of each user's SQLite thread database (see
+//! [`crate::thread_state`]). Migrations are
+//! numbered, applied in order and each has a down step, so a deploy that is
+//! aborted can be rolled back with the `schema_migrate` binary of the build
+//! that applied them before the old build is started again.
+//!
+//! New schema changes (indexes, field renames, backfills) are added to the
+//! end of [`MONGO_MIGRATIONS`], [`THREAD_DB_MIGRATIONS`] or the ingestion
+//! list; released migrations are never edited.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use mongodb::bson::{doc, Bson, DateTime as BsonDateTime, Document};
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::{IndexOptions, UpdateOptions};
+use mongodb::sync::{Collection, Database};
+use mongodb::IndexModel;
+use rusqlite::{params, Connection, TransactionBehavior};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::mongo_store::{
+    create_client_from_env, database_from_env, ensure_index_compatible, MongoStoreError,
+};
+use crate::storage_backend::StorageBackend;
+
+/// Collection recording the applied MongoDB migrations.
+pub const SCHEMA_MIGRATIONS_COLLECTION: &str = "schema_migrations";
+
+const LOCK_ID: &str = "lock";
+const LOCK_TTL_SECS: i64 = 600;
+const LOCK_WAIT: Duration = Duration::from_secs(120);
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+/// Decided and expired approval requests are kept this long after creation.
+const APPROVAL_RETENTION: Duration = Duration::from_secs(30 * DAY_SECS);
+/// Resolved Slack actions are kept this long after creation for auditing.
+const PENDING_ACTION_RETENTION: Duration = Duration::from_secs(30 * DAY_SECS);
+/// Reactions only control recent messages; older links expire.
+const MESSAGE_LINK_RETENTION: Duration = Duration::from_secs(30 * DAY_SECS);
+
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error(transparent)]
+    MongoStore(#[from] MongoStoreError),
+    #[error("mongodb error: {0}")]
+    Mongo(#[from] mongodb::error::Error),
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("unknown schema version {0}")]
+    UnknownVersion(u32),
+    #[error("version {0} was applied by a newer build; roll it back with that build")]
+    AppliedByNewerBuild(u32),
+    #[error("timed out waiting for the migration lock held by {0}")]
+    Locked(String),
+    #[error("{0}")]
+    Store(String),
+}
+
+/// One migration step the runner takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Up(u32),
+    Down(u32),
+}
+
+/// Where a store's schema stands.
+#[derive(Debug, Clone)]
+pub struct SchemaStatus {
+    pub store: &'static str,
+    pub applied: Vec<u32>,
+    pub latest: u32,
+}
+
+impl SchemaStatus {
+    pub fn current(&self) -> u32 {
+        self.applied.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Known versions not applied yet; versions are numbered from 1.
+    pub fn pending(&self) -> usize {
+        let applied = self.applied.iter().filter(|v| **v <= self.latest).count();
+        (self.latest as usize).saturating_sub(applied)
+    }
+}
+
+/// Steps that take a store with `applied` versions to `target`: missing
+/// known versions up to `target` in ascending order, then applied versions
+/// above it in descending order.
+pub fn plan_steps(
+    known: &[u32],
+    applied: &BTreeSet<u32>,
+    target: u32,
+) -> Result<Vec<Step>, MigrationError> {
+    if target != 0 && !known.contains(&target) {
+        return Err(MigrationError::UnknownVersion(target));
+    }
+    let mut steps: Vec<Step> = known
+        .iter()
+        .copied()
+        .filter(|version| *version <= target && !applied.contains(version))
+        .map(Step::Up)
+        .collect();
+    for version in applied.iter().rev().copied().filter(|v| *v > target) {
+        if !known.contains(&version) {
+            return Err(MigrationError::AppliedByNewerBuild(version));
+        }
+        steps.push(Step::Down(version));
+    }
+    Ok(steps)
+}
+
+pub struct MongoMigration {
+    pub version: u32,
+    pub name: &'static str,
+    up: fn(&Database) -> Result<(), mongodb::error::Error>,
+    down: fn(&Database) -> Result<(), mongodb::error::Error>,
+}
+
+/// Ordered MongoDB migrations. Versions 1-7 and 9-20 are the indexes the
+/// stores used to create ad hoc on startup; the stores no longer create any,
+/// so every process that opens them runs [`migrate_from_env`] first.
+pub const MONGO_MIGRATIONS: &[MongoMigration] = &[
+    MongoMigration {
+        version: 1,
+        name: "users_indexes",
+        up: |db| ensure_indexes(db, "users", &users_indexes()),
+        down: |db| drop_indexes(db, "users", &users_indexes()),
+    },
+    MongoMigration {
+        version: 2,
+        name: "tasks_indexes",
+        up: |db| ensure_indexes(db, "tasks", &tasks_indexes()),
+        down: |db| drop_indexes(db, "tasks", &tasks_indexes()),
+    },
+    MongoMigration {
+        version: 3,
+        name: "task_executions_indexes",
+        up: |db| ensure_indexes(db, "task_executions", &task_executions_indexes()),
+        down: |db| drop_indexes(db, "task_executions", &task_executions_indexes()),
+    },
+    MongoMigration {
+        version: 4,
+        name: "task_index_indexes",
+        up: |db| ensure_indexes(db, "task_index", &task_index_indexes()),
+        down: |db| drop_indexes(db, "task_index", &task_index_indexes()),
+    },
+    MongoMigration {
+        version: 5,
+        name: "account_task_views_indexes",
+        up: |db| ensure_indexes(db, "account_task_views", &account_task_views_indexes()),
+        down: |db| drop_indexes(db, "account_task_views", &account_task_views_indexes()),
+    },
+    MongoMigration {
+        version: 6,
+        name: "slack_installations_indexes",
+        up: |db| ensure_indexes(db, "slack_installations", &slack_installations_indexes()),
+        down: |db| drop_indexes(db, "slack_installations", &slack_installations_indexes()),
+    },
+    MongoMigration {
+        version: 7,
+        name: "processed_comments_indexes",
+        up: |db| ensure_indexes(db, "processed_comments", &processed_comments_indexes()),
+        down: |db| drop_indexes(db, "processed_comments", &processed_comments_indexes()),
+    },
+    MongoMigration {
+        version: 8,
+        name: "task_index_query_indexes",
+        up: |db| ensure_indexes(db, "task_index", &task_index_query_indexes()),
+        down: |db| drop_indexes(db, "task_index", &task_index_query_indexes()),
+    },
+    MongoMigration {
+        version: 9,
+        name: "tasks_delivery_indexes",
+        up: |db| ensure_indexes(db, "tasks", &tasks_delivery_indexes()),
+        down: |db| drop_indexes(db, "tasks", &tasks_delivery_indexes()),
+    },
+    MongoMigration {
+        version: 10,
+        name: "user_preferences_indexes",
+        up: |db| ensure_indexes(db, "user_preferences", &user_preferences_indexes()),
+        down: |db| drop_indexes(db, "user_preferences", &user_preferences_indexes()),
+    },
+    MongoMigration {
+        version: 11,
+        name: "workspace_files_indexes",
+        up: |db| ensure_indexes(db, "workspace_files", &workspace_files_indexes()),
+        down: |db| drop_indexes(db, "workspace_files", &workspace_files_indexes()),
+    },
+    MongoMigration {
+        version: 12,
+        name: "approval_requests_indexes",
+        up: |db| ensure_indexes(db, "approval_requests", &approval_requests_indexes()),
+        down: |db| drop_indexes(db, "approval_requests", &approval_requests_indexes()),
+    },
+    MongoMigration {
+        version: 13,
+        name: "slack_pending_actions_indexes",
+        up: |db| {
+            ensure_indexes(
+                db,
+                "slack_pending_actions",
+                &slack_pending_actions_indexes(),
+            )
+        },
+        down: |db| {
+            drop_indexes(
+                db,
+                "slack_pending_actions",
+                &slack_pending_actions_indexes(),
+            )
+        },
+    },
+    MongoMigration {
+        version: 14,
+        name: "chat_message_links_indexes",
+        up: |db| ensure_indexes(db, "chat_message_links", &chat_message_links_indexes()),
+        down: |db| drop_indexes(db, "chat_message_links", &chat_message_links_indexes()),
+    },
+    MongoMigration {
+        version: 15,
+        name: "inbound_dedupe_indexes",
+        up: |db| ensure_indexes(db, "inbound_dedupe", &inbound_dedupe_indexes()),
+        down: |db| drop_indexes(db, "inbound_dedupe", &inbound_dedupe_indexes()),
+    },
+    MongoMigration {
+        version: 16,
+        name: "agent_audit_log_indexes",
+        up: |db| ensure_indexes(db, "agent_audit_log", &agent_audit_log_indexes()),
+        down: |db| drop_indexes(db, "agent_audit_log", &agent_audit_log_indexes()),
+    },
+    MongoMigration {
+        version: 17,
+        name: "router_decisions_indexes",
+        up: |db| ensure_indexes(db, "router_decisions", &router_decisions_indexes()),
+        down: |db| drop_indexes(db, "router_decisions", &router_decisions_indexes()),
+    },
+    MongoMigration {
+        version: 18,
+        name: "notion_processed_notifications_indexes",
+        up: |db| {
+            ensure_indexes(
+                db,
+                "notion_processed_notifications",
+                &notion_processed_notifications_indexes(),
+            )
+        },
+        down: |db| {
+            drop_indexes(
+                db,
+                "notion_processed_notifications",
+                &notion_processed_notifications_indexes(),
+            )
+        },
+    },
+    MongoMigration {
+        version: 19,
+        name: "notion_oauth_tokens_indexes",
+        up: |db| ensure_indexes(db, "notion_oauth_tokens", &notion_oauth_tokens_indexes()),
+        down: |db| drop_indexes(db, "notion_oauth_tokens", &notion_oauth_tokens_indexes()),
+    },
+    MongoMigration {
+        version: 20,
+        name: "notion_credentials_indexes",
+        up: |db| ensure_indexes(db, "notion_credentials", &notion_credentials_indexes()),
+        down: |db| drop_indexes(db, "notion_credentials", &notion_credentials_indexes()),
+    },
+];
+
+/// One migration of a user's thread database. `up` gets the user's
+/// workspaces directory, whose `thread_state.json` files a migration may
+/// read.
+pub struct ThreadDbMigration {
+    pub version: u32,
+    pub name: &'static str,
+    up: fn(&Connection, &Path) -> rusqlite::Result<()>,
+    down: fn(&Connection) -> rusqlite::Result<()>,
+}
+
+/// Ordered thread database migrations.
+pub const THREAD_DB_MIGRATIONS: &[ThreadDbMigration] = &[
+    ThreadDbMigration {
+        version: 1,
+        name: "threads_schema",
+        up: |conn, _| conn.execute_batch(THREADS_SCHEMA),
+        down: |conn| {
+            conn.execute_batch(
+                "DROP TABLE IF EXISTS sent_messages;
+                 DROP TABLE IF EXISTS thread_messages;
+                 DROP TABLE IF EXISTS threads;",
+            )
+        },
+    },
+    // The files stay in the workspaces, kept current by every change, so
+    // there is nothing to undo.
+    ThreadDbMigration {
+        version: 2,
+        name: "import_thread_state_files",
+        up: crate::thread_state::import_thread_state_files,
+        down: |_| Ok(()),
+    },
+];
+
+const THREADS_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS threads (
+    workspace TEXT PRIMARY KEY,
+    thread_id TEXT NOT NULL,
+    epoch INTEGER NOT NULL,
+    last_email_seq INTEGER NOT NULL,
+    last_message_id TEXT,
+    updated_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS thread_messages (
+    workspace TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    message_id TEXT,
+    received_at TEXT NOT NULL,
+    PRIMARY KEY (workspace, seq)
+);
+CREATE TABLE IF NOT EXISTS sent_messages (
+    workspace TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    channel TEXT NOT NULL,
+    channel_id TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    sent_at TEXT NOT NULL,
+    edited_at TEXT,
+    deleted_at TEXT,
+    PRIMARY KEY (workspace, position)
+);
+";
+
+const THREAD_DB_MIGRATIONS_TABLE: &str = "
+CREATE TABLE IF NOT EXISTS schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_at TEXT NOT NULL
+);
+";
+
+/// Highest thread database schema version this build knows.
+pub fn latest_thread_db_version() -> u32 {
+    THREAD_DB_MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn thread_db_schema_status(conn: &Connection) -> Result<SchemaStatus, MigrationError> {
+    conn.execute_batch(THREAD_DB_MIGRATIONS_TABLE)?;
+    Ok(SchemaStatus {
+        store: "threads",
+        applied: thread_db_applied_versions(conn)?.into_iter().collect(),
+        latest: latest_thread_db_version(),
+    })
+}
+
+/// Applies or rolls back migrations until the thread database on `conn` is
+/// at `target`. Runs in one immediate transaction, so processes opening the
+/// same database take turns and each step is all or nothing.
+pub fn migrate_thread_db(
+    conn: &mut Connection,
+    workspaces_root: &Path,
+    target: u32,
+) -> Result<Vec<Step>, MigrationError> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    tx.execute_batch(THREAD_DB_MIGRATIONS_TABLE)?;
+    let known: Vec<u32> = THREAD_DB_MIGRATIONS.iter().map(|m| m.version).collect();
+    let steps = plan_steps(&known, &thread_db_applied_versions(&tx)?, target)?;
+    for step in &steps {
+        match *step {
+            Step::Up(version) => {
+                let migration = thread_db_migration(version)?;
+                (migration.up)(&tx, workspaces_root)?;
+                tx.execute(
+                    "INSERT OR REPLACE INTO schema_migrations (version, name, applied_at)
+                     VALUES (?1, ?2, ?3)",
+                    params![version, migration.name, Utc::now().to_rfc3339()],
+                )?;
+            }
+            Step::Down(version) => {
+                (thread_db_migration(version)?.down)(&tx)?;
+                tx.execute(
+                    "DELETE FROM schema_migrations WHERE version = ?1",
+                    params![version],
+                )?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(steps)
+}
+
+/// The thread databases under `users_root` (`<id>/state/threads.db`), each
+/// with the workspaces directory it holds the threads of.
+pub fn thread_databases(users_root: &Path) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    let mut databases = Vec::new();
+    for entry in fs::read_dir(users_root)? {
+        let user_root = entry?.path();
+        let db_path = user_root.join("state").join("threads.db");
+        if db_path.is_file() {
+            databases.push((db_path, user_root.join("workspaces")));
+        }
+    }
+    databases.sort();
+    Ok(databases)
+}
+
+fn thread_db_migration(version: u32) -> Result<&'static ThreadDbMigration, MigrationError> {
+    THREAD_DB_MIGRATIONS
+        .iter()
+        .find(|m| m.version == version)
+        .ok_or(MigrationError::UnknownVersion(version))
+}
+
+fn thread_db_applied_versions(conn: &Connection) -> rusqlite::Result<BTreeSet<u32>> {
+    let mut stmt = conn.prepare("SELECT version FROM schema_migrations")?;
+    let versions = stmt
+        .query_map([], |row| row.get::<_, u32>(0))?
+        .collect::<Result<_, _>>()?;
+    Ok(versions)
+}
+
+/// Highest MongoDB schema version this build knows.
+pub fn latest_mongo_version() -> u32 {
+    MONGO_MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Startup migration of the state database to the latest version. A
+/// database migrated by a newer build is left alone with a warning, so the
+/// workers of a rolling deploy keep running.
+pub fn migrate_from_env() -> Result<(), MigrationError> {
+    if !StorageBackend::from_env().uses_mongo() {
+        return Ok(());
+    }
+    let client = create_client_from_env()?;
+    let db = database_from_env(&client);
+    let status = mongo_schema_status(&db)?;
+    if status.current() > status.latest {
+        warn!(
+            "database schema is at version {} but this build knows up to {}; skipping migrations",
+            status.current(),
+            status.latest
+        );
+        return Ok(());
+    }
+    let steps = migrate_mongo(&db, status.latest)?;
+    info!(
+        "database schema at version {} ({} migration(s) applied)",
+        status.latest,
+        steps.len()
+    );
+    Ok(())
+}
+
+pub fn mongo_schema_status(db: &Database) -> Result<SchemaStatus, MigrationError> {
+    Ok(SchemaStatus {
+        store: "mongo",
+        applied: applied_versions(&migrations_collection(db))?
+            .into_iter()
+            .collect(),
+        latest: latest_mongo_version(),
+    })
+}
+
+/// Applies or rolls back migrations until the database is at `target`,
+/// holding the migration lock. Returns the steps taken.
+pub fn migrate_mongo(db: &Database, target: u32) -> Result<Vec<Step>, MigrationError> {
+    let collection = migrations_collection(db);
+    let owner = format!("{}-{}", hostname(), Uuid::new_v4());
+    acquire_lock(&collection, &owner)?;
+    let result = run_steps(db, &collection, target);
+    if let Err(err) = collection.delete_one(doc! { "_id": LOCK_ID, "owner": &owner }, None) {
+        warn!("failed to release the migration lock: {}", err);
+    }
+    result
+}
+
+fn run_steps(
+    db: &Database,
+    collection: &Collection<Document>,
+    target: u32,
+) -> Result<Vec<Step>, MigrationError> {
+    let known: Vec<u32> = MONGO_MIGRATIONS.iter().map(|m| m.version).collect();
+    let steps = plan_steps(&known, &applied_versions(collection)?, target)?;
+    for step in &steps {
+        match *step {
+            Step::Up(version) => {
+                let migration = mongo_migration(version)?;
+                info!("applying migration {} {}", version, migration.name);
+                (migration.up)(db)?;
+                collection.update_one(
+                    doc! { "_id": version as i64 },
+                    doc! { "$set": {
+                        "name": migration.name,
+                        "applied_at": BsonDateTime::from_chrono(Utc::now()),
+                    } },
+                    UpdateOptions::builder().upsert(true).build(),
+                )?;
+            }
+            Step::Down(version) => {
+                let migration = mongo_migration(version)?;
+                info!("rolling back migration {} {}", version, migration.name);
+                (migration.down)(db)?;
+                collection.delete_one(doc! { "_id": version as i64 }, None)?;
+            }
+        }
+    }
+    Ok(steps)
+}
+
+fn mongo_migration(version: u32) -> Result<&'static MongoMigration, MigrationError> {
+    MONGO_MIGRATIONS
+        .iter()
+        .find(|m| m.version == version)
+        .ok_or(MigrationError::UnknownVersion(version))
+}
+
+fn migrations_collection(db: &Database) -> Collection<Document> {
+    db.collection::<Document>(SCHEMA_MIGRATIONS_COLLECTION)
+}
+
+fn applied_versions(collection: &Collection<Document>) -> Result<BTreeSet<u32>, MigrationError> {
+    let mut versions = BTreeSet::new();
+    for row in collection.find(doc! { "_id": { "$type": "number" } }, None)? {
+        match row?.get("_id") {
+            Some(Bson::Int64(version)) => versions.insert(*version as u32),
+            Some(Bson::Int32(version)) => versions.insert(*version as u32),
+            _ => false,
+        };
+    }
+    Ok(versions)
+}
+
+/// Takes the lock document, waiting for another process's run to finish.
+/// A lock older than `LOCK_TTL_SECS` is taken over.
+fn acquire_lock(collection: &Collection<Document>, owner: &str) -> Result<(), MigrationError> {
+    let started = Instant::now();
+    loop {
+        let now = Utc::now();
+        let result = collection.update_one(
+            doc! {
+                "_id": LOCK_ID,
+                "expires_at": { "$lt": BsonDateTime::from_chrono(now) },
+            },
+            doc! { "$set": {
+                "owner": owner,
+                "expires_at": BsonDateTime::from_chrono(now + chrono::Duration::seconds(LOCK_TTL_SECS)),
+            } },
+            UpdateOptions::builder().upsert(true).build(),
+        );
+        match result {
+            Ok(_) => return Ok(()),
+            Err(err) if is_duplicate_key(&err) => {}
+            Err(err) => return Err(err.into()),
+        }
+        if started.elapsed() >= LOCK_WAIT {
+            let holder = collection
+                .find_one(doc! { "_id": LOCK_ID }, None)?
+                .and_then(|lock| lock.get_str("owner").ok().map(str::to_string))
+                .unwrap_or_default();
+            return Err(MigrationError::Locked(holder));
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn is_duplicate_key(err: &mongodb::error::Error) -> bool {
+    match err.kind.as_ref() {
+        ErrorKind::Command(command) => command.code == 11000,
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => write_error.code == 11000,
+        _ => false,
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn ensure_indexes(
+    db: &Database,
+    collection: &str,
+    models: &[IndexModel],
+) -> Result<(), mongodb::error::Error> {
+    let collection = db.collection::<Document>(collection);
+    for model in models {
+        ensure_index_compatible(&collection, model.clone())?;
+    }
+    Ok(())
+}
+
+/// Drops the indexes by their default names; missing ones are skipped.
+fn drop_indexes(
+    db: &Database,
+    collection: &str,
+    models: &[IndexModel],
+) -> Result<(), mongodb::error::Error> {
+    let collection = db.collection::<Document>(collection);
+    for model in models {
+        match collection.drop_index(index_name(&model.keys), None) {
+            Ok(()) => {}
+            Err(err) if is_missing_index(&err) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// IndexNotFound or NamespaceNotFound.
+fn is_missing_index(err: &mongodb::error::Error) -> bool {
+    matches!(err.kind.as_ref(), ErrorKind::Command(command) if matches!(command.code, 26 | 27))
+}
+
+/// MongoDB's default index name, e.g. `user_id_1_task_id_1`.
+fn index_name(keys: &Document) -> String {
+    keys.iter()
+        .map(|(field, direction)| format!("{}_{}", field, direction))
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn index(keys: Document) -> IndexModel {
+    IndexModel::builder().keys(keys).build()
+}
+
+fn unique_index(keys: Document) -> IndexModel {
+    IndexModel::builder()
+        .keys(keys)
+        .options(IndexOptions::builder().unique(Some(true)).build())
+        .build()
+}
+
+fn partial_index(keys: Document, unique: bool, filter: Document) -> IndexModel {
+    IndexModel::builder()
+        .keys(keys)
+        .options(
+            IndexOptions::builder()
+                .unique(unique.then_some(true))
+                .partial_filter_expression(Some(filter))
+                .build(),
+        )
+        .build()
+}
+
+/// Documents expire `after` the time in `field`.
+fn ttl_index(field: &str, after: Duration) -> IndexModel {
+    IndexModel::builder()
+        .keys(doc! { field: 1 })
+        .options(IndexOptions::builder().expire_after(Some(after)).build())
+        .build()
+}
+
+fn users_indexes() -> Vec<IndexModel> {
+    vec![
+        unique_index(doc! { "user_id": 1 }),
+        index(doc! { "identifier_type": 1, "identifier": 1 }),
+        index(doc! { "created_at": 1 }),
+    ]
+}
+
+fn tasks_indexes() -> Vec<IndexModel> {
+    vec![
+        index(doc! { "owner_scope.kind": 1, "owner_scope.id": 1, "task_id": 1 }),
+        index(doc! { "owner_scope.kind": 1, "owner_scope.id": 1, "enabled": 1 }),
+        index(doc! { "enabled": 1, "schedule.next_run": 1 }),
+        index(doc! { "owner_scope.kind": 1, "owner_scope.id": 1, "created_at": 1 }),
+    ]
+}
+
+fn task_executions_indexes() -> Vec<IndexModel> {
+    vec![
+        index(doc! {
+            "owner_scope.kind": 1,
+            "owner_scope.id": 1,
+            "task_id": 1,
+            "started_at": -1
+        }),
+        index(doc! { "started_at": -1 }),
+    ]
+}
+
+fn task_index_indexes() -> Vec<IndexModel> {
+    // user_id must come first for sharded collections (Cosmos DB shard key compatibility)
+    vec![
+        unique_index(doc! { "user_id": 1, "task_id": 1 }),
+        index(doc! { "enabled": 1, "next_run": 1 }),
+        index(doc! { "enabled": 1, "heartbeat_deadline": 1 }),
+    ]
+}
+
+/// Backs `IndexStore::query_tasks`; rows now also carry `kind` and
+/// `channel` and stay in the index while their task is disabled.
+fn task_index_query_indexes() -> Vec<IndexModel> {
+    vec![
+        index(doc! { "user_id": 1, "next_run": 1 }),
+        index(doc! { "channel": 1, "kind": 1, "enabled": 1, "next_run": 1 }),
+    ]
+}
+
+fn account_task_views_indexes() -> Vec<IndexModel> {
+    vec![
+        unique_index(doc! { "account_id": 1, "task_id": 1 }),
+        index(doc! { "account_id": 1, "created_at": -1 }),
+    ]
+}
+
+fn slack_installations_indexes() -> Vec<IndexModel> {
+    vec![
+        unique_index(doc! { "team_id": 1 }),
+        index(doc! { "installed_at": -1 }),
+    ]
+}
+
+fn processed_comments_indexes() -> Vec<IndexModel> {
+    vec![
+        unique_index(doc! { "file_id": 1, "tracking_id": 1 }),
+        index(doc! { "file_type": 1, "processed_at": -1 }),
+    ]
+}
+
+/// One send per reply key (see `begin_delivery`); provider webhooks find a
+/// reply by its message ID (see `record_delivery_outcome`).
+fn tasks_delivery_indexes() -> Vec<IndexModel> {
+    vec![
+        partial_index(
+            doc! { "owner_scope.kind": 1, "owner_scope.id": 1, "reply_key": 1 },
+            true,
+            doc! { "reply_key": { "$type": "string" } },
+        ),
+        partial_index(
+            doc! { "provider_message_id": 1 },
+            false,
+            doc! { "provider_message_id": { "$type": "string" } },
+        ),
+    ]
+}
+
+fn user_preferences_indexes() -> Vec<IndexModel> {
+    vec![unique_index(doc! { "user_id": 1 })]
+}
+
+fn workspace_files_indexes() -> Vec<IndexModel> {
+    vec![unique_index(doc! { "file_id": 1, "file_type": 1 })]
+}
+
+fn approval_requests_indexes() -> Vec<IndexModel> {
+    vec![
+        unique_index(doc! { "approval_id": 1 }),
+        ttl_index("created_at", APPROVAL_RETENTION),
+    ]
+}
+
+fn slack_pending_actions_indexes() -> Vec<IndexModel> {
+    vec![
+        unique_index(doc! { "callback_id": 1 }),
+        ttl_index("created_at", PENDING_ACTION_RETENTION),
+    ]
+}
+
+fn chat_message_links_indexes() -> Vec<IndexModel> {
+    vec![
+        unique_index(doc! { "channel": 1, "message_id": 1 }),
+        ttl_index("created_at", MESSAGE_LINK_RETENTION),
+    ]
+}
+
+/// Entries carry their own `expires_at`, set per channel.
+fn inbound_dedupe_indexes() -> Vec<IndexModel> {
+    vec![
+        unique_index(doc! { "channel": 1, "key": 1 }),
+        ttl_index("expires_at", Duration::ZERO),
+    ]
+}
+
+fn agent_audit_log_indexes() -> Vec<IndexModel> {
+    vec![
+        index(doc! { "recorded_at": -1 }),
+        index(doc! { "on_behalf_of": 1, "recorded_at": -1 }),
+        index(doc! { "actor": 1, "recorded_at": -1 }),
+        index(doc! { "trace_id": 1 }),
+    ]
+}
+
+fn router_decisions_indexes() -> Vec<IndexModel> {
+    vec![
+        index(doc! { "recorded_at": -1 }),
+        index(doc! { "employee_id": 1, "user_id": 1, "channel": 1, "recorded_at": -1 }),
+    ]
+}
+
+fn notion_processed_notifications_indexes() -> Vec<IndexModel> {
+    vec![
+        unique_index(doc! { "notification_id": 1 }),
+        index(doc! { "processed_at": -1 }),
+        index(doc! { "workspace_id": 1, "processed_at": -1 }),
+    ]
+}
+
+fn notion_oauth_tokens_indexes() -> Vec<IndexModel> {
+    vec![
+        unique_index(doc! { "workspace_id": 1, "employee_id": 1 }),
+        index(doc! { "employee_id": 1 }),
+    ]
+}
+
+fn notion_credentials_indexes() -> Vec<IndexModel> {
+    vec![
+        unique_index(doc! { "account_id": 1, "workspace_id": 1 }),
+        index(doc! { "workspace_id": 1 }),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans_up_and_down_steps() {
+        let known = [1, 2, 3];
+        let applied = BTreeSet::from([1]);
+        assert_eq!(
+            plan_steps(&known, &applied, 3).unwrap(),
+            vec![Step::Up(2), Step::Up(3)]
+        );
+        assert_eq!(
+            plan_steps(&known, &BTreeSet::from([1, 2, 3]), 1).unwrap(),
+            vec![Step::Down(3), Step::Down(2)]
+        );
+        assert!(plan_steps(&known, &BTreeSet::from([1, 2, 3]), 3)
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            plan_steps(&known, &BTreeSet::from([1, 4]), 2),
+            Err(MigrationError::AppliedByNewerBuild(4))
+        ));
+        assert!(matches!(
+            plan_steps(&known, &applied, 9),
+            Err(MigrationError::UnknownVersion(9))
+        ));
+    }
+
+    #[test]
+    fn mongo_migrations_are_ordered_and_named_like_mongodb() {
+        let versions: Vec<u32> = MONGO_MIGRATIONS.iter().map(|m| m.version).collect();
+        let expected: Vec<u32> = (1..=versions.len() as u32).collect();
+        assert_eq!(versions, expected);
+        assert_eq!(
+            index_name(&doc! { "owner_scope.kind": 1, "started_at": -1 }),
+            "owner_scope.kind_1_started_at_-1"
+        );
+    }
+
+    #[test]
+    fn thread_db_migrations_import_files_and_roll_back() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let workspaces_root = dir.path().join("workspaces");
+        let workspace = workspaces_root.join("thread_old");
+        fs::create_dir_all(&workspace).unwrap();
+        let state = crate::thread_state::ThreadState::new("thread-old".to_string(), None);
+        fs::write(
+            workspace.join("thread_state.json"),
+            serde_json::to_string(&state).unwrap(),
+        )
+        .unwrap();
+        let mut conn = Connection::open_in_memory().unwrap();
+
+        let steps =
+            migrate_thread_db(&mut conn, &workspaces_root, latest_thread_db_version()).expect("up");
+        assert_eq!(steps, vec![Step::Up(1), Step::Up(2)]);
+        let threads: i64 = conn
+            .query_row("SELECT COUNT(*) FROM threads", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(threads, 1);
+        assert_eq!(thread_db_schema_status(&conn).unwrap().pending(), 0);
+
+        let steps = migrate_thread_db(&mut conn, &workspaces_root, 0).expect("down");
+        assert_eq!(steps, vec![Step::Down(2), Step::Down(1)]);
+        assert!(conn.prepare("SELECT * FROM threads").is_err());
+        assert_eq!(thread_db_schema_status(&conn).unwrap().current(), 0);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::sync::{Mutex, OnceLock};
 use std::thread;
@@ -6,7 +6,7 @@ use std::time::Duration;
 
 use mongodb::bson::{doc, Document};
 use mongodb::error::ErrorKind;
-use mongodb::options::{ClientOptions, IndexOptions};
+use mongodb::options::ClientOptions;
 use mongodb::sync::{Client, Collection, Database};
 use mongodb::IndexModel;
 use tracing::warn;
@@ -21,15 +21,50 @@ pub enum MongoStoreError {
     Mongo(#[from] mongodb::error::Error),
 }
 
+/// Shared client for `MONGODB_URI`.
+///
+/// Stores are opened per request and per task owner, so building a fresh
+/// `Client` each time would spin up a new connection pool and server monitors
+/// on every call. Clients are cached per URI for the life of the process and
+/// cloned (cheaply) into each store; index creation is cached separately in
+/// [`ensure_index_compatible`].
 pub fn create_client_from_env() -> Result<Client, MongoStoreError> {
     let uri = env::var("MONGODB_URI")
         .ok()
         .map(|value| value.trim().to_string())
         .filter(|value| !value.is_empty())
         .ok_or(MongoStoreError::MissingMongoUri)?;
-    let mut options = ClientOptions::parse(uri)?;
+    let mut clients = shared_clients()
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    if let Some(client) = clients.get(&uri) {
+        return Ok(client.clone());
+    }
+    let mut options = ClientOptions::parse(&uri)?;
     options.app_name = Some("DoWhizScheduler".to_string());
-    Ok(Client::with_options(options)?)
+    if let Some(size) = pool_size_from_env("MONGODB_MAX_POOL_SIZE") {
+        options.max_pool_size = Some(size);
+    }
+    if let Some(size) = pool_size_from_env("MONGODB_MIN_POOL_SIZE") {
+        options.min_pool_size = Some(size);
+    }
+    let client = Client::with_options(options)?;
+    clients.insert(uri, client.clone());
+    Ok(client)
+}
+
+fn shared_clients() -> &'static Mutex<HashMap<String, Client>> {
+    static CLIENTS: OnceLock<Mutex<HashMap<String, Client>>> = OnceLock::new();
+    CLIENTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn pool_size_from_env(name: &str) -> Option<u32> {
+    parse_pool_size(env::var(name).ok().as_deref())
+}
+
+fn parse_pool_size(raw: Option<&str>) -> Option<u32> {
+    raw.and_then(|value| value.trim().parse::<u32>().ok())
+        .filter(|value| *value > 0)
 }
 
 pub fn mongo_database_name_from_env() -> String {
@@ -67,21 +102,27 @@ pub fn health_check_from_env() -> Result<(), MongoStoreError> {
     Ok(())
 }
 
-pub fn bootstrap_indexes_from_env() -> Result<(), MongoStoreError> {
-    if !StorageBackend::from_env().uses_mongo() {
-        return Ok(());
-    }
+/// Runs `validate` on every collection and returns the ones the server
+/// reports invalid; `None` when the server does not support the command
+/// (e.g. Cosmos DB).
+pub fn invalid_collections_from_env() -> Result<Option<Vec<String>>, MongoStoreError> {
     let client = create_client_from_env()?;
     let db = database_from_env(&client);
-
-    ensure_users_indexes(&db)?;
-    ensure_tasks_indexes(&db)?;
-    ensure_task_executions_indexes(&db)?;
-    ensure_task_index_indexes(&db)?;
-    ensure_account_task_views_indexes(&db)?;
-    ensure_slack_installation_indexes(&db)?;
-    ensure_processed_comments_indexes(&db)?;
-    Ok(())
+    let mut names = db.list_collection_names(doc! { "type": "collection" })?;
+    names.sort();
+    let mut invalid = Vec::new();
+    for name in names {
+        match db.run_command(doc! { "validate": &name }, None) {
+            Ok(result) => {
+                if !result.get_bool("valid").unwrap_or(true) {
+                    invalid.push(name);
+                }
+            }
+            Err(err) if matches!(err.kind.as_ref(), ErrorKind::Command(_)) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(Some(invalid))
 }
 
 pub fn ensure_index_compatible(
@@ -195,152 +236,6 @@ fn index_cache_key(collection: &Collection<Document>, model: &IndexModel) -> Str
     )
 }
 
-fn ensure_users_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
-    let collection = db.collection::<Document>("users");
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "user_id": 1 })
-            .options(IndexOptions::builder().unique(Some(true)).build())
-            .build(),
-    )?;
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "identifier_type": 1, "identifier": 1 })
-            .build(),
-    )?;
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder().keys(doc! { "created_at": 1 }).build(),
-    )?;
-    Ok(())
-}
-
-fn ensure_tasks_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
-    let collection = db.collection::<Document>("tasks");
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "owner_scope.kind": 1, "owner_scope.id": 1, "task_id": 1 })
-            .build(),
-    )?;
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "owner_scope.kind": 1, "owner_scope.id": 1, "enabled": 1 })
-            .build(),
-    )?;
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "enabled": 1, "schedule.next_run": 1 })
-            .build(),
-    )?;
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "owner_scope.kind": 1, "owner_scope.id": 1, "created_at": 1 })
-            .build(),
-    )?;
-    Ok(())
-}
-
-fn ensure_task_executions_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
-    let collection = db.collection::<Document>("task_executions");
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! {
-                "owner_scope.kind": 1,
-                "owner_scope.id": 1,
-                "task_id": 1,
-                "started_at": -1
-            })
-            .build(),
-    )?;
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "started_at": -1 })
-            .build(),
-    )?;
-    Ok(())
-}
-
-fn ensure_task_index_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
-    let collection = db.collection::<Document>("task_index");
-    // user_id must come first for sharded collections (Cosmos DB shard key compatibility)
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "user_id": 1, "task_id": 1 })
-            .options(IndexOptions::builder().unique(Some(true)).build())
-            .build(),
-    )?;
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "enabled": 1, "next_run": 1 })
-            .build(),
-    )?;
-    Ok(())
-}
-
-fn ensure_account_task_views_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
-    let collection = db.collection::<Document>("account_task_views");
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "account_id": 1, "task_id": 1 })
-            .options(IndexOptions::builder().unique(Some(true)).build())
-            .build(),
-    )?;
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "account_id": 1, "created_at": -1 })
-            .build(),
-    )?;
-    Ok(())
-}
-
-fn ensure_slack_installation_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
-    let collection = db.collection::<Document>("slack_installations");
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "team_id": 1 })
-            .options(IndexOptions::builder().unique(Some(true)).build())
-            .build(),
-    )?;
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "installed_at": -1 })
-            .build(),
-    )?;
-    Ok(())
-}
-
-fn ensure_processed_comments_indexes(db: &Database) -> Result<(), mongodb::error::Error> {
-    let collection = db.collection::<Document>("processed_comments");
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "file_id": 1, "tracking_id": 1 })
-            .options(IndexOptions::builder().unique(Some(true)).build())
-            .build(),
-    )?;
-    ensure_index_compatible(
-        &collection,
-        IndexModel::builder()
-            .keys(doc! { "file_type": 1, "processed_at": -1 })
-            .build(),
-    )?;
-    Ok(())
-}
-
 fn sanitize_fragment(raw: &str) -> String {
     let mut result = String::with_capacity(raw.len());
     let mut last_was_underscore = false;
@@ -358,7 +253,7 @@ fn sanitize_fragment(raw: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::sanitize_fragment;
+    use super::{parse_pool_size, sanitize_fragment};
 
     #[test]
     fn sanitize_fragment_normalizes_separators() {
@@ -368,4 +263,12 @@ mod tests {
         );
         assert_eq!(sanitize_fragment("prod--west"), "prod_west");
     }
+
+    #[test]
+    fn parse_pool_size_ignores_blank_zero_and_garbage() {
+        assert_eq!(parse_pool_size(Some(" 32 ")), Some(32));
+        assert_eq!(parse_pool_size(Some("0")), None);
+        assert_eq!(parse_pool_size(Some("lots")), None);
+        assert_eq!(parse_pool_size(None), None);
+    }
 }
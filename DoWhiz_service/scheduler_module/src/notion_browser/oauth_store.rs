@@ -5,9 +5,8 @@
 
 use chrono::{DateTime, Utc};
 use mongodb::bson::{doc, Document};
-use mongodb::options::{IndexOptions, UpdateOptions};
+use mongodb::options::UpdateOptions;
 use mongodb::sync::Collection;
-use mongodb::IndexModel;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
 
@@ -65,7 +64,6 @@ impl NotionOAuthStore {
         let collection = db.collection("notion_oauth_tokens");
 
         let store = Self::new(collection);
-        store.ensure_indexes()?;
 
         info!(
             "Initialized NotionOAuthStore for employee {}",
@@ -75,35 +73,6 @@ impl NotionOAuthStore {
         Ok(store)
     }
 
-    /// Ensure required indexes exist on the collection.
-    fn ensure_indexes(&self) -> Result<(), NotionError> {
-        let collection = match &self.collection {
-            Some(c) => c,
-            None => return Ok(()),
-        };
-
-        use crate::mongo_store::ensure_index_compatible;
-
-        // Unique index on workspace_id + employee_id
-        let workspace_index = IndexModel::builder()
-            .keys(doc! { "workspace_id": 1, "employee_id": 1 })
-            .options(IndexOptions::builder().unique(true).build())
-            .build();
-
-        // Index on employee_id for listing all tokens for an employee
-        let employee_index = IndexModel::builder()
-            .keys(doc! { "employee_id": 1 })
-            .build();
-
-        ensure_index_compatible(collection, workspace_index)
-            .map_err(|e| NotionError::StorageError(e.to_string()))?;
-
-        ensure_index_compatible(collection, employee_index)
-            .map_err(|e| NotionError::StorageError(e.to_string()))?;
-
-        Ok(())
-    }
-
     /// Get the access token for a workspace.
     pub fn get_token(&self, workspace_id: &str, employee_id: &str) -> Result<Option<String>, NotionError> {
         let collection = match &self.collection {
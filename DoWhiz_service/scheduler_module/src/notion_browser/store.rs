@@ -2,9 +2,8 @@
 
 use chrono::{DateTime, Utc};
 use mongodb::bson::{doc, Document};
-use mongodb::options::{IndexOptions, UpdateOptions};
+use mongodb::options::UpdateOptions;
 use mongodb::sync::Collection;
-use mongodb::IndexModel;
 use tracing::{debug, info, warn};
 
 use super::NotionError;
@@ -51,7 +50,6 @@ impl MongoNotionProcessedStore {
         let collection = db.collection("notion_processed_notifications");
 
         let store = Self::new(collection);
-        store.ensure_indexes()?;
 
         info!(
             "Initialized MongoNotionProcessedStore for employee {}",
@@ -61,43 +59,6 @@ impl MongoNotionProcessedStore {
         Ok(store)
     }
 
-    /// Ensure required indexes exist on the collection.
-    fn ensure_indexes(&self) -> Result<(), NotionError> {
-        let collection = match &self.collection {
-            Some(c) => c,
-            None => return Ok(()), // noop mode
-        };
-
-        use crate::mongo_store::ensure_index_compatible;
-
-        // Unique index on notification_id
-        let notification_index = IndexModel::builder()
-            .keys(doc! { "notification_id": 1 })
-            .options(IndexOptions::builder().unique(true).build())
-            .build();
-
-        // Index on processed_at for cleanup queries
-        let timestamp_index = IndexModel::builder()
-            .keys(doc! { "processed_at": -1 })
-            .build();
-
-        // Index on workspace_id for workspace-specific queries
-        let workspace_index = IndexModel::builder()
-            .keys(doc! { "workspace_id": 1, "processed_at": -1 })
-            .build();
-
-        ensure_index_compatible(collection, notification_index)
-            .map_err(|e| NotionError::StorageError(e.to_string()))?;
-
-        ensure_index_compatible(collection, timestamp_index)
-            .map_err(|e| NotionError::StorageError(e.to_string()))?;
-
-        ensure_index_compatible(collection, workspace_index)
-            .map_err(|e| NotionError::StorageError(e.to_string()))?;
-
-        Ok(())
-    }
-
     /// Check if a notification has already been processed.
     pub fn is_processed(&self, notification_id: &str) -> Result<bool, NotionError> {
         let collection = match &self.collection {
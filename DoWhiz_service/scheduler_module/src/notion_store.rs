@@ -4,11 +4,10 @@
 
 use chrono::{DateTime, Utc};
 use mongodb::bson::{doc, Bson, DateTime as BsonDateTime, Document};
-use mongodb::options::{IndexOptions, UpdateOptions};
+use mongodb::options::UpdateOptions;
 use mongodb::sync::Collection;
-use mongodb::IndexModel;
 
-use crate::mongo_store::{create_client_from_env, database_from_env, ensure_index_compatible};
+use crate::mongo_store::{create_client_from_env, database_from_env};
 
 /// A Notion OAuth credential record.
 #[derive(Debug, Clone)]
@@ -46,33 +45,7 @@ impl NotionStore {
             .map_err(|e| NotionStoreError::MongoConfig(e.to_string()))?;
         let db = database_from_env(&client);
         let credentials = db.collection::<Document>("notion_credentials");
-
-        // Create indexes
-        let store = Self { credentials };
-        store.ensure_indexes()?;
-
-        Ok(store)
-    }
-
-    fn ensure_indexes(&self) -> Result<(), NotionStoreError> {
-        // Unique index on account_id + workspace_id
-        ensure_index_compatible(
-            &self.credentials,
-            IndexModel::builder()
-                .keys(doc! { "account_id": 1, "workspace_id": 1 })
-                .options(IndexOptions::builder().unique(Some(true)).build())
-                .build(),
-        )?;
-
-        // Index on workspace_id for lookups
-        ensure_index_compatible(
-            &self.credentials,
-            IndexModel::builder()
-                .keys(doc! { "workspace_id": 1 })
-                .build(),
-        )?;
-
-        Ok(())
+        Ok(Self { credentials })
     }
 
     /// Save or update a credential for an account/workspace.
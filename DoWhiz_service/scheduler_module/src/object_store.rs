@@ -0,0 +1,355 @@
+//! Object storage for data moved off the worker: tiered mail (see
+//! [`crate::archive_tiering`]) and state backups (see [`crate::backup`]).
+//!
+//! Each user of this module has its own env prefix, e.g. `ARCHIVE_TIER`:
+//! `<PREFIX>_BACKEND` picks `azure` (`<PREFIX>_AZURE_CONTAINER_SAS_URL`),
+//! `s3` (`<PREFIX>_S3_BUCKET`, `<PREFIX>_S3_REGION`, `<PREFIX>_S3_ENDPOINT`
+//! plus the standard AWS credentials) or `local` (`<PREFIX>_LOCAL_DIR`).
+
+use std::fs;
+use std::io;
+use std::path::{Component, PathBuf};
+use std::sync::Arc;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::blocking::{Client, Response};
+use reqwest::Method;
+use sha2::{Digest, Sha256};
+
+const DEFAULT_S3_REGION: &str = "us-east-1";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ObjectStoreError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("object storage is misconfigured: {0}")]
+    Config(String),
+    #[error("object storage error: {0}")]
+    Storage(String),
+}
+
+pub trait ObjectStore: Send + Sync {
+    /// Backend name, e.g. for recording where an object went.
+    fn name(&self) -> &'static str;
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), ObjectStoreError>;
+    fn get(&self, key: &str) -> Result<Vec<u8>, ObjectStoreError>;
+}
+
+/// The store picked by `<prefix>_BACKEND`; `None` when unset.
+pub fn object_store_from_env(
+    prefix: &str,
+) -> Result<Option<Arc<dyn ObjectStore>>, ObjectStoreError> {
+    let var = |name: &str| format!("{}_{}", prefix, name);
+    let Some(backend) = env_value(&var("BACKEND")) else {
+        return Ok(None);
+    };
+    let store: Arc<dyn ObjectStore> = match backend.to_ascii_lowercase().as_str() {
+        "azure" => Arc::new(AzureObjectStore::new(required_env(&var(
+            "AZURE_CONTAINER_SAS_URL",
+        ))?)),
+        "s3" => Arc::new(S3ObjectStore::from_env(prefix)?),
+        "local" => Arc::new(LocalObjectStore::new(PathBuf::from(required_env(&var(
+            "LOCAL_DIR",
+        ))?))),
+        other => {
+            return Err(ObjectStoreError::Config(format!(
+                "unknown {} '{}'",
+                var("BACKEND"),
+                other
+            )))
+        }
+    };
+    Ok(Some(store))
+}
+
+/// Azure Blob container addressed by a container SAS URL with read and write
+/// permissions.
+pub struct AzureObjectStore {
+    container_sas_url: String,
+    client: Client,
+}
+
+impl AzureObjectStore {
+    pub fn new(container_sas_url: String) -> Self {
+        Self {
+            container_sas_url,
+            client: Client::new(),
+        }
+    }
+
+    fn blob_url(&self, key: &str) -> String {
+        let mut parts = self.container_sas_url.splitn(2, '?');
+        let base = parts.next().unwrap_or("").trim_end_matches('/');
+        match parts.next().filter(|sas| !sas.is_empty()) {
+            Some(sas) => format!("{}/{}?{}", base, uri_encode_path(key), sas),
+            None => format!("{}/{}", base, uri_encode_path(key)),
+        }
+    }
+}
+
+impl ObjectStore for AzureObjectStore {
+    fn name(&self) -> &'static str {
+        "azure"
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), ObjectStoreError> {
+        let response = self
+            .client
+            .put(self.blob_url(key))
+            .header("x-ms-blob-type", "BlockBlob")
+            .body(data.to_vec())
+            .send()?;
+        check_status(response, "upload").map(|_| ())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        let response = self.client.get(self.blob_url(key)).send()?;
+        Ok(check_status(response, "download")?.bytes()?.to_vec())
+    }
+}
+
+/// S3 or S3-compatible bucket, path-style, signed with AWS Signature V4.
+pub struct S3ObjectStore {
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+    client: Client,
+}
+
+impl S3ObjectStore {
+    /// Reads `<prefix>_S3_BUCKET`, `<prefix>_S3_REGION` (else `AWS_REGION`,
+    /// default us-east-1), `<prefix>_S3_ENDPOINT` (default the AWS endpoint of
+    /// the region) and the standard `AWS_ACCESS_KEY_ID`,
+    /// `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`.
+    pub fn from_env(prefix: &str) -> Result<Self, ObjectStoreError> {
+        let var = |name: &str| format!("{}_{}", prefix, name);
+        let region = env_value(&var("S3_REGION"))
+            .or_else(|| env_value("AWS_REGION"))
+            .unwrap_or_else(|| DEFAULT_S3_REGION.to_string());
+        let endpoint = env_value(&var("S3_ENDPOINT"))
+            .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        Ok(Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: required_env(&var("S3_BUCKET"))?,
+            region,
+            access_key_id: required_env("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: required_env("AWS_SECRET_ACCESS_KEY")?,
+            session_token: env_value("AWS_SESSION_TOKEN"),
+            client: Client::new(),
+        })
+    }
+
+    fn send(&self, method: Method, key: &str, body: Vec<u8>) -> Result<Response, ObjectStoreError> {
+        let path = format!(
+            "/{}/{}",
+            uri_encode_path(&self.bucket),
+            uri_encode_path(key)
+        );
+        let url = reqwest::Url::parse(&format!("{}{}", self.endpoint, path))
+            .map_err(|err| ObjectStoreError::Config(format!("invalid S3 endpoint: {}", err)))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => {
+                return Err(ObjectStoreError::Config(format!(
+                    "S3 endpoint {} has no host",
+                    self.endpoint
+                )))
+            }
+        };
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let mut headers = vec![
+            ("host", host),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method.as_str(),
+            path,
+            canonical_headers,
+            signed_headers,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&self.secret_access_key, &date, &self.region, "s3");
+        let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        );
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("authorization", authorization);
+        for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+            request = request.header(name, value);
+        }
+        Ok(request.body(body).send()?)
+    }
+}
+
+impl ObjectStore for S3ObjectStore {
+    fn name(&self) -> &'static str {
+        "s3"
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), ObjectStoreError> {
+        let response = self.send(Method::PUT, key, data.to_vec())?;
+        check_status(response, "upload").map(|_| ())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        let response = self.send(Method::GET, key, Vec::new())?;
+        Ok(check_status(response, "download")?.bytes()?.to_vec())
+    }
+}
+
+/// A directory standing in for object storage, for local runs.
+pub struct LocalObjectStore {
+    root: PathBuf,
+}
+
+impl LocalObjectStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, ObjectStoreError> {
+        safe_relative_path(key)
+            .map(|relative| self.root.join(relative))
+            .ok_or_else(|| ObjectStoreError::Storage(format!("invalid object key {}", key)))
+    }
+}
+
+impl ObjectStore for LocalObjectStore {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), ObjectStoreError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        fs::write(&tmp_path, data)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, ObjectStoreError> {
+        Ok(fs::read(self.path(key)?)?)
+    }
+}
+
+/// `path` when it only has normal components, so it cannot leave the
+/// directory it is joined to.
+pub(crate) fn safe_relative_path(path: &str) -> Option<PathBuf> {
+    let path = PathBuf::from(path);
+    (!path.as_os_str().is_empty()
+        && path
+            .components()
+            .all(|part| matches!(part, Component::Normal(_))))
+    .then_some(path)
+}
+
+fn check_status(response: Response, action: &str) -> Result<Response, ObjectStoreError> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().unwrap_or_default();
+    Err(ObjectStoreError::Storage(format!(
+        "{} failed (status {}): {}",
+        action, status, body
+    )))
+}
+
+/// Percent-encode everything but unreserved characters and `/`.
+fn uri_encode_path(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~' | b'/') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{}", secret).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn required_env(name: &str) -> Result<String, ObjectStoreError> {
+    env_value(name).ok_or_else(|| ObjectStoreError::Config(format!("{} is not set", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signing_key_matches_aws_example() {
+        // From the AWS Signature Version 4 documentation.
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+        assert_eq!(safe_relative_path("../etc/passwd"), None);
+        assert_eq!(uri_encode_path("a b/c+d.json"), "a%20b/c%2Bd.json");
+    }
+}
@@ -0,0 +1,269 @@
+//! Per-employee limits on where outbound messages may go.
+//!
+//! Configured under `[employees.outbound_policy]` in `employee.toml` and
+//! checked before every SendReply adapter call. An empty policy allows
+//! everything, so employees without the section behave as before.
+
+use serde::Deserialize;
+
+use crate::channel::Channel;
+
+/// Channels whose recipients are phone numbers.
+const PHONE_CHANNELS: &[Channel] = &[Channel::Sms, Channel::WhatsApp, Channel::BlueBubbles];
+
+/// Raw `[employees.outbound_policy]` table.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OutboundPolicyConfig {
+    /// Email recipients must belong to one of these domains (subdomains included).
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    /// SMS, WhatsApp and iMessage recipients must be one of these numbers.
+    #[serde(default)]
+    pub allowed_numbers: Vec<String>,
+    /// Always refused: email addresses, phone numbers, chat IDs, or `@domain`.
+    #[serde(default)]
+    pub denied_recipients: Vec<String>,
+    /// Most recipients (to + cc + bcc) one message may have.
+    #[serde(default)]
+    pub max_recipients: Option<usize>,
+    /// Channels this employee may never send on.
+    #[serde(default)]
+    pub forbidden_channels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutboundPolicy {
+    pub allowed_domains: Vec<String>,
+    pub allowed_numbers: Vec<String>,
+    pub denied_recipients: Vec<String>,
+    pub max_recipients: Option<usize>,
+    pub forbidden_channels: Vec<Channel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PolicyViolation {
+    #[error("sending on {0} is not allowed")]
+    ForbiddenChannel(Channel),
+    #[error("{count} recipients exceed the limit of {max}")]
+    TooManyRecipients { count: usize, max: usize },
+    #[error("{0} is on the deny list")]
+    DeniedRecipient(String),
+    #[error("{0} is not on the allow list")]
+    RecipientNotAllowed(String),
+}
+
+impl OutboundPolicy {
+    pub fn from_config(config: &OutboundPolicyConfig) -> Result<Self, String> {
+        let forbidden_channels = config
+            .forbidden_channels
+            .iter()
+            .map(|value| value.trim().parse::<Channel>())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            allowed_domains: normalize_entries(&config.allowed_domains, |value| {
+                value.trim_start_matches('@').to_string()
+            }),
+            allowed_numbers: normalize_entries(&config.allowed_numbers, phone_digits),
+            denied_recipients: normalize_entries(&config.denied_recipients, |value| {
+                if value.contains('@') {
+                    value.to_string()
+                } else {
+                    match phone_digits(value) {
+                        digits if digits.is_empty() => value.to_string(),
+                        digits => digits,
+                    }
+                }
+            }),
+            max_recipients: config.max_recipients,
+            forbidden_channels,
+        })
+    }
+
+    /// Check one message on `channel` addressed to `recipients`.
+    pub fn check(&self, channel: Channel, recipients: &[&str]) -> Result<(), PolicyViolation> {
+        if self.forbidden_channels.contains(&channel) {
+            return Err(PolicyViolation::ForbiddenChannel(channel));
+        }
+        if let Some(max) = self.max_recipients {
+            if recipients.len() > max {
+                return Err(PolicyViolation::TooManyRecipients {
+                    count: recipients.len(),
+                    max,
+                });
+            }
+        }
+        for recipient in recipients {
+            self.check_recipient(channel, recipient)?;
+        }
+        Ok(())
+    }
+
+    /// Whether `recipient` alone passes the deny and allow lists.
+    pub fn permits(&self, channel: Channel, recipient: &str) -> bool {
+        self.check_recipient(channel, recipient).is_ok()
+    }
+
+    fn check_recipient(&self, channel: Channel, recipient: &str) -> Result<(), PolicyViolation> {
+        let raw = recipient.trim().to_ascii_lowercase();
+        if let Some(address) = email_address(&raw) {
+            let domain = address.rsplit('@').next().unwrap_or_default();
+            let denied = self.denied_recipients.iter().any(|entry| {
+                *entry == address
+                    || entry
+                        .strip_prefix('@')
+                        .is_some_and(|denied| domain_matches(domain, denied))
+            });
+            if denied {
+                return Err(PolicyViolation::DeniedRecipient(address));
+            }
+            if !self.allowed_domains.is_empty()
+                && !self
+                    .allowed_domains
+                    .iter()
+                    .any(|allowed| domain_matches(domain, allowed))
+            {
+                return Err(PolicyViolation::RecipientNotAllowed(address));
+            }
+            return Ok(());
+        }
+
+        if PHONE_CHANNELS.contains(&channel) {
+            let digits = phone_digits(&raw);
+            if self.denied_recipients.contains(&digits) {
+                return Err(PolicyViolation::DeniedRecipient(
+                    recipient.trim().to_string(),
+                ));
+            }
+            if !self.allowed_numbers.is_empty() && !self.allowed_numbers.contains(&digits) {
+                return Err(PolicyViolation::RecipientNotAllowed(
+                    recipient.trim().to_string(),
+                ));
+            }
+            return Ok(());
+        }
+
+        if self.denied_recipients.contains(&raw) {
+            return Err(PolicyViolation::DeniedRecipient(
+                recipient.trim().to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn normalize_entries(values: &[String], normalize: impl Fn(&str) -> String) -> Vec<String> {
+    values
+        .iter()
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty())
+        .map(|value| normalize(&value))
+        .collect()
+}
+
+/// The bare address of `recipient`, which may be in `Name <addr>` form.
+fn email_address(recipient: &str) -> Option<String> {
+    let address = match (recipient.rfind('<'), recipient.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &recipient[start + 1..end],
+        _ => recipient,
+    };
+    let address = address.trim();
+    address.contains('@').then(|| address.to_string())
+}
+
+fn phone_digits(value: &str) -> String {
+    value.chars().filter(char::is_ascii_digit).collect()
+}
+
+fn domain_matches(domain: &str, allowed: &str) -> bool {
+    domain == allowed
+        || domain
+            .strip_suffix(allowed)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(config: OutboundPolicyConfig) -> OutboundPolicy {
+        OutboundPolicy::from_config(&config).expect("valid policy")
+    }
+
+    #[test]
+    fn empty_policy_allows_everything() {
+        let policy = OutboundPolicy::default();
+        assert!(policy
+            .check(Channel::Email, &["someone@anywhere.io", "x@y.z"])
+            .is_ok());
+        assert!(policy.check(Channel::Sms, &["+1 555 0100"]).is_ok());
+    }
+
+    #[test]
+    fn domain_allowlist_covers_subdomains_and_display_names() {
+        let policy = policy(OutboundPolicyConfig {
+            allowed_domains: vec!["@Acme.com".to_string()],
+            ..Default::default()
+        });
+        assert!(policy
+            .check(Channel::Email, &["Ann <ann@acme.com>", "bob@eu.acme.com"])
+            .is_ok());
+        assert_eq!(
+            policy.check(Channel::Email, &["eve@notacme.com"]),
+            Err(PolicyViolation::RecipientNotAllowed(
+                "eve@notacme.com".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn deny_list_wins_over_allowlist() {
+        let policy = policy(OutboundPolicyConfig {
+            allowed_domains: vec!["acme.com".to_string()],
+            allowed_numbers: vec!["+1 (555) 010-0100".to_string()],
+            denied_recipients: vec!["ceo@acme.com".to_string(), "+15550100100".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            policy.check(Channel::Email, &["CEO@acme.com"]),
+            Err(PolicyViolation::DeniedRecipient("ceo@acme.com".to_string()))
+        );
+        assert!(!policy.permits(Channel::WhatsApp, "whatsapp:+15550100100"));
+    }
+
+    #[test]
+    fn phone_allowlist_applies_to_phone_channels_only() {
+        let policy = policy(OutboundPolicyConfig {
+            allowed_numbers: vec!["+1 555 010 0100".to_string()],
+            ..Default::default()
+        });
+        assert!(policy.permits(Channel::Sms, "+15550100100"));
+        assert!(!policy.permits(Channel::Sms, "+15550100199"));
+        assert!(policy.permits(Channel::Slack, "C0123456"));
+    }
+
+    #[test]
+    fn channel_and_recipient_caps() {
+        let policy = policy(OutboundPolicyConfig {
+            max_recipients: Some(2),
+            forbidden_channels: vec!["WhatsApp".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            policy.check(Channel::WhatsApp, &["+15550100100"]),
+            Err(PolicyViolation::ForbiddenChannel(Channel::WhatsApp))
+        );
+        assert_eq!(
+            policy.check(Channel::Email, &["a@x.com", "b@x.com", "c@x.com"]),
+            Err(PolicyViolation::TooManyRecipients { count: 3, max: 2 })
+        );
+    }
+
+    #[test]
+    fn unknown_forbidden_channel_is_rejected() {
+        let config = OutboundPolicyConfig {
+            forbidden_channels: vec!["fax".to_string()],
+            ..Default::default()
+        };
+        assert!(OutboundPolicy::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! Full-text search over a user's past emails.
+//!
+//! Instead of copying every archived message into each workspace, the
+//! workspace gets one `references/past_emails/search_index.json` built from
+//! the user's mail archive (see `past_emails::index_past_emails`). It holds
+//! each message's headers and text plus an inverted index, and the
+//! `past-emails` CLI ranks messages against a query with BM25 (subject words
+//! count three times) so the runner pulls in only the history it needs.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Index file name under `references/past_emails/`.
+pub const SEARCH_INDEX_FILE: &str = "search_index.json";
+/// Message bodies are cut off after this many characters.
+pub const MAX_BODY_CHARS: usize = 20_000;
+
+const INDEX_VERSION: u32 = 1;
+const SUBJECT_WEIGHT: u32 = 3;
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+const SNIPPET_CHARS: usize = 200;
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "has", "have", "in", "is",
+    "it", "of", "on", "or", "re", "that", "the", "this", "to", "was", "we", "with", "you",
+];
+
+/// One archived message as stored in the index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedEmail {
+    /// Archive path relative to the mail root, e.g. `2026/02/msg-1`
+    pub id: String,
+    pub message_id: String,
+    /// RFC 3339
+    pub date: Option<String>,
+    /// `inbound` or `outbound`
+    pub direction: String,
+    pub from: String,
+    pub to: String,
+    pub cc: String,
+    pub subject: String,
+    /// Attachment file names; the files stay in the archive
+    pub attachments: Vec<String>,
+    pub body: String,
+}
+
+impl IndexedEmail {
+    fn parsed_date(&self) -> Option<DateTime<Utc>> {
+        self.date
+            .as_deref()
+            .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+            .map(|date| date.with_timezone(&Utc))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub version: u32,
+    pub user_id: String,
+    pub generated_at: String,
+    /// Newest first
+    pub emails: Vec<IndexedEmail>,
+    /// Weighted token count of each email
+    lengths: Vec<u32>,
+    /// Term to `(email index, weighted term frequency)`
+    postings: BTreeMap<String, Vec<(u32, u32)>>,
+}
+
+/// A query for [`SearchIndex::search`]. Without `text`, the newest emails
+/// passing the filters are returned.
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    pub text: String,
+    pub limit: usize,
+    pub since: Option<DateTime<Utc>>,
+    /// Case-insensitive substring of the From, To or Cc header
+    pub participant: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit<'a> {
+    pub email: &'a IndexedEmail,
+    pub score: f64,
+    pub snippet: String,
+}
+
+impl SearchIndex {
+    pub fn build(user_id: &str, mut emails: Vec<IndexedEmail>) -> Self {
+        emails.sort_by_key(|email| std::cmp::Reverse(email.parsed_date()));
+        let mut lengths = Vec::with_capacity(emails.len());
+        let mut postings: BTreeMap<String, Vec<(u32, u32)>> = BTreeMap::new();
+        for (index, email) in emails.iter().enumerate() {
+            let mut counts: HashMap<String, u32> = HashMap::new();
+            for token in tokenize(&email.subject) {
+                *counts.entry(token).or_default() += SUBJECT_WEIGHT;
+            }
+            let headers = format!("{} {} {}", email.from, email.to, email.cc);
+            for token in tokenize(&headers)
+                .into_iter()
+                .chain(tokenize(&email.attachments.join(" ")))
+                .chain(tokenize(&email.body))
+            {
+                *counts.entry(token).or_default() += 1;
+            }
+            lengths.push(counts.values().sum());
+            for (term, count) in counts {
+                postings
+                    .entry(term)
+                    .or_default()
+                    .push((index as u32, count));
+            }
+        }
+        Self {
+            version: INDEX_VERSION,
+            user_id: user_id.to_string(),
+            generated_at: Utc::now().to_rfc3339(),
+            emails,
+            lengths,
+            postings,
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let raw = fs::read(path)?;
+        serde_json::from_slice(&raw).map_err(io::Error::other)
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_vec(self).map_err(io::Error::other)?)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&IndexedEmail> {
+        self.emails
+            .iter()
+            .find(|email| email.id == id || email.message_id == id)
+    }
+
+    pub fn search(&self, query: &SearchQuery) -> Vec<SearchHit<'_>> {
+        let limit = query.limit.max(1);
+        let participant = query
+            .participant
+            .as_deref()
+            .map(|value| value.trim().to_lowercase())
+            .filter(|value| !value.is_empty());
+        let passes = |email: &IndexedEmail| {
+            query
+                .since
+                .is_none_or(|since| email.parsed_date().is_some_and(|date| date >= since))
+                && participant.as_deref().is_none_or(|needle| {
+                    [&email.from, &email.to, &email.cc]
+                        .iter()
+                        .any(|value| value.to_lowercase().contains(needle))
+                })
+        };
+
+        let mut terms = tokenize(&query.text);
+        terms.sort();
+        terms.dedup();
+        if terms.is_empty() {
+            return self
+                .emails
+                .iter()
+                .filter(|email| passes(email))
+                .take(limit)
+                .map(|email| SearchHit {
+                    email,
+                    score: 0.0,
+                    snippet: snippet(&email.body, &[]),
+                })
+                .collect();
+        }
+
+        let total = self.emails.len() as f64;
+        let average_length =
+            (self.lengths.iter().map(|len| *len as f64).sum::<f64>() / total.max(1.0)).max(1.0);
+        let mut scores: HashMap<u32, f64> = HashMap::new();
+        for term in &terms {
+            let Some(postings) = self.postings.get(term) else {
+                continue;
+            };
+            let df = postings.len() as f64;
+            let idf = (1.0 + (total - df + 0.5) / (df + 0.5)).ln();
+            for (index, tf) in postings {
+                let tf = *tf as f64;
+                let length = self.lengths[*index as usize] as f64;
+                let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * length / average_length);
+                *scores.entry(*index).or_default() += idf * tf * (BM25_K1 + 1.0) / (tf + norm);
+            }
+        }
+        let mut ranked: Vec<(u32, f64)> = scores
+            .into_iter()
+            .filter(|(index, _)| passes(&self.emails[*index as usize]))
+            .collect();
+        // Emails are stored newest first, so ties go to the newer one.
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(index, score)| {
+                let email = &self.emails[index as usize];
+                SearchHit {
+                    email,
+                    score,
+                    snippet: snippet(&email.body, &terms),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Lowercased words of two or more characters, without stopwords.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 2)
+        .map(str::to_lowercase)
+        .filter(|word| !STOPWORDS.contains(&word.as_str()))
+        .collect()
+}
+
+/// The first body line mentioning a query term, else the start of the body.
+fn snippet(body: &str, terms: &[String]) -> String {
+    let lines = || body.lines().map(str::trim).filter(|line| !line.is_empty());
+    let line = lines()
+        .find(|line| {
+            let words = tokenize(line);
+            terms.iter().any(|term| words.contains(term))
+        })
+        .or_else(|| lines().next())
+        .unwrap_or_default();
+    if line.chars().count() <= SNIPPET_CHARS {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(SNIPPET_CHARS).collect();
+    format!("{}...", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(id: &str, date: &str, from: &str, subject: &str, body: &str) -> IndexedEmail {
+        IndexedEmail {
+            id: id.to_string(),
+            message_id: format!("<{}@example.com>", id),
+            date: Some(date.to_string()),
+            direction: "inbound".to_string(),
+            from: from.to_string(),
+            to: "Oliver <oliver@dowhiz.com>".to_string(),
+            cc: String::new(),
+            subject: subject.to_string(),
+            attachments: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    fn index() -> SearchIndex {
+        SearchIndex::build(
+            "user-1",
+            vec![
+                email(
+                    "2026/01/a",
+                    "2026-01-05T10:00:00+00:00",
+                    "Alice <alice@example.com>",
+                    "Quarterly invoice",
+                    "Hi,\nThe invoice for Q4 is attached.\nThanks",
+                ),
+                email(
+                    "2026/03/b",
+                    "2026-03-01T10:00:00+00:00",
+                    "Bob <bob@example.com>",
+                    "Lunch on Friday?",
+                    "Are you free for lunch? We could also talk about the invoice.",
+                ),
+                email(
+                    "2026/02/c",
+                    "2026-02-01T10:00:00+00:00",
+                    "Alice <alice@example.com>",
+                    "Travel plans",
+                    "Booking flights to Berlin next month.",
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn search_ranks_subject_matches_first_and_applies_filters() {
+        let index = index();
+        assert_eq!(index.emails[0].id, "2026/03/b", "newest first");
+
+        let hits = index.search(&SearchQuery {
+            text: "Invoice".to_string(),
+            limit: 10,
+            ..Default::default()
+        });
+        let ids: Vec<&str> = hits.iter().map(|hit| hit.email.id.as_str()).collect();
+        assert_eq!(ids, vec!["2026/01/a", "2026/03/b"]);
+        assert_eq!(hits[0].snippet, "The invoice for Q4 is attached.");
+
+        let hits = index.search(&SearchQuery {
+            text: "invoice".to_string(),
+            limit: 10,
+            since: Some(Utc::now() - chrono::Duration::days(100_000)),
+            participant: Some("BOB@".to_string()),
+        });
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].email.id, "2026/03/b");
+
+        let recent = index.search(&SearchQuery {
+            text: "the".to_string(),
+            limit: 2,
+            participant: Some("alice".to_string()),
+            ..Default::default()
+        });
+        let ids: Vec<&str> = recent.iter().map(|hit| hit.email.id.as_str()).collect();
+        assert_eq!(ids, vec!["2026/02/c", "2026/01/a"]);
+    }
+
+    #[test]
+    fn index_round_trips_through_json() {
+        let temp = tempfile::TempDir::new().expect("tempdir");
+        let path = temp.path().join("past_emails").join(SEARCH_INDEX_FILE);
+        index().write(&path).expect("write");
+
+        let loaded = SearchIndex::load(&path).expect("load");
+        assert_eq!(
+            loaded
+                .get("<2026/02/c@example.com>")
+                .map(|email| &email.subject),
+            Some(&"Travel plans".to_string())
+        );
+        let hits = loaded.search(&SearchQuery {
+            text: "berlin flights".to_string(),
+            limit: 5,
+            ..Default::default()
+        });
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].email.id, "2026/02/c");
+    }
+}
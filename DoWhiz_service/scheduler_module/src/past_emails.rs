@@ -4,6 +4,11 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::archive_crypto::{self, ArchiveCryptoError, ArchiveKey};
+use crate::past_email_index::{IndexedEmail, SearchIndex, MAX_BODY_CHARS, SEARCH_INDEX_FILE};
+use crate::redaction::{RedactionError, RedactionPolicy};
 
 const DEFAULT_MAX_ATTACHMENT_BYTES: u64 = 50 * 1024 * 1024;
 
@@ -13,6 +18,10 @@ pub enum PastEmailsError {
     Io(#[from] io::Error),
     #[error("json error: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("redaction error: {0}")]
+    Redaction(#[from] RedactionError),
+    #[error("archive encryption error: {0}")]
+    ArchiveCrypto(#[from] ArchiveCryptoError),
 }
 
 #[derive(Debug, Default, Serialize)]
@@ -50,6 +59,10 @@ struct PostmarkPayload {
     direction: Option<String>,
     #[serde(rename = "Attachments")]
     attachments: Option<Vec<PostmarkAttachment>>,
+    #[serde(rename = "TextBody")]
+    text_body: Option<String>,
+    #[serde(rename = "HtmlBody")]
+    html_body: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -105,11 +118,147 @@ struct AttachmentEntry {
     azure_blob_url: Option<String>,
 }
 
+/// Build the past-email search index for `user_id` from `archive_root` into
+/// `references_dir/past_emails/search_index.json`, including messages moved
+/// to cold storage (see [`crate::archive_tiering`]). Returns the number of
+/// messages indexed.
+pub fn index_past_emails(
+    archive_root: &Path,
+    references_dir: &Path,
+    user_id: &str,
+) -> Result<usize, PastEmailsError> {
+    let key = if archive_root.exists() {
+        ArchiveKey::for_reading(archive_root)?
+    } else {
+        None
+    };
+    index_with_key(archive_root, references_dir, user_id, key.as_ref())
+}
+
+fn index_with_key(
+    archive_root: &Path,
+    references_dir: &Path,
+    user_id: &str,
+    key: Option<&ArchiveKey>,
+) -> Result<usize, PastEmailsError> {
+    let messages = if archive_root.exists() {
+        collect_archive_messages(archive_root, key)?
+    } else {
+        Vec::new()
+    };
+    let mut emails: Vec<IndexedEmail> = messages
+        .iter()
+        .map(|message| to_indexed_email(archive_root, key, message))
+        .collect();
+    emails.extend(crate::archive_tiering::tiered_emails(archive_root, key));
+    let count = emails.len();
+    SearchIndex::build(user_id, emails)
+        .write(&references_dir.join("past_emails").join(SEARCH_INDEX_FILE))?;
+    Ok(count)
+}
+
+/// Search record of the archived message in `entry_dir`; `None` when the
+/// directory holds no readable message.
+pub(crate) fn indexed_email_for_entry(
+    archive_root: &Path,
+    key: Option<&ArchiveKey>,
+    entry_dir: &Path,
+) -> Result<Option<IndexedEmail>, PastEmailsError> {
+    Ok(collect_archive_messages(entry_dir, key)?
+        .first()
+        .map(|message| to_indexed_email(archive_root, key, message)))
+}
+
+fn to_indexed_email(
+    archive_root: &Path,
+    key: Option<&ArchiveKey>,
+    message: &ArchiveMessage,
+) -> IndexedEmail {
+    let payload = &message.payload;
+    let id = message
+        .root_dir
+        .strip_prefix(archive_root)
+        .unwrap_or(&message.root_dir)
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    let body = archived_body(key, message);
+    IndexedEmail {
+        message_id: normalize_message_id(payload.message_id.as_deref())
+            .unwrap_or_else(|| id.clone()),
+        id,
+        date: parse_payload_date(payload.date.as_deref()).map(|date| date.to_rfc3339()),
+        direction: normalize_direction(payload.direction.as_deref().unwrap_or("inbound"))
+            .to_string(),
+        from: payload.from.clone().unwrap_or_default(),
+        to: payload.to.clone().unwrap_or_default(),
+        cc: payload.cc.clone().unwrap_or_default(),
+        subject: payload.subject.clone().unwrap_or_default(),
+        attachments: payload
+            .attachments
+            .iter()
+            .flatten()
+            .map(|attachment| attachment.name.clone())
+            .collect(),
+        body: body.chars().take(MAX_BODY_CHARS).collect(),
+    }
+}
+
+/// Plain text of an archived message: the payload's text body, else its HTML
+/// body or archived `email.html` as readable text.
+fn archived_body(key: Option<&ArchiveKey>, message: &ArchiveMessage) -> String {
+    let payload = &message.payload;
+    if let Some(text) = payload
+        .text_body
+        .as_deref()
+        .filter(|text| !text.trim().is_empty())
+    {
+        return text.trim().to_string();
+    }
+    let html = payload
+        .html_body
+        .clone()
+        .filter(|html| !html.trim().is_empty())
+        .or_else(|| {
+            archive_crypto::read_archived(key, &message.incoming_email_dir.join("email.html"))
+                .ok()
+                .map(|data| String::from_utf8_lossy(&data).into_owned())
+        })
+        .unwrap_or_default();
+    crate::service::html::page_to_text(&html)
+}
+
+/// Copy every archived message, attachments included, into
+/// `references_dir/past_emails`. Workspaces get the search index from
+/// [`index_past_emails`] instead; this is for operators who need the raw
+/// files (`hydrate_past_emails` binary).
 pub fn hydrate_past_emails(
     archive_root: &Path,
     references_dir: &Path,
     user_id: &str,
     max_attachment_bytes: Option<u64>,
+) -> Result<HydrateReport, PastEmailsError> {
+    let key = if archive_root.exists() {
+        ArchiveKey::for_reading(archive_root)?
+    } else {
+        None
+    };
+    hydrate_with_key(
+        archive_root,
+        references_dir,
+        user_id,
+        max_attachment_bytes,
+        key.as_ref(),
+    )
+}
+
+fn hydrate_with_key(
+    archive_root: &Path,
+    references_dir: &Path,
+    user_id: &str,
+    max_attachment_bytes: Option<u64>,
+    key: Option<&ArchiveKey>,
 ) -> Result<HydrateReport, PastEmailsError> {
     let mut report = HydrateReport::default();
     let max_attachment_bytes = max_attachment_bytes.unwrap_or(DEFAULT_MAX_ATTACHMENT_BYTES);
@@ -122,7 +271,7 @@ pub fn hydrate_past_emails(
         return Ok(report);
     }
 
-    let mut messages = collect_archive_messages(archive_root)?;
+    let mut messages = collect_archive_messages(archive_root, key)?;
     messages.sort_by(|a, b| {
         let a_date = parse_payload_date(a.payload.date.as_deref());
         let b_date = parse_payload_date(b.payload.date.as_deref());
@@ -159,10 +308,11 @@ pub fn hydrate_past_emails(
             .unwrap_or_else(|| base.clone());
 
         let dest_email_dir = entry_dir.join("incoming_email");
-        copy_dir_recursive(&message.incoming_email_dir, &dest_email_dir)?;
+        copy_dir_recursive(key, &message.incoming_email_dir, &dest_email_dir)?;
 
         let dest_attachments_dir = entry_dir.join("incoming_attachments");
         let (manifest, attachment_counts) = hydrate_attachments(
+            key,
             &message.payload,
             &message.incoming_attachments_dir,
             &dest_attachments_dir,
@@ -210,6 +360,7 @@ pub fn archive_outbound(
     message_id: &str,
     submitted_at: &str,
     from: &str,
+    redaction: Option<&RedactionPolicy>,
 ) -> Result<(), PastEmailsError> {
     let html_body = fs::read_to_string(html_path)?;
     let mut text_body = strip_html_tags(&html_body);
@@ -243,10 +394,11 @@ pub fn archive_outbound(
     fs::create_dir_all(&incoming_email)?;
     fs::create_dir_all(&incoming_attachments)?;
 
-    let attachments = archive_attachments(attachments_dir, &incoming_attachments)?;
+    let attachments =
+        archive_attachments(attachments_dir, &incoming_attachments, &mail_dir, redaction)?;
 
     let headers = build_headers(in_reply_to, references);
-    let payload = serde_json::json!({
+    let mut payload = serde_json::json!({
         "From": from,
         "To": join_recipients(to),
         "Cc": join_recipients(cc),
@@ -260,13 +412,27 @@ pub fn archive_outbound(
         "Attachments": attachments,
         "Direction": "outbound",
     });
+    if let Some(policy) = redaction {
+        policy.store_original(
+            &mail_dir,
+            "postmark_payload.json",
+            serde_json::to_string_pretty(&payload)?.as_bytes(),
+        )?;
+        policy.redact_payload(&mut payload);
+    }
     fs::write(
         incoming_email.join("postmark_payload.json"),
         serde_json::to_string_pretty(&payload)?,
     )?;
 
-    let email_html = render_email_html(&html_body, &text_body);
+    let email_html = render_email_html(
+        payload["HtmlBody"].as_str().unwrap_or_default(),
+        payload["TextBody"].as_str().unwrap_or_default(),
+    );
     fs::write(incoming_email.join("email.html"), email_html)?;
+    if let Some(key) = ArchiveKey::for_writing(archive_root)? {
+        key.seal_dir(&mail_dir)?;
+    }
     Ok(())
 }
 
@@ -288,15 +454,27 @@ fn write_index(
     Ok(())
 }
 
-fn collect_archive_messages(root: &Path) -> Result<Vec<ArchiveMessage>, PastEmailsError> {
+/// Archived messages under `root`. Sealed messages that cannot be opened
+/// (no key, wrong key) are skipped with a warning.
+fn collect_archive_messages(
+    root: &Path,
+    key: Option<&ArchiveKey>,
+) -> Result<Vec<ArchiveMessage>, PastEmailsError> {
     let mut messages = Vec::new();
     let mut stack = vec![root.to_path_buf()];
     while let Some(dir) = stack.pop() {
         let incoming_email = dir.join("incoming_email");
         let payload_path = incoming_email.join("postmark_payload.json");
         if incoming_email.is_dir() && payload_path.is_file() {
-            let payload_data = fs::read_to_string(&payload_path)?;
-            let payload: PostmarkPayload = serde_json::from_str(&payload_data)?;
+            let payload_data = match archive_crypto::read_archived(key, &payload_path) {
+                Ok(data) => data,
+                Err(ArchiveCryptoError::Io(err)) => return Err(err.into()),
+                Err(err) => {
+                    warn!("skipping archived message {}: {}", dir.display(), err);
+                    continue;
+                }
+            };
+            let payload: PostmarkPayload = serde_json::from_slice(&payload_data)?;
             messages.push(ArchiveMessage {
                 root_dir: dir.clone(),
                 incoming_email_dir: incoming_email,
@@ -317,6 +495,7 @@ fn collect_archive_messages(root: &Path) -> Result<Vec<ArchiveMessage>, PastEmai
 }
 
 fn hydrate_attachments(
+    key: Option<&ArchiveKey>,
     payload: &PostmarkPayload,
     incoming_attachments_dir: &Path,
     dest_attachments_dir: &Path,
@@ -352,7 +531,8 @@ fn hydrate_attachments(
             let file_name = entry.file_name().to_string_lossy().to_string();
             if file_name.ends_with(".azure_url") {
                 let base = file_name.trim_end_matches(".azure_url").to_string();
-                let content = fs::read_to_string(entry.path())?;
+                let content = archive_crypto::read_archived(key, &entry.path())?;
+                let content = String::from_utf8_lossy(&content);
                 let trimmed = content.trim();
                 if !trimmed.is_empty() {
                     azure_urls.insert(base, trimmed.to_string());
@@ -375,7 +555,7 @@ fn hydrate_attachments(
             }
             let path = entry.path();
             let metadata = entry.metadata()?;
-            let size_bytes = metadata.len();
+            let mut size_bytes = metadata.len();
             let (original_name, content_type) = attachment_meta
                 .get(&file_name)
                 .cloned()
@@ -400,7 +580,7 @@ fn hydrate_attachments(
             }
 
             let dest_path = dest_attachments_dir.join(&file_name);
-            copy_file_with_fallback(&path, &dest_path)?;
+            size_bytes = copy_archived(key, &path, &dest_path)?;
             counts.total += 1;
             entries.push(AttachmentEntry {
                 file_name,
@@ -453,21 +633,41 @@ struct AttachmentCounts {
     large: usize,
 }
 
-fn copy_dir_recursive(src: &Path, dst: &Path) -> io::Result<()> {
+fn copy_dir_recursive(
+    key: Option<&ArchiveKey>,
+    src: &Path,
+    dst: &Path,
+) -> Result<(), PastEmailsError> {
     fs::create_dir_all(dst)?;
     for entry in fs::read_dir(src)? {
         let entry = entry?;
         let file_type = entry.file_type()?;
         let dest = dst.join(entry.file_name());
         if file_type.is_dir() {
-            copy_dir_recursive(&entry.path(), &dest)?;
+            copy_dir_recursive(key, &entry.path(), &dest)?;
         } else if file_type.is_file() {
-            copy_file_with_fallback(&entry.path(), &dest)?;
+            copy_archived(key, &entry.path(), &dest)?;
         }
     }
     Ok(())
 }
 
+/// Copy an archived file into the workspace, decrypting it when sealed.
+/// Returns the plaintext size.
+fn copy_archived(
+    key: Option<&ArchiveKey>,
+    src: &Path,
+    dest: &Path,
+) -> Result<u64, PastEmailsError> {
+    if archive_crypto::is_sealed_file(src)? {
+        let data = archive_crypto::read_archived(key, src)?;
+        fs::write(dest, &data)?;
+        return Ok(data.len() as u64);
+    }
+    copy_file_with_fallback(src, dest)?;
+    Ok(fs::metadata(src)?.len())
+}
+
 fn copy_file_with_fallback(src: &Path, dest: &Path) -> io::Result<()> {
     match fs::copy(src, dest) {
         Ok(_) => Ok(()),
@@ -485,9 +685,13 @@ fn copy_file_with_fallback(src: &Path, dest: &Path) -> io::Result<()> {
     }
 }
 
+/// Copy outgoing attachments into the archive. Under a redaction policy text
+/// files are redacted and withheld binaries are left out of the list.
 fn archive_attachments(
     src_dir: &Path,
     dest_dir: &Path,
+    mail_dir: &Path,
+    redaction: Option<&RedactionPolicy>,
 ) -> Result<Vec<serde_json::Value>, PastEmailsError> {
     let mut attachments = Vec::new();
     if !src_dir.is_dir() {
@@ -507,7 +711,15 @@ fn archive_attachments(
             copy_file_with_fallback(&path, &dest_dir.join(&file_name))?;
             continue;
         }
-        copy_file_with_fallback(&path, &dest_dir.join(&file_name))?;
+        match redaction {
+            Some(policy) => {
+                let data = fs::read(&path)?;
+                if !policy.archive_attachment(mail_dir, dest_dir, &file_name, &data)? {
+                    continue;
+                }
+            }
+            None => copy_file_with_fallback(&path, &dest_dir.join(&file_name))?,
+        }
         attachments.push(serde_json::json!({
             "Name": file_name,
             "ContentType": "",
@@ -867,6 +1079,7 @@ mod tests {
             "msg-123@example.com",
             "2026-02-03T20:10:44Z",
             "agent@example.com",
+            None,
         )
         .expect("archive outbound");
 
@@ -886,4 +1099,174 @@ mod tests {
             .join("note.txt")
             .exists());
     }
+
+    #[test]
+    fn archive_outbound_redacts_under_policy() {
+        let temp = TempDir::new().expect("tempdir");
+        let archive_root = temp.path().join("mail");
+        fs::create_dir_all(&archive_root).expect("archive root");
+
+        let html_path = temp.path().join("reply.html");
+        fs::write(&html_path, "<p>Reach me at 555-010-0100</p>").expect("html");
+        let attachments_dir = temp.path().join("attachments");
+        fs::create_dir_all(&attachments_dir).expect("attachments dir");
+        fs::write(attachments_dir.join("note.txt"), "ssn 123-45-6789").expect("attachment");
+        fs::write(attachments_dir.join("scan.pdf"), "%PDF").expect("attachment");
+
+        let policy = RedactionPolicy::from_config(&crate::redaction::RedactionConfig {
+            enabled: true,
+            ..Default::default()
+        })
+        .expect("policy")
+        .expect("enabled");
+        archive_outbound(
+            &archive_root,
+            "Subject",
+            &html_path,
+            &attachments_dir,
+            &[String::from("user@example.com")],
+            &[],
+            &[],
+            None,
+            None,
+            "msg-456@example.com",
+            "2026-02-03T20:10:44Z",
+            "agent@example.com",
+            Some(&policy),
+        )
+        .expect("archive outbound");
+
+        let payload_path = find_payload(&archive_root).expect("payload");
+        let payload_json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&payload_path).expect("payload read"))
+                .expect("payload json");
+        assert_eq!(
+            payload_json["HtmlBody"],
+            "<p>Reach me at [REDACTED:phone]</p>"
+        );
+        assert_eq!(payload_json["To"], "user@example.com");
+        assert_eq!(
+            payload_json["Attachments"].as_array().map(Vec::len),
+            Some(1)
+        );
+
+        let mail_dir = payload_path
+            .parent()
+            .and_then(|value| value.parent())
+            .expect("mail dir");
+        let attachments = mail_dir.join("incoming_attachments");
+        assert_eq!(
+            fs::read_to_string(attachments.join("note.txt")).expect("note"),
+            "ssn [REDACTED:ssn]"
+        );
+        assert!(!attachments.join("scan.pdf").exists());
+        assert!(!mail_dir.join(crate::redaction::ORIGINALS_DIR).exists());
+    }
+
+    #[test]
+    fn hydrate_decrypts_sealed_archive() {
+        let temp = TempDir::new().expect("tempdir");
+        let archive_root = temp.path().join("mail");
+        fs::create_dir_all(&archive_root).expect("archive root");
+        let html_path = temp.path().join("reply.html");
+        fs::write(&html_path, "<p>Hello</p>").expect("html");
+        let attachments_dir = temp.path().join("attachments");
+        fs::create_dir_all(&attachments_dir).expect("attachments dir");
+        fs::write(attachments_dir.join("note.txt"), "hello").expect("attachment");
+        archive_outbound(
+            &archive_root,
+            "Sealed",
+            &html_path,
+            &attachments_dir,
+            &[String::from("user@example.com")],
+            &[],
+            &[],
+            None,
+            None,
+            "msg-789@example.com",
+            "2026-02-03T20:10:44Z",
+            "agent@example.com",
+            None,
+        )
+        .expect("archive outbound");
+
+        let key = ArchiveKey::from_bytes([4u8; 32]);
+        assert_eq!(key.seal_dir(&archive_root).expect("seal"), 3);
+        let payload_path = find_payload(&archive_root).expect("payload");
+        assert!(archive_crypto::is_sealed_file(&payload_path).expect("sealed"));
+
+        let references = temp.path().join("references");
+        let skipped = hydrate_with_key(&archive_root, &references, "u1", None, None)
+            .expect("hydrate without key");
+        assert_eq!(skipped.entries_written, 0);
+
+        let report =
+            hydrate_with_key(&archive_root, &references, "u1", None, Some(&key)).expect("hydrate");
+        assert_eq!(report.entries_written, 1);
+        assert_eq!(report.attachments_total, 1);
+        let past_root = references.join("past_emails");
+        let entry = fs::read_dir(&past_root)
+            .expect("past emails")
+            .flatten()
+            .find(|entry| entry.path().is_dir())
+            .expect("entry dir")
+            .path();
+        assert_eq!(
+            fs::read_to_string(entry.join("incoming_email").join("email.html")).expect("html"),
+            "<p>Hello</p>"
+        );
+        assert_eq!(
+            fs::read_to_string(entry.join("incoming_attachments").join("note.txt")).expect("note"),
+            "hello"
+        );
+    }
+
+    #[test]
+    fn index_reads_sealed_archive_into_search_index() {
+        let temp = TempDir::new().expect("tempdir");
+        let archive_root = temp.path().join("mail");
+        fs::create_dir_all(&archive_root).expect("archive root");
+        let html_path = temp.path().join("reply.html");
+        fs::write(&html_path, "<p>The contract draft is ready for review.</p>").expect("html");
+        archive_outbound(
+            &archive_root,
+            "Contract draft",
+            &html_path,
+            &temp.path().join("no_attachments"),
+            &[String::from("user@example.com")],
+            &[],
+            &[],
+            None,
+            None,
+            "msg-contract@example.com",
+            "2026-02-03T20:10:44Z",
+            "agent@example.com",
+            None,
+        )
+        .expect("archive outbound");
+        let key = ArchiveKey::from_bytes([5u8; 32]);
+        key.seal_dir(&archive_root).expect("seal");
+
+        let references = temp.path().join("references");
+        assert_eq!(
+            index_with_key(&archive_root, &references, "u1", None).expect("index without key"),
+            0
+        );
+        assert_eq!(
+            index_with_key(&archive_root, &references, "u1", Some(&key)).expect("index"),
+            1
+        );
+
+        let index = SearchIndex::load(&references.join("past_emails").join(SEARCH_INDEX_FILE))
+            .expect("load index");
+        let hits = index.search(&crate::past_email_index::SearchQuery {
+            text: "contract review".to_string(),
+            limit: 5,
+            ..Default::default()
+        });
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].email.direction, "outbound");
+        assert_eq!(hits[0].email.message_id, "msg-contract@example.com");
+        assert_eq!(hits[0].snippet, "The contract draft is ready for review.");
+    }
 }
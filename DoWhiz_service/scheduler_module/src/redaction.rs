@@ -0,0 +1,466 @@
+//! Redaction of personal data in archived mail.
+//!
+//! Configured under `[employees.redaction]` in `employee.toml`. When enabled,
+//! the mail archive stores payloads and text attachments with detector matches
+//! replaced by `[REDACTED:<detector>]`. The original files are dropped, or
+//! kept encrypted under `originals/` in the archived message directory
+//! (AES-256-CBC with an HMAC-SHA256 tag, key from `ARCHIVE_ENCRYPTION_KEY`).
+//!
+//! Thread workspaces are not redacted: the agent works on the message as sent.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::archive_crypto::{self, ArchiveCryptoError};
+
+/// Hex-encoded 32-byte key for encrypted originals.
+pub const ARCHIVE_ENCRYPTION_KEY_ENV: &str = "ARCHIVE_ENCRYPTION_KEY";
+
+/// Directory, inside an archived message, holding encrypted originals.
+pub const ORIGINALS_DIR: &str = "originals";
+
+/// Postmark payload fields that carry message content. Addresses, dates and
+/// ids are left alone so threading and the past-emails index keep working.
+const PAYLOAD_CONTENT_FIELDS: &[&str] = &["Subject", "TextBody", "HtmlBody", "StrippedTextReply"];
+
+/// Attachment extensions redacted as text; anything else is binary.
+const TEXT_ATTACHMENT_EXTENSIONS: &[&str] = &[
+    "txt", "csv", "tsv", "md", "html", "htm", "json", "xml", "ics", "vcf", "eml", "log",
+];
+
+/// Built-in detectors, applied in this order. Card numbers and SSNs run
+/// before phone numbers so their digits are not half-matched as phones.
+const BUILTIN_DETECTORS: &[(&str, &str)] = &[
+    ("ssn", r"\b\d{3}-\d{2}-\d{4}\b"),
+    ("credit_card", r"\b(?:\d[ -]?){12,18}\d\b"),
+    (
+        "phone",
+        r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{3}\)|\b\d{3})[\s.-]?\d{3}[\s.-]?\d{4}\b",
+    ),
+    ("email", r"(?i)\b[A-Z0-9._%+-]+@[A-Z0-9.-]+\.[A-Z]{2,}\b"),
+    (
+        "street_address",
+        r"(?i)\b\d{1,6}\s+(?:[A-Z0-9.'-]+\s+){1,4}(?:street|st|avenue|ave|road|rd|boulevard|blvd|lane|ln|drive|dr|court|ct|way|place|pl|terrace|parkway|pkwy)\b\.?",
+    ),
+    ("ip_address", r"\b(?:\d{1,3}\.){3}\d{1,3}\b"),
+];
+
+/// Built-ins used when `detectors` is empty; `ip_address` is opt-in.
+const DEFAULT_DETECTORS: &[&str] = &["ssn", "credit_card", "phone", "email", "street_address"];
+
+/// Raw `[employees.redaction]` table.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RedactionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Built-in detectors to run; all but `ip_address` when empty.
+    #[serde(default)]
+    pub detectors: Vec<String>,
+    /// Extra regex detectors, run after the built-ins.
+    #[serde(default)]
+    pub patterns: Vec<PatternConfig>,
+    /// `drop` (default) or `encrypt`.
+    #[serde(default)]
+    pub originals: Option<String>,
+    /// Archive non-text attachments as-is instead of withholding them.
+    #[serde(default)]
+    pub keep_binary_attachments: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PatternConfig {
+    pub name: String,
+    pub regex: String,
+}
+
+/// What happens to the unredacted files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OriginalsPolicy {
+    Drop,
+    Encrypt,
+}
+
+#[derive(Debug, Clone)]
+struct Detector {
+    name: String,
+    pattern: Regex,
+    /// Only replace matches that pass a Luhn check (card numbers).
+    luhn: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RedactionPolicy {
+    detectors: Vec<Detector>,
+    pub originals: OriginalsPolicy,
+    pub keep_binary_attachments: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RedactionError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("{ARCHIVE_ENCRYPTION_KEY_ENV} is not set")]
+    MissingKey,
+    #[error(transparent)]
+    Crypto(#[from] ArchiveCryptoError),
+}
+
+impl RedactionPolicy {
+    /// `None` when redaction is disabled; an error for unknown detectors,
+    /// invalid patterns or an unknown originals policy.
+    pub fn from_config(config: &RedactionConfig) -> Result<Option<Self>, String> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let selected: Vec<String> = if config.detectors.is_empty() {
+            DEFAULT_DETECTORS
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        } else {
+            config
+                .detectors
+                .iter()
+                .map(|name| name.trim().to_ascii_lowercase())
+                .collect()
+        };
+        if let Some(unknown) = selected
+            .iter()
+            .find(|name| !BUILTIN_DETECTORS.iter().any(|(builtin, _)| builtin == name))
+        {
+            return Err(format!("unknown detector '{}'", unknown));
+        }
+
+        let mut detectors = Vec::new();
+        for (name, pattern) in BUILTIN_DETECTORS {
+            if selected.iter().any(|selected| selected == name) {
+                detectors.push(Detector {
+                    name: name.to_string(),
+                    pattern: Regex::new(pattern).expect("built-in detector compiles"),
+                    luhn: *name == "credit_card",
+                });
+            }
+        }
+        for pattern in &config.patterns {
+            let name = pattern.name.trim();
+            if name.is_empty() {
+                return Err("pattern name is required".to_string());
+            }
+            let regex =
+                Regex::new(&pattern.regex).map_err(|err| format!("pattern '{}': {}", name, err))?;
+            detectors.push(Detector {
+                name: name.to_string(),
+                pattern: regex,
+                luhn: false,
+            });
+        }
+
+        let originals = match config
+            .originals
+            .as_deref()
+            .map(|value| value.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("drop") => OriginalsPolicy::Drop,
+            Some("encrypt") => OriginalsPolicy::Encrypt,
+            Some(other) => {
+                return Err(format!(
+                    "originals must be 'drop' or 'encrypt', got '{}'",
+                    other
+                ))
+            }
+        };
+
+        Ok(Some(Self {
+            detectors,
+            originals,
+            keep_binary_attachments: config.keep_binary_attachments,
+        }))
+    }
+
+    /// Replace every detector match in `text`. Returns the redacted text and
+    /// the number of replacements.
+    pub fn redact(&self, text: &str) -> (String, usize) {
+        let mut redacted = text.to_string();
+        let mut count = 0;
+        for detector in &self.detectors {
+            let placeholder = format!("[REDACTED:{}]", detector.name);
+            redacted = detector
+                .pattern
+                .replace_all(&redacted, |caps: &regex::Captures| {
+                    let matched = &caps[0];
+                    if detector.luhn && !luhn_valid(matched) {
+                        return matched.to_string();
+                    }
+                    count += 1;
+                    placeholder.clone()
+                })
+                .into_owned();
+        }
+        (redacted, count)
+    }
+
+    /// Redact the content fields of a Postmark-shaped payload in place and
+    /// strip inline attachment content, which is archived separately.
+    pub fn redact_payload(&self, payload: &mut serde_json::Value) -> usize {
+        let mut count = 0;
+        let Some(object) = payload.as_object_mut() else {
+            return 0;
+        };
+        for field in PAYLOAD_CONTENT_FIELDS {
+            if let Some(serde_json::Value::String(value)) = object.get_mut(*field) {
+                let (redacted, matches) = self.redact(value);
+                *value = redacted;
+                count += matches;
+            }
+        }
+        if let Some(serde_json::Value::Array(attachments)) = object.get_mut("Attachments") {
+            for attachment in attachments
+                .iter_mut()
+                .filter_map(|value| value.as_object_mut())
+            {
+                if attachment.contains_key("Content") {
+                    attachment.insert("Content".to_string(), serde_json::Value::from(""));
+                }
+            }
+        }
+        count
+    }
+
+    /// Write the attachment `name` into `dest_dir`: text files redacted,
+    /// other files as-is only when `keep_binary_attachments` is set. The
+    /// original goes to [`Self::store_original`]. Returns whether a file was
+    /// written.
+    pub fn archive_attachment(
+        &self,
+        mail_dir: &Path,
+        dest_dir: &Path,
+        name: &str,
+        data: &[u8],
+    ) -> Result<bool, RedactionError> {
+        self.store_original(mail_dir, &format!("attachments/{}", name), data)?;
+        let text = is_text_attachment(name)
+            .then(|| std::str::from_utf8(data).ok())
+            .flatten();
+        match text {
+            Some(text) => {
+                fs::write(dest_dir.join(name), self.redact(text).0)?;
+                Ok(true)
+            }
+            None if self.keep_binary_attachments => {
+                fs::write(dest_dir.join(name), data)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Keep `data` as `originals/<relative>.enc` under `mail_dir` when the
+    /// policy is `encrypt`; a no-op for `drop`.
+    pub fn store_original(
+        &self,
+        mail_dir: &Path,
+        relative: &str,
+        data: &[u8],
+    ) -> Result<(), RedactionError> {
+        if self.originals == OriginalsPolicy::Drop {
+            return Ok(());
+        }
+        let key = archive_key_from_env()?;
+        let target = original_path(mail_dir, relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, archive_crypto::seal(&key, data))?;
+        Ok(())
+    }
+}
+
+/// Path of the encrypted original of `relative` inside `mail_dir`.
+pub fn original_path(mail_dir: &Path, relative: &str) -> PathBuf {
+    mail_dir
+        .join(ORIGINALS_DIR)
+        .join(format!("{}.enc", relative))
+}
+
+pub fn archive_key_from_env() -> Result<[u8; 32], RedactionError> {
+    archive_crypto::key_from_env(ARCHIVE_ENCRYPTION_KEY_ENV)?.ok_or(RedactionError::MissingKey)
+}
+
+/// Decrypt a file written by [`RedactionPolicy::store_original`].
+pub fn decrypt_original(key: &[u8; 32], blob: &[u8]) -> Result<Vec<u8>, RedactionError> {
+    Ok(archive_crypto::open(key, blob)?)
+}
+
+fn is_text_attachment(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            TEXT_ATTACHMENT_EXTENSIONS
+                .iter()
+                .any(|text| ext.eq_ignore_ascii_case(text))
+        })
+}
+
+fn luhn_valid(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|ch| ch.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, digit)| {
+            if index % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                *digit
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn policy(config: RedactionConfig) -> RedactionPolicy {
+        RedactionPolicy::from_config(&RedactionConfig {
+            enabled: true,
+            ..config
+        })
+        .expect("valid config")
+        .expect("enabled")
+    }
+
+    #[test]
+    fn disabled_config_yields_no_policy() {
+        assert!(RedactionPolicy::from_config(&RedactionConfig::default())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn default_detectors_redact_common_pii() {
+        let policy = policy(RedactionConfig::default());
+        let (text, count) = policy.redact(
+            "Call me at (555) 010-0100 or +1 555.010.0199, mail ann@acme.com. \
+             SSN 123-45-6789, card 4111 1111 1111 1111. I live at 42 Elm Street.",
+        );
+        assert_eq!(
+            text,
+            "Call me at [REDACTED:phone] or [REDACTED:phone], mail [REDACTED:email]. \
+             SSN [REDACTED:ssn], card [REDACTED:credit_card]. I live at [REDACTED:street_address]"
+        );
+        assert_eq!(count, 6);
+    }
+
+    #[test]
+    fn card_numbers_must_pass_luhn() {
+        let policy = policy(RedactionConfig {
+            detectors: vec!["credit_card".to_string()],
+            ..Default::default()
+        });
+        let (text, count) = policy.redact("order 1234 5678 9012 3456");
+        assert_eq!(text, "order 1234 5678 9012 3456");
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn custom_patterns_and_config_errors() {
+        let policy = policy(RedactionConfig {
+            detectors: vec!["email".to_string()],
+            patterns: vec![PatternConfig {
+                name: "patient_id".to_string(),
+                regex: r"\bPT-\d{6}\b".to_string(),
+            }],
+            ..Default::default()
+        });
+        assert_eq!(
+            policy.redact("PT-123456 / bob@x.io").0,
+            "[REDACTED:patient_id] / [REDACTED:email]"
+        );
+
+        for config in [
+            RedactionConfig {
+                enabled: true,
+                detectors: vec!["dna".to_string()],
+                ..Default::default()
+            },
+            RedactionConfig {
+                enabled: true,
+                patterns: vec![PatternConfig {
+                    name: "broken".to_string(),
+                    regex: "(".to_string(),
+                }],
+                ..Default::default()
+            },
+            RedactionConfig {
+                enabled: true,
+                originals: Some("keep".to_string()),
+                ..Default::default()
+            },
+        ] {
+            assert!(RedactionPolicy::from_config(&config).is_err());
+        }
+    }
+
+    #[test]
+    fn payload_redaction_keeps_addresses_and_strips_inline_content() {
+        let policy = policy(RedactionConfig::default());
+        let mut payload = serde_json::json!({
+            "From": "ann@acme.com",
+            "Subject": "My number is 555-010-0100",
+            "TextBody": "ssn 123-45-6789",
+            "Attachments": [{"Name": "id.png", "Content": "aGVsbG8="}],
+        });
+        assert_eq!(policy.redact_payload(&mut payload), 2);
+        assert_eq!(payload["From"], "ann@acme.com");
+        assert_eq!(payload["Subject"], "My number is [REDACTED:phone]");
+        assert_eq!(payload["Attachments"][0]["Content"], "");
+    }
+
+    #[test]
+    fn originals_open_only_with_their_key() {
+        let sealed = archive_crypto::seal(&[7u8; 32], b"call 555-010-0100");
+        assert_eq!(
+            decrypt_original(&[7u8; 32], &sealed).unwrap(),
+            b"call 555-010-0100".to_vec()
+        );
+        assert!(decrypt_original(&[8u8; 32], &sealed).is_err());
+    }
+
+    #[test]
+    fn binary_attachments_are_withheld_unless_kept() {
+        let temp = TempDir::new().expect("tempdir");
+        let dest = temp.path().join("incoming_attachments");
+        fs::create_dir_all(&dest).expect("dest");
+
+        let policy = policy(RedactionConfig::default());
+        assert!(policy
+            .archive_attachment(temp.path(), &dest, "notes.txt", b"ssn 123-45-6789")
+            .unwrap());
+        assert_eq!(
+            fs::read_to_string(dest.join("notes.txt")).unwrap(),
+            "ssn [REDACTED:ssn]"
+        );
+        assert!(!policy
+            .archive_attachment(temp.path(), &dest, "scan.pdf", b"%PDF")
+            .unwrap());
+        assert!(!dest.join("scan.pdf").exists());
+        assert!(!temp.path().join(ORIGINALS_DIR).exists());
+    }
+}
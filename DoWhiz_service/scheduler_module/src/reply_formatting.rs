@@ -0,0 +1,85 @@
+//! Per-employee, per-channel reply formatting.
+//!
+//! Configured under `[employees.formatting.<channel>]` in `employee.toml`.
+//! Each table is a [`ReplyFormatting`]: the prompt builder passes it on to
+//! the runner, and chat sends apply it to the reply text before it goes out.
+//! Channels without a table are left as they were.
+
+use std::collections::HashMap;
+
+use run_task_module::ReplyFormatting;
+
+use crate::channel::Channel;
+
+/// Raw `[employees.formatting]` table, keyed by channel name.
+pub type ReplyFormattingConfig = HashMap<String, ReplyFormatting>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChannelFormatting {
+    by_channel: HashMap<Channel, ReplyFormatting>,
+}
+
+impl ChannelFormatting {
+    pub fn from_config(config: &ReplyFormattingConfig) -> Result<Self, String> {
+        let mut by_channel = HashMap::new();
+        for (name, formatting) in config {
+            let channel = name.trim().parse::<Channel>()?;
+            if formatting.max_chars == Some(0) {
+                return Err(format!("{}: max_chars must be positive", name));
+            }
+            if by_channel.insert(channel, formatting.clone()).is_some() {
+                return Err(format!("{} is configured twice", channel));
+            }
+        }
+        Ok(Self { by_channel })
+    }
+
+    /// The formatting for `channel`; empty when it has none.
+    pub fn for_channel(&self, channel: Channel) -> ReplyFormatting {
+        self.by_channel.get(&channel).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml_str: &str) -> ReplyFormattingConfig {
+        toml::from_str(toml_str).unwrap()
+    }
+
+    #[test]
+    fn channels_are_parsed_and_unconfigured_ones_are_empty() {
+        let formatting = ChannelFormatting::from_config(&config(
+            r#"
+            [slack]
+            emoji = false
+            signature = "— Oliver"
+
+            [imessage]
+            max_chars = 300
+            tone = "casual"
+            "#,
+        ))
+        .unwrap();
+        let slack = formatting.for_channel(Channel::Slack);
+        assert_eq!(slack.emoji, Some(false));
+        assert_eq!(slack.signature.as_deref(), Some("— Oliver"));
+        assert_eq!(
+            formatting.for_channel(Channel::BlueBubbles).max_chars,
+            Some(300)
+        );
+        assert!(formatting.for_channel(Channel::Email).is_empty());
+    }
+
+    #[test]
+    fn bad_tables_are_rejected() {
+        let err = ChannelFormatting::from_config(&config("[pager]\nemoji = true")).unwrap_err();
+        assert!(err.contains("unknown channel"), "{}", err);
+        let err = ChannelFormatting::from_config(&config("[sms]\nmax_chars = 0")).unwrap_err();
+        assert!(err.contains("max_chars"), "{}", err);
+        let err = ChannelFormatting::from_config(&config("[bluebubbles]\n[imessage]")).unwrap_err();
+        assert!(err.contains("twice"), "{}", err);
+        assert!(toml::from_str::<ReplyFormattingConfig>("[slack]\nemojis = false").is_err());
+    }
+}
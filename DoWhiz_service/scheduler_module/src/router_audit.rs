@@ -0,0 +1,324 @@
+//! Record of the quick-response router's decisions, for finding misroutes.
+//!
+//! Every message the router classifies leaves one entry: which way it went,
+//! how long the router took and, for simple decisions, whether the quick
+//! reply was delivered. Like the agent audit log, only a hash of the input
+//! is kept. A simple decision counts as a misroute candidate when its reply
+//! could not be sent, or when the same user's next message on that channel
+//! went to the full agent within ten minutes, which usually means the quick
+//! answer did not do.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::{DateTime, Duration, Utc};
+use mongodb::bson::{doc, DateTime as BsonDateTime, Document};
+use mongodb::options::FindOneAndUpdateOptions;
+use mongodb::sync::Collection;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::audit_store::payload_hash;
+use crate::channel::Channel;
+use crate::message_router::RouterDecision;
+use crate::mongo_store::{create_client_from_env, database_from_env};
+
+pub const DEFAULT_SAMPLE_SIZE: i64 = 20;
+pub const MAX_SAMPLE_SIZE: i64 = 200;
+/// How soon a forwarded message must follow a quick reply to flag it
+const FOLLOW_UP_WINDOW_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RouterDecisionEntry {
+    pub employee_id: String,
+    pub user_id: String,
+    pub channel: String,
+    /// `sha256:<hex>` of the text the router saw.
+    pub input_hash: String,
+    /// `simple`, `complex` or `passthrough`.
+    pub decision: String,
+    pub latency_ms: i64,
+    /// Whether the quick reply was delivered; unset for forwarded messages.
+    #[serde(default)]
+    pub quick_response_sent: Option<bool>,
+    /// Set on a simple decision when the user's next message went to the
+    /// full agent soon after.
+    #[serde(default)]
+    pub followed_by_agent: bool,
+    #[serde(with = "mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Filters for [`RouterAuditStore::sample_misrouted`]; unset fields match
+/// everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MisroutedQuery {
+    pub employee_id: Option<String>,
+    pub channel: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+impl MisroutedQuery {
+    fn filter(&self) -> Document {
+        let mut filter = doc! {
+            "decision": "simple",
+            "$or": [
+                { "quick_response_sent": false },
+                { "followed_by_agent": true },
+            ],
+        };
+        for (key, value) in [
+            ("employee_id", &self.employee_id),
+            ("channel", &self.channel),
+        ] {
+            if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                filter.insert(key, value);
+            }
+        }
+        let mut window = Document::new();
+        if let Some(since) = self.since {
+            window.insert("$gte", BsonDateTime::from_chrono(since));
+        }
+        if let Some(until) = self.until {
+            window.insert("$lt", BsonDateTime::from_chrono(until));
+        }
+        if !window.is_empty() {
+            filter.insert("recorded_at", window);
+        }
+        filter
+    }
+
+    fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_SAMPLE_SIZE)
+            .clamp(1, MAX_SAMPLE_SIZE)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RouterAuditError {
+    #[error("mongodb error: {0}")]
+    Mongo(#[from] mongodb::error::Error),
+    #[error("bson error: {0}")]
+    Serialize(#[from] mongodb::bson::ser::Error),
+    #[error("bson error: {0}")]
+    Deserialize(#[from] mongodb::bson::de::Error),
+    #[error("mongo config error: {0}")]
+    MongoConfig(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct RouterAuditStore {
+    decisions: Collection<Document>,
+}
+
+impl RouterAuditStore {
+    pub fn new() -> Result<Self, RouterAuditError> {
+        let client = create_client_from_env()
+            .map_err(|err| RouterAuditError::MongoConfig(err.to_string()))?;
+        let db = database_from_env(&client);
+        let decisions = db.collection::<Document>("router_decisions");
+        Ok(Self { decisions })
+    }
+
+    /// Store `entry`. A complex decision also flags the user's latest simple
+    /// decision on the channel if it came within the follow-up window.
+    pub fn append(&self, entry: &RouterDecisionEntry) -> Result<(), RouterAuditError> {
+        if entry.decision == "complex" {
+            let window_start = entry.recorded_at - Duration::minutes(FOLLOW_UP_WINDOW_MINUTES);
+            self.decisions.find_one_and_update(
+                doc! {
+                    "employee_id": entry.employee_id.as_str(),
+                    "user_id": entry.user_id.as_str(),
+                    "channel": entry.channel.as_str(),
+                    "decision": "simple",
+                    "recorded_at": { "$gte": BsonDateTime::from_chrono(window_start) },
+                },
+                doc! { "$set": { "followed_by_agent": true } },
+                FindOneAndUpdateOptions::builder()
+                    .sort(doc! { "recorded_at": -1 })
+                    .build(),
+            )?;
+        }
+        let document = mongodb::bson::to_document(entry)?;
+        self.decisions.insert_one(document, None)?;
+        Ok(())
+    }
+
+    /// A random sample of misroute candidates matching `query`.
+    pub fn sample_misrouted(
+        &self,
+        query: &MisroutedQuery,
+    ) -> Result<Vec<RouterDecisionEntry>, RouterAuditError> {
+        let pipeline = [
+            doc! { "$match": query.filter() },
+            doc! { "$sample": { "size": query.limit() } },
+        ];
+        let cursor = self.decisions.aggregate(pipeline, None)?;
+        let mut entries = Vec::new();
+        for document in cursor {
+            entries.push(mongodb::bson::from_document(document?)?);
+        }
+        Ok(entries)
+    }
+}
+
+static ROUTER_AUDIT_STORE: std::sync::OnceLock<Option<Arc<RouterAuditStore>>> =
+    std::sync::OnceLock::new();
+
+/// Get or initialize the global RouterAuditStore (returns None if not configured)
+pub fn get_global_router_audit_store() -> Option<Arc<RouterAuditStore>> {
+    ROUTER_AUDIT_STORE
+        .get_or_init(|| match RouterAuditStore::new() {
+            Ok(store) => Some(Arc::new(store)),
+            Err(err) => {
+                warn!(
+                    "RouterAuditStore not available ({}), router decisions are logged only",
+                    err
+                );
+                None
+            }
+        })
+        .clone()
+}
+
+/// Store `entry`. Best effort: a store failure never holds up a reply.
+pub fn record(entry: RouterDecisionEntry) {
+    info!(
+        "router decision employee={} user_id={} channel={} decision={} latency_ms={} sent={:?}",
+        entry.employee_id,
+        entry.user_id,
+        entry.channel,
+        entry.decision,
+        entry.latency_ms,
+        entry.quick_response_sent
+    );
+    let Some(store) = get_global_router_audit_store() else {
+        return;
+    };
+    if let Err(err) = store.append(&entry) {
+        warn!("failed to record router decision: {}", err);
+    }
+}
+
+/// The audit entry of one quick-response message, filled in as it is routed
+/// and answered.
+pub struct RouterAudit {
+    employee_id: String,
+    user_id: String,
+    channel: Channel,
+    /// A simple decision waiting for its send outcome.
+    pending: Option<RouterDecisionEntry>,
+}
+
+impl RouterAudit {
+    pub fn new(employee_id: &str, user_id: &str, channel: Channel) -> Self {
+        Self {
+            employee_id: employee_id.to_string(),
+            user_id: user_id.to_string(),
+            channel,
+            pending: None,
+        }
+    }
+
+    /// Run `route` on `text` and audit its decision. Simple decisions are
+    /// stored once [`RouterAudit::sent`] reports the outcome.
+    pub fn time(&mut self, text: &str, route: impl FnOnce() -> RouterDecision) -> RouterDecision {
+        let started = Instant::now();
+        let decision = route();
+        let entry = RouterDecisionEntry {
+            employee_id: self.employee_id.clone(),
+            user_id: self.user_id.clone(),
+            channel: self.channel.to_string(),
+            input_hash: payload_hash(text.as_bytes()),
+            decision: decision_label(&decision).to_string(),
+            latency_ms: started.elapsed().as_millis() as i64,
+            quick_response_sent: None,
+            followed_by_agent: false,
+            recorded_at: Utc::now(),
+        };
+        match decision {
+            RouterDecision::Simple { .. } => self.pending = Some(entry),
+            _ => record(entry),
+        }
+        decision
+    }
+
+    /// Store the pending simple decision with whether its reply went out.
+    pub fn sent(&mut self, sent: bool) {
+        if let Some(mut entry) = self.pending.take() {
+            entry.quick_response_sent = Some(sent);
+            record(entry);
+        }
+    }
+}
+
+impl Drop for RouterAudit {
+    /// A simple decision whose reply was never attempted is stored as is.
+    fn drop(&mut self) {
+        if let Some(entry) = self.pending.take() {
+            record(entry);
+        }
+    }
+}
+
+fn decision_label(decision: &RouterDecision) -> &'static str {
+    match decision {
+        RouterDecision::Simple { .. } => "simple",
+        RouterDecision::Complex => "complex",
+        RouterDecision::Passthrough => "passthrough",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn misrouted_filter_keeps_the_candidate_clause_and_skips_blank_fields() {
+        let since = Utc::now();
+        let query = MisroutedQuery {
+            employee_id: Some("little_bear".to_string()),
+            channel: Some(" ".to_string()),
+            since: Some(since),
+            ..Default::default()
+        };
+        let filter = query.filter();
+        assert_eq!(filter.get_str("decision").unwrap(), "simple");
+        assert_eq!(filter.get_array("$or").unwrap().len(), 2);
+        assert_eq!(filter.get_str("employee_id").unwrap(), "little_bear");
+        assert!(!filter.contains_key("channel"));
+        let window = filter.get_document("recorded_at").unwrap();
+        assert!(window.contains_key("$gte"));
+        assert!(!window.contains_key("$lt"));
+    }
+
+    #[test]
+    fn sample_size_is_clamped() {
+        let mut query = MisroutedQuery::default();
+        assert_eq!(query.limit(), DEFAULT_SAMPLE_SIZE);
+        query.limit = Some(0);
+        assert_eq!(query.limit(), 1);
+        query.limit = Some(10_000);
+        assert_eq!(query.limit(), MAX_SAMPLE_SIZE);
+    }
+
+    #[test]
+    fn timed_simple_decisions_wait_for_the_send_outcome() {
+        let mut audit = RouterAudit::new("little_bear", "user-1", Channel::Slack);
+        let decision = audit.time("what time is it?", || RouterDecision::Simple {
+            response: "Noon.".to_string(),
+            memory_update: None,
+        });
+        assert!(matches!(decision, RouterDecision::Simple { .. }));
+        let pending = audit.pending.clone().unwrap();
+        assert_eq!(pending.decision, "simple");
+        assert_eq!(pending.channel, "slack");
+        assert_eq!(pending.input_hash, payload_hash(b"what time is it?"));
+        assert_eq!(pending.quick_response_sent, None);
+        // Not stored: the test has no store to write to.
+        audit.pending = None;
+    }
+}
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::path::{Component, Path, PathBuf};
 use std::time::Duration;
@@ -7,19 +7,33 @@ use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::account_store::{get_global_account_store, lookup_account_by_identifier};
-use crate::channel::Channel;
+use crate::adapters::jira::{JiraIssueDraft, JiraIssueUpdate};
+use crate::adapters::linear::LinearIssueDraft;
+use crate::adapters::meetings::{MeetingProvider, MeetingRequest};
+use crate::channel::{Channel, SheetEdit};
 use crate::employee_config;
 use crate::service;
 use crate::thread_state::{current_thread_epoch, default_thread_state_path};
 
-use super::core::Scheduler;
+use super::core::{escape_html, Scheduler};
+use super::delegation::delegate;
 use super::executor::TaskExecutor;
+use super::handoff::hand_off;
+use super::outbound::{
+    change_sent_message, create_jira_issue, create_linear_issue, create_meeting, update_jira_issue,
+    update_sheet,
+};
 use super::reply::load_reply_context;
-use super::schedule::{next_run_after, validate_cron_expression};
-use super::types::{RunTaskTask, Schedule, SchedulerError, SendReplyTask, TaskKind};
+use super::schedule::{
+    format_preview_run, next_run_after, preview_next_runs, recurrence_cron_expression,
+    validate_cron_expression,
+};
+use super::snapshot::{thread_tasks, SchedulerSnapshotTask};
+use super::types::{RecurrenceEnd, RunTaskTask, Schedule, SchedulerError, SendReplyTask, TaskKind};
 use super::utils::parse_datetime;
 
 const SECRET_SCAN_MAX_BYTES: u64 = 512 * 1024;
+const SCHEDULER_ACTION_RESULTS_FILENAME: &str = "scheduler_action_results.json";
 const SECRET_GUARD_MESSAGE: &str = "For security, I cannot send content that appears to contain credentials or secret tokens. Please resend the request without asking to expose secrets.";
 const SECRET_ENV_KEY_MARKERS: &[&str] = &[
     "PASSWORD",
@@ -389,18 +403,50 @@ fn apply_workspace_secret_leak_guard(
     false
 }
 
-fn thread_epoch_matches(task: &RunTaskTask) -> bool {
+fn thread_state_path(task: &RunTaskTask) -> PathBuf {
+    task.thread_state_path
+        .clone()
+        .unwrap_or_else(|| default_thread_state_path(&task.workspace_dir))
+}
+
+pub(super) fn thread_epoch_matches(task: &RunTaskTask) -> bool {
     let expected = match task.thread_epoch {
         Some(value) => value,
         None => return true,
     };
-    let state_path = task
-        .thread_state_path
-        .clone()
-        .unwrap_or_else(|| default_thread_state_path(&task.workspace_dir));
+    let state_path = thread_state_path(task);
     match current_thread_epoch(&state_path) {
-        Some(current) => current == expected,
-        None => true,
+        Ok(Some(current)) => current == expected,
+        Ok(None) => true,
+        Err(err) => {
+            warn!(
+                "failed to read thread epoch at {}: {}",
+                state_path.display(),
+                err
+            );
+            true
+        }
+    }
+}
+
+/// False when a newer message moved the reply's thread past the epoch the
+/// reply answers; such a reply is stale and is not sent.
+pub(super) fn send_reply_epoch_matches(task: &SendReplyTask) -> bool {
+    let (Some(expected), Some(state_path)) = (task.thread_epoch, task.thread_state_path.as_ref())
+    else {
+        return true;
+    };
+    match current_thread_epoch(state_path) {
+        Ok(Some(current)) => current <= expected,
+        Ok(None) => true,
+        Err(err) => {
+            warn!(
+                "failed to read thread epoch at {}: {}",
+                state_path.display(),
+                err
+            );
+            true
+        }
     }
 }
 
@@ -606,9 +652,13 @@ fn is_internal_sender(task: &RunTaskTask) -> bool {
             let allowlist = load_internal_sender_id_whitelist(task.channel);
             !allowlist.is_empty() && allowlist.contains(&sender)
         }
-        Channel::GoogleDocs | Channel::GoogleSheets | Channel::GoogleSlides | Channel::Notion => {
-            false
-        }
+        // Other employees' requests always get their result back.
+        Channel::GoogleDocs
+        | Channel::GoogleSheets
+        | Channel::GoogleSlides
+        | Channel::Notion
+        | Channel::Jira
+        | Channel::Internal => false,
     }
 }
 
@@ -910,7 +960,9 @@ pub(crate) fn schedule_auto_reply<E: TaskExecutor>(
         | Channel::WhatsApp
         | Channel::Sms
         | Channel::Notion
-        | Channel::WeChat => ("reply_message.txt", "reply_attachments"),
+        | Channel::WeChat
+        | Channel::Jira
+        | Channel::Internal => ("reply_message.txt", "reply_attachments"),
         Channel::Email | Channel::GoogleDocs | Channel::GoogleSheets | Channel::GoogleSlides => {
             ("reply_email_draft.html", "reply_email_attachments")
         }
@@ -979,7 +1031,9 @@ pub(crate) fn schedule_auto_reply<E: TaskExecutor>(
             | Channel::WhatsApp
             | Channel::Sms
             | Channel::Notion
-            | Channel::WeChat => ("cross_channel_ack.txt", "reply_attachments"),
+            | Channel::WeChat
+            | Channel::Jira
+            | Channel::Internal => ("cross_channel_ack.txt", "reply_attachments"),
             Channel::Email
             | Channel::GoogleDocs
             | Channel::GoogleSheets
@@ -1018,6 +1072,9 @@ pub(crate) fn schedule_auto_reply<E: TaskExecutor>(
                 thread_epoch: task.thread_epoch,
                 thread_state_path: task.thread_state_path.clone(),
                 employee_id: task.employee_id.clone(),
+                idempotency_key: None,
+                trace_id: None,
+                scheduled: task.scheduled,
             };
 
             let ack_task_id =
@@ -1044,6 +1101,9 @@ pub(crate) fn schedule_auto_reply<E: TaskExecutor>(
         thread_epoch: task.thread_epoch,
         thread_state_path: task.thread_state_path.clone(),
         employee_id: task.employee_id.clone(),
+        idempotency_key: None,
+        trace_id: None,
+        scheduled: task.scheduled,
     };
 
     let task_id =
@@ -1068,6 +1128,8 @@ fn format_channel_name(channel: &Channel) -> &'static str {
         Channel::WhatsApp => "WhatsApp",
         Channel::BlueBubbles => "iMessage",
         Channel::WeChat => "WeChat",
+        Channel::Jira => "Jira",
+        Channel::Internal => "Internal",
         Channel::GoogleDocs => "Google Docs",
         Channel::GoogleSheets => "Google Sheets",
         Channel::GoogleSlides => "Google Slides",
@@ -1168,12 +1230,16 @@ pub(crate) fn schedule_send_email<E: TaskExecutor>(
         thread_epoch: task.thread_epoch,
         thread_state_path: task.thread_state_path.clone(),
         employee_id: task.employee_id.clone(),
+        idempotency_key: None,
+        trace_id: None,
+        scheduled: true,
     };
 
     if let Some(run_at_raw) = request.run_at.as_deref() {
         match parse_datetime(run_at_raw) {
             Ok(run_at) => {
                 let task_id = scheduler.add_one_shot_at(run_at, TaskKind::SendReply(send_task))?;
+                hold_if_requested(scheduler, task_id, request.approval.as_ref())?;
                 info!(
                     "scheduled follow-up send_email task {} from {} run_at={} via {:?}",
                     task_id,
@@ -1215,6 +1281,7 @@ pub(crate) fn schedule_send_email<E: TaskExecutor>(
         Duration::from_secs(delay_seconds),
         TaskKind::SendReply(send_task),
     )?;
+    hold_if_requested(scheduler, task_id, request.approval.as_ref())?;
     info!(
         "scheduled follow-up send_email task {} from {} delay_seconds={}",
         task_id,
@@ -1236,10 +1303,24 @@ pub(crate) fn apply_scheduler_actions<E: TaskExecutor>(
     let mut canceled = 0usize;
     let mut rescheduled = 0usize;
     let mut created = 0usize;
+    let mut handed_off = 0usize;
+    let mut delegated = 0usize;
+    let mut messages_changed = 0usize;
+    let mut sheets_updated = 0usize;
+    let mut jira_issues_changed = 0usize;
+    let mut linear_issues_created = 0usize;
+    let mut meetings_created = 0usize;
+    let mut previewed = 0usize;
     let mut skipped = 0usize;
+    let mut results = Vec::with_capacity(actions.len());
+    let mut list_requested = false;
 
     for action in actions {
         match action {
+            run_task_module::SchedulerActionRequest::ListTasks => {
+                list_requested = true;
+                results.push(ActionResult::applied("list_tasks", Vec::new(), None));
+            }
             run_task_module::SchedulerActionRequest::Cancel { task_ids } => {
                 let (ids, invalid) = parse_action_task_ids(task_ids);
                 if !invalid.is_empty() {
@@ -1247,34 +1328,88 @@ pub(crate) fn apply_scheduler_actions<E: TaskExecutor>(
                 }
                 if ids.is_empty() {
                     skipped += 1;
+                    results.push(ActionResult::skipped(
+                        "cancel",
+                        task_ids.clone(),
+                        "no valid task ids",
+                    ));
                     continue;
                 }
-                canceled += scheduler.disable_tasks_by(|task| ids.contains(&task.id))?;
+                let mut canceled_ids = HashSet::new();
+                // Heartbeats are service plumbing, not the agent's to cancel.
+                scheduler.disable_tasks_by(|task| {
+                    let matched = ids.contains(&task.id) && task.heartbeat_deadline().is_none();
+                    if matched {
+                        canceled_ids.insert(task.id);
+                    }
+                    matched
+                })?;
+                canceled += canceled_ids.len();
+                let mut not_found: Vec<String> = ids
+                    .difference(&canceled_ids)
+                    .map(|id| id.to_string())
+                    .chain(invalid)
+                    .collect();
+                not_found.sort();
+                let canceled_ids: Vec<String> =
+                    canceled_ids.iter().map(|id| id.to_string()).collect();
+                let detail = (!not_found.is_empty())
+                    .then(|| format!("no active task with id {}", not_found.join(", ")));
+                if canceled_ids.is_empty() {
+                    skipped += 1;
+                    results.push(ActionResult::skipped(
+                        "cancel",
+                        task_ids.clone(),
+                        detail.unwrap_or_default(),
+                    ));
+                } else {
+                    results.push(ActionResult::applied("cancel", canceled_ids, detail));
+                }
             }
             run_task_module::SchedulerActionRequest::Reschedule { task_id, schedule } => {
+                let raw_task_id = task_id;
                 let task_id = match Uuid::parse_str(task_id) {
                     Ok(id) => id,
                     Err(_) => {
                         warn!("scheduler actions invalid task id: {}", task_id);
                         skipped += 1;
+                        results.push(ActionResult::skipped(
+                            "reschedule",
+                            vec![raw_task_id.clone()],
+                            "invalid task id",
+                        ));
                         continue;
                     }
                 };
-                let target = scheduler.tasks.iter_mut().find(|task| task.id == task_id);
+                let target = scheduler
+                    .tasks
+                    .iter_mut()
+                    .find(|task| task.id == task_id && task.heartbeat_deadline().is_none());
                 let target = match target {
                     Some(target) => target,
                     None => {
                         warn!("scheduler actions task not found: {}", task_id);
                         skipped += 1;
+                        results.push(ActionResult::skipped(
+                            "reschedule",
+                            vec![raw_task_id.clone()],
+                            "task not found",
+                        ));
                         continue;
                     }
                 };
                 match resolve_schedule_request(schedule, now) {
                     Ok(new_schedule) => {
                         target.schedule = new_schedule;
-                        target.enabled = true;
+                        // A held task stays held until its approver decides.
+                        target.enabled = !target.awaiting_approval();
                         scheduler.store.update_task(target)?;
                         rescheduled += 1;
+                        results.push(ActionResult::applied(
+                            "reschedule",
+                            vec![task_id.to_string()],
+                            Some(format!("next run {}", next_run_label(&target.schedule))),
+                        ));
                     }
                     Err(err) => {
                         warn!(
@@ -1282,6 +1417,11 @@ pub(crate) fn apply_scheduler_actions<E: TaskExecutor>(
                             task_id, err
                         );
                         skipped += 1;
+                        results.push(ActionResult::skipped(
+                            "reschedule",
+                            vec![task_id.to_string()],
+                            err.to_string(),
+                        ));
                     }
                 }
             }
@@ -1290,19 +1430,38 @@ pub(crate) fn apply_scheduler_actions<E: TaskExecutor>(
                 model_name,
                 codex_disabled,
                 reply_to,
+                approval,
+                description,
+                until,
+                count,
             } => {
-                let schedule = match resolve_schedule_request(schedule, now) {
-                    Ok(schedule) => schedule,
+                let resolved = resolve_schedule_request(schedule, now).and_then(|schedule| {
+                    let ends = resolve_recurrence_end(&schedule, until.as_deref(), *count)?;
+                    Ok((schedule, ends))
+                });
+                let (schedule, ends) = match resolved {
+                    Ok(resolved) => resolved,
                     Err(err) => {
                         warn!(
                             "scheduler actions invalid create_run_task schedule: {}",
                             err
                         );
                         skipped += 1;
+                        results.push(ActionResult::skipped(
+                            "create_run_task",
+                            Vec::new(),
+                            err.to_string(),
+                        ));
                         continue;
                     }
                 };
+                let description = description
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string);
                 let mut new_task = task.clone();
+                new_task.scheduled = true;
                 if let Some(model_name) =
                     model_name.as_ref().filter(|value| !value.trim().is_empty())
                 {
@@ -1314,31 +1473,548 @@ pub(crate) fn apply_scheduler_actions<E: TaskExecutor>(
                 if !reply_to.is_empty() {
                     new_task.reply_to = reply_to.clone();
                 }
-                match schedule {
+                let new_task_id = match schedule {
                     Schedule::Cron { expression, .. } => {
-                        scheduler.add_cron_task(&expression, TaskKind::RunTask(new_task))?;
-                        created += 1;
+                        scheduler.add_cron_task(&expression, TaskKind::RunTask(new_task))?
                     }
                     Schedule::OneShot { run_at } => {
-                        scheduler.add_one_shot_at(run_at, TaskKind::RunTask(new_task))?;
-                        created += 1;
+                        scheduler.add_one_shot_at(run_at, TaskKind::RunTask(new_task))?
+                    }
+                };
+                if description.is_some() || ends.is_some() {
+                    scheduler.set_task_details(new_task_id, description, ends)?;
+                }
+                hold_if_requested(scheduler, new_task_id, approval.as_ref())?;
+                created += 1;
+                results.push(ActionResult::applied(
+                    "create_run_task",
+                    vec![new_task_id.to_string()],
+                    approval.is_some().then(|| "held for approval".to_string()),
+                ));
+            }
+            run_task_module::SchedulerActionRequest::Handoff {
+                employee_id,
+                summary,
+            } => match hand_off(scheduler, task, employee_id, summary, now) {
+                Ok(envelope_id) => {
+                    handed_off += 1;
+                    results.push(ActionResult::applied(
+                        "handoff",
+                        Vec::new(),
+                        Some(format!(
+                            "handed to {} (envelope {})",
+                            employee_id, envelope_id
+                        )),
+                    ));
+                }
+                Err(reason) => {
+                    warn!(
+                        "scheduler actions handoff to {} skipped: {}",
+                        employee_id, reason
+                    );
+                    skipped += 1;
+                    results.push(ActionResult::skipped("handoff", Vec::new(), reason));
+                }
+            },
+            run_task_module::SchedulerActionRequest::Delegate {
+                employee_id,
+                request,
+            } => match delegate(scheduler, task, employee_id, request, now) {
+                Ok(correlation_id) => {
+                    delegated += 1;
+                    results.push(ActionResult::applied(
+                        "delegate",
+                        Vec::new(),
+                        Some(format!(
+                            "asked {} (correlation {}); this thread resumes with the answer",
+                            employee_id, correlation_id
+                        )),
+                    ));
+                }
+                Err(reason) => {
+                    warn!(
+                        "scheduler actions delegate to {} skipped: {}",
+                        employee_id, reason
+                    );
+                    skipped += 1;
+                    results.push(ActionResult::skipped("delegate", Vec::new(), reason));
+                }
+            },
+            run_task_module::SchedulerActionRequest::EditMessage { message_id, text } => {
+                let state_path = thread_state_path(task);
+                match change_sent_message(
+                    &state_path,
+                    task.employee_id.as_deref(),
+                    message_id,
+                    Some(text),
+                ) {
+                    Ok(()) => {
+                        messages_changed += 1;
+                        results.push(ActionResult::applied(
+                            "edit_message",
+                            Vec::new(),
+                            Some(format!("edited message {}", message_id)),
+                        ));
+                    }
+                    Err(reason) => {
+                        warn!(
+                            "scheduler actions edit_message {} skipped: {}",
+                            message_id, reason
+                        );
+                        skipped += 1;
+                        results.push(ActionResult::skipped("edit_message", Vec::new(), reason));
+                    }
+                }
+            }
+            run_task_module::SchedulerActionRequest::DeleteMessage { message_id } => {
+                let state_path = thread_state_path(task);
+                match change_sent_message(
+                    &state_path,
+                    task.employee_id.as_deref(),
+                    message_id,
+                    None,
+                ) {
+                    Ok(()) => {
+                        messages_changed += 1;
+                        results.push(ActionResult::applied(
+                            "delete_message",
+                            Vec::new(),
+                            Some(format!("deleted message {}", message_id)),
+                        ));
+                    }
+                    Err(reason) => {
+                        warn!(
+                            "scheduler actions delete_message {} skipped: {}",
+                            message_id, reason
+                        );
+                        skipped += 1;
+                        results.push(ActionResult::skipped("delete_message", Vec::new(), reason));
+                    }
+                }
+            }
+            run_task_module::SchedulerActionRequest::UpdateSheet {
+                spreadsheet_id,
+                edits,
+            } => {
+                let edits: Vec<SheetEdit> = edits.iter().cloned().map(sheet_edit).collect();
+                let count = edits.len();
+                match update_sheet(task.employee_id.as_deref(), spreadsheet_id, edits) {
+                    Ok(()) => {
+                        sheets_updated += 1;
+                        results.push(ActionResult::applied(
+                            "update_sheet",
+                            Vec::new(),
+                            Some(format!("applied {} edits to {}", count, spreadsheet_id)),
+                        ));
+                    }
+                    Err(reason) => {
+                        warn!(
+                            "scheduler actions update_sheet {} skipped: {}",
+                            spreadsheet_id, reason
+                        );
+                        skipped += 1;
+                        results.push(ActionResult::skipped("update_sheet", Vec::new(), reason));
+                    }
+                }
+            }
+            run_task_module::SchedulerActionRequest::CreateJiraIssue {
+                project,
+                summary,
+                description,
+                issue_type,
+                labels,
+            } => {
+                let draft = JiraIssueDraft {
+                    project: project.clone(),
+                    summary: summary.clone(),
+                    description: description.clone(),
+                    issue_type: issue_type.clone(),
+                    labels: labels.clone(),
+                };
+                match create_jira_issue(task.employee_id.as_deref(), &draft) {
+                    Ok((key, url)) => {
+                        jira_issues_changed += 1;
+                        results.push(ActionResult::applied(
+                            "create_jira_issue",
+                            Vec::new(),
+                            Some(format!("created {} ({})", key, url)),
+                        ));
+                    }
+                    Err(reason) => {
+                        warn!(
+                            "scheduler actions create_jira_issue in {} skipped: {}",
+                            project, reason
+                        );
+                        skipped += 1;
+                        results.push(ActionResult::skipped(
+                            "create_jira_issue",
+                            Vec::new(),
+                            reason,
+                        ));
+                    }
+                }
+            }
+            run_task_module::SchedulerActionRequest::UpdateJiraIssue {
+                issue_key,
+                summary,
+                description,
+                labels,
+                transition,
+                comment,
+            } => {
+                let update = JiraIssueUpdate {
+                    summary: summary.clone(),
+                    description: description.clone(),
+                    labels: labels.clone(),
+                    transition: transition.clone(),
+                    comment: comment.clone(),
+                };
+                match update_jira_issue(task.employee_id.as_deref(), issue_key, &update) {
+                    Ok(()) => {
+                        jira_issues_changed += 1;
+                        results.push(ActionResult::applied(
+                            "update_jira_issue",
+                            Vec::new(),
+                            Some(format!("updated {}", issue_key)),
+                        ));
+                    }
+                    Err(reason) => {
+                        warn!(
+                            "scheduler actions update_jira_issue {} skipped: {}",
+                            issue_key, reason
+                        );
+                        skipped += 1;
+                        results.push(ActionResult::skipped(
+                            "update_jira_issue",
+                            Vec::new(),
+                            reason,
+                        ));
+                    }
+                }
+            }
+            run_task_module::SchedulerActionRequest::CreateLinearIssue {
+                team,
+                title,
+                description,
+                labels,
+                priority,
+            } => {
+                let draft = LinearIssueDraft {
+                    team: team.clone(),
+                    title: title.clone(),
+                    description: description.clone(),
+                    labels: labels.clone(),
+                    priority: *priority,
+                };
+                match create_linear_issue(task.employee_id.as_deref(), &draft) {
+                    Ok(issue) => {
+                        linear_issues_created += 1;
+                        add_link_to_reply(&task.workspace_dir, &issue.identifier, &issue.url);
+                        let mut detail = format!("created {} ({})", issue.identifier, issue.url);
+                        if !issue.missing_labels.is_empty() {
+                            detail.push_str(&format!(
+                                "; labels not found: {}",
+                                issue.missing_labels.join(", ")
+                            ));
+                        }
+                        results.push(ActionResult::applied(
+                            "create_linear_issue",
+                            Vec::new(),
+                            Some(detail),
+                        ));
+                    }
+                    Err(reason) => {
+                        warn!(
+                            "scheduler actions create_linear_issue in {} skipped: {}",
+                            team, reason
+                        );
+                        skipped += 1;
+                        results.push(ActionResult::skipped(
+                            "create_linear_issue",
+                            Vec::new(),
+                            reason,
+                        ));
+                    }
+                }
+            }
+            run_task_module::SchedulerActionRequest::CreateMeeting {
+                title,
+                start,
+                duration_minutes,
+                attendees,
+                description,
+                provider,
+            } => {
+                let start = match parse_datetime(start) {
+                    Ok(start) if start < now => Err("start is in the past".to_string()),
+                    Ok(start) => Ok(start),
+                    Err(err) => Err(format!("invalid start {}: {}", start, err)),
+                };
+                let provider = match provider {
+                    run_task_module::MeetingProvider::GoogleMeet => MeetingProvider::GoogleMeet,
+                    run_task_module::MeetingProvider::Zoom => MeetingProvider::Zoom,
+                };
+                let outcome = start.and_then(|start| {
+                    let request = MeetingRequest {
+                        title: title.clone(),
+                        start,
+                        duration_minutes: *duration_minutes,
+                        attendees: attendees.clone(),
+                        description: description.clone(),
+                    };
+                    create_meeting(task.employee_id.as_deref(), provider, &request)
+                });
+                match outcome {
+                    Ok(meeting) => {
+                        meetings_created += 1;
+                        add_link_to_reply(&task.workspace_dir, title, &meeting.join_url);
+                        let mut detail = format!("{} meeting {}", provider, meeting.join_url);
+                        if let Some(event_url) = &meeting.event_url {
+                            detail.push_str(&format!("; calendar event {}", event_url));
+                        }
+                        results.push(ActionResult::applied(
+                            "create_meeting",
+                            Vec::new(),
+                            Some(detail),
+                        ));
+                    }
+                    Err(reason) => {
+                        warn!(
+                            "scheduler actions create_meeting {:?} skipped: {}",
+                            title, reason
+                        );
+                        skipped += 1;
+                        results.push(ActionResult::skipped("create_meeting", Vec::new(), reason));
+                    }
+                }
+            }
+            run_task_module::SchedulerActionRequest::PreviewSchedule {
+                expression,
+                timezone,
+                count,
+                add_to_reply,
+            } => {
+                let timezone = timezone.as_deref().unwrap_or("UTC");
+                match preview_next_runs(expression, timezone, *count) {
+                    Ok(runs) => {
+                        previewed += 1;
+                        let labels: Vec<String> = runs.iter().map(format_preview_run).collect();
+                        if let (true, Some(next)) = (*add_to_reply, labels.first()) {
+                            add_note_to_reply(&task.workspace_dir, &format!("Next run: {}", next));
+                        }
+                        results.push(ActionResult::applied(
+                            "preview_schedule",
+                            Vec::new(),
+                            Some(format!(
+                                "next runs of {}: {}",
+                                expression,
+                                labels.join("; ")
+                            )),
+                        ));
+                    }
+                    Err(err) => {
+                        warn!(
+                            "scheduler actions preview_schedule {:?} skipped: {}",
+                            expression, err
+                        );
+                        skipped += 1;
+                        results.push(ActionResult::skipped(
+                            "preview_schedule",
+                            Vec::new(),
+                            err.to_string(),
+                        ));
                     }
                 }
             }
         }
     }
 
+    let thread_tasks =
+        list_requested.then(|| thread_tasks(&scheduler.tasks, &task.workspace_dir, now));
+    if let Err(err) = write_action_results(&task.workspace_dir, now, results, thread_tasks) {
+        warn!(
+            "failed to write scheduler action results for {}: {}",
+            task.workspace_dir.display(),
+            err
+        );
+    }
     info!(
-        "scheduler actions applied workspace={} canceled={} rescheduled={} created={} skipped={}",
+        "scheduler actions applied workspace={} canceled={} rescheduled={} created={} handed_off={} delegated={} messages_changed={} sheets_updated={} jira_issues_changed={} linear_issues_created={} meetings_created={} previewed={} skipped={}",
         task.workspace_dir.display(),
         canceled,
         rescheduled,
         created,
+        handed_off,
+        delegated,
+        messages_changed,
+        sheets_updated,
+        jira_issues_changed,
+        linear_issues_created,
+        meetings_created,
+        previewed,
         skipped
     );
     Ok(())
 }
 
+fn sheet_edit(request: run_task_module::SheetEditRequest) -> SheetEdit {
+    match request {
+        run_task_module::SheetEditRequest::Append { range, values } => {
+            SheetEdit::Append { range, values }
+        }
+        run_task_module::SheetEditRequest::Update { range, values } => {
+            SheetEdit::Update { range, values }
+        }
+    }
+}
+
+/// Add `label: url` to the end of the drafted reply, which is sent after the
+/// actions are applied, so a link that only exists once an action ran still
+/// reaches the user.
+fn add_link_to_reply(workspace_dir: &Path, label: &str, url: &str) {
+    let paragraph = format!(
+        "<p>{}: <a href=\"{}\">{}</a></p>",
+        escape_html(label),
+        escape_html(url),
+        escape_html(url)
+    );
+    append_to_reply(
+        workspace_dir,
+        url,
+        &format!("{}: {}", label, url),
+        &paragraph,
+    );
+}
+
+/// Add a line of plain text, such as a schedule preview, to the end of the
+/// drafted reply.
+fn add_note_to_reply(workspace_dir: &Path, note: &str) {
+    let paragraph = format!("<p>{}</p>", escape_html(note));
+    append_to_reply(workspace_dir, note, note, &paragraph);
+}
+
+/// Append `line` to the text reply and `paragraph` to the HTML draft, each
+/// unless it already contains `marker`.
+fn append_to_reply(workspace_dir: &Path, marker: &str, line: &str, paragraph: &str) {
+    let text_path = workspace_dir.join("reply_message.txt");
+    if let Ok(mut text) = std::fs::read_to_string(&text_path) {
+        if !text.contains(marker) {
+            text = format!("{}\n\n{}\n", text.trim_end(), line);
+            if let Err(err) = std::fs::write(&text_path, text) {
+                warn!(
+                    "failed to add {} to {}: {}",
+                    marker,
+                    text_path.display(),
+                    err
+                );
+            }
+        }
+    }
+    let html_path = workspace_dir.join("reply_email_draft.html");
+    if let Ok(mut html) = std::fs::read_to_string(&html_path) {
+        if !html.contains(&escape_html(marker)) {
+            match html.rfind("</body>") {
+                Some(index) => html.insert_str(index, paragraph),
+                None => html.push_str(paragraph),
+            }
+            if let Err(err) = std::fs::write(&html_path, html) {
+                warn!(
+                    "failed to add {} to {}: {}",
+                    marker,
+                    html_path.display(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// Outcome of one scheduler action. Actions run after the reply is drafted,
+/// so the outcomes are written back for the thread's next run.
+#[derive(Debug, Serialize)]
+struct ActionResult {
+    action: &'static str,
+    /// "applied" or "skipped".
+    status: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    task_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl ActionResult {
+    fn applied(action: &'static str, task_ids: Vec<String>, detail: Option<String>) -> Self {
+        Self {
+            action,
+            status: "applied",
+            task_ids,
+            detail,
+        }
+    }
+
+    fn skipped(action: &'static str, task_ids: Vec<String>, detail: impl Into<String>) -> Self {
+        Self {
+            action,
+            status: "skipped",
+            task_ids,
+            detail: Some(detail.into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ActionResults {
+    generated_at: DateTime<Utc>,
+    results: Vec<ActionResult>,
+    /// The thread's tasks after the actions, when `list_tasks` was asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_tasks: Option<Vec<SchedulerSnapshotTask>>,
+}
+
+fn write_action_results(
+    workspace_dir: &Path,
+    now: DateTime<Utc>,
+    results: Vec<ActionResult>,
+    thread_tasks: Option<Vec<SchedulerSnapshotTask>>,
+) -> Result<(), SchedulerError> {
+    let payload = serde_json::to_string_pretty(&ActionResults {
+        generated_at: now,
+        results,
+        thread_tasks,
+    })
+    .map_err(|err| SchedulerError::Storage(format!("action results json error: {}", err)))?;
+    std::fs::write(
+        workspace_dir.join(SCHEDULER_ACTION_RESULTS_FILENAME),
+        payload,
+    )?;
+    Ok(())
+}
+
+fn next_run_label(schedule: &Schedule) -> String {
+    match schedule {
+        Schedule::Cron { next_run, .. } => next_run.to_rfc3339(),
+        Schedule::OneShot { run_at } => run_at.to_rfc3339(),
+    }
+}
+
+/// Hold the new task `task_id` for approval when the agent asked for one.
+fn hold_if_requested<E: TaskExecutor>(
+    scheduler: &mut Scheduler<E>,
+    task_id: Uuid,
+    approval: Option<&run_task_module::ApprovalRequest>,
+) -> Result<(), SchedulerError> {
+    let Some(approval) = approval else {
+        return Ok(());
+    };
+    let summary = match approval.summary.trim() {
+        "" => "(no summary given)",
+        summary => summary,
+    };
+    scheduler.hold_for_approval(task_id, summary)?;
+    info!("task {} held for approval: {}", task_id, summary);
+    Ok(())
+}
+
 fn parse_action_task_ids(task_ids: &[String]) -> (HashSet<Uuid>, Vec<String>) {
     let mut ids = HashSet::new();
     let mut invalid = Vec::new();
@@ -1375,7 +2051,63 @@ pub(crate) fn resolve_schedule_request(
             }
             Ok(Schedule::OneShot { run_at })
         }
+        run_task_module::ScheduleRequest::Recurring {
+            frequency,
+            interval,
+            by_weekday,
+            by_month_day,
+            at,
+        } => {
+            let expression = recurrence_cron_expression(
+                *frequency,
+                *interval,
+                by_weekday,
+                by_month_day,
+                at.as_deref(),
+                now,
+            )?;
+            let next_run = next_run_after(&expression, now)?;
+            Ok(Schedule::Cron {
+                expression,
+                next_run,
+            })
+        }
+    }
+}
+
+/// End condition for a new recurring task. `until` and `count` only apply to
+/// cron schedules, and must leave at least the first run.
+fn resolve_recurrence_end(
+    schedule: &Schedule,
+    until: Option<&str>,
+    count: Option<u32>,
+) -> Result<Option<RecurrenceEnd>, SchedulerError> {
+    let until = until.map(str::trim).filter(|value| !value.is_empty());
+    if until.is_none() && count.is_none() {
+        return Ok(None);
+    }
+    let Schedule::Cron { next_run, .. } = schedule else {
+        return Err(SchedulerError::InvalidRecurrence(
+            "until and count only apply to recurring schedules".to_string(),
+        ));
+    };
+    if count == Some(0) {
+        return Err(SchedulerError::InvalidRecurrence(
+            "count must be at least 1".to_string(),
+        ));
     }
+    let until = until.map(parse_datetime).transpose()?;
+    if let Some(until) = until.filter(|until| until < next_run) {
+        return Err(SchedulerError::InvalidRecurrence(format!(
+            "until {} is before the first run at {}",
+            until.to_rfc3339(),
+            next_run.to_rfc3339()
+        )));
+    }
+    Ok(Some(RecurrenceEnd {
+        until,
+        remaining_runs: count,
+    }))
 }
 
 fn resolve_rel_path(root: &Path, raw: &str) -> Option<PathBuf> {
@@ -1499,7 +2231,9 @@ mod tests {
             | Channel::WhatsApp
             | Channel::Sms
             | Channel::Notion
-            | Channel::WeChat => ("cross_channel_ack.txt", "reply_attachments"),
+            | Channel::WeChat
+            | Channel::Jira
+            | Channel::Internal => ("cross_channel_ack.txt", "reply_attachments"),
             Channel::Email
             | Channel::GoogleDocs
             | Channel::GoogleSheets
@@ -1520,7 +2254,9 @@ mod tests {
             | Channel::WhatsApp
             | Channel::Sms
             | Channel::Notion
-            | Channel::WeChat => ("cross_channel_ack.txt", "reply_attachments"),
+            | Channel::WeChat
+            | Channel::Jira
+            | Channel::Internal => ("cross_channel_ack.txt", "reply_attachments"),
             Channel::Email
             | Channel::GoogleDocs
             | Channel::GoogleSheets
@@ -1686,6 +2422,8 @@ addresses = ["proto@dowhiz.com", "boiled-egg@dowhiz.com"]
             requester_identifier_type: None,
             requester_identifier: None,
             account_id: None,
+            trace_id: None,
+            scheduled: false,
         }
     }
 
@@ -1885,7 +2623,9 @@ addresses = ["proto@dowhiz.com", "boiled-egg@dowhiz.com"]
             | Channel::Telegram
             | Channel::WhatsApp
             | Channel::Sms
-            | Channel::WeChat => ("cross_channel_ack.txt", "reply_attachments"),
+            | Channel::WeChat
+            | Channel::Jira
+            | Channel::Internal => ("cross_channel_ack.txt", "reply_attachments"),
             Channel::Email
             | Channel::GoogleDocs
             | Channel::GoogleSheets
@@ -1946,7 +2686,9 @@ addresses = ["proto@dowhiz.com", "boiled-egg@dowhiz.com"]
             | Channel::Telegram
             | Channel::WhatsApp
             | Channel::Sms
-            | Channel::WeChat => ("reply_message.txt", "reply_attachments"),
+            | Channel::WeChat
+            | Channel::Jira
+            | Channel::Internal => ("reply_message.txt", "reply_attachments"),
             Channel::Email
             | Channel::GoogleDocs
             | Channel::GoogleSheets
@@ -2205,4 +2947,113 @@ addresses = ["dowhiz@deep-tutor.com"]
         ));
         std::env::remove_var("INTERNAL_SLACK_SENDER_IDS");
     }
+
+    #[test]
+    fn recurring_task_ends_after_its_count_or_until() {
+        use super::super::types::{NoopTask, ScheduledTask};
+        use chrono::Timelike;
+
+        let now = Utc::now();
+        let request = run_task_module::ScheduleRequest::Recurring {
+            frequency: run_task_module::RecurrenceFrequency::Daily,
+            interval: 1,
+            by_weekday: Vec::new(),
+            by_month_day: Vec::new(),
+            at: Some("09:00".to_string()),
+        };
+        let schedule = resolve_schedule_request(&request, now).unwrap();
+        let Schedule::Cron { next_run, .. } = schedule.clone() else {
+            panic!("recurring schedules resolve to cron");
+        };
+        assert_eq!((next_run.hour(), next_run.minute()), (9, 0));
+
+        let before_first = (next_run - chrono::Duration::hours(1)).to_rfc3339();
+        assert!(resolve_recurrence_end(&schedule, Some(&before_first), None).is_err());
+        assert!(resolve_recurrence_end(&schedule, None, Some(0)).is_err());
+        let one_shot = Schedule::OneShot { run_at: now };
+        assert!(resolve_recurrence_end(&one_shot, None, Some(2)).is_err());
+        assert_eq!(
+            resolve_recurrence_end(&one_shot, Some(" "), None).unwrap(),
+            None
+        );
+
+        let ends = resolve_recurrence_end(&schedule, None, Some(2)).unwrap();
+        let mut task = ScheduledTask {
+            id: Uuid::new_v4(),
+            kind: TaskKind::Noop(NoopTask::default()),
+            schedule: schedule.clone(),
+            enabled: true,
+            created_at: now,
+            last_run: None,
+            approval: None,
+            description: Some("Daily standup notes".to_string()),
+            ends,
+        };
+        assert!(!task.count_recurring_run());
+        assert!(task.enabled);
+        assert!(task.count_recurring_run());
+        assert!(!task.enabled);
+
+        task.enabled = true;
+        let until = (next_run + chrono::Duration::hours(3)).to_rfc3339();
+        task.ends = resolve_recurrence_end(&schedule, Some(&until), None).unwrap();
+        if let Schedule::Cron { next_run, .. } = &mut task.schedule {
+            *next_run += chrono::Duration::days(1);
+        }
+        assert!(task.count_recurring_run());
+        assert!(!task.enabled);
+    }
+
+    #[test]
+    fn add_link_to_reply_appends_once_to_each_draft() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspace = temp.path();
+        std::fs::write(workspace.join("reply_message.txt"), "Filed the bug.\n").unwrap();
+        std::fs::write(
+            workspace.join("reply_email_draft.html"),
+            "<html><body><p>Filed the bug.</p></body></html>",
+        )
+        .unwrap();
+        let url = "https://linear.app/acme/issue/ENG-123/login-fails";
+
+        add_link_to_reply(workspace, "ENG-123", url);
+        add_link_to_reply(workspace, "ENG-123", url);
+
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("reply_message.txt")).unwrap(),
+            format!("Filed the bug.\n\nENG-123: {}\n", url)
+        );
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("reply_email_draft.html")).unwrap(),
+            format!(
+                "<html><body><p>Filed the bug.</p><p>ENG-123: <a href=\"{0}\">{0}</a></p></body></html>",
+                url
+            )
+        );
+    }
+
+    #[test]
+    fn add_note_to_reply_escapes_html_and_appends_once() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspace = temp.path();
+        std::fs::write(workspace.join("reply_message.txt"), "Scheduled.\n").unwrap();
+        std::fs::write(
+            workspace.join("reply_email_draft.html"),
+            "<html><body><p>Scheduled.</p></body></html>",
+        )
+        .unwrap();
+        let note = "Next run: Tue Mar 10, 9:00 AM PDT <weekly>";
+
+        add_note_to_reply(workspace, note);
+        add_note_to_reply(workspace, note);
+
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("reply_message.txt")).unwrap(),
+            format!("Scheduled.\n\n{}\n", note)
+        );
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("reply_email_draft.html")).unwrap(),
+            "<html><body><p>Scheduled.</p><p>Next run: Tue Mar 10, 9:00 AM PDT &lt;weekly&gt;</p></body></html>"
+        );
+    }
 }
@@ -0,0 +1,259 @@
+use chrono::Utc;
+use std::path::Path;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::approval_store::{
+    get_global_approval_store, ApprovalRecord, ApprovalStatus, Approver, APPROVAL_TTL_HOURS,
+};
+use crate::channel::Channel;
+
+use super::core::{escape_html, Scheduler};
+use super::executor::TaskExecutor;
+use super::outbound::{resolve_employee_profile, resolve_slack_bot_token_for_employee};
+use super::schedule::next_run_after;
+use super::types::{RunTaskTask, Schedule, ScheduledTask, SchedulerError};
+use super::utils::task_kind_label;
+
+const APPROVAL_NOTICE_DIR: &str = "dowhiz_approval_requests";
+
+impl<E: TaskExecutor> Scheduler<E> {
+    /// Record the approver's decision on the held task `task_id`. Approval
+    /// enables the task (a cron task resumes from its next run after now);
+    /// rejection leaves it disabled. Returns false when the task is not
+    /// awaiting approval.
+    pub fn resolve_approval(
+        &mut self,
+        task_id: Uuid,
+        status: ApprovalStatus,
+        decided_by: &str,
+    ) -> Result<bool, SchedulerError> {
+        let Some(task) = self
+            .tasks
+            .iter_mut()
+            .find(|task| task.id == task_id && task.awaiting_approval())
+        else {
+            return Ok(false);
+        };
+        let now = Utc::now();
+        if let Some(approval) = task.approval.as_mut() {
+            approval.status = status;
+            approval.decided_by = Some(decided_by.to_string());
+            approval.decided_at = Some(now);
+        }
+        if status == ApprovalStatus::Approved {
+            if let Schedule::Cron {
+                expression,
+                next_run,
+            } = &mut task.schedule
+            {
+                if *next_run < now {
+                    *next_run = next_run_after(expression, now)?;
+                }
+            }
+            task.enabled = true;
+        }
+        self.store.update_task(task)?;
+        Ok(true)
+    }
+}
+
+/// Raise an approval request for each task `source` held and notify the
+/// approver. Requests that already exist (a replayed completion) are not
+/// sent again. Failures are logged; the tasks simply stay held.
+pub(super) fn request_approvals(
+    storage_path: &Path,
+    owner_user: Option<String>,
+    source: &RunTaskTask,
+    held: &[&ScheduledTask],
+) {
+    if held.is_empty() {
+        return;
+    }
+    let Some(approver) = resolve_approver(source) else {
+        warn!(
+            "no approver for {} held task(s) from {}; they stay disabled",
+            held.len(),
+            source.workspace_dir.display()
+        );
+        return;
+    };
+    let Some(store) = get_global_approval_store() else {
+        return;
+    };
+    let owner_user_id = owner_user
+        .as_deref()
+        .and_then(|owner| owner.strip_prefix("user:"))
+        .map(str::to_string);
+    let now = Utc::now();
+    for task in held {
+        let Some(approval) = &task.approval else {
+            continue;
+        };
+        let record = ApprovalRecord {
+            approval_id: task.id.to_string(),
+            token: Uuid::new_v4().simple().to_string(),
+            employee_id: source.employee_id.clone(),
+            owner_user_id: owner_user_id.clone(),
+            tasks_db_path: storage_path.display().to_string(),
+            task_id: task.id.to_string(),
+            kind: task_kind_label(&task.kind).to_string(),
+            summary: approval.summary.clone(),
+            approver: approver.clone(),
+            requester: match source.channel {
+                Channel::Slack => source.reply_to.first().cloned(),
+                _ => None,
+            },
+            status: ApprovalStatus::Pending,
+            decided_by: None,
+            created_at: now,
+            expires_at: now + chrono::Duration::hours(APPROVAL_TTL_HOURS),
+            held_envelope: None,
+        };
+        match store.insert_if_absent(&record) {
+            Ok(true) => match notify_approver(&record) {
+                Ok(()) => info!(
+                    "approval requested for task {} via {} to {}",
+                    record.task_id, record.approver.channel, record.approver.address
+                ),
+                Err(err) => warn!(
+                    "failed to notify approver for task {}: {}",
+                    record.task_id, err
+                ),
+            },
+            Ok(false) => {}
+            Err(err) => warn!(
+                "failed to store approval request for task {}: {}",
+                record.task_id, err
+            ),
+        }
+    }
+}
+
+/// The employee's configured approver, else the requester on the channel the
+/// request came in on (email and Slack only).
+fn resolve_approver(source: &RunTaskTask) -> Option<Approver> {
+    if let Some(approver) =
+        resolve_employee_profile(source.employee_id.as_deref()).and_then(|profile| profile.approver)
+    {
+        return Some(approver);
+    }
+    let address = match source.channel {
+        Channel::Email => source.reply_to.first(),
+        // reply_to[0] is the Slack user, reply_to[1] the channel.
+        Channel::Slack => source.reply_to.get(1),
+        _ => None,
+    }?;
+    Some(Approver {
+        channel: source.channel,
+        address: address.clone(),
+    })
+}
+
+fn approval_url(record: &ApprovalRecord) -> String {
+    let api_base = std::env::var("DOWHIZ_API_URL")
+        .unwrap_or_else(|_| "https://api.production1.dowhiz.com/service".to_string());
+    format!(
+        "{}/approvals/{}?token={}",
+        api_base.trim_end_matches('/'),
+        record.approval_id,
+        record.token
+    )
+}
+
+pub(crate) fn notify_approver(record: &ApprovalRecord) -> Result<(), SchedulerError> {
+    match record.approver.channel {
+        Channel::Slack => notify_slack_approver(record),
+        _ => notify_email_approver(record),
+    }
+}
+
+fn notify_email_approver(record: &ApprovalRecord) -> Result<(), SchedulerError> {
+    let from = resolve_employee_profile(record.employee_id.as_deref())
+        .and_then(|profile| profile.addresses.first().cloned())
+        .or_else(|| std::env::var("ADMIN_EMAIL").ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| {
+            SchedulerError::TaskFailed("from address missing for approval request".to_string())
+        })?;
+
+    let notice_dir = std::env::temp_dir().join(APPROVAL_NOTICE_DIR);
+    std::fs::create_dir_all(&notice_dir)?;
+    let notice_path = notice_dir.join(format!("approval_{}.html", record.approval_id));
+    std::fs::write(
+        &notice_path,
+        format!(
+            "<p>A digital employee is waiting for your approval before it continues:</p><blockquote>{}</blockquote><p><a href=\"{}\">Review and approve or reject</a></p><p>Nothing happens unless you approve. The request expires at {}.</p>",
+            escape_html(&record.summary),
+            escape_html(&approval_url(record)),
+            record.expires_at.to_rfc3339()
+        ),
+    )?;
+    let notice_attachments =
+        notice_dir.join(format!("attachments_approval_{}", record.approval_id));
+    std::fs::create_dir_all(&notice_attachments)?;
+
+    let params = send_emails_module::SendEmailParams {
+        subject: format!("Approval needed: {}", record.summary),
+        html_path: notice_path,
+        attachments_dir: notice_attachments,
+        from: Some(from),
+        to: vec![record.approver.address.clone()],
+        cc: vec![],
+        bcc: vec![],
+        in_reply_to: None,
+        references: None,
+        reply_to: None,
+    };
+    send_emails_module::send_email(&params)
+        .map_err(|err| SchedulerError::TaskFailed(err.to_string()))?;
+    Ok(())
+}
+
+/// Post Approve/Reject buttons whose callback_id is the approval id.
+fn notify_slack_approver(record: &ApprovalRecord) -> Result<(), SchedulerError> {
+    use crate::adapters::slack::SlackOutboundAdapter;
+
+    let bot_token = resolve_slack_bot_token_for_employee(record.employee_id.as_deref())?;
+    let adapter = SlackOutboundAdapter::new(bot_token);
+    let prompt = format!("*Approval needed* ({}): {}", record.kind, record.summary);
+    let result = adapter
+        .send_confirmation(&record.approver.address, None, &record.approval_id, &prompt)
+        .map_err(|err| SchedulerError::TaskFailed(err.to_string()))?;
+    if !result.success {
+        return Err(SchedulerError::TaskFailed(format!(
+            "slack approval post failed: {}",
+            result.error.unwrap_or_default()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::tests::run_task_in;
+
+    fn source(channel: Channel, reply_to: &[&str]) -> RunTaskTask {
+        RunTaskTask {
+            reply_to: reply_to.iter().map(|value| value.to_string()).collect(),
+            channel,
+            employee_id: Some("no_such_employee".to_string()),
+            ..run_task_in(Path::new("/tmp/ws"))
+        }
+    }
+
+    #[test]
+    fn approver_falls_back_to_requester() {
+        let email = resolve_approver(&source(Channel::Email, &["ann@acme.com"])).unwrap();
+        assert_eq!(email.channel, Channel::Email);
+        assert_eq!(email.address, "ann@acme.com");
+
+        let slack = resolve_approver(&source(Channel::Slack, &["U1", "C1"])).unwrap();
+        assert_eq!(slack.channel, Channel::Slack);
+        assert_eq!(slack.address, "C1");
+
+        assert!(resolve_approver(&source(Channel::Sms, &["+15550100100"])).is_none());
+    }
+}
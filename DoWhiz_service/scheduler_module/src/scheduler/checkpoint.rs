@@ -0,0 +1,195 @@
+//! Stage checkpoints for run_tasks, kept in the workspace so a retry after a
+//! crash or a failed later step resumes where the last attempt stopped.
+//!
+//! The scheduler begins a checkpoint before it executes a run_task and clears
+//! it once the run's replies are committed. In between the executor records
+//! each stage it completes. The model output is saved with
+//! [`RunStage::ModelCompleted`], so a retry past that point reuses it instead
+//! of running the model again.
+
+use chrono::{DateTime, Utc};
+use run_task_module::RunTaskOutput;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::types::Schedule;
+
+const CHECKPOINT_FILENAME: &str = ".run_task_checkpoint.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RunStage {
+    Started,
+    /// Memory, secrets and attachments are in the workspace.
+    WorkspacePrepared,
+    /// The runner finished; `output` holds its result.
+    ModelCompleted,
+    /// Usage and memory/secrets changes are written back; only the replies
+    /// are left to schedule.
+    ResultsSynced,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RunCheckpoint {
+    /// Identifies the run this checkpoint belongs to; see [`run_key`].
+    pub(crate) key: String,
+    pub(crate) stage: RunStage,
+    pub(crate) updated_at: DateTime<Utc>,
+    /// Memo as synced into the workspace, the base for the memory diff.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) memo_snapshot: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) output: Option<RunTaskOutput>,
+}
+
+/// Retries of a one-shot task resume the same run; each cron occurrence is
+/// its own run.
+pub(crate) fn run_key(task_id: Uuid, schedule: &Schedule) -> String {
+    match schedule {
+        Schedule::OneShot { .. } => task_id.to_string(),
+        Schedule::Cron { next_run, .. } => format!("{}@{}", task_id, next_run.to_rfc3339()),
+    }
+}
+
+/// Returns the checkpoint for `key`, starting a new one unless an earlier
+/// attempt of the same run left one behind.
+pub(crate) fn begin(workspace_dir: &Path, key: &str) -> io::Result<RunCheckpoint> {
+    if let Some(checkpoint) = load(workspace_dir).filter(|checkpoint| checkpoint.key == key) {
+        return Ok(checkpoint);
+    }
+    let checkpoint = RunCheckpoint {
+        key: key.to_string(),
+        stage: RunStage::Started,
+        updated_at: Utc::now(),
+        memo_snapshot: None,
+        output: None,
+    };
+    if workspace_dir.is_dir() {
+        save(workspace_dir, &checkpoint)?;
+    }
+    Ok(checkpoint)
+}
+
+pub(crate) fn load(workspace_dir: &Path) -> Option<RunCheckpoint> {
+    let path = workspace_dir.join(CHECKPOINT_FILENAME);
+    let raw = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&raw) {
+        Ok(checkpoint) => Some(checkpoint),
+        Err(err) => {
+            warn!("ignoring unreadable checkpoint {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+impl RunCheckpoint {
+    pub(crate) fn reached(&self, stage: RunStage) -> bool {
+        self.stage >= stage
+    }
+
+    /// Record `stage` as done. Stages never move backwards.
+    pub(crate) fn advance(&mut self, workspace_dir: &Path, stage: RunStage) -> io::Result<()> {
+        self.stage = self.stage.max(stage);
+        self.updated_at = Utc::now();
+        save(workspace_dir, self)
+    }
+}
+
+pub(crate) fn clear(workspace_dir: &Path) -> io::Result<()> {
+    match fs::remove_file(workspace_dir.join(CHECKPOINT_FILENAME)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Written through a temp file so a crash mid-write leaves the previous
+/// checkpoint intact.
+fn save(workspace_dir: &Path, checkpoint: &RunCheckpoint) -> io::Result<()> {
+    let payload = serde_json::to_vec(checkpoint).map_err(io::Error::other)?;
+    let path = workspace_dir.join(CHECKPOINT_FILENAME);
+    let tmp_path = workspace_dir.join(format!("{}.tmp", CHECKPOINT_FILENAME));
+    fs::write(&tmp_path, payload)?;
+    fs::rename(&tmp_path, &path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    fn sample_output(workspace: &Path) -> RunTaskOutput {
+        RunTaskOutput {
+            reply_html_path: workspace.join("reply_email_draft.html"),
+            reply_attachments_dir: workspace.join("reply_email_attachments"),
+            codex_output: "done".to_string(),
+            scheduled_tasks: Vec::new(),
+            scheduled_tasks_error: None,
+            scheduler_actions: Vec::new(),
+            scheduler_actions_error: None,
+            token_usage: None,
+            output_issues: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn begin_resumes_the_same_run_and_restarts_another() {
+        let temp = TempDir::new().unwrap();
+        let workspace = temp.path();
+
+        let mut checkpoint = begin(workspace, "task-a").unwrap();
+        assert_eq!(checkpoint.stage, RunStage::Started);
+        checkpoint.memo_snapshot = Some("# Memo\n".to_string());
+        checkpoint.output = Some(sample_output(workspace));
+        checkpoint
+            .advance(workspace, RunStage::ModelCompleted)
+            .unwrap();
+        checkpoint
+            .advance(workspace, RunStage::WorkspacePrepared)
+            .unwrap();
+
+        let resumed = begin(workspace, "task-a").unwrap();
+        assert_eq!(resumed.stage, RunStage::ModelCompleted);
+        assert!(resumed.reached(RunStage::WorkspacePrepared));
+        assert_eq!(resumed.memo_snapshot.as_deref(), Some("# Memo\n"));
+        assert_eq!(resumed.output.unwrap().codex_output, "done");
+
+        let restarted = begin(workspace, "task-b").unwrap();
+        assert_eq!(restarted.stage, RunStage::Started);
+        assert!(restarted.output.is_none());
+        assert_eq!(load(workspace).unwrap().key, "task-b");
+
+        clear(workspace).unwrap();
+        assert!(load(workspace).is_none());
+        clear(workspace).unwrap();
+    }
+
+    #[test]
+    fn begin_without_workspace_writes_nothing() {
+        let temp = TempDir::new().unwrap();
+        let missing = temp.path().join("missing");
+        let checkpoint = begin(&missing, "task").unwrap();
+        assert_eq!(checkpoint.stage, RunStage::Started);
+        assert!(!missing.exists());
+    }
+
+    #[test]
+    fn cron_runs_are_keyed_per_occurrence() {
+        let task_id = Uuid::new_v4();
+        let first = Schedule::Cron {
+            expression: "0 0 9 * * *".to_string(),
+            next_run: Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap(),
+        };
+        let second = Schedule::Cron {
+            expression: "0 0 9 * * *".to_string(),
+            next_run: Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap(),
+        };
+        assert_ne!(run_key(task_id, &first), run_key(task_id, &second));
+        let retried = Schedule::OneShot { run_at: Utc::now() };
+        assert_eq!(run_key(task_id, &retried), task_id.to_string());
+    }
+}
@@ -1,66 +1,245 @@
 use chrono::{DateTime, Local, Utc};
-use std::path::PathBuf;
+use run_task_module::{ExecLogScope, EXEC_LOG_DIR};
+use sha1::{Digest, Sha1};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
-use tracing::{info, warn};
+use tracing::{info, info_span, warn};
 use uuid::Uuid;
 
-use crate::account_store::{lookup_account_by_channel, lookup_account_by_identifier};
+use crate::account_store::{
+    get_global_account_store, lookup_account_by_channel, lookup_account_by_identifier,
+};
+use crate::alerting::{self, Alert, AlertKind};
+use crate::approval_store::ApprovalStatus;
+use crate::audit_store;
 use crate::channel::Channel;
+use crate::telemetry;
+use crate::thread_state::ThreadState;
+use crate::trace_context;
+use crate::workspace_recovery::{
+    detect_workspace_corruption, mark_recovery_notified, recover_corrupt_workspace,
+};
+use crate::workspace_snapshot;
 
-use super::actions::{apply_scheduler_actions, ingest_follow_up_tasks, schedule_auto_reply};
+use super::actions::{
+    apply_scheduler_actions, ingest_follow_up_tasks, schedule_auto_reply, send_reply_epoch_matches,
+    thread_epoch_matches,
+};
+use super::approval::request_approvals;
+use super::checkpoint::{self, RunStage};
 use super::executor::TaskExecutor;
-use super::outbound::execute_slack_send;
+use super::outbound::{
+    execute_slack_send, policy_permitted_email, send_reply_audit_entry, OUTBOUND_POLICY_ERROR,
+};
+use super::outbound_failure::{classify_outbound_failure, OutboundFailureKind};
 use super::reply::load_reply_context;
 use super::schedule::{next_run_after, validate_cron_expression};
 use super::snapshot::{snapshot_reply_draft, write_scheduler_snapshot};
-use super::store::SchedulerStore;
+use super::store::{DeliveryState, SchedulerStore};
 use super::types::{
-    RunTaskTask, Schedule, ScheduledTask, SchedulerError, SendReplyTask, TaskKind,
+    DigestTask, HeartbeatSpec, NoopTask, ProbeTask, RecurrenceEnd, RunTaskTask, Schedule,
+    ScheduledTask, SchedulerError, SendReplyTask, TaskApproval, TaskExecution, TaskKind,
     RUN_TASK_FAILURE_DIR, RUN_TASK_FAILURE_LIMIT, RUN_TASK_FAILURE_NOTICE,
     RUN_TASK_FAILURE_REPORT_DIR,
 };
+use super::utils::task_kind_label;
 
 pub struct Scheduler<E: TaskExecutor> {
     pub(super) tasks: Vec<ScheduledTask>,
     executor: E,
     pub(super) store: SchedulerStore,
+    pub(super) storage_path: PathBuf,
+    outbox: Option<ReplyOutbox>,
+}
+
+/// Tasks added while a run_task finishes (its reply, scheduled sends,
+/// follow-up runs). They are held back and committed together with the
+/// run_task's completion.
+struct ReplyOutbox {
+    source: Uuid,
+    staged: Vec<Uuid>,
+    /// `(thread_id, epoch)` of an interactive source run; its replies are
+    /// keyed by thread so a retried run cannot answer the same message twice.
+    thread: Option<(String, u64)>,
+    replies: u32,
+}
+
+impl ReplyOutbox {
+    /// Ids derive from the source task and staging order, so a replayed
+    /// completion lands on the same documents instead of adding new ones.
+    fn stage(&mut self, mut task: ScheduledTask) -> ScheduledTask {
+        task.id = sha1_uuid(&[
+            self.source.as_bytes(),
+            format!("follow-up:{}", self.staged.len()).as_bytes(),
+        ]);
+        if let (TaskKind::SendReply(reply), Some((thread_id, epoch))) =
+            (&mut task.kind, self.thread.as_ref())
+        {
+            if reply.idempotency_key.is_none() {
+                reply.idempotency_key = Some(thread_reply_key(thread_id, *epoch, self.replies));
+                self.replies += 1;
+            }
+        }
+        self.staged.push(task.id);
+        task
+    }
+}
+
+/// Delivery key of the `seq`-th reply to epoch `epoch` of a thread. Every run
+/// answering that message derives the same keys, so the delivery journal
+/// lets only one of them send.
+pub(super) fn thread_reply_key(thread_id: &str, epoch: u64, seq: u32) -> String {
+    sha1_uuid(&[format!("reply:{}:{}:{}", thread_id, epoch, seq).as_bytes()]).to_string()
+}
+
+fn sha1_uuid(parts: &[&[u8]]) -> Uuid {
+    let mut hasher = Sha1::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let digest = hasher.finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_sha1_bytes(bytes).into_uuid()
 }
 
 impl<E: TaskExecutor> Scheduler<E> {
     pub fn load(storage_path: impl Into<PathBuf>, executor: E) -> Result<Self, SchedulerError> {
         let storage_path = storage_path.into();
-        let store = SchedulerStore::new(storage_path)?;
+        let store = SchedulerStore::new(storage_path.clone())?;
         let tasks = store.load_tasks()?;
         Ok(Self {
             tasks,
             executor,
             store,
+            storage_path,
+            outbox: None,
         })
     }
 
+    /// Insert a new task, or stage it while a run_task completion is open.
+    fn push_new_task(&mut self, mut task: ScheduledTask) -> Result<Uuid, SchedulerError> {
+        task.kind.inherit_trace_id();
+        let task = match self.outbox.as_mut() {
+            Some(outbox) => outbox.stage(task),
+            None => {
+                self.store.insert_task(&task)?;
+                task
+            }
+        };
+        let task_id = task.id;
+        self.tasks.push(task);
+        Ok(task_id)
+    }
+
     pub fn tasks(&self) -> &[ScheduledTask] {
         &self.tasks
     }
 
-    pub fn disable_tasks_by<F>(&mut self, mut predicate: F) -> Result<usize, SchedulerError>
+    /// Disable matching tasks. Matching tasks still awaiting approval are
+    /// rejected so a later approval cannot revive them, and the execution
+    /// intents of matching one-shots are closed so startup does not either.
+    pub fn disable_tasks_by<F>(&mut self, predicate: F) -> Result<usize, SchedulerError>
     where
         F: FnMut(&ScheduledTask) -> bool,
     {
+        self.disable_matching(predicate, true)
+    }
+
+    /// Disable matching tasks that a newer message superseded. Unlike
+    /// [`Self::disable_tasks_by`], open execution intents are left open, so a
+    /// run lost mid-flight is still recovered and dropped by the epoch checks.
+    pub fn supersede_tasks_by<F>(&mut self, predicate: F) -> Result<usize, SchedulerError>
+    where
+        F: FnMut(&ScheduledTask) -> bool,
+    {
+        self.disable_matching(predicate, false)
+    }
+
+    fn disable_matching<F>(
+        &mut self,
+        mut predicate: F,
+        close_intents: bool,
+    ) -> Result<usize, SchedulerError>
+    where
+        F: FnMut(&ScheduledTask) -> bool,
+    {
+        let now = Utc::now();
         let mut disabled = 0usize;
         for task in &mut self.tasks {
-            if !task.enabled {
+            let held = task.awaiting_approval();
+            if !task.enabled && !held {
                 continue;
             }
             if predicate(task) {
                 task.enabled = false;
+                if let Some(approval) = task.approval.as_mut().filter(|_| held) {
+                    approval.status = ApprovalStatus::Rejected;
+                    approval.decided_by = Some("canceled".to_string());
+                    approval.decided_at = Some(now);
+                }
                 self.store.update_task(task)?;
+                if close_intents && matches!(task.schedule, Schedule::OneShot { .. }) {
+                    self.store.close_intent(&task.id.to_string(), now)?;
+                }
                 disabled += 1;
             }
         }
         Ok(disabled)
     }
 
+    /// Keep the new task `task_id` disabled until an approver signs off on
+    /// `summary`. The approver is notified once the task is persisted.
+    pub(super) fn hold_for_approval(
+        &mut self,
+        task_id: Uuid,
+        summary: &str,
+    ) -> Result<(), SchedulerError> {
+        let Some(task) = self.tasks.iter_mut().find(|task| task.id == task_id) else {
+            return Ok(());
+        };
+        task.enabled = false;
+        task.approval = Some(TaskApproval {
+            summary: summary.trim().to_string(),
+            status: ApprovalStatus::Pending,
+            decided_by: None,
+            decided_at: None,
+        });
+        // Staged tasks are written with the run_task's completion.
+        let staged = self
+            .outbox
+            .as_ref()
+            .is_some_and(|outbox| outbox.staged.contains(&task_id));
+        if !staged {
+            self.store.update_task(task)?;
+        }
+        Ok(())
+    }
+
+    /// Attach the agent's description and end condition to a new task.
+    pub(crate) fn set_task_details(
+        &mut self,
+        task_id: Uuid,
+        description: Option<String>,
+        ends: Option<RecurrenceEnd>,
+    ) -> Result<(), SchedulerError> {
+        let Some(task) = self.tasks.iter_mut().find(|task| task.id == task_id) else {
+            return Ok(());
+        };
+        task.description = description;
+        task.ends = ends;
+        // Staged tasks are written with the run_task's completion.
+        let staged = self
+            .outbox
+            .as_ref()
+            .is_some_and(|outbox| outbox.staged.contains(&task_id));
+        if !staged {
+            self.store.update_task(task)?;
+        }
+        Ok(())
+    }
+
     pub fn add_cron_task(
         &mut self,
         expression: &str,
@@ -80,11 +259,12 @@ impl<E: TaskExecutor> Scheduler<E> {
             enabled: true,
             created_at: now,
             last_run: None,
+            approval: None,
+            description: None,
+            ends: None,
         };
 
-        self.tasks.push(task);
-        self.store.insert_task(self.tasks.last().unwrap())?;
-        Ok(self.tasks.last().unwrap().id)
+        self.push_new_task(task)
     }
 
     pub fn add_one_shot_in(
@@ -105,11 +285,12 @@ impl<E: TaskExecutor> Scheduler<E> {
             enabled: true,
             created_at: utc_now,
             last_run: None,
+            approval: None,
+            description: None,
+            ends: None,
         };
 
-        self.tasks.push(task);
-        self.store.insert_task(self.tasks.last().unwrap())?;
-        Ok(self.tasks.last().unwrap().id)
+        self.push_new_task(task)
     }
 
     /// Add a one-shot task with a specific task ID.
@@ -126,15 +307,19 @@ impl<E: TaskExecutor> Scheduler<E> {
             chrono::Duration::from_std(delay).map_err(|_| SchedulerError::DurationOutOfRange)?;
         let run_at = utc_now + chrono_delay;
 
-        let task = ScheduledTask {
+        let mut task = ScheduledTask {
             id,
             kind,
             schedule: Schedule::OneShot { run_at },
             enabled: true,
             created_at: utc_now,
             last_run: None,
+            approval: None,
+            description: None,
+            ends: None,
         };
 
+        task.kind.inherit_trace_id();
         self.tasks.push(task);
         self.store.insert_task(self.tasks.last().unwrap())?;
         Ok(())
@@ -152,11 +337,148 @@ impl<E: TaskExecutor> Scheduler<E> {
             enabled: true,
             created_at: Utc::now(),
             last_run: None,
+            approval: None,
+            description: None,
+            ends: None,
         };
 
-        self.tasks.push(task);
-        self.store.insert_task(self.tasks.last().unwrap())?;
-        Ok(self.tasks.last().unwrap().id)
+        self.push_new_task(task)
+    }
+
+    /// Installs the named heartbeat noop task, or updates it in place when its
+    /// cron expression or grace period changed, so repeated startups never
+    /// stack duplicate heartbeats.
+    pub fn ensure_heartbeat(
+        &mut self,
+        name: &str,
+        expression: &str,
+        grace_secs: u64,
+    ) -> Result<Uuid, SchedulerError> {
+        validate_cron_expression(expression)?;
+        let spec = HeartbeatSpec {
+            name: name.to_string(),
+            grace_secs,
+        };
+        let existing = self.tasks.iter().position(|task| {
+            task.enabled
+                && matches!(
+                    &task.kind,
+                    TaskKind::Noop(NoopTask { heartbeat: Some(current) }) if current.name == name
+                )
+        });
+        let Some(index) = existing else {
+            return self.add_cron_task(
+                expression,
+                TaskKind::Noop(NoopTask {
+                    heartbeat: Some(spec),
+                }),
+            );
+        };
+
+        let task = &mut self.tasks[index];
+        let mut changed = false;
+        if let TaskKind::Noop(noop) = &mut task.kind {
+            if noop.heartbeat.as_ref() != Some(&spec) {
+                noop.heartbeat = Some(spec);
+                changed = true;
+            }
+        }
+        let same_expression = matches!(
+            &task.schedule,
+            Schedule::Cron { expression: current, .. } if current == expression
+        );
+        if !same_expression {
+            task.schedule = Schedule::Cron {
+                expression: expression.to_string(),
+                next_run: next_run_after(expression, Utc::now())?,
+            };
+            changed = true;
+        }
+        let id = task.id;
+        if changed {
+            let updated_task = task.clone();
+            self.store.update_task(&updated_task)?;
+        }
+        Ok(id)
+    }
+
+    /// Installs the account's digest task, or updates it in place when its
+    /// delivery settings or cron expression changed.
+    pub fn ensure_digest(
+        &mut self,
+        expression: &str,
+        digest: DigestTask,
+    ) -> Result<Uuid, SchedulerError> {
+        self.ensure_single_cron_task(expression, digest, TaskKind::Digest, |kind| match kind {
+            TaskKind::Digest(current) => Some(current),
+            _ => None,
+        })
+    }
+
+    /// Disables the account's digest task. Returns how many were disabled.
+    pub fn remove_digest(&mut self) -> Result<usize, SchedulerError> {
+        self.disable_tasks_by(|task| matches!(task.kind, TaskKind::Digest(_)))
+    }
+
+    /// Installs the employee's health probe task, or updates it in place
+    /// when its settings or cron expression changed.
+    pub fn ensure_probe(
+        &mut self,
+        expression: &str,
+        probe: ProbeTask,
+    ) -> Result<Uuid, SchedulerError> {
+        self.ensure_single_cron_task(expression, probe, TaskKind::Probe, |kind| match kind {
+            TaskKind::Probe(current) => Some(current),
+            _ => None,
+        })
+    }
+
+    /// Disables the health probe task. Returns how many were disabled.
+    pub fn remove_probe(&mut self) -> Result<usize, SchedulerError> {
+        self.disable_tasks_by(|task| matches!(task.kind, TaskKind::Probe(_)))
+    }
+
+    /// Keeps one enabled cron task of the kind `extract` picks out, rewriting
+    /// it only when its settings or the expression changed.
+    fn ensure_single_cron_task<T: PartialEq>(
+        &mut self,
+        expression: &str,
+        settings: T,
+        wrap: fn(T) -> TaskKind,
+        extract: fn(&TaskKind) -> Option<&T>,
+    ) -> Result<Uuid, SchedulerError> {
+        validate_cron_expression(expression)?;
+        let existing = self
+            .tasks
+            .iter()
+            .position(|task| task.enabled && extract(&task.kind).is_some());
+        let Some(index) = existing else {
+            return self.add_cron_task(expression, wrap(settings));
+        };
+
+        let task = &mut self.tasks[index];
+        let mut changed = false;
+        if extract(&task.kind) != Some(&settings) {
+            task.kind = wrap(settings);
+            changed = true;
+        }
+        let same_expression = matches!(
+            &task.schedule,
+            Schedule::Cron { expression: current, .. } if current == expression
+        );
+        if !same_expression {
+            task.schedule = Schedule::Cron {
+                expression: expression.to_string(),
+                next_run: next_run_after(expression, Utc::now())?,
+            };
+            changed = true;
+        }
+        let id = task.id;
+        if changed {
+            let updated_task = task.clone();
+            self.store.update_task(&updated_task)?;
+        }
+        Ok(id)
     }
 
     /// Pushes a one-shot task into the future to avoid hot-loop retries.
@@ -195,13 +517,107 @@ impl<E: TaskExecutor> Scheduler<E> {
         }
     }
 
+    /// Holds a task back until `until` without running it. Cron tasks skip
+    /// to their first occurrence at or after `until`.
+    pub fn defer_task_until(
+        &mut self,
+        task_id: Uuid,
+        until: DateTime<Utc>,
+    ) -> Result<bool, SchedulerError> {
+        let index = match self.tasks.iter().position(|task| task.id == task_id) {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+        if !self.tasks[index].enabled {
+            return Ok(false);
+        }
+
+        match &mut self.tasks[index].schedule {
+            Schedule::OneShot { run_at } => {
+                if *run_at >= until {
+                    return Ok(false);
+                }
+                *run_at = until;
+            }
+            Schedule::Cron {
+                expression,
+                next_run,
+            } => {
+                let deferred = next_run_after(expression, until - chrono::Duration::seconds(1))?;
+                if *next_run >= deferred {
+                    return Ok(false);
+                }
+                *next_run = deferred;
+            }
+        }
+        let updated_task = self.tasks[index].clone();
+        self.store.update_task(&updated_task)?;
+        Ok(true)
+    }
+
+    /// Moves a task's next run to `until` without skipping it: a cron task
+    /// runs the held occurrence at `until`, then resumes its schedule.
+    pub fn delay_task_until(
+        &mut self,
+        task_id: Uuid,
+        until: DateTime<Utc>,
+    ) -> Result<bool, SchedulerError> {
+        let index = match self.tasks.iter().position(|task| task.id == task_id) {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+        if !self.tasks[index].enabled {
+            return Ok(false);
+        }
+
+        let next_run = match &mut self.tasks[index].schedule {
+            Schedule::OneShot { run_at } => run_at,
+            Schedule::Cron { next_run, .. } => next_run,
+        };
+        if *next_run >= until {
+            return Ok(false);
+        }
+        *next_run = until;
+        let updated_task = self.tasks[index].clone();
+        self.store.update_task(&updated_task)?;
+        Ok(true)
+    }
+
+    /// Makes an enabled task due at `now`, so the next tick runs it. A cron
+    /// task then resumes its schedule. Disabled tasks, including those held
+    /// for approval, are left alone.
+    pub fn trigger_task_now(
+        &mut self,
+        task_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<bool, SchedulerError> {
+        let Some(task) = self.tasks.iter_mut().find(|task| task.id == task_id) else {
+            return Ok(false);
+        };
+        if !task.enabled {
+            return Ok(false);
+        }
+        match &mut task.schedule {
+            Schedule::OneShot { run_at } => *run_at = now,
+            Schedule::Cron { next_run, .. } => *next_run = now,
+        }
+        let updated_task = task.clone();
+        self.store.update_task(&updated_task)?;
+        Ok(true)
+    }
+
     pub fn execute_task_by_id(&mut self, task_id: Uuid) -> Result<bool, SchedulerError> {
         let now = Utc::now();
         let index = match self.tasks.iter().position(|task| task.id == task_id) {
             Some(index) => index,
             None => return Ok(false),
         };
-        if !self.tasks[index].enabled || !self.tasks[index].is_due(now) {
+        if !self.tasks[index].enabled {
+            // Disabled before its run started: nothing is left to deliver.
+            self.close_intent(index);
+            return Ok(false);
+        }
+        if !self.tasks[index].is_due(now) {
             return Ok(false);
         }
         self.execute_task_at_index(index)?;
@@ -226,7 +642,9 @@ impl<E: TaskExecutor> Scheduler<E> {
 
     fn execute_task_at_index(&mut self, index: usize) -> Result<(), SchedulerError> {
         let task_id = self.tasks[index].id;
-        let task_kind = self.tasks[index].kind.clone();
+        let mut task_kind = self.tasks[index].kind.clone();
+        // Follow-ups and replies scheduled below inherit this trace.
+        let _trace = task_kind.trace_id().map(trace_context::enter);
         if let TaskKind::RunTask(task) = &self.tasks[index].kind {
             if let Err(err) = write_scheduler_snapshot(&task.workspace_dir, &self.tasks, Utc::now())
             {
@@ -236,11 +654,97 @@ impl<E: TaskExecutor> Scheduler<E> {
                     err
                 );
             }
+            let run_key = checkpoint::run_key(task_id, &self.tasks[index].schedule);
+            match checkpoint::begin(&task.workspace_dir, &run_key) {
+                Ok(checkpoint) if checkpoint.stage > RunStage::Started => info!(
+                    "run_task {} resumes from checkpoint stage {:?}",
+                    task_id, checkpoint.stage
+                ),
+                Ok(_) => snapshot_run_task_workspace(task),
+                Err(err) => warn!(
+                    "failed to begin run checkpoint for {}: {}",
+                    task.workspace_dir.display(),
+                    err
+                ),
+            }
         }
+        let kind_label = task_kind_label(&task_kind);
+        let _span = info_span!("scheduler.task", %task_id, kind = kind_label).entered();
         let started_at = Utc::now();
-        let execution_id = self.store.record_execution_start(task_id, started_at)?;
-        let result = self.executor.execute(&task_kind);
+        let log_dir = match &task_kind {
+            TaskKind::RunTask(task) => Some(task.workspace_dir.join(EXEC_LOG_DIR)),
+            _ => None,
+        };
+        let (execution_id, log_path) =
+            self.store
+                .record_execution_start(task_id, started_at, log_dir.as_deref())?;
+        // One-shot sends track delivery on their document; a cron send would
+        // never run again once marked sent.
+        let tracks_delivery = matches!(task_kind, TaskKind::SendReply(_))
+            && matches!(self.tasks[index].schedule, Schedule::OneShot { .. });
+        let stale_reply =
+            matches!(&task_kind, TaskKind::SendReply(send) if !send_reply_epoch_matches(send));
+        let delivery = if tracks_delivery && !stale_reply {
+            self.begin_delivery(task_id, &mut task_kind, started_at)?
+        } else {
+            DeliveryState::Pending
+        };
+        let sends = !stale_reply
+            && !matches!(
+                delivery,
+                DeliveryState::Delivered | DeliveryState::Duplicate
+            );
+        let result = match delivery {
+            _ if stale_reply => {
+                info!(
+                    "send_reply {} answers a superseded thread epoch; not sending it",
+                    task_id
+                );
+                Ok(TaskExecution::empty())
+            }
+            DeliveryState::Delivered => {
+                info!(
+                    "send_reply {} was already delivered; finalizing without resending",
+                    task_id
+                );
+                Ok(TaskExecution::empty())
+            }
+            DeliveryState::Duplicate => {
+                info!(
+                    "send_reply {} duplicates a reply another run already sent; suppressing it",
+                    task_id
+                );
+                Ok(TaskExecution::empty())
+            }
+            DeliveryState::Pending | DeliveryState::Interrupted => {
+                let _log = log_path.as_ref().and_then(|path| {
+                    ExecLogScope::enter(path)
+                        .map_err(|err| {
+                            warn!("failed to open execution log {}: {}", path.display(), err)
+                        })
+                        .ok()
+                });
+                self.executor.execute(&task_kind)
+            }
+        };
         let executed_at = Utc::now();
+        telemetry::record_task_run(
+            kind_label,
+            (executed_at - started_at).to_std().unwrap_or_default(),
+            result.is_ok(),
+        );
+        if tracks_delivery && !stale_reply && delivery != DeliveryState::Duplicate {
+            let provider_message_id = result
+                .as_ref()
+                .ok()
+                .and_then(|execution| execution.provider_message_id.as_deref());
+            self.store.finish_delivery(
+                &task_id.to_string(),
+                result.is_ok(),
+                provider_message_id,
+                executed_at,
+            )?;
+        }
 
         match result {
             Ok(execution) => {
@@ -264,14 +768,27 @@ impl<E: TaskExecutor> Scheduler<E> {
                         next_run,
                     } => {
                         *next_run = next_run_after(expression, executed_at)?;
+                        if self.tasks[index].count_recurring_run() {
+                            info!("recurring task {} reached its end condition", task_id);
+                        }
                     }
                     Schedule::OneShot { .. } => {
                         self.tasks[index].enabled = false;
                     }
                 }
-                let updated_task = self.tasks[index].clone();
-                self.store.update_task(&updated_task)?;
                 if let TaskKind::RunTask(task) = &task_kind {
+                    let thread = match (&task.thread_id, task.thread_epoch) {
+                        (Some(thread_id), Some(epoch)) if !task.scheduled => {
+                            Some((thread_id.clone(), epoch))
+                        }
+                        _ => None,
+                    };
+                    self.outbox = Some(ReplyOutbox {
+                        source: task_id,
+                        staged: Vec::new(),
+                        thread,
+                        replies: 0,
+                    });
                     if let Some(err) = execution.follow_up_error.as_deref() {
                         warn!("scheduled tasks parse error: {}", err);
                     }
@@ -307,19 +824,68 @@ impl<E: TaskExecutor> Scheduler<E> {
                             err
                         );
                     }
+                    self.commit_outbox(index)?;
+                    if let Err(err) = checkpoint::clear(&task.workspace_dir) {
+                        warn!(
+                            "failed to clear run checkpoint for {}: {}",
+                            task.workspace_dir.display(),
+                            err
+                        );
+                    }
                     // Sync success status to user's account-level storage for Discord/Slack
                     sync_task_status_to_user_storage(task_id, task, executed_at, "success", None);
+                } else {
+                    match &task_kind {
+                        TaskKind::SendReply(reply) if sends => {
+                            audit_store::record(send_reply_audit_entry(
+                                reply,
+                                &task_id.to_string(),
+                                self.store.owner_user(),
+                            ));
+                            note_recovery_delivered(reply);
+                        }
+                        _ => {}
+                    }
+                    let updated_task = self.tasks[index].clone();
+                    self.store.update_task(&updated_task)?;
                 }
+                self.close_intent(index);
             }
             Err(err) => {
                 let message = err.to_string();
+                if let TaskKind::RunTask(task) = &task_kind {
+                    let one_shot = matches!(self.tasks[index].schedule, Schedule::OneShot { .. });
+                    if one_shot && !thread_epoch_matches(task) {
+                        return self.drop_superseded_run(
+                            index,
+                            execution_id,
+                            executed_at,
+                            &message,
+                        );
+                    }
+                }
+                let outbound_failure = match &task_kind {
+                    TaskKind::SendReply(_) => Some(classify_outbound_failure(&message)),
+                    _ => None,
+                };
+                let recorded_message = match outbound_failure {
+                    Some(kind) => format!("[{}] {}", kind.label(), message),
+                    None => message.clone(),
+                };
                 self.store.record_execution_finish(
                     task_id,
                     execution_id,
                     executed_at,
                     "failed",
-                    Some(&message),
+                    Some(&recorded_message),
                 )?;
+                if let (TaskKind::SendReply(task), Some(kind)) = (&task_kind, outbound_failure) {
+                    if kind.is_permanent() {
+                        if let Err(err) = notify_send_reply_failure(task_id, task, kind, &message) {
+                            warn!("failed to report send_reply failure {}: {}", task_id, err);
+                        }
+                    }
+                }
                 // Sync failure status to user's account-level storage for Discord/Slack
                 if let TaskKind::RunTask(task) = &task_kind {
                     sync_task_status_to_user_storage(
@@ -330,6 +896,11 @@ impl<E: TaskExecutor> Scheduler<E> {
                         Some(&message),
                     );
                 }
+                if let TaskKind::RunTask(task) = &task_kind {
+                    if is_output_validation_failure(&message) {
+                        rollback_run_task_workspace(task_id, task);
+                    }
+                }
                 // Disable one-shot tasks on failure, but allow a few retries for RunTask.
                 if matches!(self.tasks[index].schedule, Schedule::OneShot { .. }) {
                     let mut disable_task = true;
@@ -338,6 +909,11 @@ impl<E: TaskExecutor> Scheduler<E> {
                         let task_id_str = task_id.to_string();
                         let retry_count = self.store.increment_retry_count(&task_id_str)?;
                         let failure_class = classify_run_task_failure(&message);
+                        let user_id = self
+                            .store
+                            .owner_user()
+                            .and_then(|owner| owner.strip_prefix("user:").map(str::to_string));
+                        recover_run_task_workspace(task_id, &task, user_id.as_deref());
                         if retry_count < RUN_TASK_FAILURE_LIMIT {
                             disable_task = false;
                             let delay = run_task_retry_delay(retry_count, failure_class);
@@ -366,6 +942,20 @@ impl<E: TaskExecutor> Scheduler<E> {
                                 message
                             );
                         } else {
+                            alerting::raise(
+                                Alert::new(
+                                    AlertKind::RetriesExhausted,
+                                    &task_id_str,
+                                    format!(
+                                        "run_task {} failed {} times and was disabled",
+                                        task_id, retry_count
+                                    ),
+                                )
+                                .detail("failure_class", failure_class.label())
+                                .detail("channel", task.channel)
+                                .detail("workspace", task.workspace_dir.display())
+                                .detail("error", &message),
+                            );
                             if let Err(err) = notify_run_task_failure(
                                 task_id,
                                 &task,
@@ -393,6 +983,7 @@ impl<E: TaskExecutor> Scheduler<E> {
                         );
                     }
                 }
+                self.close_intent(index);
                 return Err(err);
             }
         }
@@ -400,6 +991,171 @@ impl<E: TaskExecutor> Scheduler<E> {
         Ok(())
     }
 
+    /// Finish a one-shot run_task whose thread got a newer message while it
+    /// ran: it was stopped on purpose, so it is neither retried nor reported,
+    /// and the run answering the newer message takes over the workspace.
+    fn drop_superseded_run(
+        &mut self,
+        index: usize,
+        execution_id: i64,
+        executed_at: DateTime<Utc>,
+        message: &str,
+    ) -> Result<(), SchedulerError> {
+        let task_id = self.tasks[index].id;
+        info!(
+            "run_task {} was superseded by a newer message; dropping its output: {}",
+            task_id, message
+        );
+        self.store.record_execution_finish(
+            task_id,
+            execution_id,
+            executed_at,
+            "cancelled",
+            Some(message),
+        )?;
+        if let Err(err) = self.store.reset_retry_count(&task_id.to_string()) {
+            warn!(
+                "failed to reset retry count for superseded task {}: {}",
+                task_id, err
+            );
+        }
+        if let TaskKind::RunTask(task) = &self.tasks[index].kind {
+            if let Err(err) = checkpoint::clear(&task.workspace_dir) {
+                warn!(
+                    "failed to clear run checkpoint for {}: {}",
+                    task.workspace_dir.display(),
+                    err
+                );
+            }
+        }
+        self.tasks[index].last_run = Some(executed_at);
+        self.tasks[index].enabled = false;
+        let updated_task = self.tasks[index].clone();
+        self.store.update_task(&updated_task)?;
+        self.close_intent(index);
+        Ok(())
+    }
+
+    /// Close the execution intent of the one-shot at `index` once its run
+    /// recorded an outcome. A failure is only logged: startup recovery then
+    /// finds the outcome on the task and closes the intent itself.
+    fn close_intent(&self, index: usize) {
+        if !matches!(self.tasks[index].schedule, Schedule::OneShot { .. }) {
+            return;
+        }
+        let task_id = self.tasks[index].id;
+        if let Err(err) = self.store.close_intent(&task_id.to_string(), Utc::now()) {
+            warn!(
+                "failed to close execution intent of task {}: {}",
+                task_id, err
+            );
+        }
+    }
+
+    /// Make one-shot tasks due again whose claimed run never recorded an
+    /// outcome, so a run lost with its worker still happens: one-shots are
+    /// delivered at least once. Called at startup, after the intents were
+    /// persisted by [`super::record_execution_intent`].
+    ///
+    /// Only disabled tasks are touched; an enabled one is still due and the
+    /// poller picks it up. A task that ran after the claim, or was
+    /// rescheduled past it, only has its intent closed. A task superseded
+    /// while its run was in flight is re-enabled, and the epoch checks drop
+    /// the run again if its thread has moved on meanwhile; a canceled one had
+    /// its intent closed by [`Self::disable_tasks_by`].
+    /// Returns the number of tasks made due.
+    pub fn recover_intents(&mut self) -> Result<usize, SchedulerError> {
+        let now = Utc::now();
+        let mut recovered = 0usize;
+        for (task_id, claimed_at) in self.store.open_intents()? {
+            let Some(task) = self
+                .tasks
+                .iter_mut()
+                .find(|task| task.id.to_string() == task_id)
+            else {
+                continue;
+            };
+            if task.enabled {
+                continue;
+            }
+            let ran = task.last_run.is_some_and(|last_run| last_run >= claimed_at);
+            let unapproved = task
+                .approval
+                .as_ref()
+                .is_some_and(|approval| approval.status != ApprovalStatus::Approved);
+            let Schedule::OneShot { run_at } = &mut task.schedule else {
+                self.store.close_intent(&task_id, now)?;
+                continue;
+            };
+            if ran || unapproved || *run_at > claimed_at {
+                self.store.close_intent(&task_id, now)?;
+                continue;
+            }
+            *run_at = (*run_at).min(now);
+            task.enabled = true;
+            self.store.update_task(task)?;
+            warn!(
+                "one-shot task {} was claimed at {} but never finished; making it due again",
+                task_id, claimed_at
+            );
+            recovered += 1;
+        }
+        Ok(recovered)
+    }
+
+    /// Mark a send_reply task as in delivery and stamp its id as the provider
+    /// idempotency key unless staging already gave it a thread reply key. The
+    /// key is claimed in the delivery journal.
+    fn begin_delivery(
+        &self,
+        task_id: Uuid,
+        task_kind: &mut TaskKind,
+        now: DateTime<Utc>,
+    ) -> Result<DeliveryState, SchedulerError> {
+        let TaskKind::SendReply(task) = task_kind else {
+            return Ok(DeliveryState::Pending);
+        };
+        task.idempotency_key
+            .get_or_insert_with(|| task_id.to_string());
+        let state = self.store.begin_delivery(
+            &task_id.to_string(),
+            task.idempotency_key.as_deref(),
+            now,
+        )?;
+        if state == DeliveryState::Interrupted {
+            warn!(
+                "send_reply {} was interrupted mid-delivery; resending with idempotency key",
+                task_id
+            );
+        }
+        Ok(state)
+    }
+
+    /// Close the outbox opened for the run_task at `index` and persist its
+    /// completion together with every task it staged. The in-memory copies
+    /// are written, since scheduler actions may already have changed them.
+    fn commit_outbox(&mut self, index: usize) -> Result<(), SchedulerError> {
+        let staged = self
+            .outbox
+            .take()
+            .map(|outbox| outbox.staged)
+            .unwrap_or_default();
+        let follow_ups: Vec<ScheduledTask> = staged
+            .iter()
+            .filter_map(|id| self.tasks.iter().find(|task| task.id == *id).cloned())
+            .collect();
+        self.store
+            .commit_completion(&self.tasks[index], &follow_ups)?;
+        if let TaskKind::RunTask(source) = &self.tasks[index].kind {
+            let held: Vec<&ScheduledTask> = follow_ups
+                .iter()
+                .filter(|task| task.awaiting_approval())
+                .collect();
+            request_approvals(&self.storage_path, self.store.owner_user(), source, &held);
+        }
+        Ok(())
+    }
+
     pub fn run_loop(
         &mut self,
         poll_interval: Duration,
@@ -434,7 +1190,10 @@ impl<E: TaskExecutor> Scheduler<E> {
             task.enabled = false;
         }
         // Update in database
-        self.store.disable_task_by_id(task_id)
+        self.store.disable_task_by_id(task_id)?;
+        // Disabling by id is the task's outcome (retries exhausted, recipient
+        // muted); startup must not revive it.
+        self.store.close_intent(task_id, Utc::now())
     }
 }
 
@@ -519,8 +1278,8 @@ fn sync_task_status_to_user_storage(
     match SchedulerStore::new(user_tasks_db_path.clone()) {
         Ok(store) => {
             // Record execution start and finish to update status
-            match store.record_execution_start(task_id, executed_at) {
-                Ok(execution_id) => {
+            match store.record_execution_start(task_id, executed_at, None) {
+                Ok((execution_id, _)) => {
                     if let Err(err) = store.record_execution_finish(
                         task_id,
                         execution_id,
@@ -655,6 +1414,9 @@ fn notify_run_task_failure(
                 thread_epoch: None,
                 thread_state_path: None,
                 employee_id: task.employee_id.clone(),
+                idempotency_key: None,
+                trace_id: None,
+                scheduled: false,
             };
             execute_slack_send(&send_task)?;
         } else {
@@ -707,6 +1469,250 @@ fn notify_run_task_failure(
     Ok(())
 }
 
+/// Explain a permanent delivery failure to the user on another channel and
+/// queue it for admin review.
+///
+/// The user is reached by email when the failed recipient belongs to an
+/// account with a verified email address; the admin report is always sent so
+/// failures nobody could be told about still get looked at.
+fn notify_send_reply_failure(
+    task_id: Uuid,
+    task: &SendReplyTask,
+    kind: OutboundFailureKind,
+    error_message: &str,
+) -> Result<(), SchedulerError> {
+    let mut explanation = kind.user_explanation(&task.channel);
+    let policy_blocked = kind == OutboundFailureKind::PolicyBlocked;
+    if policy_blocked {
+        if let Some((_, detail)) = error_message.split_once(&format!("{}: ", OUTBOUND_POLICY_ERROR))
+        {
+            explanation.push_str(&format!(" Blocked: {}.", detail));
+        }
+    }
+    let alternate_email = if task.channel == Channel::Email {
+        // The requester is usually among the recipients the policy allows.
+        policy_blocked
+            .then(|| policy_permitted_email(task))
+            .flatten()
+    } else {
+        task.to
+            .first()
+            .and_then(|recipient| lookup_account_by_channel(&task.channel, recipient))
+            .and_then(verified_account_email)
+    };
+
+    let mut user_notified = false;
+    if let Some(email) = alternate_email.as_deref() {
+        match send_outbound_failure_email(task_id, task, email, &explanation) {
+            Ok(()) => user_notified = true,
+            Err(err) => warn!(
+                "failed to email delivery failure notice for {}: {}",
+                task_id, err
+            ),
+        }
+    }
+
+    let report_body = format!(
+        "<p>{}</p><p>Task ID: {}</p><p>Failure class: {}</p><p>Channel: {}</p><p>Recipients: {}</p><p>User notified: {}</p><pre>{}</pre>",
+        escape_html(&explanation),
+        escape_html(&task_id.to_string()),
+        escape_html(kind.label()),
+        escape_html(&task.channel.to_string()),
+        escape_html(&task.to.join(", ")),
+        match (user_notified, alternate_email.as_deref()) {
+            (true, Some(email)) => format!("yes, by email to {}", escape_html(email)),
+            _ => "no alternate channel available".to_string(),
+        },
+        escape_html(error_message),
+    );
+    send_admin_report(
+        format!("delivery_failure_{}.html", task_id),
+        format!("Delivery failure: {} [{}]", task_id, kind.label()),
+        report_body,
+        &format!("delivery failure report {}", task_id),
+    )
+}
+
+pub(super) fn verified_account_email(account_id: Uuid) -> Option<String> {
+    let store = get_global_account_store()?;
+    match store.list_identifiers(account_id) {
+        Ok(identifiers) => identifiers
+            .into_iter()
+            .find(|identifier| identifier.verified && identifier.identifier_type == "email")
+            .map(|identifier| identifier.identifier),
+        Err(err) => {
+            warn!(
+                "failed to list identifiers for account {}: {}",
+                account_id, err
+            );
+            None
+        }
+    }
+}
+
+fn send_outbound_failure_email(
+    task_id: Uuid,
+    task: &SendReplyTask,
+    email: &str,
+    explanation: &str,
+) -> Result<(), SchedulerError> {
+    let from = task
+        .from
+        .clone()
+        .filter(|value| value.contains('@'))
+        .or_else(|| std::env::var("ADMIN_EMAIL").ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| {
+            SchedulerError::TaskFailed("from address missing for delivery notice".to_string())
+        })?;
+
+    let notice_dir = std::env::temp_dir().join(RUN_TASK_FAILURE_REPORT_DIR);
+    std::fs::create_dir_all(&notice_dir)?;
+    let notice_path = notice_dir.join(format!("delivery_notice_{}.html", task_id));
+    std::fs::write(&notice_path, format!("<p>{}</p>", escape_html(explanation)))?;
+    let notice_attachments = notice_dir.join(format!("attachments_delivery_notice_{}", task_id));
+    std::fs::create_dir_all(&notice_attachments)?;
+
+    let params = send_emails_module::SendEmailParams {
+        subject: "We couldn't deliver your reply".to_string(),
+        html_path: notice_path,
+        attachments_dir: notice_attachments,
+        from: Some(from),
+        to: vec![email.to_string()],
+        cc: vec![],
+        bcc: vec![],
+        in_reply_to: None,
+        references: None,
+        reply_to: None,
+    };
+    send_emails_module::send_email(&params)
+        .map_err(|err| SchedulerError::TaskFailed(err.to_string()))?;
+    Ok(())
+}
+
+/// Rebuild a corrupt workspace after a failed run so the retry (and later
+/// messages in the thread) do not keep hitting the same broken files.
+/// Snapshot the workspace as this run finds it, so a run whose output fails
+/// validation can be rolled back. Runs outside a thread epoch are not
+/// snapshotted.
+fn snapshot_run_task_workspace(task: &RunTaskTask) {
+    let Some(epoch) = task.thread_epoch else {
+        return;
+    };
+    if !task.workspace_dir.is_dir() {
+        return;
+    }
+    if let Err(err) = workspace_snapshot::snapshot_epoch(&task.workspace_dir, epoch) {
+        warn!(
+            "failed to snapshot workspace {} for epoch {}: {}",
+            task.workspace_dir.display(),
+            epoch,
+            err
+        );
+    }
+}
+
+fn is_output_validation_failure(error_message: &str) -> bool {
+    error_message.contains("Output failed validation:")
+}
+
+/// Undo a run whose output failed validation: restore the workspace from its
+/// epoch snapshot and drop the run's checkpoint, so the retry starts over.
+fn rollback_run_task_workspace(task_id: Uuid, task: &RunTaskTask) {
+    let Some(epoch) = task.thread_epoch else {
+        return;
+    };
+    if !workspace_snapshot::has_snapshot(&task.workspace_dir, epoch) {
+        return;
+    }
+    match workspace_snapshot::rollback_to_epoch(&task.workspace_dir, epoch) {
+        Ok(restored) => {
+            warn!(
+                "run_task {} output failed validation; rolled workspace {} back to epoch {} ({} entries)",
+                task_id,
+                task.workspace_dir.display(),
+                epoch,
+                restored
+            );
+            if let Err(err) = checkpoint::clear(&task.workspace_dir) {
+                warn!(
+                    "failed to clear run checkpoint for {}: {}",
+                    task.workspace_dir.display(),
+                    err
+                );
+            }
+        }
+        Err(err) => warn!(
+            "run_task {} failed to roll workspace {} back to epoch {}: {}",
+            task_id,
+            task.workspace_dir.display(),
+            epoch,
+            err
+        ),
+    }
+}
+
+fn recover_run_task_workspace(task_id: Uuid, task: &RunTaskTask, user_id: Option<&str>) {
+    let Some(reason) = detect_workspace_corruption(&task.workspace_dir) else {
+        return;
+    };
+    let archive = task.archive_root.as_deref().zip(user_id);
+    let fallback_state = task.thread_id.as_ref().map(|thread_id| {
+        let mut state = ThreadState::new(thread_id.clone(), None);
+        if let Some(epoch) = task.thread_epoch {
+            state.epoch = epoch;
+            state.last_email_seq = epoch;
+        }
+        state
+    });
+    match recover_corrupt_workspace(
+        &task.workspace_dir,
+        &reason,
+        archive,
+        fallback_state.as_ref(),
+    ) {
+        Ok(recovery) => warn!(
+            "run_task {} workspace {} was corrupt ({}); rebuilt with {} entries, damaged copy at {}",
+            task_id,
+            task.workspace_dir.display(),
+            reason,
+            recovery.entries_restored,
+            recovery.quarantine_path.display()
+        ),
+        Err(err) => warn!(
+            "run_task {} workspace {} is corrupt ({}) and could not be rebuilt: {}",
+            task_id,
+            task.workspace_dir.display(),
+            reason,
+            err
+        ),
+    }
+}
+
+/// Once the reply of a recovered workspace is delivered, the user has been
+/// told about the recovery; acknowledgements of a cross-channel reply do not
+/// count.
+fn note_recovery_delivered(reply: &SendReplyTask) {
+    let Some(workspace) = reply.thread_state_path.as_deref().and_then(Path::parent) else {
+        return;
+    };
+    let is_ack = reply
+        .html_path
+        .file_stem()
+        .is_some_and(|stem| stem == "cross_channel_ack");
+    if is_ack || reply.html_path.parent() != Some(workspace) {
+        return;
+    }
+    if let Err(err) = mark_recovery_notified(workspace) {
+        warn!(
+            "failed to mark recovery notice delivered in {}: {}",
+            workspace.display(),
+            err
+        );
+    }
+}
+
 fn notify_run_task_retry(
     task_id: Uuid,
     task: &RunTaskTask,
@@ -736,6 +1742,34 @@ fn notify_run_task_retry(
     )
 }
 
+/// Report a heartbeat that did not run before its grace period ran out.
+pub(crate) fn notify_missed_heartbeat(
+    owner_id: &str,
+    task_id: &str,
+    name: &str,
+    due_at: DateTime<Utc>,
+    deadline: DateTime<Utc>,
+) -> Result<(), SchedulerError> {
+    let report_body = format!(
+        "<p>Heartbeat missed: the scheduler did not run it before its grace period expired.</p><p>Heartbeat: {}</p><p>Owner: {}</p><p>Task ID: {}</p><p>Due at: {}</p><p>Deadline: {}</p>",
+        escape_html(name),
+        escape_html(owner_id),
+        escape_html(task_id),
+        escape_html(&due_at.to_rfc3339()),
+        escape_html(&deadline.to_rfc3339()),
+    );
+    send_admin_report(
+        format!(
+            "heartbeat_missed_{}_{}.html",
+            task_id,
+            deadline.format("%Y%m%dT%H%M%S")
+        ),
+        format!("Heartbeat missed: {} [{}]", name, owner_id),
+        report_body,
+        &format!("missed heartbeat alert {} for {}", name, owner_id),
+    )
+}
+
 fn build_run_task_report_html(
     headline: &str,
     task_id: Uuid,
@@ -815,7 +1849,7 @@ fn send_admin_report(
     Ok(())
 }
 
-fn escape_html(value: &str) -> String {
+pub(crate) fn escape_html(value: &str) -> String {
     let mut escaped = String::with_capacity(value.len());
     for ch in value.chars() {
         match ch {
@@ -985,6 +2019,18 @@ mod tests {
         assert!(is_codex_stream_disconnect_error(message));
     }
 
+    #[test]
+    fn output_validation_failures_are_detected_through_the_task_error() {
+        let err = SchedulerError::TaskFailed(
+            "Output failed validation: /tmp/ws/reply_email_draft.html\n- reply is empty"
+                .to_string(),
+        );
+        assert!(is_output_validation_failure(&err.to_string()));
+        assert!(!is_output_validation_failure(
+            "task execution failed: Codex failed with status 1"
+        ));
+    }
+
     #[test]
     fn classify_run_task_failure_detects_codex_capacity_message() {
         let message = "The system is currently experiencing high demand and exceeds the maximum usage size allowed during peak load. Consider provisioned throughput.";
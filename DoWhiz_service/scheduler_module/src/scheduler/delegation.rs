@@ -0,0 +1,142 @@
+//! The `delegate` scheduler action and the internal sends that answer it.
+//!
+//! See [`crate::internal_bus`] for the round trip: the request goes out here,
+//! the delegate's reply goes back through [`execute_internal_send`], and the
+//! service resumes this thread when it arrives.
+
+use chrono::{DateTime, Utc};
+use std::fs;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::audit_store::{self, AuditEntry};
+use crate::channel::Channel;
+use crate::internal_bus::{
+    self, InternalCorrelation, InternalMessageKind, PendingDelegation, CORRELATION_FILE,
+};
+
+use super::core::Scheduler;
+use super::executor::TaskExecutor;
+use super::outbound::resolve_employee_profile;
+use super::types::{RunTaskTask, SchedulerError, SendReplyTask};
+
+/// Send `request` to `employee_id` and park `task` until the result comes
+/// back. Returns the correlation id, or why the delegation was skipped.
+pub(super) fn delegate<E: TaskExecutor>(
+    scheduler: &mut Scheduler<E>,
+    task: &RunTaskTask,
+    employee_id: &str,
+    request: &str,
+    now: DateTime<Utc>,
+) -> Result<Uuid, String> {
+    let employee_id = employee_id.trim();
+    let request = request.trim();
+    if request.is_empty() {
+        return Err("request is empty".to_string());
+    }
+    let from = resolve_employee_profile(task.employee_id.as_deref())
+        .ok_or_else(|| "current employee is not configured".to_string())?;
+    if employee_id == from.id {
+        return Err("cannot delegate to the current employee".to_string());
+    }
+    let target = resolve_employee_profile(Some(employee_id))
+        .filter(|profile| profile.id == employee_id)
+        .ok_or_else(|| format!("unknown employee {}", employee_id))?;
+    let requester_user_id = scheduler
+        .store
+        .owner_user()
+        .and_then(|owner| owner.strip_prefix("user:").map(str::to_string))
+        .ok_or_else(|| "only a user's thread can delegate".to_string())?;
+    // The tasks database sits in the user's state dir.
+    let state_dir = scheduler
+        .storage_path
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .ok_or_else(|| "no state dir for this thread".to_string())?;
+
+    let correlation_id = Uuid::new_v4();
+    let pending = PendingDelegation {
+        correlation_id,
+        delegate_employee_id: target.id.clone(),
+        request: request.to_string(),
+        task: task.clone(),
+        created_at: now,
+    };
+    internal_bus::save_pending(&state_dir, &pending).map_err(|err| err.to_string())?;
+    let envelope = internal_bus::internal_envelope(
+        &from.id,
+        &target.id,
+        request,
+        Vec::new(),
+        InternalCorrelation {
+            correlation_id,
+            kind: InternalMessageKind::Request,
+            requester_employee_id: from.id.clone(),
+            requester_user_id,
+            probe: false,
+        },
+        task.trace_id.clone(),
+    );
+    if let Err(err) = internal_bus::send(&envelope) {
+        let _ = internal_bus::take_pending(&state_dir, correlation_id);
+        return Err(err.to_string());
+    }
+    info!(
+        "delegated workspace={} from={} to={} correlation={}",
+        task.workspace_dir.display(),
+        from.id,
+        target.id,
+        correlation_id
+    );
+
+    audit_store::record(AuditEntry {
+        actor: format!("employee:{}", from.id),
+        on_behalf_of: scheduler.store.owner_user(),
+        action: "delegate".to_string(),
+        target: format!("employee:{}", target.id),
+        channel: Some(Channel::Internal.to_string()),
+        payload_hash: audit_store::payload_hash(request.as_bytes()),
+        trace_id: task.trace_id.clone(),
+        task_id: None,
+        recorded_at: now,
+    });
+    Ok(correlation_id)
+}
+
+/// Send a delegate's reply back to the employee that asked, as the result
+/// of the request named in the workspace's correlation file.
+pub(crate) fn execute_internal_send(task: &SendReplyTask) -> Result<(), SchedulerError> {
+    let workspace = task
+        .html_path
+        .parent()
+        .ok_or_else(|| SchedulerError::TaskFailed("reply has no workspace".to_string()))?;
+    let correlation: InternalCorrelation =
+        serde_json::from_slice(&fs::read(workspace.join(CORRELATION_FILE))?).map_err(|err| {
+            SchedulerError::TaskFailed(format!("invalid {}: {}", CORRELATION_FILE, err))
+        })?;
+    let text = fs::read_to_string(&task.html_path)?;
+    let attachments = internal_bus::read_attachments(&task.attachments_dir)
+        .map_err(|err| SchedulerError::TaskFailed(err.to_string()))?;
+    let from = task
+        .employee_id
+        .clone()
+        .ok_or_else(|| SchedulerError::TaskFailed("internal send needs an employee".to_string()))?;
+    let to = correlation.requester_employee_id.clone();
+    let envelope = internal_bus::internal_envelope(
+        &from,
+        &to,
+        &text,
+        attachments,
+        InternalCorrelation {
+            kind: InternalMessageKind::Result,
+            ..correlation
+        },
+        task.trace_id.clone(),
+    );
+    internal_bus::send(&envelope).map_err(|err| SchedulerError::TaskFailed(err.to_string()))?;
+    info!(
+        "internal result sent from={} to={} envelope={}",
+        from, to, envelope.envelope_id
+    );
+    Ok(())
+}
@@ -0,0 +1,382 @@
+//! Daily digests: an account's last 24 hours and next 24 hours, collected
+//! from its scheduler databases and sent straight to the account's preferred
+//! channel.
+
+use chrono::{DateTime, Duration, Utc};
+use std::fs;
+use tracing::info;
+
+use crate::channel::Channel;
+use crate::i18n::{Locale, Message};
+
+use super::core::escape_html;
+use super::outbound::execute_slack_send;
+use super::store::{derive_run_task_summary, SchedulerStore};
+use super::types::{DigestTask, Schedule, ScheduledTask, SchedulerError, SendReplyTask, TaskKind};
+use super::utils::task_kind_channel;
+
+const DIGEST_WINDOW_HOURS: i64 = 24;
+/// Longest list shown per section; the rest is summarized as a count.
+const DIGEST_SECTION_LIMIT: usize = 15;
+
+#[derive(Debug, Default)]
+pub(crate) struct Digest {
+    pub(crate) inbound: Vec<DigestItem>,
+    pub(crate) completed: Vec<DigestItem>,
+    pub(crate) upcoming: Vec<DigestItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DigestItem {
+    pub(crate) at: DateTime<Utc>,
+    pub(crate) channel: Channel,
+    pub(crate) label: String,
+}
+
+impl Digest {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.inbound.is_empty() && self.completed.is_empty() && self.upcoming.is_empty()
+    }
+}
+
+/// Inbound messages and completed tasks from the last 24 hours and tasks due
+/// in the next 24. Heartbeats, digests and health probes are left out.
+pub(crate) fn build_digest(tasks: &[ScheduledTask], now: DateTime<Utc>) -> Digest {
+    let since = now - Duration::hours(DIGEST_WINDOW_HOURS);
+    let until = now + Duration::hours(DIGEST_WINDOW_HOURS);
+    let mut digest = Digest::default();
+    for task in tasks {
+        if matches!(
+            task.kind,
+            TaskKind::Noop(_) | TaskKind::Digest(_) | TaskKind::Probe(_)
+        ) {
+            continue;
+        }
+        let item = |at| DigestItem {
+            at,
+            channel: task_kind_channel(&task.kind),
+            label: digest_label(task),
+        };
+        if let TaskKind::RunTask(run) = &task.kind {
+            if !run.scheduled && task.created_at >= since && task.created_at <= now {
+                digest.inbound.push(item(task.created_at));
+            }
+        }
+        if let Some(last_run) = task.last_run.filter(|at| *at >= since && *at <= now) {
+            digest.completed.push(item(last_run));
+        }
+        let next_run = match &task.schedule {
+            Schedule::Cron { next_run, .. } => *next_run,
+            Schedule::OneShot { run_at } => *run_at,
+        };
+        if task.enabled && next_run > now && next_run <= until {
+            digest.upcoming.push(item(next_run));
+        }
+    }
+    digest
+        .inbound
+        .sort_by_key(|item| std::cmp::Reverse(item.at));
+    digest
+        .completed
+        .sort_by_key(|item| std::cmp::Reverse(item.at));
+    digest.upcoming.sort_by_key(|item| item.at);
+    digest
+}
+
+fn digest_label(task: &ScheduledTask) -> String {
+    if let Some(description) = task
+        .description
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        return description.to_string();
+    }
+    match &task.kind {
+        TaskKind::SendReply(send) if !send.subject.trim().is_empty() => {
+            format!("Reply: {}", send.subject.trim())
+        }
+        TaskKind::SendReply(_) => "Reply".to_string(),
+        TaskKind::RunTask(run) => {
+            derive_run_task_summary(&run.workspace_dir, &run.channel.to_string())
+                .unwrap_or_else(|| "Request".to_string())
+        }
+        TaskKind::Noop(_) | TaskKind::Digest(_) | TaskKind::Probe(_) => String::new(),
+    }
+}
+
+const SECTIONS: [Message; 3] = [
+    Message::DigestInbound,
+    Message::DigestCompleted,
+    Message::DigestUpcoming,
+];
+
+fn sections(digest: &Digest) -> [&[DigestItem]; 3] {
+    [&digest.inbound, &digest.completed, &digest.upcoming]
+}
+
+/// Plain-text rendering, used for Slack.
+pub(crate) fn render_digest_text(digest: &Digest, now: DateTime<Utc>, locale: Locale) -> String {
+    let mut out = format!("*{}*\n", digest_subject(now, locale));
+    for (title, items) in SECTIONS.iter().zip(sections(digest)) {
+        if items.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n*{}* ({})\n", locale.text(*title), items.len()));
+        for item in items.iter().take(DIGEST_SECTION_LIMIT) {
+            out.push_str(&format!(
+                "• {} UTC [{}] {}\n",
+                item.at.format("%b %-d %H:%M"),
+                item.channel,
+                item.label
+            ));
+        }
+        if items.len() > DIGEST_SECTION_LIMIT {
+            out.push_str(&more_line(items.len(), locale));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// HTML rendering, used for email.
+pub(crate) fn render_digest_html(digest: &Digest, now: DateTime<Utc>, locale: Locale) -> String {
+    let mut out = format!("<h2>{}</h2>", escape_html(&digest_subject(now, locale)));
+    for (title, items) in SECTIONS.iter().zip(sections(digest)) {
+        if items.is_empty() {
+            continue;
+        }
+        out.push_str(&format!(
+            "<h3>{} ({})</h3><ul>",
+            escape_html(locale.text(*title)),
+            items.len()
+        ));
+        for item in items.iter().take(DIGEST_SECTION_LIMIT) {
+            out.push_str(&format!(
+                "<li>{} UTC [{}] {}</li>",
+                item.at.format("%b %-d %H:%M"),
+                escape_html(&item.channel.to_string()),
+                escape_html(&item.label)
+            ));
+        }
+        if items.len() > DIGEST_SECTION_LIMIT {
+            out.push_str(&format!(
+                "<li>{}</li>",
+                escape_html(&more_line(items.len(), locale))
+            ));
+        }
+        out.push_str("</ul>");
+    }
+    out
+}
+
+/// "…and N more" for a section of `total` items.
+fn more_line(total: usize, locale: Locale) -> String {
+    locale.render(
+        Message::ListMore,
+        &[("count", &(total - DIGEST_SECTION_LIMIT))],
+    )
+}
+
+fn digest_subject(now: DateTime<Utc>, locale: Locale) -> String {
+    locale.render(
+        Message::DigestSubject,
+        &[("date", &locale.format_date(now))],
+    )
+}
+
+/// Build the digest for `task` and send it. Returns false when nothing
+/// happened and nothing is coming up, in which case no digest is sent.
+pub(crate) fn deliver_digest(
+    task: &DigestTask,
+    now: DateTime<Utc>,
+) -> Result<bool, SchedulerError> {
+    let mut tasks = Vec::new();
+    for source in &task.sources {
+        tasks.extend(SchedulerStore::new(source.clone())?.load_tasks()?);
+    }
+    let digest = build_digest(&tasks, now);
+    if digest.is_empty() {
+        info!(
+            "digest for account {} skipped: no activity",
+            task.account_id
+        );
+        return Ok(false);
+    }
+
+    let locale = task
+        .language
+        .as_deref()
+        .and_then(Locale::parse)
+        .unwrap_or_default();
+    fs::create_dir_all(&task.output_dir)?;
+    let stem = format!("digest_{}", now.format("%Y%m%d"));
+    let attachments_dir = task.output_dir.join(format!("{}_attachments", stem));
+    fs::create_dir_all(&attachments_dir)?;
+    let subject = digest_subject(now, locale);
+    match task.channel {
+        Channel::Slack => {
+            let text_path = task.output_dir.join(format!("{}.txt", stem));
+            fs::write(&text_path, render_digest_text(&digest, now, locale))?;
+            execute_slack_send(&SendReplyTask {
+                channel: Channel::Slack,
+                subject,
+                html_path: text_path,
+                attachments_dir,
+                from: None,
+                to: vec![task.recipient.clone()],
+                cc: vec![],
+                bcc: vec![],
+                in_reply_to: None,
+                references: None,
+                archive_root: None,
+                thread_epoch: None,
+                thread_state_path: None,
+                employee_id: task.employee_id.clone(),
+                idempotency_key: None,
+                trace_id: None,
+                scheduled: false,
+            })?;
+        }
+        Channel::Email => {
+            let html_path = task.output_dir.join(format!("{}.html", stem));
+            fs::write(&html_path, render_digest_html(&digest, now, locale))?;
+            let from = std::env::var("ADMIN_EMAIL")
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| {
+                    SchedulerError::TaskFailed("from address missing for digest".to_string())
+                })?;
+            let params = send_emails_module::SendEmailParams {
+                subject,
+                html_path,
+                attachments_dir,
+                from: Some(from),
+                to: vec![task.recipient.clone()],
+                cc: vec![],
+                bcc: vec![],
+                in_reply_to: None,
+                references: None,
+                reply_to: None,
+            };
+            send_emails_module::send_email(&params)
+                .map_err(|err| SchedulerError::TaskFailed(err.to_string()))?;
+        }
+        ref other => {
+            return Err(SchedulerError::TaskFailed(format!(
+                "digests cannot be sent on {}",
+                other
+            )))
+        }
+    }
+    info!(
+        "digest for account {} sent on {} inbound={} completed={} upcoming={}",
+        task.account_id,
+        task.channel,
+        digest.inbound.len(),
+        digest.completed.len(),
+        digest.upcoming.len()
+    );
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::tests::run_task_in;
+    use crate::scheduler::types::{NoopTask, RunTaskTask};
+    use std::path::Path;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn run_task(workspace: &Path, scheduled: bool) -> TaskKind {
+        TaskKind::RunTask(RunTaskTask {
+            scheduled,
+            ..run_task_in(workspace)
+        })
+    }
+
+    fn task(kind: TaskKind, schedule: Schedule, created_at: DateTime<Utc>) -> ScheduledTask {
+        ScheduledTask {
+            id: Uuid::new_v4(),
+            kind,
+            schedule,
+            enabled: true,
+            created_at,
+            last_run: None,
+            approval: None,
+            description: None,
+            ends: None,
+        }
+    }
+
+    #[test]
+    fn digest_covers_the_last_and_next_day() {
+        let temp = TempDir::new().unwrap();
+        let now = Utc::now();
+        let hours = Duration::hours;
+
+        let mut answered = task(
+            run_task(temp.path(), false),
+            Schedule::OneShot {
+                run_at: now - hours(3),
+            },
+            now - hours(3),
+        );
+        answered.enabled = false;
+        answered.last_run = Some(now - hours(2));
+        answered.description = Some("Draft the Q3 plan".to_string());
+
+        let mut old = task(
+            run_task(temp.path(), false),
+            Schedule::OneShot {
+                run_at: now - hours(40),
+            },
+            now - hours(40),
+        );
+        old.enabled = false;
+        old.last_run = Some(now - hours(39));
+
+        let mut standup = task(
+            run_task(temp.path(), true),
+            Schedule::Cron {
+                expression: "0 0 9 * * *".to_string(),
+                next_run: now + hours(5),
+            },
+            now - hours(100),
+        );
+        standup.description = Some("Standup notes".to_string());
+        let next_week = task(
+            run_task(temp.path(), true),
+            Schedule::OneShot {
+                run_at: now + hours(24 * 7),
+            },
+            now - hours(1),
+        );
+        let heartbeat = task(
+            TaskKind::Noop(NoopTask::default()),
+            Schedule::OneShot {
+                run_at: now + hours(1),
+            },
+            now - hours(1),
+        );
+
+        let digest = build_digest(&[answered, old, standup, next_week, heartbeat], now);
+        assert_eq!(digest.inbound.len(), 1);
+        assert_eq!(digest.inbound[0].label, "Draft the Q3 plan");
+        assert_eq!(digest.completed.len(), 1);
+        assert_eq!(digest.upcoming.len(), 1);
+        assert_eq!(digest.upcoming[0].label, "Standup notes");
+
+        let text = render_digest_text(&digest, now, Locale::En);
+        assert!(text.contains("*Messages received* (1)"));
+        assert!(text.contains("[email] Standup notes"));
+        let html = render_digest_html(&digest, now, Locale::En);
+        assert!(html.contains("<h3>Coming up in the next 24 hours (1)</h3>"));
+        let html = render_digest_html(&digest, now, Locale::Fr);
+        assert!(html.contains("<h3>Dans les prochaines 24 heures (1)</h3>"));
+
+        assert!(build_digest(&[], now).is_empty());
+    }
+}
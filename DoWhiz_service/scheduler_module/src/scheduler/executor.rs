@@ -4,15 +4,16 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::JoinHandle;
-use std::time::Duration;
-use tracing::{info, warn};
+use std::time::{Duration, Instant};
+use tracing::{info, info_span, warn};
 
 use crate::account_store::{
     get_global_account_store, lookup_account_by_channel, lookup_account_by_identifier,
     AccountIdentifier, AnalyticsEventInsert,
 };
+use crate::adapters::slack::SlackOutboundAdapter;
 use crate::blob_store::get_blob_store;
-use crate::channel::Channel;
+use crate::channel::{AdapterError, Channel, MessageRef, OutboundAdapter};
 use crate::github_inbound::{
     extract_github_sender_login_from_postmark_payload, is_github_notifications_postmark_payload,
 };
@@ -25,9 +26,11 @@ use crate::memory_store::{
 use crate::secrets_store::{
     resolve_user_secrets_path, sync_user_secrets_to_workspace, sync_workspace_secrets_to_user,
 };
+use crate::task_costs::{self, TaskCostRecord};
+use crate::telemetry;
 use crate::thread_state::{current_thread_epoch, find_thread_state_path};
-use crate::user_store::lookup_user_id_by_identifier;
-use run_task_module::UserIdentities;
+use crate::user_store::{lookup_user_id_by_identifier, lookup_user_preferences};
+use run_task_module::{ReplyPreferences, RunTaskOutput, UserIdentities};
 use uuid::Uuid;
 
 /// Sync memo from Azure Blob to workspace directory.
@@ -78,10 +81,13 @@ fn sync_blob_memo_to_workspace(account_id: Uuid, workspace_memory_dir: &Path) ->
     Some(memo_content)
 }
 
+use super::checkpoint::{self, RunCheckpoint, RunStage};
+use super::delegation::execute_internal_send;
 use super::outbound::{
-    execute_bluebubbles_send, execute_discord_send, execute_email_send, execute_google_docs_send,
-    execute_notion_send, execute_slack_send, execute_sms_send, execute_telegram_send,
-    execute_wechat_send, execute_whatsapp_send,
+    enforce_outbound_policy, execute_bluebubbles_send, execute_discord_send, execute_email_send,
+    execute_google_docs_send, execute_jira_send, execute_notion_send, execute_slack_send,
+    execute_sms_send, execute_telegram_send, execute_wechat_send, execute_whatsapp_send,
+    resolve_employee_profile,
 };
 use super::types::{SchedulerError, SendReplyTask, TaskExecution, TaskKind};
 use super::utils::load_google_access_token_from_service_env;
@@ -157,6 +163,49 @@ fn run_task_dedupe_key(task: &super::types::RunTaskTask) -> String {
     )
 }
 
+/// Model output an earlier attempt of this run left in its checkpoint, if it
+/// can still be used. Otherwise the checkpoint drops back to before the model
+/// run so it is redone.
+fn resumable_output(
+    checkpoint: &mut RunCheckpoint,
+    task: &super::types::RunTaskTask,
+) -> Option<RunTaskOutput> {
+    if !checkpoint.reached(RunStage::ModelCompleted) {
+        return None;
+    }
+    match checkpoint.output.clone() {
+        Some(output) if task.reply_to.is_empty() || output.reply_html_path.exists() => Some(output),
+        _ => {
+            warn!(
+                "checkpoint for {} has no usable model output; running the model again",
+                task.workspace_dir.display()
+            );
+            checkpoint.stage = RunStage::WorkspacePrepared;
+            checkpoint.output = None;
+            None
+        }
+    }
+}
+
+/// Record a finished stage when the scheduler began a checkpoint for this run.
+fn advance_checkpoint(
+    checkpoint: &mut Option<RunCheckpoint>,
+    task: &super::types::RunTaskTask,
+    stage: RunStage,
+) {
+    let Some(checkpoint) = checkpoint.as_mut() else {
+        return;
+    };
+    if let Err(err) = checkpoint.advance(&task.workspace_dir, stage) {
+        warn!(
+            "failed to record checkpoint stage {:?} for {}: {}",
+            stage,
+            task.workspace_dir.display(),
+            err
+        );
+    }
+}
+
 fn track_scheduler_event(
     event_name: &str,
     account_id: Uuid,
@@ -332,6 +381,37 @@ fn fetch_user_identities(account_id: Option<Uuid>) -> UserIdentities {
     identifiers_to_user_identities(account_id, &identifiers)
 }
 
+/// Reply language and preferred channel of the user who sent the request,
+/// plus the employee's formatting for the task's channel.
+fn fetch_reply_preferences(task: &super::types::RunTaskTask) -> ReplyPreferences {
+    let formatting = resolve_employee_profile(task.employee_id.as_deref())
+        .map(|profile| profile.formatting.for_channel(task.channel))
+        .unwrap_or_default();
+    let (Some(identifier_type), Some(identifier)) = (
+        task.requester_identifier_type.as_deref(),
+        task.requester_identifier.as_deref(),
+    ) else {
+        return ReplyPreferences {
+            formatting,
+            ..Default::default()
+        };
+    };
+    let Some(user_id) = lookup_user_id_by_identifier(identifier_type, identifier) else {
+        return ReplyPreferences {
+            formatting,
+            ..Default::default()
+        };
+    };
+    let preferences = lookup_user_preferences(&user_id);
+    ReplyPreferences {
+        preferred_channel: preferences
+            .preferred_channel
+            .map(|channel| channel.to_string()),
+        language: preferences.language,
+        formatting,
+    }
+}
+
 /// Convert account identifiers to UserIdentities struct for cross-channel routing.
 fn identifiers_to_user_identities(
     account_id: Uuid,
@@ -498,14 +578,18 @@ fn write_insufficient_balance_notice_body(
     Ok(path)
 }
 
-fn dispatch_send_reply_task(task: &SendReplyTask) -> Result<(), SchedulerError> {
+/// Send `task` on its channel; returns the provider's message ID when the
+/// channel reports delivery outcomes (email).
+fn dispatch_send_reply_task(task: &SendReplyTask) -> Result<Option<String>, SchedulerError> {
     if let Some(expected_epoch) = task.thread_epoch {
         let state_path = task
             .thread_state_path
             .clone()
             .or_else(|| task.html_path.parent().and_then(find_thread_state_path));
         if let Some(state_path) = state_path {
-            if let Some(current_epoch) = current_thread_epoch(&state_path) {
+            // An unreadable epoch fails the send, which is retried, rather
+            // than risking a stale reply.
+            if let Some(current_epoch) = current_thread_epoch(&state_path)? {
                 if current_epoch != expected_epoch {
                     info!(
                         "skip stale send_email (expected epoch {}, current {}) for {}",
@@ -513,46 +597,39 @@ fn dispatch_send_reply_task(task: &SendReplyTask) -> Result<(), SchedulerError>
                         current_epoch,
                         task.html_path.display()
                     );
-                    return Ok(());
+                    return Ok(None);
                 }
             }
         }
     }
 
-    match task.channel {
+    let _span = info_span!("outbound.send", channel = %task.channel).entered();
+    enforce_outbound_policy(task)?;
+    let started = Instant::now();
+    let mut provider_message_id = None;
+    let result = match task.channel {
         Channel::Slack => {
             delete_slack_working_placeholder_before_send(task);
-            execute_slack_send(task)?;
-        }
-        Channel::Discord => {
-            execute_discord_send(task)?;
+            execute_slack_send(task)
         }
+        Channel::Discord => execute_discord_send(task),
         Channel::GoogleDocs | Channel::GoogleSheets | Channel::GoogleSlides => {
-            execute_google_docs_send(task)?;
-        }
-        Channel::Sms => {
-            execute_sms_send(task)?;
-        }
-        Channel::BlueBubbles => {
-            execute_bluebubbles_send(task)?;
-        }
-        Channel::Telegram => {
-            execute_telegram_send(task)?;
-        }
-        Channel::WhatsApp => {
-            execute_whatsapp_send(task)?;
-        }
-        Channel::WeChat => {
-            execute_wechat_send(task)?;
-        }
-        Channel::Email => {
-            execute_email_send(task)?;
+            execute_google_docs_send(task)
         }
-        Channel::Notion => {
-            execute_notion_send(task)?;
-        }
-    }
-    Ok(())
+        Channel::Sms => execute_sms_send(task),
+        Channel::BlueBubbles => execute_bluebubbles_send(task),
+        Channel::Telegram => execute_telegram_send(task),
+        Channel::WhatsApp => execute_whatsapp_send(task),
+        Channel::WeChat => execute_wechat_send(task),
+        Channel::Jira => execute_jira_send(task),
+        Channel::Internal => execute_internal_send(task),
+        Channel::Email => execute_email_send(task).map(|message_id| {
+            provider_message_id = Some(message_id);
+        }),
+        Channel::Notion => execute_notion_send(task),
+    };
+    telemetry::record_outbound_send(task.channel, started.elapsed(), result.is_ok());
+    result.map(|()| provider_message_id)
 }
 
 fn send_insufficient_balance_notice(
@@ -592,6 +669,9 @@ fn send_insufficient_balance_notice(
         thread_epoch: task.thread_epoch,
         thread_state_path: task.thread_state_path.clone(),
         employee_id: task.employee_id.clone(),
+        idempotency_key: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     dispatch_send_reply_task(&send_task)?;
@@ -880,77 +960,21 @@ fn delete_slack_working_placeholder_before_send(task: &SendReplyTask) {
         return;
     };
 
-    let api_base =
-        std::env::var("SLACK_API_BASE_URL").unwrap_or_else(|_| "https://slack.com/api".to_string());
-    let url = format!("{}/chat.delete", api_base.trim_end_matches('/'));
-    let request = serde_json::json!({
-        "channel": channel_id,
-        "ts": message_ts
-    });
-
-    let client = reqwest::blocking::Client::new();
-    let response = match client
-        .post(&url)
-        .header("Authorization", format!("Bearer {}", bot_token))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-    {
-        Ok(response) => response,
-        Err(err) => {
-            warn!(
-                "failed to delete slack working placeholder for {}: {}",
-                task.html_path.display(),
-                err
-            );
-            return;
-        }
-    };
-
-    let status = response.status();
-    let body = match response.text() {
-        Ok(value) => value,
-        Err(err) => {
-            warn!(
-                "failed reading slack delete placeholder response for {}: {}",
-                task.html_path.display(),
-                err
-            );
-            return;
-        }
+    let adapter = SlackOutboundAdapter::new(bot_token);
+    let placeholder = MessageRef {
+        channel_id,
+        message_id: message_ts,
     };
-    if !status.is_success() {
-        warn!(
-            "slack delete placeholder failed for {} status={} body={}",
-            task.html_path.display(),
-            status,
-            body
-        );
-        return;
-    }
-
-    let payload: serde_json::Value = match serde_json::from_str(&body) {
-        Ok(value) => value,
-        Err(err) => {
-            warn!(
-                "failed parsing slack delete placeholder response for {}: {}",
-                task.html_path.display(),
-                err
-            );
-            return;
+    match adapter.delete(&placeholder) {
+        Ok(()) => clear_slack_placeholder_marker(&marker_path),
+        Err(AdapterError::SendError(error)) if error == "message_not_found" => {
+            clear_slack_placeholder_marker(&marker_path)
         }
-    };
-
-    let ok = payload.get("ok").and_then(|value| value.as_bool()) == Some(true);
-    let error = payload.get("error").and_then(|value| value.as_str());
-    if ok || matches!(error, Some("message_not_found")) {
-        clear_slack_placeholder_marker(&marker_path);
-    } else {
-        warn!(
-            "slack delete placeholder API error for {}: {}",
+        Err(err) => warn!(
+            "slack delete placeholder failed for {}: {}",
             task.html_path.display(),
-            error.unwrap_or("unknown")
-        );
+            err
+        ),
     }
 }
 
@@ -1072,10 +1096,10 @@ pub struct ModuleExecutor;
 impl TaskExecutor for ModuleExecutor {
     fn execute(&self, task: &TaskKind) -> Result<TaskExecution, SchedulerError> {
         match task {
-            TaskKind::SendReply(task) => {
-                dispatch_send_reply_task(task)?;
-                Ok(TaskExecution::empty())
-            }
+            TaskKind::SendReply(task) => Ok(TaskExecution {
+                provider_message_id: dispatch_send_reply_task(task)?,
+                ..TaskExecution::empty()
+            }),
             TaskKind::RunTask(task) => {
                 let github_inbound = load_github_inbound_context(task);
                 let account_id =
@@ -1143,14 +1167,30 @@ impl TaskExecutor for ModuleExecutor {
                     track_task_start_markers(account_id, task, &task_dedupe_key);
                 }
 
+                let mut checkpoint = checkpoint::load(&task.workspace_dir);
+                let resumed_output = checkpoint
+                    .as_mut()
+                    .and_then(|checkpoint| resumable_output(checkpoint, task));
+                let stage = checkpoint
+                    .as_ref()
+                    .map_or(RunStage::Started, |checkpoint| checkpoint.stage);
+
                 let workspace_memory_dir = task.workspace_dir.join(&task.memory_dir);
                 let user_memory_dir = resolve_user_memory_dir(task);
                 let user_secrets_path = resolve_user_secrets_path(task);
                 let _typing_heartbeat = DiscordTypingHeartbeat::start(task);
-                post_slack_working_placeholder(task);
+                if resumed_output.is_none() {
+                    post_slack_working_placeholder(task);
+                }
 
                 // Sync memo to workspace: prefer Azure Blob if account exists, else local storage
-                let original_memo_snapshot = if let Some(account_id) = account_id {
+                let original_memo_snapshot = if resumed_output.is_some() {
+                    // The workspace memo already holds the model's edits; keep
+                    // the memo it started from as the diff base.
+                    checkpoint
+                        .as_ref()
+                        .and_then(|checkpoint| checkpoint.memo_snapshot.clone())
+                } else if let Some(account_id) = account_id {
                     // User has a unified account - try to sync from Azure Blob
                     info!(
                         "Found unified account {} for channel {:?}, syncing from Azure Blob",
@@ -1216,163 +1256,254 @@ impl TaskExecutor for ModuleExecutor {
                     }
                     snapshot
                 };
-                if let Some(user_secrets_path) = user_secrets_path.as_ref() {
-                    sync_user_secrets_to_workspace(user_secrets_path, &task.workspace_dir)
-                        .map_err(|err| {
+                if resumed_output.is_none() {
+                    if let Some(user_secrets_path) = user_secrets_path.as_ref() {
+                        sync_user_secrets_to_workspace(user_secrets_path, &task.workspace_dir)
+                            .map_err(|err| {
+                                if let Some(account_id) = account_id {
+                                    track_scheduler_event(
+                                        "task_failed",
+                                        account_id,
+                                        Some(format!(
+                                            "task_failed:{}:secrets_sync_to_workspace",
+                                            task_dedupe_key
+                                        )),
+                                        task,
+                                        json!({
+                                            "error_reason": "secrets_sync_to_workspace_failed",
+                                            "error": err.to_string(),
+                                            "channel": task.channel.to_string(),
+                                        }),
+                                    );
+                                }
+                                SchedulerError::TaskFailed(format!("secrets sync failed: {}", err))
+                            })?;
+                    } else {
+                        warn!(
+                            "unable to resolve user secrets for workspace {}",
+                            task.workspace_dir.display()
+                        );
+                    }
+                }
+                if stage < RunStage::WorkspacePrepared {
+                    crate::attachment_vision::preprocess_image_attachments(
+                        &task.workspace_dir.join(&task.input_attachments_dir),
+                    );
+                    crate::link_fetch::fetch_message_links(
+                        &task.workspace_dir.join(&task.input_email_dir),
+                        &task.workspace_dir.join(&task.reference_dir),
+                    );
+                    if let Err(err) = crate::tools::search::write_tools_config(&task.workspace_dir)
+                    {
+                        warn!(
+                            "failed to write tools config for workspace {}: {}",
+                            task.workspace_dir.display(),
+                            err
+                        );
+                    }
+                }
+                if resumed_output.is_none() {
+                    if let Some(checkpoint) = checkpoint.as_mut() {
+                        checkpoint.memo_snapshot = original_memo_snapshot.clone();
+                    }
+                    advance_checkpoint(&mut checkpoint, task, RunStage::WorkspacePrepared);
+                }
+                let output = match resumed_output {
+                    Some(output) => {
+                        info!(
+                            "reusing checkpointed run_task output for {}",
+                            task.workspace_dir.display()
+                        );
+                        output
+                    }
+                    None => {
+                        let user_identities = fetch_user_identities(account_id);
+                        let params = run_task_module::RunTaskParams {
+                            workspace_dir: task.workspace_dir.clone(),
+                            input_email_dir: task.input_email_dir.clone(),
+                            input_attachments_dir: task.input_attachments_dir.clone(),
+                            memory_dir: task.memory_dir.clone(),
+                            reference_dir: task.reference_dir.clone(),
+                            reply_to: task.reply_to.clone(),
+                            model_name: task.model_name.clone(),
+                            runner: task.runner.clone(),
+                            codex_disabled: task.codex_disabled,
+                            channel: task.channel.to_string(),
+                            google_access_token: load_google_access_token_from_service_env(),
+                            has_unified_account: account_id.is_some(),
+                            user_identities,
+                            reply_preferences: fetch_reply_preferences(task),
+                            trace_id: task.trace_id.clone(),
+                            sandbox: resolve_employee_profile(task.employee_id.as_deref())
+                                .and_then(|profile| profile.sandbox),
+                        };
+                        let runner_started = Instant::now();
+                        let output = {
+                            let _span = info_span!(
+                                "run_task.runner",
+                                runner = %task.runner,
+                                channel = %task.channel
+                            )
+                            .entered();
+                            run_task_module::run_task(&params)
+                        };
+                        telemetry::record_runner_run(
+                            &task.runner,
+                            runner_started.elapsed(),
+                            output.is_ok(),
+                        );
+                        let output = output.map_err(|err| {
                             if let Some(account_id) = account_id {
                                 track_scheduler_event(
                                     "task_failed",
                                     account_id,
-                                    Some(format!(
-                                        "task_failed:{}:secrets_sync_to_workspace",
-                                        task_dedupe_key
-                                    )),
+                                    Some(format!("task_failed:{}:run_task", task_dedupe_key)),
                                     task,
                                     json!({
-                                        "error_reason": "secrets_sync_to_workspace_failed",
+                                        "error_reason": "run_task_failed",
                                         "error": err.to_string(),
                                         "channel": task.channel.to_string(),
                                     }),
                                 );
                             }
-                            SchedulerError::TaskFailed(format!("secrets sync failed: {}", err))
+                            SchedulerError::TaskFailed(err.to_string())
                         })?;
-                } else {
-                    warn!(
-                        "unable to resolve user secrets for workspace {}",
-                        task.workspace_dir.display()
-                    );
-                }
-                let user_identities = fetch_user_identities(account_id);
-                let params = run_task_module::RunTaskParams {
-                    workspace_dir: task.workspace_dir.clone(),
-                    input_email_dir: task.input_email_dir.clone(),
-                    input_attachments_dir: task.input_attachments_dir.clone(),
-                    memory_dir: task.memory_dir.clone(),
-                    reference_dir: task.reference_dir.clone(),
-                    reply_to: task.reply_to.clone(),
-                    model_name: task.model_name.clone(),
-                    runner: task.runner.clone(),
-                    codex_disabled: task.codex_disabled,
-                    channel: task.channel.to_string(),
-                    google_access_token: load_google_access_token_from_service_env(),
-                    has_unified_account: account_id.is_some(),
-                    user_identities,
-                };
-                let output = run_task_module::run_task(&params).map_err(|err| {
-                    if let Some(account_id) = account_id {
-                        track_scheduler_event(
-                            "task_failed",
-                            account_id,
-                            Some(format!("task_failed:{}:run_task", task_dedupe_key)),
-                            task,
-                            json!({
-                                "error_reason": "run_task_failed",
-                                "error": err.to_string(),
-                                "channel": task.channel.to_string(),
-                            }),
-                        );
+                        if let Some(checkpoint) = checkpoint.as_mut() {
+                            checkpoint.output = Some(output.clone());
+                        }
+                        advance_checkpoint(&mut checkpoint, task, RunStage::ModelCompleted);
+                        output
                     }
-                    SchedulerError::TaskFailed(err.to_string())
-                })?;
+                };
 
-                // Track token usage for accounts
-                if let Some(account_id) = account_id {
-                    if let Some(ref usage) = output.token_usage {
-                        let total_tokens = (usage.input_tokens + usage.output_tokens) as i64;
-                        if let Some(store) = get_global_account_store() {
-                            if let Err(e) = store.add_tokens(account_id, total_tokens) {
-                                warn!(
-                                    "Failed to update token usage for account {}: {}",
-                                    account_id, e
-                                );
-                            } else {
-                                info!(
-                                    "Recorded {} tokens for account {} (input: {}, output: {})",
-                                    total_tokens,
-                                    account_id,
-                                    usage.input_tokens,
-                                    usage.output_tokens
-                                );
+                if stage < RunStage::ResultsSynced {
+                    // Track token usage for accounts
+                    if let Some(account_id) = account_id {
+                        if let Some(ref usage) = output.token_usage {
+                            let total_tokens = (usage.input_tokens + usage.output_tokens) as i64;
+                            if let Some(store) = get_global_account_store() {
+                                if let Err(e) = store.add_tokens(account_id, total_tokens) {
+                                    warn!(
+                                        "Failed to update token usage for account {}: {}",
+                                        account_id, e
+                                    );
+                                } else {
+                                    info!(
+                                        "Recorded {} tokens for account {} (input: {}, output: {})",
+                                        total_tokens,
+                                        account_id,
+                                        usage.input_tokens,
+                                        usage.output_tokens
+                                    );
+                                }
                             }
                         }
                     }
-                }
 
-                // After task completes, compute diff and submit to queue instead of direct sync
-                if let Some(user_memory_dir) = user_memory_dir.as_ref() {
-                    if let Some(original_content) = original_memo_snapshot {
-                        // Read modified workspace memo
-                        if let Some(modified_content) = read_memo_content(&workspace_memory_dir) {
-                            let diff = compute_memory_diff(&original_content, &modified_content);
-                            if !diff.is_empty() {
-                                // Extract user_id from path: users/{user_id}/memory
-                                let user_id = user_memory_dir
-                                    .parent()
-                                    .and_then(|p| p.file_name())
-                                    .and_then(|n| n.to_str())
-                                    .unwrap_or("unknown")
-                                    .to_string();
-
-                                let request = MemoryWriteRequest {
-                                    account_id,
-                                    user_id: user_id.clone(),
-                                    user_memory_dir: user_memory_dir.clone(),
-                                    diff,
-                                };
+                    if !output.output_issues.is_empty() {
+                        warn!(
+                            "run_task output issues workspace={}: {}",
+                            task.workspace_dir.display(),
+                            output.output_issues.join("; ")
+                        );
+                    }
 
-                                // Submit to queue - blocks until worker applies the diff
-                                if let Err(e) = global_memory_queue().submit(request) {
-                                    warn!(
-                                        "Failed to submit memory diff to queue for user {}: {}",
-                                        user_id, e
-                                    );
-                                    // Fall back to direct sync on queue failure
-                                    if let Err(e) =
-                                        crate::memory_store::sync_workspace_memory_to_user(
-                                            &workspace_memory_dir,
-                                            user_memory_dir,
-                                        )
-                                    {
-                                        warn!("Fallback memory sync also failed: {}", e);
+                    let mut cost = match output.token_usage.as_ref() {
+                        Some(usage) => {
+                            TaskCostRecord::from_usage(usage, &task.runner, &task.model_name)
+                        }
+                        None => TaskCostRecord::unmetered(&task.runner, &task.model_name),
+                    };
+                    cost.account_id = account_id;
+                    cost.user_id = user_memory_dir
+                        .as_ref()
+                        .and_then(|dir| dir.parent())
+                        .and_then(|root| root.file_name())
+                        .and_then(|name| name.to_str())
+                        .map(str::to_string);
+                    cost.employee_id = task.employee_id.clone();
+                    cost.channel = task.channel.to_string();
+                    cost.trace_id = task.trace_id.clone();
+                    task_costs::record(cost);
+
+                    // After task completes, compute diff and submit to queue instead of direct sync
+                    if let Some(user_memory_dir) = user_memory_dir.as_ref() {
+                        if let Some(original_content) = original_memo_snapshot {
+                            // Read modified workspace memo
+                            if let Some(modified_content) = read_memo_content(&workspace_memory_dir)
+                            {
+                                let diff =
+                                    compute_memory_diff(&original_content, &modified_content);
+                                if !diff.is_empty() {
+                                    // Extract user_id from path: users/{user_id}/memory
+                                    let user_id = user_memory_dir
+                                        .parent()
+                                        .and_then(|p| p.file_name())
+                                        .and_then(|n| n.to_str())
+                                        .unwrap_or("unknown")
+                                        .to_string();
+
+                                    let request = MemoryWriteRequest {
+                                        account_id,
+                                        user_id: user_id.clone(),
+                                        user_memory_dir: user_memory_dir.clone(),
+                                        diff,
+                                    };
+
+                                    // Submit to queue - blocks until worker applies the diff
+                                    if let Err(e) = global_memory_queue().submit(request) {
+                                        warn!(
+                                            "Failed to submit memory diff to queue for user {}: {}",
+                                            user_id, e
+                                        );
+                                        // Fall back to direct sync on queue failure
+                                        if let Err(e) =
+                                            crate::memory_store::sync_workspace_memory_to_user(
+                                                &workspace_memory_dir,
+                                                user_memory_dir,
+                                            )
+                                        {
+                                            warn!("Fallback memory sync also failed: {}", e);
+                                        }
                                     }
                                 }
+                                // If diff is empty, no changes to sync
+                            }
+                        } else {
+                            // No snapshot available, fall back to direct sync
+                            warn!("No original memo snapshot, falling back to direct sync");
+                            if let Err(e) = crate::memory_store::sync_workspace_memory_to_user(
+                                &workspace_memory_dir,
+                                user_memory_dir,
+                            ) {
+                                warn!("Memory sync failed: {}", e);
                             }
-                            // If diff is empty, no changes to sync
-                        }
-                    } else {
-                        // No snapshot available, fall back to direct sync
-                        warn!("No original memo snapshot, falling back to direct sync");
-                        if let Err(e) = crate::memory_store::sync_workspace_memory_to_user(
-                            &workspace_memory_dir,
-                            user_memory_dir,
-                        ) {
-                            warn!("Memory sync failed: {}", e);
                         }
                     }
-                }
 
-                if let Some(user_secrets_path) = user_secrets_path.as_ref() {
-                    sync_workspace_secrets_to_user(&task.workspace_dir, user_secrets_path)
-                        .map_err(|err| {
-                            if let Some(account_id) = account_id {
-                                track_scheduler_event(
-                                    "task_failed",
-                                    account_id,
-                                    Some(format!(
-                                        "task_failed:{}:secrets_sync_to_user",
-                                        task_dedupe_key
-                                    )),
-                                    task,
-                                    json!({
-                                        "error_reason": "secrets_sync_to_user_failed",
-                                        "error": err.to_string(),
-                                        "channel": task.channel.to_string(),
-                                    }),
-                                );
-                            }
-                            SchedulerError::TaskFailed(format!("secrets sync failed: {}", err))
-                        })?;
+                    if let Some(user_secrets_path) = user_secrets_path.as_ref() {
+                        sync_workspace_secrets_to_user(&task.workspace_dir, user_secrets_path)
+                            .map_err(|err| {
+                                if let Some(account_id) = account_id {
+                                    track_scheduler_event(
+                                        "task_failed",
+                                        account_id,
+                                        Some(format!(
+                                            "task_failed:{}:secrets_sync_to_user",
+                                            task_dedupe_key
+                                        )),
+                                        task,
+                                        json!({
+                                            "error_reason": "secrets_sync_to_user_failed",
+                                            "error": err.to_string(),
+                                            "channel": task.channel.to_string(),
+                                        }),
+                                    );
+                                }
+                                SchedulerError::TaskFailed(format!("secrets sync failed: {}", err))
+                            })?;
+                    }
+                    advance_checkpoint(&mut checkpoint, task, RunStage::ResultsSynced);
                 }
                 if let Some(account_id) = account_id {
                     track_task_success_markers(account_id, task, &task_dedupe_key);
@@ -1383,9 +1514,19 @@ impl TaskExecutor for ModuleExecutor {
                     scheduler_actions: output.scheduler_actions,
                     scheduler_actions_error: output.scheduler_actions_error,
                     skip_auto_reply: false,
+                    provider_message_id: None,
                 })
             }
-            TaskKind::Noop => Ok(TaskExecution::empty()),
+            TaskKind::Noop(_) => Ok(TaskExecution::empty()),
+            TaskKind::Digest(task) => {
+                super::digest::deliver_digest(task, Utc::now())?;
+                Ok(TaskExecution::empty())
+            }
+            TaskKind::Probe(task) => {
+                crate::health_probe::run_probe(task, Utc::now())
+                    .map_err(|err| SchedulerError::TaskFailed(err.to_string()))?;
+                Ok(TaskExecution::empty())
+            }
         }
     }
 }
@@ -1421,6 +1562,8 @@ mod tests {
             requester_identifier_type: None,
             requester_identifier: None,
             account_id: None,
+            trace_id: None,
+            scheduled: false,
         }
     }
 
@@ -1446,6 +1589,8 @@ mod tests {
             requester_identifier_type: None,
             requester_identifier: None,
             account_id: None,
+            trace_id: None,
+            scheduled: false,
         }
     }
 
@@ -1516,6 +1661,39 @@ mod tests {
         assert!(workspace.join("reply_email_attachments").is_dir());
     }
 
+    #[test]
+    fn resumable_output_requires_the_checkpointed_reply() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspace = temp.path().to_path_buf();
+        let task = sample_email_task(workspace.clone());
+        let mut checkpoint = checkpoint::begin(&workspace, "task").expect("begin");
+        checkpoint.output = Some(RunTaskOutput {
+            reply_html_path: workspace.join("reply_email_draft.html"),
+            reply_attachments_dir: workspace.join("reply_email_attachments"),
+            codex_output: "done".to_string(),
+            scheduled_tasks: Vec::new(),
+            scheduled_tasks_error: None,
+            scheduler_actions: Vec::new(),
+            scheduler_actions_error: None,
+            token_usage: None,
+            output_issues: Vec::new(),
+        });
+        checkpoint
+            .advance(&workspace, RunStage::ModelCompleted)
+            .expect("advance");
+
+        fs::write(workspace.join("reply_email_draft.html"), "<p>Hi</p>").expect("reply");
+        let mut resumed = checkpoint.clone();
+        assert!(resumable_output(&mut resumed, &task).is_some());
+        assert_eq!(resumed.stage, RunStage::ModelCompleted);
+
+        fs::remove_file(workspace.join("reply_email_draft.html")).expect("remove reply");
+        let mut rerun = checkpoint.clone();
+        assert!(resumable_output(&mut rerun, &task).is_none());
+        assert_eq!(rerun.stage, RunStage::WorkspacePrepared);
+        assert!(rerun.output.is_none());
+    }
+
     #[test]
     fn write_github_sender_parse_failed_reply_writes_template() {
         let temp = TempDir::new().expect("tempdir");
@@ -1603,6 +1781,9 @@ mod tests {
             thread_epoch: None,
             thread_state_path: Some(workspace.join("thread_state.json")),
             employee_id: Some("little_bear".to_string()),
+            idempotency_key: None,
+            trace_id: None,
+            scheduled: false,
         };
 
         let found = find_slack_placeholder_marker(&send_task).expect("marker found");
@@ -0,0 +1,361 @@
+//! Handing a conversation to another employee.
+//!
+//! The `handoff` scheduler action re-enqueues the thread as an email to the
+//! other employee, sent as if by the user: the agent's summary is the body
+//! and the user's messages so far ride along as attachments. The envelope
+//! and the audit log record where it came from, and the user is told on the
+//! channel they were using.
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::account_store::lookup_account_by_channel;
+use crate::audit_store::{self, AuditEntry};
+use crate::channel::{Attachment, Channel, ChannelMetadata};
+use crate::employee_config::EmployeeProfile;
+use crate::i18n::{resolve_locale, user_locale, Message};
+use crate::ingestion::{HandoffProvenance, IngestionEnvelope, IngestionPayload};
+use crate::ingestion_queue::get_global_ingestion_queue;
+
+use super::core::{verified_account_email, Scheduler};
+use super::executor::TaskExecutor;
+use super::outbound::resolve_employee_profile;
+use super::reply::load_reply_context;
+use super::types::{RunTaskTask, SendReplyTask, TaskKind};
+
+/// Most recent inbound message files attached to a handoff.
+const MAX_CONTEXT_FILES: usize = 20;
+/// Larger message files are left out of the handoff.
+const MAX_CONTEXT_FILE_BYTES: u64 = 256 * 1024;
+
+/// Hand the thread of `task` to `employee_id`. Returns the enqueued
+/// envelope's id, or why the handoff was skipped.
+pub(super) fn hand_off<E: TaskExecutor>(
+    scheduler: &mut Scheduler<E>,
+    task: &RunTaskTask,
+    employee_id: &str,
+    summary: &str,
+    now: DateTime<Utc>,
+) -> Result<Uuid, String> {
+    let employee_id = employee_id.trim();
+    let summary = summary.trim();
+    if summary.is_empty() {
+        return Err("summary is empty".to_string());
+    }
+    let from = resolve_employee_profile(task.employee_id.as_deref())
+        .ok_or_else(|| "current employee is not configured".to_string())?;
+    if employee_id == from.id {
+        return Err("cannot hand off to the current employee".to_string());
+    }
+    let target = resolve_employee_profile(Some(employee_id))
+        .filter(|profile| profile.id == employee_id)
+        .ok_or_else(|| format!("unknown employee {}", employee_id))?;
+    let target_address = target
+        .addresses
+        .first()
+        .cloned()
+        .ok_or_else(|| format!("employee {} has no email address", target.id))?;
+    let user_email =
+        requester_email(task).ok_or_else(|| "no email address known for the user".to_string())?;
+    let queue = get_global_ingestion_queue().ok_or_else(|| "no ingestion queue".to_string())?;
+
+    let provenance = HandoffProvenance {
+        from_employee_id: from.id.clone(),
+        from_workspace: task.workspace_dir.display().to_string(),
+        from_channel: task.channel,
+        summary: summary.to_string(),
+        handed_off_at: now,
+    };
+    let envelope = build_handoff_envelope(
+        task,
+        provenance,
+        &display_name(&from),
+        &target.id,
+        &target_address,
+        &user_email,
+    );
+    queue
+        .enqueue(&envelope)
+        .map_err(|err| format!("enqueue failed: {}", err))?;
+    info!(
+        "handed off workspace={} from={} to={} envelope={}",
+        task.workspace_dir.display(),
+        from.id,
+        target.id,
+        envelope.envelope_id
+    );
+
+    audit_store::record(AuditEntry {
+        actor: format!("employee:{}", from.id),
+        on_behalf_of: scheduler.store.owner_user(),
+        action: "handoff".to_string(),
+        target: format!("employee:{}", target.id),
+        channel: Some(Channel::Email.to_string()),
+        payload_hash: audit_store::payload_hash(summary.as_bytes()),
+        trace_id: task.trace_id.clone(),
+        task_id: None,
+        recorded_at: now,
+    });
+
+    if let Err(err) = schedule_handoff_notice(scheduler, task, &from, &target, &user_email) {
+        warn!(
+            "failed to schedule handoff notice for {}: {}",
+            task.workspace_dir.display(),
+            err
+        );
+    }
+    Ok(envelope.envelope_id)
+}
+
+/// The email the other employee receives, from the user.
+pub(super) fn build_handoff_envelope(
+    task: &RunTaskTask,
+    provenance: HandoffProvenance,
+    from_name: &str,
+    to_employee_id: &str,
+    to_address: &str,
+    user_email: &str,
+) -> IngestionEnvelope {
+    let envelope_id = Uuid::new_v4();
+    let reply_context = load_reply_context(&task.workspace_dir);
+    let subject = if reply_context.subject.trim().is_empty() {
+        format!("Handoff from {}", from_name)
+    } else {
+        format!(
+            "Handoff from {}: {}",
+            from_name,
+            reply_context.subject.trim()
+        )
+    };
+    let text_body = format!(
+        "{} handed this conversation over to you.\n\n{}\n\nThe user's messages so far are attached.",
+        from_name, provenance.summary
+    );
+    let domain = to_address.rsplit('@').next().unwrap_or("dowhiz.local");
+
+    IngestionEnvelope {
+        envelope_id,
+        received_at: provenance.handed_off_at,
+        tenant_id: None,
+        employee_id: to_employee_id.to_string(),
+        channel: Channel::Email,
+        external_message_id: None,
+        dedupe_key: format!("handoff:{}", envelope_id),
+        payload: IngestionPayload {
+            sender: user_email.to_string(),
+            sender_name: None,
+            recipient: to_address.to_string(),
+            subject: Some(subject),
+            text_body: Some(text_body),
+            html_body: None,
+            thread_id: format!("handoff-{}", envelope_id.simple()),
+            message_id: Some(format!("<handoff-{}@{}>", envelope_id.simple(), domain)),
+            attachments: context_attachments(&task.workspace_dir.join(&task.input_email_dir)),
+            reply_to: vec![user_email.to_string()],
+            metadata: ChannelMetadata::default(),
+        },
+        raw_payload_ref: None,
+        account_id: task.account_id,
+        trace_id: task.trace_id.clone(),
+        handoff: Some(provenance),
+    }
+}
+
+fn display_name(profile: &EmployeeProfile) -> String {
+    profile
+        .display_name
+        .clone()
+        .unwrap_or_else(|| profile.id.clone())
+}
+
+/// The user's email: the reply address on email threads, otherwise the
+/// verified email of their account.
+fn requester_email(task: &RunTaskTask) -> Option<String> {
+    if task.channel == Channel::Email {
+        return task
+            .reply_to
+            .iter()
+            .flat_map(|address| crate::user_store::extract_emails(address))
+            .next();
+    }
+    let account_id = task.account_id.or_else(|| {
+        let identifier = task.requester_identifier.as_deref()?;
+        lookup_account_by_channel(&task.channel, identifier)
+    })?;
+    verified_account_email(account_id)
+}
+
+/// Text transcripts of the inbound messages (`*_message.txt`, email
+/// `thread_history.md`), oldest first.
+fn context_attachments(incoming_dir: &Path) -> Vec<Attachment> {
+    let Ok(entries) = std::fs::read_dir(incoming_dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .metadata()
+                .map(|meta| meta.is_file() && meta.len() <= MAX_CONTEXT_FILE_BYTES)
+                .unwrap_or(false)
+        })
+        .map(|entry| entry.path())
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("txt" | "md")
+            )
+        })
+        .collect();
+    paths.sort();
+    let skip = paths.len().saturating_sub(MAX_CONTEXT_FILES);
+    paths
+        .into_iter()
+        .skip(skip)
+        .filter_map(|path| {
+            let content = std::fs::read(&path).ok()?;
+            let name = path.file_name()?.to_str()?.to_string();
+            let content_type = if name.ends_with(".md") {
+                "text/markdown"
+            } else {
+                "text/plain"
+            };
+            Some(Attachment {
+                name,
+                content_type: content_type.to_string(),
+                content: base64::engine::general_purpose::STANDARD.encode(content),
+            })
+        })
+        .collect()
+}
+
+/// Tell the user, on the channel they were using, who took over.
+fn schedule_handoff_notice<E: TaskExecutor>(
+    scheduler: &mut Scheduler<E>,
+    task: &RunTaskTask,
+    from: &EmployeeProfile,
+    target: &EmployeeProfile,
+    user_email: &str,
+) -> Result<(), String> {
+    let employee_language = from.language.as_deref();
+    let locale = match scheduler
+        .store
+        .owner_user()
+        .and_then(|owner| owner.strip_prefix("user:").map(str::to_string))
+    {
+        Some(user_id) => user_locale(&user_id, employee_language),
+        None => resolve_locale(None, employee_language),
+    };
+    let message = locale.render(
+        Message::HandoffNotice,
+        &[("employee", &display_name(target)), ("email", &user_email)],
+    );
+    let (filename, attachments_dirname, content) = match task.channel {
+        Channel::Email | Channel::GoogleDocs | Channel::GoogleSheets | Channel::GoogleSlides => (
+            "handoff_notice.html",
+            "reply_email_attachments",
+            format!("<html><body><p>{}</p></body></html>", message),
+        ),
+        _ => ("handoff_notice.txt", "reply_attachments", message),
+    };
+    let notice_path = task.workspace_dir.join(filename);
+    std::fs::write(&notice_path, content).map_err(|err| err.to_string())?;
+
+    let reply_context = load_reply_context(&task.workspace_dir);
+    let notice = SendReplyTask {
+        channel: task.channel,
+        subject: reply_context.subject,
+        html_path: notice_path,
+        attachments_dir: task.workspace_dir.join(attachments_dirname),
+        from: task.reply_from.clone().or(reply_context.from),
+        to: task.reply_to.clone(),
+        cc: Vec::new(),
+        bcc: Vec::new(),
+        in_reply_to: reply_context.in_reply_to,
+        references: reply_context.references,
+        archive_root: task.archive_root.clone(),
+        thread_epoch: task.thread_epoch,
+        thread_state_path: task.thread_state_path.clone(),
+        employee_id: task.employee_id.clone(),
+        idempotency_key: None,
+        trace_id: task.trace_id.clone(),
+        scheduled: task.scheduled,
+    };
+    let notice_id = scheduler
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::SendReply(notice))
+        .map_err(|err| err.to_string())?;
+    info!(
+        "scheduled handoff notice task {} on {:?}",
+        notice_id, task.channel
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::tests::run_task_in;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn run_task(workspace: &Path) -> RunTaskTask {
+        RunTaskTask {
+            reply_to: vec!["U123".to_string()],
+            channel: Channel::Slack,
+            employee_id: Some("little_bear".to_string()),
+            trace_id: Some("trace-1".to_string()),
+            ..run_task_in(workspace)
+        }
+    }
+
+    #[test]
+    fn build_handoff_envelope_addresses_target_from_user() {
+        let temp = TempDir::new().expect("tempdir");
+        let incoming = temp.path().join("incoming_email");
+        fs::create_dir_all(&incoming).expect("incoming_email");
+        fs::write(incoming.join("00001_slack_message.txt"), "fix the build").expect("message");
+        fs::write(incoming.join("00001_slack_message.json"), "{}").expect("raw");
+        let now = Utc::now();
+        let provenance = HandoffProvenance {
+            from_employee_id: "little_bear".to_string(),
+            from_workspace: temp.path().display().to_string(),
+            from_channel: Channel::Slack,
+            summary: "Fix the failing CI build on main.".to_string(),
+            handed_off_at: now,
+        };
+
+        let envelope = build_handoff_envelope(
+            &run_task(temp.path()),
+            provenance.clone(),
+            "Oliver",
+            "boiled_egg",
+            "devin@dowhiz.com",
+            "user@example.com",
+        );
+
+        assert_eq!(envelope.employee_id, "boiled_egg");
+        assert_eq!(envelope.channel, Channel::Email);
+        assert_eq!(envelope.payload.sender, "user@example.com");
+        assert_eq!(envelope.payload.recipient, "devin@dowhiz.com");
+        assert_eq!(envelope.payload.reply_to, vec!["user@example.com"]);
+        assert!(envelope
+            .payload
+            .text_body
+            .as_deref()
+            .unwrap()
+            .contains("Fix the failing CI build on main."));
+        assert_eq!(envelope.trace_id.as_deref(), Some("trace-1"));
+        assert_eq!(envelope.handoff, Some(provenance));
+        let names: Vec<_> = envelope
+            .payload
+            .attachments
+            .iter()
+            .map(|attachment| attachment.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["00001_slack_message.txt"]);
+    }
+}
@@ -0,0 +1,130 @@
+//! Durable task leases, so two workers loading the same owner scope (for
+//! example during a blue/green overlap) never execute the same task at once.
+//!
+//! In-process claims only guard one worker. A lease is recorded on the task
+//! document (`claimed_by`, `lease_expires_at`); it is renewed while held and
+//! can be taken over once it expires, so a crashed worker blocks a task for at
+//! most one TTL.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::sync::watch;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::store::{SchedulerStore, TaskLeaseStore};
+use super::types::SchedulerError;
+
+const MIN_RENEW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A lease held on one task. While a Tokio runtime is available it is renewed
+/// every third of its TTL; it is released on drop.
+pub struct TaskLease {
+    store: TaskLeaseStore,
+    task_id: String,
+    owner: String,
+    lost: Arc<AtomicBool>,
+    stop: Option<watch::Sender<bool>>,
+}
+
+/// Take the lease on `task_id` for `owner`. Returns `None` while another
+/// worker holds an unexpired lease.
+pub fn acquire_task_lease(
+    tasks_db_path: &Path,
+    task_id: Uuid,
+    owner: &str,
+    ttl: Duration,
+) -> Result<Option<TaskLease>, SchedulerError> {
+    let store = SchedulerStore::new(tasks_db_path.to_path_buf())?.leases();
+    let task_id = task_id.to_string();
+    if !store.acquire(&task_id, owner, Utc::now(), chrono_ttl(ttl))? {
+        return Ok(None);
+    }
+    let lost = Arc::new(AtomicBool::new(false));
+    let stop = tokio::runtime::Handle::try_current().ok().map(|runtime| {
+        spawn_renewal(
+            &runtime,
+            store.clone(),
+            task_id.clone(),
+            owner.to_string(),
+            ttl,
+            lost.clone(),
+        )
+    });
+    Ok(Some(TaskLease {
+        store,
+        task_id,
+        owner: owner.to_string(),
+        lost,
+        stop,
+    }))
+}
+
+impl TaskLease {
+    /// False once a renewal found the lease taken over by another worker.
+    pub fn is_held(&self) -> bool {
+        !self.lost.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for TaskLease {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(true);
+        }
+        if !self.is_held() {
+            return;
+        }
+        if let Err(err) = self.store.release(&self.task_id, &self.owner) {
+            warn!("failed to release lease on task {}: {}", self.task_id, err);
+        }
+    }
+}
+
+fn spawn_renewal(
+    runtime: &tokio::runtime::Handle,
+    store: TaskLeaseStore,
+    task_id: String,
+    owner: String,
+    ttl: Duration,
+    lost: Arc<AtomicBool>,
+) -> watch::Sender<bool> {
+    let (stop_tx, mut stop_rx) = watch::channel(false);
+    let interval = (ttl / 3).max(MIN_RENEW_INTERVAL);
+    runtime.spawn(async move {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = stop_rx.changed() => break,
+            }
+            let renewing = (store.clone(), task_id.clone(), owner.clone());
+            let renewed = tokio::task::spawn_blocking(move || {
+                let (store, task_id, owner) = renewing;
+                store.renew(&task_id, &owner, Utc::now(), chrono_ttl(ttl))
+            })
+            .await;
+            match renewed {
+                Ok(Ok(true)) => {}
+                Ok(Ok(false)) => {
+                    // A release racing this renewal also lands here.
+                    if !*stop_rx.borrow() {
+                        warn!("lease on task {} was taken over by another worker", task_id);
+                        lost.store(true, Ordering::Relaxed);
+                    }
+                    break;
+                }
+                Ok(Err(err)) => warn!("failed to renew lease on task {}: {}", task_id, err),
+                Err(err) => warn!("lease renewal for task {} failed: {}", task_id, err),
+            }
+        }
+    });
+    stop_tx
+}
+
+fn chrono_ttl(ttl: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(ttl).unwrap_or_else(|_| chrono::Duration::days(1))
+}
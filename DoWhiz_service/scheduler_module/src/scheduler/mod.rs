@@ -1,7 +1,14 @@
 mod actions;
+mod approval;
+mod checkpoint;
 mod core;
+mod delegation;
+mod digest;
 mod executor;
+mod handoff;
+mod lease;
 mod outbound;
+mod outbound_failure;
 mod reply;
 mod schedule;
 mod snapshot;
@@ -9,15 +16,30 @@ mod store;
 mod types;
 mod utils;
 
+pub(crate) use approval::notify_approver;
 pub use core::Scheduler;
+pub(crate) use core::{escape_html, notify_missed_heartbeat};
 pub use executor::{ModuleExecutor, TaskExecutor};
-pub use store::TaskStatusSummary;
+pub use lease::{acquire_task_lease, TaskLease};
+pub(crate) use outbound::{
+    change_sent_message, resolve_discord_bot_token_for_employee,
+    resolve_slack_bot_token_for_employee,
+};
+pub(crate) use schedule::next_run_after;
+pub(crate) use snapshot::build_scheduler_snapshot;
+pub(crate) use store::execution_counts_by_task;
+pub use store::{
+    DeliveryOutcome, ExecutionQuery, ProviderEventMatch, TaskDeliveryRecord, TaskExecutionRecord,
+    TaskStatusSummary,
+};
 pub use types::{
-    RunTaskTask, Schedule, ScheduledTask, SchedulerError, SendReplyTask, TaskExecution, TaskKind,
+    DigestTask, HeartbeatSpec, NoopTask, ProbeTask, RecurrenceEnd, RunTaskTask, Schedule,
+    ScheduledTask, SchedulerError, SendReplyTask, TaskApproval, TaskExecution, TaskKind,
 };
 pub use utils::load_google_access_token_from_service_env;
+pub(crate) use utils::{task_kind_channel, task_kind_label};
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Load task status summaries for the owner scope derived from `tasks_db_path`.
 /// Returns an empty vector if the storage backend can't be reached.
@@ -28,5 +50,46 @@ pub fn load_tasks_with_status(tasks_db_path: &Path) -> Vec<TaskStatusSummary> {
     }
 }
 
+/// Persist the intent to run the one-shot `task_id` once a worker holds its
+/// lease and thread turn, right before the run, so [`Scheduler::recover_intents`] can make it due again if the run is
+/// lost. Returns false, recording nothing, unless it is an enabled one-shot.
+pub fn record_execution_intent(
+    tasks_db_path: &Path,
+    task_id: uuid::Uuid,
+) -> Result<bool, SchedulerError> {
+    store::SchedulerStore::new(tasks_db_path.to_path_buf())?
+        .record_intent(&task_id.to_string(), chrono::Utc::now())
+}
+
+/// Recent task executions across every scheduler database; see
+/// [`ExecutionQuery`] for the filters.
+pub fn list_task_executions(
+    query: &ExecutionQuery,
+) -> Result<Vec<TaskExecutionRecord>, SchedulerError> {
+    store::list_task_executions(query)
+}
+
+/// Log file of the execution with row id `id`, as recorded when it started;
+/// `None` for an unknown id or an execution without a log.
+pub fn task_execution_log_path(id: &str) -> Result<Option<PathBuf>, SchedulerError> {
+    store::task_execution_log_path(id)
+}
+
+/// Delivery journal of the user's send_reply tasks.
+pub fn list_task_deliveries(user_id: &str) -> Result<Vec<TaskDeliveryRecord>, SchedulerError> {
+    store::list_task_deliveries(user_id)
+}
+
+/// Record a provider's bounce or delivery report for the reply it accepted
+/// as `message_id`; returns the matching send_reply task, if any.
+pub fn record_delivery_outcome(
+    message_id: &str,
+    outcome: DeliveryOutcome,
+    detail: Option<&str>,
+    at: chrono::DateTime<chrono::Utc>,
+) -> Result<Option<ProviderEventMatch>, SchedulerError> {
+    store::record_delivery_outcome(message_id, outcome, detail, at)
+}
+
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
@@ -1,16 +1,23 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use chrono::Utc;
 use tracing::{info, warn};
 
-use crate::channel::Channel;
+use crate::audit_store::{self, AuditEntry};
+use crate::channel::{Channel, SheetEdit};
 use crate::employee_config;
 use crate::service;
+use crate::thread_state::{
+    find_sent_message, find_thread_state_path, mark_sent_message, record_sent_messages,
+};
 
 use super::types::{SchedulerError, SendReplyTask};
 
 /// Execute a SendReplyTask via email (Postmark).
-pub(crate) fn execute_email_send(task: &SendReplyTask) -> Result<(), SchedulerError> {
+/// Send the reply through Postmark; returns the `MessageID` Postmark
+/// assigned to it.
+pub(crate) fn execute_email_send(task: &SendReplyTask) -> Result<String, SchedulerError> {
     let params = send_emails_module::SendEmailParams {
         subject: task.subject.clone(),
         html_path: task.html_path.clone(),
@@ -34,6 +41,8 @@ pub(crate) fn execute_email_send(task: &SendReplyTask) -> Result<(), SchedulerEr
             .map(str::trim)
             .filter(|value| !value.is_empty())
             .unwrap_or("");
+        let redaction = resolve_employee_profile(task.employee_id.as_deref())
+            .and_then(|profile| profile.redaction);
         if let Err(err) = crate::past_emails::archive_outbound(
             archive_root,
             &task.subject,
@@ -47,18 +56,19 @@ pub(crate) fn execute_email_send(task: &SendReplyTask) -> Result<(), SchedulerEr
             &response.message_id,
             &response.submitted_at,
             from,
+            redaction.as_ref(),
         ) {
             warn!("failed to archive outbound email: {}", err);
         }
     }
-    Ok(())
+    Ok(response.message_id)
 }
 
 /// Resolve the Slack bot token for a specific employee.
 ///
 /// Looks for `{EMPLOYEE}_SLACK_BOT_TOKEN` env var first (e.g., `OLIVER_SLACK_BOT_TOKEN`),
 /// then falls back to the global `SLACK_BOT_TOKEN`.
-fn resolve_slack_bot_token_for_employee(
+pub(crate) fn resolve_slack_bot_token_for_employee(
     employee_id: Option<&str>,
 ) -> Result<String, SchedulerError> {
     if let Some(emp_id) = employee_id {
@@ -85,12 +95,7 @@ pub(crate) fn execute_slack_send(task: &SendReplyTask) -> Result<(), SchedulerEr
 
     let adapter = SlackOutboundAdapter::new(bot_token);
 
-    // Read text content from html_path if it exists
-    let text_body = if task.html_path.exists() {
-        fs::read_to_string(&task.html_path).unwrap_or_default()
-    } else {
-        String::new()
-    };
+    let text_body = read_reply_text(task);
 
     let message = OutboundMessage {
         channel: Channel::Slack,
@@ -126,14 +131,286 @@ pub(crate) fn execute_slack_send(task: &SendReplyTask) -> Result<(), SchedulerEr
         "sent Slack message to {:?}, message_id={}",
         task.to, result.message_id
     );
+
+    let channel_id = task.to.get(1).map(String::as_str).unwrap_or_default();
+    record_sent_in_thread(
+        task,
+        Channel::Slack,
+        channel_id,
+        std::slice::from_ref(&result.message_id),
+    );
+    let thread_ts = task
+        .in_reply_to
+        .clone()
+        .unwrap_or_else(|| result.message_id.clone());
+    let mut message_ids = vec![result.message_id.clone()];
+    let pending_action_id = post_slack_confirmation_if_requested(&adapter, task, &thread_ts).map(
+        |(callback_id, confirmation_ts)| {
+            message_ids.extend(confirmation_ts);
+            callback_id
+        },
+    );
+    record_chat_message_links(
+        task,
+        Channel::Slack,
+        channel_id,
+        &message_ids,
+        pending_action_id,
+    );
+    Ok(())
+}
+
+/// Add sent messages to the thread state, so later runs can edit or delete
+/// them. Messages sent to a channel we do not know (a new DM) are skipped.
+fn record_sent_in_thread(
+    task: &SendReplyTask,
+    channel: Channel,
+    channel_id: &str,
+    message_ids: &[String],
+) {
+    if channel_id.is_empty() {
+        return;
+    }
+    let Some(state_path) = task
+        .thread_state_path
+        .clone()
+        .or_else(|| task.html_path.parent().and_then(find_thread_state_path))
+    else {
+        return;
+    };
+    if let Err(err) = record_sent_messages(&state_path, channel, channel_id, message_ids) {
+        warn!(
+            "failed to record sent {} messages in {}: {}",
+            channel,
+            state_path.display(),
+            err
+        );
+    }
+}
+
+/// Edit (`text` set) or delete a message this thread sent earlier. Only
+/// messages recorded in the thread state at `state_path` can be changed.
+pub(crate) fn change_sent_message(
+    state_path: &Path,
+    employee_id: Option<&str>,
+    message_id: &str,
+    text: Option<&str>,
+) -> Result<(), String> {
+    use crate::adapters::discord::DiscordOutboundAdapter;
+    use crate::adapters::slack::SlackOutboundAdapter;
+    use crate::channel::{MessageRef, OutboundAdapter};
+
+    let sent = find_sent_message(state_path, message_id)
+        .map_err(|err| format!("failed to read the thread's sent messages: {}", err))?
+        .ok_or_else(|| format!("message {} was not sent in this thread", message_id.trim()))?;
+    let text = text.map(str::trim);
+    if text.is_some_and(str::is_empty) {
+        return Err("the new text is empty".to_string());
+    }
+    dotenvy::dotenv().ok();
+    let adapter: Box<dyn OutboundAdapter> = match sent.channel {
+        Channel::Slack => Box::new(SlackOutboundAdapter::new(
+            resolve_slack_bot_token_for_employee(employee_id).map_err(|err| err.to_string())?,
+        )),
+        Channel::Discord => {
+            if text.is_some_and(|text| text.chars().count() > DISCORD_MAX_CONTENT_CHARS) {
+                return Err(format!(
+                    "Discord messages are limited to {} characters",
+                    DISCORD_MAX_CONTENT_CHARS
+                ));
+            }
+            Box::new(DiscordOutboundAdapter::new(
+                resolve_discord_bot_token_for_employee(employee_id)
+                    .map_err(|err| err.to_string())?,
+            ))
+        }
+        other => return Err(format!("{} messages cannot be changed", other)),
+    };
+    let message = MessageRef {
+        channel_id: sent.channel_id.clone(),
+        message_id: sent.message_id.clone(),
+    };
+    match text {
+        Some(text) => adapter.update(&message, text),
+        None => adapter.delete(&message),
+    }
+    .map_err(|err| err.to_string())?;
+    if let Err(err) = mark_sent_message(state_path, &sent.message_id, text.is_none()) {
+        warn!(
+            "failed to mark message {} in {}: {}",
+            sent.message_id,
+            state_path.display(),
+            err
+        );
+    }
     Ok(())
 }
 
+/// Remember which thread each sent chat message belongs to, so reactions on
+/// it (⏰/❌/✅) can control that thread. Failures are logged; the send succeeded.
+fn record_chat_message_links(
+    task: &SendReplyTask,
+    channel: Channel,
+    channel_id: &str,
+    message_ids: &[String],
+    pending_action_id: Option<String>,
+) {
+    use crate::message_link_store::{get_global_message_link_store, MessageLink};
+
+    let (Some(requester), Some(workspace_dir)) = (task.to.first(), task.html_path.parent()) else {
+        return;
+    };
+    let Some(store) = get_global_message_link_store() else {
+        return;
+    };
+    for message_id in message_ids.iter().filter(|id| !id.is_empty()) {
+        let link = MessageLink {
+            channel,
+            message_id: message_id.clone(),
+            channel_id: channel_id.to_string(),
+            employee_id: task.employee_id.clone(),
+            requester_id: requester.clone(),
+            workspace_dir: workspace_dir.to_string_lossy().into_owned(),
+            pending_action_id: pending_action_id.clone(),
+            created_at: chrono::Utc::now(),
+        };
+        if let Err(err) = store.record(&link) {
+            warn!(
+                "failed to record {} message link {}: {}",
+                channel, message_id, err
+            );
+        }
+    }
+}
+
+const SLACK_CONFIRMATION_FILENAME: &str = "reply_confirmation.json";
+const SLACK_CONFIRMATION_DEFAULT_TTL_HOURS: i64 = 24;
+
+/// Agent-written request for an Approve/Reject prompt after the reply.
+#[derive(Debug, serde::Deserialize)]
+struct SlackConfirmationRequest {
+    prompt: String,
+    #[serde(default)]
+    on_approve: crate::slack_action_store::PendingActionOutcome,
+    #[serde(default)]
+    on_reject: crate::slack_action_store::PendingActionOutcome,
+    #[serde(default)]
+    expires_in_hours: Option<i64>,
+}
+
+/// Post confirmation buttons if the agent left `reply_confirmation.json` next
+/// to the reply. The file is renamed afterwards so later replies in the same
+/// thread don't repost it. Failures are logged; the reply itself already went out.
+///
+/// Returns the stored callback_id and the confirmation message ts, if posted.
+fn post_slack_confirmation_if_requested(
+    adapter: &crate::adapters::slack::SlackOutboundAdapter,
+    task: &SendReplyTask,
+    thread_ts: &str,
+) -> Option<(String, Option<String>)> {
+    use crate::slack_action_store::{
+        get_global_slack_action_store, PendingActionStatus, PendingSlackAction,
+    };
+
+    let workspace_dir = task.html_path.parent()?;
+    let request_path = workspace_dir.join(SLACK_CONFIRMATION_FILENAME);
+    if !request_path.exists() {
+        return None;
+    }
+    let posted_path = workspace_dir.join(format!(
+        "reply_confirmation.{}.posted.json",
+        chrono::Utc::now().format("%Y%m%dT%H%M%S")
+    ));
+    if let Err(err) = fs::rename(&request_path, &posted_path) {
+        warn!("failed to archive {}: {}", request_path.display(), err);
+        return None;
+    }
+
+    let request = match fs::read_to_string(&posted_path)
+        .map_err(|err| err.to_string())
+        .and_then(|raw| {
+            serde_json::from_str::<SlackConfirmationRequest>(&raw).map_err(|err| err.to_string())
+        }) {
+        Ok(request) if !request.prompt.trim().is_empty() => request,
+        Ok(_) => {
+            warn!("ignoring {} with empty prompt", posted_path.display());
+            return None;
+        }
+        Err(err) => {
+            warn!("invalid {}: {}", posted_path.display(), err);
+            return None;
+        }
+    };
+    let (Some(requester), Some(channel_id)) = (task.to.first(), task.to.get(1)) else {
+        warn!(
+            "slack confirmation requested without requester/channel in {:?}",
+            task.to
+        );
+        return None;
+    };
+    let store = get_global_slack_action_store()?;
+
+    let now = chrono::Utc::now();
+    let ttl_hours = request
+        .expires_in_hours
+        .filter(|hours| *hours > 0)
+        .unwrap_or(SLACK_CONFIRMATION_DEFAULT_TTL_HOURS);
+    let action = PendingSlackAction {
+        callback_id: uuid::Uuid::new_v4().to_string(),
+        employee_id: task.employee_id.clone(),
+        team_id: None,
+        channel_id: channel_id.clone(),
+        thread_ts: Some(thread_ts.to_string()),
+        requester_user_id: requester.clone(),
+        prompt: request.prompt,
+        on_approve: request.on_approve,
+        on_reject: request.on_reject,
+        status: PendingActionStatus::Pending,
+        resolved_by: None,
+        created_at: now,
+        expires_at: now + chrono::Duration::hours(ttl_hours),
+    };
+    if let Err(err) = store.insert(&action) {
+        warn!("failed to store slack pending action: {}", err);
+        return None;
+    }
+    let confirmation_ts = match adapter.send_confirmation(
+        channel_id,
+        Some(thread_ts),
+        &action.callback_id,
+        &action.prompt,
+    ) {
+        Ok(result) if result.success => {
+            info!(
+                "posted slack confirmation callback_id={} channel={}",
+                action.callback_id, channel_id
+            );
+            Some(result.message_id)
+        }
+        Ok(result) => {
+            warn!(
+                "slack confirmation post failed callback_id={}: {}",
+                action.callback_id,
+                result.error.unwrap_or_default()
+            );
+            None
+        }
+        Err(err) => {
+            warn!(
+                "slack confirmation post failed callback_id={}: {}",
+                action.callback_id, err
+            );
+            None
+        }
+    };
+    Some((action.callback_id, confirmation_ts))
+}
+
 /// Resolve the Discord bot token for a specific employee.
 ///
 /// Looks for `{EMPLOYEE}_DISCORD_BOT_TOKEN` env var first (e.g., `LITTLE_BEAR_DISCORD_BOT_TOKEN`),
 /// then falls back to the global `DISCORD_BOT_TOKEN`.
-fn resolve_discord_bot_token_for_employee(
+pub(crate) fn resolve_discord_bot_token_for_employee(
     employee_id: Option<&str>,
 ) -> Result<String, SchedulerError> {
     if let Some(emp_id) = employee_id {
@@ -326,21 +603,29 @@ pub(crate) fn execute_discord_send(task: &SendReplyTask) -> Result<(), Scheduler
 
     let adapter = DiscordOutboundAdapter::new(bot_token);
 
-    let base_text_body = if task.html_path.exists() {
-        fs::read_to_string(&task.html_path).unwrap_or_default()
-    } else {
-        String::new()
-    };
+    let base_text_body = read_reply_text(task);
     let text_body = append_discord_attachment_links(&base_text_body, &task.attachments_dir);
     let text_chunks = split_discord_message_chunks(&text_body);
 
     // For Discord, reply_to[0] = user_id, reply_to[1] = channel_id
     let channel_id = task.to.get(1).and_then(|value| value.parse::<u64>().ok());
 
-    let mut next_thread_id = task.in_reply_to.clone();
+    // A thread opened from a message reuses that message's ID; the starter lives in
+    // the parent channel, so replies inside the thread must not reference it.
+    let replying_in_started_thread = task
+        .in_reply_to
+        .as_deref()
+        .zip(task.to.get(1))
+        .map(|(reply_id, channel)| reply_id == channel)
+        .unwrap_or(false);
+    let mut next_thread_id = if replying_in_started_thread {
+        None
+    } else {
+        task.in_reply_to.clone()
+    };
     let mut sent_message_ids = Vec::new();
 
-    for chunk in text_chunks {
+    for (chunk_index, chunk) in text_chunks.into_iter().enumerate() {
         let mut message = OutboundMessage {
             channel: Channel::Discord,
             from: task.from.clone(),
@@ -355,6 +640,10 @@ pub(crate) fn execute_discord_send(task: &SendReplyTask) -> Result<(), Scheduler
             thread_id: next_thread_id.clone(),
             metadata: ChannelMetadata {
                 discord_channel_id: channel_id,
+                discord_nonce: task
+                    .idempotency_key
+                    .as_deref()
+                    .map(|key| discord_nonce(key, chunk_index)),
                 ..Default::default()
             },
         };
@@ -391,9 +680,23 @@ pub(crate) fn execute_discord_send(task: &SendReplyTask) -> Result<(), Scheduler
         sent_message_ids.len(),
         sent_message_ids
     );
+    let channel_id = task.to.get(1).map(String::as_str).unwrap_or_default();
+    record_sent_in_thread(task, Channel::Discord, channel_id, &sent_message_ids);
+    record_chat_message_links(task, Channel::Discord, channel_id, &sent_message_ids, None);
     Ok(())
 }
 
+/// Discord caps nonces at 25 characters: keep enough of the task key to stay
+/// unique and suffix the chunk index so every chunk dedupes on its own.
+fn discord_nonce(idempotency_key: &str, chunk_index: usize) -> String {
+    let compact: String = idempotency_key
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .take(20)
+        .collect();
+    format!("{compact}-{chunk_index}")
+}
+
 /// Execute a SendReplyTask via BlueBubbles (iMessage).
 pub(crate) fn execute_bluebubbles_send(task: &SendReplyTask) -> Result<(), SchedulerError> {
     use crate::adapters::bluebubbles::BlueBubblesOutboundAdapter;
@@ -408,12 +711,7 @@ pub(crate) fn execute_bluebubbles_send(task: &SendReplyTask) -> Result<(), Sched
 
     let adapter = BlueBubblesOutboundAdapter::new(server_url, password);
 
-    // Read plain text content from reply_message.txt (html_path field reused for simplicity)
-    let text_body = if task.html_path.exists() {
-        fs::read_to_string(&task.html_path).unwrap_or_default()
-    } else {
-        String::new()
-    };
+    let text_body = read_reply_text(task);
 
     // For BlueBubbles, to[0] contains the chat_guid
     let chat_guid = task.to.first().cloned();
@@ -462,6 +760,13 @@ fn env_var_non_empty(key: &str) -> Option<String> {
 }
 
 fn resolve_employee_profile_from_env() -> Option<employee_config::EmployeeProfile> {
+    resolve_employee_profile(None)
+}
+
+/// Profile for `employee_id`, or for `EMPLOYEE_ID` / the config default when unset.
+pub(super) fn resolve_employee_profile(
+    employee_id: Option<&str>,
+) -> Option<employee_config::EmployeeProfile> {
     let config_path = env_var_non_empty("EMPLOYEE_CONFIG_PATH")
         .map(PathBuf::from)
         .map(|path| {
@@ -475,11 +780,84 @@ fn resolve_employee_profile_from_env() -> Option<employee_config::EmployeeProfil
         })
         .unwrap_or_else(service::default_employee_config_path);
     let employee_directory = employee_config::load_employee_directory(&config_path).ok()?;
-    let employee_id = env_var_non_empty("EMPLOYEE_ID")
+    let employee_id = employee_id
+        .map(str::to_string)
+        .or_else(|| env_var_non_empty("EMPLOYEE_ID"))
         .or_else(|| employee_directory.default_employee_id.clone())?;
     employee_directory.employee(&employee_id).cloned()
 }
 
+/// The reply text of a chat send (reply_message.txt, in the reused
+/// `html_path` field), shaped by the employee's formatting for the channel.
+fn read_reply_text(task: &SendReplyTask) -> String {
+    let text = if task.html_path.exists() {
+        fs::read_to_string(&task.html_path).unwrap_or_default()
+    } else {
+        String::new()
+    };
+    if text.trim().is_empty() {
+        return text;
+    }
+    match resolve_employee_profile(task.employee_id.as_deref()) {
+        Some(profile) => profile.formatting.for_channel(task.channel).apply(&text),
+        None => text,
+    }
+}
+
+/// Prefix of the task error for a send the outbound policy refused; the
+/// failure classifier keys on it.
+pub(crate) const OUTBOUND_POLICY_ERROR: &str = "outbound policy violation";
+
+/// Check `task` against its employee's outbound policy. Runs before every
+/// adapter send; a violation fails the send without contacting the provider.
+pub(crate) fn enforce_outbound_policy(task: &SendReplyTask) -> Result<(), SchedulerError> {
+    let Some(profile) = resolve_employee_profile(task.employee_id.as_deref()) else {
+        return Ok(());
+    };
+    let recipients = policy_recipients(task);
+    match profile.outbound_policy.check(task.channel, &recipients) {
+        Ok(()) => Ok(()),
+        Err(violation) => {
+            warn!(
+                "outbound policy blocked send for employee={} channel={} recipients={}: {}",
+                profile.id,
+                task.channel,
+                recipients.join(", "),
+                violation
+            );
+            Err(SchedulerError::TaskFailed(format!(
+                "{}: {}",
+                OUTBOUND_POLICY_ERROR, violation
+            )))
+        }
+    }
+}
+
+/// First email recipient of `task` the policy still allows, used to tell the
+/// requester why the rest of the send was refused.
+pub(crate) fn policy_permitted_email(task: &SendReplyTask) -> Option<String> {
+    let profile = resolve_employee_profile(task.employee_id.as_deref())?;
+    policy_recipients(task)
+        .into_iter()
+        .find(|recipient| {
+            recipient.contains('@') && profile.outbound_policy.permits(task.channel, recipient)
+        })
+        .map(str::to_string)
+}
+
+fn policy_recipients(task: &SendReplyTask) -> Vec<&str> {
+    let recipients = task.to.iter();
+    if task.channel == Channel::Email {
+        recipients
+            .chain(&task.cc)
+            .chain(&task.bcc)
+            .map(String::as_str)
+            .collect()
+    } else {
+        recipients.map(String::as_str).collect()
+    }
+}
+
 fn resolve_telegram_bot_token_from_env() -> Option<String> {
     resolve_employee_profile_from_env()
         .and_then(|profile| service::resolve_telegram_bot_token(&profile))
@@ -498,12 +876,7 @@ pub(crate) fn execute_telegram_send(task: &SendReplyTask) -> Result<(), Schedule
 
     let adapter = TelegramOutboundAdapter::new(bot_token);
 
-    // Read plain text content from reply_message.txt (html_path field reused for simplicity)
-    let text_body = if task.html_path.exists() {
-        fs::read_to_string(&task.html_path).unwrap_or_default()
-    } else {
-        String::new()
-    };
+    let text_body = read_reply_text(task);
 
     // For Telegram, to[0] contains the chat_id as a string
     let chat_id = task.to.first().and_then(|s| s.parse::<i64>().ok());
@@ -558,12 +931,7 @@ pub(crate) fn execute_whatsapp_send(task: &SendReplyTask) -> Result<(), Schedule
 
     let adapter = WhatsAppOutboundAdapter::new(access_token, phone_number_id);
 
-    // Read plain text content from reply_message.txt (html_path field reused for simplicity)
-    let text_body = if task.html_path.exists() {
-        fs::read_to_string(&task.html_path).unwrap_or_default()
-    } else {
-        String::new()
-    };
+    let text_body = read_reply_text(task);
 
     // For WhatsApp, to[0] contains the phone number
     let phone_number = task.to.first().cloned();
@@ -613,12 +981,7 @@ pub(crate) fn execute_wechat_send(task: &SendReplyTask) -> Result<(), SchedulerE
     let adapter = WeChatOutboundAdapter::from_env()
         .map_err(|err| SchedulerError::TaskFailed(format!("WeChat config error: {}", err)))?;
 
-    // Read plain text content from reply_message.txt
-    let text_body = if task.html_path.exists() {
-        fs::read_to_string(&task.html_path).unwrap_or_default()
-    } else {
-        String::new()
-    };
+    let text_body = read_reply_text(task);
 
     let message = OutboundMessage {
         channel: Channel::WeChat,
@@ -678,11 +1041,7 @@ pub(crate) fn execute_sms_send(task: &SendReplyTask) -> Result<(), SchedulerErro
         .map(|value| value.as_str())
         .ok_or_else(|| SchedulerError::TaskFailed("SMS to number missing".to_string()))?;
 
-    let text_body = if task.html_path.exists() {
-        fs::read_to_string(&task.html_path).unwrap_or_default()
-    } else {
-        String::new()
-    };
+    let text_body = read_reply_text(task);
 
     let api_base = std::env::var("TWILIO_API_BASE_URL")
         .unwrap_or_else(|_| "https://api.twilio.com".to_string());
@@ -795,6 +1154,249 @@ pub(crate) fn execute_google_docs_send(task: &SendReplyTask) -> Result<(), Sched
     Ok(())
 }
 
+/// Apply `edits` to a Google Sheet with the employee's Google credentials.
+/// `spreadsheet` is the spreadsheet ID or its URL.
+pub(crate) fn update_sheet(
+    employee_id: Option<&str>,
+    spreadsheet: &str,
+    edits: Vec<SheetEdit>,
+) -> Result<(), String> {
+    use crate::adapters::google_sheets::GoogleSheetsOutboundAdapter;
+    use crate::channel::{ChannelMetadata, OutboundAdapter, OutboundMessage};
+    use crate::google_auth::{GoogleAuth, GoogleAuthConfig};
+
+    let spreadsheet_id = spreadsheet_id_from(spreadsheet)
+        .ok_or_else(|| format!("not a spreadsheet ID or URL: {}", spreadsheet.trim()))?;
+    if edits.is_empty() {
+        return Err("no edits".to_string());
+    }
+    dotenvy::dotenv().ok();
+    let auth = GoogleAuth::new(GoogleAuthConfig::from_env_for_employee(employee_id))
+        .map_err(|err| format!("Google auth failed: {}", err))?;
+    let message = OutboundMessage {
+        channel: Channel::GoogleSheets,
+        from: None,
+        to: vec![],
+        cc: vec![],
+        bcc: vec![],
+        subject: String::new(),
+        text_body: String::new(),
+        html_body: String::new(),
+        html_path: None,
+        attachments_dir: None,
+        thread_id: None,
+        metadata: ChannelMetadata {
+            google_sheets_spreadsheet_id: Some(spreadsheet_id.to_string()),
+            google_sheets_edits: edits,
+            ..Default::default()
+        },
+    };
+    GoogleSheetsOutboundAdapter::new(auth)
+        .send(&message)
+        .map_err(|err| err.to_string())?;
+    info!(
+        "applied {} edits to spreadsheet {}",
+        message.metadata.google_sheets_edits.len(),
+        spreadsheet_id
+    );
+    Ok(())
+}
+
+/// The ID in a `docs.google.com/spreadsheets/d/<id>/...` URL, or `input`
+/// itself when it already looks like an ID.
+fn spreadsheet_id_from(input: &str) -> Option<&str> {
+    let input = input.trim();
+    let id = match input.split_once("/spreadsheets/d/") {
+        Some((_, rest)) => rest.split(['/', '?', '#']).next().unwrap_or_default(),
+        None => input,
+    };
+    (!id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+    .then_some(id)
+}
+
+/// Execute a SendReplyTask via Jira (comment on the issue).
+pub(crate) fn execute_jira_send(task: &SendReplyTask) -> Result<(), SchedulerError> {
+    use crate::adapters::jira::{JiraConfig, JiraOutboundAdapter};
+    use crate::channel::{ChannelMetadata, OutboundAdapter, OutboundMessage};
+
+    dotenvy::dotenv().ok();
+    let config = JiraConfig::from_env_for_employee(task.employee_id.as_deref())
+        .map_err(|err| SchedulerError::TaskFailed(format!("Jira config error: {}", err)))?;
+    let adapter = JiraOutboundAdapter::new(config);
+
+    let text_body = if task.html_path.exists() {
+        fs::read_to_string(&task.html_path).unwrap_or_default()
+    } else {
+        String::new()
+    };
+
+    // For Jira, in_reply_to is the issue key
+    let issue_key = task
+        .in_reply_to
+        .clone()
+        .or_else(|| task.to.first().cloned())
+        .ok_or_else(|| SchedulerError::TaskFailed("Missing issue key for Jira".to_string()))?;
+
+    let message = OutboundMessage {
+        channel: Channel::Jira,
+        from: task.from.clone(),
+        to: task.to.clone(),
+        cc: vec![],
+        bcc: vec![],
+        subject: task.subject.clone(),
+        text_body,
+        html_body: String::new(),
+        html_path: Some(task.html_path.clone()),
+        attachments_dir: Some(task.attachments_dir.clone()),
+        thread_id: task.in_reply_to.clone(),
+        metadata: ChannelMetadata {
+            jira_issue_key: Some(issue_key.clone()),
+            ..Default::default()
+        },
+    };
+
+    let result = adapter
+        .send(&message)
+        .map_err(|err| SchedulerError::TaskFailed(format!("Jira send failed: {}", err)))?;
+
+    info!(
+        "posted Jira comment on {}, comment_id={}",
+        issue_key, result.message_id
+    );
+    Ok(())
+}
+
+/// Create a Jira issue with the employee's Jira credentials; returns its key
+/// and browser link.
+pub(crate) fn create_jira_issue(
+    employee_id: Option<&str>,
+    draft: &crate::adapters::jira::JiraIssueDraft,
+) -> Result<(String, String), String> {
+    use crate::adapters::jira::{JiraConfig, JiraOutboundAdapter};
+
+    if draft.project.trim().is_empty() || draft.summary.trim().is_empty() {
+        return Err("project and summary are required".to_string());
+    }
+    dotenvy::dotenv().ok();
+    let adapter = JiraOutboundAdapter::new(
+        JiraConfig::from_env_for_employee(employee_id).map_err(|err| err.to_string())?,
+    );
+    let key = adapter.create_issue(draft).map_err(|err| err.to_string())?;
+    let url = adapter.config().issue_url(&key);
+    Ok((key, url))
+}
+
+/// Edit, transition and/or comment on a Jira issue with the employee's Jira
+/// credentials.
+pub(crate) fn update_jira_issue(
+    employee_id: Option<&str>,
+    issue_key: &str,
+    update: &crate::adapters::jira::JiraIssueUpdate,
+) -> Result<(), String> {
+    use crate::adapters::jira::{JiraConfig, JiraOutboundAdapter};
+
+    if update.is_empty() {
+        return Err("no changes".to_string());
+    }
+    dotenvy::dotenv().ok();
+    JiraOutboundAdapter::new(
+        JiraConfig::from_env_for_employee(employee_id).map_err(|err| err.to_string())?,
+    )
+    .update_issue(issue_key.trim(), update)
+    .map_err(|err| err.to_string())?;
+    info!("updated Jira issue {}", issue_key);
+    Ok(())
+}
+
+/// Create a Linear issue with the employee's Linear API key.
+pub(crate) fn create_linear_issue(
+    employee_id: Option<&str>,
+    draft: &crate::adapters::linear::LinearIssueDraft,
+) -> Result<crate::adapters::linear::LinearIssue, String> {
+    use crate::adapters::linear::LinearClient;
+
+    if draft.team.trim().is_empty() || draft.title.trim().is_empty() {
+        return Err("team and title are required".to_string());
+    }
+    dotenvy::dotenv().ok();
+    LinearClient::from_env_for_employee(employee_id)
+        .map_err(|err| err.to_string())?
+        .create_issue(draft)
+        .map_err(|err| err.to_string())
+}
+
+/// A meeting made by [`create_meeting`].
+pub(crate) struct CreatedMeeting {
+    pub(crate) join_url: String,
+    /// Google Calendar event carrying the invites, when one was created
+    pub(crate) event_url: Option<String>,
+}
+
+/// Create a meeting with the employee's Google or Zoom credentials. Google
+/// Meet links come from a calendar event that invites the attendees; Zoom
+/// meetings get such an event too when the employee has Google credentials.
+pub(crate) fn create_meeting(
+    employee_id: Option<&str>,
+    provider: crate::adapters::meetings::MeetingProvider,
+    request: &crate::adapters::meetings::MeetingRequest,
+) -> Result<CreatedMeeting, String> {
+    use crate::adapters::meetings::{GoogleCalendarClient, MeetingProvider, ZoomClient};
+    use crate::google_auth::{GoogleAuth, GoogleAuthConfig};
+
+    if request.title.trim().is_empty() {
+        return Err("title is required".to_string());
+    }
+    if !(1..=24 * 60).contains(&request.duration_minutes) {
+        return Err(format!(
+            "duration_minutes must be 1-1440, got {}",
+            request.duration_minutes
+        ));
+    }
+    dotenvy::dotenv().ok();
+    let google = GoogleAuthConfig::from_env_for_employee(employee_id);
+    match provider {
+        MeetingProvider::GoogleMeet => {
+            let auth =
+                GoogleAuth::new(google).map_err(|err| format!("Google auth failed: {}", err))?;
+            let event = GoogleCalendarClient::new(auth)
+                .create_event(request, None)
+                .map_err(|err| err.to_string())?;
+            Ok(CreatedMeeting {
+                join_url: event.meet_url.unwrap_or_default(),
+                event_url: Some(event.html_link),
+            })
+        }
+        MeetingProvider::Zoom => {
+            let join_url = ZoomClient::from_env_for_employee(employee_id)
+                .and_then(|zoom| zoom.create_meeting(request))
+                .map_err(|err| err.to_string())?;
+            let event_url = if google.is_valid() {
+                GoogleAuth::new(google)
+                    .map_err(|err| err.to_string())
+                    .and_then(|auth| {
+                        GoogleCalendarClient::new(auth)
+                            .create_event(request, Some(&join_url))
+                            .map_err(|err| err.to_string())
+                    })
+                    .map(|event| event.html_link)
+                    .map_err(|err| {
+                        warn!("Zoom meeting {} has no calendar invite: {}", join_url, err)
+                    })
+                    .ok()
+            } else {
+                None
+            };
+            Ok(CreatedMeeting {
+                join_url,
+                event_url,
+            })
+        }
+    }
+}
+
 /// Get the central Notion reply queue directory for an employee.
 pub fn notion_reply_queue_dir(employee_id: &str) -> PathBuf {
     dirs::home_dir()
@@ -916,13 +1518,64 @@ pub(crate) fn execute_notion_send(task: &SendReplyTask) -> Result<(), SchedulerE
     Ok(())
 }
 
+/// Audit record for a reply the scheduler just delivered. The hash covers the
+/// subject and the rendered body file, not the attachments.
+pub(super) fn send_reply_audit_entry(
+    task: &SendReplyTask,
+    task_id: &str,
+    on_behalf_of: Option<String>,
+) -> AuditEntry {
+    let employee_id = task
+        .employee_id
+        .clone()
+        .or_else(|| env_var_non_empty("EMPLOYEE_ID"))
+        .unwrap_or_else(|| "unknown".to_string());
+    let recipients: Vec<&str> = task
+        .to
+        .iter()
+        .chain(&task.cc)
+        .chain(&task.bcc)
+        .map(String::as_str)
+        .collect();
+    let target = if recipients.is_empty() {
+        task.in_reply_to.clone().unwrap_or_else(|| "-".to_string())
+    } else {
+        recipients.join(", ")
+    };
+    let mut payload = task.subject.clone().into_bytes();
+    payload.push(b'\n');
+    payload.extend(fs::read(&task.html_path).unwrap_or_default());
+    AuditEntry {
+        actor: format!("employee:{}", employee_id),
+        on_behalf_of,
+        action: "send_message".to_string(),
+        target,
+        channel: Some(task.channel.to_string()),
+        payload_hash: audit_store::payload_hash(&payload),
+        trace_id: task.trace_id.clone(),
+        task_id: Some(task_id.to_string()),
+        recorded_at: Utc::now(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        is_discord_unknown_message_reference, split_discord_message_chunks,
-        DISCORD_MAX_CONTENT_CHARS,
+        discord_nonce, is_discord_unknown_message_reference, split_discord_message_chunks,
+        spreadsheet_id_from, DISCORD_MAX_CONTENT_CHARS,
     };
 
+    #[test]
+    fn spreadsheet_id_from_accepts_ids_and_urls() {
+        assert_eq!(spreadsheet_id_from(" 1AbC-d_9 "), Some("1AbC-d_9"));
+        assert_eq!(
+            spreadsheet_id_from("https://docs.google.com/spreadsheets/d/1AbC-d_9/edit#gid=0"),
+            Some("1AbC-d_9")
+        );
+        assert_eq!(spreadsheet_id_from("my expense tracker"), None);
+        assert_eq!(spreadsheet_id_from(""), None);
+    }
+
     #[test]
     fn split_discord_message_keeps_short_text() {
         let text = "short reply";
@@ -951,6 +1604,16 @@ mod tests {
         assert_eq!(chunks.concat(), text);
     }
 
+    #[test]
+    fn discord_nonce_fits_limit_and_varies_per_chunk() {
+        let key = "0f8fad5b-d9cb-469f-a165-70867728950e";
+        let first = discord_nonce(key, 0);
+        let second = discord_nonce(key, 11);
+        assert_eq!(first, "0f8fad5bd9cb469fa165-0");
+        assert_ne!(first, second);
+        assert!(second.len() <= 25);
+    }
+
     #[test]
     fn discord_unknown_message_reference_detection() {
         let error = r#"{"errors":{"message_reference":{"_errors":[{"code":"MESSAGE_REFERENCE_UNKNOWN_MESSAGE"}]}}}"#;
@@ -994,6 +1657,9 @@ mod tests {
             thread_epoch: None,
             thread_state_path: None,
             employee_id: None,
+            idempotency_key: None,
+            trace_id: None,
+            scheduled: false,
         };
 
         // execute_notion_send should return Ok(()) without doing anything
@@ -1003,4 +1669,47 @@ mod tests {
         // Clean up
         let _ = fs::remove_dir_all(&temp_dir);
     }
+
+    #[test]
+    fn send_reply_audit_entry_lists_recipients_and_hashes_body() {
+        use super::{send_reply_audit_entry, SendReplyTask};
+        use crate::audit_store::payload_hash;
+        use crate::channel::Channel;
+        use std::fs;
+
+        let temp_dir = std::env::temp_dir().join(format!("audit_test_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&temp_dir).unwrap();
+        let html_path = temp_dir.join("reply_email_draft.html");
+        fs::write(&html_path, "<p>Done</p>").unwrap();
+
+        let task = SendReplyTask {
+            channel: Channel::Email,
+            subject: "Re: report".to_string(),
+            html_path,
+            attachments_dir: temp_dir.clone(),
+            from: None,
+            to: vec!["alice@example.com".to_string()],
+            cc: vec!["bob@example.com".to_string()],
+            bcc: vec![],
+            in_reply_to: None,
+            references: None,
+            archive_root: None,
+            thread_epoch: None,
+            thread_state_path: None,
+            employee_id: Some("little_bear".to_string()),
+            idempotency_key: None,
+            trace_id: Some("abc".to_string()),
+            scheduled: false,
+        };
+
+        let entry = send_reply_audit_entry(&task, "task-1", Some("user:u1".to_string()));
+        assert_eq!(entry.actor, "employee:little_bear");
+        assert_eq!(entry.on_behalf_of.as_deref(), Some("user:u1"));
+        assert_eq!(entry.target, "alice@example.com, bob@example.com");
+        assert_eq!(entry.channel.as_deref(), Some("email"));
+        assert_eq!(entry.payload_hash, payload_hash(b"Re: report\n<p>Done</p>"));
+        assert_eq!(entry.trace_id.as_deref(), Some("abc"));
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
 }
@@ -0,0 +1,215 @@
+//! Classification of failed SendReply deliveries.
+//!
+//! Adapters surface delivery errors as free-form strings ("Slack API error:
+//! is_archived", Twilio JSON bodies, ...). This maps them onto a small
+//! taxonomy so permanent failures can be explained to the user instead of
+//! only landing in the execution's `error_message`.
+
+use crate::channel::Channel;
+
+use super::outbound::OUTBOUND_POLICY_ERROR;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutboundFailureKind {
+    /// The address, phone number, or chat id does not exist.
+    InvalidRecipient,
+    /// The conversation is gone or the bot lost access (archived, removed).
+    ChannelUnavailable,
+    /// The recipient blocked the bot or unsubscribed.
+    RecipientBlocked,
+    /// The workspace or bot credentials were revoked.
+    AuthRevoked,
+    /// The platform refused the payload (too long, attachment too large).
+    ContentRejected,
+    RateLimited,
+    /// The employee's outbound policy refused the recipient or channel.
+    PolicyBlocked,
+    Unknown,
+}
+
+impl OutboundFailureKind {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::InvalidRecipient => "invalid_recipient",
+            Self::ChannelUnavailable => "channel_unavailable",
+            Self::RecipientBlocked => "recipient_blocked",
+            Self::AuthRevoked => "auth_revoked",
+            Self::ContentRejected => "content_rejected",
+            Self::RateLimited => "rate_limited",
+            Self::PolicyBlocked => "policy_blocked",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    /// Permanent failures will not succeed on retry and need a human to act.
+    pub(crate) fn is_permanent(self) -> bool {
+        !matches!(self, Self::RateLimited | Self::Unknown)
+    }
+
+    pub(crate) fn user_explanation(self, channel: &Channel) -> String {
+        let reason = match self {
+            Self::InvalidRecipient => {
+                "the recipient address or number looks invalid. Please double-check it and message us again."
+            }
+            Self::ChannelUnavailable => {
+                "the conversation is no longer available. It may have been archived or deleted, or the assistant was removed from it."
+            }
+            Self::RecipientBlocked => {
+                "the recipient has blocked or unsubscribed from our messages."
+            }
+            Self::AuthRevoked => {
+                "our access to your workspace was revoked. Please reconnect it from your DoWhiz account settings."
+            }
+            Self::ContentRejected => {
+                "the platform rejected the message content, for example because it was too long or an attachment was too large."
+            }
+            Self::PolicyBlocked => {
+                "your organization's outbound policy for this assistant does not allow it. Ask your administrator if the recipient or channel should be permitted."
+            }
+            Self::RateLimited | Self::Unknown => {
+                "of an unexpected delivery error. Our team has been notified."
+            }
+        };
+        format!(
+            "We couldn't deliver a reply on {} because {}",
+            channel, reason
+        )
+    }
+}
+
+pub(crate) fn classify_outbound_failure(error_message: &str) -> OutboundFailureKind {
+    let lowered = error_message.to_ascii_lowercase();
+    if lowered.contains(OUTBOUND_POLICY_ERROR) {
+        OutboundFailureKind::PolicyBlocked
+    } else if contains_any(
+        &lowered,
+        &[
+            "invalid_auth",
+            "token_revoked",
+            "account_inactive",
+            "not_authed",
+            "401 unauthorized",
+            "\"code\":20003",
+            "\"code\": 20003",
+        ],
+    ) {
+        OutboundFailureKind::AuthRevoked
+    } else if contains_any(
+        &lowered,
+        &[
+            "is_archived",
+            "channel_not_found",
+            "not_in_channel",
+            "unknown channel",
+            "missing access",
+            "group chat was deleted",
+        ],
+    ) {
+        OutboundFailureKind::ChannelUnavailable
+    } else if contains_any(
+        &lowered,
+        &[
+            "bot was blocked by the user",
+            "cannot send messages to this user",
+            "inactive recipient",
+            "marked as inactive",
+            "unsubscribed",
+            "\"code\":21610",
+            "\"code\": 21610",
+        ],
+    ) {
+        OutboundFailureKind::RecipientBlocked
+    } else if contains_any(
+        &lowered,
+        &[
+            "user_not_found",
+            "chat not found",
+            "invalid phone",
+            "not a valid phone number",
+            "not a valid mobile number",
+            "invalid 'to' address",
+            "invalid recipient",
+            "\"code\":21211",
+            "\"code\": 21211",
+            "\"code\":21614",
+            "\"code\": 21614",
+        ],
+    ) {
+        OutboundFailureKind::InvalidRecipient
+    } else if contains_any(
+        &lowered,
+        &[
+            "msg_too_long",
+            "message is too long",
+            "payload too large",
+            "request entity too large",
+        ],
+    ) {
+        OutboundFailureKind::ContentRejected
+    } else if contains_any(
+        &lowered,
+        &["ratelimited", "rate limit", "too many requests"],
+    ) {
+        OutboundFailureKind::RateLimited
+    } else {
+        OutboundFailureKind::Unknown
+    }
+}
+
+fn contains_any(haystack: &str, patterns: &[&str]) -> bool {
+    patterns.iter().any(|pattern| haystack.contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_outbound_failure_maps_adapter_errors() {
+        assert_eq!(
+            classify_outbound_failure("task execution failed: Slack API error: is_archived"),
+            OutboundFailureKind::ChannelUnavailable
+        );
+        assert_eq!(
+            classify_outbound_failure("Slack API error: invalid_auth"),
+            OutboundFailureKind::AuthRevoked
+        );
+        assert_eq!(
+            classify_outbound_failure(
+                r#"Twilio API error: {"code": 21211, "message": "The 'To' number is not a valid phone number."}"#
+            ),
+            OutboundFailureKind::InvalidRecipient
+        );
+        assert_eq!(
+            classify_outbound_failure("Telegram API error: Forbidden: bot was blocked by the user"),
+            OutboundFailureKind::RecipientBlocked
+        );
+        assert_eq!(
+            classify_outbound_failure("Slack API error: ratelimited"),
+            OutboundFailureKind::RateLimited
+        );
+        assert_eq!(
+            classify_outbound_failure(
+                "task execution failed: outbound policy violation: eve@rival.com is not on the allow list"
+            ),
+            OutboundFailureKind::PolicyBlocked
+        );
+        assert_eq!(
+            classify_outbound_failure("Slack send failed: connection reset"),
+            OutboundFailureKind::Unknown
+        );
+    }
+
+    #[test]
+    fn only_permanent_kinds_need_escalation() {
+        assert!(OutboundFailureKind::ChannelUnavailable.is_permanent());
+        assert!(OutboundFailureKind::InvalidRecipient.is_permanent());
+        assert!(!OutboundFailureKind::RateLimited.is_permanent());
+        assert!(!OutboundFailureKind::Unknown.is_permanent());
+        assert!(OutboundFailureKind::PolicyBlocked.is_permanent());
+
+        let explanation = OutboundFailureKind::ChannelUnavailable.user_explanation(&Channel::Slack);
+        assert!(explanation.starts_with("We couldn't deliver a reply on slack because"));
+        assert!(explanation.contains("archived"));
+    }
+}
@@ -79,6 +79,12 @@ struct DiscordMetaLite {
     message_id: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct JiraMetaLite {
+    issue_key: Option<String>,
+    subject: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct SlackMetaLite {
     channel: Option<String>,
@@ -138,6 +144,16 @@ pub(crate) fn load_reply_context(workspace_dir: &Path) -> ReplyContext {
         }
     }
 
+    // Jira: comment on the issue the event came from.
+    if let Some((issue_key, subject)) = jira_issue(&incoming_dir) {
+        return ReplyContext {
+            subject: ensure_reply_prefix(&subject),
+            in_reply_to: Some(issue_key),
+            references: None,
+            from: None,
+        };
+    }
+
     // Fall back to Postmark (email) payload
     let payload_path = incoming_dir.join("postmark_payload.json");
     let payload = fs::read_to_string(&payload_path)
@@ -221,6 +237,18 @@ fn latest_slack_thread_id(incoming_dir: &Path) -> Option<String> {
     None
 }
 
+/// Issue key and subject from `jira_metadata.json`, written for Jira events.
+fn jira_issue(incoming_dir: &Path) -> Option<(String, String)> {
+    let content = fs::read_to_string(incoming_dir.join("jira_metadata.json")).ok()?;
+    let meta = serde_json::from_str::<JiraMetaLite>(&content).ok()?;
+    let issue_key = meta
+        .issue_key
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())?;
+    let subject = meta.subject.unwrap_or_else(|| issue_key.clone());
+    Some((issue_key, subject))
+}
+
 const REPLY_SUBJECT_FALLBACK: &str = "Your request";
 const REPLY_SUBJECT_HINT_MAX_CHARS: usize = 72;
 
@@ -395,6 +423,22 @@ mod tests {
         assert_eq!(context.in_reply_to.as_deref(), Some("1002"));
     }
 
+    #[test]
+    fn load_reply_context_replies_on_jira_issue() {
+        let temp = TempDir::new().expect("tempdir");
+        let incoming_dir = temp.path().join("incoming_email");
+        fs::create_dir_all(&incoming_dir).expect("incoming_email");
+        fs::write(
+            incoming_dir.join("jira_metadata.json"),
+            r#"{"issue_key":"OPS-42","comment_id":"10050","subject":"[OPS-42] Renew TLS cert"}"#,
+        )
+        .expect("write metadata");
+
+        let context = load_reply_context(temp.path());
+        assert_eq!(context.in_reply_to.as_deref(), Some("OPS-42"));
+        assert_eq!(context.subject, "Re: [OPS-42] Renew TLS cert");
+    }
+
     #[test]
     fn load_reply_context_falls_back_to_email_headers() {
         let temp = TempDir::new().expect("tempdir");
@@ -1,17 +1,89 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveTime, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
 use cron::Schedule as CronSchedule;
+use run_task_module::RecurrenceFrequency;
 use std::str::FromStr;
 
 use super::types::SchedulerError;
 
+/// Names of the six cron fields, in order.
+const CRON_FIELDS: [&str; 6] = [
+    "second",
+    "minute",
+    "hour",
+    "day-of-month",
+    "month",
+    "day-of-week",
+];
+/// Most runs [`preview_next_runs`] lists.
+const MAX_PREVIEW_RUNS: usize = 10;
+
+/// Checks that `expression` parses and still has a run ahead, so a bad
+/// expression is rejected when it is scheduled, naming the field at fault.
 pub(crate) fn validate_cron_expression(expression: &str) -> Result<(), SchedulerError> {
-    let fields = expression.split_whitespace().count();
-    if fields != 6 {
-        return Err(SchedulerError::InvalidCron(fields));
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    if fields.len() != 6 {
+        return Err(SchedulerError::InvalidCron(fields.len()));
+    }
+    let schedule = match CronSchedule::from_str(expression) {
+        Ok(schedule) => schedule,
+        Err(err) => {
+            // Parse each field on its own to find the one at fault.
+            let culprit = (0..fields.len()).find(|&position| {
+                let mut probe = ["0", "0", "0", "*", "*", "*"];
+                probe[position] = fields[position];
+                CronSchedule::from_str(&probe.join(" ")).is_err()
+            });
+            return Err(SchedulerError::InvalidCronField(match culprit {
+                Some(position) => format!(
+                    "bad {} field {:?} in {:?}",
+                    CRON_FIELDS[position], fields[position], expression
+                ),
+                None => format!("{:?}: {}", expression, err),
+            }));
+        }
+    };
+    if schedule.upcoming(Utc).next().is_none() {
+        return Err(SchedulerError::NoNextRun);
     }
     Ok(())
 }
 
+/// The next `count` runs of `expression` (at most [`MAX_PREVIEW_RUNS`]) in
+/// the IANA time zone `timezone`. Cron expressions run in UTC; the zone only
+/// changes how the runs read, e.g. "Tue Mar 10, 9:00 AM PDT".
+pub(crate) fn preview_next_runs(
+    expression: &str,
+    timezone: &str,
+    count: usize,
+) -> Result<Vec<DateTime<Tz>>, SchedulerError> {
+    preview_next_runs_after(expression, timezone, count, Utc::now())
+}
+
+fn preview_next_runs_after(
+    expression: &str,
+    timezone: &str,
+    count: usize,
+    after: DateTime<Utc>,
+) -> Result<Vec<DateTime<Tz>>, SchedulerError> {
+    let zone: Tz = timezone
+        .trim()
+        .parse()
+        .map_err(|_| SchedulerError::InvalidTimezone(timezone.to_string()))?;
+    validate_cron_expression(expression)?;
+    let schedule = CronSchedule::from_str(expression)?;
+    Ok(schedule
+        .after(&after)
+        .take(count.clamp(1, MAX_PREVIEW_RUNS))
+        .map(|run| run.with_timezone(&zone))
+        .collect())
+}
+
+/// A previewed run as the user reads it, e.g. "Tue Mar 10, 9:00 AM PDT".
+pub(crate) fn format_preview_run(run: &DateTime<Tz>) -> String {
+    run.format("%a %b %-d, %-I:%M %p %Z").to_string()
+}
+
 pub(crate) fn next_run_after(
     expression: &str,
     after: DateTime<Utc>,
@@ -25,3 +97,302 @@ pub(crate) fn next_run_after(
     }
     Err(SchedulerError::NoNextRun)
 }
+
+/// Cron expression for an RRULE-style recurrence. Intervals are accepted only
+/// where cron repeats exactly: hourly intervals that divide a day and monthly
+/// intervals that divide a year. Without `at` the recurrence keeps the time of
+/// day of `now`, like an RRULE keeps its start time.
+pub(crate) fn recurrence_cron_expression(
+    frequency: RecurrenceFrequency,
+    interval: u32,
+    by_weekday: &[String],
+    by_month_day: &[u32],
+    at: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<String, SchedulerError> {
+    let invalid = |message: String| SchedulerError::InvalidRecurrence(message);
+    if interval == 0 {
+        return Err(invalid("interval must be at least 1".to_string()));
+    }
+    let time = match at.map(str::trim).filter(|raw| !raw.is_empty()) {
+        Some(raw) => NaiveTime::parse_from_str(raw, "%H:%M")
+            .map_err(|_| invalid(format!("at must be HH:MM, got {:?}", raw)))?,
+        None => now.time(),
+    };
+    let (hour, minute) = (time.hour(), time.minute());
+    let weekdays = by_weekday
+        .iter()
+        .map(|raw| parse_weekday(raw).ok_or_else(|| invalid(format!("unknown weekday {:?}", raw))))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !by_month_day.is_empty() && frequency != RecurrenceFrequency::Monthly {
+        return Err(invalid(
+            "by_month_day only applies to monthly recurrences".to_string(),
+        ));
+    }
+    let day_of_week = if weekdays.is_empty() {
+        "*".to_string()
+    } else {
+        weekdays.join(",")
+    };
+
+    match frequency {
+        RecurrenceFrequency::Hourly => {
+            if 24 % interval != 0 {
+                return Err(invalid(format!(
+                    "an hourly interval must divide 24, got {}",
+                    interval
+                )));
+            }
+            let hours = if interval == 1 {
+                "*".to_string()
+            } else {
+                format!("*/{}", interval)
+            };
+            Ok(format!("0 {} {} * * {}", minute, hours, day_of_week))
+        }
+        RecurrenceFrequency::Daily | RecurrenceFrequency::Weekly => {
+            if interval != 1 {
+                return Err(invalid(
+                    "daily and weekly recurrences only support interval 1; use a cron expression"
+                        .to_string(),
+                ));
+            }
+            let day_of_week = if frequency == RecurrenceFrequency::Weekly && weekdays.is_empty() {
+                weekday_name(now.weekday()).to_string()
+            } else {
+                day_of_week
+            };
+            Ok(format!("0 {} {} * * {}", minute, hour, day_of_week))
+        }
+        RecurrenceFrequency::Monthly => {
+            if !weekdays.is_empty() {
+                return Err(invalid(
+                    "by_weekday does not apply to monthly recurrences".to_string(),
+                ));
+            }
+            if 12 % interval != 0 {
+                return Err(invalid(format!(
+                    "a monthly interval must divide 12, got {}",
+                    interval
+                )));
+            }
+            let mut days = if by_month_day.is_empty() {
+                vec![now.day()]
+            } else {
+                by_month_day.to_vec()
+            };
+            if let Some(day) = days.iter().find(|day| !(1..=31).contains(*day)) {
+                return Err(invalid(format!("by_month_day {} is out of range", day)));
+            }
+            days.sort_unstable();
+            days.dedup();
+            let months = if interval == 1 {
+                "*".to_string()
+            } else {
+                let mut months: Vec<u32> = (0..12 / interval)
+                    .map(|step| (now.month0() + step * interval) % 12 + 1)
+                    .collect();
+                months.sort_unstable();
+                join_numbers(&months)
+            };
+            Ok(format!(
+                "0 {} {} {} {} *",
+                minute,
+                hour,
+                join_numbers(&days),
+                months
+            ))
+        }
+    }
+}
+
+fn parse_weekday(raw: &str) -> Option<&'static str> {
+    let lowered = raw.trim().to_ascii_lowercase();
+    let weekday = match lowered.as_str() {
+        "mo" | "mon" | "monday" => Weekday::Mon,
+        "tu" | "tue" | "tuesday" => Weekday::Tue,
+        "we" | "wed" | "wednesday" => Weekday::Wed,
+        "th" | "thu" | "thursday" => Weekday::Thu,
+        "fr" | "fri" | "friday" => Weekday::Fri,
+        "sa" | "sat" | "saturday" => Weekday::Sat,
+        "su" | "sun" | "sunday" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday_name(weekday))
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Mon",
+        Weekday::Tue => "Tue",
+        Weekday::Wed => "Wed",
+        Weekday::Thu => "Thu",
+        Weekday::Fri => "Fri",
+        Weekday::Sat => "Sat",
+        Weekday::Sun => "Sun",
+    }
+}
+
+fn join_numbers(values: &[u32]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn weekdays(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn recurrences_become_cron_expressions() {
+        // A Wednesday.
+        let now = Utc.with_ymd_and_hms(2026, 3, 4, 14, 25, 0).unwrap();
+        let cases = [
+            (
+                RecurrenceFrequency::Hourly,
+                6,
+                vec![],
+                vec![],
+                Some("00:15"),
+                "0 15 */6 * * *",
+            ),
+            (
+                RecurrenceFrequency::Daily,
+                1,
+                weekdays(&["MO", "tue", "Wednesday", "th", "fri"]),
+                vec![],
+                Some("09:00"),
+                "0 0 9 * * Mon,Tue,Wed,Thu,Fri",
+            ),
+            (
+                RecurrenceFrequency::Weekly,
+                1,
+                vec![],
+                vec![],
+                None,
+                "0 25 14 * * Wed",
+            ),
+            (
+                RecurrenceFrequency::Monthly,
+                3,
+                vec![],
+                vec![15, 1],
+                Some("08:30"),
+                "0 30 8 1,15 3,6,9,12 *",
+            ),
+            (
+                RecurrenceFrequency::Monthly,
+                1,
+                vec![],
+                vec![],
+                Some("08:30"),
+                "0 30 8 4 * *",
+            ),
+        ];
+        for (frequency, interval, by_weekday, by_month_day, at, expected) in cases {
+            let expression = recurrence_cron_expression(
+                frequency,
+                interval,
+                &by_weekday,
+                &by_month_day,
+                at,
+                now,
+            )
+            .unwrap();
+            assert_eq!(expression, expected);
+            next_run_after(&expression, now).unwrap();
+        }
+    }
+
+    #[test]
+    fn cron_errors_name_the_field_at_fault() {
+        let cases = [
+            ("0 9 * * 1", "expected 6 fields"),
+            ("0 0 25 * * *", "bad hour field \"25\""),
+            ("0 0 9 * * Frii", "bad day-of-week field \"Frii\""),
+        ];
+        for (expression, expected) in cases {
+            let err = validate_cron_expression(expression)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(expected), "{}: {}", expression, err);
+        }
+        assert!(matches!(
+            validate_cron_expression("0 0 0 30 2 *"),
+            Err(SchedulerError::NoNextRun)
+        ));
+        validate_cron_expression("0 0 17 * * Tue").unwrap();
+    }
+
+    #[test]
+    fn preview_shows_next_runs_in_the_requested_zone() {
+        // A Wednesday.
+        let now = Utc.with_ymd_and_hms(2026, 3, 4, 14, 25, 0).unwrap();
+        let runs =
+            preview_next_runs_after("0 0 17 * * Tue", "America/Los_Angeles", 2, now).unwrap();
+        let labels: Vec<String> = runs.iter().map(format_preview_run).collect();
+        // Daylight saving time starts on March 8.
+        assert_eq!(
+            labels,
+            ["Tue Mar 10, 10:00 AM PDT", "Tue Mar 17, 10:00 AM PDT"]
+        );
+
+        let runs = preview_next_runs_after("0 30 * * * *", "UTC", 50, now).unwrap();
+        assert_eq!(runs.len(), MAX_PREVIEW_RUNS);
+        assert_eq!(format_preview_run(&runs[0]), "Wed Mar 4, 2:30 PM UTC");
+        assert!(matches!(
+            preview_next_runs_after("0 30 * * * *", "Mars/Olympus", 1, now),
+            Err(SchedulerError::InvalidTimezone(_))
+        ));
+    }
+
+    #[test]
+    fn recurrences_cron_cannot_repeat_exactly_are_rejected() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 4, 14, 25, 0).unwrap();
+        let rejected = [
+            (RecurrenceFrequency::Hourly, 5, vec![], vec![], None),
+            (RecurrenceFrequency::Daily, 2, vec![], vec![], None),
+            (RecurrenceFrequency::Monthly, 5, vec![], vec![], None),
+            (
+                RecurrenceFrequency::Monthly,
+                1,
+                weekdays(&["mon"]),
+                vec![],
+                None,
+            ),
+            (RecurrenceFrequency::Weekly, 1, vec![], vec![3], None),
+            (
+                RecurrenceFrequency::Weekly,
+                1,
+                weekdays(&["someday"]),
+                vec![],
+                None,
+            ),
+            (RecurrenceFrequency::Daily, 1, vec![], vec![], Some("9am")),
+            (RecurrenceFrequency::Daily, 0, vec![], vec![], None),
+        ];
+        for (frequency, interval, by_weekday, by_month_day, at) in rejected {
+            let result = recurrence_cron_expression(
+                frequency,
+                interval,
+                &by_weekday,
+                &by_month_day,
+                at,
+                now,
+            );
+            assert!(
+                matches!(result, Err(SchedulerError::InvalidRecurrence(_))),
+                "{:?} every {} should be rejected",
+                frequency,
+                interval
+            );
+        }
+    }
+}
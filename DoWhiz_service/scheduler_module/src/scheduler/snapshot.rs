@@ -54,6 +54,10 @@ pub(crate) struct SchedulerSnapshot {
     pub(crate) upcoming: Vec<SchedulerSnapshotTask>,
     pub(crate) omitted_past_due: usize,
     pub(crate) omitted_after_window: usize,
+    /// Enabled tasks scheduled from the run's own thread, whatever their
+    /// window. Only written into run_task workspaces.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) thread_tasks: Vec<SchedulerSnapshotTask>,
 }
 
 #[derive(Debug, Serialize)]
@@ -84,7 +88,8 @@ pub(crate) fn write_scheduler_snapshot(
     tasks: &[ScheduledTask],
     now: DateTime<Utc>,
 ) -> Result<(), SchedulerError> {
-    let snapshot = build_scheduler_snapshot(tasks, now);
+    let mut snapshot = build_scheduler_snapshot(tasks, now);
+    snapshot.thread_tasks = thread_tasks(tasks, workspace_dir, now);
     let payload = serde_json::to_string_pretty(&snapshot)
         .map_err(|err| SchedulerError::Storage(format!("snapshot json error: {}", err)))?;
     let path = workspace_dir.join(SCHEDULER_SNAPSHOT_FILENAME);
@@ -104,36 +109,21 @@ pub(crate) fn build_scheduler_snapshot(
     let mut total_enabled = 0usize;
 
     for task in tasks {
-        if !task.enabled {
+        // Heartbeats are service plumbing; keep them out of the agent's view.
+        if !task.enabled || task.heartbeat_deadline().is_some() {
             continue;
         }
         total_enabled += 1;
         let next_run = schedule_next_run_at(&task.schedule);
         if task.is_due(now) {
-            due.push(SchedulerSnapshotTask {
-                id: task.id.to_string(),
-                kind: task_kind_label(&task.kind).to_string(),
-                schedule: snapshot_schedule(&task.schedule),
-                next_run,
-                last_run: task.last_run,
-                status: task_status_label(task, now),
-                label: task_label(&task.kind),
-            });
+            due.push(snapshot_task(task, now));
             continue;
         }
         if next_run > window_end {
             omitted_after_window += 1;
             continue;
         }
-        upcoming.push(SchedulerSnapshotTask {
-            id: task.id.to_string(),
-            kind: task_kind_label(&task.kind).to_string(),
-            schedule: snapshot_schedule(&task.schedule),
-            next_run,
-            last_run: task.last_run,
-            status: task_status_label(task, now),
-            label: task_label(&task.kind),
-        });
+        upcoming.push(snapshot_task(task, now));
     }
 
     due.sort_by_key(|task| task.next_run);
@@ -148,6 +138,7 @@ pub(crate) fn build_scheduler_snapshot(
         upcoming,
         omitted_past_due,
         omitted_after_window,
+        thread_tasks: Vec::new(),
     }
 }
 
@@ -173,6 +164,42 @@ fn schedule_next_run_at(schedule: &Schedule) -> DateTime<Utc> {
     }
 }
 
+/// Enabled tasks scheduled from the run_task thread in `workspace_dir`:
+/// later runs of the thread and replies drafted in it.
+pub(crate) fn thread_tasks(
+    tasks: &[ScheduledTask],
+    workspace_dir: &Path,
+    now: DateTime<Utc>,
+) -> Vec<SchedulerSnapshotTask> {
+    let mut listed: Vec<SchedulerSnapshotTask> = tasks
+        .iter()
+        .filter(|task| task.enabled && belongs_to_thread(task, workspace_dir))
+        .map(|task| snapshot_task(task, now))
+        .collect();
+    listed.sort_by_key(|task| task.next_run);
+    listed
+}
+
+pub(crate) fn belongs_to_thread(task: &ScheduledTask, workspace_dir: &Path) -> bool {
+    match &task.kind {
+        TaskKind::RunTask(task) => task.workspace_dir == workspace_dir,
+        TaskKind::SendReply(task) => task.html_path.starts_with(workspace_dir),
+        TaskKind::Noop(_) | TaskKind::Digest(_) | TaskKind::Probe(_) => false,
+    }
+}
+
+fn snapshot_task(task: &ScheduledTask, now: DateTime<Utc>) -> SchedulerSnapshotTask {
+    SchedulerSnapshotTask {
+        id: task.id.to_string(),
+        kind: task_kind_label(&task.kind).to_string(),
+        schedule: snapshot_schedule(&task.schedule),
+        next_run: schedule_next_run_at(&task.schedule),
+        last_run: task.last_run,
+        status: task_status_label(task, now),
+        label: task_label(task),
+    }
+}
+
 fn task_status_label(task: &ScheduledTask, now: DateTime<Utc>) -> String {
     if !task.enabled {
         if task.last_run.is_some() {
@@ -187,8 +214,16 @@ fn task_status_label(task: &ScheduledTask, now: DateTime<Utc>) -> String {
     }
 }
 
-fn task_label(kind: &TaskKind) -> Option<String> {
-    match kind {
+fn task_label(task: &ScheduledTask) -> Option<String> {
+    if let Some(description) = task
+        .description
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        return Some(truncate_label(description, 120));
+    }
+    match &task.kind {
         TaskKind::SendReply(task) => {
             if task.subject.trim().is_empty() {
                 None
@@ -209,7 +244,9 @@ fn task_label(kind: &TaskKind) -> Option<String> {
                     .map(|value| truncate_label(&value.to_string_lossy(), 120))
             }
         }
-        TaskKind::Noop => None,
+        TaskKind::Noop(_) => None,
+        TaskKind::Digest(_) => Some("Daily digest".to_string()),
+        TaskKind::Probe(_) => Some("Health probe".to_string()),
     }
 }
 
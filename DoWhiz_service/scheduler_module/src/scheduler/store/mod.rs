@@ -1,12 +1,17 @@
-use chrono::{DateTime, Utc};
-use std::path::PathBuf;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 use super::types::{ScheduledTask, SchedulerError};
 
 mod mongo;
 
-use mongo::MongoSchedulerStore;
+pub(crate) use mongo::{
+    derive_run_task_summary, execution_counts_by_task, list_task_deliveries, list_task_executions,
+    record_delivery_outcome, task_execution_log_path,
+};
+use mongo::{MongoSchedulerStore, MongoTaskLeaseStore};
 
 #[derive(Debug)]
 pub(crate) struct SchedulerStore {
@@ -20,6 +25,10 @@ impl SchedulerStore {
         })
     }
 
+    pub(crate) fn owner_user(&self) -> Option<String> {
+        self.mongo.owner_user()
+    }
+
     pub(crate) fn load_tasks(&self) -> Result<Vec<ScheduledTask>, SchedulerError> {
         self.mongo.load_tasks()
     }
@@ -32,12 +41,64 @@ impl SchedulerStore {
         self.mongo.update_task(task)
     }
 
+    pub(crate) fn commit_completion(
+        &self,
+        task: &ScheduledTask,
+        follow_ups: &[ScheduledTask],
+    ) -> Result<(), SchedulerError> {
+        self.mongo.commit_completion(task, follow_ups)
+    }
+
+    pub(crate) fn begin_delivery(
+        &self,
+        task_id: &str,
+        reply_key: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<DeliveryState, SchedulerError> {
+        self.mongo.begin_delivery(task_id, reply_key, now)
+    }
+
+    pub(crate) fn finish_delivery(
+        &self,
+        task_id: &str,
+        delivered: bool,
+        provider_message_id: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<(), SchedulerError> {
+        self.mongo
+            .finish_delivery(task_id, delivered, provider_message_id, now)
+    }
+
+    pub(crate) fn record_intent(
+        &self,
+        task_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<bool, SchedulerError> {
+        self.mongo.record_intent(task_id, now)
+    }
+
+    pub(crate) fn close_intent(
+        &self,
+        task_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), SchedulerError> {
+        self.mongo.close_intent(task_id, now)
+    }
+
+    pub(crate) fn open_intents(&self) -> Result<Vec<(String, DateTime<Utc>)>, SchedulerError> {
+        self.mongo.open_intents()
+    }
+
+    /// With `log_dir`, also names the run's log in it; see
+    /// [`MongoSchedulerStore::record_execution_start`].
     pub(crate) fn record_execution_start(
         &self,
         task_id: Uuid,
         started_at: DateTime<Utc>,
-    ) -> Result<i64, SchedulerError> {
-        self.mongo.record_execution_start(task_id, started_at)
+        log_dir: Option<&Path>,
+    ) -> Result<(i64, Option<PathBuf>), SchedulerError> {
+        self.mongo
+            .record_execution_start(task_id, started_at, log_dir)
     }
 
     pub(crate) fn record_execution_finish(
@@ -76,6 +137,61 @@ impl SchedulerStore {
     pub fn list_tasks_with_status(&self) -> Result<Vec<TaskStatusSummary>, SchedulerError> {
         self.mongo.list_tasks_with_status()
     }
+
+    pub(crate) fn leases(&self) -> TaskLeaseStore {
+        TaskLeaseStore {
+            mongo: self.mongo.leases(),
+        }
+    }
+}
+
+/// Lease operations for one owner scope; cheap to clone into a renewal task.
+#[derive(Debug, Clone)]
+pub(crate) struct TaskLeaseStore {
+    mongo: MongoTaskLeaseStore,
+}
+
+impl TaskLeaseStore {
+    /// Take the lease if it is free, expired, or already held by `owner`.
+    pub(crate) fn acquire(
+        &self,
+        task_id: &str,
+        owner: &str,
+        now: DateTime<Utc>,
+        ttl: ChronoDuration,
+    ) -> Result<bool, SchedulerError> {
+        self.mongo.acquire(task_id, owner, now, ttl)
+    }
+
+    /// Extend a lease still held by `owner`; false once another worker took it.
+    pub(crate) fn renew(
+        &self,
+        task_id: &str,
+        owner: &str,
+        now: DateTime<Utc>,
+        ttl: ChronoDuration,
+    ) -> Result<bool, SchedulerError> {
+        self.mongo.renew(task_id, owner, now, ttl)
+    }
+
+    pub(crate) fn release(&self, task_id: &str, owner: &str) -> Result<(), SchedulerError> {
+        self.mongo.release(task_id, owner)
+    }
+}
+
+/// Where a send_reply task stood before the current delivery attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DeliveryState {
+    /// Never attempted, or the last attempt reported a failure.
+    Pending,
+    /// A previous attempt started but never recorded its outcome; the provider
+    /// may already have the message.
+    Interrupted,
+    /// Already delivered; only the task's finalization was lost.
+    Delivered,
+    /// Another task holds the same reply key, e.g. the reply of a retried run
+    /// whose earlier attempt also completed; this one must not send.
+    Duplicate,
 }
 
 /// Summary of a task with its latest execution status.
@@ -97,4 +213,98 @@ pub struct TaskStatusSummary {
     pub execution_status: Option<String>,
     pub error_message: Option<String>,
     pub execution_started_at: Option<String>,
+    /// "pending", "approved" or "rejected" for tasks held for approval.
+    pub approval_status: Option<String>,
+    /// Description the agent gave when it created the task.
+    pub description: Option<String>,
+    /// End condition of a recurring task: the last time it may run and the
+    /// runs it has left.
+    pub ends_at: Option<String>,
+    pub remaining_runs: Option<u32>,
+}
+
+const DEFAULT_EXECUTION_LIMIT: i64 = 100;
+const MAX_EXECUTION_LIMIT: i64 = 1000;
+
+/// Filters for [`list_task_executions`]; unset fields match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExecutionQuery {
+    pub user_id: Option<String>,
+    pub task_id: Option<String>,
+    /// "running", "success" or "failed".
+    pub status: Option<String>,
+    /// Only executions started after this instant.
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+}
+
+impl ExecutionQuery {
+    pub(crate) fn limit(&self) -> i64 {
+        self.limit
+            .unwrap_or(DEFAULT_EXECUTION_LIMIT)
+            .clamp(1, MAX_EXECUTION_LIMIT)
+    }
+}
+
+/// One run of a task, across every scheduler database.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TaskExecutionRecord {
+    /// Row id; names the execution's log.
+    pub id: String,
+    /// "user" for a user's tasks; other scopes are keyed by path.
+    pub owner_kind: String,
+    pub owner_id: String,
+    pub task_id: String,
+    pub status: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub error_message: Option<String>,
+    /// `<workspace>/.logs/exec_<id>.log` for run_task executions.
+    pub log_path: Option<String>,
+}
+
+/// Delivery journal of one send_reply task, as left by `begin_delivery` and
+/// `finish_delivery`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TaskDeliveryRecord {
+    pub task_id: String,
+    /// "sending", "sent" or "failed"; "sending" after a crash mid-send.
+    pub state: String,
+    pub attempts: u32,
+    pub started_at: Option<String>,
+    pub delivered_at: Option<String>,
+    /// What the provider reported after accepting the reply: "delivered",
+    /// "bounced" or "soft_bounced". Only email reports outcomes.
+    pub outcome: Option<String>,
+    /// The provider's description of a bounce.
+    pub outcome_detail: Option<String>,
+}
+
+/// What a provider reported for a reply it accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryOutcome {
+    Delivered,
+    /// The address does not exist or no longer accepts mail.
+    Bounced,
+    /// A temporary failure, such as a full mailbox.
+    SoftBounced,
+}
+
+impl DeliveryOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Delivered => "delivered",
+            Self::Bounced => "bounced",
+            Self::SoftBounced => "soft_bounced",
+        }
+    }
+}
+
+/// The send_reply task a provider event was matched to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderEventMatch {
+    /// "user" for a user's scheduler.
+    pub owner_kind: String,
+    pub owner_id: String,
+    pub task_id: String,
 }
@@ -1,25 +1,33 @@
 use chrono::{Duration as ChronoDuration, Utc};
+use mongodb::bson::oid::ObjectId;
 use mongodb::bson::{doc, Bson, DateTime as BsonDateTime, Document};
-use mongodb::options::{FindOneOptions, FindOptions, UpdateOptions};
-use mongodb::sync::Collection;
-use mongodb::IndexModel;
-use std::collections::HashSet;
+use mongodb::error::ErrorKind;
+use mongodb::error::WriteFailure;
+use mongodb::options::{
+    FindOneAndUpdateOptions, FindOneOptions, FindOptions, ReturnDocument, UpdateOptions,
+};
+use mongodb::sync::{Client, Collection};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicI64, Ordering};
 use uuid::Uuid;
 
-use crate::mongo_store::{create_client_from_env, database_from_env, ensure_index_compatible};
+use crate::mongo_store::{create_client_from_env, database_from_env};
 
 use super::super::types::{Schedule, ScheduledTask, SchedulerError};
 use super::super::utils::{task_kind_channel, task_kind_label};
-use super::TaskStatusSummary;
+use super::{
+    DeliveryOutcome, DeliveryState, ExecutionQuery, ProviderEventMatch, TaskDeliveryRecord,
+    TaskExecutionRecord, TaskStatusSummary,
+};
 
 static EXECUTION_SEQ: AtomicI64 = AtomicI64::new(1);
 const REQUEST_SUMMARY_MAX_CHARS: usize = 72;
 
 #[derive(Debug)]
 pub(crate) struct MongoSchedulerStore {
+    client: Client,
     tasks: Collection<Document>,
     executions: Collection<Document>,
     owner_kind: String,
@@ -32,34 +40,9 @@ impl MongoSchedulerStore {
         let db = database_from_env(&client);
         let (owner_kind, owner_id) = resolve_owner_scope(tasks_db_path);
         let tasks = db.collection::<Document>("tasks");
-        ensure_index_compatible(
-            &tasks,
-            IndexModel::builder()
-                .keys(doc! { "owner_scope.kind": 1, "owner_scope.id": 1, "task_id": 1 })
-                .build(),
-        )
-        .map_err(mongo_err)?;
-        ensure_index_compatible(
-            &tasks,
-            IndexModel::builder()
-                .keys(doc! { "owner_scope.kind": 1, "owner_scope.id": 1, "created_at": 1 })
-                .build(),
-        )
-        .map_err(mongo_err)?;
         let executions = db.collection::<Document>("task_executions");
-        ensure_index_compatible(
-            &executions,
-            IndexModel::builder()
-                .keys(doc! {
-                    "owner_scope.kind": 1,
-                    "owner_scope.id": 1,
-                    "task_id": 1,
-                    "started_at": -1
-                })
-                .build(),
-        )
-        .map_err(mongo_err)?;
         Ok(Self {
+            client,
             tasks,
             executions,
             owner_kind,
@@ -67,6 +50,11 @@ impl MongoSchedulerStore {
         })
     }
 
+    /// `user:<id>` when the tasks belong to a user; `None` for path-scoped stores.
+    pub(crate) fn owner_user(&self) -> Option<String> {
+        (self.owner_kind == "user").then(|| format!("user:{}", self.owner_id))
+    }
+
     pub(crate) fn load_tasks(&self) -> Result<Vec<ScheduledTask>, SchedulerError> {
         let cursor = self
             .tasks
@@ -136,22 +124,10 @@ impl MongoSchedulerStore {
     }
 
     pub(crate) fn update_task(&self, task: &ScheduledTask) -> Result<(), SchedulerError> {
-        let task_json = serde_json::to_string(task)
-            .map_err(|err| SchedulerError::Storage(format!("serialize task failed: {err}")))?;
         let filter = self.task_filter(&task.id.to_string());
-        let result = self.tasks
-            .update_one(
-                filter.clone(),
-                doc! {
-                    "$set": {
-                        "enabled": task.enabled,
-                        "last_run": task.last_run.map(BsonDateTime::from_chrono).map(Bson::DateTime).unwrap_or(Bson::Null),
-                        "schedule": schedule_doc(&task.schedule),
-                        "task_json": task_json,
-                    }
-                },
-                None,
-            )
+        let result = self
+            .tasks
+            .update_one(filter.clone(), task_update_doc(task)?, None)
             .map_err(mongo_err)?;
 
         // Log warning if no document was matched - this indicates a bug
@@ -175,15 +151,242 @@ impl MongoSchedulerStore {
         Ok(())
     }
 
+    /// Persist a finished task together with the follow-up tasks it produced
+    /// (replies, scheduled sends) in one transaction, so a crash can neither
+    /// drop the replies nor leave the task to run again after they were
+    /// written. Standalone servers cannot run transactions; there the
+    /// follow-ups are written first and the completion last. Follow-ups are
+    /// insert-only, so replaying the completion never duplicates a reply or
+    /// re-enables one that was already sent.
+    pub(crate) fn commit_completion(
+        &self,
+        task: &ScheduledTask,
+        follow_ups: &[ScheduledTask],
+    ) -> Result<(), SchedulerError> {
+        if follow_ups.is_empty() {
+            return self.update_task(task);
+        }
+        let completion = task_update_doc(task)?;
+        let inserts = follow_ups
+            .iter()
+            .map(|follow_up| Ok((follow_up.id.to_string(), self.task_insert_doc(follow_up)?)))
+            .collect::<Result<Vec<_>, SchedulerError>>()?;
+        match self.commit_completion_in_transaction(task, &completion, &inserts) {
+            Ok(()) => Ok(()),
+            Err(err) if transactions_unsupported(&err) => {
+                tracing::debug!(
+                    "transactions unavailable; committing task {} follow-ups before completion",
+                    task.id
+                );
+                let upsert = UpdateOptions::builder().upsert(Some(true)).build();
+                for (task_id, insert) in inserts {
+                    self.tasks
+                        .update_one(self.task_filter(&task_id), insert, upsert.clone())
+                        .map_err(mongo_err)?;
+                }
+                self.tasks
+                    .update_one(self.task_filter(&task.id.to_string()), completion, None)
+                    .map_err(mongo_err)?;
+                Ok(())
+            }
+            Err(err) => Err(mongo_err(err)),
+        }
+    }
+
+    fn commit_completion_in_transaction(
+        &self,
+        task: &ScheduledTask,
+        completion: &Document,
+        inserts: &[(String, Document)],
+    ) -> mongodb::error::Result<()> {
+        let mut session = self.client.start_session(None)?;
+        session.start_transaction(None)?;
+        let upsert = UpdateOptions::builder().upsert(Some(true)).build();
+        for (task_id, insert) in inserts {
+            self.tasks.update_one_with_session(
+                self.task_filter(task_id),
+                insert.clone(),
+                upsert.clone(),
+                &mut session,
+            )?;
+        }
+        self.tasks.update_one_with_session(
+            self.task_filter(&task.id.to_string()),
+            completion.clone(),
+            None,
+            &mut session,
+        )?;
+        session.commit_transaction()
+    }
+
+    /// Mark a send_reply task as being delivered and report what earlier
+    /// attempts left behind. A delivered task is left untouched. `reply_key`
+    /// is stored on the task; when another task of the owner already holds
+    /// it, this one is marked `duplicate` and must not send.
+    pub(crate) fn begin_delivery(
+        &self,
+        task_id: &str,
+        reply_key: Option<&str>,
+        now: chrono::DateTime<Utc>,
+    ) -> Result<DeliveryState, SchedulerError> {
+        let mut filter = self.task_filter(task_id);
+        filter.insert("delivery_state", doc! { "$ne": "sent" });
+        let mut set = doc! {
+            "delivery_state": "sending",
+            "delivery_started_at": BsonDateTime::from_chrono(now),
+        };
+        if let Some(reply_key) = reply_key {
+            set.insert("reply_key", reply_key);
+        }
+        let previous = match self.tasks.find_one_and_update(
+            filter,
+            doc! { "$set": set, "$inc": { "delivery_attempts": 1i32 } },
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::Before)
+                .build(),
+        ) {
+            Ok(previous) => previous,
+            Err(err) if is_duplicate_key(&err) => {
+                self.tasks
+                    .update_one(
+                        self.task_filter(task_id),
+                        doc! { "$set": { "delivery_state": "duplicate" } },
+                        None,
+                    )
+                    .map_err(mongo_err)?;
+                return Ok(DeliveryState::Duplicate);
+            }
+            Err(err) => return Err(mongo_err(err)),
+        };
+        if let Some(previous) = previous {
+            return Ok(match previous.get_str("delivery_state") {
+                Ok("sending") => DeliveryState::Interrupted,
+                _ => DeliveryState::Pending,
+            });
+        }
+        let exists = self
+            .tasks
+            .find_one(self.task_filter(task_id), None)
+            .map_err(mongo_err)?
+            .is_some();
+        Ok(if exists {
+            DeliveryState::Delivered
+        } else {
+            DeliveryState::Pending
+        })
+    }
+
+    /// Record the outcome of the attempt opened by `begin_delivery`. A failed
+    /// attempt gives up its reply key so another task may send the reply.
+    /// `provider_message_id` ties a sent reply to later provider events.
+    pub(crate) fn finish_delivery(
+        &self,
+        task_id: &str,
+        delivered: bool,
+        provider_message_id: Option<&str>,
+        now: chrono::DateTime<Utc>,
+    ) -> Result<(), SchedulerError> {
+        let update = if delivered {
+            let mut set =
+                doc! { "delivery_state": "sent", "delivered_at": BsonDateTime::from_chrono(now) };
+            if let Some(message_id) = provider_message_id {
+                set.insert("provider_message_id", message_id);
+            }
+            doc! { "$set": set }
+        } else {
+            doc! { "$set": { "delivery_state": "failed" }, "$unset": { "reply_key": "" } }
+        };
+        self.tasks
+            .update_one(self.task_filter(task_id), update, None)
+            .map_err(mongo_err)?;
+        Ok(())
+    }
+
+    /// Record the intent to run the one-shot `task_id` before a worker claims
+    /// it (`intent_state: "open"`). Returns false, writing nothing, when the
+    /// task is not an enabled one-shot.
+    pub(crate) fn record_intent(
+        &self,
+        task_id: &str,
+        now: chrono::DateTime<Utc>,
+    ) -> Result<bool, SchedulerError> {
+        let mut filter = self.task_filter(task_id);
+        filter.insert("enabled", true);
+        filter.insert("schedule.type", "one_shot");
+        let result = self
+            .tasks
+            .update_one(
+                filter,
+                doc! {
+                    "$set": {
+                        "intent_state": "open",
+                        "intent_claimed_at": BsonDateTime::from_chrono(now),
+                    },
+                    "$inc": { "intent_attempts": 1i32 },
+                },
+                None,
+            )
+            .map_err(mongo_err)?;
+        Ok(result.matched_count > 0)
+    }
+
+    /// Close the open intent of `task_id` once its run recorded an outcome.
+    pub(crate) fn close_intent(
+        &self,
+        task_id: &str,
+        now: chrono::DateTime<Utc>,
+    ) -> Result<(), SchedulerError> {
+        let mut filter = self.task_filter(task_id);
+        filter.insert("intent_state", "open");
+        self.tasks
+            .update_one(
+                filter,
+                doc! { "$set": {
+                    "intent_state": "closed",
+                    "intent_closed_at": BsonDateTime::from_chrono(now),
+                } },
+                None,
+            )
+            .map_err(mongo_err)?;
+        Ok(())
+    }
+
+    /// Task ids with an open intent, and when each was claimed.
+    pub(crate) fn open_intents(
+        &self,
+    ) -> Result<Vec<(String, chrono::DateTime<Utc>)>, SchedulerError> {
+        let mut filter = self.owner_filter();
+        filter.insert("intent_state", "open");
+        let cursor = self.tasks.find(filter, None).map_err(mongo_err)?;
+        let mut intents = Vec::new();
+        for row in cursor {
+            let document = row.map_err(mongo_err)?;
+            let (Ok(task_id), Ok(claimed_at)) = (
+                document.get_str("task_id"),
+                document.get_datetime("intent_claimed_at"),
+            ) else {
+                continue;
+            };
+            intents.push((task_id.to_string(), claimed_at.to_chrono()));
+        }
+        Ok(intents)
+    }
+
+    /// With `log_dir`, the run's log is `<log_dir>/exec_<row id>.log`; its
+    /// path is recorded on the row and returned.
     pub(crate) fn record_execution_start(
         &self,
         task_id: Uuid,
         started_at: chrono::DateTime<Utc>,
-    ) -> Result<i64, SchedulerError> {
+        log_dir: Option<&Path>,
+    ) -> Result<(i64, Option<PathBuf>), SchedulerError> {
         let execution_id = EXECUTION_SEQ.fetch_add(1, Ordering::Relaxed);
+        let row_id = ObjectId::new();
+        let log_path = log_dir.map(|dir| dir.join(format!("exec_{}.log", row_id.to_hex())));
         self.executions
             .insert_one(
                 doc! {
+                    "_id": row_id,
                     "owner_scope": self.owner_scope_doc(),
                     "execution_id": execution_id,
                     "task_id": task_id.to_string(),
@@ -191,11 +394,15 @@ impl MongoSchedulerStore {
                     "finished_at": Bson::Null,
                     "status": "running",
                     "error_message": Bson::Null,
+                    "log_path": log_path
+                        .as_ref()
+                        .map(|path| Bson::from(path.to_string_lossy().into_owned()))
+                        .unwrap_or(Bson::Null),
                 },
                 None,
             )
             .map_err(mongo_err)?;
-        Ok(execution_id)
+        Ok((execution_id, log_path))
     }
 
     pub(crate) fn record_execution_finish(
@@ -297,6 +504,8 @@ impl MongoSchedulerStore {
                 continue;
             }
             let request_summary = derive_request_summary(&task_doc);
+            let approval_status = derive_approval_status(&task_doc);
+            let (description, ends_at, remaining_runs) = derive_recurrence_details(&task_doc);
             let schedule = task_doc.get_document("schedule").ok();
             let execution = self
                 .executions
@@ -337,11 +546,23 @@ impl MongoSchedulerStore {
                 execution_started_at: execution
                     .as_ref()
                     .and_then(|doc| datetime_field_to_rfc3339(doc, "started_at")),
+                approval_status,
+                description,
+                ends_at,
+                remaining_runs,
             });
         }
         Ok(summaries)
     }
 
+    pub(crate) fn leases(&self) -> MongoTaskLeaseStore {
+        MongoTaskLeaseStore {
+            tasks: self.tasks.clone(),
+            owner_kind: self.owner_kind.clone(),
+            owner_id: self.owner_id.clone(),
+        }
+    }
+
     fn owner_filter(&self) -> Document {
         doc! {
             "owner_scope.kind": &self.owner_kind,
@@ -363,6 +584,151 @@ impl MongoSchedulerStore {
             "id": &self.owner_id,
         }
     }
+
+    /// Upsert body that writes `task` only when no document exists yet.
+    fn task_insert_doc(&self, task: &ScheduledTask) -> Result<Document, SchedulerError> {
+        let task_json = serde_json::to_string(task)
+            .map_err(|err| SchedulerError::Storage(format!("serialize task failed: {err}")))?;
+        Ok(doc! {
+            "$setOnInsert": {
+                "owner_scope": self.owner_scope_doc(),
+                "task_id": task.id.to_string(),
+                "kind": task_kind_label(&task.kind),
+                "channel": task_kind_channel(&task.kind).to_string(),
+                "enabled": task.enabled,
+                "created_at": BsonDateTime::from_chrono(task.created_at),
+                "last_run": task.last_run.map(BsonDateTime::from_chrono).map(Bson::DateTime).unwrap_or(Bson::Null),
+                "schedule": schedule_doc(&task.schedule),
+                "task_json": task_json,
+                "retry_count": 0i32,
+            }
+        })
+    }
+}
+
+/// Lease fields on task documents (`claimed_by`, `lease_expires_at`), shared by
+/// every worker that loads the same owner scope.
+#[derive(Debug, Clone)]
+pub(crate) struct MongoTaskLeaseStore {
+    tasks: Collection<Document>,
+    owner_kind: String,
+    owner_id: String,
+}
+
+impl MongoTaskLeaseStore {
+    pub(crate) fn acquire(
+        &self,
+        task_id: &str,
+        owner: &str,
+        now: chrono::DateTime<Utc>,
+        ttl: ChronoDuration,
+    ) -> Result<bool, SchedulerError> {
+        let mut filter = self.task_filter(task_id);
+        filter.insert(
+            "$or",
+            vec![
+                doc! { "claimed_by": Bson::Null },
+                doc! { "claimed_by": owner },
+                doc! { "lease_expires_at": { "$lte": BsonDateTime::from_chrono(now) } },
+            ],
+        );
+        let result = self
+            .tasks
+            .update_one(
+                filter,
+                doc! {
+                    "$set": {
+                        "claimed_by": owner,
+                        "lease_expires_at": BsonDateTime::from_chrono(now + ttl),
+                    }
+                },
+                None,
+            )
+            .map_err(mongo_err)?;
+        if result.matched_count > 0 {
+            return Ok(true);
+        }
+        // A task with no stored document has nothing to contend for; let the
+        // caller go on and drop it from the index.
+        let exists = self
+            .tasks
+            .find_one(self.task_filter(task_id), None)
+            .map_err(mongo_err)?
+            .is_some();
+        Ok(!exists)
+    }
+
+    pub(crate) fn renew(
+        &self,
+        task_id: &str,
+        owner: &str,
+        now: chrono::DateTime<Utc>,
+        ttl: ChronoDuration,
+    ) -> Result<bool, SchedulerError> {
+        let mut filter = self.task_filter(task_id);
+        filter.insert("claimed_by", owner);
+        let result = self
+            .tasks
+            .update_one(
+                filter,
+                doc! { "$set": { "lease_expires_at": BsonDateTime::from_chrono(now + ttl) } },
+                None,
+            )
+            .map_err(mongo_err)?;
+        Ok(result.matched_count > 0)
+    }
+
+    pub(crate) fn release(&self, task_id: &str, owner: &str) -> Result<(), SchedulerError> {
+        let mut filter = self.task_filter(task_id);
+        filter.insert("claimed_by", owner);
+        self.tasks
+            .update_one(
+                filter,
+                doc! { "$set": { "claimed_by": Bson::Null, "lease_expires_at": Bson::Null } },
+                None,
+            )
+            .map_err(mongo_err)?;
+        Ok(())
+    }
+
+    fn task_filter(&self, task_id: &str) -> Document {
+        doc! {
+            "owner_scope.kind": &self.owner_kind,
+            "owner_scope.id": &self.owner_id,
+            "task_id": task_id,
+        }
+    }
+}
+
+fn task_update_doc(task: &ScheduledTask) -> Result<Document, SchedulerError> {
+    let task_json = serde_json::to_string(task)
+        .map_err(|err| SchedulerError::Storage(format!("serialize task failed: {err}")))?;
+    Ok(doc! {
+        "$set": {
+            "enabled": task.enabled,
+            "last_run": task.last_run.map(BsonDateTime::from_chrono).map(Bson::DateTime).unwrap_or(Bson::Null),
+            "schedule": schedule_doc(&task.schedule),
+            "task_json": task_json,
+        }
+    })
+}
+
+/// A unique index rejected the write (11000); `findAndModify` reports it as
+/// a command error.
+fn is_duplicate_key(err: &mongodb::error::Error) -> bool {
+    match err.kind.as_ref() {
+        ErrorKind::Command(command) => command.code == 11000,
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => write_error.code == 11000,
+        _ => false,
+    }
+}
+
+/// Standalone servers reject transactions with IllegalOperation (20).
+fn transactions_unsupported(err: &mongodb::error::Error) -> bool {
+    match err.kind.as_ref() {
+        ErrorKind::Command(command) => command.code == 20,
+        _ => false,
+    }
 }
 
 fn schedule_doc(schedule: &Schedule) -> Document {
@@ -417,6 +783,214 @@ fn resolve_owner_scope(path: &Path) -> (String, String) {
     ("path_scope".to_string(), hashed)
 }
 
+/// Executions matching `query` from every owner scope, newest first.
+pub(crate) fn list_task_executions(
+    query: &ExecutionQuery,
+) -> Result<Vec<TaskExecutionRecord>, SchedulerError> {
+    let client = create_client_from_env().map_err(mongo_config_err)?;
+    let executions = database_from_env(&client).collection::<Document>("task_executions");
+    let mut filter = Document::new();
+    if let Some(user_id) = query.user_id.as_deref().filter(|value| !value.is_empty()) {
+        filter.insert("owner_scope.kind", "user");
+        filter.insert("owner_scope.id", user_id);
+    }
+    for (key, value) in [("task_id", &query.task_id), ("status", &query.status)] {
+        if let Some(value) = value.as_deref().filter(|value| !value.is_empty()) {
+            filter.insert(key, value);
+        }
+    }
+    if let Some(since) = query.since {
+        filter.insert(
+            "started_at",
+            doc! { "$gt": BsonDateTime::from_chrono(since) },
+        );
+    }
+    let cursor = executions
+        .find(
+            filter,
+            FindOptions::builder()
+                .sort(doc! { "started_at": -1 })
+                .limit(query.limit())
+                .build(),
+        )
+        .map_err(mongo_err)?;
+    let mut records = Vec::new();
+    for row in cursor {
+        let document = row.map_err(mongo_err)?;
+        let owner_scope = document.get_document("owner_scope").ok();
+        let owner_field = |key| {
+            owner_scope
+                .and_then(|scope| scope.get_str(key).ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        records.push(TaskExecutionRecord {
+            id: document
+                .get_object_id("_id")
+                .map(|id| id.to_hex())
+                .unwrap_or_default(),
+            owner_kind: owner_field("kind"),
+            owner_id: owner_field("id"),
+            task_id: document.get_str("task_id").unwrap_or_default().to_string(),
+            status: document.get_str("status").unwrap_or("unknown").to_string(),
+            started_at: datetime_field_to_rfc3339(&document, "started_at"),
+            finished_at: datetime_field_to_rfc3339(&document, "finished_at"),
+            error_message: document
+                .get_str("error_message")
+                .ok()
+                .map(|value| value.to_string()),
+            log_path: document
+                .get_str("log_path")
+                .ok()
+                .map(|value| value.to_string()),
+        });
+    }
+    Ok(records)
+}
+
+/// Log path recorded on the execution row `id`; `None` when there is no such
+/// row or it has no log.
+pub(crate) fn task_execution_log_path(id: &str) -> Result<Option<PathBuf>, SchedulerError> {
+    let Ok(id) = ObjectId::parse_str(id) else {
+        return Ok(None);
+    };
+    let client = create_client_from_env().map_err(mongo_config_err)?;
+    let executions = database_from_env(&client).collection::<Document>("task_executions");
+    let row = executions
+        .find_one(
+            doc! { "_id": id },
+            FindOneOptions::builder()
+                .projection(doc! { "log_path": 1 })
+                .build(),
+        )
+        .map_err(mongo_err)?;
+    Ok(row
+        .as_ref()
+        .and_then(|row| row.get_str("log_path").ok())
+        .map(PathBuf::from))
+}
+
+/// Number of recorded executions per task ID in the user's scheduler.
+pub(crate) fn execution_counts_by_task(
+    user_id: &str,
+) -> Result<HashMap<String, u64>, SchedulerError> {
+    let client = create_client_from_env().map_err(mongo_config_err)?;
+    let executions = database_from_env(&client).collection::<Document>("task_executions");
+    let cursor = executions
+        .aggregate(
+            [
+                doc! { "$match": { "owner_scope.kind": "user", "owner_scope.id": user_id } },
+                doc! { "$group": { "_id": "$task_id", "count": { "$sum": 1 } } },
+            ],
+            None,
+        )
+        .map_err(mongo_err)?;
+    let mut counts = HashMap::new();
+    for row in cursor {
+        let document = row.map_err(mongo_err)?;
+        let Ok(task_id) = document.get_str("_id") else {
+            continue;
+        };
+        let count = match document.get("count") {
+            Some(Bson::Int32(count)) => *count as u64,
+            Some(Bson::Int64(count)) => *count as u64,
+            _ => 0,
+        };
+        counts.insert(task_id.to_string(), count);
+    }
+    Ok(counts)
+}
+
+/// Delivery journal of every send_reply task in the user's scheduler that
+/// has been attempted at least once.
+pub(crate) fn list_task_deliveries(
+    user_id: &str,
+) -> Result<Vec<TaskDeliveryRecord>, SchedulerError> {
+    let client = create_client_from_env().map_err(mongo_config_err)?;
+    let tasks = database_from_env(&client).collection::<Document>("tasks");
+    let cursor = tasks
+        .find(
+            doc! {
+                "owner_scope.kind": "user",
+                "owner_scope.id": user_id,
+                "delivery_state": { "$exists": true },
+            },
+            None,
+        )
+        .map_err(mongo_err)?;
+    let mut records = Vec::new();
+    for row in cursor {
+        let document = row.map_err(mongo_err)?;
+        records.push(TaskDeliveryRecord {
+            task_id: document.get_str("task_id").unwrap_or_default().to_string(),
+            state: document
+                .get_str("delivery_state")
+                .unwrap_or("unknown")
+                .to_string(),
+            attempts: numeric_field_to_u32(&document, "delivery_attempts").unwrap_or(0),
+            started_at: datetime_field_to_rfc3339(&document, "delivery_started_at"),
+            delivered_at: datetime_field_to_rfc3339(&document, "delivered_at"),
+            outcome: document
+                .get_str("delivery_outcome")
+                .ok()
+                .map(|value| value.to_string()),
+            outcome_detail: document
+                .get_str("delivery_outcome_detail")
+                .ok()
+                .map(|value| value.to_string()),
+        });
+    }
+    Ok(records)
+}
+
+/// Record what the provider reported after accepting the reply it assigned
+/// `message_id`, across every scheduler. A later "delivered" does not hide
+/// an earlier bounce to another recipient. Returns the reply's task, or
+/// `None` when no task carries the message ID.
+pub(crate) fn record_delivery_outcome(
+    message_id: &str,
+    outcome: DeliveryOutcome,
+    detail: Option<&str>,
+    at: chrono::DateTime<Utc>,
+) -> Result<Option<ProviderEventMatch>, SchedulerError> {
+    let client = create_client_from_env().map_err(mongo_config_err)?;
+    let tasks = database_from_env(&client).collection::<Document>("tasks");
+    let mut filter = doc! { "provider_message_id": message_id };
+    if outcome == DeliveryOutcome::Delivered {
+        filter.insert(
+            "delivery_outcome",
+            doc! { "$nin": ["bounced", "soft_bounced"] },
+        );
+    }
+    let mut set = doc! {
+        "delivery_outcome": outcome.as_str(),
+        "delivery_outcome_at": BsonDateTime::from_chrono(at),
+    };
+    if let Some(detail) = detail {
+        set.insert("delivery_outcome_detail", detail);
+    }
+    tasks
+        .update_one(filter, doc! { "$set": set }, None)
+        .map_err(mongo_err)?;
+    let document = tasks
+        .find_one(doc! { "provider_message_id": message_id }, None)
+        .map_err(mongo_err)?;
+    Ok(document.map(|document| {
+        let owner = document.get_document("owner_scope").ok();
+        let owner_field = |key| {
+            owner
+                .and_then(|owner| owner.get_str(key).ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+        ProviderEventMatch {
+            owner_kind: owner_field("kind"),
+            owner_id: owner_field("id"),
+            task_id: document.get_str("task_id").unwrap_or_default().to_string(),
+        }
+    }))
+}
+
 fn datetime_field_to_rfc3339(document: &Document, key: &str) -> Option<String> {
     match document.get(key) {
         Some(Bson::DateTime(value)) => Some(value.to_chrono().to_rfc3339()),
@@ -458,7 +1032,38 @@ fn derive_request_summary(task_doc: &Document) -> Option<String> {
     }
 }
 
-fn derive_run_task_summary(workspace_dir: &Path, channel: &str) -> Option<String> {
+fn derive_approval_status(task_doc: &Document) -> Option<String> {
+    let task_json = task_doc.get_str("task_json").ok()?;
+    let task_value: serde_json::Value = serde_json::from_str(task_json).ok()?;
+    task_value
+        .pointer("/approval/status")
+        .and_then(|v| v.as_str())
+        .map(|value| value.to_string())
+}
+
+/// Description, end time and remaining runs the agent set on a task.
+fn derive_recurrence_details(task_doc: &Document) -> (Option<String>, Option<String>, Option<u32>) {
+    let Some(task_value) = task_doc
+        .get_str("task_json")
+        .ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+    else {
+        return (None, None, None);
+    };
+    let text = |pointer: &str| {
+        task_value
+            .pointer(pointer)
+            .and_then(|v| v.as_str())
+            .map(|value| value.to_string())
+    };
+    let remaining_runs = task_value
+        .pointer("/ends/remaining_runs")
+        .and_then(|v| v.as_u64())
+        .and_then(|value| u32::try_from(value).ok());
+    (text("/description"), text("/ends/until"), remaining_runs)
+}
+
+pub(crate) fn derive_run_task_summary(workspace_dir: &Path, channel: &str) -> Option<String> {
     let incoming_dir = workspace_dir.join("incoming_email");
     if !incoming_dir.exists() {
         return None;
@@ -643,7 +1248,7 @@ mod tests {
     use mongodb::bson::doc;
     use tempfile::TempDir;
 
-    use super::{derive_request_summary, resolve_owner_scope};
+    use super::{derive_recurrence_details, derive_request_summary, resolve_owner_scope};
 
     #[test]
     fn resolve_owner_scope_extracts_user_id() {
@@ -740,4 +1345,25 @@ mod tests {
             Some("Review the attached budget and flag risks.")
         );
     }
+
+    #[test]
+    fn derive_recurrence_details_reads_description_and_end() {
+        let task_json = serde_json::json!({
+            "description": "Weekly metrics digest",
+            "ends": { "until": "2026-06-30T00:00:00Z", "remaining_runs": 4 }
+        })
+        .to_string();
+        let doc = doc! { "task_json": task_json };
+        assert_eq!(
+            derive_recurrence_details(&doc),
+            (
+                Some("Weekly metrics digest".to_string()),
+                Some("2026-06-30T00:00:00Z".to_string()),
+                Some(4)
+            )
+        );
+
+        let plain = doc! { "task_json": serde_json::json!({ "kind": {} }).to_string() };
+        assert_eq!(derive_recurrence_details(&plain), (None, None, None));
+    }
 }
@@ -1,17 +1,22 @@
 use chrono::Utc;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use tempfile::TempDir;
 use uuid::Uuid;
 
+use crate::approval_store::ApprovalStatus;
 use crate::channel::Channel;
 
 use super::{
+    acquire_task_lease,
     actions::{apply_scheduler_actions, schedule_send_email},
-    snapshot::build_scheduler_snapshot,
-    RunTaskTask, Schedule, ScheduledTask, Scheduler, SchedulerError, TaskExecution, TaskExecutor,
-    TaskKind,
+    record_execution_intent,
+    snapshot::{build_scheduler_snapshot, thread_tasks},
+    HeartbeatSpec, NoopTask, RunTaskTask, Schedule, ScheduledTask, Scheduler, SchedulerError,
+    SendReplyTask, TaskExecution, TaskExecutor, TaskKind,
 };
 
 #[derive(Default)]
@@ -41,7 +46,9 @@ impl TaskExecutor for FailingExecutor {
     }
 }
 
-fn base_run_task(workspace: &Path, mail_root: &Path) -> RunTaskTask {
+/// An email RunTask in `workspace` with every optional field unset. Tests
+/// elsewhere in the crate override the fields they check.
+pub(crate) fn run_task_in(workspace: &Path) -> RunTaskTask {
     RunTaskTask {
         workspace_dir: workspace.to_path_buf(),
         input_email_dir: PathBuf::from("incoming_email"),
@@ -53,16 +60,28 @@ fn base_run_task(workspace: &Path, mail_root: &Path) -> RunTaskTask {
         codex_disabled: false,
         reply_to: vec!["user@example.com".to_string()],
         reply_from: None,
-        archive_root: Some(mail_root.to_path_buf()),
-        thread_id: Some("thread-test".to_string()),
-        thread_epoch: Some(1),
-        thread_state_path: Some(workspace.join("thread_state.json")),
+        archive_root: None,
+        thread_id: None,
+        thread_epoch: None,
+        thread_state_path: None,
         channel: Channel::default(),
         slack_team_id: None,
         employee_id: None,
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
+    }
+}
+
+fn base_run_task(workspace: &Path, mail_root: &Path) -> RunTaskTask {
+    RunTaskTask {
+        archive_root: Some(mail_root.to_path_buf()),
+        thread_id: Some("thread-test".to_string()),
+        thread_epoch: Some(1),
+        thread_state_path: Some(workspace.join("thread_state.json")),
+        ..run_task_in(workspace)
     }
 }
 
@@ -90,7 +109,7 @@ fn defer_one_shot_task_by_id_pushes_run_at_forward_and_persists() {
     let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
 
     let task_id = scheduler
-        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop)
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop(NoopTask::default()))
         .expect("add task");
     force_one_shot_due(&mut scheduler, task_id);
 
@@ -138,7 +157,7 @@ fn defer_one_shot_task_by_id_is_noop_for_cron_tasks() {
     let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
 
     let task_id = scheduler
-        .add_cron_task("0 * * * * *", TaskKind::Noop)
+        .add_cron_task("0 * * * * *", TaskKind::Noop(NoopTask::default()))
         .expect("add cron task");
     let changed = scheduler
         .defer_one_shot_task_by_id(task_id, chrono::Duration::seconds(15))
@@ -149,28 +168,124 @@ fn defer_one_shot_task_by_id_is_noop_for_cron_tasks() {
     );
 }
 
+#[test]
+fn defer_task_until_moves_cron_to_first_occurrence_after() {
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
+
+    let task_id = scheduler
+        .add_cron_task("0 0 * * * *", TaskKind::Noop(NoopTask::default()))
+        .expect("add cron task");
+    let until = (Utc::now() + chrono::Duration::days(2))
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    assert!(scheduler.defer_task_until(task_id, until).expect("defer"));
+
+    let reloaded = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("reload");
+    match &reloaded
+        .tasks()
+        .iter()
+        .find(|task| task.id == task_id)
+        .expect("task exists after reload")
+        .schedule
+    {
+        Schedule::Cron { next_run, .. } => assert_eq!(*next_run, until),
+        _ => panic!("expected cron schedule"),
+    }
+    assert!(
+        !scheduler.defer_task_until(task_id, until).expect("defer"),
+        "deferring to the same time again should be a no-op"
+    );
+}
+
+#[test]
+fn delay_task_until_holds_the_cron_occurrence() {
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
+
+    let task_id = scheduler
+        .add_cron_task("0 0 9 * * *", TaskKind::Noop(NoopTask::default()))
+        .expect("add cron task");
+    let until = Utc::now() + chrono::Duration::days(1) + chrono::Duration::minutes(7);
+    assert!(scheduler.delay_task_until(task_id, until).expect("delay"));
+
+    let reloaded = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("reload");
+    match &reloaded
+        .tasks()
+        .iter()
+        .find(|task| task.id == task_id)
+        .expect("task exists after reload")
+        .schedule
+    {
+        Schedule::Cron { next_run, .. } => assert_eq!(*next_run, until),
+        _ => panic!("expected cron schedule"),
+    }
+    assert!(!scheduler.delay_task_until(task_id, until).expect("delay"));
+}
+
+#[test]
+fn trigger_task_now_makes_an_enabled_task_due() {
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
+
+    let task_id = scheduler
+        .add_one_shot_in(
+            Duration::from_secs(3600),
+            TaskKind::Noop(NoopTask::default()),
+        )
+        .expect("add one-shot task");
+    let now = Utc::now();
+    assert!(scheduler.trigger_task_now(task_id, now).expect("trigger"));
+    let reloaded = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("reload");
+    let task = reloaded
+        .tasks()
+        .iter()
+        .find(|task| task.id == task_id)
+        .expect("task exists after reload");
+    assert!(task.is_due(now));
+
+    scheduler
+        .disable_tasks_by(|task| task.id == task_id)
+        .expect("disable");
+    assert!(!scheduler.trigger_task_now(task_id, now).expect("trigger"));
+    assert!(!scheduler
+        .trigger_task_now(Uuid::new_v4(), now)
+        .expect("trigger unknown"));
+}
+
 #[test]
 fn build_scheduler_snapshot_limits_to_window() {
     let now = Utc::now();
     let in_window = ScheduledTask {
         id: Uuid::new_v4(),
-        kind: TaskKind::Noop,
+        kind: TaskKind::Noop(NoopTask::default()),
         schedule: Schedule::OneShot {
             run_at: now + chrono::Duration::days(1),
         },
         enabled: true,
         created_at: now,
         last_run: None,
+        approval: None,
+        description: None,
+        ends: None,
     };
     let out_window = ScheduledTask {
         id: Uuid::new_v4(),
-        kind: TaskKind::Noop,
+        kind: TaskKind::Noop(NoopTask::default()),
         schedule: Schedule::OneShot {
             run_at: now + chrono::Duration::days(10),
         },
         enabled: true,
         created_at: now,
         last_run: None,
+        approval: None,
+        description: None,
+        ends: None,
     };
 
     let snapshot = build_scheduler_snapshot(&[in_window, out_window], now);
@@ -185,7 +300,7 @@ fn build_scheduler_snapshot_surfaces_due_tasks_with_ids() {
     let now = Utc::now();
     let due_cron = ScheduledTask {
         id: Uuid::new_v4(),
-        kind: TaskKind::Noop,
+        kind: TaskKind::Noop(NoopTask::default()),
         schedule: Schedule::Cron {
             expression: "0 0 16 * * *".to_string(),
             next_run: now - chrono::Duration::minutes(3),
@@ -193,16 +308,22 @@ fn build_scheduler_snapshot_surfaces_due_tasks_with_ids() {
         enabled: true,
         created_at: now,
         last_run: Some(now - chrono::Duration::days(1)),
+        approval: None,
+        description: None,
+        ends: None,
     };
     let future_one_shot = ScheduledTask {
         id: Uuid::new_v4(),
-        kind: TaskKind::Noop,
+        kind: TaskKind::Noop(NoopTask::default()),
         schedule: Schedule::OneShot {
             run_at: now + chrono::Duration::hours(2),
         },
         enabled: true,
         created_at: now,
         last_run: None,
+        approval: None,
+        description: None,
+        ends: None,
     };
 
     let snapshot = build_scheduler_snapshot(&[due_cron.clone(), future_one_shot], now);
@@ -214,6 +335,88 @@ fn build_scheduler_snapshot_surfaces_due_tasks_with_ids() {
     assert_eq!(snapshot.upcoming.len(), 1);
 }
 
+#[test]
+fn legacy_noop_rows_deserialize_without_heartbeat() {
+    let kind: TaskKind = serde_json::from_str(r#"{"type":"noop"}"#).expect("parse legacy noop");
+    assert!(matches!(kind, TaskKind::Noop(NoopTask { heartbeat: None })));
+
+    let heartbeat = TaskKind::Noop(NoopTask {
+        heartbeat: Some(HeartbeatSpec {
+            name: "user".to_string(),
+            grace_secs: 600,
+        }),
+    });
+    let json = serde_json::to_string(&heartbeat).expect("serialize heartbeat");
+    let parsed: TaskKind = serde_json::from_str(&json).expect("parse heartbeat");
+    match parsed {
+        TaskKind::Noop(NoopTask {
+            heartbeat: Some(spec),
+        }) => assert_eq!(spec.grace_secs, 600),
+        other => panic!("unexpected kind: {:?}", other),
+    }
+}
+
+#[test]
+fn heartbeat_deadline_adds_grace_and_hides_from_snapshot() {
+    let now = Utc::now();
+    let next_run = now - chrono::Duration::minutes(20);
+    let heartbeat = ScheduledTask {
+        id: Uuid::new_v4(),
+        kind: TaskKind::Noop(NoopTask {
+            heartbeat: Some(HeartbeatSpec {
+                name: "employee".to_string(),
+                grace_secs: 600,
+            }),
+        }),
+        schedule: Schedule::Cron {
+            expression: "0 */5 * * * *".to_string(),
+            next_run,
+        },
+        enabled: true,
+        created_at: now,
+        last_run: None,
+        approval: None,
+        description: None,
+        ends: None,
+    };
+    let (spec, deadline) = heartbeat.heartbeat_deadline().expect("heartbeat deadline");
+    assert_eq!(spec.name, "employee");
+    assert_eq!(deadline, next_run + chrono::Duration::seconds(600));
+
+    let snapshot = build_scheduler_snapshot(&[heartbeat], now);
+    assert_eq!(snapshot.total_enabled, 0);
+    assert!(snapshot.due.is_empty());
+}
+
+#[test]
+fn ensure_heartbeat_reuses_existing_task() {
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
+
+    let first = scheduler
+        .ensure_heartbeat("user", "0 */5 * * * *", 600)
+        .expect("ensure heartbeat");
+    let second = scheduler
+        .ensure_heartbeat("user", "0 */10 * * * *", 900)
+        .expect("re-ensure heartbeat");
+    assert_eq!(first, second);
+
+    let reloaded = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("reload");
+    let heartbeats: Vec<_> = reloaded
+        .tasks()
+        .iter()
+        .filter(|task| task.heartbeat_deadline().is_some())
+        .collect();
+    assert_eq!(heartbeats.len(), 1);
+    match &heartbeats[0].schedule {
+        Schedule::Cron { expression, .. } => assert_eq!(expression, "0 */10 * * * *"),
+        other => panic!("unexpected schedule: {:?}", other),
+    }
+    let (spec, _) = heartbeats[0].heartbeat_deadline().expect("deadline");
+    assert_eq!(spec.grace_secs, 900);
+}
+
 #[test]
 fn apply_scheduler_actions_cancels_and_reschedules() {
     let temp = TempDir::new().expect("tempdir");
@@ -222,10 +425,16 @@ fn apply_scheduler_actions_cancels_and_reschedules() {
     let now = Utc::now();
 
     let cancel_id = scheduler
-        .add_one_shot_at(now + chrono::Duration::days(1), TaskKind::Noop)
+        .add_one_shot_at(
+            now + chrono::Duration::days(1),
+            TaskKind::Noop(NoopTask::default()),
+        )
         .expect("cancel task");
     let resched_id = scheduler
-        .add_one_shot_at(now + chrono::Duration::days(2), TaskKind::Noop)
+        .add_one_shot_at(
+            now + chrono::Duration::days(2),
+            TaskKind::Noop(NoopTask::default()),
+        )
         .expect("resched task");
 
     let workspace = temp.path().join("workspaces").join("thread_1");
@@ -287,6 +496,10 @@ fn apply_scheduler_actions_creates_run_task() {
         model_name: None,
         codex_disabled: None,
         reply_to: Vec::new(),
+        approval: None,
+        description: None,
+        until: None,
+        count: None,
     }];
 
     apply_scheduler_actions(&mut scheduler, &run_task, &actions).expect("apply actions");
@@ -301,6 +514,155 @@ fn apply_scheduler_actions_creates_run_task() {
     }
 }
 
+#[test]
+fn apply_scheduler_actions_writes_results_and_lists_thread_tasks() {
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
+    let now = Utc::now();
+
+    let workspace = temp.path().join("workspaces").join("thread_1");
+    let mail_root = temp.path().join("mail");
+    fs::create_dir_all(&workspace).expect("workspace");
+    fs::create_dir_all(&mail_root).expect("mail");
+    let run_task = base_run_task(&workspace, &mail_root);
+
+    let reminder_id = scheduler
+        .add_one_shot_at(
+            now + chrono::Duration::days(1),
+            TaskKind::RunTask(run_task.clone()),
+        )
+        .expect("reminder");
+    let digest_id = scheduler
+        .add_cron_task("0 0 9 * * Mon", TaskKind::RunTask(run_task.clone()))
+        .expect("digest");
+    let heartbeat_id = scheduler
+        .ensure_heartbeat("user", "0 */10 * * * *", 600)
+        .expect("heartbeat");
+
+    let missing_id = Uuid::new_v4();
+    let actions = vec![
+        run_task_module::SchedulerActionRequest::Cancel {
+            task_ids: vec![reminder_id.to_string(), missing_id.to_string()],
+        },
+        run_task_module::SchedulerActionRequest::Cancel {
+            task_ids: vec![heartbeat_id.to_string()],
+        },
+        run_task_module::SchedulerActionRequest::ListTasks,
+    ];
+    apply_scheduler_actions(&mut scheduler, &run_task, &actions).expect("apply actions");
+
+    let heartbeat = scheduler
+        .tasks()
+        .iter()
+        .find(|task| task.id == heartbeat_id)
+        .expect("heartbeat");
+    assert!(heartbeat.enabled);
+
+    let raw = fs::read_to_string(workspace.join("scheduler_action_results.json"))
+        .expect("action results");
+    let results: serde_json::Value = serde_json::from_str(&raw).expect("results json");
+    assert_eq!(results["results"][0]["status"], "applied");
+    assert_eq!(
+        results["results"][0]["task_ids"],
+        serde_json::json!([reminder_id.to_string()])
+    );
+    assert!(results["results"][0]["detail"]
+        .as_str()
+        .expect("detail")
+        .contains(&missing_id.to_string()));
+    assert_eq!(results["results"][1]["status"], "skipped");
+    let listed: Vec<&str> = results["thread_tasks"]
+        .as_array()
+        .expect("thread tasks")
+        .iter()
+        .map(|task| task["id"].as_str().expect("id"))
+        .collect();
+    assert_eq!(listed, vec![digest_id.to_string()]);
+}
+
+#[test]
+fn thread_tasks_only_lists_the_workspace_runs() {
+    let temp = TempDir::new().expect("tempdir");
+    let mail_root = temp.path().join("mail");
+    let thread = temp.path().join("workspaces").join("thread_1");
+    let other = temp.path().join("workspaces").join("thread_2");
+    let now = Utc::now();
+    let task = |workspace: &Path, enabled: bool, hours: i64| ScheduledTask {
+        id: Uuid::new_v4(),
+        kind: TaskKind::RunTask(base_run_task(workspace, &mail_root)),
+        schedule: Schedule::OneShot {
+            run_at: now + chrono::Duration::hours(hours),
+        },
+        enabled,
+        created_at: now,
+        last_run: None,
+        approval: None,
+        description: Some(format!("run in {} hours", hours)),
+        ends: None,
+    };
+    let later = task(&thread, true, 30 * 24);
+    let sooner = task(&thread, true, 2);
+    let tasks = [
+        later.clone(),
+        task(&other, true, 1),
+        task(&thread, false, 3),
+        sooner.clone(),
+    ];
+
+    let listed = thread_tasks(&tasks, &thread, now);
+    let ids: Vec<String> = listed.iter().map(|task| task.id.clone()).collect();
+    assert_eq!(ids, vec![sooner.id.to_string(), later.id.to_string()]);
+    assert_eq!(listed[0].label.as_deref(), Some("run in 2 hours"));
+    assert_eq!(build_scheduler_snapshot(&tasks, now).upcoming.len(), 2);
+}
+
+#[test]
+fn held_run_task_waits_for_approval() {
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
+    let now = Utc::now();
+
+    let workspace = temp.path().join("workspaces").join("thread_1");
+    let mail_root = temp.path().join("mail");
+    fs::create_dir_all(&workspace).expect("workspace");
+    fs::create_dir_all(&mail_root).expect("mail");
+    let run_task = base_run_task(&workspace, &mail_root);
+
+    let actions = vec![run_task_module::SchedulerActionRequest::CreateRunTask {
+        schedule: run_task_module::ScheduleRequest::OneShot {
+            run_at: (now + chrono::Duration::minutes(1)).to_rfc3339(),
+        },
+        model_name: None,
+        codex_disabled: None,
+        reply_to: Vec::new(),
+        approval: Some(run_task_module::ApprovalRequest {
+            summary: "Send this contract to legal?".to_string(),
+        }),
+        description: None,
+        until: None,
+        count: None,
+    }];
+    apply_scheduler_actions(&mut scheduler, &run_task, &actions).expect("apply actions");
+
+    let task_id = scheduler.tasks()[0].id;
+    assert!(!scheduler.tasks()[0].enabled);
+    assert!(scheduler.tasks()[0].awaiting_approval());
+
+    let reloaded = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("reload");
+    assert!(reloaded.tasks()[0].awaiting_approval());
+
+    assert!(scheduler
+        .resolve_approval(task_id, ApprovalStatus::Approved, "email:legal@acme.com")
+        .expect("resolve"));
+    assert!(scheduler.tasks()[0].enabled);
+    assert!(!scheduler.tasks()[0].awaiting_approval());
+    assert!(!scheduler
+        .resolve_approval(task_id, ApprovalStatus::Rejected, "email:legal@acme.com")
+        .expect("resolve again"));
+}
+
 #[test]
 fn schedule_send_email_supports_five_and_twenty_minute_reminders() {
     let temp = TempDir::new().expect("tempdir");
@@ -330,6 +692,7 @@ fn schedule_send_email_supports_five_and_twenty_minute_reminders() {
         delay_minutes: Some(5),
         delay_seconds: None,
         run_at: None,
+        approval: None,
     };
     let request_20 = run_task_module::ScheduledSendEmailTask {
         subject: "Reminder in 20 minutes".to_string(),
@@ -342,6 +705,7 @@ fn schedule_send_email_supports_five_and_twenty_minute_reminders() {
         delay_minutes: Some(20),
         delay_seconds: None,
         run_at: None,
+        approval: None,
     };
 
     let now_before_first = Utc::now();
@@ -409,7 +773,11 @@ fn add_one_shot_in_with_id_uses_specified_id() {
 
     // Add task with specific ID
     scheduler
-        .add_one_shot_in_with_id(specific_id, Duration::from_secs(0), TaskKind::Noop)
+        .add_one_shot_in_with_id(
+            specific_id,
+            Duration::from_secs(0),
+            TaskKind::Noop(NoopTask::default()),
+        )
         .expect("add task with id");
 
     // Verify the task has the specified ID
@@ -431,12 +799,16 @@ fn same_task_id_can_be_used_in_different_schedulers() {
 
     // Add task to workspace scheduler (generates new ID)
     let task_id = workspace_scheduler
-        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop)
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop(NoopTask::default()))
         .expect("add to workspace");
 
     // Add same task to user scheduler with the SAME ID
     user_scheduler
-        .add_one_shot_in_with_id(task_id, Duration::from_secs(0), TaskKind::Noop)
+        .add_one_shot_in_with_id(
+            task_id,
+            Duration::from_secs(0),
+            TaskKind::Noop(NoopTask::default()),
+        )
         .expect("add to user with same id");
 
     // Verify both schedulers have a task with the same ID
@@ -456,7 +828,11 @@ fn add_one_shot_in_with_id_persists_to_database() {
     {
         let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
         scheduler
-            .add_one_shot_in_with_id(specific_id, Duration::from_secs(0), TaskKind::Noop)
+            .add_one_shot_in_with_id(
+                specific_id,
+                Duration::from_secs(0),
+                TaskKind::Noop(NoopTask::default()),
+            )
             .expect("add task");
     }
 
@@ -478,14 +854,18 @@ fn execution_status_can_be_recorded_for_task() {
     {
         let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
         scheduler
-            .add_one_shot_in_with_id(specific_id, Duration::from_secs(0), TaskKind::Noop)
+            .add_one_shot_in_with_id(
+                specific_id,
+                Duration::from_secs(0),
+                TaskKind::Noop(NoopTask::default()),
+            )
             .expect("add task");
 
         // Record execution start and finish (this is what sync_task_status_to_user_storage does)
         let now = Utc::now();
-        let execution_id = scheduler
+        let (execution_id, _) = scheduler
             .store
-            .record_execution_start(specific_id, now)
+            .record_execution_start(specific_id, now, None)
             .expect("record start");
         scheduler
             .store
@@ -559,6 +939,8 @@ fn discord_run_task(workspace: &Path) -> RunTaskTask {
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     }
 }
 
@@ -586,6 +968,8 @@ fn slack_run_task(workspace: &Path) -> RunTaskTask {
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     }
 }
 
@@ -662,8 +1046,8 @@ fn full_discord_flow_task_sync_and_status_update() {
     {
         use super::store::SchedulerStore;
         let workspace_store = SchedulerStore::new(workspace_db.clone()).expect("open workspace");
-        let execution_id = workspace_store
-            .record_execution_start(task_id, executed_at)
+        let (execution_id, _) = workspace_store
+            .record_execution_start(task_id, executed_at, None)
             .expect("record start");
         workspace_store
             .record_execution_finish(task_id, execution_id, executed_at, "success", None)
@@ -674,8 +1058,8 @@ fn full_discord_flow_task_sync_and_status_update() {
     {
         use super::store::SchedulerStore;
         let user_store = SchedulerStore::new(user_db.clone()).expect("open user");
-        let execution_id = user_store
-            .record_execution_start(task_id, executed_at)
+        let (execution_id, _) = user_store
+            .record_execution_start(task_id, executed_at, None)
             .expect("record start");
         user_store
             .record_execution_finish(task_id, execution_id, executed_at, "success", None)
@@ -776,8 +1160,8 @@ fn full_slack_flow_task_sync_and_status_update() {
     {
         use super::store::SchedulerStore;
         let workspace_store = SchedulerStore::new(workspace_db.clone()).expect("open workspace");
-        let execution_id = workspace_store
-            .record_execution_start(task_id, executed_at)
+        let (execution_id, _) = workspace_store
+            .record_execution_start(task_id, executed_at, None)
             .expect("record start");
         workspace_store
             .record_execution_finish(
@@ -794,8 +1178,8 @@ fn full_slack_flow_task_sync_and_status_update() {
     {
         use super::store::SchedulerStore;
         let account_store = SchedulerStore::new(account_db.clone()).expect("open account");
-        let execution_id = account_store
-            .record_execution_start(task_id, executed_at)
+        let (execution_id, _) = account_store
+            .record_execution_start(task_id, executed_at, None)
             .expect("record start");
         account_store
             .record_execution_finish(
@@ -901,16 +1285,16 @@ fn multiple_tasks_sync_independently() {
         let user_store = SchedulerStore::new(user_db.clone()).expect("open user");
 
         // Task 1: success
-        let exec_id_1 = user_store
-            .record_execution_start(task_id_1, executed_at)
+        let (exec_id_1, _) = user_store
+            .record_execution_start(task_id_1, executed_at, None)
             .expect("start 1");
         user_store
             .record_execution_finish(task_id_1, exec_id_1, executed_at, "success", None)
             .expect("finish 1");
 
         // Task 2: failed
-        let exec_id_2 = user_store
-            .record_execution_start(task_id_2, executed_at)
+        let (exec_id_2, _) = user_store
+            .record_execution_start(task_id_2, executed_at, None)
             .expect("start 2");
         user_store
             .record_execution_finish(task_id_2, exec_id_2, executed_at, "failed", Some("timeout"))
@@ -1007,6 +1391,293 @@ fn run_task_failures_persist_retry_count_and_disable_at_limit() {
     );
 }
 
+#[test]
+fn task_lease_is_exclusive_until_released_or_expired() {
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor).expect("load scheduler");
+    let task_id = scheduler
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop(NoopTask::default()))
+        .expect("add noop");
+    let ttl = Duration::from_secs(60);
+
+    let lease = acquire_task_lease(&tasks_db, task_id, "worker-a", ttl)
+        .expect("acquire")
+        .expect("lease is free");
+    assert!(lease.is_held());
+    assert!(acquire_task_lease(&tasks_db, task_id, "worker-b", ttl)
+        .expect("acquire")
+        .is_none());
+    drop(lease);
+
+    let expiring = acquire_task_lease(&tasks_db, task_id, "worker-b", Duration::ZERO)
+        .expect("acquire")
+        .expect("released lease is free");
+    // A crashed worker never releases; its lease lapses after the TTL.
+    std::mem::forget(expiring);
+    assert!(acquire_task_lease(&tasks_db, task_id, "worker-a", ttl)
+        .expect("acquire")
+        .is_some());
+}
+
+#[test]
+fn run_task_completion_commits_auto_reply() {
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let workspace = temp.path().join("workspace");
+    let mail_root = temp.path().join("mail");
+    fs::create_dir_all(&workspace).expect("workspace");
+    fs::create_dir_all(&mail_root).expect("mail");
+    fs::write(
+        workspace.join("reply_email_draft.html"),
+        "<html><body>Done</body></html>",
+    )
+    .expect("reply draft");
+    let run_task = base_run_task(&workspace, &mail_root);
+
+    let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor).expect("load scheduler");
+    let task_id = scheduler
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::RunTask(run_task))
+        .expect("add run_task");
+    assert!(scheduler.execute_task_by_id(task_id).expect("execute"));
+
+    let reloaded = Scheduler::load(&tasks_db, NoopExecutor).expect("reload scheduler");
+    let run_task = reloaded
+        .tasks()
+        .iter()
+        .find(|task| task.id == task_id)
+        .expect("run_task stored");
+    assert!(!run_task.enabled);
+    let replies: Vec<_> = reloaded
+        .tasks()
+        .iter()
+        .filter(|task| matches!(task.kind, TaskKind::SendReply(_)))
+        .collect();
+    assert_eq!(replies.len(), 1);
+    assert!(replies[0].enabled);
+}
+
+#[test]
+fn only_sends_nobody_waits_for_can_be_held() {
+    let temp = TempDir::new().expect("tempdir");
+    let send = |scheduled| ScheduledTask {
+        id: Uuid::new_v4(),
+        kind: TaskKind::SendReply(SendReplyTask {
+            channel: Channel::Email,
+            subject: "Weekly report".to_string(),
+            html_path: temp.path().join("reply_email_draft.html"),
+            attachments_dir: temp.path().join("reply_email_attachments"),
+            from: None,
+            to: vec!["user@example.com".to_string()],
+            cc: Vec::new(),
+            bcc: Vec::new(),
+            in_reply_to: None,
+            references: None,
+            archive_root: None,
+            thread_epoch: None,
+            thread_state_path: None,
+            employee_id: None,
+            idempotency_key: None,
+            trace_id: None,
+            scheduled,
+        }),
+        schedule: Schedule::OneShot { run_at: Utc::now() },
+        enabled: true,
+        created_at: Utc::now(),
+        last_run: None,
+        approval: None,
+        description: None,
+        ends: None,
+    };
+    assert!(send(false).is_interactive());
+    assert!(!send(true).is_interactive());
+
+    // Tasks stored before the flag existed are replies someone is waiting on.
+    let legacy: SendReplyTask = serde_json::from_value(serde_json::json!({
+        "subject": "Re: hi",
+        "html_path": "reply_email_draft.html",
+        "attachments_dir": "reply_email_attachments",
+        "to": ["user@example.com"],
+        "cc": [],
+        "bcc": []
+    }))
+    .expect("legacy send_reply");
+    assert!(!legacy.scheduled);
+}
+
+#[test]
+fn delivered_send_reply_is_finalized_without_resending() {
+    struct CountingExecutor(Arc<AtomicUsize>);
+
+    impl TaskExecutor for CountingExecutor {
+        fn execute(&self, _task: &TaskKind) -> Result<TaskExecution, SchedulerError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(TaskExecution::empty())
+        }
+    }
+
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut scheduler =
+        Scheduler::load(&tasks_db, CountingExecutor(calls.clone())).expect("load scheduler");
+    let send_task = SendReplyTask {
+        channel: Channel::Email,
+        subject: "Done".to_string(),
+        html_path: temp.path().join("reply_email_draft.html"),
+        attachments_dir: temp.path().join("reply_email_attachments"),
+        from: None,
+        to: vec!["user@example.com".to_string()],
+        cc: Vec::new(),
+        bcc: Vec::new(),
+        in_reply_to: None,
+        references: None,
+        archive_root: None,
+        thread_epoch: None,
+        thread_state_path: None,
+        employee_id: None,
+        idempotency_key: None,
+        trace_id: None,
+        scheduled: false,
+    };
+    let task_id = scheduler
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::SendReply(send_task))
+        .expect("add send_reply");
+
+    // A worker delivered the reply and crashed before disabling the task.
+    use super::store::{DeliveryState, SchedulerStore};
+    let store = SchedulerStore::new(tasks_db.clone()).expect("open store");
+    let now = Utc::now();
+    assert_eq!(
+        store
+            .begin_delivery(&task_id.to_string(), None, now)
+            .expect("begin"),
+        DeliveryState::Pending
+    );
+    store
+        .finish_delivery(&task_id.to_string(), true, None, now)
+        .expect("finish");
+
+    assert!(scheduler.execute_task_by_id(task_id).expect("execute"));
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+    let reloaded = Scheduler::load(&tasks_db, NoopExecutor).expect("reload scheduler");
+    assert!(
+        !reloaded
+            .tasks()
+            .iter()
+            .find(|task| task.id == task_id)
+            .expect("task stored")
+            .enabled
+    );
+}
+
+#[test]
+fn thread_reply_keys_are_stable_per_thread_epoch_and_sequence() {
+    use super::core::thread_reply_key;
+
+    let key = thread_reply_key("thread-1", 3, 0);
+    assert_eq!(key, thread_reply_key("thread-1", 3, 0));
+    assert_ne!(key, thread_reply_key("thread-1", 3, 1));
+    assert_ne!(key, thread_reply_key("thread-1", 4, 0));
+    assert_ne!(key, thread_reply_key("thread-2", 3, 0));
+    assert!(Uuid::parse_str(&key).is_ok());
+}
+
+#[test]
+fn send_reply_for_a_superseded_epoch_is_stale() {
+    use super::actions::send_reply_epoch_matches;
+    use crate::thread_state::bump_thread_state;
+
+    let temp = TempDir::new().expect("tempdir");
+    let state_path = temp
+        .path()
+        .join("users/u1/workspaces/thread_1")
+        .join("thread_state.json");
+    let first = bump_thread_state(&state_path, "thread-1", None).expect("first message");
+    let mut reply = SendReplyTask {
+        channel: Channel::Slack,
+        subject: String::new(),
+        html_path: temp.path().join("reply_message.txt"),
+        attachments_dir: temp.path().join("reply_attachments"),
+        from: None,
+        to: vec!["U123".to_string()],
+        cc: Vec::new(),
+        bcc: Vec::new(),
+        in_reply_to: None,
+        references: None,
+        archive_root: None,
+        thread_epoch: Some(first.epoch),
+        thread_state_path: Some(state_path.clone()),
+        employee_id: None,
+        idempotency_key: None,
+        trace_id: None,
+        scheduled: false,
+    };
+    assert!(send_reply_epoch_matches(&reply));
+
+    let second = bump_thread_state(&state_path, "thread-1", None).expect("second message");
+    assert!(!send_reply_epoch_matches(&reply));
+    reply.thread_epoch = Some(second.epoch);
+    assert!(send_reply_epoch_matches(&reply));
+    reply.thread_state_path = None;
+    reply.thread_epoch = Some(first.epoch);
+    assert!(send_reply_epoch_matches(&reply));
+}
+
+#[test]
+fn reply_sharing_a_delivered_reply_key_is_suppressed() {
+    struct CountingExecutor(Arc<AtomicUsize>);
+
+    impl TaskExecutor for CountingExecutor {
+        fn execute(&self, _task: &TaskKind) -> Result<TaskExecution, SchedulerError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(TaskExecution::empty())
+        }
+    }
+
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut scheduler =
+        Scheduler::load(&tasks_db, CountingExecutor(calls.clone())).expect("load scheduler");
+    // The original run and its retry both staged a reply to the same message.
+    let reply = |name: &str| SendReplyTask {
+        channel: Channel::Email,
+        subject: "Done".to_string(),
+        html_path: temp.path().join(format!("{name}.html")),
+        attachments_dir: temp.path().join("reply_email_attachments"),
+        from: None,
+        to: vec!["user@example.com".to_string()],
+        cc: Vec::new(),
+        bcc: Vec::new(),
+        in_reply_to: None,
+        references: None,
+        archive_root: None,
+        thread_epoch: Some(1),
+        thread_state_path: None,
+        employee_id: None,
+        idempotency_key: Some(super::core::thread_reply_key("thread-1", 1, 0)),
+        trace_id: None,
+        scheduled: false,
+    };
+    let first = scheduler
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::SendReply(reply("first")))
+        .expect("add first reply");
+    let retry = scheduler
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::SendReply(reply("retry")))
+        .expect("add retried reply");
+
+    assert!(scheduler.execute_task_by_id(first).expect("execute first"));
+    assert!(scheduler.execute_task_by_id(retry).expect("execute retry"));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    let retry_task = scheduler
+        .tasks()
+        .iter()
+        .find(|task| task.id == retry)
+        .expect("retry stored");
+    assert!(!retry_task.enabled);
+}
+
 #[test]
 fn run_task_channel_is_preserved_in_sync() {
     let temp = TempDir::new().expect("tempdir");
@@ -1060,3 +1731,82 @@ fn run_task_channel_is_preserved_in_sync() {
         assert_eq!(tasks[0].channel, "email");
     }
 }
+
+#[test]
+fn one_shot_lost_mid_flight_is_made_due_again_at_startup() {
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
+    let lost = scheduler
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop(NoopTask::default()))
+        .expect("add lost");
+    let finished = scheduler
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop(NoopTask::default()))
+        .expect("add finished");
+    force_one_shot_due(&mut scheduler, lost);
+    force_one_shot_due(&mut scheduler, finished);
+
+    assert!(record_execution_intent(&tasks_db, lost).expect("intent"));
+    assert!(record_execution_intent(&tasks_db, finished).expect("intent"));
+    assert!(scheduler
+        .execute_task_by_id(finished)
+        .expect("run finished"));
+    // The worker running `lost` dies; a newer message then supersedes it.
+    scheduler
+        .supersede_tasks_by(|task| task.id == lost)
+        .expect("supersede");
+
+    let mut restarted = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("reload");
+    assert_eq!(restarted.recover_intents().expect("recover"), 1);
+    let task = restarted
+        .tasks()
+        .iter()
+        .find(|task| task.id == lost)
+        .expect("lost task");
+    assert!(task.enabled && task.is_due(Utc::now()));
+    assert!(
+        !restarted
+            .tasks()
+            .iter()
+            .find(|task| task.id == finished)
+            .expect("finished task")
+            .enabled
+    );
+
+    assert!(restarted.execute_task_by_id(lost).expect("rerun"));
+    let mut again = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("reload");
+    assert_eq!(again.recover_intents().expect("recover again"), 0);
+    assert!(!record_execution_intent(&tasks_db, lost).expect("no intent for a finished task"));
+}
+
+#[test]
+fn one_shot_canceled_with_an_open_intent_stays_canceled_at_startup() {
+    let temp = TempDir::new().expect("tempdir");
+    let tasks_db = temp.path().join("tasks.db");
+    let mut scheduler = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("load");
+    let queued = scheduler
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop(NoopTask::default()))
+        .expect("add queued");
+    force_one_shot_due(&mut scheduler, queued);
+
+    // Its intent is open, e.g. from a run lost before the restart, when the
+    // user cancels it while it waits for its thread.
+    assert!(record_execution_intent(&tasks_db, queued).expect("intent"));
+    assert_eq!(
+        scheduler
+            .disable_tasks_by(|task| task.id == queued)
+            .expect("cancel"),
+        1
+    );
+
+    let mut restarted = Scheduler::load(&tasks_db, NoopExecutor::default()).expect("reload");
+    assert_eq!(restarted.recover_intents().expect("recover"), 0);
+    assert!(
+        !restarted
+            .tasks()
+            .iter()
+            .find(|task| task.id == queued)
+            .expect("canceled task")
+            .enabled
+    );
+}
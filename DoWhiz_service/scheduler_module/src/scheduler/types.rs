@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use uuid::Uuid;
 
+use crate::approval_store::ApprovalStatus;
 use crate::channel::Channel;
 
 pub(crate) const RUN_TASK_FAILURE_LIMIT: u32 = 3;
@@ -15,7 +16,82 @@ pub enum TaskKind {
     #[serde(rename = "send_email")]
     SendReply(SendReplyTask),
     RunTask(RunTaskTask),
-    Noop,
+    Noop(NoopTask),
+    Digest(DigestTask),
+    Probe(ProbeTask),
+}
+
+impl TaskKind {
+    pub(crate) fn trace_id(&self) -> Option<&str> {
+        match self {
+            TaskKind::SendReply(task) => task.trace_id.as_deref(),
+            TaskKind::RunTask(task) => task.trace_id.as_deref(),
+            TaskKind::Noop(_) | TaskKind::Digest(_) | TaskKind::Probe(_) => None,
+        }
+    }
+
+    /// Tag the task with the trace entered on this thread unless it already
+    /// carries one.
+    pub(crate) fn inherit_trace_id(&mut self) {
+        let slot = match self {
+            TaskKind::SendReply(task) => &mut task.trace_id,
+            TaskKind::RunTask(task) => &mut task.trace_id,
+            TaskKind::Noop(_) | TaskKind::Digest(_) | TaskKind::Probe(_) => return,
+        };
+        if slot.is_none() {
+            *slot = crate::trace_context::current_trace_id();
+        }
+    }
+}
+
+/// Task that does no work when it runs.
+///
+/// With `heartbeat` set it acts as a dead-man's switch: the heartbeat
+/// reconciler raises an alert when a run slips past its grace period.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NoopTask {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heartbeat: Option<HeartbeatSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeartbeatSpec {
+    /// Stable name, used to find the heartbeat again when it is re-ensured.
+    pub name: String,
+    /// How long a run may be overdue before it counts as missed.
+    pub grace_secs: u64,
+}
+
+/// Daily summary of an account's last 24 hours: inbound messages, completed
+/// tasks and what is scheduled next. Installed from the account's digest
+/// preference; see `service::digests`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DigestTask {
+    pub account_id: String,
+    /// Email or Slack; the digest is sent directly, not as a reply.
+    pub channel: Channel,
+    /// Email address or Slack user id the digest goes to.
+    pub recipient: String,
+    /// Scheduler databases whose tasks the digest covers.
+    pub sources: Vec<PathBuf>,
+    /// Where each rendered digest is kept.
+    pub output_dir: PathBuf,
+    #[serde(default)]
+    pub employee_id: Option<String>,
+    /// Language code the digest is written in; English when unset.
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+/// Synthetic round trip through the employee's own ingestion pipeline; see
+/// `crate::health_probe`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeTask {
+    pub employee_id: String,
+    /// Where the probe's outcome is kept for `/health/detail`.
+    pub state_path: PathBuf,
+    /// How long a reply may take before the probe counts as failed.
+    pub sla_secs: u64,
 }
 
 /// Task for sending an outbound reply message to any channel.
@@ -47,6 +123,19 @@ pub struct SendReplyTask {
     /// Employee ID for per-employee credentials (optional)
     #[serde(default)]
     pub employee_id: Option<String>,
+    /// Stable key handed to providers that deduplicate sends and claimed in
+    /// the delivery journal. Replies of an interactive run get a key derived
+    /// from thread id, epoch and reply sequence; other sends use the task id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// Correlation ID of the inbound message this reply answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    /// Set on sends nobody is waiting for: replies of scheduled run_tasks
+    /// and emails the agent scheduled. Held during the recipient's quiet
+    /// hours; see [`ScheduledTask::is_interactive`].
+    #[serde(default)]
+    pub scheduled: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,6 +183,13 @@ pub struct RunTaskTask {
     /// The resolved account ID for this task (avoids re-lookup during status sync)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub account_id: Option<Uuid>,
+    /// Correlation ID of the inbound message that triggered this task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    /// Set on run_tasks the agent scheduled for later rather than ones
+    /// answering an inbound message; see [`ScheduledTask::is_interactive`].
+    #[serde(default)]
+    pub scheduled: bool,
 }
 
 fn default_runner() -> String {
@@ -120,15 +216,106 @@ pub struct ScheduledTask {
     pub enabled: bool,
     pub created_at: DateTime<Utc>,
     pub last_run: Option<DateTime<Utc>>,
+    /// Set when the task was held for a human decision; it stays disabled
+    /// until approved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<TaskApproval>,
+    /// What the task does, as the agent described it; shown in task listings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// When a cron task stops recurring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ends: Option<RecurrenceEnd>,
+}
+
+/// End condition of a cron task. Whichever limit is reached first disables
+/// the task.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecurrenceEnd {
+    /// No run is scheduled after this time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<DateTime<Utc>>,
+    /// Runs left, counting down after each successful run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_runs: Option<u32>,
+}
+
+/// Approval state of a held task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskApproval {
+    /// Question shown to the approver.
+    pub summary: String,
+    pub status: ApprovalStatus,
+    #[serde(default)]
+    pub decided_by: Option<String>,
+    #[serde(default)]
+    pub decided_at: Option<DateTime<Utc>>,
 }
 
 impl ScheduledTask {
+    pub fn awaiting_approval(&self) -> bool {
+        self.approval
+            .as_ref()
+            .is_some_and(|approval| approval.status == ApprovalStatus::Pending)
+    }
+
+    /// Whether someone is waiting on this run: inbound replies are,
+    /// agent-scheduled and recurring run_tasks, their sends and digests are
+    /// not.
+    pub(crate) fn is_interactive(&self) -> bool {
+        match &self.kind {
+            TaskKind::RunTask(task) => {
+                !task.scheduled && matches!(self.schedule, Schedule::OneShot { .. })
+            }
+            TaskKind::SendReply(task) => !task.scheduled,
+            TaskKind::Digest(_) | TaskKind::Probe(_) => false,
+            _ => true,
+        }
+    }
+
+    /// Count a successful run of a cron task against its end condition, once
+    /// `next_run` has moved on. Returns true when that ended the task, which
+    /// is then disabled.
+    pub(crate) fn count_recurring_run(&mut self) -> bool {
+        let Schedule::Cron { next_run, .. } = &self.schedule else {
+            return false;
+        };
+        let Some(ends) = self.ends.as_mut() else {
+            return false;
+        };
+        if let Some(remaining) = ends.remaining_runs.as_mut() {
+            *remaining = remaining.saturating_sub(1);
+        }
+        let finished =
+            ends.remaining_runs == Some(0) || ends.until.is_some_and(|until| *next_run > until);
+        if finished {
+            self.enabled = false;
+        }
+        finished
+    }
+
     pub(crate) fn is_due(&self, now: DateTime<Utc>) -> bool {
         match &self.schedule {
             Schedule::Cron { next_run, .. } => *next_run <= now,
             Schedule::OneShot { run_at } => *run_at <= now,
         }
     }
+
+    /// Heartbeat spec and the time after which the next run counts as missed.
+    pub(crate) fn heartbeat_deadline(&self) -> Option<(&HeartbeatSpec, DateTime<Utc>)> {
+        let TaskKind::Noop(NoopTask {
+            heartbeat: Some(spec),
+        }) = &self.kind
+        else {
+            return None;
+        };
+        let due_at = match &self.schedule {
+            Schedule::Cron { next_run, .. } => *next_run,
+            Schedule::OneShot { run_at } => *run_at,
+        };
+        let grace = chrono::Duration::seconds(spec.grace_secs.min(u32::MAX as u64) as i64);
+        Some((spec, due_at.checked_add_signed(grace)?))
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -143,8 +330,16 @@ pub enum SchedulerError {
     Storage(String),
     #[error("cron parse error: {0}")]
     Cron(#[from] cron::error::Error),
-    #[error("invalid cron expression (expected 6 fields, got {0})")]
+    #[error(
+        "invalid cron expression (expected 6 fields: second minute hour day-of-month month day-of-week, got {0})"
+    )]
     InvalidCron(usize),
+    #[error("invalid cron expression: {0}")]
+    InvalidCronField(String),
+    #[error("unknown time zone {0:?} (use an IANA name such as America/Los_Angeles)")]
+    InvalidTimezone(String),
+    #[error("invalid recurrence: {0}")]
+    InvalidRecurrence(String),
     #[error("no next run available for cron expression")]
     NoNextRun,
     #[error("duration out of range")]
@@ -160,6 +355,9 @@ pub struct TaskExecution {
     pub scheduler_actions: Vec<run_task_module::SchedulerActionRequest>,
     pub scheduler_actions_error: Option<String>,
     pub skip_auto_reply: bool,
+    /// Message ID the provider assigned to a sent reply (Postmark's
+    /// `MessageID`), matched against its bounce and delivery webhooks.
+    pub provider_message_id: Option<String>,
 }
 
 impl TaskExecution {
@@ -10,7 +10,9 @@ pub(crate) fn task_kind_label(kind: &TaskKind) -> &'static str {
     match kind {
         TaskKind::SendReply(_) => "send_email",
         TaskKind::RunTask(_) => "run_task",
-        TaskKind::Noop => "noop",
+        TaskKind::Noop(_) => "noop",
+        TaskKind::Digest(_) => "digest",
+        TaskKind::Probe(_) => "probe",
     }
 }
 
@@ -18,7 +20,9 @@ pub(crate) fn task_kind_channel(kind: &TaskKind) -> Channel {
     match kind {
         TaskKind::SendReply(send) => send.channel.clone(),
         TaskKind::RunTask(run) => run.channel.clone(),
-        TaskKind::Noop => Channel::default(),
+        TaskKind::Noop(_) => Channel::default(),
+        TaskKind::Digest(digest) => digest.channel,
+        TaskKind::Probe(_) => Channel::Internal,
     }
 }
 
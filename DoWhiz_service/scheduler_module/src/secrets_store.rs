@@ -100,6 +100,8 @@ mod tests {
             requester_identifier_type: None,
             requester_identifier: None,
             account_id: None,
+            trace_id: None,
+            scheduled: false,
         }
     }
 
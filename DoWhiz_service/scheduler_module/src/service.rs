@@ -1,18 +1,33 @@
 pub mod agent_market;
 pub mod analytics;
+pub mod approvals;
+pub mod audit;
 pub mod auth;
 pub mod billing;
+pub mod bounces;
+pub mod broadcasts;
 mod config;
+pub mod costs;
+pub mod dashboard;
+mod digests;
 mod email;
-mod html;
+pub(crate) mod html;
 mod inbound;
 mod ingestion;
+mod ingestion_lanes;
+mod integrity;
+pub mod ops;
 mod postmark;
+mod readiness;
 mod recipients;
 mod scheduler;
+mod sender_gate;
 mod server;
+mod spam;
 pub mod startup_workspace;
 mod state;
+mod thread_queue;
+pub mod tools;
 mod workspace;
 
 pub(crate) type BoxError = Box<dyn std::error::Error + Send + Sync>;
@@ -21,13 +36,14 @@ pub use crate::thread_state::{bump_thread_state, default_thread_state_path};
 
 pub use config::{ServiceConfig, DEFAULT_INBOUND_BODY_MAX_BYTES};
 pub use email::{process_inbound_payload, PostmarkInbound};
-pub use scheduler::cancel_pending_thread_tasks;
+pub use scheduler::{cancel_pending_thread_tasks, inbound_coalesce_delay};
 pub use server::run_server;
 pub(crate) use workspace::ensure_thread_workspace;
 pub use workspace::{bootstrap_startup_workspace_files, copy_dir_recursive};
 
 pub(crate) use config::{default_employee_config_path, resolve_telegram_bot_token};
 pub(crate) use inbound::{
-    build_discord_message_text_with_quote, build_discord_router_context,
+    build_discord_message_text_with_quote, build_discord_router_context, discord_thread_key,
     hydrate_discord_attachments, hydrate_discord_context_files, persist_discord_ingest_context,
+    resolve_discord_reply_channel,
 };
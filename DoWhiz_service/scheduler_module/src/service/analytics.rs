@@ -1,6 +1,6 @@
 use axum::extract::{Query, State};
 use axum::http::{HeaderMap, StatusCode};
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
@@ -414,43 +414,11 @@ pub async fn get_dashboard(
     headers: HeaderMap,
     Query(query): Query<DashboardQuery>,
 ) -> impl axum::response::IntoResponse {
-    let token = match extract_bearer_token(&headers) {
-        Some(token) => token,
-        None => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(json!({ "error": "Missing Authorization header" })),
-            )
-                .into_response();
-        }
-    };
-
-    let auth_user = match validate_supabase_token(&state.supabase_url, &token).await {
-        Ok(user) => user,
-        Err((status, msg)) => {
-            return (status, Json(json!({ "error": msg }))).into_response();
-        }
-    };
-
-    let email = match auth_user.email {
-        Some(email) => email.trim().to_ascii_lowercase(),
-        None => {
-            return (
-                StatusCode::FORBIDDEN,
-                Json(json!({ "error": "Admin email claim required" })),
-            )
-                .into_response();
-        }
+    let email = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
     };
 
-    if !state.admin_emails.contains(&email) {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(json!({ "error": "Dashboard is admin-only" })),
-        )
-            .into_response();
-    }
-
     let (start, end) = match resolve_window(&query) {
         Ok(window) => window,
         Err(msg) => {
@@ -500,6 +468,43 @@ pub async fn get_dashboard(
     (StatusCode::OK, Json(response)).into_response()
 }
 
+/// Resolve the caller's Supabase email and require it to be an admin.
+pub(super) async fn authorize_admin(
+    headers: &HeaderMap,
+    supabase_url: &str,
+    admin_emails: &HashSet<String>,
+) -> Result<String, Response> {
+    let Some(token) = extract_bearer_token(headers) else {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "Missing Authorization header" })),
+        )
+            .into_response());
+    };
+
+    let auth_user = validate_supabase_token(supabase_url, &token)
+        .await
+        .map_err(|(status, msg)| (status, Json(json!({ "error": msg }))).into_response())?;
+
+    let Some(email) = auth_user.email else {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "Admin email claim required" })),
+        )
+            .into_response());
+    };
+    let email = email.trim().to_ascii_lowercase();
+
+    if !admin_emails.contains(&email) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "Admin-only endpoint" })),
+        )
+            .into_response());
+    }
+    Ok(email)
+}
+
 pub fn analytics_router(state: AnalyticsState) -> Router {
     Router::new()
         .route("/analytics/track", post(track_event))
@@ -507,7 +512,7 @@ pub fn analytics_router(state: AnalyticsState) -> Router {
         .with_state(state)
 }
 
-fn parse_admin_emails(value: &str) -> HashSet<String> {
+pub(super) fn parse_admin_emails(value: &str) -> HashSet<String> {
     value
         .split(',')
         .map(|part| part.trim().to_ascii_lowercase())
@@ -0,0 +1,285 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse};
+use axum::routing::{get, post};
+use axum::{Form, Router};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::task;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::approval_store::{
+    get_global_approval_store, ApprovalDecision, ApprovalRecord, ApprovalStatus,
+};
+use crate::audit_store::{self, payload_hash, AuditEntry};
+use crate::index_store::IndexStore;
+use crate::{ModuleExecutor, Scheduler};
+
+use super::sender_gate::{release_first_contact, FIRST_CONTACT_KIND};
+use super::BoxError;
+
+#[derive(Clone)]
+pub struct ApprovalsState {
+    pub index_store: Arc<IndexStore>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApprovalLinkQuery {
+    #[serde(default)]
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApprovalDecisionForm {
+    #[serde(default)]
+    token: String,
+    #[serde(default)]
+    decision: String,
+}
+
+/// Claim the approval request and apply the decision to its held task, or
+/// release the held message of an approved first contact.
+///
+/// Returns `None` when the request is unknown, expired, already decided, or
+/// `token` does not match. Used by the approval page and Slack buttons.
+pub(crate) fn apply_approval_decision(
+    index_store: &IndexStore,
+    approval_id: &str,
+    token: Option<&str>,
+    decision: ApprovalDecision,
+    decided_by: &str,
+) -> Result<Option<ApprovalRecord>, BoxError> {
+    let store = get_global_approval_store().ok_or("approval store unavailable")?;
+    let Some(record) = store.claim(approval_id, token, decision, decided_by)? else {
+        return Ok(None);
+    };
+    if record.kind == FIRST_CONTACT_KIND {
+        release_first_contact(&record)?;
+    } else {
+        let task_id = Uuid::parse_str(&record.task_id)?;
+        let mut scheduler = Scheduler::load(&record.tasks_db_path, ModuleExecutor)?;
+        if !scheduler.resolve_approval(task_id, decision.status(), decided_by)? {
+            warn!(
+                "approval {} decided but task {} is no longer held",
+                approval_id, record.task_id
+            );
+        }
+        if let Some(user_id) = record.owner_user_id.as_deref() {
+            index_store.sync_user_tasks(user_id, scheduler.tasks())?;
+        }
+    }
+    info!(
+        "approval {} {} by {} for task {}",
+        approval_id,
+        record.status.as_str(),
+        decided_by,
+        record.task_id
+    );
+    audit_store::record(AuditEntry {
+        actor: decided_by.to_string(),
+        on_behalf_of: record
+            .owner_user_id
+            .as_ref()
+            .map(|user_id| format!("user:{}", user_id)),
+        action: format!("approval.{}", record.status.as_str()),
+        target: record.task_id.clone(),
+        channel: Some(record.approver.channel.to_string()),
+        payload_hash: payload_hash(record.summary.as_bytes()),
+        trace_id: None,
+        task_id: Some(record.task_id.clone()),
+        recorded_at: chrono::Utc::now(),
+    });
+    Ok(Some(record))
+}
+
+/// GET /approvals/{id}?token=... - Show the request with Approve/Reject
+/// buttons. Deciding takes a POST so mail scanners that prefetch links
+/// cannot approve anything.
+pub async fn show_approval(
+    Path(approval_id): Path<String>,
+    Query(query): Query<ApprovalLinkQuery>,
+) -> impl IntoResponse {
+    let Some(store) = get_global_approval_store() else {
+        return page(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Approvals unavailable",
+            "<p>Approvals are not configured on this server.</p>".to_string(),
+        );
+    };
+    let lookup_id = approval_id.clone();
+    let record = match task::spawn_blocking(move || store.get(&lookup_id)).await {
+        Ok(Ok(record)) => record,
+        Ok(Err(err)) => {
+            error!("approval.get error: {}", err);
+            return internal_error();
+        }
+        Err(err) => {
+            error!("approval.get join error: {}", err);
+            return internal_error();
+        }
+    };
+    let Some(record) = record.filter(|record| record.token == query.token) else {
+        return not_found();
+    };
+
+    let summary = escape_html(&record.summary);
+    if record.status != ApprovalStatus::Pending {
+        return page(
+            StatusCode::OK,
+            "Already decided",
+            format!(
+                "<p>This request was already {}.</p><blockquote>{}</blockquote>",
+                record.status.as_str(),
+                summary
+            ),
+        );
+    }
+    if record.expires_at <= chrono::Utc::now() {
+        return page(
+            StatusCode::GONE,
+            "Request expired",
+            format!(
+                "<p>This request expired and will not be carried out.</p><blockquote>{}</blockquote>",
+                summary
+            ),
+        );
+    }
+
+    // Relative to /approvals/{id}, so it works behind a path prefix.
+    let action = format!("{}/decision", escape_html(&approval_id));
+    let token = escape_html(&record.token);
+    page(
+        StatusCode::OK,
+        "Approval needed",
+        format!(
+            r#"<p>A digital employee is waiting for your approval before it continues:</p>
+<blockquote>{summary}</blockquote>
+<form method="post" action="{action}" style="display: inline;">
+  <input type="hidden" name="token" value="{token}" />
+  <button type="submit" name="decision" value="approve">Approve</button>
+</form>
+<form method="post" action="{action}" style="display: inline;">
+  <input type="hidden" name="token" value="{token}" />
+  <button type="submit" name="decision" value="reject">Reject</button>
+</form>"#
+        ),
+    )
+}
+
+/// POST /approvals/{id}/decision - Approve or reject a held task.
+pub async fn decide_approval(
+    State(state): State<ApprovalsState>,
+    Path(approval_id): Path<String>,
+    Form(form): Form<ApprovalDecisionForm>,
+) -> impl IntoResponse {
+    let Some(decision) = ApprovalDecision::parse(&form.decision) else {
+        return page(
+            StatusCode::BAD_REQUEST,
+            "Unknown decision",
+            "<p>Choose Approve or Reject.</p>".to_string(),
+        );
+    };
+    let Some(store) = get_global_approval_store() else {
+        return page(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Approvals unavailable",
+            "<p>Approvals are not configured on this server.</p>".to_string(),
+        );
+    };
+
+    let applied = task::spawn_blocking(move || -> Result<Option<ApprovalRecord>, BoxError> {
+        let Some(record) = store
+            .get(&approval_id)?
+            .filter(|record| record.token == form.token)
+        else {
+            return Ok(None);
+        };
+        let decided_by = format!("email:{}", record.approver.address);
+        apply_approval_decision(
+            &state.index_store,
+            &approval_id,
+            Some(&form.token),
+            decision,
+            &decided_by,
+        )
+    })
+    .await;
+    match applied {
+        Ok(Ok(Some(record))) => page(
+            StatusCode::OK,
+            match decision {
+                ApprovalDecision::Approve => "Approved",
+                ApprovalDecision::Reject => "Rejected",
+            },
+            format!(
+                "<p>Thanks, the request was {}.</p><blockquote>{}</blockquote>",
+                record.status.as_str(),
+                escape_html(&record.summary)
+            ),
+        ),
+        Ok(Ok(None)) => page(
+            StatusCode::CONFLICT,
+            "Request not available",
+            "<p>This request was already decided, has expired, or the link is invalid.</p>"
+                .to_string(),
+        ),
+        Ok(Err(err)) => {
+            error!("approval.decide error: {}", err);
+            internal_error()
+        }
+        Err(err) => {
+            error!("approval.decide join error: {}", err);
+            internal_error()
+        }
+    }
+}
+
+pub fn approvals_router(state: ApprovalsState) -> Router {
+    Router::new()
+        .route("/approvals/:approval_id", get(show_approval))
+        .route("/approvals/:approval_id/decision", post(decide_approval))
+        .with_state(state)
+}
+
+fn page(status: StatusCode, title: &str, body: String) -> axum::response::Response {
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="UTF-8" />
+  <title>DoWhiz | {title}</title>
+</head>
+<body style="font-family: sans-serif; max-width: 640px; margin: 0 auto; padding: 50px;">
+    <h1>{title}</h1>
+    {body}
+</body>
+</html>"#
+    );
+    (status, Html(html)).into_response()
+}
+
+fn not_found() -> axum::response::Response {
+    page(
+        StatusCode::NOT_FOUND,
+        "Request not found",
+        "<p>This approval link is invalid.</p>".to_string(),
+    )
+}
+
+fn internal_error() -> axum::response::Response {
+    page(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Something went wrong",
+        "<p>The request could not be loaded. Please try again later.</p>".to_string(),
+    )
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
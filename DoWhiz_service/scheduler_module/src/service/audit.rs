@@ -0,0 +1,139 @@
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::task;
+use tracing::{error, info};
+
+use crate::audit_store::{get_global_audit_store, AuditQuery};
+use crate::router_audit::{get_global_router_audit_store, MisroutedQuery};
+
+use super::analytics::{authorize_admin, parse_admin_emails};
+
+#[derive(Clone)]
+pub struct AuditState {
+    pub supabase_url: String,
+    pub admin_emails: Arc<HashSet<String>>,
+}
+
+impl AuditState {
+    /// Admins come from `AUDIT_ADMIN_EMAILS`, falling back to the analytics
+    /// dashboard list.
+    pub fn from_env() -> Self {
+        let supabase_url = std::env::var("SUPABASE_PROJECT_URL")
+            .unwrap_or_else(|_| "https://resmseutzmwumflevfqw.supabase.co".to_string());
+        let admin_emails = std::env::var("AUDIT_ADMIN_EMAILS")
+            .or_else(|_| std::env::var("ANALYTICS_ADMIN_EMAILS"))
+            .unwrap_or_else(|_| "admin@dowhiz.com,oliver@dowhiz.com".to_string());
+
+        Self {
+            supabase_url,
+            admin_emails: Arc::new(parse_admin_emails(&admin_emails)),
+        }
+    }
+}
+
+/// GET /admin/audit - List agent actions, newest first.
+pub async fn list_audit_entries(
+    State(state): State<AuditState>,
+    headers: HeaderMap,
+    Query(query): Query<AuditQuery>,
+) -> impl IntoResponse {
+    let email = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
+    };
+
+    let Some(store) = get_global_audit_store() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "Audit log is not configured" })),
+        )
+            .into_response();
+    };
+
+    let fetched = task::spawn_blocking(move || store.list(&query)).await;
+    match fetched {
+        Ok(Ok(entries)) => {
+            info!("audit.list admin={} entries={}", email, entries.len());
+            Json(json!({ "entries": entries })).into_response()
+        }
+        Ok(Err(err)) => {
+            error!("audit.list query error: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to query audit log" })),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            error!("audit.list join error: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to query audit log" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// GET /admin/router/misrouted - Random sample of quick-response decisions
+/// that look misrouted: simple answers that were not delivered or were
+/// followed by a message the full agent had to handle.
+pub async fn sample_misrouted_decisions(
+    State(state): State<AuditState>,
+    headers: HeaderMap,
+    Query(query): Query<MisroutedQuery>,
+) -> impl IntoResponse {
+    let email = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
+    };
+
+    let Some(store) = get_global_router_audit_store() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "Router audit is not configured" })),
+        )
+            .into_response();
+    };
+
+    let fetched = task::spawn_blocking(move || store.sample_misrouted(&query)).await;
+    match fetched {
+        Ok(Ok(decisions)) => {
+            info!(
+                "router_audit.misrouted admin={} decisions={}",
+                email,
+                decisions.len()
+            );
+            Json(json!({ "decisions": decisions })).into_response()
+        }
+        Ok(Err(err)) => {
+            error!("router_audit.misrouted query error: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to query router decisions" })),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            error!("router_audit.misrouted join error: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to query router decisions" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+pub fn audit_router(state: AuditState) -> Router {
+    Router::new()
+        .route("/admin/audit", get(list_audit_entries))
+        .route("/admin/router/misrouted", get(sample_misrouted_decisions))
+        .with_state(state)
+}
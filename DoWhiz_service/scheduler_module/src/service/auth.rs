@@ -1,4 +1,4 @@
-use axum::extract::{Query, State};
+use axum::extract::{Path, Query, State};
 use axum::http::{HeaderMap, StatusCode};
 use axum::response::{IntoResponse, Redirect, Response};
 use axum::routing::{delete, get, post};
@@ -12,13 +12,17 @@ use tokio::task;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
-use crate::account_store::{AccountStore, AccountStoreError, AnalyticsEventInsert};
+use crate::account_store::{
+    AccountStore, AccountStoreError, AnalyticsEventInsert, DigestPreferenceRecord,
+};
 use crate::blob_store::BlobStore;
 use crate::google_auth::GoogleAuthConfig;
 use crate::notion_store::{NotionCredential, NotionStore};
+use crate::user_skills::{self, UserSkillError, UserSkillUpload};
 use crate::user_store::UserStore;
 use crate::{load_tasks_with_status, TaskStatusSummary};
 
+use super::digests::{DEFAULT_DIGEST_HOUR_UTC, DIGEST_CHANNELS};
 use super::startup_workspace::{
     derive_provider_capabilities, derive_provider_connections, evaluate_workspace_recommendations,
     generate_startup_intake_chat_response, LinkedIdentifierSnapshot, ProactivityLevel,
@@ -1219,6 +1223,193 @@ pub async fn update_workspace_recommendation_preferences(
         .into_response()
 }
 
+#[derive(Debug, Serialize)]
+pub struct DigestPreferencesResponse {
+    pub enabled: bool,
+    pub channel: String,
+    pub identifier: Option<String>,
+    pub hour_utc: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigestPreferencesUpdateRequest {
+    pub enabled: bool,
+    pub channel: String,
+    pub identifier: String,
+    #[serde(default = "default_digest_hour_utc")]
+    pub hour_utc: i32,
+}
+
+fn default_digest_hour_utc() -> i32 {
+    DEFAULT_DIGEST_HOUR_UTC
+}
+
+impl From<DigestPreferenceRecord> for DigestPreferencesResponse {
+    fn from(record: DigestPreferenceRecord) -> Self {
+        Self {
+            enabled: record.enabled,
+            channel: record.channel,
+            identifier: Some(record.identifier),
+            hour_utc: record.hour_utc,
+        }
+    }
+}
+
+/// GET /api/workspace/digest-preferences
+/// Returns the daily digest settings for the authenticated account.
+pub async fn get_digest_preferences(
+    State(state): State<AuthState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let token = match extract_bearer_token(&headers) {
+        Some(t) => t,
+        None => {
+            return json_error_response(StatusCode::UNAUTHORIZED, "Missing Authorization header")
+        }
+    };
+
+    let auth_user = match validate_supabase_token(&state.supabase_url, &token).await {
+        Ok(user) => user,
+        Err((status, msg)) => return json_error_response(status, &msg),
+    };
+
+    let account = match load_account_for_auth_user(&state, auth_user.id).await {
+        Ok(account) => account,
+        Err(response) => return response,
+    };
+
+    let store = state.account_store.clone();
+    let account_id = account.id;
+    let preference_result = task::spawn_blocking(move || store.get_digest_preference(account_id))
+        .await
+        .map_err(|e| {
+            error!("spawn_blocking panicked: {}", e);
+            json_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal error")
+        });
+
+    let preference = match preference_result {
+        Ok(Ok(value)) => value,
+        Ok(Err(e)) => {
+            error!("Failed to load digest preference: {}", e);
+            return json_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error");
+        }
+        Err(response) => return response,
+    };
+
+    let response = preference
+        .map(DigestPreferencesResponse::from)
+        .unwrap_or_else(|| DigestPreferencesResponse {
+            enabled: false,
+            channel: "email".to_string(),
+            identifier: None,
+            hour_utc: DEFAULT_DIGEST_HOUR_UTC,
+        });
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+/// POST /api/workspace/digest-preferences
+/// Updates the daily digest settings for the authenticated account. The digest
+/// is sent to one of the account's verified identifiers on that channel.
+pub async fn update_digest_preferences(
+    State(state): State<AuthState>,
+    headers: HeaderMap,
+    Json(payload): Json<DigestPreferencesUpdateRequest>,
+) -> impl IntoResponse {
+    let token = match extract_bearer_token(&headers) {
+        Some(t) => t,
+        None => {
+            return json_error_response(StatusCode::UNAUTHORIZED, "Missing Authorization header")
+        }
+    };
+
+    let auth_user = match validate_supabase_token(&state.supabase_url, &token).await {
+        Ok(user) => user,
+        Err((status, msg)) => return json_error_response(status, &msg),
+    };
+
+    let channel = payload.channel.trim().to_ascii_lowercase();
+    if !DIGEST_CHANNELS.contains(&channel.as_str()) {
+        return json_error_response(
+            StatusCode::BAD_REQUEST,
+            "Digest channel must be email or slack",
+        );
+    }
+    if !(0..24).contains(&payload.hour_utc) {
+        return json_error_response(StatusCode::BAD_REQUEST, "hour_utc must be between 0 and 23");
+    }
+    let identifier = payload.identifier.trim().to_string();
+
+    let account = match load_account_for_auth_user(&state, auth_user.id).await {
+        Ok(account) => account,
+        Err(response) => return response,
+    };
+
+    let store = state.account_store.clone();
+    let account_id = account.id;
+    let enabled = payload.enabled;
+    let hour_utc = payload.hour_utc;
+    let preference_result = task::spawn_blocking(move || {
+        let linked = store
+            .list_identifiers(account_id)?
+            .into_iter()
+            .find(|value| {
+                value.verified
+                    && value.identifier_type == channel
+                    && value.identifier.eq_ignore_ascii_case(&identifier)
+            });
+        let Some(linked) = linked else {
+            return Ok(None);
+        };
+        store
+            .upsert_digest_preference(account_id, enabled, &channel, &linked.identifier, hour_utc)
+            .map(Some)
+    })
+    .await
+    .map_err(|e| {
+        error!("spawn_blocking panicked: {}", e);
+        json_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal error")
+    });
+
+    let preference = match preference_result {
+        Ok(Ok(Some(value))) => value,
+        Ok(Ok(None)) => {
+            return json_error_response(
+                StatusCode::BAD_REQUEST,
+                "Digest identifier must be a verified identifier linked to this account",
+            )
+        }
+        Ok(Err(e)) => {
+            error!("Failed to update digest preference: {}", e);
+            return json_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error");
+        }
+        Err(response) => return response,
+    };
+
+    track_auth_event(
+        &state.account_store,
+        "digest_preference_updated",
+        Some(account.id),
+        Some(auth_user.id),
+        Some(format!(
+            "digest_preference_updated:{}:{}",
+            account.id,
+            preference.updated_at.timestamp_millis()
+        )),
+        Some("/api/workspace/digest-preferences"),
+        serde_json::json!({
+            "enabled": preference.enabled,
+            "channel": preference.channel,
+            "hour_utc": preference.hour_utc
+        }),
+    );
+
+    (
+        StatusCode::OK,
+        Json(DigestPreferencesResponse::from(preference)),
+    )
+        .into_response()
+}
+
 /// POST /api/startup-workspace/intake-chat
 /// LLM-driven conversational intake that returns a structured draft JSON.
 pub async fn startup_workspace_intake_chat(
@@ -4214,6 +4405,145 @@ pub async fn get_account_tasks(
     (StatusCode::OK, Json(TasksResponse { tasks })).into_response()
 }
 
+// ============================================================================
+// Personal Skills
+// ============================================================================
+
+/// The authenticated account and its `users/<account_id>/skills` directory,
+/// which is synced into the account's workspaces after the employee skills.
+async fn load_account_skills_dir(
+    state: &AuthState,
+    headers: &HeaderMap,
+) -> Result<(crate::account_store::Account, Uuid, std::path::PathBuf), Response> {
+    let token = extract_bearer_token(headers).ok_or_else(|| {
+        json_error_response(StatusCode::UNAUTHORIZED, "Missing Authorization header")
+    })?;
+    let auth_user = validate_supabase_token(&state.supabase_url, &token)
+        .await
+        .map_err(|(status, msg)| json_error_response(status, &msg))?;
+    let Some(users_root) = state.users_root.clone() else {
+        return Err(json_error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Skill storage not configured",
+        ));
+    };
+    let account = load_account_for_auth_user(state, auth_user.id).await?;
+    let skills_dir = users_root.join(account.id.to_string()).join("skills");
+    Ok((account, auth_user.id, skills_dir))
+}
+
+async fn run_user_skill_op<T, F>(op: F) -> Result<T, Response>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, UserSkillError> + Send + 'static,
+{
+    let result = task::spawn_blocking(op).await.map_err(|e| {
+        error!("spawn_blocking panicked: {}", e);
+        json_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal error")
+    })?;
+    result.map_err(|err| match err {
+        UserSkillError::Invalid(msg) => json_error_response(StatusCode::BAD_REQUEST, &msg),
+        UserSkillError::Io(e) => {
+            error!("Failed to access user skills: {}", e);
+            json_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to access skills")
+        }
+    })
+}
+
+/// GET /api/account/skills
+/// Lists the authenticated account's personal skills.
+pub async fn list_account_skills(
+    State(state): State<AuthState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let (_, _, skills_dir) = match load_account_skills_dir(&state, &headers).await {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+    match run_user_skill_op(move || user_skills::list_user_skills(&skills_dir)).await {
+        Ok(skills) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "skills": skills })),
+        )
+            .into_response(),
+        Err(response) => response,
+    }
+}
+
+/// POST /api/account/skills
+/// Creates or replaces a personal skill. The body carries every file of the
+/// skill (`SKILL.md` is required); new runs of the account's threads see it.
+pub async fn upload_account_skill(
+    State(state): State<AuthState>,
+    headers: HeaderMap,
+    Json(payload): Json<UserSkillUpload>,
+) -> impl IntoResponse {
+    let (account, auth_user_id, skills_dir) = match load_account_skills_dir(&state, &headers).await
+    {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+    let skill = match run_user_skill_op(move || user_skills::save_user_skill(&skills_dir, &payload))
+        .await
+    {
+        Ok(skill) => skill,
+        Err(response) => return response,
+    };
+
+    info!("Saved skill {} for account {}", skill.name, account.id);
+    track_auth_event(
+        &state.account_store,
+        "user_skill_saved",
+        Some(account.id),
+        Some(auth_user_id),
+        None,
+        Some("/api/account/skills"),
+        serde_json::json!({
+            "name": skill.name,
+            "files": skill.files.len(),
+            "content_sha256": skill.content_sha256
+        }),
+    );
+    (StatusCode::OK, Json(serde_json::json!({ "skill": skill }))).into_response()
+}
+
+/// DELETE /api/account/skills/:name
+/// Deletes a personal skill; workspaces drop it on their next run.
+pub async fn delete_account_skill(
+    State(state): State<AuthState>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    let (account, auth_user_id, skills_dir) = match load_account_skills_dir(&state, &headers).await
+    {
+        Ok(value) => value,
+        Err(response) => return response,
+    };
+    let skill_name = name.clone();
+    let deleted =
+        match run_user_skill_op(move || user_skills::delete_user_skill(&skills_dir, &skill_name))
+            .await
+        {
+            Ok(deleted) => deleted,
+            Err(response) => return response,
+        };
+    if !deleted {
+        return json_error_response(StatusCode::NOT_FOUND, "Skill not found");
+    }
+
+    info!("Deleted skill {} for account {}", name, account.id);
+    track_auth_event(
+        &state.account_store,
+        "user_skill_deleted",
+        Some(account.id),
+        Some(auth_user_id),
+        None,
+        Some("/api/account/skills"),
+        serde_json::json!({ "name": name }),
+    );
+    (StatusCode::OK, Json(serde_json::json!({ "deleted": name }))).into_response()
+}
+
 // ============================================================================
 // Router
 // ============================================================================
@@ -4258,8 +4588,17 @@ pub fn auth_router(state: AuthState) -> Router {
             get(get_workspace_recommendation_preferences)
                 .post(update_workspace_recommendation_preferences),
         )
+        .route(
+            "/api/workspace/digest-preferences",
+            get(get_digest_preferences).post(update_digest_preferences),
+        )
         .route("/api/tasks", get(get_tasks))
         .route("/api/account/tasks", get(get_account_tasks))
+        .route(
+            "/api/account/skills",
+            get(list_account_skills).post(upload_account_skill),
+        )
+        .route("/api/account/skills/:name", delete(delete_account_skill))
         .with_state(state)
 }
 
@@ -0,0 +1,407 @@
+//! Postmark bounce and delivery webhooks.
+//!
+//! Postmark reports what became of a reply after accepting it. The outcome
+//! is recorded in the delivery journal of the send_reply task that carries
+//! the event's `MessageID`, and a hard-bounced address is marked on its
+//! email user (a later delivery clears the mark). With
+//! `POSTMARK_BOUNCE_NOTIFY=true` the user is also told about the first hard
+//! bounce on another channel they can be reached on.
+//!
+//! The route is only mounted when `POSTMARK_WEBHOOK_BASIC_AUTH` and/or
+//! `POSTMARK_WEBHOOK_TOKEN` (an `X-Postmark-Token` custom header) is set;
+//! every configured credential must match.
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tokio::task;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::account_store::AccountStore;
+use crate::i18n::{user_locale, Message};
+use crate::index_store::IndexStore;
+use crate::user_store::{UserRecord, UserStore};
+use crate::{
+    record_delivery_outcome, DeliveryOutcome, ModuleExecutor, Scheduler, SendReplyTask, TaskKind,
+};
+
+use super::broadcasts::{broadcast_target, reachable_identifiers};
+use super::config::ServiceConfig;
+use super::BoxError;
+
+/// Postmark bounce types after which the address will not accept mail.
+const HARD_BOUNCE_TYPES: &[&str] = &[
+    "HardBounce",
+    "BadEmailAddress",
+    "ManuallyDeactivated",
+    "SpamComplaint",
+    "Blocked",
+];
+
+#[derive(Clone)]
+pub struct BouncesState {
+    pub config: Arc<ServiceConfig>,
+    pub user_store: Arc<UserStore>,
+    pub index_store: Arc<IndexStore>,
+    pub account_store: Arc<AccountStore>,
+    basic_auth: Option<String>,
+    token: Option<String>,
+    notify_users: bool,
+}
+
+impl BouncesState {
+    /// `None` unless a webhook credential is configured.
+    pub fn from_env(
+        config: Arc<ServiceConfig>,
+        user_store: Arc<UserStore>,
+        index_store: Arc<IndexStore>,
+        account_store: Arc<AccountStore>,
+    ) -> Option<Self> {
+        let secret = |name| {
+            std::env::var(name)
+                .ok()
+                .map(|value: String| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let basic_auth = secret("POSTMARK_WEBHOOK_BASIC_AUTH");
+        let token = secret("POSTMARK_WEBHOOK_TOKEN");
+        if basic_auth.is_none() && token.is_none() {
+            return None;
+        }
+        let notify_users = std::env::var("POSTMARK_BOUNCE_NOTIFY")
+            .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        Some(Self {
+            config,
+            user_store,
+            index_store,
+            account_store,
+            basic_auth,
+            token,
+            notify_users,
+        })
+    }
+
+    fn authorized(&self, headers: &HeaderMap) -> bool {
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+        if let Some(expected) = self.basic_auth.as_deref() {
+            let decoded = header("authorization")
+                .and_then(|value| value.strip_prefix("Basic "))
+                .and_then(|value| {
+                    base64::engine::general_purpose::STANDARD
+                        .decode(value.trim())
+                        .ok()
+                });
+            if !decoded.is_some_and(|decoded| bool::from(decoded.ct_eq(expected.as_bytes()))) {
+                return false;
+            }
+        }
+        if let Some(expected) = self.token.as_deref() {
+            if !header("x-postmark-token")
+                .is_some_and(|token| bool::from(token.as_bytes().ct_eq(expected.as_bytes())))
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Fields of Postmark's Bounce, SpamComplaint and Delivery webhooks.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PostmarkEvent {
+    record_type: String,
+    #[serde(rename = "MessageID", default)]
+    message_id: Option<String>,
+    /// Bounced address.
+    #[serde(default)]
+    email: Option<String>,
+    /// Delivered address.
+    #[serde(default)]
+    recipient: Option<String>,
+    #[serde(rename = "Type", default)]
+    bounce_type: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    /// Set once Postmark stopped sending to the address.
+    #[serde(default)]
+    inactive: bool,
+    #[serde(default)]
+    bounced_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    delivered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DeliveryEvent {
+    message_id: Option<String>,
+    recipient: String,
+    outcome: DeliveryOutcome,
+    detail: Option<String>,
+    at: DateTime<Utc>,
+}
+
+/// The event's outcome, or `None` for record types that say nothing about
+/// delivery (opens, clicks, subscription changes).
+fn parse_event(event: PostmarkEvent, now: DateTime<Utc>) -> Option<DeliveryEvent> {
+    let (recipient, outcome, at) = match event.record_type.as_str() {
+        "Delivery" => (
+            event.recipient?,
+            DeliveryOutcome::Delivered,
+            event.delivered_at,
+        ),
+        "Bounce" | "SpamComplaint" => {
+            let hard = event.inactive
+                || event.record_type == "SpamComplaint"
+                || event
+                    .bounce_type
+                    .as_deref()
+                    .is_some_and(|kind| HARD_BOUNCE_TYPES.contains(&kind));
+            let outcome = if hard {
+                DeliveryOutcome::Bounced
+            } else {
+                DeliveryOutcome::SoftBounced
+            };
+            (event.email?, outcome, event.bounced_at)
+        }
+        _ => return None,
+    };
+    let detail = (outcome != DeliveryOutcome::Delivered).then(|| {
+        event
+            .description
+            .filter(|description| !description.trim().is_empty())
+            .or(event.bounce_type)
+            .unwrap_or_else(|| event.record_type.clone())
+    });
+    Some(DeliveryEvent {
+        message_id: event.message_id.filter(|id| !id.trim().is_empty()),
+        recipient: recipient.trim().to_string(),
+        outcome,
+        detail,
+        at: at.unwrap_or(now),
+    })
+}
+
+/// POST /postmark/webhook - Record a bounce or delivery. Failures answer
+/// 500 so Postmark retries.
+async fn postmark_webhook(
+    State(state): State<BouncesState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    if !state.authorized(&headers) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    let event: PostmarkEvent = match serde_json::from_slice(&body) {
+        Ok(event) => event,
+        Err(err) => {
+            warn!("invalid postmark webhook payload: {}", err);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+    let Some(event) = parse_event(event, Utc::now()) else {
+        return StatusCode::OK;
+    };
+    match task::spawn_blocking(move || handle_event(&state, &event)).await {
+        Ok(Ok(())) => StatusCode::OK,
+        Ok(Err(err)) => {
+            error!("postmark webhook failed: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+        Err(err) => {
+            error!("postmark webhook join error: {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+fn handle_event(state: &BouncesState, event: &DeliveryEvent) -> Result<(), BoxError> {
+    if let Some(message_id) = event.message_id.as_deref() {
+        match record_delivery_outcome(message_id, event.outcome, event.detail.as_deref(), event.at)?
+        {
+            Some(reply) => info!(
+                "reply {} of {}:{} to {}: {}",
+                reply.task_id,
+                reply.owner_kind,
+                reply.owner_id,
+                event.recipient,
+                event.outcome.as_str()
+            ),
+            None => info!(
+                "{} for unknown message {} to {}",
+                event.outcome.as_str(),
+                message_id,
+                event.recipient
+            ),
+        }
+    }
+    if crate::user_store::normalize_email(&event.recipient).is_none() {
+        return Ok(());
+    }
+    match event.outcome {
+        DeliveryOutcome::Delivered => {
+            state.user_store.clear_email_bounce(&event.recipient)?;
+        }
+        DeliveryOutcome::Bounced => {
+            let reason = event.detail.as_deref().unwrap_or("bounced");
+            let already_bounced = state
+                .user_store
+                .get_user_by_identifier("email", &event.recipient)?
+                .is_some_and(|user| user.bounced_at.is_some());
+            let user = state
+                .user_store
+                .mark_email_bounced(&event.recipient, reason, event.at)?;
+            if let Some(user) = user.filter(|_| state.notify_users && !already_bounced) {
+                if let Err(err) = notify_bounce(state, &user, reason) {
+                    warn!(
+                        "failed to tell user {} about the bounce: {}",
+                        user.user_id, err
+                    );
+                }
+            }
+        }
+        DeliveryOutcome::SoftBounced => {}
+    }
+    Ok(())
+}
+
+/// Queue a notice about the bounce on a channel other than email, if the
+/// user can be reached on one.
+fn notify_bounce(state: &BouncesState, user: &UserRecord, reason: &str) -> Result<(), BoxError> {
+    let identifiers: Vec<(String, String)> = reachable_identifiers(&state.account_store, user)
+        .into_iter()
+        .filter(|(identifier_type, _)| identifier_type != "email")
+        .collect();
+    let preferences = state.user_store.get_pref(&user.user_id)?;
+    let Some(target) = broadcast_target(preferences.preferred_channel, &identifiers) else {
+        info!(
+            "no channel besides email reaches user {}; bounce not announced",
+            user.user_id
+        );
+        return Ok(());
+    };
+
+    let config = &state.config;
+    let locale = user_locale(&user.user_id, config.employee_profile.language.as_deref());
+    let message = locale.render(
+        Message::EmailBounced,
+        &[("email", &user.identifier), ("reason", &reason)],
+    );
+    let paths = state
+        .user_store
+        .user_paths(&config.users_root, &user.user_id);
+    let notice_dir = paths
+        .state_dir
+        .join("bounces")
+        .join(Uuid::new_v4().to_string());
+    fs::create_dir_all(&notice_dir)?;
+    let body_path = notice_dir.join("message.txt");
+    fs::write(&body_path, &message)?;
+
+    let channel = target.channel;
+    let task = SendReplyTask {
+        channel,
+        subject: "Email delivery failed".to_string(),
+        html_path: body_path,
+        // Never created, so nothing is attached.
+        attachments_dir: notice_dir.join("attachments"),
+        from: None,
+        to: target.to,
+        cc: Vec::new(),
+        bcc: Vec::new(),
+        in_reply_to: None,
+        references: None,
+        archive_root: None,
+        thread_epoch: None,
+        thread_state_path: None,
+        employee_id: Some(config.employee_profile.id.clone()),
+        idempotency_key: None,
+        trace_id: None,
+        scheduled: true,
+    };
+    let mut scheduler = Scheduler::load(&paths.tasks_db_path, ModuleExecutor)?;
+    let task_id = scheduler.add_one_shot_in(Duration::ZERO, TaskKind::SendReply(task))?;
+    scheduler.set_task_details(task_id, Some("Email bounce notice".to_string()), None)?;
+    state
+        .index_store
+        .sync_user_tasks(&user.user_id, scheduler.tasks())?;
+    info!(
+        "scheduled bounce notice {} for user {} on {}",
+        task_id, user.user_id, channel
+    );
+    Ok(())
+}
+
+pub fn bounces_router(state: BouncesState) -> Router {
+    Router::new()
+        .route("/postmark/webhook", post(postmark_webhook))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(value: serde_json::Value) -> Option<DeliveryEvent> {
+        parse_event(serde_json::from_value(value).expect("event"), Utc::now())
+    }
+
+    #[test]
+    fn bounces_and_deliveries_map_to_outcomes() {
+        let hard = event(json!({
+            "RecordType": "Bounce",
+            "MessageID": "883953f4-6105-42a2-a16a-77a8eac79483",
+            "Type": "HardBounce",
+            "Email": "gone@example.com",
+            "Description": "The server was unable to deliver your message",
+            "Inactive": true,
+            "BouncedAt": "2026-03-02T12:00:00Z",
+        }))
+        .expect("bounce");
+        assert_eq!(hard.outcome, DeliveryOutcome::Bounced);
+        assert_eq!(hard.recipient, "gone@example.com");
+        assert_eq!(
+            hard.detail.as_deref(),
+            Some("The server was unable to deliver your message")
+        );
+        assert_eq!(hard.at.to_rfc3339(), "2026-03-02T12:00:00+00:00");
+
+        let soft = event(json!({
+            "RecordType": "Bounce",
+            "Type": "SoftBounce",
+            "Email": "full@example.com",
+        }))
+        .expect("soft bounce");
+        assert_eq!(soft.outcome, DeliveryOutcome::SoftBounced);
+        assert_eq!(soft.detail.as_deref(), Some("SoftBounce"));
+        assert_eq!(soft.message_id, None);
+
+        let delivered = event(json!({
+            "RecordType": "Delivery",
+            "MessageID": "abc",
+            "Recipient": "ok@example.com",
+            "DeliveredAt": "2026-03-02T12:00:00Z",
+        }))
+        .expect("delivery");
+        assert_eq!(delivered.outcome, DeliveryOutcome::Delivered);
+        assert_eq!(delivered.detail, None);
+
+        assert_eq!(
+            event(json!({ "RecordType": "Open", "Recipient": "ok@example.com" })),
+            None
+        );
+        assert_eq!(event(json!({ "RecordType": "Bounce" })), None);
+    }
+}
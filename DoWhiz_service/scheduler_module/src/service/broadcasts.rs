@@ -0,0 +1,559 @@
+//! Operator broadcasts. An admin posts one announcement (a maintenance
+//! window, a new capability) and it is scheduled as a send to every user of
+//! this employee on their preferred channel. Sends are spaced out per channel
+//! so no provider sees a burst, and users with the broadcast opt-out
+//! preference are skipped.
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::post;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::account_store::AccountStore;
+use crate::audit_store::{self, payload_hash, AuditEntry};
+use crate::channel::Channel;
+use crate::i18n::{resolve_locale, Locale};
+use crate::index_store::IndexStore;
+use crate::user_store::{UserRecord, UserStore};
+use crate::{ModuleExecutor, Scheduler, SendReplyTask, TaskKind};
+
+use super::analytics::{authorize_admin, parse_admin_emails};
+use super::config::ServiceConfig;
+use super::BoxError;
+
+#[derive(Clone)]
+pub struct BroadcastsState {
+    pub config: Arc<ServiceConfig>,
+    pub user_store: Arc<UserStore>,
+    pub index_store: Arc<IndexStore>,
+    pub account_store: Arc<AccountStore>,
+    pub supabase_url: String,
+    pub admin_emails: Arc<HashSet<String>>,
+}
+
+impl BroadcastsState {
+    /// Admins come from `BROADCAST_ADMIN_EMAILS`, falling back to the
+    /// analytics dashboard list.
+    pub fn from_env(
+        config: Arc<ServiceConfig>,
+        user_store: Arc<UserStore>,
+        index_store: Arc<IndexStore>,
+        account_store: Arc<AccountStore>,
+    ) -> Self {
+        let supabase_url = std::env::var("SUPABASE_PROJECT_URL")
+            .unwrap_or_else(|_| "https://resmseutzmwumflevfqw.supabase.co".to_string());
+        let admin_emails = std::env::var("BROADCAST_ADMIN_EMAILS")
+            .or_else(|_| std::env::var("ANALYTICS_ADMIN_EMAILS"))
+            .unwrap_or_else(|_| "admin@dowhiz.com,oliver@dowhiz.com".to_string());
+
+        Self {
+            config,
+            user_store,
+            index_store,
+            account_store,
+            supabase_url,
+            admin_emails: Arc::new(parse_admin_emails(&admin_emails)),
+        }
+    }
+}
+
+/// Subject and body of a broadcast in one language. `{employee}` is replaced
+/// with the employee's name.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BroadcastText {
+    pub subject: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BroadcastRequest {
+    #[serde(flatten)]
+    pub text: BroadcastText,
+    /// Translations keyed by language code or name ("fr", "Japanese"); users
+    /// whose language has none get `text`.
+    #[serde(default)]
+    pub translations: HashMap<String, BroadcastText>,
+    /// When the first sends go out; now when unset or in the past.
+    #[serde(default)]
+    pub send_at: Option<DateTime<Utc>>,
+    /// Sends per minute on each channel, overriding the channel defaults.
+    #[serde(default)]
+    pub per_minute: Option<u32>,
+    /// Count the recipients without scheduling anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl BroadcastRequest {
+    fn text_for(&self, locale: Locale) -> &BroadcastText {
+        self.translations
+            .iter()
+            .find(|(language, _)| Locale::parse(language) == Some(locale))
+            .map(|(_, text)| text)
+            .unwrap_or(&self.text)
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BroadcastSummary {
+    pub broadcast_id: Uuid,
+    pub dry_run: bool,
+    pub scheduled: usize,
+    pub opted_out: usize,
+    /// Users with no identifier a broadcast can be sent to.
+    pub unreachable: usize,
+    pub failed: usize,
+    pub per_channel: BTreeMap<String, usize>,
+}
+
+/// Where one user's copy of a broadcast goes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BroadcastTarget {
+    pub(crate) channel: Channel,
+    pub(crate) to: Vec<String>,
+}
+
+/// Recipients of a send on `channel` to an identifier of `identifier_type`,
+/// or `None` when a broadcast cannot start a conversation that way.
+fn address_on(channel: Channel, identifier_type: &str, identifier: &str) -> Option<Vec<String>> {
+    let identifier = identifier.to_string();
+    match (channel, identifier_type) {
+        (Channel::Email, "email")
+        | (Channel::Telegram, "telegram")
+        | (Channel::WhatsApp, "whatsapp" | "phone")
+        | (Channel::Sms, "phone")
+        | (Channel::WeChat, "wechat") => Some(vec![identifier]),
+        // Posting to the user ID delivers a DM from the app.
+        (Channel::Slack, "slack") => Some(vec![identifier.clone(), identifier]),
+        _ => None,
+    }
+}
+
+fn default_channel(identifier_type: &str) -> Option<Channel> {
+    match identifier_type {
+        "email" => Some(Channel::Email),
+        "slack" => Some(Channel::Slack),
+        "telegram" => Some(Channel::Telegram),
+        "whatsapp" => Some(Channel::WhatsApp),
+        "phone" => Some(Channel::Sms),
+        "wechat" => Some(Channel::WeChat),
+        _ => None,
+    }
+}
+
+/// The user's preferred channel when one of `identifiers` reaches them on
+/// it, else the first identifier's own channel. `identifiers` holds
+/// `(identifier_type, identifier)` pairs, the user's own first.
+pub(crate) fn broadcast_target(
+    preferred: Option<Channel>,
+    identifiers: &[(String, String)],
+) -> Option<BroadcastTarget> {
+    let on = |channel: Channel| {
+        identifiers
+            .iter()
+            .find_map(|(identifier_type, identifier)| {
+                address_on(channel, identifier_type, identifier)
+                    .map(|to| BroadcastTarget { channel, to })
+            })
+    };
+    preferred.and_then(on).or_else(|| {
+        identifiers
+            .iter()
+            .find_map(|(identifier_type, _)| default_channel(identifier_type).and_then(on))
+    })
+}
+
+/// Sends per minute on `channel` unless the broadcast sets its own rate.
+fn default_per_minute(channel: Channel) -> u32 {
+    match channel {
+        Channel::Email => 60,
+        Channel::Slack => 50,
+        Channel::WeChat => 20,
+        _ => 30,
+    }
+}
+
+/// Spaces the sends on each channel evenly at its rate.
+#[derive(Debug, Default)]
+pub(crate) struct Throttle {
+    per_minute: Option<u32>,
+    sent: HashMap<Channel, u64>,
+}
+
+impl Throttle {
+    pub(crate) fn new(per_minute: Option<u32>) -> Self {
+        Self {
+            per_minute,
+            sent: HashMap::new(),
+        }
+    }
+
+    /// How long after the broadcast starts the next send on `channel` goes.
+    pub(crate) fn next_offset(&mut self, channel: Channel) -> Duration {
+        let rate = self
+            .per_minute
+            .unwrap_or_else(|| default_per_minute(channel))
+            .max(1);
+        let slot = self.sent.entry(channel).or_insert(0);
+        let offset = Duration::from_millis(*slot * 60_000 / u64::from(rate));
+        *slot += 1;
+        offset
+    }
+}
+
+fn render(template: &str, employee_name: &str) -> String {
+    template.replace("{employee}", employee_name)
+}
+
+/// POST /admin/broadcasts - Schedule an announcement to every user.
+pub async fn create_broadcast(
+    State(state): State<BroadcastsState>,
+    headers: HeaderMap,
+    Json(request): Json<BroadcastRequest>,
+) -> impl IntoResponse {
+    let email = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
+    };
+    if request.text.subject.trim().is_empty() || request.text.message.trim().is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "subject and message are required" })),
+        )
+            .into_response();
+    }
+
+    let result = task::spawn_blocking(move || run_broadcast(&state, &request, &email)).await;
+    match result {
+        Ok(Ok(summary)) => Json(summary).into_response(),
+        Ok(Err(err)) => {
+            error!("broadcast failed: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to schedule broadcast" })),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            error!("broadcast join error: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to schedule broadcast" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+fn run_broadcast(
+    state: &BroadcastsState,
+    request: &BroadcastRequest,
+    admin_email: &str,
+) -> Result<BroadcastSummary, BoxError> {
+    let config = &state.config;
+    let now = Utc::now();
+    let start = request.send_at.filter(|at| *at > now).unwrap_or(now);
+    let lead = (start - now).to_std().unwrap_or_default();
+    let mut summary = BroadcastSummary {
+        broadcast_id: Uuid::new_v4(),
+        dry_run: request.dry_run,
+        ..Default::default()
+    };
+    let mut throttle = Throttle::new(request.per_minute);
+
+    for user in state.user_store.list_users()? {
+        // Other employees delegating work are not users to announce to.
+        if user.identifier_type == "employee" {
+            continue;
+        }
+        // Users of this employee have a home under its users root.
+        let paths = state
+            .user_store
+            .user_paths(&config.users_root, &user.user_id);
+        if !paths.root.exists() {
+            continue;
+        }
+        let preferences = match state.user_store.get_pref(&user.user_id) {
+            Ok(preferences) => preferences,
+            Err(err) => {
+                warn!("broadcast skipped user {}: {}", user.user_id, err);
+                summary.failed += 1;
+                continue;
+            }
+        };
+        if preferences.broadcast_opt_out {
+            summary.opted_out += 1;
+            continue;
+        }
+        let identifiers = reachable_identifiers(&state.account_store, &user);
+        let Some(target) = broadcast_target(preferences.preferred_channel, &identifiers) else {
+            summary.unreachable += 1;
+            continue;
+        };
+        let locale = resolve_locale(
+            preferences.language.as_deref(),
+            config.employee_profile.language.as_deref(),
+        );
+        let delay = lead + throttle.next_offset(target.channel);
+        let channel = target.channel.to_string();
+        if !request.dry_run {
+            let text = request.text_for(locale);
+            if let Err(err) =
+                schedule_broadcast_send(state, &user, summary.broadcast_id, target, text, delay)
+            {
+                warn!(
+                    "broadcast {} failed for user {}: {}",
+                    summary.broadcast_id, user.user_id, err
+                );
+                summary.failed += 1;
+                continue;
+            }
+        }
+        summary.scheduled += 1;
+        *summary.per_channel.entry(channel).or_insert(0) += 1;
+    }
+
+    info!(
+        "broadcast {} by {} dry_run={} scheduled={} opted_out={} unreachable={} failed={}",
+        summary.broadcast_id,
+        admin_email,
+        summary.dry_run,
+        summary.scheduled,
+        summary.opted_out,
+        summary.unreachable,
+        summary.failed
+    );
+    if !request.dry_run {
+        audit_store::record(AuditEntry {
+            actor: admin_email.to_string(),
+            on_behalf_of: None,
+            action: "broadcast".to_string(),
+            target: format!("broadcast:{}", summary.broadcast_id),
+            channel: None,
+            payload_hash: payload_hash(request.text.message.as_bytes()),
+            trace_id: None,
+            task_id: None,
+            recorded_at: now,
+        });
+    }
+    Ok(summary)
+}
+
+/// The user's own identifier, then the verified identifiers of the account it
+/// is linked to.
+pub(super) fn reachable_identifiers(
+    account_store: &AccountStore,
+    user: &UserRecord,
+) -> Vec<(String, String)> {
+    let mut identifiers = vec![(user.identifier_type.clone(), user.identifier.clone())];
+    let account =
+        match account_store.get_account_by_identifier(&user.identifier_type, &user.identifier) {
+            Ok(account) => account,
+            Err(err) => {
+                warn!("account lookup failed for user {}: {}", user.user_id, err);
+                None
+            }
+        };
+    let Some(account) = account else {
+        return identifiers;
+    };
+    match account_store.list_identifiers(account.id) {
+        Ok(linked) => identifiers.extend(
+            linked
+                .into_iter()
+                .filter(|identifier| identifier.verified)
+                .map(|identifier| (identifier.identifier_type, identifier.identifier)),
+        ),
+        Err(err) => warn!(
+            "identifier lookup failed for account {}: {}",
+            account.id, err
+        ),
+    }
+    identifiers
+}
+
+/// Queue one user's copy in their scheduler. It is a scheduled send, so it
+/// waits out the recipient's quiet hours.
+fn schedule_broadcast_send(
+    state: &BroadcastsState,
+    user: &UserRecord,
+    broadcast_id: Uuid,
+    target: BroadcastTarget,
+    text: &BroadcastText,
+    delay: Duration,
+) -> Result<Uuid, BoxError> {
+    let config = &state.config;
+    let paths = state
+        .user_store
+        .user_paths(&config.users_root, &user.user_id);
+    let employee_name = config
+        .employee_profile
+        .display_name
+        .as_deref()
+        .unwrap_or(&config.employee_profile.id);
+    let subject = render(&text.subject, employee_name);
+    let message = render(&text.message, employee_name);
+
+    let broadcast_dir = paths
+        .state_dir
+        .join("broadcasts")
+        .join(broadcast_id.to_string());
+    fs::create_dir_all(&broadcast_dir)?;
+    let body_path = if target.channel == Channel::Email {
+        let path = broadcast_dir.join("message.html");
+        let paragraphs: String = message
+            .split("\n\n")
+            .map(|paragraph| format!("<p>{}</p>", escape_html(paragraph).replace('\n', "<br>")))
+            .collect();
+        fs::write(&path, paragraphs)?;
+        path
+    } else {
+        let path = broadcast_dir.join("message.txt");
+        fs::write(&path, &message)?;
+        path
+    };
+
+    let task = SendReplyTask {
+        channel: target.channel,
+        subject: subject.clone(),
+        html_path: body_path,
+        // Never created, so nothing is attached.
+        attachments_dir: broadcast_dir.join("attachments"),
+        from: None,
+        to: target.to,
+        cc: Vec::new(),
+        bcc: Vec::new(),
+        in_reply_to: None,
+        references: None,
+        archive_root: None,
+        thread_epoch: None,
+        thread_state_path: None,
+        employee_id: Some(config.employee_profile.id.clone()),
+        idempotency_key: None,
+        trace_id: None,
+        scheduled: true,
+    };
+    let mut scheduler = Scheduler::load(&paths.tasks_db_path, ModuleExecutor)?;
+    let task_id = scheduler.add_one_shot_in(delay, TaskKind::SendReply(task))?;
+    scheduler.set_task_details(task_id, Some(format!("Broadcast: {}", subject)), None)?;
+    state
+        .index_store
+        .sync_user_tasks(&user.user_id, scheduler.tasks())?;
+    Ok(task_id)
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+pub fn broadcasts_router(state: BroadcastsState) -> Router {
+    Router::new()
+        .route("/admin/broadcasts", post(create_broadcast))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identifiers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(kind, value)| (kind.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn target_prefers_the_preferred_channel_across_linked_identifiers() {
+        let linked = identifiers(&[("email", "ada@example.com"), ("slack", "U123")]);
+
+        assert_eq!(
+            broadcast_target(Some(Channel::Slack), &linked),
+            Some(BroadcastTarget {
+                channel: Channel::Slack,
+                to: vec!["U123".to_string(), "U123".to_string()],
+            })
+        );
+        // No identifier reaches the user on Telegram, so their own channel wins.
+        assert_eq!(
+            broadcast_target(Some(Channel::Telegram), &linked),
+            Some(BroadcastTarget {
+                channel: Channel::Email,
+                to: vec!["ada@example.com".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn target_falls_back_to_a_linked_identifier() {
+        let linked = identifiers(&[("discord", "42"), ("phone", "+15550100")]);
+
+        assert_eq!(
+            broadcast_target(None, &linked),
+            Some(BroadcastTarget {
+                channel: Channel::Sms,
+                to: vec!["+15550100".to_string()],
+            })
+        );
+        assert_eq!(
+            broadcast_target(Some(Channel::WhatsApp), &linked).map(|target| target.channel),
+            Some(Channel::WhatsApp)
+        );
+        assert_eq!(
+            broadcast_target(None, &identifiers(&[("notion", "x")])),
+            None
+        );
+    }
+
+    #[test]
+    fn throttle_spaces_sends_per_channel() {
+        let mut throttle = Throttle::new(None);
+        assert_eq!(throttle.next_offset(Channel::Email), Duration::ZERO);
+        assert_eq!(throttle.next_offset(Channel::Email), Duration::from_secs(1));
+        assert_eq!(throttle.next_offset(Channel::Slack), Duration::ZERO);
+        assert_eq!(throttle.next_offset(Channel::Sms), Duration::ZERO);
+        assert_eq!(throttle.next_offset(Channel::Sms), Duration::from_secs(2));
+
+        let mut throttle = Throttle::new(Some(6));
+        throttle.next_offset(Channel::Slack);
+        assert_eq!(
+            throttle.next_offset(Channel::Slack),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn text_for_picks_the_translation_of_the_locale() {
+        let request: BroadcastRequest = serde_json::from_value(json!({
+            "subject": "Maintenance tonight",
+            "message": "{employee} is offline from 22:00 UTC.",
+            "translations": {
+                "French": { "subject": "Maintenance ce soir", "message": "{employee} est hors ligne." }
+            }
+        }))
+        .expect("request");
+
+        assert_eq!(request.text_for(Locale::Fr).subject, "Maintenance ce soir");
+        assert_eq!(request.text_for(Locale::Ja).subject, "Maintenance tonight");
+        assert_eq!(
+            render(&request.text_for(Locale::En).message, "Oliver"),
+            "Oliver is offline from 22:00 UTC."
+        );
+    }
+}
@@ -4,7 +4,7 @@ use std::path::PathBuf;
 use std::time::Duration;
 
 use crate::employee_config::{load_employee_directory, EmployeeDirectory, EmployeeProfile};
-use crate::ingestion_queue::resolve_ingestion_queue_backend;
+use crate::ingestion_queue::{is_broker_backend, resolve_ingestion_queue_backend};
 
 use super::BoxError;
 
@@ -27,6 +27,8 @@ pub struct ServiceConfig {
     pub users_db_path: PathBuf,
     pub task_index_path: PathBuf,
     pub codex_model: String,
+    /// Default model for `runner = "gemini"` employees (`GEMINI_MODEL`).
+    pub gemini_model: String,
     pub codex_disabled: bool,
     pub scheduler_poll_interval: Duration,
     pub scheduler_max_concurrency: usize,
@@ -133,28 +135,27 @@ impl ServiceConfig {
                     .into_owned()
             }))?;
         let ingestion_backend = resolve_ingestion_queue_backend();
-        let ingestion_db_url =
-            if ingestion_backend == "servicebus" || ingestion_backend == "service_bus" {
-                String::new()
-            } else {
-                env::var("INGESTION_DB_URL")
-                    .ok()
-                    .map(|value| value.trim().to_string())
-                    .filter(|value| !value.is_empty())
-                    .or_else(|| {
-                        env::var("SUPABASE_DB_URL")
-                            .ok()
-                            .map(|value| value.trim().to_string())
-                            .filter(|value| !value.is_empty())
-                    })
-                    .or_else(|| {
-                        env::var("DATABASE_URL")
-                            .ok()
-                            .map(|value| value.trim().to_string())
-                            .filter(|value| !value.is_empty())
-                    })
-                    .ok_or_else(|| "missing INGESTION_DB_URL or SUPABASE_DB_URL".to_string())?
-            };
+        let ingestion_db_url = if is_broker_backend(&ingestion_backend) {
+            String::new()
+        } else {
+            env::var("INGESTION_DB_URL")
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .or_else(|| {
+                    env::var("SUPABASE_DB_URL")
+                        .ok()
+                        .map(|value| value.trim().to_string())
+                        .filter(|value| !value.is_empty())
+                })
+                .or_else(|| {
+                    env::var("DATABASE_URL")
+                        .ok()
+                        .map(|value| value.trim().to_string())
+                        .filter(|value| !value.is_empty())
+                })
+                .ok_or_else(|| "missing INGESTION_DB_URL or SUPABASE_DB_URL".to_string())?
+        };
         let users_root = resolve_path(env::var("USERS_ROOT").unwrap_or_else(|_| {
             employee_runtime_root
                 .join("users")
@@ -176,6 +177,8 @@ impl ServiceConfig {
                 .into_owned()
         }))?;
         let codex_model = env::var("CODEX_MODEL").unwrap_or_else(|_| "gpt-5.4".to_string());
+        let gemini_model =
+            env::var("GEMINI_MODEL").unwrap_or_else(|_| "gemini-2.5-pro".to_string());
         let codex_disabled = env_flag("CODEX_DISABLED", false);
         let scheduler_poll_interval = env::var("SCHEDULER_POLL_INTERVAL_SECS")
             .ok()
@@ -271,6 +274,7 @@ impl ServiceConfig {
             users_db_path,
             task_index_path,
             codex_model,
+            gemini_model,
             codex_disabled,
             scheduler_poll_interval,
             scheduler_max_concurrency,
@@ -294,6 +298,16 @@ impl ServiceConfig {
             whatsapp_verify_token,
         })
     }
+
+    /// Model for a task when the employee sets no `model`. Empty lets run_task
+    /// pick the runner's own default.
+    pub fn default_model_for_runner(&self, runner: &str) -> String {
+        match runner {
+            "claude" | "local" | "ollama" => String::new(),
+            "gemini" => self.gemini_model.clone(),
+            _ => self.codex_model.clone(),
+        }
+    }
 }
 
 fn env_flag(key: &str, default: bool) -> bool {
@@ -483,11 +497,23 @@ mod tests {
             runtime_root: None,
             agents_path: None,
             claude_path: None,
+            gemini_path: None,
             soul_path: None,
             skills_dir: None,
             discord_enabled: false,
             slack_enabled: false,
             bluebubbles_enabled: false,
+            outbound_policy: Default::default(),
+            inbound_policy: Default::default(),
+            approver: None,
+            slack_approvers: Vec::new(),
+            redaction: None,
+            archive_tiering: None,
+            sandbox: None,
+            language: None,
+            mention_names: Vec::new(),
+            router: Default::default(),
+            formatting: Default::default(),
         }
     }
 
@@ -0,0 +1,95 @@
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::task;
+use tracing::{error, info};
+
+use crate::account_store::get_global_account_store;
+use crate::task_costs::TaskCostQuery;
+
+use super::analytics::{authorize_admin, parse_admin_emails};
+
+#[derive(Clone)]
+pub struct CostsState {
+    pub supabase_url: String,
+    pub admin_emails: Arc<HashSet<String>>,
+}
+
+impl CostsState {
+    /// Admins come from `COSTS_ADMIN_EMAILS`, falling back to the analytics
+    /// dashboard list.
+    pub fn from_env() -> Self {
+        let supabase_url = std::env::var("SUPABASE_PROJECT_URL")
+            .unwrap_or_else(|_| "https://resmseutzmwumflevfqw.supabase.co".to_string());
+        let admin_emails = std::env::var("COSTS_ADMIN_EMAILS")
+            .or_else(|_| std::env::var("ANALYTICS_ADMIN_EMAILS"))
+            .unwrap_or_else(|_| "admin@dowhiz.com,oliver@dowhiz.com".to_string());
+
+        Self {
+            supabase_url,
+            admin_emails: Arc::new(parse_admin_emails(&admin_emails)),
+        }
+    }
+}
+
+/// GET /admin/costs - Token and cost totals, grouped by `group_by`.
+pub async fn list_cost_totals(
+    State(state): State<CostsState>,
+    headers: HeaderMap,
+    Query(query): Query<TaskCostQuery>,
+) -> impl IntoResponse {
+    let email = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
+    };
+
+    let dimensions = match query.dimensions() {
+        Ok(dimensions) => dimensions,
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": message }))).into_response()
+        }
+    };
+
+    let Some(store) = get_global_account_store() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "Account store is not configured" })),
+        )
+            .into_response();
+    };
+
+    let fetched = task::spawn_blocking(move || store.task_cost_totals(&query, &dimensions)).await;
+    match fetched {
+        Ok(Ok(totals)) => {
+            info!("costs.totals admin={} rows={}", email, totals.len());
+            Json(json!({ "totals": totals })).into_response()
+        }
+        Ok(Err(err)) => {
+            error!("costs.totals query error: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to query task costs" })),
+            )
+                .into_response()
+        }
+        Err(err) => {
+            error!("costs.totals join error: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to query task costs" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+pub fn costs_router(state: CostsState) -> Router {
+    Router::new()
+        .route("/admin/costs", get(list_cost_totals))
+        .with_state(state)
+}
@@ -0,0 +1,566 @@
+//! Read-only JSON for the internal dashboard: a user's threads and the
+//! activity timeline of one thread, assembled from the thread workspaces,
+//! the user's scheduler, the task index, executions and the delivery journal.
+
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::index_store::{IndexStore, TaskIndexQuery};
+use crate::thread_state::{default_thread_state_path, load_thread_state};
+use crate::user_store::UserStore;
+use crate::{
+    list_task_deliveries, list_task_executions, ExecutionQuery, ModuleExecutor, ScheduledTask,
+    Scheduler, TaskDeliveryRecord, TaskExecutionRecord, TaskKind,
+};
+
+use super::analytics::{authorize_admin, parse_admin_emails};
+use super::config::ServiceConfig;
+use super::ops::{resolve_workspace_dir, respond};
+use super::scheduler::task_kind_label;
+use super::BoxError;
+
+/// Executions fetched per user; older ones drop off the dashboard.
+const EXECUTION_LIMIT: i64 = 1000;
+
+#[derive(Clone)]
+pub struct DashboardState {
+    pub config: Arc<ServiceConfig>,
+    pub user_store: Arc<UserStore>,
+    pub index_store: Arc<IndexStore>,
+    pub supabase_url: String,
+    pub admin_emails: Arc<HashSet<String>>,
+}
+
+impl DashboardState {
+    /// Admins come from `DASHBOARD_ADMIN_EMAILS`, falling back to the
+    /// analytics dashboard list.
+    pub fn from_env(
+        config: Arc<ServiceConfig>,
+        user_store: Arc<UserStore>,
+        index_store: Arc<IndexStore>,
+    ) -> Self {
+        let supabase_url = std::env::var("SUPABASE_PROJECT_URL")
+            .unwrap_or_else(|_| "https://resmseutzmwumflevfqw.supabase.co".to_string());
+        let admin_emails = std::env::var("DASHBOARD_ADMIN_EMAILS")
+            .or_else(|_| std::env::var("ANALYTICS_ADMIN_EMAILS"))
+            .unwrap_or_else(|_| "admin@dowhiz.com,oliver@dowhiz.com".to_string());
+
+        Self {
+            config,
+            user_store,
+            index_store,
+            supabase_url,
+            admin_emails: Arc::new(parse_admin_emails(&admin_emails)),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TimelineQuery {
+    /// Owner of the thread; every user is searched when omitted.
+    user_id: Option<String>,
+}
+
+/// Everything recorded about one user's tasks, loaded once per request.
+struct UserActivity {
+    tasks: Vec<ScheduledTask>,
+    next_runs: HashMap<String, DateTime<Utc>>,
+    executions: Vec<TaskExecutionRecord>,
+    deliveries: Vec<TaskDeliveryRecord>,
+}
+
+impl UserActivity {
+    fn load(state: &DashboardState, user_id: &str) -> Result<Self, BoxError> {
+        let paths = state
+            .user_store
+            .user_paths(&state.config.users_root, user_id);
+        let scheduler = Scheduler::load(&paths.tasks_db_path, ModuleExecutor)?;
+        Ok(Self {
+            tasks: scheduler.tasks().to_vec(),
+            next_runs: state.index_store.user_next_runs(user_id)?,
+            executions: list_task_executions(&ExecutionQuery {
+                user_id: Some(user_id.to_string()),
+                limit: Some(EXECUTION_LIMIT),
+                ..Default::default()
+            })?,
+            deliveries: list_task_deliveries(user_id)?,
+        })
+    }
+
+    /// Ids of the tasks working the thread in `workspace`.
+    fn thread_task_ids(&self, workspace: &FsPath) -> HashSet<String> {
+        self.tasks
+            .iter()
+            .filter(|task| task_in_thread(task, workspace))
+            .map(|task| task.id.to_string())
+            .collect()
+    }
+}
+
+/// One row of the threads list.
+#[derive(Debug, Serialize)]
+pub(crate) struct ThreadSummary {
+    pub(crate) workspace: String,
+    pub(crate) thread_key: Option<String>,
+    pub(crate) epoch: u64,
+    pub(crate) messages: u64,
+    pub(crate) updated_at: Option<String>,
+    pub(crate) tasks: usize,
+    pub(crate) enabled_tasks: usize,
+    /// Earliest indexed run among the thread's enabled tasks.
+    pub(crate) next_run: Option<DateTime<Utc>>,
+    pub(crate) last_execution: Option<TaskExecutionRecord>,
+    /// Replies that failed to send or bounced.
+    pub(crate) failed_deliveries: usize,
+}
+
+/// One entry of a thread timeline; `detail` depends on `kind`.
+#[derive(Debug, Serialize)]
+pub(crate) struct TimelineEvent {
+    pub(crate) at: DateTime<Utc>,
+    /// "message", "task_created", "execution", "delivery" or "scheduled".
+    pub(crate) kind: &'static str,
+    pub(crate) task_id: Option<String>,
+    pub(crate) detail: serde_json::Value,
+}
+
+/// Same test as `cancel_pending_thread_tasks`, without the epoch.
+fn task_in_thread(task: &ScheduledTask, workspace: &FsPath) -> bool {
+    match &task.kind {
+        TaskKind::RunTask(run) => run.workspace_dir == workspace,
+        TaskKind::SendReply(send) => send
+            .thread_state_path
+            .as_ref()
+            .map(|path| path == &default_thread_state_path(workspace))
+            .unwrap_or_else(|| send.html_path.starts_with(workspace)),
+        _ => false,
+    }
+}
+
+fn parse_timestamp(value: Option<&str>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value?)
+        .ok()
+        .map(|value| value.with_timezone(&Utc))
+}
+
+fn summarize_thread(workspace: &FsPath, activity: &UserActivity) -> ThreadSummary {
+    let state = load_thread_state(&default_thread_state_path(workspace)).unwrap_or_else(|err| {
+        warn!(
+            "dashboard: failed to load thread state of {}: {}",
+            workspace.display(),
+            err
+        );
+        None
+    });
+    let task_ids = activity.thread_task_ids(workspace);
+    let enabled: Vec<&ScheduledTask> = activity
+        .tasks
+        .iter()
+        .filter(|task| task.enabled && task_ids.contains(&task.id.to_string()))
+        .collect();
+    ThreadSummary {
+        workspace: workspace
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string(),
+        thread_key: state.as_ref().map(|state| state.thread_id.clone()),
+        epoch: state.as_ref().map(|state| state.epoch).unwrap_or(0),
+        messages: state
+            .as_ref()
+            .map(|state| state.last_email_seq)
+            .unwrap_or(0),
+        updated_at: state.map(|state| state.updated_at),
+        tasks: task_ids.len(),
+        enabled_tasks: enabled.len(),
+        next_run: enabled
+            .iter()
+            .filter_map(|task| activity.next_runs.get(&task.id.to_string()))
+            .min()
+            .copied(),
+        // Executions come newest first.
+        last_execution: activity
+            .executions
+            .iter()
+            .find(|execution| task_ids.contains(&execution.task_id))
+            .cloned(),
+        failed_deliveries: activity
+            .deliveries
+            .iter()
+            .filter(|delivery| {
+                (delivery.state == "failed" || delivery.outcome.as_deref() == Some("bounced"))
+                    && task_ids.contains(&delivery.task_id)
+            })
+            .count(),
+    }
+}
+
+/// Events of the thread in `workspace`, oldest first.
+fn thread_timeline(
+    workspace: &FsPath,
+    activity: &UserActivity,
+) -> Result<Vec<TimelineEvent>, BoxError> {
+    let mut events = Vec::new();
+    match std::fs::read_dir(workspace.join("incoming_email")) {
+        Ok(entries) => {
+            for entry in entries.flatten() {
+                let metadata = entry.metadata()?;
+                if !metadata.is_file() {
+                    continue;
+                }
+                events.push(TimelineEvent {
+                    at: metadata.modified()?.into(),
+                    kind: "message",
+                    task_id: None,
+                    detail: json!({ "file": entry.file_name().to_string_lossy() }),
+                });
+            }
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    let task_ids = activity.thread_task_ids(workspace);
+    for task in activity
+        .tasks
+        .iter()
+        .filter(|task| task_ids.contains(&task.id.to_string()))
+    {
+        let task_id = task.id.to_string();
+        events.push(TimelineEvent {
+            at: task.created_at,
+            kind: "task_created",
+            task_id: Some(task_id.clone()),
+            detail: json!({
+                "task_kind": task_kind_label(&task.kind),
+                "enabled": task.enabled,
+                "description": task.description,
+            }),
+        });
+        if let Some(next_run) = activity.next_runs.get(&task_id).filter(|_| task.enabled) {
+            events.push(TimelineEvent {
+                at: *next_run,
+                kind: "scheduled",
+                task_id: Some(task_id),
+                detail: json!({ "task_kind": task_kind_label(&task.kind) }),
+            });
+        }
+    }
+    for execution in &activity.executions {
+        let Some(at) = parse_timestamp(execution.started_at.as_deref()) else {
+            continue;
+        };
+        if !task_ids.contains(&execution.task_id) {
+            continue;
+        }
+        events.push(TimelineEvent {
+            at,
+            kind: "execution",
+            task_id: Some(execution.task_id.clone()),
+            detail: json!({
+                "status": execution.status,
+                "finished_at": execution.finished_at,
+                "error_message": execution.error_message,
+            }),
+        });
+    }
+    for delivery in &activity.deliveries {
+        let at = parse_timestamp(delivery.delivered_at.as_deref())
+            .or_else(|| parse_timestamp(delivery.started_at.as_deref()));
+        let Some(at) = at else {
+            continue;
+        };
+        if !task_ids.contains(&delivery.task_id) {
+            continue;
+        }
+        events.push(TimelineEvent {
+            at,
+            kind: "delivery",
+            task_id: Some(delivery.task_id.clone()),
+            detail: json!({
+                "state": delivery.state,
+                "attempts": delivery.attempts,
+                "outcome": delivery.outcome,
+                "outcome_detail": delivery.outcome_detail,
+            }),
+        });
+    }
+    events.sort_by_key(|event| event.at);
+    Ok(events)
+}
+
+/// The owner and workspace of the thread keyed `thread_key`.
+fn find_thread(
+    state: &DashboardState,
+    thread_key: &str,
+    user_id: Option<&str>,
+) -> Result<Option<(String, PathBuf)>, BoxError> {
+    let candidates = match user_id {
+        Some(user_id) => vec![user_id.to_string()],
+        None => {
+            let entries = match std::fs::read_dir(&state.config.users_root) {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+            entries
+                .flatten()
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+                .collect()
+        }
+    };
+    for user_id in candidates {
+        let paths = state
+            .user_store
+            .user_paths(&state.config.users_root, &user_id);
+        if let Some(dir) = resolve_workspace_dir(&paths.workspaces_root, thread_key) {
+            return Ok(Some((user_id, dir)));
+        }
+    }
+    Ok(None)
+}
+
+/// GET /dashboard/users/:user_id/threads - The user's threads, most recently
+/// active first.
+pub async fn list_user_threads(
+    State(state): State<DashboardState>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("dashboard.threads", move || {
+        let paths = state
+            .user_store
+            .user_paths(&state.config.users_root, &user_id);
+        let entries = match std::fs::read_dir(&paths.workspaces_root) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let activity = UserActivity::load(&state, &user_id)?;
+        let mut threads: Vec<ThreadSummary> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| summarize_thread(&entry.path(), &activity))
+            .collect();
+        threads.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        Ok(Some(json!({ "user_id": user_id, "threads": threads })))
+    })
+    .await
+}
+
+/// GET /dashboard/users/:user_id/tasks - The user's indexed tasks by next
+/// run, with the filters and paging of `GET /admin/tasks`.
+pub async fn list_user_indexed_tasks(
+    State(state): State<DashboardState>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+    Query(mut query): Query<TaskIndexQuery>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    query.user_id = Some(user_id);
+    respond("dashboard.tasks", move || {
+        Ok(Some(state.index_store.query_tasks(&query)?))
+    })
+    .await
+}
+
+/// GET /dashboard/threads/:thread_key/timeline - Messages, tasks, executions
+/// and deliveries of one thread, oldest first.
+pub async fn show_thread_timeline(
+    State(state): State<DashboardState>,
+    headers: HeaderMap,
+    Path(thread_key): Path<String>,
+    Query(query): Query<TimelineQuery>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("dashboard.timeline", move || {
+        let Some((user_id, workspace)) =
+            find_thread(&state, &thread_key, query.user_id.as_deref())?
+        else {
+            return Ok(None);
+        };
+        let activity = UserActivity::load(&state, &user_id)?;
+        let events = thread_timeline(&workspace, &activity)?;
+        Ok(Some(json!({
+            "user_id": user_id,
+            "thread": summarize_thread(&workspace, &activity),
+            "events": events,
+        })))
+    })
+    .await
+}
+
+pub fn dashboard_router(state: DashboardState) -> Router {
+    Router::new()
+        .route("/dashboard/users/:user_id/threads", get(list_user_threads))
+        .route(
+            "/dashboard/users/:user_id/tasks",
+            get(list_user_indexed_tasks),
+        )
+        .route(
+            "/dashboard/threads/:thread_key/timeline",
+            get(show_thread_timeline),
+        )
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::tests::run_task_in;
+    use crate::thread_state::{write_thread_state, ThreadState};
+    use crate::Schedule;
+    use chrono::Duration;
+    use tempfile::TempDir;
+    use uuid::Uuid;
+
+    fn run_task(workspace: &FsPath, enabled: bool, created_at: DateTime<Utc>) -> ScheduledTask {
+        ScheduledTask {
+            id: Uuid::new_v4(),
+            kind: TaskKind::RunTask(run_task_in(workspace)),
+            schedule: Schedule::OneShot {
+                run_at: created_at + Duration::hours(1),
+            },
+            enabled,
+            created_at,
+            last_run: None,
+            approval: None,
+            description: None,
+            ends: None,
+        }
+    }
+
+    fn execution(
+        task: &ScheduledTask,
+        started_at: DateTime<Utc>,
+        status: &str,
+    ) -> TaskExecutionRecord {
+        TaskExecutionRecord {
+            id: "0".repeat(24),
+            owner_kind: "user".to_string(),
+            owner_id: "user-1".to_string(),
+            task_id: task.id.to_string(),
+            status: status.to_string(),
+            started_at: Some(started_at.to_rfc3339()),
+            finished_at: None,
+            error_message: None,
+            log_path: None,
+        }
+    }
+
+    #[test]
+    fn timeline_keeps_only_the_thread_and_orders_events() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspace = temp.path().join("thread_a");
+        let other = temp.path().join("thread_b");
+        std::fs::create_dir_all(workspace.join("incoming_email")).expect("incoming");
+        std::fs::write(workspace.join("incoming_email/0001_message.txt"), "hi").expect("write");
+
+        let start = Utc::now() - Duration::days(1);
+        let done = run_task(&workspace, false, start);
+        let pending = run_task(&workspace, true, start + Duration::minutes(5));
+        let elsewhere = run_task(&other, true, start);
+        let next_run = Utc::now() + Duration::hours(2);
+        let activity = UserActivity {
+            next_runs: HashMap::from([
+                (pending.id.to_string(), next_run),
+                (elsewhere.id.to_string(), next_run),
+            ]),
+            executions: vec![
+                execution(&elsewhere, start + Duration::minutes(2), "success"),
+                execution(&done, start + Duration::minutes(1), "success"),
+            ],
+            deliveries: vec![TaskDeliveryRecord {
+                task_id: elsewhere.id.to_string(),
+                state: "sent".to_string(),
+                attempts: 1,
+                started_at: Some(start.to_rfc3339()),
+                delivered_at: Some(start.to_rfc3339()),
+                outcome: Some("delivered".to_string()),
+                outcome_detail: None,
+            }],
+            tasks: vec![done.clone(), pending.clone(), elsewhere],
+        };
+
+        let events = thread_timeline(&workspace, &activity).expect("timeline");
+        let kinds: Vec<&str> = events.iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "task_created",
+                "execution",
+                "task_created",
+                "message",
+                "scheduled"
+            ]
+        );
+        assert_eq!(events[1].task_id, Some(done.id.to_string()));
+        assert_eq!(events[4].at, next_run);
+    }
+
+    #[test]
+    fn summary_reads_thread_state_and_counts_thread_tasks() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspace = temp.path().join("users/u1/workspaces/thread_a");
+        let mut thread_state = ThreadState::new("slack:T1:C1:1.0".to_string(), None);
+        thread_state.bump(None);
+        write_thread_state(&default_thread_state_path(&workspace), &thread_state).expect("state");
+
+        let start = Utc::now();
+        let first = run_task(&workspace, true, start);
+        let second = run_task(&workspace, true, start);
+        let first_run = start + Duration::minutes(10);
+        let activity = UserActivity {
+            next_runs: HashMap::from([
+                (first.id.to_string(), first_run),
+                (second.id.to_string(), start + Duration::hours(1)),
+            ]),
+            executions: vec![execution(&second, start, "failed")],
+            deliveries: vec![TaskDeliveryRecord {
+                task_id: first.id.to_string(),
+                state: "failed".to_string(),
+                attempts: 2,
+                started_at: None,
+                delivered_at: None,
+                outcome: None,
+                outcome_detail: None,
+            }],
+            tasks: vec![first, second.clone()],
+        };
+
+        let summary = summarize_thread(&workspace, &activity);
+        assert_eq!(summary.workspace, "thread_a");
+        assert_eq!(summary.thread_key.as_deref(), Some("slack:T1:C1:1.0"));
+        assert_eq!(summary.epoch, 2);
+        assert_eq!(summary.messages, 2);
+        assert_eq!(summary.tasks, 2);
+        assert_eq!(summary.enabled_tasks, 2);
+        assert_eq!(summary.next_run, Some(first_run));
+        assert_eq!(
+            summary.last_execution.map(|execution| execution.task_id),
+            Some(second.id.to_string())
+        );
+        assert_eq!(summary.failed_deliveries, 1);
+    }
+}
@@ -0,0 +1,131 @@
+//! Daily digests. Accounts opt in through their digest preference; the
+//! reconciler here keeps each account's `TaskKind::Digest` task in line with
+//! that preference, and the scheduler delivers it like any other cron task.
+
+use tracing::{error, warn};
+
+use crate::account_store::{channel_to_identifier_type, AccountStore, DigestPreferenceRecord};
+use crate::channel::Channel;
+use crate::i18n::{resolve_locale, user_locale};
+use crate::index_store::IndexStore;
+use crate::user_store::UserStore;
+use crate::{DigestTask, ModuleExecutor, Scheduler};
+
+use super::config::ServiceConfig;
+use super::BoxError;
+
+/// Hour (UTC) a digest goes out when the account has not picked one.
+pub(crate) const DEFAULT_DIGEST_HOUR_UTC: i32 = 13;
+/// Channels a digest can be delivered on.
+pub(crate) const DIGEST_CHANNELS: [&str; 2] = ["email", "slack"];
+/// Digest reconciler check interval in seconds
+pub(super) const DIGEST_RECONCILE_INTERVAL_SECS: u64 = 300;
+
+/// Digests are off unless `DAILY_DIGEST_ENABLED` is set.
+pub(super) fn digests_enabled() -> bool {
+    std::env::var("DAILY_DIGEST_ENABLED")
+        .map(|value| matches!(value.trim(), "true" | "1"))
+        .unwrap_or(false)
+}
+
+fn digest_cron(hour_utc: i32) -> String {
+    format!("0 0 {} * * *", hour_utc)
+}
+
+/// Install, update or remove the digest task of every account with a stored
+/// preference. Returns how many accounts failed.
+pub(super) fn reconcile_digests(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    account_store: &AccountStore,
+) -> usize {
+    let preferences = match account_store.list_digest_preferences() {
+        Ok(preferences) => preferences,
+        Err(err) => {
+            error!("digest reconciler query failed: {}", err);
+            return 0;
+        }
+    };
+    let mut failed = 0;
+    for preference in &preferences {
+        if let Err(err) =
+            reconcile_account_digest(config, user_store, index_store, account_store, preference)
+        {
+            warn!(
+                "digest setup failed for account {}: {}",
+                preference.account_id, err
+            );
+            failed += 1;
+        }
+    }
+    failed
+}
+
+/// The digest lives in the account-level scheduler database and reads the
+/// account's own tasks plus those of every verified identifier linked to it.
+fn reconcile_account_digest(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    account_store: &AccountStore,
+    preference: &DigestPreferenceRecord,
+) -> Result<(), BoxError> {
+    let owner_id = preference.account_id.to_string();
+    let paths = user_store.user_paths(&config.users_root, &owner_id);
+    let mut scheduler = Scheduler::load(&paths.tasks_db_path, ModuleExecutor)?;
+
+    if !preference.enabled {
+        if scheduler.remove_digest()? > 0 {
+            index_store.sync_user_tasks(&owner_id, scheduler.tasks())?;
+        }
+        return Ok(());
+    }
+
+    let channel = match preference.channel.as_str() {
+        "slack" => Channel::Slack,
+        "email" => Channel::Email,
+        other => return Err(format!("unsupported digest channel {}", other).into()),
+    };
+    let mut sources = vec![paths.tasks_db_path.clone()];
+    for identifier in account_store.list_identifiers(preference.account_id)? {
+        if !identifier.verified {
+            continue;
+        }
+        let Some(user) = user_store
+            .get_user_by_identifier(&identifier.identifier_type, &identifier.identifier)?
+        else {
+            continue;
+        };
+        let tasks_db_path = user_store
+            .user_paths(&config.users_root, &user.user_id)
+            .tasks_db_path;
+        if !sources.contains(&tasks_db_path) {
+            sources.push(tasks_db_path);
+        }
+    }
+
+    // Written in the language of the user the digest goes to.
+    let employee_language = config.employee_profile.language.as_deref();
+    let locale = match user_store
+        .get_user_by_identifier(channel_to_identifier_type(&channel), &preference.identifier)?
+    {
+        Some(recipient) => user_locale(&recipient.user_id, employee_language),
+        None => resolve_locale(None, employee_language),
+    };
+
+    scheduler.ensure_digest(
+        &digest_cron(preference.hour_utc),
+        DigestTask {
+            account_id: owner_id.clone(),
+            channel,
+            recipient: preference.identifier.clone(),
+            sources,
+            output_dir: paths.state_dir.join("digests"),
+            employee_id: Some(config.employee_id.clone()),
+            language: Some(locale.code().to_string()),
+        },
+    )?;
+    index_store.sync_user_tasks(&owner_id, scheduler.tasks())?;
+    Ok(())
+}
@@ -10,6 +10,7 @@ use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::account_store::AccountStore;
+use crate::archive_crypto::ArchiveKey;
 use crate::artifact_extractor::extract_artifacts_from_email;
 use crate::channel::Channel;
 use crate::github_inbound::{
@@ -20,6 +21,7 @@ use crate::index_store::IndexStore;
 use crate::mailbox;
 use crate::notion_email_detector::{detect_notion_email, is_notion_sender};
 use crate::raw_payload_store;
+use crate::redaction::RedactionPolicy;
 use crate::user_store::{extract_emails, UserStore};
 use crate::{ModuleExecutor, RunTaskTask, Scheduler, TaskKind};
 
@@ -255,20 +257,11 @@ pub fn process_inbound_payload(
         .first()
         .cloned()
         .or_else(|| Some(inbound_service_mailbox.formatted()));
-    let model_name = match config.employee_profile.model.clone() {
-        Some(model) => model,
-        None => {
-            if config
-                .employee_profile
-                .runner
-                .eq_ignore_ascii_case("claude")
-            {
-                String::new()
-            } else {
-                config.codex_model.clone()
-            }
-        }
-    };
+    let model_name = config
+        .employee_profile
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model_for_runner(&config.employee_profile.runner));
     let thread_state_path = default_thread_state_path(&workspace);
     let message_id = payload
         .header_message_id()
@@ -297,7 +290,12 @@ pub fn process_inbound_payload(
             err
         ))
     })?;
-    if let Err(err) = archive_inbound(&user_paths, payload, raw_payload) {
+    if let Err(err) = archive_inbound(
+        &user_paths,
+        payload,
+        raw_payload,
+        config.employee_profile.redaction.as_ref(),
+    ) {
         error!("failed to archive inbound email: {}", err);
     }
     info!(
@@ -383,6 +381,8 @@ pub fn process_inbound_payload(
         requester_identifier_type: Some(requester.identifier_type.to_string()),
         requester_identifier: Some(requester.identifier.clone()),
         account_id: resolved_account_id,
+        trace_id: None,
+        scheduled: false,
     };
 
     // Clone run_task before consuming it, in case we need to write to account-level storage
@@ -659,6 +659,7 @@ fn archive_inbound(
     user_paths: &crate::user_store::UserPaths,
     payload: &PostmarkInbound,
     raw_payload: &[u8],
+    redaction: Option<&RedactionPolicy>,
 ) -> Result<(), BoxError> {
     let fallback = format!("email_{}", Utc::now().timestamp());
     let message_id = payload
@@ -675,7 +676,64 @@ fn archive_inbound(
     let incoming_attachments = mail_dir.join("incoming_attachments");
     std::fs::create_dir_all(&incoming_email)?;
     std::fs::create_dir_all(&incoming_attachments)?;
-    write_inbound_payload(payload, raw_payload, &incoming_email, &incoming_attachments)?;
+    match redaction {
+        Some(policy) => write_redacted_inbound_payload(
+            policy,
+            payload,
+            raw_payload,
+            &mail_dir,
+            &incoming_email,
+            &incoming_attachments,
+        )?,
+        None => {
+            write_inbound_payload(payload, raw_payload, &incoming_email, &incoming_attachments)?
+        }
+    }
+    if let Some(key) = ArchiveKey::for_writing(&user_paths.mail_root)? {
+        key.seal_dir(&mail_dir)?;
+    }
+    Ok(())
+}
+
+/// Archive-side counterpart of `write_inbound_payload`: content fields and
+/// text attachments are redacted, originals kept or dropped per `policy`.
+fn write_redacted_inbound_payload(
+    policy: &RedactionPolicy,
+    payload: &PostmarkInbound,
+    raw_payload: &[u8],
+    mail_dir: &Path,
+    incoming_email: &Path,
+    incoming_attachments: &Path,
+) -> Result<(), BoxError> {
+    policy.store_original(mail_dir, "postmark_payload.json", raw_payload)?;
+    let mut value: serde_json::Value = serde_json::from_slice(raw_payload)?;
+    let redactions = policy.redact_payload(&mut value);
+    std::fs::write(
+        incoming_email.join("postmark_payload.json"),
+        serde_json::to_vec(&value)?,
+    )?;
+    let redacted: PostmarkInbound = serde_json::from_value(value)?;
+    std::fs::write(
+        incoming_email.join("email.html"),
+        render_email_html(&redacted),
+    )?;
+
+    let mut withheld = 0;
+    if let Some(attachments) = payload.attachments.as_ref() {
+        for attachment in attachments {
+            let name = sanitize_token(&attachment.name, "attachment");
+            let data = resolve_attachment_bytes(attachment)?;
+            if !policy.archive_attachment(mail_dir, incoming_attachments, &name, &data)? {
+                withheld += 1;
+            }
+        }
+    }
+    info!(
+        "archived redacted inbound email at {} ({} redactions, {} attachments withheld)",
+        mail_dir.display(),
+        redactions,
+        withheld
+    );
     Ok(())
 }
 
@@ -831,7 +889,7 @@ mod tests {
     }
 
     #[test]
-    fn create_workspace_hydrates_past_emails() {
+    fn create_workspace_indexes_past_emails() {
         let temp = TempDir::new().expect("tempdir");
         let user_root = temp.path().join("user");
         let user_paths = UserPaths {
@@ -842,6 +900,7 @@ mod tests {
             secrets_dir: user_root.join("secrets"),
             mail_root: user_root.join("mail"),
             workspaces_root: user_root.join("workspaces"),
+            skills_dir: user_root.join("skills"),
         };
         fs::create_dir_all(&user_paths.mail_root).expect("mail root");
         fs::create_dir_all(&user_paths.workspaces_root).expect("workspaces root");
@@ -893,29 +952,42 @@ mod tests {
             runtime_root: None,
             agents_path: None,
             claude_path: None,
+            gemini_path: None,
             soul_path: None,
             skills_dir: None,
             discord_enabled: false,
             slack_enabled: false,
             bluebubbles_enabled: false,
+            outbound_policy: Default::default(),
+            inbound_policy: Default::default(),
+            approver: None,
+            slack_approvers: Vec::new(),
+            redaction: None,
+            archive_tiering: None,
+            sandbox: None,
+            language: None,
+            mention_names: Vec::new(),
+            router: Default::default(),
+            formatting: Default::default(),
         };
         let workspace = ensure_thread_workspace(&user_paths, "user123", &thread, &employee, None)
             .expect("create workspace");
 
         let past_root = workspace.join("references").join("past_emails");
-        let index_path = past_root.join("index.json");
-        assert!(index_path.exists(), "index.json created");
-
-        let index_data = fs::read_to_string(index_path).expect("read index");
-        let index_json: serde_json::Value = serde_json::from_str(&index_data).expect("parse index");
-        let entries = index_json["entries"].as_array().expect("entries array");
-        assert_eq!(entries.len(), 1, "one archived entry");
-        let entry_path = entries[0]["path"].as_str().expect("entry path");
-        assert!(past_root.join(entry_path).join("incoming_email").exists());
-        assert!(past_root
-            .join(entry_path)
-            .join("attachments_manifest.json")
-            .exists());
+        let index = crate::past_email_index::SearchIndex::load(
+            &past_root.join(crate::past_email_index::SEARCH_INDEX_FILE),
+        )
+        .expect("search index created");
+        assert_eq!(index.emails.len(), 1, "one archived entry");
+        let email = &index.emails[0];
+        assert_eq!(email.id, "2026/02/msg_1");
+        assert_eq!(email.subject, "Archive hello");
+        assert!(email.body.contains("Hello"));
+        assert_eq!(email.attachments, vec!["report.pdf".to_string()]);
+        assert!(
+            !past_root.join("2026").exists() && !past_root.join("index.json").exists(),
+            "archive is not copied into the workspace"
+        );
     }
 
     #[test]
@@ -40,6 +40,148 @@ fn clean_inbound_html(html: &str) -> String {
     extract_body_html(&document)
 }
 
+/// Readable text of a web page: the inbound cleaning steps plus page chrome
+/// (navigation, headers, sidebars, forms) removed, rendered as plain text
+/// with markdown headings, list items and links. Footer heuristics are left
+/// out because a page's wrapper element often contains its footer text.
+pub(crate) fn page_to_text(html: &str) -> String {
+    let document = kuchiki::parse_html().one(html);
+    remove_html_comments(&document);
+    remove_elements_by_selector(
+        &document,
+        "head, script, style, meta, link, title, noscript",
+    );
+    remove_elements_by_selector(&document, "nav, header, footer, aside, form, iframe, svg");
+    remove_hidden_elements(&document);
+    remove_tracking_pixels(&document);
+    sanitize_allowed_elements(&document);
+
+    let root = match document.select_first("body") {
+        Ok(body) => body.as_node().clone(),
+        Err(()) => document,
+    };
+    let mut out = String::new();
+    render_text(&root, false, &mut out);
+    let mut text = String::new();
+    let mut blank = true;
+    for line in out.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            if !blank {
+                text.push('\n');
+            }
+            blank = true;
+            continue;
+        }
+        text.push_str(line);
+        text.push('\n');
+        blank = false;
+    }
+    text.trim_end().to_string()
+}
+
+fn render_text(node: &NodeRef, in_pre: bool, out: &mut String) {
+    for child in node.children() {
+        if let Some(text) = child.as_text() {
+            let text = text.borrow();
+            if in_pre {
+                out.push_str(&text);
+                continue;
+            }
+            let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if collapsed.is_empty() {
+                if text.chars().next().is_some_and(char::is_whitespace) && !at_line_start(out) {
+                    out.push(' ');
+                }
+                continue;
+            }
+            if text.starts_with(char::is_whitespace) && !at_line_start(out) && !out.ends_with(' ') {
+                out.push(' ');
+            }
+            out.push_str(&collapsed);
+            if text.ends_with(char::is_whitespace) {
+                out.push(' ');
+            }
+            continue;
+        }
+        let Some(element) = child.as_element() else {
+            continue;
+        };
+        let tag = element.name.local.as_ref();
+        match tag {
+            "br" => out.push('\n'),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                out.push_str("\n\n");
+                let level = tag[1..].parse::<usize>().unwrap_or(1);
+                out.push_str(&"#".repeat(level));
+                out.push(' ');
+                render_text(&child, in_pre, out);
+                out.push_str("\n\n");
+            }
+            "li" => {
+                if !at_line_start(out) {
+                    out.push('\n');
+                }
+                out.push_str("- ");
+                render_text(&child, in_pre, out);
+                out.push('\n');
+            }
+            "pre" => {
+                out.push_str("\n\n```\n");
+                render_text(&child, true, out);
+                out.push_str("\n```\n\n");
+            }
+            "p" | "div" | "blockquote" | "ul" | "ol" | "table" => {
+                out.push_str("\n\n");
+                render_text(&child, in_pre, out);
+                out.push_str("\n\n");
+            }
+            "tr" => {
+                out.push('\n');
+                render_text(&child, in_pre, out);
+                out.push('\n');
+            }
+            "td" | "th" => {
+                render_text(&child, in_pre, out);
+                out.push_str(" | ");
+            }
+            "a" => {
+                let href = element.attributes.borrow().get("href").map(str::to_string);
+                let label_start = out.len();
+                render_text(&child, in_pre, out);
+                let label = out[label_start..].trim().to_string();
+                match href {
+                    Some(href)
+                        if !in_pre
+                            && !label.is_empty()
+                            && label != href
+                            && (href.starts_with("http://") || href.starts_with("https://")) =>
+                    {
+                        out.truncate(label_start);
+                        out.push_str(&format!("[{}]({})", label, href));
+                    }
+                    _ => {}
+                }
+            }
+            "img" => {
+                if let Some(alt) = element
+                    .attributes
+                    .borrow()
+                    .get("alt")
+                    .map(str::trim)
+                    .filter(|alt| !alt.is_empty())
+                {
+                    out.push_str(&format!("[image: {}]", alt));
+                }
+            }
+            _ => render_text(&child, in_pre, out),
+        }
+    }
+}
+
+fn at_line_start(out: &str) -> bool {
+    out.is_empty() || out.ends_with('\n')
+}
+
 fn remove_html_comments(document: &NodeRef) {
     let nodes: Vec<NodeRef> = document.descendants().collect();
     for node in nodes {
@@ -51,6 +193,8 @@ fn remove_html_comments(document: &NodeRef) {
 
 fn remove_elements_by_selector(document: &NodeRef, selector: &str) {
     if let Ok(nodes) = document.select(selector) {
+        // Collect first: detaching while selecting ends the traversal early.
+        let nodes: Vec<_> = nodes.collect();
         for node in nodes {
             node.as_node().detach();
         }
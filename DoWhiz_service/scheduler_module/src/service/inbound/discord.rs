@@ -5,15 +5,17 @@ use std::time::Duration;
 use tracing::{info, warn};
 
 use crate::account_store::AccountStore;
-use crate::channel::Channel;
+use crate::adapters::discord::DiscordOutboundAdapter;
+use crate::channel::{Channel, InboundMessage};
 use crate::index_store::IndexStore;
+use crate::scheduler::resolve_discord_bot_token_for_employee;
 use crate::user_store::UserStore;
 use crate::{ModuleExecutor, RunTaskTask, Scheduler, TaskKind};
 
 use super::super::bump_thread_state;
 use super::super::config::ServiceConfig;
 use super::super::default_thread_state_path;
-use super::super::scheduler::cancel_pending_thread_tasks;
+use super::super::scheduler::{cancel_pending_thread_tasks, inbound_coalesce_delay};
 use super::super::workspace::ensure_thread_workspace;
 use super::super::BoxError;
 use super::discord_context::{
@@ -35,13 +37,31 @@ struct DiscordRawPayloadAttachments {
     attachments: Vec<DiscordAttachmentDownloadPayload>,
 }
 
-pub(crate) fn persist_discord_ingest_context(
-    config: &ServiceConfig,
-    user_store: &UserStore,
-    message: &crate::channel::InboundMessage,
-    raw_payload: &[u8],
-    snapshot: Option<&DiscordContextSnapshot>,
-) -> Result<(), BoxError> {
+/// Set `DISCORD_AUTO_THREAD=false` to keep replying at the channel root.
+fn discord_auto_thread_enabled() -> bool {
+    match std::env::var("DISCORD_AUTO_THREAD") {
+        Ok(value) => !matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "0" | "false" | "no" | "off"
+        ),
+        Err(_) => true,
+    }
+}
+
+/// Guild-channel messages outside a thread get their own reply thread so busy
+/// servers are not flooded with top-level bot posts. DMs never do.
+fn should_start_discord_thread(message: &InboundMessage) -> bool {
+    message.metadata.discord_guild_id.is_some()
+        && message.metadata.discord_thread_id.is_none()
+        && discord_auto_thread_enabled()
+}
+
+/// Thread key used to pick the workspace for a Discord message.
+///
+/// Messages inside a thread share one workspace per thread. A guild-channel
+/// message that opens a thread is keyed by its own ID, which Discord reuses as
+/// the thread ID, so follow-ups posted in that thread land in the same workspace.
+pub(crate) fn discord_thread_key(message: &InboundMessage) -> Result<String, BoxError> {
     let channel_id = message
         .metadata
         .discord_channel_id
@@ -52,11 +72,81 @@ pub(crate) fn persist_discord_ingest_context(
         .map(|id| id.to_string())
         .unwrap_or_else(|| "dm".to_string());
 
+    if let Some(thread_id) = message.metadata.discord_thread_id {
+        return Ok(format!("discord:{}:thread:{}", guild_id, thread_id));
+    }
+    if should_start_discord_thread(message) {
+        if let Some(message_id) = message.message_id.as_deref() {
+            return Ok(format!("discord:{}:thread:{}", guild_id, message_id));
+        }
+    }
+    Ok(format!(
+        "discord:{}:{}:{}",
+        guild_id, channel_id, message.thread_id
+    ))
+}
+
+/// Channel that replies to `message` should be posted in: the originating
+/// thread, a thread opened from the message, or `channel_id` itself when
+/// threading is disabled or the thread cannot be created.
+pub(crate) fn resolve_discord_reply_channel(
+    employee_id: &str,
+    message: &InboundMessage,
+    channel_id: u64,
+) -> u64 {
+    if let Some(thread_id) = message.metadata.discord_thread_id {
+        return thread_id;
+    }
+    if !should_start_discord_thread(message) {
+        return channel_id;
+    }
+    let Some(message_id) = message
+        .message_id
+        .as_deref()
+        .and_then(|id| id.parse::<u64>().ok())
+    else {
+        return channel_id;
+    };
+    let bot_token = match resolve_discord_bot_token_for_employee(Some(employee_id)) {
+        Ok(token) => token,
+        Err(err) => {
+            warn!("discord auto-thread skipped: {}", err);
+            return channel_id;
+        }
+    };
+
+    let adapter = DiscordOutboundAdapter::new(bot_token);
+    let name = message.text_body.as_deref().unwrap_or_default();
+    match adapter.start_thread_from_message(channel_id, message_id, name) {
+        Ok(thread_id) => {
+            info!(
+                "discord reply thread ready channel={} message_id={} thread_id={}",
+                channel_id, message_id, thread_id
+            );
+            thread_id
+        }
+        Err(err) => {
+            warn!(
+                "failed to start discord thread channel={} message_id={}: {}; replying in channel",
+                channel_id, message_id, err
+            );
+            channel_id
+        }
+    }
+}
+
+pub(crate) fn persist_discord_ingest_context(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    message: &crate::channel::InboundMessage,
+    raw_payload: &[u8],
+    snapshot: Option<&DiscordContextSnapshot>,
+) -> Result<(), BoxError> {
     let user = user_store.get_or_create_user("discord", &message.sender)?;
     let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
     user_store.ensure_user_dirs(&user_paths)?;
 
-    let thread_key = format!("discord:{}:{}:{}", guild_id, channel_id, message.thread_id);
+    let thread_key = discord_thread_key(message)?;
     let workspace = ensure_thread_workspace(
         &user_paths,
         &user.user_id,
@@ -126,7 +216,7 @@ pub(crate) fn process_discord_inbound_message(
     let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
     user_store.ensure_user_dirs(&user_paths)?;
 
-    let thread_key = format!("discord:{}:{}:{}", guild_id, channel_id, message.thread_id);
+    let thread_key = discord_thread_key(message)?;
 
     let workspace = ensure_thread_workspace(
         &user_paths,
@@ -169,20 +259,13 @@ pub(crate) fn process_discord_inbound_message(
         );
     }
 
-    let model_name = match config.employee_profile.model.clone() {
-        Some(model) => model,
-        None => {
-            if config
-                .employee_profile
-                .runner
-                .eq_ignore_ascii_case("claude")
-            {
-                String::new()
-            } else {
-                config.codex_model.clone()
-            }
-        }
-    };
+    let reply_channel_id = resolve_discord_reply_channel(&config.employee_id, message, channel_id);
+
+    let model_name = config
+        .employee_profile
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model_for_runner(&config.employee_profile.runner));
 
     let run_task = RunTaskTask {
         workspace_dir: workspace.clone(),
@@ -193,8 +276,8 @@ pub(crate) fn process_discord_inbound_message(
         model_name,
         runner: config.employee_profile.runner.clone(),
         codex_disabled: config.codex_disabled,
-        // reply_to[0] = user_id (for account lookup), reply_to[1] = channel_id
-        reply_to: vec![message.sender.clone(), channel_id.to_string()],
+        // reply_to[0] = user_id (for account lookup), reply_to[1] = reply channel/thread id
+        reply_to: vec![message.sender.clone(), reply_channel_id.to_string()],
         reply_from: None,
         archive_root: Some(user_paths.mail_root.clone()),
         thread_id: Some(thread_key.clone()),
@@ -206,6 +289,8 @@ pub(crate) fn process_discord_inbound_message(
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     // Clone run_task before consuming it, in case we need to write to account-level storage
@@ -219,7 +304,13 @@ pub(crate) fn process_discord_inbound_message(
             err
         );
     }
-    let task_id = scheduler.add_one_shot_in(Duration::from_secs(0), TaskKind::RunTask(run_task))?;
+    let delay = inbound_coalesce_delay(
+        &scheduler,
+        &workspace,
+        &config.employee_profile.inbound_policy,
+        Channel::Discord,
+    );
+    let task_id = scheduler.add_one_shot_in(delay, TaskKind::RunTask(run_task))?;
 
     index_store.sync_user_tasks(&user.user_id, scheduler.tasks())?;
 
@@ -249,7 +340,7 @@ pub(crate) fn process_discord_inbound_message(
                     // Use the same task_id so we can update status at completion
                     match user_scheduler.add_one_shot_in_with_id(
                         task_id,
-                        Duration::from_secs(0),
+                        delay,
                         TaskKind::RunTask(run_task_for_account),
                     ) {
                         Ok(()) => {
@@ -588,11 +679,23 @@ mod tests {
             runtime_root: None,
             agents_path: None,
             claude_path: None,
+            gemini_path: None,
             soul_path: None,
             skills_dir: None,
             discord_enabled: false,
             slack_enabled: false,
             bluebubbles_enabled: false,
+            outbound_policy: Default::default(),
+            inbound_policy: Default::default(),
+            approver: None,
+            slack_approvers: Vec::new(),
+            redaction: None,
+            archive_tiering: None,
+            sandbox: None,
+            language: None,
+            mention_names: Vec::new(),
+            router: Default::default(),
+            formatting: Default::default(),
         };
         let mut employee_by_id = HashMap::new();
         employee_by_id.insert(employee.id.clone(), employee.clone());
@@ -619,6 +722,7 @@ mod tests {
             users_db_path: state_root.join("users.db"),
             task_index_path: state_root.join("task_index.db"),
             codex_model: "gpt-5.4".to_string(),
+            gemini_model: "gemini-2.5-pro".to_string(),
             codex_disabled: true,
             scheduler_poll_interval: Duration::from_millis(50),
             scheduler_max_concurrency: 1,
@@ -688,6 +792,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn discord_thread_key_follows_threads_and_keeps_dms_per_message() -> Result<(), BoxError> {
+        let message = |guild_id: Option<u64>, thread_id: Option<u64>| InboundMessage {
+            channel: Channel::Discord,
+            sender: "12345".to_string(),
+            sender_name: None,
+            recipient: "67890".to_string(),
+            subject: None,
+            text_body: Some("Hello".to_string()),
+            html_body: None,
+            thread_id: "555".to_string(),
+            message_id: Some("555".to_string()),
+            attachments: Vec::new(),
+            reply_to: vec!["12345".to_string(), "67890".to_string()],
+            raw_payload: Vec::new(),
+            metadata: ChannelMetadata {
+                discord_guild_id: guild_id,
+                discord_channel_id: Some(thread_id.unwrap_or(67890)),
+                discord_thread_id: thread_id,
+                ..Default::default()
+            },
+        };
+
+        // A guild-channel message opens a thread that reuses its ID, and a later
+        // message inside that thread maps to the same workspace.
+        assert_eq!(
+            discord_thread_key(&message(Some(111), None))?,
+            "discord:111:thread:555"
+        );
+        let in_thread = message(Some(111), Some(555));
+        assert_eq!(discord_thread_key(&in_thread)?, "discord:111:thread:555");
+        assert_eq!(
+            resolve_discord_reply_channel("test-employee", &in_thread, 555),
+            555
+        );
+
+        let dm = message(None, None);
+        assert_eq!(discord_thread_key(&dm)?, "discord:dm:67890:555");
+        assert_eq!(
+            resolve_discord_reply_channel("test-employee", &dm, 67890),
+            67890
+        );
+        Ok(())
+    }
+
     #[test]
     fn hydrate_discord_attachments_downloads_attachments() -> Result<(), BoxError> {
         let temp = TempDir::new()?;
@@ -0,0 +1,98 @@
+//! "Always run the full agent for me" messages, handled without a model.
+//!
+//! Users who find the quick replies too shallow can ask for the full agent
+//! on every message; the quick-response router is then skipped for them
+//! until they ask for quick replies again.
+
+use tracing::{info, warn};
+
+use crate::message_router::RouterDecision;
+use crate::user_store::{UserPref, UserStore};
+
+/// Whole messages that turn the override on, after lowercasing and dropping
+/// punctuation.
+const FULL_AGENT_PHRASES: &[&str] = &[
+    "always run the full agent",
+    "always run the full agent for me",
+    "always use the full agent",
+    "always use the full agent for me",
+    "no quick replies",
+    "no more quick replies",
+    "stop quick replies",
+];
+
+/// Whole messages that turn it off again.
+const QUICK_REPLY_PHRASES: &[&str] = &[
+    "quick replies are fine",
+    "use quick replies again",
+    "quick replies again",
+    "allow quick replies",
+];
+
+fn normalize(text: &str) -> String {
+    let normalized = text
+        .chars()
+        .filter(|ch| ch.is_alphanumeric() || ch.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    normalized.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `Some(true)` when `text` asks for the full agent, `Some(false)` when it
+/// asks for quick replies again.
+fn requested_override(text: &str) -> Option<bool> {
+    let normalized = normalize(text);
+    if FULL_AGENT_PHRASES.contains(&normalized.as_str()) {
+        Some(true)
+    } else if QUICK_REPLY_PHRASES.contains(&normalized.as_str()) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Forward `text` to the full agent when `user_id` asked for it, now or
+/// earlier. Returns `None` when the router should decide as usual.
+pub(super) fn forward_for_full_agent(
+    user_store: &UserStore,
+    user_id: &str,
+    text: &str,
+) -> Option<RouterDecision> {
+    let always = match requested_override(text) {
+        Some(always) => {
+            match user_store.set_pref(user_id, UserPref::AlwaysFullAgent(always)) {
+                Ok(_) => info!("user {} set always_full_agent={}", user_id, always),
+                Err(err) => warn!("failed to set full agent override for {}: {}", user_id, err),
+            }
+            always
+        }
+        None => match user_store.get_pref(user_id) {
+            Ok(preferences) => preferences.always_full_agent,
+            Err(err) => {
+                warn!("failed to load preferences for user {}: {}", user_id, err);
+                false
+            }
+        },
+    };
+    always.then_some(RouterDecision::Complex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::requested_override;
+
+    #[test]
+    fn only_whole_override_messages_change_the_override() {
+        assert_eq!(
+            requested_override("Always run the full agent for me!"),
+            Some(true)
+        );
+        assert_eq!(requested_override("  no quick   replies. "), Some(true));
+        assert_eq!(requested_override("Quick replies are fine"), Some(false));
+        assert_eq!(
+            requested_override("can you always run the full agent on the report?"),
+            None
+        );
+        assert_eq!(requested_override("hello"), None);
+    }
+}
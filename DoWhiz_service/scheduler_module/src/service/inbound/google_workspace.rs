@@ -196,20 +196,11 @@ pub(crate) fn process_google_workspace_message(
         }
     }
 
-    let model_name = match config.employee_profile.model.clone() {
-        Some(model) => model,
-        None => {
-            if config
-                .employee_profile
-                .runner
-                .eq_ignore_ascii_case("claude")
-            {
-                String::new()
-            } else {
-                config.codex_model.clone()
-            }
-        }
-    };
+    let model_name = config
+        .employee_profile
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model_for_runner(&config.employee_profile.runner));
 
     let run_task = RunTaskTask {
         workspace_dir: workspace.clone(),
@@ -232,6 +223,8 @@ pub(crate) fn process_google_workspace_message(
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     // Clone run_task before consuming it, in case we need to write to account-level storage
@@ -574,11 +567,23 @@ mod tests {
             runtime_root: None,
             agents_path: None,
             claude_path: None,
+            gemini_path: None,
             soul_path: None,
             skills_dir: None,
             discord_enabled: false,
             slack_enabled: false,
             bluebubbles_enabled: false,
+            outbound_policy: Default::default(),
+            inbound_policy: Default::default(),
+            approver: None,
+            slack_approvers: Vec::new(),
+            redaction: None,
+            archive_tiering: None,
+            sandbox: None,
+            language: None,
+            mention_names: Vec::new(),
+            router: Default::default(),
+            formatting: Default::default(),
         };
         let mut employee_by_id = HashMap::new();
         employee_by_id.insert(employee.id.clone(), employee.clone());
@@ -605,6 +610,7 @@ mod tests {
             users_db_path: state_root.join("users.db"),
             task_index_path: state_root.join("task_index.db"),
             codex_model: "gpt-5.4".to_string(),
+            gemini_model: "gemini-2.5-pro".to_string(),
             codex_disabled: true,
             scheduler_poll_interval: Duration::from_millis(50),
             scheduler_max_concurrency: 1,
@@ -719,7 +725,7 @@ mod tests {
 
         // Verify files were written using thread_state seq
         let state_path = crate::thread_state::default_thread_state_path(&run_task.workspace_dir);
-        let thread_state = crate::thread_state::load_thread_state(&state_path)
+        let thread_state = crate::thread_state::load_thread_state(&state_path)?
             .ok_or("thread_state.json not found")?;
         let seq = thread_state.last_email_seq;
 
@@ -0,0 +1,257 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Utc;
+use tracing::{info, warn};
+
+use crate::channel::{Channel, InboundMessage};
+use crate::health_probe;
+use crate::index_store::IndexStore;
+use crate::internal_bus::{
+    self, InternalCorrelation, InternalMessageKind, PendingDelegation, CORRELATION_FILE,
+};
+use crate::user_store::UserStore;
+use crate::{ModuleExecutor, RunTaskTask, Scheduler, TaskKind};
+
+use super::super::bump_thread_state;
+use super::super::config::ServiceConfig;
+use super::super::default_thread_state_path;
+use super::super::scheduler::cancel_pending_thread_tasks;
+use super::super::workspace::ensure_thread_workspace;
+use super::super::BoxError;
+
+/// A request from another employee starts a thread of its own; a result
+/// resumes the thread that delegated it.
+pub(crate) fn process_internal_message(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    message: &InboundMessage,
+) -> Result<(), BoxError> {
+    let Some(correlation) = message.metadata.internal_correlation.clone() else {
+        warn!(
+            "internal message from {} has no correlation",
+            message.sender
+        );
+        return Ok(());
+    };
+    match correlation.kind {
+        InternalMessageKind::Request => {
+            process_internal_request(config, user_store, index_store, message, &correlation)
+        }
+        InternalMessageKind::Result => {
+            process_internal_result(config, user_store, index_store, message, &correlation)
+        }
+    }
+}
+
+/// The requesting employee is the user of the delegate's thread. Health
+/// probes share one thread and run without the runner.
+fn process_internal_request(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    message: &InboundMessage,
+    correlation: &InternalCorrelation,
+) -> Result<(), BoxError> {
+    if message.sender != correlation.requester_employee_id {
+        warn!(
+            "internal request {} sent by {} on behalf of {}; ignoring",
+            correlation.correlation_id, message.sender, correlation.requester_employee_id
+        );
+        return Ok(());
+    }
+    if correlation.probe && message.sender != config.employee_id {
+        warn!(
+            "health probe {} sent by another employee {}; ignoring",
+            correlation.correlation_id, message.sender
+        );
+        return Ok(());
+    }
+    let user = user_store.get_or_create_user("employee", &message.sender)?;
+    let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
+    user_store.ensure_user_dirs(&user_paths)?;
+
+    let thread_key = if correlation.probe {
+        format!("internal:{}:probe", correlation.requester_employee_id)
+    } else {
+        format!(
+            "internal:{}:{}",
+            correlation.requester_employee_id, correlation.correlation_id
+        )
+    };
+    let workspace = ensure_thread_workspace(
+        &user_paths,
+        &user.user_id,
+        &thread_key,
+        &config.employee_profile,
+        config.skills_source_dir.as_deref(),
+    )?;
+    let thread_state_path = default_thread_state_path(&workspace);
+    let thread_state = bump_thread_state(&thread_state_path, &thread_key, None)?;
+    std::fs::write(
+        workspace.join(CORRELATION_FILE),
+        serde_json::to_vec_pretty(correlation)?,
+    )?;
+    append_internal_message(
+        &workspace.join("incoming_email"),
+        &format!("{:04}_internal_message.txt", thread_state.last_email_seq),
+        &format!("employee {} (delegated request)", message.sender),
+        message,
+    )?;
+    internal_bus::write_attachments(
+        &workspace.join("incoming_attachments"),
+        &message.attachments,
+    )?;
+
+    let model_name = config
+        .employee_profile
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model_for_runner(&config.employee_profile.runner));
+    let run_task = RunTaskTask {
+        workspace_dir: workspace.clone(),
+        input_email_dir: PathBuf::from("incoming_email"),
+        input_attachments_dir: PathBuf::from("incoming_attachments"),
+        memory_dir: PathBuf::from("memory"),
+        reference_dir: PathBuf::from("references"),
+        model_name,
+        runner: config.employee_profile.runner.clone(),
+        codex_disabled: config.codex_disabled || correlation.probe,
+        reply_to: vec![message.sender.clone()],
+        reply_from: None,
+        archive_root: Some(user_paths.mail_root.clone()),
+        thread_id: Some(thread_key.clone()),
+        thread_epoch: Some(thread_state.epoch),
+        thread_state_path: Some(thread_state_path),
+        channel: Channel::Internal,
+        slack_team_id: None,
+        employee_id: Some(config.employee_profile.id.clone()),
+        requester_identifier_type: None,
+        requester_identifier: None,
+        account_id: None,
+        trace_id: None,
+        scheduled: false,
+    };
+
+    let mut scheduler = Scheduler::load(&user_paths.tasks_db_path, ModuleExecutor)?;
+    let task_id = scheduler.add_one_shot_in(Duration::from_secs(0), TaskKind::RunTask(run_task))?;
+    index_store.sync_user_tasks(&user.user_id, scheduler.tasks())?;
+    info!(
+        "internal request {} from {} scheduled task_id={} workspace={}",
+        correlation.correlation_id,
+        message.sender,
+        task_id,
+        workspace.display()
+    );
+    Ok(())
+}
+
+/// Repeat the parked run_task on its thread with the result as the newest
+/// message. A probe's result only completes the probe.
+fn process_internal_result(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    message: &InboundMessage,
+    correlation: &InternalCorrelation,
+) -> Result<(), BoxError> {
+    if correlation.probe {
+        let state_path = health_probe::state_path(&config.scheduler_state_path);
+        if message.sender != config.employee_id
+            || health_probe::record_reply(&state_path, correlation.correlation_id, Utc::now())?
+                .is_none()
+        {
+            info!(
+                "health probe result {} from {} is not pending; ignoring",
+                correlation.correlation_id, message.sender
+            );
+        }
+        return Ok(());
+    }
+    let user_paths = user_store.user_paths(&config.users_root, &correlation.requester_user_id);
+    let Some(PendingDelegation {
+        delegate_employee_id,
+        request,
+        task,
+        ..
+    }) = internal_bus::take_pending(&user_paths.state_dir, correlation.correlation_id)?
+    else {
+        info!(
+            "internal result {} has no pending delegation; ignoring",
+            correlation.correlation_id
+        );
+        return Ok(());
+    };
+    if message.sender != delegate_employee_id {
+        warn!(
+            "internal result {} came from {} instead of {}; ignoring",
+            correlation.correlation_id, message.sender, delegate_employee_id
+        );
+        return Ok(());
+    }
+
+    let mut task = task;
+    let workspace = task.workspace_dir.clone();
+    let thread_state_path = task
+        .thread_state_path
+        .clone()
+        .unwrap_or_else(|| default_thread_state_path(&workspace));
+    let thread_key = task
+        .thread_id
+        .clone()
+        .unwrap_or_else(|| workspace.display().to_string());
+    let thread_state = bump_thread_state(&thread_state_path, &thread_key, None)?;
+    append_internal_message(
+        &workspace.join(&task.input_email_dir),
+        &format!("{:04}_internal_result.txt", thread_state.last_email_seq),
+        &format!(
+            "employee {} (result of your request: {})",
+            message.sender, request
+        ),
+        message,
+    )?;
+    internal_bus::write_attachments(
+        &workspace.join(&task.input_attachments_dir),
+        &message.attachments,
+    )?;
+    task.thread_epoch = Some(thread_state.epoch);
+    task.thread_state_path = Some(thread_state_path);
+    task.trace_id = None;
+
+    let mut scheduler = Scheduler::load(&user_paths.tasks_db_path, ModuleExecutor)?;
+    if let Err(err) = cancel_pending_thread_tasks(&mut scheduler, &workspace, thread_state.epoch) {
+        warn!(
+            "failed to cancel pending thread tasks for {}: {}",
+            workspace.display(),
+            err
+        );
+    }
+    let task_id = scheduler.add_one_shot_in(Duration::from_secs(0), TaskKind::RunTask(task))?;
+    index_store.sync_user_tasks(&correlation.requester_user_id, scheduler.tasks())?;
+    info!(
+        "internal result {} from {} resumed task_id={} workspace={}",
+        correlation.correlation_id,
+        message.sender,
+        task_id,
+        workspace.display()
+    );
+    Ok(())
+}
+
+fn append_internal_message(
+    incoming_dir: &Path,
+    filename: &str,
+    from: &str,
+    message: &InboundMessage,
+) -> Result<(), BoxError> {
+    std::fs::create_dir_all(incoming_dir)?;
+    let content = format!(
+        "From: {}\nDate: {}\n\n{}",
+        from,
+        Utc::now().to_rfc3339(),
+        message.text_body.as_deref().unwrap_or_default()
+    );
+    std::fs::write(incoming_dir.join(filename), content)?;
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use std::path::Path;
+
+use tracing::{info, warn};
+
+use crate::channel::Channel;
+use crate::index_store::IndexStore;
+use crate::user_store::UserStore;
+use crate::{ModuleExecutor, RunTaskTask, Scheduler, TaskKind};
+
+use super::super::bump_thread_state;
+use super::super::config::ServiceConfig;
+use super::super::default_thread_state_path;
+use super::super::scheduler::{cancel_pending_thread_tasks, inbound_coalesce_delay};
+use super::super::workspace::ensure_thread_workspace;
+use super::super::BoxError;
+
+pub(crate) fn process_jira_event(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    message: &crate::channel::InboundMessage,
+    raw_payload: &[u8],
+) -> Result<(), BoxError> {
+    let issue_key = message
+        .metadata
+        .jira_issue_key
+        .as_deref()
+        .ok_or("jira message without issue key")?;
+    info!(
+        "processing Jira event on {} from {} ({:?})",
+        issue_key, message.sender, message.sender_name
+    );
+
+    let user = user_store.get_or_create_user("jira", &message.sender)?;
+    let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
+    user_store.ensure_user_dirs(&user_paths)?;
+
+    // One thread per issue, so follow-up comments land in the same workspace.
+    let thread_key = message.thread_id.clone();
+    let workspace = ensure_thread_workspace(
+        &user_paths,
+        &user.user_id,
+        &thread_key,
+        &config.employee_profile,
+        config.skills_source_dir.as_deref(),
+    )?;
+
+    let thread_state_path = default_thread_state_path(&workspace);
+    let thread_state =
+        bump_thread_state(&thread_state_path, &thread_key, message.message_id.clone())?;
+
+    append_jira_message(
+        &workspace,
+        message,
+        raw_payload,
+        thread_state.last_email_seq.try_into().unwrap_or(u32::MAX),
+    )?;
+
+    let model_name = config
+        .employee_profile
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model_for_runner(&config.employee_profile.runner));
+
+    info!(
+        "workspace ready at {} for user {} thread={} epoch={}",
+        workspace.display(),
+        user.user_id,
+        thread_key,
+        thread_state.epoch
+    );
+
+    // The reply is posted as a comment on the issue.
+    let run_task = RunTaskTask {
+        workspace_dir: workspace.clone(),
+        input_email_dir: std::path::PathBuf::from("incoming_email"),
+        input_attachments_dir: std::path::PathBuf::from("incoming_attachments"),
+        memory_dir: std::path::PathBuf::from("memory"),
+        reference_dir: std::path::PathBuf::from("references"),
+        model_name,
+        runner: config.employee_profile.runner.clone(),
+        codex_disabled: config.codex_disabled,
+        reply_to: vec![issue_key.to_string()],
+        reply_from: None,
+        archive_root: Some(user_paths.mail_root.clone()),
+        thread_id: Some(thread_key.clone()),
+        thread_epoch: Some(thread_state.epoch),
+        thread_state_path: Some(thread_state_path.clone()),
+        channel: Channel::Jira,
+        slack_team_id: None,
+        employee_id: Some(config.employee_profile.id.clone()),
+        requester_identifier_type: None,
+        requester_identifier: None,
+        account_id: None,
+        trace_id: None,
+        scheduled: false,
+    };
+
+    let mut scheduler = Scheduler::load(&user_paths.tasks_db_path, ModuleExecutor)?;
+    if let Err(err) = cancel_pending_thread_tasks(&mut scheduler, &workspace, thread_state.epoch) {
+        warn!(
+            "failed to cancel pending thread tasks for {}: {}",
+            workspace.display(),
+            err
+        );
+    }
+    let delay = inbound_coalesce_delay(
+        &scheduler,
+        &workspace,
+        &config.employee_profile.inbound_policy,
+        Channel::Jira,
+    );
+    let task_id = scheduler.add_one_shot_in(delay, TaskKind::RunTask(run_task))?;
+    index_store.sync_user_tasks(&user.user_id, scheduler.tasks())?;
+
+    info!(
+        "scheduler tasks enqueued user_id={} task_id={} message_id={:?} workspace={} thread_epoch={}",
+        user.user_id,
+        task_id,
+        message.message_id,
+        workspace.display(),
+        thread_state.epoch
+    );
+
+    Ok(())
+}
+
+/// Append a Jira event to the workspace inbox and record the issue the
+/// reply goes to in `jira_metadata.json`.
+fn append_jira_message(
+    workspace: &Path,
+    message: &crate::channel::InboundMessage,
+    raw_payload: &[u8],
+    seq: u32,
+) -> Result<(), BoxError> {
+    let incoming_dir = workspace.join("incoming_email");
+    std::fs::create_dir_all(&incoming_dir)?;
+
+    std::fs::write(
+        incoming_dir.join(format!("{:04}_jira.json", seq)),
+        raw_payload,
+    )?;
+
+    let metadata = &message.metadata;
+    let content = format!(
+        "From: {}\nIssue: {}\nLink: {}\nDate: {}\n\n{}",
+        message.sender_name.as_deref().unwrap_or(&message.sender),
+        message.subject.as_deref().unwrap_or_default(),
+        metadata.jira_issue_url.as_deref().unwrap_or_default(),
+        chrono::Utc::now().to_rfc3339(),
+        message.text_body.as_deref().unwrap_or_default()
+    );
+    std::fs::write(incoming_dir.join(format!("{:04}_jira.txt", seq)), content)?;
+
+    let context = serde_json::json!({
+        "issue_key": metadata.jira_issue_key,
+        "issue_url": metadata.jira_issue_url,
+        "comment_id": metadata.jira_comment_id,
+        "subject": message.subject,
+    });
+    std::fs::write(
+        incoming_dir.join("jira_metadata.json"),
+        serde_json::to_string_pretty(&context)?,
+    )?;
+    Ok(())
+}
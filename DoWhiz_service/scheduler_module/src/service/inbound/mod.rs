@@ -1,24 +1,37 @@
 mod bluebubbles;
 mod discord;
 mod discord_context;
+mod full_agent_requests;
 mod google_workspace;
+mod internal;
+mod jira;
 mod notion;
 mod notion_email;
+mod quick_history;
 mod quick_responses;
+mod quick_stream;
+mod reactions;
 mod slack;
+mod slack_commands;
+mod slack_interactions;
 mod sms;
+mod stop_requests;
 mod telegram;
 mod wechat;
 mod whatsapp;
 
 pub(super) use bluebubbles::process_bluebubbles_event;
+pub(crate) use discord::discord_thread_key;
 pub(crate) use discord::hydrate_discord_attachments;
 pub(crate) use discord::persist_discord_ingest_context;
 pub(super) use discord::process_discord_inbound_message;
+pub(crate) use discord::resolve_discord_reply_channel;
 pub(crate) use discord_context::build_discord_message_text_with_quote;
 pub(crate) use discord_context::build_discord_router_context;
 pub(crate) use discord_context::hydrate_discord_context_files;
 pub(super) use google_workspace::process_google_workspace_message;
+pub(super) use internal::process_internal_message;
+pub(super) use jira::process_jira_event;
 pub(super) use notion::process_notion_message;
 pub(super) use notion_email::process_notion_email;
 pub(super) use quick_responses::{
@@ -26,7 +39,10 @@ pub(super) use quick_responses::{
     try_quick_response_google_workspace, try_quick_response_slack, try_quick_response_telegram,
     try_quick_response_wechat, try_quick_response_whatsapp,
 };
+pub(super) use reactions::process_chat_reaction;
 pub(super) use slack::process_slack_event;
+pub(super) use slack_commands::process_slack_slash_command;
+pub(super) use slack_interactions::process_slack_interaction;
 pub(super) use sms::process_sms_message;
 pub(super) use telegram::process_telegram_event;
 pub(super) use wechat::process_wechat_event;
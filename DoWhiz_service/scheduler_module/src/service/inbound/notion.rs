@@ -173,20 +173,11 @@ pub(crate) fn process_notion_message(
     }
 
     // Determine model
-    let model_name = match config.employee_profile.model.clone() {
-        Some(model) => model,
-        None => {
-            if config
-                .employee_profile
-                .runner
-                .eq_ignore_ascii_case("claude")
-            {
-                String::new()
-            } else {
-                config.codex_model.clone()
-            }
-        }
-    };
+    let model_name = config
+        .employee_profile
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model_for_runner(&config.employee_profile.runner));
 
     // Get account_id from linked account if available
     let resolved_account_id = notion_linked_account
@@ -215,6 +206,8 @@ pub(crate) fn process_notion_message(
         requester_identifier_type: Some("notion_user".to_string()),
         requester_identifier: Some(user_email.clone()),
         account_id: resolved_account_id,
+        trace_id: None,
+        scheduled: false,
     };
 
     let run_task_for_account = run_task.clone();
@@ -335,20 +335,11 @@ pub(crate) fn process_notion_email(
     )?;
 
     // Determine model
-    let model_name = match config.employee_profile.model.clone() {
-        Some(model) => model,
-        None => {
-            if config
-                .employee_profile
-                .runner
-                .eq_ignore_ascii_case("claude")
-            {
-                String::new()
-            } else {
-                config.codex_model.clone()
-            }
-        }
-    };
+    let model_name = config
+        .employee_profile
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model_for_runner(&config.employee_profile.runner));
 
     // Create RunTask with Notion channel
     // Use account_id from NotionCredential if available
@@ -373,6 +364,8 @@ pub(crate) fn process_notion_email(
         requester_identifier_type: Some("notion_actor".to_string()),
         requester_identifier: Some(notion_identifier.clone()),
         account_id: credential_account_id,
+        trace_id: None,
+        scheduled: false,
     };
 
     let run_task_for_account = run_task.clone();
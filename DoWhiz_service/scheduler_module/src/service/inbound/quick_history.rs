@@ -0,0 +1,266 @@
+//! Recent quick exchanges per thread, fed back to the router.
+//!
+//! Quick replies are otherwise one-shots, so a follow-up like "3pm works"
+//! after "what time suits you?" reads as a new request and starts a run. The
+//! last `QUICK_HISTORY_TURNS` (default 4) quick exchanges of a thread, no
+//! older than `QUICK_HISTORY_TTL_SECS` (default 30 minutes), are passed to
+//! the router as conversation context. A message forwarded to the agent
+//! clears the thread's history: the router never sees the agent's reply, so
+//! older quick exchanges would no longer describe the conversation.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::super::BoxError;
+
+const QUICK_HISTORY_FILE: &str = "quick_history.json";
+const QUICK_HISTORY_MAX_THREADS: usize = 512;
+const DEFAULT_TURNS: usize = 4;
+const MAX_TURNS: usize = 20;
+const DEFAULT_TTL_SECS: i64 = 30 * 60;
+/// Longest message or reply kept per exchange, in characters
+const MAX_TEXT_CHARS: usize = 500;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QuickHistoryStore {
+    #[serde(default)]
+    threads: HashMap<String, QuickHistoryThread>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QuickHistoryThread {
+    #[serde(default)]
+    exchanges: Vec<QuickExchange>,
+    #[serde(default)]
+    updated_at_unix_secs: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct QuickExchange {
+    message: String,
+    reply: String,
+    at_unix_secs: i64,
+}
+
+/// `QUICK_HISTORY_TURNS`, at most 20; 0 turns the history off.
+fn history_turns() -> usize {
+    std::env::var("QUICK_HISTORY_TURNS")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_TURNS)
+        .min(MAX_TURNS)
+}
+
+fn history_ttl_secs() -> i64 {
+    std::env::var("QUICK_HISTORY_TTL_SECS")
+        .ok()
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_TTL_SECS)
+}
+
+fn now_unix_secs() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn quick_history_path(state_dir: &Path) -> PathBuf {
+    state_dir.join(QUICK_HISTORY_FILE)
+}
+
+/// The thread's recent quick exchanges as router context, oldest first.
+pub(super) fn router_context(state_dir: &Path, thread_key: &str) -> Option<String> {
+    let turns = history_turns();
+    if turns == 0 {
+        return None;
+    }
+    let store = load_quick_history_store(&quick_history_path(state_dir));
+    let thread = store.threads.get(thread_key)?;
+    render_exchanges(
+        &thread.exchanges,
+        turns,
+        now_unix_secs() - history_ttl_secs(),
+    )
+}
+
+fn render_exchanges(exchanges: &[QuickExchange], turns: usize, since: i64) -> Option<String> {
+    let recent = exchanges
+        .iter()
+        .filter(|exchange| exchange.at_unix_secs >= since)
+        .collect::<Vec<_>>();
+    if recent.is_empty() {
+        return None;
+    }
+    let mut lines = vec!["Your recent quick replies in this conversation:".to_string()];
+    for exchange in &recent[recent.len().saturating_sub(turns)..] {
+        lines.push(format!("User: {}", exchange.message));
+        lines.push(format!("You: {}", exchange.reply));
+    }
+    Some(lines.join("\n"))
+}
+
+/// Remember that `reply` answered `message` in the thread. Best effort.
+pub(super) fn remember(state_dir: &Path, thread_key: &str, message: &str, reply: &str) {
+    let turns = history_turns();
+    if turns == 0 {
+        return;
+    }
+    let path = quick_history_path(state_dir);
+    let mut store = load_quick_history_store(&path);
+    let now = now_unix_secs();
+    let thread = store.threads.entry(thread_key.to_string()).or_default();
+    thread.exchanges.push(QuickExchange {
+        message: truncate_chars(message.trim()),
+        reply: truncate_chars(reply.trim()),
+        at_unix_secs: now,
+    });
+    let overflow = thread.exchanges.len().saturating_sub(turns);
+    thread.exchanges.drain(0..overflow);
+    thread.updated_at_unix_secs = now;
+    prune_quick_history_store(&mut store);
+    if let Err(err) = write_quick_history_store(&path, &store) {
+        warn!(
+            "failed to record quick exchange thread={}: {}",
+            thread_key, err
+        );
+    }
+}
+
+/// Drop the thread's history once a message goes to the agent.
+pub(super) fn forget(state_dir: &Path, thread_key: &str) {
+    let path = quick_history_path(state_dir);
+    let mut store = load_quick_history_store(&path);
+    if store.threads.remove(thread_key).is_none() {
+        return;
+    }
+    if let Err(err) = write_quick_history_store(&path, &store) {
+        warn!(
+            "failed to clear quick exchanges thread={}: {}",
+            thread_key, err
+        );
+    }
+}
+
+fn truncate_chars(text: &str) -> String {
+    match text.char_indices().nth(MAX_TEXT_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn load_quick_history_store(path: &Path) -> QuickHistoryStore {
+    let Ok(raw) = std::fs::read(path) else {
+        return QuickHistoryStore::default();
+    };
+    match serde_json::from_slice::<QuickHistoryStore>(&raw) {
+        Ok(store) => store,
+        Err(err) => {
+            warn!(
+                "failed to parse quick history store at {}: {}",
+                path.display(),
+                err
+            );
+            QuickHistoryStore::default()
+        }
+    }
+}
+
+fn write_quick_history_store(path: &Path, store: &QuickHistoryStore) -> Result<(), BoxError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension(format!("tmp-{}", Uuid::new_v4()));
+    let serialized = serde_json::to_vec_pretty(store)?;
+    std::fs::write(&tmp, serialized)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn prune_quick_history_store(store: &mut QuickHistoryStore) {
+    let expired_before = now_unix_secs() - history_ttl_secs();
+    store
+        .threads
+        .retain(|_, thread| thread.updated_at_unix_secs >= expired_before);
+    if store.threads.len() <= QUICK_HISTORY_MAX_THREADS {
+        return;
+    }
+    let mut thread_by_age = store
+        .threads
+        .iter()
+        .map(|(key, value)| (key.clone(), value.updated_at_unix_secs))
+        .collect::<Vec<_>>();
+    thread_by_age.sort_by_key(|(_, updated_at)| *updated_at);
+    let overflow = store
+        .threads
+        .len()
+        .saturating_sub(QUICK_HISTORY_MAX_THREADS);
+    for (key, _) in thread_by_age.into_iter().take(overflow) {
+        store.threads.remove(&key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn exchanges_are_remembered_capped_and_forgotten() {
+        let temp = TempDir::new().unwrap();
+        let state_dir = temp.path().join("state");
+        let thread = "telegram:42";
+        assert_eq!(router_context(&state_dir, thread), None);
+
+        remember(&state_dir, thread, "hi", "Hello!");
+        remember(&state_dir, thread, "what time suits you?", "How about 3pm?");
+        assert_eq!(
+            router_context(&state_dir, thread).unwrap(),
+            "Your recent quick replies in this conversation:\n\
+             User: hi\nYou: Hello!\n\
+             User: what time suits you?\nYou: How about 3pm?"
+        );
+        assert_eq!(router_context(&state_dir, "telegram:7"), None);
+
+        for index in 0..DEFAULT_TURNS + 2 {
+            remember(&state_dir, thread, &format!("q{}", index), "a");
+        }
+        let store = load_quick_history_store(&quick_history_path(&state_dir));
+        let exchanges = &store.threads[thread].exchanges;
+        assert_eq!(exchanges.len(), DEFAULT_TURNS);
+        assert_eq!(exchanges[0].message, "q2");
+
+        forget(&state_dir, thread);
+        assert_eq!(router_context(&state_dir, thread), None);
+    }
+
+    #[test]
+    fn expired_exchanges_are_left_out() {
+        let exchange = |message: &str, at_unix_secs| QuickExchange {
+            message: message.to_string(),
+            reply: "ok".to_string(),
+            at_unix_secs,
+        };
+        let exchanges = [exchange("old", 100), exchange("new", 200)];
+        assert_eq!(
+            render_exchanges(&exchanges, 4, 150).unwrap(),
+            "Your recent quick replies in this conversation:\nUser: new\nYou: ok"
+        );
+        assert_eq!(render_exchanges(&exchanges, 4, 300), None);
+        assert_eq!(
+            render_exchanges(&exchanges, 1, 0).unwrap(),
+            "Your recent quick replies in this conversation:\nUser: new\nYou: ok"
+        );
+    }
+
+    #[test]
+    fn long_texts_are_truncated() {
+        let long = "x".repeat(MAX_TEXT_CHARS + 10);
+        let truncated = truncate_chars(&long);
+        assert_eq!(truncated.chars().count(), MAX_TEXT_CHARS + 3);
+        assert!(truncated.ends_with("..."));
+        assert_eq!(truncate_chars("short"), "short");
+    }
+}
@@ -6,25 +6,34 @@ use tracing::{info, warn};
 
 use crate::account_store::lookup_account_by_channel;
 use crate::adapters::bluebubbles::send_quick_bluebubbles_response;
+use crate::adapters::discord::DiscordOutboundAdapter;
 use crate::adapters::google_common::GoogleCommentsClient;
+use crate::adapters::slack::SlackOutboundAdapter;
 use crate::adapters::telegram::send_quick_telegram_response;
 use crate::adapters::wechat::WeChatOutboundAdapter;
 use crate::adapters::whatsapp::send_quick_whatsapp_response;
-use crate::channel::OutboundMessage;
+use crate::channel::{ChannelMetadata, OutboundAdapter, OutboundMessage};
 use crate::blob_store::get_blob_store;
 use crate::channel::Channel;
 use crate::google_auth::{GoogleAuth, GoogleAuthConfig};
+use crate::i18n::{user_locale, Message};
 use crate::memory_diff::{MemoryDiff, SectionChange};
 use crate::memory_queue::{global_memory_queue, MemoryWriteRequest};
 use crate::message_router::{MessageRouter, RouterDecision};
+use crate::router_audit::RouterAudit;
 use crate::slack_store::SlackStore;
+use crate::task_budgets;
 use crate::user_store::UserStore;
 use uuid::Uuid;
 
 use super::super::config::ServiceConfig;
 use super::super::BoxError;
 use super::discord_context::build_discord_router_context;
-use super::persist_discord_ingest_context;
+use super::full_agent_requests::forward_for_full_agent;
+use super::quick_history;
+use super::quick_stream::StreamedReply;
+use super::stop_requests::answer_stop_request;
+use super::{discord_thread_key, persist_discord_ingest_context};
 
 const DISCORD_QUICK_RESPONSE_DEDUPE_FILE: &str = "discord_quick_response_dedupe.json";
 const DISCORD_QUICK_RESPONSE_MAX_THREADS: usize = 512;
@@ -351,6 +360,80 @@ fn now_unix_secs() -> i64 {
         .unwrap_or(0)
 }
 
+/// Route `text` with the quick-response router. The caller checks the budget
+/// first.
+fn route_quick(
+    config: &ServiceConfig,
+    message_router: &MessageRouter,
+    runtime: &tokio::runtime::Handle,
+    text: &str,
+    memory: Option<&str>,
+    extra_context: Option<&str>,
+) -> RouterDecision {
+    let employee_name = config.employee_profile.display_name.as_deref();
+    runtime.block_on(message_router.classify(text, memory, employee_name, extra_context))
+}
+
+/// Route `text` while a simple answer is shown in `reply` as the router
+/// streams it. The caller checks the budget first and finishes or retracts
+/// `reply` afterwards.
+fn route_streaming(
+    config: &ServiceConfig,
+    message_router: &MessageRouter,
+    runtime: &tokio::runtime::Handle,
+    text: &str,
+    memory: Option<&str>,
+    extra_context: Option<&str>,
+    reply: &mut StreamedReply,
+) -> RouterDecision {
+    let (partials_tx, partials) = std::sync::mpsc::channel::<String>();
+    let router = message_router.clone();
+    let text = text.to_string();
+    let memory = memory.map(str::to_string);
+    let extra_context = extra_context.map(str::to_string);
+    let employee_name = config.employee_profile.display_name.clone();
+    // Routing runs on the runtime while this blocking thread posts the
+    // partials, so the adapters' blocking HTTP clients stay off the runtime.
+    let routing = runtime.spawn(async move {
+        router
+            .classify_streaming(
+                &text,
+                memory.as_deref(),
+                employee_name.as_deref(),
+                extra_context.as_deref(),
+                |partial| {
+                    let _ = partials_tx.send(partial.to_string());
+                },
+            )
+            .await
+    });
+    while let Ok(mut partial) = partials.recv() {
+        // Only the latest partial matters when edits fall behind.
+        while let Ok(newer) = partials.try_recv() {
+            partial = newer;
+        }
+        reply.show(&partial);
+    }
+    runtime.block_on(routing).unwrap_or_else(|err| {
+        warn!("quick response routing task failed: {}", err);
+        RouterDecision::Passthrough
+    })
+}
+
+/// The reply sent instead of routing once a task budget is used up.
+fn budget_reached(config: &ServiceConfig, user_id: &str) -> Option<RouterDecision> {
+    let exceeded = task_budgets::check(Some(user_id), Some(&config.employee_profile.id))?;
+    info!(
+        "quick response budget reached employee={} user_id={}: {}",
+        config.employee_profile.id, user_id, exceeded
+    );
+    let locale = user_locale(user_id, config.employee_profile.language.as_deref());
+    Some(RouterDecision::Simple {
+        response: locale.text(Message::BudgetReached).to_string(),
+        memory_update: None,
+    })
+}
+
 pub(crate) fn try_quick_response_slack(
     config: &ServiceConfig,
     user_store: &UserStore,
@@ -391,13 +474,60 @@ pub(crate) fn try_quick_response_slack(
         .collect::<Vec<_>>()
         .join(" ");
 
-    let employee_name = config.employee_profile.display_name.as_deref();
-    let decision = runtime.block_on(message_router.classify(
+    let thread_key = format!("slack:{}:{}", channel_id, message.thread_id);
+    let token = resolve_slack_bot_token(
+        config,
+        slack_store,
+        message.metadata.slack_team_id.as_deref(),
+    );
+    let mut reply = token
+        .as_deref()
+        .filter(|_| message_router.is_streaming())
+        .map(|token| {
+            StreamedReply::new(
+                Box::new(SlackOutboundAdapter::new(token.to_string())),
+                slack_quick_draft(channel_id, &message.thread_id),
+                channel_id.to_string(),
+            )
+        });
+    let stop = answer_stop_request(
+        config,
+        user_store,
+        &user.user_id,
+        &thread_key,
         &cleaned_text,
-        memory.as_deref(),
-        employee_name,
-        None,
-    ));
+    )
+    .or_else(|| forward_for_full_agent(user_store, &user.user_id, &cleaned_text));
+    let mut audit = RouterAudit::new(&config.employee_profile.id, &user.user_id, Channel::Slack);
+    let history = quick_history::router_context(&user_paths.state_dir, &thread_key);
+    let decision = match (stop, reply.as_mut()) {
+        (Some(decision), _) => decision,
+        (None, Some(reply)) => budget_reached(config, &user.user_id).unwrap_or_else(|| {
+            audit.time(&cleaned_text, || {
+                route_streaming(
+                    config,
+                    message_router,
+                    runtime,
+                    &cleaned_text,
+                    memory.as_deref(),
+                    history.as_deref(),
+                    reply,
+                )
+            })
+        }),
+        (None, None) => budget_reached(config, &user.user_id).unwrap_or_else(|| {
+            audit.time(&cleaned_text, || {
+                route_quick(
+                    config,
+                    message_router,
+                    runtime,
+                    &cleaned_text,
+                    memory.as_deref(),
+                    history.as_deref(),
+                )
+            })
+        }),
+    };
     match decision {
         RouterDecision::Simple {
             response,
@@ -416,19 +546,26 @@ pub(crate) fn try_quick_response_slack(
                 }
             }
 
-            let token = resolve_slack_bot_token(
-                config,
-                slack_store,
-                message.metadata.slack_team_id.as_deref(),
-            );
             if let Some(token) = token {
-                let thread_ts = Some(message.thread_id.as_str());
-                if runtime
-                    .block_on(send_quick_slack_response(
-                        &token, channel_id, thread_ts, &response,
-                    ))
-                    .is_ok()
-                {
+                let sent = match reply.as_mut() {
+                    Some(reply) => reply.finish(&response).is_ok(),
+                    None => {
+                        let thread_ts = Some(message.thread_id.as_str());
+                        runtime
+                            .block_on(send_quick_slack_response(
+                                &token, channel_id, thread_ts, &response,
+                            ))
+                            .is_ok()
+                    }
+                };
+                audit.sent(sent);
+                if sent {
+                    quick_history::remember(
+                        &user_paths.state_dir,
+                        &thread_key,
+                        &cleaned_text,
+                        &response,
+                    );
                     if let (Some(scope), Some(inbound_id)) =
                         (dedupe_scope.as_deref(), inbound_message_id)
                     {
@@ -448,7 +585,34 @@ pub(crate) fn try_quick_response_slack(
             }
             Ok(false)
         }
-        RouterDecision::Complex | RouterDecision::Passthrough => Ok(false),
+        RouterDecision::Complex | RouterDecision::Passthrough => {
+            quick_history::forget(&user_paths.state_dir, &thread_key);
+            if let Some(reply) = reply.as_mut() {
+                reply.retract();
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// Where a streamed Slack quick response is posted: the inbound message's thread.
+fn slack_quick_draft(channel_id: &str, thread_ts: &str) -> OutboundMessage {
+    OutboundMessage {
+        channel: Channel::Slack,
+        from: None,
+        to: vec![channel_id.to_string()],
+        cc: vec![],
+        bcc: vec![],
+        subject: String::new(),
+        text_body: String::new(),
+        html_body: String::new(),
+        html_path: None,
+        attachments_dir: None,
+        thread_id: Some(thread_ts.to_string()),
+        metadata: ChannelMetadata {
+            slack_channel_id: Some(channel_id.to_string()),
+            ..Default::default()
+        },
     }
 }
 
@@ -530,9 +694,30 @@ pub(crate) fn try_quick_response_bluebubbles(
     let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
     let memory = read_user_memo(runtime, account_id, &user_paths.memory_dir);
 
-    let employee_name = config.employee_profile.display_name.as_deref();
-    let decision =
-        runtime.block_on(message_router.classify(text, memory.as_deref(), employee_name, None));
+    let thread_key = format!("imessage:{}", chat_guid);
+    let stop = answer_stop_request(config, user_store, &user.user_id, &thread_key, text)
+        .or_else(|| forward_for_full_agent(user_store, &user.user_id, text));
+    let mut audit = RouterAudit::new(
+        &config.employee_profile.id,
+        &user.user_id,
+        Channel::BlueBubbles,
+    );
+    let history = quick_history::router_context(&user_paths.state_dir, &thread_key);
+    let decision = match stop {
+        Some(decision) => decision,
+        None => budget_reached(config, &user.user_id).unwrap_or_else(|| {
+            audit.time(text, || {
+                route_quick(
+                    config,
+                    message_router,
+                    runtime,
+                    text,
+                    memory.as_deref(),
+                    history.as_deref(),
+                )
+            })
+        }),
+    };
     match decision {
         RouterDecision::Simple {
             response,
@@ -551,17 +736,21 @@ pub(crate) fn try_quick_response_bluebubbles(
                 }
             }
 
-            if runtime
+            let sent = runtime
                 .block_on(send_quick_bluebubbles_response(
                     url, password, chat_guid, &response,
                 ))
-                .is_ok()
-            {
-                return Ok(true);
+                .is_ok();
+            audit.sent(sent);
+            if sent {
+                quick_history::remember(&user_paths.state_dir, &thread_key, text, &response);
             }
+            Ok(sent)
+        }
+        RouterDecision::Complex | RouterDecision::Passthrough => {
+            quick_history::forget(&user_paths.state_dir, &thread_key);
             Ok(false)
         }
-        RouterDecision::Complex | RouterDecision::Passthrough => Ok(false),
     }
 }
 
@@ -621,13 +810,47 @@ pub(crate) fn try_quick_response_discord(
         .as_ref()
         .map(|context| context.context.as_str());
 
-    let employee_name = config.employee_profile.display_name.as_deref();
-    let decision = runtime.block_on(message_router.classify(
-        router_message,
-        memory.as_deref(),
-        employee_name,
-        extra_context,
-    ));
+    let stop = match discord_thread_key(message) {
+        Ok(thread_key) => answer_stop_request(config, user_store, &user.user_id, &thread_key, text),
+        Err(_) => None,
+    }
+    .or_else(|| forward_for_full_agent(user_store, &user.user_id, text));
+    let mut audit = RouterAudit::new(&config.employee_profile.id, &user.user_id, Channel::Discord);
+    let mut reply = message_router.is_streaming().then(|| {
+        StreamedReply::new(
+            Box::new(DiscordOutboundAdapter::new(token.clone())),
+            discord_quick_draft(channel_id, message_id),
+            channel_id.to_string(),
+        )
+    });
+    let decision = match (stop, reply.as_mut()) {
+        (Some(decision), _) => decision,
+        (None, Some(reply)) => budget_reached(config, &user.user_id).unwrap_or_else(|| {
+            audit.time(router_message, || {
+                route_streaming(
+                    config,
+                    message_router,
+                    runtime,
+                    router_message,
+                    memory.as_deref(),
+                    extra_context,
+                    reply,
+                )
+            })
+        }),
+        (None, None) => budget_reached(config, &user.user_id).unwrap_or_else(|| {
+            audit.time(router_message, || {
+                route_quick(
+                    config,
+                    message_router,
+                    runtime,
+                    router_message,
+                    memory.as_deref(),
+                    extra_context,
+                )
+            })
+        }),
+    };
     match decision {
         RouterDecision::Simple {
             response,
@@ -646,9 +869,14 @@ pub(crate) fn try_quick_response_discord(
                 }
             }
 
-            let sent =
-                send_quick_discord_response_simple(&token, channel_id, message_id, &response)
-                    .is_ok();
+            let sent = match reply.as_mut() {
+                Some(reply) => reply.finish(&response).is_ok(),
+                None => {
+                    send_quick_discord_response_simple(&token, channel_id, message_id, &response)
+                        .is_ok()
+                }
+            };
+            audit.sent(sent);
             if sent {
                 if let (Some(scope), Some(inbound_id)) =
                     (dedupe_scope.as_deref(), inbound_message_id)
@@ -678,7 +906,12 @@ pub(crate) fn try_quick_response_discord(
             }
             Ok(false)
         }
-        RouterDecision::Complex | RouterDecision::Passthrough => Ok(false),
+        RouterDecision::Complex | RouterDecision::Passthrough => {
+            if let Some(reply) = reply.as_mut() {
+                reply.retract();
+            }
+            Ok(false)
+        }
     }
 }
 
@@ -705,9 +938,30 @@ pub(crate) fn try_quick_response_telegram(
     let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
     let memory = read_user_memo(runtime, account_id, &user_paths.memory_dir);
 
-    let employee_name = config.employee_profile.display_name.as_deref();
-    let decision =
-        runtime.block_on(message_router.classify(text, memory.as_deref(), employee_name, None));
+    let thread_key = format!("telegram:{}", chat_id);
+    let stop = answer_stop_request(config, user_store, &user.user_id, &thread_key, text)
+        .or_else(|| forward_for_full_agent(user_store, &user.user_id, text));
+    let mut audit = RouterAudit::new(
+        &config.employee_profile.id,
+        &user.user_id,
+        Channel::Telegram,
+    );
+    let history = quick_history::router_context(&user_paths.state_dir, &thread_key);
+    let decision = match stop {
+        Some(decision) => decision,
+        None => budget_reached(config, &user.user_id).unwrap_or_else(|| {
+            audit.time(text, || {
+                route_quick(
+                    config,
+                    message_router,
+                    runtime,
+                    text,
+                    memory.as_deref(),
+                    history.as_deref(),
+                )
+            })
+        }),
+    };
     match decision {
         RouterDecision::Simple {
             response,
@@ -726,15 +980,19 @@ pub(crate) fn try_quick_response_telegram(
                 }
             }
 
-            if runtime
+            let sent = runtime
                 .block_on(send_quick_telegram_response(token, chat_id, &response))
-                .is_ok()
-            {
-                return Ok(true);
+                .is_ok();
+            audit.sent(sent);
+            if sent {
+                quick_history::remember(&user_paths.state_dir, &thread_key, text, &response);
             }
+            Ok(sent)
+        }
+        RouterDecision::Complex | RouterDecision::Passthrough => {
+            quick_history::forget(&user_paths.state_dir, &thread_key);
             Ok(false)
         }
-        RouterDecision::Complex | RouterDecision::Passthrough => Ok(false),
     }
 }
 
@@ -797,19 +1055,31 @@ fn send_quick_discord_response_simple(
     message_id: Option<&str>,
     response_text: &str,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    use crate::adapters::discord::DiscordOutboundAdapter;
-    use crate::channel::{Channel, ChannelMetadata, OutboundAdapter, OutboundMessage};
-
     let adapter = DiscordOutboundAdapter::new(bot_token.to_string());
 
-    let message = OutboundMessage {
+    let mut message = discord_quick_draft(channel_id, message_id);
+    message.text_body = response_text.to_string();
+
+    let result = adapter.send(&message)?;
+    if !result.success {
+        return Err(result
+            .error
+            .unwrap_or_else(|| "discord send failed".to_string())
+            .into());
+    }
+    Ok(())
+}
+
+/// Where a Discord quick response is posted: a reply to the inbound message.
+fn discord_quick_draft(channel_id: u64, message_id: Option<&str>) -> OutboundMessage {
+    OutboundMessage {
         channel: Channel::Discord,
         from: None,
         to: vec![channel_id.to_string()],
         cc: vec![],
         bcc: vec![],
         subject: String::new(),
-        text_body: response_text.to_string(),
+        text_body: String::new(),
         html_body: String::new(),
         html_path: None,
         attachments_dir: None,
@@ -818,16 +1088,7 @@ fn send_quick_discord_response_simple(
             discord_channel_id: Some(channel_id),
             ..Default::default()
         },
-    };
-
-    let result = adapter.send(&message)?;
-    if !result.success {
-        return Err(result
-            .error
-            .unwrap_or_else(|| "discord send failed".to_string())
-            .into());
     }
-    Ok(())
 }
 
 pub(crate) fn try_quick_response_whatsapp(
@@ -859,9 +1120,30 @@ pub(crate) fn try_quick_response_whatsapp(
     let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
     let memory = read_user_memo(runtime, account_id, &user_paths.memory_dir);
 
-    let employee_name = config.employee_profile.display_name.as_deref();
-    let decision =
-        runtime.block_on(message_router.classify(text, memory.as_deref(), employee_name, None));
+    let thread_key = format!("whatsapp:{}", phone_number);
+    let stop = answer_stop_request(config, user_store, &user.user_id, &thread_key, text)
+        .or_else(|| forward_for_full_agent(user_store, &user.user_id, text));
+    let mut audit = RouterAudit::new(
+        &config.employee_profile.id,
+        &user.user_id,
+        Channel::WhatsApp,
+    );
+    let history = quick_history::router_context(&user_paths.state_dir, &thread_key);
+    let decision = match stop {
+        Some(decision) => decision,
+        None => budget_reached(config, &user.user_id).unwrap_or_else(|| {
+            audit.time(text, || {
+                route_quick(
+                    config,
+                    message_router,
+                    runtime,
+                    text,
+                    memory.as_deref(),
+                    history.as_deref(),
+                )
+            })
+        }),
+    };
     match decision {
         RouterDecision::Simple {
             response,
@@ -880,20 +1162,24 @@ pub(crate) fn try_quick_response_whatsapp(
                 }
             }
 
-            if runtime
+            let sent = runtime
                 .block_on(send_quick_whatsapp_response(
                     access_token,
                     phone_number_id,
                     phone_number,
                     &response,
                 ))
-                .is_ok()
-            {
-                return Ok(true);
+                .is_ok();
+            audit.sent(sent);
+            if sent {
+                quick_history::remember(&user_paths.state_dir, &thread_key, text, &response);
             }
+            Ok(sent)
+        }
+        RouterDecision::Complex | RouterDecision::Passthrough => {
+            quick_history::forget(&user_paths.state_dir, &thread_key);
             Ok(false)
         }
-        RouterDecision::Complex | RouterDecision::Passthrough => Ok(false),
     }
 }
 
@@ -947,13 +1233,21 @@ pub(crate) fn try_quick_response_google_workspace(
     let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
     let memory = read_user_memo(runtime, account_id, &user_paths.memory_dir);
 
-    let employee_name = config.employee_profile.display_name.as_deref();
-    let decision = runtime.block_on(message_router.classify(
-        text,
-        memory.as_deref(),
-        employee_name,
-        None,
-    ));
+    let mut audit = RouterAudit::new(&config.employee_profile.id, &user.user_id, message.channel);
+    let decision = forward_for_full_agent(user_store, &user.user_id, text)
+        .or_else(|| budget_reached(config, &user.user_id))
+        .unwrap_or_else(|| {
+            audit.time(text, || {
+                route_quick(
+                    config,
+                    message_router,
+                    runtime,
+                    text,
+                    memory.as_deref(),
+                    None,
+                )
+            })
+        });
 
     match decision {
         RouterDecision::Simple {
@@ -970,7 +1264,9 @@ pub(crate) fn try_quick_response_google_workspace(
             }
 
             // Send quick response via Google API
-            if send_quick_google_workspace_response(file_id, comment_id, &response).is_ok() {
+            let sent = send_quick_google_workspace_response(file_id, comment_id, &response).is_ok();
+            audit.sent(sent);
+            if sent {
                 info!(
                     "google workspace quick response sent: channel={:?} file_id={} comment_id={}",
                     message.channel, file_id, comment_id
@@ -1028,13 +1324,36 @@ pub(crate) fn try_quick_response_wechat(
     let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
     let memory = read_user_memo(runtime, account_id, &user_paths.memory_dir);
 
-    let employee_name = config.employee_profile.display_name.as_deref();
-    let decision = runtime.block_on(message_router.classify(
-        text,
-        memory.as_deref(),
-        employee_name,
-        None,
-    ));
+    let thread_key = message
+        .metadata
+        .wechat_corp_id
+        .as_deref()
+        .map(|corp_id| format!("wechat:{}:{}", corp_id, message.sender));
+    let stop = thread_key
+        .as_deref()
+        .and_then(|thread_key| {
+            answer_stop_request(config, user_store, &user.user_id, thread_key, text)
+        })
+        .or_else(|| forward_for_full_agent(user_store, &user.user_id, text));
+    let mut audit = RouterAudit::new(&config.employee_profile.id, &user.user_id, Channel::WeChat);
+    let history = thread_key
+        .as_deref()
+        .and_then(|thread_key| quick_history::router_context(&user_paths.state_dir, thread_key));
+    let decision = match stop {
+        Some(decision) => decision,
+        None => budget_reached(config, &user.user_id).unwrap_or_else(|| {
+            audit.time(text, || {
+                route_quick(
+                    config,
+                    message_router,
+                    runtime,
+                    text,
+                    memory.as_deref(),
+                    history.as_deref(),
+                )
+            })
+        }),
+    };
 
     match decision {
         RouterDecision::Simple {
@@ -1051,13 +1370,23 @@ pub(crate) fn try_quick_response_wechat(
             }
 
             // Send quick response via WeChat API
-            if send_quick_wechat_response(user_id, &response).is_ok() {
+            let sent = send_quick_wechat_response(user_id, &response).is_ok();
+            audit.sent(sent);
+            if sent {
+                if let Some(thread_key) = thread_key.as_deref() {
+                    quick_history::remember(&user_paths.state_dir, thread_key, text, &response);
+                }
                 info!("wechat quick response sent: user_id={}", user_id);
                 return Ok(true);
             }
             Ok(false)
         }
-        RouterDecision::Complex | RouterDecision::Passthrough => Ok(false),
+        RouterDecision::Complex | RouterDecision::Passthrough => {
+            if let Some(thread_key) = thread_key.as_deref() {
+                quick_history::forget(&user_paths.state_dir, thread_key);
+            }
+            Ok(false)
+        }
     }
 }
 
@@ -1338,11 +1667,8 @@ mod tests {
 
     #[test]
     fn google_docs_message_has_correct_metadata() {
-        let message = build_google_docs_message(
-            Some("doc-123"),
-            Some("comment-456"),
-            Some("Hello!"),
-        );
+        let message =
+            build_google_docs_message(Some("doc-123"), Some("comment-456"), Some("Hello!"));
 
         assert_eq!(message.channel, Channel::GoogleDocs);
         assert_eq!(
@@ -1459,10 +1785,7 @@ mod tests {
         assert_eq!(message.channel, Channel::WeChat);
         assert_eq!(message.sender, "user123");
         assert_eq!(message.text_body, Some("Hello!".to_string()));
-        assert_eq!(
-            message.metadata.wechat_corp_id,
-            Some("corp456".to_string())
-        );
+        assert_eq!(message.metadata.wechat_corp_id, Some("corp456".to_string()));
     }
 
     #[test]
@@ -0,0 +1,250 @@
+//! Quick responses that appear while the router is still writing them.
+//!
+//! On Slack and Discord the reply is posted once the router has produced a
+//! few words and then edited as more arrives. Both platforms rate limit
+//! edits, so partial text is shown at most once per `EDIT_INTERVAL`; the
+//! final text is always written.
+
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use crate::channel::{AdapterError, MessageRef, OutboundAdapter, OutboundMessage};
+
+/// Partial replies shorter than this are not posted yet
+const MIN_INITIAL_CHARS: usize = 24;
+/// Shortest time between two edits of a streamed reply
+const EDIT_INTERVAL: Duration = Duration::from_millis(800);
+
+pub(super) struct StreamedReply {
+    adapter: Box<dyn OutboundAdapter>,
+    /// Where the reply goes; its text is replaced before posting.
+    draft: OutboundMessage,
+    /// Channel ID the posted message is edited in.
+    channel_id: String,
+    posted: Option<MessageRef>,
+    shown: String,
+    last_edit: Option<Instant>,
+    edit_interval: Duration,
+    /// Set once posting a partial failed; only the final text is tried then.
+    gave_up: bool,
+}
+
+impl StreamedReply {
+    pub(super) fn new(
+        adapter: Box<dyn OutboundAdapter>,
+        draft: OutboundMessage,
+        channel_id: String,
+    ) -> Self {
+        Self {
+            adapter,
+            draft,
+            channel_id,
+            posted: None,
+            shown: String::new(),
+            last_edit: None,
+            edit_interval: EDIT_INTERVAL,
+            gave_up: false,
+        }
+    }
+
+    /// Show `partial`, posting the reply first if it is long enough.
+    pub(super) fn show(&mut self, partial: &str) {
+        if self.gave_up || partial == self.shown {
+            return;
+        }
+        let result = match &self.posted {
+            None if partial.chars().count() < MIN_INITIAL_CHARS => return,
+            None => self.post(partial),
+            Some(_) if !self.edit_due() => return,
+            Some(message) => self.adapter.update(message, partial),
+        };
+        match result {
+            Ok(()) => {
+                self.shown = partial.to_string();
+                self.last_edit = Some(Instant::now());
+            }
+            Err(err) => {
+                warn!("failed to stream quick response: {}", err);
+                self.gave_up = self.posted.is_none();
+            }
+        }
+    }
+
+    /// Leave `text` as the reply, posting it if nothing was shown yet.
+    pub(super) fn finish(&mut self, text: &str) -> Result<(), AdapterError> {
+        let result = match &self.posted {
+            None => self.post(text),
+            Some(_) if self.shown == text => Ok(()),
+            Some(message) => self.adapter.update(message, text),
+        };
+        if result.is_err() {
+            self.retract();
+        }
+        result
+    }
+
+    /// Delete the partial reply, e.g. when the router forwards after all.
+    pub(super) fn retract(&mut self) {
+        if let Some(message) = self.posted.take() {
+            if let Err(err) = self.adapter.delete(&message) {
+                warn!("failed to delete partial quick response: {}", err);
+            }
+        }
+    }
+
+    fn edit_due(&self) -> bool {
+        self.last_edit
+            .is_none_or(|last| last.elapsed() >= self.edit_interval)
+    }
+
+    fn post(&mut self, text: &str) -> Result<(), AdapterError> {
+        let mut message = self.draft.clone();
+        message.text_body = text.to_string();
+        let result = self.adapter.send(&message)?;
+        if !result.success {
+            return Err(AdapterError::SendError(
+                result.error.unwrap_or_else(|| "send failed".to_string()),
+            ));
+        }
+        self.posted = Some(MessageRef {
+            channel_id: self.channel_id.clone(),
+            message_id: result.message_id,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::{Channel, ChannelMetadata, SendResult};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Recorder {
+        calls: Arc<Mutex<Vec<String>>>,
+        fail_updates: bool,
+    }
+
+    impl OutboundAdapter for Recorder {
+        fn send(&self, message: &OutboundMessage) -> Result<SendResult, AdapterError> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("send:{}", message.text_body));
+            Ok(SendResult {
+                success: true,
+                message_id: "1700000000.000100".to_string(),
+                submitted_at: String::new(),
+                error: None,
+            })
+        }
+
+        fn update(&self, message: &MessageRef, new_content: &str) -> Result<(), AdapterError> {
+            if self.fail_updates {
+                return Err(AdapterError::SendError("rate limited".to_string()));
+            }
+            self.calls.lock().unwrap().push(format!(
+                "update:{}:{}:{}",
+                message.channel_id, message.message_id, new_content
+            ));
+            Ok(())
+        }
+
+        fn delete(&self, message: &MessageRef) -> Result<(), AdapterError> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("delete:{}", message.message_id));
+            Ok(())
+        }
+
+        fn channel(&self) -> Channel {
+            Channel::Slack
+        }
+    }
+
+    fn reply(recorder: &Recorder, edit_interval: Duration) -> StreamedReply {
+        let draft = OutboundMessage {
+            channel: Channel::Slack,
+            from: None,
+            to: vec!["C123".to_string()],
+            cc: vec![],
+            bcc: vec![],
+            subject: String::new(),
+            text_body: String::new(),
+            html_body: String::new(),
+            html_path: None,
+            attachments_dir: None,
+            thread_id: Some("1700000000.000001".to_string()),
+            metadata: ChannelMetadata {
+                slack_channel_id: Some("C123".to_string()),
+                ..Default::default()
+            },
+        };
+        let mut reply = StreamedReply::new(Box::new(recorder.clone()), draft, "C123".to_string());
+        reply.edit_interval = edit_interval;
+        reply
+    }
+
+    #[test]
+    fn posts_once_long_enough_then_edits_and_finishes() {
+        let recorder = Recorder::default();
+        let mut reply = reply(&recorder, Duration::ZERO);
+        reply.show("Hi there");
+        reply.show("Hi there, the office opens");
+        reply.show("Hi there, the office opens at nine");
+        reply.finish("Hi there, the office opens at nine.").unwrap();
+
+        assert_eq!(
+            *recorder.calls.lock().unwrap(),
+            vec![
+                "send:Hi there, the office opens",
+                "update:C123:1700000000.000100:Hi there, the office opens at nine",
+                "update:C123:1700000000.000100:Hi there, the office opens at nine.",
+            ]
+        );
+    }
+
+    #[test]
+    fn edits_between_intervals_are_skipped_but_the_final_text_lands() {
+        let recorder = Recorder::default();
+        let mut reply = reply(&recorder, Duration::from_secs(60));
+        reply.show("Hi there, the office opens");
+        reply.show("Hi there, the office opens at nine");
+        reply.finish("Hi there, the office opens at nine.").unwrap();
+
+        assert_eq!(
+            *recorder.calls.lock().unwrap(),
+            vec![
+                "send:Hi there, the office opens",
+                "update:C123:1700000000.000100:Hi there, the office opens at nine.",
+            ]
+        );
+    }
+
+    #[test]
+    fn short_replies_are_sent_once_and_failed_finishes_retract() {
+        let recorder = Recorder::default();
+        let mut short = reply(&recorder, Duration::ZERO);
+        short.show("Hi!");
+        short.finish("Hi!").unwrap();
+        assert_eq!(*recorder.calls.lock().unwrap(), vec!["send:Hi!"]);
+
+        let failing = Recorder {
+            fail_updates: true,
+            ..Recorder::default()
+        };
+        let mut reply = reply(&failing, Duration::ZERO);
+        reply.show("Hi there, the office opens");
+        assert!(reply.finish("Hi there, the office opens at nine.").is_err());
+        assert_eq!(
+            *failing.calls.lock().unwrap(),
+            vec![
+                "send:Hi there, the office opens",
+                "delete:1700000000.000100"
+            ]
+        );
+    }
+}
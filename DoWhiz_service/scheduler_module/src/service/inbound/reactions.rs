@@ -0,0 +1,338 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use crate::account_store::AccountStore;
+use crate::channel::{Channel, ChannelMetadata, InboundMessage};
+use crate::index_store::IndexStore;
+use crate::message_link_store::{get_global_message_link_store, MessageLink, ReactionControl};
+use crate::user_store::UserStore;
+use crate::{ModuleExecutor, RunTaskTask, ScheduledTask, Scheduler, TaskKind};
+
+use super::super::config::ServiceConfig;
+use super::super::scheduler::cancel_pending_thread_tasks;
+use super::super::BoxError;
+use super::super::{bump_thread_state, default_thread_state_path};
+use super::slack_interactions::process_slack_interaction;
+
+/// How far ⏰ pushes pending work (or when the reminder fires).
+const REACTION_SNOOZE_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Apply a control reaction (⏰ snooze, ❌ cancel, ✅ approve) on an employee message.
+///
+/// The reacted-to message is resolved to its thread through the message link store.
+/// Only the requester who owns the thread can control it. On a Slack message that
+/// carries confirmation buttons, ✅/❌ resolve the confirmation like a click would.
+pub(crate) fn process_chat_reaction(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    account_store: &AccountStore,
+    runtime: &tokio::runtime::Handle,
+    message: &InboundMessage,
+) -> Result<(), BoxError> {
+    let control = message
+        .metadata
+        .reaction_control
+        .as_deref()
+        .and_then(ReactionControl::parse)
+        .ok_or("missing reaction control")?;
+    let message_id = message
+        .message_id
+        .as_deref()
+        .ok_or("missing reacted message id")?;
+    let Some(store) = get_global_message_link_store() else {
+        return Err("message link store unavailable".into());
+    };
+    let Some(link) = store.find(message.channel, message_id)? else {
+        info!(
+            "ignoring {} reaction on untracked {} message {}",
+            control.as_str(),
+            message.channel,
+            message_id
+        );
+        return Ok(());
+    };
+    if link.requester_id != message.sender {
+        info!(
+            "ignoring {} reaction by {} on {} thread owned by {}",
+            control.as_str(),
+            message.sender,
+            message.channel,
+            link.requester_id
+        );
+        return Ok(());
+    }
+
+    if let (Some(callback_id), Channel::Slack) = (link.pending_action_id.as_deref(), link.channel) {
+        if control != ReactionControl::Snooze {
+            let interaction = confirmation_interaction(&link, callback_id, control, message);
+            process_slack_interaction(
+                config,
+                user_store,
+                index_store,
+                account_store,
+                runtime,
+                &interaction,
+            )?;
+            if control == ReactionControl::Approve {
+                return Ok(());
+            }
+        }
+    }
+
+    let workspace = PathBuf::from(&link.workspace_dir);
+    let user = user_store.get_or_create_user(&link.channel.to_string(), &link.requester_id)?;
+    let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
+    let mut scheduler = Scheduler::load(&user_paths.tasks_db_path, ModuleExecutor)?;
+
+    match control {
+        ReactionControl::Cancel => {
+            let disabled = scheduler
+                .disable_tasks_by(|task| task.enabled && targets_workspace(task, &workspace))?;
+            info!(
+                "{} reaction cancelled {} task(s) in {}",
+                message.channel,
+                disabled,
+                workspace.display()
+            );
+        }
+        ReactionControl::Snooze => {
+            let pending: Vec<_> = scheduler
+                .tasks()
+                .iter()
+                .filter(|task| task.enabled && targets_workspace(task, &workspace))
+                .map(|task| task.id)
+                .collect();
+            let delay = chrono::Duration::from_std(REACTION_SNOOZE_DELAY)?;
+            let mut deferred = 0;
+            for task_id in pending {
+                if scheduler.defer_one_shot_task_by_id(task_id, delay)? {
+                    deferred += 1;
+                }
+            }
+            if deferred == 0 {
+                enqueue_reaction_follow_up(
+                    &mut scheduler,
+                    &workspace,
+                    &link,
+                    control,
+                    REACTION_SNOOZE_DELAY,
+                )?;
+            } else {
+                info!(
+                    "{} reaction snoozed {} task(s) in {}",
+                    message.channel,
+                    deferred,
+                    workspace.display()
+                );
+            }
+        }
+        ReactionControl::Approve => {
+            enqueue_reaction_follow_up(&mut scheduler, &workspace, &link, control, Duration::ZERO)?;
+        }
+    }
+
+    index_store.sync_user_tasks(&user.user_id, scheduler.tasks())?;
+    Ok(())
+}
+
+fn targets_workspace(task: &ScheduledTask, workspace: &Path) -> bool {
+    match &task.kind {
+        TaskKind::RunTask(run) => run.workspace_dir == workspace,
+        TaskKind::SendReply(send) => send.html_path.starts_with(workspace),
+        _ => false,
+    }
+}
+
+/// Start a new turn in the reacted-to thread, reusing the routing of its latest
+/// RunTask. The reaction is left in `incoming_email/` as a note for the agent.
+fn enqueue_reaction_follow_up(
+    scheduler: &mut Scheduler<ModuleExecutor>,
+    workspace: &Path,
+    link: &MessageLink,
+    control: ReactionControl,
+    delay: Duration,
+) -> Result<(), BoxError> {
+    let Some(mut run_task) = latest_run_task(scheduler.tasks(), workspace) else {
+        warn!(
+            "no run task found for {} reaction in {}",
+            control.as_str(),
+            workspace.display()
+        );
+        return Ok(());
+    };
+    let thread_state_path = run_task
+        .thread_state_path
+        .clone()
+        .unwrap_or_else(|| default_thread_state_path(workspace));
+    let thread_key = run_task
+        .thread_id
+        .clone()
+        .unwrap_or_else(|| link.message_id.clone());
+    let thread_state = bump_thread_state(&thread_state_path, &thread_key, None)?;
+
+    let incoming_dir = workspace.join("incoming_email");
+    fs::create_dir_all(&incoming_dir)?;
+    let note_path = incoming_dir.join(format!(
+        "{:05}_{}_reaction.txt",
+        thread_state.last_email_seq, link.channel
+    ));
+    fs::write(&note_path, reaction_note(control, &link.message_id))?;
+
+    if let Err(err) = cancel_pending_thread_tasks(scheduler, workspace, thread_state.epoch) {
+        warn!(
+            "failed to cancel pending thread tasks for {}: {}",
+            workspace.display(),
+            err
+        );
+    }
+    run_task.thread_epoch = Some(thread_state.epoch);
+    run_task.thread_state_path = Some(thread_state_path);
+    let task_id = scheduler.add_one_shot_in(delay, TaskKind::RunTask(run_task))?;
+    info!(
+        "{} reaction {} enqueued task_id={} in {} (delay={}s)",
+        link.channel,
+        control.as_str(),
+        task_id,
+        workspace.display(),
+        delay.as_secs()
+    );
+    Ok(())
+}
+
+fn latest_run_task(tasks: &[ScheduledTask], workspace: &Path) -> Option<RunTaskTask> {
+    tasks
+        .iter()
+        .filter_map(|task| match &task.kind {
+            TaskKind::RunTask(run) if run.workspace_dir == workspace => {
+                Some((task.created_at, run))
+            }
+            _ => None,
+        })
+        .max_by_key(|(created_at, _)| *created_at)
+        .map(|(_, run)| run.clone())
+}
+
+fn reaction_note(control: ReactionControl, message_id: &str) -> String {
+    match control {
+        ReactionControl::Approve => format!(
+            "The user reacted with ✅ to your message {} to approve the action you proposed there. \
+Go ahead with it and reply with the outcome.\n",
+            message_id
+        ),
+        ReactionControl::Snooze => format!(
+            "The user reacted with ⏰ to your message {} an hour ago to be reminded about it. \
+Send a short reminder of what is still open in this thread.\n",
+            message_id
+        ),
+        ReactionControl::Cancel => format!(
+            "The user reacted with ❌ to your message {} to cancel pending work.\n",
+            message_id
+        ),
+    }
+}
+
+/// Slack interaction equivalent of a ✅/❌ reaction on a confirmation message.
+fn confirmation_interaction(
+    link: &MessageLink,
+    callback_id: &str,
+    control: ReactionControl,
+    reaction: &InboundMessage,
+) -> InboundMessage {
+    let decision = if control == ReactionControl::Approve {
+        "approve"
+    } else {
+        "reject"
+    };
+    InboundMessage {
+        channel: Channel::Slack,
+        sender: reaction.sender.clone(),
+        sender_name: reaction.sender_name.clone(),
+        recipient: link.channel_id.clone(),
+        subject: None,
+        text_body: Some(decision.to_string()),
+        html_body: None,
+        thread_id: reaction.thread_id.clone(),
+        message_id: reaction.message_id.clone(),
+        attachments: Vec::new(),
+        reply_to: vec![link.channel_id.clone()],
+        raw_payload: reaction.raw_payload.clone(),
+        metadata: ChannelMetadata {
+            slack_channel_id: Some(link.channel_id.clone()),
+            slack_team_id: reaction.metadata.slack_team_id.clone(),
+            slack_callback_id: Some(callback_id.to_string()),
+            ..Default::default()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::tests::run_task_in;
+    use crate::{Schedule, SendReplyTask};
+    use chrono::Utc;
+
+    fn run_task(workspace: &Path, thread_id: &str) -> RunTaskTask {
+        RunTaskTask {
+            thread_id: Some(thread_id.to_string()),
+            ..run_task_in(workspace)
+        }
+    }
+
+    fn scheduled(kind: TaskKind, age_secs: i64) -> ScheduledTask {
+        ScheduledTask {
+            id: uuid::Uuid::new_v4(),
+            kind,
+            schedule: Schedule::OneShot { run_at: Utc::now() },
+            enabled: true,
+            created_at: Utc::now() - chrono::Duration::seconds(age_secs),
+            last_run: None,
+            approval: None,
+            description: None,
+            ends: None,
+        }
+    }
+
+    #[test]
+    fn latest_run_task_picks_newest_in_workspace() {
+        let workspace = Path::new("/tmp/users/u1/workspaces/thread-a");
+        let other = Path::new("/tmp/users/u1/workspaces/thread-b");
+        let tasks = vec![
+            scheduled(TaskKind::RunTask(run_task(workspace, "old")), 60),
+            scheduled(TaskKind::RunTask(run_task(workspace, "new")), 10),
+            scheduled(TaskKind::RunTask(run_task(other, "other")), 0),
+        ];
+
+        let latest = latest_run_task(&tasks, workspace).unwrap();
+        assert_eq!(latest.thread_id.as_deref(), Some("new"));
+
+        let reply = scheduled(
+            TaskKind::SendReply(SendReplyTask {
+                channel: Channel::Discord,
+                subject: String::new(),
+                html_path: workspace.join("reply_message.txt"),
+                attachments_dir: workspace.join("reply_attachments"),
+                from: None,
+                to: vec!["42".to_string(), "555".to_string()],
+                cc: Vec::new(),
+                bcc: Vec::new(),
+                in_reply_to: None,
+                references: None,
+                archive_root: None,
+                thread_epoch: None,
+                thread_state_path: None,
+                employee_id: None,
+                idempotency_key: None,
+                trace_id: None,
+                scheduled: false,
+            }),
+            0,
+        );
+        assert!(targets_workspace(&reply, workspace));
+        assert!(!targets_workspace(&reply, other));
+    }
+}
@@ -1,12 +1,11 @@
 use std::collections::HashSet;
 use std::path::Path;
-use std::time::Duration;
 
 use tracing::{info, warn};
 
 use crate::account_store::AccountStore;
 use crate::adapters::slack::SlackEventWrapper;
-use crate::channel::{Channel, InboundAdapter};
+use crate::channel::{Channel, InboundAdapter, InboundMessage};
 use crate::index_store::IndexStore;
 use crate::slack_store::SlackStore;
 use crate::user_store::UserStore;
@@ -15,7 +14,7 @@ use crate::{ModuleExecutor, RunTaskTask, Scheduler, TaskKind};
 use super::super::bump_thread_state;
 use super::super::config::ServiceConfig;
 use super::super::default_thread_state_path;
-use super::super::scheduler::cancel_pending_thread_tasks;
+use super::super::scheduler::{cancel_pending_thread_tasks, inbound_coalesce_delay};
 use super::super::workspace::ensure_thread_workspace;
 use super::super::BoxError;
 
@@ -57,6 +56,25 @@ pub(crate) fn process_slack_event(
 
     let message = adapter.parse(raw_payload)?;
 
+    enqueue_slack_run_task(
+        config,
+        user_store,
+        index_store,
+        account_store,
+        &message,
+        raw_payload,
+    )
+}
+
+/// Append a Slack message to its thread workspace and schedule a RunTask for it.
+pub(super) fn enqueue_slack_run_task(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    account_store: &AccountStore,
+    message: &InboundMessage,
+    raw_payload: &[u8],
+) -> Result<(), BoxError> {
     info!(
         "slack message from {} in channel {:?}: {:?}",
         message.sender, message.metadata.slack_channel_id, message.text_body
@@ -93,26 +111,17 @@ pub(crate) fn process_slack_event(
     // Save the incoming Slack message to workspace
     append_slack_message(
         &workspace,
-        &message,
+        message,
         raw_payload,
         thread_state.last_email_seq,
     )?;
 
     // Determine model and runner
-    let model_name = match config.employee_profile.model.clone() {
-        Some(model) => model,
-        None => {
-            if config
-                .employee_profile
-                .runner
-                .eq_ignore_ascii_case("claude")
-            {
-                String::new()
-            } else {
-                config.codex_model.clone()
-            }
-        }
-    };
+    let model_name = config
+        .employee_profile
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model_for_runner(&config.employee_profile.runner));
 
     info!(
         "workspace ready at {} for user {} thread={} epoch={}",
@@ -145,6 +154,8 @@ pub(crate) fn process_slack_event(
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     // Clone run_task before consuming it, in case we need to write to account-level storage
@@ -159,7 +170,13 @@ pub(crate) fn process_slack_event(
             err
         );
     }
-    let task_id = scheduler.add_one_shot_in(Duration::from_secs(0), TaskKind::RunTask(run_task))?;
+    let delay = inbound_coalesce_delay(
+        &scheduler,
+        &workspace,
+        &config.employee_profile.inbound_policy,
+        Channel::Slack,
+    );
+    let task_id = scheduler.add_one_shot_in(delay, TaskKind::RunTask(run_task))?;
     index_store.sync_user_tasks(&user.user_id, scheduler.tasks())?;
 
     info!(
@@ -187,7 +204,7 @@ pub(crate) fn process_slack_event(
                     // Use the same task_id so we can update status at completion
                     match account_scheduler.add_one_shot_in_with_id(
                         task_id,
-                        Duration::from_secs(0),
+                        delay,
                         TaskKind::RunTask(run_task_for_account),
                     ) {
                         Ok(()) => {
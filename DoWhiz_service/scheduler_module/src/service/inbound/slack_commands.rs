@@ -0,0 +1,255 @@
+use std::fs;
+
+use chrono::{DateTime, Utc};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::adapters::slack::{
+    parse_slash_command_text, post_to_response_url, SlackCommandAction, SlackEphemeralResponse,
+    SLASH_COMMAND_HELP,
+};
+use crate::channel::{Channel, InboundMessage};
+use crate::i18n::{user_locale, Locale, Message};
+use crate::index_store::IndexStore;
+use crate::message_router::{MessageRouter, RouterDecision};
+use crate::scheduler::build_scheduler_snapshot;
+use crate::user_store::UserStore;
+use crate::{ModuleExecutor, ScheduledTask, Scheduler, SendReplyTask, TaskKind};
+
+use super::super::config::ServiceConfig;
+use super::super::BoxError;
+
+const TASK_LIST_LIMIT: usize = 10;
+
+/// Handle a `/dowhiz` slash command without starting a RunTask.
+///
+/// Status/task queries read the user's scheduler, reminders become a delayed
+/// Slack `SendReply`, and free-form text goes through the quick-response
+/// router. The answer is posted ephemerally to the command's response_url,
+/// in the user's language. Help stays English: the commands it lists are.
+pub(crate) fn process_slack_slash_command(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    message_router: &MessageRouter,
+    runtime: &tokio::runtime::Handle,
+    message: &InboundMessage,
+) -> Result<(), BoxError> {
+    let text = message.text_body.as_deref().unwrap_or("");
+    let action = parse_slash_command_text(text);
+    info!(
+        "processing slack slash command {:?} from {}: {:?}",
+        message.metadata.slack_command, message.sender, action
+    );
+
+    let user = user_store.get_or_create_user("slack", &message.sender)?;
+    let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
+    user_store.ensure_user_dirs(&user_paths)?;
+    let locale = user_locale(&user.user_id, config.employee_profile.language.as_deref());
+
+    let reply = match action {
+        SlackCommandAction::Help => SLASH_COMMAND_HELP.to_string(),
+        SlackCommandAction::Status => {
+            let scheduler = Scheduler::load(&user_paths.tasks_db_path, ModuleExecutor)?;
+            format_status_reply(scheduler.tasks(), Utc::now(), locale)
+        }
+        SlackCommandAction::Tasks => {
+            let scheduler = Scheduler::load(&user_paths.tasks_db_path, ModuleExecutor)?;
+            format_tasks_reply(scheduler.tasks(), Utc::now(), locale)
+        }
+        SlackCommandAction::Remind {
+            delay,
+            message: reminder,
+        } => {
+            let reminders_dir = user_paths.state_dir.join("slack_reminders");
+            fs::create_dir_all(&reminders_dir)?;
+            let body_path = reminders_dir.join(format!("{}.txt", Uuid::new_v4()));
+            fs::write(
+                &body_path,
+                locale.render(Message::ReminderBody, &[("reminder", &reminder)]),
+            )?;
+
+            let task = SendReplyTask {
+                channel: Channel::Slack,
+                subject: locale.render(Message::ReminderSubject, &[("reminder", &reminder)]),
+                html_path: body_path,
+                attachments_dir: reminders_dir,
+                from: None,
+                // Posting to the user ID delivers the reminder as a DM from the app.
+                to: vec![message.sender.clone(), message.sender.clone()],
+                cc: Vec::new(),
+                bcc: Vec::new(),
+                in_reply_to: None,
+                references: None,
+                archive_root: None,
+                thread_epoch: None,
+                thread_state_path: None,
+                employee_id: Some(config.employee_profile.id.clone()),
+                idempotency_key: None,
+                trace_id: None,
+                scheduled: false,
+            };
+            let mut scheduler = Scheduler::load(&user_paths.tasks_db_path, ModuleExecutor)?;
+            let task_id = scheduler.add_one_shot_in(delay, TaskKind::SendReply(task))?;
+            index_store.sync_user_tasks(&user.user_id, scheduler.tasks())?;
+            let run_at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+            info!(
+                "scheduled slack reminder user_id={} task_id={} run_at={}",
+                user.user_id, task_id, run_at
+            );
+            locale.render(
+                Message::ReminderScheduled,
+                &[
+                    ("when", &format_relative(run_at, Utc::now(), locale)),
+                    ("at", &run_at.format("%Y-%m-%d %H:%M UTC")),
+                ],
+            )
+        }
+        SlackCommandAction::Ask(question) => {
+            let employee_name = config.employee_profile.display_name.as_deref();
+            match runtime.block_on(message_router.classify(&question, None, employee_name, None)) {
+                RouterDecision::Simple { response, .. } => response,
+                _ => locale.text(Message::NeedsAgent).to_string(),
+            }
+        }
+    };
+
+    let Some(response_url) = message.metadata.slack_response_url.as_deref() else {
+        warn!("slack slash command missing response_url; dropping reply");
+        return Ok(());
+    };
+    let body = serde_json::json!(SlackEphemeralResponse::new(reply));
+    runtime.block_on(post_to_response_url(response_url, &body))?;
+    Ok(())
+}
+
+fn format_status_reply(tasks: &[ScheduledTask], now: DateTime<Utc>, locale: Locale) -> String {
+    let snapshot = build_scheduler_snapshot(tasks, now);
+    if snapshot.total_enabled == 0 {
+        return locale.text(Message::NoTasks).to_string();
+    }
+    let mut reply = locale.render(
+        Message::StatusSummary,
+        &[
+            ("active", &snapshot.total_enabled),
+            ("due", &snapshot.due.len()),
+            ("upcoming", &snapshot.upcoming.len()),
+        ],
+    );
+    if let Some(next) = snapshot.upcoming.first() {
+        reply.push('\n');
+        reply.push_str(&locale.render(
+            Message::NextUp,
+            &[
+                ("when", &format_relative(next.next_run, now, locale)),
+                ("label", &next.label.as_deref().unwrap_or(&next.kind)),
+            ],
+        ));
+    }
+    reply
+}
+
+fn format_tasks_reply(tasks: &[ScheduledTask], now: DateTime<Utc>, locale: Locale) -> String {
+    let snapshot = build_scheduler_snapshot(tasks, now);
+    let entries: Vec<_> = snapshot
+        .due
+        .iter()
+        .chain(snapshot.upcoming.iter())
+        .collect();
+    if entries.is_empty() {
+        return locale.text(Message::NoUpcomingTasks).to_string();
+    }
+    let mut reply = locale.text(Message::UpcomingTasks).to_string();
+    for task in entries.iter().take(TASK_LIST_LIMIT) {
+        reply.push_str(&format!(
+            "\n• {} – {} ({})",
+            task.next_run.format("%Y-%m-%d %H:%M UTC"),
+            task.label.as_deref().unwrap_or(&task.kind),
+            task.status
+        ));
+    }
+    if entries.len() > TASK_LIST_LIMIT {
+        reply.push('\n');
+        reply.push_str(&locale.render(
+            Message::ListMore,
+            &[("count", &(entries.len() - TASK_LIST_LIMIT))],
+        ));
+    }
+    reply
+}
+
+fn format_relative(at: DateTime<Utc>, now: DateTime<Utc>, locale: Locale) -> String {
+    let minutes = (at - now).num_minutes();
+    if minutes <= 0 {
+        return locale.text(Message::Now).to_string();
+    }
+    let (message, count) = if minutes < 60 {
+        (Message::InMinutes, minutes)
+    } else if minutes < 48 * 60 {
+        (Message::InHours, minutes / 60)
+    } else {
+        (Message::InDays, minutes / (24 * 60))
+    };
+    locale.render(message, &[("count", &count)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::tests::run_task_in;
+    use crate::{RunTaskTask, Schedule};
+    use std::path::Path;
+
+    fn scheduled(thread_id: &str, run_at: DateTime<Utc>) -> ScheduledTask {
+        ScheduledTask {
+            id: Uuid::new_v4(),
+            kind: TaskKind::RunTask(RunTaskTask {
+                thread_id: Some(thread_id.to_string()),
+                ..run_task_in(Path::new("/tmp/workspace"))
+            }),
+            schedule: Schedule::OneShot { run_at },
+            enabled: true,
+            created_at: run_at,
+            last_run: None,
+            approval: None,
+            description: None,
+            ends: None,
+        }
+    }
+
+    #[test]
+    fn status_reply_handles_empty_scheduler() {
+        assert_eq!(
+            format_status_reply(&[], Utc::now(), Locale::En),
+            "You have no scheduled tasks."
+        );
+    }
+
+    #[test]
+    fn status_reply_counts_due_and_upcoming() {
+        let now = Utc::now();
+        let tasks = vec![
+            scheduled("slack:C1:1", now - chrono::Duration::minutes(1)),
+            scheduled("slack:C1:2", now + chrono::Duration::hours(3)),
+        ];
+        let reply = format_status_reply(&tasks, now, Locale::En);
+        assert!(reply.contains("2 active task(s), 1 due now, 1 in the next 7 days"));
+        assert!(reply.contains("slack:C1:2"));
+        let reply = format_status_reply(&tasks, now, Locale::Es);
+        assert!(reply.contains("2 tarea(s) activa(s)"));
+        assert!(reply.contains("Siguiente en 3 h: slack:C1:2"));
+    }
+
+    #[test]
+    fn tasks_reply_lists_in_run_order() {
+        let now = Utc::now();
+        let tasks = vec![
+            scheduled("later", now + chrono::Duration::days(2)),
+            scheduled("sooner", now + chrono::Duration::hours(1)),
+        ];
+        let reply = format_tasks_reply(&tasks, now, Locale::En);
+        let sooner = reply.find("sooner").unwrap();
+        let later = reply.find("later").unwrap();
+        assert!(sooner < later);
+    }
+}
@@ -0,0 +1,431 @@
+use std::collections::HashSet;
+
+use tracing::{info, warn};
+
+use crate::account_store::AccountStore;
+use crate::adapters::slack::post_to_response_url;
+use crate::approval_store::{get_global_approval_store, ApprovalDecision, ApprovalRecord};
+use crate::channel::{Channel, ChannelMetadata, InboundMessage};
+use crate::index_store::IndexStore;
+use crate::slack_action_store::{
+    get_global_slack_action_store, PendingActionOutcome, PendingSlackAction, SlackActionDecision,
+};
+use crate::user_store::UserStore;
+use crate::{ModuleExecutor, Scheduler};
+
+use super::super::approvals::apply_approval_decision;
+use super::super::config::ServiceConfig;
+use super::super::BoxError;
+use super::slack::enqueue_slack_run_task;
+
+/// Handle a Slack button click or modal submission for a pending action.
+///
+/// Clicks on approval requests for held tasks are handed to
+/// [`apply_approval_decision`]. Otherwise clicks from anyone but the requester
+/// or one of the employee's Slack approvers get an ephemeral refusal, and the
+/// pending action is claimed once; its outcome either starts a follow-up
+/// RunTask in the original thread or cancels scheduled tasks. The prompt message is then replaced with the
+/// decision so the buttons disappear.
+pub(crate) fn process_slack_interaction(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    account_store: &AccountStore,
+    runtime: &tokio::runtime::Handle,
+    message: &InboundMessage,
+) -> Result<(), BoxError> {
+    let callback_id = message
+        .metadata
+        .slack_callback_id
+        .as_deref()
+        .ok_or("missing slack callback_id")?;
+    let text = message.text_body.as_deref().unwrap_or("");
+    let (decision_raw, submitted) = text.split_once('\n').unwrap_or((text, ""));
+    let Some(decision) = SlackActionDecision::parse(decision_raw) else {
+        warn!(
+            "ignoring slack interaction callback_id={} with unknown decision {:?}",
+            callback_id, decision_raw
+        );
+        return Ok(());
+    };
+
+    if let Some(approvals) = get_global_approval_store() {
+        if let Some(record) = approvals
+            .get(callback_id)?
+            .filter(|record| record.approver.channel == Channel::Slack)
+        {
+            let profile = record
+                .employee_id
+                .as_deref()
+                .and_then(|id| config.employee_directory.employee(id))
+                .unwrap_or(&config.employee_profile);
+            return process_slack_approval(
+                index_store,
+                runtime,
+                message,
+                &record,
+                &profile.slack_approvers,
+                decision,
+            );
+        }
+    }
+
+    let Some(store) = get_global_slack_action_store() else {
+        return Err("slack action store unavailable".into());
+    };
+    if let Some(action) = store.get(callback_id)? {
+        let profile = action
+            .employee_id
+            .as_deref()
+            .and_then(|id| config.employee_directory.employee(id))
+            .unwrap_or(&config.employee_profile);
+        if !may_decide(&action, &message.sender, &profile.slack_approvers) {
+            warn!(
+                "slack interaction callback_id={} from {} who is neither the requester nor an approver",
+                callback_id, message.sender
+            );
+            respond(
+                runtime,
+                message,
+                serde_json::json!({
+                    "response_type": "ephemeral",
+                    "replace_original": false,
+                    "text": "You are not allowed to answer this request.",
+                }),
+            );
+            return Ok(());
+        }
+    }
+    let Some(action) = store.claim(callback_id, decision, &message.sender)? else {
+        info!(
+            "slack interaction callback_id={} already resolved or expired",
+            callback_id
+        );
+        respond(
+            runtime,
+            message,
+            serde_json::json!({
+                "response_type": "ephemeral",
+                "replace_original": false,
+                "text": "This request was already handled or has expired.",
+            }),
+        );
+        return Ok(());
+    };
+    info!(
+        "slack interaction callback_id={} decision={} by {}",
+        callback_id,
+        decision.as_str(),
+        message.sender
+    );
+
+    match action.outcome_for(decision) {
+        PendingActionOutcome::Noop => {}
+        PendingActionOutcome::FollowUpRunTask { instruction } => {
+            let follow_up = follow_up_message(&action, decision, message, instruction, submitted);
+            enqueue_slack_run_task(
+                config,
+                user_store,
+                index_store,
+                account_store,
+                &follow_up,
+                &message.raw_payload,
+            )?;
+        }
+        PendingActionOutcome::CancelTasks { task_ids } => {
+            let user = user_store.get_or_create_user("slack", &action.requester_user_id)?;
+            let user_paths = user_store.user_paths(&config.users_root, &user.user_id);
+            let ids: HashSet<&str> = task_ids.iter().map(String::as_str).collect();
+            let mut scheduler = Scheduler::load(&user_paths.tasks_db_path, ModuleExecutor)?;
+            let disabled =
+                scheduler.disable_tasks_by(|task| ids.contains(task.id.to_string().as_str()))?;
+            index_store.sync_user_tasks(&user.user_id, scheduler.tasks())?;
+            info!(
+                "slack interaction callback_id={} disabled {} task(s) for user {}",
+                callback_id, disabled, user.user_id
+            );
+        }
+    }
+
+    respond(
+        runtime,
+        message,
+        serde_json::json!({
+            "replace_original": true,
+            "text": decision_summary(&action, decision, &message.sender),
+        }),
+    );
+    Ok(())
+}
+
+fn process_slack_approval(
+    index_store: &IndexStore,
+    runtime: &tokio::runtime::Handle,
+    message: &InboundMessage,
+    record: &ApprovalRecord,
+    approvers: &[String],
+    decision: SlackActionDecision,
+) -> Result<(), BoxError> {
+    let approval_id = record.approval_id.as_str();
+    if !may_approve(record, &message.sender, approvers) {
+        warn!(
+            "slack approval {} clicked by {} who is neither the approver nor the requester",
+            approval_id, message.sender
+        );
+        respond(
+            runtime,
+            message,
+            serde_json::json!({
+                "response_type": "ephemeral",
+                "replace_original": false,
+                "text": "You are not allowed to answer this request.",
+            }),
+        );
+        return Ok(());
+    }
+    let decision = match decision {
+        SlackActionDecision::Approve => ApprovalDecision::Approve,
+        SlackActionDecision::Reject => ApprovalDecision::Reject,
+    };
+    let decided_by = format!("slack:{}", message.sender);
+    let body = match apply_approval_decision(index_store, approval_id, None, decision, &decided_by)?
+    {
+        Some(record) => serde_json::json!({
+            "replace_original": true,
+            "text": format!(
+                "{} by <@{}>: {}",
+                match decision {
+                    ApprovalDecision::Approve => "✅ Approved",
+                    ApprovalDecision::Reject => "❌ Rejected",
+                },
+                message.sender,
+                record.summary
+            ),
+        }),
+        None => serde_json::json!({
+            "response_type": "ephemeral",
+            "replace_original": false,
+            "text": "This request was already handled or has expired.",
+        }),
+    };
+    respond(runtime, message, body);
+    Ok(())
+}
+
+/// Only the user whose thread asked, or one of the employee's Slack
+/// approvers, may answer a confirmation.
+fn may_decide(action: &PendingSlackAction, user_id: &str, approvers: &[String]) -> bool {
+    action.requester_user_id == user_id || approvers.iter().any(|approver| approver == user_id)
+}
+
+/// A held task's approval may only be answered by its approver, the user
+/// whose thread raised it, or one of the employee's Slack approvers. The
+/// approver address may be a channel, whose members are not approvers.
+fn may_approve(record: &ApprovalRecord, user_id: &str, approvers: &[String]) -> bool {
+    record.approver.address == user_id
+        || record.requester.as_deref() == Some(user_id)
+        || approvers.iter().any(|approver| approver == user_id)
+}
+
+fn follow_up_message(
+    action: &PendingSlackAction,
+    decision: SlackActionDecision,
+    interaction: &InboundMessage,
+    instruction: &str,
+    submitted: &str,
+) -> InboundMessage {
+    let mut text = format!(
+        "{}\n\nRequested action: {}",
+        decision_summary(action, decision, &interaction.sender),
+        instruction
+    );
+    if !submitted.trim().is_empty() {
+        text.push_str("\n\nSubmitted values:\n");
+        text.push_str(submitted.trim());
+    }
+    let thread_ts = action
+        .thread_ts
+        .clone()
+        .unwrap_or_else(|| interaction.thread_id.clone());
+    InboundMessage {
+        channel: Channel::Slack,
+        // Route back to the requester's thread workspace, not the clicker's.
+        sender: action.requester_user_id.clone(),
+        sender_name: None,
+        recipient: action.channel_id.clone(),
+        subject: None,
+        text_body: Some(text),
+        html_body: None,
+        thread_id: thread_ts,
+        message_id: interaction.message_id.clone(),
+        attachments: Vec::new(),
+        reply_to: vec![action.channel_id.clone()],
+        raw_payload: interaction.raw_payload.clone(),
+        metadata: ChannelMetadata {
+            slack_channel_id: Some(action.channel_id.clone()),
+            slack_team_id: interaction
+                .metadata
+                .slack_team_id
+                .clone()
+                .or(action.team_id.clone()),
+            ..Default::default()
+        },
+    }
+}
+
+fn decision_summary(
+    action: &PendingSlackAction,
+    decision: SlackActionDecision,
+    user_id: &str,
+) -> String {
+    let verdict = match decision {
+        SlackActionDecision::Approve => "✅ Approved",
+        SlackActionDecision::Reject => "❌ Rejected",
+    };
+    format!("{} by <@{}>: {}", verdict, user_id, action.prompt)
+}
+
+fn respond(runtime: &tokio::runtime::Handle, message: &InboundMessage, body: serde_json::Value) {
+    let Some(response_url) = message.metadata.slack_response_url.as_deref() else {
+        return;
+    };
+    if let Err(err) = runtime.block_on(post_to_response_url(response_url, &body)) {
+        warn!("failed to update slack interaction message: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::approval_store::{ApprovalStatus, ApprovalStore, Approver};
+    use crate::slack_action_store::PendingActionStatus;
+    use chrono::Utc;
+
+    fn pending_action() -> PendingSlackAction {
+        PendingSlackAction {
+            callback_id: "cb-1".to_string(),
+            employee_id: None,
+            team_id: Some("T1".to_string()),
+            channel_id: "C1".to_string(),
+            thread_ts: Some("1700000000.000100".to_string()),
+            requester_user_id: "U_REQ".to_string(),
+            prompt: "Apply this edit?".to_string(),
+            on_approve: PendingActionOutcome::FollowUpRunTask {
+                instruction: "Apply the edit".to_string(),
+            },
+            on_reject: PendingActionOutcome::Noop,
+            status: PendingActionStatus::Approved,
+            resolved_by: Some("U_CLICK".to_string()),
+            created_at: Utc::now(),
+            expires_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn only_the_requester_or_an_approver_may_decide() {
+        let action = pending_action();
+        let approvers = vec!["U_LEAD".to_string()];
+
+        assert!(may_decide(&action, "U_REQ", &[]));
+        assert!(may_decide(&action, "U_LEAD", &approvers));
+        assert!(!may_decide(&action, "U_CLICK", &approvers));
+        assert!(!may_decide(&action, "U_LEAD", &[]));
+    }
+
+    fn interaction(sender: &str) -> InboundMessage {
+        InboundMessage {
+            channel: Channel::Slack,
+            sender: sender.to_string(),
+            sender_name: None,
+            recipient: "C1".to_string(),
+            subject: None,
+            text_body: Some("approve".to_string()),
+            html_body: None,
+            thread_id: "1700000000.000200".to_string(),
+            message_id: Some("1700000001.000000".to_string()),
+            attachments: Vec::new(),
+            reply_to: vec!["C1".to_string()],
+            raw_payload: b"payload=%7B%7D".to_vec(),
+            metadata: ChannelMetadata::default(),
+        }
+    }
+
+    fn pending_approval() -> ApprovalRecord {
+        let id = uuid::Uuid::new_v4().to_string();
+        ApprovalRecord {
+            approval_id: id.clone(),
+            token: "token".to_string(),
+            employee_id: None,
+            owner_user_id: None,
+            tasks_db_path: String::new(),
+            task_id: id,
+            kind: "send_email".to_string(),
+            summary: "Send the contract".to_string(),
+            approver: Approver {
+                channel: Channel::Slack,
+                address: "C1".to_string(),
+            },
+            requester: Some("U_REQ".to_string()),
+            status: ApprovalStatus::Pending,
+            decided_by: None,
+            created_at: Utc::now(),
+            expires_at: Utc::now() + chrono::Duration::hours(1),
+            held_envelope: None,
+        }
+    }
+
+    #[test]
+    fn only_the_approver_or_requester_may_approve_a_held_task() {
+        let record = pending_approval();
+        let approvers = vec!["U_LEAD".to_string()];
+
+        assert!(may_approve(&record, "U_REQ", &[]));
+        assert!(may_approve(&record, "U_LEAD", &approvers));
+        assert!(!may_approve(&record, "U_CLICK", &approvers));
+    }
+
+    #[test]
+    fn a_non_approver_click_leaves_the_approval_pending() {
+        let store = ApprovalStore::new().unwrap();
+        let index_store = IndexStore::new("unused").unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let record = pending_approval();
+        assert!(store.insert_if_absent(&record).unwrap());
+
+        process_slack_approval(
+            &index_store,
+            runtime.handle(),
+            &interaction("U_CLICK"),
+            &record,
+            &[],
+            SlackActionDecision::Approve,
+        )
+        .unwrap();
+
+        let stored = store.get(&record.approval_id).unwrap().unwrap();
+        assert_eq!(stored.status, ApprovalStatus::Pending);
+        assert_eq!(stored.decided_by, None);
+    }
+
+    #[test]
+    fn follow_up_message_targets_requester_thread() {
+        let interaction = interaction("U_CLICK");
+
+        let message = follow_up_message(
+            &pending_action(),
+            SlackActionDecision::Approve,
+            &interaction,
+            "Apply the edit",
+            "form.reason: looks good",
+        );
+
+        assert_eq!(message.sender, "U_REQ");
+        assert_eq!(message.thread_id, "1700000000.000100");
+        assert_eq!(message.metadata.slack_channel_id.as_deref(), Some("C1"));
+        assert_eq!(message.metadata.slack_team_id.as_deref(), Some("T1"));
+        let text = message.text_body.unwrap();
+        assert!(text.starts_with("✅ Approved by <@U_CLICK>: Apply this edit?"));
+        assert!(text.contains("Requested action: Apply the edit"));
+        assert!(text.contains("form.reason: looks good"));
+    }
+}
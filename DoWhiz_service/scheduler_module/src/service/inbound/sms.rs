@@ -1,5 +1,4 @@
 use std::path::Path;
-use std::time::Duration;
 
 use tracing::{info, warn};
 
@@ -11,7 +10,7 @@ use crate::{ModuleExecutor, RunTaskTask, Scheduler, TaskKind};
 use super::super::bump_thread_state;
 use super::super::config::ServiceConfig;
 use super::super::default_thread_state_path;
-use super::super::scheduler::cancel_pending_thread_tasks;
+use super::super::scheduler::{cancel_pending_thread_tasks, inbound_coalesce_delay};
 use super::super::workspace::ensure_thread_workspace;
 use super::super::BoxError;
 
@@ -52,20 +51,11 @@ pub(crate) fn process_sms_message(
         thread_state.last_email_seq,
     )?;
 
-    let model_name = match config.employee_profile.model.clone() {
-        Some(model) => model,
-        None => {
-            if config
-                .employee_profile
-                .runner
-                .eq_ignore_ascii_case("claude")
-            {
-                String::new()
-            } else {
-                config.codex_model.clone()
-            }
-        }
-    };
+    let model_name = config
+        .employee_profile
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model_for_runner(&config.employee_profile.runner));
 
     let reply_from = message
         .metadata
@@ -94,6 +84,8 @@ pub(crate) fn process_sms_message(
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     let mut scheduler = Scheduler::load(&user_paths.tasks_db_path, ModuleExecutor::default())?;
@@ -104,7 +96,13 @@ pub(crate) fn process_sms_message(
             err
         );
     }
-    let task_id = scheduler.add_one_shot_in(Duration::from_secs(0), TaskKind::RunTask(run_task))?;
+    let delay = inbound_coalesce_delay(
+        &scheduler,
+        &workspace,
+        &config.employee_profile.inbound_policy,
+        Channel::Sms,
+    );
+    let task_id = scheduler.add_one_shot_in(delay, TaskKind::RunTask(run_task))?;
     index_store.sync_user_tasks(&user.user_id, scheduler.tasks())?;
 
     info!(
@@ -171,6 +169,7 @@ mod tests {
     use crate::{ModuleExecutor, Scheduler, TaskKind};
     use std::collections::{HashMap, HashSet};
     use std::fs;
+    use std::time::Duration;
     use tempfile::TempDir;
 
     fn require_supabase_db_url() -> Option<String> {
@@ -212,11 +211,23 @@ mod tests {
             runtime_root: None,
             agents_path: None,
             claude_path: None,
+            gemini_path: None,
             soul_path: None,
             skills_dir: None,
             discord_enabled: false,
             slack_enabled: false,
             bluebubbles_enabled: false,
+            outbound_policy: Default::default(),
+            inbound_policy: Default::default(),
+            approver: None,
+            slack_approvers: Vec::new(),
+            redaction: None,
+            archive_tiering: None,
+            sandbox: None,
+            language: None,
+            mention_names: Vec::new(),
+            router: Default::default(),
+            formatting: Default::default(),
         };
         let mut employee_by_id = HashMap::new();
         employee_by_id.insert(employee.id.clone(), employee.clone());
@@ -243,6 +254,7 @@ mod tests {
             users_db_path: state_root.join("users.db"),
             task_index_path: state_root.join("task_index.db"),
             codex_model: "gpt-5.4".to_string(),
+            gemini_model: "gemini-2.5-pro".to_string(),
             codex_disabled: true,
             scheduler_poll_interval: Duration::from_millis(50),
             scheduler_max_concurrency: 1,
@@ -0,0 +1,133 @@
+//! "stop" / "cancel" / "unsubscribe" messages, answered without a model.
+//!
+//! The thread's queued and running work is cancelled and the user is muted:
+//! scheduled sends, digests and scheduled runs for them are skipped until
+//! they write again.
+
+use tracing::{info, warn};
+
+use crate::i18n::{user_locale, Message};
+use crate::message_router::RouterDecision;
+use crate::user_store::{UserPref, UserStore};
+use crate::{ModuleExecutor, Scheduler};
+
+use super::super::bump_thread_state;
+use super::super::config::ServiceConfig;
+use super::super::default_thread_state_path;
+use super::super::scheduler::cancel_pending_thread_tasks;
+use super::super::workspace::thread_workspace_name;
+use super::super::BoxError;
+
+/// Whole messages that ask us to stop, after lowercasing and dropping
+/// punctuation. A longer message mentioning "stop" goes to the model.
+const STOP_PHRASES: &[&str] = &[
+    "stop",
+    "stop it",
+    "stop that",
+    "stop all",
+    "stop please",
+    "please stop",
+    "stop messaging me",
+    "cancel",
+    "cancel it",
+    "cancel that",
+    "cancel all",
+    "unsubscribe",
+];
+
+pub(super) fn is_stop_request(text: &str) -> bool {
+    let normalized = text
+        .chars()
+        .filter(|ch| ch.is_alphanumeric() || ch.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    let normalized = normalized.split_whitespace().collect::<Vec<_>>().join(" ");
+    STOP_PHRASES.contains(&normalized.as_str())
+}
+
+/// Handle `text` if it is a stop request from `user_id`, the owner of the
+/// thread. Any other message lifts an earlier mute and returns `None`, so the
+/// caller routes it as usual.
+pub(super) fn answer_stop_request(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    user_id: &str,
+    thread_key: &str,
+    text: &str,
+) -> Option<RouterDecision> {
+    let stop = is_stop_request(text);
+    if !stop {
+        match user_store.get_pref(user_id) {
+            Ok(preferences) if preferences.muted => {
+                match user_store.set_pref(user_id, UserPref::Muted(false)) {
+                    Ok(_) => info!("user {} wrote again; unmuted", user_id),
+                    Err(err) => warn!("failed to unmute user {}: {}", user_id, err),
+                }
+            }
+            Ok(_) => {}
+            Err(err) => warn!("failed to load preferences for user {}: {}", user_id, err),
+        }
+        return None;
+    }
+
+    if let Err(err) = user_store.set_pref(user_id, UserPref::Muted(true)) {
+        warn!("failed to mute user {}: {}", user_id, err);
+    }
+    let user_paths = user_store.user_paths(&config.users_root, user_id);
+    match cancel_thread(&user_paths, thread_key) {
+        Ok(cancelled) => info!(
+            "stop request from user {} thread={}: muted, cancelled {} task(s)",
+            user_id, thread_key, cancelled
+        ),
+        Err(err) => warn!(
+            "stop request from user {} thread={}: failed to cancel tasks: {}",
+            user_id, thread_key, err
+        ),
+    }
+    let locale = user_locale(user_id, config.employee_profile.language.as_deref());
+    Some(RouterDecision::Simple {
+        response: locale.text(Message::StopConfirmed).to_string(),
+        memory_update: None,
+    })
+}
+
+/// Bump the thread's epoch, which stops a run in progress and drops its
+/// replies, and disable the thread's pending tasks.
+fn cancel_thread(
+    user_paths: &crate::user_store::UserPaths,
+    thread_key: &str,
+) -> Result<usize, BoxError> {
+    let workspace = user_paths
+        .workspaces_root
+        .join(thread_workspace_name(thread_key));
+    if !workspace.exists() {
+        return Ok(0);
+    }
+    let thread_state = bump_thread_state(&default_thread_state_path(&workspace), thread_key, None)?;
+    let mut scheduler = Scheduler::load(&user_paths.tasks_db_path, ModuleExecutor)?;
+    Ok(cancel_pending_thread_tasks(
+        &mut scheduler,
+        &workspace,
+        thread_state.epoch,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_stop_request;
+
+    #[test]
+    fn only_whole_stop_messages_are_stop_requests() {
+        for text in ["stop", "STOP!", " Cancel. ", "Please stop", "unsubscribe"] {
+            assert!(is_stop_request(text), "{text:?}");
+        }
+        for text in [
+            "",
+            "don't stop",
+            "how do I cancel my subscription?",
+            "stop the meeting reminder for friday",
+        ] {
+            assert!(!is_stop_request(text), "{text:?}");
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use std::path::Path;
-use std::time::Duration;
 
 use tracing::{info, warn};
 
@@ -11,7 +10,7 @@ use crate::{ModuleExecutor, RunTaskTask, Scheduler, TaskKind};
 use super::super::bump_thread_state;
 use super::super::config::ServiceConfig;
 use super::super::default_thread_state_path;
-use super::super::scheduler::cancel_pending_thread_tasks;
+use super::super::scheduler::{cancel_pending_thread_tasks, inbound_coalesce_delay};
 use super::super::workspace::ensure_thread_workspace;
 use super::super::BoxError;
 
@@ -65,20 +64,11 @@ pub(crate) fn process_telegram_event(
     )?;
 
     // Determine model and runner
-    let model_name = match config.employee_profile.model.clone() {
-        Some(model) => model,
-        None => {
-            if config
-                .employee_profile
-                .runner
-                .eq_ignore_ascii_case("claude")
-            {
-                String::new()
-            } else {
-                config.codex_model.clone()
-            }
-        }
-    };
+    let model_name = config
+        .employee_profile
+        .model
+        .clone()
+        .unwrap_or_else(|| config.default_model_for_runner(&config.employee_profile.runner));
 
     info!(
         "workspace ready at {} for user {} thread={} epoch={}",
@@ -110,6 +100,8 @@ pub(crate) fn process_telegram_event(
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     // Schedule the task
@@ -121,7 +113,13 @@ pub(crate) fn process_telegram_event(
             err
         );
     }
-    let task_id = scheduler.add_one_shot_in(Duration::from_secs(0), TaskKind::RunTask(run_task))?;
+    let delay = inbound_coalesce_delay(
+        &scheduler,
+        &workspace,
+        &config.employee_profile.inbound_policy,
+        Channel::Telegram,
+    );
+    let task_id = scheduler.add_one_shot_in(delay, TaskKind::RunTask(run_task))?;
     index_store.sync_user_tasks(&user.user_id, scheduler.tasks())?;
 
     info!(
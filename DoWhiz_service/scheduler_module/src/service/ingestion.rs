@@ -1,44 +1,74 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::future::Future;
 use std::sync::Arc;
-use std::thread;
+use std::time::{Duration, Instant};
 
+use chrono::Utc;
 use serde_json::json;
-use tracing::{info, warn};
+use tokio::sync::watch;
+use tokio::task;
+use tracing::{error, info, info_span, warn};
 
 use crate::account_store::AccountStore;
+use crate::alerting::{self, Alert, AlertKind};
 use crate::channel::Channel;
 use crate::index_store::IndexStore;
-use crate::ingestion::IngestionEnvelope;
-use crate::ingestion_queue::IngestionQueue;
+use crate::ingestion::{IngestionEnvelope, IngestionPriority};
+use crate::ingestion_queue::{IngestionQueue, IngestionQueueError, QueuedEnvelope};
 use crate::message_router::MessageRouter;
 use crate::slack_store::SlackStore;
+use crate::telemetry;
+use crate::trace_context;
 use crate::user_store::UserStore;
 
 use super::config::ServiceConfig;
 use super::email::{process_inbound_payload, PostmarkInbound};
 use super::inbound::{
-    process_bluebubbles_event, process_discord_inbound_message, process_google_workspace_message,
-    process_notion_message, process_slack_event, process_sms_message, process_telegram_event,
-    process_wechat_event, process_whatsapp_event, try_quick_response_bluebubbles,
-    try_quick_response_discord, try_quick_response_google_workspace, try_quick_response_slack,
-    try_quick_response_telegram, try_quick_response_wechat, try_quick_response_whatsapp,
+    process_bluebubbles_event, process_chat_reaction, process_discord_inbound_message,
+    process_google_workspace_message, process_internal_message, process_jira_event,
+    process_notion_message, process_slack_event, process_slack_interaction,
+    process_slack_slash_command, process_sms_message, process_telegram_event, process_wechat_event,
+    process_whatsapp_event, try_quick_response_bluebubbles, try_quick_response_discord,
+    try_quick_response_google_workspace, try_quick_response_slack, try_quick_response_telegram,
+    try_quick_response_wechat, try_quick_response_whatsapp,
 };
+use super::ingestion_lanes::{ingestion_concurrency, IngestionLanes};
+use super::readiness::LoopPulse;
+use super::scheduler::sleep_or_stop;
+use super::sender_gate::admit_sender;
+use super::spam::{screen_inbound_email, SpamVerdict};
 use super::BoxError;
 
+/// First delay before a crashed ingestion consumer is restarted
+const CONSUMER_RESTART_MIN_SECS: u64 = 1;
+/// Longest delay between restarts of a consumer that keeps crashing
+const CONSUMER_RESTART_MAX_SECS: u64 = 60;
+/// A consumer that ran this long before crashing restarts after the first delay again
+const CONSUMER_HEALTHY_RUN_SECS: u64 = 300;
+
 pub(super) struct IngestionControl {
-    stop: Arc<AtomicBool>,
-    handle: Option<thread::JoinHandle<()>>,
+    stop: watch::Sender<bool>,
+    handle: Option<task::JoinHandle<()>>,
+    /// Ticked before every claim.
+    pub(super) pulse: Arc<LoopPulse>,
 }
 
 impl IngestionControl {
-    pub(super) fn stop_and_join(&mut self) {
-        self.stop.store(true, Ordering::Relaxed);
+    /// Stop claiming new envelopes and wait for the ones in flight to finish.
+    pub(super) async fn stop_and_join(&mut self) {
+        let _ = self.stop.send(true);
         if let Some(handle) = self.handle.take() {
-            let _ = handle.join();
+            let _ = handle.await;
         }
     }
 }
 
+/// Consume the employee's queue on the Tokio runtime.
+///
+/// Envelopes are processed on `INGESTION_CONCURRENCY` lanes, one at a time
+/// and in claim order per conversation (see [`IngestionLanes`]). Queue claims
+/// and processing run on the blocking pool, where quick responses can
+/// `block_on` their HTTP calls without stalling a runtime worker.
+/// The loop is supervised: if it panics it is restarted with backoff.
 pub(super) fn spawn_ingestion_consumer(
     config: std::sync::Arc<ServiceConfig>,
     queue: std::sync::Arc<dyn IngestionQueue>,
@@ -49,71 +79,268 @@ pub(super) fn spawn_ingestion_consumer(
     account_store: std::sync::Arc<AccountStore>,
 ) -> Result<IngestionControl, BoxError> {
     let poll_interval = config.ingestion_poll_interval;
+    let concurrency = ingestion_concurrency();
     let employee_id = config.employee_id.clone();
-    let runtime = tokio::runtime::Handle::current();
-    let stop = Arc::new(AtomicBool::new(false));
-    let stop_thread = stop.clone();
+    let consumer = Arc::new(IngestionConsumer {
+        employee_id: config.employee_id.clone(),
+        config,
+        queue,
+        user_store,
+        index_store,
+        slack_store,
+        message_router,
+        account_store,
+        runtime: tokio::runtime::Handle::current(),
+    });
+    let (stop, stop_rx) = watch::channel(false);
+    let pulse = Arc::new(LoopPulse::default());
+
+    let loop_pulse = pulse.clone();
+    let handle = task::spawn(supervise(
+        employee_id,
+        pulse.clone(),
+        stop_rx,
+        RestartBackoff::default(),
+        move |stop_rx| {
+            consume(
+                consumer.clone(),
+                loop_pulse.clone(),
+                poll_interval,
+                concurrency,
+                stop_rx,
+            )
+        },
+    ));
+
+    Ok(IngestionControl {
+        stop,
+        handle: Some(handle),
+        pulse,
+    })
+}
+
+/// Delay before restarting a crashed consumer: doubles from `min` up to
+/// `max`, and starts over once a run lasted `reset_after`.
+#[derive(Debug, Clone, Copy)]
+struct RestartBackoff {
+    min: Duration,
+    max: Duration,
+    reset_after: Duration,
+}
+
+impl Default for RestartBackoff {
+    fn default() -> Self {
+        Self {
+            min: Duration::from_secs(CONSUMER_RESTART_MIN_SECS),
+            max: Duration::from_secs(CONSUMER_RESTART_MAX_SECS),
+            reset_after: Duration::from_secs(CONSUMER_HEALTHY_RUN_SECS),
+        }
+    }
+}
+
+/// Run the consumer loop made by `run` until stopped, restarting it with
+/// backoff whenever it panics. A loop only returns once stopped, so a join
+/// error is a crash; each one is recorded on `pulse`, which fails readiness
+/// until the restarted loop ticks, and raises an alert.
+async fn supervise<F, Fut>(
+    employee_id: String,
+    pulse: Arc<LoopPulse>,
+    mut stop_rx: watch::Receiver<bool>,
+    backoff: RestartBackoff,
+    mut run: F,
+) where
+    F: FnMut(watch::Receiver<bool>) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let mut delay = backoff.min;
+    loop {
+        let started = Instant::now();
+        let err = match task::spawn(run(stop_rx.clone())).await {
+            Ok(()) => return,
+            Err(err) => err,
+        };
+        if started.elapsed() >= backoff.reset_after {
+            delay = backoff.min;
+        }
+        pulse.crash(err.to_string());
+        error!(
+            "ingestion consumer of {} crashed, restarting in {}ms: {}",
+            employee_id,
+            delay.as_millis(),
+            err
+        );
+        alerting::raise(
+            Alert::new(
+                AlertKind::IngestionConsumerDied,
+                &employee_id,
+                format!("Ingestion consumer of {} crashed", employee_id),
+            )
+            .detail("error", &err)
+            .detail("restarts", pulse.restarts())
+            .detail("restart_in_ms", delay.as_millis()),
+        );
+        if sleep_or_stop(delay, &mut stop_rx).await {
+            return;
+        }
+        delay = (delay * 2).min(backoff.max);
+    }
+}
 
-    let handle = thread::spawn(move || loop {
-        if stop_thread.load(Ordering::Relaxed) {
+/// Claim envelopes until stopped, processing them on the consumer's lanes,
+/// then wait for the ones in flight.
+async fn consume(
+    consumer: Arc<IngestionConsumer>,
+    pulse: Arc<LoopPulse>,
+    poll_interval: Duration,
+    concurrency: usize,
+    mut stop_rx: watch::Receiver<bool>,
+) {
+    let processing = consumer.clone();
+    let lanes = IngestionLanes::start(concurrency, move |item| processing.process(item));
+    while !*stop_rx.borrow() {
+        pulse.tick();
+        let slot = lanes.slot().await;
+        if *stop_rx.borrow() {
             break;
         }
-        match queue.claim_next(&employee_id) {
-            Ok(Some(item)) => {
-                info!(
-                    "ingestion claimed envelope for employee={} channel={:?}",
-                    employee_id, item.envelope.channel
-                );
-                match process_ingestion_envelope(
-                    &config,
-                    &user_store,
-                    &index_store,
-                    &slack_store,
-                    &message_router,
-                    &account_store,
-                    &runtime,
-                    &item.envelope,
-                ) {
-                    Ok(_) => {
-                        info!(
-                            "ingestion processed successfully for employee={}",
-                            employee_id
-                        );
-                        if let Err(err) = queue.mark_done(&item.id) {
-                            warn!("failed to mark envelope done: {}", err);
-                        }
-                    }
-                    Err(err) => {
-                        warn!(
-                            "ingestion processing failed for employee={}: {}",
-                            employee_id, err
-                        );
-                        if let Err(mark_err) = queue.mark_failed(&item.id, &err.to_string()) {
-                            warn!("failed to mark envelope failed: {}", mark_err);
-                        }
-                    }
+        let claiming = consumer.clone();
+        let claimed = task::spawn_blocking(move || claiming.claim_next()).await;
+        match claimed {
+            Ok(Ok(Claim::Envelope(item))) => lanes.dispatch(*item, slot),
+            Ok(Ok(Claim::Shed)) => drop(slot),
+            Ok(Ok(Claim::Empty)) => {
+                drop(slot);
+                if sleep_or_stop(poll_interval, &mut stop_rx).await {
+                    break;
                 }
             }
-            Ok(None) => {
-                if stop_thread.load(Ordering::Relaxed) {
+            Ok(Err(err)) => {
+                drop(slot);
+                if *stop_rx.borrow() {
+                    break;
+                }
+                warn!("ingestion queue claim error: {}", err);
+                if sleep_or_stop(poll_interval, &mut stop_rx).await {
                     break;
                 }
-                thread::sleep(poll_interval);
             }
             Err(err) => {
-                if stop_thread.load(Ordering::Relaxed) {
+                drop(slot);
+                warn!("ingestion queue claim task failed: {}", err);
+                if sleep_or_stop(poll_interval, &mut stop_rx).await {
                     break;
                 }
-                warn!("ingestion queue claim error: {}", err);
-                thread::sleep(poll_interval);
             }
         }
-    });
+    }
+    lanes.join().await;
+}
 
-    Ok(IngestionControl {
-        stop,
-        handle: Some(handle),
-    })
+enum Claim {
+    Envelope(Box<QueuedEnvelope>),
+    /// A stale envelope was dropped; claim again right away.
+    Shed,
+    Empty,
+}
+
+/// `INGESTION_SHED_STALE_SECS`: background envelopes that waited longer are
+/// dropped without processing. Unset or 0 processes every envelope.
+fn shed_stale_after() -> Option<Duration> {
+    std::env::var("INGESTION_SHED_STALE_SECS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+fn is_stale(envelope: &IngestionEnvelope, waited: Duration, shed_after: Option<Duration>) -> bool {
+    envelope.priority() == IngestionPriority::Background
+        && shed_after.is_some_and(|after| waited >= after)
+}
+
+struct IngestionConsumer {
+    employee_id: String,
+    config: Arc<ServiceConfig>,
+    queue: Arc<dyn IngestionQueue>,
+    user_store: Arc<UserStore>,
+    index_store: Arc<IndexStore>,
+    slack_store: Arc<SlackStore>,
+    message_router: Arc<MessageRouter>,
+    account_store: Arc<AccountStore>,
+    runtime: tokio::runtime::Handle,
+}
+
+impl IngestionConsumer {
+    fn claim_next(&self) -> Result<Claim, IngestionQueueError> {
+        let Some(item) = self.queue.claim_next(&self.employee_id)? else {
+            return Ok(Claim::Empty);
+        };
+        let envelope = &item.envelope;
+        let waited = envelope.queued_for(Utc::now());
+        telemetry::record_queue_latency(envelope.channel, envelope.priority().as_str(), waited);
+        if !is_stale(envelope, waited, shed_stale_after()) {
+            return Ok(Claim::Envelope(Box::new(item)));
+        }
+        warn!(
+            "shedding stale {} envelope {} for employee={} after {}s in queue",
+            envelope.channel,
+            envelope.envelope_id,
+            self.employee_id,
+            waited.as_secs()
+        );
+        telemetry::record_shed(envelope.channel);
+        if let Err(err) = self.queue.mark_done(&item.id) {
+            warn!("failed to mark shed envelope done: {}", err);
+        }
+        Ok(Claim::Shed)
+    }
+
+    fn process(&self, item: QueuedEnvelope) {
+        let _trace = trace_context::enter(&item.envelope.trace_id());
+        // The user is noted once the envelope's sender is resolved.
+        let _tenant = telemetry::tenant_scope();
+        let _span = info_span!(
+            "ingestion.process",
+            employee_id = %self.employee_id,
+            channel = %item.envelope.channel
+        )
+        .entered();
+        info!(
+            "ingestion claimed envelope for employee={} channel={:?}",
+            self.employee_id, item.envelope.channel
+        );
+        match process_ingestion_envelope(
+            &self.config,
+            &self.user_store,
+            &self.index_store,
+            &self.slack_store,
+            &self.message_router,
+            &self.account_store,
+            &self.runtime,
+            &item.envelope,
+        ) {
+            Ok(_) => {
+                telemetry::record_ingestion(item.envelope.channel, true);
+                info!(
+                    "ingestion processed successfully for employee={}",
+                    self.employee_id
+                );
+                if let Err(err) = self.queue.mark_done(&item.id) {
+                    warn!("failed to mark envelope done: {}", err);
+                }
+            }
+            Err(err) => {
+                telemetry::record_ingestion(item.envelope.channel, false);
+                warn!(
+                    "ingestion processing failed for employee={}: {}",
+                    self.employee_id, err
+                );
+                if let Err(mark_err) = self.queue.mark_failed(&item.id, &err.to_string()) {
+                    warn!("failed to mark envelope failed: {}", mark_err);
+                }
+            }
+        }
+    }
 }
 
 fn process_ingestion_envelope(
@@ -126,6 +353,16 @@ fn process_ingestion_envelope(
     runtime: &tokio::runtime::Handle,
     envelope: &IngestionEnvelope,
 ) -> Result<(), BoxError> {
+    if let Some(handoff) = envelope.handoff.as_ref() {
+        info!(
+            "envelope {} was handed off by employee {} from {}",
+            envelope.envelope_id, handoff.from_employee_id, handoff.from_channel
+        );
+    }
+    // Email is screened for spam first, so spam never reaches an approver.
+    if envelope.channel != Channel::Email && !admit_sender(config, user_store, envelope) {
+        return Ok(());
+    }
     match envelope.channel {
         Channel::Email => {
             let (payload, raw_payload) = resolve_email_payload(envelope)?;
@@ -160,6 +397,21 @@ fn process_ingestion_envelope(
                 );
                 return Ok(());
             }
+            match screen_inbound_email(&payload) {
+                SpamVerdict::Accept => {}
+                SpamVerdict::Spam { score, reasons } => {
+                    info!(
+                        "dropping inbound email as spam from {} score={} reasons={}",
+                        sender,
+                        score,
+                        reasons.join(", ")
+                    );
+                    return Ok(());
+                }
+            }
+            if !admit_sender(config, user_store, envelope) {
+                return Ok(());
+            }
             process_inbound_payload(
                 config,
                 user_store,
@@ -171,8 +423,38 @@ fn process_ingestion_envelope(
             )
         }
         Channel::Slack => {
-            info!("processing slack envelope, trying quick response first");
             let message = envelope.to_inbound_message();
+            if message.metadata.reaction_control.is_some() {
+                return process_chat_reaction(
+                    config,
+                    user_store,
+                    index_store,
+                    account_store,
+                    runtime,
+                    &message,
+                );
+            }
+            if message.metadata.slack_callback_id.is_some() {
+                return process_slack_interaction(
+                    config,
+                    user_store,
+                    index_store,
+                    account_store,
+                    runtime,
+                    &message,
+                );
+            }
+            if message.metadata.slack_command.is_some() {
+                return process_slack_slash_command(
+                    config,
+                    user_store,
+                    index_store,
+                    message_router,
+                    runtime,
+                    &message,
+                );
+            }
+            info!("processing slack envelope, trying quick response first");
             if try_quick_response_slack(
                 config,
                 user_store,
@@ -217,6 +499,16 @@ fn process_ingestion_envelope(
         }
         Channel::Discord => {
             let message = envelope.to_inbound_message();
+            if message.metadata.reaction_control.is_some() {
+                return process_chat_reaction(
+                    config,
+                    user_store,
+                    index_store,
+                    account_store,
+                    runtime,
+                    &message,
+                );
+            }
             let raw_payload = envelope.raw_payload_bytes();
             if try_quick_response_discord(
                 config,
@@ -300,6 +592,15 @@ Channel::Notion => {
             let raw_payload = envelope.raw_payload_bytes();
             process_wechat_event(config, user_store, index_store, &message, &raw_payload)
         }
+        Channel::Jira => {
+            let message = envelope.to_inbound_message();
+            let raw_payload = envelope.raw_payload_bytes();
+            process_jira_event(config, user_store, index_store, &message, &raw_payload)
+        }
+        Channel::Internal => {
+            let message = envelope.to_inbound_message();
+            process_internal_message(config, user_store, index_store, &message)
+        }
     }
 }
 
@@ -391,14 +692,98 @@ fn resolve_email_payload(
 #[cfg(test)]
 mod tests {
     use super::{
-        is_blacklisted_email_sender, is_human_approval_gate_subject, resolve_email_payload,
+        is_blacklisted_email_sender, is_human_approval_gate_subject, is_stale,
+        resolve_email_payload, supervise, LoopPulse, RestartBackoff,
     };
     use crate::channel::{Attachment, Channel, ChannelMetadata};
     use crate::ingestion::{IngestionEnvelope, IngestionPayload};
     use chrono::Utc;
     use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::watch;
     use uuid::Uuid;
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn supervisor_restarts_a_crashed_consumer_until_stopped() {
+        let pulse = Arc::new(LoopPulse::default());
+        let (stop, stop_rx) = watch::channel(false);
+        let runs = Arc::new(AtomicUsize::new(0));
+        let backoff = RestartBackoff {
+            min: Duration::from_millis(10),
+            max: Duration::from_millis(20),
+            reset_after: Duration::from_secs(60),
+        };
+
+        let loop_pulse = pulse.clone();
+        let loop_runs = runs.clone();
+        let supervisor = tokio::spawn(supervise(
+            "little_bear".to_string(),
+            pulse.clone(),
+            stop_rx,
+            backoff,
+            move |mut stop_rx| {
+                let pulse = loop_pulse.clone();
+                let run = loop_runs.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if run < 2 {
+                        panic!("dedupe store unavailable");
+                    }
+                    pulse.tick();
+                    let _ = stop_rx.wait_for(|stopped| *stopped).await;
+                }
+            },
+        ));
+
+        while runs.load(Ordering::SeqCst) < 3 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(pulse.restarts(), 2);
+        let _ = stop.send(true);
+        tokio::time::timeout(Duration::from_secs(5), supervisor)
+            .await
+            .expect("supervisor stops")
+            .expect("supervisor task");
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn only_background_envelopes_past_the_threshold_are_stale() {
+        let envelope = |channel| IngestionEnvelope {
+            envelope_id: Uuid::new_v4(),
+            received_at: Utc::now(),
+            tenant_id: None,
+            employee_id: "little_bear".to_string(),
+            channel,
+            external_message_id: None,
+            dedupe_key: "dedupe-stale".to_string(),
+            payload: IngestionPayload {
+                sender: "U1".to_string(),
+                sender_name: None,
+                recipient: "little_bear".to_string(),
+                subject: None,
+                text_body: None,
+                html_body: None,
+                thread_id: "thread-1".to_string(),
+                message_id: None,
+                attachments: Vec::new(),
+                reply_to: Vec::new(),
+                metadata: ChannelMetadata::default(),
+            },
+            raw_payload_ref: None,
+            account_id: None,
+            trace_id: None,
+            handoff: None,
+        };
+        let hour = Duration::from_secs(3600);
+        let jira = envelope(Channel::Jira);
+        assert!(is_stale(&jira, hour, Some(hour)));
+        assert!(!is_stale(&jira, hour - Duration::from_secs(1), Some(hour)));
+        assert!(!is_stale(&jira, hour * 24, None));
+        assert!(!is_stale(&envelope(Channel::Slack), hour * 24, Some(hour)));
+    }
+
     #[test]
     fn resolve_email_payload_builds_fallback_from_ingestion_payload() {
         let envelope = IngestionEnvelope {
@@ -432,6 +817,8 @@ mod tests {
             },
             raw_payload_ref: None,
             account_id: None,
+            trace_id: None,
+            handoff: None,
         };
 
         let (payload, raw) =
@@ -510,6 +897,8 @@ mod tests {
             },
             raw_payload_ref: None,
             account_id: Some(account_id),
+            trace_id: None,
+            handoff: None,
         };
 
         let json = serde_json::to_string(&envelope).expect("serialize");
@@ -540,4 +929,26 @@ mod tests {
         let envelope: IngestionEnvelope = serde_json::from_str(json).expect("deserialize");
         assert_eq!(envelope.account_id, None);
     }
+
+    #[test]
+    fn ingestion_envelope_without_trace_id_falls_back_to_envelope_id() {
+        let json = r#"{
+            "envelope_id": "00000000-0000-0000-0000-000000000001",
+            "received_at": "2026-03-17T00:00:00Z",
+            "tenant_id": "test",
+            "employee_id": "little_bear",
+            "channel": "email",
+            "external_message_id": null,
+            "dedupe_key": "dedupe",
+            "payload": {
+                "sender": "sender@example.com",
+                "recipient": "oliver@dowhiz.com",
+                "thread_id": "thread"
+            }
+        }"#;
+
+        let envelope: IngestionEnvelope = serde_json::from_str(json).expect("deserialize");
+        assert_eq!(envelope.trace_id, None);
+        assert_eq!(envelope.trace_id(), "00000000000000000000000000000001");
+    }
 }
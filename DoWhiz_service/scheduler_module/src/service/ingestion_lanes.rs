@@ -0,0 +1,207 @@
+//! Hash-partitioned lanes for concurrent ingestion.
+//!
+//! The consumer hands each claimed envelope to one of `INGESTION_CONCURRENCY`
+//! lanes (default 1), chosen by hashing its conversation: the channel and
+//! thread id, or the sender when an envelope has no thread id. A lane
+//! processes its envelopes one at a time in claim order, so the messages of
+//! one thread keep their order while other conversations run alongside.
+//!
+//! The consumer only claims while fewer than `INGESTION_CONCURRENCY`
+//! envelopes are in flight, so a claimed envelope waits at most for the one
+//! ahead of it in its lane and its queue lease does not run out.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio::task::{self, JoinHandle};
+use tracing::warn;
+
+use crate::ingestion::IngestionEnvelope;
+use crate::ingestion_queue::QueuedEnvelope;
+
+const DEFAULT_CONCURRENCY: usize = 1;
+const MAX_CONCURRENCY: usize = 64;
+
+type Handler = Arc<dyn Fn(QueuedEnvelope) + Send + Sync>;
+
+/// `INGESTION_CONCURRENCY`, between 1 and 64.
+pub(super) fn ingestion_concurrency() -> usize {
+    std::env::var("INGESTION_CONCURRENCY")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .min(MAX_CONCURRENCY)
+}
+
+/// The lane of `envelope` out of `lanes`.
+fn lane_index(envelope: &IngestionEnvelope, lanes: usize) -> usize {
+    let thread = envelope.payload.thread_id.trim();
+    let conversation = if thread.is_empty() {
+        envelope.payload.sender.trim()
+    } else {
+        thread
+    };
+    let mut hasher = DefaultHasher::new();
+    envelope.channel.to_string().hash(&mut hasher);
+    conversation.hash(&mut hasher);
+    (hasher.finish() % lanes as u64) as usize
+}
+
+pub(super) struct IngestionLanes {
+    senders: Vec<mpsc::UnboundedSender<(QueuedEnvelope, OwnedSemaphorePermit)>>,
+    handles: Vec<JoinHandle<()>>,
+    slots: Arc<Semaphore>,
+}
+
+impl IngestionLanes {
+    /// Start `count` lanes that run `handler` on the blocking pool.
+    pub(super) fn start(
+        count: usize,
+        handler: impl Fn(QueuedEnvelope) + Send + Sync + 'static,
+    ) -> Self {
+        let count = count.max(1);
+        let handler: Handler = Arc::new(handler);
+        let mut senders = Vec::with_capacity(count);
+        let mut handles = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (sender, mut receiver) =
+                mpsc::unbounded_channel::<(QueuedEnvelope, OwnedSemaphorePermit)>();
+            let handler = handler.clone();
+            handles.push(task::spawn(async move {
+                while let Some((item, slot)) = receiver.recv().await {
+                    let handler = handler.clone();
+                    if let Err(err) = task::spawn_blocking(move || handler(item)).await {
+                        warn!("ingestion processing task failed: {}", err);
+                    }
+                    drop(slot);
+                }
+            }));
+            senders.push(sender);
+        }
+        Self {
+            senders,
+            handles,
+            slots: Arc::new(Semaphore::new(count)),
+        }
+    }
+
+    /// Wait until fewer envelopes than lanes are in flight.
+    pub(super) async fn slot(&self) -> OwnedSemaphorePermit {
+        self.slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("ingestion lane semaphore is never closed")
+    }
+
+    /// Queue `item` on its lane; `slot` is released once it is processed.
+    pub(super) fn dispatch(&self, item: QueuedEnvelope, slot: OwnedSemaphorePermit) {
+        let lane = lane_index(&item.envelope, self.senders.len());
+        if self.senders[lane].send((item, slot)).is_err() {
+            warn!(
+                "ingestion lane {} is closed; envelope left to its lease",
+                lane
+            );
+        }
+    }
+
+    /// Let every lane finish the envelopes it was given.
+    pub(super) async fn join(self) {
+        drop(self.senders);
+        for handle in self.handles {
+            let _ = handle.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::Channel;
+    use crate::ingestion::IngestionPayload;
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    fn queued(thread_id: &str, sender: &str, seq: usize) -> QueuedEnvelope {
+        QueuedEnvelope {
+            id: Uuid::new_v4(),
+            envelope: IngestionEnvelope {
+                envelope_id: Uuid::new_v4(),
+                received_at: Utc::now(),
+                tenant_id: None,
+                employee_id: "little_bear".to_string(),
+                channel: Channel::Slack,
+                external_message_id: Some(seq.to_string()),
+                dedupe_key: format!("dedupe-{}", seq),
+                payload: IngestionPayload {
+                    sender: sender.to_string(),
+                    sender_name: None,
+                    recipient: "little_bear".to_string(),
+                    subject: None,
+                    text_body: Some(seq.to_string()),
+                    html_body: None,
+                    thread_id: thread_id.to_string(),
+                    message_id: None,
+                    attachments: Vec::new(),
+                    reply_to: Vec::new(),
+                    metadata: Default::default(),
+                },
+                raw_payload_ref: None,
+                account_id: None,
+                trace_id: None,
+                handoff: None,
+            },
+        }
+    }
+
+    #[test]
+    fn lanes_follow_the_thread_then_the_sender() {
+        let a = queued("thread-a", "U1", 0);
+        assert_eq!(lane_index(&a.envelope, 1), 0);
+        for lanes in [2, 7, 64] {
+            assert_eq!(
+                lane_index(&a.envelope, lanes),
+                lane_index(&queued("thread-a", "U2", 1).envelope, lanes)
+            );
+            assert_eq!(
+                lane_index(&queued("", "U1", 2).envelope, lanes),
+                lane_index(&queued(" ", "U1", 3).envelope, lanes)
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn each_thread_is_processed_in_claim_order() {
+        let seen: Arc<Mutex<HashMap<String, Vec<usize>>>> = Arc::default();
+        let recorded = seen.clone();
+        let lanes = IngestionLanes::start(4, move |item| {
+            let seq: usize = item.envelope.payload.text_body.unwrap().parse().unwrap();
+            // Earlier envelopes take longer, so reordering would show.
+            std::thread::sleep(Duration::from_millis((40 - seq as u64) / 4));
+            recorded
+                .lock()
+                .unwrap()
+                .entry(item.envelope.payload.thread_id)
+                .or_default()
+                .push(seq);
+        });
+        for seq in 0..40 {
+            let slot = lanes.slot().await;
+            lanes.dispatch(queued(&format!("thread-{}", seq % 5), "U1", seq), slot);
+        }
+        lanes.join().await;
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 5);
+        for (thread, seqs) in seen.iter() {
+            assert_eq!(seqs.len(), 8, "{}", thread);
+            assert!(seqs.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", seqs);
+        }
+    }
+}
@@ -0,0 +1,353 @@
+//! Store integrity pass. At startup and every
+//! `STORE_INTEGRITY_INTERVAL_SECS` the worker cross-checks its stores:
+//!
+//! - task index rows whose task is gone, or whose enabled flag disagrees
+//!   with the task: the due-task poller would keep claiming a disabled task
+//!   or never claim an enabled one. The rows are removed or rewritten.
+//! - enabled run_task tasks whose workspace directory is gone while the
+//!   user's directory is still there, which could only fail; the tasks are
+//!   disabled.
+//! - executions recorded for tasks that no longer exist. These are reported
+//!   only, since they are the history of what ran.
+//! - user thread databases (`state/threads.db`) failing SQLite's
+//!   `quick_check`. These are reported only.
+//!
+//! The startup pass also runs MongoDB's `validate` on every collection when
+//! the server supports it. Findings are logged and counted in the
+//! `dowhiz.store.integrity_issues` metric, and invalid collections or damage
+//! left unrepaired raise a store corruption alert.
+//! `STORE_INTEGRITY_REPAIR=false` reports without repairing.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::alerting::{self, Alert, AlertKind};
+use crate::index_store::IndexStore;
+use crate::mongo_store;
+use crate::scheduler::execution_counts_by_task;
+use crate::telemetry;
+use crate::thread_state::check_thread_db;
+use crate::user_store::UserStore;
+use crate::{ModuleExecutor, ScheduledTask, Scheduler, TaskKind};
+
+use super::config::ServiceConfig;
+use super::BoxError;
+
+/// Default seconds between store integrity passes
+pub(super) const STORE_INTEGRITY_INTERVAL_SECS: u64 = 6 * 3600;
+
+/// Repairs are on unless `STORE_INTEGRITY_REPAIR` is `false` or `0`.
+pub(super) fn integrity_repair_enabled() -> bool {
+    std::env::var("STORE_INTEGRITY_REPAIR")
+        .map(|value| !matches!(value.trim(), "false" | "0"))
+        .unwrap_or(true)
+}
+
+#[derive(Debug, Default)]
+pub(super) struct IntegrityReport {
+    pub(super) invalid_collections: Vec<String>,
+    pub(super) dangling_index_rows: usize,
+    pub(super) missing_workspaces: usize,
+    pub(super) orphaned_executions: u64,
+    pub(super) corrupt_thread_dbs: usize,
+    pub(super) repaired: usize,
+    pub(super) failed_users: usize,
+}
+
+impl IntegrityReport {
+    fn issues(&self) -> u64 {
+        self.invalid_collections.len() as u64
+            + self.dangling_index_rows as u64
+            + self.missing_workspaces as u64
+            + self.orphaned_executions
+            + self.corrupt_thread_dbs as u64
+    }
+}
+
+/// What is wrong with one user's stores.
+#[derive(Debug, Default, PartialEq)]
+struct UserFindings {
+    dangling_index_rows: Vec<String>,
+    missing_workspaces: Vec<Uuid>,
+    orphaned_executions: u64,
+}
+
+fn user_findings(
+    tasks: &[ScheduledTask],
+    indexed: &HashMap<String, bool>,
+    execution_counts: &HashMap<String, u64>,
+    user_dir_present: bool,
+) -> UserFindings {
+    let known: HashSet<String> = tasks.iter().map(|task| task.id.to_string()).collect();
+    let enabled: HashSet<String> = tasks
+        .iter()
+        .filter(|task| task.enabled)
+        .map(|task| task.id.to_string())
+        .collect();
+    let mut dangling_index_rows: Vec<String> = indexed
+        .iter()
+        .filter(|(task_id, row_enabled)| {
+            !known.contains(*task_id) || **row_enabled != enabled.contains(*task_id)
+        })
+        .map(|(task_id, _)| task_id.clone())
+        .collect();
+    dangling_index_rows.sort();
+    let missing_workspaces = if user_dir_present {
+        tasks
+            .iter()
+            .filter(|task| task.enabled)
+            .filter(
+                |task| matches!(&task.kind, TaskKind::RunTask(run) if !run.workspace_dir.exists()),
+            )
+            .map(|task| task.id)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    UserFindings {
+        dangling_index_rows,
+        missing_workspaces,
+        orphaned_executions: execution_counts
+            .iter()
+            .filter(|(task_id, _)| !known.contains(*task_id))
+            .map(|(_, count)| *count)
+            .sum(),
+    }
+}
+
+/// Check every user with tasks or index rows and repair what is safe to
+/// repair. `validate` also runs MongoDB's collection validation.
+pub(super) fn check_store_integrity(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    validate: bool,
+    repair: bool,
+) -> IntegrityReport {
+    let mut report = IntegrityReport::default();
+    if validate {
+        match mongo_store::invalid_collections_from_env() {
+            Ok(Some(invalid)) => report.invalid_collections = invalid,
+            Ok(None) => info!("store integrity: collection validation is not supported"),
+            Err(err) => warn!("store integrity: collection validation failed: {}", err),
+        }
+    }
+
+    let mut user_ids: BTreeSet<String> = match user_store.list_user_ids() {
+        Ok(user_ids) => user_ids.into_iter().collect(),
+        Err(err) => {
+            error!("store integrity skipped: failed to list users: {}", err);
+            return report;
+        }
+    };
+    // Account-level schedulers (digests) have index rows but no user record.
+    match index_store.indexed_user_ids() {
+        Ok(indexed) => user_ids.extend(indexed),
+        Err(err) => warn!("store integrity: failed to list indexed users: {}", err),
+    }
+    for user_id in &user_ids {
+        if let Err(err) = check_user(
+            config,
+            user_store,
+            index_store,
+            user_id,
+            repair,
+            &mut report,
+        ) {
+            warn!("store integrity check failed for user {}: {}", user_id, err);
+            report.failed_users += 1;
+        }
+    }
+
+    for (check, count) in [
+        ("invalid_collection", report.invalid_collections.len()),
+        ("dangling_index_row", report.dangling_index_rows),
+        ("missing_workspace", report.missing_workspaces),
+        ("orphaned_execution", report.orphaned_executions as usize),
+        ("corrupt_thread_db", report.corrupt_thread_dbs),
+    ] {
+        if count > 0 {
+            let repaired = repair && matches!(check, "dangling_index_row" | "missing_workspace");
+            telemetry::record_integrity_issues(check, count, repaired);
+        }
+    }
+    // Invalid collections and damage left unrepaired need an operator.
+    let unrepaired =
+        (report.dangling_index_rows + report.missing_workspaces).saturating_sub(report.repaired);
+    if !report.invalid_collections.is_empty() || report.corrupt_thread_dbs > 0 || unrepaired > 0 {
+        alerting::raise(
+            Alert::new(
+                AlertKind::StoreCorruption,
+                &config.employee_id,
+                format!(
+                    "Store integrity check of {} found damage it did not repair",
+                    config.employee_id
+                ),
+            )
+            .detail("invalid_collections", report.invalid_collections.join(", "))
+            .detail("dangling_index_rows", report.dangling_index_rows)
+            .detail("missing_workspaces", report.missing_workspaces)
+            .detail("corrupt_thread_dbs", report.corrupt_thread_dbs)
+            .detail("repaired", report.repaired),
+        );
+    }
+    if report.issues() > 0 || report.failed_users > 0 {
+        warn!(
+            "store integrity: {} user(s) checked, invalid_collections={:?}, dangling_index_rows={}, missing_workspaces={}, orphaned_executions={}, corrupt_thread_dbs={}, repaired={}, failed_users={}",
+            user_ids.len(),
+            report.invalid_collections,
+            report.dangling_index_rows,
+            report.missing_workspaces,
+            report.orphaned_executions,
+            report.corrupt_thread_dbs,
+            report.repaired,
+            report.failed_users
+        );
+    } else {
+        info!(
+            "store integrity: {} user(s) checked, no issues",
+            user_ids.len()
+        );
+    }
+    report
+}
+
+fn check_user(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    index_store: &IndexStore,
+    user_id: &str,
+    repair: bool,
+    report: &mut IntegrityReport,
+) -> Result<(), BoxError> {
+    let paths = user_store.user_paths(&config.users_root, user_id);
+    let scheduler = Scheduler::load(&paths.tasks_db_path, ModuleExecutor)?;
+    let indexed = index_store.indexed_task_states(user_id)?;
+    let executions = execution_counts_by_task(user_id)?;
+    let findings = user_findings(
+        scheduler.tasks(),
+        &indexed,
+        &executions,
+        paths.root.is_dir(),
+    );
+
+    report.dangling_index_rows += findings.dangling_index_rows.len();
+    report.missing_workspaces += findings.missing_workspaces.len();
+    report.orphaned_executions += findings.orphaned_executions;
+    for task_id in &findings.missing_workspaces {
+        warn!(
+            "store integrity: task {} of user {} has no workspace",
+            task_id, user_id
+        );
+    }
+    let thread_db_problem = match check_thread_db(&paths.workspaces_root) {
+        Ok(problem) => problem,
+        Err(err) => Some(err.to_string()),
+    };
+    if let Some(problem) = thread_db_problem {
+        warn!(
+            "store integrity: thread database of user {} failed its check: {}",
+            user_id, problem
+        );
+        report.corrupt_thread_dbs += 1;
+    }
+    if !repair
+        || (findings.dangling_index_rows.is_empty() && findings.missing_workspaces.is_empty())
+    {
+        return Ok(());
+    }
+
+    // Reload so tasks created since the first read are not mistaken for
+    // dangling rows; rows of existing tasks are rewritten from the fresh copy.
+    let mut scheduler = Scheduler::load(&paths.tasks_db_path, ModuleExecutor)?;
+    for task_id in &findings.dangling_index_rows {
+        match scheduler
+            .tasks()
+            .iter()
+            .find(|task| task.id.to_string() == *task_id)
+        {
+            Some(task) => index_store.upsert_task_ref(user_id, task)?,
+            None => index_store.remove_task_ref(user_id, task_id)?,
+        }
+        report.repaired += 1;
+    }
+    if !findings.missing_workspaces.is_empty() {
+        report.repaired += scheduler.disable_tasks_by(|task| {
+            findings.missing_workspaces.contains(&task.id)
+                && matches!(&task.kind, TaskKind::RunTask(run) if !run.workspace_dir.exists())
+        })?;
+        for task in scheduler
+            .tasks()
+            .iter()
+            .filter(|task| findings.missing_workspaces.contains(&task.id))
+        {
+            index_store.upsert_task_ref(user_id, task)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::tests::run_task_in;
+    use crate::Schedule;
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn run_task(workspace_dir: PathBuf, enabled: bool) -> ScheduledTask {
+        ScheduledTask {
+            id: Uuid::new_v4(),
+            kind: TaskKind::RunTask(run_task_in(&workspace_dir)),
+            schedule: Schedule::OneShot { run_at: Utc::now() },
+            enabled,
+            created_at: Utc::now(),
+            last_run: None,
+            approval: None,
+            description: None,
+            ends: None,
+        }
+    }
+
+    #[test]
+    fn finds_dangling_rows_missing_workspaces_and_orphaned_executions() {
+        let temp = tempfile::TempDir::new().expect("tempdir");
+        let healthy = run_task(temp.path().to_path_buf(), true);
+        let lost = run_task(temp.path().join("gone"), true);
+        let disabled = run_task(temp.path().join("gone"), false);
+        let stale = run_task(temp.path().to_path_buf(), false);
+        let tasks = vec![
+            healthy.clone(),
+            lost.clone(),
+            disabled.clone(),
+            stale.clone(),
+        ];
+        let indexed = HashMap::from([
+            (healthy.id.to_string(), true),
+            (lost.id.to_string(), true),
+            (disabled.id.to_string(), false),
+            (stale.id.to_string(), true),
+            ("deleted-task".to_string(), false),
+        ]);
+        let executions = HashMap::from([
+            (healthy.id.to_string(), 3),
+            (disabled.id.to_string(), 1),
+            ("deleted-task".to_string(), 2),
+        ]);
+
+        let findings = user_findings(&tasks, &indexed, &executions, true);
+        let mut expected = vec![stale.id.to_string(), "deleted-task".to_string()];
+        expected.sort();
+        assert_eq!(findings.dangling_index_rows, expected);
+        assert_eq!(findings.missing_workspaces, vec![lost.id]);
+        assert_eq!(findings.orphaned_executions, 2);
+
+        let elsewhere = user_findings(&tasks, &indexed, &executions, false);
+        assert!(
+            elsewhere.missing_workspaces.is_empty(),
+            "workspaces on another host are not judged"
+        );
+    }
+}
@@ -0,0 +1,829 @@
+//! Operator endpoints behind `dowhizctl`: users, their tasks and workspaces,
+//! recent executions, ingestion dead letters and the employee's installed
+//! skills. Every route needs an admin
+//! bearer token; changes are recorded in the audit log.
+
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::{Component, Path as FsPath, PathBuf};
+use std::sync::Arc;
+use tokio::task;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::archive_tiering::{self, TieringError};
+use crate::audit_store::{self, AuditEntry};
+use crate::index_store::{IndexStore, IndexStoreError, TaskIndexQuery};
+use crate::ingestion_queue::{IngestionQueue, IngestionQueueError};
+use crate::object_store;
+use crate::scheduler::change_sent_message;
+use crate::skill_market::{self, SkillInstallRequest, SkillMarketError};
+use crate::thread_state::{default_thread_state_path, find_sent_message, list_threads};
+use crate::user_store::UserStore;
+use crate::{
+    list_task_executions, task_execution_log_path, ExecutionQuery, ModuleExecutor, Scheduler,
+};
+
+use super::analytics::{authorize_admin, parse_admin_emails};
+use super::config::ServiceConfig;
+use super::workspace::thread_workspace_name;
+use super::BoxError;
+
+const DEFAULT_LIST_LIMIT: usize = 100;
+const MAX_LIST_LIMIT: usize = 1000;
+/// Workspace files past this many bytes in total are listed without content.
+const MAX_DUMP_BYTES: u64 = 50 * 1024 * 1024;
+const DEFAULT_LOG_TAIL_BYTES: u64 = 64 * 1024;
+const MAX_LOG_TAIL_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Clone)]
+pub struct OpsState {
+    pub config: Arc<ServiceConfig>,
+    pub user_store: Arc<UserStore>,
+    pub index_store: Arc<IndexStore>,
+    pub ingestion_queue: Arc<dyn IngestionQueue>,
+    pub supabase_url: String,
+    pub admin_emails: Arc<HashSet<String>>,
+}
+
+impl OpsState {
+    /// Admins come from `OPS_ADMIN_EMAILS`, falling back to the analytics
+    /// dashboard list.
+    pub fn from_env(
+        config: Arc<ServiceConfig>,
+        user_store: Arc<UserStore>,
+        index_store: Arc<IndexStore>,
+        ingestion_queue: Arc<dyn IngestionQueue>,
+    ) -> Self {
+        let supabase_url = std::env::var("SUPABASE_PROJECT_URL")
+            .unwrap_or_else(|_| "https://resmseutzmwumflevfqw.supabase.co".to_string());
+        let admin_emails = std::env::var("OPS_ADMIN_EMAILS")
+            .or_else(|_| std::env::var("ANALYTICS_ADMIN_EMAILS"))
+            .unwrap_or_else(|_| "admin@dowhiz.com,oliver@dowhiz.com".to_string());
+
+        Self {
+            config,
+            user_store,
+            index_store,
+            ingestion_queue,
+            supabase_url,
+            admin_emails: Arc::new(parse_admin_emails(&admin_emails)),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UsersQuery {
+    identifier_type: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LimitQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ThreadsQuery {
+    since: Option<DateTime<Utc>>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LogTailQuery {
+    bytes: Option<u64>,
+}
+
+/// One file of a dumped workspace; `content_base64` is `None` once the dump
+/// is over its size limit.
+#[derive(Debug, Serialize)]
+pub(crate) struct WorkspaceFile {
+    pub(crate) path: String,
+    pub(crate) size: u64,
+    pub(crate) content_base64: Option<String>,
+}
+
+fn list_limit(limit: Option<usize>) -> usize {
+    limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT)
+}
+
+pub(super) fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+/// Run `work` off the async runtime and turn its outcome into a response.
+pub(super) async fn respond<T, F>(label: &'static str, work: F) -> Response
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce() -> Result<Option<T>, BoxError> + Send + 'static,
+{
+    match task::spawn_blocking(work).await {
+        Ok(Ok(Some(body))) => Json(body).into_response(),
+        Ok(Ok(None)) => error_response(StatusCode::NOT_FOUND, "Not found"),
+        Ok(Err(err)) => {
+            if let Some(IngestionQueueError::Unsupported(what)) = err.downcast_ref() {
+                return error_response(
+                    StatusCode::NOT_IMPLEMENTED,
+                    &format!("{} is not supported by this queue backend", what),
+                );
+            }
+            if let Some(IndexStoreError::InvalidCursor(_)) = err.downcast_ref() {
+                return error_response(StatusCode::BAD_REQUEST, "Invalid cursor");
+            }
+            match err.downcast_ref::<SkillMarketError>() {
+                Some(SkillMarketError::Invalid(_) | SkillMarketError::ChecksumMismatch { .. }) => {
+                    return error_response(StatusCode::BAD_REQUEST, &err.to_string());
+                }
+                Some(SkillMarketError::Fetch(_)) => {
+                    return error_response(StatusCode::BAD_GATEWAY, &err.to_string());
+                }
+                _ => {}
+            }
+            error!("{} failed: {}", label, err);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Operation failed")
+        }
+        Err(err) => {
+            error!("{} join error: {}", label, err);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "Operation failed")
+        }
+    }
+}
+
+fn record_ops_action(admin: &str, action: &str, user_id: Option<&str>, target: String) {
+    audit_store::record(AuditEntry {
+        actor: admin.to_string(),
+        on_behalf_of: user_id.map(|user_id| format!("user:{}", user_id)),
+        action: format!("ops.{}", action),
+        target,
+        channel: None,
+        payload_hash: audit_store::payload_hash(b""),
+        trace_id: None,
+        task_id: None,
+        recorded_at: Utc::now(),
+    });
+}
+
+/// GET /admin/users - Users, oldest first.
+pub async fn list_users(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Query(query): Query<UsersQuery>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("ops.users", move || {
+        let users: Vec<_> = state
+            .user_store
+            .list_users()?
+            .into_iter()
+            .filter(|user| {
+                query
+                    .identifier_type
+                    .as_deref()
+                    .is_none_or(|kind| user.identifier_type == kind)
+            })
+            .take(list_limit(query.limit))
+            .map(|user| {
+                json!({
+                    "user_id": user.user_id,
+                    "identifier_type": user.identifier_type,
+                    "identifier": user.identifier,
+                    "created_at": user.created_at,
+                    "last_seen_at": user.last_seen_at,
+                })
+            })
+            .collect();
+        Ok(Some(json!({ "users": users })))
+    })
+    .await
+}
+
+fn load_user_scheduler(
+    state: &OpsState,
+    user_id: &str,
+) -> Result<Option<Scheduler<ModuleExecutor>>, BoxError> {
+    let paths = state
+        .user_store
+        .user_paths(&state.config.users_root, user_id);
+    if !paths.root.exists() {
+        return Ok(None);
+    }
+    Ok(Some(Scheduler::load(&paths.tasks_db_path, ModuleExecutor)?))
+}
+
+/// GET /admin/users/:user_id/tasks - Every task in the user's scheduler.
+pub async fn list_user_tasks(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("ops.tasks", move || {
+        let Some(scheduler) = load_user_scheduler(&state, &user_id)? else {
+            return Ok(None);
+        };
+        Ok(Some(json!({ "tasks": scheduler.tasks() })))
+    })
+    .await
+}
+
+/// GET /admin/users/:user_id/tasks/:task_id - A task and its recent executions.
+pub async fn show_user_task(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Path((user_id, task_id)): Path<(String, Uuid)>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("ops.task", move || {
+        let Some(scheduler) = load_user_scheduler(&state, &user_id)? else {
+            return Ok(None);
+        };
+        let Some(task) = scheduler.tasks().iter().find(|task| task.id == task_id) else {
+            return Ok(None);
+        };
+        let executions = list_task_executions(&ExecutionQuery {
+            user_id: Some(user_id.clone()),
+            task_id: Some(task_id.to_string()),
+            limit: Some(20),
+            ..Default::default()
+        })?;
+        Ok(Some(json!({ "task": task, "executions": executions })))
+    })
+    .await
+}
+
+/// POST /admin/users/:user_id/tasks/:task_id/cancel - Disable the task.
+pub async fn cancel_user_task(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Path((user_id, task_id)): Path<(String, Uuid)>,
+) -> Response {
+    let admin = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
+    };
+    respond("ops.cancel", move || {
+        let Some(mut scheduler) = load_user_scheduler(&state, &user_id)? else {
+            return Ok(None);
+        };
+        let cancelled = scheduler.disable_tasks_by(|task| task.id == task_id && task.enabled)?;
+        state
+            .index_store
+            .sync_user_tasks(&user_id, scheduler.tasks())?;
+        if cancelled > 0 {
+            info!(
+                "ops.cancel admin={} user_id={} task_id={}",
+                admin, user_id, task_id
+            );
+            record_ops_action(&admin, "cancel", Some(&user_id), task_id.to_string());
+        }
+        Ok(Some(json!({ "cancelled": cancelled > 0 })))
+    })
+    .await
+}
+
+/// POST /admin/users/:user_id/tasks/:task_id/run - Make the task due now.
+pub async fn run_user_task(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Path((user_id, task_id)): Path<(String, Uuid)>,
+) -> Response {
+    let admin = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
+    };
+    respond("ops.run", move || {
+        let Some(mut scheduler) = load_user_scheduler(&state, &user_id)? else {
+            return Ok(None);
+        };
+        let triggered = scheduler.trigger_task_now(task_id, Utc::now())?;
+        state
+            .index_store
+            .sync_user_tasks(&user_id, scheduler.tasks())?;
+        if triggered {
+            info!(
+                "ops.run admin={} user_id={} task_id={}",
+                admin, user_id, task_id
+            );
+            record_ops_action(&admin, "run", Some(&user_id), task_id.to_string());
+        }
+        Ok(Some(json!({ "triggered": triggered })))
+    })
+    .await
+}
+
+/// GET /admin/tasks - Indexed tasks of every user, by next run. Filters:
+/// `user_id`, `channel`, `kind`, `enabled`, `next_run_from`, `next_run_to`;
+/// pages with `limit` and the previous page's `next_cursor` as `cursor`.
+pub async fn list_indexed_tasks(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Query(query): Query<TaskIndexQuery>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("ops.indexed_tasks", move || {
+        Ok(Some(state.index_store.query_tasks(&query)?))
+    })
+    .await
+}
+
+/// GET /admin/executions - Recent task executions, newest first.
+pub async fn list_executions(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Query(query): Query<ExecutionQuery>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("ops.executions", move || {
+        let executions = list_task_executions(&query)?;
+        Ok(Some(json!({ "executions": executions })))
+    })
+    .await
+}
+
+/// GET /admin/executions/:id/log - The end of a run_task execution's log,
+/// `bytes` long (default 64 KiB). Logs stay in the workspace of the host
+/// that ran the task, so ask the service on that host.
+pub async fn show_execution_log(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<LogTailQuery>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("ops.execution_log", move || {
+        let Some(log_path) = task_execution_log_path(&id)? else {
+            return Ok(None);
+        };
+        let max_bytes = query
+            .bytes
+            .unwrap_or(DEFAULT_LOG_TAIL_BYTES)
+            .clamp(1, MAX_LOG_TAIL_BYTES);
+        let (tail, truncated) = match run_task_module::read_log_tail(&log_path, max_bytes) {
+            Ok(tail) => tail,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Some(json!({
+            "id": id,
+            "log_path": log_path,
+            "truncated": truncated,
+            "tail": tail,
+        })))
+    })
+    .await
+}
+
+/// GET /admin/dead-letters - This employee's failed ingestion envelopes.
+pub async fn list_dead_letters(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Query(query): Query<LimitQuery>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("ops.dead_letters", move || {
+        let dead_letters = state
+            .ingestion_queue
+            .list_dead_letters(&state.config.employee_id, list_limit(query.limit) as i64)?;
+        Ok(Some(json!({ "dead_letters": dead_letters })))
+    })
+    .await
+}
+
+/// POST /admin/dead-letters/:id/requeue - Give a dead letter another round
+/// of attempts.
+pub async fn requeue_dead_letter(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Response {
+    let admin = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
+    };
+    respond("ops.requeue", move || {
+        if !state.ingestion_queue.requeue_dead_letter(&id)? {
+            return Ok(None);
+        }
+        info!("ops.requeue admin={} envelope={}", admin, id);
+        record_ops_action(&admin, "requeue", None, format!("envelope:{}", id));
+        Ok(Some(json!({ "requeued": true })))
+    })
+    .await
+}
+
+/// GET /admin/users/:user_id/workspaces - The user's thread workspaces.
+pub async fn list_user_workspaces(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("ops.workspaces", move || {
+        let paths = state
+            .user_store
+            .user_paths(&state.config.users_root, &user_id);
+        let entries = match std::fs::read_dir(&paths.workspaces_root) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let mut workspaces: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .collect();
+        workspaces.sort();
+        Ok(Some(json!({ "workspaces": workspaces })))
+    })
+    .await
+}
+
+/// GET /admin/users/:user_id/threads - The user's threads from the thread
+/// database, most recently active first; `since` keeps those active at or
+/// after it.
+pub async fn list_user_threads(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+    Query(query): Query<ThreadsQuery>,
+) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("ops.threads", move || {
+        let paths = state
+            .user_store
+            .user_paths(&state.config.users_root, &user_id);
+        let threads = list_threads(&paths.workspaces_root, query.since, list_limit(query.limit))?;
+        Ok(Some(json!({ "threads": threads })))
+    })
+    .await
+}
+
+/// GET /admin/users/:user_id/workspaces/:workspace - Every file of a thread
+/// workspace. `workspace` is the directory name or the thread key.
+pub async fn dump_user_workspace(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Path((user_id, workspace)): Path<(String, String)>,
+) -> Response {
+    let admin = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
+    };
+    respond("ops.dump", move || {
+        let paths = state
+            .user_store
+            .user_paths(&state.config.users_root, &user_id);
+        let Some(dir) = resolve_workspace_dir(&paths.workspaces_root, &workspace) else {
+            return Ok(None);
+        };
+        let name = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let files = collect_workspace_files(&dir, MAX_DUMP_BYTES)?;
+        info!(
+            "ops.dump admin={} user_id={} workspace={} files={}",
+            admin,
+            user_id,
+            name,
+            files.len()
+        );
+        record_ops_action(
+            &admin,
+            "dump",
+            Some(&user_id),
+            format!("workspace:{}", name),
+        );
+        Ok(Some(json!({ "workspace": name, "files": files })))
+    })
+    .await
+}
+
+/// POST /admin/users/:user_id/workspaces/:workspace/messages/:message_id/delete
+/// - Delete a Slack or Discord reply sent in the thread.
+pub async fn retract_sent_message(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Path((user_id, workspace, message_id)): Path<(String, String, String)>,
+) -> Response {
+    let admin = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
+    };
+    respond("ops.retract", move || {
+        let paths = state
+            .user_store
+            .user_paths(&state.config.users_root, &user_id);
+        let Some(dir) = resolve_workspace_dir(&paths.workspaces_root, &workspace) else {
+            return Ok(None);
+        };
+        let state_path = default_thread_state_path(&dir);
+        let Some(sent) = find_sent_message(&state_path, &message_id)? else {
+            return Ok(None);
+        };
+        change_sent_message(
+            &state_path,
+            Some(&state.config.employee_id),
+            &sent.message_id,
+            None,
+        )?;
+        info!(
+            "ops.retract admin={} user_id={} channel={} message_id={}",
+            admin, user_id, sent.channel, sent.message_id
+        );
+        record_ops_action(
+            &admin,
+            "retract",
+            Some(&user_id),
+            format!("message:{}:{}", sent.channel_id, sent.message_id),
+        );
+        Ok(Some(json!({ "deleted": true })))
+    })
+    .await
+}
+
+/// The workspace directory named `workspace`, or the one of the thread it
+/// names. Anything but a plain directory name is taken as a thread key.
+pub(crate) fn resolve_workspace_dir(workspaces_root: &FsPath, workspace: &str) -> Option<PathBuf> {
+    let mut components = FsPath::new(workspace).components();
+    if let (Some(Component::Normal(name)), None) = (components.next(), components.next()) {
+        let dir = workspaces_root.join(name);
+        if dir.is_dir() {
+            return Some(dir);
+        }
+    }
+    let dir = workspaces_root.join(thread_workspace_name(workspace));
+    dir.is_dir().then_some(dir)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RehydrateRequest {
+    /// One message (`2026/02/msg-1`) or a month (`2026/02`)
+    id: String,
+}
+
+/// POST /admin/users/:user_id/mail/rehydrate - Bring archived mail moved to
+/// cold storage back into the user's mail archive.
+pub async fn rehydrate_user_mail(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+    Json(request): Json<RehydrateRequest>,
+) -> Response {
+    let admin = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
+    };
+    let store = match object_store::object_store_from_env(archive_tiering::COLD_STORE_ENV_PREFIX) {
+        Ok(Some(store)) => store,
+        Ok(None) => {
+            return error_response(
+                StatusCode::NOT_IMPLEMENTED,
+                "Archive tiering is not configured",
+            )
+        }
+        Err(err) => {
+            error!("ops.rehydrate failed: {}", err);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Operation failed");
+        }
+    };
+    respond("ops.rehydrate", move || {
+        let paths = state
+            .user_store
+            .user_paths(&state.config.users_root, &user_id);
+        let restored =
+            match archive_tiering::rehydrate(&paths.mail_root, store.as_ref(), &request.id) {
+                Ok(restored) => restored,
+                Err(TieringError::NotTiered(_)) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+        info!(
+            "ops.rehydrate admin={} user_id={} id={} restored={}",
+            admin,
+            user_id,
+            request.id,
+            restored.len()
+        );
+        record_ops_action(&admin, "rehydrate", Some(&user_id), request.id.clone());
+        Ok(Some(json!({ "restored": restored })))
+    })
+    .await
+}
+
+/// The employee's skills directory, where marketplace installs go.
+fn employee_skills_dir(state: &OpsState) -> Result<PathBuf, BoxError> {
+    state
+        .config
+        .employee_profile
+        .skills_dir
+        .clone()
+        .ok_or_else(|| {
+            SkillMarketError::Invalid("this employee has no skills_dir configured".to_string())
+                .into()
+        })
+}
+
+/// GET /admin/skills - Skills installed into the employee's skills dir.
+pub async fn list_skills(State(state): State<OpsState>, headers: HeaderMap) -> Response {
+    if let Err(response) = authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await
+    {
+        return response;
+    }
+    respond("ops.skills", move || {
+        let skills_dir = employee_skills_dir(&state)?;
+        Ok(Some(json!({
+            "skills_dir": skills_dir,
+            "skills": skill_market::list_installed(&skills_dir)?,
+        })))
+    })
+    .await
+}
+
+/// POST /admin/skills - Install or update a skill from a git URL or a
+/// `.tar.gz` archive, pinned to a version. New runs pick it up when their
+/// workspace is prepared.
+pub async fn install_skill(
+    State(state): State<OpsState>,
+    headers: HeaderMap,
+    Json(request): Json<SkillInstallRequest>,
+) -> Response {
+    let admin = match authorize_admin(&headers, &state.supabase_url, &state.admin_emails).await {
+        Ok(email) => email,
+        Err(response) => return response,
+    };
+    respond("ops.install_skill", move || {
+        let skills_dir = employee_skills_dir(&state)?;
+        let installed = skill_market::install_skill(&skills_dir, &request, &admin)?;
+        info!(
+            "ops.install_skill admin={} skill={} version={} sha256={}",
+            admin, installed.name, installed.version, installed.content_sha256
+        );
+        record_ops_action(
+            &admin,
+            "install_skill",
+            None,
+            format!("skill:{}@{}", installed.name, installed.version),
+        );
+        Ok(Some(json!({ "installed": installed })))
+    })
+    .await
+}
+
+/// Files under `dir`, sorted by path. Symlinks are skipped so a dump never
+/// leaves the workspace.
+pub(crate) fn collect_workspace_files(
+    dir: &FsPath,
+    max_bytes: u64,
+) -> Result<Vec<WorkspaceFile>, BoxError> {
+    let mut paths = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                paths.push(entry.path());
+            }
+        }
+    }
+    paths.sort();
+
+    let mut remaining = max_bytes;
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        let size = std::fs::metadata(&path)?.len();
+        let content_base64 = if size <= remaining {
+            remaining -= size;
+            Some(base64::engine::general_purpose::STANDARD.encode(std::fs::read(&path)?))
+        } else {
+            None
+        };
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        files.push(WorkspaceFile {
+            path: relative.to_string_lossy().into_owned(),
+            size,
+            content_base64,
+        });
+    }
+    Ok(files)
+}
+
+pub fn ops_router(state: OpsState) -> Router {
+    Router::new()
+        .route("/admin/users", get(list_users))
+        .route("/admin/users/:user_id/tasks", get(list_user_tasks))
+        .route("/admin/users/:user_id/tasks/:task_id", get(show_user_task))
+        .route(
+            "/admin/users/:user_id/tasks/:task_id/cancel",
+            post(cancel_user_task),
+        )
+        .route(
+            "/admin/users/:user_id/tasks/:task_id/run",
+            post(run_user_task),
+        )
+        .route("/admin/users/:user_id/threads", get(list_user_threads))
+        .route(
+            "/admin/users/:user_id/workspaces",
+            get(list_user_workspaces),
+        )
+        .route(
+            "/admin/users/:user_id/workspaces/:workspace",
+            get(dump_user_workspace),
+        )
+        .route(
+            "/admin/users/:user_id/workspaces/:workspace/messages/:message_id/delete",
+            post(retract_sent_message),
+        )
+        .route(
+            "/admin/users/:user_id/mail/rehydrate",
+            post(rehydrate_user_mail),
+        )
+        .route("/admin/tasks", get(list_indexed_tasks))
+        .route("/admin/executions", get(list_executions))
+        .route("/admin/executions/:id/log", get(show_execution_log))
+        .route("/admin/dead-letters", get(list_dead_letters))
+        .route("/admin/dead-letters/:id/requeue", post(requeue_dead_letter))
+        .route("/admin/skills", get(list_skills).post(install_skill))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn resolve_workspace_dir_accepts_a_name_or_a_thread_key() {
+        let temp = TempDir::new().expect("tempdir");
+        let by_name = temp.path().join("thread_abc");
+        std::fs::create_dir_all(&by_name).expect("workspace");
+        let thread_key = "slack:T1:C1:1.0";
+        let by_thread = temp.path().join(thread_workspace_name(thread_key));
+        std::fs::create_dir_all(&by_thread).expect("workspace");
+
+        assert_eq!(
+            resolve_workspace_dir(temp.path(), "thread_abc"),
+            Some(by_name)
+        );
+        assert_eq!(
+            resolve_workspace_dir(temp.path(), thread_key),
+            Some(by_thread)
+        );
+        assert_eq!(resolve_workspace_dir(temp.path(), "../thread_abc"), None);
+        assert_eq!(resolve_workspace_dir(temp.path(), "thread_missing"), None);
+    }
+
+    #[test]
+    fn collect_workspace_files_stops_reading_past_the_limit() {
+        let temp = TempDir::new().expect("tempdir");
+        std::fs::create_dir_all(temp.path().join("incoming_email")).expect("dir");
+        std::fs::write(temp.path().join("incoming_email/0001.txt"), "hello").expect("write");
+        std::fs::write(temp.path().join("reply.html"), "0123456789").expect("write");
+
+        let files = collect_workspace_files(temp.path(), 8).expect("collect");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "incoming_email/0001.txt");
+        assert_eq!(files[0].content_base64.as_deref(), Some("aGVsbG8="));
+        assert_eq!(files[1].path, "reply.html");
+        assert_eq!(files[1].size, 10);
+        assert!(files[1].content_base64.is_none());
+    }
+}
@@ -0,0 +1,462 @@
+//! `GET /health/ready`: whether this worker can do its job, checked per
+//! dependency. The scheduler poller and the ingestion consumer report each
+//! loop iteration through a [`LoopPulse`]; Mongo and the runtime directories
+//! are checked on every call; Slack and Discord bot tokens are verified
+//! against their APIs and the result is cached, so frequent probes do not
+//! run into rate limits.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::json;
+use tokio::task;
+use tracing::warn;
+
+use crate::mongo_store::health_check_from_env;
+use crate::scheduler::{
+    resolve_discord_bot_token_for_employee, resolve_slack_bot_token_for_employee,
+};
+use crate::storage_backend::StorageBackend;
+
+use super::config::ServiceConfig;
+
+/// A loop that has not ticked for this long is reported as stalled.
+const DEFAULT_LOOP_STALE_SECS: u64 = 120;
+/// How long a Slack or Discord token check is reused.
+const CREDENTIAL_CHECK_TTL: Duration = Duration::from_secs(300);
+/// Upper bound for one Mongo ping or API call.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Created and removed again to prove a directory is writable.
+const WRITE_PROBE_FILE: &str = ".ready_probe";
+
+/// Time of the latest iteration of a background loop, and for a supervised
+/// loop, its crashes.
+#[derive(Debug, Default)]
+pub(super) struct LoopPulse {
+    /// Unix milliseconds; 0 until the first tick.
+    last_tick_ms: AtomicI64,
+    restarts: AtomicU64,
+    /// Why the loop is down; cleared by the next tick.
+    crashed: Mutex<Option<String>>,
+}
+
+impl LoopPulse {
+    pub(super) fn tick(&self) {
+        self.last_tick_ms
+            .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+        let mut crashed = self
+            .crashed
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner());
+        if crashed.is_some() {
+            *crashed = None;
+        }
+    }
+
+    /// The loop died with `error` and is waiting to be restarted.
+    pub(super) fn crash(&self, error: String) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+        *self
+            .crashed
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner()) = Some(error);
+    }
+
+    pub(super) fn restarts(&self) -> u64 {
+        self.restarts.load(Ordering::Relaxed)
+    }
+
+    fn crash_error(&self) -> Option<String> {
+        self.crashed
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner())
+            .clone()
+    }
+
+    fn last_tick(&self) -> Option<DateTime<Utc>> {
+        match self.last_tick_ms.load(Ordering::Relaxed) {
+            0 => None,
+            millis => Utc.timestamp_millis_opt(millis).single(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CheckStatus {
+    Ok,
+    Failed,
+    /// Not used by this employee; does not affect readiness.
+    Disabled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct DependencyCheck {
+    status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_tick_at: Option<DateTime<Utc>>,
+    /// Times a supervised loop was restarted after crashing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    restarts: Option<u64>,
+}
+
+impl DependencyCheck {
+    fn ok() -> Self {
+        Self {
+            status: CheckStatus::Ok,
+            detail: None,
+            last_tick_at: None,
+            restarts: None,
+        }
+    }
+
+    fn failed(detail: impl Into<String>) -> Self {
+        Self {
+            status: CheckStatus::Failed,
+            detail: Some(detail.into()),
+            last_tick_at: None,
+            restarts: None,
+        }
+    }
+
+    fn disabled() -> Self {
+        Self {
+            status: CheckStatus::Disabled,
+            detail: None,
+            last_tick_at: None,
+            restarts: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct ReadinessState {
+    config: Arc<ServiceConfig>,
+    scheduler: Arc<LoopPulse>,
+    ingestion: Arc<LoopPulse>,
+    loop_stale: chrono::Duration,
+    credentials: Arc<Mutex<Option<(Instant, CredentialChecks)>>>,
+}
+
+#[derive(Debug, Clone)]
+struct CredentialChecks {
+    slack: DependencyCheck,
+    discord: DependencyCheck,
+}
+
+impl ReadinessState {
+    /// `READINESS_LOOP_STALE_SECS` (default 120) is how long the scheduler
+    /// and ingestion loops may go without ticking.
+    pub(super) fn from_env(
+        config: Arc<ServiceConfig>,
+        scheduler: Arc<LoopPulse>,
+        ingestion: Arc<LoopPulse>,
+    ) -> Self {
+        let stale_secs = std::env::var("READINESS_LOOP_STALE_SECS")
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .unwrap_or(DEFAULT_LOOP_STALE_SECS);
+        Self {
+            config,
+            scheduler,
+            ingestion,
+            loop_stale: chrono::Duration::seconds(stale_secs as i64),
+            credentials: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn cached_credentials(&self) -> Option<CredentialChecks> {
+        let cache = self
+            .credentials
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner());
+        cache
+            .as_ref()
+            .filter(|(checked_at, _)| checked_at.elapsed() < CREDENTIAL_CHECK_TTL)
+            .map(|(_, checks)| checks.clone())
+    }
+
+    async fn credential_checks(&self) -> CredentialChecks {
+        if let Some(checks) = self.cached_credentials() {
+            return checks;
+        }
+        let profile = &self.config.employee_profile;
+        let slack = if profile.slack_enabled {
+            let employee_id = self.config.employee_id.clone();
+            run_check(move || check_slack_token(&employee_id)).await
+        } else {
+            DependencyCheck::disabled()
+        };
+        let discord = if profile.discord_enabled {
+            let employee_id = self.config.employee_id.clone();
+            run_check(move || check_discord_token(&employee_id)).await
+        } else {
+            DependencyCheck::disabled()
+        };
+        let checks = CredentialChecks { slack, discord };
+        *self
+            .credentials
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner()) = Some((Instant::now(), checks.clone()));
+        checks
+    }
+}
+
+fn loop_check(pulse: &LoopPulse, now: DateTime<Utc>, stale: chrono::Duration) -> DependencyCheck {
+    let mut check = loop_liveness(pulse, now, stale);
+    check.restarts = Some(pulse.restarts()).filter(|restarts| *restarts > 0);
+    check
+}
+
+fn loop_liveness(
+    pulse: &LoopPulse,
+    now: DateTime<Utc>,
+    stale: chrono::Duration,
+) -> DependencyCheck {
+    if let Some(error) = pulse.crash_error() {
+        let mut check = DependencyCheck::failed(format!("crashed, restarting: {}", error));
+        check.last_tick_at = pulse.last_tick();
+        return check;
+    }
+    let Some(last_tick) = pulse.last_tick() else {
+        return DependencyCheck::failed("loop has not run yet");
+    };
+    let mut check = if now - last_tick > stale {
+        DependencyCheck::failed(format!(
+            "no iteration for {}s",
+            (now - last_tick).num_seconds()
+        ))
+    } else {
+        DependencyCheck::ok()
+    };
+    check.last_tick_at = Some(last_tick);
+    check
+}
+
+/// Create and remove a file in each directory.
+fn check_dirs_writable(dirs: &[PathBuf]) -> DependencyCheck {
+    for dir in dirs {
+        let probe = dir.join(WRITE_PROBE_FILE);
+        let result = fs::create_dir_all(dir)
+            .and_then(|_| fs::write(&probe, b"ok"))
+            .and_then(|_| fs::remove_file(&probe));
+        if let Err(err) = result {
+            return DependencyCheck::failed(format!("{}: {}", dir.display(), err));
+        }
+    }
+    DependencyCheck::ok()
+}
+
+fn check_mongo() -> DependencyCheck {
+    if !StorageBackend::from_env().uses_mongo() {
+        return DependencyCheck::disabled();
+    }
+    match health_check_from_env() {
+        Ok(()) => DependencyCheck::ok(),
+        Err(err) => DependencyCheck::failed(err.to_string()),
+    }
+}
+
+fn check_slack_token(employee_id: &str) -> DependencyCheck {
+    let token = match resolve_slack_bot_token_for_employee(Some(employee_id)) {
+        Ok(token) => token,
+        Err(err) => return DependencyCheck::failed(err.to_string()),
+    };
+    let api_base =
+        std::env::var("SLACK_API_BASE_URL").unwrap_or_else(|_| "https://slack.com/api".to_string());
+    let response = reqwest::blocking::Client::new()
+        .post(format!("{}/auth.test", api_base.trim_end_matches('/')))
+        .bearer_auth(token)
+        .timeout(CHECK_TIMEOUT)
+        .send()
+        .and_then(|response| response.json::<serde_json::Value>());
+    match response {
+        Ok(body) if body.get("ok").and_then(|ok| ok.as_bool()) == Some(true) => {
+            DependencyCheck::ok()
+        }
+        Ok(body) => DependencyCheck::failed(format!(
+            "auth.test: {}",
+            body.get("error")
+                .and_then(|error| error.as_str())
+                .unwrap_or("unexpected response")
+        )),
+        Err(err) => DependencyCheck::failed(format!("auth.test: {}", err)),
+    }
+}
+
+fn check_discord_token(employee_id: &str) -> DependencyCheck {
+    let token = match resolve_discord_bot_token_for_employee(Some(employee_id)) {
+        Ok(token) => token,
+        Err(err) => return DependencyCheck::failed(err.to_string()),
+    };
+    let api_base = std::env::var("DISCORD_API_BASE_URL")
+        .unwrap_or_else(|_| "https://discord.com/api/v10".to_string());
+    let response = reqwest::blocking::Client::new()
+        .get(format!("{}/users/@me", api_base.trim_end_matches('/')))
+        .header("Authorization", format!("Bot {}", token))
+        .timeout(CHECK_TIMEOUT)
+        .send();
+    match response {
+        Ok(response) if response.status().is_success() => DependencyCheck::ok(),
+        Ok(response) => DependencyCheck::failed(format!("users/@me: {}", response.status())),
+        Err(err) => DependencyCheck::failed(format!("users/@me: {}", err)),
+    }
+}
+
+/// Run a blocking check on the blocking pool, giving up after
+/// [`CHECK_TIMEOUT`] plus a grace second.
+async fn run_check(check: impl FnOnce() -> DependencyCheck + Send + 'static) -> DependencyCheck {
+    let handle = task::spawn_blocking(check);
+    match tokio::time::timeout(CHECK_TIMEOUT + Duration::from_secs(1), handle).await {
+        Ok(Ok(check)) => check,
+        Ok(Err(err)) => DependencyCheck::failed(format!("check task failed: {}", err)),
+        Err(_) => DependencyCheck::failed("timed out"),
+    }
+}
+
+fn is_ready(checks: &BTreeMap<&'static str, DependencyCheck>) -> bool {
+    checks
+        .values()
+        .all(|check| check.status != CheckStatus::Failed)
+}
+
+/// GET /health/ready - per-dependency status; 503 when any check failed.
+async fn health_ready(State(state): State<ReadinessState>) -> impl IntoResponse {
+    let now = Utc::now();
+    let mut checks = BTreeMap::new();
+    checks.insert(
+        "scheduler",
+        loop_check(&state.scheduler, now, state.loop_stale),
+    );
+    checks.insert(
+        "ingestion_consumer",
+        loop_check(&state.ingestion, now, state.loop_stale),
+    );
+    checks.insert("mongo", run_check(check_mongo).await);
+    let dirs = state_dirs(&state.config);
+    checks.insert(
+        "storage",
+        run_check(move || check_dirs_writable(&dirs)).await,
+    );
+    let credentials = state.credential_checks().await;
+    checks.insert("slack", credentials.slack);
+    checks.insert("discord", credentials.discord);
+
+    let ready = is_ready(&checks);
+    if !ready {
+        let failed: Vec<&str> = checks
+            .iter()
+            .filter(|(_, check)| check.status == CheckStatus::Failed)
+            .map(|(name, _)| *name)
+            .collect();
+        warn!("readiness check failed: {}", failed.join(", "));
+    }
+    let code = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        code,
+        Json(json!({
+            "status": if ready { "ready" } else { "not_ready" },
+            "employee_id": state.config.employee_id,
+            "checks": checks,
+        })),
+    )
+}
+
+/// Directories the worker writes to: workspaces, per-user state and the
+/// employee's own scheduler state.
+fn state_dirs(config: &ServiceConfig) -> Vec<PathBuf> {
+    let mut dirs = vec![config.workspace_root.clone(), config.users_root.clone()];
+    if let Some(parent) = config.scheduler_state_path.parent().map(Path::to_path_buf) {
+        dirs.push(parent);
+    }
+    dirs
+}
+
+pub(super) fn readiness_router(state: ReadinessState) -> Router {
+    Router::new()
+        .route("/health/ready", get(health_ready))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn loop_check_fails_before_the_first_tick_and_once_stale() {
+        let pulse = LoopPulse::default();
+        let stale = chrono::Duration::seconds(120);
+        let now = Utc::now();
+        assert_eq!(loop_check(&pulse, now, stale).status, CheckStatus::Failed);
+
+        pulse.tick();
+        let check = loop_check(&pulse, Utc::now(), stale);
+        assert_eq!(check.status, CheckStatus::Ok);
+        assert!(check.last_tick_at.is_some());
+
+        let later = Utc::now() + chrono::Duration::seconds(300);
+        assert_eq!(loop_check(&pulse, later, stale).status, CheckStatus::Failed);
+    }
+
+    #[test]
+    fn loop_check_fails_while_a_crashed_loop_restarts() {
+        let pulse = LoopPulse::default();
+        let stale = chrono::Duration::seconds(120);
+        pulse.tick();
+        pulse.crash("task panicked".to_string());
+        let check = loop_check(&pulse, Utc::now(), stale);
+        assert_eq!(check.status, CheckStatus::Failed);
+        assert_eq!(
+            check.detail.as_deref(),
+            Some("crashed, restarting: task panicked")
+        );
+        assert_eq!(check.restarts, Some(1));
+
+        pulse.tick();
+        let check = loop_check(&pulse, Utc::now(), stale);
+        assert_eq!(check.status, CheckStatus::Ok);
+        assert_eq!(check.restarts, Some(1));
+    }
+
+    #[test]
+    fn disabled_checks_do_not_block_readiness() {
+        let temp = TempDir::new().expect("tempdir");
+        let mut checks = BTreeMap::new();
+        checks.insert(
+            "storage",
+            check_dirs_writable(&[temp.path().join("workspaces")]),
+        );
+        checks.insert("slack", DependencyCheck::disabled());
+        assert!(is_ready(&checks));
+        assert!(!temp
+            .path()
+            .join("workspaces")
+            .join(WRITE_PROBE_FILE)
+            .exists());
+
+        checks.insert(
+            "discord",
+            DependencyCheck::failed("DISCORD_BOT_TOKEN not set"),
+        );
+        assert!(!is_ready(&checks));
+    }
+}
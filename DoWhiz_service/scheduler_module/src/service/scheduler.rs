@@ -1,21 +1,45 @@
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, OnceLock};
-use std::thread;
 use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Utc};
-use tracing::{error, info, warn};
+use tokio::sync::{watch, Semaphore};
+use tokio::task;
+use tracing::{error, info, info_span, warn};
 use uuid::Uuid;
 
-use crate::index_store::{IndexStore, TaskRef};
-use crate::thread_state::default_thread_state_path;
+use crate::account_store::{channel_to_identifier_type, get_global_account_store};
+use crate::alerting::{self, Alert, AlertKind};
+use crate::archive_tiering::{self, ArchiveTiering};
+use crate::backup;
+use crate::channel::Channel;
+use crate::execution_retention::{self, RetentionPolicy};
+use crate::health_probe;
+use crate::i18n::{user_locale, Message};
+use crate::inbound_policy::InboundPolicy;
+use crate::index_store::{IndexStore, MissedHeartbeat, TaskRef};
+use crate::ingestion_queue::resolve_worker_instance_id;
+use crate::mongo_store;
+use crate::object_store::{self, ObjectStore};
+use crate::scheduler::{next_run_after, notify_missed_heartbeat, record_execution_intent};
+use crate::task_budgets::{self, BudgetExceeded};
+use crate::telemetry;
+use crate::thread_state::{current_thread_epoch, default_thread_state_path};
 use crate::user_store::UserStore;
-use crate::{ModuleExecutor, Schedule, ScheduledTask, Scheduler, SchedulerError, TaskKind};
+use crate::{
+    acquire_task_lease, ModuleExecutor, ProbeTask, RunTaskTask, Schedule, ScheduledTask, Scheduler,
+    SchedulerError, TaskKind,
+};
 
 use super::config::ServiceConfig;
-use super::state::{ClaimResult, ConcurrencyLimiter, SchedulerClaims, TaskClaim};
+use super::digests::{digests_enabled, reconcile_digests, DIGEST_RECONCILE_INTERVAL_SECS};
+use super::integrity::{
+    check_store_integrity, integrity_repair_enabled, STORE_INTEGRITY_INTERVAL_SECS,
+};
+use super::readiness::LoopPulse;
+use super::state::{ClaimResult, SchedulerClaims, TaskClaim};
+use super::thread_queue::{Admission, ThreadQueues};
 use super::BoxError;
 
 /// Default task timeout in seconds (100 minutes)
@@ -28,10 +52,39 @@ const MAX_TASK_RETRIES: u32 = 3;
 const RETRY_BACKOFF_SECS: [u64; 3] = [10, 100, 1000];
 /// Watchdog check interval in seconds
 const WATCHDOG_INTERVAL_SECS: u64 = 30;
+/// How long a stale runner gets to exit after SIGTERM before SIGKILL
+const WATCHDOG_KILL_GRACE_SECS: u64 = 10;
+/// How often a running run_task checks whether a newer message superseded it
+const EPOCH_WATCH_INTERVAL_SECS: u64 = 2;
 /// Minimum interval between busy logs for the same task
 const BUSY_LOG_THROTTLE_SECS: u64 = 10;
-/// Delay before retrying a run_task when the workspace thread is still busy
-const THREAD_BUSY_DEFER_SECS: i64 = 15;
+/// Delay before retrying a run_task when another run_task is editing the same document
+const DOCUMENT_BUSY_DEFER_SECS: i64 = 15;
+/// Longest an over-budget scheduled run_task waits before the budget is checked again
+const BUDGET_RECHECK_SECS: i64 = 3600;
+/// How long a heartbeat may be overdue before the reconciler alerts
+const DEFAULT_HEARTBEAT_GRACE_SECS: u64 = 600;
+/// Heartbeat reconciler check interval in seconds
+const HEARTBEAT_CHECK_INTERVAL_SECS: u64 = 60;
+/// Maximum missed heartbeats reported per reconciler pass
+const HEARTBEAT_CHECK_LIMIT: usize = 200;
+/// Default seconds between archive tiering passes
+const ARCHIVE_TIER_INTERVAL_SECS: u64 = 6 * 3600;
+/// Default number of local state backups kept
+const DEFAULT_BACKUP_KEEP: usize = 7;
+/// Default seconds between task execution pruning passes
+const EXECUTION_PRUNE_INTERVAL_SECS: u64 = 24 * 3600;
+/// How long a health probe may take to come back before it counts as failed
+const DEFAULT_HEALTH_PROBE_SLA_SECS: u64 = 300;
+/// Default task lease TTL; a held lease is renewed every third of this
+const DEFAULT_TASK_LEASE_SECS: u64 = 120;
+/// Default cap on how long a burst of chat messages holds back its run
+const DEFAULT_INBOUND_COALESCE_MAX_SECS: u64 = 60;
+/// Index owner prefix for the employee-level scheduler database
+const EMPLOYEE_OWNER_PREFIX: &str = "employee:";
+/// Heartbeat names for the employee-level and per-user scheduler databases
+const EMPLOYEE_HEARTBEAT_NAME: &str = "employee";
+pub(super) const USER_HEARTBEAT_NAME: &str = "user";
 
 fn parse_timeout_secs_env(key: &str) -> Option<u64> {
     std::env::var(key)
@@ -40,6 +93,25 @@ fn parse_timeout_secs_env(key: &str) -> Option<u64> {
         .filter(|value| *value > 0)
 }
 
+fn task_lease_ttl() -> Duration {
+    Duration::from_secs(
+        parse_timeout_secs_env("TASK_LEASE_SECS").unwrap_or(DEFAULT_TASK_LEASE_SECS),
+    )
+}
+
+/// Identity recorded as a task's `claimed_by`: the worker instance plus a
+/// per-process suffix, so two processes on one host never share a lease.
+fn task_lease_owner(employee_id: &str) -> &'static str {
+    static OWNER: OnceLock<String> = OnceLock::new();
+    OWNER.get_or_init(|| {
+        format!(
+            "{}:{}",
+            resolve_worker_instance_id(employee_id),
+            Uuid::new_v4().simple()
+        )
+    })
+}
+
 fn resolve_watchdog_task_timeout_secs() -> u64 {
     if let Some(explicit_timeout) = parse_timeout_secs_env("TASK_TIMEOUT_SECS") {
         return explicit_timeout;
@@ -50,28 +122,286 @@ fn resolve_watchdog_task_timeout_secs() -> u64 {
     DEFAULT_TASK_TIMEOUT_SECS.max(run_task_timeout.saturating_add(WATCHDOG_TIMEOUT_HEADROOM_SECS))
 }
 
-struct RunningThreadGuard {
-    running_threads: Arc<Mutex<HashSet<String>>>,
+/// Heartbeat schedule from `SCHEDULER_HEARTBEAT_CRON`; heartbeats are off when unset.
+pub(super) struct HeartbeatConfig {
+    cron: String,
+    grace_secs: u64,
+}
+
+pub(super) fn heartbeat_config_from_env() -> Option<HeartbeatConfig> {
+    let cron = std::env::var("SCHEDULER_HEARTBEAT_CRON")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())?;
+    let grace_secs = parse_timeout_secs_env("SCHEDULER_HEARTBEAT_GRACE_SECS")
+        .unwrap_or(DEFAULT_HEARTBEAT_GRACE_SECS);
+    Some(HeartbeatConfig { cron, grace_secs })
+}
+
+impl HeartbeatConfig {
+    pub(super) fn ensure<E: crate::TaskExecutor>(
+        &self,
+        scheduler: &mut Scheduler<E>,
+        name: &str,
+    ) -> Result<Uuid, SchedulerError> {
+        scheduler.ensure_heartbeat(name, &self.cron, self.grace_secs)
+    }
+}
+
+/// Install the employee-level heartbeat and index it under the employee owner id.
+pub(super) fn ensure_employee_heartbeat(
+    config: &ServiceConfig,
+    index_store: &IndexStore,
+    heartbeat: &HeartbeatConfig,
+) -> Result<(), BoxError> {
+    let mut scheduler = Scheduler::load(&config.scheduler_state_path, ModuleExecutor)?;
+    heartbeat.ensure(&mut scheduler, EMPLOYEE_HEARTBEAT_NAME)?;
+    index_store.sync_user_tasks(&employee_owner_id(&config.employee_id), scheduler.tasks())?;
+    Ok(())
+}
+
+/// Health probe schedule from `HEALTH_PROBE_CRON`; the probe is off when unset.
+pub(super) struct ProbeConfig {
+    cron: String,
+    sla_secs: u64,
+}
+
+pub(super) fn probe_config_from_env() -> Option<ProbeConfig> {
+    let cron = std::env::var("HEALTH_PROBE_CRON")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())?;
+    let sla_secs =
+        parse_timeout_secs_env("HEALTH_PROBE_SLA_SECS").unwrap_or(DEFAULT_HEALTH_PROBE_SLA_SECS);
+    Some(ProbeConfig { cron, sla_secs })
+}
+
+/// Install the employee's health probe, or disable it when the probe is off,
+/// and index the employee-level tasks.
+pub(super) fn sync_employee_probe(
+    config: &ServiceConfig,
+    index_store: &IndexStore,
+    probe: Option<&ProbeConfig>,
+) -> Result<(), BoxError> {
+    let mut scheduler = Scheduler::load(&config.scheduler_state_path, ModuleExecutor)?;
+    match probe {
+        Some(probe) => {
+            scheduler.ensure_probe(
+                &probe.cron,
+                ProbeTask {
+                    employee_id: config.employee_id.clone(),
+                    state_path: health_probe::state_path(&config.scheduler_state_path),
+                    sla_secs: probe.sla_secs,
+                },
+            )?;
+        }
+        None => {
+            if scheduler.remove_probe()? == 0 {
+                return Ok(());
+            }
+        }
+    }
+    index_store.sync_user_tasks(&employee_owner_id(&config.employee_id), scheduler.tasks())?;
+    Ok(())
+}
+
+/// Index owner id for the employee-level scheduler database.
+fn employee_owner_id(employee_id: &str) -> String {
+    format!("{}{}", EMPLOYEE_OWNER_PREFIX, employee_id)
+}
+
+/// Map an index owner id back to the scheduler database it was synced from.
+fn resolve_owner_tasks_db_path(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    owner_id: &str,
+) -> PathBuf {
+    // Handle Discord guild-based paths differently from regular user paths
+    if let Some(guild_id) = owner_id.strip_prefix("discord:") {
+        let guild_paths =
+            crate::discord_gateway::DiscordGuildPaths::new(&config.workspace_root, guild_id);
+        return guild_paths.tasks_db_path;
+    }
+    if owner_id == employee_owner_id(&config.employee_id) {
+        return config.scheduler_state_path.clone();
+    }
+    user_store
+        .user_paths(&config.users_root, owner_id)
+        .tasks_db_path
+}
+
+/// Alert on every heartbeat the index shows as missed, once per deadline.
+fn reconcile_heartbeats(index_store: &IndexStore, now: DateTime<Utc>) -> usize {
+    let missed = match index_store.missed_heartbeats(now, HEARTBEAT_CHECK_LIMIT) {
+        Ok(missed) => missed,
+        Err(err) => {
+            error!("heartbeat reconciler query failed: {}", err);
+            return 0;
+        }
+    };
+    for heartbeat in &missed {
+        report_missed_heartbeat(heartbeat, now);
+        if let Err(err) = index_store.mark_heartbeat_alerted(heartbeat) {
+            warn!(
+                "failed to mark heartbeat {} for {} as alerted: {}",
+                heartbeat.task_id, heartbeat.user_id, err
+            );
+        }
+    }
+    missed.len()
+}
+
+fn report_missed_heartbeat(heartbeat: &MissedHeartbeat, now: DateTime<Utc>) {
+    error!(
+        "HEARTBEAT_MISSED_ALERT: name={} owner={} task_id={} due_at={} overdue_secs={}",
+        heartbeat.name,
+        heartbeat.user_id,
+        heartbeat.task_id,
+        heartbeat.due_at.to_rfc3339(),
+        (now - heartbeat.due_at).num_seconds()
+    );
+    if let Err(err) = notify_missed_heartbeat(
+        &heartbeat.user_id,
+        &heartbeat.task_id,
+        &heartbeat.name,
+        heartbeat.due_at,
+        heartbeat.deadline,
+    ) {
+        warn!(
+            "failed to send missed heartbeat alert {}: {}",
+            heartbeat.task_id, err
+        );
+    }
+}
+
+struct RunningDocumentGuard {
+    running_documents: Arc<Mutex<HashSet<String>>>,
     key: String,
 }
 
-impl RunningThreadGuard {
-    fn new(running_threads: Arc<Mutex<HashSet<String>>>, key: String) -> Self {
+impl RunningDocumentGuard {
+    fn new(running_documents: Arc<Mutex<HashSet<String>>>, key: String) -> Self {
         Self {
-            running_threads,
+            running_documents,
             key,
         }
     }
 }
 
-impl Drop for RunningThreadGuard {
+impl Drop for RunningDocumentGuard {
     fn drop(&mut self) {
-        if let Ok(mut running) = self.running_threads.lock() {
+        if let Ok(mut running) = self.running_documents.lock() {
             running.remove(&self.key);
         }
     }
 }
 
+/// Stops a run_task once its thread moves past the epoch it answers, i.e. a
+/// newer message bumped `thread_state.json`. The run's processes are
+/// terminated and the scheduler drops the run as superseded. The check runs
+/// as a Tokio task while a runtime is available and stops on drop.
+struct EpochWatch {
+    stop: Option<watch::Sender<bool>>,
+}
+
+impl EpochWatch {
+    fn start(run_id: String, state_path: PathBuf, epoch: u64) -> Self {
+        let stop = tokio::runtime::Handle::try_current().ok().map(|runtime| {
+            let (stop_tx, stop_rx) = watch::channel(false);
+            runtime.spawn(watch_epoch(run_id, state_path, epoch, stop_rx));
+            stop_tx
+        });
+        Self { stop }
+    }
+}
+
+impl Drop for EpochWatch {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(true);
+        }
+    }
+}
+
+async fn watch_epoch(
+    run_id: String,
+    state_path: PathBuf,
+    epoch: u64,
+    mut stop: watch::Receiver<bool>,
+) {
+    let mut ticks = tokio::time::interval(Duration::from_secs(EPOCH_WATCH_INTERVAL_SECS));
+    // The first tick completes at once; the run has only just started.
+    ticks.tick().await;
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            _ = stop.changed() => return,
+        }
+        let path = state_path.clone();
+        let read = task::spawn_blocking(move || current_thread_epoch(&path)).await;
+        // The run may have finished while the epoch was read.
+        if *stop.borrow() {
+            return;
+        }
+        let current = match read {
+            Ok(Ok(Some(current))) => current,
+            Ok(Ok(None)) => continue,
+            Ok(Err(err)) => {
+                warn!(
+                    "run {} could not read the epoch at {}: {}",
+                    run_id,
+                    state_path.display(),
+                    err
+                );
+                continue;
+            }
+            Err(err) => {
+                warn!("epoch check for run {} failed: {}", run_id, err);
+                continue;
+            }
+        };
+        if current > epoch {
+            info!(
+                "run {} answers thread epoch {} but {} is at epoch {}; stopping it",
+                run_id,
+                epoch,
+                state_path.display(),
+                current
+            );
+            let _ = task::spawn_blocking(move || {
+                run_task_module::terminate_run(
+                    &run_id,
+                    Duration::from_secs(WATCHDOG_KILL_GRACE_SECS),
+                )
+            })
+            .await;
+            return;
+        }
+    }
+}
+
+/// Lock key for run_tasks that edit a shared Google Workspace file.
+///
+/// Each actionable comment gets its own workspace (`gdocs:{file_id}:{comment_id}`),
+/// so the thread queue alone lets two comments on the same document run at once
+/// and clobber each other's index-based edits. Keying by file ID serializes them.
+fn document_lock_key(run: &RunTaskTask) -> Option<String> {
+    if !matches!(
+        run.channel,
+        Channel::GoogleDocs | Channel::GoogleSheets | Channel::GoogleSlides
+    ) {
+        return None;
+    }
+    let thread_id = run.thread_id.as_deref()?;
+    let mut parts = thread_id.splitn(3, ':');
+    let prefix = parts.next()?.trim();
+    let file_id = parts.next()?.trim();
+    if prefix.is_empty() || file_id.is_empty() {
+        return None;
+    }
+    Some(format!("{}:{}", prefix, file_id))
+}
+
 fn should_log_busy(key: &str) -> bool {
     static BUSY_LOGS: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
     let logs = BUSY_LOGS.get_or_init(|| Mutex::new(HashMap::new()));
@@ -88,179 +418,105 @@ fn should_log_busy(key: &str) -> bool {
 }
 
 pub(super) struct SchedulerControl {
-    stop: Arc<AtomicBool>,
-    handles: Vec<thread::JoinHandle<()>>,
+    stop: watch::Sender<bool>,
+    handles: Vec<task::JoinHandle<()>>,
+    /// Ticked by the due-task poller on every iteration.
+    pub(super) pulse: Arc<LoopPulse>,
 }
 
 impl SchedulerControl {
     pub(super) fn stop(&self) {
-        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.stop.send(true);
     }
 
-    pub(super) fn stop_and_join(&mut self) {
+    /// Stop the poller, watchdog, and heartbeat loops and wait for them to exit.
+    /// Tasks already executing finish on the blocking pool.
+    pub(super) async fn stop_and_join(&mut self) {
         self.stop();
         for handle in self.handles.drain(..) {
-            let _ = handle.join();
+            let _ = handle.await;
         }
     }
 }
 
-pub(super) fn start_scheduler_threads(
+/// Sleep for `interval`; returns true once a stop has been requested.
+pub(super) async fn sleep_or_stop(interval: Duration, stop: &mut watch::Receiver<bool>) -> bool {
+    if *stop.borrow() {
+        return true;
+    }
+    tokio::select! {
+        _ = tokio::time::sleep(interval) => *stop.borrow(),
+        _ = stop.changed() => true,
+    }
+}
+
+/// Start the scheduler loops on the current Tokio runtime.
+///
+/// Polling and housekeeping run as async tasks. Each claimed task executes on
+/// the runtime's blocking pool while holding a permit from a semaphore sized
+/// by `SCHEDULER_MAX_CONCURRENCY`, so a burst of due sends queues on permits
+/// instead of spawning an OS thread apiece.
+pub(super) async fn start_scheduler_tasks(
     config: Arc<ServiceConfig>,
     user_store: Arc<UserStore>,
     index_store: Arc<IndexStore>,
 ) -> SchedulerControl {
-    let scheduler_stop = Arc::new(AtomicBool::new(false));
-    let scheduler_poll_interval = config.scheduler_poll_interval;
-    let scheduler_max_concurrency = config.scheduler_max_concurrency;
-    let scheduler_user_max_concurrency = config.scheduler_user_max_concurrency;
+    let (stop_tx, stop_rx) = watch::channel(false);
     let claims = Arc::new(Mutex::new(SchedulerClaims::default()));
-    let running_threads = Arc::new(Mutex::new(HashSet::new()));
-    let limiter = Arc::new(ConcurrencyLimiter::new(scheduler_max_concurrency));
 
-    let mut handles = Vec::with_capacity(2);
+    // Check heartbeats before the poller runs them, so beats missed while the
+    // service was down are still reported.
+    let startup_index_store = index_store.clone();
+    match task::spawn_blocking(move || reconcile_heartbeats(&startup_index_store, Utc::now())).await
+    {
+        Ok(missed) if missed > 0 => {
+            warn!("{} heartbeat(s) were missed before startup", missed);
+        }
+        Ok(_) => {}
+        Err(err) => error!("startup heartbeat check failed: {}", err),
+    }
+
+    let mut handles = Vec::with_capacity(4);
+    let pulse = Arc::new(LoopPulse::default());
 
     {
-        let config = config.clone();
-        let user_store = user_store.clone();
-        let index_store = index_store.clone();
-        let scheduler_stop = scheduler_stop.clone();
-        let claims = claims.clone();
-        let running_threads = running_threads.clone();
-        let limiter = limiter.clone();
-        let query_limit = scheduler_max_concurrency.saturating_mul(4).max(1);
-        let handle = thread::spawn(move || {
-            let mut last_due_tasks: HashSet<String> = HashSet::new();
-            let mut logged_user_busy: HashSet<String> = HashSet::new();
-            let mut logged_task_busy: HashSet<String> = HashSet::new();
-            let mut last_capacity_deferral: Option<usize> = None;
-            while !scheduler_stop.load(Ordering::Relaxed) {
+        let pulse = pulse.clone();
+        let poll_interval = config.scheduler_poll_interval;
+        let query_limit = config.scheduler_max_concurrency.saturating_mul(4).max(1);
+        let mut poller = DueTaskPoller::new(
+            config.clone(),
+            user_store.clone(),
+            index_store.clone(),
+            claims.clone(),
+        );
+        let mut stop = stop_rx.clone();
+        handles.push(task::spawn(async move {
+            while !*stop.borrow() {
+                pulse.tick();
+                let index_store = poller.index_store.clone();
                 let now = Utc::now();
-                match index_store.due_task_refs(now, query_limit) {
-                    Ok(task_refs) => {
-                        let mut current_due_tasks = HashSet::with_capacity(task_refs.len());
-                        for task_ref in &task_refs {
-                            current_due_tasks
-                                .insert(format!("{}@{}", task_ref.task_id, task_ref.user_id));
-                        }
-                        if current_due_tasks != last_due_tasks {
-                            if !current_due_tasks.is_empty() {
-                                let refs = task_refs
-                                    .iter()
-                                    .map(|task_ref| {
-                                        format!("{}@{}", task_ref.task_id, task_ref.user_id)
-                                    })
-                                    .collect::<Vec<_>>()
-                                    .join(", ");
-                                info!("scheduler found {} due task(s): {}", task_refs.len(), refs);
-                            }
-                            last_due_tasks = current_due_tasks.clone();
-                        }
-                        logged_user_busy.retain(|key| current_due_tasks.contains(key));
-                        logged_task_busy.retain(|key| current_due_tasks.contains(key));
-                        if current_due_tasks.is_empty() {
-                            last_capacity_deferral = None;
-                        }
-                        let total_refs = task_refs.len();
-                        for (idx, task_ref) in task_refs.into_iter().enumerate() {
-                            if !limiter.try_acquire() {
-                                let remaining = total_refs.saturating_sub(idx);
-                                if last_capacity_deferral != Some(remaining) {
-                                    info!(
-                                        "scheduler at capacity; deferring {} due task(s)",
-                                        remaining
-                                    );
-                                    last_capacity_deferral = Some(remaining);
-                                }
-                                break;
-                            }
-                            last_capacity_deferral = None;
-                            let task_key = format!("{}@{}", task_ref.task_id, task_ref.user_id);
-                            let claim_result = {
-                                let mut claims =
-                                    claims.lock().unwrap_or_else(|poison| poison.into_inner());
-                                // TODO: Get retry_count from task metadata in the future
-                                claims.try_claim(&task_ref, scheduler_user_max_concurrency, 0)
-                            };
-                            match claim_result {
-                                ClaimResult::Claimed => {
-                                    logged_user_busy.remove(&task_key);
-                                    logged_task_busy.remove(&task_key);
-                                    info!(
-                                        "scheduler claimed task {} for user {}",
-                                        task_ref.task_id, task_ref.user_id
-                                    );
-                                }
-                                ClaimResult::UserBusy => {
-                                    if logged_user_busy.insert(task_key) {
-                                        info!(
-                                            "scheduler deferred task {} for user {} (user already running)",
-                                            task_ref.task_id, task_ref.user_id
-                                        );
-                                    }
-                                    limiter.release();
-                                    continue;
-                                }
-                                ClaimResult::TaskBusy => {
-                                    if logged_task_busy.insert(task_key) {
-                                        let log_key = format!(
-                                            "task_busy:{}@{}",
-                                            task_ref.task_id, task_ref.user_id
-                                        );
-                                        if should_log_busy(&log_key) {
-                                            info!(
-                                                "scheduler deferred task {} for user {} (task already running)",
-                                                task_ref.task_id, task_ref.user_id
-                                            );
-                                        }
-                                    }
-                                    limiter.release();
-                                    continue;
-                                }
-                            }
-
-                            let config = config.clone();
-                            let user_store = user_store.clone();
-                            let index_store = index_store.clone();
-                            let claims = claims.clone();
-                            let limiter = limiter.clone();
-                            let running_threads = running_threads.clone();
-                            thread::spawn(move || {
-                                if let Err(err) = execute_due_task(
-                                    &config,
-                                    &user_store,
-                                    &index_store,
-                                    &task_ref,
-                                    &running_threads,
-                                ) {
-                                    error!(
-                                        "scheduler task {} for user {} failed: {}",
-                                        task_ref.task_id, task_ref.user_id, err
-                                    );
-                                }
-                                let mut claims =
-                                    claims.lock().unwrap_or_else(|poison| poison.into_inner());
-                                claims.release(&task_ref);
-                                limiter.release();
-                            });
-                        }
-                    }
-                    Err(err) => {
-                        error!("index store query failed: {}", err);
+                match task::spawn_blocking(move || index_store.due_task_refs(now, query_limit))
+                    .await
+                {
+                    Ok(Ok(task_refs)) => {
+                        telemetry::record_due_tasks(task_refs.len());
+                        poller.dispatch(task_refs)
                     }
+                    Ok(Err(err)) => error!("index store query failed: {}", err),
+                    Err(err) => error!("index store query task failed: {}", err),
+                }
+                if sleep_or_stop(poll_interval, &mut stop).await {
+                    break;
                 }
-                thread::sleep(scheduler_poll_interval);
             }
-        });
-        handles.push(handle);
+        }));
     }
 
-    // Start task watchdog thread to detect and recover from stuck/crashed tasks
+    // Start task watchdog to detect and recover from stuck/crashed tasks
     {
         let claims = claims.clone();
-        let scheduler_stop = scheduler_stop.clone();
         let user_store = user_store.clone();
-        let users_root = config.users_root.clone();
+        let config = config.clone();
         let task_timeout_secs = resolve_watchdog_task_timeout_secs();
         let watchdog_interval_ms = std::env::var("WATCHDOG_INTERVAL_MS")
             .ok()
@@ -268,118 +524,689 @@ pub(super) fn start_scheduler_threads(
             .filter(|value| *value > 0)
             .unwrap_or(WATCHDOG_INTERVAL_SECS * 1000);
         let watchdog_interval = Duration::from_millis(watchdog_interval_ms);
+        let mut stop = stop_rx.clone();
 
-        let handle = thread::spawn(move || {
+        handles.push(task::spawn(async move {
             info!(
                 "Task watchdog started (timeout={}s, check_interval={}ms)",
                 task_timeout_secs, watchdog_interval_ms
             );
+            while !sleep_or_stop(watchdog_interval, &mut stop).await {
+                let claims = claims.clone();
+                let user_store = user_store.clone();
+                let config = config.clone();
+                let result = task::spawn_blocking(move || {
+                    recover_stale_tasks(&claims, &config, &user_store, task_timeout_secs)
+                })
+                .await;
+                if let Err(err) = result {
+                    error!("task watchdog pass failed: {}", err);
+                }
+            }
+            info!("Task watchdog stopped");
+        }));
+    }
 
-            while !scheduler_stop.load(Ordering::Relaxed) {
-                thread::sleep(watchdog_interval);
+    // Start heartbeat reconciler to alert on heartbeat noop tasks that stopped running
+    {
+        let index_store = index_store.clone();
+        let check_interval = Duration::from_secs(
+            parse_timeout_secs_env("HEARTBEAT_CHECK_INTERVAL_SECS")
+                .unwrap_or(HEARTBEAT_CHECK_INTERVAL_SECS),
+        );
+        let mut stop = stop_rx.clone();
 
-                let stale_tasks = {
-                    let claims = claims.lock().unwrap_or_else(|poison| poison.into_inner());
-                    claims.find_stale_tasks(task_timeout_secs)
-                };
+        handles.push(task::spawn(async move {
+            info!(
+                "Heartbeat reconciler started (check_interval={}s)",
+                check_interval.as_secs()
+            );
+            while !sleep_or_stop(check_interval, &mut stop).await {
+                let index_store = index_store.clone();
+                let result =
+                    task::spawn_blocking(move || reconcile_heartbeats(&index_store, Utc::now()))
+                        .await;
+                if let Err(err) = result {
+                    error!("heartbeat reconciler pass failed: {}", err);
+                }
+            }
+            info!("Heartbeat reconciler stopped");
+        }));
+    }
 
-                for stale_claim in stale_tasks {
-                    warn!(
-                        "Watchdog detected stale task: task_id={} user_id={} thread_id={:?} started_at={} retry_count={}",
-                        stale_claim.task_id,
-                        stale_claim.user_id,
-                        stale_claim.thread_id,
-                        stale_claim.started_at,
-                        stale_claim.retry_count
-                    );
+    // Start digest reconciler to keep account digest tasks in line with their preferences
+    if digests_enabled() {
+        let config = config.clone();
+        let user_store = user_store.clone();
+        let index_store = index_store.clone();
+        let check_interval = Duration::from_secs(DIGEST_RECONCILE_INTERVAL_SECS);
+        let mut stop = stop_rx.clone();
 
-                    // Force release the stale task from claims
-                    let released = {
-                        let mut claims = claims.lock().unwrap_or_else(|poison| poison.into_inner());
-                        claims.force_release(&stale_claim.task_id)
+        handles.push(task::spawn(async move {
+            info!(
+                "Digest reconciler started (check_interval={}s)",
+                check_interval.as_secs()
+            );
+            loop {
+                let config = config.clone();
+                let user_store = user_store.clone();
+                let index_store = index_store.clone();
+                let result = task::spawn_blocking(move || {
+                    let Some(account_store) = get_global_account_store() else {
+                        return 0;
                     };
+                    reconcile_digests(&config, &user_store, &index_store, &account_store)
+                })
+                .await;
+                match result {
+                    Ok(failed) if failed > 0 => {
+                        warn!("digest reconciler pass had {} failure(s)", failed)
+                    }
+                    Ok(_) => {}
+                    Err(err) => error!("digest reconciler pass failed: {}", err),
+                }
+                if sleep_or_stop(check_interval, &mut stop).await {
+                    break;
+                }
+            }
+            info!("Digest reconciler stopped");
+        }));
+    }
 
-                    if released.is_some() {
-                        // Load scheduler to manage retry count
-                        let user_paths = user_store.user_paths(&users_root, &stale_claim.user_id);
-                        let scheduler_result =
-                            Scheduler::load(&user_paths.tasks_db_path, ModuleExecutor::default());
-
-                        match scheduler_result {
-                            Ok(mut scheduler) => {
-                                // Increment retry count in database
-                                match scheduler.increment_retry_count(&stale_claim.task_id) {
-                                    Ok(new_count) => {
-                                        if new_count < MAX_TASK_RETRIES {
-                                            warn!(
-                                                "Watchdog released stale task {} (will be retried, attempt {}/{})",
-                                                stale_claim.task_id,
-                                                new_count,
-                                                MAX_TASK_RETRIES
-                                            );
-                                            // Task will be re-picked up by scheduler on next tick
-                                        } else {
-                                            error!(
-                                                "Watchdog: Task {} exceeded max retries ({}), disabling task",
-                                                stale_claim.task_id, MAX_TASK_RETRIES
-                                            );
-
-                                            // Disable the task in database
-                                            if let Err(err) =
-                                                scheduler.disable_task_by_id(&stale_claim.task_id)
-                                            {
-                                                error!(
-                                                    "Failed to disable task {}: {}",
-                                                    stale_claim.task_id, err
-                                                );
-                                            }
-
-                                            // Notify user about the failure
-                                            if let Err(err) = notify_task_failure(
-                                                &user_store,
-                                                &users_root,
-                                                &stale_claim,
-                                            ) {
-                                                error!(
-                                                    "Failed to notify user about task failure {}: {}",
-                                                    stale_claim.task_id, err
-                                                );
-                                            }
-                                        }
-                                    }
-                                    Err(err) => {
-                                        error!(
-                                            "Failed to increment retry count for task {}: {}",
-                                            stale_claim.task_id, err
-                                        );
-                                    }
-                                }
-                            }
-                            Err(err) => {
-                                error!(
-                                    "Watchdog failed to load scheduler for user {}: {}",
-                                    stale_claim.user_id, err
-                                );
+    // Start store integrity checks: a full pass at startup, then cross-store checks
+    {
+        let config = config.clone();
+        let user_store = user_store.clone();
+        let index_store = index_store.clone();
+        let check_interval = Duration::from_secs(
+            parse_timeout_secs_env("STORE_INTEGRITY_INTERVAL_SECS")
+                .unwrap_or(STORE_INTEGRITY_INTERVAL_SECS),
+        );
+        let repair = integrity_repair_enabled();
+        let mut stop = stop_rx.clone();
+
+        handles.push(task::spawn(async move {
+            info!(
+                "Store integrity checks started (repair={}, check_interval={}s)",
+                repair,
+                check_interval.as_secs()
+            );
+            let mut validate = true;
+            loop {
+                let config = config.clone();
+                let user_store = user_store.clone();
+                let index_store = index_store.clone();
+                let result = task::spawn_blocking(move || {
+                    check_store_integrity(&config, &user_store, &index_store, validate, repair)
+                })
+                .await;
+                if let Err(err) = result {
+                    error!("store integrity pass failed: {}", err);
+                }
+                validate = false;
+                if sleep_or_stop(check_interval, &mut stop).await {
+                    break;
+                }
+            }
+            info!("Store integrity checks stopped");
+        }));
+    }
+
+    // Start archive tiering to move old archived mail to cold storage
+    if let Some(tiering) = config.employee_profile.archive_tiering.clone() {
+        match object_store::object_store_from_env(archive_tiering::COLD_STORE_ENV_PREFIX) {
+            Ok(Some(store)) => {
+                let config = config.clone();
+                let user_store = user_store.clone();
+                let check_interval = Duration::from_secs(
+                    parse_timeout_secs_env("ARCHIVE_TIER_INTERVAL_SECS")
+                        .unwrap_or(ARCHIVE_TIER_INTERVAL_SECS),
+                );
+                let mut stop = stop_rx.clone();
+
+                handles.push(task::spawn(async move {
+                    info!(
+                        "Archive tiering started (backend={}, after_days={}, check_interval={}s)",
+                        store.name(),
+                        tiering.after_days,
+                        check_interval.as_secs()
+                    );
+                    while !sleep_or_stop(check_interval, &mut stop).await {
+                        let config = config.clone();
+                        let user_store = user_store.clone();
+                        let tiering = tiering.clone();
+                        let store = store.clone();
+                        let result = task::spawn_blocking(move || {
+                            tier_mail_archives(&config, &user_store, &tiering, store.as_ref())
+                        })
+                        .await;
+                        match result {
+                            Ok(failed) if failed > 0 => {
+                                warn!("archive tiering pass had {} failure(s)", failed)
                             }
+                            Ok(_) => {}
+                            Err(err) => error!("archive tiering pass failed: {}", err),
                         }
                     }
-                }
+                    info!("Archive tiering stopped");
+                }));
             }
-            info!("Task watchdog stopped");
-        });
-        handles.push(handle);
+            Ok(None) => warn!(
+                "archive_tiering is enabled for {} but ARCHIVE_TIER_BACKEND is not set",
+                config.employee_id
+            ),
+            Err(err) => error!("archive tiering disabled: {}", err),
+        }
+    }
+
+    // Start state backups on BACKUP_CRON (set it on one worker per database)
+    if let Some(expression) = std::env::var("BACKUP_CRON")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    {
+        let store = match object_store::object_store_from_env(backup::BACKUP_STORE_ENV_PREFIX) {
+            Ok(store) => Some(store),
+            Err(err) => {
+                error!("state backups disabled: {}", err);
+                None
+            }
+        };
+        match (store, next_run_after(&expression, Utc::now())) {
+            (Some(store), Ok(_)) => {
+                let backup_dir = std::env::var("BACKUP_DIR")
+                    .ok()
+                    .filter(|value| !value.trim().is_empty())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| {
+                        config
+                            .scheduler_state_path
+                            .parent()
+                            .unwrap_or_else(|| Path::new("."))
+                            .join("backups")
+                    });
+                let keep = std::env::var("BACKUP_KEEP")
+                    .ok()
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .unwrap_or(DEFAULT_BACKUP_KEEP);
+                let mut stop = stop_rx.clone();
+
+                handles.push(task::spawn(async move {
+                    info!(
+                        "State backups started (cron={}, dir={}, keep={}, upload={})",
+                        expression,
+                        backup_dir.display(),
+                        keep,
+                        store.as_ref().map(|store| store.name()).unwrap_or("none")
+                    );
+                    loop {
+                        let now = Utc::now();
+                        let Ok(next_run) = next_run_after(&expression, now) else {
+                            warn!("BACKUP_CRON {} has no upcoming run", expression);
+                            break;
+                        };
+                        let wait = (next_run - now).to_std().unwrap_or_default();
+                        if sleep_or_stop(wait, &mut stop).await {
+                            break;
+                        }
+                        let backup_dir = backup_dir.clone();
+                        let store = store.clone();
+                        let result = task::spawn_blocking(move || {
+                            run_state_backup(&backup_dir, keep, store.as_deref())
+                        })
+                        .await;
+                        match result {
+                            Ok(Ok(())) => {}
+                            Ok(Err(err)) => error!("state backup failed: {}", err),
+                            Err(err) => error!("state backup failed: {}", err),
+                        }
+                    }
+                    info!("State backups stopped");
+                }));
+            }
+            (Some(_), Err(err)) => error!("state backups disabled: invalid BACKUP_CRON: {}", err),
+            (None, _) => {}
+        }
+    }
+
+    // Start task execution pruning (set the retention on one worker per database)
+    if let Some(policy) = RetentionPolicy::from_env() {
+        match object_store::object_store_from_env(execution_retention::EXECUTION_EXPORT_ENV_PREFIX)
+        {
+            Ok(export) => {
+                let check_interval = Duration::from_secs(
+                    parse_timeout_secs_env("TASK_EXECUTION_PRUNE_INTERVAL_SECS")
+                        .unwrap_or(EXECUTION_PRUNE_INTERVAL_SECS),
+                );
+                let compact = std::env::var("TASK_EXECUTION_COMPACT")
+                    .map(|value| matches!(value.trim(), "true" | "1"))
+                    .unwrap_or(false);
+                let mut stop = stop_rx.clone();
+
+                handles.push(task::spawn(async move {
+                    info!(
+                        "Task execution pruning started (max_age_days={:?}, keep_per_task={:?}, export={}, compact={}, check_interval={}s)",
+                        policy.max_age.map(|max_age| max_age.num_days()),
+                        policy.keep_per_task,
+                        export.as_ref().map(|store| store.name()).unwrap_or("none"),
+                        compact,
+                        check_interval.as_secs()
+                    );
+                    loop {
+                        let export = export.clone();
+                        let result = task::spawn_blocking(move || {
+                            prune_task_executions(&policy, export.as_deref(), compact)
+                        })
+                        .await;
+                        match result {
+                            Ok(Ok(())) => {}
+                            Ok(Err(err)) => error!("task execution pruning failed: {}", err),
+                            Err(err) => error!("task execution pruning failed: {}", err),
+                        }
+                        if sleep_or_stop(check_interval, &mut stop).await {
+                            break;
+                        }
+                    }
+                    info!("Task execution pruning stopped");
+                }));
+            }
+            Err(err) => error!("task execution pruning disabled: {}", err),
+        }
     }
 
     SchedulerControl {
-        stop: scheduler_stop,
+        stop: stop_tx,
         handles,
+        pulse,
+    }
+}
+
+/// Move every user's old archived mail to cold storage, under
+/// `<prefix or employee id>/<user id>/`. Returns how many users failed.
+fn tier_mail_archives(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    tiering: &ArchiveTiering,
+    store: &dyn ObjectStore,
+) -> usize {
+    let user_ids = match user_store.list_user_ids() {
+        Ok(user_ids) => user_ids,
+        Err(err) => {
+            error!("archive tiering skipped: failed to list users: {}", err);
+            return 0;
+        }
+    };
+    let prefix = tiering.prefix.as_deref().unwrap_or(&config.employee_id);
+    let mut failed = 0;
+    for user_id in user_ids {
+        let paths = user_store.user_paths(&config.users_root, &user_id);
+        let key_prefix = format!("{}/{}", prefix, user_id);
+        match archive_tiering::tier_mail_archive(
+            &paths.mail_root,
+            store,
+            tiering,
+            &key_prefix,
+            Utc::now(),
+        ) {
+            Ok(report) => {
+                if report.tiered > 0 {
+                    info!(
+                        "archive tiering moved {} message(s) ({} bytes) for user {}",
+                        report.tiered, report.bytes_uploaded, user_id
+                    );
+                }
+                if report.failed > 0 {
+                    failed += 1;
+                }
+            }
+            Err(err) => {
+                warn!("archive tiering failed for user {}: {}", user_id, err);
+                failed += 1;
+            }
+        }
+    }
+    failed
+}
+
+/// Back up the state database into `dir`, keep the newest `keep` local
+/// backups and upload the new one when a store is configured.
+fn run_state_backup(
+    dir: &Path,
+    keep: usize,
+    store: Option<&dyn ObjectStore>,
+) -> Result<(), backup::BackupError> {
+    let client = mongo_store::create_client_from_env()?;
+    let database = mongo_store::mongo_database_name_from_env();
+    let backup = backup::create_backup(&client, &database, dir, Utc::now())?;
+    info!(
+        "state backup wrote {} document(s) from {} collection(s) to {} (consistent={})",
+        backup.manifest.documents(),
+        backup.manifest.collections.len(),
+        backup.path.display(),
+        backup.manifest.consistent
+    );
+    if let Some(store) = store {
+        let key = backup::upload_backup(store, &backup)?;
+        info!("state backup uploaded to {} as {}", store.name(), key);
+    }
+    let pruned = backup::prune_backups(dir, keep)?;
+    if pruned > 0 {
+        info!("state backup pruned {} old local backup(s)", pruned);
+    }
+    Ok(())
+}
+
+/// Prune the state database's execution history past `policy`.
+fn prune_task_executions(
+    policy: &RetentionPolicy,
+    export: Option<&dyn ObjectStore>,
+    compact: bool,
+) -> Result<(), execution_retention::RetentionError> {
+    let client = mongo_store::create_client_from_env()?;
+    let db = mongo_store::database_from_env(&client);
+    let report =
+        execution_retention::prune_task_executions(&db, policy, export, compact, Utc::now())?;
+    if report.pruned > 0 {
+        info!(
+            "task execution pruning removed {} execution(s), exported {} (compacted={})",
+            report.pruned, report.exported, report.compacted
+        );
+    }
+    Ok(())
+}
+
+/// Claims due tasks and hands them to the blocking pool, one permit each.
+struct DueTaskPoller {
+    config: Arc<ServiceConfig>,
+    user_store: Arc<UserStore>,
+    index_store: Arc<IndexStore>,
+    claims: Arc<Mutex<SchedulerClaims>>,
+    thread_queues: Arc<ThreadQueues>,
+    running_documents: Arc<Mutex<HashSet<String>>>,
+    permits: Arc<Semaphore>,
+    last_due_tasks: HashSet<String>,
+    logged_user_busy: HashSet<String>,
+    logged_task_busy: HashSet<String>,
+    last_capacity_deferral: Option<usize>,
+}
+
+impl DueTaskPoller {
+    fn new(
+        config: Arc<ServiceConfig>,
+        user_store: Arc<UserStore>,
+        index_store: Arc<IndexStore>,
+        claims: Arc<Mutex<SchedulerClaims>>,
+    ) -> Self {
+        let permits = Arc::new(Semaphore::new(config.scheduler_max_concurrency));
+        Self {
+            config,
+            user_store,
+            index_store,
+            claims,
+            thread_queues: Arc::new(ThreadQueues::default()),
+            running_documents: Arc::new(Mutex::new(HashSet::new())),
+            permits,
+            last_due_tasks: HashSet::new(),
+            logged_user_busy: HashSet::new(),
+            logged_task_busy: HashSet::new(),
+            last_capacity_deferral: None,
+        }
+    }
+
+    fn dispatch(&mut self, task_refs: Vec<TaskRef>) {
+        let scheduler_user_max_concurrency = self.config.scheduler_user_max_concurrency;
+        let mut current_due_tasks = HashSet::with_capacity(task_refs.len());
+        for task_ref in &task_refs {
+            current_due_tasks.insert(format!("{}@{}", task_ref.task_id, task_ref.user_id));
+        }
+        if current_due_tasks != self.last_due_tasks {
+            if !current_due_tasks.is_empty() {
+                let refs = task_refs
+                    .iter()
+                    .map(|task_ref| format!("{}@{}", task_ref.task_id, task_ref.user_id))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                info!("scheduler found {} due task(s): {}", task_refs.len(), refs);
+            }
+            self.last_due_tasks = current_due_tasks.clone();
+        }
+        self.logged_user_busy
+            .retain(|key| current_due_tasks.contains(key));
+        self.logged_task_busy
+            .retain(|key| current_due_tasks.contains(key));
+        if current_due_tasks.is_empty() {
+            self.last_capacity_deferral = None;
+        }
+        self.thread_queues.retain_due(&current_due_tasks);
+        let total_refs = task_refs.len();
+        for (idx, task_ref) in task_refs.into_iter().enumerate() {
+            let task_key = format!("{}@{}", task_ref.task_id, task_ref.user_id);
+            if !self.thread_queues.may_start(&task_key) {
+                continue;
+            }
+            let Ok(permit) = self.permits.clone().try_acquire_owned() else {
+                let remaining = total_refs.saturating_sub(idx);
+                if self.last_capacity_deferral != Some(remaining) {
+                    info!("scheduler at capacity; deferring {} due task(s)", remaining);
+                    self.last_capacity_deferral = Some(remaining);
+                }
+                break;
+            };
+            self.last_capacity_deferral = None;
+            let run_id = format!("{}:{}", task_ref.task_id, Uuid::new_v4());
+            let claim_result = {
+                let mut claims = self
+                    .claims
+                    .lock()
+                    .unwrap_or_else(|poison| poison.into_inner());
+                // TODO: Get retry_count from task metadata in the future
+                claims.try_claim(&task_ref, scheduler_user_max_concurrency, 0, &run_id)
+            };
+            match claim_result {
+                ClaimResult::Claimed => {
+                    self.logged_user_busy.remove(&task_key);
+                    self.logged_task_busy.remove(&task_key);
+                    info!(
+                        "scheduler claimed task {} for user {}",
+                        task_ref.task_id, task_ref.user_id
+                    );
+                }
+                ClaimResult::UserBusy => {
+                    if self.logged_user_busy.insert(task_key) {
+                        info!(
+                            "scheduler deferred task {} for user {} (user already running)",
+                            task_ref.task_id, task_ref.user_id
+                        );
+                    }
+                    continue;
+                }
+                ClaimResult::TaskBusy => {
+                    if self.logged_task_busy.insert(task_key) {
+                        let log_key =
+                            format!("task_busy:{}@{}", task_ref.task_id, task_ref.user_id);
+                        if should_log_busy(&log_key) {
+                            info!(
+                                "scheduler deferred task {} for user {} (task already running)",
+                                task_ref.task_id, task_ref.user_id
+                            );
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            let config = self.config.clone();
+            let user_store = self.user_store.clone();
+            let index_store = self.index_store.clone();
+            let claims = self.claims.clone();
+            let thread_queues = self.thread_queues.clone();
+            let running_documents = self.running_documents.clone();
+            task::spawn_blocking(move || {
+                let _permit = permit;
+                let scope = run_task_module::RunScope::enter(run_id.clone());
+                let _span = info_span!(
+                    "scheduler.run",
+                    employee_id = %config.employee_id,
+                    user_id = %task_ref.user_id
+                )
+                .entered();
+                if let Err(err) = execute_due_task(
+                    &config,
+                    &user_store,
+                    &index_store,
+                    &task_ref,
+                    &run_id,
+                    &thread_queues,
+                    &running_documents,
+                ) {
+                    error!(
+                        "scheduler task {} for user {} failed: {}",
+                        task_ref.task_id, task_ref.user_id, err
+                    );
+                }
+                drop(scope);
+                let mut claims = claims.lock().unwrap_or_else(|poison| poison.into_inner());
+                claims.release(&task_ref, &run_id);
+            });
+        }
     }
 }
 
-/// Notify user that a task has failed after max retries
+/// One watchdog pass: release claims held longer than `task_timeout_secs`,
+/// then retry or disable the task.
+fn recover_stale_tasks(
+    claims: &Mutex<SchedulerClaims>,
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    task_timeout_secs: u64,
+) {
+    let stale_tasks = {
+        let claims = claims.lock().unwrap_or_else(|poison| poison.into_inner());
+        claims.find_stale_tasks(task_timeout_secs)
+    };
+
+    for stale_claim in stale_tasks {
+        warn!(
+            "Watchdog detected stale task: task_id={} user_id={} thread_id={:?} started_at={} retry_count={}",
+            stale_claim.task_id,
+            stale_claim.user_id,
+            stale_claim.thread_id,
+            stale_claim.started_at,
+            stale_claim.retry_count
+        );
+
+        // Stop the runner first, so it cannot reply after the retry starts
+        let stopped = run_task_module::terminate_run(
+            &stale_claim.run_id,
+            Duration::from_secs(WATCHDOG_KILL_GRACE_SECS),
+        );
+        if stopped > 0 {
+            warn!(
+                "Watchdog terminated {} process group(s) of stale task {}",
+                stopped, stale_claim.task_id
+            );
+            alerting::raise(
+                Alert::new(
+                    AlertKind::WatchdogKill,
+                    &stale_claim.task_id,
+                    format!(
+                        "Watchdog stopped task {} after {}s",
+                        stale_claim.task_id, task_timeout_secs
+                    ),
+                )
+                .detail("user_id", &stale_claim.user_id)
+                .detail("run_id", &stale_claim.run_id)
+                .detail("started_at", stale_claim.started_at)
+                .detail("process_groups", stopped),
+            );
+        }
+
+        // Force release the stale task from claims
+        let released = {
+            let mut claims = claims.lock().unwrap_or_else(|poison| poison.into_inner());
+            claims.force_release(&stale_claim.task_id)
+        };
+
+        if released.is_none() {
+            continue;
+        }
+
+        // Load scheduler to manage retry count
+        let tasks_db_path = resolve_owner_tasks_db_path(config, user_store, &stale_claim.user_id);
+        let mut scheduler = match Scheduler::load(&tasks_db_path, ModuleExecutor) {
+            Ok(scheduler) => scheduler,
+            Err(err) => {
+                error!(
+                    "Watchdog failed to load scheduler for user {}: {}",
+                    stale_claim.user_id, err
+                );
+                continue;
+            }
+        };
+
+        // Increment retry count in database
+        match scheduler.increment_retry_count(&stale_claim.task_id) {
+            Ok(new_count) => {
+                if new_count < MAX_TASK_RETRIES {
+                    warn!(
+                        "Watchdog released stale task {} (will be retried, attempt {}/{})",
+                        stale_claim.task_id, new_count, MAX_TASK_RETRIES
+                    );
+                    // Task will be re-picked up by scheduler on next tick
+                } else {
+                    error!(
+                        "Watchdog: Task {} exceeded max retries ({}), disabling task",
+                        stale_claim.task_id, MAX_TASK_RETRIES
+                    );
+                    alerting::raise(
+                        Alert::new(
+                            AlertKind::RetriesExhausted,
+                            &stale_claim.task_id,
+                            format!(
+                                "Task {} timed out {} times and was disabled",
+                                stale_claim.task_id, new_count
+                            ),
+                        )
+                        .detail("user_id", &stale_claim.user_id)
+                        .detail("thread_id", format!("{:?}", stale_claim.thread_id))
+                        .detail("retries", new_count),
+                    );
+
+                    // Disable the task in database
+                    if let Err(err) = scheduler.disable_task_by_id(&stale_claim.task_id) {
+                        error!("Failed to disable task {}: {}", stale_claim.task_id, err);
+                    }
+
+                    // Notify user about the failure
+                    if let Err(err) = notify_task_failure(
+                        user_store,
+                        &config.users_root,
+                        config.employee_profile.language.as_deref(),
+                        &stale_claim,
+                    ) {
+                        error!(
+                            "Failed to notify user about task failure {}: {}",
+                            stale_claim.task_id, err
+                        );
+                    }
+                }
+            }
+            Err(err) => {
+                error!(
+                    "Failed to increment retry count for task {}: {}",
+                    stale_claim.task_id, err
+                );
+            }
+        }
+    }
+}
+
+/// Notify user that a task has failed after max retries, in the user's
+/// language when one is set.
 fn notify_task_failure(
     user_store: &UserStore,
     users_root: &Path,
+    employee_language: Option<&str>,
     stale_claim: &TaskClaim,
 ) -> Result<(), BoxError> {
     let user_paths = user_store.user_paths(users_root, &stale_claim.user_id);
@@ -391,36 +1218,19 @@ fn notify_task_failure(
     let timestamp = Utc::now().format("%Y%m%d_%H%M%S").to_string();
     let notification_file = notification_dir.join(format!("task_failure_{}.txt", timestamp));
 
-    let notification_content = format!(
-        "Task Failure Notification\n\
-        ==========================\n\
-        \n\
-        Task ID: {}\n\
-        User ID: {}\n\
-        Thread ID: {:?}\n\
-        Started at: {}\n\
-        Failed at: {}\n\
-        Retry count: {} (max: {})\n\
-        \n\
-        The task has been automatically disabled after exceeding the maximum retry attempts.\n\
-        \n\
-        Possible causes:\n\
-        - The task timed out (took longer than {} seconds)\n\
-        - The processing service crashed or became unresponsive\n\
-        - Network or external service issues\n\
-        \n\
-        Recommended actions:\n\
-        - Check the service logs for more details\n\
-        - Try the operation again by creating a new request\n\
-        - Contact support if the issue persists\n",
-        stale_claim.task_id,
-        stale_claim.user_id,
-        stale_claim.thread_id,
-        stale_claim.started_at,
-        Utc::now(),
-        stale_claim.retry_count,
-        MAX_TASK_RETRIES,
-        DEFAULT_TASK_TIMEOUT_SECS,
+    let locale = user_locale(&stale_claim.user_id, employee_language);
+    let notification_content = locale.render(
+        Message::TaskFailureNotice,
+        &[
+            ("task_id", &stale_claim.task_id),
+            ("user_id", &stale_claim.user_id),
+            ("thread_id", &format!("{:?}", stale_claim.thread_id)),
+            ("started_at", &stale_claim.started_at),
+            ("failed_at", &Utc::now()),
+            ("retry_count", &stale_claim.retry_count),
+            ("max_retries", &MAX_TASK_RETRIES),
+            ("timeout_secs", &DEFAULT_TASK_TIMEOUT_SECS),
+        ],
     );
 
     std::fs::write(&notification_file, &notification_content)?;
@@ -443,22 +1253,33 @@ fn execute_due_task(
     user_store: &UserStore,
     index_store: &IndexStore,
     task_ref: &TaskRef,
-    running_threads: &Arc<Mutex<HashSet<String>>>,
+    run_id: &str,
+    thread_queues: &Arc<ThreadQueues>,
+    running_documents: &Arc<Mutex<HashSet<String>>>,
 ) -> Result<(), BoxError> {
     let task_id = Uuid::parse_str(&task_ref.task_id)?;
-
-    // Handle Discord guild-based paths differently from regular user paths
-    let tasks_db_path = if task_ref.user_id.starts_with("discord:") {
-        let guild_id = task_ref
-            .user_id
-            .strip_prefix("discord:")
-            .unwrap_or(&task_ref.user_id);
-        let guild_paths =
-            crate::discord_gateway::DiscordGuildPaths::new(&config.workspace_root, guild_id);
-        guild_paths.tasks_db_path
-    } else {
-        let user_paths = user_store.user_paths(&config.users_root, &task_ref.user_id);
-        user_paths.tasks_db_path
+    let tasks_db_path = resolve_owner_tasks_db_path(config, user_store, &task_ref.user_id);
+
+    // Claims above only cover this process; the lease keeps a second worker on
+    // the same data from running the task too. Load after acquiring it so the
+    // task state reflects any run that worker already finished.
+    let lease = match acquire_task_lease(
+        &tasks_db_path,
+        task_id,
+        task_lease_owner(&config.employee_id),
+        task_lease_ttl(),
+    )? {
+        Some(lease) => lease,
+        None => {
+            let log_key = format!("leased:{}@{}", task_ref.task_id, task_ref.user_id);
+            if should_log_busy(&log_key) {
+                info!(
+                    "scheduler skipped task_id={} user_id={} (leased by another worker)",
+                    task_ref.task_id, task_ref.user_id
+                );
+            }
+            return Ok(());
+        }
     };
 
     let mut scheduler = Scheduler::load(&tasks_db_path, ModuleExecutor::default())?;
@@ -476,8 +1297,46 @@ fn execute_due_task(
         "scheduler executing task_id={} user_id={} kind={} status={}",
         task_ref.task_id, task_ref.user_id, kind_label, status_label
     );
-    let mut thread_guard: Option<RunningThreadGuard> = None;
-    if let Some((key, workspace_dir_display)) = scheduler
+    if let Some(employee_id) = scheduler
+        .tasks()
+        .iter()
+        .find(|task| task.id == task_id && !task.is_interactive())
+        .and_then(|task| match &task.kind {
+            TaskKind::RunTask(run) => Some(
+                run.employee_id
+                    .clone()
+                    .unwrap_or_else(|| config.employee_id.clone()),
+            ),
+            _ => None,
+        })
+    {
+        if let Some(exceeded) = task_budgets::check(Some(&task_ref.user_id), Some(&employee_id)) {
+            defer_over_budget_run_task(&mut scheduler, index_store, task_ref, task_id, &exceeded);
+            return Ok(());
+        }
+    }
+    if scheduler
+        .tasks()
+        .iter()
+        .find(|task| task.id == task_id)
+        .is_some_and(|task| recipient_is_muted(user_store, task_ref, task))
+    {
+        skip_muted_task(&mut scheduler, index_store, task_ref, task_id, now);
+        return Ok(());
+    }
+    if let Some(until) = scheduler
+        .tasks()
+        .iter()
+        .find(|task| task.id == task_id)
+        .and_then(|task| quiet_hours_end(user_store, task_ref, task, now))
+    {
+        defer_quiet_hours_task(&mut scheduler, index_store, task_ref, task_id, until);
+        return Ok(());
+    }
+    let mut thread_turn = None;
+    let mut document_guard: Option<RunningDocumentGuard> = None;
+    let mut epoch_watch = None;
+    if let Some((key, workspace_dir_display, document_key, thread_epoch)) = scheduler
         .tasks()
         .iter()
         .find(|task| task.id == task_id)
@@ -485,50 +1344,77 @@ fn execute_due_task(
             TaskKind::RunTask(run) => Some((
                 run.workspace_dir.to_string_lossy().into_owned(),
                 run.workspace_dir.display().to_string(),
+                document_lock_key(run),
+                run.thread_epoch.map(|epoch| {
+                    let state_path = run
+                        .thread_state_path
+                        .clone()
+                        .unwrap_or_else(|| default_thread_state_path(&run.workspace_dir));
+                    (state_path, epoch)
+                }),
             )),
             _ => None,
         })
     {
-        let mut running = running_threads
-            .lock()
-            .expect("running thread lock poisoned");
-        if running.contains(&key) {
-            drop(running);
-            let defer_result = scheduler.defer_one_shot_task_by_id(
-                task_id,
-                chrono::Duration::seconds(THREAD_BUSY_DEFER_SECS),
-            );
-            let log_key = format!("thread_busy:{}@{}", task_ref.task_id, task_ref.user_id);
-            if should_log_busy(&log_key) {
-                info!(
-                    "scheduler deferred run_task task_id={} user_id={} workspace_dir={} (thread busy, next_attempt_in={}s)",
-                    task_ref.task_id,
-                    task_ref.user_id,
-                    workspace_dir_display,
-                    THREAD_BUSY_DEFER_SECS
-                );
-            }
-            if let Err(err) = defer_result {
-                warn!(
-                    "failed to defer busy run_task task_id={} user_id={}: {}",
-                    task_ref.task_id, task_ref.user_id, err
-                );
+        let task_key = format!("{}@{}", task_ref.task_id, task_ref.user_id);
+        let turn = match thread_queues.enter(&key, &task_key) {
+            Admission::Run(turn) => turn,
+            Admission::Queued { position, newly } => {
+                if newly {
+                    info!(
+                        "scheduler queued run_task task_id={} user_id={} workspace_dir={} (thread busy, position={})",
+                        task_ref.task_id, task_ref.user_id, workspace_dir_display, position
+                    );
+                }
+                return Ok(());
             }
-            if let Err(err) = index_store.sync_user_tasks(&task_ref.user_id, scheduler.tasks()) {
-                warn!(
-                    "scheduler sync failed after thread-busy defer task_id={} user_id={} error={}",
-                    task_ref.task_id, task_ref.user_id, err
+        };
+        if let Some(document_key) = document_key {
+            let mut documents = running_documents
+                .lock()
+                .expect("running document lock poisoned");
+            if documents.contains(&document_key) {
+                drop(documents);
+                drop(turn);
+                defer_busy_run_task(
+                    &mut scheduler,
+                    index_store,
+                    task_ref,
+                    task_id,
+                    DOCUMENT_BUSY_DEFER_SECS,
+                    &format!("document={}", document_key),
+                    "document",
                 );
+                return Ok(());
             }
-            return Ok(());
+            documents.insert(document_key.clone());
+            document_guard = Some(RunningDocumentGuard::new(
+                running_documents.clone(),
+                document_key,
+            ));
         }
-        running.insert(key.clone());
-        thread_guard = Some(RunningThreadGuard::new(running_threads.clone(), key));
+        thread_turn = Some(turn);
+        epoch_watch = thread_epoch
+            .map(|(state_path, epoch)| EpochWatch::start(run_id.to_string(), state_path, epoch));
     }
 
+    // Persisted only once the run is certain to start, so a task skipped or
+    // queued above leaves no intent behind. A one-shot whose run dies with
+    // this worker is made due again at startup even if a later pass
+    // supersedes it.
+    record_execution_intent(&tasks_db_path, task_id)?;
     let executed = scheduler.execute_task_by_id(task_id);
 
-    drop(thread_guard);
+    drop(epoch_watch);
+    drop(thread_turn);
+    drop(document_guard);
+    if !lease.is_held() {
+        warn!(
+            "scheduler lost lease during task_id={} user_id={}; another worker may have run it",
+            task_ref.task_id, task_ref.user_id
+        );
+    }
+    drop(lease);
 
     match executed {
         Ok(true) => {
@@ -590,6 +1476,207 @@ fn execute_due_task(
     }
 }
 
+/// Push a busy run_task forward and resync the index so the poller stops
+/// re-claiming it until the deferral expires.
+fn defer_busy_run_task(
+    scheduler: &mut Scheduler<ModuleExecutor>,
+    index_store: &IndexStore,
+    task_ref: &TaskRef,
+    task_id: Uuid,
+    defer_secs: i64,
+    busy_target: &str,
+    busy_kind: &str,
+) {
+    let defer_result =
+        scheduler.defer_one_shot_task_by_id(task_id, chrono::Duration::seconds(defer_secs));
+    let log_key = format!("busy:{}@{}", task_ref.task_id, task_ref.user_id);
+    if should_log_busy(&log_key) {
+        info!(
+            "scheduler deferred run_task task_id={} user_id={} {} ({} busy, next_attempt_in={}s)",
+            task_ref.task_id, task_ref.user_id, busy_target, busy_kind, defer_secs
+        );
+    }
+    if let Err(err) = defer_result {
+        warn!(
+            "failed to defer busy run_task task_id={} user_id={}: {}",
+            task_ref.task_id, task_ref.user_id, err
+        );
+    }
+    if let Err(err) = index_store.sync_user_tasks(&task_ref.user_id, scheduler.tasks()) {
+        warn!(
+            "scheduler sync failed after busy defer task_id={} user_id={} error={}",
+            task_ref.task_id, task_ref.user_id, err
+        );
+    }
+}
+
+/// Hold a scheduled run_task back until its budget resets. Re-checked at
+/// least hourly so a raised budget takes effect without waiting out the
+/// window.
+fn defer_over_budget_run_task(
+    scheduler: &mut Scheduler<ModuleExecutor>,
+    index_store: &IndexStore,
+    task_ref: &TaskRef,
+    task_id: Uuid,
+    exceeded: &BudgetExceeded,
+) {
+    let until = exceeded
+        .resets_at
+        .min(Utc::now() + chrono::Duration::seconds(BUDGET_RECHECK_SECS));
+    let log_key = format!("over_budget:{}@{}", task_ref.task_id, task_ref.user_id);
+    if should_log_busy(&log_key) {
+        info!(
+            "scheduler deferred run_task task_id={} user_id={} until={} (budget reached: {})",
+            task_ref.task_id,
+            task_ref.user_id,
+            until.to_rfc3339(),
+            exceeded
+        );
+    }
+    if let Err(err) = scheduler.defer_task_until(task_id, until) {
+        warn!(
+            "failed to defer over-budget run_task task_id={} user_id={}: {}",
+            task_ref.task_id, task_ref.user_id, err
+        );
+    }
+    if let Err(err) = index_store.sync_user_tasks(&task_ref.user_id, scheduler.tasks()) {
+        warn!(
+            "scheduler sync failed after budget defer task_id={} user_id={} error={}",
+            task_ref.task_id, task_ref.user_id, err
+        );
+    }
+}
+
+/// End of the recipient's quiet hours when they hold `task` back. Only
+/// non-interactive tasks (scheduled run_tasks, their sends and digests) are
+/// held; replies to a message the user just sent always go out.
+fn quiet_hours_end(
+    user_store: &UserStore,
+    task_ref: &TaskRef,
+    task: &ScheduledTask,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    if task.is_interactive() {
+        return None;
+    }
+    let user_id = recipient_user_id(user_store, task_ref, task);
+    let quiet_hours = match user_store.get_pref(&user_id) {
+        Ok(preferences) => preferences.quiet_hours?,
+        Err(err) => {
+            warn!("failed to load preferences for user {}: {}", user_id, err);
+            return None;
+        }
+    };
+    quiet_hours
+        .contains(now)
+        .then(|| quiet_hours.end_after(now))
+}
+
+/// The user a task is delivered to. Sends and digests go to whoever receives
+/// them; everything else to the owner of the scheduler database.
+fn recipient_user_id(user_store: &UserStore, task_ref: &TaskRef, task: &ScheduledTask) -> String {
+    let recipient = match &task.kind {
+        TaskKind::Digest(digest) => Some((digest.channel, digest.recipient.as_str())),
+        TaskKind::SendReply(send) => send.to.first().map(|to| (send.channel, to.as_str())),
+        _ => None,
+    };
+    recipient
+        .and_then(|(channel, recipient)| {
+            user_store
+                .get_user_by_identifier(channel_to_identifier_type(&channel), recipient)
+                .ok()
+                .flatten()
+        })
+        .map(|user| user.user_id)
+        .unwrap_or_else(|| task_ref.user_id.clone())
+}
+
+/// True when the recipient asked us to stop and has not written since. Like
+/// quiet hours, only non-interactive tasks are affected.
+fn recipient_is_muted(user_store: &UserStore, task_ref: &TaskRef, task: &ScheduledTask) -> bool {
+    if task.is_interactive() {
+        return false;
+    }
+    let user_id = recipient_user_id(user_store, task_ref, task);
+    match user_store.get_pref(&user_id) {
+        Ok(preferences) => preferences.muted,
+        Err(err) => {
+            warn!("failed to load preferences for user {}: {}", user_id, err);
+            false
+        }
+    }
+}
+
+/// Drop a task meant for a muted user: one-shot tasks are disabled, cron
+/// tasks skip this occurrence and check again at the next one.
+fn skip_muted_task(
+    scheduler: &mut Scheduler<ModuleExecutor>,
+    index_store: &IndexStore,
+    task_ref: &TaskRef,
+    task_id: Uuid,
+    now: DateTime<Utc>,
+) {
+    let cron = scheduler
+        .tasks()
+        .iter()
+        .any(|task| task.id == task_id && matches!(task.schedule, Schedule::Cron { .. }));
+    let result = if cron {
+        scheduler
+            .defer_task_until(task_id, now + chrono::Duration::seconds(1))
+            .map(|_| ())
+    } else {
+        scheduler.disable_task_by_id(&task_id.to_string())
+    };
+    info!(
+        "scheduler skipped task_id={} user_id={} (recipient muted)",
+        task_ref.task_id, task_ref.user_id
+    );
+    if let Err(err) = result {
+        warn!(
+            "failed to skip task for muted recipient task_id={} user_id={}: {}",
+            task_ref.task_id, task_ref.user_id, err
+        );
+    }
+    if let Err(err) = index_store.sync_user_tasks(&task_ref.user_id, scheduler.tasks()) {
+        warn!(
+            "scheduler sync failed after muted skip task_id={} user_id={} error={}",
+            task_ref.task_id, task_ref.user_id, err
+        );
+    }
+}
+
+/// Hold a non-interactive task until the recipient's quiet hours end. Cron
+/// tasks run the held occurrence then instead of skipping it.
+fn defer_quiet_hours_task(
+    scheduler: &mut Scheduler<ModuleExecutor>,
+    index_store: &IndexStore,
+    task_ref: &TaskRef,
+    task_id: Uuid,
+    until: DateTime<Utc>,
+) {
+    let log_key = format!("quiet_hours:{}@{}", task_ref.task_id, task_ref.user_id);
+    if should_log_busy(&log_key) {
+        info!(
+            "scheduler held task_id={} user_id={} until={} (quiet hours)",
+            task_ref.task_id,
+            task_ref.user_id,
+            until.to_rfc3339()
+        );
+    }
+    if let Err(err) = scheduler.delay_task_until(task_id, until) {
+        warn!(
+            "failed to hold task for quiet hours task_id={} user_id={}: {}",
+            task_ref.task_id, task_ref.user_id, err
+        );
+    }
+    if let Err(err) = index_store.sync_user_tasks(&task_ref.user_id, scheduler.tasks()) {
+        warn!(
+            "scheduler sync failed after quiet hours hold task_id={} user_id={} error={}",
+            task_ref.task_id, task_ref.user_id, err
+        );
+    }
+}
+
 struct TaskSummary {
     total: usize,
     enabled: usize,
@@ -663,6 +1750,9 @@ fn format_task_line(task: &ScheduledTask, now: DateTime<Utc>) -> String {
 }
 
 fn task_status(task: &ScheduledTask, now: DateTime<Utc>) -> &'static str {
+    if task.awaiting_approval() {
+        return "awaiting_approval";
+    }
     if !task.enabled {
         if task.last_run.is_some() {
             return "completed";
@@ -696,12 +1786,86 @@ fn format_datetime_opt(value: Option<DateTime<Utc>>) -> String {
         .unwrap_or_else(|| "-".to_string())
 }
 
-fn task_kind_label(kind: &TaskKind) -> &'static str {
+pub(super) fn task_kind_label(kind: &TaskKind) -> &'static str {
     match kind {
         TaskKind::SendReply(_) => "send_email",
         TaskKind::RunTask(_) => "run_task",
-        TaskKind::Noop => "noop",
+        TaskKind::Noop(_) => "noop",
+        TaskKind::Digest(_) => "digest",
+        TaskKind::Probe(_) => "probe",
+    }
+}
+
+/// Delay before the run_task for a chat message on `channel` starts. Every
+/// message of a thread cancels the pending run and schedules a new one, so a
+/// burst arriving within the window is answered by one run that sees all of
+/// its messages. The window comes from the employee's inbound policy, else
+/// `INBOUND_COALESCE_SECS` (default 0); a burst never holds its run back
+/// longer than `coalesce_max_secs` (`INBOUND_COALESCE_MAX_SECS`) after its
+/// first message. Call after [`cancel_pending_thread_tasks`].
+pub fn inbound_coalesce_delay<E: crate::TaskExecutor>(
+    scheduler: &Scheduler<E>,
+    workspace: &Path,
+    policy: &InboundPolicy,
+    channel: Channel,
+) -> Duration {
+    let window = policy.coalesce_window(channel).unwrap_or_else(|| {
+        Duration::from_secs(parse_timeout_secs_env("INBOUND_COALESCE_SECS").unwrap_or(0))
+    });
+    if window.is_zero() {
+        return window;
     }
+    let max = policy.coalesce_max.unwrap_or_else(|| {
+        Duration::from_secs(
+            parse_timeout_secs_env("INBOUND_COALESCE_MAX_SECS")
+                .unwrap_or(DEFAULT_INBOUND_COALESCE_MAX_SECS),
+        )
+    });
+    coalesce_delay(scheduler.tasks(), workspace, window, max, Utc::now())
+}
+
+fn coalesce_delay(
+    tasks: &[ScheduledTask],
+    workspace: &Path,
+    window: Duration,
+    max: Duration,
+    now: DateTime<Utc>,
+) -> Duration {
+    let elapsed = burst_start(tasks, workspace, now)
+        .and_then(|start| (now - start).to_std().ok())
+        .unwrap_or_default();
+    window.min(max.saturating_sub(elapsed))
+}
+
+/// When the burst the message arriving at `now` belongs to began: the
+/// creation of the oldest of the workspace's latest inbound runs that were
+/// each replaced before they came due, so none of them ran.
+fn burst_start(
+    tasks: &[ScheduledTask],
+    workspace: &Path,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let mut runs: Vec<&ScheduledTask> = tasks
+        .iter()
+        .filter(|task| match &task.kind {
+            TaskKind::RunTask(run) => run.workspace_dir == workspace && !run.scheduled,
+            _ => false,
+        })
+        .collect();
+    runs.sort_by_key(|task| std::cmp::Reverse(task.created_at));
+    let mut start = None;
+    let mut next_arrival = now;
+    for task in runs {
+        let Schedule::OneShot { run_at } = &task.schedule else {
+            break;
+        };
+        if task.last_run.is_some() || *run_at < next_arrival {
+            break;
+        }
+        start = Some(task.created_at);
+        next_arrival = task.created_at;
+    }
+    start
 }
 
 pub fn cancel_pending_thread_tasks<E: crate::TaskExecutor>(
@@ -710,7 +1874,7 @@ pub fn cancel_pending_thread_tasks<E: crate::TaskExecutor>(
     current_epoch: u64,
 ) -> Result<usize, SchedulerError> {
     let thread_state_path = default_thread_state_path(workspace);
-    scheduler.disable_tasks_by(|task| {
+    scheduler.supersede_tasks_by(|task| {
         if !task.enabled {
             return false;
         }
@@ -736,6 +1900,7 @@ mod tests {
     use super::*;
     use crate::employee_config::{EmployeeDirectory, EmployeeProfile};
     use crate::index_store::IndexStore;
+    use crate::scheduler::tests::run_task_in;
     use crate::service::DEFAULT_INBOUND_BODY_MAX_BYTES;
     use crate::user_store::UserStore;
     use std::collections::{HashMap, HashSet};
@@ -798,11 +1963,23 @@ mod tests {
             runtime_root: None,
             agents_path: None,
             claude_path: None,
+            gemini_path: None,
             soul_path: None,
             skills_dir: None,
             discord_enabled: false,
             slack_enabled: false,
             bluebubbles_enabled: false,
+            outbound_policy: Default::default(),
+            inbound_policy: Default::default(),
+            approver: None,
+            slack_approvers: Vec::new(),
+            redaction: None,
+            archive_tiering: None,
+            sandbox: None,
+            language: None,
+            mention_names: Vec::new(),
+            router: Default::default(),
+            formatting: Default::default(),
         };
         let mut employee_by_id = HashMap::new();
         employee_by_id.insert(employee_profile.id.clone(), employee_profile.clone());
@@ -831,6 +2008,7 @@ mod tests {
             users_db_path: state_dir.join("users.db"),
             task_index_path: state_dir.join("task_index.db"),
             codex_model: "test".to_string(),
+            gemini_model: "gemini-2.5-pro".to_string(),
             codex_disabled: true,
             scheduler_poll_interval: Duration::from_millis(20),
             scheduler_max_concurrency: 1,
@@ -855,8 +2033,8 @@ mod tests {
         })
     }
 
-    #[test]
-    fn stop_and_join_returns_quickly_with_short_watchdog_interval() {
+    #[tokio::test(flavor = "multi_thread")]
+    async fn stop_and_join_returns_quickly_with_short_watchdog_interval() {
         let _guard = EnvGuard::set("WATCHDOG_INTERVAL_MS", "100");
         let temp = TempDir::new().expect("tempdir");
         let Some(config) = build_test_config(&temp) else {
@@ -867,8 +2045,8 @@ mod tests {
 
         let start = Instant::now();
         let mut control =
-            start_scheduler_threads(Arc::new(config), user_store.clone(), index_store.clone());
-        control.stop_and_join();
+            start_scheduler_tasks(Arc::new(config), user_store.clone(), index_store.clone()).await;
+        control.stop_and_join().await;
 
         let elapsed = start.elapsed();
         assert!(
@@ -934,4 +2112,119 @@ mod tests {
             "MAX_TASK_RETRIES should match number of backoff delays"
         );
     }
+
+    fn run_task_for(channel: Channel, thread_id: &str) -> RunTaskTask {
+        RunTaskTask {
+            thread_id: Some(thread_id.to_string()),
+            channel,
+            ..run_task_in(Path::new("/tmp/workspace"))
+        }
+    }
+
+    #[test]
+    fn a_burst_of_messages_is_answered_by_one_run() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspace = temp.path().join("workspace");
+        let mut scheduler =
+            Scheduler::load(temp.path().join("tasks.db"), ModuleExecutor).expect("load");
+        let policy = InboundPolicy {
+            coalesce: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+
+        for epoch in 1..=3 {
+            cancel_pending_thread_tasks(&mut scheduler, &workspace, epoch).expect("cancel");
+            let delay = inbound_coalesce_delay(&scheduler, &workspace, &policy, Channel::Slack);
+            assert!(delay > Duration::ZERO);
+            let mut run_task = run_task_for(Channel::Slack, "slack:C1:1700000000.000100");
+            run_task.workspace_dir = workspace.clone();
+            run_task.thread_epoch = Some(epoch);
+            scheduler
+                .add_one_shot_in(delay, TaskKind::RunTask(run_task))
+                .expect("schedule");
+        }
+
+        let pending: Vec<&ScheduledTask> = scheduler
+            .tasks()
+            .iter()
+            .filter(|task| task.enabled)
+            .collect();
+        assert_eq!(pending.len(), 1);
+        assert!(matches!(
+            &pending[0].kind,
+            TaskKind::RunTask(run) if run.thread_epoch == Some(3)
+        ));
+    }
+
+    #[test]
+    fn a_burst_holds_its_run_back_no_longer_than_the_cap() {
+        let now = Utc::now();
+        let workspace = PathBuf::from("/tmp/workspace");
+        let window = Duration::from_secs(30);
+        // A message every 20s, each replacing the run 10s before it came due.
+        let tasks: Vec<ScheduledTask> = (1..=3)
+            .map(|i| {
+                let created_at = now - chrono::Duration::seconds(20 * i);
+                ScheduledTask {
+                    id: Uuid::new_v4(),
+                    kind: TaskKind::RunTask(run_task_for(Channel::Slack, "slack:C1:1")),
+                    schedule: Schedule::OneShot {
+                        run_at: created_at + chrono::Duration::seconds(30),
+                    },
+                    enabled: false,
+                    created_at,
+                    last_run: None,
+                    approval: None,
+                    description: None,
+                    ends: None,
+                }
+            })
+            .collect();
+
+        assert_eq!(
+            coalesce_delay(&tasks, &workspace, window, Duration::from_secs(75), now),
+            Duration::from_secs(15)
+        );
+        assert_eq!(
+            coalesce_delay(&tasks, &workspace, window, Duration::from_secs(60), now),
+            Duration::ZERO
+        );
+
+        let mut ran = tasks.clone();
+        ran[0].last_run = Some(now - chrono::Duration::seconds(5));
+        assert_eq!(
+            coalesce_delay(&ran, &workspace, window, Duration::from_secs(60), now),
+            window
+        );
+    }
+
+    #[test]
+    fn document_lock_key_is_shared_across_comments_on_same_doc() {
+        let first = run_task_for(Channel::GoogleDocs, "gdocs:doc-123:comment-a");
+        let second = run_task_for(Channel::GoogleDocs, "gdocs:doc-123:comment-b");
+        let other = run_task_for(Channel::GoogleDocs, "gdocs:doc-456:comment-a");
+
+        assert_eq!(document_lock_key(&first).as_deref(), Some("gdocs:doc-123"));
+        assert_eq!(document_lock_key(&first), document_lock_key(&second));
+        assert_ne!(document_lock_key(&first), document_lock_key(&other));
+    }
+
+    #[test]
+    fn document_lock_key_covers_sheets_and_slides_only() {
+        let sheets = run_task_for(Channel::GoogleSheets, "gsheets:sheet-1:c1");
+        let slides = run_task_for(Channel::GoogleSlides, "gslides:deck-1:c1");
+        let email = run_task_for(Channel::Email, "gdocs:doc-123:comment-a");
+        let malformed = run_task_for(Channel::GoogleDocs, "gdocs");
+
+        assert_eq!(
+            document_lock_key(&sheets).as_deref(),
+            Some("gsheets:sheet-1")
+        );
+        assert_eq!(
+            document_lock_key(&slides).as_deref(),
+            Some("gslides:deck-1")
+        );
+        assert_eq!(document_lock_key(&email), None);
+        assert_eq!(document_lock_key(&malformed), None);
+    }
 }
@@ -0,0 +1,303 @@
+//! Per-sender limits, checked by the ingestion consumer before a message
+//! gets a user or a workspace.
+//!
+//! Every sender is capped at the employee's `max_messages_per_hour` (else
+//! `INBOUND_SENDER_MAX_PER_HOUR`, default 30; `0` lifts the cap), counted
+//! per process. Employees whose `inbound_policy.new_contacts` is not `open`
+//! only hear from allowed senders and known contacts, i.e. senders who
+//! already have a user. In `approval` mode a new sender's first message is
+//! held in an approval request; approving it re-enqueues the message, and
+//! the sender is known from then on. Further messages while the request is
+//! pending, and all messages after a rejection, are dropped.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::account_store::channel_to_identifier_type;
+use crate::approval_store::{
+    get_global_approval_store, ApprovalRecord, ApprovalStatus, APPROVAL_TTL_HOURS,
+};
+use crate::channel::Channel;
+use crate::inbound_policy::{normalize_sender, NewContacts};
+use crate::ingestion::IngestionEnvelope;
+use crate::ingestion_queue::get_global_ingestion_queue;
+use crate::scheduler::notify_approver;
+use crate::user_store::UserStore;
+
+use super::config::ServiceConfig;
+use super::BoxError;
+
+/// `ApprovalRecord::kind` of a held first message.
+pub(super) const FIRST_CONTACT_KIND: &str = "first_contact";
+
+const DEFAULT_SENDER_MAX_PER_HOUR: usize = 30;
+const SUMMARY_PREVIEW_CHARS: usize = 200;
+
+static SENDER_HISTORY: LazyLock<Mutex<HashMap<String, VecDeque<DateTime<Utc>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Gate {
+    Admit,
+    RateLimited { count: usize, max: usize },
+    NotAllowed,
+    AwaitingApproval { newly: bool },
+}
+
+/// Whether `envelope` may be processed; logs why not. Handoffs and
+/// employee-to-employee messages are not limited.
+pub(super) fn admit_sender(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    envelope: &IngestionEnvelope,
+) -> bool {
+    if envelope.channel == Channel::Internal || envelope.handoff.is_some() {
+        return true;
+    }
+    let sender = envelope.payload.sender.trim();
+    if sender.is_empty() {
+        return true;
+    }
+    match check_sender(config, user_store, envelope, sender, Utc::now()) {
+        Gate::Admit => true,
+        Gate::RateLimited { count, max } => {
+            info!(
+                "dropping {} message from {}: {} in the past hour (max {})",
+                envelope.channel, sender, count, max
+            );
+            false
+        }
+        Gate::NotAllowed => {
+            info!(
+                "dropping {} message from new contact {}: not on the allow list",
+                envelope.channel, sender
+            );
+            false
+        }
+        Gate::AwaitingApproval { newly } => {
+            if newly {
+                info!(
+                    "holding first {} message from {} for approval",
+                    envelope.channel, sender
+                );
+            } else {
+                info!(
+                    "dropping {} message from {}: first contact awaiting approval",
+                    envelope.channel, sender
+                );
+            }
+            false
+        }
+    }
+}
+
+fn check_sender(
+    config: &ServiceConfig,
+    user_store: &UserStore,
+    envelope: &IngestionEnvelope,
+    sender: &str,
+    now: DateTime<Utc>,
+) -> Gate {
+    let policy = &config.employee_profile.inbound_policy;
+    let max = policy.max_messages_per_hour.unwrap_or_else(|| {
+        std::env::var("INBOUND_SENDER_MAX_PER_HOUR")
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(DEFAULT_SENDER_MAX_PER_HOUR)
+    });
+    if max > 0 {
+        let key = format!("{}:{}", envelope.channel, normalize_sender(sender));
+        let count = record_sender(&key, now);
+        if count > max {
+            return Gate::RateLimited { count, max };
+        }
+    }
+
+    if policy.new_contacts == NewContacts::Open
+        || policy.allows(sender)
+        || is_known_contact(user_store, envelope.channel, sender)
+    {
+        return Gate::Admit;
+    }
+    match policy.new_contacts {
+        NewContacts::Open => Gate::Admit,
+        NewContacts::Allowlist => Gate::NotAllowed,
+        NewContacts::Approval => hold_first_contact(config, envelope, sender, now),
+    }
+}
+
+fn is_known_contact(user_store: &UserStore, channel: Channel, sender: &str) -> bool {
+    match user_store.get_user_by_identifier(channel_to_identifier_type(&channel), sender) {
+        Ok(user) => user.is_some(),
+        Err(err) => {
+            warn!(
+                "failed to look up sender {} on {}: {}",
+                sender, channel, err
+            );
+            false
+        }
+    }
+}
+
+/// Look up, or raise, the approval request for `sender`'s first message.
+fn hold_first_contact(
+    config: &ServiceConfig,
+    envelope: &IngestionEnvelope,
+    sender: &str,
+    now: DateTime<Utc>,
+) -> Gate {
+    let Some(approver) = config.employee_profile.approver.clone() else {
+        warn!(
+            "employee {} asks for first-contact approval but has no approver",
+            config.employee_id
+        );
+        return Gate::NotAllowed;
+    };
+    let Some(store) = get_global_approval_store() else {
+        return Gate::NotAllowed;
+    };
+    let approval_id = first_contact_id(&config.employee_id, envelope.channel, sender);
+    match store.get(&approval_id) {
+        Ok(Some(record)) => {
+            return match record.status {
+                ApprovalStatus::Approved => Gate::Admit,
+                ApprovalStatus::Pending if record.expires_at > now => {
+                    Gate::AwaitingApproval { newly: false }
+                }
+                _ => Gate::NotAllowed,
+            };
+        }
+        Ok(None) => {}
+        Err(err) => {
+            warn!("failed to load approval {}: {}", approval_id, err);
+            return Gate::NotAllowed;
+        }
+    }
+
+    let held_envelope = match serde_json::to_string(envelope) {
+        Ok(json) => json,
+        Err(err) => {
+            warn!("failed to hold envelope {}: {}", envelope.envelope_id, err);
+            return Gate::NotAllowed;
+        }
+    };
+    let preview: String = envelope
+        .payload
+        .text_body
+        .as_deref()
+        .or(envelope.payload.subject.as_deref())
+        .unwrap_or("")
+        .chars()
+        .take(SUMMARY_PREVIEW_CHARS)
+        .collect();
+    let record = ApprovalRecord {
+        approval_id: approval_id.clone(),
+        token: Uuid::new_v4().simple().to_string(),
+        employee_id: Some(config.employee_id.clone()),
+        owner_user_id: None,
+        tasks_db_path: String::new(),
+        task_id: envelope.envelope_id.to_string(),
+        kind: FIRST_CONTACT_KIND.to_string(),
+        summary: format!(
+            "Reply to a first message from {} on {}: \"{}\"",
+            sender, envelope.channel, preview
+        ),
+        approver,
+        requester: None,
+        status: ApprovalStatus::Pending,
+        decided_by: None,
+        created_at: now,
+        expires_at: now + Duration::hours(APPROVAL_TTL_HOURS),
+        held_envelope: Some(held_envelope),
+    };
+    match store.insert_if_absent(&record) {
+        Ok(true) => {
+            if let Err(err) = notify_approver(&record) {
+                warn!("failed to notify approver for {}: {}", approval_id, err);
+            }
+            Gate::AwaitingApproval { newly: true }
+        }
+        Ok(false) => Gate::AwaitingApproval { newly: false },
+        Err(err) => {
+            warn!("failed to store approval {}: {}", approval_id, err);
+            Gate::NotAllowed
+        }
+    }
+}
+
+/// Re-enqueue the message an approved first-contact request held.
+pub(super) fn release_first_contact(record: &ApprovalRecord) -> Result<(), BoxError> {
+    if record.status != ApprovalStatus::Approved {
+        return Ok(());
+    }
+    let Some(held) = record.held_envelope.as_deref() else {
+        return Ok(());
+    };
+    let mut envelope: IngestionEnvelope = serde_json::from_str(held)?;
+    envelope.envelope_id = Uuid::new_v4();
+    envelope.dedupe_key = format!("{}:approved", envelope.dedupe_key);
+    let queue = get_global_ingestion_queue().ok_or("no ingestion queue")?;
+    queue.enqueue(&envelope)?;
+    Ok(())
+}
+
+/// Stable per employee, channel and sender, so one sender gets one request.
+fn first_contact_id(employee_id: &str, channel: Channel, sender: &str) -> String {
+    let digest = Sha256::digest(
+        format!("{}:{}:{}", employee_id, channel, normalize_sender(sender)).as_bytes(),
+    );
+    let hex = format!("{:x}", digest);
+    format!("{}_{}", FIRST_CONTACT_KIND, &hex[..32])
+}
+
+/// Record a message under `key` at `now`; returns how many arrived in the
+/// past hour, this one included.
+fn record_sender(key: &str, now: DateTime<Utc>) -> usize {
+    let window_start = now - Duration::hours(1);
+    let mut history = SENDER_HISTORY
+        .lock()
+        .unwrap_or_else(|poison| poison.into_inner());
+    history.retain(|_, sent| {
+        while sent.front().is_some_and(|at| *at <= window_start) {
+            sent.pop_front();
+        }
+        !sent.is_empty()
+    });
+    let sent = history.entry(key.to_string()).or_default();
+    sent.push_back(now);
+    sent.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sender_counts_only_cover_the_past_hour() {
+        let key = "email:burst-sender@example.com";
+        let start = Utc::now();
+        assert_eq!(record_sender(key, start), 1);
+        assert_eq!(record_sender(key, start + Duration::minutes(30)), 2);
+        assert_eq!(record_sender(key, start + Duration::minutes(61)), 2);
+        assert_eq!(record_sender(key, start + Duration::minutes(200)), 1);
+    }
+
+    #[test]
+    fn first_contact_ids_ignore_address_formatting() {
+        let id = first_contact_id("boiled_egg", Channel::Email, "Ann <Ann@Acme.com>");
+        assert_eq!(
+            id,
+            first_contact_id("boiled_egg", Channel::Email, "ann@acme.com")
+        );
+        assert_ne!(
+            id,
+            first_contact_id("little_bear", Channel::Email, "ann@acme.com")
+        );
+        assert!(id.starts_with("first_contact_"));
+    }
+}
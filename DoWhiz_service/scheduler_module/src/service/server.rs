@@ -4,22 +4,25 @@ use std::sync::Arc;
 use socket2::{Domain, Protocol, Socket, Type};
 
 use axum::extract::{DefaultBodyLimit, Query, State};
-use axum::http::StatusCode;
+use axum::http::{header, HeaderMap, StatusCode};
 use axum::response::{IntoResponse, Redirect};
 use axum::routing::get;
-use axum::Router;
+use axum::{Json, Router};
 use chrono::Utc;
+use serde_json::json;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::{error, info};
 
 use crate::account_store::AccountStore;
+use crate::adapters::configure_employee_mentions;
 use crate::blob_store::get_blob_store;
+use crate::health_probe;
+use crate::i18n::{resolve_locale, Locale, Message};
 use crate::index_store::IndexStore;
-use crate::ingestion_queue::{build_queue_from_env, IngestionQueue};
+use crate::ingestion_queue::{build_queue_from_env, set_global_ingestion_queue, IngestionQueue};
 use crate::message_router::MessageRouter;
-use crate::mongo_store::{
-    bootstrap_indexes_from_env, health_check_from_env, mongo_database_name_from_env,
-};
+use crate::migrations::migrate_from_env;
+use crate::mongo_store::{health_check_from_env, mongo_database_name_from_env};
 use crate::slack_store::{SlackInstallation, SlackStore};
 use crate::storage_backend::StorageBackend;
 use crate::user_store::UserStore;
@@ -28,12 +31,24 @@ use tokio::task;
 
 use super::agent_market::{agent_market_router, AgentMarketState};
 use super::analytics::{analytics_router, AnalyticsState};
+use super::approvals::{approvals_router, ApprovalsState};
+use super::audit::{audit_router, AuditState};
 use super::auth::{auth_router, AuthState};
 use super::billing::{billing_router, BillingState};
+use super::bounces::{bounces_router, BouncesState};
+use super::broadcasts::{broadcasts_router, BroadcastsState};
+use super::costs::{costs_router, CostsState};
+use super::dashboard::{dashboard_router, DashboardState};
+use super::ops::{ops_router, OpsState};
+use super::readiness::{readiness_router, ReadinessState};
+use super::tools::{tools_router, ToolsState};
 
 use super::config::ServiceConfig;
 use super::ingestion::spawn_ingestion_consumer;
-use super::scheduler::start_scheduler_threads;
+use super::scheduler::{
+    ensure_employee_heartbeat, heartbeat_config_from_env, probe_config_from_env,
+    start_scheduler_tasks, sync_employee_probe, USER_HEARTBEAT_NAME,
+};
 use super::state::AppState;
 use super::BoxError;
 
@@ -46,7 +61,7 @@ pub async fn run_server(
         task::spawn_blocking(health_check_from_env)
             .await
             .map_err(|err| -> BoxError { err.into() })??;
-        task::spawn_blocking(bootstrap_indexes_from_env)
+        task::spawn_blocking(migrate_from_env)
             .await
             .map_err(|err| -> BoxError { err.into() })??;
         info!(
@@ -76,6 +91,7 @@ pub async fn run_server(
 
     // Export SLACK_STORE_PATH so execute_slack_send can find the OAuth tokens
     std::env::set_var("SLACK_STORE_PATH", &config.slack_store_path);
+    configure_employee_mentions(&config.employee_directory);
     let config = Arc::new(config);
     let user_store = Arc::new(UserStore::new(&config.users_db_path)?);
     let index_store = Arc::new(IndexStore::new(&config.task_index_path)?);
@@ -85,46 +101,82 @@ pub async fn run_server(
         task::spawn_blocking(move || build_queue_from_env(Some(ingestion_db_url)))
             .await
             .map_err(|err| -> BoxError { err.into() })??;
-    let message_router = Arc::new(MessageRouter::new());
+    set_global_ingestion_queue(ingestion_queue.clone());
+    let message_router = Arc::new(MessageRouter::for_employee(&config.employee_profile));
     let bootstrap_user_store = user_store.clone();
     let bootstrap_index_store = index_store.clone();
-    let bootstrap_users_root = config.users_root.clone();
-    task::spawn_blocking(move || match bootstrap_user_store.list_user_ids() {
-        Ok(user_ids) => {
-            let total = user_ids.len();
-            if total > 0 {
-                info!("index bootstrap started for {} user(s)", total);
+    let bootstrap_config = config.clone();
+    let heartbeat = heartbeat_config_from_env();
+    let probe = probe_config_from_env();
+    task::spawn_blocking(move || {
+        if let Some(heartbeat) = &heartbeat {
+            if let Err(err) =
+                ensure_employee_heartbeat(&bootstrap_config, &bootstrap_index_store, heartbeat)
+            {
+                error!("employee heartbeat setup failed: {}", err);
             }
-            for (idx, user_id) in user_ids.into_iter().enumerate() {
-                let paths = bootstrap_user_store.user_paths(&bootstrap_users_root, &user_id);
-                let scheduler = Scheduler::load(&paths.tasks_db_path, ModuleExecutor::default());
-                match scheduler {
-                    Ok(scheduler) => {
-                        if let Err(err) =
-                            bootstrap_index_store.sync_user_tasks(&user_id, scheduler.tasks())
-                        {
-                            error!("index bootstrap failed for {}: {}", user_id, err);
+        }
+        if let Err(err) =
+            sync_employee_probe(&bootstrap_config, &bootstrap_index_store, probe.as_ref())
+        {
+            error!("health probe setup failed: {}", err);
+        }
+        match bootstrap_user_store.list_user_ids() {
+            Ok(user_ids) => {
+                let total = user_ids.len();
+                if total > 0 {
+                    info!("index bootstrap started for {} user(s)", total);
+                }
+                for (idx, user_id) in user_ids.into_iter().enumerate() {
+                    let paths =
+                        bootstrap_user_store.user_paths(&bootstrap_config.users_root, &user_id);
+                    let scheduler =
+                        Scheduler::load(&paths.tasks_db_path, ModuleExecutor::default());
+                    match scheduler {
+                        Ok(mut scheduler) => {
+                            match scheduler.recover_intents() {
+                                Ok(0) => {}
+                                Ok(recovered) => info!(
+                                    "recovered {} unfinished one-shot task(s) for {}",
+                                    recovered, user_id
+                                ),
+                                Err(err) => {
+                                    error!("intent recovery failed for {}: {}", user_id, err)
+                                }
+                            }
+                            if let Some(heartbeat) = &heartbeat {
+                                if let Err(err) =
+                                    heartbeat.ensure(&mut scheduler, USER_HEARTBEAT_NAME)
+                                {
+                                    error!("user heartbeat setup failed for {}: {}", user_id, err);
+                                }
+                            }
+                            if let Err(err) =
+                                bootstrap_index_store.sync_user_tasks(&user_id, scheduler.tasks())
+                            {
+                                error!("index bootstrap failed for {}: {}", user_id, err);
+                            }
+                        }
+                        Err(err) => {
+                            error!("scheduler bootstrap failed for {}: {}", user_id, err);
                         }
                     }
-                    Err(err) => {
-                        error!("scheduler bootstrap failed for {}: {}", user_id, err);
+                    if (idx + 1) % 100 == 0 {
+                        info!("index bootstrap progress: {}/{} user(s)", idx + 1, total);
                     }
                 }
-                if (idx + 1) % 100 == 0 {
-                    info!("index bootstrap progress: {}/{} user(s)", idx + 1, total);
+                if total > 0 {
+                    info!("index bootstrap finished for {} user(s)", total);
                 }
             }
-            if total > 0 {
-                info!("index bootstrap finished for {} user(s)", total);
+            Err(err) => {
+                error!("index bootstrap skipped: failed to list users: {}", err);
             }
         }
-        Err(err) => {
-            error!("index bootstrap skipped: failed to list users: {}", err);
-        }
     });
 
     let mut scheduler_control =
-        start_scheduler_threads(config.clone(), user_store.clone(), index_store.clone());
+        start_scheduler_tasks(config.clone(), user_store.clone(), index_store.clone()).await;
 
     info!(
         "Inbound webhooks are handled by the ingestion gateway; worker {} will only consume queued messages",
@@ -207,22 +259,65 @@ pub async fn run_server(
         users_root: Some(config.users_root.clone()),
     };
     let analytics_state = AnalyticsState::from_env(auth_state.account_store.clone());
+    let broadcasts_state = BroadcastsState::from_env(
+        config.clone(),
+        user_store.clone(),
+        index_store.clone(),
+        auth_state.account_store.clone(),
+    );
+    let ops_state = OpsState::from_env(
+        config.clone(),
+        user_store.clone(),
+        index_store.clone(),
+        ingestion_queue.clone(),
+    );
+    let bounces_state = BouncesState::from_env(
+        config.clone(),
+        user_store.clone(),
+        index_store.clone(),
+        auth_state.account_store.clone(),
+    );
+    let dashboard_state =
+        DashboardState::from_env(config.clone(), user_store.clone(), index_store.clone());
     let agent_market_state = AgentMarketState::from_env();
+    let readiness_state = ReadinessState::from_env(
+        config.clone(),
+        scheduler_control.pulse.clone(),
+        ingestion_control.pulse.clone(),
+    );
 
     let mut app = Router::new()
         .route("/", get(health))
         .route("/health", get(health))
+        .route("/health/detail", get(health_detail))
         .route("/slack/install", get(slack_install))
         .route("/slack/oauth/callback", get(slack_oauth_callback))
         .with_state(state)
+        .merge(readiness_router(readiness_state))
         .merge(auth_router(auth_state))
         .merge(analytics_router(analytics_state))
+        .merge(audit_router(AuditState::from_env()))
+        .merge(costs_router(CostsState::from_env()))
+        .merge(approvals_router(ApprovalsState {
+            index_store: index_store.clone(),
+        }))
+        .merge(broadcasts_router(broadcasts_state))
+        .merge(ops_router(ops_state))
+        .merge(dashboard_router(dashboard_state))
         .merge(agent_market_router(agent_market_state));
 
     // Add billing routes if Stripe is configured
     if let Some(billing) = billing_state {
         app = app.merge(billing_router(billing));
     }
+    // Bounce and delivery webhooks only when Postmark credentials are set
+    if let Some(bounces) = bounces_state {
+        app = app.merge(bounces_router(bounces));
+    }
+    // Runner tools only when runners have a token to call them with
+    if let Some(tools) = ToolsState::from_env() {
+        app = app.merge(tools_router(tools));
+    }
 
     let app = app
         .layer(DefaultBodyLimit::max(config.inbound_body_max_bytes))
@@ -237,8 +332,8 @@ pub async fn run_server(
         .with_graceful_shutdown(shutdown)
         .await;
     info!("shutdown signal received, stopping services...");
-    ingestion_control.stop_and_join();
-    scheduler_control.stop_and_join();
+    ingestion_control.stop_and_join().await;
+    scheduler_control.stop_and_join().await;
 
     // Clean up any active ACI containers to prevent orphans
     let cleaned = run_task_module::cleanup_all_aci_containers();
@@ -257,6 +352,36 @@ async fn health() -> impl IntoResponse {
     (StatusCode::OK, "ok")
 }
 
+/// GET /health/detail - Outcome of the latest health probe; 503 once the
+/// probe failed or is overdue, "unknown" before the first probe finished.
+async fn health_detail(State(state): State<AppState>) -> impl IntoResponse {
+    let state_path = health_probe::state_path(&state.config.scheduler_state_path);
+    let probe = match task::spawn_blocking(move || health_probe::load_state(&state_path)).await {
+        Ok(Ok(probe)) => probe,
+        Ok(Err(err)) => {
+            error!("health probe state unreadable: {}", err);
+            None
+        }
+        Err(err) => {
+            error!("health probe state join error: {}", err);
+            None
+        }
+    };
+    let (code, status) = match probe.as_ref().and_then(|probe| probe.healthy(Utc::now())) {
+        Some(true) => (StatusCode::OK, "ok"),
+        Some(false) => (StatusCode::SERVICE_UNAVAILABLE, "degraded"),
+        None => (StatusCode::OK, "unknown"),
+    };
+    (
+        code,
+        Json(json!({
+            "status": status,
+            "employee_id": state.config.employee_id,
+            "probe": probe,
+        })),
+    )
+}
+
 /// Redirect to Slack OAuth authorization page.
 /// GET /slack/install
 async fn slack_install(State(state): State<AppState>) -> impl IntoResponse {
@@ -297,10 +422,12 @@ struct SlackOAuthCallbackParams {
     error: Option<String>,
 }
 
-/// Handle Slack OAuth callback.
+/// Handle Slack OAuth callback. The success page follows the installer's
+/// browser language, then the employee's.
 /// GET /slack/oauth/callback?code=...
 async fn slack_oauth_callback(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Query(params): Query<SlackOAuthCallbackParams>,
 ) -> impl IntoResponse {
     // Check for OAuth errors
@@ -442,24 +569,38 @@ async fn slack_oauth_callback(
     );
 
     // Return success page
+    let browser_language = headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Locale::from_accept_language);
+    let locale = browser_language
+        .unwrap_or_else(|| resolve_locale(None, state.config.employee_profile.language.as_deref()));
     let html = format!(
         r#"<!DOCTYPE html>
-<html>
+<html lang="{}">
 <head>
   <meta charset="UTF-8" />
   <meta
     name="description"
-    content="DoWhiz Slack integration is installed. Confirm your workspace, learn next steps, and start chatting with digital employees right away."
+    content="{}"
   />
-  <title>DoWhiz Slack Integration | Install Complete and Next Steps</title>
+  <title>{}</title>
 </head>
 <body style="font-family: sans-serif; text-align: center; padding: 50px;">
-    <h1>Installation Complete!</h1>
-    <p>DoWhiz has been successfully installed to <strong>{}</strong>.</p>
-    <p>You can now close this window and start chatting with the bot in Slack.</p>
+    <h1>{}</h1>
+    <p>{}</p>
+    <p>{}</p>
 </body>
 </html>"#,
-        team_name.unwrap_or(team_id)
+        locale.code(),
+        locale.text(Message::SlackInstallDescription),
+        locale.text(Message::SlackInstallTitle),
+        locale.text(Message::SlackInstallHeading),
+        locale.render(
+            Message::SlackInstallBody,
+            &[("team", &team_name.unwrap_or(team_id))]
+        ),
+        locale.text(Message::SlackInstallClose)
     );
 
     (StatusCode::OK, axum::response::Html(html)).into_response()
@@ -0,0 +1,192 @@
+//! Spam screening of inbound email, ahead of `process_inbound_payload`, so
+//! cold outreach and phishing never reach a run.
+//!
+//! A message is scored from the authentication results Postmark records in
+//! its headers (SPF, DKIM, DMARC and its own SpamAssassin verdict) and from
+//! a few keyword heuristics; at `INBOUND_SPAM_THRESHOLD` (default 5, `0` turns
+//! scoring off) it is dropped. Per-sender caps are applied to every channel
+//! in `sender_gate`.
+
+use super::postmark::PostmarkInbound;
+
+const DEFAULT_SPAM_THRESHOLD: u32 = 5;
+/// Keyword points are capped so a long newsletter cannot score on wording
+/// alone.
+const MAX_KEYWORD_POINTS: u32 = 4;
+
+/// Phrases typical of phishing; each match scores 2.
+const PHISHING_PHRASES: &[&str] = &[
+    "verify your account",
+    "confirm your identity",
+    "your password will expire",
+    "your account has been suspended",
+    "unusual sign-in activity",
+    "update your payment details",
+    "wire transfer",
+    "gift card",
+    "bitcoin wallet",
+];
+
+/// Phrases typical of cold outreach; each match scores 1.
+const OUTREACH_PHRASES: &[&str] = &[
+    "quick call",
+    "15 minutes of your time",
+    "book a demo",
+    "limited time offer",
+    "act now",
+    "increase your revenue",
+    "boost your sales",
+    "guest post",
+    "seo services",
+    "lead generation",
+    "unsubscribe",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum SpamVerdict {
+    Accept,
+    /// Scored at or above the threshold; `reasons` lists what counted.
+    Spam {
+        score: u32,
+        reasons: Vec<String>,
+    },
+}
+
+pub(super) fn screen_inbound_email(payload: &PostmarkInbound) -> SpamVerdict {
+    let threshold = std::env::var("INBOUND_SPAM_THRESHOLD")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_SPAM_THRESHOLD);
+    if threshold == 0 {
+        return SpamVerdict::Accept;
+    }
+    let (score, reasons) = spam_score(payload);
+    if score >= threshold {
+        SpamVerdict::Spam { score, reasons }
+    } else {
+        SpamVerdict::Accept
+    }
+}
+
+/// Points and the reasons behind them. Keywords are not scored on replies
+/// to an earlier message, where the wording usually comes from the quote.
+fn spam_score(payload: &PostmarkInbound) -> (u32, Vec<String>) {
+    let mut score = 0;
+    let mut reasons = Vec::new();
+    let mut add = |points: u32, reason: String| {
+        score += points;
+        reasons.push(reason);
+    };
+
+    let authentication = payload
+        .header_values("Authentication-Results")
+        .join(";")
+        .to_ascii_lowercase();
+    let received_spf = payload
+        .header_value("Received-SPF")
+        .map(|value| value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    if received_spf.starts_with("fail") || authentication.contains("spf=fail") {
+        add(3, "spf=fail".to_string());
+    } else if received_spf.starts_with("softfail") || authentication.contains("spf=softfail") {
+        add(1, "spf=softfail".to_string());
+    }
+    if authentication.contains("dkim=fail") {
+        add(2, "dkim=fail".to_string());
+    }
+    if authentication.contains("dmarc=fail") {
+        add(3, "dmarc=fail".to_string());
+    }
+
+    let spam_status = payload
+        .header_value("X-Spam-Status")
+        .map(|value| value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+    if spam_status.starts_with("yes") {
+        add(5, "x-spam-status=yes".to_string());
+    } else if let Some(spam_score) = payload
+        .header_value("X-Spam-Score")
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|spam_score| *spam_score >= 3.0)
+    {
+        add(2, format!("x-spam-score={}", spam_score));
+    }
+
+    if payload.header_value("In-Reply-To").is_none() {
+        let text = format!(
+            "{}\n{}",
+            payload.subject.as_deref().unwrap_or(""),
+            payload.text_body.as_deref().unwrap_or("")
+        )
+        .to_lowercase();
+        let mut keyword_points = 0;
+        for (phrases, points) in [(PHISHING_PHRASES, 2), (OUTREACH_PHRASES, 1)] {
+            for phrase in phrases.iter().filter(|phrase| text.contains(*phrase)) {
+                if keyword_points >= MAX_KEYWORD_POINTS {
+                    break;
+                }
+                let points = points.min(MAX_KEYWORD_POINTS - keyword_points);
+                keyword_points += points;
+                add(points, format!("keyword \"{}\"", phrase));
+            }
+        }
+    }
+
+    (score, reasons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn payload(from: &str, headers: &[(&str, &str)], subject: &str, body: &str) -> PostmarkInbound {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| json!({ "Name": name, "Value": value }))
+            .collect::<Vec<_>>();
+        serde_json::from_value(json!({
+            "From": from,
+            "Subject": subject,
+            "TextBody": body,
+            "Headers": headers,
+        }))
+        .expect("payload")
+    }
+
+    #[test]
+    fn failed_authentication_and_phishing_wording_score_as_spam() {
+        let phishing = payload(
+            "security@examp1e-bank.test",
+            &[
+                ("Received-SPF", "Fail (sender not permitted)"),
+                ("Authentication-Results", "mx.test; dkim=fail; dmarc=fail"),
+            ],
+            "Action required",
+            "Please verify your account before your password will expire.",
+        );
+        let (score, reasons) = spam_score(&phishing);
+        assert!(score >= DEFAULT_SPAM_THRESHOLD, "{score} {reasons:?}");
+        assert!(reasons.contains(&"spf=fail".to_string()));
+        assert!(reasons.contains(&"dkim=fail".to_string()));
+
+        let ordinary = payload(
+            "alice@example.com",
+            &[
+                ("Received-SPF", "Pass"),
+                ("Authentication-Results", "mx.test; spf=pass; dkim=pass"),
+            ],
+            "Notes from today",
+            "Can you summarize the meeting and book a demo slot for next week?",
+        );
+        assert_eq!(spam_score(&ordinary).0, 1);
+
+        let reply = payload(
+            "alice@example.com",
+            &[("In-Reply-To", "<abc@dowhiz.com>")],
+            "Re: offer",
+            "> limited time offer, act now, gift card, wire transfer",
+        );
+        assert_eq!(spam_score(&reply).0, 0);
+    }
+}
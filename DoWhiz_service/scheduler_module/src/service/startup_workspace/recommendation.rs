@@ -608,6 +608,10 @@ mod tests {
             execution_status: execution_status.map(|value| value.to_string()),
             error_message: None,
             execution_started_at: Some(Utc::now().to_rfc3339()),
+            approval_status: None,
+            description: None,
+            ends_at: None,
+            remaining_runs: None,
         }
     }
 
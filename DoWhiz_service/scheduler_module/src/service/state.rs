@@ -1,5 +1,4 @@
 use std::collections::HashMap;
-use std::sync::Mutex;
 
 use chrono::{DateTime, Utc};
 
@@ -22,6 +21,8 @@ pub(super) struct TaskClaim {
     pub(super) started_at: DateTime<Utc>,
     pub(super) thread_id: Option<String>,
     pub(super) retry_count: u32,
+    /// Unique per execution; the run_task process registry key.
+    pub(super) run_id: String,
 }
 
 #[derive(Default)]
@@ -42,6 +43,7 @@ impl SchedulerClaims {
         task_ref: &TaskRef,
         user_limit: usize,
         retry_count: u32,
+        run_id: &str,
     ) -> ClaimResult {
         let active = self
             .running_users
@@ -62,12 +64,22 @@ impl SchedulerClaims {
             started_at: Utc::now(),
             thread_id: None, // TaskRef doesn't have thread_id; it's tracked in the task itself
             retry_count,
+            run_id: run_id.to_string(),
         };
         self.running_tasks.insert(task_ref.task_id.clone(), claim);
         ClaimResult::Claimed
     }
 
-    pub(super) fn release(&mut self, task_ref: &TaskRef) {
+    /// Release the claim of execution `run_id`. A claim the watchdog already
+    /// released, or that now belongs to a newer execution, is left alone.
+    pub(super) fn release(&mut self, task_ref: &TaskRef, run_id: &str) {
+        if self
+            .running_tasks
+            .get(&task_ref.task_id)
+            .is_none_or(|claim| claim.run_id != run_id)
+        {
+            return;
+        }
         if let Some(active) = self.running_users.get_mut(&task_ref.user_id) {
             if *active <= 1 {
                 self.running_users.remove(&task_ref.user_id);
@@ -106,42 +118,6 @@ impl SchedulerClaims {
     }
 }
 
-pub(super) struct ConcurrencyLimiter {
-    max: usize,
-    in_flight: Mutex<usize>,
-}
-
-impl ConcurrencyLimiter {
-    pub(super) fn new(max: usize) -> Self {
-        Self {
-            max,
-            in_flight: Mutex::new(0),
-        }
-    }
-
-    pub(super) fn try_acquire(&self) -> bool {
-        let mut in_flight = self
-            .in_flight
-            .lock()
-            .expect("concurrency limiter lock poisoned");
-        if *in_flight >= self.max {
-            return false;
-        }
-        *in_flight += 1;
-        true
-    }
-
-    pub(super) fn release(&self) {
-        let mut in_flight = self
-            .in_flight
-            .lock()
-            .expect("concurrency limiter lock poisoned");
-        if *in_flight > 0 {
-            *in_flight -= 1;
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,7 +135,7 @@ mod tests {
                 task_id: Uuid::new_v4().to_string(),
                 user_id: user_id.clone(),
             };
-            let result = claims.try_claim(&task_ref, 200, 0);
+            let result = claims.try_claim(&task_ref, 200, 0, &task_ref.task_id);
             assert!(matches!(result, ClaimResult::Claimed));
             claimed.push(task_ref);
         }
@@ -168,13 +144,40 @@ mod tests {
             task_id: Uuid::new_v4().to_string(),
             user_id: user_id.clone(),
         };
-        let overflow_result = claims.try_claim(&overflow, 200, 0);
+        let overflow_result = claims.try_claim(&overflow, 200, 0, &overflow.task_id);
         assert!(matches!(overflow_result, ClaimResult::UserBusy));
 
         for task_ref in claimed {
-            claims.release(&task_ref);
+            claims.release(&task_ref, &task_ref.task_id);
         }
         assert!(claims.running_users.is_empty());
         assert!(claims.running_tasks.is_empty());
     }
+
+    #[test]
+    fn release_leaves_a_newer_claim_of_the_same_task() {
+        let mut claims = SchedulerClaims::default();
+        let task_ref = TaskRef {
+            task_id: Uuid::new_v4().to_string(),
+            user_id: "user-1".to_string(),
+        };
+        assert!(matches!(
+            claims.try_claim(&task_ref, 1, 0, "run-1"),
+            ClaimResult::Claimed
+        ));
+        // The watchdog gives up on run-1 and the task is claimed again.
+        claims.force_release(&task_ref.task_id);
+        assert!(matches!(
+            claims.try_claim(&task_ref, 1, 1, "run-2"),
+            ClaimResult::Claimed
+        ));
+
+        claims.release(&task_ref, "run-1");
+        assert_eq!(claims.running_users.get("user-1"), Some(&1));
+        assert_eq!(claims.running_tasks[&task_ref.task_id].run_id, "run-2");
+
+        claims.release(&task_ref, "run-2");
+        assert!(claims.running_users.is_empty());
+        assert!(claims.running_tasks.is_empty());
+    }
 }
@@ -0,0 +1,208 @@
+//! Per-thread FIFO of run_tasks, so the tasks of one workspace run one at a
+//! time in arrival order.
+//!
+//! A run_task whose workspace is busy joins that workspace's queue instead of
+//! being deferred. It stays due in the index; the poller leaves it unclaimed
+//! until it is at the front and the workspace is free, then dispatches it as
+//! usual. Queues are per process; the task lease covers other workers.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+#[derive(Default)]
+pub(super) struct ThreadQueues {
+    inner: Mutex<Lanes>,
+}
+
+#[derive(Default)]
+struct Lanes {
+    /// Keyed by workspace dir.
+    by_thread: HashMap<String, Lane>,
+    /// Waiting task key -> workspace it waits on.
+    waiting_on: HashMap<String, String>,
+}
+
+#[derive(Default)]
+struct Lane {
+    running: bool,
+    waiting: VecDeque<String>,
+}
+
+pub(super) enum Admission {
+    /// The task holds the workspace until the turn is dropped.
+    Run(ThreadTurn),
+    /// The task waits; `position` is 1 for the next to run.
+    Queued { position: usize, newly: bool },
+}
+
+/// The running slot of one workspace; frees it when dropped.
+pub(super) struct ThreadTurn {
+    queues: Arc<ThreadQueues>,
+    thread: String,
+}
+
+impl Drop for ThreadTurn {
+    fn drop(&mut self) {
+        let mut lanes = self.queues.lanes();
+        if let Some(lane) = lanes.by_thread.get_mut(&self.thread) {
+            lane.running = false;
+            if lane.waiting.is_empty() {
+                lanes.by_thread.remove(&self.thread);
+            }
+        }
+    }
+}
+
+impl ThreadQueues {
+    fn lanes(&self) -> MutexGuard<'_, Lanes> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner())
+    }
+
+    /// Start `task` on `thread` if the workspace is free and nobody queued
+    /// earlier; otherwise queue it (once) behind the others.
+    pub(super) fn enter(self: &Arc<Self>, thread: &str, task: &str) -> Admission {
+        let mut lanes = self.lanes();
+        let Lanes {
+            by_thread,
+            waiting_on,
+        } = &mut *lanes;
+        let lane = by_thread.entry(thread.to_string()).or_default();
+        if !lane.running
+            && lane
+                .waiting
+                .front()
+                .is_none_or(|front| front.as_str() == task)
+        {
+            if lane.waiting.front().is_some() {
+                lane.waiting.pop_front();
+                waiting_on.remove(task);
+            }
+            lane.running = true;
+            return Admission::Run(ThreadTurn {
+                queues: self.clone(),
+                thread: thread.to_string(),
+            });
+        }
+        if let Some(index) = lane.waiting.iter().position(|waiting| waiting == task) {
+            return Admission::Queued {
+                position: index + 1,
+                newly: false,
+            };
+        }
+        lane.waiting.push_back(task.to_string());
+        waiting_on.insert(task.to_string(), thread.to_string());
+        Admission::Queued {
+            position: lane.waiting.len(),
+            newly: true,
+        }
+    }
+
+    /// False while `task` waits behind a running or earlier task.
+    pub(super) fn may_start(&self, task: &str) -> bool {
+        let lanes = self.lanes();
+        let Some(thread) = lanes.waiting_on.get(task) else {
+            return true;
+        };
+        lanes.by_thread.get(thread).is_none_or(|lane| {
+            !lane.running && lane.waiting.front().is_some_and(|front| front == task)
+        })
+    }
+
+    /// Drop waiting tasks that are no longer due (deleted, disabled or run
+    /// by another worker), so they cannot hold up their queue.
+    pub(super) fn retain_due(&self, due: &HashSet<String>) {
+        let mut lanes = self.lanes();
+        let gone: Vec<(String, String)> = lanes
+            .waiting_on
+            .iter()
+            .filter(|(task, _)| !due.contains(*task))
+            .map(|(task, thread)| (task.clone(), thread.clone()))
+            .collect();
+        for (task, thread) in gone {
+            lanes.waiting_on.remove(&task);
+            if let Some(lane) = lanes.by_thread.get_mut(&thread) {
+                lane.waiting.retain(|waiting| *waiting != task);
+                if !lane.running && lane.waiting.is_empty() {
+                    lanes.by_thread.remove(&thread);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(admission: Admission) -> ThreadTurn {
+        match admission {
+            Admission::Run(turn) => turn,
+            Admission::Queued { .. } => panic!("expected to run"),
+        }
+    }
+
+    #[test]
+    fn tasks_of_a_busy_thread_run_in_arrival_order() {
+        let queues = Arc::new(ThreadQueues::default());
+        let first = run(queues.enter("ws", "a"));
+        assert!(matches!(
+            queues.enter("ws", "b"),
+            Admission::Queued {
+                position: 1,
+                newly: true
+            }
+        ));
+        assert!(matches!(
+            queues.enter("ws", "c"),
+            Admission::Queued {
+                position: 2,
+                newly: true
+            }
+        ));
+        assert!(matches!(
+            queues.enter("ws", "b"),
+            Admission::Queued {
+                position: 1,
+                newly: false
+            }
+        ));
+        // Other workspaces are unaffected.
+        drop(run(queues.enter("other", "x")));
+        assert!(!queues.may_start("b"));
+
+        drop(first);
+        assert!(queues.may_start("b"));
+        assert!(!queues.may_start("c"));
+        assert!(matches!(
+            queues.enter("ws", "c"),
+            Admission::Queued { position: 2, .. }
+        ));
+        let second = run(queues.enter("ws", "b"));
+        assert!(queues.may_start("d"));
+        assert!(matches!(
+            queues.enter("ws", "d"),
+            Admission::Queued { position: 2, .. }
+        ));
+        drop(second);
+        drop(run(queues.enter("ws", "c")));
+        drop(run(queues.enter("ws", "d")));
+        assert!(queues.lanes().by_thread.is_empty());
+    }
+
+    #[test]
+    fn tasks_no_longer_due_leave_the_queue() {
+        let queues = Arc::new(ThreadQueues::default());
+        let first = run(queues.enter("ws", "a"));
+        queues.enter("ws", "b");
+        queues.enter("ws", "c");
+        drop(first);
+
+        queues.retain_due(&HashSet::from(["c".to_string()]));
+        assert!(queues.may_start("c"));
+        drop(run(queues.enter("ws", "c")));
+        assert!(queues.lanes().waiting_on.is_empty());
+        assert!(queues.lanes().by_thread.is_empty());
+    }
+}
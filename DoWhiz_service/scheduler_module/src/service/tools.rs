@@ -0,0 +1,100 @@
+//! Tool endpoints for runners.
+//!
+//! `POST /tools/search` runs a web search with the service's provider keys
+//! so runners without network access can still search. The route is only
+//! mounted when `TOOLS_API_TOKEN` is set, and callers must send it as a
+//! bearer token.
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::task;
+use tracing::{error, info, warn};
+
+use crate::tools::search::{self, SearchError, SearchRequest};
+
+#[derive(Clone)]
+pub struct ToolsState {
+    token: Arc<str>,
+}
+
+impl ToolsState {
+    /// `None` unless `TOOLS_API_TOKEN` is set.
+    pub fn from_env() -> Option<Self> {
+        let token = std::env::var("TOOLS_API_TOKEN")
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())?;
+        Some(Self {
+            token: Arc::from(token),
+        })
+    }
+
+    fn authorized(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| {
+                let (provided, expected) = (token.trim().as_bytes(), self.token.as_bytes());
+                provided.len() == expected.len()
+                    && provided
+                        .iter()
+                        .zip(expected)
+                        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                        == 0
+            })
+    }
+}
+
+/// POST /tools/search - Search the web with the configured provider.
+async fn web_search(
+    State(state): State<ToolsState>,
+    headers: HeaderMap,
+    Json(request): Json<SearchRequest>,
+) -> impl IntoResponse {
+    if !state.authorized(&headers) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "Unauthorized" })),
+        )
+            .into_response();
+    }
+    match task::spawn_blocking(move || search::search(&request)).await {
+        Ok(Ok(response)) => {
+            info!(
+                "tools.search provider={} results={}",
+                response.provider,
+                response.results.len()
+            );
+            Json(response).into_response()
+        }
+        Ok(Err(err)) => {
+            let status = match err {
+                SearchError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+                SearchError::NotConfigured => StatusCode::SERVICE_UNAVAILABLE,
+                SearchError::Provider { .. } => StatusCode::BAD_GATEWAY,
+            };
+            warn!("tools.search failed: {}", err);
+            (status, Json(json!({ "error": err.to_string() }))).into_response()
+        }
+        Err(err) => {
+            error!("tools.search join error: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Search failed" })),
+            )
+                .into_response()
+        }
+    }
+}
+
+pub fn tools_router(state: ToolsState) -> Router {
+    Router::new()
+        .route("/tools/search", post(web_search))
+        .with_state(state)
+}
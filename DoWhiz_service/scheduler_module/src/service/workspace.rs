@@ -1,12 +1,14 @@
+use std::collections::BTreeSet;
 use std::io;
 use std::path::{Path, PathBuf};
 
 use chrono::Utc;
 use serde::Serialize;
-use tracing::error;
+use tracing::{error, warn};
 
 use crate::domain::workspace_blueprint::StartupWorkspaceBlueprint;
 use crate::employee_config::EmployeeProfile;
+use crate::workspace_recovery::{detect_workspace_corruption, recover_corrupt_workspace};
 
 use super::html::{strip_html_tags, truncate_preview};
 use super::startup_workspace::{
@@ -14,12 +16,24 @@ use super::startup_workspace::{
 };
 use super::BoxError;
 
-fn thread_workspace_name(thread_key: &str) -> String {
+pub(super) fn thread_workspace_name(thread_key: &str) -> String {
     let hash = format!("{:x}", md5::compute(thread_key.as_bytes()));
     format!("thread_{}", hash)
 }
 
-pub(super) fn copy_skills_directory(src: &Path, dest: &Path) -> std::io::Result<()> {
+/// Workspace file naming the skills last synced from the user's own skills
+/// dir.
+const USER_SKILLS_MANIFEST: &str = ".user_skills";
+
+/// Syncs each skill directory of `src` into `dest`, except those named in
+/// `skip`. Files already copied and unchanged since are left alone, so an
+/// existing workspace does not copy the whole skills tree again for every
+/// message.
+pub(super) fn copy_skills_directory(
+    src: &Path,
+    dest: &Path,
+    skip: &BTreeSet<String>,
+) -> std::io::Result<()> {
     if !src.exists() {
         return Ok(());
     }
@@ -28,14 +42,109 @@ pub(super) fn copy_skills_directory(src: &Path, dest: &Path) -> std::io::Result<
         let entry = entry?;
         let skill_src = entry.path();
         let skill_dest = dest.join(entry.file_name());
+        // Dot directories are skill installs still being staged.
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') || skip.contains(&name) {
+            continue;
+        }
 
         if skill_src.is_dir() {
-            copy_dir_recursive(&skill_src, &skill_dest)?;
+            sync_dir_recursive(&skill_src, &skill_dest)?;
+        }
+    }
+    Ok(())
+}
+
+fn sync_dir_recursive(src: &Path, dest: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dest)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dest_path = dest.join(entry.file_name());
+
+        if src_path.is_dir() {
+            sync_dir_recursive(&src_path, &dest_path)?;
+        } else if !is_up_to_date_copy(&src_path, &dest_path) {
+            copy_file_with_fallback(&src_path, &dest_path)?;
         }
     }
     Ok(())
 }
 
+/// Names of the skill directories in `dir`.
+fn skill_names(dir: &Path) -> BTreeSet<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return BTreeSet::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| !name.starts_with('.'))
+        .collect()
+}
+
+/// Removes the workspace copies of user skills that were deleted, and of
+/// skills the user now provides in place of a shared one (so no file of the
+/// shared version is left), then records `user_skills` as synced.
+fn reset_user_skills(dest: &Path, user_skills: &BTreeSet<String>) -> std::io::Result<()> {
+    let manifest = dest.join(USER_SKILLS_MANIFEST);
+    let previous: BTreeSet<String> = std::fs::read_to_string(&manifest)
+        .map(|text| text.lines().map(str::to_string).collect())
+        .unwrap_or_default();
+    for name in previous.symmetric_difference(user_skills) {
+        match std::fs::remove_dir_all(dest.join(name)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+    }
+    if user_skills.is_empty() {
+        if manifest.exists() {
+            std::fs::remove_file(manifest)?;
+        }
+        return Ok(());
+    }
+    std::fs::create_dir_all(dest)?;
+    let names: Vec<&str> = user_skills.iter().map(String::as_str).collect();
+    std::fs::write(manifest, names.join("\n"))
+}
+
+/// Syncs the shared skill dirs (base, then employee) and then the user's own
+/// skills into `dest`. A user skill wins over a shared one of the same name.
+fn sync_workspace_skills(dest: &Path, shared_skills: &[&Path], user_skills_dir: &Path) {
+    let user_skills = skill_names(user_skills_dir);
+    if let Err(err) = reset_user_skills(dest, &user_skills) {
+        error!("failed to reset user skills in workspace: {}", err);
+    }
+    for src in shared_skills {
+        if let Err(err) = copy_skills_directory(src, dest, &user_skills) {
+            error!(
+                "failed to copy skills from {} to workspace: {}",
+                src.display(),
+                err
+            );
+        }
+    }
+    if !user_skills.is_empty() {
+        if let Err(err) = copy_skills_directory(user_skills_dir, dest, &BTreeSet::new()) {
+            error!("failed to copy user skills to workspace: {}", err);
+        }
+    }
+}
+
+/// Same size and modified no earlier than the source.
+fn is_up_to_date_copy(src: &Path, dest: &Path) -> bool {
+    let (Ok(src_meta), Ok(dest_meta)) = (std::fs::metadata(src), std::fs::metadata(dest)) else {
+        return false;
+    };
+    match (src_meta.modified(), dest_meta.modified()) {
+        (Ok(src_modified), Ok(dest_modified)) => {
+            src_meta.len() == dest_meta.len() && dest_modified >= src_modified
+        }
+        _ => false,
+    }
+}
+
 pub fn copy_dir_recursive(src: &Path, dest: &Path) -> std::io::Result<()> {
     std::fs::create_dir_all(dest)?;
     for entry in std::fs::read_dir(src)? {
@@ -66,6 +175,11 @@ pub(super) fn ensure_workspace_employee_files(
             copy_file_with_fallback(path, &workspace.join("CLAUDE.md"))?;
         }
     }
+    if let Some(path) = employee.gemini_path.as_ref() {
+        if path.exists() {
+            copy_file_with_fallback(path, &workspace.join("GEMINI.md"))?;
+        }
+    }
     if let Some(path) = employee.soul_path.as_ref() {
         if path.exists() {
             copy_file_with_fallback(path, &workspace.join("SOUL.md"))?;
@@ -110,6 +224,35 @@ pub(crate) fn ensure_thread_workspace(
     let workspace_name = thread_workspace_name(thread_key);
     let workspace = user_paths.workspaces_root.join(workspace_name);
     let is_new = !workspace.exists();
+    if !is_new {
+        if let Some(reason) = detect_workspace_corruption(&workspace) {
+            warn!(
+                "workspace {} is corrupt ({}); quarantining and rebuilding",
+                workspace.display(),
+                reason
+            );
+            let recovery = recover_corrupt_workspace(
+                &workspace,
+                &reason,
+                Some((&user_paths.mail_root, user_id)),
+                None,
+            )
+            .map_err(|err| {
+                io::Error::other(format!(
+                    "recover_corrupt_workspace failed path={} error={}",
+                    workspace.display(),
+                    err
+                ))
+            })?;
+            warn!(
+                "rebuilt workspace {} (restored {} entries, dropped {}); damaged copy at {}",
+                workspace.display(),
+                recovery.entries_restored,
+                recovery.entries_dropped,
+                recovery.quarantine_path.display()
+            );
+        }
+    }
     if is_new {
         std::fs::create_dir_all(&workspace).map_err(|err| {
             io::Error::other(format!(
@@ -154,14 +297,14 @@ pub(crate) fn ensure_thread_workspace(
         ))
     })?;
 
-    if is_new || !references.join("past_emails").exists() {
-        if let Err(err) = crate::past_emails::hydrate_past_emails(
-            &user_paths.mail_root,
-            &references,
-            user_id,
-            None,
-        ) {
-            error!("failed to hydrate past_emails: {}", err);
+    let search_index = references
+        .join("past_emails")
+        .join(crate::past_email_index::SEARCH_INDEX_FILE);
+    if is_new || !search_index.exists() {
+        if let Err(err) =
+            crate::past_emails::index_past_emails(&user_paths.mail_root, &references, user_id)
+        {
+            error!("failed to index past_emails: {}", err);
         }
     }
 
@@ -173,23 +316,22 @@ pub(crate) fn ensure_thread_workspace(
         ))
     })?;
 
-    // Copy skills to workspace for Codex/Claude runners.
-    let agents_skills_dir = workspace.join(".agents").join("skills");
-    if let Some(skills_src) = skills_source_dir {
-        if let Err(err) = copy_skills_directory(skills_src, &agents_skills_dir) {
-            error!("failed to copy base skills to workspace: {}", err);
-        }
-    }
+    // Sync skills into the workspace for Codex/Claude runners; the prompt
+    // points at skills_index.md so runners open only the skills they need.
+    let mut shared_skills: Vec<&Path> = skills_source_dir.into_iter().collect();
     if let Some(employee_skills) = employee.skills_dir.as_deref() {
-        let should_copy = skills_source_dir
-            .map(|base| base != employee_skills)
-            .unwrap_or(true);
-        if should_copy {
-            if let Err(err) = copy_skills_directory(employee_skills, &agents_skills_dir) {
-                error!("failed to copy employee skills to workspace: {}", err);
-            }
+        if skills_source_dir != Some(employee_skills) {
+            shared_skills.push(employee_skills);
         }
     }
+    sync_workspace_skills(
+        &workspace.join(".agents").join("skills"),
+        &shared_skills,
+        &user_paths.skills_dir,
+    );
+    if let Err(err) = run_task_module::write_skills_index(&workspace) {
+        error!("failed to write skills index: {}", err);
+    }
 
     Ok(workspace)
 }
@@ -577,4 +719,58 @@ mod tests {
             .expect("resources.json should exist");
         assert!(resources_json.contains("manual_next_step"));
     }
+
+    #[test]
+    fn user_skills_replace_shared_skills_until_deleted() {
+        let root = tempdir().expect("tempdir should be created");
+        let shared = root.path().join("employee_skills");
+        let user = root.path().join("user").join("skills");
+        let dest = root.path().join("workspace").join(".agents").join("skills");
+        std::fs::create_dir_all(shared.join("pdf").join("scripts")).expect("shared skill");
+        std::fs::write(shared.join("pdf").join("SKILL.md"), "shared").expect("SKILL.md");
+        std::fs::write(shared.join("pdf").join("scripts").join("run.sh"), "echo").expect("script");
+        sync_workspace_skills(&dest, &[&shared], &user);
+
+        std::fs::create_dir_all(user.join("pdf")).expect("user skill");
+        std::fs::write(user.join("pdf").join("SKILL.md"), "mine").expect("SKILL.md");
+        sync_workspace_skills(&dest, &[&shared], &user);
+        assert_eq!(
+            std::fs::read_to_string(dest.join("pdf").join("SKILL.md")).unwrap(),
+            "mine"
+        );
+        assert!(!dest.join("pdf").join("scripts").exists());
+
+        std::fs::remove_dir_all(user.join("pdf")).expect("delete user skill");
+        sync_workspace_skills(&dest, &[&shared], &user);
+        assert_eq!(
+            std::fs::read_to_string(dest.join("pdf").join("SKILL.md")).unwrap(),
+            "shared"
+        );
+        assert!(!dest.join(USER_SKILLS_MANIFEST).exists());
+    }
+
+    #[test]
+    fn copy_skills_directory_copies_only_changed_files() {
+        let root = tempdir().expect("tempdir should be created");
+        let src = root.path().join("skills");
+        let dest = root.path().join("workspace").join(".agents").join("skills");
+        std::fs::create_dir_all(src.join("pdf").join("scripts")).expect("skill dir");
+        std::fs::write(src.join("pdf").join("SKILL.md"), "v1").expect("SKILL.md");
+        std::fs::write(src.join("pdf").join("scripts").join("run.sh"), "echo").expect("script");
+
+        copy_skills_directory(&src, &dest, &BTreeSet::new()).expect("first sync");
+        // Same size and newer than the source: a recopy would overwrite it.
+        std::fs::write(dest.join("pdf").join("scripts").join("run.sh"), "ECHO").expect("mark");
+        std::fs::write(src.join("pdf").join("SKILL.md"), "v2 changed").expect("update");
+        copy_skills_directory(&src, &dest, &BTreeSet::new()).expect("second sync");
+
+        assert_eq!(
+            std::fs::read_to_string(dest.join("pdf").join("scripts").join("run.sh")).unwrap(),
+            "ECHO"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dest.join("pdf").join("SKILL.md")).unwrap(),
+            "v2 changed"
+        );
+    }
 }
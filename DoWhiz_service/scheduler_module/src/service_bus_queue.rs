@@ -40,27 +40,27 @@ pub struct ServiceBusIngestionQueue {
     pending: Mutex<HashMap<Uuid, PendingLock>>,
 }
 
-struct PendingLock {
-    response: Arc<PeekLockResponse>,
+pub(crate) struct PendingLock {
+    pub(crate) response: Arc<PeekLockResponse>,
     renewer: Option<LockRenewer>,
 }
 
 impl PendingLock {
-    fn new(response: Arc<PeekLockResponse>, renewer: LockRenewer) -> Self {
+    pub(crate) fn new(response: Arc<PeekLockResponse>, renewer: LockRenewer) -> Self {
         Self {
             response,
             renewer: Some(renewer),
         }
     }
 
-    fn stop_renewer(&mut self) {
+    pub(crate) fn stop_renewer(&mut self) {
         if let Some(renewer) = self.renewer.take() {
             renewer.stop();
         }
     }
 }
 
-struct LockRenewer {
+pub(crate) struct LockRenewer {
     stop_tx: mpsc::Sender<()>,
     handle: thread::JoinHandle<()>,
 }
@@ -224,59 +224,13 @@ impl ServiceBusIngestionQueue {
         handle_id: Uuid,
         response: Arc<PeekLockResponse>,
     ) -> Result<LockRenewer, IngestionQueueError> {
-        let runtime_handle = self.runtime()?.handle().clone();
-        let renew_interval = self.lock_renew_interval;
-        let queue_name = self.queue_name.clone();
-        let message_id = response
-            .broker_properties()
-            .map(|properties| properties.message_id);
-        let (stop_tx, stop_rx) = mpsc::channel::<()>();
-        let thread_name = format!("sb-lock-renew-{}", &handle_id.to_string()[..8]);
-
-        let handle = thread::Builder::new()
-            .name(thread_name)
-            .spawn(move || {
-                debug!(
-                    "service bus lock renewer started queue={} handle_id={} message_id={:?} interval_secs={}",
-                    queue_name,
-                    handle_id,
-                    message_id,
-                    renew_interval.as_secs()
-                );
-                loop {
-                    match stop_rx.recv_timeout(renew_interval) {
-                        Ok(()) | Err(mpsc::RecvTimeoutError::Disconnected) => break,
-                        Err(mpsc::RecvTimeoutError::Timeout) => {
-                            match runtime_handle.block_on(response.renew_message_lock()) {
-                                Ok(()) => {
-                                    debug!(
-                                        "service bus lock renewed queue={} handle_id={} message_id={:?}",
-                                        queue_name, handle_id, message_id
-                                    );
-                                }
-                                Err(err) => {
-                                    warn!(
-                                        "service bus lock renew failed queue={} handle_id={} message_id={:?}: {}",
-                                        queue_name, handle_id, message_id, err
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
-                debug!(
-                    "service bus lock renewer stopped queue={} handle_id={} message_id={:?}",
-                    queue_name, handle_id, message_id
-                );
-            })
-            .map_err(|err| {
-                IngestionQueueError::ServiceBus(format!(
-                    "failed to spawn service bus lock renewer: {}",
-                    err
-                ))
-            })?;
-
-        Ok(LockRenewer { stop_tx, handle })
+        spawn_lock_renewer(
+            self.runtime()?.handle().clone(),
+            self.lock_renew_interval,
+            self.queue_name.clone(),
+            handle_id,
+            response,
+        )
     }
 
     fn stop_all_pending_renewers(&self) {
@@ -316,36 +270,111 @@ impl Drop for ServiceBusIngestionQueue {
     }
 }
 
-fn map_service_bus_error(err: AzureError) -> IngestionQueueError {
+/// Keep a peek-lock alive until the returned renewer is stopped. `entity_name`
+/// is only used in logs.
+pub(crate) fn spawn_lock_renewer(
+    runtime_handle: tokio::runtime::Handle,
+    renew_interval: Duration,
+    entity_name: String,
+    handle_id: Uuid,
+    response: Arc<PeekLockResponse>,
+) -> Result<LockRenewer, IngestionQueueError> {
+    let message_id = response
+        .broker_properties()
+        .map(|properties| properties.message_id);
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+    let thread_name = format!("sb-lock-renew-{}", &handle_id.to_string()[..8]);
+
+    let handle = thread::Builder::new()
+        .name(thread_name)
+        .spawn(move || {
+            debug!(
+                "service bus lock renewer started entity={} handle_id={} message_id={:?} interval_secs={}",
+                entity_name,
+                handle_id,
+                message_id,
+                renew_interval.as_secs()
+            );
+            loop {
+                match stop_rx.recv_timeout(renew_interval) {
+                    Ok(()) | Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        match runtime_handle.block_on(response.renew_message_lock()) {
+                            Ok(()) => {
+                                debug!(
+                                    "service bus lock renewed entity={} handle_id={} message_id={:?}",
+                                    entity_name, handle_id, message_id
+                                );
+                            }
+                            Err(err) => {
+                                warn!(
+                                    "service bus lock renew failed entity={} handle_id={} message_id={:?}: {}",
+                                    entity_name, handle_id, message_id, err
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            debug!(
+                "service bus lock renewer stopped entity={} handle_id={} message_id={:?}",
+                entity_name, handle_id, message_id
+            );
+        })
+        .map_err(|err| {
+            IngestionQueueError::ServiceBus(format!(
+                "failed to spawn service bus lock renewer: {}",
+                err
+            ))
+        })?;
+
+    Ok(LockRenewer { stop_tx, handle })
+}
+
+pub(crate) fn map_service_bus_error(err: AzureError) -> IngestionQueueError {
     IngestionQueueError::ServiceBus(format!("{err:?}"))
 }
 
 pub fn resolve_service_bus_config_from_env() -> Result<ServiceBusConfig, IngestionQueueError> {
+    let (peek_lock_timeout, lock_renew_interval) = resolve_lock_timings_from_env();
+    let credentials = resolve_service_bus_credentials_from_env()?;
+    let queue_name = var_with_scale_oliver("SERVICE_BUS_QUEUE_NAME")
+        .or(credentials.entity_path)
+        .ok_or_else(|| {
+            IngestionQueueError::Config(
+                "missing SCALE_OLIVER_SERVICE_BUS_QUEUE_NAME/SERVICE_BUS_QUEUE_NAME".to_string(),
+            )
+        })?;
+    Ok(ServiceBusConfig {
+        namespace: credentials.namespace,
+        policy_name: credentials.policy_name,
+        policy_key: credentials.policy_key,
+        queue_name,
+        peek_lock_timeout,
+        lock_renew_interval,
+    })
+}
+
+/// Peek-lock timeout and lock renew interval shared by queue and topic consumers.
+pub(crate) fn resolve_lock_timings_from_env() -> (Duration, Duration) {
     let timeout_secs = resolve_i64_env("SERVICE_BUS_PEEK_LOCK_TIMEOUT_SECS", 30);
     let renew_secs = resolve_i64_env(
         "SERVICE_BUS_LOCK_RENEW_INTERVAL_SECS",
         default_lock_renew_interval_secs(timeout_secs),
     );
     let lock_renew_interval_secs = clamp_lock_renew_interval_secs(renew_secs);
+    (
+        Duration::from_secs(timeout_secs as u64),
+        Duration::from_secs(lock_renew_interval_secs as u64),
+    )
+}
 
+/// Namespace and SAS policy from `SERVICE_BUS_CONNECTION_STRING`, or from the
+/// individual `SERVICE_BUS_NAMESPACE`/`_POLICY_NAME`/`_POLICY_KEY` vars.
+pub(crate) fn resolve_service_bus_credentials_from_env(
+) -> Result<ParsedConnectionString, IngestionQueueError> {
     if let Some(conn_str) = var_with_scale_oliver("SERVICE_BUS_CONNECTION_STRING") {
-        let parts = parse_service_bus_connection_string(&conn_str)?;
-        let queue_name = var_with_scale_oliver("SERVICE_BUS_QUEUE_NAME")
-            .or(parts.entity_path)
-            .ok_or_else(|| {
-                IngestionQueueError::Config(
-                    "missing SCALE_OLIVER_SERVICE_BUS_QUEUE_NAME/SERVICE_BUS_QUEUE_NAME"
-                        .to_string(),
-                )
-            })?;
-        return Ok(ServiceBusConfig {
-            namespace: parts.namespace,
-            policy_name: parts.policy_name,
-            policy_key: parts.policy_key,
-            queue_name,
-            peek_lock_timeout: Duration::from_secs(timeout_secs as u64),
-            lock_renew_interval: Duration::from_secs(lock_renew_interval_secs as u64),
-        });
+        return parse_service_bus_connection_string(&conn_str);
     }
 
     let namespace = var_with_scale_oliver("SERVICE_BUS_NAMESPACE").ok_or_else(|| {
@@ -363,26 +392,19 @@ pub fn resolve_service_bus_config_from_env() -> Result<ServiceBusConfig, Ingesti
             "missing SCALE_OLIVER_SERVICE_BUS_POLICY_KEY/SERVICE_BUS_POLICY_KEY".to_string(),
         )
     })?;
-    let queue_name = var_with_scale_oliver("SERVICE_BUS_QUEUE_NAME").ok_or_else(|| {
-        IngestionQueueError::Config(
-            "missing SCALE_OLIVER_SERVICE_BUS_QUEUE_NAME/SERVICE_BUS_QUEUE_NAME".to_string(),
-        )
-    })?;
-    Ok(ServiceBusConfig {
+    Ok(ParsedConnectionString {
         namespace,
         policy_name,
         policy_key,
-        queue_name,
-        peek_lock_timeout: Duration::from_secs(timeout_secs as u64),
-        lock_renew_interval: Duration::from_secs(lock_renew_interval_secs as u64),
+        entity_path: None,
     })
 }
 
-struct ParsedConnectionString {
-    namespace: String,
-    policy_name: String,
-    policy_key: String,
-    entity_path: Option<String>,
+pub(crate) struct ParsedConnectionString {
+    pub(crate) namespace: String,
+    pub(crate) policy_name: String,
+    pub(crate) policy_key: String,
+    pub(crate) entity_path: Option<String>,
 }
 
 fn parse_service_bus_connection_string(
@@ -443,7 +465,7 @@ fn parse_service_bus_connection_string(
     })
 }
 
-fn resolve_i64_env(key: &str, default_value: i64) -> i64 {
+pub(crate) fn resolve_i64_env(key: &str, default_value: i64) -> i64 {
     env::var(key)
         .ok()
         .and_then(|value| value.parse::<i64>().ok())
@@ -0,0 +1,596 @@
+//! Skill marketplace: installs skills into an employee's skills directory
+//! from a git repository or a `.tar.gz` archive.
+//!
+//! Every install is pinned. A git source checks out the ref given as the
+//! version (a tag, branch or commit; a full commit hash must match exactly),
+//! and an archive source must match its expected SHA-256. The skill is
+//! unpacked and validated in a staging directory (a `SKILL.md` at its root,
+//! only regular files and directories) and then swapped in, so a failed
+//! install leaves the previous version in place. Installed skills are
+//! recorded in `.skills_lock.json` in the skills directory with their
+//! source, version and content digest.
+//!
+//! Workspaces sync the employee's skills directory each time they are
+//! prepared, so an install reaches the next run of every thread without a
+//! restart.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// File in the skills directory that records installed skills.
+pub const SKILLS_LOCK_FILE: &str = ".skills_lock.json";
+
+const STAGING_PREFIX: &str = ".staging-";
+const DEFAULT_MAX_ARCHIVE_BYTES: u64 = 50 * 1024 * 1024;
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// One install at a time per process, so two installs never race on the
+/// lock file or the same skill directory.
+static INSTALL_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, thiserror::Error)]
+pub enum SkillMarketError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The request itself is unusable (bad name, URL or layout).
+    #[error("invalid skill install: {0}")]
+    Invalid(String),
+    #[error("checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+    /// The source could not be fetched.
+    #[error("fetch failed: {0}")]
+    Fetch(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SkillSource {
+    Git { url: String },
+    Archive { url: String, sha256: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SkillInstallRequest {
+    /// Directory name of the skill under the skills directory.
+    pub name: String,
+    /// The git ref to check out, or the archive's version label.
+    pub version: String,
+    pub source: SkillSource,
+    /// Directory of the skill inside the repository or archive.
+    #[serde(default)]
+    pub subdir: Option<String>,
+    /// Expected [`content_digest`] of the installed files.
+    #[serde(default)]
+    pub content_sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstalledSkill {
+    pub name: String,
+    pub version: String,
+    pub source: SkillSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subdir: Option<String>,
+    /// Commit checked out for a git source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub content_sha256: String,
+    pub installed_at: DateTime<Utc>,
+    pub installed_by: String,
+}
+
+/// The installed skills recorded in `skills_dir`, by name.
+pub fn list_installed(skills_dir: &Path) -> Result<Vec<InstalledSkill>, SkillMarketError> {
+    Ok(load_lock(skills_dir)?.into_values().collect())
+}
+
+/// Installs or updates `request.name` in `skills_dir` and records it in the
+/// lock file. Reinstalling the recorded version and digest is a no-op.
+pub fn install_skill(
+    skills_dir: &Path,
+    request: &SkillInstallRequest,
+    installed_by: &str,
+) -> Result<InstalledSkill, SkillMarketError> {
+    validate_request(request)?;
+    let _guard = INSTALL_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    fs::create_dir_all(skills_dir)?;
+    let staging = StagingDir::create(skills_dir)?;
+
+    let fetched = staging.path.join("fetched");
+    let commit = match &request.source {
+        SkillSource::Git { url } => Some(fetch_git(url, &request.version, &fetched)?),
+        SkillSource::Archive { url, sha256 } => {
+            let bytes = download_archive(url)?;
+            verify_sha256(sha256, &hex::encode(Sha256::digest(&bytes)))?;
+            unpack_archive(&bytes, &fetched)?;
+            None
+        }
+    };
+    let root = skill_root(&fetched, request.subdir.as_deref())?;
+    validate_tree(&root)?;
+    let digest = content_digest(&root)?;
+    if let Some(expected) = request.content_sha256.as_deref() {
+        verify_sha256(expected, &digest)?;
+    }
+
+    let mut lock = load_lock(skills_dir)?;
+    let target = skills_dir.join(&request.name);
+    if let Some(existing) = lock.get(&request.name) {
+        if existing.version == request.version
+            && existing.content_sha256 == digest
+            && target.is_dir()
+        {
+            return Ok(existing.clone());
+        }
+    }
+
+    let previous = staging.path.join("previous");
+    if target.exists() {
+        fs::rename(&target, &previous)?;
+    }
+    if let Err(err) = fs::rename(&root, &target) {
+        if previous.exists() {
+            fs::rename(&previous, &target)?;
+        }
+        return Err(err.into());
+    }
+
+    let installed = InstalledSkill {
+        name: request.name.clone(),
+        version: request.version.clone(),
+        source: request.source.clone(),
+        subdir: request.subdir.clone(),
+        commit,
+        content_sha256: digest,
+        installed_at: Utc::now(),
+        installed_by: installed_by.to_string(),
+    };
+    lock.insert(installed.name.clone(), installed.clone());
+    save_lock(skills_dir, &lock)?;
+    Ok(installed)
+}
+
+/// SHA-256 over the files under `dir`: each file's relative path, a NUL,
+/// its length and its bytes, in path order. Independent of timestamps and
+/// of how the files were packaged.
+pub fn content_digest(dir: &Path) -> Result<String, SkillMarketError> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files)?;
+    files.sort();
+    let mut hasher = Sha256::new();
+    for relative in files {
+        let bytes = fs::read(dir.join(&relative))?;
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update((bytes.len() as u64).to_be_bytes());
+        hasher.update(&bytes);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            files.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
+fn validate_request(request: &SkillInstallRequest) -> Result<(), SkillMarketError> {
+    let invalid = |message: &str| Err(SkillMarketError::Invalid(message.to_string()));
+    if !is_valid_skill_name(&request.name) {
+        return invalid("name must be letters, digits, '-', '_' or '.'");
+    }
+    let version = request.version.trim();
+    if version.is_empty() || version.starts_with('-') || version.chars().any(char::is_whitespace) {
+        return invalid("version must be a non-empty ref or label");
+    }
+    if let Some(subdir) = request.subdir.as_deref() {
+        if !is_relative_subpath(subdir) {
+            return invalid("subdir must be a relative path inside the source");
+        }
+    }
+    match &request.source {
+        SkillSource::Git { url } => {
+            let allowed = url.starts_with("https://")
+                || url.starts_with("ssh://")
+                || (url.starts_with("git@") && url.contains(':'));
+            if !allowed {
+                return invalid("git url must use https, ssh or git@host:path");
+            }
+        }
+        SkillSource::Archive { url, sha256 } => {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return invalid("archive url must use http or https");
+            }
+            if sha256.len() != 64 || !sha256.chars().all(|ch| ch.is_ascii_hexdigit()) {
+                return invalid("archive sha256 must be 64 hex characters");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A skill name is also its directory name: letters, digits, `-`, `_` and
+/// `.`, not starting with `.` (staging) or `-`.
+pub(crate) fn is_valid_skill_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(['.', '-'])
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+}
+
+pub(crate) fn is_relative_subpath(path: &str) -> bool {
+    let path = Path::new(path);
+    path.components().count() > 0
+        && path
+            .components()
+            .all(|component| matches!(component, std::path::Component::Normal(_)))
+}
+
+fn verify_sha256(expected: &str, actual: &str) -> Result<(), SkillMarketError> {
+    if expected.eq_ignore_ascii_case(actual) {
+        Ok(())
+    } else {
+        Err(SkillMarketError::ChecksumMismatch {
+            expected: expected.to_ascii_lowercase(),
+            actual: actual.to_string(),
+        })
+    }
+}
+
+/// Checks out `rev` of `url` into `dest` and returns the commit. A full
+/// commit hash must resolve to itself, so a pin cannot drift.
+fn fetch_git(url: &str, rev: &str, dest: &Path) -> Result<String, SkillMarketError> {
+    fs::create_dir_all(dest)?;
+    run_git(dest, &["init", "--quiet"])?;
+    run_git(dest, &["fetch", "--quiet", "--depth", "1", "--", url, rev])?;
+    run_git(dest, &["checkout", "--quiet", "FETCH_HEAD"])?;
+    let commit = run_git(dest, &["rev-parse", "HEAD"])?;
+    let is_full_hash = rev.len() == 40 && rev.chars().all(|ch| ch.is_ascii_hexdigit());
+    if is_full_hash && !commit.eq_ignore_ascii_case(rev) {
+        return Err(SkillMarketError::Fetch(format!(
+            "{} resolved to {} instead of the pinned commit",
+            rev, commit
+        )));
+    }
+    fs::remove_dir_all(dest.join(".git"))?;
+    Ok(commit)
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<String, SkillMarketError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|err| SkillMarketError::Fetch(format!("git: {}", err)))?;
+    if !output.status.success() {
+        return Err(SkillMarketError::Fetch(format!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn download_archive(url: &str) -> Result<Vec<u8>, SkillMarketError> {
+    let max_bytes = std::env::var("SKILL_MARKET_MAX_ARCHIVE_BYTES")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_MAX_ARCHIVE_BYTES);
+    let fetch_err = |err: reqwest::Error| SkillMarketError::Fetch(err.to_string());
+    let response = reqwest::blocking::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()
+        .map_err(fetch_err)?
+        .get(url)
+        .send()
+        .map_err(fetch_err)?
+        .error_for_status()
+        .map_err(fetch_err)?;
+    let mut bytes = Vec::new();
+    response.take(max_bytes + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > max_bytes {
+        return Err(SkillMarketError::Fetch(format!(
+            "archive is larger than {} bytes",
+            max_bytes
+        )));
+    }
+    Ok(bytes)
+}
+
+/// Unpacks a `.tar.gz` into `dest`. Links and other special entries are
+/// rejected; the tar crate already refuses paths that leave `dest`.
+fn unpack_archive(bytes: &[u8], dest: &Path) -> Result<(), SkillMarketError> {
+    fs::create_dir_all(dest)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(bytes));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let kind = entry.header().entry_type();
+        if !(kind.is_file() || kind.is_dir()) {
+            return Err(SkillMarketError::Invalid(format!(
+                "archive entry {} is not a regular file or directory",
+                entry.path()?.display()
+            )));
+        }
+        entry.unpack_in(dest)?;
+    }
+    Ok(())
+}
+
+/// The directory holding `SKILL.md`: `subdir` when given, else the fetched
+/// root or its single top-level directory (how most archives are packed).
+fn skill_root(fetched: &Path, subdir: Option<&str>) -> Result<PathBuf, SkillMarketError> {
+    let root = match subdir {
+        Some(subdir) => fetched.join(subdir),
+        None if fetched.join("SKILL.md").is_file() => fetched.to_path_buf(),
+        None => {
+            let entries: Vec<_> = fs::read_dir(fetched)?.collect::<Result<_, _>>()?;
+            match entries.as_slice() {
+                [only] if only.file_type()?.is_dir() => only.path(),
+                _ => fetched.to_path_buf(),
+            }
+        }
+    };
+    if !root.join("SKILL.md").is_file() {
+        return Err(SkillMarketError::Invalid(
+            "no SKILL.md at the root of the skill".to_string(),
+        ));
+    }
+    Ok(root)
+}
+
+fn validate_tree(dir: &Path) -> Result<(), SkillMarketError> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            validate_tree(&entry.path())?;
+        } else if !file_type.is_file() {
+            return Err(SkillMarketError::Invalid(format!(
+                "{} is not a regular file or directory",
+                entry.path().display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn load_lock(skills_dir: &Path) -> Result<BTreeMap<String, InstalledSkill>, SkillMarketError> {
+    match fs::read(skills_dir.join(SKILLS_LOCK_FILE)) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn save_lock(
+    skills_dir: &Path,
+    lock: &BTreeMap<String, InstalledSkill>,
+) -> Result<(), SkillMarketError> {
+    let path = skills_dir.join(SKILLS_LOCK_FILE);
+    let tmp = skills_dir.join(format!("{}.tmp", SKILLS_LOCK_FILE));
+    fs::write(&tmp, serde_json::to_vec_pretty(lock)?)?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
+
+/// A dot-prefixed directory next to the skills, which workspace syncs skip,
+/// removed when dropped.
+pub(crate) struct StagingDir {
+    pub(crate) path: PathBuf,
+}
+
+impl StagingDir {
+    pub(crate) fn create(skills_dir: &Path) -> io::Result<Self> {
+        let path = skills_dir.join(format!("{}{}", STAGING_PREFIX, Uuid::new_v4()));
+        fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use tempfile::TempDir;
+
+    fn skill_archive(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, content.as_bytes())
+                .expect("append");
+        }
+        builder.into_inner().expect("tar").finish().expect("gzip")
+    }
+
+    #[test]
+    fn archive_install_is_pinned_by_checksum_and_replaces_the_previous_version() {
+        let temp = TempDir::new().expect("tempdir");
+        let skills_dir = temp.path().join("skills");
+        let v1 = skill_archive(&[("pdf/SKILL.md", "v1"), ("pdf/old.txt", "old")]);
+        let v2 = skill_archive(&[("pdf/SKILL.md", "v2")]);
+        let mut server = mockito::Server::new();
+        let _v1 = server.mock("GET", "/pdf-1.tar.gz").with_body(&v1).create();
+        let _v2 = server.mock("GET", "/pdf-2.tar.gz").with_body(&v2).create();
+        let request = |version: &str, bytes: &[u8]| SkillInstallRequest {
+            name: "pdf".to_string(),
+            version: version.to_string(),
+            source: SkillSource::Archive {
+                url: format!("{}/pdf-{}.tar.gz", server.url(), version),
+                sha256: hex::encode(Sha256::digest(bytes)),
+            },
+            subdir: None,
+            content_sha256: None,
+        };
+
+        let first = install_skill(&skills_dir, &request("1", &v1), "admin").expect("install");
+        assert_eq!(first.version, "1");
+        assert_eq!(
+            fs::read_to_string(skills_dir.join("pdf/SKILL.md")).unwrap(),
+            "v1"
+        );
+
+        // A checksum that does not match leaves the installed version alone.
+        let mut tampered = request("2", &v2);
+        tampered.source = SkillSource::Archive {
+            url: format!("{}/pdf-2.tar.gz", server.url()),
+            sha256: "0".repeat(64),
+        };
+        assert!(matches!(
+            install_skill(&skills_dir, &tampered, "admin"),
+            Err(SkillMarketError::ChecksumMismatch { .. })
+        ));
+        assert_eq!(
+            fs::read_to_string(skills_dir.join("pdf/SKILL.md")).unwrap(),
+            "v1"
+        );
+
+        install_skill(&skills_dir, &request("2", &v2), "admin").expect("update");
+        assert_eq!(
+            fs::read_to_string(skills_dir.join("pdf/SKILL.md")).unwrap(),
+            "v2"
+        );
+        assert!(!skills_dir.join("pdf/old.txt").exists());
+        let installed = list_installed(&skills_dir).expect("list");
+        assert_eq!(installed.len(), 1);
+        assert_eq!(installed[0].version, "2");
+        let leftovers: Vec<_> = fs::read_dir(&skills_dir)
+            .unwrap()
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with(STAGING_PREFIX))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    #[test]
+    fn unpacked_archive_is_found_under_its_top_level_directory() {
+        let temp = TempDir::new().expect("tempdir");
+        let bytes = skill_archive(&[
+            ("pdf-1.2.0/SKILL.md", "---\nname: pdf\n---\n"),
+            ("pdf-1.2.0/scripts/run.sh", "echo"),
+        ]);
+
+        unpack_archive(&bytes, temp.path()).expect("unpack");
+        let root = skill_root(temp.path(), None).expect("root");
+
+        assert_eq!(root, temp.path().join("pdf-1.2.0"));
+        let digest = content_digest(&root).expect("digest");
+        assert_eq!(digest.len(), 64);
+        fs::write(root.join("scripts/run.sh"), "rm").expect("tamper");
+        assert_ne!(content_digest(&root).expect("digest"), digest);
+    }
+
+    #[test]
+    fn archive_without_skill_md_or_with_links_is_rejected() {
+        let temp = TempDir::new().expect("tempdir");
+        unpack_archive(&skill_archive(&[("README.md", "hi")]), temp.path()).expect("unpack");
+        assert!(matches!(
+            skill_root(temp.path(), None),
+            Err(SkillMarketError::Invalid(_))
+        ));
+
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder
+            .append_link(&mut header, "SKILL.md", "/etc/passwd")
+            .expect("link");
+        let bytes = builder.into_inner().expect("tar").finish().expect("gzip");
+        let dest = temp.path().join("links");
+        assert!(matches!(
+            unpack_archive(&bytes, &dest),
+            Err(SkillMarketError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn requests_with_unsafe_names_or_sources_are_rejected() {
+        let request = |name: &str, source: SkillSource| SkillInstallRequest {
+            name: name.to_string(),
+            version: "v1".to_string(),
+            source,
+            subdir: None,
+            content_sha256: None,
+        };
+        let git = |url: &str| SkillSource::Git {
+            url: url.to_string(),
+        };
+
+        assert!(validate_request(&request("pdf", git("https://github.com/acme/pdf"))).is_ok());
+        assert!(validate_request(&request("../pdf", git("https://github.com/a/b"))).is_err());
+        assert!(validate_request(&request(".staging", git("https://github.com/a/b"))).is_err());
+        assert!(validate_request(&request("pdf", git("--upload-pack=evil"))).is_err());
+        assert!(validate_request(&request("pdf", git("/srv/repo"))).is_err());
+        assert!(validate_request(&request(
+            "pdf",
+            SkillSource::Archive {
+                url: "https://example.com/pdf.tar.gz".to_string(),
+                sha256: "abc".to_string(),
+            }
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn lock_round_trips_installed_skills() {
+        let temp = TempDir::new().expect("tempdir");
+        let installed = InstalledSkill {
+            name: "pdf".to_string(),
+            version: "v1.2.0".to_string(),
+            source: SkillSource::Git {
+                url: "https://github.com/acme/pdf".to_string(),
+            },
+            subdir: None,
+            commit: Some("0123456789abcdef0123456789abcdef01234567".to_string()),
+            content_sha256: "f".repeat(64),
+            installed_at: Utc::now(),
+            installed_by: "admin@dowhiz.com".to_string(),
+        };
+        let lock = BTreeMap::from([(installed.name.clone(), installed.clone())]);
+
+        save_lock(temp.path(), &lock).expect("save");
+
+        assert_eq!(list_installed(temp.path()).expect("list"), vec![installed]);
+        assert!(list_installed(&temp.path().join("missing"))
+            .expect("list")
+            .is_empty());
+    }
+}
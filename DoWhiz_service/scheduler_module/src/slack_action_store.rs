@@ -0,0 +1,250 @@
+//! Pending Slack interactive actions (confirmation buttons and modals).
+//!
+//! When an agent reply asks for confirmation ("Apply this edit?"), the work to do
+//! for each answer is stored here keyed by the Slack `callback_id`. Clicks that
+//! arrive through `/slack/interactions` claim the record exactly once.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use mongodb::bson::{doc, DateTime as BsonDateTime, Document};
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument};
+use mongodb::sync::Collection;
+use serde::{Deserialize, Serialize};
+
+use crate::mongo_store::{create_client_from_env, database_from_env};
+
+/// Which button the user pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlackActionDecision {
+    Approve,
+    Reject,
+}
+
+impl SlackActionDecision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Approve => "approve",
+            Self::Reject => "reject",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "approve" | "confirm" | "yes" => Some(Self::Approve),
+            "reject" | "cancel" | "no" => Some(Self::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// What to do once a decision comes back.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PendingActionOutcome {
+    /// Only acknowledge the click.
+    #[default]
+    Noop,
+    /// Start a follow-up RunTask in the original thread with this instruction.
+    FollowUpRunTask { instruction: String },
+    /// Disable the given scheduled tasks of the requester.
+    CancelTasks { task_ids: Vec<String> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingActionStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// A confirmation posted to Slack that is waiting for a click.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingSlackAction {
+    pub callback_id: String,
+    #[serde(default)]
+    pub employee_id: Option<String>,
+    #[serde(default)]
+    pub team_id: Option<String>,
+    pub channel_id: String,
+    #[serde(default)]
+    pub thread_ts: Option<String>,
+    /// Slack user whose thread produced the confirmation
+    pub requester_user_id: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub on_approve: PendingActionOutcome,
+    #[serde(default)]
+    pub on_reject: PendingActionOutcome,
+    pub status: PendingActionStatus,
+    #[serde(default)]
+    pub resolved_by: Option<String>,
+    #[serde(with = "mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "mongodb::bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub expires_at: DateTime<Utc>,
+}
+
+impl PendingSlackAction {
+    pub fn outcome_for(&self, decision: SlackActionDecision) -> &PendingActionOutcome {
+        match decision {
+            SlackActionDecision::Approve => &self.on_approve,
+            SlackActionDecision::Reject => &self.on_reject,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SlackActionStoreError {
+    #[error("mongodb error: {0}")]
+    Mongo(#[from] mongodb::error::Error),
+    #[error("bson error: {0}")]
+    Serialize(#[from] mongodb::bson::ser::Error),
+    #[error("bson error: {0}")]
+    Deserialize(#[from] mongodb::bson::de::Error),
+    #[error("mongo config error: {0}")]
+    MongoConfig(String),
+}
+
+/// Store for pending Slack actions.
+#[derive(Debug, Clone)]
+pub struct SlackActionStore {
+    actions: Collection<Document>,
+}
+
+impl SlackActionStore {
+    pub fn new() -> Result<Self, SlackActionStoreError> {
+        let client = create_client_from_env()
+            .map_err(|err| SlackActionStoreError::MongoConfig(err.to_string()))?;
+        let db = database_from_env(&client);
+        let actions = db.collection::<Document>("slack_pending_actions");
+        Ok(Self { actions })
+    }
+
+    pub fn insert(&self, action: &PendingSlackAction) -> Result<(), SlackActionStoreError> {
+        let document = mongodb::bson::to_document(action)?;
+        self.actions.insert_one(document, None)?;
+        Ok(())
+    }
+
+    pub fn get(
+        &self,
+        callback_id: &str,
+    ) -> Result<Option<PendingSlackAction>, SlackActionStoreError> {
+        match self
+            .actions
+            .find_one(doc! { "callback_id": callback_id }, None)?
+        {
+            Some(document) => Ok(Some(mongodb::bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Atomically resolve a pending, unexpired action.
+    ///
+    /// Returns `None` when the action is unknown, expired, or already resolved,
+    /// so double clicks only trigger the outcome once.
+    pub fn claim(
+        &self,
+        callback_id: &str,
+        decision: SlackActionDecision,
+        resolved_by: &str,
+    ) -> Result<Option<PendingSlackAction>, SlackActionStoreError> {
+        let status = match decision {
+            SlackActionDecision::Approve => "approved",
+            SlackActionDecision::Reject => "rejected",
+        };
+        let updated = self.actions.find_one_and_update(
+            doc! {
+                "callback_id": callback_id,
+                "status": "pending",
+                "expires_at": { "$gt": BsonDateTime::now() },
+            },
+            doc! {
+                "$set": {
+                    "status": status,
+                    "resolved_by": resolved_by,
+                }
+            },
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        )?;
+        match updated {
+            Some(document) => Ok(Some(mongodb::bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+static SLACK_ACTION_STORE: std::sync::OnceLock<Option<Arc<SlackActionStore>>> =
+    std::sync::OnceLock::new();
+
+/// Get or initialize the global SlackActionStore (returns None if not configured)
+pub fn get_global_slack_action_store() -> Option<Arc<SlackActionStore>> {
+    SLACK_ACTION_STORE
+        .get_or_init(|| match SlackActionStore::new() {
+            Ok(store) => Some(Arc::new(store)),
+            Err(err) => {
+                tracing::warn!(
+                    "SlackActionStore not available ({}), Slack confirmations disabled",
+                    err
+                );
+                None
+            }
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decision_parse_accepts_button_aliases() {
+        assert_eq!(
+            SlackActionDecision::parse("Confirm"),
+            Some(SlackActionDecision::Approve)
+        );
+        assert_eq!(
+            SlackActionDecision::parse("cancel"),
+            Some(SlackActionDecision::Reject)
+        );
+        assert_eq!(SlackActionDecision::parse("maybe"), None);
+    }
+
+    #[test]
+    fn pending_action_round_trips_through_bson() {
+        let now = Utc::now();
+        let action = PendingSlackAction {
+            callback_id: "cb-1".to_string(),
+            employee_id: Some("little_bear".to_string()),
+            team_id: Some("T1".to_string()),
+            channel_id: "C1".to_string(),
+            thread_ts: Some("1700000000.000100".to_string()),
+            requester_user_id: "U1".to_string(),
+            prompt: "Apply this edit?".to_string(),
+            on_approve: PendingActionOutcome::FollowUpRunTask {
+                instruction: "apply the edit".to_string(),
+            },
+            on_reject: PendingActionOutcome::Noop,
+            status: PendingActionStatus::Pending,
+            resolved_by: None,
+            created_at: now,
+            expires_at: now + chrono::Duration::hours(24),
+        };
+
+        let document = mongodb::bson::to_document(&action).expect("to bson");
+        assert_eq!(document.get_str("status").unwrap(), "pending");
+        assert!(document.get_datetime("expires_at").is_ok());
+
+        let parsed: PendingSlackAction = mongodb::bson::from_document(document).expect("from bson");
+        assert_eq!(parsed.on_approve, action.on_approve);
+        assert_eq!(
+            parsed.outcome_for(SlackActionDecision::Reject),
+            &PendingActionOutcome::Noop
+        );
+    }
+}
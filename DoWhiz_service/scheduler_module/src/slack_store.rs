@@ -5,12 +5,10 @@
 use chrono::{DateTime, Utc};
 use mongodb::bson::{doc, Bson, DateTime as BsonDateTime, Document};
 use mongodb::options::FindOptions;
-use mongodb::options::IndexOptions;
 use mongodb::sync::Collection;
-use mongodb::IndexModel;
 use std::path::PathBuf;
 
-use crate::mongo_store::{create_client_from_env, database_from_env, ensure_index_compatible};
+use crate::mongo_store::{create_client_from_env, database_from_env};
 
 /// A Slack workspace installation record.
 #[derive(Debug, Clone)]
@@ -110,19 +108,6 @@ impl MongoSlackStore {
             .map_err(|err| SlackStoreError::MongoConfig(err.to_string()))?;
         let db = database_from_env(&client);
         let installations = db.collection::<Document>("slack_installations");
-        ensure_index_compatible(
-            &installations,
-            IndexModel::builder()
-                .keys(doc! { "team_id": 1 })
-                .options(IndexOptions::builder().unique(Some(true)).build())
-                .build(),
-        )?;
-        ensure_index_compatible(
-            &installations,
-            IndexModel::builder()
-                .keys(doc! { "installed_at": -1 })
-                .build(),
-        )?;
         Ok(Self { installations })
     }
 
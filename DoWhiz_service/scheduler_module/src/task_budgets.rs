@@ -0,0 +1,338 @@
+//! Per-user and per-employee usage budgets.
+//!
+//! Budgets come from `TASK_BUDGETS_JSON` and are checked against the
+//! `task_costs` rows recorded after each run (see [`crate::task_costs`]):
+//! run_tasks per UTC day and tokens (input + output) per UTC month. Once a
+//! budget is used up, the scheduler defers scheduled run_tasks until it
+//! resets and the quick-response path answers with a budget notice
+//! ([`crate::i18n::Message::BudgetReached`]) instead of calling a model.
+//! Without a configured budget or an account store nothing is limited.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::account_store::get_global_account_store;
+use crate::task_costs::{CostDimension, TaskCostQuery, TaskCostTotal};
+
+pub const TASK_BUDGETS_ENV: &str = "TASK_BUDGETS_JSON";
+
+/// Limits for one user or employee. Unset limits are unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct Budget {
+    #[serde(default)]
+    pub tasks_per_day: Option<u64>,
+    #[serde(default)]
+    pub tokens_per_month: Option<u64>,
+}
+
+impl Budget {
+    fn is_unlimited(&self) -> bool {
+        self.tasks_per_day.is_none() && self.tokens_per_month.is_none()
+    }
+
+    fn exceeded(&self, usage: &BudgetUsage) -> Option<BudgetLimit> {
+        if self
+            .tasks_per_day
+            .is_some_and(|limit| usage.tasks_today >= limit)
+        {
+            return Some(BudgetLimit::TasksPerDay);
+        }
+        if self
+            .tokens_per_month
+            .is_some_and(|limit| usage.tokens_this_month >= limit)
+        {
+            return Some(BudgetLimit::TokensPerMonth);
+        }
+        None
+    }
+}
+
+/// `TASK_BUDGETS_JSON`, e.g.
+/// `{"user_default": {"tasks_per_day": 50}, "users": {"<user_id>": {"tokens_per_month": 2000000}},
+/// "employees": {"little_bear": {"tokens_per_month": 50000000}}}`.
+/// A user entry replaces `user_default` for that user.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BudgetConfig {
+    #[serde(default)]
+    pub user_default: Budget,
+    #[serde(default)]
+    pub users: HashMap<String, Budget>,
+    #[serde(default)]
+    pub employees: HashMap<String, Budget>,
+}
+
+impl BudgetConfig {
+    fn user_budget(&self, user_id: &str) -> Budget {
+        self.users
+            .get(user_id)
+            .copied()
+            .unwrap_or(self.user_default)
+    }
+
+    fn employee_budget(&self, employee_id: &str) -> Budget {
+        self.employees.get(employee_id).copied().unwrap_or_default()
+    }
+}
+
+fn budget_config() -> &'static BudgetConfig {
+    static CONFIG: OnceLock<BudgetConfig> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        let Some(raw) = std::env::var(TASK_BUDGETS_ENV)
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+        else {
+            return BudgetConfig::default();
+        };
+        match serde_json::from_str(&raw) {
+            Ok(config) => config,
+            Err(err) => {
+                warn!("ignoring invalid {}: {}", TASK_BUDGETS_ENV, err);
+                BudgetConfig::default()
+            }
+        }
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetScope {
+    User,
+    Employee,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetLimit {
+    TasksPerDay,
+    TokensPerMonth,
+}
+
+/// A used-up budget and when its window rolls over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    pub scope: BudgetScope,
+    pub limit: BudgetLimit,
+    pub resets_at: DateTime<Utc>,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scope = match self.scope {
+            BudgetScope::User => "user",
+            BudgetScope::Employee => "employee",
+        };
+        let limit = match self.limit {
+            BudgetLimit::TasksPerDay => "tasks_per_day",
+            BudgetLimit::TokensPerMonth => "tokens_per_month",
+        };
+        write!(
+            f,
+            "{} {} (resets {})",
+            scope,
+            limit,
+            self.resets_at.to_rfc3339()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct BudgetUsage {
+    tasks_today: u64,
+    tokens_this_month: u64,
+}
+
+impl BudgetUsage {
+    /// Fold per-day totals (grouped by [`CostDimension::Day`]) covering the
+    /// current month.
+    fn from_daily_totals(totals: &[TaskCostTotal], now: DateTime<Utc>) -> Self {
+        let today = now.format("%Y-%m-%d").to_string();
+        let mut usage = Self::default();
+        for total in totals {
+            usage.tokens_this_month += (total.input_tokens + total.output_tokens).max(0) as u64;
+            if total.group.get(CostDimension::Day.as_str()) == Some(&today) {
+                usage.tasks_today += total.tasks.max(0) as u64;
+            }
+        }
+        usage
+    }
+}
+
+fn day_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+fn month_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(now.year(), now.month(), 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+}
+
+fn resets_at(limit: BudgetLimit, now: DateTime<Utc>) -> DateTime<Utc> {
+    match limit {
+        BudgetLimit::TasksPerDay => day_start(now) + Duration::days(1),
+        BudgetLimit::TokensPerMonth => {
+            let (year, month) = if now.month() == 12 {
+                (now.year() + 1, 1)
+            } else {
+                (now.year(), now.month() + 1)
+            };
+            NaiveDate::from_ymd_opt(year, month, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc()
+        }
+    }
+}
+
+/// The first used-up budget for `user_id` or `employee_id`, if any. Usage
+/// lookups fail open: an unreachable store never blocks work.
+pub fn check(user_id: Option<&str>, employee_id: Option<&str>) -> Option<BudgetExceeded> {
+    let config = budget_config();
+    let now = Utc::now();
+    let candidates = [
+        (
+            BudgetScope::User,
+            user_id.map(|id| (id, config.user_budget(id))),
+        ),
+        (
+            BudgetScope::Employee,
+            employee_id.map(|id| (id, config.employee_budget(id))),
+        ),
+    ];
+    for (scope, candidate) in candidates {
+        let Some((id, budget)) =
+            candidate.filter(|(id, budget)| !id.trim().is_empty() && !budget.is_unlimited())
+        else {
+            continue;
+        };
+        let usage = match usage_for(scope, id, now) {
+            Some(usage) => usage,
+            None => continue,
+        };
+        if let Some(limit) = budget.exceeded(&usage) {
+            return Some(BudgetExceeded {
+                scope,
+                limit,
+                resets_at: resets_at(limit, now),
+            });
+        }
+    }
+    None
+}
+
+fn usage_for(scope: BudgetScope, id: &str, now: DateTime<Utc>) -> Option<BudgetUsage> {
+    let store = get_global_account_store()?;
+    let mut query = TaskCostQuery {
+        since: Some(month_start(now)),
+        ..Default::default()
+    };
+    match scope {
+        BudgetScope::User => query.user_id = Some(id.to_string()),
+        BudgetScope::Employee => query.employee_id = Some(id.to_string()),
+    }
+    match store.task_cost_totals(&query, &[CostDimension::Day]) {
+        Ok(totals) => Some(BudgetUsage::from_daily_totals(&totals, now)),
+        Err(err) => {
+            warn!("budget usage lookup failed for {}: {}", id, err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn day_total(day: &str, tasks: i64, input: i64, output: i64) -> TaskCostTotal {
+        TaskCostTotal {
+            group: BTreeMap::from([("day".to_string(), day.to_string())]),
+            tasks,
+            input_tokens: input,
+            cached_input_tokens: 0,
+            output_tokens: output,
+            cost_usd: 0.0,
+            unpriced_tasks: tasks,
+        }
+    }
+
+    #[test]
+    fn usage_counts_todays_tasks_and_the_months_tokens() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 14, 9, 30, 0).unwrap();
+        let usage = BudgetUsage::from_daily_totals(
+            &[
+                day_total("2026-03-02", 4, 1_000, 200),
+                day_total("2026-03-14", 3, 500, 100),
+            ],
+            now,
+        );
+        assert_eq!(
+            usage,
+            BudgetUsage {
+                tasks_today: 3,
+                tokens_this_month: 1_800,
+            }
+        );
+
+        let budget = Budget {
+            tasks_per_day: Some(3),
+            tokens_per_month: Some(10_000),
+        };
+        assert_eq!(budget.exceeded(&usage), Some(BudgetLimit::TasksPerDay));
+        let budget = Budget {
+            tasks_per_day: Some(10),
+            tokens_per_month: Some(1_800),
+        };
+        assert_eq!(budget.exceeded(&usage), Some(BudgetLimit::TokensPerMonth));
+        assert_eq!(Budget::default().exceeded(&usage), None);
+    }
+
+    #[test]
+    fn budgets_reset_at_the_next_utc_day_or_month() {
+        let now = Utc.with_ymd_and_hms(2026, 12, 31, 23, 59, 0).unwrap();
+        assert_eq!(
+            resets_at(BudgetLimit::TasksPerDay, now),
+            Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            resets_at(BudgetLimit::TokensPerMonth, now),
+            Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            month_start(now),
+            Utc.with_ymd_and_hms(2026, 12, 1, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn user_entries_replace_the_default() {
+        let config: BudgetConfig = serde_json::from_str(
+            r#"{"user_default": {"tasks_per_day": 5},
+                "users": {"vip": {"tokens_per_month": 100}},
+                "employees": {"little_bear": {"tasks_per_day": 500}}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.user_budget("someone").tasks_per_day, Some(5));
+        assert_eq!(
+            config.user_budget("vip"),
+            Budget {
+                tasks_per_day: None,
+                tokens_per_month: Some(100),
+            }
+        );
+        assert_eq!(
+            config.employee_budget("little_bear").tasks_per_day,
+            Some(500)
+        );
+        assert!(config.employee_budget("boiled_egg").is_unlimited());
+    }
+}
@@ -0,0 +1,391 @@
+//! Token and cost accounting per run_task execution.
+//!
+//! After each run the executor records the runner's token usage in the
+//! `task_costs` table of the account store, with the user, account and
+//! employee it ran for; runs without reported usage get a row with zero
+//! tokens so they still count toward [`crate::task_budgets`]. Runners that
+//! report an API cost (Claude) keep it; for the others the cost is estimated
+//! from `TASK_COST_PRICING_JSON`, and left empty for models without a price. `GET /admin/costs` serves totals
+//! grouped by user, account, employee, runner, model and/or day.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use postgres::types::ToSql;
+use run_task_module::TokenUsage;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::account_store::get_global_account_store;
+
+pub const TASK_COST_PRICING_ENV: &str = "TASK_COST_PRICING_JSON";
+
+/// USD per million tokens for one model.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_mtok: f64,
+    /// Defaults to `input_per_mtok`.
+    #[serde(default)]
+    pub cached_input_per_mtok: Option<f64>,
+    pub output_per_mtok: f64,
+}
+
+impl ModelPrice {
+    pub fn cost_usd(&self, usage: &TokenUsage) -> f64 {
+        let cached = usage.cached_input_tokens.min(usage.input_tokens);
+        let uncached = usage.input_tokens - cached;
+        let cached_price = self.cached_input_per_mtok.unwrap_or(self.input_per_mtok);
+        (uncached as f64 * self.input_per_mtok
+            + cached as f64 * cached_price
+            + usage.output_tokens as f64 * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// Prices keyed by model name, from `TASK_COST_PRICING_JSON`, e.g.
+/// `{"gpt-5.4": {"input_per_mtok": 1.25, "cached_input_per_mtok": 0.125, "output_per_mtok": 10}}`.
+fn pricing() -> &'static HashMap<String, ModelPrice> {
+    static PRICING: OnceLock<HashMap<String, ModelPrice>> = OnceLock::new();
+    PRICING.get_or_init(|| {
+        let Some(raw) = std::env::var(TASK_COST_PRICING_ENV)
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+        else {
+            return HashMap::new();
+        };
+        match serde_json::from_str(&raw) {
+            Ok(pricing) => pricing,
+            Err(err) => {
+                warn!("ignoring invalid {}: {}", TASK_COST_PRICING_ENV, err);
+                HashMap::new()
+            }
+        }
+    })
+}
+
+/// One run_task execution's usage, as stored in `task_costs`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskCostRecord {
+    pub account_id: Option<Uuid>,
+    pub user_id: Option<String>,
+    pub employee_id: Option<String>,
+    pub runner: String,
+    pub model: String,
+    pub channel: String,
+    pub trace_id: Option<String>,
+    pub input_tokens: i64,
+    pub cached_input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_usd: Option<f64>,
+    /// `runner` when the runner reported the cost, `pricing` when estimated.
+    pub cost_source: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl TaskCostRecord {
+    /// Tokens and cost from `usage`, priced with `TASK_COST_PRICING_JSON`.
+    /// Identity fields are left for the caller.
+    pub fn from_usage(usage: &TokenUsage, runner: &str, model: &str) -> Self {
+        Self::priced(usage, runner, model, pricing())
+    }
+
+    /// A run whose runner reported no usage. Still recorded so it counts
+    /// toward task budgets.
+    pub fn unmetered(runner: &str, model: &str) -> Self {
+        Self::priced(&TokenUsage::default(), runner, model, &HashMap::new())
+    }
+
+    fn priced(
+        usage: &TokenUsage,
+        runner: &str,
+        model: &str,
+        pricing: &HashMap<String, ModelPrice>,
+    ) -> Self {
+        let (cost_usd, cost_source) = match usage.cost_usd {
+            Some(cost) => (Some(cost), Some("runner".to_string())),
+            None => match pricing.get(model) {
+                Some(price) => (Some(price.cost_usd(usage)), Some("pricing".to_string())),
+                None => (None, None),
+            },
+        };
+        Self {
+            account_id: None,
+            user_id: None,
+            employee_id: None,
+            runner: runner.to_string(),
+            model: model.to_string(),
+            channel: String::new(),
+            trace_id: None,
+            input_tokens: usage.input_tokens as i64,
+            cached_input_tokens: usage.cached_input_tokens as i64,
+            output_tokens: usage.output_tokens as i64,
+            cost_usd,
+            cost_source,
+            recorded_at: Utc::now(),
+        }
+    }
+}
+
+/// Store `record`. Best effort: the run already happened, so a store failure
+/// is logged rather than failing the task.
+pub fn record(record: TaskCostRecord) {
+    info!(
+        "task cost user={} employee={} runner={} model={} input={} cached={} output={} cost_usd={}",
+        record.user_id.as_deref().unwrap_or("-"),
+        record.employee_id.as_deref().unwrap_or("-"),
+        record.runner,
+        record.model,
+        record.input_tokens,
+        record.cached_input_tokens,
+        record.output_tokens,
+        record
+            .cost_usd
+            .map(|cost| format!("{:.6}", cost))
+            .unwrap_or_else(|| "-".to_string())
+    );
+    let Some(store) = get_global_account_store() else {
+        return;
+    };
+    if let Err(err) = store.record_task_cost(&record) {
+        warn!("failed to record task cost: {}", err);
+    }
+}
+
+/// A column `/admin/costs` can group by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostDimension {
+    User,
+    Account,
+    Employee,
+    Runner,
+    Model,
+    Day,
+}
+
+impl CostDimension {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "user" => Some(Self::User),
+            "account" => Some(Self::Account),
+            "employee" => Some(Self::Employee),
+            "runner" => Some(Self::Runner),
+            "model" => Some(Self::Model),
+            "day" => Some(Self::Day),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Account => "account",
+            Self::Employee => "employee",
+            Self::Runner => "runner",
+            Self::Model => "model",
+            Self::Day => "day",
+        }
+    }
+
+    fn sql(self) -> &'static str {
+        match self {
+            Self::User => "COALESCE(user_id, '')",
+            Self::Account => "COALESCE(account_id::text, '')",
+            Self::Employee => "COALESCE(employee_id, '')",
+            Self::Runner => "runner",
+            Self::Model => "model",
+            Self::Day => "to_char(recorded_at AT TIME ZONE 'UTC', 'YYYY-MM-DD')",
+        }
+    }
+}
+
+/// Filters and grouping for [`crate::account_store::AccountStore::task_cost_totals`].
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TaskCostQuery {
+    /// Comma-separated [`CostDimension`]s, e.g. `user,day`. Empty gives one
+    /// grand total.
+    pub group_by: Option<String>,
+    pub account_id: Option<Uuid>,
+    pub user_id: Option<String>,
+    pub employee_id: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl TaskCostQuery {
+    pub fn dimensions(&self) -> Result<Vec<CostDimension>, String> {
+        let mut dimensions = Vec::new();
+        for raw in self.group_by.as_deref().unwrap_or("").split(',') {
+            if raw.trim().is_empty() {
+                continue;
+            }
+            let dimension =
+                CostDimension::parse(raw).ok_or_else(|| format!("unknown group_by: {}", raw))?;
+            if !dimensions.contains(&dimension) {
+                dimensions.push(dimension);
+            }
+        }
+        Ok(dimensions)
+    }
+
+    /// The aggregate query and its parameters. Grouping columns come first,
+    /// then tasks, input, cached input and output tokens, cost and the
+    /// number of tasks without a cost.
+    pub(crate) fn to_sql(
+        &self,
+        dimensions: &[CostDimension],
+    ) -> (String, Vec<Box<dyn ToSql + Sync + Send>>) {
+        let mut params: Vec<Box<dyn ToSql + Sync + Send>> = Vec::new();
+        let mut conditions = Vec::new();
+        if let Some(account_id) = self.account_id {
+            params.push(Box::new(account_id));
+            conditions.push(format!("account_id = ${}", params.len()));
+        }
+        for (column, value) in [
+            ("user_id", &self.user_id),
+            ("employee_id", &self.employee_id),
+        ] {
+            if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                params.push(Box::new(value.to_string()));
+                conditions.push(format!("{} = ${}", column, params.len()));
+            }
+        }
+        if let Some(since) = self.since {
+            params.push(Box::new(since));
+            conditions.push(format!("recorded_at >= ${}", params.len()));
+        }
+        if let Some(until) = self.until {
+            params.push(Box::new(until));
+            conditions.push(format!("recorded_at < ${}", params.len()));
+        }
+
+        let columns: Vec<String> = dimensions
+            .iter()
+            .map(|dimension| dimension.sql().to_string())
+            .collect();
+        let mut sql = String::from("SELECT ");
+        for column in &columns {
+            sql.push_str(column);
+            sql.push_str(", ");
+        }
+        sql.push_str(
+            "COUNT(*)::bigint, \
+             COALESCE(SUM(input_tokens), 0)::bigint, \
+             COALESCE(SUM(cached_input_tokens), 0)::bigint, \
+             COALESCE(SUM(output_tokens), 0)::bigint, \
+             COALESCE(SUM(cost_usd), 0)::float8, \
+             COUNT(*) FILTER (WHERE cost_usd IS NULL)::bigint \
+             FROM task_costs",
+        );
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        if !columns.is_empty() {
+            let positions: Vec<String> = (1..=columns.len()).map(|i| i.to_string()).collect();
+            sql.push_str(" GROUP BY ");
+            sql.push_str(&positions.join(", "));
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&positions.join(", "));
+        }
+        (sql, params)
+    }
+}
+
+/// One row of `/admin/costs`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskCostTotal {
+    /// Value of each grouping dimension; empty when ungrouped.
+    pub group: BTreeMap<String, String>,
+    pub tasks: i64,
+    pub input_tokens: i64,
+    pub cached_input_tokens: i64,
+    pub output_tokens: i64,
+    /// Sum over the tasks that have a cost.
+    pub cost_usd: f64,
+    /// Tasks whose model has no price and whose runner reported no cost.
+    pub unpriced_tasks: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input: u64, cached: u64, output: u64, cost_usd: Option<f64>) -> TokenUsage {
+        TokenUsage {
+            input_tokens: input,
+            cached_input_tokens: cached,
+            output_tokens: output,
+            cost_usd,
+        }
+    }
+
+    #[test]
+    fn cost_prefers_runner_report_then_pricing() {
+        let pricing = HashMap::from([(
+            "gpt-5.4".to_string(),
+            ModelPrice {
+                input_per_mtok: 2.0,
+                cached_input_per_mtok: Some(0.5),
+                output_per_mtok: 10.0,
+            },
+        )]);
+
+        let priced = TaskCostRecord::priced(
+            &usage(1_000_000, 400_000, 100_000, None),
+            "codex",
+            "gpt-5.4",
+            &pricing,
+        );
+        // 600k uncached * $2 + 400k cached * $0.5 + 100k output * $10
+        assert!((priced.cost_usd.unwrap() - 2.4).abs() < 1e-9);
+        assert_eq!(priced.cost_source.as_deref(), Some("pricing"));
+
+        let reported =
+            TaskCostRecord::priced(&usage(10, 0, 5, Some(0.03)), "claude", "gpt-5.4", &pricing);
+        assert_eq!(reported.cost_usd, Some(0.03));
+        assert_eq!(reported.cost_source.as_deref(), Some("runner"));
+
+        let unpriced =
+            TaskCostRecord::priced(&usage(10, 0, 5, None), "local", "llama3.1", &pricing);
+        assert_eq!(unpriced.cost_usd, None);
+        assert_eq!(unpriced.cost_source, None);
+    }
+
+    #[test]
+    fn query_rejects_unknown_dimensions_and_dedupes() {
+        let query = TaskCostQuery {
+            group_by: Some("user, day,user".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            query.dimensions().unwrap(),
+            vec![CostDimension::User, CostDimension::Day]
+        );
+        let bad = TaskCostQuery {
+            group_by: Some("user,team".to_string()),
+            ..Default::default()
+        };
+        assert!(bad.dimensions().is_err());
+    }
+
+    #[test]
+    fn query_sql_groups_and_numbers_filters() {
+        let query = TaskCostQuery {
+            employee_id: Some("little_bear".to_string()),
+            user_id: Some(" ".to_string()),
+            since: Some(Utc::now()),
+            ..Default::default()
+        };
+        let (sql, params) = query.to_sql(&[CostDimension::User, CostDimension::Day]);
+        assert_eq!(params.len(), 2);
+        assert!(sql.starts_with("SELECT COALESCE(user_id, ''), to_char("));
+        assert!(sql.contains(" WHERE employee_id = $1 AND recorded_at >= $2"));
+        assert!(sql.ends_with(" GROUP BY 1, 2 ORDER BY 1, 2"));
+
+        let (total, params) = TaskCostQuery::default().to_sql(&[]);
+        assert!(params.is_empty());
+        assert!(total.ends_with("FROM task_costs"));
+    }
+}
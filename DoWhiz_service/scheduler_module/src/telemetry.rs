@@ -0,0 +1,206 @@
+//! Log subscriber setup and service metrics.
+//!
+//! Every binary calls [`init`] once at startup. Builds with the `otel` feature
+//! additionally export spans and metrics over OTLP/HTTP when
+//! `OTEL_EXPORTER_OTLP_ENDPOINT` is set; otherwise only the log output is
+//! installed and the `record_*` helpers are no-ops. Log output is passed
+//! through [`scrub_secrets`] before it is written; its format and levels are
+//! configured as described in [`logging`](self::logging).
+
+use std::time::Duration;
+
+use crate::channel::Channel;
+
+mod logging;
+#[cfg(feature = "otel")]
+mod otlp;
+mod scrub;
+
+pub use logging::{note_user, set_log_employee_id, tenant_scope, TenantScope};
+pub use scrub::scrub_secrets;
+pub(crate) use scrub::ScrubbedStdout;
+
+use logging::LogConfig;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Flushes and shuts down the OTLP exporters when dropped. Hold it until
+/// `main` returns.
+#[must_use = "dropping the guard shuts down span and metric export"]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    providers: Option<otlp::Providers>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(providers) = self.providers.take() {
+            providers.shutdown();
+        }
+    }
+}
+
+/// Install the global log subscriber; `service_name` is the OTLP
+/// `service.name` unless `OTEL_SERVICE_NAME` overrides it.
+pub fn init(service_name: &str) -> TelemetryGuard {
+    let config = LogConfig::from_env();
+    #[cfg(feature = "otel")]
+    if otlp::enabled_from_env() {
+        match otlp::init(service_name, &config) {
+            Ok(providers) => {
+                run_task_module::set_external_command_observer(record_external_command);
+                return TelemetryGuard {
+                    providers: Some(providers),
+                };
+            }
+            Err(err) => {
+                init_logs(&config);
+                tracing::warn!("OTLP export disabled: {}", err);
+                return TelemetryGuard { providers: None };
+            }
+        }
+    }
+    let _ = service_name;
+    init_logs(&config);
+    TelemetryGuard {
+        #[cfg(feature = "otel")]
+        providers: None,
+    }
+}
+
+fn init_logs(config: &LogConfig) {
+    tracing_subscriber::registry()
+        .with(config.filter())
+        .with(config.output_layer(ScrubbedStdout))
+        .init();
+}
+
+/// Parent `span` under the correlation ID so exported spans from every
+/// process share one trace.
+pub(crate) fn link_trace(span: &tracing::Span, trace_id: &str) {
+    #[cfg(feature = "otel")]
+    otlp::link_trace(span, trace_id);
+    #[cfg(not(feature = "otel"))]
+    let _ = (span, trace_id);
+}
+
+/// Number of due tasks returned by one scheduler poll.
+pub(crate) fn record_due_tasks(count: usize) {
+    #[cfg(feature = "otel")]
+    otlp::instruments().due_tasks.record(count as u64, &[]);
+    #[cfg(not(feature = "otel"))]
+    let _ = count;
+}
+
+/// One scheduled task execution.
+pub(crate) fn record_task_run(kind: &'static str, elapsed: Duration, ok: bool) {
+    #[cfg(feature = "otel")]
+    otlp::instruments().task_duration.record(
+        elapsed.as_secs_f64(),
+        &[otlp::attr("kind", kind), otlp::outcome(ok)],
+    );
+    #[cfg(not(feature = "otel"))]
+    let _ = (kind, elapsed, ok);
+}
+
+/// One envelope handled by the ingestion consumer.
+pub(crate) fn record_ingestion(channel: Channel, ok: bool) {
+    #[cfg(feature = "otel")]
+    otlp::instruments().ingested.add(
+        1,
+        &[
+            otlp::attr("channel", channel.to_string()),
+            otlp::outcome(ok),
+        ],
+    );
+    #[cfg(not(feature = "otel"))]
+    let _ = (channel, ok);
+}
+
+/// How long one claimed envelope waited in the ingestion queue.
+pub(crate) fn record_queue_latency(channel: Channel, priority: &'static str, waited: Duration) {
+    #[cfg(feature = "otel")]
+    otlp::instruments().queue_latency.record(
+        waited.as_secs_f64(),
+        &[
+            otlp::attr("channel", channel.to_string()),
+            otlp::attr("priority", priority),
+        ],
+    );
+    #[cfg(not(feature = "otel"))]
+    let _ = (channel, priority, waited);
+}
+
+/// One stale envelope shed by the ingestion consumer.
+pub(crate) fn record_shed(channel: Channel) {
+    #[cfg(feature = "otel")]
+    otlp::instruments()
+        .shed
+        .add(1, &[otlp::attr("channel", channel.to_string())]);
+    #[cfg(not(feature = "otel"))]
+    let _ = channel;
+}
+
+/// One codex/claude runner invocation.
+pub(crate) fn record_runner_run(runner: &str, elapsed: Duration, ok: bool) {
+    #[cfg(feature = "otel")]
+    otlp::instruments().runner_duration.record(
+        elapsed.as_secs_f64(),
+        &[otlp::attr("runner", runner.to_string()), otlp::outcome(ok)],
+    );
+    #[cfg(not(feature = "otel"))]
+    let _ = (runner, elapsed, ok);
+}
+
+/// One outbound send through a channel adapter.
+pub(crate) fn record_outbound_send(channel: Channel, elapsed: Duration, ok: bool) {
+    #[cfg(feature = "otel")]
+    otlp::instruments().send_duration.record(
+        elapsed.as_secs_f64(),
+        &[
+            otlp::attr("channel", channel.to_string()),
+            otlp::outcome(ok),
+        ],
+    );
+    #[cfg(not(feature = "otel"))]
+    let _ = (channel, elapsed, ok);
+}
+
+/// One health probe round trip; failed probes report the time they waited.
+pub(crate) fn record_probe(elapsed: Duration, ok: bool) {
+    #[cfg(feature = "otel")]
+    otlp::instruments()
+        .probe_duration
+        .record(elapsed.as_secs_f64(), &[otlp::outcome(ok)]);
+    #[cfg(not(feature = "otel"))]
+    let _ = (elapsed, ok);
+}
+
+/// Inconsistencies of one kind found by a store integrity pass.
+pub(crate) fn record_integrity_issues(check: &'static str, count: usize, repaired: bool) {
+    #[cfg(feature = "otel")]
+    otlp::instruments().integrity_issues.add(
+        count as u64,
+        &[otlp::attr("check", check), otlp::attr("repaired", repaired)],
+    );
+    #[cfg(not(feature = "otel"))]
+    let _ = (check, count, repaired);
+}
+
+/// One az/gh/docker invocation made by run_task, across its retries.
+#[cfg(feature = "otel")]
+fn record_external_command(report: &run_task_module::ExternalCommandReport) {
+    let outcome = report
+        .failure
+        .map(run_task_module::FailureClass::as_str)
+        .unwrap_or("success");
+    otlp::instruments().external_command_duration.record(
+        report.elapsed.as_secs_f64(),
+        &[
+            otlp::attr("command", report.label),
+            otlp::attr("outcome", outcome),
+            otlp::attr("attempts", i64::from(report.attempts)),
+        ],
+    );
+}
@@ -0,0 +1,417 @@
+//! Log format, levels and tenant fields.
+//!
+//! `LOG_FORMAT=json` writes one JSON object per line instead of the default
+//! text. Levels come from `LOG_LEVEL` (default `info`) and the comma-separated
+//! `target=level` overrides in `LOG_MODULE_LEVELS`, such as
+//! `mongodb=warn,scheduler_module::ingestion=debug`; `RUST_LOG`, when set,
+//! replaces both.
+//!
+//! JSON records carry `employee_id`, `user_id` and `trace_id` at the top
+//! level. Each is taken from the nearest enclosing span with a field of that
+//! name, then from the thread's [`TenantScope`] (user) or current trace
+//! (trace ID), then from the process employee set with
+//! [`set_log_employee_id`] or `EMPLOYEE_ID`.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::OnceLock;
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+use crate::trace_context;
+
+const DEFAULT_LEVEL: &str = "info";
+
+static LOG_EMPLOYEE_ID: OnceLock<String> = OnceLock::new();
+
+thread_local! {
+    /// `Some` while a [`TenantScope`] is held; the inner value is its user.
+    static TENANT_USER: RefCell<Option<Option<String>>> = const { RefCell::new(None) };
+}
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LogConfig {
+    pub(crate) format: LogFormat,
+    /// `EnvFilter` directives.
+    pub(crate) directives: String,
+}
+
+impl LogConfig {
+    pub(crate) fn from_env() -> Self {
+        let var = |key: &str| std::env::var(key).ok();
+        Self::parse(
+            var("LOG_FORMAT").as_deref(),
+            var("LOG_LEVEL").as_deref(),
+            var("LOG_MODULE_LEVELS").as_deref(),
+            var("RUST_LOG").as_deref(),
+        )
+    }
+
+    fn parse(
+        format: Option<&str>,
+        level: Option<&str>,
+        module_levels: Option<&str>,
+        rust_log: Option<&str>,
+    ) -> Self {
+        let format = match non_empty(format) {
+            Some(format) if format.eq_ignore_ascii_case("json") => LogFormat::Json,
+            _ => LogFormat::Text,
+        };
+        let directives = match non_empty(rust_log) {
+            Some(rust_log) => rust_log.to_string(),
+            None => std::iter::once(non_empty(level).unwrap_or(DEFAULT_LEVEL))
+                .chain(
+                    non_empty(module_levels)
+                        .into_iter()
+                        .flat_map(|levels| levels.split(','))
+                        .map(str::trim)
+                        .filter(|directive| !directive.is_empty()),
+                )
+                .collect::<Vec<_>>()
+                .join(","),
+        };
+        Self { format, directives }
+    }
+
+    /// Invalid directives are reported on stderr and skipped.
+    pub(crate) fn filter(&self) -> EnvFilter {
+        EnvFilter::builder().parse_lossy(&self.directives)
+    }
+
+    /// The log output for this format, writing through `writer`.
+    pub(crate) fn output_layer<S, W>(&self, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+        W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+    {
+        match self.format {
+            LogFormat::Text => tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_writer(writer)
+                .boxed(),
+            LogFormat::Json => tracing_subscriber::fmt::layer()
+                .event_format(JsonFormat)
+                .with_writer(writer)
+                .and_then(TenantLayer)
+                .boxed(),
+        }
+    }
+}
+
+/// Employee reported on records outside any span that names one. Only the
+/// first call takes effect.
+pub fn set_log_employee_id(employee_id: &str) {
+    let _ = LOG_EMPLOYEE_ID.set(employee_id.to_string());
+}
+
+fn log_employee_id() -> Option<String> {
+    LOG_EMPLOYEE_ID.get().cloned().or_else(|| {
+        std::env::var("EMPLOYEE_ID")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+    })
+}
+
+/// Opens a tenant scope on this thread until dropped; [`note_user`] inside
+/// it tags the thread's records with that user. Used around work that learns
+/// its user part-way through, such as processing one inbound message.
+pub fn tenant_scope() -> TenantScope {
+    let previous = TENANT_USER.with(|current| current.replace(Some(None)));
+    TenantScope { previous }
+}
+
+/// Restores the enclosing tenant scope, if any, on drop.
+pub struct TenantScope {
+    previous: Option<Option<String>>,
+}
+
+impl Drop for TenantScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        TENANT_USER.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Tag the rest of this thread's tenant scope with `user_id`; a no-op
+/// outside one.
+pub fn note_user(user_id: &str) {
+    TENANT_USER.with(|current| {
+        if let Some(user) = current.borrow_mut().as_mut() {
+            *user = Some(user_id.to_string());
+        }
+    });
+}
+
+fn scoped_user() -> Option<String> {
+    TENANT_USER.with(|current| current.borrow().clone().flatten())
+}
+
+/// Tenant fields a span was created or recorded with.
+#[derive(Debug, Default)]
+struct SpanTenant {
+    employee_id: Option<String>,
+    user_id: Option<String>,
+    trace_id: Option<String>,
+}
+
+impl Visit for SpanTenant {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        let slot = match field.name() {
+            "employee_id" => &mut self.employee_id,
+            "user_id" => &mut self.user_id,
+            "trace_id" => &mut self.trace_id,
+            _ => return,
+        };
+        *slot = Some(value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        // `%value` fields arrive here, formatted with `Display`.
+        self.record_str(field, &format!("{:?}", value));
+    }
+}
+
+/// Keeps the tenant fields of each span for [`JsonFormat`].
+struct TenantLayer;
+
+impl<S> Layer<S> for TenantLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut tenant = SpanTenant::default();
+        attrs.record(&mut tenant);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(tenant);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut extensions = span.extensions_mut();
+            match extensions.get_mut::<SpanTenant>() {
+                Some(tenant) => values.record(tenant),
+                None => {
+                    let mut tenant = SpanTenant::default();
+                    values.record(&mut tenant);
+                    extensions.insert(tenant);
+                }
+            }
+        }
+    }
+}
+
+/// One JSON object per event.
+struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut tenant = SpanTenant::default();
+        let mut span_name = None;
+        if let Some(scope) = ctx.event_scope() {
+            for span in scope {
+                span_name.get_or_insert(span.name());
+                if let Some(fields) = span.extensions().get::<SpanTenant>() {
+                    for (slot, value) in [
+                        (&mut tenant.employee_id, &fields.employee_id),
+                        (&mut tenant.user_id, &fields.user_id),
+                        (&mut tenant.trace_id, &fields.trace_id),
+                    ] {
+                        if slot.is_none() {
+                            slot.clone_from(value);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+        let metadata = event.metadata();
+        let mut record = Map::new();
+        record.insert(
+            "timestamp".to_string(),
+            Utc::now()
+                .to_rfc3339_opts(SecondsFormat::Millis, true)
+                .into(),
+        );
+        record.insert("level".to_string(), metadata.level().as_str().into());
+        record.insert("target".to_string(), metadata.target().into());
+        record.insert(
+            "message".to_string(),
+            fields.message.unwrap_or_default().into(),
+        );
+        let tenant_fields = [
+            ("employee_id", tenant.employee_id.or_else(log_employee_id)),
+            ("user_id", tenant.user_id.or_else(scoped_user)),
+            (
+                "trace_id",
+                tenant.trace_id.or_else(trace_context::current_trace_id),
+            ),
+        ];
+        for (key, value) in tenant_fields {
+            record.insert(key.to_string(), value.map_or(Value::Null, Value::from));
+        }
+        if let Some(span_name) = span_name {
+            record.insert("span".to_string(), span_name.into());
+        }
+        if !fields.values.is_empty() {
+            record.insert("fields".to_string(), Value::Object(fields.values));
+        }
+        let line = serde_json::to_string(&record).map_err(|_| fmt::Error)?;
+        writeln!(writer, "{}", line)
+    }
+}
+
+#[derive(Default)]
+struct JsonFields {
+    message: Option<String>,
+    values: Map<String, Value>,
+}
+
+impl JsonFields {
+    fn insert(&mut self, field: &Field, value: Value) {
+        if field.name() == "message" {
+            self.message = Some(match value {
+                Value::String(message) => message,
+                other => other.to_string(),
+            });
+        } else {
+            self.values.insert(field.name().to_string(), value);
+        }
+    }
+}
+
+impl Visit for JsonFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{:?}", value).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Captured {
+        type Writer = Captured;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn levels_combine_with_module_overrides_unless_rust_log_is_set() {
+        let config = LogConfig::parse(Some("JSON"), None, Some(" mongodb=warn, ,a::b=debug"), None);
+        assert_eq!(config.format, LogFormat::Json);
+        assert_eq!(config.directives, "info,mongodb=warn,a::b=debug");
+
+        let config = LogConfig::parse(None, Some("debug"), Some("mongodb=warn"), Some("trace"));
+        assert_eq!(config.format, LogFormat::Text);
+        assert_eq!(config.directives, "trace");
+    }
+
+    #[test]
+    fn json_records_carry_tenant_fields() {
+        let captured = Captured::default();
+        let config = LogConfig {
+            format: LogFormat::Json,
+            directives: "info,noisy=warn".to_string(),
+        };
+        let subscriber = tracing_subscriber::registry()
+            .with(config.filter())
+            .with(config.output_layer(captured.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let _span =
+                tracing::info_span!("ingestion.process", employee_id = %"little_bear").entered();
+            let _tenant = tenant_scope();
+            tracing::info!(attempt = 2, "claimed envelope");
+            note_user("user-1");
+            let _inner = tracing::info_span!("inner").entered();
+            tracing::warn!("sent reply");
+            tracing::info!(target: "noisy", "filtered out");
+        });
+        assert_eq!(scoped_user(), None);
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let records: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("json line"))
+            .collect();
+        assert_eq!(records.len(), 2, "{}", output);
+        assert_eq!(records[0]["message"], "claimed envelope");
+        assert_eq!(records[0]["level"], "INFO");
+        assert_eq!(records[0]["employee_id"], "little_bear");
+        assert_eq!(records[0]["user_id"], Value::Null);
+        assert_eq!(records[0]["fields"]["attempt"], 2);
+        assert_eq!(records[0]["span"], "ingestion.process");
+        assert_eq!(records[1]["user_id"], "user-1");
+        assert_eq!(records[1]["employee_id"], "little_bear");
+        assert_eq!(records[1]["span"], "inner");
+    }
+}
@@ -0,0 +1,211 @@
+use std::env;
+use std::sync::OnceLock;
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::trace::{
+    SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState, TracerProvider as _,
+};
+use opentelemetry::{global, Context, KeyValue, Value};
+use opentelemetry_otlp::{MetricExporter, SpanExporter};
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use tracing::info;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+const METER_NAME: &str = "dowhiz";
+
+pub(super) struct Providers {
+    tracer: SdkTracerProvider,
+    meter: SdkMeterProvider,
+}
+
+impl Providers {
+    pub(super) fn shutdown(self) {
+        if let Err(err) = self.tracer.shutdown() {
+            eprintln!("[telemetry] span exporter shutdown failed: {}", err);
+        }
+        if let Err(err) = self.meter.shutdown() {
+            eprintln!("[telemetry] metric exporter shutdown failed: {}", err);
+        }
+    }
+}
+
+pub(super) struct Instruments {
+    pub(super) due_tasks: Histogram<u64>,
+    pub(super) task_duration: Histogram<f64>,
+    pub(super) ingested: Counter<u64>,
+    pub(super) queue_latency: Histogram<f64>,
+    pub(super) shed: Counter<u64>,
+    pub(super) runner_duration: Histogram<f64>,
+    pub(super) send_duration: Histogram<f64>,
+    pub(super) external_command_duration: Histogram<f64>,
+    pub(super) probe_duration: Histogram<f64>,
+    pub(super) integrity_issues: Counter<u64>,
+}
+
+/// Export is on when an OTLP endpoint is configured and the SDK is not
+/// disabled through `OTEL_SDK_DISABLED`.
+pub(super) fn enabled_from_env() -> bool {
+    let disabled = env::var("OTEL_SDK_DISABLED")
+        .map(|value| value.trim().eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    !disabled
+        && [
+            "OTEL_EXPORTER_OTLP_ENDPOINT",
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+        ]
+        .iter()
+        .any(|key| env::var(key).is_ok_and(|value| !value.trim().is_empty()))
+}
+
+/// Build OTLP/HTTP span and metric exporters (endpoint, headers and export
+/// interval come from the standard `OTEL_*` variables) and install them
+/// alongside the log output.
+pub(super) fn init(
+    default_service_name: &str,
+    config: &super::LogConfig,
+) -> Result<Providers, BoxError> {
+    let service_name = env::var("OTEL_SERVICE_NAME")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| default_service_name.to_string());
+    let resource = Resource::builder()
+        .with_service_name(service_name.clone())
+        .build();
+
+    let span_exporter = SpanExporter::builder().with_http().build()?;
+    let tracer = SdkTracerProvider::builder()
+        .with_resource(resource.clone())
+        .with_batch_exporter(span_exporter)
+        .build();
+    let metric_exporter = MetricExporter::builder().with_http().build()?;
+    let meter = SdkMeterProvider::builder()
+        .with_resource(resource)
+        .with_periodic_exporter(metric_exporter)
+        .build();
+
+    tracing_subscriber::registry()
+        .with(config.filter())
+        .with(config.output_layer(super::ScrubbedStdout))
+        .with(tracing_opentelemetry::layer().with_tracer(tracer.tracer(METER_NAME)))
+        .try_init()?;
+    global::set_meter_provider(meter.clone());
+    info!("OTLP export enabled service_name={}", service_name);
+
+    Ok(Providers { tracer, meter })
+}
+
+pub(super) fn instruments() -> &'static Instruments {
+    static INSTRUMENTS: OnceLock<Instruments> = OnceLock::new();
+    INSTRUMENTS.get_or_init(|| {
+        let meter = global::meter(METER_NAME);
+        Instruments {
+            due_tasks: meter
+                .u64_histogram("dowhiz.scheduler.due_tasks")
+                .with_description("Due tasks returned by one scheduler poll")
+                .build(),
+            task_duration: meter
+                .f64_histogram("dowhiz.scheduler.task.duration")
+                .with_unit("s")
+                .with_description("Scheduled task execution time")
+                .build(),
+            ingested: meter
+                .u64_counter("dowhiz.ingestion.envelopes")
+                .with_description("Envelopes handled by the ingestion consumer")
+                .build(),
+            queue_latency: meter
+                .f64_histogram("dowhiz.ingestion.queue_latency")
+                .with_unit("s")
+                .with_description("Time from gateway receipt to claim by the ingestion consumer")
+                .build(),
+            shed: meter
+                .u64_counter("dowhiz.ingestion.shed")
+                .with_description("Stale background envelopes dropped without processing")
+                .build(),
+            runner_duration: meter
+                .f64_histogram("dowhiz.runner.duration")
+                .with_unit("s")
+                .with_description("Codex/Claude runner invocation time")
+                .build(),
+            send_duration: meter
+                .f64_histogram("dowhiz.outbound.send.duration")
+                .with_unit("s")
+                .with_description("Outbound channel adapter send time")
+                .build(),
+            external_command_duration: meter
+                .f64_histogram("dowhiz.external_command.duration")
+                .with_unit("s")
+                .with_description("az/gh/docker invocation time across retries")
+                .build(),
+            probe_duration: meter
+                .f64_histogram("dowhiz.health_probe.duration")
+                .with_unit("s")
+                .with_description("Health probe round trip through the ingestion pipeline")
+                .build(),
+            integrity_issues: meter
+                .u64_counter("dowhiz.store.integrity_issues")
+                .with_description("Inconsistencies found by the store integrity check")
+                .build(),
+        }
+    })
+}
+
+pub(super) fn attr(key: &'static str, value: impl Into<Value>) -> KeyValue {
+    KeyValue::new(key, value)
+}
+
+pub(super) fn outcome(ok: bool) -> KeyValue {
+    KeyValue::new("outcome", if ok { "success" } else { "failure" })
+}
+
+/// Parent `span` under a remote span context carrying `trace_id`. The parent
+/// span ID is derived from the trace ID so every process agrees on it.
+pub(super) fn link_trace(span: &tracing::Span, trace_id: &str) {
+    let Some(parent) = remote_parent(trace_id) else {
+        return;
+    };
+    // Fails only when no OpenTelemetry layer is installed.
+    let _ = span.set_parent(Context::new().with_remote_span_context(parent));
+}
+
+fn remote_parent(trace_id: &str) -> Option<SpanContext> {
+    let trace_id = TraceId::from_hex(trace_id).ok()?;
+    let bytes = trace_id.to_bytes();
+    let span_id = SpanId::from_bytes(bytes[..8].try_into().ok()?);
+    if trace_id == TraceId::INVALID || span_id == SpanId::INVALID {
+        return None;
+    }
+    Some(SpanContext::new(
+        trace_id,
+        span_id,
+        TraceFlags::SAMPLED,
+        true,
+        TraceState::default(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_parent_keeps_trace_id_and_derives_stable_span_id() {
+        let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+        let parent = remote_parent(trace_id).expect("valid trace id");
+        assert_eq!(parent.trace_id().to_string(), trace_id);
+        assert_eq!(parent.span_id().to_string(), "4bf92f3577b34da6");
+        assert!(parent.is_remote());
+        assert!(parent.is_sampled());
+    }
+
+    #[test]
+    fn remote_parent_rejects_invalid_ids() {
+        assert!(remote_parent("not-a-trace-id").is_none());
+        assert!(remote_parent("00000000000000000000000000000000").is_none());
+    }
+}
@@ -1,8 +1,45 @@
-use chrono::Utc;
+//! Per-thread state: epoch, inbound sequence, message-id history and the
+//! chat messages the employee sent in the thread.
+//!
+//! State lives in a per-user SQLite database, `users/<id>/state/threads.db`
+//! (see [`thread_db_path`]), keyed by workspace directory name, so it can be
+//! updated atomically and listed across threads. A process opens each
+//! database once and keeps the connection; opening applies the schema
+//! migrations in [`crate::migrations::THREAD_DB_MIGRATIONS`], one of which
+//! imports the `thread_state.json` of workspaces from before the database.
+//! Every change runs in an immediate transaction, which takes the database's
+//! write lock before it reads, so concurrent bumps from any number of
+//! consumers or processes are serialized and epochs are strictly monotonic.
+//! The thread's state is also written to `thread_state.json` in the
+//! workspace, for the agent to read, while the lock is held; that file is
+//! only a snapshot and never goes back to an older epoch.
+//!
+//! Functions take the snapshot path (see [`default_thread_state_path`]),
+//! which names the workspace and, through it, the database.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::channel::Channel;
+use crate::migrations;
+
+/// Most sent messages a thread remembers; older ones are forgotten first.
+const MAX_SENT_MESSAGES: usize = 50;
+const THREAD_STATE_FILE: &str = "thread_state.json";
+const THREADS_DB_FILE: &str = "threads.db";
+/// How long a write waits for another process holding the database.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The connection of each database this process opened, see [`connection`].
+static CONNECTIONS: OnceLock<Mutex<HashMap<PathBuf, Arc<Mutex<Connection>>>>> = OnceLock::new();
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThreadState {
@@ -11,6 +48,39 @@ pub struct ThreadState {
     pub last_email_seq: u64,
     pub last_message_id: Option<String>,
     pub updated_at: String,
+    /// Chat messages sent in this thread, oldest first, so later runs can
+    /// refer to, edit or delete them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sent_messages: Vec<SentMessage>,
+}
+
+/// A message the employee sent, by its provider ID (Slack `ts`, Discord
+/// message ID).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SentMessage {
+    pub channel: Channel,
+    /// Slack or Discord channel the message was posted in.
+    pub channel_id: String,
+    pub message_id: String,
+    pub sent_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<String>,
+}
+
+/// One thread of a user, as listed by [`list_threads`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThreadRecord {
+    /// Workspace directory name.
+    pub workspace: String,
+    pub thread_id: String,
+    pub epoch: u64,
+    pub last_email_seq: u64,
+    pub last_message_id: Option<String>,
+    pub updated_at: String,
+    /// Inbound messages in the thread's message-id history.
+    pub messages: u64,
 }
 
 impl ThreadState {
@@ -21,6 +91,7 @@ impl ThreadState {
             last_email_seq: 1,
             last_message_id: message_id,
             updated_at: Utc::now().to_rfc3339(),
+            sent_messages: Vec::new(),
         }
     }
 
@@ -32,19 +103,44 @@ impl ThreadState {
     }
 }
 
-pub fn load_thread_state(path: &Path) -> Option<ThreadState> {
-    let raw = fs::read_to_string(path).ok()?;
-    serde_json::from_str(&raw).ok()
+/// The database holding the state of every thread under `workspaces_root`,
+/// which must be a user's `users/<id>/workspaces` directory:
+/// `users/<id>/state/threads.db`.
+pub fn thread_db_path(workspaces_root: &Path) -> io::Result<PathBuf> {
+    match (workspaces_root.file_name(), workspaces_root.parent()) {
+        (Some(name), Some(user_root))
+            if name == "workspaces" && user_root.file_name().is_some() =>
+        {
+            Ok(user_root.join("state").join(THREADS_DB_FILE))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} is not a users/<id>/workspaces directory",
+                workspaces_root.display()
+            ),
+        )),
+    }
+}
+
+/// The thread's state, or `None` when the thread has none yet.
+pub fn load_thread_state(path: &Path) -> io::Result<Option<ThreadState>> {
+    let (conn, workspace) = open(path)?;
+    let mut conn = lock(&conn);
+    // One read transaction, so the row and its sent messages agree.
+    let tx = conn.transaction().map_err(db_error)?;
+    load_row(&tx, &workspace).map_err(db_error)
 }
 
 pub fn write_thread_state(path: &Path, state: &ThreadState) -> Result<(), io::Error> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    let serialized = serde_json::to_string_pretty(state)
-        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
-    fs::write(path, serialized)?;
-    Ok(())
+    let (conn, workspace) = open(path)?;
+    let mut conn = lock(&conn);
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(db_error)?;
+    store_row(&tx, &workspace, state).map_err(db_error)?;
+    write_snapshot(path, state)?;
+    tx.commit().map_err(db_error)
 }
 
 pub fn bump_thread_state(
@@ -52,34 +148,199 @@ pub fn bump_thread_state(
     thread_id: &str,
     message_id: Option<String>,
 ) -> Result<ThreadState, io::Error> {
-    let mut state = load_thread_state(path)
-        .unwrap_or_else(|| ThreadState::new(thread_id.to_string(), message_id.clone()));
-    if state.thread_id != thread_id {
-        state = ThreadState::new(thread_id.to_string(), message_id.clone());
-    } else if state.epoch == 0 || state.last_email_seq == 0 {
-        state.epoch = 1;
-        state.last_email_seq = 1;
-        state.last_message_id = message_id.clone();
-        state.updated_at = Utc::now().to_rfc3339();
-    } else {
-        state.bump(message_id.clone());
-    }
-    write_thread_state(path, &state)?;
+    let (conn, workspace) = open(path)?;
+    let mut conn = lock(&conn);
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(db_error)?;
+    let state = match load_row(&tx, &workspace).map_err(db_error)? {
+        Some(mut state) if state.thread_id == thread_id => {
+            if state.epoch == 0 || state.last_email_seq == 0 {
+                state.epoch = 1;
+                state.last_email_seq = 1;
+                state.last_message_id = message_id.clone();
+                state.updated_at = Utc::now().to_rfc3339();
+            } else {
+                state.bump(message_id.clone());
+            }
+            state
+        }
+        _ => {
+            tx.execute(
+                "DELETE FROM thread_messages WHERE workspace = ?1",
+                params![workspace],
+            )
+            .map_err(db_error)?;
+            ThreadState::new(thread_id.to_string(), message_id.clone())
+        }
+    };
+    store_row(&tx, &workspace, &state).map_err(db_error)?;
+    tx.execute(
+        "INSERT OR REPLACE INTO thread_messages (workspace, seq, message_id, received_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            workspace,
+            state.last_email_seq as i64,
+            message_id,
+            state.updated_at
+        ],
+    )
+    .map_err(db_error)?;
+    write_snapshot(path, &state)?;
+    tx.commit().map_err(db_error)?;
     Ok(state)
 }
 
-pub fn current_thread_epoch(path: &Path) -> Option<u64> {
-    load_thread_state(path).map(|state| state.epoch)
+/// Append sent `message_ids` to the thread state at `path`. Does nothing
+/// when the thread has no state yet.
+pub fn record_sent_messages(
+    path: &Path,
+    channel: Channel,
+    channel_id: &str,
+    message_ids: &[String],
+) -> Result<(), io::Error> {
+    update_thread_state(path, |state| {
+        let sent_at = Utc::now().to_rfc3339();
+        for message_id in message_ids.iter().filter(|id| !id.is_empty()) {
+            if state
+                .sent_messages
+                .iter()
+                .any(|sent| sent.channel == channel && sent.message_id == *message_id)
+            {
+                continue;
+            }
+            state.sent_messages.push(SentMessage {
+                channel,
+                channel_id: channel_id.to_string(),
+                message_id: message_id.clone(),
+                sent_at: sent_at.clone(),
+                edited_at: None,
+                deleted_at: None,
+            });
+        }
+        let excess = state.sent_messages.len().saturating_sub(MAX_SENT_MESSAGES);
+        state.sent_messages.drain(..excess);
+    })
+}
+
+/// The sent message with `message_id`, unless it was deleted.
+pub fn find_sent_message(path: &Path, message_id: &str) -> io::Result<Option<SentMessage>> {
+    Ok(load_thread_state(path)?.and_then(|state| {
+        state
+            .sent_messages
+            .into_iter()
+            .find(|sent| sent.message_id == message_id.trim() && sent.deleted_at.is_none())
+    }))
+}
+
+/// Mark the sent message with `message_id` as edited, or as deleted.
+pub fn mark_sent_message(path: &Path, message_id: &str, deleted: bool) -> Result<(), io::Error> {
+    update_thread_state(path, |state| {
+        let now = Utc::now().to_rfc3339();
+        for sent in state
+            .sent_messages
+            .iter_mut()
+            .filter(|sent| sent.message_id == message_id.trim())
+        {
+            if deleted {
+                sent.deleted_at = Some(now.clone());
+            } else {
+                sent.edited_at = Some(now.clone());
+            }
+        }
+    })
+}
+
+/// The thread's epoch, or `None` when the thread has no state yet.
+pub fn current_thread_epoch(path: &Path) -> io::Result<Option<u64>> {
+    Ok(load_thread_state(path)?.map(|state| state.epoch))
+}
+
+/// Threads under `workspaces_root`, most recently updated first. With
+/// `active_since`, only threads updated at or after it.
+pub fn list_threads(
+    workspaces_root: &Path,
+    active_since: Option<DateTime<Utc>>,
+    limit: usize,
+) -> Result<Vec<ThreadRecord>, io::Error> {
+    let db_path = thread_db_path(workspaces_root)?;
+    if !db_path.exists() {
+        return Ok(Vec::new());
+    }
+    let conn = connection(&db_path, workspaces_root)?;
+    let conn = lock(&conn);
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.workspace, t.thread_id, t.epoch, t.last_email_seq, t.last_message_id,
+                    t.updated_at,
+                    (SELECT COUNT(*) FROM thread_messages m WHERE m.workspace = t.workspace)
+             FROM threads t",
+        )
+        .map_err(db_error)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(ThreadRecord {
+                workspace: row.get(0)?,
+                thread_id: row.get(1)?,
+                epoch: row.get::<_, i64>(2)? as u64,
+                last_email_seq: row.get::<_, i64>(3)? as u64,
+                last_message_id: row.get(4)?,
+                updated_at: row.get(5)?,
+                messages: row.get::<_, i64>(6)? as u64,
+            })
+        })
+        .map_err(db_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(db_error)?;
+
+    let mut threads: Vec<(Option<DateTime<Utc>>, ThreadRecord)> = rows
+        .into_iter()
+        .map(|record| {
+            let updated_at = DateTime::parse_from_rfc3339(&record.updated_at)
+                .ok()
+                .map(|value| value.with_timezone(&Utc));
+            (updated_at, record)
+        })
+        .filter(|(updated_at, _)| match active_since {
+            Some(since) => updated_at.is_some_and(|updated_at| updated_at >= since),
+            None => true,
+        })
+        .collect();
+    threads.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then_with(|| a.1.workspace.cmp(&b.1.workspace))
+    });
+    Ok(threads
+        .into_iter()
+        .take(limit)
+        .map(|(_, record)| record)
+        .collect())
+}
+
+/// SQLite's `quick_check` of the thread database of `workspaces_root`:
+/// `None` when it passes or there is no database yet, else the first
+/// problem found.
+pub fn check_thread_db(workspaces_root: &Path) -> io::Result<Option<String>> {
+    let db_path = thread_db_path(workspaces_root)?;
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    let conn = connection(&db_path, workspaces_root)?;
+    let conn = lock(&conn);
+    let result: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(db_error)?;
+    Ok((result != "ok").then_some(result))
 }
 
 pub fn default_thread_state_path(workspace_dir: &Path) -> PathBuf {
-    workspace_dir.join("thread_state.json")
+    workspace_dir.join(THREAD_STATE_FILE)
 }
 
 pub fn find_thread_state_path(start: &Path) -> Option<PathBuf> {
     let mut current = Some(start);
     while let Some(path) = current {
-        let candidate = path.join("thread_state.json");
+        let candidate = path.join(THREAD_STATE_FILE);
         if candidate.exists() {
             return Some(candidate);
         }
@@ -87,3 +348,345 @@ pub fn find_thread_state_path(start: &Path) -> Option<PathBuf> {
     }
     None
 }
+
+/// Applies `change` to the thread's state in one transaction. Does nothing
+/// when the thread has no state yet.
+fn update_thread_state(path: &Path, change: impl FnOnce(&mut ThreadState)) -> io::Result<()> {
+    let (conn, workspace) = open(path)?;
+    let mut conn = lock(&conn);
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(db_error)?;
+    let Some(mut state) = load_row(&tx, &workspace).map_err(db_error)? else {
+        return Ok(());
+    };
+    change(&mut state);
+    store_row(&tx, &workspace, &state).map_err(db_error)?;
+    write_snapshot(path, &state)?;
+    tx.commit().map_err(db_error)
+}
+
+/// Imports the `thread_state.json` of every workspace under
+/// `workspaces_root` that has no row yet. Run once, by the thread database
+/// migrations; unreadable files are left to workspace recovery.
+pub(crate) fn import_thread_state_files(
+    conn: &Connection,
+    workspaces_root: &Path,
+) -> rusqlite::Result<()> {
+    let Ok(entries) = fs::read_dir(workspaces_root) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let workspace = entry.file_name().to_string_lossy().into_owned();
+        let Some(state) = read_snapshot(&default_thread_state_path(&entry.path())) else {
+            continue;
+        };
+        if load_row(conn, &workspace)?.is_none() {
+            store_row(conn, &workspace, &state)?;
+        }
+    }
+    Ok(())
+}
+
+/// The database connection for the workspace of `path`, and the workspace
+/// name that keys its rows.
+fn open(path: &Path) -> io::Result<(Arc<Mutex<Connection>>, String)> {
+    let workspace_dir = path
+        .parent()
+        .ok_or_else(|| io::Error::other("thread state path has no workspace"))?;
+    let workspace = workspace_dir
+        .file_name()
+        .ok_or_else(|| io::Error::other("thread state path has no workspace"))?
+        .to_string_lossy()
+        .into_owned();
+    let workspaces_root = workspace_dir
+        .parent()
+        .ok_or_else(|| io::Error::other("thread state path has no workspaces directory"))?;
+    let conn = connection(&thread_db_path(workspaces_root)?, workspaces_root)?;
+    Ok((conn, workspace))
+}
+
+/// The process's connection to `db_path`, opened and migrated on first use.
+/// A database removed since, e.g. with its user, is opened afresh instead of
+/// being written through a stale handle.
+fn connection(db_path: &Path, workspaces_root: &Path) -> io::Result<Arc<Mutex<Connection>>> {
+    let mut connections = CONNECTIONS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(conn) = connections.get(db_path) {
+        if db_path.exists() {
+            return Ok(conn.clone());
+        }
+    }
+    let conn = Arc::new(Mutex::new(connect(db_path, workspaces_root)?));
+    connections.insert(db_path.to_path_buf(), conn.clone());
+    Ok(conn)
+}
+
+/// A transaction that failed part way is rolled back when dropped, so a
+/// connection whose holder panicked is still usable.
+fn lock(conn: &Mutex<Connection>) -> MutexGuard<'_, Connection> {
+    conn.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn connect(db_path: &Path, workspaces_root: &Path) -> io::Result<Connection> {
+    if let Some(parent) = db_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut conn = Connection::open(db_path).map_err(db_error)?;
+    conn.busy_timeout(BUSY_TIMEOUT).map_err(db_error)?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+        .map_err(db_error)?;
+    let status = migrations::thread_db_schema_status(&conn).map_err(io::Error::other)?;
+    if status.current() > status.latest {
+        warn!(
+            "thread database {} is at schema version {} but this build knows up to {}",
+            db_path.display(),
+            status.current(),
+            status.latest
+        );
+    } else if status.pending() > 0 {
+        migrations::migrate_thread_db(&mut conn, workspaces_root, status.latest)
+            .map_err(io::Error::other)?;
+    }
+    Ok(conn)
+}
+
+fn load_row(conn: &Connection, workspace: &str) -> rusqlite::Result<Option<ThreadState>> {
+    let Some(mut state) = conn
+        .query_row(
+            "SELECT thread_id, epoch, last_email_seq, last_message_id, updated_at
+             FROM threads WHERE workspace = ?1",
+            params![workspace],
+            |row| {
+                Ok(ThreadState {
+                    thread_id: row.get(0)?,
+                    epoch: row.get::<_, i64>(1)? as u64,
+                    last_email_seq: row.get::<_, i64>(2)? as u64,
+                    last_message_id: row.get(3)?,
+                    updated_at: row.get(4)?,
+                    sent_messages: Vec::new(),
+                })
+            },
+        )
+        .optional()?
+    else {
+        return Ok(None);
+    };
+    let mut stmt = conn.prepare(
+        "SELECT channel, channel_id, message_id, sent_at, edited_at, deleted_at
+         FROM sent_messages WHERE workspace = ?1 ORDER BY position",
+    )?;
+    state.sent_messages = stmt
+        .query_map(params![workspace], |row| {
+            let channel: String = row.get(0)?;
+            Ok(SentMessage {
+                channel: channel.parse().unwrap_or_default(),
+                channel_id: row.get(1)?,
+                message_id: row.get(2)?,
+                sent_at: row.get(3)?,
+                edited_at: row.get(4)?,
+                deleted_at: row.get(5)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(Some(state))
+}
+
+fn store_row(conn: &Connection, workspace: &str, state: &ThreadState) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO threads (workspace, thread_id, epoch, last_email_seq, last_message_id, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (workspace) DO UPDATE SET
+             thread_id = excluded.thread_id,
+             epoch = excluded.epoch,
+             last_email_seq = excluded.last_email_seq,
+             last_message_id = excluded.last_message_id,
+             updated_at = excluded.updated_at",
+        params![
+            workspace,
+            state.thread_id,
+            state.epoch as i64,
+            state.last_email_seq as i64,
+            state.last_message_id,
+            state.updated_at
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM sent_messages WHERE workspace = ?1",
+        params![workspace],
+    )?;
+    let mut insert = conn.prepare(
+        "INSERT INTO sent_messages
+             (workspace, position, channel, channel_id, message_id, sent_at, edited_at, deleted_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    for (position, sent) in state.sent_messages.iter().enumerate() {
+        insert.execute(params![
+            workspace,
+            position as i64,
+            sent.channel.to_string(),
+            sent.channel_id,
+            sent.message_id,
+            sent.sent_at,
+            sent.edited_at,
+            sent.deleted_at
+        ])?;
+    }
+    Ok(())
+}
+
+fn read_snapshot(path: &Path) -> Option<ThreadState> {
+    let raw = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+/// Replaces the snapshot through a rename, so a crash never leaves it half
+/// written. Callers hold the database's write lock, which also keeps two
+/// writers off the temporary file.
+fn write_snapshot(path: &Path, state: &ThreadState) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let serialized = serde_json::to_string_pretty(state).map_err(io::Error::other)?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serialized)?;
+    fs::rename(tmp, path)
+}
+
+fn db_error(err: rusqlite::Error) -> io::Error {
+    io::Error::other(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sent_messages_are_recorded_once_and_marked() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = default_thread_state_path(&dir.path().join("users/u1/workspaces/thread_1"));
+        let ids = vec!["1700000000.000100".to_string()];
+        record_sent_messages(&path, Channel::Slack, "C1", &ids).unwrap();
+        assert!(!path.exists(), "no state is created for an unknown thread");
+
+        bump_thread_state(&path, "thread-1", None).unwrap();
+        record_sent_messages(&path, Channel::Slack, "C1", &ids).unwrap();
+        record_sent_messages(&path, Channel::Slack, "C1", &ids).unwrap();
+        bump_thread_state(&path, "thread-1", Some("next".to_string())).unwrap();
+        let state = load_thread_state(&path).unwrap().expect("state");
+        assert_eq!(state.sent_messages.len(), 1);
+        assert_eq!(state.sent_messages[0].channel_id, "C1");
+
+        mark_sent_message(&path, &ids[0], false).unwrap();
+        let sent = find_sent_message(&path, &ids[0])
+            .unwrap()
+            .expect("still there");
+        assert!(sent.edited_at.is_some());
+        mark_sent_message(&path, &ids[0], true).unwrap();
+        assert_eq!(find_sent_message(&path, &ids[0]).unwrap(), None);
+    }
+
+    #[test]
+    fn state_lives_in_the_user_database_and_survives_a_lost_snapshot() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let workspaces_root = dir.path().join("users").join("u1").join("workspaces");
+        let path = default_thread_state_path(&workspaces_root.join("thread_a"));
+
+        bump_thread_state(&path, "thread-a", Some("m1".to_string())).unwrap();
+        fs::write(&path, "{").expect("truncate snapshot");
+        let state = bump_thread_state(&path, "thread-a", Some("m2".to_string())).unwrap();
+
+        assert_eq!(state.epoch, 2);
+        assert!(dir
+            .path()
+            .join("users/u1/state")
+            .join(THREADS_DB_FILE)
+            .exists());
+        assert_eq!(read_snapshot(&path).expect("snapshot rewritten").epoch, 2);
+    }
+
+    #[test]
+    fn concurrent_bumps_get_distinct_increasing_epochs() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let workspaces_root = dir.path().join("users").join("u1").join("workspaces");
+        let path = default_thread_state_path(&workspaces_root.join("thread_busy"));
+        bump_thread_state(&path, "thread-busy", None).unwrap();
+
+        let workers: Vec<_> = (0..8)
+            .map(|worker| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    (0..10)
+                        .map(|n| {
+                            let message_id = Some(format!("m{worker}-{n}"));
+                            bump_thread_state(&path, "thread-busy", message_id)
+                                .expect("bump")
+                                .epoch
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut epochs = Vec::new();
+        for worker in workers {
+            let seen = worker.join().expect("worker");
+            assert!(seen.windows(2).all(|pair| pair[0] < pair[1]), "{seen:?}");
+            epochs.extend(seen);
+        }
+
+        epochs.sort_unstable();
+        assert_eq!(epochs, (2..=81).collect::<Vec<u64>>());
+        assert_eq!(current_thread_epoch(&path).unwrap(), Some(81));
+        assert_eq!(read_snapshot(&path).expect("snapshot").epoch, 81);
+        let threads = list_threads(&workspaces_root, None, 10).unwrap();
+        assert_eq!(threads[0].messages, 81);
+    }
+
+    #[test]
+    fn legacy_state_file_is_imported_and_threads_are_listed() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let workspaces_root = dir.path().join("users").join("u1").join("workspaces");
+        let legacy = default_thread_state_path(&workspaces_root.join("thread_old"));
+        let mut old = ThreadState::new("thread-old".to_string(), None);
+        old.epoch = 7;
+        old.last_email_seq = 7;
+        old.updated_at = "2026-01-01T00:00:00+00:00".to_string();
+        fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        fs::write(&legacy, serde_json::to_string(&old).unwrap()).unwrap();
+
+        assert_eq!(current_thread_epoch(&legacy).unwrap(), Some(7));
+        let recent = default_thread_state_path(&workspaces_root.join("thread_new"));
+        bump_thread_state(&recent, "thread-new", Some("m1".to_string())).unwrap();
+        bump_thread_state(&recent, "thread-new", Some("m2".to_string())).unwrap();
+
+        let all = list_threads(&workspaces_root, None, 10).unwrap();
+        let names: Vec<_> = all.iter().map(|thread| thread.workspace.as_str()).collect();
+        assert_eq!(names, ["thread_new", "thread_old"]);
+        assert_eq!(all[0].messages, 2);
+        assert_eq!(all[0].last_message_id.as_deref(), Some("m2"));
+
+        let since = DateTime::parse_from_rfc3339("2026-02-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let active = list_threads(&workspaces_root, Some(since), 10).unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].thread_id, "thread-new");
+    }
+
+    #[test]
+    fn paths_outside_a_user_workspaces_directory_are_rejected() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let stray = default_thread_state_path(&dir.path().join("elsewhere").join("thread_1"));
+
+        let err = bump_thread_state(&stray, "thread-1", None).expect_err("rejected");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(load_thread_state(&stray).is_err());
+        assert!(!dir.path().join(THREADS_DB_FILE).exists());
+        assert!(!dir.path().join("state").exists());
+
+        let unknown = default_thread_state_path(&dir.path().join("users/u2/workspaces/thread_new"));
+        assert!(load_thread_state(&unknown).unwrap().is_none());
+    }
+}
@@ -0,0 +1,4 @@
+//! Tools the service runs for runners, which may have no network access of
+//! their own.
+
+pub mod search;
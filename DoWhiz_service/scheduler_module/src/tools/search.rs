@@ -0,0 +1,533 @@
+//! Web search for runners.
+//!
+//! Searches go through a [`SearchProvider`] (Brave Search or Tavily), picked
+//! by `WEB_SEARCH_PROVIDER` or by whichever API key is set. The service serves
+//! them at `POST /tools/search` and records where in the workspace's
+//! `.dowhiz_tools.json`, so the `web-search` CLI works from runners without
+//! internet access; it saves each search as markdown under
+//! `references/web_search/`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Workspace file telling the `web-search` CLI where the service's search
+/// endpoint is.
+pub const TOOLS_CONFIG_FILENAME: &str = ".dowhiz_tools.json";
+/// Directory under the references dir that searches are saved to.
+pub const SEARCH_RESULTS_DIR: &str = "web_search";
+const MAX_RESULTS: u32 = 20;
+const DEFAULT_BRAVE_API_URL: &str = "https://api.search.brave.com/res/v1/web/search";
+const DEFAULT_TAVILY_API_URL: &str = "https://api.tavily.com/search";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SearchError {
+    #[error("no web search provider configured (set BRAVE_SEARCH_API_KEY or TAVILY_API_KEY)")]
+    NotConfigured,
+    #[error("invalid search request: {0}")]
+    InvalidRequest(String),
+    #[error("{provider} search failed: {message}")]
+    Provider {
+        provider: &'static str,
+        message: String,
+    },
+}
+
+/// How recent results must be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Freshness {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Freshness {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "day" | "d" => Some(Freshness::Day),
+            "week" | "w" => Some(Freshness::Week),
+            "month" | "m" => Some(Freshness::Month),
+            "year" | "y" => Some(Freshness::Year),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Freshness::Day => "past day",
+            Freshness::Week => "past week",
+            Freshness::Month => "past month",
+            Freshness::Year => "past year",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchRequest {
+    pub query: String,
+    #[serde(default = "default_count")]
+    pub count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freshness: Option<Freshness>,
+}
+
+fn default_count() -> u32 {
+    10
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    #[serde(default)]
+    pub snippet: String,
+    /// Publication date or age as the provider reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub provider: String,
+    pub results: Vec<SearchResult>,
+}
+
+/// A web search backend.
+pub trait SearchProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn search(&self, request: &SearchRequest) -> Result<Vec<SearchResult>, SearchError>;
+}
+
+fn env_value(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn read_json(
+    provider: &'static str,
+    response: reqwest::blocking::Response,
+) -> Result<Value, SearchError> {
+    let error = |message: String| SearchError::Provider { provider, message };
+    let status = response.status();
+    let text = response.text().unwrap_or_default();
+    if !status.is_success() {
+        return Err(error(format!("HTTP {}: {}", status, text)));
+    }
+    serde_json::from_str(&text).map_err(|err| error(err.to_string()))
+}
+
+/// Brave Search web results (`BRAVE_SEARCH_API_KEY`).
+#[derive(Debug, Clone)]
+pub struct BraveSearch {
+    api_key: String,
+    api_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl BraveSearch {
+    /// `BRAVE_SEARCH_API_URL` overrides the endpoint.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            api_url: env_value("BRAVE_SEARCH_API_URL")
+                .unwrap_or_else(|| DEFAULT_BRAVE_API_URL.to_string()),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+}
+
+impl SearchProvider for BraveSearch {
+    fn name(&self) -> &'static str {
+        "brave"
+    }
+
+    fn search(&self, request: &SearchRequest) -> Result<Vec<SearchResult>, SearchError> {
+        let mut params = vec![
+            ("q", request.query.clone()),
+            ("count", request.count.to_string()),
+        ];
+        if let Some(freshness) = request.freshness {
+            let code = match freshness {
+                Freshness::Day => "pd",
+                Freshness::Week => "pw",
+                Freshness::Month => "pm",
+                Freshness::Year => "py",
+            };
+            params.push(("freshness", code.to_string()));
+        }
+        let response = self
+            .client
+            .get(&self.api_url)
+            .header("X-Subscription-Token", &self.api_key)
+            .header("Accept", "application/json")
+            .query(&params)
+            .send()
+            .map_err(|err| SearchError::Provider {
+                provider: self.name(),
+                message: err.to_string(),
+            })?;
+        let body = read_json(self.name(), response)?;
+        Ok(body["web"]["results"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| {
+                Some(SearchResult {
+                    title: item["title"].as_str()?.to_string(),
+                    url: item["url"].as_str()?.to_string(),
+                    snippet: item["description"].as_str().unwrap_or_default().to_string(),
+                    published: item["page_age"]
+                        .as_str()
+                        .or_else(|| item["age"].as_str())
+                        .map(str::to_string),
+                })
+            })
+            .collect())
+    }
+}
+
+/// Tavily search (`TAVILY_API_KEY`).
+#[derive(Debug, Clone)]
+pub struct TavilySearch {
+    api_key: String,
+    api_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl TavilySearch {
+    /// `TAVILY_API_URL` overrides the endpoint.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            api_url: env_value("TAVILY_API_URL")
+                .unwrap_or_else(|| DEFAULT_TAVILY_API_URL.to_string()),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+}
+
+impl SearchProvider for TavilySearch {
+    fn name(&self) -> &'static str {
+        "tavily"
+    }
+
+    fn search(&self, request: &SearchRequest) -> Result<Vec<SearchResult>, SearchError> {
+        let mut body = json!({
+            "query": request.query,
+            "max_results": request.count,
+        });
+        if let Some(freshness) = request.freshness {
+            body["time_range"] = json!(freshness);
+        }
+        let response = self
+            .client
+            .post(&self.api_url)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()
+            .map_err(|err| SearchError::Provider {
+                provider: self.name(),
+                message: err.to_string(),
+            })?;
+        let body = read_json(self.name(), response)?;
+        Ok(body["results"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| {
+                Some(SearchResult {
+                    title: item["title"].as_str()?.to_string(),
+                    url: item["url"].as_str()?.to_string(),
+                    snippet: item["content"].as_str().unwrap_or_default().to_string(),
+                    published: item["published_date"].as_str().map(str::to_string),
+                })
+            })
+            .collect())
+    }
+}
+
+/// The provider named by `WEB_SEARCH_PROVIDER` (`brave` or `tavily`), or else
+/// the first one with an API key.
+pub fn provider_from_env() -> Result<Box<dyn SearchProvider>, SearchError> {
+    let brave = || env_value("BRAVE_SEARCH_API_KEY").map(BraveSearch::new);
+    let tavily = || env_value("TAVILY_API_KEY").map(TavilySearch::new);
+    let provider: Option<Box<dyn SearchProvider>> = match env_value("WEB_SEARCH_PROVIDER")
+        .map(|value| value.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("brave") => brave().map(|p| Box::new(p) as Box<dyn SearchProvider>),
+        Some("tavily") => tavily().map(|p| Box::new(p) as Box<dyn SearchProvider>),
+        _ => brave()
+            .map(|p| Box::new(p) as Box<dyn SearchProvider>)
+            .or_else(|| tavily().map(|p| Box::new(p) as Box<dyn SearchProvider>)),
+    };
+    provider.ok_or(SearchError::NotConfigured)
+}
+
+/// Run `request` on `provider`, with the result count capped at 20.
+pub fn search_with(
+    provider: &dyn SearchProvider,
+    request: &SearchRequest,
+) -> Result<SearchResponse, SearchError> {
+    let query = request.query.trim();
+    if query.is_empty() {
+        return Err(SearchError::InvalidRequest("query is empty".to_string()));
+    }
+    let request = SearchRequest {
+        query: query.to_string(),
+        count: request.count.clamp(1, MAX_RESULTS),
+        freshness: request.freshness,
+    };
+    let results = provider.search(&request)?;
+    info!(
+        "web search via {} returned {} results",
+        provider.name(),
+        results.len()
+    );
+    Ok(SearchResponse {
+        provider: provider.name().to_string(),
+        results,
+    })
+}
+
+/// Run `request` on the configured provider.
+pub fn search(request: &SearchRequest) -> Result<SearchResponse, SearchError> {
+    search_with(provider_from_env()?.as_ref(), request)
+}
+
+/// Save a search as markdown in `references_dir/web_search/` and return the
+/// file's path.
+pub fn write_results(
+    references_dir: &Path,
+    request: &SearchRequest,
+    response: &SearchResponse,
+    searched_at: DateTime<Utc>,
+) -> std::io::Result<PathBuf> {
+    let dir = references_dir.join(SEARCH_RESULTS_DIR);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!(
+        "{}_{}.md",
+        searched_at.format("%Y%m%dT%H%M%S"),
+        slug(&request.query)
+    ));
+
+    let mut content = format!("# Web search: {}\n\n", request.query.trim());
+    content.push_str(&format!(
+        "Searched {} via {}",
+        searched_at.to_rfc3339(),
+        response.provider
+    ));
+    if let Some(freshness) = request.freshness {
+        content.push_str(&format!(", {} only", freshness.label()));
+    }
+    content.push_str(".\n");
+    if response.results.is_empty() {
+        content.push_str("\nNo results.\n");
+    }
+    for (index, result) in response.results.iter().enumerate() {
+        content.push_str(&format!(
+            "\n## {}. [{}]({})\n",
+            index + 1,
+            result.title,
+            result.url
+        ));
+        if let Some(published) = &result.published {
+            content.push_str(&format!("Published: {}\n", published));
+        }
+        if !result.snippet.trim().is_empty() {
+            content.push_str(&format!("\n{}\n", result.snippet.trim()));
+        }
+    }
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+fn slug(query: &str) -> String {
+    let mut slug = String::new();
+    for ch in query.trim().chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.ends_with('-') && !slug.is_empty() {
+            slug.push('-');
+        }
+        if slug.len() >= 48 {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "search".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Where runners reach the service's search endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolsConfig {
+    pub search_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl ToolsConfig {
+    /// `DOWHIZ_TOOLS_URL` (the service's base URL as runners see it) and
+    /// `TOOLS_API_TOKEN`.
+    pub fn from_env() -> Option<Self> {
+        let base = env_value("DOWHIZ_TOOLS_URL")?;
+        Some(Self {
+            search_url: format!("{}/tools/search", base.trim_end_matches('/')),
+            token: env_value("TOOLS_API_TOKEN"),
+        })
+    }
+
+    pub fn load(workspace_dir: &Path) -> Option<Self> {
+        let raw = fs::read_to_string(workspace_dir.join(TOOLS_CONFIG_FILENAME)).ok()?;
+        serde_json::from_str(&raw).ok()
+    }
+
+    /// Search through the service endpoint.
+    pub fn search(&self, request: &SearchRequest) -> Result<SearchResponse, SearchError> {
+        let error = |message: String| SearchError::Provider {
+            provider: "dowhiz",
+            message,
+        };
+        let mut http = reqwest::blocking::Client::new()
+            .post(&self.search_url)
+            .json(request);
+        if let Some(token) = &self.token {
+            http = http.bearer_auth(token);
+        }
+        let response = http.send().map_err(|err| error(err.to_string()))?;
+        let body = read_json("dowhiz", response)?;
+        serde_json::from_value(body).map_err(|err| error(err.to_string()))
+    }
+}
+
+/// Write `.dowhiz_tools.json` into the workspace when the service serves
+/// searches to runners; returns whether it did.
+pub fn write_tools_config(workspace_dir: &Path) -> std::io::Result<bool> {
+    let Some(config) = ToolsConfig::from_env() else {
+        return Ok(false);
+    };
+    let payload = serde_json::to_string_pretty(&config).map_err(std::io::Error::other)?;
+    fs::write(workspace_dir.join(TOOLS_CONFIG_FILENAME), payload)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    struct FixedProvider(Vec<SearchResult>);
+
+    impl SearchProvider for FixedProvider {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn search(&self, request: &SearchRequest) -> Result<Vec<SearchResult>, SearchError> {
+            assert_eq!(request.count, MAX_RESULTS);
+            Ok(self.0.clone())
+        }
+    }
+
+    #[test]
+    fn search_results_are_saved_as_markdown_references() {
+        let provider = FixedProvider(vec![SearchResult {
+            title: "Rust 1.90 released".to_string(),
+            url: "https://blog.rust-lang.org/2026/10/16/rust-1.90".to_string(),
+            snippet: "The Rust team is happy to announce 1.90.".to_string(),
+            published: Some("2026-10-16".to_string()),
+        }]);
+        let request = SearchRequest {
+            query: "  What changed in Rust this week?  ".to_string(),
+            count: 50,
+            freshness: Some(Freshness::Week),
+        };
+        let response = search_with(&provider, &request).expect("search");
+        assert_eq!(response.provider, "fixed");
+
+        let temp = tempfile::TempDir::new().expect("tempdir");
+        let searched_at = Utc.with_ymd_and_hms(2026, 10, 17, 9, 30, 0).unwrap();
+        let path = write_results(temp.path(), &request, &response, searched_at).expect("write");
+
+        assert_eq!(
+            path,
+            temp.path()
+                .join("web_search")
+                .join("20261017T093000_what-changed-in-rust-this-week.md")
+        );
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            "# Web search: What changed in Rust this week?\n\n\
+             Searched 2026-10-17T09:30:00+00:00 via fixed, past week only.\n\n\
+             ## 1. [Rust 1.90 released](https://blog.rust-lang.org/2026/10/16/rust-1.90)\n\
+             Published: 2026-10-16\n\n\
+             The Rust team is happy to announce 1.90.\n"
+        );
+    }
+
+    #[test]
+    fn empty_query_is_rejected() {
+        let request = SearchRequest {
+            query: "   ".to_string(),
+            count: 5,
+            freshness: None,
+        };
+        assert!(matches!(
+            search_with(&FixedProvider(Vec::new()), &request),
+            Err(SearchError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn brave_results_are_parsed() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("GET", "/")
+            .match_header("x-subscription-token", "brave_key")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), "rust release".into()),
+                mockito::Matcher::UrlEncoded("count".into(), "3".into()),
+                mockito::Matcher::UrlEncoded("freshness".into(), "pw".into()),
+            ]))
+            .with_body(
+                json!({ "web": { "results": [
+                    { "title": "Rust 1.90", "url": "https://example.com/rust", "description": "Released.", "age": "2 days ago" },
+                    { "title": "No URL" }
+                ] } })
+                .to_string(),
+            )
+            .create();
+        let mut provider = BraveSearch::new("brave_key");
+        provider.api_url = server.url();
+
+        let results = provider
+            .search(&SearchRequest {
+                query: "rust release".to_string(),
+                count: 3,
+                freshness: Some(Freshness::Week),
+            })
+            .expect("search");
+
+        mock.assert();
+        assert_eq!(
+            results,
+            vec![SearchResult {
+                title: "Rust 1.90".to_string(),
+                url: "https://example.com/rust".to_string(),
+                snippet: "Released.".to_string(),
+                published: Some("2 days ago".to_string()),
+            }]
+        );
+    }
+}
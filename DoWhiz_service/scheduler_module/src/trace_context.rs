@@ -0,0 +1,83 @@
+//! Correlation IDs that follow one inbound message across modules.
+//!
+//! A `trace_id` is minted when the gateway builds an [`IngestionEnvelope`],
+//! carried on the envelope and on the tasks scheduled from it, exported to the
+//! runner as `DOWHIZ_TRACE_ID`, and attached to every log line emitted while a
+//! [`TraceGuard`] is held. IDs use the W3C trace-context format (32 lowercase
+//! hex characters) and each guard opens a `trace` span; with OTLP export on
+//! (see [`crate::telemetry`]) that span is parented under the same trace ID.
+//!
+//! [`IngestionEnvelope`]: crate::ingestion::IngestionEnvelope
+
+use std::cell::RefCell;
+
+use tracing::span::EnteredSpan;
+use uuid::Uuid;
+
+thread_local! {
+    static CURRENT_TRACE_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Generate a fresh trace ID (32 lowercase hex characters).
+pub fn new_trace_id() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// The trace ID entered on this thread, if any.
+pub fn current_trace_id() -> Option<String> {
+    CURRENT_TRACE_ID.with(|current| current.borrow().clone())
+}
+
+/// Make `trace_id` current on this thread until the returned guard drops.
+pub fn enter(trace_id: &str) -> TraceGuard {
+    let previous = CURRENT_TRACE_ID.with(|current| current.replace(Some(trace_id.to_string())));
+    let span = tracing::info_span!("trace", trace_id = %trace_id);
+    crate::telemetry::link_trace(&span, trace_id);
+    let span = span.entered();
+    TraceGuard {
+        previous,
+        _span: span,
+    }
+}
+
+/// Restores the previously current trace ID and closes the span on drop.
+pub struct TraceGuard {
+    previous: Option<String>,
+    _span: EnteredSpan,
+}
+
+impl Drop for TraceGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT_TRACE_ID.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_trace_id_uses_w3c_format() {
+        let trace_id = new_trace_id();
+        assert_eq!(trace_id.len(), 32);
+        assert!(trace_id
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)));
+    }
+
+    #[test]
+    fn nested_guards_restore_previous_trace() {
+        assert_eq!(current_trace_id(), None);
+        {
+            let _outer = enter("outer");
+            assert_eq!(current_trace_id().as_deref(), Some("outer"));
+            {
+                let _inner = enter("inner");
+                assert_eq!(current_trace_id().as_deref(), Some("inner"));
+            }
+            assert_eq!(current_trace_id().as_deref(), Some("outer"));
+        }
+        assert_eq!(current_trace_id(), None);
+    }
+}
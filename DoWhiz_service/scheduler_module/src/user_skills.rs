@@ -0,0 +1,275 @@
+//! Personal skills of one user, kept in `users/<id>/skills/<name>/`.
+//!
+//! They are synced into the user's workspaces after the shared and employee
+//! skills, so a user skill replaces an employee skill of the same name in
+//! that user's threads only. Uploads replace a skill as a whole: the files
+//! are written to a staging directory and swapped in.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::skill_market::{
+    content_digest, is_relative_subpath, is_valid_skill_name, SkillMarketError, StagingDir,
+};
+
+/// Most files one skill may hold.
+pub const MAX_USER_SKILL_FILES: usize = 50;
+/// Most bytes of file content one skill may hold.
+pub const MAX_USER_SKILL_BYTES: usize = 1024 * 1024;
+/// Most skills one user may keep.
+pub const MAX_USER_SKILLS: usize = 50;
+
+#[derive(Debug, thiserror::Error)]
+pub enum UserSkillError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid skill: {0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserSkillFile {
+    /// Path inside the skill, e.g. `SKILL.md` or `scripts/run.sh`.
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserSkillUpload {
+    pub name: String,
+    /// Every file of the skill; `SKILL.md` is required.
+    pub files: Vec<UserSkillFile>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct UserSkill {
+    pub name: String,
+    /// Paths of the skill's files, sorted.
+    pub files: Vec<String>,
+    pub content_sha256: String,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// The skills in `skills_dir`, by name.
+pub fn list_user_skills(skills_dir: &Path) -> Result<Vec<UserSkill>, UserSkillError> {
+    let entries = match fs::read_dir(skills_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut skills = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_dir() && is_valid_skill_name(&name) {
+            skills.push(describe(skills_dir, &name)?);
+        }
+    }
+    skills.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(skills)
+}
+
+/// Writes `upload` as `skills_dir/<name>`, replacing any skill of that name.
+pub fn save_user_skill(
+    skills_dir: &Path,
+    upload: &UserSkillUpload,
+) -> Result<UserSkill, UserSkillError> {
+    validate_upload(upload)?;
+    let target = skills_dir.join(&upload.name);
+    if !target.exists() && list_user_skills(skills_dir)?.len() >= MAX_USER_SKILLS {
+        return Err(UserSkillError::Invalid(format!(
+            "at most {} skills per user",
+            MAX_USER_SKILLS
+        )));
+    }
+
+    fs::create_dir_all(skills_dir)?;
+    let staging = StagingDir::create(skills_dir)?;
+    let staged = staging.path.join(&upload.name);
+    for file in &upload.files {
+        let path = staged.join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &file.content)?;
+    }
+
+    let previous = staging.path.join(".previous");
+    if target.exists() {
+        fs::rename(&target, &previous)?;
+    }
+    if let Err(err) = fs::rename(&staged, &target) {
+        if previous.exists() {
+            fs::rename(&previous, &target)?;
+        }
+        return Err(err.into());
+    }
+    describe(skills_dir, &upload.name)
+}
+
+/// Removes `skills_dir/<name>`; `false` when there was no such skill.
+pub fn delete_user_skill(skills_dir: &Path, name: &str) -> Result<bool, UserSkillError> {
+    if !is_valid_skill_name(name) {
+        return Err(UserSkillError::Invalid(format!(
+            "invalid skill name: {}",
+            name
+        )));
+    }
+    match fs::remove_dir_all(skills_dir.join(name)) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn describe(skills_dir: &Path, name: &str) -> Result<UserSkill, UserSkillError> {
+    let dir = skills_dir.join(name);
+    let mut files = Vec::new();
+    collect_files(&dir, &dir, &mut files)?;
+    files.sort();
+    let content_sha256 = content_digest(&dir).map_err(|err| match err {
+        SkillMarketError::Io(err) => UserSkillError::Io(err),
+        other => UserSkillError::Invalid(other.to_string()),
+    })?;
+    let updated_at = fs::metadata(&dir)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .map(DateTime::<Utc>::from);
+    Ok(UserSkill {
+        name: name.to_string(),
+        files,
+        content_sha256,
+        updated_at,
+    })
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            files.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(())
+}
+
+fn validate_upload(upload: &UserSkillUpload) -> Result<(), UserSkillError> {
+    let invalid = |message: String| Err(UserSkillError::Invalid(message));
+    if !is_valid_skill_name(&upload.name) {
+        return invalid("name must be letters, digits, '-', '_' or '.'".to_string());
+    }
+    if upload.files.len() > MAX_USER_SKILL_FILES {
+        return invalid(format!("at most {} files per skill", MAX_USER_SKILL_FILES));
+    }
+    let total: usize = upload.files.iter().map(|file| file.content.len()).sum();
+    if total > MAX_USER_SKILL_BYTES {
+        return invalid(format!(
+            "skill content is {} bytes; the limit is {}",
+            total, MAX_USER_SKILL_BYTES
+        ));
+    }
+    let mut paths = std::collections::BTreeSet::new();
+    for file in &upload.files {
+        let hidden = file.path.split('/').any(|segment| segment.starts_with('.'));
+        if !is_relative_subpath(&file.path) || hidden || file.path.contains('\\') {
+            return invalid(format!("invalid file path: {}", file.path));
+        }
+        if !paths.insert(file.path.as_str()) {
+            return invalid(format!("duplicate file path: {}", file.path));
+        }
+    }
+    // A path cannot be both a file and the directory of another file.
+    if let Some(path) = paths.iter().find(|path| {
+        let dir = format!("{}/", path);
+        paths.iter().any(|other| other.starts_with(&dir))
+    }) {
+        return invalid(format!("{} is both a file and a directory", path));
+    }
+    if !paths.contains("SKILL.md") {
+        return invalid("a skill needs a SKILL.md".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn upload(name: &str, files: &[(&str, &str)]) -> UserSkillUpload {
+        UserSkillUpload {
+            name: name.to_string(),
+            files: files
+                .iter()
+                .map(|(path, content)| UserSkillFile {
+                    path: path.to_string(),
+                    content: content.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn save_replaces_the_whole_skill_and_delete_removes_it() {
+        let temp = TempDir::new().expect("tempdir");
+        let skills_dir = temp.path().join("skills");
+
+        save_user_skill(
+            &skills_dir,
+            &upload(
+                "expenses",
+                &[("SKILL.md", "v1"), ("scripts/old.sh", "echo old")],
+            ),
+        )
+        .expect("first save");
+        let saved = save_user_skill(&skills_dir, &upload("expenses", &[("SKILL.md", "v2")]))
+            .expect("second save");
+
+        assert_eq!(saved.files, ["SKILL.md"]);
+        assert!(!skills_dir.join("expenses/scripts").exists());
+        let listed = list_user_skills(&skills_dir).expect("list");
+        assert_eq!(listed, [saved]);
+        let leftovers: Vec<_> = fs::read_dir(&skills_dir)
+            .expect("read")
+            .map(|entry| entry.expect("entry").file_name())
+            .collect();
+        assert_eq!(leftovers, ["expenses"]);
+
+        assert!(delete_user_skill(&skills_dir, "expenses").expect("delete"));
+        assert!(!delete_user_skill(&skills_dir, "expenses").expect("delete again"));
+        assert!(list_user_skills(&skills_dir).expect("list").is_empty());
+    }
+
+    #[test]
+    fn save_rejects_unsafe_or_incomplete_uploads() {
+        let temp = TempDir::new().expect("tempdir");
+        let skills_dir = temp.path().join("skills");
+        let rejected = [
+            upload("../escape", &[("SKILL.md", "x")]),
+            upload("notes", &[("README.md", "no SKILL.md")]),
+            upload("notes", &[("SKILL.md", "x"), ("../../secrets", "x")]),
+            upload("notes", &[("SKILL.md", "x"), ("/etc/passwd", "x")]),
+            upload("notes", &[("SKILL.md", "x"), (".git/config", "x")]),
+            upload("notes", &[("SKILL.md", "x"), ("a", "x"), ("a/b", "x")]),
+        ];
+        for bad in rejected {
+            assert!(
+                matches!(
+                    save_user_skill(&skills_dir, &bad),
+                    Err(UserSkillError::Invalid(_))
+                ),
+                "accepted {:?}",
+                bad
+            );
+        }
+        assert!(list_user_skills(&skills_dir).expect("list").is_empty());
+    }
+}
@@ -1,16 +1,15 @@
+use crate::channel::Channel;
 use crate::memory_store::ensure_default_user_memo;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, FixedOffset, NaiveTime, TimeZone, Utc};
 use mongodb::bson::{doc, Bson, DateTime as BsonDateTime, Document};
-use mongodb::options::FindOptions;
-use mongodb::options::IndexOptions;
+use mongodb::options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument};
 use mongodb::sync::Collection;
-use mongodb::IndexModel;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::mongo_store::{create_client_from_env, database_from_env, ensure_index_compatible};
+use crate::mongo_store::{create_client_from_env, database_from_env};
 
 #[derive(Debug)]
 pub struct UserStore {
@@ -20,6 +19,7 @@ pub struct UserStore {
 #[derive(Debug, Clone)]
 struct MongoUserStore {
     users: Collection<Document>,
+    preferences: Collection<Document>,
 }
 
 #[derive(Debug, Clone)]
@@ -29,6 +29,10 @@ pub struct UserRecord {
     pub identifier: String,
     pub created_at: DateTime<Utc>,
     pub last_seen_at: DateTime<Utc>,
+    /// When mail to this email user last hard-bounced; cleared once mail to
+    /// the address is delivered again.
+    pub bounced_at: Option<DateTime<Utc>>,
+    pub bounce_reason: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +44,85 @@ pub struct UserPaths {
     pub secrets_dir: PathBuf,
     pub mail_root: PathBuf,
     pub workspaces_root: PathBuf,
+    /// The user's own skills, synced into workspaces after the employee's.
+    pub skills_dir: PathBuf,
+}
+
+/// Contact preferences of one user. Unset fields fall back to the channel
+/// and language of each conversation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserPreferences {
+    pub preferred_channel: Option<Channel>,
+    pub quiet_hours: Option<QuietHours>,
+    /// Reply language, as the user named it (e.g. "French").
+    pub language: Option<String>,
+    /// Leave the user out of operator broadcasts.
+    pub broadcast_opt_out: bool,
+    /// Set when the user asked us to stop; scheduled sends, digests and
+    /// scheduled runs for them are skipped until they write again.
+    pub muted: bool,
+    /// Set when the user asked for the full agent on every message; the
+    /// quick-response router is skipped for them.
+    pub always_full_agent: bool,
+}
+
+/// Daily window, in the user's local time, during which non-urgent sends are
+/// held. A window whose `start` is after its `end` runs past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    /// The user's offset from UTC, in minutes.
+    pub utc_offset_minutes: i32,
+}
+
+/// Longest UTC offset in use, in minutes.
+const MAX_UTC_OFFSET_MINUTES: i32 = 14 * 60;
+
+impl QuietHours {
+    fn offset(&self) -> FixedOffset {
+        FixedOffset::east_opt(self.utc_offset_minutes * 60)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).expect("zero offset"))
+    }
+
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let time = at.with_timezone(&self.offset()).time();
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// End of the window `at` falls in, or `at` itself when it is outside
+    /// quiet hours.
+    pub fn end_after(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        if !self.contains(at) {
+            return at;
+        }
+        let offset = self.offset();
+        let local = at.with_timezone(&offset).naive_local();
+        let mut end = local.date().and_time(self.end);
+        if end <= local {
+            end += Duration::days(1);
+        }
+        offset
+            .from_local_datetime(&end)
+            .single()
+            .map(|end| end.with_timezone(&Utc))
+            .unwrap_or(at)
+    }
+}
+
+/// One preference for [`UserStore::set_pref`]; `None` clears it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserPref {
+    PreferredChannel(Option<Channel>),
+    QuietHours(Option<QuietHours>),
+    Language(Option<String>),
+    BroadcastOptOut(bool),
+    Muted(bool),
+    AlwaysFullAgent(bool),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -54,6 +137,8 @@ pub enum UserStoreError {
     DateTimeParse(#[from] chrono::ParseError),
     #[error("mongo config error: {0}")]
     MongoConfig(String),
+    #[error("invalid preference: {0}")]
+    InvalidPreference(String),
 }
 
 impl UserStore {
@@ -74,18 +159,57 @@ impl UserStore {
             .get_user_by_identifier(identifier_type, identifier)
     }
 
+    /// Inside a [`crate::telemetry::tenant_scope`], the user also tags the
+    /// thread's log records.
     pub fn get_or_create_user(
         &self,
         identifier_type: &str,
         identifier: &str,
     ) -> Result<UserRecord, UserStoreError> {
-        self.mongo.get_or_create_user(identifier_type, identifier)
+        let user = self.mongo.get_or_create_user(identifier_type, identifier)?;
+        crate::telemetry::note_user(&user.user_id);
+        Ok(user)
+    }
+
+    /// Mark the email user of `address` as hard-bounced. Returns the user,
+    /// or `None` when nobody writes from `address`.
+    pub fn mark_email_bounced(
+        &self,
+        address: &str,
+        reason: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Option<UserRecord>, UserStoreError> {
+        self.mongo.set_email_bounce(address, Some((reason, at)))
+    }
+
+    /// Clear the bounce mark of `address` after mail to it was delivered.
+    pub fn clear_email_bounce(&self, address: &str) -> Result<Option<UserRecord>, UserStoreError> {
+        self.mongo.set_email_bounce(address, None)
     }
 
     pub fn list_user_ids(&self) -> Result<Vec<String>, UserStoreError> {
         self.mongo.list_user_ids()
     }
 
+    /// Every user, oldest first.
+    pub fn list_users(&self) -> Result<Vec<UserRecord>, UserStoreError> {
+        self.mongo.list_users()
+    }
+
+    /// The user's preferences; all unset when none were stored.
+    pub fn get_pref(&self, user_id: &str) -> Result<UserPreferences, UserStoreError> {
+        self.mongo.get_pref(user_id)
+    }
+
+    /// Store one preference and return the user's preferences after it.
+    pub fn set_pref(
+        &self,
+        user_id: &str,
+        pref: UserPref,
+    ) -> Result<UserPreferences, UserStoreError> {
+        self.mongo.set_pref(user_id, pref)
+    }
+
     pub fn user_paths(&self, users_root: &Path, user_id: &str) -> UserPaths {
         let root = users_root.join(user_id);
         let state_dir = root.join("state");
@@ -94,6 +218,7 @@ impl UserStore {
         let secrets_dir = root.join("secrets");
         let mail_root = root.join("mail");
         let workspaces_root = root.join("workspaces");
+        let skills_dir = root.join("skills");
         UserPaths {
             root,
             state_dir,
@@ -102,6 +227,7 @@ impl UserStore {
             secrets_dir,
             mail_root,
             workspaces_root,
+            skills_dir,
         }
     }
 
@@ -227,24 +353,8 @@ impl MongoUserStore {
             create_client_from_env().map_err(|err| UserStoreError::MongoConfig(err.to_string()))?;
         let db = database_from_env(&client);
         let users = db.collection::<Document>("users");
-        ensure_index_compatible(
-            &users,
-            IndexModel::builder()
-                .keys(doc! { "user_id": 1 })
-                .options(IndexOptions::builder().unique(Some(true)).build())
-                .build(),
-        )?;
-        ensure_index_compatible(
-            &users,
-            IndexModel::builder()
-                .keys(doc! { "identifier_type": 1, "identifier": 1 })
-                .build(),
-        )?;
-        ensure_index_compatible(
-            &users,
-            IndexModel::builder().keys(doc! { "created_at": 1 }).build(),
-        )?;
-        Ok(Self { users })
+        let preferences = db.collection::<Document>("user_preferences");
+        Ok(Self { users, preferences })
     }
 
     fn get_user_by_identifier(
@@ -315,6 +425,8 @@ impl MongoUserStore {
                 identifier: normalized,
                 created_at: now,
                 last_seen_at: now,
+                bounced_at: None,
+                bounce_reason: None,
             }),
             Err(err) => {
                 if let Some(existing) = self.users.find_one(filter, None)? {
@@ -325,6 +437,31 @@ impl MongoUserStore {
         }
     }
 
+    fn set_email_bounce(
+        &self,
+        address: &str,
+        bounce: Option<(&str, DateTime<Utc>)>,
+    ) -> Result<Option<UserRecord>, UserStoreError> {
+        let normalized = normalize_email(address)
+            .ok_or_else(|| UserStoreError::InvalidIdentifier(address.to_string()))?;
+        let update = match bounce {
+            Some((reason, at)) => doc! {
+                "$set": { "bounced_at": BsonDateTime::from_chrono(at), "bounce_reason": reason }
+            },
+            None => doc! { "$unset": { "bounced_at": "", "bounce_reason": "" } },
+        };
+        self.users
+            .find_one_and_update(
+                doc! { "identifier_type": "email", "identifier": normalized.as_str() },
+                update,
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            )?
+            .map(document_to_user_record)
+            .transpose()
+    }
+
     fn list_user_ids(&self) -> Result<Vec<String>, UserStoreError> {
         let mut ids = Vec::new();
         let cursor = self.users.find(
@@ -342,6 +479,121 @@ impl MongoUserStore {
         }
         Ok(ids)
     }
+
+    fn list_users(&self) -> Result<Vec<UserRecord>, UserStoreError> {
+        let cursor = self.users.find(
+            doc! {},
+            FindOptions::builder()
+                .sort(doc! { "created_at": 1 })
+                .build(),
+        )?;
+        let mut users = Vec::new();
+        for row in cursor {
+            users.push(document_to_user_record(row?)?);
+        }
+        Ok(users)
+    }
+
+    fn get_pref(&self, user_id: &str) -> Result<UserPreferences, UserStoreError> {
+        Ok(self
+            .preferences
+            .find_one(doc! { "user_id": user_id }, None)?
+            .map(|document| document_to_user_preferences(&document))
+            .unwrap_or_default())
+    }
+
+    fn set_pref(&self, user_id: &str, pref: UserPref) -> Result<UserPreferences, UserStoreError> {
+        let (field, value) = match pref {
+            UserPref::PreferredChannel(channel) => (
+                "preferred_channel",
+                channel.map(|channel| Bson::String(channel.to_string())),
+            ),
+            UserPref::QuietHours(quiet_hours) => {
+                if let Some(quiet_hours) = &quiet_hours {
+                    if quiet_hours.utc_offset_minutes.abs() > MAX_UTC_OFFSET_MINUTES {
+                        return Err(UserStoreError::InvalidPreference(format!(
+                            "utc offset {} minutes is out of range",
+                            quiet_hours.utc_offset_minutes
+                        )));
+                    }
+                }
+                (
+                    "quiet_hours",
+                    quiet_hours.map(|quiet_hours| {
+                        Bson::Document(doc! {
+                            "start": quiet_hours.start.format("%H:%M").to_string(),
+                            "end": quiet_hours.end.format("%H:%M").to_string(),
+                            "utc_offset_minutes": quiet_hours.utc_offset_minutes,
+                        })
+                    }),
+                )
+            }
+            UserPref::Language(language) => (
+                "language",
+                language
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+                    .map(Bson::String),
+            ),
+            // Opted in is the default, so only an opt-out is stored.
+            UserPref::BroadcastOptOut(opt_out) => {
+                ("broadcast_opt_out", opt_out.then_some(Bson::Boolean(true)))
+            }
+            UserPref::Muted(muted) => ("muted", muted.then_some(Bson::Boolean(true))),
+            UserPref::AlwaysFullAgent(always) => {
+                ("always_full_agent", always.then_some(Bson::Boolean(true)))
+            }
+        };
+        let now = BsonDateTime::from_chrono(Utc::now());
+        let update = match value {
+            Some(value) => doc! { "$set": { field: value, "updated_at": now } },
+            None => doc! { "$unset": { field: "" }, "$set": { "updated_at": now } },
+        };
+        self.preferences.update_one(
+            doc! { "user_id": user_id },
+            update,
+            mongodb::options::UpdateOptions::builder()
+                .upsert(true)
+                .build(),
+        )?;
+        self.get_pref(user_id)
+    }
+}
+
+/// Unreadable fields are dropped rather than failing the whole lookup.
+fn document_to_user_preferences(document: &Document) -> UserPreferences {
+    let preferred_channel = document
+        .get_str("preferred_channel")
+        .ok()
+        .and_then(|value| value.parse::<Channel>().ok());
+    let quiet_hours = document.get_document("quiet_hours").ok().and_then(|value| {
+        let time = |key| {
+            value
+                .get_str(key)
+                .ok()
+                .and_then(|raw| NaiveTime::parse_from_str(raw, "%H:%M").ok())
+        };
+        Some(QuietHours {
+            start: time("start")?,
+            end: time("end")?,
+            utc_offset_minutes: value.get_i32("utc_offset_minutes").unwrap_or(0),
+        })
+    });
+    let language = document
+        .get_str("language")
+        .ok()
+        .map(|value| value.to_string());
+    let broadcast_opt_out = document.get_bool("broadcast_opt_out").unwrap_or(false);
+    let muted = document.get_bool("muted").unwrap_or(false);
+    let always_full_agent = document.get_bool("always_full_agent").unwrap_or(false);
+    UserPreferences {
+        preferred_channel,
+        quiet_hours,
+        language,
+        broadcast_opt_out,
+        muted,
+        always_full_agent,
+    }
 }
 
 fn document_to_user_record(document: Document) -> Result<UserRecord, UserStoreError> {
@@ -359,12 +611,19 @@ fn document_to_user_record(document: Document) -> Result<UserRecord, UserStoreEr
         .to_string();
     let created_at = bson_datetime_to_utc(&document, "created_at")?;
     let last_seen_at = bson_datetime_to_utc(&document, "last_seen_at")?;
+    let bounced_at = bson_datetime_to_utc(&document, "bounced_at").ok();
+    let bounce_reason = document
+        .get_str("bounce_reason")
+        .ok()
+        .map(|value| value.to_string());
     Ok(UserRecord {
         user_id,
         identifier_type,
         identifier,
         created_at,
         last_seen_at,
+        bounced_at,
+        bounce_reason,
     })
 }
 
@@ -427,5 +686,16 @@ pub fn lookup_user_id_by_identifier(identifier_type: &str, identifier: &str) ->
     }
 }
 
+/// Preferences of `user_id`, all unset when the store is unavailable.
+pub fn lookup_user_preferences(user_id: &str) -> UserPreferences {
+    let Some(store) = get_global_user_store() else {
+        return UserPreferences::default();
+    };
+    store.get_pref(user_id).unwrap_or_else(|err| {
+        tracing::warn!("Failed to load preferences for user {}: {}", user_id, err);
+        UserPreferences::default()
+    })
+}
+
 #[cfg(test)]
 mod tests;
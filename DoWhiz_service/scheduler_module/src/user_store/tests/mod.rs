@@ -1,5 +1,9 @@
-use super::{extract_emails, normalize_email, normalize_phone, normalize_slack_id, UserStore};
-use chrono::{Duration, Utc};
+use super::{
+    extract_emails, normalize_email, normalize_phone, normalize_slack_id, QuietHours, UserPref,
+    UserStore,
+};
+use crate::channel::Channel;
+use chrono::{Duration, NaiveTime, TimeZone, Utc};
 use mongodb::bson::{doc, DateTime as BsonDateTime, Document};
 use tempfile::TempDir;
 
@@ -184,3 +188,133 @@ fn user_store_refreshes_last_seen_after_interval() {
         .unwrap();
     assert!(refreshed.last_seen_at > stale);
 }
+
+#[test]
+fn quiet_hours_wrap_past_midnight_in_local_time() {
+    // 22:00-07:00 at UTC-5.
+    let quiet_hours = QuietHours {
+        start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+        end: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+        utc_offset_minutes: -5 * 60,
+    };
+    let late_evening = Utc.with_ymd_and_hms(2026, 3, 2, 4, 30, 0).unwrap();
+    assert!(quiet_hours.contains(late_evening));
+    assert_eq!(
+        quiet_hours.end_after(late_evening),
+        Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap()
+    );
+
+    let afternoon = Utc.with_ymd_and_hms(2026, 3, 2, 19, 0, 0).unwrap();
+    assert!(!quiet_hours.contains(afternoon));
+    assert_eq!(quiet_hours.end_after(afternoon), afternoon);
+    assert!(!quiet_hours.contains(Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap()));
+}
+
+#[test]
+fn set_pref_stores_and_clears_each_preference() {
+    let temp = TempDir::new().unwrap();
+    let store = UserStore::new(temp.path().join("users.db")).unwrap();
+    let user = store
+        .get_or_create_user("email", "prefs@example.com")
+        .unwrap();
+    assert_eq!(store.get_pref(&user.user_id).unwrap(), Default::default());
+
+    let quiet_hours = QuietHours {
+        start: NaiveTime::from_hms_opt(21, 30, 0).unwrap(),
+        end: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+        utc_offset_minutes: 60,
+    };
+    store
+        .set_pref(
+            &user.user_id,
+            UserPref::PreferredChannel(Some(Channel::Slack)),
+        )
+        .unwrap();
+    store
+        .set_pref(&user.user_id, UserPref::QuietHours(Some(quiet_hours)))
+        .unwrap();
+    let preferences = store
+        .set_pref(
+            &user.user_id,
+            UserPref::Language(Some(" French ".to_string())),
+        )
+        .unwrap();
+    assert_eq!(preferences.preferred_channel, Some(Channel::Slack));
+    assert_eq!(preferences.quiet_hours, Some(quiet_hours));
+    assert_eq!(preferences.language.as_deref(), Some("French"));
+
+    let preferences = store
+        .set_pref(&user.user_id, UserPref::QuietHours(None))
+        .unwrap();
+    assert_eq!(preferences.quiet_hours, None);
+    assert_eq!(preferences.preferred_channel, Some(Channel::Slack));
+
+    let preferences = store
+        .set_pref(&user.user_id, UserPref::BroadcastOptOut(true))
+        .unwrap();
+    assert!(preferences.broadcast_opt_out);
+    let preferences = store
+        .set_pref(&user.user_id, UserPref::BroadcastOptOut(false))
+        .unwrap();
+    assert!(!preferences.broadcast_opt_out);
+    assert_eq!(preferences.language.as_deref(), Some("French"));
+
+    let preferences = store
+        .set_pref(&user.user_id, UserPref::Muted(true))
+        .unwrap();
+    assert!(preferences.muted);
+    let preferences = store
+        .set_pref(&user.user_id, UserPref::Muted(false))
+        .unwrap();
+    assert!(!preferences.muted);
+
+    let preferences = store
+        .set_pref(&user.user_id, UserPref::AlwaysFullAgent(true))
+        .unwrap();
+    assert!(preferences.always_full_agent);
+    let preferences = store
+        .set_pref(&user.user_id, UserPref::AlwaysFullAgent(false))
+        .unwrap();
+    assert!(!preferences.always_full_agent);
+
+    let out_of_range = QuietHours {
+        utc_offset_minutes: 15 * 60,
+        ..quiet_hours
+    };
+    assert!(store
+        .set_pref(&user.user_id, UserPref::QuietHours(Some(out_of_range)))
+        .is_err());
+}
+
+#[test]
+fn email_bounce_marks_and_clears_the_user() {
+    let temp = TempDir::new().unwrap();
+    let store = UserStore::new(temp.path().join("users.db")).unwrap();
+    let user = store
+        .get_or_create_user("email", "bounce@example.com")
+        .unwrap();
+    assert!(user.bounced_at.is_none());
+
+    let at = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+    let marked = store
+        .mark_email_bounced("Bounce@Example.com", "Mailbox does not exist", at)
+        .unwrap()
+        .expect("user marked");
+    assert_eq!(marked.user_id, user.user_id);
+    assert_eq!(marked.bounced_at, Some(at));
+    assert_eq!(
+        marked.bounce_reason.as_deref(),
+        Some("Mailbox does not exist")
+    );
+
+    let cleared = store
+        .clear_email_bounce("bounce@example.com")
+        .unwrap()
+        .expect("user cleared");
+    assert!(cleared.bounced_at.is_none());
+    assert!(cleared.bounce_reason.is_none());
+    assert!(store
+        .mark_email_bounced("nobody@example.com", "unknown", at)
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,418 @@
+//! Detection and repair of corrupt thread workspaces.
+//!
+//! Partial writes can leave the `incoming_email` tree (or a `thread_state.json`
+//! not yet imported into the thread database) unreadable, after which every
+//! retry of the thread fails the same way. A
+//! corrupt workspace is moved aside into `.quarantine/` and rebuilt: intact
+//! inbound entries, memory, and employee files are carried over, past emails
+//! are re-hydrated from the user's mail archive, and a recovery note is left
+//! for the agent so it can tell the user that earlier context may be summarized.
+//! With archive encryption on, the quarantined copy is sealed like the archive.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use tracing::warn;
+
+use run_task_module::{WORKSPACE_RECOVERY_NOTE, WORKSPACE_RECOVERY_NOTIFIED};
+
+use crate::archive_crypto::ArchiveKey;
+use crate::thread_state::{
+    default_thread_state_path, load_thread_state, write_thread_state, ThreadState,
+};
+
+const QUARANTINE_DIR: &str = ".quarantine";
+const CARRIED_OVER_FILES: &[&str] = &["AGENTS.md", "CLAUDE.md", "GEMINI.md", "SOUL.md", ".env"];
+const CARRIED_OVER_DIRS: &[&str] = &["memory", ".agents"];
+
+#[derive(Debug, Clone)]
+pub(crate) struct WorkspaceRecovery {
+    pub(crate) quarantine_path: PathBuf,
+    pub(crate) entries_restored: usize,
+    pub(crate) entries_dropped: usize,
+}
+
+/// Return a description of the first corruption found, or `None` if the
+/// workspace looks readable.
+pub(crate) fn detect_workspace_corruption(workspace: &Path) -> Option<String> {
+    // Once imported, the thread database holds the state and the file is
+    // just a snapshot that the next change rewrites.
+    let state_path = default_thread_state_path(workspace);
+    if state_path.exists() && !matches!(load_thread_state(&state_path), Ok(Some(_))) {
+        match fs::read_to_string(&state_path) {
+            Ok(raw) => {
+                if let Err(err) = serde_json::from_str::<ThreadState>(&raw) {
+                    return Some(format!("thread_state.json is unreadable: {}", err));
+                }
+            }
+            Err(err) => return Some(format!("thread_state.json is unreadable: {}", err)),
+        }
+    }
+
+    let incoming_email = workspace.join("incoming_email");
+    if !incoming_email.exists() {
+        return None;
+    }
+    if !incoming_email.is_dir() {
+        return Some("incoming_email is not a directory".to_string());
+    }
+    if let Some(reason) = payload_corruption(&incoming_email) {
+        return Some(format!("incoming_email/{}", reason));
+    }
+    let entries = incoming_email.join("entries");
+    if !entries.exists() {
+        return None;
+    }
+    let entry_dirs = match list_entry_dirs(&entries) {
+        Ok(dirs) => dirs,
+        Err(err) => return Some(format!("incoming_email/entries is unreadable: {}", err)),
+    };
+    for entry in entry_dirs {
+        if let Some(reason) = payload_corruption(&entry) {
+            let name = entry.file_name().unwrap_or_default().to_string_lossy();
+            return Some(format!("incoming_email/entries/{}/{}", name, reason));
+        }
+    }
+    None
+}
+
+/// Move a corrupt workspace into quarantine and rebuild it at the same path.
+///
+/// `archive` is the user's mail root and user id used to rebuild the
+/// past-email search index. `fallback_state` is written when the old
+/// thread state cannot be carried over, so queued tasks keep their epoch.
+pub(crate) fn recover_corrupt_workspace(
+    workspace: &Path,
+    reason: &str,
+    archive: Option<(&Path, &str)>,
+    fallback_state: Option<&ThreadState>,
+) -> io::Result<WorkspaceRecovery> {
+    let workspaces_root = workspace
+        .parent()
+        .ok_or_else(|| io::Error::other("workspace has no parent directory"))?;
+    let name = workspace
+        .file_name()
+        .ok_or_else(|| io::Error::other("workspace has no directory name"))?
+        .to_string_lossy()
+        .to_string();
+    let quarantine_root = workspaces_root.join(QUARANTINE_DIR);
+    fs::create_dir_all(&quarantine_root)?;
+    let quarantine_path = quarantine_root.join(format!(
+        "{}_{}",
+        name,
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+    ));
+    fs::rename(workspace, &quarantine_path)?;
+
+    for dir in [
+        "incoming_email",
+        "incoming_attachments",
+        "memory",
+        "references",
+    ] {
+        fs::create_dir_all(workspace.join(dir))?;
+    }
+    for file in CARRIED_OVER_FILES {
+        let src = quarantine_path.join(file);
+        if src.is_file() {
+            fs::copy(&src, workspace.join(file))?;
+        }
+    }
+    for dir in CARRIED_OVER_DIRS {
+        let src = quarantine_path.join(dir);
+        if src.is_dir() {
+            if let Err(err) = copy_dir(&src, &workspace.join(dir)) {
+                warn!(
+                    "failed to carry {} over from quarantined workspace {}: {}",
+                    dir,
+                    quarantine_path.display(),
+                    err
+                );
+            }
+        }
+    }
+
+    let (entries_restored, entries_dropped) = restore_inbound_entries(&quarantine_path, workspace)?;
+
+    // The thread database is outside the workspace, so the state survives
+    // the move; a state file never imported is read from the quarantined copy.
+    let state_path = default_thread_state_path(workspace);
+    let stored_state = load_thread_state(&state_path).unwrap_or_else(|err| {
+        warn!(
+            "failed to load thread state of {}: {}",
+            workspace.display(),
+            err
+        );
+        None
+    });
+    let old_state = stored_state.or_else(|| {
+        fs::read_to_string(default_thread_state_path(&quarantine_path))
+            .ok()
+            .and_then(|raw| serde_json::from_str::<ThreadState>(&raw).ok())
+    });
+    if let Some(state) = old_state.as_ref().or(fallback_state) {
+        write_thread_state(&state_path, state)?;
+    }
+
+    if let Some((mail_root, user_id)) = archive {
+        if let Err(err) =
+            crate::past_emails::index_past_emails(mail_root, &workspace.join("references"), user_id)
+        {
+            warn!(
+                "failed to index past_emails for recovered workspace {}: {}",
+                workspace.display(),
+                err
+            );
+        }
+        // The quarantined copy is kept like an archive entry, so it is
+        // sealed with the user's archive key when encryption is on.
+        let sealed = ArchiveKey::for_writing(mail_root)
+            .and_then(|key| key.map(|key| key.seal_dir(&quarantine_path)).transpose());
+        if let Err(err) = sealed {
+            warn!(
+                "failed to seal quarantined workspace {}: {}",
+                quarantine_path.display(),
+                err
+            );
+        }
+    }
+
+    fs::write(
+        workspace.join(WORKSPACE_RECOVERY_NOTE),
+        format!(
+            "# Workspace recovered\n\nThis workspace was rebuilt at {} because it was corrupt ({}).\nThe damaged copy is kept at {}.\n\nRestored {} inbound entries, dropped {} unreadable ones. Past emails were re-hydrated from the mail archive, so earlier context in this thread may be summarized rather than complete.\n",
+            Utc::now().to_rfc3339(),
+            reason,
+            quarantine_path.display(),
+            entries_restored,
+            entries_dropped,
+        ),
+    )?;
+
+    Ok(WorkspaceRecovery {
+        quarantine_path,
+        entries_restored,
+        entries_dropped,
+    })
+}
+
+/// Record that a reply from a recovered workspace was delivered, so later
+/// runs stop asking the agent to mention the recovery.
+pub(crate) fn mark_recovery_notified(workspace: &Path) -> io::Result<()> {
+    if !workspace.join(WORKSPACE_RECOVERY_NOTE).exists() {
+        return Ok(());
+    }
+    fs::write(workspace.join(WORKSPACE_RECOVERY_NOTIFIED), "1")
+}
+
+/// Copy readable `incoming_email/entries/*` (and their attachments) from the
+/// quarantined workspace, then rebuild the top-level current message from the
+/// old one if it is intact or from the latest readable entry otherwise.
+fn restore_inbound_entries(quarantine: &Path, workspace: &Path) -> io::Result<(usize, usize)> {
+    let old_email = quarantine.join("incoming_email");
+    let old_attachments = quarantine.join("incoming_attachments");
+    let new_email = workspace.join("incoming_email");
+    let new_attachments = workspace.join("incoming_attachments");
+
+    let mut restored = 0;
+    let mut dropped = 0;
+    let mut latest_good: Option<PathBuf> = None;
+    if old_email.is_dir() {
+        let entry_dirs = list_entry_dirs(&old_email.join("entries")).unwrap_or_default();
+        for entry in entry_dirs {
+            let Some(entry_name) = entry.file_name().map(|name| name.to_os_string()) else {
+                continue;
+            };
+            if payload_corruption(&entry).is_some() {
+                dropped += 1;
+                continue;
+            }
+            copy_dir(&entry, &new_email.join("entries").join(&entry_name))?;
+            let attachments = old_attachments.join("entries").join(&entry_name);
+            if attachments.is_dir() {
+                copy_dir(
+                    &attachments,
+                    &new_attachments.join("entries").join(&entry_name),
+                )?;
+            }
+            restored += 1;
+            latest_good = Some(entry);
+        }
+    }
+
+    let current_source = if old_email.is_dir() && payload_corruption(&old_email).is_none() {
+        Some(old_email)
+    } else {
+        latest_good
+    };
+    if let Some(source) = current_source {
+        copy_top_level_files(&source, &new_email)?;
+    }
+    if old_attachments.is_dir() {
+        copy_top_level_files(&old_attachments, &new_attachments)?;
+    }
+    Ok((restored, dropped))
+}
+
+fn payload_corruption(dir: &Path) -> Option<String> {
+    let payload_path = dir.join("postmark_payload.json");
+    if !payload_path.exists() {
+        return None;
+    }
+    let raw = match fs::read(&payload_path) {
+        Ok(raw) => raw,
+        Err(err) => return Some(format!("postmark_payload.json is unreadable: {}", err)),
+    };
+    match serde_json::from_slice::<serde_json::Value>(&raw) {
+        Ok(_) => None,
+        Err(err) => Some(format!("postmark_payload.json is unreadable: {}", err)),
+    }
+}
+
+fn list_entry_dirs(entries: &Path) -> io::Result<Vec<PathBuf>> {
+    if !entries.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(entries)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            dirs.push(entry.path());
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn copy_top_level_files(src: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            fs::copy(entry.path(), dest.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_dir(src: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let path = entry.path();
+        let target = dest.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_entry(workspace: &Path, name: &str, payload: &str) {
+        let entry = workspace.join("incoming_email").join("entries").join(name);
+        fs::create_dir_all(&entry).expect("entry dir");
+        fs::write(entry.join("postmark_payload.json"), payload).expect("payload");
+        fs::write(entry.join("email.html"), "<p>hi</p>").expect("email");
+    }
+
+    #[test]
+    fn detects_truncated_thread_state_and_payloads() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspaces_root = temp.path().join("users/u1/workspaces");
+        let workspace = workspaces_root.join("thread_a");
+        write_entry(&workspace, "00001_a", r#"{"Subject":"hello"}"#);
+        assert!(detect_workspace_corruption(&workspace).is_none());
+
+        fs::write(
+            workspace.join("thread_state.json"),
+            r#"{"thread_id": "t", "ep"#,
+        )
+        .expect("state");
+        let reason = detect_workspace_corruption(&workspace).expect("corrupt state");
+        assert!(reason.starts_with("thread_state.json"));
+
+        let state = ThreadState::new("t".to_string(), None);
+        write_thread_state(&workspace.join("thread_state.json"), &state).expect("state");
+        write_entry(&workspace, "00002_b", r#"{"Subject": "cut"#);
+        let reason = detect_workspace_corruption(&workspace).expect("corrupt entry");
+        assert!(reason.contains("entries/00002_b/postmark_payload.json"));
+    }
+
+    #[test]
+    fn recover_quarantines_and_keeps_readable_entries() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspaces_root = temp.path().join("users/u1/workspaces");
+        let workspace = workspaces_root.join("thread_a");
+        write_entry(&workspace, "00001_a", r#"{"Subject":"first"}"#);
+        write_entry(&workspace, "00002_b", r#"{"Subject": "cut"#);
+        fs::write(
+            workspace
+                .join("incoming_email")
+                .join("postmark_payload.json"),
+            r#"{"Subj"#,
+        )
+        .expect("current payload");
+        fs::create_dir_all(workspace.join("memory")).expect("memory");
+        fs::write(workspace.join("memory").join("memo.md"), "likes tea").expect("memo");
+        fs::write(workspace.join("thread_state.json"), "{").expect("state");
+
+        let mut fallback = ThreadState::new("t".to_string(), None);
+        fallback.epoch = 4;
+        let reason = detect_workspace_corruption(&workspace).expect("corrupt");
+        let recovery =
+            recover_corrupt_workspace(&workspace, &reason, None, Some(&fallback)).expect("recover");
+
+        assert!(recovery.quarantine_path.exists());
+        assert!(recovery
+            .quarantine_path
+            .starts_with(workspaces_root.join(".quarantine")));
+        assert_eq!(recovery.entries_restored, 1);
+        assert_eq!(recovery.entries_dropped, 1);
+        assert!(detect_workspace_corruption(&workspace).is_none());
+
+        let current = fs::read_to_string(
+            workspace
+                .join("incoming_email")
+                .join("postmark_payload.json"),
+        )
+        .expect("current payload restored");
+        assert!(current.contains("first"));
+        assert_eq!(
+            fs::read_to_string(workspace.join("memory").join("memo.md")).unwrap(),
+            "likes tea"
+        );
+        let state = crate::thread_state::load_thread_state(&workspace.join("thread_state.json"))
+            .expect("load")
+            .expect("state");
+        assert_eq!(state.epoch, 4);
+        let note = fs::read_to_string(workspace.join(WORKSPACE_RECOVERY_NOTE)).expect("note");
+        assert!(note.contains("may be summarized"));
+    }
+
+    #[test]
+    fn recovery_is_marked_notified_only_in_recovered_workspaces() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspace = temp.path().join("users/u1/workspaces/thread_a");
+        fs::create_dir_all(&workspace).expect("workspace");
+
+        mark_recovery_notified(&workspace).expect("mark");
+        assert!(!workspace.join(WORKSPACE_RECOVERY_NOTIFIED).exists());
+
+        fs::write(
+            workspace.join(WORKSPACE_RECOVERY_NOTE),
+            "# Workspace recovered\n",
+        )
+        .expect("note");
+        mark_recovery_notified(&workspace).expect("mark");
+        assert!(workspace.join(WORKSPACE_RECOVERY_NOTIFIED).exists());
+    }
+}
@@ -0,0 +1,206 @@
+//! Per-epoch snapshots of thread workspaces.
+//!
+//! The agent edits workspace files in place, so a bad run can destroy the
+//! context later runs depend on. Before a run starts, the workspace is copied
+//! to `<workspaces root>/.snapshots/<workspace>/epoch_<n>`, outside the
+//! directory the agent can see. When a run fails output validation the
+//! scheduler calls [`rollback_to_epoch`] to put the workspace back the way
+//! the run found it. Only the latest [`SNAPSHOTS_KEPT`] epochs per workspace
+//! are kept. Execution logs are not part of a snapshot and survive a
+//! rollback, so the log of the run that was rolled back can still be read.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const SNAPSHOTS_DIR: &str = ".snapshots";
+const SNAPSHOTS_KEPT: usize = 3;
+/// Workspace entries left out of snapshots and kept across a rollback.
+const UNVERSIONED: &[&str] = &[run_task_module::EXEC_LOG_DIR];
+
+/// Copy `workspace` as the snapshot for `epoch`, replacing an older snapshot
+/// of the same epoch.
+pub(crate) fn snapshot_epoch(workspace: &Path, epoch: u64) -> io::Result<PathBuf> {
+    let snapshots = snapshots_root(workspace)?;
+    fs::create_dir_all(&snapshots)?;
+    let target = snapshots.join(epoch_dir_name(epoch));
+    let partial = snapshots.join(format!("{}.partial", epoch_dir_name(epoch)));
+    remove_path(&partial)?;
+    fs::create_dir_all(&partial)?;
+    for entry in versioned_entries(workspace)? {
+        copy_entry(&entry, &partial.join(entry.file_name().unwrap_or_default()))?;
+    }
+    remove_path(&target)?;
+    fs::rename(&partial, &target)?;
+    prune_snapshots(&snapshots)?;
+    Ok(target)
+}
+
+/// Whether a snapshot for `epoch` exists.
+pub(crate) fn has_snapshot(workspace: &Path, epoch: u64) -> bool {
+    snapshots_root(workspace)
+        .map(|root| root.join(epoch_dir_name(epoch)).is_dir())
+        .unwrap_or(false)
+}
+
+/// Replace the contents of `workspace` with its snapshot for `epoch`.
+/// Returns the number of top-level entries restored.
+pub(crate) fn rollback_to_epoch(workspace: &Path, epoch: u64) -> io::Result<usize> {
+    let snapshot = snapshots_root(workspace)?.join(epoch_dir_name(epoch));
+    if !snapshot.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no snapshot for epoch {} of {}", epoch, workspace.display()),
+        ));
+    }
+    fs::create_dir_all(workspace)?;
+    for entry in versioned_entries(workspace)? {
+        remove_path(&entry)?;
+    }
+    copy_dir(&snapshot, workspace)?;
+    Ok(versioned_entries(workspace)?.len())
+}
+
+/// Top-level entries of `workspace`, without the [`UNVERSIONED`] ones.
+fn versioned_entries(workspace: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(workspace)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if !UNVERSIONED.iter().any(|skipped| name == *skipped) {
+            entries.push(entry.path());
+        }
+    }
+    Ok(entries)
+}
+
+fn snapshots_root(workspace: &Path) -> io::Result<PathBuf> {
+    let workspaces_root = workspace
+        .parent()
+        .ok_or_else(|| io::Error::other("workspace has no parent directory"))?;
+    let name = workspace
+        .file_name()
+        .ok_or_else(|| io::Error::other("workspace has no directory name"))?;
+    Ok(workspaces_root.join(SNAPSHOTS_DIR).join(name))
+}
+
+fn epoch_dir_name(epoch: u64) -> String {
+    format!("epoch_{}", epoch)
+}
+
+fn prune_snapshots(snapshots: &Path) -> io::Result<()> {
+    let mut epochs = Vec::new();
+    for entry in fs::read_dir(snapshots)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if let Some(epoch) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("epoch_"))
+            .and_then(|epoch| epoch.parse::<u64>().ok())
+        {
+            epochs.push((epoch, entry.path()));
+        }
+    }
+    epochs.sort_by_key(|(epoch, _)| std::cmp::Reverse(*epoch));
+    for (_, path) in epochs.into_iter().skip(SNAPSHOTS_KEPT) {
+        remove_path(&path)?;
+    }
+    Ok(())
+}
+
+fn remove_path(path: &Path) -> io::Result<()> {
+    let result = match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(err) => Err(err),
+    };
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+/// Symlinks are skipped: the agent may leave ones that dangle or point
+/// outside the workspace.
+fn copy_dir(src: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        copy_entry(&entry.path(), &dest.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+fn copy_entry(src: &Path, target: &Path) -> io::Result<()> {
+    let file_type = fs::symlink_metadata(src)?.file_type();
+    if file_type.is_dir() {
+        copy_dir(src, target)?;
+    } else if file_type.is_file() {
+        fs::copy(src, target)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn rollback_restores_the_workspace_as_the_run_found_it() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspace = temp.path().join("thread_1");
+        fs::create_dir_all(workspace.join("memory")).expect("memory dir");
+        fs::write(workspace.join("memory").join("memo.md"), "# Memo\n").expect("memo");
+        fs::write(workspace.join("notes.md"), "context").expect("notes");
+        let logs = workspace.join(run_task_module::EXEC_LOG_DIR);
+        fs::create_dir_all(&logs).expect("logs dir");
+
+        snapshot_epoch(&workspace, 2).expect("snapshot");
+        assert!(has_snapshot(&workspace, 2));
+        assert!(!workspace.join(SNAPSHOTS_DIR).exists());
+        fs::write(logs.join("exec_1.log"), "stdout| run\n").expect("log");
+
+        fs::write(workspace.join("notes.md"), "").expect("clobber notes");
+        fs::remove_dir_all(workspace.join("memory")).expect("remove memory");
+        fs::write(workspace.join("scratch.txt"), "junk").expect("scratch");
+
+        assert_eq!(rollback_to_epoch(&workspace, 2).expect("rollback"), 2);
+        assert_eq!(
+            fs::read_to_string(workspace.join("notes.md")).unwrap(),
+            "context"
+        );
+        assert_eq!(
+            fs::read_to_string(workspace.join("memory").join("memo.md")).unwrap(),
+            "# Memo\n"
+        );
+        assert!(!workspace.join("scratch.txt").exists());
+        assert!(logs.join("exec_1.log").exists());
+
+        let err = rollback_to_epoch(&workspace, 7).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn only_the_latest_epochs_are_kept() {
+        let temp = TempDir::new().expect("tempdir");
+        let workspace = temp.path().join("thread_1");
+        fs::create_dir_all(&workspace).expect("workspace");
+        for epoch in 1..=5 {
+            fs::write(workspace.join("epoch.txt"), epoch.to_string()).expect("write");
+            snapshot_epoch(&workspace, epoch).expect("snapshot");
+        }
+        let kept: Vec<u64> = (1..=5)
+            .filter(|epoch| has_snapshot(&workspace, *epoch))
+            .collect();
+        assert_eq!(kept, vec![3, 4, 5]);
+
+        fs::write(workspace.join("epoch.txt"), "5 again").expect("write");
+        snapshot_epoch(&workspace, 5).expect("replace snapshot");
+        rollback_to_epoch(&workspace, 5).expect("rollback");
+        assert_eq!(
+            fs::read_to_string(workspace.join("epoch.txt")).unwrap(),
+            "5 again"
+        );
+    }
+}
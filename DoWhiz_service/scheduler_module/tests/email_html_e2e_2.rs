@@ -110,11 +110,23 @@ fn test_employee_directory() -> (EmployeeProfile, EmployeeDirectory) {
         runtime_root: None,
         agents_path: None,
         claude_path: None,
+        gemini_path: None,
         soul_path: None,
         skills_dir: None,
         discord_enabled: false,
         slack_enabled: false,
         bluebubbles_enabled: false,
+        outbound_policy: Default::default(),
+        inbound_policy: Default::default(),
+        approver: None,
+        slack_approvers: Vec::new(),
+        redaction: None,
+        archive_tiering: None,
+        sandbox: None,
+        language: None,
+        mention_names: Vec::new(),
+        router: Default::default(),
+        formatting: Default::default(),
     };
     let mut employee_by_id = HashMap::new();
     employee_by_id.insert(employee.id.clone(), employee.clone());
@@ -162,6 +174,7 @@ fn setup_service_for_test(
         users_db_path: state_root.join("users.db"),
         task_index_path: state_root.join("task_index.db"),
         codex_model: "gpt-5.4".to_string(),
+        gemini_model: "gemini-2.5-pro".to_string(),
         codex_disabled: true,
         scheduler_poll_interval: Duration::from_millis(50),
         scheduler_max_concurrency: 1,
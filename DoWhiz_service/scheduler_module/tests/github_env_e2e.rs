@@ -96,11 +96,23 @@ fn test_employee_directory() -> (EmployeeProfile, EmployeeDirectory) {
         runtime_root: None,
         agents_path: None,
         claude_path: None,
+        gemini_path: None,
         soul_path: None,
         skills_dir: None,
         discord_enabled: false,
         slack_enabled: false,
         bluebubbles_enabled: false,
+        outbound_policy: Default::default(),
+        inbound_policy: Default::default(),
+        approver: None,
+        slack_approvers: Vec::new(),
+        redaction: None,
+        archive_tiering: None,
+        sandbox: None,
+        language: None,
+        mention_names: Vec::new(),
+        router: Default::default(),
+        formatting: Default::default(),
     };
     let mut employee_by_id = HashMap::new();
     employee_by_id.insert(employee.id.clone(), employee.clone());
@@ -134,6 +146,9 @@ impl TaskExecutor for RecordingExecutor {
                         scheduler_module::load_google_access_token_from_service_env(),
                     has_unified_account: false,
                     user_identities: Default::default(),
+                    reply_preferences: Default::default(),
+                    trace_id: None,
+                    sandbox: None,
                 };
                 let output = run_task_module::run_task(&params)
                     .map_err(|err| SchedulerError::TaskFailed(err.to_string()))?;
@@ -143,10 +158,13 @@ impl TaskExecutor for RecordingExecutor {
                     scheduler_actions: output.scheduler_actions,
                     scheduler_actions_error: output.scheduler_actions_error,
                     skip_auto_reply: false,
+                    provider_message_id: None,
                 })
             }
             TaskKind::SendReply(_) => Ok(TaskExecution::default()),
-            TaskKind::Noop => Ok(TaskExecution::default()),
+            TaskKind::Noop(_) | TaskKind::Digest(_) | TaskKind::Probe(_) => {
+                Ok(TaskExecution::default())
+            }
         }
     }
 }
@@ -346,6 +364,7 @@ fn email_flow_injects_github_env() {
         users_db_path: state_root.join("users.db"),
         task_index_path: state_root.join("task_index.db"),
         codex_model: "gpt-5.4".to_string(),
+        gemini_model: "gemini-2.5-pro".to_string(),
         codex_disabled: false,
         scheduler_poll_interval: Duration::from_millis(50),
         scheduler_max_concurrency: 1,
@@ -488,6 +507,7 @@ fn email_flow_injects_employee_github_env() {
         users_db_path: state_root.join("users.db"),
         task_index_path: state_root.join("task_index.db"),
         codex_model: "gpt-5.4".to_string(),
+        gemini_model: "gemini-2.5-pro".to_string(),
         codex_disabled: false,
         scheduler_poll_interval: Duration::from_millis(50),
         scheduler_max_concurrency: 1,
@@ -640,6 +660,7 @@ fn email_flow_injects_x402_env() {
         users_db_path: state_root.join("users.db"),
         task_index_path: state_root.join("task_index.db"),
         codex_model: "gpt-5.4".to_string(),
+        gemini_model: "gemini-2.5-pro".to_string(),
         codex_disabled: false,
         scheduler_poll_interval: Duration::from_millis(50),
         scheduler_max_concurrency: 1,
@@ -803,6 +824,7 @@ fn email_flow_injects_employee_prefixed_x402_env() {
         users_db_path: state_root.join("users.db"),
         task_index_path: state_root.join("task_index.db"),
         codex_model: "gpt-5.4".to_string(),
+        gemini_model: "gemini-2.5-pro".to_string(),
         codex_disabled: false,
         scheduler_poll_interval: Duration::from_millis(50),
         scheduler_max_concurrency: 1,
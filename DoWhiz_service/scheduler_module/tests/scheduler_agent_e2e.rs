@@ -1,7 +1,7 @@
 use chrono::Utc;
 use run_task_module::RunTaskParams;
 use scheduler_module::{
-    RunTaskTask, Scheduler, SchedulerError, TaskExecution, TaskExecutor, TaskKind,
+    NoopTask, RunTaskTask, Scheduler, SchedulerError, TaskExecution, TaskExecutor, TaskKind,
 };
 use std::env;
 use std::fs;
@@ -57,6 +57,9 @@ impl TaskExecutor for RecordingExecutor {
                         scheduler_module::load_google_access_token_from_service_env(),
                     has_unified_account: false,
                     user_identities: Default::default(),
+                    reply_preferences: Default::default(),
+                    trace_id: None,
+                    sandbox: None,
                 };
                 let output = run_task_module::run_task(&params)
                     .map_err(|err| SchedulerError::TaskFailed(err.to_string()))?;
@@ -66,6 +69,7 @@ impl TaskExecutor for RecordingExecutor {
                     scheduler_actions: output.scheduler_actions,
                     scheduler_actions_error: output.scheduler_actions_error,
                     skip_auto_reply: false,
+                    provider_message_id: None,
                 })
             }
             TaskKind::SendReply(send) => {
@@ -75,7 +79,9 @@ impl TaskExecutor for RecordingExecutor {
                     .push(send.subject.clone());
                 Ok(TaskExecution::default())
             }
-            TaskKind::Noop => Ok(TaskExecution::default()),
+            TaskKind::Noop(_) | TaskKind::Digest(_) | TaskKind::Probe(_) => {
+                Ok(TaskExecution::default())
+            }
         }
     }
 }
@@ -153,10 +159,16 @@ fn scheduler_actions_end_to_end() {
     let sent_subjects = executor.sent_subjects.clone();
     let mut scheduler = Scheduler::load(root.join("tasks.db"), executor).expect("load scheduler");
     let cancel_id = scheduler
-        .add_one_shot_at(now + chrono::Duration::seconds(60), TaskKind::Noop)
+        .add_one_shot_at(
+            now + chrono::Duration::seconds(60),
+            TaskKind::Noop(NoopTask::default()),
+        )
         .expect("cancel task");
     let reschedule_id = scheduler
-        .add_one_shot_at(now + chrono::Duration::seconds(90), TaskKind::Noop)
+        .add_one_shot_at(
+            now + chrono::Duration::seconds(90),
+            TaskKind::Noop(NoopTask::default()),
+        )
         .expect("reschedule task");
 
     let _cancel_guard = EnvGuard::set("CANCEL_TASK_ID", &cancel_id.to_string());
@@ -185,6 +197,8 @@ fn scheduler_actions_end_to_end() {
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     scheduler
@@ -1,4 +1,6 @@
-use scheduler_module::{Scheduler, SchedulerError, TaskExecution, TaskExecutor, TaskKind};
+use scheduler_module::{
+    NoopTask, Scheduler, SchedulerError, TaskExecution, TaskExecutor, TaskKind,
+};
 use std::sync::atomic::AtomicBool;
 use std::time::Duration;
 
@@ -17,10 +19,10 @@ fn cron_requires_six_fields() {
     let storage = temp.path().join("tasks.db");
     let mut scheduler = Scheduler::load(storage, NoopExecutor).expect("load failed");
 
-    let bad = scheduler.add_cron_task("0 0 * * *", TaskKind::Noop);
+    let bad = scheduler.add_cron_task("0 0 * * *", TaskKind::Noop(NoopTask::default()));
     assert!(bad.is_err(), "expected 5-field cron to fail");
 
-    let good = scheduler.add_cron_task("0 0 0 * * *", TaskKind::Noop);
+    let good = scheduler.add_cron_task("0 0 0 * * *", TaskKind::Noop(NoopTask::default()));
     assert!(good.is_ok(), "expected 6-field cron to succeed");
 }
 
@@ -31,7 +33,7 @@ fn one_shot_persists_across_restarts() {
     let mut scheduler = Scheduler::load(&storage, NoopExecutor).expect("load failed");
 
     scheduler
-        .add_one_shot_in(Duration::from_secs(60), TaskKind::Noop)
+        .add_one_shot_in(Duration::from_secs(60), TaskKind::Noop(NoopTask::default()))
         .expect("add one-shot failed");
 
     let loaded = Scheduler::load(&storage, NoopExecutor).expect("reload failed");
@@ -44,7 +46,7 @@ fn tick_disables_one_shot_tasks() {
     let storage = temp.path().join("tasks.db");
     let mut scheduler = Scheduler::load(storage, NoopExecutor).expect("load failed");
     let task_id = scheduler
-        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop)
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop(NoopTask::default()))
         .expect("add one-shot failed");
 
     scheduler.tick().expect("tick failed");
@@ -63,7 +65,7 @@ fn tick_sets_last_run_for_one_shot() {
     let storage = temp.path().join("tasks.db");
     let mut scheduler = Scheduler::load(storage, NoopExecutor).expect("load failed");
     let task_id = scheduler
-        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop)
+        .add_one_shot_in(Duration::from_secs(0), TaskKind::Noop(NoopTask::default()))
         .expect("add one-shot failed");
 
     scheduler.tick().expect("tick failed");
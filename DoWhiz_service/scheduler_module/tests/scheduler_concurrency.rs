@@ -37,11 +37,23 @@ fn test_employee_directory() -> (EmployeeProfile, EmployeeDirectory) {
         runtime_root: None,
         agents_path: None,
         claude_path: None,
+        gemini_path: None,
         soul_path: None,
         skills_dir: None,
         discord_enabled: false,
         slack_enabled: false,
         bluebubbles_enabled: false,
+        outbound_policy: Default::default(),
+        inbound_policy: Default::default(),
+        approver: None,
+        slack_approvers: Vec::new(),
+        redaction: None,
+        archive_tiering: None,
+        sandbox: None,
+        language: None,
+        mention_names: Vec::new(),
+        router: Default::default(),
+        formatting: Default::default(),
     };
     let mut employee_by_id = HashMap::new();
     employee_by_id.insert(employee.id.clone(), employee.clone());
@@ -117,6 +129,8 @@ fn scheduler_parallelism_reduces_wall_clock_time() -> Result<(), Box<dyn std::er
             requester_identifier_type: None,
             requester_identifier: None,
             account_id: None,
+            trace_id: None,
+            scheduled: false,
         };
 
         let mut scheduler = Scheduler::load(&paths.tasks_db_path, ModuleExecutor::default())?;
@@ -147,6 +161,7 @@ fn scheduler_parallelism_reduces_wall_clock_time() -> Result<(), Box<dyn std::er
         users_db_path: state_dir.join("users.db"),
         task_index_path: state_dir.join("task_index.db"),
         codex_model: "gpt-5.4".to_string(),
+        gemini_model: "gemini-2.5-pro".to_string(),
         codex_disabled: false,
         scheduler_poll_interval: Duration::from_millis(100),
         scheduler_max_concurrency: CONCURRENCY_LIMIT,
@@ -24,6 +24,7 @@ impl TaskExecutor for FollowUpExecutor {
                     delay_minutes: Some(0),
                     delay_seconds: None,
                     run_at: None,
+                    approval: None,
                 });
                 Ok(TaskExecution {
                     follow_up_tasks: vec![follow_up],
@@ -31,6 +32,7 @@ impl TaskExecutor for FollowUpExecutor {
                     scheduler_actions: Vec::new(),
                     scheduler_actions_error: None,
                     skip_auto_reply: false,
+                    provider_message_id: None,
                 })
             }
             _ => Ok(TaskExecution::default()),
@@ -68,6 +70,8 @@ fn run_task_followups_persist_across_restarts() {
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     let mut scheduler =
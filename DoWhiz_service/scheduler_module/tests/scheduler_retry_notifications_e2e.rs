@@ -230,6 +230,8 @@ fn run_task_failure_retries_and_notifies() -> Result<(), Box<dyn std::error::Err
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     let db_path = temp.path().join("tasks.db");
@@ -398,6 +400,8 @@ fn transient_codex_failures_send_retry_alerts_and_terminal_notice(
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     let db_path = temp.path().join("tasks.db");
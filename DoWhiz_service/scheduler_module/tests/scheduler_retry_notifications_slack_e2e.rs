@@ -226,6 +226,8 @@ fn slack_failure_retries_and_notifies() -> Result<(), Box<dyn std::error::Error>
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     let db_path = temp.path().join("tasks.db");
@@ -85,6 +85,9 @@ impl TaskExecutor for RecordingExecutor {
                         scheduler_module::load_google_access_token_from_service_env(),
                     has_unified_account: false,
                     user_identities: Default::default(),
+                    reply_preferences: Default::default(),
+                    trace_id: None,
+                    sandbox: None,
                 };
                 let output = run_task_module::run_task(&params)
                     .map_err(|err| SchedulerError::TaskFailed(err.to_string()))?;
@@ -94,10 +97,13 @@ impl TaskExecutor for RecordingExecutor {
                     scheduler_actions: output.scheduler_actions,
                     scheduler_actions_error: output.scheduler_actions_error,
                     skip_auto_reply: false,
+                    provider_message_id: None,
                 })
             }
             TaskKind::SendReply(_) => Ok(TaskExecution::default()),
-            TaskKind::Noop => Ok(TaskExecution::default()),
+            TaskKind::Noop(_) | TaskKind::Digest(_) | TaskKind::Probe(_) => {
+                Ok(TaskExecution::default())
+            }
         }
     }
 }
@@ -242,6 +248,8 @@ fn run_scheduler_x402_env_test(
         requester_identifier_type: None,
         requester_identifier: None,
         account_id: None,
+        trace_id: None,
+        scheduled: false,
     };
 
     scheduler
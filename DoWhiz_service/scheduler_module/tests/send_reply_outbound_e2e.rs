@@ -65,6 +65,9 @@ fn base_send_task(channel: Channel, html_path: PathBuf, attachments_dir: PathBuf
         thread_epoch: None,
         thread_state_path: None,
         employee_id: None,
+        idempotency_key: None,
+        trace_id: None,
+        scheduled: false,
     }
 }
 
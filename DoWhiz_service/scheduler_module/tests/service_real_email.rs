@@ -758,6 +758,7 @@ fn rust_service_real_email_end_to_end() -> Result<(), BoxError> {
         users_db_path: state_dir.join("users.db"),
         task_index_path: state_dir.join("task_index.db"),
         codex_model: env::var("CODEX_MODEL").unwrap_or_else(|_| "gpt-5.4".to_string()),
+        gemini_model: "gemini-2.5-pro".to_string(),
         codex_disabled,
         scheduler_poll_interval: Duration::from_secs(1),
         scheduler_max_concurrency: 10,
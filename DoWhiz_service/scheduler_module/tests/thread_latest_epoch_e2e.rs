@@ -73,11 +73,23 @@ fn test_employee_directory(root: &Path) -> (EmployeeProfile, EmployeeDirectory)
         runtime_root: None,
         agents_path: Some(agents_path),
         claude_path: Some(claude_path),
+        gemini_path: None,
         soul_path: Some(soul_path),
         skills_dir: None,
         discord_enabled: false,
         slack_enabled: false,
         bluebubbles_enabled: false,
+        outbound_policy: Default::default(),
+        inbound_policy: Default::default(),
+        approver: None,
+        slack_approvers: Vec::new(),
+        redaction: None,
+        archive_tiering: None,
+        sandbox: None,
+        language: None,
+        mention_names: Vec::new(),
+        router: Default::default(),
+        formatting: Default::default(),
     };
     let mut employee_by_id = HashMap::new();
     employee_by_id.insert(employee.id.clone(), employee.clone());
@@ -111,6 +123,9 @@ impl TaskExecutor for RecordingExecutor {
                         scheduler_module::load_google_access_token_from_service_env(),
                     has_unified_account: false,
                     user_identities: Default::default(),
+                    reply_preferences: Default::default(),
+                    trace_id: None,
+                    sandbox: None,
                 };
                 let output = run_task_module::run_task(&params)
                     .map_err(|err| SchedulerError::TaskFailed(err.to_string()))?;
@@ -120,6 +135,7 @@ impl TaskExecutor for RecordingExecutor {
                     scheduler_actions: output.scheduler_actions,
                     scheduler_actions_error: output.scheduler_actions_error,
                     skip_auto_reply: false,
+                    provider_message_id: None,
                 })
             }
             TaskKind::SendReply(send) => {
@@ -129,7 +145,9 @@ impl TaskExecutor for RecordingExecutor {
                     .push(send.subject.clone());
                 Ok(TaskExecution::default())
             }
-            TaskKind::Noop => Ok(TaskExecution::default()),
+            TaskKind::Noop(_) | TaskKind::Digest(_) | TaskKind::Probe(_) => {
+                Ok(TaskExecution::default())
+            }
         }
     }
 }
@@ -232,6 +250,7 @@ fn thread_latest_epoch_end_to_end() {
         users_db_path: state_root.join("users.db"),
         task_index_path: state_root.join("task_index.db"),
         codex_model: "gpt-5.4".to_string(),
+        gemini_model: "gemini-2.5-pro".to_string(),
         codex_disabled: false,
         scheduler_poll_interval: Duration::from_millis(50),
         scheduler_max_concurrency: 2,
@@ -2,7 +2,7 @@ use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
 use base64::Engine;
 use mime_guess::MimeGuess;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -78,6 +78,9 @@ struct PostmarkAttachment {
     name: String,
     content: String,
     content_type: String,
+    /// `cid:<name>` for an image the HTML body shows inline.
+    #[serde(rename = "ContentID", skip_serializing_if = "Option::is_none")]
+    content_id: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -115,12 +118,26 @@ pub fn send_email(params: &SendEmailParams) -> Result<PostmarkSendResponse, Send
     let bcc = join_recipients(&bcc_list);
 
     let html_body = fs::read_to_string(&params.html_path)?;
-    let mut text_body = strip_html_tags(&html_body);
+    let mut text_body =
+        read_text_alternative(&params.html_path).unwrap_or_else(|| strip_html_tags(&html_body));
     if text_body.trim().is_empty() {
         text_body = "(no content)".to_string();
     }
 
-    let attachments = load_attachments(&params.attachments_dir)?;
+    let mut used_names = HashSet::new();
+    let html_dir = params
+        .html_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let inline = inline_local_images(&html_body, html_dir, &mut used_names)?;
+    let html_body = inline.html;
+    let mut attachments = inline.attachments;
+    attachments.extend(load_attachments(
+        &params.attachments_dir,
+        &inline.paths,
+        &mut used_names,
+    )?);
 
     let mut headers = Vec::new();
     if let Some(value) = clean_header_value(&params.in_reply_to) {
@@ -251,6 +268,20 @@ fn clean_header_value(value: &Option<String>) -> Option<String> {
         .map(|trimmed| trimmed.to_string())
 }
 
+/// The plaintext alternative written next to the HTML body
+/// (`reply_email_draft.txt` for `reply_email_draft.html`), unless it is
+/// older than the HTML and so describes an earlier draft.
+fn read_text_alternative(html_path: &Path) -> Option<String> {
+    let text_path = html_path.with_extension("txt");
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    if modified(&text_path)? < modified(html_path)? {
+        return None;
+    }
+    fs::read_to_string(&text_path)
+        .ok()
+        .filter(|text| !text.trim().is_empty())
+}
+
 fn strip_html_tags(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
     let mut in_tag = false;
@@ -345,13 +376,17 @@ fn uniquify_attachment_name(
     }
 }
 
-fn load_attachments(dir: &Path) -> Result<Vec<PostmarkAttachment>, std::io::Error> {
+/// Files in `dir`, except those already attached inline (`inlined`).
+fn load_attachments(
+    dir: &Path,
+    inlined: &HashSet<PathBuf>,
+    used_names: &mut HashSet<String>,
+) -> Result<Vec<PostmarkAttachment>, std::io::Error> {
     if !dir.exists() {
         return Ok(Vec::new());
     }
 
     let mut attachments = Vec::new();
-    let mut used_names = HashSet::new();
     let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<Result<_, _>>()?;
     entries.sort_by_key(|entry| entry.path());
 
@@ -360,18 +395,187 @@ fn load_attachments(dir: &Path) -> Result<Vec<PostmarkAttachment>, std::io::Erro
         if !path.is_file() {
             continue;
         }
+        if path
+            .canonicalize()
+            .is_ok_and(|canonical| inlined.contains(&canonical))
+        {
+            continue;
+        }
         let content = fs::read(&path)?;
         let mime = MimeGuess::from_path(&path)
             .first_or_octet_stream()
             .essence_str()
             .to_string();
         let attachment = PostmarkAttachment {
-            name: ascii_safe_attachment_name(&path, &mut used_names),
+            name: ascii_safe_attachment_name(&path, used_names),
             content: BASE64_STANDARD.encode(content),
             content_type: mime,
+            content_id: None,
         };
         attachments.push(attachment);
     }
 
     Ok(attachments)
 }
+
+struct InlineImages {
+    html: String,
+    attachments: Vec<PostmarkAttachment>,
+    /// Canonical paths of the inlined files.
+    paths: HashSet<PathBuf>,
+}
+
+/// Attach the local images the draft shows with `<img src>` inline and point
+/// their `src` at `cid:<name>`. Only image files under `base_dir` are
+/// inlined; remote, `data:` and `cid:` sources are left as they are.
+fn inline_local_images(
+    html: &str,
+    base_dir: &Path,
+    used_names: &mut HashSet<String>,
+) -> Result<InlineImages, std::io::Error> {
+    let mut out = String::with_capacity(html.len());
+    let mut attachments = Vec::new();
+    let mut cids: HashMap<PathBuf, String> = HashMap::new();
+    let mut copied = 0;
+    for (start, end) in img_src_ranges(html) {
+        let Some(path) = local_image_path(&html[start..end], base_dir) else {
+            continue;
+        };
+        let cid = match cids.get(&path) {
+            Some(cid) => cid.clone(),
+            None => {
+                let name = ascii_safe_attachment_name(&path, used_names);
+                let cid = format!("cid:{}", name);
+                attachments.push(PostmarkAttachment {
+                    name,
+                    content: BASE64_STANDARD.encode(fs::read(&path)?),
+                    content_type: MimeGuess::from_path(&path)
+                        .first_or_octet_stream()
+                        .essence_str()
+                        .to_string(),
+                    content_id: Some(cid.clone()),
+                });
+                cids.insert(path, cid.clone());
+                cid
+            }
+        };
+        out.push_str(&html[copied..start]);
+        out.push_str(&cid);
+        copied = end;
+    }
+    out.push_str(&html[copied..]);
+    Ok(InlineImages {
+        html: out,
+        attachments,
+        paths: cids.into_keys().collect(),
+    })
+}
+
+/// Byte ranges of the `src` values of `<img>` tags.
+fn img_src_ranges(html: &str) -> Vec<(usize, usize)> {
+    let lower = html.to_ascii_lowercase();
+    let mut ranges = Vec::new();
+    let mut from = 0;
+    while let Some(offset) = lower[from..].find("<img") {
+        let tag_start = from + offset;
+        let tag_end = lower[tag_start..]
+            .find('>')
+            .map_or(lower.len(), |end| tag_start + end);
+        if let Some(range) = src_value_range(&lower, tag_start + 4, tag_end) {
+            ranges.push(range);
+        }
+        from = tag_end;
+    }
+    ranges
+}
+
+fn src_value_range(tag: &str, mut at: usize, end: usize) -> Option<(usize, usize)> {
+    let bytes = tag.as_bytes();
+    let skip_space = |mut cursor: usize| {
+        while cursor < end && bytes[cursor].is_ascii_whitespace() {
+            cursor += 1;
+        }
+        cursor
+    };
+    while let Some(offset) = tag[at..end].find("src") {
+        let name_start = at + offset;
+        at = name_start + 3;
+        // Skips `data-src`, `srcset` and the like.
+        if !bytes[name_start - 1].is_ascii_whitespace() {
+            continue;
+        }
+        let cursor = skip_space(at);
+        if cursor >= end || bytes[cursor] != b'=' {
+            continue;
+        }
+        let cursor = skip_space(cursor + 1);
+        if cursor >= end {
+            return None;
+        }
+        return Some(match bytes[cursor] {
+            quote @ (b'"' | b'\'') => {
+                let start = cursor + 1;
+                let close = tag[start..end]
+                    .find(quote as char)
+                    .map_or(end, |offset| start + offset);
+                (start, close)
+            }
+            _ => {
+                let close = tag[cursor..end]
+                    .find(|ch: char| ch.is_ascii_whitespace())
+                    .map_or(end, |offset| cursor + offset);
+                (cursor, close)
+            }
+        });
+    }
+    None
+}
+
+/// The image file `src` names under `base_dir`, canonicalized.
+fn local_image_path(src: &str, base_dir: &Path) -> Option<PathBuf> {
+    let src = src.trim();
+    let src = src.strip_prefix("file://").unwrap_or(src);
+    if src.is_empty() || src.starts_with("//") {
+        return None;
+    }
+    if let Some((scheme, _)) = src.split_once(':') {
+        if scheme
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+        {
+            return None;
+        }
+    }
+    let src = src.split(['?', '#']).next().unwrap_or(src);
+    let path = base_dir
+        .join(percent_decode(&src.replace("&amp;", "&")))
+        .canonicalize()
+        .ok()?;
+    let is_image = MimeGuess::from_path(&path)
+        .first()
+        .is_some_and(|mime| mime.type_() == mime_guess::mime::IMAGE);
+    (is_image && path.is_file() && path.starts_with(base_dir.canonicalize().ok()?)).then_some(path)
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
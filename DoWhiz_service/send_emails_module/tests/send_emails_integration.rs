@@ -316,6 +316,153 @@ fn send_payload_sanitizes_attachment_names_to_ascii() -> Result<(), Box<dyn std:
     Ok(())
 }
 
+#[test]
+fn send_payload_inlines_local_images() -> Result<(), Box<dyn std::error::Error>> {
+    let _lock = ENV_MUTEX.lock().unwrap_or_else(|err| err.into_inner());
+    let temp = TempDir::new()?;
+    let workspace = temp.path().join("workspace");
+    let attachments_dir = workspace.join("reply_email_attachments");
+    fs::create_dir_all(&attachments_dir)?;
+    let chart = attachments_dir.join("chart.png");
+    fs::write(&chart, b"png-bytes")?;
+    fs::write(workspace.join("my logo.jpg"), b"jpg-bytes")?;
+    fs::write(attachments_dir.join("notes.txt"), "hello world")?;
+    fs::write(temp.path().join("outside.png"), b"secret")?;
+    let html_path = workspace.join("reply_email_draft.html");
+    fs::write(
+        &html_path,
+        "<p>Chart</p><img src=\"reply_email_attachments/chart.png\" alt=\"chart\">\
+<IMG alt='logo' SRC='my%20logo.jpg'><img src=\"reply_email_attachments/chart.png\">\
+<img src=\"https://example.com/remote.png\"><img src=\"../outside.png\">",
+    )?;
+
+    let encoded = |bytes: &[u8]| general_purpose::STANDARD.encode(bytes);
+    let expected_payload = json!({
+        "From": "sender@example.com",
+        "To": "to@example.com",
+        "Bcc": "sender@example.com",
+        "Subject": "Inline images",
+        "TextBody": "Chart",
+        "HtmlBody": "<p>Chart</p><img src=\"cid:chart.png\" alt=\"chart\">\
+    <IMG alt='logo' SRC='cid:my_logo.jpg'><img src=\"cid:chart.png\">\
+    <img src=\"https://example.com/remote.png\"><img src=\"../outside.png\">",
+        "Attachments": [
+            {
+                "Name": "chart.png",
+                "Content": encoded(b"png-bytes"),
+                "ContentType": "image/png",
+                "ContentID": "cid:chart.png",
+            },
+            {
+                "Name": "my_logo.jpg",
+                "Content": encoded(b"jpg-bytes"),
+                "ContentType": "image/jpeg",
+                "ContentID": "cid:my_logo.jpg",
+            },
+            {
+                "Name": "notes.txt",
+                "Content": encoded(b"hello world"),
+                "ContentType": "text/plain",
+            },
+        ],
+    });
+
+    let response_body = json!({
+        "To": "to@example.com",
+        "SubmittedAt": "2024-01-01T00:00:00Z",
+        "MessageID": "test-message-id",
+        "ErrorCode": 0,
+        "Message": "OK",
+    });
+
+    let mut server = Server::new();
+    let api_base_url = server.url();
+    let mock = server
+        .mock("POST", "/email")
+        .match_body(Matcher::Json(expected_payload))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(response_body.to_string())
+        .create();
+
+    let _env = EnvGuard::set(&[
+        ("POSTMARK_SERVER_TOKEN", "test-token"),
+        ("POSTMARK_API_BASE_URL", api_base_url.as_str()),
+    ]);
+
+    let request = SendEmailParams {
+        subject: "Inline images".to_string(),
+        html_path,
+        attachments_dir,
+        from: Some("sender@example.com".to_string()),
+        to: vec!["to@example.com".to_string()],
+        cc: vec![],
+        bcc: vec![],
+        in_reply_to: None,
+        references: None,
+        reply_to: None,
+    };
+
+    send_email(&request)?;
+    mock.assert();
+    Ok(())
+}
+
+#[test]
+fn send_payload_uses_the_plaintext_alternative() -> Result<(), Box<dyn std::error::Error>> {
+    let _lock = ENV_MUTEX.lock().unwrap_or_else(|err| err.into_inner());
+    let temp = TempDir::new()?;
+    let html_path = temp.path().join("reply_email_draft.html");
+    fs::write(
+        &html_path,
+        "<html><body><ul><li>One</li><li>Two</li></ul></body></html>",
+    )?;
+    fs::write(temp.path().join("reply_email_draft.txt"), "- One\n- Two\n")?;
+
+    let mut server = Server::new();
+    let api_base_url = server.url();
+    let mock = server
+        .mock("POST", "/email")
+        .match_body(Matcher::PartialJson(
+            json!({ "TextBody": "- One\n- Two\n" }),
+        ))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(
+            json!({
+                "To": "to@example.com",
+                "SubmittedAt": "2024-01-01T00:00:00Z",
+                "MessageID": "test-message-id",
+                "ErrorCode": 0,
+                "Message": "OK",
+            })
+            .to_string(),
+        )
+        .create();
+
+    let _env = EnvGuard::set(&[
+        ("POSTMARK_SERVER_TOKEN", "test-token"),
+        ("POSTMARK_API_BASE_URL", api_base_url.as_str()),
+    ]);
+
+    let request = SendEmailParams {
+        subject: "Plaintext".to_string(),
+        html_path,
+        attachments_dir: temp.path().join("reply_email_attachments"),
+        from: Some("sender@example.com".to_string()),
+        to: vec!["to@example.com".to_string()],
+        cc: vec![],
+        bcc: vec![],
+        in_reply_to: None,
+        references: None,
+        reply_to: None,
+    };
+
+    send_email(&request)?;
+    mock.assert();
+    Ok(())
+}
+
 #[test]
 fn live_postmark_delivery_with_attachments() -> Result<(), Box<dyn std::error::Error>> {
     let _lock = ENV_MUTEX.lock().unwrap_or_else(|err| err.into_inner());